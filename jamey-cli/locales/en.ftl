@@ -0,0 +1,77 @@
+# Strings shown by the `jamey` CLI. Keep every message here; translations
+# fall back to these for anything they lack.
+
+error-prefix = Error:
+
+## jamey chat
+
+chat-banner = Digital Twin Jamey - Chat Mode
+chat-hint-exit = Type 'exit' or press Ctrl+C to quit
+chat-hint-cancel = Press Ctrl+C while Jamey is replying to cancel the turn
+chat-hint-help = Type 'help' for available commands
+chat-listening-with = Listening with { $device }: press Enter on an empty line to talk, then Enter to send
+chat-session = Session ID: { $id }
+chat-persona = Persona: { $persona } ({ $model })
+chat-workspace = Workspace: { $workspace }
+chat-resumed = Resumed { $count } earlier { $count ->
+        [one] message
+       *[other] messages
+    }
+chat-new-briefing = New briefing from { $at }; run { $command } to read it
+chat-you = You:
+chat-jamey = Jamey:
+chat-goodbye = Goodbye!
+chat-turn-cancelled = Turn cancelled
+chat-turn-failed = Sorry, I encountered an error processing your message: { $error }
+chat-listening = Listening… press Enter to send
+chat-too-short = Too short to send
+chat-not-heard = Didn't catch anything
+chat-transcription-failed = Transcription failed: { $error }
+chat-summarized = Summarized { $count } earlier messages to stay within the context budget
+chat-awaiting-approval = Waiting for approval { $id } — run { $command }
+chat-degraded = Degraded reply ({ $level })
+chat-speak-failed = Couldn't speak the reply: { $error }
+chat-no-player = No audio player found; saved { $file }
+chat-tool-unknown-error = Unknown error
+chat-tool-output = { $count } { $count ->
+        [one] line
+       *[other] lines
+    } of output
+chat-tool-collapsed = { $count } more { $count ->
+        [one] line
+       *[other] lines
+    } collapsed; type 'expand' to show
+chat-no-tool-output = No tool output from the last turn
+chat-citation = ({ $similarity }% match, memory { $memory })
+chat-usage = { $prompt } prompt + { $completion } completion = { $total } tokens
+chat-history-title = Chat History:
+
+chat-help-title = Available Commands:
+chat-help-exit = Exit the chat
+chat-help-help = Show this help
+chat-help-clear = Clear the screen
+chat-help-history = Show chat history
+chat-help-expand = Show full tool output from the last turn
+chat-help-attach = Attach a file (text, code, PDF or image) to your next message
+chat-help-up = Rate the last reply as helpful
+chat-help-down = Rate the last reply as unhelpful
+chat-help-correct = Tell Jamey what the last reply should have been
+chat-help-undo = Undo the file writes, downloads and setting changes of the last turn
+chat-help-new = Start a new session
+chat-help-save = Save current session
+chat-help-load = Load saved session
+
+chat-usage-hint = Usage: { $usage }
+chat-correct-usage = /correct <what the reply should have been>
+chat-queued = (queued)
+chat-attached = Attached { $name }
+chat-attached-note = It will be sent with your next message
+chat-attach-failed = Could not attach { $path }: { $error }
+chat-feedback-nothing = There is no reply to give feedback on yet
+chat-feedback-saved = Thanks, noted for future replies
+chat-feedback-memory = (memory { $memory })
+chat-feedback-failed = Could not save feedback: { $error }
+chat-undo-nothing = Nothing to undo in this session
+chat-undo-log-failed = Could not read the undo log: { $error }
+chat-undo-retry = Run { $command } again to retry what failed
+chat-undo-failed = Could not undo: { $error }
@@ -0,0 +1,76 @@
+# Cadenas de la CLI `jamey` en español
+
+error-prefix = Error:
+
+## jamey chat
+
+chat-banner = Gemelo digital Jamey - Modo chat
+chat-hint-exit = Escribe 'exit' o pulsa Ctrl+C para salir
+chat-hint-cancel = Pulsa Ctrl+C mientras Jamey responde para cancelar el turno
+chat-hint-help = Escribe 'help' para ver los comandos disponibles
+chat-listening-with = Escuchando con { $device }: pulsa Intro en una línea vacía para hablar y luego Intro para enviar
+chat-session = ID de sesión: { $id }
+chat-persona = Personaje: { $persona } ({ $model })
+chat-workspace = Espacio de trabajo: { $workspace }
+chat-resumed = { $count ->
+        [one] Se ha recuperado { $count } mensaje anterior
+       *[other] Se han recuperado { $count } mensajes anteriores
+    }
+chat-new-briefing = Nuevo resumen del { $at }; ejecuta { $command } para leerlo
+chat-you = Tú:
+chat-jamey = Jamey:
+chat-goodbye = ¡Hasta luego!
+chat-turn-cancelled = Turno cancelado
+chat-turn-failed = Lo siento, se produjo un error al procesar tu mensaje: { $error }
+chat-listening = Escuchando… pulsa Intro para enviar
+chat-too-short = Demasiado corto para enviarlo
+chat-not-heard = No he entendido nada
+chat-transcription-failed = Error en la transcripción: { $error }
+chat-summarized = Se han resumido { $count } mensajes anteriores para no superar el presupuesto de contexto
+chat-awaiting-approval = Esperando la aprobación { $id } — ejecuta { $command }
+chat-degraded = Respuesta degradada ({ $level })
+chat-speak-failed = No se pudo leer la respuesta en voz alta: { $error }
+chat-no-player = No se encontró ningún reproductor de audio; guardado en { $file }
+chat-tool-unknown-error = Error desconocido
+chat-tool-output = { $count } { $count ->
+        [one] línea
+       *[other] líneas
+    } de salida
+chat-tool-collapsed = { $count } { $count ->
+        [one] línea más oculta
+       *[other] líneas más ocultas
+    }; escribe 'expand' para verlas
+chat-no-tool-output = El último turno no produjo salida de herramientas
+chat-citation = ({ $similarity }% de coincidencia, memoria { $memory })
+chat-usage = { $prompt } de entrada + { $completion } de respuesta = { $total } tokens
+chat-history-title = Historial del chat:
+
+chat-help-title = Comandos disponibles:
+chat-help-exit = Salir del chat
+chat-help-help = Mostrar esta ayuda
+chat-help-clear = Limpiar la pantalla
+chat-help-history = Mostrar el historial del chat
+chat-help-expand = Mostrar la salida completa de las herramientas del último turno
+chat-help-attach = Adjuntar un archivo (texto, código, PDF o imagen) al próximo mensaje
+chat-help-up = Valorar la última respuesta como útil
+chat-help-down = Valorar la última respuesta como poco útil
+chat-help-correct = Decirle a Jamey cuál debería haber sido la última respuesta
+chat-help-undo = Deshacer las escrituras de archivos, descargas y cambios de configuración del último turno
+chat-help-new = Empezar una sesión nueva
+chat-help-save = Guardar la sesión actual
+chat-help-load = Cargar una sesión guardada
+
+chat-usage-hint = Uso: { $usage }
+chat-correct-usage = /correct <lo que debería haber sido la respuesta>
+chat-queued = (en cola)
+chat-attached = Adjuntado { $name }
+chat-attached-note = Se enviará con tu próximo mensaje
+chat-attach-failed = No se pudo adjuntar { $path }: { $error }
+chat-feedback-nothing = Todavía no hay ninguna respuesta que valorar
+chat-feedback-saved = Gracias, lo tendré en cuenta en próximas respuestas
+chat-feedback-memory = (memoria { $memory })
+chat-feedback-failed = No se pudo guardar la valoración: { $error }
+chat-undo-nothing = No hay nada que deshacer en esta sesión
+chat-undo-log-failed = No se pudo leer el registro de deshacer: { $error }
+chat-undo-retry = Ejecuta { $command } de nuevo para reintentar lo que falló
+chat-undo-failed = No se pudo deshacer: { $error }
@@ -0,0 +1,165 @@
+//! Approval queue commands
+//!
+//! Review connector calls that are waiting for a human decision. The queue
+//! lives on disk, so this works against a runtime started elsewhere
+//! (e.g. `jamey start --daemon`).
+
+use anyhow::Result;
+use colored::*;
+use crate::ApprovalsAction;
+use jamey_runtime::approvals::{ApprovalQueue, ApprovalRequest, ApprovalStatus};
+use std::collections::HashSet;
+use std::io::Write;
+use std::time::Duration;
+
+/// How often `--watch` re-reads the queue
+const WATCH_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Run approvals action
+pub async fn run_approvals_action(action: ApprovalsAction) -> Result<()> {
+    let queue = ApprovalQueue::from_env();
+
+    match action {
+        ApprovalsAction::List { all, watch } => {
+            if watch {
+                watch_approvals(&queue).await
+            } else {
+                list_approvals(&queue, all).await
+            }
+        }
+        ApprovalsAction::Approve { id, note } => {
+            let id = queue.resolve(&id).await?;
+            let request = queue.approve(id, &approver(), note).await?;
+            println!(
+                "{} Approved {} {} ({})",
+                "✅".green(),
+                request.connector_id.cyan(),
+                request.action.bold(),
+                request.id
+            );
+            Ok(())
+        }
+        ApprovalsAction::Deny { id, reason } => {
+            let id = queue.resolve(&id).await?;
+            let request = queue.deny(id, &approver(), reason).await?;
+            println!(
+                "{} Denied {} {} ({})",
+                "🚫".red(),
+                request.connector_id.cyan(),
+                request.action.bold(),
+                request.id
+            );
+            Ok(())
+        }
+    }
+}
+
+/// Name recorded as the decider
+fn approver() -> String {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_else(|_| "cli".to_string())
+}
+
+async fn list_approvals(queue: &ApprovalQueue, all: bool) -> Result<()> {
+    let filter = if all { None } else { Some(ApprovalStatus::Pending) };
+    let requests = queue.list(filter).await?;
+
+    println!(
+        "{} {} approvals ({})",
+        "🛡️".cyan().bold(),
+        if all { "All" } else { "Pending" },
+        queue.dir().display()
+    );
+    println!("{}", "─".repeat(60));
+
+    if requests.is_empty() {
+        println!("Nothing waiting for approval.");
+        return Ok(());
+    }
+
+    for request in &requests {
+        print_request(request);
+    }
+
+    if !all {
+        println!();
+        println!(
+            "{} Decide with: {} or {}",
+            "💡".yellow(),
+            "jamey approvals approve <id>".bold(),
+            "jamey approvals deny <id>".bold()
+        );
+    }
+    Ok(())
+}
+
+/// Block and announce each new pending request until Ctrl+C
+async fn watch_approvals(queue: &ApprovalQueue) -> Result<()> {
+    println!(
+        "{} Watching {} for approval requests (Ctrl+C to stop)",
+        "👀".cyan().bold(),
+        queue.dir().display()
+    );
+
+    let mut seen = HashSet::new();
+    let mut ticker = tokio::time::interval(WATCH_INTERVAL);
+    loop {
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => {
+                println!();
+                return Ok(());
+            }
+            _ = ticker.tick() => {}
+        }
+
+        let pending = match queue.list(Some(ApprovalStatus::Pending)).await {
+            Ok(pending) => pending,
+            Err(e) => {
+                eprintln!("{} Failed to read approval queue: {}", "⚠️".yellow(), e);
+                continue;
+            }
+        };
+        for request in pending {
+            if seen.insert(request.id) {
+                // Terminal bell so a backgrounded terminal gets attention
+                print!("\x07");
+                println!("{} New approval request", "🔔".yellow().bold());
+                print_request(&request);
+                std::io::stdout().flush()?;
+            }
+        }
+    }
+}
+
+fn print_request(request: &ApprovalRequest) {
+    let status = match request.status {
+        ApprovalStatus::Pending => "pending".yellow(),
+        ApprovalStatus::Approved => "approved".green(),
+        ApprovalStatus::Denied => "denied".red(),
+        ApprovalStatus::Expired => "expired".dimmed(),
+    };
+    println!(
+        "  {} {} {} [{}] {}",
+        request.id.to_string()[..8].yellow(),
+        request.connector_id.cyan(),
+        request.action.bold(),
+        status,
+        request.requested_at.format("%Y-%m-%d %H:%M:%S").to_string().dimmed()
+    );
+
+    let mut params: Vec<_> = request.params.iter().filter(|(k, _)| k.as_str() != "action").collect();
+    params.sort();
+    for (key, value) in params {
+        println!("      {} {}", format!("{}:", key).dimmed(), value);
+    }
+    for check in &request.safety_checks {
+        println!("      {} {}", "check:".dimmed(), check);
+    }
+    if let Some(ref by) = request.decided_by {
+        match request.note {
+            Some(ref note) => println!("      {} {} — {}", "by".dimmed(), by, note),
+            None => println!("      {} {}", "by".dimmed(), by),
+        }
+    }
+}
@@ -0,0 +1,443 @@
+//! One-shot ask command
+//!
+//! Runs a single turn without the interactive shell, e.g.
+//! `jamey ask "what changed?"` or `cat error.log | jamey ask "explain"`.
+//! The answer goes to stdout, rendered as Markdown on a terminal unless
+//! `--raw` is given; progress and tool activity go to stderr.
+//! `--attach <path>` sends files (text, code, PDF or images) along with the question.
+//! `--context project` answers inside the background session of the project
+//! being watched by `jamey watch`, with its recent changes in the prompt.
+//! `--speak` reads the answer aloud after printing it.
+
+use anyhow::Result;
+use colored::*;
+use jamey_protocol::{Attachment, Citation, Message, TokenUsage, ToolCall, ToolResult};
+use jamey_runtime::attachments::AttachmentStore;
+use jamey_runtime::chat::TurnEvent;
+use jamey_runtime::project::{ProjectState, ProjectStore};
+use jamey_runtime::session_store::SessionStoreError;
+use jamey_runtime::voice::VoiceOutput;
+use jamey_runtime::Runtime;
+use crate::render::ReplyWriter;
+use crate::utils::format_bytes;
+use serde::Serialize;
+use std::io::{IsTerminal, Read, Write};
+use std::path::PathBuf;
+use thiserror::Error;
+use uuid::Uuid;
+
+/// Piped input beyond this is truncated before it reaches the model
+const MAX_PIPED_BYTES: usize = 256 * 1024;
+
+/// Earlier project-session messages replayed with a `--context project` question
+const PROJECT_HISTORY: usize = 20;
+
+/// Failures of `jamey ask`, each mapped to a distinct exit status
+#[derive(Debug, Error)]
+pub enum AskError {
+    #[error("No question given and nothing piped on stdin")]
+    NoInput,
+    #[error("Unsupported output format: {0} (expected text or json)")]
+    InvalidFormat(String),
+    #[error("Unsupported context: {0} (expected project)")]
+    InvalidContext(String),
+    #[error("{0} is not inside a watched project; run `jamey watch <dir>` first")]
+    NoProject(PathBuf),
+    #[error("Cannot attach {0}: {1}")]
+    Attachment(PathBuf, String),
+    #[error("Runtime unavailable: {0}")]
+    Unavailable(String),
+    #[error("Voice output unavailable: {0}")]
+    Voice(String),
+    #[error("Turn failed: {0}")]
+    Failed(String),
+    #[error("Interrupted")]
+    Interrupted,
+}
+
+impl AskError {
+    /// 1 = turn failed, 2 = usage error, 3 = runtime unavailable, 130 = Ctrl+C
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            AskError::Failed(_) => 1,
+            AskError::NoInput
+            | AskError::InvalidFormat(_)
+            | AskError::InvalidContext(_)
+            | AskError::NoProject(_)
+            | AskError::Attachment(..)
+            | AskError::Voice(_) => 2,
+            AskError::Unavailable(_) => 3,
+            AskError::Interrupted => 130,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum OutputFormat {
+    Text,
+    Json,
+}
+
+impl std::str::FromStr for OutputFormat {
+    type Err = AskError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "text" | "plain" => Ok(OutputFormat::Text),
+            "json" => Ok(OutputFormat::Json),
+            other => Err(AskError::InvalidFormat(other.to_string())),
+        }
+    }
+}
+
+/// Presentation flags from the command line
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AskFlags {
+    pub raw: bool,
+    pub speak: bool,
+    pub quiet: bool,
+}
+
+/// How the answer and progress are printed
+#[derive(Debug, Clone, Copy)]
+struct Output {
+    format: OutputFormat,
+    /// Render Markdown in text answers; off with `--raw`
+    render: bool,
+    /// Read the answer aloud once it is printed
+    speak: bool,
+    quiet: bool,
+}
+
+/// Extra context a question is answered with
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum AskContext {
+    Project,
+}
+
+impl std::str::FromStr for AskContext {
+    type Err = AskError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "project" => Ok(AskContext::Project),
+            other => Err(AskError::InvalidContext(other.to_string())),
+        }
+    }
+}
+
+/// JSON shape printed with `--format json`
+#[derive(Debug, Serialize)]
+struct AskAnswer {
+    model: String,
+    answer: String,
+    tool_calls: Vec<ToolCall>,
+    tool_results: Vec<ToolResult>,
+    /// Memories the answer was given; `[n]` in it refers to the n-th
+    citations: Vec<Citation>,
+    usage: Option<TokenUsage>,
+    cost_usd: Option<f64>,
+}
+
+/// Run ask command
+pub async fn run_ask(
+    question: Option<String>,
+    model: String,
+    format: String,
+    context: Option<String>,
+    attach: Vec<PathBuf>,
+    flags: AskFlags,
+) -> Result<()> {
+    let format: OutputFormat = format.parse()?;
+    let output = Output { format, render: !flags.raw, speak: flags.speak, quiet: flags.quiet };
+    let result = match context.map(|c| c.parse::<AskContext>()).transpose() {
+        Ok(context) => ask(question, model, output, context, &attach).await,
+        Err(e) => Err(e.into()),
+    };
+
+    // Scripts consuming JSON get a machine-readable error on stdout as well
+    if let (Err(e), OutputFormat::Json) = (&result, format) {
+        let exit_code = e.downcast_ref::<AskError>().map_or(1, AskError::exit_code);
+        println!(
+            "{}",
+            serde_json::json!({ "error": e.to_string(), "exit_code": exit_code })
+        );
+    }
+    result
+}
+
+async fn ask(
+    question: Option<String>,
+    model: String,
+    output: Output,
+    context: Option<AskContext>,
+    attach: &[PathBuf],
+) -> Result<()> {
+    let piped = read_piped_stdin()?;
+    let prompt = build_prompt(question.as_deref(), piped.as_deref()).ok_or(AskError::NoInput)?;
+
+    // Checked before the runtime starts, since that is the slow part
+    let project = match context {
+        Some(AskContext::Project) => Some(find_project().await?),
+        None => None,
+    };
+
+    let config = super::chat::load_runtime_config(&model)
+        .await
+        .map_err(|e| AskError::Unavailable(e.to_string()))?;
+    let voice = if output.speak {
+        Some(VoiceOutput::new(&config.voice).map_err(|e| AskError::Voice(e.to_string()))?)
+    } else {
+        None
+    };
+    let store = AttachmentStore::new(config.attachment_dir.clone());
+    let attachments = upload_attachments(&store, attach, output).await?;
+    let runtime = Runtime::new(config)
+        .await
+        .map_err(|e| AskError::Unavailable(e.to_string()))?;
+
+    let question = Message::user(prompt).with_attachments(&attachments);
+    let result = match project {
+        Some(project) => ask_in_project(&runtime, &project, question, model, output).await,
+        None => run_turn(&runtime, None, vec![question], model, output).await,
+    };
+    let result = match (result, voice) {
+        (Ok(reply), Some(voice)) => speak(&voice, &reply.content, output).await,
+        (result, _) => result.map(|_| ()),
+    };
+    runtime.shutdown().await;
+    result
+}
+
+/// Read the answer aloud; Ctrl+C stops playback
+async fn speak(voice: &VoiceOutput, answer: &str, output: Output) -> Result<()> {
+    let spoken = tokio::select! {
+        _ = tokio::signal::ctrl_c() => return Err(AskError::Interrupted.into()),
+        spoken = voice.speak(answer) => spoken.map_err(|e| AskError::Voice(e.to_string()))?,
+    };
+    if !output.quiet {
+        let note = if spoken.played { "Spoken and saved to" } else { "No audio player found; saved to" };
+        for file in &spoken.files {
+            eprintln!("{} {} {}", "🔊".cyan(), note, file.display());
+        }
+    }
+    Ok(())
+}
+
+/// Upload `--attach` files; each is checked for readable text here, so a bad
+/// file fails the command before any tokens are spent
+async fn upload_attachments(store: &AttachmentStore, paths: &[PathBuf], output: Output) -> Result<Vec<Attachment>> {
+    let mut attachments = Vec::with_capacity(paths.len());
+    for path in paths {
+        let attachment = store
+            .upload(path)
+            .await
+            .map_err(|e| AskError::Attachment(path.clone(), e.to_string()))?;
+        if output.format == OutputFormat::Text && !output.quiet {
+            eprintln!("{} Attached {} {}", "📎".cyan(), attachment.name, format_bytes(attachment.size_bytes).dimmed());
+        }
+        attachments.push(attachment);
+    }
+    Ok(attachments)
+}
+
+/// The watched project containing the working directory
+async fn find_project() -> Result<ProjectState> {
+    let cwd = std::env::current_dir()?;
+    ProjectStore::from_env()
+        .find_for(&cwd)
+        .await
+        .map_err(|e| AskError::Unavailable(e.to_string()))?
+        .ok_or_else(|| AskError::NoProject(cwd).into())
+}
+
+/// Answer in the project's background session and record the exchange there
+async fn ask_in_project(
+    runtime: &Runtime,
+    project: &ProjectState,
+    question: Message,
+    model: String,
+    output: Output,
+) -> Result<Message> {
+    let state = runtime.state();
+    let context = state
+        .project_context(project, &question.content)
+        .await
+        .map_err(|e| AskError::Unavailable(e.to_string()))?;
+    let previous = match state.session_store.load(project.session_id).await {
+        Ok(record) => record.messages,
+        Err(SessionStoreError::NotFound(_)) => Vec::new(),
+        Err(e) => return Err(e.into()),
+    };
+
+    // Context goes first and is rebuilt every time, so it is never stored
+    let mut history = vec![context];
+    history.extend_from_slice(&previous[previous.len().saturating_sub(PROJECT_HISTORY)..]);
+    history.push(question.clone());
+
+    let reply = run_turn(runtime, Some(project.session_id), history, model.clone(), output).await?;
+    if let Err(e) = state
+        .session_store
+        .append(project.session_id, &[question, reply.clone()], Some(&model))
+        .await
+    {
+        tracing::warn!("Failed to save project session: {}", e);
+    }
+    Ok(reply)
+}
+
+/// Stream one turn to the terminal and return the final reply
+async fn run_turn(
+    runtime: &Runtime,
+    session_id: Option<Uuid>,
+    history: Vec<Message>,
+    model: String,
+    output: Output,
+) -> Result<Message> {
+    let mut turn = match session_id {
+        Some(id) => runtime.state().stream_session_turn(id, history),
+        None => runtime.state().stream_turn(history),
+    };
+    let mut answer = AskAnswer {
+        model,
+        answer: String::new(),
+        tool_calls: Vec::new(),
+        tool_results: Vec::new(),
+        citations: Vec::new(),
+        usage: None,
+        cost_usd: None,
+    };
+    let text = output.format == OutputFormat::Text;
+    let show_progress = text && !output.quiet;
+    let mut writer = ReplyWriter::new(output.render);
+    let mut streamed = false;
+
+    let reply = loop {
+        let event = tokio::select! {
+            _ = tokio::signal::ctrl_c() => {
+                turn.cancel();
+                return Err(AskError::Interrupted.into());
+            }
+            event = turn.next() => event,
+        };
+
+        match event {
+            Some(TurnEvent::Summarized(compaction)) => {
+                if show_progress {
+                    eprintln!("{} Summarized {} earlier messages", "📝".dimmed(), compaction.replaced);
+                }
+            }
+            Some(TurnEvent::Token(token)) => {
+                if text {
+                    print!("{}", writer.push(&token));
+                    std::io::stdout().flush()?;
+                    streamed = true;
+                }
+            }
+            Some(TurnEvent::ToolCall(call)) => {
+                if show_progress {
+                    let action = call.args.get("action").and_then(|a| a.as_str()).unwrap_or("?");
+                    eprintln!("{} {} {}", "🔧".cyan(), call.name.cyan(), action.dimmed());
+                }
+                answer.tool_calls.push(call);
+            }
+            Some(TurnEvent::AwaitingApproval(request)) => {
+                if show_progress {
+                    eprintln!("   {} Waiting for approval {}", "⏸️".yellow(), request.id);
+                }
+            }
+            Some(TurnEvent::ToolResult(result)) => {
+                if show_progress && !result.success {
+                    eprintln!("   {} {}", "❌".red(), result.error.as_deref().unwrap_or("Unknown error"));
+                }
+                answer.tool_results.push(result);
+            }
+            Some(TurnEvent::Usage { usage, cost_usd }) => {
+                answer.usage = Some(usage);
+                answer.cost_usd = cost_usd;
+            }
+            Some(TurnEvent::Completed(message)) => {
+                answer.answer = message.content.clone();
+                answer.citations = message.citations.clone();
+                break message;
+            }
+            Some(TurnEvent::Failed(e)) => return Err(AskError::Failed(e).into()),
+            None => return Err(AskError::Failed("Turn ended without a reply".to_string()).into()),
+        }
+    };
+
+    match output.format {
+        OutputFormat::Text => {
+            let rest = if streamed { writer.finish() } else { writer.whole(&answer.answer) };
+            print!("{}", rest);
+            if !writer.is_rendering() && !answer.answer.ends_with('\n') {
+                println!();
+            }
+            if !output.quiet {
+                super::chat::print_citations(&answer.citations);
+            }
+        }
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&answer)?),
+    }
+    Ok(reply)
+}
+
+/// Read stdin when it is piped; `None` for an interactive terminal
+fn read_piped_stdin() -> Result<Option<String>> {
+    let stdin = std::io::stdin();
+    if stdin.is_terminal() {
+        return Ok(None);
+    }
+
+    let mut bytes = Vec::new();
+    stdin.lock().read_to_end(&mut bytes)?;
+    if bytes.len() > MAX_PIPED_BYTES {
+        eprintln!(
+            "{} Piped input truncated to {} of {} bytes",
+            "⚠️".yellow(),
+            MAX_PIPED_BYTES,
+            bytes.len()
+        );
+        bytes.truncate(MAX_PIPED_BYTES);
+    }
+    Ok(Some(String::from_utf8_lossy(&bytes).into_owned()))
+}
+
+/// Combine the question with piped input; `None` when both are empty
+fn build_prompt(question: Option<&str>, piped: Option<&str>) -> Option<String> {
+    let question = question.map(str::trim).filter(|q| !q.is_empty());
+    let piped = piped.map(|p| p.trim_end()).filter(|p| !p.trim().is_empty());
+
+    match (question, piped) {
+        (Some(question), Some(input)) => Some(format!("{}\n\n```\n{}\n```", question, input)),
+        (Some(question), None) => Some(question.to_string()),
+        (None, Some(input)) => Some(input.to_string()),
+        (None, None) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_prompt() {
+        assert_eq!(build_prompt(Some(" hi "), None).as_deref(), Some("hi"));
+        assert_eq!(build_prompt(None, Some("log line\n")).as_deref(), Some("log line"));
+        assert_eq!(
+            build_prompt(Some("explain"), Some("boom\n")).as_deref(),
+            Some("explain\n\n```\nboom\n```")
+        );
+        assert!(build_prompt(Some("  "), Some("\n")).is_none());
+    }
+
+    #[test]
+    fn test_exit_codes() {
+        assert_eq!(AskError::NoInput.exit_code(), 2);
+        assert_eq!(AskError::Unavailable("db".into()).exit_code(), 3);
+        assert_eq!(AskError::Interrupted.exit_code(), 130);
+        assert!("yaml".parse::<OutputFormat>().is_err());
+        assert_eq!("Project".parse::<AskContext>().unwrap(), AskContext::Project);
+        assert_eq!(AskError::NoProject(PathBuf::from("/tmp")).exit_code(), 2);
+    }
+}
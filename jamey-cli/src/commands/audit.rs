@@ -0,0 +1,85 @@
+//! Audit command
+//!
+//! `jamey audit verify` walks the runtime's signed audit log and checks that
+//! every entry follows the one before it and carries a valid signature. It
+//! exits with an error at the first entry that doesn't, so it can gate
+//! incident-response scripts. The head hash it prints is what catches
+//! entries cut from the end, once recorded somewhere else.
+
+use anyhow::{Context, Result};
+use colored::*;
+use jamey_core::secrets::SecretManager;
+use jamey_runtime::audit::{self, VerifyReport};
+use jamey_runtime::config::RuntimeConfig;
+use serde::Serialize;
+use std::path::PathBuf;
+
+/// What `--format json` prints
+#[derive(Serialize)]
+struct VerifyOutput<'a> {
+    path: PathBuf,
+    public_key: String,
+    #[serde(flatten)]
+    report: &'a VerifyReport,
+}
+
+pub async fn run_verify(public_key: Option<String>, dir: Option<PathBuf>, format: String) -> Result<()> {
+    if format != "table" && format != "json" {
+        return Err(anyhow::anyhow!("Invalid format: {}. Must be 'table' or 'json'", format));
+    }
+    let dir = match dir {
+        Some(dir) => dir,
+        None => RuntimeConfig::from_env()
+            .map_err(|e| anyhow::anyhow!("Failed to load runtime config: {}", e))?
+            .audit_dir,
+    };
+    let key = match public_key {
+        Some(hex_key) => audit::parse_verifying_key(&hex_key)?,
+        None => {
+            let backend = jamey_core::backend_from_env()
+                .context("Failed to open secrets backend")?;
+            let secrets = SecretManager::with_backend("jamey_runtime", backend)?;
+            audit::verifying_key(&secrets).context("No audit signing key in the secret store")?
+        }
+    };
+
+    let path = audit::log_path(&dir);
+    let report = audit::verify(&path, &key)
+        .with_context(|| format!("Failed to read audit log at {}", path.display()))?;
+    let output = VerifyOutput {
+        path,
+        public_key: audit::public_key_hex(&key),
+        report: &report,
+    };
+    match format.as_str() {
+        "json" => println!("{}", serde_json::to_string_pretty(&output)?),
+        _ => print_report(&output),
+    }
+
+    match &report.broken {
+        Some(broken) => Err(anyhow::anyhow!("Audit log is broken at line {}", broken.line)),
+        None => Ok(()),
+    }
+}
+
+fn print_report(output: &VerifyOutput) {
+    println!("{} {}", "Audit log".bold(), output.path.display());
+    println!("  {:<12} {}", "Public key", output.public_key);
+    println!("  {:<12} {}", "Verified", output.report.verified);
+    if let Some(head) = &output.report.head {
+        println!("  {:<12} {}", "Head", head);
+    }
+    match &output.report.broken {
+        None => println!("{} Chain and signatures are intact", "✅".green()),
+        Some(broken) => {
+            let seq = broken.seq.map_or_else(|| "?".to_string(), |seq| seq.to_string());
+            println!(
+                "{} Line {} (entry {}): {}",
+                "❌".red(),
+                broken.line,
+                seq,
+                broken.reason
+            );
+        }
+    }
+}
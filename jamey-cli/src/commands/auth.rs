@@ -0,0 +1,121 @@
+//! OAuth sign-in commands
+//!
+//! Authorize connectors (GitHub, Google, LinkedIn, Slack) without pasting
+//! long-lived tokens. Tokens are stored through the runtime's secret backend.
+
+use anyhow::{Context, Result};
+use colored::*;
+use crate::AuthAction;
+use jamey_core::secrets::SecretManager;
+use jamey_tools::oauth::{OAuthClientConfig, OAuthManager, OAuthProvider};
+use std::sync::Arc;
+
+/// Run auth action
+pub async fn run_auth_action(action: AuthAction) -> Result<()> {
+    match action {
+        AuthAction::Login { provider, browser } => login(&provider, browser).await,
+        AuthAction::Logout { provider } => logout(&provider).await,
+        AuthAction::Status => show_status().await,
+    }
+}
+
+fn secret_manager() -> Result<Arc<SecretManager>> {
+    let backend = jamey_core::backend_from_env()
+        .context("Failed to open secrets backend")?;
+    Ok(Arc::new(SecretManager::with_backend("jamey_runtime", backend)?))
+}
+
+fn oauth_manager(provider: OAuthProvider) -> Result<OAuthManager> {
+    let client = OAuthClientConfig::from_env(provider).ok_or_else(|| {
+        anyhow::anyhow!(
+            "No OAuth client configured for {}. Set JAMEY_{}_CLIENT_ID (and _CLIENT_SECRET if required).",
+            provider.as_str(),
+            provider.as_str().to_uppercase()
+        )
+    })?;
+    Ok(OAuthManager::new(secret_manager()?)?.with_client(client))
+}
+
+/// Sign in to a provider via device code or browser redirect
+async fn login(provider: &str, browser: bool) -> Result<()> {
+    let provider: OAuthProvider = provider.parse()?;
+    let manager = oauth_manager(provider)?;
+
+    println!("{} Signing in to {}", "🔐".cyan().bold(), provider.as_str());
+    println!();
+
+    let device = if browser {
+        None
+    } else {
+        match manager.start_device_flow(provider).await {
+            Ok(device) => Some(device),
+            Err(jamey_tools::oauth::OAuthError::DeviceFlowUnsupported(_)) => None,
+            Err(e) => return Err(e.into()),
+        }
+    };
+
+    let token = match device {
+        Some(device) => {
+            println!("{} Open: {}", "🌐".blue(), device.verification_uri.bold());
+            println!("{} Enter code: {}", "🔑".blue(), device.user_code.yellow().bold());
+            println!();
+            println!("{} Waiting for approval...", "⏳".yellow());
+            manager.complete_device_flow(provider, &device).await?
+        }
+        None => {
+            manager
+                .authorize_with_redirect(provider, |url| {
+                    println!("{} Open this URL in your browser to continue:", "🌐".blue());
+                    println!("  {}", url);
+                    println!();
+                    println!("{} Waiting for the redirect...", "⏳".yellow());
+                })
+                .await?
+        }
+    };
+
+    println!();
+    println!("{} Signed in to {}", "✅".green(), provider.as_str());
+    if let Some(expires_at) = token.expires_at {
+        println!("  Access token refreshes automatically (expires {})", expires_at.format("%Y-%m-%d %H:%M UTC"));
+    }
+    if let Some(scope) = token.scope {
+        println!("  Scopes: {}", scope);
+    }
+
+    Ok(())
+}
+
+/// Remove stored tokens for a provider
+async fn logout(provider: &str) -> Result<()> {
+    let provider: OAuthProvider = provider.parse()?;
+    let manager = OAuthManager::new(secret_manager()?)?;
+    manager.logout(provider).await?;
+    println!("{} Signed out of {}", "✓".green(), provider.as_str());
+    Ok(())
+}
+
+/// Show which providers are configured and signed in
+async fn show_status() -> Result<()> {
+    println!("{} OAuth Status", "🔐".cyan().bold());
+    println!("{}", "═".repeat(50));
+
+    let secrets = secret_manager()?;
+    println!("{} Secrets backend: {}", "📦".blue(), secrets.backend_name());
+    println!();
+
+    let manager = OAuthManager::new(secrets)?;
+    for provider in OAuthProvider::ALL {
+        let configured = OAuthClientConfig::from_env(provider).is_some();
+        let status = if manager.is_authorized(provider) {
+            "signed in".green()
+        } else if configured {
+            "not signed in".yellow()
+        } else {
+            "no client configured".dimmed()
+        };
+        println!("  {:<10} {}", provider.as_str(), status);
+    }
+
+    Ok(())
+}
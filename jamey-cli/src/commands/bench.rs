@@ -0,0 +1,228 @@
+//! Quick benchmark command
+//!
+//! `jamey bench` times the same hot paths as the criterion suites (memory
+//! stores, embedding encodings, cache serialization and context packing)
+//! against the installed build, with no external services. It is meant for
+//! a quick before/after comparison on a user's machine; use `cargo bench`
+//! for statistically sound numbers.
+
+use anyhow::Result;
+use chrono::Utc;
+use colored::*;
+use jamey_core::cache::CacheConfig;
+use jamey_core::memory::{
+    embedding_from_binary, embedding_from_text, embedding_to_binary, embedding_to_text,
+};
+use jamey_core::{CachedMemoryStore, InMemoryStore, Memory, MemoryStore, MemoryType};
+use jamey_runtime::project::{ChangeKind, FileChange, ProjectState};
+use serde::Serialize;
+use std::future::Future;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+const DIM: usize = 1536;
+const CORPUS: usize = 1000;
+
+/// Timings for one benchmark
+#[derive(Debug, Serialize)]
+struct BenchResult {
+    name: String,
+    iterations: usize,
+    mean_us: f64,
+    p50_us: f64,
+    p99_us: f64,
+    ops_per_sec: f64,
+}
+
+impl BenchResult {
+    fn new(name: String, mut samples: Vec<Duration>) -> Self {
+        samples.sort();
+        let micros = |d: Duration| d.as_secs_f64() * 1e6;
+        let total: Duration = samples.iter().sum();
+        let mean_us = micros(total) / samples.len() as f64;
+        Self {
+            name,
+            iterations: samples.len(),
+            mean_us,
+            p50_us: micros(samples[samples.len() / 2]),
+            p99_us: micros(samples[(samples.len() * 99 / 100).min(samples.len() - 1)]),
+            ops_per_sec: if mean_us > 0.0 { 1e6 / mean_us } else { 0.0 },
+        }
+    }
+}
+
+/// Runs the benchmarks whose names contain `filter`
+struct Runner {
+    iterations: usize,
+    filter: Option<String>,
+    results: Vec<BenchResult>,
+}
+
+impl Runner {
+    fn wants(&self, name: &str) -> bool {
+        self.filter.as_deref().is_none_or(|f| name.contains(f))
+    }
+
+    fn sync<T>(&mut self, name: &str, mut f: impl FnMut(usize) -> T) {
+        if !self.wants(name) {
+            return;
+        }
+        let samples = (0..self.iterations)
+            .map(|i| {
+                let started = Instant::now();
+                std::hint::black_box(f(i));
+                started.elapsed()
+            })
+            .collect();
+        self.results.push(BenchResult::new(name.to_string(), samples));
+    }
+
+    async fn run<F, Fut, T>(&mut self, name: &str, mut f: F) -> Result<()>
+    where
+        F: FnMut(usize) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        if !self.wants(name) {
+            return Ok(());
+        }
+        let mut samples = Vec::with_capacity(self.iterations);
+        for i in 0..self.iterations {
+            let started = Instant::now();
+            std::hint::black_box(f(i).await?);
+            samples.push(started.elapsed());
+        }
+        self.results.push(BenchResult::new(name.to_string(), samples));
+        Ok(())
+    }
+}
+
+/// Run bench command
+pub async fn run_bench(iterations: usize, filter: Option<String>, format: String) -> Result<()> {
+    if !matches!(format.as_str(), "table" | "json") {
+        return Err(anyhow::anyhow!("Invalid format: {}. Must be 'table' or 'json'", format));
+    }
+    if iterations == 0 {
+        return Err(anyhow::anyhow!("--iterations must be at least 1"));
+    }
+
+    let mut runner = Runner { iterations, filter, results: Vec::new() };
+
+    let plain = InMemoryStore::new();
+    let cached = CachedMemoryStore::new(InMemoryStore::new(), CacheConfig::default()).await?;
+    bench_store(&mut runner, "in_memory", &plain).await?;
+    bench_store(&mut runner, "cached_in_memory", &cached).await?;
+
+    let values = embedding(1);
+    let text = embedding_to_text(&values);
+    let binary = embedding_to_binary(&values)?;
+    runner.sync("embedding/text_encode", |_| embedding_to_text(&values));
+    runner.sync("embedding/text_decode", |_| embedding_from_text(&text));
+    runner.sync("embedding/binary_encode", |_| embedding_to_binary(&values));
+    runner.sync("embedding/binary_decode", |_| embedding_from_binary(&binary));
+
+    let memory = test_memory(1);
+    let encoded = serde_json::to_vec(&memory)?;
+    runner.sync("cache/serialize", |_| serde_json::to_vec(&memory));
+    runner.sync("cache/deserialize", |_| serde_json::from_slice::<Memory>(&encoded));
+
+    let state = project();
+    let hits: Vec<Memory> = (0..24).map(test_memory).map(|m| in_project(m, &state)).collect();
+    runner.sync("context/pack", |_| state.pack_context(&hits));
+
+    if runner.results.is_empty() {
+        println!("No benchmarks matched the filter.");
+        return Ok(());
+    }
+    match format.as_str() {
+        "json" => println!("{}", serde_json::to_string_pretty(&runner.results)?),
+        _ => print_table(&runner.results),
+    }
+    Ok(())
+}
+
+async fn bench_store<S: MemoryStore>(runner: &mut Runner, backend: &str, store: &S) -> Result<()> {
+    let name = |op: &str| format!("store/{}/{}", backend, op);
+    // Filling the corpus dominates the run, so skip it when nothing here is wanted
+    if !["store", "retrieve", "search"].iter().any(|op| runner.wants(&name(op))) {
+        return Ok(());
+    }
+
+    let mut ids = Vec::with_capacity(CORPUS);
+    for seed in 0..CORPUS {
+        ids.push(store.store(test_memory(seed)).await?);
+    }
+    let query = embedding(7);
+
+    runner.run(&name("store"), |i| store.store(test_memory(CORPUS + i))).await?;
+    runner.run(&name("retrieve"), |i| store.retrieve(ids[i % ids.len()])).await?;
+    runner.run(&name("search"), |_| store.search(&query, 10)).await?;
+    Ok(())
+}
+
+fn embedding(seed: usize) -> Vec<f32> {
+    (0..DIM).map(|i| (((seed * 31 + i * 17) % 1000) as f32 / 1000.0) - 0.5).collect()
+}
+
+fn test_memory(seed: usize) -> Memory {
+    Memory {
+        id: Uuid::new_v4(),
+        memory_type: MemoryType::Knowledge,
+        content: "fn example() { let value = compute(); }\n".repeat(20),
+        embedding: embedding(seed),
+        metadata: serde_json::json!({"benchmark": true, "seed": seed}),
+        created_at: Utc::now(),
+        last_accessed: Utc::now(),
+    }
+}
+
+fn project() -> ProjectState {
+    let mut state = ProjectState::new(PathBuf::from("/work/app"));
+    for i in 0..100 {
+        state.recent_changes.push_back(FileChange {
+            path: format!("src/module_{}.rs", i % 40),
+            kind: ChangeKind::Modified,
+            at: Utc::now(),
+            chunks: 2,
+        });
+    }
+    state
+}
+
+fn in_project(mut memory: Memory, state: &ProjectState) -> Memory {
+    memory.metadata = serde_json::json!({
+        "namespace": state.namespace,
+        "source": state.root.join("src/lib.rs"),
+    });
+    memory
+}
+
+fn print_table(results: &[BenchResult]) {
+    println!("{} Benchmarks", "⏱".cyan().bold());
+    println!("{}", "─".repeat(90));
+    println!(
+        "{:<32} {:>8} {:>11} {:>11} {:>11} {:>11}",
+        "NAME", "ITERS", "MEAN µs", "P50 µs", "P99 µs", "OPS/S"
+    );
+    for result in results {
+        println!(
+            "{:<32} {:>8} {:>11.2} {:>11.2} {:>11.2} {:>11.0}",
+            result.name, result.iterations, result.mean_us, result.p50_us, result.p99_us, result.ops_per_sec
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bench_result_percentiles() {
+        let samples = (1..=100).map(Duration::from_micros).collect();
+        let result = BenchResult::new("x".to_string(), samples);
+        assert_eq!(result.iterations, 100);
+        assert!((result.mean_us - 50.5).abs() < 1e-6);
+        assert!((result.p50_us - 51.0).abs() < 1e-6);
+        assert!((result.p99_us - 100.0).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,65 @@
+//! Briefing command
+//!
+//! `jamey briefing` prints the newest briefing the `cli` channel saved and
+//! marks it read. `jamey briefing --now` gathers the configured sources,
+//! composes a briefing and delivers it to every configured channel straight
+//! away, which is also how a new schedule or channel can be tried out.
+
+use anyhow::{Context, Result};
+use chrono::Local;
+use colored::*;
+use jamey_runtime::briefing::{self, BriefingStore};
+use jamey_runtime::config::RuntimeConfig;
+use jamey_runtime::Runtime;
+use crate::render::ReplyWriter;
+
+pub async fn run_briefing(now: bool) -> Result<()> {
+    let config = RuntimeConfig::from_env()
+        .map_err(|e| anyhow::anyhow!("Failed to load runtime config: {}", e))?;
+    if now {
+        return compose_now(config).await;
+    }
+
+    let store = BriefingStore::new(&config.briefing.dir);
+    let Some((at, text)) = store.latest().await? else {
+        println!("{} No briefings yet", "📰".cyan());
+        println!(
+            "{}",
+            "Set JAMEY_BRIEFING_SCHEDULE for regular ones or run `jamey briefing --now`".dimmed()
+        );
+        return Ok(());
+    };
+    println!(
+        "{} {}",
+        "📰".cyan(),
+        format!("Briefing from {}", at.with_timezone(&Local).format("%a %-d %b %H:%M")).bold()
+    );
+    println!();
+    println!("{}", ReplyWriter::new(true).whole(&text));
+    store.mark_read(at).await?;
+    Ok(())
+}
+
+async fn compose_now(config: RuntimeConfig) -> Result<()> {
+    let store = BriefingStore::new(&config.briefing.dir);
+    let runtime = Runtime::new(config).await
+        .context("Failed to initialize runtime for the briefing")?;
+    eprintln!("{} Gathering and composing a briefing...", "📰".cyan());
+    let result = briefing::run(runtime.state()).await;
+    runtime.shutdown().await;
+    let briefing = result?;
+
+    println!("{}", ReplyWriter::new(true).whole(&briefing.text));
+    // Already on screen, so `jamey chat` needn't point it out
+    store.mark_read(briefing.at).await?;
+    eprintln!();
+    for warning in &briefing.warnings {
+        eprintln!("{} {}", "⚠️".yellow(), warning);
+    }
+    if briefing.delivered.is_empty() {
+        eprintln!("{}", "No channels configured; set JAMEY_BRIEFING_CHANNELS".dimmed());
+    } else {
+        eprintln!("{} Delivered to {}", "✓".green(), briefing.delivered.join(", "));
+    }
+    Ok(())
+}
@@ -1,56 +1,186 @@
 //! Chat command implementation
 //! 
-//! Interactive chat interface for conversing with Jamey
+//! Interactive chat interface for conversing with Jamey. Replies stream in
+//! token by token and are rendered as Markdown unless `--raw` is given;
+//! Ctrl+C cancels the current turn and keeps the session. `/attach <path>`
+//! uploads a file to go with the next message, and `/up`, `/down` and
+//! `/correct` record feedback on the last reply. With `--speak` each reply is
+//! also read aloud while the next message is typed; with `--voice` Enter on
+//! an empty line records a spoken message instead.
 
 use anyhow::{Context, Result};
 use colored::*;
 use crossterm::{
-    event::{self, Event, KeyCode, KeyEvent},
     execute,
-    terminal::{disable_raw_mode, enable_raw_mode, Clear, ClearType},
-    cursor::{MoveTo, Show, Hide},
+    terminal::{Clear, ClearType},
+    cursor::MoveTo,
 };
 use std::io::{stdout, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use tokio::sync::RwLock;
-use uuid::Uuid;
-use jamey_protocol::{Message, Role, ProcessMessageRequest, ProcessContext};
+use tokio::sync::{Notify, RwLock};
+use tokio::task::JoinHandle;
+use jamey_protocol::{Attachment, Citation, ContentPart, Message, Role, TokenUsage, ToolCall, ToolResult};
+use jamey_runtime::archive::ArchiveError;
+use jamey_runtime::briefing::BriefingStore;
+use jamey_runtime::chat::TurnEvent;
+use jamey_runtime::feedback::{local_user, Feedback, Rating};
+use jamey_runtime::guardrails::Strictness;
+use jamey_runtime::session_store::SessionStoreError;
+use jamey_runtime::summarize::Compaction;
+use jamey_runtime::voice::{VoiceInput, VoiceOutput};
 use jamey_runtime::Runtime;
-use tracing::{info, debug, error};
+use crate::i18n::t;
+use crate::microphone::Microphone;
+use crate::render::ReplyWriter;
+use crate::utils::format_bytes;
+use tracing::error;
+use uuid::Uuid;
+
+/// Model used when neither `--model` nor the persona names one
+pub(crate) const DEFAULT_MODEL: &str = "claude-3-sonnet";
+
+/// Lines of tool output shown before it is collapsed
+const COLLAPSED_OUTPUT_LINES: usize = 8;
+
+/// Presentation and input flags from the command line
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ChatFlags {
+    pub verbose: bool,
+    pub raw: bool,
+    pub speak: bool,
+    pub voice: bool,
+    /// Overrides `guardrails.strictness` for this session
+    pub strictness: Option<Strictness>,
+}
 
 /// Run interactive chat session
 pub async fn run_chat(
     session_id: Option<String>,
-    model: String,
-    verbose: bool,
+    model: Option<String>,
+    persona: Option<String>,
+    workspace: Option<String>,
+    flags: ChatFlags,
 ) -> Result<()> {
-    println!("{}", "🤖 Digital Twin Jamey - Chat Mode".bright_cyan().bold());
-    println!("{}", "Type 'exit' or press Ctrl+C to quit".dim());
-    println!("{}", "Type 'help' for available commands".dim());
+    let ChatFlags { verbose, raw, speak, voice: voice_input, strictness } = flags;
+    println!("{}", format!("🤖 {}", t!("chat-banner")).bright_cyan().bold());
+    println!("{}", t!("chat-hint-exit").dimmed());
+    println!("{}", t!("chat-hint-cancel").dimmed());
+    println!("{}", t!("chat-hint-help").dimmed());
     println!();
 
     // Initialize runtime
-    let config = load_runtime_config(&model).await?;
-    let mut runtime = Runtime::new(config).await?;
-    
+    let config = load_runtime_config(model.as_deref().unwrap_or(DEFAULT_MODEL)).await?;
+    let voice = if speak {
+        Some(Arc::new(VoiceOutput::new(&config.voice).context("Voice replies are not available")?))
+    } else {
+        None
+    };
+    let listener = if voice_input {
+        let device = Microphone::device_name().context("Voice input is not available")?;
+        println!(
+            "{} {}",
+            "🎙️".cyan(),
+            t!("chat-listening-with", "device" => device.bold().to_string())
+        );
+        println!();
+        Some(VoiceInput::new(&config.voice).context("Voice input is not available")?)
+    } else {
+        None
+    };
+    let runtime = Runtime::new(config).await?;
+    runtime.notify_desktop();
+    // Scheduled briefings go out while the chat is open
+    runtime.start_scheduler().await;
+
     // Create or resume session
+    let session_store = Arc::clone(&runtime.state().session_store);
     let session_id = if let Some(id) = session_id {
-        // Validate UUID format
-        crate::utils::validate_uuid(&id)
-            .with_context(|| format!("Invalid session ID format: {}", id))?
+        // Accepts a full UUID or a unique prefix from `jamey sessions list`
+        let id = session_store.resolve(&id).await
+            .with_context(|| format!("Unknown session: {}", id))?;
+        match runtime.state().revive_session(id).await {
+            Ok(_) => id,
+            // Nothing saved under a full ID yet; the conversation starts under it
+            Err(ArchiveError::Sessions(SessionStoreError::NotFound(_))) => {
+                runtime.state().session_manager.resume_session(id)
+            }
+            Err(e) => return Err(e).with_context(|| format!("Failed to resume session {}", id)),
+        }
     } else {
         runtime.state().session_manager.create_session()
     };
+    runtime.state().session_manager.set_user(session_id, &local_user());
+    if let Some(strictness) = strictness {
+        runtime.state().session_manager.set_strictness(session_id, strictness);
+    }
+
+    let state = runtime.state();
+    let persona_name = persona.or_else(|| state.config.default_persona.clone());
+    let mut persona = match persona_name {
+        Some(name) => state.persona_store.get(&name).await?,
+        None => state.persona_store.resolve(None).await,
+    };
+    if model.is_some() {
+        // An explicit --model beats the persona's preference
+        persona.model = model;
+    }
+    let model = persona
+        .model
+        .clone()
+        .unwrap_or_else(|| state.config.llm.openrouter_default_model.clone());
+    let persona_label = persona.name.clone();
+    state.session_manager.set_persona(session_id, persona);
+    let workspace = match workspace {
+        Some(name) => Some(state.workspace_store.get(&name).await?),
+        None => None,
+    };
+    let workspace_label = workspace.as_ref().map(|w| format!("{} ({})", w.name, w.root.display()));
+    state.session_manager.set_workspace(session_id, workspace);
 
-    println!("{} Session ID: {}", "📝".blue(), session_id);
+    // Chat history, restored from the transcript when resuming
+    let previous = match session_store.load(session_id).await {
+        Ok(record) => record.messages,
+        Err(SessionStoreError::NotFound(_)) => Vec::new(),
+        Err(e) => return Err(e.into()),
+    };
+
+    println!("{} {}", "📝".blue(), t!("chat-session", "id" => session_id.to_string()));
+    println!("{} {}", "🎭".blue(), t!("chat-persona", "persona" => persona_label, "model" => model.as_str()));
+    if let Some(label) = workspace_label {
+        println!("{} {}", "📁".blue(), t!("chat-workspace", "workspace" => label));
+    }
+    if !previous.is_empty() {
+        println!("{} {}", "↩️".blue(), t!("chat-resumed", "count" => previous.len()));
+    }
+    let briefings = BriefingStore::new(&runtime.state().config.briefing.dir);
+    if let Ok(Some(at)) = briefings.unread().await {
+        println!(
+            "{} {}",
+            "📰".cyan(),
+            t!(
+                "chat-new-briefing",
+                "at" => at.with_timezone(&chrono::Local).format("%a %H:%M").to_string(),
+                "command" => "jamey briefing".bold().to_string(),
+            )
+        );
+    }
     println!();
 
-    // Chat history
-    let chat_history = Arc::new(RwLock::new(Vec::<Message>::new()));
+    let chat_history = Arc::new(RwLock::new(previous));
+    // Tool results of the last turn, for `expand`
+    let mut last_tool_results: Vec<ToolResult> = Vec::new();
+    // Files from `/attach`, sent with the next message
+    let mut pending_attachments: Vec<Attachment> = Vec::new();
+    // Playback of the last reply, stopped when the next one arrives
+    let mut speaking: Option<JoinHandle<()>> = None;
+
+    let interrupt = Interrupt::install();
 
     // Main chat loop
     loop {
-        print!("{} ", "You:".green().bold());
+        print!("{} ", t!("chat-you").green().bold());
         stdout().flush()?;
 
         let mut input = String::new();
@@ -61,12 +191,30 @@ pub async fn run_chat(
             continue;
         }
 
-        let input = input.trim().to_string();
+        let mut input = input.trim().to_string();
+        // With --voice an empty line is push-to-talk
+        if input.is_empty() {
+            if let Some(listener) = &listener {
+                match listen(listener).await {
+                    Some(text) => input = text,
+                    None => continue,
+                }
+            }
+        }
+
+        if let Some(path) = input.strip_prefix("/attach").filter(|rest| rest.is_empty() || rest.starts_with(' ')) {
+            attach_file(&runtime, path.trim(), &mut pending_attachments).await;
+            continue;
+        }
+        if let Some(feedback) = Feedback::from_command(&input) {
+            give_feedback(&runtime, session_id, &chat_history, feedback).await;
+            continue;
+        }
         
         // Handle special commands
         match input.as_str() {
             "exit" | "quit" => {
-                println!("{} Goodbye!", "👋".yellow());
+                println!("{} {}", "👋".yellow(), t!("chat-goodbye"));
                 break;
             }
             "help" => {
@@ -82,52 +230,56 @@ pub async fn run_chat(
                 show_history(&chat_history).await;
                 continue;
             }
+            "expand" => {
+                expand_tool_results(&last_tool_results);
+                continue;
+            }
+            "/undo" => {
+                undo_last_turn(&runtime, session_id).await;
+                continue;
+            }
             "" => continue, // Skip empty input
             _ => {}
         }
 
         // Add user message to history
-        let user_message = Message::user(input.clone());
-        chat_history.write().await.push(user_message.clone());
+        chat_history.write().await.push(Message::user(input).with_attachments(&pending_attachments));
+        let history = chat_history.read().await.clone();
 
-        if verbose {
-            println!("{} Processing message...", "⏳".yellow());
-        }
+        match stream_reply(&runtime, session_id, history, verbose, raw, &interrupt).await {
+            Ok(TurnOutcome::Completed { message, tool_results, compaction }) => {
+                let mut history = chat_history.write().await;
+                if let Some(compaction) = compaction {
+                    *history = compaction.apply(&history);
+                }
+                let exchange: Vec<Message> = history.last().cloned().into_iter()
+                    .chain(std::iter::once(message.clone()))
+                    .collect();
+                history.push(message);
+                drop(history);
 
-        // Process message through Jamey
-        match process_message(&runtime, session_id, &user_message, verbose).await {
-            Ok(response) => {
-                // Display assistant response
-                println!("{} {}", "Jamey:".blue().bold(), response.message.content);
-                
-                // Add to history
-                chat_history.write().await.push(response.message);
-                
-                // Show tool results if any
-                if !response.tool_results.is_empty() && verbose {
-                    println!("{} Tool Results:", "🔧".cyan());
-                    for result in response.tool_results {
-                        if result.success {
-                            println!("  ✅ {}: {}", result.name, result.output);
-                        } else {
-                            println!("  ❌ {}: {}", result.name, 
-                                result.error.unwrap_or_else(|| "Unknown error".to_string()));
-                        }
-                    }
+                if let Err(e) = session_store.append(session_id, &exchange, Some(&model)).await {
+                    error!("Failed to save session transcript: {}", e);
                 }
-                
-                // Show token usage if verbose
-                if verbose {
-                    println!("{} Tokens: {} prompt + {} completion = {} total", 
-                        "📊".magenta(),
-                        response.usage.prompt_tokens,
-                        response.usage.completion_tokens,
-                        response.usage.total_tokens);
+                last_tool_results = tool_results;
+                pending_attachments.clear();
+
+                if let (Some(voice), Some(reply)) = (&voice, exchange.last()) {
+                    if let Some(previous) = speaking.take() {
+                        previous.abort();
+                    }
+                    speaking = speak_reply(voice, &reply.content).await;
                 }
             }
+            Ok(TurnOutcome::Cancelled) => {
+                // Forget the prompt so it isn't replayed with the next turn
+                chat_history.write().await.pop();
+                println!("{} {}", "⏹".yellow(), t!("chat-turn-cancelled"));
+            }
             Err(e) => {
+                chat_history.write().await.pop();
                 error!("Failed to process message: {}", e);
-                println!("{} {}", "❌".red(), "Sorry, I encountered an error processing your message.");
+                println!("{} {}", "❌".red(), t!("chat-turn-failed", "error" => e.to_string()));
             }
         }
         
@@ -135,12 +287,51 @@ pub async fn run_chat(
     }
 
     // Cleanup
+    if let Some(playback) = speaking {
+        playback.abort();
+    }
     runtime.shutdown().await;
     Ok(())
 }
 
+/// Record until Enter is pressed and return what was said
+async fn listen(listener: &VoiceInput) -> Option<String> {
+    let microphone = match Microphone::start() {
+        Ok(microphone) => microphone,
+        Err(e) => {
+            println!("{} {:#}", "❌".red(), e);
+            return None;
+        }
+    };
+    print!("{} {}", "🎙️".red(), t!("chat-listening").dimmed());
+    let _ = stdout().flush();
+    let mut line = String::new();
+    let _ = std::io::stdin().read_line(&mut line);
+    let recording = microphone.stop();
+    if recording.seconds() < 0.3 {
+        println!("{} {}", "🔇".yellow(), t!("chat-too-short"));
+        return None;
+    }
+
+    match listener.transcribe(&recording.samples, recording.sample_rate).await {
+        Ok(text) if !text.is_empty() => {
+            println!("{} {}", t!("chat-you").green().bold(), text);
+            Some(text)
+        }
+        Ok(_) => {
+            println!("{} {}", "🔇".yellow(), t!("chat-not-heard"));
+            None
+        }
+        Err(e) => {
+            error!("Transcription failed: {}", e);
+            println!("{} {}", "❌".red(), t!("chat-transcription-failed", "error" => e.to_string()));
+            None
+        }
+    }
+}
+
 /// Load runtime configuration for chat
-async fn load_runtime_config(model: &str) -> Result<jamey_runtime::RuntimeConfig> {
+pub(crate) async fn load_runtime_config(model: &str) -> Result<jamey_runtime::RuntimeConfig> {
     let mut config = jamey_runtime::RuntimeConfig::from_env()
         .map_err(|e| anyhow::anyhow!("Failed to load config: {}", e))?;
     
@@ -150,106 +341,417 @@ async fn load_runtime_config(model: &str) -> Result<jamey_runtime::RuntimeConfig
     Ok(config)
 }
 
-/// Process a message through the runtime
-async fn process_message(
+/// Routes Ctrl+C: cancels the running turn, or exits when idle at the prompt
+struct Interrupt {
+    in_turn: Arc<AtomicBool>,
+    cancel: Arc<Notify>,
+}
+
+impl Interrupt {
+    fn install() -> Self {
+        let in_turn = Arc::new(AtomicBool::new(false));
+        let cancel = Arc::new(Notify::new());
+
+        let (flag, notify) = (Arc::clone(&in_turn), Arc::clone(&cancel));
+        tokio::spawn(async move {
+            while tokio::signal::ctrl_c().await.is_ok() {
+                if flag.load(Ordering::SeqCst) {
+                    notify.notify_waiters();
+                } else {
+                    println!();
+                    println!("{} {}", "👋".yellow(), t!("chat-goodbye"));
+                    std::process::exit(0);
+                }
+            }
+        });
+
+        Self { in_turn, cancel }
+    }
+}
+
+/// Marks a turn as running until dropped, so Ctrl+C cancels instead of exiting
+struct TurnGuard<'a>(&'a AtomicBool);
+
+impl<'a> TurnGuard<'a> {
+    fn enter(flag: &'a AtomicBool) -> Self {
+        flag.store(true, Ordering::SeqCst);
+        Self(flag)
+    }
+}
+
+impl Drop for TurnGuard<'_> {
+    fn drop(&mut self) {
+        self.0.store(false, Ordering::SeqCst);
+    }
+}
+
+enum TurnOutcome {
+    Completed {
+        message: Message,
+        tool_results: Vec<ToolResult>,
+        /// Summary that replaced the oldest history for this turn
+        compaction: Option<Box<Compaction>>,
+    },
+    Cancelled,
+}
+
+/// Stream one turn to the terminal
+async fn stream_reply(
     runtime: &Runtime,
     session_id: Uuid,
-    message: &Message,
+    history: Vec<Message>,
     verbose: bool,
-) -> Result<jamey_protocol::ProcessMessageResponse> {
-    let state = runtime.state();
-    let start_time = std::time::Instant::now();
-    
+    raw: bool,
+    interrupt: &Interrupt,
+) -> Result<TurnOutcome> {
+    let mut turn = runtime.state().stream_session_turn(session_id, history);
+    let mut out = stdout();
+    let mut tool_results = Vec::new();
+    let mut compaction = None;
+    let mut usage = None;
+    let mut reply_started = false;
+    let mut writer = ReplyWriter::new(!raw);
+
+    let cancelled = interrupt.cancel.notified();
+    tokio::pin!(cancelled);
+    cancelled.as_mut().enable();
+    let _guard = TurnGuard::enter(&interrupt.in_turn);
+
+    loop {
+        let event = tokio::select! {
+            _ = &mut cancelled => {
+                turn.cancel();
+                if reply_started {
+                    println!("{}", writer.finish());
+                }
+                return Ok(TurnOutcome::Cancelled);
+            }
+            event = turn.next() => event,
+        };
+
+        let Some(event) = event else {
+            anyhow::bail!("Turn ended without a reply");
+        };
+
+        match event {
+            TurnEvent::Summarized(summary) => {
+                println!("{}", format!("📝 {}", t!("chat-summarized", "count" => summary.replaced)).dimmed());
+                compaction = Some(summary);
+            }
+            TurnEvent::Token(text) => {
+                if !reply_started {
+                    print_reply_label(&writer);
+                    reply_started = true;
+                }
+                print!("{}", writer.push(&text));
+                out.flush()?;
+            }
+            TurnEvent::ToolCall(call) => {
+                if reply_started {
+                    println!("{}", writer.finish());
+                    reply_started = false;
+                }
+                print_tool_call(&call, verbose);
+            }
+            TurnEvent::AwaitingApproval(request) => {
+                let short_id = &request.id.to_string()[..8];
+                println!(
+                    "   {} {}",
+                    "⏸️".yellow(),
+                    t!(
+                        "chat-awaiting-approval",
+                        "id" => short_id.yellow().to_string(),
+                        "command" => format!("jamey approvals approve {}", short_id).bold().to_string(),
+                    )
+                );
+            }
+            TurnEvent::ToolResult(result) => {
+                print_tool_result(&result, verbose);
+                tool_results.push(result);
+            }
+            TurnEvent::Usage { usage: turn_usage, cost_usd } => {
+                usage = Some((turn_usage, cost_usd));
+            }
+            TurnEvent::Completed(message) => {
+                if reply_started {
+                    print!("{}", writer.finish());
+                } else {
+                    print_reply_label(&writer);
+                    print!("{}", writer.whole(&message.content));
+                }
+                if !writer.is_rendering() {
+                    println!();
+                }
+                print_citations(&message.citations);
+                if let Some(level) = message.metadata.get("degradation").and_then(|l| l.as_str()) {
+                    println!("{}", format!("⚠️  {}", t!("chat-degraded", "level" => level.replace('_', " "))).yellow());
+                }
+                if let Some((turn_usage, cost_usd)) = usage.take() {
+                    print_usage(&turn_usage, cost_usd);
+                }
+                return Ok(TurnOutcome::Completed { message, tool_results, compaction: compaction.map(Box::new) });
+            }
+            TurnEvent::Failed(e) => {
+                if reply_started {
+                    println!("{}", writer.finish());
+                }
+                anyhow::bail!(e);
+            }
+        }
+    }
+}
+
+/// Synthesize a reply, then play it in the background; aborting the
+/// returned task stops playback
+async fn speak_reply(voice: &Arc<VoiceOutput>, text: &str) -> Option<JoinHandle<()>> {
+    let files = match voice.synthesize(text).await {
+        Ok(files) if !files.is_empty() => files,
+        Ok(_) => return None,
+        Err(e) => {
+            println!("{} {}", "🔇".yellow(), t!("chat-speak-failed", "error" => e.to_string()));
+            return None;
+        }
+    };
+    let voice = Arc::clone(voice);
+    Some(tokio::spawn(async move {
+        match voice.play(&files).await {
+            Ok(true) => {}
+            Ok(false) => {
+                for file in &files {
+                    println!("{} {}", "🔊".cyan(), t!("chat-no-player", "file" => file.display().to_string()));
+                }
+            }
+            Err(e) => println!("{} {}", "🔇".yellow(), e),
+        }
+    }))
+}
+
+/// Rendered replies start on their own line, so headings and code line up
+fn print_reply_label(writer: &ReplyWriter) {
+    if writer.is_rendering() {
+        println!("{}", t!("chat-jamey").blue().bold());
+    } else {
+        print!("{} ", t!("chat-jamey").blue().bold());
+    }
+}
+
+fn print_tool_call(call: &ToolCall, verbose: bool) {
+    let action = call.args.get("action").and_then(|a| a.as_str()).unwrap_or("?");
+    println!("{} {} {}", "🔧".cyan(), call.name.cyan().bold(), action.dimmed());
     if verbose {
-        debug!("Processing message for session {}: {}", session_id, message.content);
+        let args = serde_json::to_string_pretty(&call.args).unwrap_or_else(|_| call.args.to_string());
+        for line in args.lines() {
+            println!("   {}", line.dimmed());
+        }
     }
+}
 
-    // Get session (should already exist from run_chat)
-    let session = state.session_manager.get_session(session_id)
-        .ok_or_else(|| anyhow::anyhow!("Session not found: {}. Session may have expired.", session_id))?;
-    
-    // Build message history for LLM
-    let mut llm_messages = Vec::new();
-    
-    // Add system message if this is a new conversation
-    if session.memory_context.is_empty() {
-        llm_messages.push(jamey_providers::openrouter::Message {
-            role: "system".to_string(),
-            content: "You are Jamey, a helpful AI assistant. Be concise, accurate, and helpful.".to_string(),
-        });
+fn print_tool_result(result: &ToolResult, verbose: bool) {
+    let elapsed = result
+        .execution_time_ms
+        .map(|ms| format!(" ({} ms)", ms))
+        .unwrap_or_default();
+
+    if !result.success {
+        let error = result.error.clone().unwrap_or_else(|| t!("chat-tool-unknown-error"));
+        println!("   {} {}{}", "❌".red(), error, elapsed.dimmed());
+        return;
     }
-    
-    // Convert protocol messages to provider messages
-    // For now, we'll just send the current message
-    // In a full implementation, we'd include conversation history
-    llm_messages.push(jamey_providers::openrouter::Message {
-        role: match message.role {
-            jamey_protocol::Role::User => "user".to_string(),
-            jamey_protocol::Role::Assistant => "assistant".to_string(),
-            jamey_protocol::Role::System => "system".to_string(),
-            jamey_protocol::Role::Tool => "tool".to_string(),
-        },
-        content: message.content.clone(),
-    });
-    
-    // Create chat request
-    let chat_request = jamey_providers::openrouter::ChatRequest {
-        model: state.config.llm.openrouter_default_model.clone(),
-        messages: llm_messages,
-        tools: None,
-        tool_choice: None,
-        temperature: Some(0.7),
-        max_tokens: Some(4000),
-    };
-    
-    // Call LLM provider
-    let chat_response = state.llm_provider.chat(chat_request).await
-        .with_context(|| "Failed to get response from LLM provider")?;
-    
-    // Extract response
-    let assistant_message = chat_response.choices
-        .first()
-        .and_then(|c| Some(c.message.content.clone()))
-        .unwrap_or_else(|| "No response from LLM".to_string());
-    
-    let processing_time_ms = start_time.elapsed().as_millis() as u64;
-    
-    // Create protocol response
-    let response = jamey_protocol::ProcessMessageResponse {
-        session_id,
-        message: jamey_protocol::Message::assistant(assistant_message),
-        tool_calls: vec![], // TODO: Extract tool calls from response
-        tool_results: vec![],
-        memory_entries_added: 0, // TODO: Store message in memory
-        processing_time_ms,
-        usage: jamey_protocol::TokenUsage {
-            prompt_tokens: chat_response.usage.prompt_tokens,
-            completion_tokens: chat_response.usage.completion_tokens,
-            total_tokens: chat_response.usage.total_tokens,
-        },
-    };
 
-    Ok(response)
+    let lines: Vec<&str> = result.output.lines().collect();
+    println!("   {} {}{}", "✅".green(), t!("chat-tool-output", "count" => lines.len()), elapsed.dimmed());
+    for attachment in &result.attachments {
+        println!("   📎 {} ({})", attachment.name, attachment.id.to_string().dimmed());
+    }
+    if verbose {
+        for line in lines.iter().take(COLLAPSED_OUTPUT_LINES) {
+            println!("   │ {}", line);
+        }
+        if lines.len() > COLLAPSED_OUTPUT_LINES {
+            println!(
+                "   {} {}",
+                "▸".dimmed(),
+                t!("chat-tool-collapsed", "count" => lines.len() - COLLAPSED_OUTPUT_LINES)
+            );
+        }
+    }
+}
+
+/// Print full output of the last turn's tool calls
+fn expand_tool_results(results: &[ToolResult]) {
+    if results.is_empty() {
+        println!("{} {}", "ℹ️".blue(), t!("chat-no-tool-output"));
+        return;
+    }
+    for result in results {
+        println!("{} {} ({})", "🔧".cyan(), result.name.cyan().bold(), result.id.dimmed());
+        match result.error {
+            Some(ref error) => println!("   {} {}", "❌".red(), error),
+            None => {
+                for line in result.output.lines() {
+                    println!("   │ {}", line);
+                }
+            }
+        }
+    }
+    println!();
+}
+
+/// Footnotes for the memories a reply was given, numbered as the reply cites them
+pub(crate) fn print_citations(citations: &[Citation]) {
+    for (i, citation) in citations.iter().enumerate() {
+        println!(
+            "{} {} {}",
+            format!("[{}]", i + 1).cyan(),
+            citation.snippet,
+            t!(
+                "chat-citation",
+                "similarity" => format!("{:.0}", citation.similarity * 100.0),
+                "memory" => citation.memory_id.to_string(),
+            )
+            .dimmed()
+        );
+    }
+}
+
+fn print_usage(usage: &TokenUsage, cost_usd: Option<f64>) {
+    let cost = cost_usd
+        .map(|cost| format!(" · ${:.4}", cost))
+        .unwrap_or_default();
+    println!(
+        "{}",
+        format!(
+            "📊 {}{}",
+            t!(
+                "chat-usage",
+                "prompt" => usage.prompt_tokens,
+                "completion" => usage.completion_tokens,
+                "total" => usage.total_tokens,
+            ),
+            cost
+        )
+        .dimmed()
+    );
 }
 
 /// Print help information
 fn print_help() {
-    println!("{} Available Commands:", "📖".cyan());
-    println!("  {}  Exit the chat", "exit, quit".yellow());
-    println!("  {}  Show this help", "help".yellow());
-    println!("  {}  Clear the screen", "clear".yellow());
-    println!("  {}  Show chat history", "history".yellow());
-    println!("  {}  Start a new session", "new".yellow());
-    println!("  {}  Save current session", "save".yellow());
-    println!("  {}  Load saved session", "load <id>".yellow());
+    const COMMANDS: &[(&str, &str)] = &[
+        ("exit, quit", "chat-help-exit"),
+        ("help", "chat-help-help"),
+        ("clear", "chat-help-clear"),
+        ("history", "chat-help-history"),
+        ("expand", "chat-help-expand"),
+        ("/attach <path>", "chat-help-attach"),
+        ("/up [note]", "chat-help-up"),
+        ("/down [correction]", "chat-help-down"),
+        ("/correct <text>", "chat-help-correct"),
+        ("/undo", "chat-help-undo"),
+        ("new", "chat-help-new"),
+        ("save", "chat-help-save"),
+        ("load <id>", "chat-help-load"),
+    ];
+    println!("{} {}", "📖".cyan(), t!("chat-help-title"));
+    for (command, help) in COMMANDS {
+        println!("  {}  {}", command.yellow(), t!(help));
+    }
     println!();
 }
 
+/// Upload a file for the next message, or list what is queued when no path is given
+async fn attach_file(runtime: &Runtime, path: &str, pending: &mut Vec<Attachment>) {
+    if path.is_empty() {
+        if pending.is_empty() {
+            println!("{} {}", "💡".yellow(), t!("chat-usage-hint", "usage" => "/attach <path>".bold().to_string()));
+        }
+        for attachment in pending.iter() {
+            println!("{} {} {}", "📎".cyan(), attachment.name, t!("chat-queued").dimmed());
+        }
+        return;
+    }
+
+    let path = PathBuf::from(path.trim_matches(|c| c == '"' || c == '\''));
+    match runtime.state().attachment_store.upload(&path).await {
+        Ok(attachment) => {
+            println!(
+                "{} {} {}",
+                "📎".cyan(),
+                t!("chat-attached", "name" => attachment.name.bold().to_string()),
+                format!("({}, {})", attachment.mime_type, format_bytes(attachment.size_bytes)).dimmed()
+            );
+            println!("{}", t!("chat-attached-note").dimmed());
+            pending.push(attachment);
+        }
+        Err(e) => println!(
+            "{} {}",
+            "❌".red(),
+            t!("chat-attach-failed", "path" => path.display().to_string(), "error" => e.to_string())
+        ),
+    }
+}
+
+/// Record feedback on the newest reply; it shapes replies in later sessions too
+async fn give_feedback(runtime: &Runtime, session_id: Uuid, history: &Arc<RwLock<Vec<Message>>>, feedback: Feedback) {
+    if feedback.rating.is_none() && feedback.correction.is_none() {
+        println!("{} {}", "💡".yellow(), t!("chat-usage-hint", "usage" => t!("chat-correct-usage").bold().to_string()));
+        return;
+    }
+    let reply = history.read().await.iter().rev().find(|m| m.role == Role::Assistant).map(|m| m.id);
+    let Some(reply) = reply else {
+        println!("{} {}", "💡".yellow(), t!("chat-feedback-nothing"));
+        return;
+    };
+    let icon = match feedback.rating {
+        Some(Rating::Up) => "👍",
+        Some(Rating::Down) => "👎",
+        None => "✏️",
+    };
+    match runtime.state().record_feedback(session_id, reply, &local_user(), feedback).await {
+        Ok(entry) => println!(
+            "{} {} {}",
+            icon,
+            t!("chat-feedback-saved"),
+            t!("chat-feedback-memory", "memory" => entry.memory_id.to_string()).dimmed()
+        ),
+        Err(e) => println!("{} {}", "❌".red(), t!("chat-feedback-failed", "error" => e.to_string())),
+    }
+}
+
+/// Reverse what the session's latest turn with reversible effects changed
+async fn undo_last_turn(runtime: &Runtime, session_id: Uuid) {
+    let state = runtime.state();
+    let turn = match state.undo_log.last_for(session_id).await {
+        Ok(Some(turn)) => turn,
+        Ok(None) => {
+            println!("{} {}", "💡".yellow(), t!("chat-undo-nothing"));
+            return;
+        }
+        Err(e) => {
+            println!("{} {}", "❌".red(), t!("chat-undo-log-failed", "error" => e.to_string()));
+            return;
+        }
+    };
+    match state.rollback(turn).await {
+        Ok(report) => {
+            for undone in &report.undone {
+                println!("{} {}", "↩️".green(), undone);
+            }
+            for (effect, reason) in &report.failed {
+                println!("{} {}: {}", "❌".red(), effect, reason);
+            }
+            if !report.failed.is_empty() {
+                println!("{} {}", "💡".yellow(), t!("chat-undo-retry", "command" => "/undo".bold().to_string()));
+            }
+        }
+        Err(e) => println!("{} {}", "❌".red(), t!("chat-undo-failed", "error" => e.to_string())),
+    }
+}
+
 /// Show chat history
 async fn show_history(history: &Arc<RwLock<Vec<Message>>>) {
     let history = history.read().await;
     
-    println!("{} Chat History:", "📜".cyan());
+    println!("{} {}", "📜".cyan(), t!("chat-history-title"));
     println!("{}", "─".repeat(50));
     
     for (i, message) in history.iter().enumerate() {
@@ -261,9 +763,13 @@ async fn show_history(history: &Arc<RwLock<Vec<Message>>>) {
         };
         
         println!("{} {}: {}", 
-            (i + 1).to_string().dim(),
+            (i + 1).to_string().dimmed(),
             format!("{:?}", message.role).color(role_color).bold(),
             message.content);
+        for part in &message.parts {
+            let ContentPart::Attachment { name, .. } = part;
+            println!("   {} {}", "📎".cyan(), name.dimmed());
+        }
     }
     
     println!("{}", "─".repeat(50));
@@ -0,0 +1,15 @@
+//! Shell completion scripts
+//!
+//! `jamey completions zsh > ~/.zfunc/_jamey` and similar for other shells.
+
+use anyhow::Result;
+use clap::CommandFactory;
+use clap_complete::Shell;
+
+/// Write the completion script for `shell` to stdout
+pub fn run_completions(shell: Shell) -> Result<()> {
+    let mut command = crate::Cli::command();
+    let name = command.get_name().to_string();
+    clap_complete::generate(shell, &mut command, name, &mut std::io::stdout());
+    Ok(())
+}
@@ -0,0 +1,126 @@
+//! Evaluation command
+//!
+//! `jamey eval run suite.yaml` sends each prompt in the suite through the
+//! full chat pipeline, checks the expectations and prints a pass rate, so a
+//! prompt, model or configuration change can be checked for regressions.
+//! `--min-pass-rate` makes it fail below a threshold, for use in CI.
+//! `jamey eval validate suite.yaml` checks a suite without running it.
+
+use crate::EvalAction;
+use anyhow::{Context, Result};
+use colored::*;
+use jamey_runtime::config::RuntimeConfig;
+use jamey_runtime::eval::{CaseResult, EvalReport, EvalSuite};
+use jamey_runtime::Runtime;
+use std::path::Path;
+
+pub async fn run_eval_action(action: EvalAction) -> Result<()> {
+    match action {
+        EvalAction::Run { suite, model, case, format, min_pass_rate } => {
+            run_suite(&suite, model, case, &format, min_pass_rate).await
+        }
+        EvalAction::Validate { suite } => {
+            let loaded = EvalSuite::load(&suite).with_context(|| format!("{}", suite.display()))?;
+            let checks: usize = loaded.cases.iter().map(|c| c.expect.len()).sum();
+            println!(
+                "{} {}: {} cases, {} expectations",
+                "✓".green(),
+                loaded.name.bold(),
+                loaded.cases.len(),
+                checks
+            );
+            Ok(())
+        }
+    }
+}
+
+async fn run_suite(
+    path: &Path,
+    model: Option<String>,
+    case: Option<String>,
+    format: &str,
+    min_pass_rate: Option<f64>,
+) -> Result<()> {
+    if format != "text" && format != "json" {
+        return Err(anyhow::anyhow!("Invalid format: {}. Must be 'text' or 'json'", format));
+    }
+    if let Some(rate) = min_pass_rate.filter(|r| !(0.0..=1.0).contains(r)) {
+        return Err(anyhow::anyhow!("--min-pass-rate must be between 0 and 1, got {}", rate));
+    }
+    let suite = EvalSuite::load(path).with_context(|| format!("{}", path.display()))?;
+
+    let mut config = RuntimeConfig::from_env()
+        .map_err(|e| anyhow::anyhow!("Failed to load runtime config: {}", e))?;
+    if let Some(model) = model {
+        config.llm.openrouter_default_model = model;
+    }
+    let runtime = Runtime::new(config).await
+        .context("Failed to initialize runtime for evaluation")?;
+
+    let text = format == "text";
+    if text {
+        eprintln!("{} Running {} ({} cases)", "🧪".cyan().bold(), suite.name.bold(), suite.cases.len());
+    }
+    let report = runtime
+        .state()
+        .eval_runner()
+        .with_filter(case)
+        .run(&suite, |result| {
+            if text {
+                print_case(result);
+            }
+        })
+        .await;
+    runtime.shutdown().await;
+
+    if report.cases.is_empty() {
+        return Err(anyhow::anyhow!("No cases matched the filter"));
+    }
+    match format {
+        "json" => println!("{}", serde_json::to_string_pretty(&report)?),
+        _ => print_summary(&report),
+    }
+    if let Some(min) = min_pass_rate {
+        if report.pass_rate() < min {
+            return Err(anyhow::anyhow!(
+                "Pass rate {:.1}% is below the required {:.1}%",
+                report.pass_rate() * 100.0,
+                min * 100.0
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn print_case(result: &CaseResult) {
+    let mark = if result.passed { "✓".green() } else { "✗".red() };
+    println!("{} {} {}", mark, result.name, format!("({} ms)", result.duration_ms).dimmed());
+    if let Some(error) = &result.error {
+        println!("    {} {}", "error:".red(), error);
+        return;
+    }
+    for check in result.checks.iter().filter(|c| !c.passed) {
+        println!("    {} {}", "✗".red(), check.expectation);
+        if let Some(detail) = &check.detail {
+            println!("      {}", detail.dimmed());
+        }
+    }
+}
+
+fn print_summary(report: &EvalReport) {
+    println!("{}", "─".repeat(60));
+    let rate = format!("{:.1}%", report.pass_rate() * 100.0);
+    let rate = if report.passed() == report.cases.len() { rate.green() } else { rate.yellow() };
+    println!(
+        "{} {}/{} passed ({}) with {}",
+        "📊".cyan(),
+        report.passed(),
+        report.cases.len(),
+        rate,
+        report.model
+    );
+    let cost = report.cost_usd();
+    if cost > 0.0 {
+        println!("   {}", format!("Cost: ${:.4}", cost).dimmed());
+    }
+}
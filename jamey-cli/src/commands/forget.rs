@@ -0,0 +1,92 @@
+//! Forget command
+//!
+//! `jamey forget --user alice` (or `--session <id>` / `--pattern <regex>`)
+//! deletes every memory, transcript, usage record, prompt trace and piece of
+//! feedback tied to the target, for answering deletion requests. `--dry-run`
+//! lists what would go; otherwise the same list is shown for confirmation
+//! first unless `--force` is given.
+
+use anyhow::{Context, Result};
+use colored::*;
+use jamey_runtime::config::RuntimeConfig;
+use jamey_runtime::forget::{ForgetReport, ForgetTarget};
+use jamey_runtime::session_store::SessionStore;
+use jamey_runtime::Runtime;
+
+pub async fn run_forget(
+    user: Option<String>,
+    session: Option<String>,
+    pattern: Option<String>,
+    dry_run: bool,
+    force: bool,
+    format: String,
+) -> Result<()> {
+    if format != "table" && format != "json" {
+        return Err(anyhow::anyhow!("Invalid format: {}. Must be 'table' or 'json'", format));
+    }
+    let target = match (user, session, pattern) {
+        (Some(user), _, _) => ForgetTarget::User(user),
+        // A prefix needs the transcript; a full ID works after it is gone
+        (_, Some(session), _) => ForgetTarget::Session(SessionStore::from_env().resolve(&session).await?),
+        (_, _, Some(pattern)) => ForgetTarget::Pattern(pattern),
+        _ => return Err(anyhow::anyhow!("Give one of --user, --session or --pattern")),
+    };
+
+    let config = RuntimeConfig::from_env()
+        .map_err(|e| anyhow::anyhow!("Failed to load runtime config: {}", e))?;
+    let runtime = Runtime::new(config).await
+        .context("Failed to initialize runtime to forget data")?;
+    let result = forget(&runtime, &target, dry_run, force, &format).await;
+    runtime.shutdown().await;
+    result
+}
+
+async fn forget(runtime: &Runtime, target: &ForgetTarget, dry_run: bool, force: bool, format: &str) -> Result<()> {
+    let preview = runtime.state().forget(target, true).await?;
+    if dry_run || preview.is_empty() {
+        return print(&preview, format);
+    }
+    if !force {
+        print_report(&preview);
+        if !crate::utils::confirm("Delete all of this? It cannot be undone")? {
+            println!("{} Cancelled", "ℹ️".blue());
+            return Ok(());
+        }
+    }
+    let report = runtime.state().forget(target, false).await?;
+    print(&report, format)
+}
+
+fn print(report: &ForgetReport, format: &str) -> Result<()> {
+    match format {
+        "json" => println!("{}", serde_json::to_string_pretty(report)?),
+        _ => print_report(report),
+    }
+    Ok(())
+}
+
+fn print_report(report: &ForgetReport) {
+    let (icon, verb) = if report.dry_run { ("🔍", "Would forget") } else { ("🗑️", "Forgot") };
+    println!("{} {} {}", icon.cyan(), verb.bold(), report.target);
+    if report.is_empty() {
+        println!("  Nothing is stored about it.");
+        return;
+    }
+    let rows = [
+        ("Memories", report.memories.len() as u64),
+        ("Transcripts", report.transcripts.len() as u64),
+        ("Live sessions", report.live_sessions as u64),
+        ("Cached memories", report.cached_memories as u64),
+        ("Model calls", report.usage.model_calls as u64),
+        ("Tool runs", report.usage.tool_runs as u64),
+        ("Usage ledger rows", report.usage.ledger_rows),
+        ("Prompt traces", report.prompt_traces as u64),
+        ("Feedback entries", report.preference_entries as u64),
+    ];
+    for (label, count) in rows {
+        println!("  {:<18} {}", label, count);
+    }
+    for id in &report.transcripts {
+        println!("  {} {}", "session".dimmed(), id);
+    }
+}
@@ -1,63 +1,273 @@
 //! Initialization command
-//! 
-//! Set up Jamey configuration and environment
+//!
+//! Set up Jamey configuration and environment. On a terminal this runs an
+//! interactive wizard; with `--defaults` or piped stdin it only writes the
+//! template configuration.
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use colored::*;
-use std::path::PathBuf;
+use crate::config::{CliConfig, Profile};
+use dialoguer::theme::ColorfulTheme;
+use dialoguer::{Confirm, Input, Password, Select};
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 use tracing::{info, error};
 
+/// Models offered by the wizard; anything else can be typed in
+const MODELS: &[&str] = &["claude-3-sonnet", "gpt-4", "gpt-3.5-turbo"];
+
+const DEFAULT_MODEL: &str = "claude-3-sonnet";
+
 /// Run initialization
-pub async fn run_init(dir: PathBuf, force: bool) -> Result<()> {
+pub async fn run_init(dir: PathBuf, force: bool, defaults: bool) -> Result<()> {
     println!("{} Initializing Jamey configuration...", "🚀".cyan().bold());
-    
-    // Expand home directory
-    let config_dir = dirs::home_dir()
-        .unwrap_or_else(|| PathBuf::from("."))
-        .join(".config")
-        .join("jamey");
-    
+
+    let config_dir = expand_home(&dir);
     println!("{} Configuration directory: {}", "📁".blue(), config_dir.display());
-    
+
     // Create config directory
     std::fs::create_dir_all(&config_dir)?;
-    
-    // Create default config file
+
     let config_file = config_dir.join("config.toml");
     if config_file.exists() && !force {
         println!("{} Configuration already exists. Use --force to overwrite.", "⚠️".yellow());
         return Ok(());
     }
-    
-    // Write default configuration (NO SECRETS - use environment variables)
-    let default_config = r#"# Jamey CLI Configuration
-# 
+
+    if defaults || !std::io::stdin().is_terminal() {
+        std::fs::write(&config_file, default_config(DEFAULT_MODEL))?;
+        println!("{} Configuration created at: {}", "✅".green(), config_file.display());
+        println!("{} Please edit the configuration file with your settings.", "💡".yellow());
+        return Ok(());
+    }
+
+    run_wizard(&config_file).await
+}
+
+/// Interactive setup: directories, API key, model, database, profile
+async fn run_wizard(config_file: &Path) -> Result<()> {
+    let theme = ColorfulTheme::default();
+    println!();
+
+    // 1. Directories
+    step(1, "Data directories");
+    let default_data_dir = dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("jamey");
+    let data_dir: String = Input::with_theme(&theme)
+        .with_prompt("Where should Jamey keep sessions, approvals and logs?")
+        .default(default_data_dir.display().to_string())
+        .interact_text()?;
+    let data_dir = expand_home(Path::new(&data_dir));
+    for sub in ["sessions", "approvals", "logs", "downloads"] {
+        let path = data_dir.join(sub);
+        std::fs::create_dir_all(&path)
+            .with_context(|| format!("Failed to create {}", path.display()))?;
+    }
+    println!("  {} Created {}", "✓".green(), data_dir.display());
+
+    // 2. API key
+    step(2, "OpenRouter API key");
+    let api_key = Password::with_theme(&theme)
+        .with_prompt("API key (leave empty to set OPENROUTER_API_KEY yourself)")
+        .allow_empty_password(true)
+        .interact()?;
+    if !api_key.is_empty() && !api_key.starts_with("sk-or-") {
+        println!("  {} That doesn't look like an OpenRouter key (sk-or-...); keeping it anyway", "⚠️".yellow());
+    }
+
+    // 3. Model
+    step(3, "Default model");
+    let mut choices: Vec<&str> = MODELS.to_vec();
+    choices.push("Other...");
+    let selection = Select::with_theme(&theme)
+        .with_prompt("Model to use by default")
+        .items(&choices)
+        .default(0)
+        .interact()?;
+    let model = if selection == MODELS.len() {
+        Input::with_theme(&theme)
+            .with_prompt("Model ID (e.g. anthropic/claude-3-opus)")
+            .interact_text()?
+    } else {
+        MODELS[selection].to_string()
+    };
+
+    // 4. Database
+    step(4, "PostgreSQL memory store");
+    let db = loop {
+        let db = DatabaseSettings {
+            host: Input::with_theme(&theme).with_prompt("Host").default("localhost".to_string()).interact_text()?,
+            port: Input::with_theme(&theme).with_prompt("Port").default(5432).interact_text()?,
+            name: Input::with_theme(&theme).with_prompt("Database").default("jamey".to_string()).interact_text()?,
+            user: Input::with_theme(&theme).with_prompt("User").default("jamey".to_string()).interact_text()?,
+            password: Password::with_theme(&theme).with_prompt("Password").allow_empty_password(true).interact()?,
+        };
+
+        print!("  {} Testing connection... ", "⏳".yellow());
+        match test_database(&db).await {
+            Ok(true) => {
+                println!("{}", "✓ connected, pgvector installed".green());
+                break db;
+            }
+            Ok(false) => {
+                println!("{}", "✓ connected".green());
+                println!("  {} pgvector is not installed; run {} as a superuser",
+                    "⚠️".yellow(), "CREATE EXTENSION vector;".bold());
+                break db;
+            }
+            Err(e) => {
+                println!("{}", "✗ failed".red());
+                error!("Database connectivity test failed: {}", e);
+                println!("  {}", e.to_string().red());
+                let retry = Confirm::with_theme(&theme)
+                    .with_prompt("Re-enter database settings?")
+                    .default(true)
+                    .interact()?;
+                if !retry {
+                    break db;
+                }
+            }
+        }
+    };
+
+    // 5. Profile
+    step(5, "Save");
+    let profile_name: String = Input::with_theme(&theme)
+        .with_prompt("Profile name")
+        .default("local".to_string())
+        .validate_with(|name: &String| crate::config::validate_profile_name(name).map_err(|e| e.to_string()))
+        .interact_text()?;
+
+    let mut profile = Profile {
+        default_model: Some(model.clone()),
+        ..Default::default()
+    };
+    let data = |sub: &str| data_dir.join(sub).display().to_string();
+    profile.env.extend([
+        ("POSTGRES_HOST".to_string(), db.host.clone()),
+        ("POSTGRES_PORT".to_string(), db.port.to_string()),
+        ("POSTGRES_DB".to_string(), db.name.clone()),
+        ("POSTGRES_USER".to_string(), db.user.clone()),
+        ("JAMEY_SESSION_DIR".to_string(), data("sessions")),
+        ("JAMEY_APPROVAL_DIR".to_string(), data("approvals")),
+        ("DOWNLOAD_DIR".to_string(), data("downloads")),
+        // The HTTP API only listens locally until configured otherwise
+        ("API_KEY_REQUIRED".to_string(), "false".to_string()),
+    ]);
+
+    let secrets = [("OPENROUTER_API_KEY", &api_key), ("POSTGRES_PASSWORD", &db.password)];
+    if secrets.iter().any(|(_, value)| !value.is_empty()) {
+        let store = Profile::secret_manager(&profile_name)?;
+        for (key, value) in secrets {
+            if value.is_empty() {
+                continue;
+            }
+            store.store_secret(key, value)
+                .with_context(|| format!("Failed to store {} in the secret store", key))?;
+            profile.secrets.push(key.to_string());
+        }
+    }
+
+    let mut cli_config = CliConfig::load().unwrap_or_default();
+    cli_config.default_model = model.clone();
+    cli_config.profiles.insert(profile_name.clone(), profile);
+    cli_config.active_profile = Some(profile_name.clone());
+    cli_config.save()?;
+
+    std::fs::write(config_file, default_config(&model))?;
+    info!("Initialized configuration with profile {}", profile_name);
+
+    println!();
+    println!("{} Configuration created at: {}", "✅".green(), config_file.display());
+    println!("{} Profile '{}' saved to {} and set as active", "✅".green(), profile_name, CliConfig::config_file_path().display());
+    println!("{} Secrets are in the {} secret store, never in config files", "🔐".blue(),
+        std::env::var("JAMEY_SECRETS_BACKEND").unwrap_or_else(|_| "keyring".to_string()));
+    println!("{} Try it: {}", "💡".yellow(), "jamey chat".bold());
+
+    Ok(())
+}
+
+struct DatabaseSettings {
+    host: String,
+    port: u16,
+    name: String,
+    user: String,
+    password: String,
+}
+
+/// Connect and report whether the pgvector extension is installed
+async fn test_database(db: &DatabaseSettings) -> Result<bool> {
+    let mut config = tokio_postgres::Config::new();
+    config
+        .host(&db.host)
+        .port(db.port)
+        .dbname(&db.name)
+        .user(&db.user)
+        .password(&db.password)
+        .connect_timeout(Duration::from_secs(5));
+
+    let (client, connection) = config.connect(tokio_postgres::NoTls).await?;
+    let connection = tokio::spawn(connection);
+    let row = client
+        .query_opt("SELECT extversion FROM pg_extension WHERE extname = 'vector'", &[])
+        .await;
+    connection.abort();
+    Ok(row?.is_some())
+}
+
+fn step(number: usize, title: &str) {
+    println!();
+    println!("{} {}", format!("[{}/5]", number).cyan().bold(), title.bold());
+}
+
+/// Expand a leading `~` to the home directory
+pub(crate) fn expand_home(path: &Path) -> PathBuf {
+    match path.strip_prefix("~") {
+        Ok(rest) => dirs::home_dir().unwrap_or_else(|| PathBuf::from(".")).join(rest),
+        Err(_) => path.to_path_buf(),
+    }
+}
+
+/// Template configuration (NO SECRETS - use environment variables)
+fn default_config(model: &str) -> String {
+    format!(r#"# Jamey CLI Configuration
+#
 # SECURITY: Never store API keys or passwords in this file!
 # Use environment variables instead:
 #   - JAMEY_API_KEY or OPENROUTER_API_KEY for API authentication
 #   - Database credentials should be in environment variables or .env file
 
 [llm]
-provider = "openrouter"
-model = "claude-3-sonnet"
+openrouter_default_model = "{model}"
 # openrouter_api_key is loaded from OPENROUTER_API_KEY environment variable
 
 [api]
 host = "127.0.0.1"
-port = 3000
+http_port = 3000
 enable_cors = true
 
-[cli]
-default_model = "claude-3-sonnet"
-runtime_url = "http://localhost:3000"
-timeout_seconds = 30
-verbose = false
-"#;
-    
-    std::fs::write(&config_file, default_config)?;
-    
-    println!("{} Configuration created at: {}", "✅".green(), config_file.display());
-    println!("{} Please edit the configuration file with your settings.", "💡".yellow());
-    
-    Ok(())
-}
\ No newline at end of file
+# Any RuntimeConfig key can be set here; environment variables win.
+# `jamey system config show --origin` shows where each value came from.
+"#)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand_home() {
+        let home = dirs::home_dir().unwrap_or_else(|| PathBuf::from("."));
+        assert_eq!(expand_home(Path::new("~/.config/jamey")), home.join(".config/jamey"));
+        assert_eq!(expand_home(Path::new("/etc/jamey")), PathBuf::from("/etc/jamey"));
+    }
+
+    #[test]
+    fn test_default_config_uses_model() {
+        let config = default_config("gpt-4");
+        assert!(config.contains("model = \"gpt-4\""));
+        assert!(config.contains("default_model = \"gpt-4\""));
+    }
+}
@@ -4,23 +4,34 @@
 
 use anyhow::{Context, Result};
 use colored::*;
-use crate::commands::MemoryAction;
-use jamey_core::memory::{Memory, MemoryType};
+use crate::MemoryAction;
+use jamey_core::maintenance::{JobProgress, MemoryStats, SimilarGroup};
+use jamey_core::partition::PartitionInfo;
+use jamey_core::memory::{Memory, MemoryStore, MemoryType};
+use jamey_providers::openrouter::LlmProvider;
+use jamey_runtime::ingest::IngestOptions;
 use jamey_runtime::{Runtime, RuntimeConfig};
 use uuid::Uuid;
 use tracing::{info, error, debug};
 use std::path::PathBuf;
 use std::fs::File;
 use std::io::Write;
+use indicatif::{ProgressBar, ProgressStyle};
 
 /// Run memory management action
 pub async fn run_memory_action(action: MemoryAction) -> Result<()> {
     match action {
-        MemoryAction::Search { query, limit, type_filter } => {
-            search_memory(query, limit, type_filter).await
+        MemoryAction::Search { query, limit, type_filter, namespace, explain } => {
+            search_memory(query, limit, type_filter, namespace, explain).await
         }
-        MemoryAction::List { count, detailed } => {
-            list_memory(count, detailed).await
+        MemoryAction::List { count, detailed, pinned } => {
+            list_memory(count, detailed, pinned).await
+        }
+        MemoryAction::Pin { id } => {
+            pin_memory(id, true).await
+        }
+        MemoryAction::Unpin { id } => {
+            pin_memory(id, false).await
         }
         MemoryAction::Delete { id, force } => {
             delete_memory(id, force).await
@@ -28,11 +39,55 @@ pub async fn run_memory_action(action: MemoryAction) -> Result<()> {
         MemoryAction::Export { output, format } => {
             export_memory(output, format).await
         }
+        MemoryAction::Ingest { target, chunk_size, overlap, memory_type, namespace, tags, dry_run } => {
+            let options = IngestOptions {
+                chunk_size,
+                chunk_overlap: overlap,
+                memory_type: parse_memory_type(&memory_type)?,
+                namespace,
+                tags,
+                dry_run,
+            };
+            ingest_memory(target, options).await
+        }
+        MemoryAction::Stats { format } => {
+            memory_stats(format).await
+        }
+        MemoryAction::Dedupe { threshold, dry_run, force } => {
+            dedupe_memory(threshold, dry_run, force).await
+        }
+        MemoryAction::Consolidate { threshold, dry_run, force } => {
+            consolidate_memory(threshold, dry_run, force).await
+        }
+        MemoryAction::Reembed { model, dry_run, force } => {
+            reembed_memory(model, dry_run, force).await
+        }
+        MemoryAction::Archive { days, dry_run, force } => {
+            archive_memory(days, dry_run, force).await
+        }
+        MemoryAction::Requantize { force } => {
+            requantize_memory(force).await
+        }
+        MemoryAction::Partitions { format } => {
+            list_partitions(format).await
+        }
+        MemoryAction::Partition { namespace, force } => {
+            partition_memory(namespace, force).await
+        }
+        MemoryAction::Reindex { namespace } => {
+            reindex_memory(namespace).await
+        }
     }
 }
 
 /// Search memory entries
-async fn search_memory(query: String, limit: usize, type_filter: Option<String>) -> Result<()> {
+async fn search_memory(
+    query: String,
+    limit: usize,
+    type_filter: Option<String>,
+    namespace: Option<String>,
+    explain: bool,
+) -> Result<()> {
     // Validate input length to prevent DoS
     crate::utils::validate_input_length(&query, 1000, "Search query")?;
     
@@ -61,9 +116,46 @@ async fn search_memory(query: String, limit: usize, type_filter: Option<String>)
     // Search memory store
     print!("{} Searching memory store... ", "⏳".yellow());
     std::io::stdout().flush()?;
+
+    if explain {
+        let explanation = state.memory_store.search_explain(namespace.as_deref(), &query_embedding, limit).await
+            .with_context(|| "Failed to search memory store")?;
+        println!("{}", "✓".green());
+        println!();
+        let target_type = type_filter.as_deref().map(parse_memory_type).transpose()?;
+        let hits: Vec<_> = explanation.hits.iter()
+            .filter(|hit| target_type.as_ref().is_none_or(|t| {
+                std::mem::discriminant(&hit.memory.memory_type) == std::mem::discriminant(t)
+            }))
+            .collect();
+        println!("{} Score = {}", "🧮".blue().bold(), explanation.formula);
+        println!();
+        if hits.is_empty() {
+            println!("{} No memories found matching your query.", "📝".blue());
+        }
+        for (i, hit) in hits.iter().enumerate() {
+            let b = &hit.breakdown;
+            println!("{} Result {}:", "─".repeat(50).cyan(), (i + 1).to_string().cyan().bold());
+            println!("  {} ID: {} ({})", "🆔".blue(), hit.memory.id, hit.memory.memory_type);
+            println!("  {} Content: {}", "💬".blue(), 
+                if hit.memory.content.chars().count() > 200 {
+                    format!("{}...", hit.memory.content.chars().take(200).collect::<String>())
+                } else {
+                    hit.memory.content.clone()
+                });
+            println!("  {} Score: {:.4}", "🧮".blue(), b.score);
+            println!("    similarity {:.4}  recency {:.4}  importance {:.4}  frequency {:.4} ({} accesses)",
+                b.similarity, b.recency, b.importance, b.frequency, b.access_count);
+            println!();
+        }
+        return Ok(());
+    }
     
-    let memories = state.memory_store.search(query_embedding, limit).await
-        .with_context(|| "Failed to search memory store")?;
+    let memories = match &namespace {
+        Some(namespace) => state.memory_store.search_namespace(namespace, &query_embedding, limit).await,
+        None => state.memory_store.search(&query_embedding, limit).await,
+    }
+    .with_context(|| "Failed to search memory store")?;
     
     println!("{}", "✓".green());
     println!();
@@ -124,14 +216,18 @@ async fn load_runtime_config() -> Result<RuntimeConfig> {
         .map_err(|e| anyhow::anyhow!("Failed to load runtime config: {}", e))
 }
 
-/// List recent memory entries
-async fn list_memory(count: usize, detailed: bool) -> Result<()> {
+/// List recent memory entries, or only the pinned ones
+async fn list_memory(count: usize, detailed: bool, pinned: bool) -> Result<()> {
     // Validate count
     if count > 1000 {
         return Err(anyhow::anyhow!("List count cannot exceed 1000 (got {})", count));
     }
     
-    println!("{} Recent Memory Entries ({}):", "📚".cyan().bold(), count);
+    if pinned {
+        println!("{} Pinned Memory Entries:", "📌".cyan().bold());
+    } else {
+        println!("{} Recent Memory Entries ({}):", "📚".cyan().bold(), count);
+    }
     
     // Initialize runtime
     let config = load_runtime_config().await?;
@@ -149,8 +245,12 @@ async fn list_memory(count: usize, detailed: bool) -> Result<()> {
     let generic_embedding = vec![0.0; vector_dim];
     
     // Get more than needed, then we'll sort and limit
-    let all_memories = state.memory_store.search(generic_embedding, count * 2).await
-        .with_context(|| "Failed to retrieve memories")?;
+    let all_memories = if pinned {
+        state.memory_store.pinned().await
+    } else {
+        state.memory_store.search(&generic_embedding, count * 2).await
+    }
+    .with_context(|| "Failed to retrieve memories")?;
     
     // Sort by created_at (most recent first) and limit
     let mut sorted_memories = all_memories;
@@ -168,6 +268,9 @@ async fn list_memory(count: usize, detailed: bool) -> Result<()> {
                 println!("{} Memory {}:", "─".repeat(50).cyan(), (i + 1).to_string().cyan().bold());
                 println!("  {} ID: {}", "🆔".blue(), memory.id);
                 println!("  {} Type: {}", "📋".blue(), memory.memory_type);
+                if memory.is_pinned() {
+                    println!("  {} Pinned", "📌".blue());
+                }
                 println!("  {} Content: {}", "💬".blue(), memory.content);
                 println!("  {} Created: {}", "📅".blue(), memory.created_at.format("%Y-%m-%d %H:%M:%S"));
                 println!("  {} Last Accessed: {}", "🕐".blue(), memory.last_accessed.format("%Y-%m-%d %H:%M:%S"));
@@ -180,11 +283,12 @@ async fn list_memory(count: usize, detailed: bool) -> Result<()> {
                 }
                 println!();
             } else {
-                println!("  {} {} | {} | {} | {}", 
-                    (i + 1).to_string().dim(),
+                println!("  {} {}{} | {} | {} | {}", 
+                    (i + 1).to_string().dimmed(),
+                    if memory.is_pinned() { "📌 " } else { "" },
                     memory.id.to_string()[..8].cyan(),
                     format!("{:?}", memory.memory_type).yellow(),
-                    memory.created_at.format("%Y-%m-%d %H:%M").to_string().dim(),
+                    memory.created_at.format("%Y-%m-%d %H:%M").to_string().dimmed(),
                     if memory.content.len() > 50 {
                         format!("{}...", &memory.content[..50])
                     } else {
@@ -215,8 +319,10 @@ async fn delete_memory(id: String, force: bool) -> Result<()> {
     let state = runtime.state();
     
     // Check if memory exists
+    let pinned_note;
     match state.memory_store.retrieve(memory_id).await {
         Ok(memory) => {
+            pinned_note = if memory.is_pinned() { " It is pinned." } else { "" };
             println!("{} Deleting memory entry: {}", "🗑️".red().bold(), id);
             println!("  Type: {}", memory.memory_type);
             println!("  Content: {}", 
@@ -235,7 +341,7 @@ async fn delete_memory(id: String, force: bool) -> Result<()> {
     if !force {
         // Require confirmation for destructive operations
         let confirmed = crate::utils::confirm(
-            &format!("Are you sure you want to delete memory entry {}? This action cannot be undone.", id)
+            &format!("Are you sure you want to delete memory entry {}?{} This action cannot be undone.", id, pinned_note)
         )?;
         
         if !confirmed {
@@ -253,6 +359,25 @@ async fn delete_memory(id: String, force: bool) -> Result<()> {
     Ok(())
 }
 
+/// Pin or unpin a memory
+async fn pin_memory(id: String, pinned: bool) -> Result<()> {
+    let memory_id = crate::utils::validate_uuid(&id)
+        .with_context(|| format!("Invalid memory ID format: {}", id))?;
+
+    let config = load_runtime_config().await?;
+    let runtime = Runtime::new(config).await
+        .context("Failed to initialize runtime to pin memory")?;
+    runtime.state().memory_store.set_pinned(memory_id, pinned).await
+        .with_context(|| format!("Failed to update memory: {}", id))?;
+
+    if pinned {
+        println!("{} Pinned {}; it will be included in every chat turn", "📌".green(), id);
+    } else {
+        println!("{} Unpinned {}", "✅".green(), id);
+    }
+    Ok(())
+}
+
 /// Export memory to file
 async fn export_memory(output: PathBuf, format: String) -> Result<()> {
     // Validate path to prevent directory traversal
@@ -279,7 +404,7 @@ async fn export_memory(output: PathBuf, format: String) -> Result<()> {
     let generic_embedding = vec![0.0; vector_dim];
     
     // Get a large number of memories (adjust limit as needed)
-    let memories = state.memory_store.search(generic_embedding, 10000).await
+    let memories = state.memory_store.search(&generic_embedding, 10000).await
         .with_context(|| "Failed to retrieve memories for export")?;
     
     println!("{} Found {} memories", "✓".green(), memories.len());
@@ -329,4 +454,573 @@ async fn export_memory(output: PathBuf, format: String) -> Result<()> {
     );
     
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Ingest documents into memory
+async fn ingest_memory(target: String, options: IngestOptions) -> Result<()> {
+    crate::utils::validate_input_length(&target, 4096, "Ingest target")?;
+
+    println!("{} Ingesting: {}", "📥".cyan().bold(), target);
+    println!(
+        "  Chunks of {} chars ({} overlap) as {} memories{}",
+        options.chunk_size,
+        options.chunk_overlap,
+        options.memory_type,
+        options.namespace.as_ref().map(|ns| format!(" in '{}'", ns)).unwrap_or_default()
+    );
+    if !options.tags.is_empty() {
+        println!("  Tags: {}", options.tags.join(", "));
+    }
+
+    let config = load_runtime_config().await?;
+    let runtime = Runtime::new(config).await
+        .context("Failed to initialize runtime for memory ingestion")?;
+
+    print!("{} {}... ", "⏳".yellow(), if options.dry_run { "Chunking" } else { "Embedding and storing" });
+    std::io::stdout().flush()?;
+
+    let result = runtime.state().ingest(&target, &options).await;
+    runtime.shutdown().await;
+    let report = result.with_context(|| format!("Failed to ingest {}", target))?;
+
+    println!("{}", "✓".green());
+    println!();
+
+    for source in &report.sources {
+        if source.errors.is_empty() && source.queued == 0 {
+            println!("  {} {} ({} chunks)", "✓".green(), source.source, source.chunks);
+        } else if source.errors.is_empty() {
+            println!(
+                "  {} {} ({}/{} chunks stored, {} queued)",
+                "⏸️".yellow(),
+                source.source,
+                source.memories_created,
+                source.chunks,
+                source.queued
+            );
+        } else {
+            println!(
+                "  {} {} ({}/{} chunks stored)",
+                "⚠️".yellow(),
+                source.source,
+                source.memories_created,
+                source.chunks
+            );
+            for error in &source.errors {
+                println!("      {}", error.red());
+            }
+        }
+    }
+    for (source, reason) in &report.skipped {
+        println!("  {} {} — skipped: {}", "⏭️".dimmed(), source, reason.dimmed());
+    }
+
+    println!();
+    println!("{} Sources: {} ingested, {} skipped", "📊".blue(), report.sources.len(), report.skipped.len());
+    if options.dry_run {
+        println!("{} Dry run: {} chunks would be stored", "ℹ️".blue(), report.chunks);
+    } else {
+        println!("{} Memories created: {}", "✅".green(), report.memories_created.to_string().bold());
+        if report.queued > 0 {
+            println!(
+                "{} {} chunks queued until the provider is reachable; the runtime stores them then",
+                "⏸️".yellow(),
+                report.queued
+            );
+        }
+    }
+    println!(
+        "{} Estimated embedding cost: ~{} tokens (${:.4})",
+        "💰".yellow(),
+        report.estimated_tokens,
+        report.estimated_cost_usd
+    );
+
+    Ok(())
+}
+
+/// Show memory statistics
+async fn memory_stats(format: String) -> Result<()> {
+    if format != "table" && format != "json" {
+        return Err(anyhow::anyhow!("Invalid format: {}. Must be 'table' or 'json'", format));
+    }
+
+    let config = load_runtime_config().await?;
+    let runtime = Runtime::new(config).await
+        .context("Failed to initialize runtime for memory statistics")?;
+    let result = runtime.state().memory_store.stats().await;
+    runtime.shutdown().await;
+    let stats = result.context("Failed to collect memory statistics")?;
+
+    if format == "json" {
+        println!("{}", serde_json::to_string_pretty(&stats)?);
+    } else {
+        print_stats(&stats);
+    }
+    Ok(())
+}
+
+fn print_stats(stats: &MemoryStats) {
+    println!("{} Memory Statistics", "📊".cyan().bold());
+    println!("{}", "─".repeat(50));
+    println!("  Total memories:   {}", stats.total.to_string().bold());
+    println!("  Storage:          {}", crate::utils::format_bytes(stats.table_bytes.max(0) as u64));
+    println!("  Avg. length:      {:.0} chars", stats.avg_content_chars);
+    println!("  Unread for 30d:   {}", stats.stale);
+    if stats.archived > 0 {
+        println!("  Archived:         {}", stats.archived);
+    }
+    if let (Some(oldest), Some(newest)) = (stats.oldest, stats.newest) {
+        println!(
+            "  Created:          {} – {}",
+            oldest.format("%Y-%m-%d"),
+            newest.format("%Y-%m-%d")
+        );
+    }
+
+    for (title, rows) in [
+        ("By type", &stats.by_type),
+        ("By namespace", &stats.by_namespace),
+        ("By embedding model", &stats.by_embedding_model),
+    ] {
+        if rows.is_empty() {
+            continue;
+        }
+        println!();
+        println!("{}", title.bold());
+        for (key, count) in rows {
+            println!("  {:<40} {:>8}", key, count);
+        }
+    }
+}
+
+/// Remove near-duplicate memories
+async fn dedupe_memory(threshold: f64, dry_run: bool, force: bool) -> Result<()> {
+    println!(
+        "{} Looking for duplicates (similarity ≥ {}){}",
+        "🔍".cyan().bold(),
+        threshold,
+        if dry_run { " — dry run" } else { "" }
+    );
+
+    let config = load_runtime_config().await?;
+    let runtime = Runtime::new(config).await
+        .context("Failed to initialize runtime for memory deduplication")?;
+    let store = &runtime.state().memory_store;
+
+    let bar = job_progress_bar();
+    let progress = progress_callback(&bar);
+    let report = store.dedupe(threshold, true, &progress).await;
+    bar.finish_and_clear();
+    let report = report.context("Failed to scan for duplicates")?;
+
+    let duplicates: usize = report.groups.iter().map(|g| g.members.len()).sum();
+    println!("  Scanned {} memories", report.scanned);
+    print_groups(&report.groups, "duplicate");
+    if duplicates == 0 || dry_run {
+        runtime.shutdown().await;
+        if duplicates > 0 {
+            println!("{} Dry run: {} memories would be deleted", "ℹ️".blue(), duplicates);
+        }
+        return Ok(());
+    }
+
+    if !force && !crate::utils::confirm(&format!("Delete {} duplicate memories?", duplicates))? {
+        runtime.shutdown().await;
+        println!("{} Deduplication cancelled.", "ℹ️".blue());
+        return Ok(());
+    }
+
+    let bar = job_progress_bar();
+    let progress = progress_callback(&bar);
+    let removed = store.remove_duplicates(&report.groups, &progress).await;
+    bar.finish_and_clear();
+    runtime.shutdown().await;
+    let removed = removed.context("Failed to delete duplicates")?;
+
+    info!("Deduplicated memory: {} removed", removed);
+    println!("{} Deleted {} duplicate memories", "✅".green(), removed.to_string().bold());
+    Ok(())
+}
+
+/// Merge clusters of related memories
+async fn consolidate_memory(threshold: f64, dry_run: bool, force: bool) -> Result<()> {
+    println!(
+        "{} Looking for related memories (similarity ≥ {}){}",
+        "🔍".cyan().bold(),
+        threshold,
+        if dry_run { " — dry run" } else { "" }
+    );
+
+    let config = load_runtime_config().await?;
+    let runtime = Runtime::new(config).await
+        .context("Failed to initialize runtime for memory consolidation")?;
+    let state = runtime.state();
+    // Clustered runtimes share one memory store; only one may merge it at a time
+    let _lock = match &state.cluster {
+        Some(cluster) if !dry_run => Some(
+            cluster
+                .try_lock("consolidation")
+                .await?
+                .context("Memories are already being consolidated elsewhere in the cluster")?,
+        ),
+        _ => None,
+    };
+    let (consolidator, embedder) = (state.consolidator(), state.embedder(None));
+
+    let bar = job_progress_bar();
+    let progress = progress_callback(&bar);
+    let report = state.memory_store.consolidate(threshold, &consolidator, &embedder, true, &progress).await;
+    bar.finish_and_clear();
+    let mut report = report.context("Failed to scan for related memories")?;
+
+    let merged: usize = report.clusters.iter().map(|c| c.members.len() + 1).sum();
+    println!("  Scanned {} memories", report.scanned);
+    print_groups(&report.clusters, "related");
+    if report.clusters.is_empty() || dry_run {
+        runtime.shutdown().await;
+        if !report.clusters.is_empty() {
+            println!(
+                "{} Dry run: {} memories would be merged into {}",
+                "ℹ️".blue(),
+                merged,
+                report.clusters.len()
+            );
+        }
+        return Ok(());
+    }
+
+    let prompt = format!(
+        "Merge {} memories into {} using the chat model? The originals are deleted.",
+        merged,
+        report.clusters.len()
+    );
+    if !force && !crate::utils::confirm(&prompt)? {
+        runtime.shutdown().await;
+        println!("{} Consolidation cancelled.", "ℹ️".blue());
+        return Ok(());
+    }
+
+    let bar = job_progress_bar();
+    let progress = progress_callback(&bar);
+    let queued = state.merge_clusters(&mut report, &progress).await;
+    bar.finish_and_clear();
+    runtime.shutdown().await;
+
+    for err in &report.errors {
+        println!("  {} {}", "⚠️".yellow(), err.red());
+    }
+    if queued > 0 {
+        println!(
+            "  {} {} clusters queued until the provider is reachable; the runtime merges them then",
+            "⏸️".yellow(),
+            queued
+        );
+    }
+    info!("Consolidated memory: {} created, {} removed", report.created.len(), report.removed);
+    println!(
+        "{} Merged {} memories into {}",
+        "✅".green(),
+        report.removed.to_string().bold(),
+        report.created.len().to_string().bold()
+    );
+    Ok(())
+}
+
+/// Re-embed memories with another model
+async fn reembed_memory(model: String, dry_run: bool, force: bool) -> Result<()> {
+    crate::utils::validate_input_length(&model, 200, "Embedding model")?;
+    println!(
+        "{} Re-embedding memories with {}{}",
+        "🧬".cyan().bold(),
+        model.bold(),
+        if dry_run { " — dry run" } else { "" }
+    );
+
+    let config = load_runtime_config().await?;
+    let runtime = Runtime::new(config).await
+        .context("Failed to initialize runtime for re-embedding")?;
+    let state = runtime.state();
+    let embedder = state.embedder(Some(&model));
+
+    // A dry run first, to check the model's dimension and count the work
+    let noop = |_: JobProgress| {};
+    let plan = state.memory_store.reembed(&embedder, true, &noop).await;
+    let plan = match plan {
+        Ok(plan) => plan,
+        Err(e) => {
+            runtime.shutdown().await;
+            return Err(e.context(format!("Cannot re-embed with {}", model)));
+        }
+    };
+    println!(
+        "  {} memories to re-embed (~{} tokens)",
+        plan.pending.to_string().bold(),
+        plan.estimated_tokens
+    );
+    if plan.pending == 0 || dry_run {
+        runtime.shutdown().await;
+        return Ok(());
+    }
+
+    let prompt = format!("Re-embed {} memories with {}?", plan.pending, model);
+    if !force && !crate::utils::confirm(&prompt)? {
+        runtime.shutdown().await;
+        println!("{} Re-embedding cancelled.", "ℹ️".blue());
+        return Ok(());
+    }
+
+    let bar = job_progress_bar();
+    let progress = progress_callback(&bar);
+    let report = state.memory_store.reembed(&embedder, false, &progress).await;
+    bar.finish_and_clear();
+    runtime.shutdown().await;
+    let report = report.context("Re-embedding failed")?;
+
+    for (id, err) in report.failed.iter().take(10) {
+        println!("  {} {} {}", "⚠️".yellow(), id, err.red());
+    }
+    if report.failed.len() > 10 {
+        println!("  ... and {} more failures", report.failed.len() - 10);
+    }
+    info!("Re-embedded {} memories with {}", report.updated, model);
+    println!("{} Re-embedded {} memories", "✅".green(), report.updated.to_string().bold());
+    if !report.failed.is_empty() {
+        println!(
+            "{} {} failed; run the command again to retry them",
+            "ℹ️".blue(),
+            report.failed.len()
+        );
+    }
+    Ok(())
+}
+
+/// Archive memories unread for `days` to the cold table
+async fn archive_memory(days: Option<u32>, dry_run: bool, force: bool) -> Result<()> {
+    let config = load_runtime_config().await?;
+    let days = match days.unwrap_or(config.memory.cold_after_days) {
+        0 => return Err(anyhow::anyhow!("Pass --days or set memory.cold_after_days")),
+        days => days,
+    };
+    println!(
+        "{} Archiving memories unread for {} days{}",
+        "🧊".cyan().bold(),
+        days,
+        if dry_run { " — dry run" } else { "" }
+    );
+
+    let runtime = Runtime::new(config).await
+        .context("Failed to initialize runtime for archiving")?;
+    let store = &runtime.state().memory_store;
+    let noop = |_: JobProgress| {};
+    let plan = match store.archive_cold(days, true, &noop).await {
+        Ok(plan) => plan,
+        Err(e) => {
+            runtime.shutdown().await;
+            return Err(e.context("Failed to count cold memories"));
+        }
+    };
+    println!(
+        "  {} memories to archive ({})",
+        plan.pending.to_string().bold(),
+        crate::utils::format_bytes(plan.content_bytes)
+    );
+    if plan.pending == 0 || dry_run {
+        runtime.shutdown().await;
+        return Ok(());
+    }
+
+    let prompt = format!("Archive {} memories? They are re-embedded when next read", plan.pending);
+    if !force && !crate::utils::confirm(&prompt)? {
+        runtime.shutdown().await;
+        println!("{} Archiving cancelled.", "ℹ️".blue());
+        return Ok(());
+    }
+
+    let bar = job_progress_bar();
+    let progress = progress_callback(&bar);
+    let report = store.archive_cold(days, false, &progress).await;
+    bar.finish_and_clear();
+    runtime.shutdown().await;
+    let report = report.context("Archiving failed")?;
+
+    info!("Archived {} memories", report.archived);
+    println!(
+        "{} Archived {} memories, {} compressed",
+        "✅".green(),
+        report.archived.to_string().bold(),
+        crate::utils::format_bytes(report.compressed_bytes)
+    );
+    Ok(())
+}
+
+/// Re-encode embeddings at their type's configured precision
+async fn requantize_memory(force: bool) -> Result<()> {
+    let config = load_runtime_config().await?;
+    let quantization = config.memory.quantization;
+    println!("{} Embedding precision by type", "🗜️".cyan().bold());
+    for memory_type in [
+        MemoryType::Conversation,
+        MemoryType::Knowledge,
+        MemoryType::Experience,
+        MemoryType::Skill,
+        MemoryType::Preference,
+    ] {
+        println!("  {:<14} {:?}", memory_type.to_string(), quantization.for_type(&memory_type));
+    }
+
+    let prompt = "Convert stored embeddings to these precisions? Lost precision isn't recovered";
+    if !force && !crate::utils::confirm(prompt)? {
+        println!("{} Requantizing cancelled.", "ℹ️".blue());
+        return Ok(());
+    }
+
+    let runtime = Runtime::new(config).await
+        .context("Failed to initialize runtime for requantizing")?;
+    print!("{} Converting embeddings... ", "⏳".yellow());
+    std::io::stdout().flush()?;
+    let result = runtime.state().memory_store.requantize().await;
+    runtime.shutdown().await;
+    let converted = result.context("Requantizing failed")?;
+    println!("{}", "✓".green());
+
+    info!("Requantized {} embeddings", converted);
+    println!("{} Converted {} embeddings", "✅".green(), converted.to_string().bold());
+    Ok(())
+}
+
+/// List the partitions of the memory table
+async fn list_partitions(format: String) -> Result<()> {
+    if format != "table" && format != "json" {
+        return Err(anyhow::anyhow!("Invalid format: {}. Must be 'table' or 'json'", format));
+    }
+
+    let config = load_runtime_config().await?;
+    let runtime = Runtime::new(config).await
+        .context("Failed to initialize runtime for listing partitions")?;
+    let result = runtime.state().memory_store.partitions().await;
+    runtime.shutdown().await;
+    let partitions = result.context("Failed to list partitions")?;
+
+    if format == "json" {
+        println!("{}", serde_json::to_string_pretty(&partitions)?);
+    } else {
+        print_partitions(&partitions);
+    }
+    Ok(())
+}
+
+fn print_partitions(partitions: &[PartitionInfo]) {
+    if partitions.is_empty() {
+        println!("{} The memory table is not partitioned", "ℹ️".blue());
+        return;
+    }
+    println!("{} Memory Partitions", "🗂️".cyan().bold());
+    println!("{}", "─".repeat(70));
+    for partition in partitions {
+        let namespace = partition.namespace.as_deref().unwrap_or("(default)");
+        println!(
+            "  {:<30} {:>10} {:>12}  {}",
+            namespace,
+            partition.rows,
+            crate::utils::format_bytes(partition.bytes.max(0) as u64),
+            partition.name.dimmed()
+        );
+    }
+}
+
+/// Partition the table, or split one namespace into its own partition
+async fn partition_memory(namespace: Option<String>, force: bool) -> Result<()> {
+    if let Some(namespace) = &namespace {
+        crate::utils::validate_input_length(namespace, 1024, "Namespace")?;
+    }
+    let prompt = match &namespace {
+        Some(namespace) => format!("Move namespace {} into its own partition?", namespace),
+        None => "Rebuild the memory table partitioned by namespace? It is locked until done".to_string(),
+    };
+    if !force && !crate::utils::confirm(&prompt)? {
+        println!("{} Partitioning cancelled.", "ℹ️".blue());
+        return Ok(());
+    }
+
+    let config = load_runtime_config().await?;
+    let runtime = Runtime::new(config).await
+        .context("Failed to initialize runtime for partitioning")?;
+    let store = &runtime.state().memory_store;
+    let spinner = ProgressBar::new_spinner();
+    spinner.set_message("Moving memories");
+    spinner.enable_steady_tick(std::time::Duration::from_millis(120));
+    let result = match &namespace {
+        Some(namespace) => store
+            .create_partition(namespace)
+            .await
+            .map(|(name, moved)| format!("Moved {} memories into {}", moved, name)),
+        None => store
+            .partition_by_namespace()
+            .await
+            .map(|moved| format!("Partitioned {} memories by namespace", moved)),
+    };
+    spinner.finish_and_clear();
+    runtime.shutdown().await;
+    let summary = result.context("Partitioning failed")?;
+
+    info!("{}", summary);
+    println!("{} {}", "✅".green(), summary);
+    Ok(())
+}
+
+/// Rebuild vector indexes
+async fn reindex_memory(namespace: Option<String>) -> Result<()> {
+    let config = load_runtime_config().await?;
+    let runtime = Runtime::new(config).await
+        .context("Failed to initialize runtime for reindexing")?;
+    print!("{} Rebuilding index... ", "⏳".yellow());
+    std::io::stdout().flush()?;
+    let result = runtime.state().memory_store.reindex(namespace.as_deref()).await;
+    runtime.shutdown().await;
+    result.context("Reindexing failed")?;
+    println!("{}", "✓".green());
+    Ok(())
+}
+
+fn print_groups(groups: &[SimilarGroup], label: &str) {
+    if groups.is_empty() {
+        println!("{} No {} memories found", "✅".green(), label);
+        return;
+    }
+    println!("  Found {} group(s) of {} memories:", groups.len(), label);
+    for group in groups.iter().take(20) {
+        let closest = group.members.iter().map(|m| m.similarity).fold(0.0, f64::max);
+        println!(
+            "  {} keep {} + {} more (closest {:.3})",
+            "•".dimmed(),
+            group.keep,
+            group.members.len(),
+            closest
+        );
+    }
+    if groups.len() > 20 {
+        println!("  ... and {} more groups", groups.len() - 20);
+    }
+}
+
+fn job_progress_bar() -> ProgressBar {
+    let bar = ProgressBar::new(0);
+    bar.set_style(
+        ProgressStyle::with_template("{spinner:.cyan} {msg:<10} [{bar:40.cyan/blue}] {pos}/{len} ({eta})")
+            .unwrap_or_else(|_| ProgressStyle::default_bar())
+            .progress_chars("=> "),
+    );
+    bar
+}
+
+/// Drive `bar` from a maintenance job; each stage restarts it
+fn progress_callback(bar: &ProgressBar) -> impl Fn(JobProgress) + Send + Sync + '_ {
+    move |p: JobProgress| {
+        if bar.length() != Some(p.total) {
+            bar.set_length(p.total);
+        }
+        bar.set_message(p.stage);
+        bar.set_position(p.done);
+    }
+}
@@ -10,4 +10,22 @@ pub mod system;
 pub mod init;
 pub mod start;
 pub mod stop;
-pub mod status;
\ No newline at end of file
+pub mod status;
+pub mod auth;
+pub mod ask;
+pub mod sessions;
+pub mod persona;
+pub mod workspace;
+pub mod tool;
+pub mod approvals;
+pub mod bench;
+pub mod completions;
+pub mod usage;
+pub mod watch;
+pub mod research;
+pub mod briefing;
+pub mod eval;
+pub mod forget;
+pub mod audit;
+pub mod tasks;
+pub mod traces;
@@ -0,0 +1,143 @@
+//! Persona commands
+//!
+//! List, inspect, create, change and delete the personas `jamey chat
+//! --persona` picks from. Like the session commands these work on the
+//! persona directory directly, so the runtime does not need to be up.
+
+use anyhow::{Context, Result};
+use colored::*;
+use crate::PersonaAction;
+use jamey_runtime::config::RuntimeConfig;
+use jamey_runtime::persona::{Persona, PersonaError, PersonaStore};
+use std::path::PathBuf;
+
+/// Run persona action
+pub async fn run_persona_action(action: PersonaAction) -> Result<()> {
+    let config = RuntimeConfig::from_env()
+        .map_err(|e| anyhow::anyhow!("Failed to load runtime config: {}", e))?;
+    let store = PersonaStore::new(&config.persona_dir);
+    let default = config.default_persona.as_deref().unwrap_or(jamey_runtime::persona::DEFAULT_PERSONA);
+
+    match action {
+        PersonaAction::List => list_personas(&store, default).await,
+        PersonaAction::Show { name } => show_persona(&store, &name).await,
+        PersonaAction::Set { name, prompt, prompt_file, description, tone, tools, model } => {
+            let prompt = match prompt_file {
+                Some(path) => Some(read_prompt(path).await?),
+                None => prompt,
+            };
+            let changes = Changes { prompt, description, tone, tools, model };
+            set_persona(&store, &name, changes).await
+        }
+        PersonaAction::Delete { name } => {
+            store.delete(&name).await?;
+            println!("{} Deleted persona {}", "✓".green(), name.bold());
+            Ok(())
+        }
+    }
+}
+
+async fn read_prompt(path: PathBuf) -> Result<String> {
+    tokio::fs::read_to_string(&path)
+        .await
+        .with_context(|| format!("Failed to read {}", path.display()))
+}
+
+/// List personas, marking the one sessions get by default
+async fn list_personas(store: &PersonaStore, default: &str) -> Result<()> {
+    let personas = store.list().await?;
+
+    println!("{} Personas ({})", "🎭".cyan().bold(), store.dir().display());
+    println!("{}", "─".repeat(80));
+    println!("{:<16} {:<24} {}", "NAME".bold(), "MODEL".bold(), "DESCRIPTION".bold());
+    for persona in &personas {
+        let name = if persona.name == default {
+            format!("{} *", persona.name)
+        } else {
+            persona.name.clone()
+        };
+        println!(
+            "{:<16} {:<24} {}",
+            name.yellow(),
+            persona.model.as_deref().unwrap_or("-"),
+            persona.description
+        );
+    }
+
+    println!();
+    println!("{} * is used when no persona is picked; set JAMEY_PERSONA to change it", "💡".yellow());
+    println!("{} Chat with one: {}", "💡".yellow(), "jamey chat --persona <name>".bold());
+    Ok(())
+}
+
+async fn show_persona(store: &PersonaStore, name: &str) -> Result<()> {
+    let persona = store.get(name).await?;
+
+    println!("{} {}", "🎭".cyan().bold(), persona.name.bold());
+    if !persona.description.is_empty() {
+        println!("  {}", persona.description);
+    }
+    println!("  Model: {}", persona.model.as_deref().unwrap_or("configured default"));
+    println!("  Tone: {}", persona.tone.as_deref().unwrap_or("-"));
+    if persona.tools.is_empty() {
+        println!("  Tools: all the session allows");
+    } else {
+        println!("  Tools: {}", persona.tools.join(", "));
+    }
+    println!("{}", "─".repeat(50));
+    println!("{}", persona.system_prompt);
+    Ok(())
+}
+
+/// Fields given to `jamey persona set`; unset ones keep their value
+struct Changes {
+    prompt: Option<String>,
+    description: Option<String>,
+    tone: Option<String>,
+    tools: Option<Vec<String>>,
+    model: Option<String>,
+}
+
+async fn set_persona(store: &PersonaStore, name: &str, changes: Changes) -> Result<()> {
+    let (mut persona, created) = match store.get(name).await {
+        Ok(persona) => (persona, false),
+        Err(PersonaError::NotFound(_)) => {
+            let persona = Persona {
+                name: name.to_string(),
+                description: String::new(),
+                system_prompt: String::new(),
+                tone: None,
+                tools: Vec::new(),
+                model: None,
+            };
+            (persona, true)
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    if let Some(prompt) = changes.prompt {
+        persona.system_prompt = prompt.trim().to_string();
+    }
+    if let Some(description) = changes.description {
+        persona.description = description;
+    }
+    if let Some(tone) = changes.tone {
+        persona.tone = Some(tone).filter(|t| !t.trim().is_empty());
+    }
+    if let Some(tools) = changes.tools {
+        persona.tools = tools.into_iter().filter(|t| !t.trim().is_empty()).collect();
+    }
+    if let Some(model) = changes.model {
+        persona.model = Some(model).filter(|m| !m.trim().is_empty());
+    }
+    store.save(&persona).await.map_err(|e| match e {
+        PersonaError::EmptyPrompt(_) if created => {
+            anyhow::anyhow!("A new persona needs a system prompt; pass --prompt or --prompt-file")
+        }
+        e => e.into(),
+    })?;
+
+    let verb = if created { "Created" } else { "Updated" };
+    println!("{} {} persona {}", "✓".green(), verb, persona.name.bold());
+    Ok(())
+}
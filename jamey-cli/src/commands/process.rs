@@ -4,7 +4,7 @@
 
 use anyhow::Result;
 use colored::*;
-use crate::commands::ProcessAction;
+use crate::ProcessAction;
 use jamey_tools::system::ProcessTool;
 use tracing::{info, error};
 
@@ -0,0 +1,63 @@
+//! Deep research command
+//!
+//! `jamey research "<topic>"` breaks the topic into search queries, reads
+//! the top pages and related memories, and prints a report citing them.
+//! The report is stored as a Knowledge memory unless `--no-store` is given.
+
+use anyhow::{Context, Result};
+use colored::*;
+use jamey_runtime::config::RuntimeConfig;
+use jamey_runtime::research::{ResearchOptions, SourceKind};
+use jamey_runtime::Runtime;
+use std::io::Write;
+
+/// Run research command
+pub async fn run_research(topic: String, queries: usize, no_store: bool, format: String) -> Result<()> {
+    if format != "text" && format != "json" {
+        return Err(anyhow::anyhow!("Invalid format: {}. Must be 'text' or 'json'", format));
+    }
+    crate::utils::validate_input_length(&topic, 500, "Research topic")?;
+    let options = ResearchOptions {
+        max_queries: queries,
+        store: !no_store,
+        ..ResearchOptions::default()
+    };
+
+    let config = RuntimeConfig::from_env()
+        .map_err(|e| anyhow::anyhow!("Failed to load runtime config: {}", e))?;
+    let runtime = Runtime::new(config).await
+        .context("Failed to initialize runtime for research")?;
+
+    if format == "text" {
+        eprint!("{} Researching: {}... ", "🔎".cyan().bold(), topic);
+        std::io::stderr().flush()?;
+    }
+    let result = match runtime.state().research_workflow() {
+        Ok(workflow) => workflow.run(&topic, &options).await,
+        Err(e) => Err(e),
+    };
+    runtime.shutdown().await;
+    let report = result.with_context(|| format!("Research on '{}' failed", topic))?;
+
+    if format == "json" {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+
+    eprintln!("{}", "✓".green());
+    eprintln!("  Queries: {}", report.queries.join(" | ").dimmed());
+    let pages = report.sources.iter().filter(|s| s.kind == SourceKind::Web).count();
+    eprintln!("  Sources: {} pages, {} memories", pages, report.sources.len() - pages);
+    for warning in &report.warnings {
+        eprintln!("  {} {}", "⚠️".yellow(), warning);
+    }
+    eprintln!();
+
+    println!("{}", report.report);
+
+    if let Some(id) = report.memory_id {
+        eprintln!();
+        eprintln!("{} Stored as knowledge memory {}", "💾".blue(), id.to_string().bold());
+    }
+    Ok(())
+}
@@ -0,0 +1,143 @@
+//! Session history commands
+//!
+//! List, inspect, export, delete and resume persisted conversations. These
+//! read the session store directly, so the runtime does not need to be up.
+
+use anyhow::{Context, Result};
+use colored::*;
+use crate::SessionsAction;
+use jamey_protocol::Role;
+use jamey_runtime::session_store::SessionStore;
+
+/// Run sessions action
+pub async fn run_sessions_action(action: SessionsAction) -> Result<()> {
+    let store = SessionStore::from_env();
+
+    match action {
+        SessionsAction::List { limit } => list_sessions(&store, limit).await,
+        SessionsAction::Show { id } => show_session(&store, &id).await,
+        SessionsAction::Delete { id, force } => delete_session(&store, &id, force).await,
+        SessionsAction::Export { id, format, output } => {
+            export_session(&store, &id, &format, output).await
+        }
+        SessionsAction::Resume { id, model, persona, verbose } => {
+            let id = store.resolve(&id).await?;
+            let flags = super::chat::ChatFlags { verbose, ..Default::default() };
+            super::chat::run_chat(Some(id.to_string()), model, persona, None, flags).await
+        }
+    }
+}
+
+/// List sessions, most recent first
+async fn list_sessions(store: &SessionStore, limit: usize) -> Result<()> {
+    let sessions = store.list().await?;
+
+    println!("{} Sessions ({})", "💬".cyan().bold(), store.dir().display());
+    println!("{}", "─".repeat(80));
+
+    if sessions.is_empty() {
+        println!("No saved sessions yet. Start one with {}", "jamey chat".yellow());
+        return Ok(());
+    }
+
+    println!("{:<10} {:<18} {:>6}  {}", "ID".bold(), "UPDATED".bold(), "MSGS".bold(), "TITLE".bold());
+    for session in sessions.iter().take(limit) {
+        let title = if session.title.is_empty() { "Untitled session" } else { &session.title };
+        let archived = if session.archived { " (archived)".dimmed().to_string() } else { String::new() };
+        println!(
+            "{:<10} {:<18} {:>6}  {}{}",
+            session.id.to_string()[..8].yellow(),
+            session.updated_at.format("%Y-%m-%d %H:%M").to_string(),
+            session.message_count,
+            title,
+            archived
+        );
+    }
+    if sessions.len() > limit {
+        println!("{}", format!("... and {} more", sessions.len() - limit).dimmed());
+    }
+
+    println!();
+    println!("{} Resume with: {}", "💡".yellow(), "jamey sessions resume <id>".bold());
+    Ok(())
+}
+
+/// Print a session transcript
+async fn show_session(store: &SessionStore, id: &str) -> Result<()> {
+    let record = store.load(store.resolve(id).await?).await?;
+
+    println!("{} {}", "💬".cyan().bold(), record.display_title().bold());
+    println!("  ID: {}", record.id);
+    println!("  Created: {}", record.created_at.format("%Y-%m-%d %H:%M UTC"));
+    println!("  Updated: {}", record.updated_at.format("%Y-%m-%d %H:%M UTC"));
+    if let Some(ref model) = record.model {
+        println!("  Model: {}", model);
+    }
+    if let Some(archive) = record.archive.as_ref().filter(|_| record.is_archived()) {
+        println!("  Archived: {} (resume to revive)", archive.archived_at.format("%Y-%m-%d %H:%M UTC"));
+    }
+    println!("{}", "─".repeat(50));
+
+    for message in &record.messages {
+        let speaker = match message.role {
+            Role::User => "You:".green().bold(),
+            Role::Assistant => "Jamey:".blue().bold(),
+            Role::System => "System:".magenta().bold(),
+            Role::Tool => "Tool:".yellow().bold(),
+        };
+        println!(
+            "{} {} {}",
+            message.timestamp.format("%H:%M").to_string().dimmed(),
+            speaker,
+            message.content
+        );
+    }
+    Ok(())
+}
+
+/// Delete a session transcript
+async fn delete_session(store: &SessionStore, id: &str, force: bool) -> Result<()> {
+    let id = store.resolve(id).await?;
+    let record = store.load(id).await?;
+
+    if !force
+        && !crate::utils::confirm(&format!(
+            "Delete session '{}' ({} messages)?",
+            record.display_title(),
+            record.messages.len()
+        ))?
+    {
+        println!("{} Cancelled", "ℹ️".blue());
+        return Ok(());
+    }
+
+    store.delete(id).await?;
+    println!("{} Deleted session {}", "✓".green(), id);
+    Ok(())
+}
+
+/// Export a transcript as Markdown or JSON, to a file or stdout
+async fn export_session(
+    store: &SessionStore,
+    id: &str,
+    format: &str,
+    output: Option<std::path::PathBuf>,
+) -> Result<()> {
+    let record = store.load(store.resolve(id).await?).await?;
+
+    let content = match format.to_lowercase().as_str() {
+        "markdown" | "md" => record.to_markdown(),
+        "json" => serde_json::to_string_pretty(&record)?,
+        other => anyhow::bail!("Unsupported export format: {} (expected markdown or json)", other),
+    };
+
+    match output {
+        Some(path) => {
+            std::fs::write(&path, content)
+                .with_context(|| format!("Failed to write export: {}", path.display()))?;
+            eprintln!("{} Exported {} to {}", "✓".green(), record.id, path.display());
+        }
+        None => print!("{}", content),
+    }
+    Ok(())
+}
@@ -1,23 +1,171 @@
 //! Start command
-//! 
-//! Start Jamey runtime service
+//!
+//! Start Jamey runtime service, in the foreground or detached as a daemon
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use colored::*;
-use tracing::{info, error};
+use crate::daemon::{self, PidFile};
+use jamey_runtime::{web, Runtime, RuntimeConfig};
+use std::time::{Duration, Instant};
+use tracing::info;
+
+/// How long `--daemon` waits for the detached runtime to come up
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(30);
 
 /// Run start command
-pub async fn run_start(daemon: bool, port: u16) -> Result<()> {
-    println!("{} Starting Jamey runtime...", "🚀".cyan().bold());
-    
-    if daemon {
-        println!("{} Daemon mode not yet implemented", "⚠️".yellow());
+///
+/// `passthrough` holds the global flags (`--debug`, `--profile`) to hand to
+/// the detached process.
+pub async fn run_start(daemon: bool, detached: bool, port: u16, passthrough: Vec<String>) -> Result<()> {
+    if daemon && !detached {
+        return start_daemon(port, passthrough).await;
+    }
+    run_runtime(port, detached).await
+}
+
+/// Spawn a detached copy of ourselves and wait until it has written its PID file
+async fn start_daemon(port: u16, passthrough: Vec<String>) -> Result<()> {
+    println!("{} Starting Jamey runtime in the background...", "🚀".cyan().bold());
+
+    let pid_path = daemon::pid_file_path();
+    if let Some(pid) = daemon::read_pid(&pid_path)? {
+        if daemon::is_runtime_process(pid) {
+            return Err(anyhow::anyhow!("Jamey is already running (pid {}). Stop it with `jamey stop`", pid));
+        }
+    }
+
+    let mut args = passthrough;
+    args.extend(["start".to_string(), "--detached".to_string(), "--port".to_string(), port.to_string()]);
+    let mut child = daemon::spawn_detached(&args)?;
+    let child_pid = child.id();
+
+    let started = Instant::now();
+    loop {
+        if let Some(status) = child.try_wait()? {
+            println!("{} Runtime exited during startup ({})", "❌".red(), status);
+            let console = daemon::console_log_path();
+            for line in daemon::tail(&console, 10) {
+                println!("  {}", line.dimmed());
+            }
+            return Err(anyhow::anyhow!("Background runtime failed to start; see {}", console.display()));
+        }
+        if daemon::read_pid(&pid_path).ok().flatten() == Some(child_pid) {
+            break;
+        }
+        if started.elapsed() > STARTUP_TIMEOUT {
+            println!(
+                "{} Still starting after {}s (pid {}); check {}",
+                "⚠️".yellow(),
+                STARTUP_TIMEOUT.as_secs(),
+                child_pid,
+                daemon::log_dir().display()
+            );
+            return Ok(());
+        }
+        tokio::time::sleep(Duration::from_millis(200)).await;
     }
-    
+
+    println!("{} Runtime running in the background (pid {})", "✅".green(), child_pid);
     println!("{} Port: {}", "🔌".blue(), port);
-    
-    // TODO: Implement actual runtime startup
-    println!("{} Runtime startup not yet implemented", "⚠️".yellow());
-    
+    if let Some(address) = web_address(port) {
+        println!("{} Web chat: {}", "🌐".blue(), address);
+    }
+    println!("{} PID file: {}", "📄".blue(), pid_path.display());
+    println!("{} Logs: {}", "📋".blue(), daemon::log_dir().display());
+    println!("{} Stop it with: {}", "💡".yellow(), "jamey stop".bold());
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Run the runtime in this process until a shutdown request arrives
+async fn run_runtime(port: u16, detached: bool) -> Result<()> {
+    if !detached {
+        println!("{} Starting Jamey runtime...", "🚀".cyan().bold());
+    }
+
+    // Fail fast, before the slow parts of startup
+    let pid_path = daemon::pid_file_path();
+    if let Some(pid) = daemon::read_pid(&pid_path)? {
+        if pid != std::process::id() && daemon::is_runtime_process(pid) {
+            return Err(anyhow::anyhow!("Jamey is already running (pid {})", pid));
+        }
+    }
+
+    let mut config = RuntimeConfig::from_env()
+        .map_err(|e| anyhow::anyhow!("Failed to load runtime config: {}", e))?;
+    config.api.http_port = port;
+    let mut runtime = Runtime::new(config).await
+        .context("Failed to initialize runtime")?;
+
+    // Written only once the runtime is up; `start --daemon` waits for it
+    let _pid_file = PidFile::acquire(&pid_path)?;
+    let shutdown = runtime.state().shutdown_signal.clone();
+    info!("Jamey runtime started (pid {}, port {})", std::process::id(), port);
+    if !detached {
+        println!("{} Runtime running (pid {}, port {}). Press Ctrl+C to stop.", "✅".green(), std::process::id(), port);
+        if let Some(address) = web::address(&runtime.state().config) {
+            println!("{} Web chat: {}", "🌐".blue(), address);
+        }
+    }
+
+    let mut run = tokio::spawn(async move { runtime.run().await });
+    tokio::select! {
+        result = &mut run => {
+            // Shut down from inside, e.g. by a fatal error
+            result??;
+            return Ok(());
+        }
+        reason = shutdown_requested() => {
+            info!("{}, draining runtime", reason?);
+            let _ = shutdown.send(());
+        }
+    }
+
+    run.await??;
+    info!("Jamey runtime stopped");
+    if !detached {
+        println!("{} Runtime stopped", "🛑".cyan());
+    }
+    Ok(())
+}
+
+/// Where the detached runtime serves its web chat, as far as its config says
+fn web_address(port: u16) -> Option<String> {
+    let mut config = RuntimeConfig::from_env().ok()?;
+    config.api.http_port = port;
+    web::address(&config)
+}
+
+/// Resolves on Ctrl+C or SIGTERM; SIGHUP (terminal closed) is ignored
+#[cfg(unix)]
+async fn shutdown_requested() -> Result<&'static str> {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut terminate = signal(SignalKind::terminate())?;
+    let mut hangup = signal(SignalKind::hangup())?;
+    loop {
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => return Ok("Interrupted"),
+            _ = terminate.recv() => return Ok("SIGTERM received"),
+            _ = hangup.recv() => tracing::warn!("Ignoring SIGHUP"),
+        }
+    }
+}
+
+/// Resolves on Ctrl+C or when `jamey stop` drops a stop file
+#[cfg(not(unix))]
+async fn shutdown_requested() -> Result<&'static str> {
+    let stop_file = daemon::stop_file_path();
+    let _ = std::fs::remove_file(&stop_file);
+    let mut poll = tokio::time::interval(Duration::from_secs(1));
+    loop {
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => return Ok("Interrupted"),
+            _ = poll.tick() => {
+                if stop_file.exists() {
+                    let _ = std::fs::remove_file(&stop_file);
+                    return Ok("Stop requested");
+                }
+            }
+        }
+    }
+}
@@ -1,30 +1,296 @@
 //! Status command
-//! 
-//! Show system status and health
+//!
+//! Show system status and health by scraping the runtime's metrics endpoint.
+//! Works against a runtime in any process, including `jamey start --daemon`.
 
 use anyhow::Result;
 use colored::*;
-use tracing::{info, error};
+use crate::utils::format_duration;
+use jamey_runtime::status::{parse_prometheus, RuntimeStatus, Sample};
+use serde::Serialize;
+use std::time::Duration;
+use tracing::debug;
+
+/// Give up on the metrics endpoint after this long
+const SCRAPE_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// What `--format json` prints
+#[derive(Serialize)]
+struct StatusReport {
+    metrics_url: String,
+    reachable: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    #[serde(flatten)]
+    status: RuntimeStatus,
+    /// Every `jamey_*` sample, with `--detailed`
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    samples: Vec<Sample>,
+}
 
 /// Run status command
-pub async fn run_status(detailed: bool, format: String) -> Result<()> {
-    println!("{} Jamey System Status", "📊".cyan().bold());
-    
-    if detailed {
-        println!("{} Detailed status not yet implemented", "⚠️".yellow());
+pub async fn run_status(detailed: bool, format: String, metrics_url: String) -> Result<()> {
+    if !matches!(format.as_str(), "table" | "json" | "plain") {
+        return Err(anyhow::anyhow!("Invalid format: {}. Must be 'table', 'json' or 'plain'", format));
     }
-    
+
+    let report = match scrape(&metrics_url).await {
+        Ok(samples) => StatusReport {
+            metrics_url,
+            reachable: true,
+            error: None,
+            status: RuntimeStatus::from_samples(&samples),
+            samples: if detailed {
+                samples.into_iter().filter(|s| s.name.starts_with("jamey_")).collect()
+            } else {
+                Vec::new()
+            },
+        },
+        Err(e) => {
+            debug!("Metrics scrape failed: {:#}", e);
+            StatusReport {
+                metrics_url,
+                reachable: false,
+                error: Some(e.to_string()),
+                status: RuntimeStatus::default(),
+                samples: Vec::new(),
+            }
+        }
+    };
+
     match format.as_str() {
-        "json" => {
-            println!("{} JSON format not yet implemented", "⚠️".yellow());
+        "json" => println!("{}", serde_json::to_string_pretty(&report)?),
+        "plain" => print_plain(&report),
+        _ => print_table(&report),
+    }
+
+    // Scripts can use the exit code as a liveness check
+    if !report.status.up {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+async fn scrape(url: &str) -> Result<Vec<Sample>> {
+    let client = reqwest::Client::builder().timeout(SCRAPE_TIMEOUT).build()?;
+    let body = client.get(url).send().await?.error_for_status()?.text().await?;
+    Ok(parse_prometheus(&body))
+}
+
+fn print_table(report: &StatusReport) {
+    let status = &report.status;
+    println!("{} Jamey System Status", "📊".cyan().bold());
+    println!("{}", "─".repeat(60));
+
+    if !report.reachable {
+        println!("{:<12} {} (no metrics at {})", "Runtime", "● not running".red().bold(), report.metrics_url);
+        if let Some(ref error) = report.error {
+            println!("{:<12} {}", "", error.dimmed());
         }
-        "table" => {
-            println!("{} Table format not yet implemented", "⚠️".yellow());
+        println!();
+        println!("{} Start it with {} or point {} at it", "💡".yellow(), "jamey start".bold(), "--metrics-url".bold());
+        return;
+    }
+
+    let runtime = if status.up { "● running".green().bold() } else { "● stopping".yellow().bold() };
+    match status.uptime_seconds {
+        Some(uptime) => println!("{:<12} {} (up {})", "Runtime", runtime, format_duration(uptime as u64)),
+        None => println!("{:<12} {}", "Runtime", runtime),
+    }
+    println!("{:<12} {}", "Sessions", or_na(status.sessions_active.map(|n| format!("{} active", n))));
+
+    let db = &status.database;
+    let db_state = match db.up {
+        Some(true) => "✓ up".green(),
+        Some(false) => "✗ down".red(),
+        None => "n/a".dimmed(),
+    };
+    match (db.pool_size, db.pool_max_size) {
+        (Some(size), Some(max)) => println!(
+            "{:<12} {} — pool {}/{} connections, {} idle, {} waiting",
+            "Database",
+            db_state,
+            size,
+            max,
+            db.pool_available.unwrap_or(0),
+            db.pool_waiting.unwrap_or(0)
+        ),
+        _ => println!("{:<12} {}", "Database", db_state),
+    }
+
+    println!("{:<12} {}", "Cache", or_na(status.cache_hit_rate.map(|r| format!("{:.1}% hit rate", r * 100.0))));
+
+    let budget = &status.budget;
+    let spend = match (budget.spent_today_usd, budget.daily_limit_usd) {
+        (Some(spent), Some(limit)) => {
+            let used = budget.used_fraction().unwrap_or(0.0);
+            let line = format!("${:.2} of ${:.2} today ({:.0}%)", spent, limit, used * 100.0);
+            if used >= 1.0 {
+                line.red().bold().to_string()
+            } else if used >= 0.8 {
+                line.yellow().to_string()
+            } else {
+                line
+            }
+        }
+        (Some(spent), None) => format!("${:.2} today {}", spent, "(no budget set)".dimmed()),
+        _ => "n/a".dimmed().to_string(),
+    };
+    println!("{:<12} {}", "Budget", spend);
+    let quota = &status.quota;
+    let mut limits = Vec::new();
+    if let Some(credits) = quota.credits_remaining_usd {
+        limits.push(format!("${:.2} credits left", credits));
+    } else if let Some(used) = quota.credits_used_usd {
+        limits.push(format!("${:.2} credits used {}", used, "(no limit)".dimmed()));
+    }
+    match (quota.requests_remaining, quota.request_limit) {
+        (Some(0), _) => limits.push("rate limit reached, throttling".yellow().to_string()),
+        (Some(remaining), Some(limit)) => limits.push(format!("{}/{} requests left in window", remaining, limit)),
+        _ => {}
+    }
+    if !limits.is_empty() {
+        println!("{:<12} {}", "Provider", limits.join(", "));
+    }
+    if !status.queues.is_empty() {
+        let queues: Vec<String> = status
+            .queues
+            .iter()
+            .map(|(queue, depth)| format!("{} {}", depth, queue.replace('_', " ")))
+            .collect();
+        println!("{:<12} {}", "Queues", queues.join(", "));
+    }
+    for gpu in &status.gpus {
+        let mut parts = Vec::new();
+        if let Some(utilization) = gpu.utilization {
+            parts.push(format!("{:.0}% busy", utilization * 100.0));
         }
-        _ => {
-            println!("{} Plain format not yet implemented", "⚠️".yellow());
+        match (gpu.memory_used_bytes, gpu.memory_total_bytes) {
+            (Some(used), Some(total)) => parts.push(format!("{} of {} VRAM", gib(used), gib(total))),
+            (None, Some(total)) => parts.push(format!("{} VRAM", gib(total))),
+            _ => {}
         }
+        println!("{:<12} {}", format!("GPU {}", gpu.gpu), or_na((!parts.is_empty()).then(|| parts.join(", "))));
     }
-    
-    Ok(())
-}
\ No newline at end of file
+
+    println!();
+    println!("{}", "Provider latency".bold());
+    if status.providers.is_empty() {
+        println!("  {}", "No model requests yet".dimmed());
+    } else {
+        println!(
+            "  {:<28} {:>6} {:>6} {:>8} {:>8} {:>8} {:>10}",
+            "MODEL", "REQS", "ERRS", "MEAN", "P50", "P99", "TOKENS"
+        );
+        for provider in &status.providers {
+            println!(
+                "  {:<28} {:>6} {:>6} {:>8} {:>8} {:>8} {:>10}",
+                provider.model,
+                provider.requests,
+                provider.errors,
+                seconds(provider.mean_seconds),
+                seconds(provider.p50_seconds),
+                seconds(provider.p99_seconds),
+                provider.prompt_tokens + provider.completion_tokens
+            );
+        }
+    }
+
+    if !report.samples.is_empty() {
+        println!();
+        println!("{}", "Raw metrics".bold());
+        for sample in &report.samples {
+            println!("  {} {}", sample_key(sample).dimmed(), sample.value);
+        }
+    }
+}
+
+/// `key=value` lines for grep and shell scripts
+fn print_plain(report: &StatusReport) {
+    let status = &report.status;
+    let opt = |value: Option<String>| value.unwrap_or_else(|| "n/a".to_string());
+
+    println!("reachable={}", report.reachable);
+    println!("up={}", status.up);
+    if let Some(ref error) = report.error {
+        println!("error={}", error);
+    }
+    if !report.reachable {
+        return;
+    }
+    println!("uptime_seconds={}", opt(status.uptime_seconds.map(|v| format!("{:.0}", v))));
+    println!("sessions_active={}", opt(status.sessions_active.map(|v| v.to_string())));
+    println!("db_up={}", opt(status.database.up.map(|v| v.to_string())));
+    println!("db_pool_size={}", opt(status.database.pool_size.map(|v| v.to_string())));
+    println!("db_pool_max_size={}", opt(status.database.pool_max_size.map(|v| v.to_string())));
+    println!("db_pool_waiting={}", opt(status.database.pool_waiting.map(|v| v.to_string())));
+    println!("cache_hit_rate={}", opt(status.cache_hit_rate.map(|v| format!("{:.4}", v))));
+    println!("spent_today_usd={}", opt(status.budget.spent_today_usd.map(|v| format!("{:.4}", v))));
+    println!("daily_budget_usd={}", opt(status.budget.daily_limit_usd.map(|v| format!("{:.2}", v))));
+    println!("credits_remaining_usd={}", opt(status.quota.credits_remaining_usd.map(|v| format!("{:.4}", v))));
+    println!("rate_limit_remaining={}", opt(status.quota.requests_remaining.map(|v| v.to_string())));
+    for provider in &status.providers {
+        println!(
+            "provider.{}.mean_seconds={}",
+            provider.model,
+            opt(provider.mean_seconds.map(|v| format!("{:.3}", v)))
+        );
+        println!("provider.{}.requests={}", provider.model, provider.requests);
+        println!("provider.{}.errors={}", provider.model, provider.errors);
+        println!("provider.{}.prompt_tokens={}", provider.model, provider.prompt_tokens);
+        println!("provider.{}.completion_tokens={}", provider.model, provider.completion_tokens);
+    }
+    for (queue, depth) in &status.queues {
+        println!("queue.{}={}", queue, depth);
+    }
+    for gpu in &status.gpus {
+        println!("gpu.{}.utilization={}", gpu.gpu, opt(gpu.utilization.map(|v| format!("{:.2}", v))));
+        println!("gpu.{}.memory_used_bytes={}", gpu.gpu, opt(gpu.memory_used_bytes.map(|v| v.to_string())));
+        println!("gpu.{}.memory_total_bytes={}", gpu.gpu, opt(gpu.memory_total_bytes.map(|v| v.to_string())));
+    }
+    for sample in &report.samples {
+        println!("{}={}", sample_key(sample), sample.value);
+    }
+}
+
+fn or_na(value: Option<String>) -> ColoredString {
+    match value {
+        Some(value) => value.normal(),
+        None => "n/a".dimmed(),
+    }
+}
+
+fn seconds(value: Option<f64>) -> String {
+    match value {
+        Some(v) if v < 1.0 => format!("{:.0}ms", v * 1000.0),
+        Some(v) => format!("{:.2}s", v),
+        None => "-".to_string(),
+    }
+}
+
+fn gib(bytes: u64) -> String {
+    format!("{:.1} GiB", bytes as f64 / (1u64 << 30) as f64)
+}
+
+/// `name{k="v",...}` as Prometheus would print it
+fn sample_key(sample: &Sample) -> String {
+    if sample.labels.is_empty() {
+        return sample.name.clone();
+    }
+    let labels: Vec<String> = sample.labels.iter().map(|(k, v)| format!("{}=\"{}\"", k, v)).collect();
+    format!("{}{{{}}}", sample.name, labels.join(","))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sample_key() {
+        let sample = &parse_prometheus(r#"jamey_provider_errors_total{model="gpt-4"} 2"#)[0];
+        assert_eq!(sample_key(sample), r#"jamey_provider_errors_total{model="gpt-4"}"#);
+        assert_eq!(seconds(Some(0.25)), "250ms");
+        assert_eq!(seconds(Some(1.5)), "1.50s");
+        assert_eq!(seconds(None), "-");
+    }
+}
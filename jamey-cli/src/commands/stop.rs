@@ -1,19 +1,58 @@
 //! Stop command
-//! 
-//! Stop Jamey runtime service
+//!
+//! Stop the Jamey runtime recorded in the PID file, escalating to a kill if
+//! it doesn't drain within the timeout
 
 use anyhow::Result;
 use colored::*;
-use tracing::{info, error};
+use crate::daemon;
+use std::io::Write;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
 
 /// Run stop command
 pub async fn run_stop(timeout: u64) -> Result<()> {
-    println!("{} Stopping Jamey runtime...", "🛑".cyan().bold());
-    
+    let pid_path = daemon::pid_file_path();
+    let pid = match daemon::read_pid(&pid_path)? {
+        Some(pid) => pid,
+        None => {
+            println!("{} Jamey is not running (no PID file at {})", "ℹ️".blue(), pid_path.display());
+            return Ok(());
+        }
+    };
+
+    if !daemon::is_runtime_process(pid) {
+        std::fs::remove_file(&pid_path)?;
+        println!("{} Removed stale PID file (pid {} is not running)", "🧹".yellow(), pid);
+        return Ok(());
+    }
+
+    println!("{} Stopping Jamey runtime (pid {})...", "🛑".cyan().bold(), pid);
     println!("{} Timeout: {} seconds", "⏱️".blue(), timeout);
-    
-    // TODO: Implement actual runtime shutdown
-    println!("{} Runtime shutdown not yet implemented", "⚠️".yellow());
-    
+    daemon::request_stop(pid)?;
+    info!("Requested shutdown of pid {}", pid);
+
+    let started = Instant::now();
+    let deadline = Duration::from_secs(timeout);
+    while started.elapsed() < deadline {
+        if !daemon::is_runtime_process(pid) {
+            println!();
+            println!("{} Runtime stopped after {:.1}s", "✅".green(), started.elapsed().as_secs_f64());
+            return Ok(());
+        }
+        print!(".");
+        std::io::stdout().flush()?;
+        tokio::time::sleep(Duration::from_millis(500)).await;
+    }
+
+    println!();
+    warn!("pid {} did not drain within {}s, killing it", pid, timeout);
+    println!("{} Runtime did not stop within {}s; forcing", "⚠️".yellow(), timeout);
+    if !daemon::force_kill(pid) {
+        return Err(anyhow::anyhow!("Failed to kill pid {}", pid));
+    }
+    // A killed process can't clean up after itself
+    let _ = std::fs::remove_file(&pid_path);
+    println!("{} Runtime killed", "💀".red());
     Ok(())
-}
\ No newline at end of file
+}
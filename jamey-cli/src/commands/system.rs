@@ -4,8 +4,10 @@
 
 use anyhow::{Context, Result};
 use colored::*;
-use crate::commands::{SystemAction, ConfigAction};
-use crate::config::CliConfig;
+use crate::{ConfigAction, SystemAction};
+use crate::config::{CliConfig, Profile};
+use crate::ProfileAction;
+use jamey_core::memory::MemoryStore;
 use jamey_runtime::{Runtime, RuntimeConfig};
 use tracing::{info, error, debug};
 use std::time::Instant;
@@ -162,7 +164,7 @@ async fn check_system_health(comprehensive: bool) -> Result<()> {
                         // Try a simple operation to verify connection
                         let vector_dim = state.config.memory.vector_dimension;
                         let test_embedding = vec![0.0; vector_dim];
-                        match state.memory_store.search(test_embedding, 1).await {
+                        match state.memory_store.search(&test_embedding, 1).await {
                             Ok(_) => {
                                 println!("  {} Memory store accessible", "✓".green());
                             }
@@ -177,7 +179,7 @@ async fn check_system_health(comprehensive: bool) -> Result<()> {
                     if comprehensive {
                         println!("  {} Checking LLM provider...", "⏳".yellow());
                         // We can't easily test without making an API call, so just check config
-                        if state.config.llm.openrouter_api_key.is_empty() {
+                        if state.config.llm.openrouter_api_key.0.is_empty() {
                             println!("  {} LLM API key not configured", "✗".red());
                             all_healthy = false;
                         } else {
@@ -209,10 +211,43 @@ async fn check_system_health(comprehensive: bool) -> Result<()> {
     Ok(())
 }
 
+/// Print every runtime setting with the layer it came from
+fn show_runtime_origins() -> Result<()> {
+    let path = std::env::var_os(jamey_runtime::config::CONFIG_FILE_ENV).map(PathBuf::from);
+    let (config, origins) = RuntimeConfig::load_with_origins(path.as_deref())
+        .context("Failed to load runtime configuration")?;
+
+    println!("{} Runtime Configuration:", "📋".blue().bold());
+    match (origins.file(), path.as_deref()) {
+        (Some(file), _) => println!("  File: {}", file.display()),
+        (None, Some(path)) => println!("  File: {} {}", path.display(), "(not found, using defaults)".dimmed()),
+        (None, None) => println!("  File: {}", "none".dimmed()),
+    }
+    println!();
+    println!("  {:<40} {:<32} ORIGIN", "KEY", "VALUE");
+    for (key, value) in config.flattened() {
+        let origin = origins.get(&key);
+        let origin = match origin {
+            jamey_runtime::config::ConfigOrigin::Default => origin.to_string().dimmed(),
+            _ => origin.to_string().normal(),
+        };
+        println!("  {:<40} {:<32} {}", key, display_value(&value), origin);
+    }
+    Ok(())
+}
+
+/// Render a config value; secrets are already masked by `flattened`
+fn display_value(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
 /// Run configuration action
 async fn run_config_action(action: ConfigAction) -> Result<()> {
     match action {
-        ConfigAction::Show => {
+        ConfigAction::Show { origin } => {
             println!("{} Current Configuration", "⚙️".cyan().bold());
             println!("{}", "═".repeat(50));
             println!();
@@ -223,7 +258,9 @@ async fn run_config_action(action: ConfigAction) -> Result<()> {
             println!("  Runtime URL: {}", config.runtime_url);
             println!("  Timeout: {} seconds", config.timeout_seconds);
             println!("  Verbose: {}", config.verbose);
+            println!("  Language: {}", config.language.as_deref().unwrap_or("system"));
             println!("  API Key: {}", if config.api_key.is_some() { "✓ Configured (from environment)" } else { "✗ Not configured" });
+            println!("  Active Profile: {}", config.active_profile.as_deref().unwrap_or("none"));
             println!();
             
             // Show config file path
@@ -233,6 +270,11 @@ async fn run_config_action(action: ConfigAction) -> Result<()> {
                 .join("jamey")
                 .join("cli.toml");
             println!("{} Config File: {}", "📁".blue(), config_path.display());
+
+            if origin {
+                println!();
+                show_runtime_origins()?;
+            }
         }
         ConfigAction::Set { key, value } => {
             let mut config = CliConfig::load().unwrap_or_default();
@@ -256,11 +298,15 @@ async fn run_config_action(action: ConfigAction) -> Result<()> {
                         .with_context(|| format!("Invalid verbose value: {}. Must be 'true' or 'false'", value))?;
                     println!("{} Set verbose to: {}", "✓".green(), config.verbose);
                 }
+                "language" => {
+                    config.language = Some(value).filter(|v| !v.is_empty());
+                    println!("{} Set language to: {}", "✓".green(), config.language.as_deref().unwrap_or("system"));
+                }
                 "api_key" => {
                     return Err(anyhow::anyhow!("API keys cannot be set via config command. Use environment variables (JAMEY_API_KEY or OPENROUTER_API_KEY) instead."));
                 }
                 _ => {
-                    return Err(anyhow::anyhow!("Unknown config key: {}. Valid keys: default_model, runtime_url, timeout_seconds, verbose", key));
+                    return Err(anyhow::anyhow!("Unknown config key: {}. Valid keys: default_model, runtime_url, timeout_seconds, verbose, language", key));
                 }
             }
             
@@ -283,6 +329,9 @@ async fn run_config_action(action: ConfigAction) -> Result<()> {
                 "verbose" => {
                     println!("{}", config.verbose);
                 }
+                "language" => {
+                    println!("{}", config.language.as_deref().unwrap_or("system"));
+                }
                 "api_key" => {
                     if config.api_key.is_some() {
                         println!("***REDACTED*** (API keys cannot be displayed for security)");
@@ -291,7 +340,7 @@ async fn run_config_action(action: ConfigAction) -> Result<()> {
                     }
                 }
                 _ => {
-                    return Err(anyhow::anyhow!("Unknown config key: {}. Valid keys: default_model, runtime_url, timeout_seconds, verbose, api_key", key));
+                    return Err(anyhow::anyhow!("Unknown config key: {}. Valid keys: default_model, runtime_url, timeout_seconds, verbose, language, api_key", key));
                 }
             }
         }
@@ -307,10 +356,152 @@ async fn run_config_action(action: ConfigAction) -> Result<()> {
                 }
             }
             
-            let default_config = CliConfig::default();
+            // Profiles are kept; their secrets live outside the file
+            let current = CliConfig::load().unwrap_or_default();
+            let default_config = CliConfig {
+                profiles: current.profiles,
+                ..CliConfig::default()
+            };
             default_config.save()?;
             println!("{} Configuration reset to defaults.", "✅".green());
         }
+        ConfigAction::Validate { file } => {
+            let file = crate::commands::init::expand_home(&file);
+            let problems = RuntimeConfig::check_file(&file)
+                .with_context(|| format!("Failed to read {}", file.display()))?;
+            if problems.is_empty() {
+                println!("{} {} is valid.", "✅".green(), file.display());
+            } else {
+                println!("{} {} has {} problem(s):", "❌".red(), file.display(), problems.len());
+                for problem in &problems {
+                    println!("  • {}", problem);
+                }
+                // Scripts and editors can use the exit code
+                std::process::exit(1);
+            }
+        }
+        ConfigAction::Schema => {
+            println!("{}", serde_json::to_string_pretty(&RuntimeConfig::json_schema())?);
+        }
+        ConfigAction::Profile { action } => run_profile_action(action)?,
+    }
+    
+    Ok(())
+}
+
+/// Manage named profiles
+fn run_profile_action(action: ProfileAction) -> Result<()> {
+    let mut config = CliConfig::load().unwrap_or_default();
+    
+    match action {
+        ProfileAction::Add { name, model, runtime_url, timeout, env } => {
+            crate::config::validate_profile_name(&name)?;
+            if let Some((key, _)) = env.iter().find(|(key, _)| crate::config::is_secret_key(key)) {
+                return Err(anyhow::anyhow!(
+                    "{} looks like a secret. Store it with: jamey system config profile secret {} {}",
+                    key, name, key
+                ));
+            }
+            
+            let created = !config.profiles.contains_key(&name);
+            let profile = config.profiles.entry(name.clone()).or_default();
+            if model.is_some() {
+                profile.default_model = model;
+            }
+            if runtime_url.is_some() {
+                profile.runtime_url = runtime_url;
+            }
+            if timeout.is_some() {
+                profile.timeout_seconds = timeout;
+            }
+            profile.env.extend(env);
+            config.save()?;
+            
+            println!("{} Profile '{}' {}", "✓".green(), name, if created { "added" } else { "updated" });
+            if created {
+                println!("{} Use it with: {} or {}", "💡".yellow(),
+                    format!("jamey --profile {} <command>", name).bold(),
+                    format!("jamey system config profile use {}", name).bold());
+            }
+        }
+        ProfileAction::List => {
+            println!("{} Profiles", "🗂️".cyan().bold());
+            println!("{}", "─".repeat(50));
+            if config.profiles.is_empty() {
+                println!("No profiles. Add one with: {}", "jamey system config profile add <name>".bold());
+                return Ok(());
+            }
+            for (name, profile) in &config.profiles {
+                let active = config.active_profile.as_deref() == Some(name.as_str());
+                println!("{} {}", if active { "*".green().bold() } else { " ".normal() },
+                    if active { name.green().bold() } else { name.normal() });
+                if let Some(ref url) = profile.runtime_url {
+                    println!("    Runtime URL: {}", url);
+                }
+                if let Some(ref model) = profile.default_model {
+                    println!("    Model: {}", model);
+                }
+                if let Some(timeout) = profile.timeout_seconds {
+                    println!("    Timeout: {} seconds", timeout);
+                }
+                for (key, value) in &profile.env {
+                    println!("    {}={}", key, value);
+                }
+                if !profile.secrets.is_empty() {
+                    println!("    Secrets: {}", profile.secrets.join(", ").dimmed());
+                }
+            }
+        }
+        ProfileAction::Use { name } => {
+            if !config.profiles.contains_key(&name) {
+                return Err(anyhow::anyhow!("Unknown profile '{}'", name));
+            }
+            config.active_profile = Some(name.clone());
+            config.save()?;
+            println!("{} Now using profile '{}'", "✓".green(), name);
+        }
+        ProfileAction::Remove { name, force } => {
+            let Some(profile) = config.profiles.get(&name).cloned() else {
+                return Err(anyhow::anyhow!("Unknown profile '{}'", name));
+            };
+            if !force && !crate::utils::confirm(&format!("Remove profile '{}' and its secrets?", name))? {
+                println!("{} Removal cancelled.", "ℹ️".blue());
+                return Ok(());
+            }
+            
+            if !profile.secrets.is_empty() {
+                let secrets = Profile::secret_manager(&name)?;
+                for key in &profile.secrets {
+                    if let Err(e) = secrets.delete_secret(key) {
+                        println!("{} Could not delete secret {}: {}", "⚠️".yellow(), key, e);
+                    }
+                }
+            }
+            config.profiles.remove(&name);
+            if config.active_profile.as_deref() == Some(name.as_str()) {
+                config.active_profile = None;
+            }
+            config.save()?;
+            println!("{} Profile '{}' removed", "✓".green(), name);
+        }
+        ProfileAction::Secret { name, key } => {
+            let Some(profile) = config.profiles.get_mut(&name) else {
+                return Err(anyhow::anyhow!("Unknown profile '{}'", name));
+            };
+            let value = dialoguer::Password::new()
+                .with_prompt(format!("{} for profile '{}'", key, name))
+                .interact()
+                .context("Failed to read secret value")?;
+            
+            Profile::secret_manager(&name)?.store_secret(&key, &value)
+                .with_context(|| format!("Failed to store secret {}", key))?;
+            if !profile.secrets.contains(&key) {
+                profile.secrets.push(key.clone());
+                config.save()?;
+            }
+            println!("{} Stored {} for profile '{}' ({} backend)", "🔐".green(), key, name,
+                std::env::var("JAMEY_SECRETS_BACKEND").unwrap_or_else(|_| "keyring".to_string()));
+        }
     }
     
     Ok(())
@@ -320,6 +511,8 @@ async fn run_config_action(action: ConfigAction) -> Result<()> {
 async fn show_logs(lines: usize, follow: bool, level: Option<String>) -> Result<()> {
     // Find log file (common locations)
     let log_paths = vec![
+        // Written by `jamey start`, rotated daily
+        crate::daemon::latest_log_file(),
         home_dir().map(|h| h.join(".local").join("share").join("jamey").join("logs").join("jamey.log")),
         Some(PathBuf::from("./logs/jamey.log")),
         Some(PathBuf::from("./jamey.log")),
@@ -328,7 +521,7 @@ async fn show_logs(lines: usize, follow: bool, level: Option<String>) -> Result<
     let log_path = log_paths.iter()
         .flatten()
         .find(|p| p.exists())
-        .ok_or_else(|| anyhow::anyhow!("Log file not found. Checked: ~/.local/share/jamey/logs/jamey.log, ./logs/jamey.log, ./jamey.log"))?;
+        .ok_or_else(|| anyhow::anyhow!("Log file not found. Checked: ~/.config/jamey/logs, ~/.local/share/jamey/logs/jamey.log, ./logs/jamey.log, ./jamey.log"))?;
     
     println!("{} System Logs", "📋".cyan().bold());
     println!("{}", "═".repeat(50));
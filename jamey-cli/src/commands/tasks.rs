@@ -0,0 +1,139 @@
+//! Tasks command
+//!
+//! `jamey tasks list` shows what a running runtime is busy with: chat turns,
+//! the model calls and tool executions under them, scheduled jobs, the
+//! request queue's depths, and background tasks that have panicked.
+//! `jamey tasks cancel` stops one by id. Both go through the runtime's
+//! `/tasks` endpoint on `api.http_port`.
+
+use crate::TasksAction;
+use crate::utils::format_duration;
+use anyhow::{Context, Result};
+use colored::*;
+use jamey_core::supervisor::{SupervisedTask, TaskState};
+use jamey_runtime::inflight::{InFlightReport, WorkInfo};
+use std::collections::HashSet;
+use std::time::Duration;
+use uuid::Uuid;
+
+/// Give up on the runtime after this long
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Work running longer than this is highlighted
+const SLOW_AFTER_SECS: f64 = 60.0;
+
+pub async fn run_tasks_action(action: TasksAction) -> Result<()> {
+    match action {
+        TasksAction::List { url, token, format } => list_tasks(&url, token.as_deref(), &format).await,
+        TasksAction::Cancel { id, url, token } => cancel_task(&url, token.as_deref(), id).await,
+    }
+}
+
+fn client() -> Result<reqwest::Client> {
+    Ok(reqwest::Client::builder().timeout(REQUEST_TIMEOUT).build()?)
+}
+
+fn with_token(request: reqwest::RequestBuilder, token: Option<&str>) -> reqwest::RequestBuilder {
+    match token {
+        Some(token) => request.bearer_auth(token),
+        None => request,
+    }
+}
+
+async fn list_tasks(url: &str, token: Option<&str>, format: &str) -> Result<()> {
+    if format != "table" && format != "json" {
+        return Err(anyhow::anyhow!("Invalid format: {}. Must be 'table' or 'json'", format));
+    }
+    let endpoint = format!("{}/tasks", url.trim_end_matches('/'));
+    let report: InFlightReport = with_token(client()?.get(&endpoint), token)
+        .send()
+        .await
+        .with_context(|| format!("No runtime answering at {}", url))?
+        .error_for_status()?
+        .json()
+        .await
+        .context("Unexpected response from the runtime")?;
+
+    if format == "json" {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+
+    let queue = &report.queue;
+    println!(
+        "{} Queue: {} running ({} background), {} interactive and {} background waiting",
+        "📥".cyan().bold(),
+        queue.running,
+        queue.running_background,
+        queue.waiting_interactive,
+        queue.waiting_background
+    );
+    println!();
+    if report.tasks.is_empty() {
+        println!("{} Nothing running.", "✓".green());
+    } else {
+        println!("{:<36}  {:<10}  {:>10}  {}", "ID".bold(), "KIND".bold(), "AGE".bold(), "LABEL".bold());
+        // Calls made by a turn are listed, indented, under it
+        let listed: HashSet<Uuid> = report.tasks.iter().map(|task| task.id).collect();
+        let roots = report.tasks.iter().filter(|task| !task.parent.is_some_and(|parent| listed.contains(&parent)));
+        for root in roots {
+            print_task(root, false);
+            for child in report.tasks.iter().filter(|task| task.parent == Some(root.id)) {
+                print_task(child, true);
+            }
+        }
+    }
+
+    // Healthy background loops aren't worth a line each
+    let troubled: Vec<&SupervisedTask> = report.supervised.iter().filter(|task| task.panics > 0).collect();
+    if !troubled.is_empty() {
+        println!();
+        println!("{} Background tasks that panicked:", "⚠".yellow().bold());
+        for task in troubled {
+            print_supervised(task);
+        }
+    }
+    Ok(())
+}
+
+fn print_supervised(task: &SupervisedTask) {
+    let state = match task.state {
+        TaskState::Running => "running".green(),
+        TaskState::Restarting => "restarting".yellow(),
+        TaskState::Finished => "finished".normal(),
+        TaskState::Failed => "failed".red(),
+    };
+    println!(
+        "  {:<24}  {:<10}  {} panic(s), {} restart(s)",
+        task.name, state, task.panics, task.restarts
+    );
+    if let Some(message) = &task.last_panic {
+        let at = task
+            .last_panic_at
+            .map(|at| at.format(" at %Y-%m-%d %H:%M:%S UTC").to_string())
+            .unwrap_or_default();
+        println!("    last{}: {}", at, message.dimmed());
+    }
+}
+
+fn print_task(task: &WorkInfo, nested: bool) {
+    let age = format_duration(task.age_secs as u64);
+    let age = if task.age_secs > SLOW_AFTER_SECS { age.yellow() } else { age.normal() };
+    let label = if nested { format!("  └ {}", task.label) } else { task.label.clone() };
+    println!("{:<36}  {:<10}  {:>10}  {}", task.id, task.kind.to_string(), age, label);
+}
+
+async fn cancel_task(url: &str, token: Option<&str>, id: String) -> Result<()> {
+    let id = Uuid::parse_str(&id).with_context(|| format!("Invalid task ID: {}", id))?;
+    let endpoint = format!("{}/tasks/{}", url.trim_end_matches('/'), id);
+    let response = with_token(client()?.delete(&endpoint), token)
+        .send()
+        .await
+        .with_context(|| format!("No runtime answering at {}", url))?;
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        return Err(anyhow::anyhow!("No running task {}; it may already have finished", id));
+    }
+    response.error_for_status()?;
+    println!("{} Cancelled {}", "✓".green(), id);
+    Ok(())
+}
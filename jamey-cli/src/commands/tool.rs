@@ -0,0 +1,158 @@
+//! Connector invocation commands
+//!
+//! Run connector actions by hand and inspect what each connector accepts,
+//! bypassing the model entirely. Useful for debugging connectors and
+//! credentials.
+
+use anyhow::{Context, Result};
+use colored::*;
+use crate::ToolAction;
+use jamey_runtime::{Runtime, RuntimeConfig};
+use std::collections::HashMap;
+use std::time::Instant;
+
+/// Run tool action
+pub async fn run_tool_action(action: ToolAction) -> Result<()> {
+    let config = RuntimeConfig::from_env()
+        .map_err(|e| anyhow::anyhow!("Failed to load runtime config: {}", e))?;
+    let runtime = Runtime::new(config).await
+        .context("Failed to initialize runtime for connector access")?;
+
+    let result = match action {
+        ToolAction::List { detailed, format } => list_tools(&runtime, detailed, &format).await,
+        ToolAction::Run { connector, action, params, yes, json } => {
+            run_tool(&runtime, connector, action, params, yes, json).await
+        }
+    };
+    runtime.shutdown().await;
+    result
+}
+
+/// List registered connectors
+async fn list_tools(runtime: &Runtime, detailed: bool, format: &str) -> Result<()> {
+    let connectors = runtime.state().hybrid_orchestrator.lock().await
+        .get_registry()
+        .describe()
+        .await;
+
+    match format {
+        "json" => {
+            println!("{}", serde_json::to_string_pretty(&connectors)?);
+            return Ok(());
+        }
+        "table" => {}
+        other => return Err(anyhow::anyhow!("Invalid format: {}. Must be 'table' or 'json'", other)),
+    }
+
+    println!("{} Connectors ({})", "🔧".cyan().bold(), connectors.len());
+    println!("{}", "─".repeat(60));
+
+    for info in &connectors {
+        let meta = &info.metadata;
+        let status = if info.enabled { "enabled".green() } else { "disabled".red() };
+        println!(
+            "{} {} v{} [{}] {:?}{}",
+            meta.id.cyan().bold(),
+            meta.name.dimmed(),
+            meta.version,
+            status,
+            meta.capability_level,
+            if meta.requires_approval { " (requires approval)".yellow().to_string() } else { String::new() }
+        );
+        println!("  {}", meta.description);
+        if info.actions.is_empty() {
+            println!("  Actions: {}", "not enumerated".dimmed());
+        } else {
+            println!("  Actions: {}", info.actions.join(", "));
+        }
+        println!("  Required params: {}", info.required_params.join(", "));
+
+        if detailed {
+            if !info.required_credentials.is_empty() {
+                println!("  Credentials: {}", info.required_credentials.join(", "));
+            }
+            if !meta.safety_checks.is_empty() {
+                println!("  Safety checks:");
+                for check in &meta.safety_checks {
+                    println!("    • {}", check);
+                }
+            }
+        }
+        println!();
+    }
+
+    println!("{} Run one with: {}", "💡".yellow(), "jamey tool run <connector> <action> -p key=value".bold());
+    Ok(())
+}
+
+/// Execute a single connector action
+async fn run_tool(
+    runtime: &Runtime,
+    connector: String,
+    action: String,
+    params: Vec<(String, String)>,
+    yes: bool,
+    json: bool,
+) -> Result<()> {
+    let mut params: HashMap<String, String> = params.into_iter().collect();
+    params.insert("action".to_string(), action.clone());
+    // Connectors gate destructive actions on an explicit confirmation
+    if yes {
+        params.insert("confirmed".to_string(), "true".to_string());
+    } else {
+        params.remove("confirmed");
+    }
+
+    if !json {
+        println!("{} {} {}", "🔧".cyan().bold(), connector.cyan(), action.bold());
+    }
+
+    let started = Instant::now();
+    let result = runtime.state().hybrid_orchestrator.lock().await
+        .execute_connector(&connector, params)
+        .await
+        .with_context(|| format!("Connector '{}' failed", connector))?;
+    let elapsed = started.elapsed();
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&result)?);
+    } else {
+        if !result.output.is_empty() {
+            println!("{}", result.output);
+        }
+        for warning in &result.warnings {
+            println!("{} {}", "⚠️".yellow(), warning);
+        }
+        for error in &result.errors {
+            println!("{} {}", "❌".red(), error);
+        }
+        if !result.metadata.is_empty() {
+            let mut keys: Vec<_> = result.metadata.keys().collect();
+            keys.sort();
+            for key in keys {
+                println!("  {} {}", format!("{}:", key).dimmed(), result.metadata[key]);
+            }
+        }
+        if !result.network_requests.is_empty() || !result.files_accessed.is_empty() {
+            println!(
+                "  {}",
+                format!(
+                    "{} network request(s), {} file(s) accessed",
+                    result.network_requests.len(),
+                    result.files_accessed.len()
+                )
+                .dimmed()
+            );
+        }
+        let status = if result.success { "✓ success".green() } else { "✗ failed".red() };
+        println!("{} in {:.2?}", status, elapsed);
+        if !result.success && !yes && result.errors.iter().any(|e| e.contains("confirm")) {
+            println!("{} Re-run with {} to confirm this action", "💡".yellow(), "--yes".bold());
+        }
+    }
+
+    if !result.success {
+        return Err(anyhow::anyhow!("{} {} reported failure", connector, action));
+    }
+    Ok(())
+}
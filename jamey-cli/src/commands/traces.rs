@@ -0,0 +1,154 @@
+//! Traces command
+//!
+//! `jamey traces list` shows the model calls recorded by prompt tracing
+//! (`prompt_trace.enabled`), newest first, and `jamey traces show <id>`
+//! prints one in full: every prompt message, the tools offered, the
+//! completion and the tool calls it made. Both read the trace files at
+//! `JAMEY_TRACE_DIR` directly.
+
+use super::usage::parse_since;
+use crate::utils::validate_uuid;
+use crate::TracesAction;
+use anyhow::{Context, Result};
+use chrono::Utc;
+use colored::*;
+use jamey_runtime::prompt_trace::{PromptTrace, PromptTraceLog, TraceQuery};
+
+/// Characters of the last prompt message shown in the list
+const PREVIEW_CHARS: usize = 60;
+
+pub async fn run_traces_action(action: TracesAction) -> Result<()> {
+    match action {
+        TracesAction::List { since, session, turn, limit, format } => {
+            list_traces(&since, session, turn, limit, &format).await
+        }
+        TracesAction::Show { id, format } => show_trace(&id, &format).await,
+    }
+}
+
+async fn list_traces(
+    since: &str,
+    session: Option<String>,
+    turn: Option<String>,
+    limit: usize,
+    format: &str,
+) -> Result<()> {
+    if format != "table" && format != "json" {
+        return Err(anyhow::anyhow!("Invalid format: {}. Must be 'table' or 'json'", format));
+    }
+    let query = TraceQuery {
+        since: Some(parse_since(since, Utc::now())?),
+        session_id: session.as_deref().map(validate_uuid).transpose()?,
+        turn_id: turn.as_deref().map(validate_uuid).transpose()?,
+    };
+    let log = PromptTraceLog::from_env();
+    let mut traces = log
+        .query(&query)
+        .await
+        .with_context(|| format!("Failed to read prompt traces at {}", log.dir().display()))?;
+    traces.reverse();
+    traces.truncate(limit);
+
+    if format == "json" {
+        println!("{}", serde_json::to_string_pretty(&traces)?);
+        return Ok(());
+    }
+    if traces.is_empty() {
+        println!(
+            "{} No traces in {}. Is prompt_trace.enabled set?",
+            "ℹ".blue(),
+            log.dir().display()
+        );
+        return Ok(());
+    }
+
+    println!(
+        "{:<36}  {:<19}  {:<28}  {:>7}  {}",
+        "ID".bold(),
+        "TIME".bold(),
+        "MODEL".bold(),
+        "MS".bold(),
+        "PROMPT".bold()
+    );
+    for trace in &traces {
+        let preview = trace
+            .messages
+            .last()
+            .map(|message| preview(&message.content))
+            .unwrap_or_default();
+        let preview = if trace.error.is_some() { preview.red() } else { preview.normal() };
+        println!(
+            "{:<36}  {:<19}  {:<28}  {:>7}  {}",
+            trace.id,
+            trace.timestamp.format("%Y-%m-%d %H:%M:%S"),
+            trace.model,
+            trace.latency_ms,
+            preview
+        );
+    }
+    Ok(())
+}
+
+async fn show_trace(id: &str, format: &str) -> Result<()> {
+    if format != "text" && format != "json" {
+        return Err(anyhow::anyhow!("Invalid format: {}. Must be 'text' or 'json'", format));
+    }
+    let id = validate_uuid(id)?;
+    let log = PromptTraceLog::from_env();
+    let trace = log
+        .get(id)
+        .await
+        .with_context(|| format!("Failed to read prompt traces at {}", log.dir().display()))?
+        .ok_or_else(|| anyhow::anyhow!("No trace {} in {}", id, log.dir().display()))?;
+
+    if format == "json" {
+        println!("{}", serde_json::to_string_pretty(&trace)?);
+        return Ok(());
+    }
+    print_trace(&trace);
+    Ok(())
+}
+
+fn print_trace(trace: &PromptTrace) {
+    println!("{} {}", "🔎".cyan(), trace.id.to_string().bold());
+    println!("  Turn:     {}", trace.turn_id);
+    if let Some(session) = trace.session_id {
+        println!("  Session:  {}", session);
+    }
+    println!("  Model:    {}", trace.model);
+    println!("  At:       {} ({} ms)", trace.timestamp.format("%Y-%m-%d %H:%M:%S UTC"), trace.latency_ms);
+    if let Some(temperature) = trace.temperature {
+        println!("  Temp:     {}", temperature);
+    }
+    if !trace.tools.is_empty() {
+        println!("  Tools:    {}", trace.tools.join(", "));
+    }
+
+    for message in &trace.messages {
+        println!();
+        println!("{}", format!("── {} ──", message.role).dimmed());
+        println!("{}", message.content);
+    }
+
+    println!();
+    match &trace.error {
+        Some(error) => println!("{} {}", "✗ Failed:".red().bold(), error),
+        None => {
+            println!("{}", "── completion ──".green());
+            println!("{}", trace.completion);
+        }
+    }
+    for call in &trace.tool_calls {
+        println!("{} {}({})", "→".yellow(), call.name.bold(), call.arguments);
+    }
+}
+
+/// First line of `text`, cut to fit the list
+fn preview(text: &str) -> String {
+    let line = text.lines().next().unwrap_or_default();
+    if line.chars().count() <= PREVIEW_CHARS {
+        return line.to_string();
+    }
+    let cut: String = line.chars().take(PREVIEW_CHARS - 1).collect();
+    format!("{}…", cut)
+}
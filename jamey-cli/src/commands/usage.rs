@@ -0,0 +1,352 @@
+//! Usage reporting command
+//!
+//! Summarize token and dollar usage from the runtime's usage log, grouped by
+//! model, session or day, with CSV and JSON export for expense reports.
+//! `jamey usage insights` reports tool failure rates, latency percentiles
+//! and, with `--topics`, what recent sessions were about.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use colored::*;
+use jamey_runtime::analytics::{Insights, Percentiles, DEFAULT_TOPIC_SESSIONS};
+use jamey_runtime::config::RuntimeConfig;
+use jamey_runtime::usage::{self, GroupBy, UsageLog, UsageSummary};
+use jamey_runtime::Runtime;
+use serde::Serialize;
+use std::path::PathBuf;
+
+/// What `--format json` prints
+#[derive(Serialize)]
+struct UsageReport {
+    since: DateTime<Utc>,
+    until: DateTime<Utc>,
+    group_by: GroupBy,
+    rows: Vec<UsageSummary>,
+    total: UsageSummary,
+}
+
+/// Run usage command
+pub async fn run_usage(
+    since: String,
+    group_by: GroupBy,
+    format: String,
+    output: Option<PathBuf>,
+) -> Result<()> {
+    let format = resolve_format(&format, output.as_ref())?;
+    let now = Utc::now();
+    let since = parse_since(&since, now)?;
+
+    let log = UsageLog::from_env();
+    let records = log.query(Some(since)).await
+        .with_context(|| format!("Failed to read usage log at {}", log.dir().display()))?;
+    let report = UsageReport {
+        since,
+        until: now,
+        group_by,
+        rows: usage::summarize(&records, group_by),
+        total: usage::total(&records),
+    };
+
+    let rendered = match format {
+        "json" => serde_json::to_string_pretty(&report)? + "\n",
+        "csv" => to_csv(&report),
+        _ => {
+            print_table(&report, &log);
+            return Ok(());
+        }
+    };
+
+    match output {
+        Some(path) => {
+            std::fs::write(&path, rendered)
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+            println!(
+                "{} Exported {} row(s) to {}",
+                "✅".green(),
+                report.rows.len(),
+                path.display()
+            );
+        }
+        None => print!("{}", rendered),
+    }
+    Ok(())
+}
+
+/// Run usage insights command
+pub async fn run_insights(since: String, topics: bool, format: String) -> Result<()> {
+    if format != "table" && format != "json" {
+        return Err(anyhow::anyhow!("Invalid format: {}. Must be 'table' or 'json'", format));
+    }
+    let since = parse_since(&since, Utc::now())?;
+
+    let log = UsageLog::from_env();
+    let mut insights = Insights::gather(&log, Some(since)).await
+        .with_context(|| format!("Failed to read usage log at {}", log.dir().display()))?;
+
+    if topics {
+        // Topics need the embedding provider, so only they start the runtime
+        let config = RuntimeConfig::from_env()
+            .map_err(|e| anyhow::anyhow!("Failed to load runtime config: {}", e))?;
+        let runtime = Runtime::new(config).await
+            .context("Failed to initialize runtime for topic embeddings")?;
+        let state = runtime.state();
+        let embedder = state.embedder(None);
+        let result = insights.with_topics(&state.session_store, &embedder, DEFAULT_TOPIC_SESSIONS).await;
+        runtime.shutdown().await;
+        insights = result.context("Failed to group sessions into topics")?;
+    }
+
+    match format.as_str() {
+        "json" => println!("{}", serde_json::to_string_pretty(&insights)?),
+        _ => print_insights(&insights, &log),
+    }
+    Ok(())
+}
+
+fn print_insights(insights: &Insights, log: &UsageLog) {
+    println!(
+        "{} Insights since {}",
+        "📈".cyan().bold(),
+        insights.since.map(|s| s.format("%Y-%m-%d %H:%M UTC").to_string()).unwrap_or_default()
+    );
+    println!("{}", "─".repeat(80));
+    if insights.tools.is_empty() && insights.models.is_empty() {
+        println!("No activity recorded in this period ({}).", log.dir().display());
+        return;
+    }
+    println!(
+        "Sessions: {}   Tool runs: {}   Failed: {} ({:.1}%)",
+        insights.sessions,
+        insights.tool_calls,
+        insights.tool_failures,
+        insights.failure_rate() * 100.0
+    );
+
+    if !insights.tools.is_empty() {
+        println!();
+        println!("{:<38} {:>7} {:>7} {:>8} {:>8} {:>8}", "TOOL", "CALLS", "FAILED", "P50", "P95", "P99");
+        for tool in &insights.tools {
+            let failed = format!("{:>7}", format!("{:.1}%", tool.failure_rate() * 100.0));
+            let failed = if tool.failures > 0 { failed.yellow() } else { failed.normal() };
+            println!(
+                "{:<38} {:>7} {} {}",
+                truncate(&tool.tool, 38),
+                tool.calls,
+                failed,
+                latency_columns(Some(tool.latency))
+            );
+        }
+    }
+
+    if !insights.models.is_empty() {
+        println!();
+        println!("{:<38} {:>7} {:>7} {:>8} {:>8} {:>8}", "MODEL", "CALLS", "", "P50", "P95", "P99");
+        for model in &insights.models {
+            println!(
+                "{:<38} {:>7} {:>7} {}",
+                truncate(&model.model, 38),
+                model.calls,
+                "",
+                latency_columns(model.latency)
+            );
+        }
+    }
+
+    if !insights.topics.is_empty() {
+        println!();
+        println!("{}", "Topics".bold());
+        for topic in &insights.topics {
+            println!("  {} {}", format!("{:>4}", topic.session_ids.len()).cyan(), topic.label);
+            for example in topic.examples.iter().skip(1) {
+                println!("       {}", example.dimmed());
+            }
+        }
+    }
+}
+
+fn latency_columns(latency: Option<Percentiles>) -> String {
+    match latency {
+        Some(p) => format!(
+            "{:>8} {:>8} {:>8}",
+            format_ms(p.p50_ms),
+            format_ms(p.p95_ms),
+            format_ms(p.p99_ms)
+        ),
+        None => format!("{:>8} {:>8} {:>8}", "-", "-", "-"),
+    }
+}
+
+fn format_ms(ms: u64) -> String {
+    if ms >= 1000 {
+        format!("{:.1}s", ms as f64 / 1000.0)
+    } else {
+        format!("{}ms", ms)
+    }
+}
+
+fn truncate(value: &str, width: usize) -> String {
+    if value.chars().count() > width {
+        format!("{}…", value.chars().take(width - 1).collect::<String>())
+    } else {
+        value.to_string()
+    }
+}
+
+/// `--output` without an explicit format picks one from the file extension
+fn resolve_format<'a>(format: &'a str, output: Option<&PathBuf>) -> Result<&'a str> {
+    match (format, output) {
+        ("table", Some(path)) => match path.extension().and_then(|e| e.to_str()) {
+            Some("csv") => Ok("csv"),
+            Some("json") => Ok("json"),
+            _ => Err(anyhow::anyhow!("Use --format csv or --format json when exporting to a file")),
+        },
+        ("table" | "csv" | "json", _) => Ok(format),
+        (other, _) => Err(anyhow::anyhow!("Invalid format: {}. Must be 'table', 'csv' or 'json'", other)),
+    }
+}
+
+/// Accepts a relative span (`30m`, `24h`, `7d`, `4w`) or a UTC date
+pub(crate) fn parse_since(value: &str, now: DateTime<Utc>) -> Result<DateTime<Utc>> {
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return Ok(date.and_hms_opt(0, 0, 0).expect("midnight is valid").and_utc());
+    }
+
+    let invalid = || anyhow::anyhow!("Invalid --since: {}. Use e.g. 24h, 7d, 4w or 2024-01-31", value);
+    let split = value.len().checked_sub(1).ok_or_else(invalid)?;
+    let (amount, unit) = value.split_at(split);
+    let amount: i64 = amount.parse().map_err(|_| invalid())?;
+    let span = match unit {
+        "m" => Duration::minutes(amount),
+        "h" => Duration::hours(amount),
+        "d" => Duration::days(amount),
+        "w" => Duration::weeks(amount),
+        _ => return Err(invalid()),
+    };
+    Ok(now - span)
+}
+
+fn column_name(group_by: GroupBy) -> &'static str {
+    match group_by {
+        GroupBy::Model => "model",
+        GroupBy::Session => "session",
+        GroupBy::Day => "day",
+    }
+}
+
+fn print_table(report: &UsageReport, log: &UsageLog) {
+    println!(
+        "{} Usage since {} by {}",
+        "💰".cyan().bold(),
+        report.since.format("%Y-%m-%d %H:%M UTC"),
+        column_name(report.group_by)
+    );
+    println!("{}", "─".repeat(92));
+
+    if report.rows.is_empty() {
+        println!("No usage recorded in this period ({}).", log.dir().display());
+        return;
+    }
+
+    println!(
+        "{:<38} {:>7} {:>11} {:>11} {:>11} {:>10}",
+        column_name(report.group_by).to_uppercase(),
+        "CALLS",
+        "PROMPT",
+        "COMPLETION",
+        "TOTAL",
+        "COST"
+    );
+    for row in &report.rows {
+        print_row(row);
+    }
+    println!("{}", "─".repeat(92));
+    print_row(&report.total);
+
+    if report.total.unpriced_requests > 0 {
+        println!();
+        println!(
+            "{} {} call(s) had no reported cost and are counted as $0",
+            "⚠️".yellow(),
+            report.total.unpriced_requests
+        );
+    }
+}
+
+fn print_row(row: &UsageSummary) {
+    println!(
+        "{:<38} {:>7} {:>11} {:>11} {:>11} {:>10}",
+        truncate(&row.key, 38),
+        row.requests,
+        row.prompt_tokens,
+        row.completion_tokens,
+        row.total_tokens,
+        format!("${:.4}", row.cost_usd)
+    );
+}
+
+fn to_csv(report: &UsageReport) -> String {
+    let mut csv = format!(
+        "{},calls,prompt_tokens,completion_tokens,total_tokens,cost_usd,unpriced_calls\n",
+        column_name(report.group_by)
+    );
+    for row in report.rows.iter().chain(std::iter::once(&report.total)) {
+        csv.push_str(&format!(
+            "{},{},{},{},{},{:.6},{}\n",
+            csv_field(&row.key),
+            row.requests,
+            row.prompt_tokens,
+            row.completion_tokens,
+            row.total_tokens,
+            row.cost_usd,
+            row.unpriced_requests
+        ));
+    }
+    csv
+}
+
+/// Quote a field per RFC 4180 when it needs it
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_since() {
+        let now = Utc::now();
+        assert_eq!(parse_since("7d", now).unwrap(), now - Duration::days(7));
+        assert_eq!(parse_since("24h", now).unwrap(), now - Duration::hours(24));
+        assert_eq!(
+            parse_since("2024-01-31", now).unwrap().to_rfc3339(),
+            "2024-01-31T00:00:00+00:00"
+        );
+        assert!(parse_since("7y", now).is_err());
+        assert!(parse_since("", now).is_err());
+    }
+
+    #[test]
+    fn test_resolve_format() {
+        assert_eq!(resolve_format("table", Some(&PathBuf::from("q3.csv"))).unwrap(), "csv");
+        assert_eq!(resolve_format("json", Some(&PathBuf::from("q3.txt"))).unwrap(), "json");
+        assert!(resolve_format("table", Some(&PathBuf::from("q3.txt"))).is_err());
+        assert!(resolve_format("xml", None).is_err());
+    }
+
+    #[test]
+    fn test_format_ms() {
+        assert_eq!(format_ms(840), "840ms");
+        assert_eq!(format_ms(2350), "2.4s");
+    }
+
+    #[test]
+    fn test_csv_quoting() {
+        assert_eq!(csv_field("gpt-4"), "gpt-4");
+        assert_eq!(csv_field("a,\"b\""), "\"a,\"\"b\"\"\"");
+    }
+}
@@ -0,0 +1,102 @@
+//! Watch command
+//!
+//! `jamey watch <dir>` indexes a project into memory and re-ingests files as
+//! they change, so `jamey ask --context project` can answer questions about
+//! the current code and recent edits without re-reading the tree.
+
+use anyhow::{Context, Result};
+use colored::*;
+use jamey_runtime::project::{ChangeKind, WatchOptions, WatchUpdate};
+use jamey_runtime::{Runtime, RuntimeConfig};
+use std::path::PathBuf;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+/// Run watch command
+pub async fn run_watch(dir: PathBuf, ignore: Vec<String>, debounce_ms: u64) -> Result<()> {
+    if !dir.is_dir() {
+        return Err(anyhow::anyhow!("Not a directory: {}", dir.display()));
+    }
+    println!("{} Watching {}", "👀".cyan().bold(), dir.display());
+    if !ignore.is_empty() {
+        println!("  Ignoring: {}", ignore.join(", "));
+    }
+
+    let config = RuntimeConfig::from_env()
+        .map_err(|e| anyhow::anyhow!("Failed to load runtime config: {}", e))?;
+    let runtime = Runtime::new(config).await
+        .context("Failed to initialize runtime for watch mode")?;
+
+    let options = WatchOptions {
+        debounce: Duration::from_millis(debounce_ms),
+        ignore,
+        ..Default::default()
+    };
+    println!("{} Indexing project files...", "⏳".yellow());
+
+    let (tx, mut updates) = mpsc::unbounded_channel();
+    let shutdown = runtime.state().shutdown_signal.subscribe();
+    let watch = runtime.state().watch_project(&dir, &options, tx, shutdown);
+    tokio::pin!(watch);
+
+    let mut interrupted = false;
+    let result = loop {
+        tokio::select! {
+            result = &mut watch => break result,
+            Some(update) = updates.recv() => print_update(update),
+            _ = tokio::signal::ctrl_c(), if !interrupted => {
+                interrupted = true;
+                println!();
+                println!("{} Stopping watcher...", "🛑".cyan());
+                runtime.shutdown().await;
+            }
+        }
+    };
+    // Report anything sent just before the watcher returned
+    while let Ok(update) = updates.try_recv() {
+        print_update(update);
+    }
+    runtime.shutdown().await;
+
+    let project = result.with_context(|| format!("Watching {} failed", dir.display()))?;
+    println!(
+        "{} Stopped; {} files indexed in '{}'",
+        "✅".green(),
+        project.files.len(),
+        project.namespace
+    );
+    Ok(())
+}
+
+fn print_update(update: WatchUpdate) {
+    match update {
+        WatchUpdate::Ready { namespace, session_id, indexed, changed } => {
+            println!(
+                "{} {} files indexed in '{}' ({} updated since last run)",
+                "✅".green(),
+                indexed,
+                namespace,
+                changed
+            );
+            println!("{} Project session: {}", "📝".blue(), session_id);
+            println!(
+                "{} Ask about it with: {}",
+                "💡".yellow(),
+                "jamey ask --context project \"what changed?\"".bold()
+            );
+            println!("{}", "Watching for changes. Press Ctrl+C to stop.".dimmed());
+        }
+        WatchUpdate::Changed(change) => {
+            let time = change.at.with_timezone(&chrono::Local).format("%H:%M:%S");
+            let (icon, detail) = match change.kind {
+                ChangeKind::Added => ("➕".green(), format!("{} chunks", change.chunks)),
+                ChangeKind::Modified => ("✏️".yellow(), format!("{} chunks", change.chunks)),
+                ChangeKind::Removed => ("➖".red(), "dropped".to_string()),
+            };
+            println!("{} {} {} {}", time.to_string().dimmed(), icon, change.path, detail.dimmed());
+        }
+        WatchUpdate::Skipped { path, reason } => {
+            println!("{} {} — skipped: {}", "⏭️".dimmed(), path, reason.dimmed());
+        }
+    }
+}
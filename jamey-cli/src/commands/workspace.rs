@@ -0,0 +1,118 @@
+//! Workspace commands
+//!
+//! List, inspect, create, change and delete the workspaces `jamey chat
+//! --workspace` attaches a session to. Like the persona commands these work
+//! on the workspace directory directly, so the runtime does not need to be up.
+
+use anyhow::Result;
+use colored::*;
+use crate::WorkspaceAction;
+use jamey_runtime::config::RuntimeConfig;
+use jamey_runtime::workspace::{Workspace, WorkspaceError, WorkspaceStore};
+use std::path::PathBuf;
+
+/// Run workspace action
+pub async fn run_workspace_action(action: WorkspaceAction) -> Result<()> {
+    let config = RuntimeConfig::from_env()
+        .map_err(|e| anyhow::anyhow!("Failed to load runtime config: {}", e))?;
+    let store = WorkspaceStore::new(&config.workspace_dir);
+
+    match action {
+        WorkspaceAction::List => list_workspaces(&store).await,
+        WorkspaceAction::Show { name } => show_workspace(&store, &name).await,
+        WorkspaceAction::Set { name, root, ignore, namespace, tools } => {
+            let root = root.map(|root| super::init::expand_home(&root));
+            let changes = Changes { root, ignore, namespace, tools };
+            set_workspace(&store, &name, changes).await
+        }
+        WorkspaceAction::Delete { name } => {
+            store.delete(&name).await?;
+            println!("{} Deleted workspace {}", "✓".green(), name.bold());
+            Ok(())
+        }
+    }
+}
+
+async fn list_workspaces(store: &WorkspaceStore) -> Result<()> {
+    let workspaces = store.list().await?;
+
+    println!("{} Workspaces ({})", "📁".cyan().bold(), store.dir().display());
+    println!("{}", "─".repeat(80));
+    if workspaces.is_empty() {
+        println!("No workspaces yet. Create one with {}", "jamey workspace set <name> --root <dir>".yellow());
+        return Ok(());
+    }
+    println!("{:<16} {:<24} {}", "NAME".bold(), "NAMESPACE".bold(), "ROOT".bold());
+    for workspace in &workspaces {
+        println!(
+            "{:<16} {:<24} {}",
+            workspace.name.yellow(),
+            workspace.namespace,
+            workspace.root.display()
+        );
+    }
+
+    println!();
+    println!("{} Work in one: {}", "💡".yellow(), "jamey chat --workspace <name>".bold());
+    Ok(())
+}
+
+async fn show_workspace(store: &WorkspaceStore, name: &str) -> Result<()> {
+    let workspace = store.get(name).await?;
+
+    println!("{} {}", "📁".cyan().bold(), workspace.name.bold());
+    println!("  Root: {}", workspace.root.display());
+    println!("  Memory namespace: {}", workspace.namespace);
+    if workspace.ignore.is_empty() {
+        println!("  Ignored: -");
+    } else {
+        println!("  Ignored: {}", workspace.ignore.join(", "));
+    }
+    if workspace.tools.is_empty() {
+        println!("  Tools: all the session allows");
+    } else {
+        println!("  Tools: {}", workspace.tools.join(", "));
+    }
+    Ok(())
+}
+
+/// Fields given to `jamey workspace set`; unset ones keep their value
+struct Changes {
+    root: Option<PathBuf>,
+    ignore: Option<Vec<String>>,
+    namespace: Option<String>,
+    tools: Option<Vec<String>>,
+}
+
+async fn set_workspace(store: &WorkspaceStore, name: &str, changes: Changes) -> Result<()> {
+    let (mut workspace, created) = match (store.get(name).await, &changes.root) {
+        (Ok(existing), Some(root)) => {
+            // A new root brings the namespace `jamey watch` uses for it
+            let mut workspace = Workspace::open(name, root).await?;
+            workspace.ignore = existing.ignore;
+            workspace.tools = existing.tools;
+            (workspace, false)
+        }
+        (Ok(existing), None) => (existing, false),
+        (Err(WorkspaceError::NotFound(_)), Some(root)) => (Workspace::open(name, root).await?, true),
+        (Err(WorkspaceError::NotFound(_)), None) => {
+            return Err(anyhow::anyhow!("A new workspace needs a project root; pass --root"));
+        }
+        (Err(e), _) => return Err(e.into()),
+    };
+
+    if let Some(ignore) = changes.ignore {
+        workspace.ignore = ignore.into_iter().filter(|i| !i.trim().is_empty()).collect();
+    }
+    if let Some(namespace) = changes.namespace.filter(|n| !n.trim().is_empty()) {
+        workspace.namespace = namespace;
+    }
+    if let Some(tools) = changes.tools {
+        workspace.tools = tools.into_iter().filter(|t| !t.trim().is_empty()).collect();
+    }
+    store.save(&workspace).await?;
+
+    let verb = if created { "Created" } else { "Updated" };
+    println!("{} {} workspace {} at {}", "✓".green(), verb, workspace.name.bold(), workspace.root.display());
+    Ok(())
+}
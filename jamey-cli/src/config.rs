@@ -7,10 +7,70 @@
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 use dirs::home_dir;
+use jamey_core::secrets::SecretManager;
+use jamey_core::secure_logging::Masked;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// A named target environment (e.g. local, staging, prod)
+///
+/// Unset fields fall back to the top-level settings. `env` holds
+/// non-secret variables exported before the runtime config is read;
+/// `secrets` only lists names, values live in the secret backend.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Profile {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_model: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub runtime_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_seconds: Option<u64>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub secrets: Vec<String>,
+}
+
+impl Profile {
+    /// Keychain service holding this profile's secrets
+    fn secret_service(name: &str) -> String {
+        format!("jamey-profile-{}", name)
+    }
+
+    pub fn secret_manager(name: &str) -> Result<SecretManager> {
+        SecretManager::with_backend(Self::secret_service(name), jamey_core::backend_from_env()?)
+            .with_context(|| format!("Failed to open secret store for profile '{}'", name))
+    }
+}
+
+/// Variables that must go through `config profile secret` rather than
+/// plain `env`, so they never land in the config file
+pub fn is_secret_key(key: &str) -> bool {
+    let key = key.to_uppercase();
+    key.ends_with("_KEY")
+        || key == "API_KEY"
+        || key.ends_with("_TOKEN")
+        || key.ends_with("_SECRET")
+        || key.contains("PASSWORD")
+        || key.contains("PASSPHRASE")
+}
+
+/// Profile names become keychain service names, so keep them simple
+pub fn validate_profile_name(name: &str) -> Result<()> {
+    if name.is_empty()
+        || name.len() > 32
+        || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(anyhow::anyhow!(
+            "Invalid profile name '{}': use up to 32 letters, digits, '-' or '_'",
+            name
+        ));
+    }
+    Ok(())
+}
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct CliConfig {
     pub default_model: String,
     #[serde(skip_serializing)] // Never serialize API keys to disk
@@ -18,6 +78,31 @@ pub struct CliConfig {
     pub runtime_url: String,
     pub timeout_seconds: u64,
     pub verbose: bool,
+    /// Language of CLI output, such as `es`; `JAMEY_LANGUAGE` overrides it
+    /// and the system locale is used when neither is set
+    pub language: Option<String>,
+    /// Profile used when `--profile` / `JAMEY_PROFILE` is not given
+    pub active_profile: Option<String>,
+    pub profiles: BTreeMap<String, Profile>,
+    /// Profile applied to this config by `load_with_profile`
+    #[serde(skip)]
+    pub selected_profile: Option<String>,
+}
+
+impl std::fmt::Debug for CliConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CliConfig")
+            .field("default_model", &self.default_model)
+            .field("api_key", &Masked::opt(self.api_key.as_deref()))
+            .field("runtime_url", &self.runtime_url)
+            .field("timeout_seconds", &self.timeout_seconds)
+            .field("verbose", &self.verbose)
+            .field("language", &self.language)
+            .field("active_profile", &self.active_profile)
+            .field("profiles", &self.profiles)
+            .field("selected_profile", &self.selected_profile)
+            .finish()
+    }
 }
 
 impl Default for CliConfig {
@@ -28,6 +113,10 @@ impl Default for CliConfig {
             runtime_url: "http://localhost:3000".to_string(),
             timeout_seconds: 30,
             verbose: false,
+            language: None,
+            active_profile: None,
+            profiles: BTreeMap::new(),
+            selected_profile: None,
         }
     }
 }
@@ -52,12 +141,15 @@ impl CliConfig {
             config.runtime_url = file_config.runtime_url;
             config.timeout_seconds = file_config.timeout_seconds;
             config.verbose = file_config.verbose;
+            config.language = file_config.language;
+            config.active_profile = file_config.active_profile;
+            config.profiles = file_config.profiles;
         }
         
         // ALWAYS load API key from environment variable (never from file)
         config.api_key = std::env::var("JAMEY_API_KEY")
             .map(|key| {
-                log::info!("Loaded JAMEY_API_KEY from environment");
+                tracing::info!("Loaded JAMEY_API_KEY from environment");
                 key
             })
             .ok()
@@ -67,7 +159,7 @@ impl CliConfig {
         if config.api_key.is_none() {
             config.api_key = std::env::var("OPENROUTER_API_KEY")
                 .map(|key| {
-                    log::info!("Loaded OPENROUTER_API_KEY from environment");
+                    tracing::info!("Loaded OPENROUTER_API_KEY from environment");
                     key
                 })
                 .ok()
@@ -77,6 +169,57 @@ impl CliConfig {
         Ok(config)
     }
     
+    /// Load configuration with a profile's overrides applied
+    /// 
+    /// `name` (from `--profile` or `JAMEY_PROFILE`) wins over the file's
+    /// `active_profile`; with neither, this is the same as `load()`.
+    pub fn load_with_profile(name: Option<&str>) -> Result<Self> {
+        let mut config = Self::load()?;
+        let Some(name) = name.map(str::to_string).or_else(|| config.active_profile.clone()) else {
+            return Ok(config);
+        };
+        
+        let profile = config.profiles.get(&name).cloned().ok_or_else(|| {
+            anyhow::anyhow!("Unknown profile '{}'. See `jamey system config profile list`", name)
+        })?;
+        if let Some(model) = profile.default_model {
+            config.default_model = model;
+        }
+        if let Some(url) = profile.runtime_url {
+            config.runtime_url = url;
+        }
+        if let Some(timeout) = profile.timeout_seconds {
+            config.timeout_seconds = timeout;
+        }
+        config.selected_profile = Some(name);
+        Ok(config)
+    }
+    
+    /// Export the selected profile's environment and secrets so the
+    /// runtime configuration (read from the environment) picks them up
+    /// 
+    /// Profile values override variables already set in the shell or `.env`.
+    pub fn apply_profile_env(&self) -> Result<()> {
+        let Some(ref name) = self.selected_profile else {
+            return Ok(());
+        };
+        let profile = &self.profiles[name];
+        
+        for (key, value) in &profile.env {
+            std::env::set_var(key, value);
+        }
+        if !profile.secrets.is_empty() {
+            let secrets = Profile::secret_manager(name)?;
+            for key in &profile.secrets {
+                let value = secrets.get_secret(key)
+                    .with_context(|| format!("Secret {} for profile '{}' is missing", key, name))?;
+                std::env::set_var(key, value);
+            }
+        }
+        tracing::info!("Using profile '{}'", name);
+        Ok(())
+    }
+    
     /// Save configuration to file (excluding secrets)
     /// 
     /// SECURITY: API keys are never written to disk
@@ -95,6 +238,9 @@ impl CliConfig {
             runtime_url: self.runtime_url.clone(),
             timeout_seconds: self.timeout_seconds,
             verbose: self.verbose,
+            language: self.language.clone(),
+            active_profile: self.active_profile.clone(),
+            profiles: self.profiles.clone(),
         };
         
         let content = toml::to_string_pretty(&file_config)
@@ -105,7 +251,7 @@ impl CliConfig {
         Ok(())
     }
     
-    fn config_file_path() -> PathBuf {
+    pub fn config_file_path() -> PathBuf {
         home_dir()
             .unwrap_or_else(|| PathBuf::from("."))
             .join(".config")
@@ -121,4 +267,59 @@ struct CliConfigFile {
     pub runtime_url: String,
     pub timeout_seconds: u64,
     pub verbose: bool,
-}
\ No newline at end of file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub active_profile: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub profiles: BTreeMap<String, Profile>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profiles_round_trip() {
+        let mut profiles = BTreeMap::new();
+        profiles.insert("staging".to_string(), Profile {
+            runtime_url: Some("https://staging.example.com".to_string()),
+            env: BTreeMap::from([("POSTGRES_HOST".to_string(), "db.staging".to_string())]),
+            secrets: vec!["OPENROUTER_API_KEY".to_string()],
+            ..Default::default()
+        });
+        let file = CliConfigFile {
+            default_model: "claude-3-sonnet".to_string(),
+            runtime_url: "http://localhost:3000".to_string(),
+            timeout_seconds: 30,
+            verbose: false,
+            language: None,
+            active_profile: Some("staging".to_string()),
+            profiles,
+        };
+
+        let toml = toml::to_string_pretty(&file).unwrap();
+        assert!(toml.contains("[profiles.staging]"));
+        let parsed: CliConfigFile = toml::from_str(&toml).unwrap();
+        assert_eq!(parsed.profiles["staging"], file.profiles["staging"]);
+
+        // Files written before profiles existed still load
+        let legacy = "default_model = \"m\"\nruntime_url = \"u\"\ntimeout_seconds = 5\nverbose = true\n";
+        let parsed: CliConfigFile = toml::from_str(legacy).unwrap();
+        assert!(parsed.profiles.is_empty());
+        assert!(parsed.active_profile.is_none());
+    }
+
+    #[test]
+    fn test_secret_keys_and_names() {
+        assert!(is_secret_key("OPENROUTER_API_KEY"));
+        assert!(is_secret_key("POSTGRES_PASSWORD"));
+        assert!(is_secret_key("github_token"));
+        assert!(!is_secret_key("POSTGRES_HOST"));
+        assert!(!is_secret_key("JAMEY_SECRETS_BACKEND"));
+
+        assert!(validate_profile_name("prod-eu_1").is_ok());
+        assert!(validate_profile_name("../prod").is_err());
+        assert!(validate_profile_name("").is_err());
+    }
+}
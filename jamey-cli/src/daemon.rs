@@ -0,0 +1,258 @@
+//! Background runtime management
+//!
+//! `jamey start --daemon` re-executes the binary detached from the terminal.
+//! The detached runtime records itself in `<config_dir>/jamey.pid` and logs to
+//! daily-rotated files under `<config_dir>/logs`; `jamey stop` finds it through
+//! the PID file.
+
+use anyhow::{Context, Result};
+use crate::config::CliConfig;
+use jamey_runtime::audit::AuditLayer;
+use std::fs::OpenOptions;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use sysinfo::{Pid, PidExt, ProcessExt, System, SystemExt};
+use tracing::Level;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::filter::LevelFilter;
+use tracing_subscriber::prelude::*;
+
+const LOG_FILE_PREFIX: &str = "jamey";
+const LOG_FILE_SUFFIX: &str = "log";
+
+/// Daily log files kept before the oldest is deleted
+const MAX_LOG_FILES: usize = 14;
+
+/// Directory holding `cli.toml`, the PID file and logs
+pub fn config_dir() -> PathBuf {
+    CliConfig::config_file_path()
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| PathBuf::from("."))
+}
+
+pub fn pid_file_path() -> PathBuf {
+    config_dir().join("jamey.pid")
+}
+
+pub fn log_dir() -> PathBuf {
+    config_dir().join("logs")
+}
+
+/// stdout/stderr of the detached process, which catches anything printed
+/// before file logging is set up (including panics)
+pub fn console_log_path() -> PathBuf {
+    log_dir().join("daemon.out")
+}
+
+/// Created by `jamey stop` where there is no SIGTERM to send
+#[cfg(not(unix))]
+pub fn stop_file_path() -> PathBuf {
+    config_dir().join("jamey.stop")
+}
+
+/// Send the detached runtime's logs to rotating files instead of stderr
+pub fn init_file_logging(level: Level) -> Result<()> {
+    let appender = RollingFileAppender::builder()
+        .rotation(Rotation::DAILY)
+        .filename_prefix(LOG_FILE_PREFIX)
+        .filename_suffix(LOG_FILE_SUFFIX)
+        .max_log_files(MAX_LOG_FILES)
+        .build(log_dir())
+        .context("Failed to open log directory")?;
+
+    let output = tracing_subscriber::fmt::layer()
+        .with_ansi(false)
+        .with_writer(appender)
+        .with_filter(LevelFilter::from_level(level));
+    let subscriber = tracing_subscriber::registry().with(output).with(AuditLayer);
+    tracing::subscriber::set_global_default(subscriber)?;
+    Ok(())
+}
+
+/// Most recent rotated log file (`jamey.YYYY-MM-DD.log`)
+pub fn latest_log_file() -> Option<PathBuf> {
+    let prefix = format!("{}.", LOG_FILE_PREFIX);
+    let suffix = format!(".{}", LOG_FILE_SUFFIX);
+    std::fs::read_dir(log_dir())
+        .ok()?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.starts_with(&prefix) && n.ends_with(&suffix))
+        })
+        // The date in the name sorts chronologically
+        .max()
+}
+
+/// PID file owned by the running runtime; removed again on drop
+pub struct PidFile {
+    path: PathBuf,
+    pid: u32,
+}
+
+impl PidFile {
+    /// Record this process, refusing if another live runtime holds the file
+    pub fn acquire(path: &Path) -> Result<Self> {
+        let pid = std::process::id();
+        if let Some(existing) = read_pid(path)? {
+            if existing != pid && is_runtime_process(existing) {
+                anyhow::bail!("Jamey is already running (pid {})", existing);
+            }
+        }
+
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, format!("{}\n", pid))
+            .with_context(|| format!("Failed to write PID file {}", path.display()))?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            pid,
+        })
+    }
+}
+
+impl Drop for PidFile {
+    fn drop(&mut self) {
+        // Leave it alone if a newer runtime has taken over
+        if matches!(read_pid(&self.path), Ok(Some(pid)) if pid == self.pid) {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+}
+
+/// PID recorded in the file, or `None` when there is no file
+pub fn read_pid(path: &Path) -> Result<Option<u32>> {
+    match std::fs::read_to_string(path) {
+        Ok(contents) => contents
+            .trim()
+            .parse()
+            .map(Some)
+            .with_context(|| format!("Corrupt PID file {}; delete it and retry", path.display())),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Whether `pid` is alive and is a jamey binary, so a PID reused by an
+/// unrelated process after a crash is never signalled
+pub fn is_runtime_process(pid: u32) -> bool {
+    let mut system = System::new();
+    let pid = Pid::from_u32(pid);
+    system.refresh_process(pid)
+        && system
+            .process(pid)
+            .is_some_and(|p| p.name().to_lowercase().contains("jamey"))
+}
+
+/// Ask the runtime to drain and exit
+pub fn request_stop(pid: u32) -> Result<()> {
+    #[cfg(unix)]
+    {
+        let mut system = System::new();
+        let pid = Pid::from_u32(pid);
+        system.refresh_process(pid);
+        let signalled = system
+            .process(pid)
+            .and_then(|p| p.kill_with(sysinfo::Signal::Term))
+            .unwrap_or(false);
+        if !signalled {
+            anyhow::bail!("Failed to send SIGTERM to pid {}", pid);
+        }
+        Ok(())
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = pid;
+        std::fs::write(stop_file_path(), b"")
+            .context("Failed to write stop request")?;
+        Ok(())
+    }
+}
+
+/// Kill without waiting for a drain
+pub fn force_kill(pid: u32) -> bool {
+    let mut system = System::new();
+    let pid = Pid::from_u32(pid);
+    system.refresh_process(pid);
+    system.process(pid).is_some_and(|p| p.kill())
+}
+
+/// Re-run this binary with `args`, detached from the terminal, with output
+/// appended to [`console_log_path`]
+pub fn spawn_detached(args: &[String]) -> Result<Child> {
+    let exe = std::env::current_exe().context("Cannot locate the jamey executable")?;
+    std::fs::create_dir_all(log_dir())?;
+    let console = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(console_log_path())?;
+
+    let mut command = Command::new(exe);
+    command
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(console.try_clone()?)
+        .stderr(console);
+
+    #[cfg(unix)]
+    {
+        // Own process group, so Ctrl+C in this terminal doesn't reach it
+        use std::os::unix::process::CommandExt;
+        command.process_group(0);
+    }
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        const DETACHED_PROCESS: u32 = 0x0000_0008;
+        const CREATE_NEW_PROCESS_GROUP: u32 = 0x0000_0200;
+        command.creation_flags(DETACHED_PROCESS | CREATE_NEW_PROCESS_GROUP);
+    }
+
+    command.spawn().context("Failed to spawn background runtime")
+}
+
+/// Last `lines` lines of `path`, for showing why a daemon died
+pub fn tail(path: &Path, lines: usize) -> Vec<String> {
+    let contents = std::fs::read_to_string(path).unwrap_or_default();
+    let all: Vec<&str> = contents.lines().collect();
+    all[all.len().saturating_sub(lines)..]
+        .iter()
+        .map(|l| l.to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_pid_file_lifecycle() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("jamey.pid");
+        assert_eq!(read_pid(&path).unwrap(), None);
+
+        {
+            let _pid_file = PidFile::acquire(&path).unwrap();
+            assert_eq!(read_pid(&path).unwrap(), Some(std::process::id()));
+            // Re-acquiring from the same process is allowed
+            let _again = PidFile::acquire(&path).unwrap();
+        }
+        assert!(!path.exists());
+
+        std::fs::write(&path, "not a pid").unwrap();
+        assert!(read_pid(&path).is_err());
+    }
+
+    #[test]
+    fn test_tail() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("daemon.out");
+        std::fs::write(&path, "a\nb\nc\n").unwrap();
+        assert_eq!(tail(&path, 2), vec!["b", "c"]);
+        assert!(tail(&dir.path().join("missing"), 2).is_empty());
+    }
+}
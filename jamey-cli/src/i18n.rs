@@ -0,0 +1,47 @@
+//! Localized CLI text
+//!
+//! Strings live in `locales/<language>.ftl`. The language is picked once,
+//! the first time a string is needed, from `JAMEY_LANGUAGE`, `language` in
+//! `cli.toml` or the system locale; see [`jamey_runtime::i18n`].
+
+use crate::config::CliConfig;
+use jamey_runtime::i18n::{self, Localizer};
+use std::sync::OnceLock;
+
+/// Languages the CLI has strings for; English must be complete
+pub const RESOURCES: &[(&str, &str)] = &[
+    ("en", include_str!("../locales/en.ftl")),
+    ("es", include_str!("../locales/es.ftl")),
+];
+
+pub fn localizer() -> &'static Localizer {
+    static LOCALIZER: OnceLock<Localizer> = OnceLock::new();
+    LOCALIZER.get_or_init(|| {
+        let configured = CliConfig::load().ok().and_then(|config| config.language);
+        Localizer::new(&i18n::requested_language(configured.as_deref()), RESOURCES)
+    })
+}
+
+/// A CLI string, with `"name" => value` arguments
+macro_rules! t {
+    ($($arg:tt)+) => {
+        jamey_runtime::tr!($crate::i18n::localizer(), $($arg)+)
+    };
+}
+pub(crate) use t;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_translations_are_complete() {
+        let english = i18n::message_ids(RESOURCES[0].1);
+        assert!(!english.is_empty());
+        for (language, source) in &RESOURCES[1..] {
+            let translated = i18n::message_ids(source);
+            let missing: Vec<_> = english.iter().filter(|id| !translated.contains(id)).collect();
+            assert!(missing.is_empty(), "{} lacks {:?}", language, missing);
+        }
+    }
+}
@@ -8,13 +8,22 @@ use colored::*;
 use anyhow::Result;
 use std::path::PathBuf;
 use tracing::{info, error, debug};
-use tracing_subscriber::FmtSubscriber;
+use tracing_subscriber::filter::LevelFilter;
+use tracing_subscriber::prelude::*;
 
 mod commands;
 mod config;
+mod daemon;
+mod i18n;
+mod microphone;
+mod render;
 mod utils;
 
 use commands::*;
+use i18n::t;
+use jamey_runtime::audit::AuditLayer;
+use jamey_runtime::guardrails::Strictness;
+use jamey_runtime::usage::GroupBy;
 
 #[derive(Parser)]
 #[command(name = "jamey")]
@@ -25,8 +34,8 @@ pub struct Cli {
     #[command(subcommand)]
     pub command: Commands,
 
-    /// Configuration file path
-    #[arg(short, long, global = true, default_value = "~/.config/jamey/config.toml")]
+    /// Runtime configuration file (TOML or YAML); environment variables override it
+    #[arg(short, long, global = true, env = "JAMEY_CONFIG", default_value = "~/.config/jamey/config.toml")]
     pub config: PathBuf,
 
     /// Enable debug logging
@@ -36,9 +45,13 @@ pub struct Cli {
     /// Quiet mode (minimal output)
     #[arg(short, long, global = true)]
     pub quiet: bool,
+
+    /// Named profile from the config file (local, staging, prod, ...)
+    #[arg(long, global = true, env = "JAMEY_PROFILE")]
+    pub profile: Option<String>,
 }
 
-#[derive(Subcommand)]
+#[derive(Debug, Subcommand)]
 pub enum Commands {
     /// Start interactive chat session
     Chat {
@@ -46,13 +59,136 @@ pub enum Commands {
         #[arg(short, long)]
         session: Option<String>,
         
-        /// Model to use for conversation
-        #[arg(short, long, default_value = "claude-3-sonnet")]
-        model: String,
+        /// Model to use for conversation; defaults to the persona's, then claude-3-sonnet
+        #[arg(short, long)]
+        model: Option<String>,
+
+        /// Persona to chat with (see `jamey persona list`)
+        #[arg(short, long)]
+        persona: Option<String>,
+
+        /// Workspace to work in (see `jamey workspace list`): file tools
+        /// resolve paths against its root and recall stays in its memories
+        #[arg(long)]
+        workspace: Option<String>,
         
         /// Enable verbose output
         #[arg(short, long)]
         verbose: bool,
+
+        /// Print replies as plain Markdown instead of rendering them
+        #[arg(long)]
+        raw: bool,
+
+        /// Read replies aloud (voice settings come from `[voice]` / `JAMEY_VOICE*`)
+        #[arg(long)]
+        speak: bool,
+
+        /// Talk instead of typing: Enter on an empty line starts recording,
+        /// Enter again sends (needs the `voice` build feature)
+        #[arg(long)]
+        voice: bool,
+
+        /// How strictly replies are held to the output guardrails: off,
+        /// lenient or strict; defaults to `guardrails.strictness`
+        #[arg(long)]
+        strictness: Option<Strictness>,
+    },
+    
+    /// Ask a single question and print the answer (piped stdin is added as context)
+    Ask {
+        /// Question or instruction; optional when input is piped
+        question: Option<String>,
+        
+        /// Model to use
+        #[arg(short, long, default_value = "claude-3-sonnet")]
+        model: String,
+        
+        /// Output format (text, json)
+        #[arg(short, long, default_value = "text")]
+        format: String,
+
+        /// Answer with extra context; `project` uses the project `jamey watch`
+        /// is keeping indexed for the current directory
+        #[arg(long)]
+        context: Option<String>,
+
+        /// File to attach (text, code, PDF or image); repeat for several
+        #[arg(long = "attach", value_name = "PATH")]
+        attach: Vec<PathBuf>,
+
+        /// Print the answer as plain Markdown instead of rendering it
+        #[arg(long)]
+        raw: bool,
+
+        /// Read the answer aloud once it is complete
+        #[arg(long)]
+        speak: bool,
+    },
+
+    /// Research a topic across the web and memory and print a cited report
+    Research {
+        /// Question or topic to research
+        topic: String,
+
+        /// Search queries to break the topic into (1-8)
+        #[arg(long, default_value = "4")]
+        queries: usize,
+
+        /// Don't store the report as a knowledge memory
+        #[arg(long)]
+        no_store: bool,
+
+        /// Output format (text, json)
+        #[arg(short, long, default_value = "text")]
+        format: String,
+    },
+
+    /// Show the latest briefing, or compose and deliver one now
+    Briefing {
+        /// Gather the configured sources and deliver a briefing now
+        #[arg(long)]
+        now: bool,
+    },
+
+    /// Run evaluation suites of prompts against the live pipeline
+    Eval {
+        #[command(subcommand)]
+        action: EvalAction,
+    },
+
+    /// Keep a project indexed in memory as its files change
+    Watch {
+        /// Project directory
+        #[arg(default_value = ".")]
+        dir: PathBuf,
+
+        /// Comma-separated directory or file names to skip, on top of hidden
+        /// entries and build output
+        #[arg(long, value_delimiter = ',')]
+        ignore: Vec<String>,
+
+        /// Milliseconds to wait for edits to settle before re-indexing
+        #[arg(long, default_value = "1500")]
+        debounce: u64,
+    },
+    
+    /// Manage personas: system prompt, tone, tools and model
+    Persona {
+        #[command(subcommand)]
+        action: PersonaAction,
+    },
+
+    /// Manage workspaces: project root, ignored names, memory namespace and tools
+    Workspace {
+        #[command(subcommand)]
+        action: WorkspaceAction,
+    },
+
+    /// Browse, export and resume saved conversations
+    Sessions {
+        #[command(subcommand)]
+        action: SessionsAction,
     },
     
     /// Manage system processes
@@ -76,19 +212,34 @@ pub enum Commands {
     /// Initialize Jamey configuration
     Init {
         /// Configuration directory
-        #[arg(short, long, default_value = "~/.config/jamey")]
+        #[arg(long, default_value = "~/.config/jamey")]
         dir: PathBuf,
         
         /// Force overwrite existing configuration
         #[arg(long)]
         force: bool,
+        
+        /// Write the template configuration without the interactive wizard
+        #[arg(long)]
+        defaults: bool,
+    },
+    
+    /// Generate shell completion scripts
+    Completions {
+        /// Target shell
+        #[arg(value_enum)]
+        shell: clap_complete::Shell,
     },
     
     /// Start Jamey runtime service
     Start {
         /// Run in background
-        #[arg(short, long)]
+        #[arg(long)]
         daemon: bool,
+
+        /// Set on the re-executed background process by --daemon
+        #[arg(long, hide = true)]
+        detached: bool,
         
         /// Port to listen on
         #[arg(short, long, default_value = "3000")]
@@ -104,17 +255,171 @@ pub enum Commands {
     
     /// Show system status and health
     Status {
-        /// Show detailed information
-        #[arg(short, long)]
+        /// Show detailed information, including every raw metric
+        #[arg(long)]
         detailed: bool,
         
         /// Output format (json, table, plain)
         #[arg(short, long, default_value = "table")]
         format: String,
+
+        /// Runtime metrics endpoint to query
+        #[arg(long, env = "JAMEY_METRICS_URL", default_value = "http://127.0.0.1:9090/metrics")]
+        metrics_url: String,
+    },
+
+    /// Report token and cost usage
+    Usage {
+        #[command(subcommand)]
+        action: Option<UsageAction>,
+
+        /// Period to report: 30m, 24h, 7d, 4w or a date (YYYY-MM-DD)
+        #[arg(long, default_value = "7d")]
+        since: String,
+
+        /// Group rows by model, session or day
+        #[arg(short, long, default_value = "model")]
+        group_by: GroupBy,
+
+        /// Output format (table, csv, json)
+        #[arg(short, long, default_value = "table")]
+        format: String,
+
+        /// Write the report to a file instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    
+    /// Delete everything stored about a user, a session or a pattern
+    #[command(group(clap::ArgGroup::new("target").required(true).args(["user", "session", "pattern"])))]
+    Forget {
+        /// User name, as used for feedback and session attribution
+        #[arg(long)]
+        user: Option<String>,
+
+        /// Session ID or unique prefix
+        #[arg(long)]
+        session: Option<String>,
+
+        /// Regular expression matched against memories and transcripts
+        #[arg(long)]
+        pattern: Option<String>,
+
+        /// Report what would be deleted without deleting it
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Skip confirmation
+        #[arg(short, long)]
+        force: bool,
+
+        /// Output format (table, json)
+        #[arg(long, default_value = "table")]
+        format: String,
+    },
+
+    /// Run and inspect connectors directly
+    Tool {
+        #[command(subcommand)]
+        action: ToolAction,
+    },
+    
+    /// Review connector calls waiting for approval
+    Approvals {
+        #[command(subcommand)]
+        action: ApprovalsAction,
+    },
+    
+    /// Sign connectors in with OAuth
+    Auth {
+        #[command(subcommand)]
+        action: AuthAction,
+    },
+
+    /// Check the signed audit log
+    Audit {
+        #[command(subcommand)]
+        action: AuditAction,
+    },
+
+    /// Inspect and cancel a running runtime's in-flight work
+    Tasks {
+        #[command(subcommand)]
+        action: TasksAction,
+    },
+
+    /// Read recorded prompts and completions (`prompt_trace.enabled`)
+    Traces {
+        #[command(subcommand)]
+        action: TracesAction,
+    },
+
+    /// Time memory, cache and context hot paths
+    #[command(hide = true)]
+    Bench {
+        /// Iterations per benchmark
+        #[arg(short = 'n', long, default_value = "200")]
+        iterations: usize,
+
+        /// Only run benchmarks whose name contains this
+        filter: Option<String>,
+
+        /// Output format (table, json)
+        #[arg(short, long, default_value = "table")]
+        format: String,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum EvalAction {
+    /// Run a suite and report its pass rate
+    Run {
+        /// Suite file (YAML)
+        suite: PathBuf,
+
+        /// Model to answer with; defaults to the configured one
+        #[arg(short, long)]
+        model: Option<String>,
+
+        /// Only run cases whose name contains this
+        #[arg(long)]
+        case: Option<String>,
+
+        /// Output format (text, json)
+        #[arg(short, long, default_value = "text")]
+        format: String,
+
+        /// Fail when fewer than this share of cases pass (0 to 1)
+        #[arg(long)]
+        min_pass_rate: Option<f64>,
+    },
+
+    /// Check a suite file without running it
+    Validate {
+        /// Suite file (YAML)
+        suite: PathBuf,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum UsageAction {
+    /// Tool usage, failure rates, latency percentiles and session topics
+    Insights {
+        /// Period to report: 30m, 24h, 7d, 4w or a date (YYYY-MM-DD)
+        #[arg(long, default_value = "7d")]
+        since: String,
+
+        /// Also group sessions into topics (one embedding call per session)
+        #[arg(long)]
+        topics: bool,
+
+        /// Output format (table, json)
+        #[arg(short, long, default_value = "table")]
+        format: String,
     },
 }
 
-#[derive(Subcommand)]
+#[derive(Debug, Subcommand)]
 pub enum ProcessAction {
     /// List all running processes
     List {
@@ -123,7 +428,7 @@ pub enum ProcessAction {
         filter: Option<String>,
         
         /// Show detailed information
-        #[arg(short, long)]
+        #[arg(long)]
         detailed: bool,
     },
     
@@ -146,7 +451,7 @@ pub enum ProcessAction {
     },
 }
 
-#[derive(Subcommand)]
+#[derive(Debug, Subcommand)]
 pub enum MemoryAction {
     /// Search memory entries
     Search {
@@ -160,17 +465,42 @@ pub enum MemoryAction {
         /// Memory type filter
         #[arg(short, long)]
         type_filter: Option<String>,
+
+        /// Only search memories in this namespace
+        #[arg(short, long)]
+        namespace: Option<String>,
+
+        /// Show each result's similarity, recency, importance and frequency
+        /// and the formula that ranked them
+        #[arg(long)]
+        explain: bool,
     },
     
     /// List recent memories
     List {
         /// Number of recent entries to show
-        #[arg(short, long, default_value = "20")]
+        #[arg(long, default_value = "20")]
         count: usize,
         
         /// Show detailed information
-        #[arg(short, long)]
+        #[arg(long)]
         detailed: bool,
+
+        /// Only list pinned memories
+        #[arg(long)]
+        pinned: bool,
+    },
+
+    /// Pin a memory so every chat turn includes it and maintenance skips it
+    Pin {
+        /// Memory ID
+        id: String,
+    },
+
+    /// Unpin a memory
+    Unpin {
+        /// Memory ID
+        id: String,
     },
     
     /// Delete memory entries
@@ -194,14 +524,147 @@ pub enum MemoryAction {
         #[arg(short, long, default_value = "json")]
         format: String,
     },
+    
+    /// Ingest documents from a file, directory, glob or URL
+    Ingest {
+        /// Path, glob pattern (quote it) or http(s) URL
+        target: String,
+        
+        /// Chunk size in characters
+        #[arg(long, default_value = "1500")]
+        chunk_size: usize,
+        
+        /// Characters shared between consecutive chunks
+        #[arg(long, default_value = "200")]
+        overlap: usize,
+        
+        /// Memory type for the created entries
+        #[arg(short = 't', long = "type", default_value = "knowledge")]
+        memory_type: String,
+        
+        /// Namespace recorded on each memory
+        #[arg(short, long)]
+        namespace: Option<String>,
+        
+        /// Comma-separated tags recorded on each memory
+        #[arg(long, value_delimiter = ',')]
+        tags: Vec<String>,
+        
+        /// Only chunk and estimate cost; store nothing
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Show counts by type, namespace and embedding model, and table size
+    Stats {
+        /// Output format (table, json)
+        #[arg(short, long, default_value = "table")]
+        format: String,
+    },
+
+    /// Delete near-identical memories, keeping the oldest copy
+    Dedupe {
+        /// Cosine similarity at or above which memories are duplicates
+        #[arg(long, default_value = "0.97")]
+        threshold: f64,
+
+        /// List duplicates without deleting anything
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Delete without prompting
+        #[arg(short, long)]
+        force: bool,
+    },
+
+    /// Merge clusters of closely related memories into one memory each
+    Consolidate {
+        /// Cosine similarity at or above which memories are merged
+        #[arg(long, default_value = "0.90")]
+        threshold: f64,
+
+        /// List clusters without calling the model or changing anything
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Merge without prompting
+        #[arg(short, long)]
+        force: bool,
+    },
+
+    /// Recompute embeddings with a different embedding model
+    Reembed {
+        /// Embedding model, e.g. openai/text-embedding-3-small
+        #[arg(short, long)]
+        model: String,
+
+        /// Count and estimate cost without re-embedding
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Re-embed without prompting
+        #[arg(short, long)]
+        force: bool,
+    },
+
+    /// Move memories nobody has read for a while to compressed cold storage
+    Archive {
+        /// Days unread before a memory is archived; defaults to
+        /// memory.cold_after_days
+        #[arg(long)]
+        days: Option<u32>,
+
+        /// Count what would be archived without moving anything
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Archive without prompting
+        #[arg(short, long)]
+        force: bool,
+    },
+
+    /// Convert stored embeddings to the precision configured for their type
+    /// under [memory.quantization]
+    Requantize {
+        /// Convert without prompting
+        #[arg(short, long)]
+        force: bool,
+    },
+
+    /// List the partitions of a namespace-partitioned memory table
+    Partitions {
+        /// Output format (table, json)
+        #[arg(short, long, default_value = "table")]
+        format: String,
+    },
+
+    /// Partition the memory table by namespace, or move one namespace into
+    /// a partition of its own
+    Partition {
+        /// Namespace to move; without it the table itself is partitioned
+        #[arg(short, long)]
+        namespace: Option<String>,
+
+        /// Proceed without prompting
+        #[arg(short, long)]
+        force: bool,
+    },
+
+    /// Rebuild the vector index of one namespace's partition, or of the
+    /// whole table
+    Reindex {
+        /// Namespace whose partition to reindex
+        #[arg(short, long)]
+        namespace: Option<String>,
+    },
 }
 
-#[derive(Subcommand)]
+#[derive(Debug, Subcommand)]
 pub enum SystemAction {
     /// Show system information
     Info {
         /// Include hardware details
-        #[arg(short, long)]
+        #[arg(long)]
         hardware: bool,
         
         /// Include network information
@@ -212,7 +675,7 @@ pub enum SystemAction {
     /// Check system health
     Health {
         /// Run comprehensive health check
-        #[arg(short, long)]
+        #[arg(long)]
         comprehensive: bool,
     },
     
@@ -233,15 +696,19 @@ pub enum SystemAction {
         follow: bool,
         
         /// Log level filter
-        #[arg(short, long)]
+        #[arg(long)]
         level: Option<String>,
     },
 }
 
-#[derive(Subcommand)]
+#[derive(Debug, Subcommand)]
 pub enum ConfigAction {
     /// Show current configuration
-    Show,
+    Show {
+        /// Also show the runtime settings and where each value came from
+        #[arg(long)]
+        origin: bool,
+    },
     
     /// Set configuration value
     Set {
@@ -263,11 +730,403 @@ pub enum ConfigAction {
         #[arg(short, long)]
         force: bool,
     },
+    
+    /// Check a runtime config file without starting the runtime
+    Validate {
+        /// TOML or YAML config file
+        file: PathBuf,
+    },
+    
+    /// Print the JSON Schema of runtime config files
+    Schema,
+    
+    /// Manage named profiles
+    Profile {
+        #[command(subcommand)]
+        action: ProfileAction,
+    },
 }
 
-#[tokio::main]
-async fn main() -> Result<()> {
-    let cli = Cli::parse();
+#[derive(Debug, Subcommand)]
+pub enum ProfileAction {
+    /// Add or update a profile
+    Add {
+        /// Profile name
+        name: String,
+        
+        /// Default model for this profile
+        #[arg(short, long)]
+        model: Option<String>,
+        
+        /// Runtime URL for this profile
+        #[arg(short, long)]
+        runtime_url: Option<String>,
+        
+        /// Request timeout in seconds
+        #[arg(short, long)]
+        timeout: Option<u64>,
+        
+        /// Non-secret environment variable as KEY=VALUE (repeatable)
+        #[arg(short, long = "env", value_parser = utils::parse_key_value)]
+        env: Vec<(String, String)>,
+    },
+    
+    /// List profiles
+    List,
+    
+    /// Make a profile the default
+    Use {
+        /// Profile name
+        name: String,
+    },
+    
+    /// Remove a profile and its secrets
+    Remove {
+        /// Profile name
+        name: String,
+        
+        /// Skip confirmation
+        #[arg(short, long)]
+        force: bool,
+    },
+    
+    /// Store a secret for a profile (prompts for the value)
+    Secret {
+        /// Profile name
+        name: String,
+        
+        /// Environment variable the secret is exported as, e.g. OPENROUTER_API_KEY
+        key: String,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ApprovalsAction {
+    /// List approval requests
+    List {
+        /// Include decided and expired requests
+        #[arg(short, long)]
+        all: bool,
+        
+        /// Keep running and announce new requests as they arrive
+        #[arg(short, long)]
+        watch: bool,
+    },
+    
+    /// Approve a pending request
+    Approve {
+        /// Request ID or unique prefix
+        id: String,
+        
+        /// Note recorded with the decision
+        #[arg(short, long)]
+        note: Option<String>,
+    },
+    
+    /// Deny a pending request
+    Deny {
+        /// Request ID or unique prefix
+        id: String,
+        
+        /// Reason passed back to the model
+        #[arg(short, long)]
+        reason: Option<String>,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ToolAction {
+    /// List connectors with their actions and capability levels
+    List {
+        /// Show credentials and safety checks
+        #[arg(long)]
+        detailed: bool,
+        
+        /// Output format (table, json)
+        #[arg(short, long, default_value = "table")]
+        format: String,
+    },
+    
+    /// Run a connector action
+    Run {
+        /// Connector ID (see `jamey tool list`)
+        connector: String,
+        
+        /// Action to perform
+        action: String,
+        
+        /// Action parameter as key=value (repeatable)
+        #[arg(short, long = "param", value_parser = utils::parse_key_value)]
+        params: Vec<(String, String)>,
+        
+        /// Confirm actions that change the system
+        #[arg(short, long)]
+        yes: bool,
+        
+        /// Print the raw connector result as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum SessionsAction {
+    /// List saved sessions, most recent first
+    List {
+        /// Maximum number of sessions to show
+        #[arg(short, long, default_value = "20")]
+        limit: usize,
+    },
+    
+    /// Show a session transcript
+    Show {
+        /// Session ID or unique prefix
+        id: String,
+    },
+    
+    /// Delete a saved session
+    Delete {
+        /// Session ID or unique prefix
+        id: String,
+        
+        /// Skip confirmation
+        #[arg(short, long)]
+        force: bool,
+    },
+    
+    /// Export a transcript
+    Export {
+        /// Session ID or unique prefix
+        id: String,
+        
+        /// Export format (markdown, json)
+        #[arg(short, long, default_value = "markdown")]
+        format: String,
+        
+        /// Output file (stdout if omitted)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    
+    /// Continue a saved session in interactive chat
+    Resume {
+        /// Session ID or unique prefix
+        id: String,
+        
+        /// Model to use for conversation; defaults to the persona's, then claude-3-sonnet
+        #[arg(short, long)]
+        model: Option<String>,
+
+        /// Persona to continue with
+        #[arg(short, long)]
+        persona: Option<String>,
+        
+        /// Enable verbose output
+        #[arg(short, long)]
+        verbose: bool,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum PersonaAction {
+    /// List saved personas
+    List,
+
+    /// Show a persona's prompt, tone, tools and model
+    Show {
+        /// Persona name
+        name: String,
+    },
+
+    /// Create a persona, or change the given fields of an existing one
+    Set {
+        /// Persona name (letters, digits, `-` and `_`)
+        name: String,
+
+        /// System prompt
+        #[arg(long, conflicts_with = "prompt_file")]
+        prompt: Option<String>,
+
+        /// Read the system prompt from a file
+        #[arg(long)]
+        prompt_file: Option<PathBuf>,
+
+        /// One-line description for `jamey persona list`
+        #[arg(long)]
+        description: Option<String>,
+
+        /// How replies should sound; an empty value clears it
+        #[arg(long)]
+        tone: Option<String>,
+
+        /// Comma-separated connector IDs the persona may use; empty allows all
+        #[arg(long, value_delimiter = ',')]
+        tools: Option<Vec<String>>,
+
+        /// Preferred model; an empty value clears it
+        #[arg(short, long)]
+        model: Option<String>,
+    },
+
+    /// Delete a saved persona
+    Delete {
+        /// Persona name
+        name: String,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum WorkspaceAction {
+    /// List saved workspaces
+    List,
+
+    /// Show a workspace's root, ignored names, namespace and tools
+    Show {
+        /// Workspace name
+        name: String,
+    },
+
+    /// Create a workspace, or change the given fields of an existing one
+    Set {
+        /// Workspace name (letters, digits, `-` and `_`)
+        name: String,
+
+        /// Project root directory; required for a new workspace
+        #[arg(long)]
+        root: Option<PathBuf>,
+
+        /// Comma-separated directory or file names file tools may not touch
+        #[arg(long, value_delimiter = ',')]
+        ignore: Option<Vec<String>>,
+
+        /// Memory namespace recall is scoped to; defaults to the one
+        /// `jamey watch` uses for the root
+        #[arg(long)]
+        namespace: Option<String>,
+
+        /// Comma-separated connector IDs the workspace allows; empty allows all
+        #[arg(long, value_delimiter = ',')]
+        tools: Option<Vec<String>>,
+    },
+
+    /// Forget a saved workspace; its files and memories are kept
+    Delete {
+        /// Workspace name
+        name: String,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum AuditAction {
+    /// Validate the hash chain and every signature
+    Verify {
+        /// Expected public key (hex); defaults to the one in the secret store
+        #[arg(long)]
+        public_key: Option<String>,
+
+        /// Log directory; defaults to the runtime's `audit_dir`
+        #[arg(long)]
+        dir: Option<PathBuf>,
+
+        /// Output format (table, json)
+        #[arg(short, long, default_value = "table")]
+        format: String,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum TasksAction {
+    /// List running turns, model calls, tools and jobs with their ages
+    List {
+        /// Runtime web address (`api.http_port`)
+        #[arg(long, env = "JAMEY_URL", default_value = "http://127.0.0.1:3000")]
+        url: String,
+
+        /// `security.api_key`, when the runtime requires one
+        #[arg(long, env = "API_KEY", hide_env_values = true)]
+        token: Option<String>,
+
+        /// Output format (table, json)
+        #[arg(short, long, default_value = "table")]
+        format: String,
+    },
+
+    /// Cancel a task, and anything running under it
+    Cancel {
+        /// Task ID, as listed
+        id: String,
+
+        /// Runtime web address (`api.http_port`)
+        #[arg(long, env = "JAMEY_URL", default_value = "http://127.0.0.1:3000")]
+        url: String,
+
+        /// `security.api_key`, when the runtime requires one
+        #[arg(long, env = "API_KEY", hide_env_values = true)]
+        token: Option<String>,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum TracesAction {
+    /// List traced model calls, newest first
+    List {
+        /// Period to list: 30m, 24h, 7d, 4w or a date (YYYY-MM-DD)
+        #[arg(long, default_value = "24h")]
+        since: String,
+
+        /// Only calls made in this session
+        #[arg(long)]
+        session: Option<String>,
+
+        /// Only calls made by this turn
+        #[arg(long)]
+        turn: Option<String>,
+
+        /// Most calls to list
+        #[arg(short, long, default_value = "20")]
+        limit: usize,
+
+        /// Output format (table, json)
+        #[arg(short, long, default_value = "table")]
+        format: String,
+    },
+
+    /// Print one traced call in full
+    Show {
+        /// Trace ID, as listed
+        id: String,
+
+        /// Output format (text, json)
+        #[arg(short, long, default_value = "text")]
+        format: String,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum AuthAction {
+    /// Sign in to a provider (github, google, linkedin, slack)
+    Login {
+        /// Provider name
+        provider: String,
+        
+        /// Use the browser redirect flow instead of a device code
+        #[arg(short, long)]
+        browser: bool,
+    },
+    
+    /// Remove stored tokens for a provider
+    Logout {
+        /// Provider name
+        provider: String,
+    },
+    
+    /// Show sign-in status for all providers
+    Status,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let cli = Cli::parse();
 
     // Initialize logging
     let log_level = if cli.debug {
@@ -278,15 +1137,43 @@ async fn main() -> Result<()> {
         tracing::Level::INFO
     };
 
-    let subscriber = FmtSubscriber::builder()
-        .with_max_level(log_level)
-        .with_target(false)
-        .finish();
+    if matches!(cli.command, Commands::Start { detached: true, .. }) {
+        // No terminal to write to; log to rotating files instead
+        daemon::init_file_logging(log_level)?;
+    } else {
+        // Logs go to stderr so stdout stays clean for piping. The level
+        // only filters output; audit events are always signed into the log.
+        let output = tracing_subscriber::fmt::layer()
+            .with_target(false)
+            .with_writer(std::io::stderr)
+            .with_filter(LevelFilter::from_level(log_level));
+        let subscriber = tracing_subscriber::registry().with(output).with(AuditLayer);
 
-    tracing::subscriber::set_global_default(subscriber)?;
+        tracing::subscriber::set_global_default(subscriber)?;
+    }
 
     debug!("Starting Jamey CLI with command: {:?}", cli.command);
 
+    // The runtime reads its config file from JAMEY_CONFIG
+    std::env::set_var("JAMEY_CONFIG", commands::init::expand_home(&cli.config));
+
+    // Setup and profile management must keep working even if a profile is broken
+    let skip_profile = matches!(
+        cli.command,
+        Commands::System { action: SystemAction::Config { .. } }
+            | Commands::Init { .. }
+            | Commands::Completions { .. }
+            | Commands::Bench { .. }
+    );
+    if !skip_profile {
+        if let Err(e) = config::CliConfig::load_with_profile(cli.profile.as_deref())
+            .and_then(|config| config.apply_profile_env())
+        {
+            eprintln!("{} {}", t!("error-prefix").red().bold(), e);
+            std::process::exit(2);
+        }
+    }
+
     // Execute command
     match run_command(cli).await {
         Ok(_) => {
@@ -295,16 +1182,45 @@ async fn main() -> Result<()> {
         }
         Err(e) => {
             error!("Command failed: {}", e);
-            eprintln!("{} {}", "Error:".red().bold(), e);
-            std::process::exit(1);
+            eprintln!("{} {}", t!("error-prefix").red().bold(), e);
+            let code = e.downcast_ref::<ask::AskError>().map_or(1, ask::AskError::exit_code);
+            std::process::exit(code);
         }
     }
 }
 
 async fn run_command(cli: Cli) -> Result<()> {
+    let quiet = cli.quiet;
     match cli.command {
-        Commands::Chat { session, model, verbose } => {
-            chat::run_chat(session, model, verbose).await
+        Commands::Chat { session, model, persona, workspace, verbose, raw, speak, voice, strictness } => {
+            let flags = chat::ChatFlags { verbose, raw, speak, voice, strictness };
+            chat::run_chat(session, model, persona, workspace, flags).await
+        }
+        Commands::Ask { question, model, format, context, attach, raw, speak } => {
+            ask::run_ask(question, model, format, context, attach, ask::AskFlags { raw, speak, quiet }).await
+        }
+        Commands::Research { topic, queries, no_store, format } => {
+            research::run_research(topic, queries, no_store, format).await
+        }
+        Commands::Briefing { now } => briefing::run_briefing(now).await,
+        Commands::Eval { action } => eval::run_eval_action(action).await,
+        Commands::Watch { dir, ignore, debounce } => {
+            watch::run_watch(dir, ignore, debounce).await
+        }
+        Commands::Approvals { action } => {
+            approvals::run_approvals_action(action).await
+        }
+        Commands::Tool { action } => {
+            tool::run_tool_action(action).await
+        }
+        Commands::Sessions { action } => {
+            sessions::run_sessions_action(action).await
+        }
+        Commands::Persona { action } => {
+            persona::run_persona_action(action).await
+        }
+        Commands::Workspace { action } => {
+            workspace::run_workspace_action(action).await
         }
         Commands::Process { action } => {
             process::run_process_action(action).await
@@ -315,17 +1231,51 @@ async fn run_command(cli: Cli) -> Result<()> {
         Commands::System { action } => {
             system::run_system_action(action).await
         }
-        Commands::Init { dir, force } => {
-            init::run_init(dir, force).await
+        Commands::Init { dir, force, defaults } => {
+            init::run_init(dir, force, defaults).await
         }
-        Commands::Start { daemon, port } => {
-            start::run_start(daemon, port).await
+        Commands::Completions { shell } => {
+            completions::run_completions(shell)
+        }
+        Commands::Start { daemon, detached, port } => {
+            let mut passthrough = Vec::new();
+            if cli.debug {
+                passthrough.push("--debug".to_string());
+            }
+            if let Some(profile) = cli.profile {
+                passthrough.extend(["--profile".to_string(), profile]);
+            }
+            start::run_start(daemon, detached, port, passthrough).await
         }
         Commands::Stop { timeout } => {
             stop::run_stop(timeout).await
         }
-        Commands::Status { detailed, format } => {
-            status::run_status(detailed, format).await
+        Commands::Status { detailed, format, metrics_url } => {
+            status::run_status(detailed, format, metrics_url).await
+        }
+        Commands::Usage { action: Some(UsageAction::Insights { since, topics, format }), .. } => {
+            usage::run_insights(since, topics, format).await
+        }
+        Commands::Usage { action: None, since, group_by, format, output } => {
+            usage::run_usage(since, group_by, format, output).await
+        }
+        Commands::Forget { user, session, pattern, dry_run, force, format } => {
+            forget::run_forget(user, session, pattern, dry_run, force, format).await
+        }
+        Commands::Auth { action } => {
+            auth::run_auth_action(action).await
+        }
+        Commands::Audit { action: AuditAction::Verify { public_key, dir, format } } => {
+            audit::run_verify(public_key, dir, format).await
+        }
+        Commands::Tasks { action } => {
+            tasks::run_tasks_action(action).await
+        }
+        Commands::Traces { action } => {
+            traces::run_traces_action(action).await
+        }
+        Commands::Bench { iterations, filter, format } => {
+            bench::run_bench(iterations, filter, format).await
         }
     }
 }
@@ -333,22 +1283,74 @@ async fn run_command(cli: Cli) -> Result<()> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use clap::Parser;
+    use clap::{CommandFactory, Parser};
 
     #[test]
     fn test_cli_parsing() {
-        let cli = Cli::try_parse_from(&["jamey", "chat", "--model", "gpt-4"]).unwrap();
+        let cli = Cli::try_parse_from(["jamey", "chat", "--model", "gpt-4", "--strictness", "strict"]).unwrap();
         match cli.command {
-            Commands::Chat { model, .. } => {
-                assert_eq!(model, "gpt-4");
+            Commands::Chat { model, persona, strictness, .. } => {
+                assert_eq!(model.as_deref(), Some("gpt-4"));
+                assert!(persona.is_none());
+                assert_eq!(strictness, Some(Strictness::Strict));
             }
             _ => panic!("Expected chat command"),
         }
     }
 
+    #[test]
+    fn test_persona_parsing() {
+        let cli = Cli::try_parse_from(["jamey", "chat", "--persona", "ops"]).unwrap();
+        match cli.command {
+            Commands::Chat { model, persona, .. } => {
+                assert!(model.is_none());
+                assert_eq!(persona.as_deref(), Some("ops"));
+            }
+            _ => panic!("Expected chat command"),
+        }
+
+        let cli = Cli::try_parse_from([
+            "jamey", "persona", "set", "ops", "--tone", "terse", "--tools", "system_admin,network_web",
+        ]).unwrap();
+        match cli.command {
+            Commands::Persona { action: PersonaAction::Set { name, tone, tools, prompt, .. } } => {
+                assert_eq!(name, "ops");
+                assert_eq!(tone.as_deref(), Some("terse"));
+                assert_eq!(tools, Some(vec!["system_admin".to_string(), "network_web".to_string()]));
+                assert!(prompt.is_none());
+            }
+            _ => panic!("Expected persona set command"),
+        }
+        assert!(Cli::try_parse_from([
+            "jamey", "persona", "set", "ops", "--prompt", "x", "--prompt-file", "p.md",
+        ]).is_err());
+    }
+
+    #[test]
+    fn test_workspace_parsing() {
+        let cli = Cli::try_parse_from(["jamey", "chat", "--workspace", "app"]).unwrap();
+        match cli.command {
+            Commands::Chat { workspace, .. } => assert_eq!(workspace.as_deref(), Some("app")),
+            _ => panic!("Expected chat command"),
+        }
+
+        let cli = Cli::try_parse_from([
+            "jamey", "workspace", "set", "app", "--root", "~/code/app", "--ignore", "secrets,fixtures",
+        ]).unwrap();
+        match cli.command {
+            Commands::Workspace { action: WorkspaceAction::Set { name, root, ignore, tools, .. } } => {
+                assert_eq!(name, "app");
+                assert_eq!(root, Some(PathBuf::from("~/code/app")));
+                assert_eq!(ignore, Some(vec!["secrets".to_string(), "fixtures".to_string()]));
+                assert!(tools.is_none());
+            }
+            _ => panic!("Expected workspace set command"),
+        }
+    }
+
     #[test]
     fn test_process_command_parsing() {
-        let cli = Cli::try_parse_from(&["jamey", "process", "list", "--filter", "chrome"]).unwrap();
+        let cli = Cli::try_parse_from(["jamey", "process", "list", "--filter", "chrome"]).unwrap();
         match cli.command {
             Commands::Process { action } => {
                 match action {
@@ -361,4 +1363,388 @@ mod tests {
             _ => panic!("Expected process command"),
         }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_auth_command_parsing() {
+        let cli = Cli::try_parse_from(["jamey", "auth", "login", "github", "--browser"]).unwrap();
+        match cli.command {
+            Commands::Auth { action: AuthAction::Login { provider, browser } } => {
+                assert_eq!(provider, "github");
+                assert!(browser);
+            }
+            _ => panic!("Expected auth login command"),
+        }
+    }
+
+    #[test]
+    fn test_audit_command_parsing() {
+        let cli = Cli::try_parse_from(["jamey", "audit", "verify", "--public-key", "ab12", "-f", "json"]).unwrap();
+        match cli.command {
+            Commands::Audit { action: AuditAction::Verify { public_key, dir, format } } => {
+                assert_eq!(public_key.as_deref(), Some("ab12"));
+                assert!(dir.is_none());
+                assert_eq!(format, "json");
+            }
+            _ => panic!("Expected audit verify command"),
+        }
+    }
+
+    #[test]
+    fn test_ask_command_parsing() {
+        let cli = Cli::try_parse_from(["jamey", "ask", "explain", "--format", "json"]).unwrap();
+        match cli.command {
+            Commands::Ask { question, format, .. } => {
+                assert_eq!(question.as_deref(), Some("explain"));
+                assert_eq!(format, "json");
+            }
+            _ => panic!("Expected ask command"),
+        }
+
+        // The question is optional when input is piped
+        assert!(Cli::try_parse_from(["jamey", "ask"]).is_ok());
+    }
+
+    #[test]
+    fn test_research_command_parsing() {
+        let cli = Cli::try_parse_from(["jamey", "research", "rust async runtimes", "--queries", "2", "--no-store"]).unwrap();
+        match cli.command {
+            Commands::Research { topic, queries, no_store, format } => {
+                assert_eq!(topic, "rust async runtimes");
+                assert_eq!(queries, 2);
+                assert!(no_store);
+                assert_eq!(format, "text");
+            }
+            _ => panic!("Expected research command"),
+        }
+    }
+
+    #[test]
+    fn test_eval_command_parsing() {
+        let cli = Cli::try_parse_from(["jamey", "eval", "run", "suite.yaml", "--case", "marathon", "--min-pass-rate", "0.9"]).unwrap();
+        match cli.command {
+            Commands::Eval { action: EvalAction::Run { suite, model, case, format, min_pass_rate } } => {
+                assert_eq!(suite, PathBuf::from("suite.yaml"));
+                assert!(model.is_none());
+                assert_eq!(case.as_deref(), Some("marathon"));
+                assert_eq!(format, "text");
+                assert_eq!(min_pass_rate, Some(0.9));
+            }
+            _ => panic!("Expected eval run command"),
+        }
+        assert!(Cli::try_parse_from(["jamey", "eval", "validate"]).is_err());
+    }
+
+    #[test]
+    fn test_briefing_command_parsing() {
+        let cli = Cli::try_parse_from(["jamey", "briefing", "--now"]).unwrap();
+        assert!(matches!(cli.command, Commands::Briefing { now: true }));
+        let cli = Cli::try_parse_from(["jamey", "briefing"]).unwrap();
+        assert!(matches!(cli.command, Commands::Briefing { now: false }));
+    }
+
+    #[test]
+    fn test_sessions_command_parsing() {
+        let cli = Cli::try_parse_from(["jamey", "sessions", "export", "1a2b", "--format", "json"]).unwrap();
+        match cli.command {
+            Commands::Sessions { action: SessionsAction::Export { id, format, output } } => {
+                assert_eq!(id, "1a2b");
+                assert_eq!(format, "json");
+                assert!(output.is_none());
+            }
+            _ => panic!("Expected sessions export command"),
+        }
+    }
+
+    #[test]
+    fn test_memory_ingest_parsing() {
+        let cli = Cli::try_parse_from([
+            "jamey", "memory", "ingest", "docs/*.md", "--type", "skill", "--tags", "rust,notes",
+        ]).unwrap();
+        match cli.command {
+            Commands::Memory { action: MemoryAction::Ingest { target, chunk_size, memory_type, tags, dry_run, .. } } => {
+                assert_eq!(target, "docs/*.md");
+                assert_eq!(chunk_size, 1500);
+                assert_eq!(memory_type, "skill");
+                assert_eq!(tags, vec!["rust", "notes"]);
+                assert!(!dry_run);
+            }
+            _ => panic!("Expected memory ingest command"),
+        }
+    }
+
+    #[test]
+    fn test_tool_run_parsing() {
+        let cli = Cli::try_parse_from([
+            "jamey", "tool", "run", "github", "get_repo", "-p", "owner=rust-lang", "--param", "repo=rust",
+        ]).unwrap();
+        match cli.command {
+            Commands::Tool { action: ToolAction::Run { connector, action, params, yes, .. } } => {
+                assert_eq!(connector, "github");
+                assert_eq!(action, "get_repo");
+                assert_eq!(params[1], ("repo".to_string(), "rust".to_string()));
+                assert!(!yes);
+            }
+            _ => panic!("Expected tool run command"),
+        }
+        assert!(Cli::try_parse_from(["jamey", "tool", "run", "github", "get_repo", "-p", "oops"]).is_err());
+    }
+
+    #[test]
+    fn test_approvals_command_parsing() {
+        let cli = Cli::try_parse_from(["jamey", "approvals", "list", "--watch"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Commands::Approvals { action: ApprovalsAction::List { all: false, watch: true } }
+        ));
+
+        let cli = Cli::try_parse_from(["jamey", "approvals", "deny", "3f2a", "-r", "too risky"]).unwrap();
+        match cli.command {
+            Commands::Approvals { action: ApprovalsAction::Deny { id, reason } } => {
+                assert_eq!(id, "3f2a");
+                assert_eq!(reason.as_deref(), Some("too risky"));
+            }
+            _ => panic!("Expected approvals deny command"),
+        }
+    }
+
+    #[test]
+    fn test_profile_parsing() {
+        let cli = Cli::try_parse_from(["jamey", "--profile", "work", "chat"]).unwrap();
+        assert_eq!(cli.profile.as_deref(), Some("work"));
+
+        let cli = Cli::try_parse_from([
+            "jamey", "system", "config", "profile", "add", "staging",
+            "--runtime-url", "https://staging:3000", "-e", "POSTGRES_HOST=db.staging",
+        ]).unwrap();
+        match cli.command {
+            Commands::System { action: SystemAction::Config { action: ConfigAction::Profile { action: ProfileAction::Add { name, runtime_url, env, .. } } } } => {
+                assert_eq!(name, "staging");
+                assert_eq!(runtime_url.as_deref(), Some("https://staging:3000"));
+                assert_eq!(env, vec![("POSTGRES_HOST".to_string(), "db.staging".to_string())]);
+            }
+            _ => panic!("Expected config profile add command"),
+        }
+    }
+
+    #[test]
+    fn test_completions_generate() {
+        let cli = Cli::try_parse_from(["jamey", "completions", "bash"]).unwrap();
+        assert!(matches!(cli.command, Commands::Completions { shell: clap_complete::Shell::Bash }));
+
+        let mut script = Vec::new();
+        let mut command = Cli::command();
+        clap_complete::generate(clap_complete::Shell::Bash, &mut command, "jamey", &mut script);
+        let script = String::from_utf8(script).unwrap();
+        assert!(script.contains("sessions"));
+        assert!(script.contains("approvals"));
+    }
+
+    #[test]
+    fn test_usage_command_parsing() {
+        let cli = Cli::try_parse_from([
+            "jamey", "usage", "--since", "30d", "--group-by", "session", "-o", "march.csv",
+        ]).unwrap();
+        match cli.command {
+            Commands::Usage { action, since, group_by, format, output } => {
+                assert!(action.is_none());
+                assert_eq!(since, "30d");
+                assert_eq!(group_by, GroupBy::Session);
+                assert_eq!(format, "table");
+                assert_eq!(output, Some(PathBuf::from("march.csv")));
+            }
+            _ => panic!("Expected usage command"),
+        }
+        assert!(Cli::try_parse_from(["jamey", "usage", "--group-by", "tool"]).is_err());
+
+        let cli = Cli::try_parse_from(["jamey", "usage", "insights", "--since", "24h", "--topics"]).unwrap();
+        match cli.command {
+            Commands::Usage { action: Some(UsageAction::Insights { since, topics, format }), .. } => {
+                assert_eq!(since, "24h");
+                assert!(topics);
+                assert_eq!(format, "table");
+            }
+            _ => panic!("Expected usage insights command"),
+        }
+    }
+
+    #[test]
+    fn test_forget_parsing() {
+        let cli = Cli::try_parse_from(["jamey", "forget", "--user", "alice", "--dry-run"]).unwrap();
+        match cli.command {
+            Commands::Forget { user, session, pattern, dry_run, force, .. } => {
+                assert_eq!(user.as_deref(), Some("alice"));
+                assert!(session.is_none() && pattern.is_none());
+                assert!(dry_run);
+                assert!(!force);
+            }
+            _ => panic!("Expected forget command"),
+        }
+        assert!(Cli::try_parse_from(["jamey", "forget"]).is_err());
+        assert!(Cli::try_parse_from(["jamey", "forget", "--user", "alice", "--pattern", "x"]).is_err());
+    }
+
+    #[test]
+    fn test_start_daemon_parsing() {
+        let cli = Cli::try_parse_from(["jamey", "--debug", "start", "--daemon", "--port", "4000"]).unwrap();
+        assert!(cli.debug);
+        match cli.command {
+            Commands::Start { daemon, detached, port } => {
+                assert!(daemon);
+                assert!(!detached);
+                assert_eq!(port, 4000);
+            }
+            _ => panic!("Expected start command"),
+        }
+    }
+
+    #[test]
+    fn test_memory_maintenance_parsing() {
+        let cli = Cli::try_parse_from(["jamey", "memory", "dedupe", "--threshold", "0.99", "--dry-run"]).unwrap();
+        match cli.command {
+            Commands::Memory { action: MemoryAction::Dedupe { threshold, dry_run, force } } => {
+                assert_eq!(threshold, 0.99);
+                assert!(dry_run);
+                assert!(!force);
+            }
+            _ => panic!("Expected memory dedupe command"),
+        }
+
+        let cli = Cli::try_parse_from(["jamey", "memory", "reembed", "-m", "openai/text-embedding-3-small", "-f"]).unwrap();
+        match cli.command {
+            Commands::Memory { action: MemoryAction::Reembed { model, force, .. } } => {
+                assert_eq!(model, "openai/text-embedding-3-small");
+                assert!(force);
+            }
+            _ => panic!("Expected memory reembed command"),
+        }
+        assert!(Cli::try_parse_from(["jamey", "memory", "reembed"]).is_err());
+
+        let cli = Cli::try_parse_from(["jamey", "memory", "pin", "7d3c1f2e-0000-4000-8000-000000000000"]).unwrap();
+        assert!(matches!(cli.command, Commands::Memory { action: MemoryAction::Pin { .. } }));
+
+        let cli = Cli::try_parse_from(["jamey", "memory", "partition", "--namespace", "project:app"]).unwrap();
+        match cli.command {
+            Commands::Memory { action: MemoryAction::Partition { namespace, force } } => {
+                assert_eq!(namespace.as_deref(), Some("project:app"));
+                assert!(!force);
+            }
+            _ => panic!("Expected memory partition command"),
+        }
+        let cli = Cli::try_parse_from(["jamey", "memory", "archive", "--days", "90", "--dry-run"]).unwrap();
+        match cli.command {
+            Commands::Memory { action: MemoryAction::Archive { days, dry_run, force } } => {
+                assert_eq!(days, Some(90));
+                assert!(dry_run && !force);
+            }
+            _ => panic!("Expected memory archive command"),
+        }
+        let cli = Cli::try_parse_from(["jamey", "memory", "reindex"]).unwrap();
+        assert!(matches!(cli.command, Commands::Memory { action: MemoryAction::Reindex { namespace: None } }));
+    }
+
+    #[test]
+    fn test_watch_command_parsing() {
+        let cli = Cli::try_parse_from(["jamey", "watch", "--ignore", "fixtures,vendor"]).unwrap();
+        match cli.command {
+            Commands::Watch { dir, ignore, debounce } => {
+                assert_eq!(dir, PathBuf::from("."));
+                assert_eq!(ignore, vec!["fixtures", "vendor"]);
+                assert_eq!(debounce, 1500);
+            }
+            _ => panic!("Expected watch command"),
+        }
+
+        let cli = Cli::try_parse_from(["jamey", "ask", "--context", "project", "what changed?"]).unwrap();
+        match cli.command {
+            Commands::Ask { context, .. } => assert_eq!(context.as_deref(), Some("project")),
+            _ => panic!("Expected ask command"),
+        }
+    }
+
+    #[test]
+    fn test_raw_output_flag() {
+        let cli = Cli::try_parse_from(["jamey", "chat", "--raw"]).unwrap();
+        match cli.command {
+            Commands::Chat { raw, .. } => assert!(raw),
+            _ => panic!("Expected chat command"),
+        }
+
+        let cli = Cli::try_parse_from(["jamey", "ask", "explain"]).unwrap();
+        match cli.command {
+            Commands::Ask { raw, .. } => assert!(!raw),
+            _ => panic!("Expected ask command"),
+        }
+    }
+
+    #[test]
+    fn test_speak_flag() {
+        let cli = Cli::try_parse_from(["jamey", "chat", "--speak", "--voice"]).unwrap();
+        match cli.command {
+            Commands::Chat { speak, voice, .. } => assert!(speak && voice),
+            _ => panic!("Expected chat command"),
+        }
+
+        let cli = Cli::try_parse_from(["jamey", "ask", "--speak", "what time is it?"]).unwrap();
+        match cli.command {
+            Commands::Ask { speak, question, .. } => {
+                assert!(speak);
+                assert_eq!(question.as_deref(), Some("what time is it?"));
+            }
+            _ => panic!("Expected ask command"),
+        }
+    }
+
+    #[test]
+    fn test_ask_attachments_parsing() {
+        let cli = Cli::try_parse_from([
+            "jamey", "ask", "--attach", "report.pdf", "--attach", "notes.md", "summarize this",
+        ]).unwrap();
+        match cli.command {
+            Commands::Ask { question, attach, .. } => {
+                assert_eq!(question.as_deref(), Some("summarize this"));
+                assert_eq!(attach, vec![PathBuf::from("report.pdf"), PathBuf::from("notes.md")]);
+            }
+            _ => panic!("Expected ask command"),
+        }
+    }
+
+    #[test]
+    fn test_tasks_parsing() {
+        let cli = Cli::try_parse_from(["jamey", "tasks", "list", "--format", "json"]).unwrap();
+        match cli.command {
+            Commands::Tasks { action: TasksAction::List { format, .. } } => assert_eq!(format, "json"),
+            _ => panic!("Expected tasks list command"),
+        }
+
+        let cli = Cli::try_parse_from(["jamey", "tasks", "cancel", "abc", "--url", "http://host:3000"]).unwrap();
+        match cli.command {
+            Commands::Tasks { action: TasksAction::Cancel { id, url, .. } } => {
+                assert_eq!(id, "abc");
+                assert_eq!(url, "http://host:3000");
+            }
+            _ => panic!("Expected tasks cancel command"),
+        }
+    }
+
+    #[test]
+    fn test_traces_parsing() {
+        let cli = Cli::try_parse_from(["jamey", "traces", "list", "--since", "7d", "--limit", "5"]).unwrap();
+        match cli.command {
+            Commands::Traces { action: TracesAction::List { since, limit, session, .. } } => {
+                assert_eq!(since, "7d");
+                assert_eq!(limit, 5);
+                assert!(session.is_none());
+            }
+            _ => panic!("Expected traces list command"),
+        }
+
+        let cli = Cli::try_parse_from(["jamey", "traces", "show", "abc", "--format", "json"]).unwrap();
+        match cli.command {
+            Commands::Traces { action: TracesAction::Show { id, format } } => {
+                assert_eq!(id, "abc");
+                assert_eq!(format, "json");
+            }
+            _ => panic!("Expected traces show command"),
+        }
+    }
+}
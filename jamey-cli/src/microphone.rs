@@ -0,0 +1,126 @@
+//! Microphone capture for `jamey chat --voice`
+//!
+//! Recording is push-to-talk: [`Microphone::start`] opens the default input
+//! device and collects samples until [`Microphone::stop`]. Channels are mixed
+//! down to 16-bit mono, the form the transcription upload is encoded in.
+//! Capture is behind the `voice` feature because cpal links the platform
+//! audio libraries (ALSA on Linux).
+
+/// A finished mono recording
+pub struct Recording {
+    pub samples: Vec<i16>,
+    pub sample_rate: u32,
+}
+
+impl Recording {
+    pub fn seconds(&self) -> f32 {
+        self.samples.len() as f32 / self.sample_rate.max(1) as f32
+    }
+}
+
+#[cfg(feature = "voice")]
+pub use capture::Microphone;
+
+#[cfg(feature = "voice")]
+mod capture {
+    use super::Recording;
+    use anyhow::{anyhow, Context, Result};
+    use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+    use cpal::{FromSample, Sample, SizedSample};
+    use std::sync::{Arc, Mutex};
+
+    pub struct Microphone {
+        stream: cpal::Stream,
+        samples: Arc<Mutex<Vec<i16>>>,
+        sample_rate: u32,
+    }
+
+    impl Microphone {
+        /// Name of the input device recordings come from
+        pub fn device_name() -> Result<String> {
+            let device = default_device()?;
+            Ok(device.name().unwrap_or_else(|_| "default input".to_string()))
+        }
+
+        pub fn start() -> Result<Self> {
+            let device = default_device()?;
+            let supported = device
+                .default_input_config()
+                .context("The microphone has no usable input format")?;
+            let format = supported.sample_format();
+            let config: cpal::StreamConfig = supported.into();
+            let samples = Arc::new(Mutex::new(Vec::new()));
+            let stream = match format {
+                cpal::SampleFormat::I16 => open::<i16>(&device, &config, &samples),
+                cpal::SampleFormat::U16 => open::<u16>(&device, &config, &samples),
+                cpal::SampleFormat::I32 => open::<i32>(&device, &config, &samples),
+                cpal::SampleFormat::F32 => open::<f32>(&device, &config, &samples),
+                other => Err(anyhow!("Unsupported microphone sample format {}", other)),
+            }?;
+            stream.play().context("Failed to start recording")?;
+            Ok(Self {
+                stream,
+                samples,
+                sample_rate: config.sample_rate.0,
+            })
+        }
+
+        pub fn stop(self) -> Recording {
+            drop(self.stream);
+            let mut samples = self.samples.lock().unwrap_or_else(|e| e.into_inner());
+            Recording {
+                samples: std::mem::take(&mut *samples),
+                sample_rate: self.sample_rate,
+            }
+        }
+    }
+
+    fn default_device() -> Result<cpal::Device> {
+        cpal::default_host()
+            .default_input_device()
+            .ok_or_else(|| anyhow!("No microphone found"))
+    }
+
+    /// Input stream that appends each frame, averaged across its channels
+    fn open<T>(device: &cpal::Device, config: &cpal::StreamConfig, samples: &Arc<Mutex<Vec<i16>>>) -> Result<cpal::Stream>
+    where
+        T: SizedSample,
+        i16: FromSample<T>,
+    {
+        let channels = usize::from(config.channels.max(1));
+        let samples = Arc::clone(samples);
+        device
+            .build_input_stream(
+                config,
+                move |data: &[T], _: &cpal::InputCallbackInfo| {
+                    let mut samples = samples.lock().unwrap_or_else(|e| e.into_inner());
+                    samples.extend(data.chunks(channels).map(|frame| {
+                        let sum: i32 = frame.iter().map(|&s| i32::from(i16::from_sample(s))).sum();
+                        (sum / frame.len() as i32) as i16
+                    }));
+                },
+                |e| tracing::warn!("Microphone error: {}", e),
+                None,
+            )
+            .context("Failed to open the microphone")
+    }
+}
+
+/// Stand-in for builds without the `voice` feature; it can't be started
+#[cfg(not(feature = "voice"))]
+pub enum Microphone {}
+
+#[cfg(not(feature = "voice"))]
+impl Microphone {
+    pub fn device_name() -> anyhow::Result<String> {
+        anyhow::bail!("This build has no microphone support; reinstall with `--features voice`")
+    }
+
+    pub fn start() -> anyhow::Result<Self> {
+        Self::device_name().map(|_| unreachable!())
+    }
+
+    pub fn stop(self) -> Recording {
+        match self {}
+    }
+}
@@ -0,0 +1,296 @@
+//! Terminal rendering of assistant replies
+//!
+//! Replies are Markdown. Prose (headings, lists, tables, emphasis) is laid out
+//! by termimad and fenced code blocks are highlighted with syntect. Streamed
+//! replies are rendered one block at a time, as each paragraph or code fence
+//! completes, so output still appears while the model is writing.
+
+use std::io::IsTerminal;
+use std::sync::OnceLock;
+use syntect::easy::HighlightLines;
+use syntect::highlighting::{Theme, ThemeSet};
+use syntect::parsing::SyntaxSet;
+use syntect::util::{as_24_bit_terminal_escaped, LinesWithEndings};
+use termimad::MadSkin;
+
+/// Widest the prose is wrapped to, however wide the terminal
+const MAX_WIDTH: usize = 100;
+
+/// syntect theme for code blocks
+const CODE_THEME: &str = "base16-ocean.dark";
+
+/// Syntax definitions and theme; loading them takes a moment, so only once
+fn highlighting() -> &'static (SyntaxSet, Theme) {
+    static ASSETS: OnceLock<(SyntaxSet, Theme)> = OnceLock::new();
+    ASSETS.get_or_init(|| {
+        let mut themes = ThemeSet::load_defaults();
+        let theme = themes.themes.remove(CODE_THEME).unwrap_or_default();
+        (SyntaxSet::load_defaults_newlines(), theme)
+    })
+}
+
+/// Renders Markdown for display in the terminal
+pub struct MarkdownRenderer {
+    skin: MadSkin,
+    width: usize,
+}
+
+impl MarkdownRenderer {
+    pub fn new() -> Self {
+        let (columns, _) = termimad::terminal_size();
+        Self {
+            skin: MadSkin::default_dark(),
+            width: (columns as usize).clamp(20, MAX_WIDTH),
+        }
+    }
+
+    /// Render a complete Markdown document or block
+    pub fn render(&self, markdown: &str) -> String {
+        let mut out = String::new();
+        let mut prose = String::new();
+        // Language and body of the code block being read
+        let mut code: Option<(String, String)> = None;
+
+        for line in markdown.split_inclusive('\n') {
+            let fence = fence_info(line);
+            match (&mut code, fence) {
+                (None, Some(lang)) => {
+                    out.push_str(&self.render_prose(&prose));
+                    prose.clear();
+                    code = Some((lang.to_string(), String::new()));
+                }
+                (None, None) => prose.push_str(line),
+                (Some((lang, body)), Some(_)) => {
+                    out.push_str(&render_code(lang, body));
+                    code = None;
+                }
+                (Some((_, body)), None) => body.push_str(line),
+            }
+        }
+
+        // An unterminated fence is still code, e.g. when a reply was cut short
+        match code {
+            Some((lang, body)) => out.push_str(&render_code(&lang, &body)),
+            None => out.push_str(&self.render_prose(&prose)),
+        }
+        out
+    }
+
+    fn render_prose(&self, text: &str) -> String {
+        if text.trim().is_empty() {
+            return text.to_string();
+        }
+        self.skin.text(&rewrite_links(text), Some(self.width)).to_string()
+    }
+}
+
+/// Highlight a code block, falling back to plain text for unknown languages
+fn render_code(lang: &str, code: &str) -> String {
+    let (syntaxes, theme) = highlighting();
+    let syntax = syntaxes
+        .find_syntax_by_token(lang)
+        .unwrap_or_else(|| syntaxes.find_syntax_plain_text());
+    let mut highlighter = HighlightLines::new(syntax, theme);
+
+    let mut out = String::new();
+    for line in LinesWithEndings::from(code) {
+        match highlighter.highlight_line(line, syntaxes) {
+            Ok(ranges) => out.push_str(&as_24_bit_terminal_escaped(&ranges, false)),
+            Err(_) => out.push_str(line),
+        }
+    }
+    out.push_str("\x1b[0m");
+    if !out.ends_with('\n') {
+        out.push('\n');
+    }
+    out
+}
+
+/// The info string of a code fence line (empty when no language is given)
+fn fence_info(line: &str) -> Option<&str> {
+    let trimmed = line.trim();
+    trimmed
+        .strip_prefix("```")
+        .or_else(|| trimmed.strip_prefix("~~~"))
+        .map(|info| info.split_whitespace().next().unwrap_or(""))
+}
+
+/// Turn `[text](url)` into `*text* (url)`, since termimad does not show link
+/// targets; bare links where the text is the URL are shown once
+fn rewrite_links(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(open) = rest.find('[') {
+        let Some((label, url, consumed)) = parse_link(&rest[open..]) else {
+            out.push_str(&rest[..=open]);
+            rest = &rest[open + 1..];
+            continue;
+        };
+        out.push_str(&rest[..open]);
+        if label == url || label.is_empty() {
+            out.push_str(url);
+        } else {
+            out.push_str(&format!("*{}* ({})", label, url));
+        }
+        rest = &rest[open + consumed..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Parse a link at the start of `s`: its label, target and length in bytes
+fn parse_link(s: &str) -> Option<(&str, &str, usize)> {
+    let close = s.find(']')?;
+    let label = &s[1..close];
+    if label.contains('[') || label.contains('\n') {
+        return None;
+    }
+    let after = s[close + 1..].strip_prefix('(')?;
+    let end = after.find(')')?;
+    let url = after[..end].trim();
+    if url.is_empty() || url.contains(char::is_whitespace) {
+        return None;
+    }
+    Some((label, url, close + 2 + end + 1))
+}
+
+/// Collects a streamed reply and hands back Markdown a block at a time
+///
+/// A block ends at a blank line outside a code fence, or where a fence closes.
+#[derive(Default)]
+pub struct BlockBuffer {
+    pending: String,
+    /// Bytes of `pending` already checked for block boundaries
+    scanned: usize,
+    in_fence: bool,
+}
+
+impl BlockBuffer {
+    /// Add streamed text, returning any blocks it completed
+    pub fn push(&mut self, text: &str) -> Option<String> {
+        self.pending.push_str(text);
+
+        let mut boundary = None;
+        let mut start = self.scanned;
+        while let Some(newline) = self.pending[start..].find('\n') {
+            let end = start + newline + 1;
+            let line = &self.pending[start..end];
+            if fence_info(line).is_some() {
+                self.in_fence = !self.in_fence;
+                if !self.in_fence {
+                    boundary = Some(end);
+                }
+            } else if !self.in_fence && line.trim().is_empty() {
+                boundary = Some(end);
+            }
+            start = end;
+        }
+        self.scanned = start;
+
+        let end = boundary?;
+        self.scanned -= end;
+        Some(self.pending.drain(..end).collect())
+    }
+
+    /// Whatever is left once the reply is complete
+    pub fn finish(&mut self) -> Option<String> {
+        self.scanned = 0;
+        self.in_fence = false;
+        let rest = std::mem::take(&mut self.pending);
+        (!rest.is_empty()).then_some(rest)
+    }
+}
+
+/// Turns a streamed reply into terminal output, rendered or passed through
+pub struct ReplyWriter {
+    renderer: Option<MarkdownRenderer>,
+    blocks: BlockBuffer,
+}
+
+impl ReplyWriter {
+    /// Renders only when asked to and stdout is a colour terminal; piped
+    /// output and `NO_COLOR` get the Markdown as-is
+    pub fn new(render: bool) -> Self {
+        let render = render
+            && std::io::stdout().is_terminal()
+            && colored::control::SHOULD_COLORIZE.should_colorize();
+        Self {
+            renderer: render.then(MarkdownRenderer::new),
+            blocks: BlockBuffer::default(),
+        }
+    }
+
+    pub fn is_rendering(&self) -> bool {
+        self.renderer.is_some()
+    }
+
+    /// Text to print for a streamed token; empty while a block is incomplete
+    pub fn push(&mut self, token: &str) -> String {
+        match &self.renderer {
+            Some(renderer) => self.blocks.push(token).map(|b| renderer.render(&b)).unwrap_or_default(),
+            None => token.to_string(),
+        }
+    }
+
+    /// Text still held back once the reply ends or pauses for a tool call
+    pub fn finish(&mut self) -> String {
+        match &self.renderer {
+            Some(renderer) => self.blocks.finish().map(|b| renderer.render(&b)).unwrap_or_default(),
+            None => String::new(),
+        }
+    }
+
+    /// Text to print for a reply that arrived whole rather than streamed
+    pub fn whole(&self, reply: &str) -> String {
+        match &self.renderer {
+            Some(renderer) => renderer.render(reply),
+            None => reply.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rewrite_links() {
+        assert_eq!(
+            rewrite_links("See [the docs](https://docs.rs) and [x]."),
+            "See *the docs* (https://docs.rs) and [x]."
+        );
+        assert_eq!(rewrite_links("[https://a.io](https://a.io)"), "https://a.io");
+        assert_eq!(rewrite_links("arr[0] (not a link)"), "arr[0] (not a link)");
+    }
+
+    #[test]
+    fn test_block_buffer_splits_on_blank_lines_and_fences() {
+        let mut buffer = BlockBuffer::default();
+        assert_eq!(buffer.push("Hello "), None);
+        assert_eq!(buffer.push("world\n"), None);
+        assert_eq!(buffer.push("\nNext"), Some("Hello world\n\n".to_string()));
+
+        // Blank lines inside a fence don't end the block
+        assert_eq!(buffer.push("\n```rust\nfn a() {}\n\n"), None);
+        assert_eq!(
+            buffer.push("fn b() {}\n```\ntail"),
+            Some("Next\n```rust\nfn a() {}\n\nfn b() {}\n```\n".to_string())
+        );
+        assert_eq!(buffer.finish(), Some("tail".to_string()));
+        assert_eq!(buffer.finish(), None);
+    }
+
+    #[test]
+    fn test_render_highlights_code_blocks() {
+        let renderer = MarkdownRenderer { skin: MadSkin::no_style(), width: 80 };
+        let out = renderer.render("Example:\n\n```rust\nlet x = 1;\n```\n");
+        assert!(out.contains("Example:"));
+        assert!(out.contains("\x1b[38;2;"));
+        assert!(!out.contains("```"));
+
+        // Unknown languages are still shown, just without colour
+        let out = renderer.render("```nosuchlang\nplain\n```\n");
+        assert!(out.contains("plain"));
+    }
+}
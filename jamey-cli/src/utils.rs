@@ -126,6 +126,18 @@ pub fn validate_input_length(input: &str, max_length: usize, field_name: &str) -
     Ok(())
 }
 
+/// Parse a `key=value` argument; the value may itself contain `=`
+pub fn parse_key_value(s: &str) -> Result<(String, String), String> {
+    let (key, value) = s
+        .split_once('=')
+        .ok_or_else(|| format!("expected key=value, got '{}'", s))?;
+    let key = key.trim();
+    if key.is_empty() {
+        return Err(format!("empty key in '{}'", s));
+    }
+    Ok((key.to_string(), value.to_string()))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -143,4 +155,12 @@ mod tests {
         assert_eq!(format_duration(90), "1m 30s");
         assert_eq!(format_duration(3661), "1h 1m 1s");
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_parse_key_value() {
+        assert_eq!(parse_key_value("path=/tmp/a=b").unwrap(), ("path".to_string(), "/tmp/a=b".to_string()));
+        assert_eq!(parse_key_value("empty=").unwrap(), ("empty".to_string(), String::new()));
+        assert!(parse_key_value("novalue").is_err());
+        assert!(parse_key_value("=x").is_err());
+    }
+}
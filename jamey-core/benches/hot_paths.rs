@@ -0,0 +1,176 @@
+//! Hot paths that don't need external services: memory stores, embedding
+//! encodings and cache serialization. PostgreSQL joins the store benchmarks
+//! when `JAMEY_BENCH_DATABASE_URL` is set.
+//!
+//!     cargo bench -p jamey-core --bench hot_paths
+
+use chrono::Utc;
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use jamey_core::cache::{CacheBackend, CacheConfig, MemoryCache};
+use jamey_core::memory::{embedding_from_binary, embedding_from_text, embedding_to_binary, embedding_to_text};
+use jamey_core::{CachedMemoryStore, InMemoryStore, Memory, MemoryStore, MemoryType, PostgresMemoryStore};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::runtime::Runtime;
+use uuid::Uuid;
+
+const DIM: usize = 1536;
+const CORPUS: usize = 1000;
+
+fn setup_runtime() -> Runtime {
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .unwrap()
+}
+
+/// Deterministic, non-uniform embedding so search has something to rank
+fn embedding(seed: usize, dim: usize) -> Vec<f32> {
+    (0..dim).map(|i| (((seed * 31 + i * 17) % 1000) as f32 / 1000.0) - 0.5).collect()
+}
+
+fn test_memory(seed: usize) -> Memory {
+    Memory {
+        id: Uuid::new_v4(),
+        memory_type: MemoryType::Knowledge,
+        content: format!("Benchmark memory {} with a sentence or two of content to carry around", seed),
+        embedding: embedding(seed, DIM),
+        metadata: serde_json::json!({"benchmark": true, "seed": seed}),
+        created_at: Utc::now(),
+        last_accessed: Utc::now(),
+    }
+}
+
+async fn postgres_store() -> Option<PostgresMemoryStore> {
+    let url = std::env::var("JAMEY_BENCH_DATABASE_URL").ok()?;
+    let config: tokio_postgres::Config = url.parse().expect("Invalid JAMEY_BENCH_DATABASE_URL");
+    let manager = deadpool_postgres::Manager::new(config, tokio_postgres::NoTls);
+    let pool = deadpool_postgres::Pool::builder(manager).max_size(16).build().unwrap();
+    Some(PostgresMemoryStore::new(pool, DIM).await.expect("Failed to connect to benchmark database"))
+}
+
+async fn backends() -> Vec<(&'static str, Arc<dyn MemoryStore + Send + Sync>)> {
+    let mut backends: Vec<(&'static str, Arc<dyn MemoryStore + Send + Sync>)> = vec![
+        ("in_memory", Arc::new(InMemoryStore::new())),
+        (
+            "cached_in_memory",
+            Arc::new(CachedMemoryStore::new(InMemoryStore::new(), CacheConfig::default()).await.unwrap()),
+        ),
+    ];
+    if let Some(store) = postgres_store().await {
+        backends.push(("postgres", Arc::new(store)));
+    }
+    backends
+}
+
+fn bench_store_backends(c: &mut Criterion) {
+    let rt = setup_runtime();
+    let backends = rt.block_on(backends());
+
+    let mut group = c.benchmark_group("store_backends");
+    for (name, store) in &backends {
+        let ids: Vec<Uuid> = rt.block_on(async {
+            let mut ids = Vec::with_capacity(CORPUS);
+            for seed in 0..CORPUS {
+                ids.push(store.store(test_memory(seed)).await.unwrap());
+            }
+            ids
+        });
+
+        group.bench_function(BenchmarkId::new("store", name), |b| {
+            b.to_async(&rt).iter(|| async {
+                let id = store.store(black_box(test_memory(CORPUS))).await.unwrap();
+                store.delete(id).await.unwrap();
+            });
+        });
+
+        group.bench_function(BenchmarkId::new("retrieve", name), |b| {
+            let mut next = 0;
+            b.to_async(&rt).iter(|| {
+                next = (next + 1) % ids.len();
+                let id = ids[next];
+                async move {
+                    black_box(store.retrieve(black_box(id)).await.unwrap());
+                }
+            });
+        });
+
+        let query = embedding(7, DIM);
+        group.bench_function(BenchmarkId::new("search_top10", name), |b| {
+            b.to_async(&rt).iter(|| async {
+                black_box(store.search(black_box(&query), 10).await.unwrap());
+            });
+        });
+
+        rt.block_on(async {
+            for id in ids {
+                let _ = store.delete(id).await;
+            }
+        });
+    }
+    group.finish();
+}
+
+fn bench_embedding_encoding(c: &mut Criterion) {
+    let mut group = c.benchmark_group("embedding_encoding");
+    for dim in [384, 1536, 3072] {
+        let values = embedding(1, dim);
+        let text = embedding_to_text(&values);
+        let binary = embedding_to_binary(&values).unwrap();
+        group.throughput(Throughput::Elements(dim as u64));
+
+        group.bench_with_input(BenchmarkId::new("text_encode", dim), &values, |b, v| {
+            b.iter(|| black_box(embedding_to_text(black_box(v))));
+        });
+        group.bench_with_input(BenchmarkId::new("text_decode", dim), &text, |b, t| {
+            b.iter(|| black_box(embedding_from_text(black_box(t)).unwrap()));
+        });
+        group.bench_with_input(BenchmarkId::new("binary_encode", dim), &values, |b, v| {
+            b.iter(|| black_box(embedding_to_binary(black_box(v)).unwrap()));
+        });
+        group.bench_with_input(BenchmarkId::new("binary_decode", dim), &binary, |b, bytes| {
+            b.iter(|| black_box(embedding_from_binary(black_box(bytes)).unwrap()));
+        });
+    }
+    group.finish();
+}
+
+fn bench_cache_serialization(c: &mut Criterion) {
+    let rt = setup_runtime();
+    let memory = test_memory(1);
+    let results: Vec<Memory> = (0..10).map(test_memory).collect();
+    let encoded = serde_json::to_vec(&memory).unwrap();
+    let encoded_results = serde_json::to_vec(&results).unwrap();
+
+    let mut group = c.benchmark_group("cache_serialization");
+    group.bench_function("memory_to_json", |b| {
+        b.iter(|| black_box(serde_json::to_vec(black_box(&memory)).unwrap()));
+    });
+    group.bench_function("memory_from_json", |b| {
+        b.iter(|| black_box(serde_json::from_slice::<Memory>(black_box(&encoded)).unwrap()));
+    });
+    group.bench_function("search_results_to_json", |b| {
+        b.iter(|| black_box(serde_json::to_vec(black_box(&results)).unwrap()));
+    });
+    group.bench_function("search_results_from_json", |b| {
+        b.iter(|| black_box(serde_json::from_slice::<Vec<Memory>>(black_box(&encoded_results)).unwrap()));
+    });
+
+    let cache = MemoryCache::new(CORPUS, Duration::from_secs(300)).unwrap();
+    group.bench_function("memory_cache_round_trip", |b| {
+        b.to_async(&rt).iter(|| async {
+            cache.set("bench", serde_json::to_vec(&memory).unwrap(), None).await.unwrap();
+            let bytes = cache.get("bench").await.unwrap().unwrap();
+            black_box(serde_json::from_slice::<Memory>(&bytes).unwrap());
+        });
+    });
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_store_backends,
+    bench_embedding_encoding,
+    bench_cache_serialization
+);
+criterion_main!(benches);
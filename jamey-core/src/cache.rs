@@ -3,9 +3,13 @@
 //! Provides Redis-backed caching with in-memory fallback
 //! for improved performance and scalability.
 
+use crate::profiling::{CACHE_HITS, CACHE_MISSES};
+use crate::secure_logging::mask_url_credentials;
 use anyhow::Result;
 use async_trait::async_trait;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::time::Duration;
 use thiserror::Error;
 use tracing::{debug, error, info, warn};
@@ -83,12 +87,12 @@ impl CacheBackend for RedisCache {
             cmd.arg(&formatted_key)
                .arg(ttl_secs)
                .arg(&value);
-            cmd.query_async(&mut self.client.clone()).await?;
+            cmd.query_async::<()>(&mut self.client.clone()).await?;
         } else {
             let mut cmd = redis::cmd("SET");
             cmd.arg(&formatted_key)
                .arg(&value);
-            cmd.query_async(&mut self.client.clone()).await?;
+            cmd.query_async::<()>(&mut self.client.clone()).await?;
         }
         
         Ok(())
@@ -118,7 +122,7 @@ impl CacheBackend for RedisCache {
         if !keys.is_empty() {
             let mut cmd = redis::cmd("DEL");
             cmd.arg(keys);
-            cmd.query_async(&mut self.client.clone()).await?;
+            cmd.query_async::<()>(&mut self.client.clone()).await?;
         }
         
         Ok(())
@@ -229,7 +233,7 @@ impl CacheBackend for MemoryCache {
 
 /// Hybrid cache with Redis primary and memory fallback
 pub struct HybridCache {
-    redis: Option<RedisCache>,
+    redis: Option<Box<dyn CacheBackend>>,
     memory: MemoryCache,
     fallback_enabled: bool,
 }
@@ -245,7 +249,7 @@ impl HybridCache {
             match RedisCache::new(url, key_prefix).await {
                 Ok(cache) => {
                     info!("Redis cache initialized successfully");
-                    Some(cache)
+                    Some(Box::new(cache) as Box<dyn CacheBackend>)
                 }
                 Err(e) => {
                     warn!("Failed to initialize Redis cache, falling back to memory-only: {}", e);
@@ -267,6 +271,19 @@ impl HybridCache {
         })
     }
 
+    /// Use `primary` in place of Redis, e.g. a stand-in backend in tests
+    pub fn with_primary(
+        primary: Box<dyn CacheBackend>,
+        memory_capacity: usize,
+        default_ttl: Duration,
+    ) -> Result<Self, CacheError> {
+        Ok(Self {
+            redis: Some(primary),
+            memory: MemoryCache::new(memory_capacity, default_ttl)?,
+            fallback_enabled: true,
+        })
+    }
+
     pub async fn get_with_fallback<T>(&self, key: &str) -> Result<Option<T>, CacheError>
     where
         T: for<'de> Deserialize<'de> + Serialize,
@@ -277,6 +294,7 @@ impl HybridCache {
                 Ok(Some(data)) => {
                     let value: T = serde_json::from_slice(&data)?;
                     debug!("Cache hit from Redis for key: {}", key);
+                    metrics::increment_counter!(CACHE_HITS);
                     return Ok(Some(value));
                 }
                 Ok(None) => {
@@ -293,6 +311,7 @@ impl HybridCache {
             Ok(Some(data)) => {
                 let value: T = serde_json::from_slice(&data)?;
                 debug!("Cache hit from memory for key: {}", key);
+                metrics::increment_counter!(CACHE_HITS);
                 
                 // If we have Redis but it failed, try to repopulate it
                 if self.redis.is_some() && self.fallback_enabled {
@@ -305,6 +324,7 @@ impl HybridCache {
             }
             Ok(None) => {
                 debug!("Cache miss from memory for key: {}", key);
+                metrics::increment_counter!(CACHE_MISSES);
                 Ok(None)
             }
             Err(e) => {
@@ -383,7 +403,7 @@ pub struct CacheStats {
 }
 
 /// Cache configuration
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize, JsonSchema)]
 pub struct CacheConfig {
     pub redis_url: Option<String>,
     pub key_prefix: String,
@@ -392,6 +412,19 @@ pub struct CacheConfig {
     pub enable_fallback: bool,
 }
 
+// The Redis URL can carry a password
+impl std::fmt::Debug for CacheConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CacheConfig")
+            .field("redis_url", &self.redis_url.as_deref().map(mask_url_credentials))
+            .field("key_prefix", &self.key_prefix)
+            .field("memory_capacity", &self.memory_capacity)
+            .field("default_ttl_seconds", &self.default_ttl_seconds)
+            .field("enable_fallback", &self.enable_fallback)
+            .finish()
+    }
+}
+
 impl Default for CacheConfig {
     fn default() -> Self {
         Self {
@@ -404,6 +437,10 @@ impl Default for CacheConfig {
     }
 }
 
+/// Embeddings are keyed by their text and model, so they only go stale
+/// when nobody embeds that text again
+const EMBEDDING_TTL: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
 /// Cache manager for Jamey
 pub struct CacheManager {
     cache: HybridCache,
@@ -422,6 +459,11 @@ impl CacheManager {
         Ok(Self { cache, config })
     }
 
+    /// Manage an already built cache
+    pub fn with_cache(cache: HybridCache, config: CacheConfig) -> Self {
+        Self { cache, config }
+    }
+
     /// Get cache statistics
     pub async fn get_stats(&self) -> Result<CacheStats, CacheError> {
         Ok(CacheStats {
@@ -470,6 +512,41 @@ impl CacheManager {
         self.cache.get_with_fallback(&key).await
     }
 
+    /// Cache the embedding `model` produced for `text`
+    pub async fn cache_embedding(&self, model: &str, text: &str, embedding: &[f32]) -> Result<(), CacheError> {
+        self.cache
+            .set_with_fallback(&embedding_key(model, text), &embedding, Some(EMBEDDING_TTL))
+            .await
+    }
+
+    /// Embedding `model` produced for `text`, or for the same text spaced
+    /// differently
+    pub async fn get_cached_embedding(&self, model: &str, text: &str) -> Result<Option<Vec<f32>>, CacheError> {
+        self.cache.get_with_fallback(&embedding_key(model, text)).await
+    }
+
+    /// Store any serializable value under `key` for `ttl`; for callers with
+    /// their own key scheme, such as tool scratchpads
+    pub async fn set_value<T>(&self, key: &str, value: &T, ttl: Duration) -> Result<(), CacheError>
+    where
+        T: Serialize,
+    {
+        // Redis rejects a zero expiry
+        self.cache.set_with_fallback(key, value, Some(ttl.max(Duration::from_secs(1)))).await
+    }
+
+    /// Value stored with [`set_value`](Self::set_value), if it hasn't expired
+    pub async fn get_value<T>(&self, key: &str) -> Result<Option<T>, CacheError>
+    where
+        T: for<'de> Deserialize<'de> + Serialize,
+    {
+        self.cache.get_with_fallback(key).await
+    }
+
+    pub async fn delete_value(&self, key: &str) -> Result<bool, CacheError> {
+        self.cache.delete(key).await
+    }
+
     /// Invalidate memory cache
     pub async fn invalidate_memory(&self, id: Uuid) -> Result<bool, CacheError> {
         let key = format!("memory:{}", id);
@@ -482,6 +559,13 @@ impl CacheManager {
     }
 }
 
+/// `embedding:<model>:<sha256>` of `text` with runs of whitespace collapsed,
+/// so re-wrapped or re-indented text finds the same entry
+fn embedding_key(model: &str, text: &str) -> String {
+    let normalized = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    format!("embedding:{}:{:x}", model, Sha256::digest(normalized.as_bytes()))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -492,6 +576,7 @@ mod tests {
     async fn test_memory_cache() {
         let config = CacheConfig {
             redis_url: None,
+            key_prefix: "test".to_string(),
             memory_capacity: 10,
             default_ttl_seconds: 60,
             enable_fallback: false,
@@ -549,4 +634,16 @@ mod tests {
         let cached = cache.get_cached_memory(memory_id).await.unwrap();
         assert!(cached.is_none());
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_embedding_cache() {
+        let cache = CacheManager::new(CacheConfig::default()).await.unwrap();
+        let model = "openai/text-embedding-3-small";
+        cache.cache_embedding(model, "Paris is  the capital\nof France", &[0.5, 0.25]).await.unwrap();
+
+        let hit = cache.get_cached_embedding(model, "  Paris is the capital of France ").await.unwrap();
+        assert_eq!(hit, Some(vec![0.5, 0.25]));
+        assert!(cache.get_cached_embedding(model, "paris is the capital of france").await.unwrap().is_none());
+        assert!(cache.get_cached_embedding("other/model", "Paris is the capital of France").await.unwrap().is_none());
+    }
+}
@@ -12,26 +12,34 @@ use uuid::Uuid;
 use crate::cache::CacheManager;
 use crate::memory::{Memory, MemoryStore, PostgresMemoryStore, MemoryError};
 
-/// Cached memory store that wraps PostgreSQL with caching
-pub struct CachedMemoryStore {
-    postgres_store: Arc<PostgresMemoryStore>,
+/// Cached memory store that wraps a persistent store (PostgreSQL unless
+/// told otherwise) with caching
+pub struct CachedMemoryStore<S = PostgresMemoryStore> {
+    store: Arc<S>,
     cache: Arc<CacheManager>,
 }
 
-impl CachedMemoryStore {
+impl<S: MemoryStore + Send + Sync> CachedMemoryStore<S> {
     pub async fn new(
-        postgres_store: PostgresMemoryStore,
+        store: S,
         cache_config: crate::cache::CacheConfig,
     ) -> Result<Self> {
-        let cache = Arc::new(CacheManager::new(cache_config).await?);
-        let postgres_store = Arc::new(postgres_store);
-        
+        let cache = CacheManager::new(cache_config).await?;
         info!("Initialized cached memory store with Redis fallback");
-        
-        Ok(Self {
-            postgres_store,
-            cache,
-        })
+        Ok(Self::with_cache(store, cache))
+    }
+
+    /// Wrap `store` with an already built cache
+    pub fn with_cache(store: S, cache: CacheManager) -> Self {
+        Self {
+            store: Arc::new(store),
+            cache: Arc::new(cache),
+        }
+    }
+
+    /// The persistent store behind the cache
+    pub fn inner_store(&self) -> &S {
+        &self.store
     }
 
     /// Invalidate cache for a specific memory entry
@@ -46,7 +54,7 @@ impl CachedMemoryStore {
         info!("Warming up cache with {} recent memories", limit);
         
         let dummy_embedding = vec![0.0; 1536];
-        let recent_memories = self.postgres_store.search(&dummy_embedding, limit).await?;
+        let recent_memories = self.store.search(&dummy_embedding, limit).await?;
         
         let mut cached_count = 0;
         for memory in recent_memories {
@@ -102,12 +110,12 @@ impl CachedMemoryStore {
 }
 
 #[async_trait]
-impl MemoryStore for CachedMemoryStore {
+impl<S: MemoryStore + Send + Sync> MemoryStore for CachedMemoryStore<S> {
     async fn store(&self, memory: Memory) -> Result<Uuid> {
         debug!("Storing memory with caching: {}", memory.id);
         
         // Store in PostgreSQL first
-        let id = self.postgres_store.store(memory.clone()).await?;
+        let id = self.store.store(memory.clone()).await?;
         
         // Cache the stored memory
         if let Err(e) = self.cache.cache_memory(&memory).await {
@@ -135,7 +143,7 @@ impl MemoryStore for CachedMemoryStore {
         }
         
         // Fallback to PostgreSQL
-        let memory = self.postgres_store.retrieve(id).await?;
+        let memory = self.store.retrieve(id).await?;
         
         // Cache the retrieved memory for future requests
         if let Err(e) = self.cache.cache_memory(&memory).await {
@@ -199,7 +207,7 @@ impl MemoryStore for CachedMemoryStore {
         }
         
         // Fallback to PostgreSQL
-        let results = self.postgres_store.search(query_embedding, limit).await?;
+        let results = self.store.search(query_embedding, limit).await?;
         
         // Validate results before caching
         Self::validate_search_results(&results)?;
@@ -230,10 +238,10 @@ impl MemoryStore for CachedMemoryStore {
         }
         
         // Update in PostgreSQL
-        self.postgres_store.update(id, content, embedding).await?;
+        self.store.update(id, content, embedding).await?;
         
         // Retrieve updated memory and update cache immediately
-        match self.postgres_store.retrieve(id).await {
+        match self.store.retrieve(id).await {
             Ok(updated_memory) => {
                 if let Err(e) = self.cache.cache_memory(&updated_memory).await {
                     warn!("Failed to update cache for memory {}: {}", id, e);
@@ -255,7 +263,7 @@ impl MemoryStore for CachedMemoryStore {
         debug!("Deleting memory with cache invalidation: {}", id);
         
         // Delete from PostgreSQL
-        self.postgres_store.delete(id).await?;
+        self.store.delete(id).await?;
         
         // Remove from cache
         if let Err(e) = self.invalidate_cache(id).await {
@@ -270,7 +278,7 @@ impl MemoryStore for CachedMemoryStore {
         
         // Pagination results are not cached as they change frequently
         // and caching would require complex invalidation logic
-        self.postgres_store.list_paginated(limit, offset).await
+        self.store.list_paginated(limit, offset).await
     }
 }
 
@@ -377,7 +385,7 @@ impl AdvancedCachedMemoryStore {
                 
                 if access_count > 10 {
                     // Frequently accessed items - update instead of invalidate
-                    if let Ok(memory) = self.inner.postgres_store.retrieve(id).await {
+                    if let Ok(memory) = self.inner.store.retrieve(id).await {
                         if let Err(e) = cache.cache_memory(&memory).await {
                             warn!("Failed to update frequently accessed memory {}: {}", id, e);
                             // Fallback to invalidation
@@ -425,7 +433,7 @@ impl MemoryStore for AdvancedCachedMemoryStore {
 
     async fn update(&self, id: Uuid, content: &str, embedding: &[f32]) -> Result<()> {
         // Update in database first
-        self.inner.postgres_store.update(id, content, embedding).await?;
+        self.inner.store.update(id, content, embedding).await?;
         
         // Invalidate cache according to strategy
         self.invalidate_with_strategy(id).await?;
@@ -435,7 +443,7 @@ impl MemoryStore for AdvancedCachedMemoryStore {
 
     async fn delete(&self, id: Uuid) -> Result<()> {
         // Delete from database first
-        self.inner.postgres_store.delete(id).await?;
+        self.inner.store.delete(id).await?;
         
         // Invalidate cache according to strategy
         self.invalidate_with_strategy(id).await?;
@@ -0,0 +1,443 @@
+//! Fault injection for resilience tests
+//!
+//! [`FaultInjectingStore`] and [`FaultInjectingCache`] wrap a real
+//! [`MemoryStore`] or [`CacheBackend`] and, per operation, add latency, fail
+//! outright or fail partway according to a [`FaultInjector`]. Decisions come
+//! from a seeded RNG, so a test that replays the same calls sees the same
+//! faults every run; [`FaultInjector::fail_next`] scripts exact failures when
+//! probabilities are too coarse.
+//!
+//! What a partial failure means depends on the operation:
+//!
+//! | Operation | Partial failure |
+//! |-----------|-----------------|
+//! | store, update, delete | The write lands but an error is returned |
+//! | search, list | Only the first half of the results come back |
+//! | retrieve | Same as an error |
+//! | cache get, exists | A spurious miss |
+//! | cache set, delete, clear | Reported as done but silently skipped |
+//!
+//! Built with the `fault-injection` feature (and always for this crate's own
+//! tests).
+
+use crate::cache::{CacheBackend, CacheError};
+use crate::memory::{Memory, MemoryStore};
+use anyhow::Result;
+use async_trait::async_trait;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::Duration;
+use thiserror::Error;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Operation {
+    Store,
+    Retrieve,
+    Search,
+    Update,
+    Delete,
+    List,
+    CacheGet,
+    CacheSet,
+    CacheDelete,
+    CacheClear,
+    CacheExists,
+}
+
+impl std::fmt::Display for Operation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Operation::Store => "store",
+            Operation::Retrieve => "retrieve",
+            Operation::Search => "search",
+            Operation::Update => "update",
+            Operation::Delete => "delete",
+            Operation::List => "list",
+            Operation::CacheGet => "cache get",
+            Operation::CacheSet => "cache set",
+            Operation::CacheDelete => "cache delete",
+            Operation::CacheClear => "cache clear",
+            Operation::CacheExists => "cache exists",
+        };
+        f.write_str(name)
+    }
+}
+
+/// The error returned for an injected failure
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+#[error("Injected {0} fault")]
+pub struct InjectedFault(pub Operation);
+
+/// Probabilities for one operation; each call rolls for latency, then for
+/// an error, then for a partial failure
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Fault {
+    pub latency: Duration,
+    pub latency_rate: f64,
+    pub error_rate: f64,
+    pub partial_rate: f64,
+}
+
+impl Fault {
+    pub fn error(rate: f64) -> Self {
+        Self { error_rate: rate, ..Default::default() }
+    }
+
+    pub fn partial(rate: f64) -> Self {
+        Self { partial_rate: rate, ..Default::default() }
+    }
+
+    pub fn latency(latency: Duration, rate: f64) -> Self {
+        Self { latency, latency_rate: rate, ..Default::default() }
+    }
+
+    pub fn with_error(mut self, rate: f64) -> Self {
+        self.error_rate = rate;
+        self
+    }
+
+    pub fn with_partial(mut self, rate: f64) -> Self {
+        self.partial_rate = rate;
+        self
+    }
+
+    pub fn with_latency(mut self, latency: Duration, rate: f64) -> Self {
+        self.latency = latency;
+        self.latency_rate = rate;
+        self
+    }
+}
+
+/// What happened to one call
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Passed,
+    Failed,
+    Partial,
+}
+
+struct InjectorState {
+    rng: StdRng,
+    scripted: HashMap<Operation, VecDeque<Outcome>>,
+    history: Vec<(Operation, Outcome)>,
+}
+
+/// Decides, call by call, which faults to inject
+///
+/// Share one injector between a store and a cache wrapper to get a single
+/// reproducible sequence across both.
+pub struct FaultInjector {
+    faults: HashMap<Operation, Fault>,
+    state: Mutex<InjectorState>,
+}
+
+impl FaultInjector {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            faults: HashMap::new(),
+            state: Mutex::new(InjectorState {
+                rng: StdRng::seed_from_u64(seed),
+                scripted: HashMap::new(),
+                history: Vec::new(),
+            }),
+        }
+    }
+
+    pub fn with_fault(mut self, operation: Operation, fault: Fault) -> Self {
+        self.faults.insert(operation, fault);
+        self
+    }
+
+    /// Fail the next `count` calls to `operation`, ahead of any probabilities
+    pub fn fail_next(&self, operation: Operation, count: usize) {
+        self.script(operation, Outcome::Failed, count);
+    }
+
+    /// Fail the next `count` calls to `operation` partway
+    pub fn partially_fail_next(&self, operation: Operation, count: usize) {
+        self.script(operation, Outcome::Partial, count);
+    }
+
+    fn script(&self, operation: Operation, outcome: Outcome, count: usize) {
+        let mut state = self.lock();
+        state.scripted.entry(operation).or_default().extend(std::iter::repeat_n(outcome, count));
+    }
+
+    /// Every call seen so far, in order
+    pub fn history(&self) -> Vec<(Operation, Outcome)> {
+        self.lock().history.clone()
+    }
+
+    /// How many calls to `operation` ended with `outcome`
+    pub fn count(&self, operation: Operation, outcome: Outcome) -> usize {
+        self.lock()
+            .history
+            .iter()
+            .filter(|(op, out)| *op == operation && *out == outcome)
+            .count()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, InjectorState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Roll for `operation`, sleeping first when latency is drawn
+    pub async fn decide(&self, operation: Operation) -> Outcome {
+        let fault = self.faults.get(&operation).cloned().unwrap_or_default();
+        let (delay, outcome) = {
+            let mut state = self.lock();
+            // Always draw three numbers so one operation's settings can't
+            // shift the sequence another operation sees
+            let rolls: [f64; 3] = [state.rng.random(), state.rng.random(), state.rng.random()];
+            let delay = (rolls[0] < fault.latency_rate).then_some(fault.latency);
+            let outcome = match state.scripted.get_mut(&operation).and_then(VecDeque::pop_front) {
+                Some(outcome) => outcome,
+                None if rolls[1] < fault.error_rate => Outcome::Failed,
+                None if rolls[2] < fault.partial_rate => Outcome::Partial,
+                None => Outcome::Passed,
+            };
+            state.history.push((operation, outcome));
+            (delay, outcome)
+        };
+        if let Some(delay) = delay.filter(|d| !d.is_zero()) {
+            tokio::time::sleep(delay).await;
+        }
+        outcome
+    }
+}
+
+fn truncated<T>(mut items: Vec<T>) -> Vec<T> {
+    items.truncate(items.len() / 2);
+    items
+}
+
+/// A [`MemoryStore`] that misbehaves on cue
+pub struct FaultInjectingStore<S> {
+    inner: S,
+    injector: std::sync::Arc<FaultInjector>,
+}
+
+impl<S> FaultInjectingStore<S> {
+    pub fn new(inner: S, injector: std::sync::Arc<FaultInjector>) -> Self {
+        Self { inner, injector }
+    }
+
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    /// Apply a write-style outcome: partial failures still write
+    async fn write<T>(&self, operation: Operation, write: impl std::future::Future<Output = Result<T>>) -> Result<T> {
+        match self.injector.decide(operation).await {
+            Outcome::Failed => Err(InjectedFault(operation).into()),
+            Outcome::Partial => {
+                write.await?;
+                Err(InjectedFault(operation).into())
+            }
+            Outcome::Passed => write.await,
+        }
+    }
+}
+
+#[async_trait]
+impl<S: MemoryStore + Send + Sync> MemoryStore for FaultInjectingStore<S> {
+    async fn store(&self, memory: Memory) -> Result<Uuid> {
+        self.write(Operation::Store, self.inner.store(memory)).await
+    }
+
+    async fn retrieve(&self, id: Uuid) -> Result<Memory> {
+        match self.injector.decide(Operation::Retrieve).await {
+            Outcome::Passed => self.inner.retrieve(id).await,
+            _ => Err(InjectedFault(Operation::Retrieve).into()),
+        }
+    }
+
+    async fn search(&self, query_embedding: &[f32], limit: usize) -> Result<Vec<Memory>> {
+        match self.injector.decide(Operation::Search).await {
+            Outcome::Failed => Err(InjectedFault(Operation::Search).into()),
+            Outcome::Partial => Ok(truncated(self.inner.search(query_embedding, limit).await?)),
+            Outcome::Passed => self.inner.search(query_embedding, limit).await,
+        }
+    }
+
+    async fn update(&self, id: Uuid, content: &str, embedding: &[f32]) -> Result<()> {
+        self.write(Operation::Update, self.inner.update(id, content, embedding)).await
+    }
+
+    async fn delete(&self, id: Uuid) -> Result<()> {
+        self.write(Operation::Delete, self.inner.delete(id)).await
+    }
+
+    async fn list_paginated(&self, limit: usize, offset: usize) -> Result<(Vec<Memory>, i64)> {
+        match self.injector.decide(Operation::List).await {
+            Outcome::Failed => Err(InjectedFault(Operation::List).into()),
+            Outcome::Partial => {
+                let (memories, total) = self.inner.list_paginated(limit, offset).await?;
+                Ok((truncated(memories), total))
+            }
+            Outcome::Passed => self.inner.list_paginated(limit, offset).await,
+        }
+    }
+}
+
+/// A [`CacheBackend`] that misbehaves on cue
+pub struct FaultInjectingCache<C> {
+    inner: C,
+    injector: std::sync::Arc<FaultInjector>,
+}
+
+impl<C> FaultInjectingCache<C> {
+    pub fn new(inner: C, injector: std::sync::Arc<FaultInjector>) -> Self {
+        Self { inner, injector }
+    }
+
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+}
+
+fn cache_fault(operation: Operation) -> CacheError {
+    CacheError::Connection(InjectedFault(operation).to_string())
+}
+
+#[async_trait]
+impl<C: CacheBackend> CacheBackend for FaultInjectingCache<C> {
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>, CacheError> {
+        match self.injector.decide(Operation::CacheGet).await {
+            Outcome::Failed => Err(cache_fault(Operation::CacheGet)),
+            Outcome::Partial => Ok(None),
+            Outcome::Passed => self.inner.get(key).await,
+        }
+    }
+
+    async fn set(&self, key: &str, value: Vec<u8>, ttl: Option<Duration>) -> Result<(), CacheError> {
+        match self.injector.decide(Operation::CacheSet).await {
+            Outcome::Failed => Err(cache_fault(Operation::CacheSet)),
+            Outcome::Partial => Ok(()),
+            Outcome::Passed => self.inner.set(key, value, ttl).await,
+        }
+    }
+
+    async fn delete(&self, key: &str) -> Result<bool, CacheError> {
+        match self.injector.decide(Operation::CacheDelete).await {
+            Outcome::Failed => Err(cache_fault(Operation::CacheDelete)),
+            Outcome::Partial => Ok(false),
+            Outcome::Passed => self.inner.delete(key).await,
+        }
+    }
+
+    async fn clear(&self) -> Result<(), CacheError> {
+        match self.injector.decide(Operation::CacheClear).await {
+            Outcome::Failed => Err(cache_fault(Operation::CacheClear)),
+            Outcome::Partial => Ok(()),
+            Outcome::Passed => self.inner.clear().await,
+        }
+    }
+
+    async fn exists(&self, key: &str) -> Result<bool, CacheError> {
+        match self.injector.decide(Operation::CacheExists).await {
+            Outcome::Failed => Err(cache_fault(Operation::CacheExists)),
+            Outcome::Partial => Ok(false),
+            Outcome::Passed => self.inner.exists(key).await,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::{CacheConfig, CacheManager, HybridCache, MemoryCache};
+    use crate::cached_memory::CachedMemoryStore;
+    use crate::memory::{InMemoryStore, MemoryType};
+    use chrono::Utc;
+    use std::sync::Arc;
+
+    fn memory(content: &str) -> Memory {
+        Memory {
+            id: Uuid::new_v4(),
+            memory_type: MemoryType::Knowledge,
+            content: content.to_string(),
+            embedding: vec![0.1; 8],
+            metadata: serde_json::json!({}),
+            created_at: Utc::now(),
+            last_accessed: Utc::now(),
+        }
+    }
+
+    fn cached_store(
+        injector: &Arc<FaultInjector>,
+    ) -> CachedMemoryStore<FaultInjectingStore<InMemoryStore>> {
+        let primary = FaultInjectingCache::new(
+            MemoryCache::new(100, Duration::from_secs(60)).unwrap(),
+            Arc::clone(injector),
+        );
+        let cache = HybridCache::with_primary(Box::new(primary), 100, Duration::from_secs(60)).unwrap();
+        CachedMemoryStore::with_cache(
+            FaultInjectingStore::new(InMemoryStore::new(), Arc::clone(injector)),
+            CacheManager::with_cache(cache, CacheConfig::default()),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_same_seed_same_faults() {
+        let run = |seed| async move {
+            let injector = FaultInjector::new(seed).with_fault(Operation::Retrieve, Fault::error(0.5).with_partial(0.5));
+            for _ in 0..20 {
+                injector.decide(Operation::Retrieve).await;
+            }
+            injector.history()
+        };
+        let first = run(7).await;
+        assert_eq!(first, run(7).await);
+        assert_ne!(first, run(8).await);
+        assert!(first.iter().any(|(_, o)| *o == Outcome::Failed));
+        assert!(first.iter().any(|(_, o)| *o == Outcome::Passed));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_latency_and_scripted_failures() {
+        let injector = FaultInjector::new(1).with_fault(Operation::Search, Fault::latency(Duration::from_secs(2), 1.0));
+        let started = tokio::time::Instant::now();
+        assert_eq!(injector.decide(Operation::Search).await, Outcome::Passed);
+        assert!(started.elapsed() >= Duration::from_secs(2));
+
+        injector.fail_next(Operation::Store, 2);
+        assert_eq!(injector.decide(Operation::Store).await, Outcome::Failed);
+        assert_eq!(injector.decide(Operation::Store).await, Outcome::Failed);
+        assert_eq!(injector.decide(Operation::Store).await, Outcome::Passed);
+    }
+
+    #[tokio::test]
+    async fn test_cached_store_survives_cache_and_store_faults() {
+        let injector = Arc::new(
+            FaultInjector::new(42)
+                .with_fault(Operation::CacheGet, Fault::error(0.5))
+                .with_fault(Operation::CacheSet, Fault::error(0.5)),
+        );
+        let store = cached_store(&injector);
+
+        let mut ids = Vec::new();
+        for i in 0..10 {
+            ids.push(store.store(memory(&format!("memory {}", i))).await.unwrap());
+        }
+        // The primary cache failing half the time never reaches callers
+        for (i, id) in ids.iter().enumerate() {
+            assert_eq!(store.retrieve(*id).await.unwrap().content, format!("memory {}", i));
+        }
+        assert!(injector.count(Operation::CacheGet, Outcome::Failed) > 0);
+
+        // With the database down, cached entries are still served
+        injector.fail_next(Operation::Retrieve, 10);
+        assert_eq!(store.retrieve(ids[0]).await.unwrap().content, "memory 0");
+        assert_eq!(injector.count(Operation::Retrieve, Outcome::Failed), 0);
+
+        // A write whose acknowledgement is lost reports failure but lands
+        injector.partially_fail_next(Operation::Store, 1);
+        let lost_ack = memory("lost ack");
+        assert!(store.store(lost_ack.clone()).await.is_err());
+        assert_eq!(store.inner_store().inner().retrieve(lost_ack.id).await.unwrap().content, "lost ack");
+    }
+}
@@ -4,25 +4,52 @@
 //! It includes PostgreSQL-backed vector storage with similarity search capabilities.
 
 pub mod memory;
+pub mod maintenance;
+pub mod partition;
+pub mod quantization;
+pub mod tiering;
+pub mod sync;
 pub mod cache;
 pub mod cached_memory;
 pub mod pool;
 pub mod secrets;
+pub mod secret_backends;
 pub mod secure_logging;
 pub mod profiling;
+pub mod redaction;
+pub mod scoring;
+pub mod supervisor;
+pub mod usage;
+#[cfg(any(test, feature = "fault-injection"))]
+pub mod fault;
 
-pub use memory::{Memory, MemoryError, MemoryStore, MemoryType, PostgresMemoryStore};
+pub use memory::{InMemoryStore, Memory, MemoryError, MemoryStore, MemoryType, PostgresMemoryStore};
+pub use maintenance::{Consolidator, Embedder, JobProgress, MemoryStats};
+pub use partition::PartitionInfo;
+pub use quantization::{Quantization, QuantizationConfig};
+pub use tiering::TieringReport;
+pub use sync::{ApplyReport, SyncChange, SyncCursor, SyncedMemory, Tombstone};
 pub use cache::{CacheManager, CacheConfig, CacheError, CacheBackend, RedisCache, MemoryCache, HybridCache};
 pub use cached_memory::{CachedMemoryStore, AdvancedCachedMemoryStore, CacheStats, InvalidationStrategy};
 pub use pool::{ConnectionPools, PoolConfig, PostgresPoolConfig, RedisPoolConfig, HealthStatus, PoolStatus};
 pub use profiling::{TimingGuard, PerformanceThresholds, PerformanceMetrics};
+pub use redaction::{RedactionConfig, RedactionError, Redactor};
+pub use scoring::RetrievalWeights;
+pub use supervisor::{Supervisor, SupervisorConfig};
+pub use usage::{PostgresUsageStore, UsageEntry, UsageGrouping, UsageQuery, UsageRetention, UsageStoreError, UsageTotals};
+pub use secrets::{SecretManager, SecretError, SecretRotation, SecretVersion};
+pub use secret_backends::{
+    SecretBackend, KeyringBackend, VaultBackend, AwsSecretsManagerBackend, EncryptedFileBackend,
+    backend_from_env,
+};
 
 /// Re-export common types used throughout the crate
 pub mod prelude {
     pub use super::memory::{Memory, MemoryError, MemoryStore, MemoryType, PostgresMemoryStore};
     pub use super::pool::{ConnectionPools, PoolConfig, PostgresPoolConfig, RedisPoolConfig};
-    pub use super::secrets::{SecretManager, SecretError};
-    pub use super::secure_logging::{redact_sensitive_data, LogConfig, init_secure_logging};
+    pub use super::secrets::{SecretManager, SecretError, SecretRotation, SecretVersion};
+    pub use super::secret_backends::SecretBackend;
+    pub use super::secure_logging::{redact_sensitive_data, redact_log_line, redact_json_secrets, mask_url_credentials, Masked, RedactingMakeWriter, LogConfig, init_secure_logging};
     pub use super::profiling::{TimingGuard, PerformanceThresholds, PerformanceMetrics};
     pub use chrono::{DateTime, Utc};
     pub use uuid::Uuid;
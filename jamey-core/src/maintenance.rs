@@ -0,0 +1,655 @@
+//! Memory maintenance jobs
+//!
+//! Whole-table batch jobs: statistics, near-duplicate removal, consolidation
+//! of closely related memories, re-embedding with a different model and
+//! selecting everything tied to a session, user or pattern for deletion. Jobs
+//! that need a model take an [`Embedder`] or [`Consolidator`], so this crate
+//! stays independent of the LLM provider.
+
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use regex::Regex;
+use tokio_postgres::types::ToSql;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use tracing::{instrument, warn};
+use uuid::Uuid;
+
+use crate::memory::{Memory, MemoryError, MemoryStore, MemoryType, PostgresMemoryStore};
+use crate::profiling::TimingGuard;
+use crate::sync::delete_with_tombstones;
+
+/// Most memories folded into one group, so a dense cluster can't swallow the table
+const MAX_GROUP_SIZE: i64 = 20;
+
+/// Rows fetched per page while re-embedding
+const REEMBED_PAGE_SIZE: i64 = 100;
+
+/// Pinned memories are never grouped, merged or removed as duplicates
+pub(crate) const UNPINNED: &str = "NOT (metadata @> '{\"pinned\": true}'::jsonb)";
+
+/// Rows fetched per page while selecting memories
+const SELECT_PAGE_SIZE: i64 = 500;
+
+/// Rough English average used for token estimates
+const CHARS_PER_TOKEN: i64 = 4;
+
+/// Computes embeddings with a specific model
+#[async_trait]
+pub trait Embedder: Send + Sync {
+    /// Model name recorded as `embedding_model` in memory metadata
+    fn model(&self) -> &str;
+    async fn embed(&self, text: &str) -> Result<Vec<f32>>;
+}
+
+/// Merges related memories into one
+#[async_trait]
+pub trait Consolidator: Send + Sync {
+    /// Combined content for `contents`, given oldest first
+    async fn merge(&self, contents: &[String]) -> Result<String>;
+}
+
+/// Where a job has got to
+#[derive(Debug, Clone, Copy)]
+pub struct JobProgress {
+    pub stage: &'static str,
+    pub done: u64,
+    pub total: u64,
+}
+
+/// Callback invoked as a job advances
+pub type ProgressFn<'a> = &'a (dyn Fn(JobProgress) + Send + Sync);
+
+/// Overview of the memory table
+#[derive(Debug, Clone, Serialize)]
+pub struct MemoryStats {
+    pub total: i64,
+    pub by_type: Vec<(String, i64)>,
+    pub by_namespace: Vec<(String, i64)>,
+    pub by_embedding_model: Vec<(String, i64)>,
+    pub oldest: Option<DateTime<Utc>>,
+    pub newest: Option<DateTime<Utc>>,
+    /// Not read in the last 30 days
+    pub stale: i64,
+    pub avg_content_chars: f64,
+    /// Table, index and TOAST size
+    pub table_bytes: i64,
+    /// Moved to the cold table; not counted in `total`
+    pub archived: i64,
+}
+
+/// A memory similar to a group's keeper
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SimilarMemory {
+    pub id: Uuid,
+    /// Cosine similarity to the keeper
+    pub similarity: f64,
+}
+
+/// The oldest memory of a cluster and the ones similar to it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SimilarGroup {
+    pub keep: Uuid,
+    pub members: Vec<SimilarMemory>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DedupeReport {
+    pub scanned: usize,
+    pub groups: Vec<SimilarGroup>,
+    /// Memories deleted (0 on a dry run)
+    pub removed: usize,
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ConsolidateReport {
+    pub scanned: usize,
+    pub clusters: Vec<SimilarGroup>,
+    /// Merged memories created (empty on a dry run)
+    pub created: Vec<Uuid>,
+    /// Originals deleted after merging
+    pub removed: usize,
+    /// Clusters left alone because merging failed
+    pub errors: Vec<String>,
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReembedReport {
+    pub model: String,
+    /// Memories not yet embedded with `model`
+    pub pending: u64,
+    pub updated: u64,
+    pub failed: Vec<(Uuid, String)>,
+    pub estimated_tokens: u64,
+    pub dry_run: bool,
+}
+
+/// Which memories a deletion request covers; a memory is selected when any
+/// criterion that is set matches it
+#[derive(Debug, Clone, Default)]
+pub struct MemorySelector {
+    /// Memories whose `session_id` metadata is one of these
+    pub sessions: HashSet<Uuid>,
+    /// Memories whose `user` metadata is this
+    pub user: Option<String>,
+    /// Memories whose content matches
+    pub pattern: Option<Regex>,
+}
+
+impl MemorySelector {
+    pub fn is_empty(&self) -> bool {
+        self.sessions.is_empty() && self.user.is_none() && self.pattern.is_none()
+    }
+
+    pub fn matches(&self, content: &str, metadata: &serde_json::Value) -> bool {
+        let session = metadata
+            .get("session_id")
+            .and_then(|v| v.as_str())
+            .and_then(|s| Uuid::parse_str(s).ok());
+        session.is_some_and(|id| self.sessions.contains(&id))
+            || self.user.as_deref().is_some_and(|user| metadata.get("user").and_then(|v| v.as_str()) == Some(user))
+            || self.pattern.as_ref().is_some_and(|pattern| pattern.is_match(content))
+    }
+}
+
+/// A memory picked by a [`MemorySelector`]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SelectedMemory {
+    pub id: Uuid,
+    /// Session it was recorded in, from its metadata
+    pub session_id: Option<Uuid>,
+}
+
+impl PostgresMemoryStore {
+    #[instrument(skip(self))]
+    pub async fn stats(&self) -> Result<MemoryStats> {
+        let _timer = TimingGuard::new("memory_stats");
+        let client = self.pool.get().await?;
+
+        let row = client
+            .query_one(
+                "SELECT COUNT(*) AS total,
+                        MIN(created_at) AS oldest,
+                        MAX(created_at) AS newest,
+                        COUNT(*) FILTER (WHERE last_accessed < NOW() - INTERVAL '30 days') AS stale,
+                        COALESCE(AVG(LENGTH(content)), 0)::float8 AS avg_chars,
+                        (SELECT SUM(pg_total_relation_size(c.oid)) FROM pg_class c
+                         WHERE c.oid = 'memories'::regclass
+                            OR c.oid IN (SELECT inhrelid FROM pg_inherits
+                                         WHERE inhparent = 'memories'::regclass))::bigint AS table_bytes,
+                        (SELECT COUNT(*) FROM memories_cold) AS archived
+                 FROM memories",
+                &[],
+            )
+            .await?;
+
+        let mut breakdowns = Vec::with_capacity(3);
+        for key in [
+            "memory_type",
+            "COALESCE(NULLIF(namespace, ''), '(none)')",
+            "COALESCE(metadata->>'embedding_model', '(unrecorded)')",
+        ] {
+            let rows = client
+                .query(
+                    &format!(
+                        "SELECT {} AS key, COUNT(*) AS count FROM memories GROUP BY 1 ORDER BY 2 DESC, 1",
+                        key
+                    ),
+                    &[],
+                )
+                .await?;
+            breakdowns.push(
+                rows.iter()
+                    .map(|r| (r.get::<_, String>("key"), r.get::<_, i64>("count")))
+                    .collect::<Vec<_>>(),
+            );
+        }
+        let by_embedding_model = breakdowns.pop().unwrap_or_default();
+        let by_namespace = breakdowns.pop().unwrap_or_default();
+        let by_type = breakdowns.pop().unwrap_or_default();
+
+        Ok(MemoryStats {
+            total: row.get("total"),
+            by_type,
+            by_namespace,
+            by_embedding_model,
+            oldest: row.get("oldest"),
+            newest: row.get("newest"),
+            stale: row.get("stale"),
+            avg_content_chars: row.get("avg_chars"),
+            table_bytes: row.get("table_bytes"),
+            archived: row.get("archived"),
+        })
+    }
+
+    /// Cluster memories of the same type whose cosine similarity to the
+    /// oldest member is at least `threshold`. Each memory joins at most one
+    /// group, and pinned memories join none.
+    #[instrument(skip(self, progress))]
+    pub async fn find_similar_groups(
+        &self,
+        threshold: f64,
+        progress: ProgressFn<'_>,
+    ) -> Result<(usize, Vec<SimilarGroup>)> {
+        validate_threshold(threshold)?;
+        let _timer = TimingGuard::new("memory_find_similar");
+        let client = self.pool.get().await?;
+
+        let ids: Vec<Uuid> = client
+            .query(&format!("SELECT id FROM memories WHERE {} ORDER BY created_at, id", UNPINNED), &[])
+            .await?
+            .iter()
+            .map(|r| r.get("id"))
+            .collect();
+        let total = ids.len() as u64;
+
+        let mut claimed = HashSet::new();
+        let mut groups = Vec::new();
+        for (index, id) in ids.iter().enumerate() {
+            progress(JobProgress { stage: "scanning", done: index as u64, total });
+            if claimed.contains(id) {
+                continue;
+            }
+            let rows = client
+                .query(
+                    &format!(
+                        "SELECT m.id, (1 - ({m} <=> {s}))::float8 AS similarity
+                         FROM memories m, memories s
+                         WHERE s.id = $1 AND m.id <> s.id AND m.memory_type = s.memory_type
+                           AND ({m} <=> {s}) <= $2 AND m.{unpinned}
+                         ORDER BY {m} <=> {s}
+                         LIMIT $3",
+                        m = self.embedding_sql_of("m"),
+                        s = self.embedding_sql_of("s"),
+                        unpinned = UNPINNED
+                    ),
+                    &[id, &(1.0 - threshold), &MAX_GROUP_SIZE],
+                )
+                .await?;
+            let neighbours = rows
+                .iter()
+                .map(|r| SimilarMemory { id: r.get("id"), similarity: r.get("similarity") })
+                .collect();
+            if let Some(group) = claim_group(*id, neighbours, &mut claimed) {
+                groups.push(group);
+            }
+        }
+        progress(JobProgress { stage: "scanning", done: total, total });
+        Ok((ids.len(), groups))
+    }
+
+    /// Delete near-identical memories, keeping the oldest of each group
+    #[instrument(skip(self, progress))]
+    pub async fn dedupe(&self, threshold: f64, dry_run: bool, progress: ProgressFn<'_>) -> Result<DedupeReport> {
+        let (scanned, groups) = self.find_similar_groups(threshold, progress).await?;
+        let removed = if dry_run {
+            0
+        } else {
+            self.remove_duplicates(&groups, progress).await?
+        };
+        Ok(DedupeReport { scanned, groups, removed, dry_run })
+    }
+
+    /// Delete every group's non-keeper members, e.g. after reviewing a dry
+    /// run; members pinned since are kept
+    pub async fn remove_duplicates(&self, groups: &[SimilarGroup], progress: ProgressFn<'_>) -> Result<usize> {
+        let client = self.pool.get().await?;
+        let total = groups.len() as u64;
+        let mut removed = 0;
+        for (index, group) in groups.iter().enumerate() {
+            progress(JobProgress { stage: "removing", done: index as u64, total });
+            let ids: Vec<Uuid> = group.members.iter().map(|m| m.id).collect();
+            removed += client
+                .execute(&delete_with_tombstones("memories", &format!("id = ANY($1) AND {}", UNPINNED)), &[&ids])
+                .await? as usize;
+        }
+        progress(JobProgress { stage: "removing", done: total, total });
+        Ok(removed)
+    }
+
+    /// Replace each cluster of related memories with a single merged memory
+    #[instrument(skip(self, consolidator, embedder, progress))]
+    pub async fn consolidate(
+        &self,
+        threshold: f64,
+        consolidator: &dyn Consolidator,
+        embedder: &dyn Embedder,
+        dry_run: bool,
+        progress: ProgressFn<'_>,
+    ) -> Result<ConsolidateReport> {
+        let (scanned, clusters) = self.find_similar_groups(threshold, progress).await?;
+        let mut report = ConsolidateReport {
+            scanned,
+            clusters,
+            created: Vec::new(),
+            removed: 0,
+            errors: Vec::new(),
+            dry_run,
+        };
+        if !dry_run {
+            self.merge_clusters(&mut report, consolidator, embedder, progress).await;
+        }
+        Ok(report)
+    }
+
+    /// Merge the clusters found by a dry run, recording the outcome in `report`.
+    /// A cluster that fails to merge is left as it was.
+    pub async fn merge_clusters(
+        &self,
+        report: &mut ConsolidateReport,
+        consolidator: &dyn Consolidator,
+        embedder: &dyn Embedder,
+        progress: ProgressFn<'_>,
+    ) {
+        report.dry_run = false;
+        let total = report.clusters.len() as u64;
+        for (index, cluster) in report.clusters.iter().enumerate() {
+            progress(JobProgress { stage: "merging", done: index as u64, total });
+            match self.merge_cluster(cluster, consolidator, embedder).await {
+                Ok((id, removed)) => {
+                    report.created.push(id);
+                    report.removed += removed;
+                }
+                Err(e) => {
+                    warn!("Failed to consolidate cluster around {}: {}", cluster.keep, e);
+                    report.errors.push(format!("{}: {}", cluster.keep, e));
+                }
+            }
+        }
+        progress(JobProgress { stage: "merging", done: total, total });
+    }
+
+    /// Merge one cluster into a new memory, deleting the originals. Returns
+    /// the new memory's ID and how many originals went.
+    pub async fn merge_cluster(
+        &self,
+        cluster: &SimilarGroup,
+        consolidator: &dyn Consolidator,
+        embedder: &dyn Embedder,
+    ) -> Result<(Uuid, usize)> {
+        let ids: Vec<Uuid> = std::iter::once(cluster.keep)
+            .chain(cluster.members.iter().map(|m| m.id))
+            .collect();
+        let client = self.pool.get().await?;
+        let rows = client
+            .query(
+                &format!(
+                    "SELECT id, memory_type, content, metadata FROM memories
+                     WHERE id = ANY($1) AND {} ORDER BY created_at, id",
+                    UNPINNED
+                ),
+                &[&ids],
+            )
+            .await?;
+        // Something else may have deleted part of the cluster since the scan
+        if rows.len() < 2 {
+            anyhow::bail!("cluster no longer exists");
+        }
+
+        let keeper = rows.iter().find(|r| r.get::<_, Uuid>("id") == cluster.keep).unwrap_or(&rows[0]);
+        let memory_type = MemoryType::try_from(keeper.get::<_, String>("memory_type").as_str())?;
+        let mut metadata: serde_json::Value = keeper.get("metadata");
+        let contents: Vec<String> = rows.iter().map(|r| r.get("content")).collect();
+        let present: Vec<Uuid> = rows.iter().map(|r| r.get("id")).collect();
+
+        let content = consolidator.merge(&contents).await?;
+        let embedding = embedder.embed(&content).await?;
+        if let Some(object) = metadata.as_object_mut() {
+            object.remove("chunk_index");
+            object.remove("chunk_count");
+            object.insert("consolidated_from".to_string(), serde_json::json!(present));
+            object.insert("consolidated_at".to_string(), serde_json::json!(Utc::now().to_rfc3339()));
+            object.insert("embedding_model".to_string(), serde_json::json!(embedder.model()));
+        }
+
+        let now = Utc::now();
+        let id = self
+            .store(Memory {
+                id: Uuid::new_v4(),
+                memory_type,
+                content,
+                embedding,
+                metadata,
+                created_at: now,
+                last_accessed: now,
+            })
+            .await?;
+        let removed = client
+            .execute(&delete_with_tombstones("memories", "id = ANY($1)"), &[&present])
+            .await?;
+        Ok((id, removed as usize))
+    }
+
+    /// Recompute every embedding not already produced by the embedder's
+    /// model. Safe to re-run after an interruption; finished rows are skipped.
+    #[instrument(skip(self, embedder, progress), fields(model = embedder.model()))]
+    pub async fn reembed(&self, embedder: &dyn Embedder, dry_run: bool, progress: ProgressFn<'_>) -> Result<ReembedReport> {
+        let model = embedder.model().to_string();
+        let client = self.pool.get().await?;
+
+        let row = client
+            .query_one(
+                "SELECT COUNT(*) AS pending, COALESCE(SUM(LENGTH(content)), 0)::int8 AS chars
+                 FROM memories WHERE COALESCE(metadata->>'embedding_model', '') <> $1",
+                &[&model],
+            )
+            .await?;
+        let pending = row.get::<_, i64>("pending") as u64;
+        let mut report = ReembedReport {
+            model: model.clone(),
+            pending,
+            updated: 0,
+            failed: Vec::new(),
+            estimated_tokens: (row.get::<_, i64>("chars") / CHARS_PER_TOKEN) as u64,
+            dry_run,
+        };
+
+        // The column has a fixed width; find out before touching any rows
+        let probe = embedder.embed("dimension check").await?;
+        if probe.len() != self.vector_dim {
+            return Err(MemoryError::VectorDimension {
+                expected: self.vector_dim,
+                actual: probe.len(),
+            }
+            .into());
+        }
+        if dry_run || pending == 0 {
+            return Ok(report);
+        }
+
+        let mut cursor: Option<(DateTime<Utc>, Uuid)> = None;
+        let mut done = 0;
+        loop {
+            let (after_time, after_id) = cursor.unzip();
+            let rows = client
+                .query(
+                    "SELECT id, memory_type, content, created_at FROM memories
+                     WHERE COALESCE(metadata->>'embedding_model', '') <> $1
+                       AND ($2::timestamptz IS NULL OR (created_at, id) > ($2, $3))
+                     ORDER BY created_at, id
+                     LIMIT $4",
+                    &[&model, &after_time, &after_id, &REEMBED_PAGE_SIZE],
+                )
+                .await?;
+            let Some(last) = rows.last() else {
+                break;
+            };
+            cursor = Some((last.get("created_at"), last.get("id")));
+
+            for row in &rows {
+                let id: Uuid = row.get("id");
+                let memory_type: String = row.get("memory_type");
+                let content: String = row.get("content");
+                progress(JobProgress { stage: "embedding", done, total: pending });
+                done += 1;
+
+                let result = async {
+                    let embedding = embedder.embed(&content).await?;
+                    self.validate_vector_dimension(&embedding)?;
+                    let memory_type = MemoryType::try_from(memory_type.as_str())?;
+                    let encoded = self.encode_embedding(&memory_type, &embedding);
+                    let mut params: Vec<&(dyn ToSql + Sync)> = vec![&id, &model];
+                    params.extend(encoded.params(self.quantized_columns));
+                    client
+                        .execute(
+                            &format!(
+                                "UPDATE memories
+                                 SET {},
+                                     metadata = metadata || jsonb_build_object('embedding_model', $2::text)
+                                 WHERE id = $1",
+                                self.embedding_assignments(3)
+                            ),
+                            &params,
+                        )
+                        .await?;
+                    anyhow::Ok(())
+                }
+                .await;
+                match result {
+                    Ok(()) => report.updated += 1,
+                    Err(e) => report.failed.push((id, e.to_string())),
+                }
+            }
+        }
+        progress(JobProgress { stage: "embedding", done: pending, total: pending });
+        Ok(report)
+    }
+
+    /// Every memory `selector` matches, oldest first. Patterns are Rust
+    /// regexes, so the table is scanned here rather than filtered in SQL.
+    #[instrument(skip(self, selector))]
+    pub async fn select(&self, selector: &MemorySelector) -> Result<Vec<SelectedMemory>> {
+        let _timer = TimingGuard::new("memory_select");
+        if selector.is_empty() {
+            return Ok(Vec::new());
+        }
+        let client = self.pool.get().await?;
+
+        let mut selected = Vec::new();
+        let mut cursor: Option<(DateTime<Utc>, Uuid)> = None;
+        loop {
+            let (after_time, after_id) = cursor.unzip();
+            let rows = client
+                .query(
+                    "SELECT id, content, metadata, created_at FROM memories
+                     WHERE ($1::timestamptz IS NULL OR (created_at, id) > ($1, $2))
+                     ORDER BY created_at, id
+                     LIMIT $3",
+                    &[&after_time, &after_id, &SELECT_PAGE_SIZE],
+                )
+                .await?;
+            let Some(last) = rows.last() else {
+                break;
+            };
+            cursor = Some((last.get("created_at"), last.get("id")));
+
+            for row in &rows {
+                let content: String = row.get("content");
+                let metadata: serde_json::Value = row.get("metadata");
+                if selector.matches(&content, &metadata) {
+                    selected.push(SelectedMemory {
+                        id: row.get("id"),
+                        session_id: metadata
+                            .get("session_id")
+                            .and_then(|v| v.as_str())
+                            .and_then(|s| Uuid::parse_str(s).ok()),
+                    });
+                }
+            }
+        }
+        selected.extend(self.select_cold(selector).await?);
+        Ok(selected)
+    }
+
+    /// Delete `ids` in one statement; returns how many existed
+    #[instrument(skip(self, ids), fields(count = ids.len()))]
+    pub async fn delete_many(&self, ids: &[Uuid]) -> Result<usize> {
+        if ids.is_empty() {
+            return Ok(0);
+        }
+        let client = self.pool.get().await?;
+        let removed = client
+            .execute(&delete_with_tombstones("memories", "id = ANY($1)"), &[&ids])
+            .await?;
+        let archived = client
+            .execute(&delete_with_tombstones("memories_cold", "id = ANY($1)"), &[&ids])
+            .await?;
+        Ok((removed + archived) as usize)
+    }
+}
+
+fn validate_threshold(threshold: f64) -> Result<(), MemoryError> {
+    if threshold > 0.0 && threshold <= 1.0 {
+        Ok(())
+    } else {
+        Err(MemoryError::InvalidRequest(format!(
+            "similarity threshold must be in (0, 1], got {}",
+            threshold
+        )))
+    }
+}
+
+/// Form a group from `keeper` and the neighbours nobody has claimed yet
+fn claim_group(keeper: Uuid, neighbours: Vec<SimilarMemory>, claimed: &mut HashSet<Uuid>) -> Option<SimilarGroup> {
+    let members: Vec<SimilarMemory> = neighbours
+        .into_iter()
+        .filter(|m| m.id != keeper && !claimed.contains(&m.id))
+        .collect();
+    if members.is_empty() {
+        return None;
+    }
+    claimed.insert(keeper);
+    claimed.extend(members.iter().map(|m| m.id));
+    Some(SimilarGroup { keep: keeper, members })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn similar(id: Uuid, similarity: f64) -> SimilarMemory {
+        SimilarMemory { id, similarity }
+    }
+
+    #[test]
+    fn test_claim_group_skips_claimed_members() {
+        let (a, b, c, d) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        let mut claimed = HashSet::new();
+
+        let group = claim_group(a, vec![similar(b, 0.99), similar(c, 0.98)], &mut claimed).unwrap();
+        assert_eq!(group.keep, a);
+        assert_eq!(group.members.len(), 2);
+
+        // Every neighbour of d already belongs to a's group
+        let group = claim_group(d, vec![similar(b, 0.99), similar(a, 0.97)], &mut claimed);
+        assert!(group.is_none());
+        assert!(!claimed.contains(&d));
+    }
+
+    #[test]
+    fn test_validate_threshold() {
+        assert!(validate_threshold(0.95).is_ok());
+        assert!(validate_threshold(1.0).is_ok());
+        assert!(validate_threshold(0.0).is_err());
+        assert!(validate_threshold(1.5).is_err());
+    }
+
+    #[test]
+    fn test_memory_selector() {
+        let session = Uuid::new_v4();
+        let selector = MemorySelector {
+            sessions: HashSet::from([session]),
+            user: Some("alice".to_string()),
+            pattern: Some(Regex::new(r"(?i)alice@example\.com").unwrap()),
+        };
+        let metadata = serde_json::json!({"session_id": session});
+        assert!(selector.matches("summary", &metadata));
+        assert!(selector.matches("prefers tea", &serde_json::json!({"user": "alice"})));
+        assert!(selector.matches("mail ALICE@example.com", &serde_json::json!({})));
+        assert!(!selector.matches("mail bob@example.com", &serde_json::json!({"user": "bob", "session_id": "nope"})));
+        assert!(MemorySelector::default().is_empty());
+    }
+}
@@ -7,7 +7,14 @@ use thiserror::Error;
 use tracing::{error, instrument};
 use uuid::Uuid;
 use validator::{Validate, ValidationError};
+use crate::maintenance::Embedder;
 use crate::profiling::TimingGuard;
+use crate::quantization::{EncodedEmbedding, Quantization, QuantizationConfig};
+use crate::redaction::Redactor;
+use crate::sync::delete_with_tombstones;
+use crate::scoring::{ExplainedHit, RetrievalWeights, SearchExplanation, CANDIDATE_FACTOR};
+use std::sync::Arc;
+use tokio_postgres::types::ToSql;
 
 #[derive(Debug, Error)]
 pub enum MemoryError {
@@ -125,9 +132,23 @@ pub trait MemoryStore {
     async fn list_paginated(&self, limit: usize, offset: usize) -> Result<(Vec<Memory>, i64)>;
 }
 
+impl Memory {
+    /// Whether the memory is pinned: recalled into every chat turn and left
+    /// alone by deduplication and consolidation
+    pub fn is_pinned(&self) -> bool {
+        self.metadata.get("pinned").and_then(|v| v.as_bool()) == Some(true)
+    }
+}
+
 pub struct PostgresMemoryStore {
-    pool: Pool,
-    vector_dim: usize,
+    pub(crate) pool: Pool,
+    pub(crate) vector_dim: usize,
+    pub(crate) redactor: Arc<Redactor>,
+    weights: RetrievalWeights,
+    rehydrator: Option<Arc<dyn Embedder>>,
+    pub(crate) quantization: QuantizationConfig,
+    /// Whether the table has the quantized embedding columns
+    pub(crate) quantized_columns: bool,
 }
 
 impl PostgresMemoryStore {
@@ -148,7 +169,9 @@ impl PostgresMemoryStore {
                     content TEXT NOT NULL,
                     embedding vector(1536) NOT NULL,
                     metadata JSONB NOT NULL DEFAULT '{}'::jsonb,
+                    namespace TEXT NOT NULL DEFAULT '',
                     created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
                     last_accessed TIMESTAMPTZ NOT NULL DEFAULT NOW()
                 )",
                 &[],
@@ -164,10 +187,327 @@ impl PostgresMemoryStore {
             )
             .await?;
 
-        Ok(Self { pool, vector_dim })
+        // Tables created before retrieval scoring have no access count
+        client
+            .execute(
+                "ALTER TABLE memories ADD COLUMN IF NOT EXISTS access_count BIGINT NOT NULL DEFAULT 0",
+                &[],
+            )
+            .await?;
+
+        // Tables created before namespaces had a column keep them only in
+        // metadata; copy them across once, as the column is added
+        let has_namespace: bool = client
+            .query_one(
+                "SELECT EXISTS (SELECT 1 FROM information_schema.columns
+                                WHERE table_name = 'memories' AND column_name = 'namespace')",
+                &[],
+            )
+            .await?
+            .get(0);
+        if !has_namespace {
+            client
+                .execute("ALTER TABLE memories ADD COLUMN namespace TEXT NOT NULL DEFAULT ''", &[])
+                .await?;
+            client
+                .execute(
+                    "UPDATE memories SET namespace = metadata->>'namespace'
+                     WHERE metadata->>'namespace' IS NOT NULL",
+                    &[],
+                )
+                .await?;
+        }
+        client
+            .execute("CREATE INDEX IF NOT EXISTS memories_namespace_idx ON memories (namespace)", &[])
+            .await?;
+
+        // Sync orders changes by when they were made; older rows count as
+        // unchanged since they were created
+        let has_updated_at: bool = client
+            .query_one(
+                "SELECT EXISTS (SELECT 1 FROM information_schema.columns
+                                WHERE table_name = 'memories' AND column_name = 'updated_at')",
+                &[],
+            )
+            .await?
+            .get(0);
+        if !has_updated_at {
+            client
+                .batch_execute(
+                    "ALTER TABLE memories ADD COLUMN updated_at TIMESTAMPTZ;
+                     UPDATE memories SET updated_at = created_at;
+                     ALTER TABLE memories ALTER COLUMN updated_at SET DEFAULT NOW(),
+                                          ALTER COLUMN updated_at SET NOT NULL;",
+                )
+                .await?;
+        }
+        client
+            .execute(
+                "CREATE INDEX IF NOT EXISTS memories_sync_idx ON memories (namespace, updated_at, id)",
+                &[],
+            )
+            .await?;
+        crate::sync::create_tables(&client).await?;
+
+        // Added by `with_quantization` the first time a type is quantized
+        let quantized_columns: bool = client
+            .query_one(
+                "SELECT EXISTS (SELECT 1 FROM information_schema.columns
+                                WHERE table_name = 'memories' AND column_name = 'embedding_half')",
+                &[],
+            )
+            .await?
+            .get(0);
+
+        // Memories archived by the tiering job: compressed, without embeddings
+        client
+            .execute(
+                "CREATE TABLE IF NOT EXISTS memories_cold (
+                    id UUID PRIMARY KEY,
+                    memory_type TEXT NOT NULL,
+                    content BYTEA NOT NULL,
+                    metadata JSONB NOT NULL DEFAULT '{}'::jsonb,
+                    namespace TEXT NOT NULL DEFAULT '',
+                    created_at TIMESTAMPTZ NOT NULL,
+                    last_accessed TIMESTAMPTZ NOT NULL,
+                    access_count BIGINT NOT NULL DEFAULT 0,
+                    archived_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+                )",
+                &[],
+            )
+            .await?;
+
+        Ok(Self {
+            pool,
+            vector_dim,
+            redactor: Arc::new(Redactor::disabled()),
+            weights: RetrievalWeights::default(),
+            rehydrator: None,
+            quantization: QuantizationConfig::default(),
+            quantized_columns,
+        })
+    }
+
+    /// Redact content and metadata with `redactor` before they are written
+    pub fn with_redactor(mut self, redactor: Arc<Redactor>) -> Self {
+        self.redactor = redactor;
+        self
+    }
+
+    /// Rank search results with `weights` instead of the defaults
+    pub fn with_retrieval(mut self, weights: RetrievalWeights) -> Self {
+        self.weights = weights;
+        self
+    }
+
+    /// Re-embed archived memories with `embedder` when they are retrieved,
+    /// returning them to the main table. Without it, archived memories read
+    /// as not found.
+    pub fn with_rehydration(mut self, embedder: Arc<dyn Embedder>) -> Self {
+        self.rehydrator = Some(embedder);
+        self
+    }
+
+    /// Count `ids` as accessed, for memories used without going through
+    /// [`retrieve`](MemoryStore::retrieve), such as those recalled into a
+    /// chat turn
+    pub async fn record_access(&self, ids: &[Uuid]) -> Result<()> {
+        if ids.is_empty() {
+            return Ok(());
+        }
+        let client = self.pool.get().await?;
+        client
+            .execute(
+                "UPDATE memories
+                 SET last_accessed = NOW(), access_count = access_count + 1
+                 WHERE id = ANY($1)",
+                &[&ids],
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Current occupancy of the connection pool
+    pub fn pool_status(&self) -> deadpool_postgres::Status {
+        self.pool.status()
+    }
+
+    /// Check out a connection and run a trivial query
+    pub async fn ping(&self) -> Result<()> {
+        let client = self.pool.get().await?;
+        client.query_one("SELECT 1", &[]).await?;
+        Ok(())
+    }
+
+    /// Pin or unpin `id`; pins are kept as `pinned` in its metadata
+    #[instrument(skip(self), fields(memory_id = %id))]
+    pub async fn set_pinned(&self, id: Uuid, pinned: bool) -> Result<()> {
+        let client = self.pool.get().await?;
+        let rows_affected = client
+            .execute(
+                "UPDATE memories
+                 SET metadata = CASE WHEN $2 THEN metadata || '{\"pinned\": true}'::jsonb
+                                     ELSE metadata - 'pinned' END,
+                     updated_at = NOW()
+                 WHERE id = $1",
+                &[&id, &pinned],
+            )
+            .await?;
+        if rows_affected == 0 {
+            return Err(MemoryError::NotFound(id).into());
+        }
+        Ok(())
+    }
+
+    /// Every pinned memory, oldest first
+    pub async fn pinned(&self) -> Result<Vec<Memory>> {
+        let _timer = TimingGuard::new("memory_pinned");
+        let client = self.pool.get().await?;
+        let rows = client
+            .query(
+                &format!(
+                    "SELECT id, memory_type, content, {} AS embedding, metadata, created_at, last_accessed
+                     FROM memories
+                     WHERE metadata @> '{{\"pinned\": true}}'::jsonb
+                     ORDER BY created_at, id",
+                    self.embedding_sql()
+                ),
+                &[],
+            )
+            .await?;
+
+        let mut memories = Vec::with_capacity(rows.len());
+        for row in rows {
+            let embedding_str: String = row.get("embedding");
+            let memory_type_str: String = row.get("memory_type");
+            memories.push(Memory {
+                id: row.get("id"),
+                memory_type: MemoryType::try_from(memory_type_str.as_str())
+                    .map_err(|e| MemoryError::InvalidRequest(format!("Invalid memory type: {}", e)))?,
+                content: row.get("content"),
+                embedding: embedding_from_text(&embedding_str)?,
+                metadata: row.get("metadata"),
+                created_at: row.get("created_at"),
+                last_accessed: row.get("last_accessed"),
+            });
+        }
+        Ok(memories)
+    }
+
+    /// Like [`search`](MemoryStore::search), but only over memories stored
+    /// under `namespace`. On a partitioned table the query touches that
+    /// namespace's partition and its index alone.
+    #[instrument(skip(self, query_embedding), fields(limit = limit))]
+    pub async fn search_namespace(&self, namespace: &str, query_embedding: &[f32], limit: usize) -> Result<Vec<Memory>> {
+        let _timer = TimingGuard::new("memory_search_namespace");
+        self.search_scoped(Some(namespace), query_embedding, limit).await
+    }
+
+    /// Like [`search`](MemoryStore::search), optionally within `namespace`,
+    /// with each hit's similarity, recency, importance and frequency and the
+    /// formula that combined them
+    #[instrument(skip(self, query_embedding), fields(limit = limit))]
+    pub async fn search_explain(
+        &self,
+        namespace: Option<&str>,
+        query_embedding: &[f32],
+        limit: usize,
+    ) -> Result<SearchExplanation> {
+        let _timer = TimingGuard::new("memory_search_explain");
+        let rows = self.ranked_rows(namespace, query_embedding, limit).await?;
+        let now = Utc::now();
+        let mut hits = Vec::with_capacity(rows.len());
+        for row in rows {
+            let distance: f64 = row.get("distance");
+            let access_count: i64 = row.get("access_count");
+            let memory = memory_from_row(&row)?;
+            let breakdown = self.weights.explain(1.0 - distance, &memory, access_count.max(0) as u64, now);
+            hits.push(ExplainedHit { memory, breakdown });
+        }
+        Ok(SearchExplanation { formula: self.weights.formula(), weights: self.weights, hits })
+    }
+
+    async fn search_scoped(&self, namespace: Option<&str>, query_embedding: &[f32], limit: usize) -> Result<Vec<Memory>> {
+        let rows = self.ranked_rows(namespace, query_embedding, limit).await?;
+        rows.iter().map(memory_from_row).collect()
     }
 
-    fn validate_vector_dimension(&self, embedding: &[f32]) -> Result<(), MemoryError> {
+    /// Search rows best first, each with its `distance` and `access_count`
+    async fn ranked_rows(
+        &self,
+        namespace: Option<&str>,
+        query_embedding: &[f32],
+        limit: usize,
+    ) -> Result<Vec<tokio_postgres::Row>> {
+        self.validate_vector_dimension(query_embedding)?;
+        let client = self.pool.get().await?;
+
+        let query_embedding_str = embedding_to_text(query_embedding);
+        
+        // The closest candidates by distance, which the index can serve,
+        // re-ranked by the full retrieval score
+        let query = format!(
+            "SELECT id, memory_type, content, embedding, metadata, created_at, last_accessed,
+                    distance::float8 AS distance, access_count::int8 AS access_count
+             FROM ({}) candidates
+             ORDER BY {} DESC, distance
+             LIMIT $2",
+            self.search_candidates(namespace.is_some()),
+            self.weights.sql()
+        );
+        let (limit, candidates) = (limit as i64, (limit * CANDIDATE_FACTOR) as i64);
+        let rows = match namespace {
+            Some(namespace) => {
+                client
+                    .query(&query, &[&query_embedding_str, &limit, &candidates, &namespace])
+                    .await?
+            }
+            None => client.query(&query, &[&query_embedding_str, &limit, &candidates]).await?,
+        };
+        Ok(rows)
+    }
+
+    /// Nearest rows to `$1`, at most `$3` per embedding column, each with
+    /// its `distance`. Int8 rows are found by Hamming distance on their
+    /// binary-quantized index and then measured exactly.
+    fn search_candidates(&self, in_namespace: bool) -> String {
+        const COLUMNS: &str = "id, memory_type, content, metadata, created_at, last_accessed, access_count";
+        if !self.quantized_columns {
+            return format!(
+                "SELECT {}, embedding, embedding <=> $1::vector as distance
+                 FROM memories
+                 {}
+                 ORDER BY distance
+                 LIMIT $3",
+                COLUMNS,
+                if in_namespace { "WHERE namespace = $4" } else { "" }
+            );
+        }
+        let namespace = if in_namespace { "AND namespace = $4" } else { "" };
+        format!(
+            "(SELECT {columns}, embedding, embedding <=> $1::vector AS distance
+              FROM memories WHERE embedding IS NOT NULL {namespace}
+              ORDER BY embedding <=> $1::vector LIMIT $3)
+             UNION ALL
+             (SELECT {columns}, embedding_half::vector, embedding_half <=> $1::vector::halfvec AS distance
+              FROM memories WHERE embedding_half IS NOT NULL {namespace}
+              ORDER BY embedding_half <=> $1::vector::halfvec LIMIT $3)
+             UNION ALL
+             (SELECT {columns}, restored, restored <=> $1::vector AS distance
+              FROM (SELECT {columns}, jamey_int8_to_vector(embedding_int8, embedding_scale) AS restored
+                    FROM memories WHERE embedding_int8 IS NOT NULL {namespace}
+                    ORDER BY binary_quantize(jamey_int8_to_vector(embedding_int8, embedding_scale))::bit({dim})
+                             <~> binary_quantize($1::vector)
+                    LIMIT $3 * {oversample}) coarse
+              ORDER BY distance LIMIT $3)",
+            columns = COLUMNS,
+            namespace = namespace,
+            dim = self.vector_dim,
+            oversample = CANDIDATE_FACTOR,
+        )
+    }
+
+    pub(crate) fn validate_vector_dimension(&self, embedding: &[f32]) -> Result<(), MemoryError> {
         if embedding.is_empty() {
             return Err(MemoryError::VectorDimension {
                 expected: self.vector_dim,
@@ -186,14 +526,14 @@ impl PostgresMemoryStore {
         Ok(())
     }
 
-    fn sanitize_content(content: &str) -> String {
+    pub(crate) fn sanitize_content(content: &str) -> String {
         content.chars()
             .filter(|c| !c.is_control() || *c == '\n' || *c == '\t')
             .take(32768)
             .collect()
     }
 
-    fn validate_metadata(metadata: &serde_json::Value) -> Result<(), MemoryError> {
+    pub(crate) fn validate_metadata(metadata: &serde_json::Value) -> Result<(), MemoryError> {
         validate_metadata(metadata).map_err(MemoryError::Validation)
     }
 }
@@ -209,6 +549,8 @@ impl MemoryStore for PostgresMemoryStore {
         
         // Sanitize content
         memory.content = Self::sanitize_content(&memory.content);
+        self.redactor.redact_string(&mut memory.content);
+        self.redactor.redact_json(&mut memory.metadata);
         if memory.content.is_empty() {
             return Err(MemoryError::InvalidRequest("Content cannot be empty".to_string()).into());
         }
@@ -218,25 +560,19 @@ impl MemoryStore for PostgresMemoryStore {
         let memory_type_str = memory.memory_type.to_string();
         let metadata_json = serde_json::to_value(&memory.metadata)?;
         
-        // Convert vector to string format for PostgreSQL
-        let embedding_str = format!("[{}]",
-            memory.embedding.iter()
-                .map(|v| v.to_string())
-                .collect::<Vec<_>>()
-                .join(",")
-        );
+        let encoded = self.encode_embedding(&memory.memory_type, &memory.embedding);
+        let (embedding_columns, embedding_values) = self.embedding_insert(5);
+        let mut params: Vec<&(dyn ToSql + Sync)> = vec![&id, &memory_type_str, &memory.content, &metadata_json];
+        params.extend(encoded.params(self.quantized_columns));
         
         client
             .execute(
-                "INSERT INTO memories (id, memory_type, content, embedding, metadata)
-                 VALUES ($1::uuid, $2, $3, $4::vector, $5::jsonb)",
-                &[
-                    &id,
-                    &memory_type_str,
-                    &memory.content,
-                    &embedding_str,
-                    &metadata_json,
-                ],
+                &format!(
+                    "INSERT INTO memories (id, memory_type, content, metadata, namespace, {})
+                     VALUES ($1::uuid, $2, $3, $4::jsonb, COALESCE($4::jsonb->>'namespace', ''), {})",
+                    embedding_columns, embedding_values
+                ),
+                &params,
             )
             .await?;
 
@@ -249,25 +585,28 @@ impl MemoryStore for PostgresMemoryStore {
         let client = self.pool.get().await?;
 
         let row = client
-            .query_one(
-                "UPDATE memories 
-                 SET last_accessed = NOW()
-                 WHERE id = $1
-                 RETURNING id, memory_type, content, embedding, metadata, created_at, last_accessed",
+            .query_opt(
+                &format!(
+                    "UPDATE memories 
+                     SET last_accessed = NOW(), access_count = access_count + 1
+                     WHERE id = $1
+                     RETURNING id, memory_type, content, {} AS embedding, metadata, created_at, last_accessed",
+                    self.embedding_sql()
+                ),
                 &[&id],
             )
             .await?;
+        let Some(row) = row else {
+            if let Some(embedder) = &self.rehydrator {
+                if let Some(memory) = self.rehydrate(id, embedder.as_ref()).await? {
+                    return Ok(memory);
+                }
+            }
+            return Err(MemoryError::NotFound(id).into());
+        };
 
-        // Get embedding as string and parse it
         let embedding_str: String = row.get("embedding");
-        let embedding: Vec<f32> = embedding_str
-            .trim_start_matches('[')
-            .trim_end_matches(']')
-            .split(',')
-            .map(|s| s.trim().parse::<f32>().map_err(|e|
-                anyhow::anyhow!("Failed to parse embedding value: {}", e)
-            ))
-            .collect::<Result<Vec<f32>>>()?;
+        let embedding = embedding_from_text(&embedding_str)?;
 
         let memory_type_str: String = row.get("memory_type");
         let memory_type = MemoryType::try_from(memory_type_str.as_str())
@@ -287,57 +626,7 @@ impl MemoryStore for PostgresMemoryStore {
     #[instrument(skip(self, query_embedding), fields(limit = limit))]
     async fn search(&self, query_embedding: &[f32], limit: usize) -> Result<Vec<Memory>> {
         let _timer = TimingGuard::new("memory_search");
-        self.validate_vector_dimension(query_embedding)?;
-        let client = self.pool.get().await?;
-
-        // Convert query embedding to string format
-        let query_embedding_str = format!("[{}]",
-            query_embedding.iter()
-                .map(ToString::to_string)
-                .collect::<Vec<_>>()
-                .join(",")
-        );
-        
-        let rows = client
-            .query(
-                "SELECT id, memory_type, content, embedding, metadata, created_at, last_accessed,
-                        embedding <=> $1::vector as distance
-                 FROM memories
-                 ORDER BY distance
-                 LIMIT $2",
-                &[&query_embedding_str, &(limit as i64)],
-            )
-            .await?;
-
-        let mut memories = Vec::with_capacity(rows.len());
-        for row in rows {
-            // Get embedding as string and parse it
-            let embedding_str: String = row.get("embedding");
-            let embedding: Vec<f32> = embedding_str
-                .trim_start_matches('[')
-                .trim_end_matches(']')
-                .split(',')
-                .map(|s| s.trim().parse::<f32>().map_err(|e|
-                    anyhow::anyhow!("Failed to parse embedding value: {}", e)
-                ))
-                .collect::<Result<Vec<f32>>>()?;
-            
-            let memory_type_str: String = row.get("memory_type");
-            let memory_type = MemoryType::try_from(memory_type_str.as_str())
-                .map_err(|e| MemoryError::InvalidRequest(format!("Invalid memory type: {}", e)))?;
-            
-            memories.push(Memory {
-                id: row.get("id"),
-                memory_type,
-                content: row.get("content"),
-                embedding,
-                metadata: row.get("metadata"),
-                created_at: row.get("created_at"),
-                last_accessed: row.get("last_accessed"),
-            });
-        }
-
-        Ok(memories)
+        self.search_scoped(None, query_embedding, limit).await
     }
 
     #[instrument(skip(self, content, embedding), fields(memory_id = %id))]
@@ -346,27 +635,38 @@ impl MemoryStore for PostgresMemoryStore {
         self.validate_vector_dimension(embedding)?;
         
         // Sanitize content
-        let content = Self::sanitize_content(content);
+        let mut content = Self::sanitize_content(content);
+        self.redactor.redact_string(&mut content);
         if content.is_empty() {
             return Err(MemoryError::InvalidRequest("Content cannot be empty".to_string()).into());
         }
 
         let client = self.pool.get().await?;
 
-        // Convert embedding to string format
-        let embedding_str = format!("[{}]",
-            embedding.iter()
-                .map(ToString::to_string)
-                .collect::<Vec<_>>()
-                .join(",")
-        );
+        // The embedding is kept at the precision of the memory's type
+        let encoded = if self.quantization.is_enabled() {
+            let row = client
+                .query_opt("SELECT memory_type FROM memories WHERE id = $1", &[&id])
+                .await?
+                .ok_or(MemoryError::NotFound(id))?;
+            let memory_type = MemoryType::try_from(row.get::<_, &str>("memory_type"))
+                .map_err(|e| MemoryError::InvalidRequest(format!("Invalid memory type: {}", e)))?;
+            self.encode_embedding(&memory_type, embedding)
+        } else {
+            EncodedEmbedding::new(embedding, Quantization::Full)
+        };
+        let mut params: Vec<&(dyn ToSql + Sync)> = vec![&id, &content];
+        params.extend(encoded.params(self.quantized_columns));
         
         let rows_affected = client
             .execute(
-                "UPDATE memories 
-                 SET content = $2, embedding = $3::vector, last_accessed = NOW()
-                 WHERE id = $1",
-                &[&id, &content, &embedding_str],
+                &format!(
+                    "UPDATE memories 
+                     SET content = $2, {}, updated_at = NOW(), last_accessed = NOW()
+                     WHERE id = $1",
+                    self.embedding_assignments(3)
+                ),
+                &params,
             )
             .await?;
 
@@ -382,9 +682,14 @@ impl MemoryStore for PostgresMemoryStore {
         let _timer = TimingGuard::new("memory_delete");
         let client = self.pool.get().await?;
 
-        let rows_affected = client
-            .execute("DELETE FROM memories WHERE id = $1", &[&id])
+        let mut rows_affected = client
+            .execute(&delete_with_tombstones("memories", "id = $1"), &[&id])
             .await?;
+        if rows_affected == 0 {
+            rows_affected = client
+                .execute(&delete_with_tombstones("memories_cold", "id = $1"), &[&id])
+                .await?;
+        }
 
         if rows_affected == 0 {
             return Err(MemoryError::NotFound(id).into());
@@ -415,26 +720,21 @@ impl MemoryStore for PostgresMemoryStore {
         // Get paginated results
         let rows = client
             .query(
-                "SELECT id, memory_type, content, embedding, metadata, created_at, last_accessed
-                 FROM memories
-                 ORDER BY created_at DESC
-                 LIMIT $1 OFFSET $2",
+                &format!(
+                    "SELECT id, memory_type, content, {} AS embedding, metadata, created_at, last_accessed
+                     FROM memories
+                     ORDER BY created_at DESC
+                     LIMIT $1 OFFSET $2",
+                    self.embedding_sql()
+                ),
                 &[&(limit as i64), &(offset as i64)],
             )
             .await?;
 
         let mut memories = Vec::with_capacity(rows.len());
         for row in rows {
-            // Get embedding as string and parse it
             let embedding_str: String = row.get("embedding");
-            let embedding: Vec<f32> = embedding_str
-                .trim_start_matches('[')
-                .trim_end_matches(']')
-                .split(',')
-                .map(|s| s.trim().parse::<f32>().map_err(|e|
-                    anyhow::anyhow!("Failed to parse embedding value: {}", e)
-                ))
-                .collect::<Result<Vec<f32>>>()?;
+            let embedding = embedding_from_text(&embedding_str)?;
             
             let memory_type_str: String = row.get("memory_type");
             let memory_type = MemoryType::try_from(memory_type_str.as_str())
@@ -455,6 +755,200 @@ impl MemoryStore for PostgresMemoryStore {
     }
 }
 
+/// pgvector's text form, `[0.1,0.2,...]`
+pub fn embedding_to_text(embedding: &[f32]) -> String {
+    let mut out = String::with_capacity(embedding.len() * 12 + 2);
+    out.push('[');
+    for (i, value) in embedding.iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        out.push_str(&value.to_string());
+    }
+    out.push(']');
+    out
+}
+
+pub fn embedding_from_text(text: &str) -> Result<Vec<f32>> {
+    text.trim_start_matches('[')
+        .trim_end_matches(']')
+        .split(',')
+        .map(|s| s.trim().parse::<f32>().map_err(|e|
+            anyhow::anyhow!("Failed to parse embedding value: {}", e)
+        ))
+        .collect()
+}
+
+/// pgvector's binary wire form: dimension and a reserved word as big-endian
+/// `u16`s, then each value as a big-endian `f32`
+pub fn embedding_to_binary(embedding: &[f32]) -> Result<Vec<u8>, MemoryError> {
+    let dim = u16::try_from(embedding.len())
+        .map_err(|_| MemoryError::InvalidRequest(format!("Embedding too large: {} values", embedding.len())))?;
+    let mut out = Vec::with_capacity(4 + embedding.len() * 4);
+    out.extend_from_slice(&dim.to_be_bytes());
+    out.extend_from_slice(&0u16.to_be_bytes());
+    for value in embedding {
+        out.extend_from_slice(&value.to_be_bytes());
+    }
+    Ok(out)
+}
+
+pub fn embedding_from_binary(bytes: &[u8]) -> Result<Vec<f32>, MemoryError> {
+    if bytes.len() < 4 {
+        return Err(MemoryError::InvalidRequest("Truncated embedding header".to_string()));
+    }
+    let dim = u16::from_be_bytes([bytes[0], bytes[1]]) as usize;
+    let values = &bytes[4..];
+    if values.len() != dim * 4 {
+        return Err(MemoryError::InvalidRequest(format!(
+            "Embedding declares {} values but carries {} bytes",
+            dim,
+            values.len()
+        )));
+    }
+    Ok(values
+        .chunks_exact(4)
+        .map(|c| f32::from_be_bytes([c[0], c[1], c[2], c[3]]))
+        .collect())
+}
+
+/// Cosine similarity of two embeddings; 0 when either is all zeros
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    let (mut dot, mut norm_a, mut norm_b) = (0.0f32, 0.0f32, 0.0f32);
+    for (x, y) in a.iter().zip(b) {
+        dot += x * y;
+        norm_a += x * x;
+        norm_b += y * y;
+    }
+    if norm_a == 0.0 || norm_b == 0.0 {
+        0.0
+    } else {
+        dot / (norm_a.sqrt() * norm_b.sqrt())
+    }
+}
+
+/// A memory from a search row, whose embedding is selected as text
+fn memory_from_row(row: &tokio_postgres::Row) -> Result<Memory> {
+    let embedding_str: String = row.get("embedding");
+    let memory_type_str: String = row.get("memory_type");
+    Ok(Memory {
+        id: row.get("id"),
+        memory_type: MemoryType::try_from(memory_type_str.as_str())
+            .map_err(|e| MemoryError::InvalidRequest(format!("Invalid memory type: {}", e)))?,
+        content: row.get("content"),
+        embedding: embedding_from_text(&embedding_str)?,
+        metadata: row.get("metadata"),
+        created_at: row.get("created_at"),
+        last_accessed: row.get("last_accessed"),
+    })
+}
+
+/// Process-local store with brute-force search, for tests, benchmarks and
+/// running without PostgreSQL
+#[derive(Default)]
+pub struct InMemoryStore {
+    memories: tokio::sync::RwLock<std::collections::HashMap<Uuid, Memory>>,
+    access_counts: tokio::sync::RwLock<std::collections::HashMap<Uuid, u64>>,
+    weights: RetrievalWeights,
+}
+
+impl InMemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Rank search results with `weights` instead of the defaults
+    pub fn with_retrieval(mut self, weights: RetrievalWeights) -> Self {
+        self.weights = weights;
+        self
+    }
+
+    /// Like [`search`](MemoryStore::search), with each hit's score breakdown
+    pub async fn search_explain(&self, query_embedding: &[f32], limit: usize) -> SearchExplanation {
+        let memories = self.memories.read().await;
+        let counts = self.access_counts.read().await;
+        let now = Utc::now();
+        let mut hits: Vec<_> = memories
+            .values()
+            .map(|m| {
+                let similarity = cosine_similarity(query_embedding, &m.embedding) as f64;
+                let accessed = counts.get(&m.id).copied().unwrap_or(0);
+                ExplainedHit { memory: m.clone(), breakdown: self.weights.explain(similarity, m, accessed, now) }
+            })
+            .collect();
+        hits.sort_by(|a, b| b.breakdown.score.total_cmp(&a.breakdown.score));
+        hits.truncate(limit);
+        SearchExplanation { formula: self.weights.formula(), weights: self.weights, hits }
+    }
+
+    /// Count `ids` as accessed
+    pub async fn record_access(&self, ids: &[Uuid]) {
+        let mut memories = self.memories.write().await;
+        let mut counts = self.access_counts.write().await;
+        for id in ids {
+            if let Some(memory) = memories.get_mut(id) {
+                memory.last_accessed = Utc::now();
+                *counts.entry(*id).or_default() += 1;
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl MemoryStore for InMemoryStore {
+    async fn store(&self, memory: Memory) -> Result<Uuid> {
+        let id = memory.id;
+        self.memories.write().await.insert(id, memory);
+        Ok(id)
+    }
+
+    async fn retrieve(&self, id: Uuid) -> Result<Memory> {
+        let mut memories = self.memories.write().await;
+        let memory = memories.get_mut(&id).ok_or(MemoryError::NotFound(id))?;
+        memory.last_accessed = Utc::now();
+        *self.access_counts.write().await.entry(id).or_default() += 1;
+        Ok(memory.clone())
+    }
+
+    async fn search(&self, query_embedding: &[f32], limit: usize) -> Result<Vec<Memory>> {
+        let memories = self.memories.read().await;
+        let counts = self.access_counts.read().await;
+        let now = Utc::now();
+        let mut scored: Vec<_> = memories
+            .values()
+            .map(|m| {
+                let accessed = counts.get(&m.id).copied().unwrap_or(0);
+                (self.weights.score(query_embedding, m, accessed, now), m)
+            })
+            .collect();
+        scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+        Ok(scored.into_iter().take(limit).map(|(_, m)| m.clone()).collect())
+    }
+
+    async fn update(&self, id: Uuid, content: &str, embedding: &[f32]) -> Result<()> {
+        let mut memories = self.memories.write().await;
+        let memory = memories.get_mut(&id).ok_or(MemoryError::NotFound(id))?;
+        memory.content = content.to_string();
+        memory.embedding = embedding.to_vec();
+        memory.last_accessed = Utc::now();
+        Ok(())
+    }
+
+    async fn delete(&self, id: Uuid) -> Result<()> {
+        self.memories.write().await.remove(&id).ok_or(MemoryError::NotFound(id))?;
+        self.access_counts.write().await.remove(&id);
+        Ok(())
+    }
+
+    async fn list_paginated(&self, limit: usize, offset: usize) -> Result<(Vec<Memory>, i64)> {
+        let memories = self.memories.read().await;
+        let mut all: Vec<_> = memories.values().collect();
+        all.sort_by_key(|m| std::cmp::Reverse(m.created_at));
+        let page = all.into_iter().skip(offset).take(limit).cloned().collect();
+        Ok((page, memories.len() as i64))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -506,4 +1000,83 @@ mod tests {
         // Test deleting
         store.delete(id).await.unwrap();
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_embedding_encodings_round_trip() {
+        let embedding = vec![0.25, -1.5, 3.0e-7, 0.0];
+        assert_eq!(embedding_to_text(&embedding), "[0.25,-1.5,0.0000003,0]");
+        assert_eq!(embedding_from_text(&embedding_to_text(&embedding)).unwrap(), embedding);
+
+        let binary = embedding_to_binary(&embedding).unwrap();
+        assert_eq!(&binary[..4], &[0, 4, 0, 0]);
+        assert_eq!(embedding_from_binary(&binary).unwrap(), embedding);
+        assert!(embedding_from_binary(&binary[..7]).is_err());
+        assert!(embedding_from_text("[0.1,x]").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_store_search_order() {
+        let store = InMemoryStore::new();
+        let memory = |content: &str, embedding: Vec<f32>| Memory {
+            id: Uuid::new_v4(),
+            memory_type: MemoryType::Knowledge,
+            content: content.to_string(),
+            embedding,
+            metadata: serde_json::json!({}),
+            created_at: Utc::now(),
+            last_accessed: Utc::now(),
+        };
+        store.store(memory("east", vec![1.0, 0.0])).await.unwrap();
+        let north = store.store(memory("north", vec![0.0, 1.0])).await.unwrap();
+
+        let results = store.search(&[0.1, 0.9], 2).await.unwrap();
+        assert_eq!(results[0].id, north);
+        assert_eq!(results.len(), 2);
+
+        store.delete(north).await.unwrap();
+        assert!(store.retrieve(north).await.is_err());
+        assert_eq!(store.list_paginated(10, 0).await.unwrap().1, 1);
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_store_ranks_by_retrieval_score() {
+        let memory = |content: &str, embedding: Vec<f32>, importance: f64| Memory {
+            id: Uuid::new_v4(),
+            memory_type: MemoryType::Knowledge,
+            content: content.to_string(),
+            embedding,
+            metadata: serde_json::json!({"importance": importance}),
+            created_at: Utc::now(),
+            last_accessed: Utc::now(),
+        };
+        let closest = memory("closest", vec![1.0, 0.0], 0.0);
+        let important = memory("important", vec![0.98, 0.2], 1.0);
+
+        let plain = InMemoryStore::new().with_retrieval(RetrievalWeights::similarity_only());
+        plain.store(closest.clone()).await.unwrap();
+        plain.store(important.clone()).await.unwrap();
+        assert_eq!(plain.search(&[1.0, 0.0], 2).await.unwrap()[0].id, closest.id);
+
+        let weighted = InMemoryStore::new();
+        weighted.store(closest.clone()).await.unwrap();
+        weighted.store(important.clone()).await.unwrap();
+        assert_eq!(weighted.search(&[1.0, 0.0], 2).await.unwrap()[0].id, important.id);
+
+        // Frequent access tips a near tie back the other way
+        let weighted = InMemoryStore::new().with_retrieval(RetrievalWeights { importance: 0.0, ..Default::default() });
+        weighted.store(closest.clone()).await.unwrap();
+        weighted.store(important.clone()).await.unwrap();
+        assert_eq!(weighted.search(&[1.0, 0.0], 2).await.unwrap()[0].id, closest.id);
+        for _ in 0..20 {
+            weighted.record_access(&[important.id]).await;
+        }
+        assert_eq!(weighted.search(&[1.0, 0.0], 2).await.unwrap()[0].id, important.id);
+
+        let explained = weighted.search_explain(&[1.0, 0.0], 2).await;
+        assert_eq!(explained.hits[0].memory.id, important.id);
+        assert_eq!(explained.hits[0].breakdown.access_count, 20);
+        assert_eq!(explained.hits[1].breakdown.similarity, 1.0);
+        assert!(explained.hits[0].breakdown.score > explained.hits[1].breakdown.score);
+        assert!(explained.formula.starts_with("1 × similarity"));
+    }
+}
@@ -0,0 +1,293 @@
+//! Namespace partitioning of the memories table
+//!
+//! Large installations can switch `memories` to PostgreSQL declarative
+//! partitioning, `PARTITION BY LIST (namespace)`. Everything starts in the
+//! default partition; a namespace that outgrows it is split into a partition
+//! of its own, with its own vector index, so searching or reindexing one
+//! namespace no longer walks every other namespace's rows.
+//!
+//! Partitioning is opt-in and one-way: [`partition_by_namespace`] rebuilds
+//! the table, and the rest of the store works the same on either layout.
+//!
+//! PostgreSQL requires a partitioned table's primary key to include the
+//! partition column, so the key becomes `(id, namespace)` and the database
+//! alone no longer stops one id appearing in two namespaces. Ids stay unique
+//! because of how rows arrive: new memories get a fresh random UUID, and
+//! [`apply_changes`] serializes changes to each id and skips a change whose
+//! id is already held in another namespace. Upserts into `memories` name
+//! `(id, namespace)` as their conflict target on this layout.
+//!
+//! [`partition_by_namespace`]: PostgresMemoryStore::partition_by_namespace
+//! [`apply_changes`]: PostgresMemoryStore::apply_changes
+
+use anyhow::Result;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use tracing::{info, instrument};
+
+use crate::memory::{MemoryError, PostgresMemoryStore};
+use crate::profiling::TimingGuard;
+
+/// Partition holding every namespace without one of its own
+pub const DEFAULT_PARTITION: &str = "memories_default";
+
+/// Characters of the namespace kept in a partition's name
+const NAME_SLUG_LEN: usize = 32;
+
+/// One partition of a partitioned memories table
+#[derive(Debug, Clone, Serialize)]
+pub struct PartitionInfo {
+    pub name: String,
+    /// Namespace the partition holds; `None` for the default partition
+    pub namespace: Option<String>,
+    pub rows: i64,
+    pub bytes: i64,
+}
+
+impl PostgresMemoryStore {
+    /// Whether `memories` is partitioned by namespace
+    pub async fn is_partitioned(&self) -> Result<bool> {
+        let client = self.pool.get().await?;
+        let row = client
+            .query_one(
+                "SELECT EXISTS (SELECT 1 FROM pg_partitioned_table
+                                WHERE partrelid = 'memories'::regclass)",
+                &[],
+            )
+            .await?;
+        Ok(row.get(0))
+    }
+
+    /// Rebuild `memories` as a table partitioned by namespace, with every
+    /// row in the default partition. Returns the number of rows moved.
+    ///
+    /// Runs in one transaction that locks the table throughout, so the
+    /// runtime should be stopped first on a large installation.
+    #[instrument(skip(self))]
+    pub async fn partition_by_namespace(&self) -> Result<u64> {
+        let _timer = TimingGuard::new("memory_partition");
+        if self.is_partitioned().await? {
+            return Err(MemoryError::InvalidRequest("memories is already partitioned".to_string()).into());
+        }
+
+        let mut client = self.pool.get().await?;
+        let tx = client.transaction().await?;
+        // Index and constraint names are per schema, so the old ones move
+        // aside before the new table claims them
+        tx.batch_execute(
+            "LOCK TABLE memories IN ACCESS EXCLUSIVE MODE;
+             ALTER TABLE memories RENAME TO memories_unpartitioned;
+             ALTER TABLE memories_unpartitioned RENAME CONSTRAINT memories_pkey TO memories_unpartitioned_pkey;
+             ALTER INDEX IF EXISTS memories_embedding_idx RENAME TO memories_unpartitioned_embedding_idx;
+             ALTER INDEX IF EXISTS memories_namespace_idx RENAME TO memories_unpartitioned_namespace_idx;
+             ALTER INDEX IF EXISTS memories_embedding_half_idx RENAME TO memories_unpartitioned_embedding_half_idx;
+             ALTER INDEX IF EXISTS memories_embedding_int8_idx RENAME TO memories_unpartitioned_embedding_int8_idx;
+             CREATE TABLE memories (LIKE memories_unpartitioned INCLUDING DEFAULTS)
+                 PARTITION BY LIST (namespace);
+             ALTER TABLE memories ADD PRIMARY KEY (id, namespace);
+             CREATE TABLE memories_default PARTITION OF memories DEFAULT;",
+        )
+        .await?;
+        // Same columns in the same order, quantized ones included
+        let moved = tx
+            .execute("INSERT INTO memories SELECT * FROM memories_unpartitioned", &[])
+            .await?;
+        tx.batch_execute(
+            "DROP TABLE memories_unpartitioned;
+             CREATE INDEX memories_embedding_idx ON memories
+                 USING ivfflat (embedding vector_cosine_ops) WITH (lists = 100);
+             CREATE INDEX memories_namespace_idx ON memories (namespace);",
+        )
+        .await?;
+        if self.quantized_columns {
+            // Recreated by `with_quantization` on the next start otherwise
+            tx.batch_execute(&format!(
+                "CREATE INDEX memories_embedding_half_idx ON memories
+                     USING ivfflat (embedding_half halfvec_cosine_ops) WITH (lists = 100);
+                 CREATE INDEX memories_embedding_int8_idx ON memories
+                     USING hnsw ((binary_quantize(jamey_int8_to_vector(embedding_int8, embedding_scale))::bit({}))
+                                 bit_hamming_ops)
+                     WHERE embedding_int8 IS NOT NULL;",
+                self.vector_dim
+            ))
+            .await?;
+        }
+        tx.commit().await?;
+
+        info!("Partitioned memories by namespace ({} rows)", moved);
+        Ok(moved)
+    }
+
+    /// Move `namespace` out of the default partition into one of its own.
+    /// Returns the partition's name and the number of rows moved.
+    #[instrument(skip(self))]
+    pub async fn create_partition(&self, namespace: &str) -> Result<(String, u64)> {
+        let _timer = TimingGuard::new("memory_create_partition");
+        validate_namespace(namespace)?;
+        if !self.is_partitioned().await? {
+            return Err(MemoryError::InvalidRequest(
+                "memories is not partitioned; partition the table first".to_string(),
+            )
+            .into());
+        }
+        if self.partition_for(namespace).await?.is_some() {
+            return Err(MemoryError::InvalidRequest(format!(
+                "namespace {} already has a partition",
+                namespace
+            ))
+            .into());
+        }
+
+        let name = partition_name(namespace);
+        let mut client = self.pool.get().await?;
+        let tx = client.transaction().await?;
+        tx.batch_execute(&format!(
+            "LOCK TABLE {default} IN ACCESS EXCLUSIVE MODE;
+             CREATE TABLE {name} (LIKE memories INCLUDING DEFAULTS INCLUDING CONSTRAINTS);",
+            default = DEFAULT_PARTITION,
+            name = name
+        ))
+        .await?;
+        let moved = tx
+            .execute(
+                &format!("INSERT INTO {} SELECT * FROM {} WHERE namespace = $1", name, DEFAULT_PARTITION),
+                &[&namespace],
+            )
+            .await?;
+        tx.execute(
+            &format!("DELETE FROM {} WHERE namespace = $1", DEFAULT_PARTITION),
+            &[&namespace],
+        )
+        .await?;
+        // Bounds can't be bound parameters; attaching also builds the
+        // partition's share of the table's indexes
+        tx.batch_execute(&format!(
+            "ALTER TABLE memories ATTACH PARTITION {} FOR VALUES IN ({})",
+            name,
+            quote_literal(namespace)
+        ))
+        .await?;
+        tx.commit().await?;
+
+        info!("Moved {} memories of namespace {} into {}", moved, namespace, name);
+        Ok((name, moved))
+    }
+
+    /// Every partition with its row count and size, largest first. Empty
+    /// when the table isn't partitioned.
+    pub async fn partitions(&self) -> Result<Vec<PartitionInfo>> {
+        let _timer = TimingGuard::new("memory_partitions");
+        let client = self.pool.get().await?;
+        let rows = client
+            .query(
+                "SELECT c.relname::text AS name,
+                        pg_get_expr(c.relpartbound, c.oid) AS bound,
+                        COALESCE(counts.rows, 0) AS rows,
+                        pg_total_relation_size(c.oid) AS bytes
+                 FROM pg_inherits i
+                 JOIN pg_class c ON c.oid = i.inhrelid
+                 LEFT JOIN (SELECT tableoid, COUNT(*) AS rows FROM memories GROUP BY tableoid) counts
+                        ON counts.tableoid = c.oid
+                 WHERE i.inhparent = 'memories'::regclass
+                 ORDER BY bytes DESC, name",
+                &[],
+            )
+            .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| PartitionInfo {
+                name: row.get("name"),
+                namespace: bound_namespace(row.get("bound")),
+                rows: row.get("rows"),
+                bytes: row.get("bytes"),
+            })
+            .collect())
+    }
+
+    /// Rebuild the vector index of `namespace`'s partition, or of the whole
+    /// table when `None`, and refresh planner statistics. IVFFlat picks its
+    /// clusters when the index is built, so an index built while a namespace
+    /// was small serves it poorly once it has grown.
+    #[instrument(skip(self))]
+    pub async fn reindex(&self, namespace: Option<&str>) -> Result<()> {
+        let _timer = TimingGuard::new("memory_reindex");
+        let table = match namespace {
+            Some(namespace) => self
+                .partition_for(namespace)
+                .await?
+                .ok_or_else(|| MemoryError::InvalidRequest(format!("namespace {} has no partition", namespace)))?
+                .name,
+            None => "memories".to_string(),
+        };
+
+        let client = self.pool.get().await?;
+        // Separate statements: REINDEX of a partitioned table refuses to run
+        // inside the implicit transaction of a multi-statement batch
+        client.batch_execute(&format!("REINDEX TABLE {}", table)).await?;
+        client.batch_execute(&format!("ANALYZE {}", table)).await?;
+        Ok(())
+    }
+
+    async fn partition_for(&self, namespace: &str) -> Result<Option<PartitionInfo>> {
+        Ok(self
+            .partitions()
+            .await?
+            .into_iter()
+            .find(|p| p.namespace.as_deref() == Some(namespace)))
+    }
+}
+
+/// Table name for `namespace`'s partition: a readable slug plus a hash, so
+/// namespaces differing only in punctuation or case don't collide
+pub fn partition_name(namespace: &str) -> String {
+    let slug: String = namespace
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '_' })
+        .take(NAME_SLUG_LEN)
+        .collect();
+    let digest = Sha256::digest(namespace.as_bytes());
+    format!("memories_ns_{}_{:x}", slug, u32::from_be_bytes([digest[0], digest[1], digest[2], digest[3]]))
+}
+
+fn validate_namespace(namespace: &str) -> Result<(), MemoryError> {
+    if namespace.is_empty() || namespace.len() > 1024 || namespace.contains('\0') {
+        return Err(MemoryError::InvalidRequest(format!("invalid namespace: {:?}", namespace)));
+    }
+    Ok(())
+}
+
+fn quote_literal(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+/// The namespace in a `FOR VALUES IN ('...')` bound; `None` for `DEFAULT`
+fn bound_namespace(bound: Option<String>) -> Option<String> {
+    let bound = bound?;
+    let quoted = bound.strip_prefix("FOR VALUES IN (")?.strip_suffix(')')?;
+    let inner = quoted.strip_prefix('\'')?.strip_suffix('\'')?;
+    Some(inner.replace("''", "'"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_partition_name() {
+        let name = partition_name("project:My App");
+        assert!(name.starts_with("memories_ns_project_my_app_"));
+        assert!(name.len() <= 63);
+        assert_ne!(name, partition_name("project:my app"));
+        assert!(partition_name(&"x".repeat(500)).len() <= 63);
+    }
+
+    #[test]
+    fn test_bound_namespace() {
+        let bound = format!("FOR VALUES IN ({})", quote_literal("team's notes"));
+        assert_eq!(bound_namespace(Some(bound)).as_deref(), Some("team's notes"));
+        assert_eq!(bound_namespace(Some("DEFAULT".to_string())), None);
+        assert_eq!(bound_namespace(None), None);
+        assert!(validate_namespace("").is_err());
+    }
+}
@@ -1,11 +1,18 @@
 //! Performance profiling utilities using tracing spans
 //! 
 //! This module provides helpers for instrumenting code with performance metrics
-//! and timing information using the tracing framework.
+//! and timing information using the tracing framework. Timings also go to the
+//! `metrics` facade, which the runtime exports for Prometheus.
 
 use std::time::Instant;
 use tracing::{info, warn, debug, span, Level};
 
+/// Histogram of [`TimingGuard`] durations, labelled by `operation`
+pub const OPERATION_DURATION: &str = "jamey_operation_duration_seconds";
+/// Cache lookups answered from Redis or the in-memory fallback
+pub const CACHE_HITS: &str = "jamey_cache_hits_total";
+pub const CACHE_MISSES: &str = "jamey_cache_misses_total";
+
 /// Performance threshold configuration
 #[derive(Debug, Clone)]
 pub struct PerformanceThresholds {
@@ -74,6 +81,11 @@ impl TimingGuard {
 impl Drop for TimingGuard {
     fn drop(&mut self) {
         let elapsed = self.elapsed_ms();
+        metrics::histogram!(
+            OPERATION_DURATION,
+            self.start.elapsed().as_secs_f64(),
+            "operation" => self.operation.clone()
+        );
         
         if elapsed >= self.thresholds.warn_threshold_ms {
             warn!(
@@ -0,0 +1,350 @@
+//! Embedding quantization
+//!
+//! Each memory type can keep its embeddings at full precision (`vector`,
+//! 4 bytes a value), as `halfvec` (2 bytes) or as scalar int8 codes with a
+//! per-vector scale (1 byte). Half precision loses almost nothing; int8 costs
+//! a little recall and is searched through a binary-quantized index, with
+//! the candidates re-ranked on their dequantized embeddings.
+//!
+//! The extra columns need pgvector 0.7 or later and are only added once a
+//! type is configured for something other than `full`. Existing rows keep
+//! their precision until [`requantize`](PostgresMemoryStore::requantize)
+//! converts them.
+
+use anyhow::Result;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tokio_postgres::types::ToSql;
+use tracing::{info, instrument};
+use uuid::Uuid;
+
+use crate::memory::{embedding_from_text, embedding_to_text, MemoryType, PostgresMemoryStore};
+use crate::profiling::TimingGuard;
+
+/// Rows converted per page while requantizing to int8
+const REQUANTIZE_PAGE_SIZE: i64 = 200;
+
+/// Rebuilds a `vector` from int8 codes and their scale, so SQL can compute
+/// exact distances and index expressions over int8 rows
+const DEQUANTIZE_FUNCTION: &str = "CREATE OR REPLACE FUNCTION jamey_int8_to_vector(codes bytea, scale real)
+RETURNS vector LANGUAGE sql IMMUTABLE STRICT PARALLEL SAFE AS $$
+    SELECT array_agg(((get_byte(codes, i) + 128) % 256 - 128) * scale ORDER BY i)::vector
+    FROM generate_series(0, length(codes) - 1) AS i
+$$";
+
+/// A memory's embedding as stored, whichever column holds it
+pub(crate) const EMBEDDING_EXPR: &str =
+    "COALESCE(embedding, embedding_half::vector, jamey_int8_to_vector(embedding_int8, embedding_scale))";
+
+/// Storage precision of an embedding
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Quantization {
+    #[default]
+    Full,
+    Half,
+    Int8,
+}
+
+/// Precision per memory type (`[memory.quantization]`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct QuantizationConfig {
+    pub conversation: Quantization,
+    pub knowledge: Quantization,
+    pub experience: Quantization,
+    pub skill: Quantization,
+    pub preference: Quantization,
+}
+
+impl QuantizationConfig {
+    pub fn for_type(&self, memory_type: &MemoryType) -> Quantization {
+        match memory_type {
+            MemoryType::Conversation => self.conversation,
+            MemoryType::Knowledge => self.knowledge,
+            MemoryType::Experience => self.experience,
+            MemoryType::Skill => self.skill,
+            MemoryType::Preference => self.preference,
+        }
+    }
+
+    /// Whether any type is stored below full precision
+    pub fn is_enabled(&self) -> bool {
+        self.types().iter().any(|(_, q)| *q != Quantization::Full)
+    }
+
+    fn types(&self) -> [(MemoryType, Quantization); 5] {
+        [
+            (MemoryType::Conversation, self.conversation),
+            (MemoryType::Knowledge, self.knowledge),
+            (MemoryType::Experience, self.experience),
+            (MemoryType::Skill, self.skill),
+            (MemoryType::Preference, self.preference),
+        ]
+    }
+}
+
+/// Values for the `embedding`, `embedding_half`, `embedding_int8` and
+/// `embedding_scale` columns; exactly one representation is set
+pub(crate) struct EncodedEmbedding {
+    pub full: Option<String>,
+    pub half: Option<String>,
+    pub int8: Option<Vec<u8>>,
+    pub scale: Option<f32>,
+}
+
+impl EncodedEmbedding {
+    /// Values to bind, one per column the store writes embeddings to
+    pub fn params(&self, quantized_columns: bool) -> Vec<&(dyn ToSql + Sync)> {
+        if quantized_columns {
+            vec![&self.full, &self.half, &self.int8, &self.scale]
+        } else {
+            vec![&self.full]
+        }
+    }
+
+    pub fn new(embedding: &[f32], quantization: Quantization) -> Self {
+        let text = || Some(embedding_to_text(embedding));
+        match quantization {
+            Quantization::Full => Self { full: text(), half: None, int8: None, scale: None },
+            Quantization::Half => Self { full: None, half: text(), int8: None, scale: None },
+            Quantization::Int8 => {
+                let (codes, scale) = quantize_int8(embedding);
+                Self { full: None, half: None, int8: Some(codes), scale: Some(scale) }
+            }
+        }
+    }
+}
+
+/// Symmetric scalar quantization: the largest magnitude maps to 127
+pub fn quantize_int8(embedding: &[f32]) -> (Vec<u8>, f32) {
+    let max = embedding.iter().fold(0.0f32, |max, v| max.max(v.abs()));
+    let scale = if max > 0.0 { max / 127.0 } else { 1.0 };
+    let codes = embedding
+        .iter()
+        .map(|v| (v / scale).round().clamp(-127.0, 127.0) as i8 as u8)
+        .collect();
+    (codes, scale)
+}
+
+pub fn dequantize_int8(codes: &[u8], scale: f32) -> Vec<f32> {
+    codes.iter().map(|c| *c as i8 as f32 * scale).collect()
+}
+
+impl PostgresMemoryStore {
+    /// Store embeddings at the precision `config` gives each memory type,
+    /// adding the quantized columns and their indexes when first needed
+    pub async fn with_quantization(mut self, config: QuantizationConfig) -> Result<Self> {
+        if config.is_enabled() && !self.quantized_columns {
+            let client = self.pool.get().await?;
+            let dim = self.vector_dim;
+            client
+                .batch_execute(&format!(
+                    "ALTER TABLE memories ALTER COLUMN embedding DROP NOT NULL;
+                     ALTER TABLE memories ADD COLUMN IF NOT EXISTS embedding_half halfvec({dim});
+                     ALTER TABLE memories ADD COLUMN IF NOT EXISTS embedding_int8 BYTEA;
+                     ALTER TABLE memories ADD COLUMN IF NOT EXISTS embedding_scale REAL;
+                     CREATE INDEX IF NOT EXISTS memories_embedding_half_idx ON memories
+                         USING ivfflat (embedding_half halfvec_cosine_ops) WITH (lists = 100);",
+                    dim = dim
+                ))
+                .await?;
+            client.batch_execute(DEQUANTIZE_FUNCTION).await?;
+            client
+                .batch_execute(&format!(
+                    "CREATE INDEX IF NOT EXISTS memories_embedding_int8_idx ON memories
+                         USING hnsw ((binary_quantize(jamey_int8_to_vector(embedding_int8, embedding_scale))::bit({dim}))
+                                     bit_hamming_ops)
+                         WHERE embedding_int8 IS NOT NULL",
+                    dim = dim
+                ))
+                .await?;
+            self.quantized_columns = true;
+        }
+        self.quantization = config;
+        Ok(self)
+    }
+
+    pub(crate) fn encode_embedding(&self, memory_type: &MemoryType, embedding: &[f32]) -> EncodedEmbedding {
+        EncodedEmbedding::new(embedding, self.quantization.for_type(memory_type))
+    }
+
+    /// `column = $n` assignments for an [`EncodedEmbedding`] bound from
+    /// parameter `$first` on, in the order of [`EncodedEmbedding::params`]
+    pub(crate) fn embedding_assignments(&self, first: usize) -> String {
+        self.embedding_columns()
+            .iter()
+            .enumerate()
+            .map(|(i, (column, cast))| format!("{} = ${}{}", column, first + i, cast))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// Column list and matching placeholders for inserting an
+    /// [`EncodedEmbedding`] bound from parameter `$first` on
+    pub(crate) fn embedding_insert(&self, first: usize) -> (String, String) {
+        let columns = self.embedding_columns();
+        (
+            columns.iter().map(|(column, _)| *column).collect::<Vec<_>>().join(", "),
+            columns
+                .iter()
+                .enumerate()
+                .map(|(i, (_, cast))| format!("${}{}", first + i, cast))
+                .collect::<Vec<_>>()
+                .join(", "),
+        )
+    }
+
+    /// `column = EXCLUDED.column` assignments for an upsert that inserted
+    /// an [`EncodedEmbedding`]
+    pub(crate) fn embedding_excluded(&self) -> String {
+        self.embedding_columns()
+            .iter()
+            .map(|(column, _)| format!("{0} = EXCLUDED.{0}", column))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    fn embedding_columns(&self) -> &'static [(&'static str, &'static str)] {
+        if self.quantized_columns {
+            &[
+                ("embedding", "::vector"),
+                ("embedding_half", "::halfvec"),
+                ("embedding_int8", ""),
+                ("embedding_scale", ""),
+            ]
+        } else {
+            &[("embedding", "::vector")]
+        }
+    }
+
+    /// SQL for a row's embedding as a `vector`, whichever column holds it
+    pub(crate) fn embedding_sql(&self) -> &'static str {
+        if self.quantized_columns {
+            EMBEDDING_EXPR
+        } else {
+            "embedding"
+        }
+    }
+
+    /// [`embedding_sql`](Self::embedding_sql) for the table aliased `alias`
+    pub(crate) fn embedding_sql_of(&self, alias: &str) -> String {
+        if self.quantized_columns {
+            format!(
+                "COALESCE({a}.embedding, {a}.embedding_half::vector, jamey_int8_to_vector({a}.embedding_int8, {a}.embedding_scale))",
+                a = alias
+            )
+        } else {
+            format!("{}.embedding", alias)
+        }
+    }
+
+    /// Convert stored embeddings to the precision now configured for their
+    /// type. Returns the number of rows converted. Going back to full
+    /// precision doesn't recover what quantizing dropped.
+    #[instrument(skip(self))]
+    pub async fn requantize(&self) -> Result<u64> {
+        let _timer = TimingGuard::new("memory_requantize");
+        if !self.quantized_columns {
+            return Ok(0);
+        }
+        let client = self.pool.get().await?;
+        let mut converted = 0;
+        for (memory_type, quantization) in self.quantization.types() {
+            let type_name = memory_type.to_string();
+            match quantization {
+                Quantization::Full => {
+                    converted += client
+                        .execute(
+                            &format!(
+                                "UPDATE memories
+                                 SET embedding = {}, embedding_half = NULL, embedding_int8 = NULL, embedding_scale = NULL
+                                 WHERE memory_type = $1 AND embedding IS NULL",
+                                EMBEDDING_EXPR
+                            ),
+                            &[&type_name],
+                        )
+                        .await?;
+                }
+                Quantization::Half => {
+                    converted += client
+                        .execute(
+                            &format!(
+                                "UPDATE memories
+                                 SET embedding_half = ({})::halfvec, embedding = NULL, embedding_int8 = NULL, embedding_scale = NULL
+                                 WHERE memory_type = $1 AND embedding_half IS NULL",
+                                EMBEDDING_EXPR
+                            ),
+                            &[&type_name],
+                        )
+                        .await?;
+                }
+                // The scale is per vector, which is easier to work out here
+                Quantization::Int8 => loop {
+                    let rows = client
+                        .query(
+                            &format!(
+                                "SELECT id, {}::text AS embedding FROM memories
+                                 WHERE memory_type = $1 AND embedding_int8 IS NULL
+                                 LIMIT $2",
+                                EMBEDDING_EXPR
+                            ),
+                            &[&type_name, &REQUANTIZE_PAGE_SIZE],
+                        )
+                        .await?;
+                    if rows.is_empty() {
+                        break;
+                    }
+                    for row in &rows {
+                        let id: Uuid = row.get("id");
+                        let embedding = embedding_from_text(row.get("embedding"))?;
+                        let (codes, scale) = quantize_int8(&embedding);
+                        client
+                            .execute(
+                                "UPDATE memories
+                                 SET embedding_int8 = $2, embedding_scale = $3, embedding = NULL, embedding_half = NULL
+                                 WHERE id = $1",
+                                &[&id, &codes, &scale],
+                            )
+                            .await?;
+                        converted += 1;
+                    }
+                },
+            }
+        }
+        info!("Requantized {} embeddings", converted);
+        Ok(converted)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::cosine_similarity;
+
+    #[test]
+    fn test_int8_round_trip() {
+        let embedding: Vec<f32> = (0..64).map(|i| ((i as f32) * 0.37).sin() * 0.2).collect();
+        let (codes, scale) = quantize_int8(&embedding);
+        assert_eq!(codes.len(), embedding.len());
+        let restored = dequantize_int8(&codes, scale);
+        assert!(cosine_similarity(&embedding, &restored) > 0.999);
+        for (a, b) in embedding.iter().zip(&restored) {
+            assert!((a - b).abs() <= scale / 2.0 + f32::EPSILON);
+        }
+
+        let (codes, scale) = quantize_int8(&[0.0, 0.0]);
+        assert_eq!(dequantize_int8(&codes, scale), vec![0.0, 0.0]);
+    }
+
+    #[test]
+    fn test_config_per_type() {
+        let config: QuantizationConfig =
+            serde_json::from_str(r#"{"conversation": "int8", "knowledge": "half"}"#).unwrap();
+        assert_eq!(config.for_type(&MemoryType::Conversation), Quantization::Int8);
+        assert_eq!(config.for_type(&MemoryType::Knowledge), Quantization::Half);
+        assert_eq!(config.for_type(&MemoryType::Skill), Quantization::Full);
+        assert!(config.is_enabled());
+        assert!(!QuantizationConfig::default().is_enabled());
+    }
+}
@@ -0,0 +1,246 @@
+//! Retrieval scoring
+//!
+//! Search ranks memories by a weighted sum of four signals, each in 0..=1:
+//!
+//! - similarity: cosine similarity to the query
+//! - recency: halves every `recency_half_life_hours` since the memory was
+//!   last accessed
+//! - importance: the memory's `importance` metadata, 0.5 when it has none
+//! - frequency: `n / (n + 5)` for a memory accessed `n` times
+//!
+//! [`PostgresMemoryStore`](crate::memory::PostgresMemoryStore) computes the
+//! score in SQL over the closest candidates by distance, other stores call
+//! [`RetrievalWeights::score`]; both use the same formula.
+//!
+//! `search_explain` on either store returns each hit's signals alongside its
+//! score, as a [`SearchExplanation`], to debug why a memory was recalled.
+
+use crate::memory::{cosine_similarity, Memory};
+use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Candidates fetched by distance per result asked for, before re-ranking
+pub const CANDIDATE_FACTOR: usize = 4;
+
+/// Accesses at which the frequency signal reaches one half
+const FREQUENCY_MIDPOINT: f64 = 5.0;
+
+/// Importance of a memory without an `importance` value
+const DEFAULT_IMPORTANCE: f64 = 0.5;
+
+/// How much each signal counts towards a memory's retrieval score
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct RetrievalWeights {
+    pub similarity: f64,
+    pub recency: f64,
+    pub importance: f64,
+    pub frequency: f64,
+    /// Hours after which the recency signal has halved
+    pub recency_half_life_hours: f64,
+}
+
+impl Default for RetrievalWeights {
+    fn default() -> Self {
+        Self {
+            similarity: 1.0,
+            recency: 0.15,
+            importance: 0.1,
+            frequency: 0.05,
+            recency_half_life_hours: 168.0,
+        }
+    }
+}
+
+impl RetrievalWeights {
+    /// Plain nearest-neighbour ordering
+    pub fn similarity_only() -> Self {
+        Self { similarity: 1.0, recency: 0.0, importance: 0.0, frequency: 0.0, ..Self::default() }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        let weights = [
+            ("similarity", self.similarity),
+            ("recency", self.recency),
+            ("importance", self.importance),
+            ("frequency", self.frequency),
+        ];
+        for (name, weight) in weights {
+            if !weight.is_finite() || weight < 0.0 {
+                return Err(format!("{} weight must be a non-negative number", name));
+            }
+        }
+        if self.similarity == 0.0 {
+            return Err("similarity weight must be above 0".to_string());
+        }
+        if !self.recency_half_life_hours.is_finite() || self.recency_half_life_hours <= 0.0 {
+            return Err("recency_half_life_hours must be above 0".to_string());
+        }
+        Ok(())
+    }
+
+    /// Score of `memory` for `query_embedding`, given how often it has been
+    /// accessed
+    pub fn score(&self, query_embedding: &[f32], memory: &Memory, access_count: u64, now: DateTime<Utc>) -> f64 {
+        let similarity = cosine_similarity(query_embedding, &memory.embedding) as f64;
+        self.explain(similarity, memory, access_count, now).score
+    }
+
+    /// The signals behind `memory`'s score, given its similarity to the query
+    pub fn explain(&self, similarity: f64, memory: &Memory, access_count: u64, now: DateTime<Utc>) -> ScoreBreakdown {
+        let recency = recency(memory.last_accessed, now, self.recency_half_life_hours);
+        let importance = importance(&memory.metadata);
+        let frequency = frequency(access_count);
+        ScoreBreakdown {
+            similarity,
+            recency,
+            importance,
+            frequency,
+            access_count,
+            score: self.similarity * similarity
+                + self.recency * recency
+                + self.importance * importance
+                + self.frequency * frequency,
+        }
+    }
+
+    /// The score formula with these weights filled in
+    pub fn formula(&self) -> String {
+        format!(
+            "{} × similarity + {} × recency (half-life {}h) + {} × importance + {} × frequency",
+            self.similarity, self.recency, self.recency_half_life_hours, self.importance, self.frequency
+        )
+    }
+
+    /// The score as an SQL expression over a row with `distance` (cosine
+    /// distance to the query), `last_accessed`, `metadata` and `access_count`
+    pub(crate) fn sql(&self) -> String {
+        format!(
+            "({similarity} * (1 - distance)
+              + {recency} * power(0.5, GREATEST(EXTRACT(EPOCH FROM NOW() - last_accessed), 0) / 3600.0 / {half_life})
+              + {importance} * (CASE WHEN jsonb_typeof(metadata->'importance') = 'number'
+                                     THEN LEAST(GREATEST((metadata->>'importance')::float8, 0), 1)
+                                     ELSE {default_importance} END)
+              + {frequency} * (access_count::float8 / (access_count + {midpoint})))",
+            similarity = self.similarity,
+            recency = self.recency,
+            half_life = self.recency_half_life_hours,
+            importance = self.importance,
+            default_importance = DEFAULT_IMPORTANCE,
+            frequency = self.frequency,
+            midpoint = FREQUENCY_MIDPOINT,
+        )
+    }
+}
+
+/// Each unweighted signal behind one search hit, and the weighted score
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ScoreBreakdown {
+    pub similarity: f64,
+    pub recency: f64,
+    pub importance: f64,
+    pub frequency: f64,
+    /// Accesses the frequency signal was computed from
+    pub access_count: u64,
+    pub score: f64,
+}
+
+/// A search hit with the signals that ranked it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExplainedHit {
+    pub memory: Memory,
+    pub breakdown: ScoreBreakdown,
+}
+
+/// Search results with their score breakdowns, best first
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchExplanation {
+    /// [`RetrievalWeights::formula`] for the weights the search ranked with
+    pub formula: String,
+    pub weights: RetrievalWeights,
+    pub hits: Vec<ExplainedHit>,
+}
+
+/// 1 for a memory accessed `now`, halving every `half_life_hours`
+pub fn recency(last_accessed: DateTime<Utc>, now: DateTime<Utc>, half_life_hours: f64) -> f64 {
+    let hours = (now - last_accessed).num_seconds().max(0) as f64 / 3600.0;
+    0.5f64.powf(hours / half_life_hours)
+}
+
+/// The `importance` metadata clamped to 0..=1
+pub fn importance(metadata: &serde_json::Value) -> f64 {
+    metadata
+        .get("importance")
+        .and_then(|v| v.as_f64())
+        .map_or(DEFAULT_IMPORTANCE, |v| v.clamp(0.0, 1.0))
+}
+
+pub fn frequency(access_count: u64) -> f64 {
+    let n = access_count as f64;
+    n / (n + FREQUENCY_MIDPOINT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::MemoryType;
+    use chrono::Duration;
+
+    fn memory(embedding: Vec<f32>, metadata: serde_json::Value, age: Duration) -> Memory {
+        Memory {
+            id: uuid::Uuid::new_v4(),
+            memory_type: MemoryType::Knowledge,
+            content: "note".to_string(),
+            embedding,
+            metadata,
+            created_at: Utc::now() - age,
+            last_accessed: Utc::now() - age,
+        }
+    }
+
+    #[test]
+    fn test_signals() {
+        let now = Utc::now();
+        assert_eq!(recency(now, now, 24.0), 1.0);
+        assert!((recency(now - Duration::hours(24), now, 24.0) - 0.5).abs() < 1e-9);
+        assert_eq!(importance(&serde_json::json!({})), 0.5);
+        assert_eq!(importance(&serde_json::json!({"importance": 3})), 1.0);
+        assert_eq!(importance(&serde_json::json!({"importance": "high"})), 0.5);
+        assert_eq!(frequency(0), 0.0);
+        assert_eq!(frequency(5), 0.5);
+    }
+
+    #[test]
+    fn test_score_trades_similarity_for_other_signals() {
+        let weights = RetrievalWeights::default();
+        let now = Utc::now();
+        let query = [1.0, 0.0];
+        let stale = memory(vec![1.0, 0.05], serde_json::json!({}), Duration::days(60));
+        let fresh = memory(vec![1.0, 0.2], serde_json::json!({"importance": 0.9}), Duration::zero());
+
+        assert!(weights.score(&query, &fresh, 10, now) > weights.score(&query, &stale, 0, now));
+        let plain = RetrievalWeights::similarity_only();
+        assert!(plain.score(&query, &fresh, 10, now) < plain.score(&query, &stale, 0, now));
+
+        assert!(weights.validate().is_ok());
+        assert!(RetrievalWeights { recency: -1.0, ..weights }.validate().is_err());
+        assert!(RetrievalWeights { similarity: 0.0, ..weights }.validate().is_err());
+        assert!(RetrievalWeights { recency_half_life_hours: 0.0, ..weights }.validate().is_err());
+    }
+
+    #[test]
+    fn test_explain_adds_up_to_score() {
+        let weights = RetrievalWeights::default();
+        let now = Utc::now();
+        let mut note = memory(vec![1.0, 0.2], serde_json::json!({"importance": 0.9}), Duration::zero());
+        note.last_accessed = now - Duration::hours(168);
+
+        let breakdown = weights.explain(cosine_similarity(&[1.0, 0.0], &note.embedding) as f64, &note, 5, now);
+        assert!((breakdown.recency - 0.5).abs() < 1e-9);
+        assert_eq!(breakdown.importance, 0.9);
+        assert_eq!(breakdown.frequency, 0.5);
+        assert_eq!(breakdown.score, weights.score(&[1.0, 0.0], &note, 5, now));
+        assert!(weights.formula().contains("0.15 × recency (half-life 168h)"));
+    }
+}
@@ -0,0 +1,552 @@
+//! Secret storage backends
+//!
+//! Pluggable stores used by [`SecretManager`](crate::secrets::SecretManager):
+//! the OS keyring (default), HashiCorp Vault KV v2, AWS Secrets Manager and a
+//! local AES-256-GCM encrypted file. Backends are synchronous so the manager
+//! keeps its blocking API; the HTTP backends use `ureq` and never touch the
+//! tokio runtime.
+
+use crate::secrets::SecretError;
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use hmac::{Hmac, Mac};
+use keyring::Entry;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+const HTTP_TIMEOUT: Duration = Duration::from_secs(10);
+const PBKDF2_ROUNDS: u32 = 210_000;
+
+/// A store that can hold secrets addressed by service and key
+pub trait SecretBackend: Send + Sync {
+    /// Short backend identifier used in logs
+    fn name(&self) -> &'static str;
+
+    fn get(&self, service: &str, key: &str) -> Result<String, SecretError>;
+
+    fn set(&self, service: &str, key: &str, value: &str) -> Result<(), SecretError>;
+
+    fn delete(&self, service: &str, key: &str) -> Result<(), SecretError>;
+}
+
+/// OS keychain (macOS Keychain, Windows Credential Manager, Secret Service)
+#[derive(Debug, Default, Clone, Copy)]
+pub struct KeyringBackend;
+
+impl SecretBackend for KeyringBackend {
+    fn name(&self) -> &'static str {
+        "keyring"
+    }
+
+    fn get(&self, service: &str, key: &str) -> Result<String, SecretError> {
+        let entry = Entry::new(service, key)?;
+        match entry.get_password() {
+            Ok(value) => Ok(value),
+            Err(keyring::Error::NoEntry) => Err(SecretError::NotFound(key.to_string())),
+            Err(e) => Err(SecretError::RetrievalError(Box::new(e))),
+        }
+    }
+
+    fn set(&self, service: &str, key: &str, value: &str) -> Result<(), SecretError> {
+        let entry = Entry::new(service, key)?;
+        entry.set_password(value)?;
+        Ok(())
+    }
+
+    fn delete(&self, service: &str, key: &str) -> Result<(), SecretError> {
+        let entry = Entry::new(service, key)?;
+        match entry.delete_password() {
+            Ok(_) => Ok(()),
+            Err(keyring::Error::NoEntry) => Err(SecretError::NotFound(key.to_string())),
+            Err(e) => Err(SecretError::StoreError(e)),
+        }
+    }
+}
+
+/// HashiCorp Vault KV version 2 engine
+///
+/// Secrets live at `{mount}/data/{service}/{key}` with the value stored under
+/// the `value` field.
+pub struct VaultBackend {
+    address: String,
+    token: String,
+    mount: String,
+    agent: ureq::Agent,
+}
+
+impl VaultBackend {
+    pub fn new(address: impl Into<String>, token: impl Into<String>) -> Self {
+        Self {
+            address: address.into().trim_end_matches('/').to_string(),
+            token: token.into(),
+            mount: "secret".to_string(),
+            agent: ureq::AgentBuilder::new().timeout(HTTP_TIMEOUT).build(),
+        }
+    }
+
+    /// Build from `VAULT_ADDR` and `VAULT_TOKEN`
+    pub fn from_env() -> Result<Self, SecretError> {
+        let address = std::env::var("VAULT_ADDR")
+            .map_err(|_| SecretError::Backend("VAULT_ADDR is not set".to_string()))?;
+        let token = std::env::var("VAULT_TOKEN")
+            .map_err(|_| SecretError::Backend("VAULT_TOKEN is not set".to_string()))?;
+        Ok(Self::new(address, token))
+    }
+
+    /// Use a KV mount other than the default `secret`
+    pub fn with_mount(mut self, mount: impl Into<String>) -> Self {
+        self.mount = mount.into();
+        self
+    }
+
+    fn url(&self, kind: &str, service: &str, key: &str) -> String {
+        format!("{}/v1/{}/{}/{}/{}", self.address, self.mount, kind, service, key)
+    }
+}
+
+impl SecretBackend for VaultBackend {
+    fn name(&self) -> &'static str {
+        "vault"
+    }
+
+    fn get(&self, service: &str, key: &str) -> Result<String, SecretError> {
+        let response = self.agent
+            .get(&self.url("data", service, key))
+            .set("X-Vault-Token", &self.token)
+            .call();
+
+        match response {
+            Ok(resp) => {
+                let body: serde_json::Value = resp.into_json()
+                    .map_err(|e| SecretError::Backend(format!("Invalid Vault response: {}", e)))?;
+                body.pointer("/data/data/value")
+                    .and_then(|v| v.as_str())
+                    .map(str::to_string)
+                    .ok_or_else(|| SecretError::NotFound(key.to_string()))
+            }
+            Err(ureq::Error::Status(404, _)) => Err(SecretError::NotFound(key.to_string())),
+            Err(e) => Err(SecretError::Backend(format!("Vault request failed: {}", e))),
+        }
+    }
+
+    fn set(&self, service: &str, key: &str, value: &str) -> Result<(), SecretError> {
+        self.agent
+            .post(&self.url("data", service, key))
+            .set("X-Vault-Token", &self.token)
+            .send_json(serde_json::json!({ "data": { "value": value } }))
+            .map_err(|e| SecretError::Backend(format!("Vault request failed: {}", e)))?;
+        Ok(())
+    }
+
+    fn delete(&self, service: &str, key: &str) -> Result<(), SecretError> {
+        // Deleting metadata removes every version, not just the latest
+        match self.agent
+            .delete(&self.url("metadata", service, key))
+            .set("X-Vault-Token", &self.token)
+            .call()
+        {
+            Ok(_) => Ok(()),
+            Err(ureq::Error::Status(404, _)) => Err(SecretError::NotFound(key.to_string())),
+            Err(e) => Err(SecretError::Backend(format!("Vault request failed: {}", e))),
+        }
+    }
+}
+
+/// AWS Secrets Manager, addressed as `{service}/{key}`
+///
+/// Requests are signed with SigV4 using static credentials.
+pub struct AwsSecretsManagerBackend {
+    region: String,
+    access_key_id: String,
+    secret_access_key: String,
+    session_token: Option<String>,
+    agent: ureq::Agent,
+}
+
+impl AwsSecretsManagerBackend {
+    pub fn new(
+        region: impl Into<String>,
+        access_key_id: impl Into<String>,
+        secret_access_key: impl Into<String>,
+    ) -> Self {
+        Self {
+            region: region.into(),
+            access_key_id: access_key_id.into(),
+            secret_access_key: secret_access_key.into(),
+            session_token: None,
+            agent: ureq::AgentBuilder::new().timeout(HTTP_TIMEOUT).build(),
+        }
+    }
+
+    /// Build from the standard `AWS_*` environment variables
+    pub fn from_env() -> Result<Self, SecretError> {
+        let var = |name: &str| {
+            std::env::var(name).map_err(|_| SecretError::Backend(format!("{} is not set", name)))
+        };
+        let region = var("AWS_REGION").or_else(|_| var("AWS_DEFAULT_REGION"))?;
+        let mut backend = Self::new(region, var("AWS_ACCESS_KEY_ID")?, var("AWS_SECRET_ACCESS_KEY")?);
+        backend.session_token = std::env::var("AWS_SESSION_TOKEN").ok();
+        Ok(backend)
+    }
+
+    /// Attach a session token for temporary (STS) credentials
+    pub fn with_session_token(mut self, token: impl Into<String>) -> Self {
+        self.session_token = Some(token.into());
+        self
+    }
+
+    fn host(&self) -> String {
+        format!("secretsmanager.{}.amazonaws.com", self.region)
+    }
+
+    /// Call a Secrets Manager JSON action, returning the error `__type` on failure
+    fn call(&self, target: &str, payload: serde_json::Value) -> Result<serde_json::Value, (Option<String>, String)> {
+        let body = payload.to_string();
+        let now = chrono::Utc::now();
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let date = now.format("%Y%m%d").to_string();
+        let host = self.host();
+        let target = format!("secretsmanager.{}", target);
+        let content_type = "application/x-amz-json-1.1";
+
+        let mut headers = vec![
+            ("content-type", content_type.to_string()),
+            ("host", host.clone()),
+            ("x-amz-date", amz_date.clone()),
+            ("x-amz-target", target.clone()),
+        ];
+        if let Some(token) = &self.session_token {
+            headers.push(("x-amz-security-token", token.clone()));
+        }
+
+        let canonical_headers: String = headers.iter()
+            .map(|(k, v)| format!("{}:{}\n", k, v.trim()))
+            .collect();
+        let signed_headers = headers.iter().map(|(k, _)| *k).collect::<Vec<_>>().join(";");
+        let canonical_request = format!(
+            "POST\n/\n\n{}\n{}\n{}",
+            canonical_headers,
+            signed_headers,
+            hex(&Sha256::digest(body.as_bytes()))
+        );
+
+        let scope = format!("{}/{}/secretsmanager/aws4_request", date, self.region);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            amz_date,
+            scope,
+            hex(&Sha256::digest(canonical_request.as_bytes()))
+        );
+
+        let k_date = hmac_sha256(format!("AWS4{}", self.secret_access_key).as_bytes(), date.as_bytes());
+        let k_region = hmac_sha256(&k_date, self.region.as_bytes());
+        let k_service = hmac_sha256(&k_region, b"secretsmanager");
+        let k_signing = hmac_sha256(&k_service, b"aws4_request");
+        let signature = hex(&hmac_sha256(&k_signing, string_to_sign.as_bytes()));
+
+        let authorization = format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+            self.access_key_id, scope, signed_headers, signature
+        );
+
+        let mut request = self.agent
+            .post(&format!("https://{}/", host))
+            .set("Authorization", &authorization);
+        for (name, value) in &headers {
+            if *name != "host" {
+                request = request.set(name, value);
+            }
+        }
+
+        match request.send_string(&body) {
+            Ok(resp) => resp.into_json().map_err(|e| (None, e.to_string())),
+            Err(ureq::Error::Status(_, resp)) => {
+                let body: serde_json::Value = resp.into_json().unwrap_or_default();
+                let kind = body.get("__type").and_then(|t| t.as_str()).map(str::to_string);
+                let message = body.get("message")
+                    .or_else(|| body.get("Message"))
+                    .and_then(|m| m.as_str())
+                    .unwrap_or("request failed")
+                    .to_string();
+                Err((kind, message))
+            }
+            Err(e) => Err((None, e.to_string())),
+        }
+    }
+}
+
+impl SecretBackend for AwsSecretsManagerBackend {
+    fn name(&self) -> &'static str {
+        "aws_secrets_manager"
+    }
+
+    fn get(&self, service: &str, key: &str) -> Result<String, SecretError> {
+        let secret_id = format!("{}/{}", service, key);
+        match self.call("GetSecretValue", serde_json::json!({ "SecretId": secret_id })) {
+            Ok(body) => body.get("SecretString")
+                .and_then(|s| s.as_str())
+                .map(str::to_string)
+                .ok_or_else(|| SecretError::NotFound(key.to_string())),
+            Err((Some(kind), _)) if kind.ends_with("ResourceNotFoundException") => {
+                Err(SecretError::NotFound(key.to_string()))
+            }
+            Err((_, message)) => Err(SecretError::Backend(format!("AWS Secrets Manager: {}", message))),
+        }
+    }
+
+    fn set(&self, service: &str, key: &str, value: &str) -> Result<(), SecretError> {
+        let secret_id = format!("{}/{}", service, key);
+        let put = self.call(
+            "PutSecretValue",
+            serde_json::json!({ "SecretId": secret_id, "SecretString": value }),
+        );
+        match put {
+            Ok(_) => Ok(()),
+            Err((Some(kind), _)) if kind.ends_with("ResourceNotFoundException") => {
+                self.call("CreateSecret", serde_json::json!({ "Name": secret_id, "SecretString": value }))
+                    .map(|_| ())
+                    .map_err(|(_, message)| SecretError::Backend(format!("AWS Secrets Manager: {}", message)))
+            }
+            Err((_, message)) => Err(SecretError::Backend(format!("AWS Secrets Manager: {}", message))),
+        }
+    }
+
+    fn delete(&self, service: &str, key: &str) -> Result<(), SecretError> {
+        let secret_id = format!("{}/{}", service, key);
+        match self.call(
+            "DeleteSecret",
+            serde_json::json!({ "SecretId": secret_id, "ForceDeleteWithoutRecovery": true }),
+        ) {
+            Ok(_) => Ok(()),
+            Err((Some(kind), _)) if kind.ends_with("ResourceNotFoundException") => {
+                Err(SecretError::NotFound(key.to_string()))
+            }
+            Err((_, message)) => Err(SecretError::Backend(format!("AWS Secrets Manager: {}", message))),
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct EncryptedFile {
+    salt: String,
+    entries: BTreeMap<String, EncryptedEntry>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct EncryptedEntry {
+    nonce: String,
+    ciphertext: String,
+}
+
+/// AES-256-GCM encrypted JSON file, keyed by a passphrase (PBKDF2-SHA256)
+///
+/// Useful on headless hosts without a keyring daemon.
+pub struct EncryptedFileBackend {
+    path: PathBuf,
+    cipher: Aes256Gcm,
+    salt: Vec<u8>,
+    // Serialises read-modify-write cycles on the file
+    lock: Mutex<()>,
+}
+
+impl EncryptedFileBackend {
+    pub fn new(path: impl Into<PathBuf>, passphrase: &str) -> Result<Self, SecretError> {
+        if passphrase.len() < 12 {
+            return Err(SecretError::Backend("Passphrase must be at least 12 characters".to_string()));
+        }
+
+        let path = path.into();
+        let salt = match Self::load(&path)? {
+            Some(file) => BASE64.decode(&file.salt)
+                .map_err(|e| SecretError::Backend(format!("Corrupt secrets file salt: {}", e)))?,
+            None => {
+                use rand::Rng;
+                let mut salt = vec![0u8; 16];
+                rand::rng().fill(&mut salt[..]);
+                salt
+            }
+        };
+
+        let mut key = [0u8; 32];
+        pbkdf2::pbkdf2_hmac::<Sha256>(passphrase.as_bytes(), &salt, PBKDF2_ROUNDS, &mut key);
+        let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key));
+
+        Ok(Self {
+            path,
+            cipher,
+            salt,
+            lock: Mutex::new(()),
+        })
+    }
+
+    fn load(path: &PathBuf) -> Result<Option<EncryptedFile>, SecretError> {
+        match std::fs::read_to_string(path) {
+            Ok(content) => serde_json::from_str(&content)
+                .map(Some)
+                .map_err(|e| SecretError::Backend(format!("Corrupt secrets file: {}", e))),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(SecretError::Backend(format!("Failed to read secrets file: {}", e))),
+        }
+    }
+
+    fn save(&self, file: &EncryptedFile) -> Result<(), SecretError> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| SecretError::Backend(format!("Failed to create secrets dir: {}", e)))?;
+        }
+        let content = serde_json::to_string_pretty(file)
+            .map_err(|e| SecretError::Backend(e.to_string()))?;
+
+        // Write then rename so a crash never leaves a truncated file
+        let tmp = self.path.with_extension("tmp");
+        std::fs::write(&tmp, content)
+            .map_err(|e| SecretError::Backend(format!("Failed to write secrets file: {}", e)))?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let _ = std::fs::set_permissions(&tmp, std::fs::Permissions::from_mode(0o600));
+        }
+        std::fs::rename(&tmp, &self.path)
+            .map_err(|e| SecretError::Backend(format!("Failed to write secrets file: {}", e)))
+    }
+
+    fn load_or_new(&self) -> Result<EncryptedFile, SecretError> {
+        Ok(Self::load(&self.path)?.unwrap_or_else(|| EncryptedFile {
+            salt: BASE64.encode(&self.salt),
+            entries: BTreeMap::new(),
+        }))
+    }
+}
+
+impl SecretBackend for EncryptedFileBackend {
+    fn name(&self) -> &'static str {
+        "encrypted_file"
+    }
+
+    fn get(&self, service: &str, key: &str) -> Result<String, SecretError> {
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        let file = self.load_or_new()?;
+        let entry = file.entries.get(&format!("{}/{}", service, key))
+            .ok_or_else(|| SecretError::NotFound(key.to_string()))?;
+
+        let nonce = BASE64.decode(&entry.nonce)
+            .map_err(|e| SecretError::Backend(format!("Corrupt nonce: {}", e)))?;
+        let ciphertext = BASE64.decode(&entry.ciphertext)
+            .map_err(|e| SecretError::Backend(format!("Corrupt ciphertext: {}", e)))?;
+        if nonce.len() != 12 {
+            return Err(SecretError::Backend("Corrupt nonce length".to_string()));
+        }
+        let plaintext = self.cipher
+            .decrypt(Nonce::from_slice(&nonce), ciphertext.as_ref())
+            .map_err(|_| SecretError::Backend("Decryption failed (wrong passphrase?)".to_string()))?;
+
+        String::from_utf8(plaintext)
+            .map_err(|e| SecretError::InvalidValue(e.to_string()))
+    }
+
+    fn set(&self, service: &str, key: &str, value: &str) -> Result<(), SecretError> {
+        use rand::Rng;
+
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        let mut file = self.load_or_new()?;
+
+        let mut nonce = [0u8; 12];
+        rand::rng().fill(&mut nonce);
+        let ciphertext = self.cipher
+            .encrypt(Nonce::from_slice(&nonce), value.as_bytes())
+            .map_err(|_| SecretError::Backend("Encryption failed".to_string()))?;
+
+        file.entries.insert(
+            format!("{}/{}", service, key),
+            EncryptedEntry {
+                nonce: BASE64.encode(nonce),
+                ciphertext: BASE64.encode(ciphertext),
+            },
+        );
+        self.save(&file)
+    }
+
+    fn delete(&self, service: &str, key: &str) -> Result<(), SecretError> {
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        let mut file = self.load_or_new()?;
+        if file.entries.remove(&format!("{}/{}", service, key)).is_none() {
+            return Err(SecretError::NotFound(key.to_string()));
+        }
+        self.save(&file)
+    }
+}
+
+/// Select a backend from `JAMEY_SECRETS_BACKEND` (`keyring`, `vault`, `aws`
+/// or `file`), defaulting to the OS keyring.
+///
+/// The file backend reads `JAMEY_SECRETS_FILE` and `JAMEY_SECRETS_PASSPHRASE`.
+pub fn backend_from_env() -> Result<Arc<dyn SecretBackend>, SecretError> {
+    let kind = std::env::var("JAMEY_SECRETS_BACKEND").unwrap_or_else(|_| "keyring".to_string());
+    match kind.to_lowercase().as_str() {
+        "keyring" => Ok(Arc::new(KeyringBackend)),
+        "vault" => Ok(Arc::new(VaultBackend::from_env()?)),
+        "aws" | "aws_secrets_manager" => Ok(Arc::new(AwsSecretsManagerBackend::from_env()?)),
+        "file" | "encrypted_file" => {
+            let path = std::env::var("JAMEY_SECRETS_FILE")
+                .map_err(|_| SecretError::Backend("JAMEY_SECRETS_FILE is not set".to_string()))?;
+            let passphrase = std::env::var("JAMEY_SECRETS_PASSPHRASE")
+                .map_err(|_| SecretError::Backend("JAMEY_SECRETS_PASSPHRASE is not set".to_string()))?;
+            Ok(Arc::new(EncryptedFileBackend::new(path, &passphrase)?))
+        }
+        other => Err(SecretError::Backend(format!("Unknown secrets backend: {}", other))),
+    }
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encrypted_file_roundtrip() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("secrets.json");
+        let backend = EncryptedFileBackend::new(&path, "correct horse battery").unwrap();
+
+        backend.set("jamey", "api_key", "sk-test-123").unwrap();
+        assert_eq!(backend.get("jamey", "api_key").unwrap(), "sk-test-123");
+
+        // Plaintext must never hit the disk
+        let raw = std::fs::read_to_string(&path).unwrap();
+        assert!(!raw.contains("sk-test-123"));
+
+        // Reopening with the same passphrase reuses the stored salt
+        let reopened = EncryptedFileBackend::new(&path, "correct horse battery").unwrap();
+        assert_eq!(reopened.get("jamey", "api_key").unwrap(), "sk-test-123");
+
+        let wrong = EncryptedFileBackend::new(&path, "wrong passphrase!").unwrap();
+        assert!(wrong.get("jamey", "api_key").is_err());
+
+        backend.delete("jamey", "api_key").unwrap();
+        assert!(matches!(backend.get("jamey", "api_key"), Err(SecretError::NotFound(_))));
+    }
+
+    #[test]
+    fn test_hmac_sha256_known_vector() {
+        // RFC 4231 test case 2
+        let mac = hmac_sha256(b"Jefe", b"what do ya want for nothing?");
+        assert_eq!(
+            hex(&mac),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+}
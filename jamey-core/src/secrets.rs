@@ -1,6 +1,10 @@
-use keyring::Entry;
+use crate::secret_backends::{KeyringBackend, SecretBackend};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
 use thiserror::Error;
 use subtle::ConstantTimeEq;
+use tokio::sync::broadcast;
 
 /// Errors that can occur during secret management operations
 #[derive(Error, Debug)]
@@ -17,24 +21,70 @@ pub enum SecretError {
     InvalidValue(String),
     #[error("Service name validation failed: {0}")]
     InvalidService(String),
+    #[error("Secret backend error: {0}")]
+    Backend(String),
 }
 
 const MAX_KEY_LENGTH: usize = 256;
 const MAX_VALUE_LENGTH: usize = 16384;
 const MAX_SERVICE_LENGTH: usize = 128;
 
+/// Suffix of the entry holding a secret's version metadata
+const META_SUFFIX: &str = ".meta";
+/// Suffix of the entry holding the value replaced by the last rotation
+const PREVIOUS_SUFFIX: &str = ".previous";
+
+/// Version metadata tracked alongside each rotated secret
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SecretVersion {
+    pub version: u32,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Notification published whenever a secret changes through rotation
+///
+/// Carries no secret material; subscribers read the new value from the
+/// manager themselves.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SecretRotation {
+    pub service: String,
+    pub key: String,
+    pub version: u32,
+}
+
 /// Manages secure storage and retrieval of sensitive information
 pub struct SecretManager {
     service_name: String,
+    backend: Arc<dyn SecretBackend>,
+    rotations: broadcast::Sender<SecretRotation>,
 }
 
 impl SecretManager {
-    /// Creates a new SecretManager instance with validation
+    /// Creates a new SecretManager instance with validation, backed by the OS keyring
     pub fn new(service_name: impl Into<String>) -> Result<Self, SecretError> {
+        Self::with_backend(service_name, Arc::new(KeyringBackend))
+    }
+
+    /// Creates a SecretManager that stores secrets in the given backend
+    pub fn with_backend(
+        service_name: impl Into<String>,
+        backend: Arc<dyn SecretBackend>,
+    ) -> Result<Self, SecretError> {
         let service_name = service_name.into();
         Self::validate_service_name(&service_name)?;
-        
-        Ok(Self { service_name })
+        let (rotations, _) = broadcast::channel(32);
+
+        Ok(Self { service_name, backend, rotations })
+    }
+
+    /// Name of the backend secrets are stored in
+    pub fn backend_name(&self) -> &'static str {
+        self.backend.name()
+    }
+
+    /// Subscribe to rotation events so dependents can reload credentials
+    pub fn subscribe(&self) -> broadcast::Receiver<SecretRotation> {
+        self.rotations.subscribe()
     }
 
     /// Validates a service name
@@ -107,12 +157,8 @@ impl SecretManager {
             return Err(SecretError::InvalidKey("Empty key".to_string()));
         }
 
-        let entry = Entry::new(&self.service_name, key)?;
-        
         // Securely store the secret
-        entry.set_password(value)?;
-        
-        Ok(())
+        self.backend.set(&self.service_name, key, value)
     }
 
     /// Retrieves a secret from the system keyring
@@ -128,9 +174,7 @@ impl SecretManager {
             return Err(SecretError::InvalidKey("Empty key".to_string()));
         }
 
-        let entry = Entry::new(&self.service_name, key)?;
-        let value = entry.get_password()
-            .map_err(|e| SecretError::RetrievalError(Box::new(e)))?;
+        let value = self.backend.get(&self.service_name, key)?;
 
         // Validate retrieved value
         Self::validate_value(&value)?;
@@ -151,12 +195,13 @@ impl SecretManager {
             return Err(SecretError::InvalidKey("Empty key".to_string()));
         }
 
-        let entry = Entry::new(&self.service_name, key)?;
-        match entry.delete_password() {
-            Ok(_) => Ok(()),
-            Err(keyring::Error::NoEntry) => Err(SecretError::NotFound(key.to_string())),
-            Err(e) => Err(SecretError::StoreError(e)),
+        self.backend.delete(&self.service_name, key)?;
+
+        // Version bookkeeping may not exist for secrets that were never rotated
+        for suffix in [META_SUFFIX, PREVIOUS_SUFFIX] {
+            let _ = self.backend.delete(&self.service_name, &format!("{key}{suffix}"));
         }
+        Ok(())
     }
 
     /// Rotates a secret by generating a new value and storing it
@@ -165,10 +210,79 @@ impl SecretManager {
 
         // Generate new secure random value
         let new_value = generate_secure_secret();
-        self.store_secret(key, &new_value)?;
+        self.rotate_secret_to(key, &new_value)?;
         
         Ok(new_value)
     }
+
+    /// Rotates a secret to a caller-supplied value, e.g. a freshly issued API key
+    ///
+    /// The replaced value stays readable through [`Self::get_previous_secret`]
+    /// until the next rotation, and subscribers are notified of the new version.
+    pub fn rotate_secret_to(&self, key: &str, new_value: &str) -> Result<SecretVersion, SecretError> {
+        Self::validate_key(key)?;
+        Self::validate_key(&format!("{key}{PREVIOUS_SUFFIX}"))?;
+        Self::validate_value(new_value)?;
+
+        match self.backend.get(&self.service_name, key) {
+            Ok(previous) => self.backend.set(&self.service_name, &format!("{key}{PREVIOUS_SUFFIX}"), &previous)?,
+            Err(SecretError::NotFound(_)) => {}
+            Err(e) => return Err(e),
+        }
+
+        let version = SecretVersion {
+            version: self.secret_version(key)?.map_or(1, |v| v.version + 1),
+            updated_at: Utc::now(),
+        };
+        self.backend.set(&self.service_name, key, new_value)?;
+        self.write_version(key, &version)?;
+
+        tracing::info!(
+            service = %self.service_name,
+            key,
+            version = version.version,
+            backend = self.backend.name(),
+            "Secret rotated"
+        );
+        // No receivers is fine; nobody may have subscribed yet
+        let _ = self.rotations.send(SecretRotation {
+            service: self.service_name.clone(),
+            key: key.to_string(),
+            version: version.version,
+        });
+
+        Ok(version)
+    }
+
+    /// Current version of a secret, or `None` if it has never been rotated
+    pub fn secret_version(&self, key: &str) -> Result<Option<SecretVersion>, SecretError> {
+        Self::validate_key(key)?;
+        match self.backend.get(&self.service_name, &format!("{key}{META_SUFFIX}")) {
+            Ok(raw) => serde_json::from_str(&raw)
+                .map(Some)
+                .map_err(|e| SecretError::InvalidValue(format!("Corrupt version metadata: {e}"))),
+            Err(SecretError::NotFound(_)) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Value that was replaced by the most recent rotation
+    pub fn get_previous_secret(&self, key: &str) -> Result<String, SecretError> {
+        Self::validate_key(key)?;
+        self.backend.get(&self.service_name, &format!("{key}{PREVIOUS_SUFFIX}"))
+    }
+
+    /// Restores the previous value, recording it as a new version
+    pub fn rollback_secret(&self, key: &str) -> Result<SecretVersion, SecretError> {
+        let previous = self.get_previous_secret(key)?;
+        self.rotate_secret_to(key, &previous)
+    }
+
+    fn write_version(&self, key: &str, version: &SecretVersion) -> Result<(), SecretError> {
+        let raw = serde_json::to_string(version)
+            .map_err(|e| SecretError::InvalidValue(e.to_string()))?;
+        self.backend.set(&self.service_name, &format!("{key}{META_SUFFIX}"), &raw)
+    }
 }
 
 /// Generates a cryptographically secure random secret
@@ -192,6 +306,48 @@ fn generate_secure_secret() -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::secret_backends::EncryptedFileBackend;
+
+    fn file_manager(dir: &tempfile::TempDir) -> SecretManager {
+        let backend = EncryptedFileBackend::new(dir.path().join("secrets.json"), "test passphrase").unwrap();
+        SecretManager::with_backend("jamey_test", Arc::new(backend)).unwrap()
+    }
+
+    #[test]
+    fn test_rotation_versioning() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let manager = file_manager(&dir);
+
+        manager.store_secret("openrouter_api_key", "sk-old").unwrap();
+        assert_eq!(manager.secret_version("openrouter_api_key").unwrap(), None);
+
+        let v1 = manager.rotate_secret_to("openrouter_api_key", "sk-new").unwrap();
+        assert_eq!(v1.version, 1);
+        assert_eq!(manager.get_secret("openrouter_api_key").unwrap(), "sk-new");
+        assert_eq!(manager.get_previous_secret("openrouter_api_key").unwrap(), "sk-old");
+
+        let v2 = manager.rollback_secret("openrouter_api_key").unwrap();
+        assert_eq!(v2.version, 2);
+        assert_eq!(manager.get_secret("openrouter_api_key").unwrap(), "sk-old");
+
+        manager.delete_secret("openrouter_api_key").unwrap();
+        assert_eq!(manager.secret_version("openrouter_api_key").unwrap(), None);
+        assert!(manager.get_previous_secret("openrouter_api_key").is_err());
+    }
+
+    #[test]
+    fn test_rotation_notifies_subscribers() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let manager = file_manager(&dir);
+        let mut rx = manager.subscribe();
+
+        manager.rotate_secret("github_token").unwrap();
+
+        let event = rx.try_recv().unwrap();
+        assert_eq!(event.key, "github_token");
+        assert_eq!(event.service, "jamey_test");
+        assert_eq!(event.version, 1);
+    }
 
     #[test]
     fn test_secret_lifecycle() {
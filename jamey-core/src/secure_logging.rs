@@ -3,8 +3,10 @@
 //! This module provides a logging framework that automatically redacts sensitive information
 //! from log messages, including API keys, passwords, tokens, email addresses, and other PII.
 
+use crate::redaction::Redactor;
 use regex::Regex;
-use std::sync::OnceLock;
+use std::borrow::Cow;
+use std::sync::{Arc, OnceLock};
 use tracing::field::{Field, Visit};
 use tracing::{Level, Subscriber};
 use tracing_subscriber::layer::{Context, Layer};
@@ -113,6 +115,170 @@ fn is_sensitive_field(field_name: &str) -> bool {
     })
 }
 
+/// `key=value`, `key: value` and JSON `"key":value` pairs in formatted log
+/// output, allowing for ANSI colour codes around the separator
+static FIELD_PATTERN: OnceLock<Regex> = OnceLock::new();
+
+fn field_pattern() -> &'static Regex {
+    FIELD_PATTERN.get_or_init(|| {
+        Regex::new(
+            r#"(?P<pre>^|[^A-Za-z0-9_."]|\x1b\[[0-9;]*m)(?P<key>"?[A-Za-z_][A-Za-z0-9_.]*"?)(?P<sep>(?:\x1b\[[0-9;]*m)*\s*[=:]\s*(?:\x1b\[[0-9;]*m)*)(?P<value>"(?:[^"\\]|\\.)*"|[^\s,}\x1b]+)"#,
+        )
+        .expect("Log field regex pattern is invalid")
+    })
+}
+
+/// Redacts one formatted log line: values of sensitive fields are replaced
+/// whole, then [`redact_sensitive_data`] runs over what is left. Numbers are
+/// kept, so counts such as `prompt_tokens=120` survive.
+pub fn redact_log_line(line: &str) -> String {
+    let fields = field_pattern().replace_all(line, |caps: &regex::Captures| {
+        let key = &caps["key"];
+        let value = &caps["value"];
+        if !is_sensitive_field(key.trim_matches('"')) || value.parse::<f64>().is_ok() {
+            return caps[0].to_string();
+        }
+        // Quoted keys mean JSON, which needs the value to stay a string
+        let redacted = if key.starts_with('"') || value.starts_with('"') {
+            "\"***REDACTED***\""
+        } else {
+            "***REDACTED***"
+        };
+        format!("{}{}{}{}", &caps["pre"], key, &caps["sep"], redacted)
+    });
+    redact_sensitive_data(&fields)
+}
+
+/// What a masked secret reads as in debug output and redacted config
+pub const REDACTED: &str = "***REDACTED***";
+
+/// `Debug` stand-in for a secret field: shows whether it is set, never its
+/// value. Config types use it in their hand-written `Debug` impls.
+pub struct Masked(bool);
+
+impl Masked {
+    pub fn of(secret: &str) -> Self {
+        Self(!secret.is_empty())
+    }
+
+    pub fn opt(secret: Option<&str>) -> Self {
+        Self(secret.is_some_and(|s| !s.is_empty()))
+    }
+}
+
+impl std::fmt::Debug for Masked {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(if self.0 { REDACTED } else { "None" })
+    }
+}
+
+static URL_CREDENTIALS: OnceLock<Regex> = OnceLock::new();
+
+/// `url` with the password of any `scheme://user:password@` part masked,
+/// for connection strings such as `redis_url`
+pub fn mask_url_credentials(url: &str) -> Cow<'_, str> {
+    URL_CREDENTIALS
+        .get_or_init(|| {
+            Regex::new(r"(?i)\b([a-z][a-z0-9+.-]*://[^:/@\s]*:)[^@/\s]+@").expect("URL credentials regex pattern is invalid")
+        })
+        .replace_all(url, format!("${{1}}{}@", REDACTED))
+}
+
+/// Mask secrets in serialized config: string values of sensitive fields
+/// anywhere in `value`, and URL passwords everywhere else. Numbers and
+/// booleans are kept, as in [`redact_log_line`], so `api_key_required` and
+/// token budgets still show.
+pub fn redact_json_secrets(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                if is_sensitive_field(key) {
+                    mask_strings(value);
+                } else {
+                    redact_json_secrets(value);
+                }
+            }
+        }
+        serde_json::Value::Array(items) => items.iter_mut().for_each(redact_json_secrets),
+        serde_json::Value::String(text) => {
+            if let Cow::Owned(masked) = mask_url_credentials(text) {
+                *text = masked;
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Mask the strings under a sensitive field; objects beneath it (the
+/// entries of `oauth_clients`) are checked key by key instead
+fn mask_strings(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::String(text) if !text.is_empty() => *text = REDACTED.to_string(),
+        serde_json::Value::Array(items) => items.iter_mut().for_each(mask_strings),
+        serde_json::Value::Object(_) => redact_json_secrets(value),
+        _ => {}
+    }
+}
+
+/// A [`MakeWriter`](tracing_subscriber::fmt::MakeWriter) that redacts what a
+/// formatting layer writes before passing it on
+///
+/// The fmt layers write each event with a single call, so every write is
+/// redacted as a complete line.
+#[derive(Debug, Clone)]
+pub struct RedactingMakeWriter<M> {
+    inner: M,
+    redactor: Option<Arc<Redactor>>,
+}
+
+impl<M> RedactingMakeWriter<M> {
+    pub fn new(inner: M) -> Self {
+        Self { inner, redactor: None }
+    }
+
+    /// Also run `redactor`, so deployment-specific patterns reach the logs
+    pub fn with_redactor(mut self, redactor: Arc<Redactor>) -> Self {
+        self.redactor = Some(redactor);
+        self
+    }
+}
+
+impl<'a, M> tracing_subscriber::fmt::MakeWriter<'a> for RedactingMakeWriter<M>
+where
+    M: tracing_subscriber::fmt::MakeWriter<'a>,
+{
+    type Writer = RedactingWriter<M::Writer>;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        RedactingWriter { inner: self.inner.make_writer(), redactor: self.redactor.clone() }
+    }
+
+    fn make_writer_for(&'a self, meta: &tracing::Metadata<'_>) -> Self::Writer {
+        RedactingWriter { inner: self.inner.make_writer_for(meta), redactor: self.redactor.clone() }
+    }
+}
+
+pub struct RedactingWriter<W> {
+    inner: W,
+    redactor: Option<Arc<Redactor>>,
+}
+
+impl<W: std::io::Write> std::io::Write for RedactingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let text = String::from_utf8_lossy(buf);
+        let mut line = redact_log_line(&text);
+        if let Some(redactor) = &self.redactor {
+            redactor.redact_string(&mut line);
+        }
+        self.inner.write_all(line.as_bytes())?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
 /// A visitor that redacts sensitive field values
 struct RedactingVisitor {
     redacted_fields: Vec<(String, String)>,
@@ -342,6 +508,45 @@ mod tests {
         assert!(!is_sensitive_field("email_verified"));
     }
 
+    #[test]
+    fn test_redact_log_line() {
+        let text = "INFO jamey: Connected password=hunter22 user=alice prompt_tokens=120";
+        assert_eq!(
+            redact_log_line(text),
+            "INFO jamey: Connected password=***REDACTED*** user=alice prompt_tokens=120"
+        );
+
+        let json = r#"{"level":"INFO","client_secret":"abc\"def","attempt":2,"message":"ok"}"#;
+        let redacted = redact_log_line(json);
+        assert_eq!(redacted, r#"{"level":"INFO","client_secret":"***REDACTED***","attempt":2,"message":"ok"}"#);
+        assert!(serde_json::from_str::<serde_json::Value>(&redacted).is_ok());
+
+        // Coloured output from the pretty formatter
+        let ansi = "\x1b[3mapi_key\x1b[0m\x1b[2m=\x1b[0msk-live-1";
+        assert!(!redact_log_line(ansi).contains("sk-live-1"));
+    }
+
+    #[test]
+    fn test_redact_json_secrets() {
+        let mut config = serde_json::json!({
+            "llm": { "openrouter_api_key": "sk-or-v1-abc", "context_budget_tokens": 24000 },
+            "security": { "api_key_required": true, "api_key": null },
+            "cache": { "redis_url": "redis://:hunter22@cache.internal:6379" },
+            "tools": { "oauth_clients": [{ "client_id": "app", "client_secret": "shh" }] },
+        });
+        redact_json_secrets(&mut config);
+        assert_eq!(config["llm"]["openrouter_api_key"], REDACTED);
+        assert_eq!(config["llm"]["context_budget_tokens"], 24000);
+        assert_eq!(config["security"]["api_key_required"], true);
+        assert!(config["security"]["api_key"].is_null());
+        assert_eq!(config["cache"]["redis_url"], "redis://:***REDACTED***@cache.internal:6379");
+        assert_eq!(config["tools"]["oauth_clients"][0]["client_id"], "app");
+        assert_eq!(config["tools"]["oauth_clients"][0]["client_secret"], REDACTED);
+
+        assert_eq!(format!("{:?}", Masked::opt(Some("shh"))), REDACTED);
+        assert_eq!(format!("{:?}", Masked::of("")), "None");
+    }
+
     #[test]
     fn test_non_sensitive_data() {
         let input = "Processing request for user_id: 12345";
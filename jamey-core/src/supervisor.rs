@@ -0,0 +1,356 @@
+//! Supervised background tasks
+//!
+//! Long-running loops such as session cleanup, the scheduler, listeners and
+//! MQTT event loops are spawned through a [`Supervisor`] instead of a bare
+//! `tokio::spawn`, so a panic doesn't quietly take one down. A loop that
+//! panics is logged, counted and started again after a backoff that doubles
+//! from `initial_backoff_ms` up to `max_backoff_secs`. After `max_restarts`
+//! panics in a row it is left down and reported as failed; one that stays
+//! up for `healthy_after_secs` starts its count over. A loop that returns
+//! is done and isn't restarted.
+//!
+//! One-off work, such as a scheduled job, goes through
+//! [`Supervisor::spawn_once`]: its panic is recorded the same way, but it
+//! isn't retried.
+//!
+//! ```toml
+//! [supervisor]
+//! max_restarts = 5
+//! initial_backoff_ms = 500
+//! max_backoff_secs = 60
+//! healthy_after_secs = 300
+//! ```
+
+use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::any::Any;
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+use tokio::task::{AbortHandle, JoinHandle};
+
+/// Panics in supervised tasks, labelled by `task`: the part of the task's
+/// name before any `:`
+pub const TASK_PANICS: &str = "jamey_task_panics_total";
+pub const TASK_RESTARTS: &str = "jamey_task_restarts_total";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct SupervisorConfig {
+    /// Panics in a row after which a task is left down
+    pub max_restarts: u32,
+    /// Wait before the first restart; doubles with each panic in a row
+    pub initial_backoff_ms: u64,
+    pub max_backoff_secs: u64,
+    /// Uptime after which a task's earlier panics no longer count
+    pub healthy_after_secs: u64,
+}
+
+impl Default for SupervisorConfig {
+    fn default() -> Self {
+        Self {
+            max_restarts: 5,
+            initial_backoff_ms: 500,
+            max_backoff_secs: 60,
+            healthy_after_secs: 300,
+        }
+    }
+}
+
+impl SupervisorConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.initial_backoff_ms == 0 {
+            return Err("supervisor.initial_backoff_ms must be above 0".to_string());
+        }
+        if self.max_backoff_secs.saturating_mul(1000) < self.initial_backoff_ms {
+            return Err("supervisor.max_backoff_secs must be at least supervisor.initial_backoff_ms".to_string());
+        }
+        Ok(())
+    }
+
+    /// Wait before restarting after the `attempt`th panic in a row
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let doublings = attempt.saturating_sub(1).min(32);
+        let ms = self.initial_backoff_ms.saturating_mul(1u64 << doublings);
+        Duration::from_millis(ms).min(Duration::from_secs(self.max_backoff_secs))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskState {
+    Running,
+    /// Panicked and waiting out its backoff
+    Restarting,
+    /// Returned, or stopped with the runtime
+    Finished,
+    /// Panicked too often in a row, or a one-off task that panicked
+    Failed,
+}
+
+/// A supervised task's health
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SupervisedTask {
+    pub name: String,
+    pub state: TaskState,
+    pub panics: u64,
+    pub restarts: u64,
+    pub last_panic: Option<String>,
+    pub last_panic_at: Option<DateTime<Utc>>,
+}
+
+/// Called with a task's name and panic message whenever one panics
+pub type PanicHook = Arc<dyn Fn(&str, &str) + Send + Sync>;
+
+/// Spawns and watches background tasks; clones share them
+#[derive(Clone)]
+pub struct Supervisor {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    config: SupervisorConfig,
+    tasks: Mutex<BTreeMap<String, SupervisedTask>>,
+    hook: Mutex<Option<PanicHook>>,
+    stopped: watch::Sender<bool>,
+}
+
+impl std::fmt::Debug for Supervisor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Supervisor")
+            .field("config", &self.inner.config)
+            .field("tasks", &self.inner.tasks.lock().unwrap().len())
+            .finish()
+    }
+}
+
+impl Default for Supervisor {
+    fn default() -> Self {
+        Self::new(SupervisorConfig::default())
+    }
+}
+
+/// Aborts the task it holds when dropped, so aborting a supervised task
+/// reaches the attempt currently running
+struct AbortOnDrop(AbortHandle);
+
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+impl Supervisor {
+    pub fn new(config: SupervisorConfig) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                config,
+                tasks: Mutex::new(BTreeMap::new()),
+                hook: Mutex::new(None),
+                stopped: watch::channel(false).0,
+            }),
+        }
+    }
+
+    /// Call `hook` on every panic, after it has been recorded
+    pub fn with_panic_hook(self, hook: PanicHook) -> Self {
+        *self.inner.hook.lock().unwrap() = Some(hook);
+        self
+    }
+
+    /// Run the future `make` returns, and a fresh one after each panic,
+    /// until one returns. Aborting the handle stops it for good.
+    pub fn spawn<F, Fut>(&self, name: impl Into<String>, mut make: F) -> JoinHandle<()>
+    where
+        F: FnMut() -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let name = name.into();
+        let this = self.clone();
+        this.set_state(&name, TaskState::Running);
+        tokio::spawn(async move {
+            let config = &this.inner.config;
+            let mut stopped = this.inner.stopped.subscribe();
+            let mut in_a_row = 0u32;
+            loop {
+                let started = Instant::now();
+                let attempt = tokio::spawn(make());
+                let _abort = AbortOnDrop(attempt.abort_handle());
+                let message = match attempt.await {
+                    Ok(()) => break,
+                    Err(e) if e.is_cancelled() => break,
+                    Err(e) => panic_message(e.into_panic()),
+                };
+                if started.elapsed() >= Duration::from_secs(config.healthy_after_secs) {
+                    in_a_row = 0;
+                }
+                in_a_row += 1;
+                if in_a_row > config.max_restarts {
+                    this.record_panic(&name, &message, TaskState::Failed);
+                    tracing::error!("Task {} panicked {} times in a row; leaving it down", name, in_a_row);
+                    return;
+                }
+                this.record_panic(&name, &message, TaskState::Restarting);
+                let backoff = config.backoff(in_a_row);
+                tracing::warn!("Restarting task {} in {:?}", name, backoff);
+                tokio::select! {
+                    _ = tokio::time::sleep(backoff) => {}
+                    _ = stopped.wait_for(|stopped| *stopped) => break,
+                }
+                if *stopped.borrow() {
+                    break;
+                }
+                metrics::increment_counter!(TASK_RESTARTS, "task" => task_label(&name));
+                this.update(&name, |task| {
+                    task.state = TaskState::Running;
+                    task.restarts += 1;
+                });
+            }
+            this.set_state(&name, TaskState::Finished);
+        })
+    }
+
+    /// Run `work` once, recording a panic instead of losing it
+    pub fn spawn_once<Fut>(&self, name: impl Into<String>, work: Fut) -> JoinHandle<()>
+    where
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let name = name.into();
+        let this = self.clone();
+        tokio::spawn(async move {
+            let attempt = tokio::spawn(work);
+            let _abort = AbortOnDrop(attempt.abort_handle());
+            if let Err(e) = attempt.await {
+                if e.is_panic() {
+                    this.record_panic(&name, &panic_message(e.into_panic()), TaskState::Failed);
+                }
+            }
+        })
+    }
+
+    /// Stop restarting tasks; those waiting out a backoff finish
+    pub fn stop(&self) {
+        self.inner.stopped.send_replace(true);
+    }
+
+    /// Every task spawned with [`spawn`](Self::spawn), and one-off tasks
+    /// that panicked, by name
+    pub fn tasks(&self) -> Vec<SupervisedTask> {
+        self.inner.tasks.lock().unwrap().values().cloned().collect()
+    }
+
+    fn set_state(&self, name: &str, state: TaskState) {
+        self.update(name, |task| task.state = state);
+    }
+
+    fn update(&self, name: &str, change: impl FnOnce(&mut SupervisedTask)) {
+        let mut tasks = self.inner.tasks.lock().unwrap();
+        let task = tasks.entry(name.to_string()).or_insert_with(|| SupervisedTask {
+            name: name.to_string(),
+            state: TaskState::Running,
+            panics: 0,
+            restarts: 0,
+            last_panic: None,
+            last_panic_at: None,
+        });
+        change(task);
+    }
+
+    fn record_panic(&self, name: &str, message: &str, state: TaskState) {
+        tracing::error!("Task {} panicked: {}", name, message);
+        metrics::increment_counter!(TASK_PANICS, "task" => task_label(name));
+        self.update(name, |task| {
+            task.state = state;
+            task.panics += 1;
+            task.last_panic = Some(message.to_string());
+            task.last_panic_at = Some(Utc::now());
+        });
+        let hook = self.inner.hook.lock().unwrap().clone();
+        if let Some(hook) = hook {
+            hook(name, message);
+        }
+    }
+}
+
+/// `mqtt:kitchen` is counted as `mqtt`
+fn task_label(name: &str) -> String {
+    name.split(':').next().unwrap_or(name).to_string()
+}
+
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    match payload.downcast::<String>() {
+        Ok(message) => *message,
+        Err(payload) => match payload.downcast::<&'static str>() {
+            Ok(message) => message.to_string(),
+            Err(_) => "non-string panic payload".to_string(),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    fn quick(max_restarts: u32) -> SupervisorConfig {
+        SupervisorConfig { max_restarts, initial_backoff_ms: 1, max_backoff_secs: 1, healthy_after_secs: 300 }
+    }
+
+    #[tokio::test]
+    async fn test_restarts_after_panic_until_success() {
+        let supervisor = Supervisor::new(quick(5));
+        let runs = Arc::new(AtomicU32::new(0));
+        let counter = Arc::clone(&runs);
+        supervisor
+            .spawn("flaky", move || {
+                let counter = Arc::clone(&counter);
+                async move {
+                    if counter.fetch_add(1, Ordering::SeqCst) < 2 {
+                        panic!("boom");
+                    }
+                }
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(runs.load(Ordering::SeqCst), 3);
+        let task = &supervisor.tasks()[0];
+        assert_eq!(task.state, TaskState::Finished);
+        assert_eq!((task.panics, task.restarts), (2, 2));
+        assert_eq!(task.last_panic.as_deref(), Some("boom"));
+    }
+
+    #[tokio::test]
+    async fn test_gives_up_after_max_restarts() {
+        let panics = Arc::new(Mutex::new(Vec::new()));
+        let seen = Arc::clone(&panics);
+        let supervisor = Supervisor::new(quick(2))
+            .with_panic_hook(Arc::new(move |name, message| seen.lock().unwrap().push(format!("{}: {}", name, message))));
+        supervisor.spawn("mqtt:kitchen", || async { panic!("broker gone") }).await.unwrap();
+
+        let task = &supervisor.tasks()[0];
+        assert_eq!(task.state, TaskState::Failed);
+        assert_eq!((task.panics, task.restarts), (3, 2));
+        assert_eq!(panics.lock().unwrap().len(), 3);
+        assert_eq!(panics.lock().unwrap()[0], "mqtt:kitchen: broker gone");
+
+        supervisor.spawn_once("job:nightly", async { panic!("{}", String::from("bad input")) }).await.unwrap();
+        let job = supervisor.tasks().into_iter().find(|task| task.name == "job:nightly").unwrap();
+        assert_eq!(job.state, TaskState::Failed);
+        assert_eq!(job.last_panic.as_deref(), Some("bad input"));
+    }
+
+    #[test]
+    fn test_backoff_doubles_up_to_cap() {
+        let config = SupervisorConfig { initial_backoff_ms: 500, max_backoff_secs: 3, ..Default::default() };
+        assert_eq!(config.backoff(1), Duration::from_millis(500));
+        assert_eq!(config.backoff(3), Duration::from_secs(2));
+        assert_eq!(config.backoff(10), Duration::from_secs(3));
+        assert!(config.validate().is_ok());
+        assert!(SupervisorConfig { initial_backoff_ms: 0, ..config.clone() }.validate().is_err());
+        assert!(SupervisorConfig { max_backoff_secs: 0, ..config }.validate().is_err());
+    }
+}
@@ -0,0 +1,417 @@
+//! Replicating a namespace's memories between instances
+//!
+//! Every memory carries `updated_at`, moved forward whenever its content or
+//! metadata changes, and every deletion leaves a row in `memory_tombstones`
+//! saying when the memory went. [`changes_since`] lists a namespace's
+//! writes and deletions in that order, and [`apply_changes`] takes another
+//! instance's: the newer of the two versions wins, and at the same instant a
+//! deletion beats a write, so both sides settle on the same memories
+//! whichever order they hear about them in.
+//!
+//! How the changes travel is up to the caller; the runtime exchanges them
+//! with one peer over a signed HTTP channel and keeps its place in each
+//! direction with [`sync_cursors`].
+//!
+//! [`changes_since`]: PostgresMemoryStore::changes_since
+//! [`apply_changes`]: PostgresMemoryStore::apply_changes
+//! [`sync_cursors`]: PostgresMemoryStore::sync_cursors
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio_postgres::types::ToSql;
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::memory::{embedding_from_text, MemoryError, MemoryType, PostgresMemoryStore};
+use crate::profiling::TimingGuard;
+
+/// A memory as it is sent to another instance
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SyncedMemory {
+    pub id: Uuid,
+    pub memory_type: MemoryType,
+    pub content: String,
+    pub embedding: Vec<f32>,
+    pub metadata: serde_json::Value,
+    pub namespace: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// A deleted memory
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Tombstone {
+    pub id: Uuid,
+    pub namespace: String,
+    pub deleted_at: DateTime<Utc>,
+}
+
+/// One write or deletion
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum SyncChange {
+    Upsert(SyncedMemory),
+    Delete(Tombstone),
+}
+
+impl SyncChange {
+    pub fn id(&self) -> Uuid {
+        match self {
+            SyncChange::Upsert(memory) => memory.id,
+            SyncChange::Delete(tombstone) => tombstone.id,
+        }
+    }
+
+    pub fn namespace(&self) -> &str {
+        match self {
+            SyncChange::Upsert(memory) => &memory.namespace,
+            SyncChange::Delete(tombstone) => &tombstone.namespace,
+        }
+    }
+
+    fn version(&self) -> Version {
+        match self {
+            SyncChange::Upsert(memory) => Version { at: memory.updated_at, deleted: false },
+            SyncChange::Delete(tombstone) => Version { at: tombstone.deleted_at, deleted: true },
+        }
+    }
+
+    /// Where a listing resumes after this change
+    pub fn cursor(&self) -> SyncCursor {
+        SyncCursor { at: self.version().at, id: self.id() }
+    }
+}
+
+/// A position in a namespace's changes: after the change to `id` at `at`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct SyncCursor {
+    pub at: DateTime<Utc>,
+    pub id: Uuid,
+}
+
+/// When a memory last changed and whether that change deleted it. Later
+/// versions win; at the same instant a deletion does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct Version {
+    at: DateTime<Utc>,
+    deleted: bool,
+}
+
+/// Whether `incoming` replaces what is held locally
+fn supersedes(incoming: Version, local: Option<Version>) -> bool {
+    local.is_none_or(|local| incoming > local)
+}
+
+/// What applying a batch of changes did
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ApplyReport {
+    pub written: u64,
+    pub deleted: u64,
+    /// Changes older than what was already held
+    pub skipped: u64,
+}
+
+/// `DELETE FROM {table} WHERE {condition}` leaving a tombstone for each row
+/// it removes; the statement's row count is the number removed
+pub(crate) fn delete_with_tombstones(table: &str, condition: &str) -> String {
+    format!(
+        "WITH gone AS (DELETE FROM {} WHERE {} RETURNING id, namespace)
+         INSERT INTO memory_tombstones (id, namespace)
+         SELECT id, namespace FROM gone
+         ON CONFLICT (id) DO UPDATE SET namespace = EXCLUDED.namespace, deleted_at = NOW()",
+        table, condition
+    )
+}
+
+/// Create the tombstone and cursor tables. Part of
+/// [`PostgresMemoryStore::new`].
+pub(crate) async fn create_tables(client: &deadpool_postgres::Object) -> Result<()> {
+    client
+        .batch_execute(
+            "CREATE TABLE IF NOT EXISTS memory_tombstones (
+                id UUID PRIMARY KEY,
+                namespace TEXT NOT NULL DEFAULT '',
+                deleted_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+             );
+             CREATE INDEX IF NOT EXISTS memory_tombstones_sync_idx
+                 ON memory_tombstones (namespace, deleted_at, id);
+             CREATE TABLE IF NOT EXISTS memory_sync_cursors (
+                peer TEXT NOT NULL,
+                namespace TEXT NOT NULL,
+                pushed JSONB,
+                pulled JSONB,
+                PRIMARY KEY (peer, namespace)
+             );",
+        )
+        .await?;
+    Ok(())
+}
+
+impl PostgresMemoryStore {
+    /// Up to `limit` writes and deletions in `namespace` after `since`,
+    /// oldest first
+    #[instrument(skip(self))]
+    pub async fn changes_since(
+        &self,
+        namespace: &str,
+        since: Option<SyncCursor>,
+        limit: usize,
+    ) -> Result<Vec<SyncChange>> {
+        let _timer = TimingGuard::new("memory_changes_since");
+        let client = self.pool.get().await?;
+        let (at, id) = match since {
+            Some(cursor) => (cursor.at, cursor.id),
+            // Nothing is older than the epoch
+            None => (DateTime::<Utc>::UNIX_EPOCH, Uuid::nil()),
+        };
+        let limit = limit as i64;
+
+        let rows = client
+            .query(
+                &format!(
+                    "SELECT id, memory_type, content, {} AS embedding, metadata, namespace, created_at, updated_at
+                     FROM memories
+                     WHERE namespace = $1 AND (updated_at, id) > ($2, $3)
+                     ORDER BY updated_at, id
+                     LIMIT $4",
+                    self.embedding_sql()
+                ),
+                &[&namespace, &at, &id, &limit],
+            )
+            .await?;
+        let mut changes = Vec::with_capacity(rows.len());
+        for row in &rows {
+            let embedding: String = row.get("embedding");
+            let memory_type: String = row.get("memory_type");
+            changes.push(SyncChange::Upsert(SyncedMemory {
+                id: row.get("id"),
+                memory_type: MemoryType::try_from(memory_type.as_str())
+                    .map_err(|e| MemoryError::InvalidRequest(format!("Invalid memory type: {}", e)))?,
+                content: row.get("content"),
+                embedding: embedding_from_text(&embedding)?,
+                metadata: row.get("metadata"),
+                namespace: row.get("namespace"),
+                created_at: row.get("created_at"),
+                updated_at: row.get("updated_at"),
+            }));
+        }
+
+        let rows = client
+            .query(
+                "SELECT id, namespace, deleted_at FROM memory_tombstones
+                 WHERE namespace = $1 AND (deleted_at, id) > ($2, $3)
+                 ORDER BY deleted_at, id
+                 LIMIT $4",
+                &[&namespace, &at, &id, &limit],
+            )
+            .await?;
+        changes.extend(rows.iter().map(|row| {
+            SyncChange::Delete(Tombstone {
+                id: row.get("id"),
+                namespace: row.get("namespace"),
+                deleted_at: row.get("deleted_at"),
+            })
+        }));
+
+        changes.sort_by_key(SyncChange::cursor);
+        changes.truncate(limit as usize);
+        Ok(changes)
+    }
+
+    /// Apply changes from another instance, keeping whichever version of
+    /// each memory is newer
+    #[instrument(skip(self, changes), fields(count = changes.len()))]
+    pub async fn apply_changes(&self, changes: &[SyncChange]) -> Result<ApplyReport> {
+        let _timer = TimingGuard::new("memory_apply_changes");
+        let mut report = ApplyReport::default();
+        // A partitioned table's key has to include the namespace
+        let conflict_target = if self.is_partitioned().await? { "id, namespace" } else { "id" };
+        let mut client = self.pool.get().await?;
+        for change in changes {
+            if let SyncChange::Upsert(memory) = change {
+                self.validate_vector_dimension(&memory.embedding)?;
+                if !memory.metadata.is_object() {
+                    return Err(MemoryError::InvalidRequest("Synced metadata must be an object".to_string()).into());
+                }
+                Self::validate_metadata(&memory.metadata)?;
+            }
+
+            let tx = client.transaction().await?;
+            let id = change.id();
+            // A row that doesn't exist yet can't be locked, and a partitioned
+            // table wouldn't stop the same id arriving in two namespaces at
+            // once, so changes to one id take turns
+            tx.execute("SELECT pg_advisory_xact_lock(hashtextextended($1::text, 0))", &[&id]).await?;
+            // Lock the memory's row, if it has one, while deciding
+            let held = tx
+                .query_opt("SELECT updated_at, namespace FROM memories WHERE id = $1 FOR UPDATE", &[&id])
+                .await?;
+            // A change is only ever to a memory in its own namespace
+            if held.as_ref().is_some_and(|row| row.get::<_, &str>("namespace") != change.namespace()) {
+                report.skipped += 1;
+                continue;
+            }
+            let written = held.map(|row| Version { at: row.get("updated_at"), deleted: false });
+            let deleted = tx
+                .query_opt("SELECT deleted_at FROM memory_tombstones WHERE id = $1", &[&id])
+                .await?
+                .map(|row| Version { at: row.get("deleted_at"), deleted: true });
+            if !supersedes(change.version(), written.max(deleted)) {
+                report.skipped += 1;
+                continue;
+            }
+
+            match change {
+                SyncChange::Upsert(memory) => {
+                    let mut content = Self::sanitize_content(&memory.content);
+                    let mut metadata = memory.metadata.clone();
+                    self.redactor.redact_string(&mut content);
+                    self.redactor.redact_json(&mut metadata);
+                    let memory_type = memory.memory_type.to_string();
+                    let encoded = self.encode_embedding(&memory.memory_type, &memory.embedding);
+                    let (embedding_columns, embedding_values) = self.embedding_insert(8);
+                    let mut params: Vec<&(dyn ToSql + Sync)> = vec![
+                        &memory.id,
+                        &memory_type,
+                        &content,
+                        &metadata,
+                        &memory.namespace,
+                        &memory.created_at,
+                        &memory.updated_at,
+                    ];
+                    params.extend(encoded.params(self.quantized_columns));
+                    // An archived copy is older than anything worth sending
+                    tx.execute("DELETE FROM memories_cold WHERE id = $1", &[&id]).await?;
+                    tx.execute(
+                        &format!(
+                            "INSERT INTO memories (id, memory_type, content, metadata, namespace,
+                                                   created_at, updated_at, {})
+                             VALUES ($1, $2, $3, $4, $5, $6, $7, {})
+                             ON CONFLICT ({}) DO UPDATE SET
+                                 memory_type = EXCLUDED.memory_type,
+                                 content = EXCLUDED.content,
+                                 metadata = EXCLUDED.metadata,
+                                 updated_at = EXCLUDED.updated_at, {}",
+                            embedding_columns,
+                            embedding_values,
+                            conflict_target,
+                            self.embedding_excluded()
+                        ),
+                        &params,
+                    )
+                    .await?;
+                    tx.execute("DELETE FROM memory_tombstones WHERE id = $1", &[&id]).await?;
+                    report.written += 1;
+                }
+                SyncChange::Delete(tombstone) => {
+                    tx.execute("DELETE FROM memories WHERE id = $1", &[&id]).await?;
+                    tx.execute("DELETE FROM memories_cold WHERE id = $1", &[&id]).await?;
+                    tx.execute(
+                        "INSERT INTO memory_tombstones (id, namespace, deleted_at) VALUES ($1, $2, $3)
+                         ON CONFLICT (id) DO UPDATE SET namespace = EXCLUDED.namespace, deleted_at = EXCLUDED.deleted_at",
+                        &[&id, &tombstone.namespace, &tombstone.deleted_at],
+                    )
+                    .await?;
+                    report.deleted += 1;
+                }
+            }
+            tx.commit().await?;
+        }
+        Ok(report)
+    }
+
+    /// How far this instance has sent `namespace` to `peer`, and how far it
+    /// has received it, in that order
+    pub async fn sync_cursors(&self, peer: &str, namespace: &str) -> Result<(Option<SyncCursor>, Option<SyncCursor>)> {
+        let client = self.pool.get().await?;
+        let row = client
+            .query_opt(
+                "SELECT pushed, pulled FROM memory_sync_cursors WHERE peer = $1 AND namespace = $2",
+                &[&peer, &namespace],
+            )
+            .await?;
+        let Some(row) = row else {
+            return Ok((None, None));
+        };
+        let cursor = |column: &str| -> Result<Option<SyncCursor>> {
+            Ok(match row.get::<_, Option<serde_json::Value>>(column) {
+                Some(value) => Some(serde_json::from_value(value)?),
+                None => None,
+            })
+        };
+        Ok((cursor("pushed")?, cursor("pulled")?))
+    }
+
+    pub async fn save_sync_cursors(
+        &self,
+        peer: &str,
+        namespace: &str,
+        pushed: Option<SyncCursor>,
+        pulled: Option<SyncCursor>,
+    ) -> Result<()> {
+        let client = self.pool.get().await?;
+        let pushed = pushed.map(serde_json::to_value).transpose()?;
+        let pulled = pulled.map(serde_json::to_value).transpose()?;
+        client
+            .execute(
+                "INSERT INTO memory_sync_cursors (peer, namespace, pushed, pulled) VALUES ($1, $2, $3, $4)
+                 ON CONFLICT (peer, namespace) DO UPDATE SET pushed = EXCLUDED.pushed, pulled = EXCLUDED.pulled",
+                &[&peer, &namespace, &pushed, &pulled],
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Forget deletions older than `days`. An instance that hasn't synced
+    /// for longer can bring the deleted memories back.
+    pub async fn prune_tombstones(&self, days: u32) -> Result<u64> {
+        let client = self.pool.get().await?;
+        let removed = client
+            .execute(
+                "DELETE FROM memory_tombstones WHERE deleted_at < NOW() - make_interval(days => $1)",
+                &[&(days as i32)],
+            )
+            .await?;
+        Ok(removed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn at(seconds: i64) -> DateTime<Utc> {
+        DateTime::<Utc>::UNIX_EPOCH + Duration::seconds(seconds)
+    }
+
+    #[test]
+    fn test_last_write_wins() {
+        let write = |s| Version { at: at(s), deleted: false };
+        let delete = |s| Version { at: at(s), deleted: true };
+
+        assert!(supersedes(write(1), None));
+        assert!(supersedes(write(2), Some(write(1))));
+        assert!(!supersedes(write(1), Some(write(2))));
+        // Replays change nothing
+        assert!(!supersedes(write(2), Some(write(2))));
+        assert!(!supersedes(delete(2), Some(delete(2))));
+        // A deletion beats a write at the same instant, either way round
+        assert!(supersedes(delete(2), Some(write(2))));
+        assert!(!supersedes(write(2), Some(delete(2))));
+        // A later write brings a deleted memory back
+        assert!(supersedes(write(3), Some(delete(2))));
+    }
+
+    #[test]
+    fn test_change_wire_format() {
+        let id = Uuid::new_v4();
+        let change = SyncChange::Delete(Tombstone { id, namespace: "home".to_string(), deleted_at: at(5) });
+        let value = serde_json::to_value(&change).unwrap();
+        assert_eq!(value["op"], "delete");
+        assert_eq!(serde_json::from_value::<SyncChange>(value).unwrap(), change);
+        assert_eq!(change.cursor(), SyncCursor { at: at(5), id });
+        assert!(change.cursor() < SyncCursor { at: at(5), id: Uuid::from_u128(u128::MAX) });
+    }
+}
@@ -0,0 +1,264 @@
+//! Cold storage tiering
+//!
+//! Memories nobody has read in months still cost their share of the table
+//! and the vector index. [`archive_cold`] moves them to `memories_cold`,
+//! gzip-compressed and without their embedding. Reading one back through
+//! [`retrieve`] re-embeds it and returns it to the main table, so an
+//! archived memory is slower to reach but otherwise unchanged. Until then it
+//! doesn't turn up in search.
+//!
+//! [`archive_cold`]: PostgresMemoryStore::archive_cold
+//! [`retrieve`]: crate::memory::MemoryStore::retrieve
+
+use anyhow::Result;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::Serialize;
+use std::io::{Read, Write};
+use tokio_postgres::types::ToSql;
+use tracing::{info, instrument};
+use uuid::Uuid;
+
+use crate::maintenance::{Embedder, JobProgress, MemorySelector, ProgressFn, SelectedMemory, UNPINNED};
+use crate::memory::{Memory, MemoryError, MemoryType, PostgresMemoryStore};
+use crate::profiling::TimingGuard;
+
+/// Rows moved per transaction while archiving
+const ARCHIVE_PAGE_SIZE: i64 = 200;
+
+/// Outcome of an archiving run
+#[derive(Debug, Clone, Serialize)]
+pub struct TieringReport {
+    pub older_than_days: u32,
+    /// Memories unread for longer than that, pinned ones aside
+    pub pending: u64,
+    pub archived: u64,
+    /// Content bytes of the pending memories before compression
+    pub content_bytes: u64,
+    /// What the archived memories' content takes compressed
+    pub compressed_bytes: u64,
+    pub dry_run: bool,
+}
+
+impl PostgresMemoryStore {
+    /// Move memories not accessed for `older_than_days` into the cold table.
+    /// Pinned memories stay where they are.
+    #[instrument(skip(self, progress))]
+    pub async fn archive_cold(&self, older_than_days: u32, dry_run: bool, progress: ProgressFn<'_>) -> Result<TieringReport> {
+        let _timer = TimingGuard::new("memory_archive_cold");
+        if older_than_days == 0 {
+            return Err(MemoryError::InvalidRequest("archive age must be at least one day".to_string()).into());
+        }
+        let days = older_than_days as i32;
+        let mut client = self.pool.get().await?;
+
+        let cold = format!(
+            "last_accessed < NOW() - make_interval(days => $1) AND {}",
+            UNPINNED
+        );
+        let row = client
+            .query_one(
+                &format!(
+                    "SELECT COUNT(*) AS pending, COALESCE(SUM(OCTET_LENGTH(content)), 0)::int8 AS bytes
+                     FROM memories WHERE {}",
+                    cold
+                ),
+                &[&days],
+            )
+            .await?;
+        let pending = row.get::<_, i64>("pending") as u64;
+        let mut report = TieringReport {
+            older_than_days,
+            pending,
+            archived: 0,
+            content_bytes: row.get::<_, i64>("bytes") as u64,
+            compressed_bytes: 0,
+            dry_run,
+        };
+        if dry_run || pending == 0 {
+            return Ok(report);
+        }
+
+        // Each page leaves the main table as it is archived, so the same
+        // query keeps returning the next one
+        loop {
+            progress(JobProgress { stage: "archiving", done: report.archived, total: pending });
+            let tx = client.transaction().await?;
+            let rows = tx
+                .query(
+                    &format!(
+                        "SELECT id, memory_type, content, metadata, namespace,
+                                created_at, last_accessed, access_count
+                         FROM memories WHERE {}
+                         ORDER BY last_accessed, id
+                         LIMIT $2
+                         FOR UPDATE SKIP LOCKED",
+                        cold
+                    ),
+                    &[&days, &ARCHIVE_PAGE_SIZE],
+                )
+                .await?;
+            if rows.is_empty() {
+                break;
+            }
+
+            let mut ids = Vec::with_capacity(rows.len());
+            for row in &rows {
+                let id: Uuid = row.get("id");
+                let content: String = row.get("content");
+                let compressed = compress(&content)?;
+                report.compressed_bytes += compressed.len() as u64;
+                tx.execute(
+                    "INSERT INTO memories_cold (id, memory_type, content, metadata, namespace,
+                                                created_at, last_accessed, access_count)
+                     VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+                     ON CONFLICT (id) DO NOTHING",
+                    &[
+                        &id,
+                        &row.get::<_, String>("memory_type"),
+                        &compressed,
+                        &row.get::<_, serde_json::Value>("metadata"),
+                        &row.get::<_, String>("namespace"),
+                        &row.get::<_, chrono::DateTime<chrono::Utc>>("created_at"),
+                        &row.get::<_, chrono::DateTime<chrono::Utc>>("last_accessed"),
+                        &row.get::<_, i64>("access_count"),
+                    ],
+                )
+                .await?;
+                ids.push(id);
+            }
+            tx.execute("DELETE FROM memories WHERE id = ANY($1)", &[&ids]).await?;
+            tx.commit().await?;
+            report.archived += ids.len() as u64;
+        }
+        progress(JobProgress { stage: "archiving", done: report.archived, total: pending });
+
+        info!("Archived {} memories unread for {} days", report.archived, older_than_days);
+        Ok(report)
+    }
+
+    /// Bring `id` back from the cold table with a fresh embedding from
+    /// `embedder`, counting it as accessed. `None` when it isn't archived.
+    #[instrument(skip(self, embedder), fields(memory_id = %id))]
+    pub async fn rehydrate(&self, id: Uuid, embedder: &dyn Embedder) -> Result<Option<Memory>> {
+        let _timer = TimingGuard::new("memory_rehydrate");
+        let mut client = self.pool.get().await?;
+        let Some(row) = client
+            .query_opt(
+                "SELECT memory_type, content, metadata, namespace, created_at, access_count
+                 FROM memories_cold WHERE id = $1",
+                &[&id],
+            )
+            .await?
+        else {
+            return Ok(None);
+        };
+
+        let content = decompress(row.get("content"))?;
+        let embedding = embedder.embed(&content).await?;
+        self.validate_vector_dimension(&embedding)?;
+        let memory_type_str: String = row.get("memory_type");
+        let memory_type = MemoryType::try_from(memory_type_str.as_str())
+            .map_err(|e| MemoryError::InvalidRequest(format!("Invalid memory type: {}", e)))?;
+        let mut metadata: serde_json::Value = row.get("metadata");
+        if let Some(obj) = metadata.as_object_mut() {
+            obj.insert("embedding_model".to_string(), embedder.model().into());
+        }
+
+        let tx = client.transaction().await?;
+        // Whoever deletes the cold row restores it; a concurrent read of
+        // the same memory finds it gone and backs off
+        if tx.execute("DELETE FROM memories_cold WHERE id = $1", &[&id]).await? == 0 {
+            return Ok(None);
+        }
+        let namespace: String = row.get("namespace");
+        let created_at: chrono::DateTime<chrono::Utc> = row.get("created_at");
+        let access_count: i64 = row.get("access_count");
+        let encoded = self.encode_embedding(&memory_type, &embedding);
+        let (embedding_columns, embedding_values) = self.embedding_insert(8);
+        let mut params: Vec<&(dyn ToSql + Sync)> =
+            vec![&id, &memory_type_str, &content, &metadata, &namespace, &created_at, &access_count];
+        params.extend(encoded.params(self.quantized_columns));
+        let restored = tx
+            .query_one(
+                &format!(
+                    "INSERT INTO memories (id, memory_type, content, metadata, namespace,
+                                           created_at, last_accessed, access_count, {})
+                     VALUES ($1, $2, $3, $4, $5, $6, NOW(), $7::int8 + 1, {})
+                     RETURNING last_accessed",
+                    embedding_columns, embedding_values
+                ),
+                &params,
+            )
+            .await?;
+        tx.commit().await?;
+
+        Ok(Some(Memory {
+            id,
+            memory_type,
+            content,
+            embedding,
+            metadata,
+            created_at,
+            last_accessed: restored.get("last_accessed"),
+        }))
+    }
+
+    /// Archived memories `selector` matches, so deletion requests reach
+    /// the cold table too
+    pub(crate) async fn select_cold(&self, selector: &MemorySelector) -> Result<Vec<SelectedMemory>> {
+        let client = self.pool.get().await?;
+        let rows = client
+            .query("SELECT id, content, metadata FROM memories_cold ORDER BY created_at, id", &[])
+            .await?;
+
+        let mut selected = Vec::new();
+        for row in &rows {
+            let metadata: serde_json::Value = row.get("metadata");
+            // Content is only unpacked when a pattern needs it
+            let content = match selector.pattern {
+                Some(_) => decompress(row.get("content"))?,
+                None => String::new(),
+            };
+            if selector.matches(&content, &metadata) {
+                selected.push(SelectedMemory {
+                    id: row.get("id"),
+                    session_id: metadata
+                        .get("session_id")
+                        .and_then(|v| v.as_str())
+                        .and_then(|s| Uuid::parse_str(s).ok()),
+                });
+            }
+        }
+        Ok(selected)
+    }
+}
+
+fn compress(text: &str) -> Result<Vec<u8>> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
+    encoder.write_all(text.as_bytes())?;
+    Ok(encoder.finish()?)
+}
+
+fn decompress(bytes: &[u8]) -> Result<String> {
+    let mut text = String::new();
+    GzDecoder::new(bytes)
+        .read_to_string(&mut text)
+        .map_err(|e| MemoryError::InvalidRequest(format!("Corrupt archived memory: {}", e)))?;
+    Ok(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compress_round_trip() {
+        let text = "The deploy runbook lives in ops/runbook.md. ".repeat(40);
+        let compressed = compress(&text).unwrap();
+        assert!(compressed.len() < text.len() / 4);
+        assert_eq!(decompress(&compressed).unwrap(), text);
+        assert!(decompress(b"not gzip").is_err());
+    }
+}
@@ -0,0 +1,305 @@
+//! Token usage ledger
+//!
+//! One row per model request in the `usage_log` table: model, session,
+//! token counts, cost and latency. Unlike the runtime's in-process counters it
+//! survives restarts, so spend can be reconciled against provider invoices and
+//! reported over any period. Old rows are removed by [`PostgresUsageStore::purge_before`]
+//! according to the configured retention.
+
+use chrono::{DateTime, Duration, Utc};
+use deadpool_postgres::Pool;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tokio_postgres::Row;
+use tracing::instrument;
+use uuid::Uuid;
+
+#[derive(Debug, Error)]
+pub enum UsageStoreError {
+    #[error("Database error: {0}")]
+    Database(#[from] tokio_postgres::Error),
+    #[error("Pool error: {0}")]
+    Pool(#[from] deadpool_postgres::PoolError),
+}
+
+/// One model request
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UsageEntry {
+    pub recorded_at: DateTime<Utc>,
+    pub session_id: Option<Uuid>,
+    pub model: String,
+    pub prompt_tokens: i64,
+    pub completion_tokens: i64,
+    pub total_tokens: i64,
+    /// Charged cost, when the provider reports one
+    pub cost_usd: Option<f64>,
+    pub latency_ms: Option<i64>,
+}
+
+impl UsageEntry {
+    fn from_row(row: &Row) -> Self {
+        Self {
+            recorded_at: row.get("recorded_at"),
+            session_id: row.get("session_id"),
+            model: row.get("model"),
+            prompt_tokens: row.get("prompt_tokens"),
+            completion_tokens: row.get("completion_tokens"),
+            total_tokens: row.get("total_tokens"),
+            cost_usd: row.get("cost_usd"),
+            latency_ms: row.get("latency_ms"),
+        }
+    }
+}
+
+/// Which rows a query covers; unset fields don't filter
+#[derive(Debug, Clone, Default)]
+pub struct UsageQuery {
+    /// Inclusive
+    pub since: Option<DateTime<Utc>>,
+    /// Exclusive
+    pub until: Option<DateTime<Utc>>,
+    pub model: Option<String>,
+    pub session_id: Option<Uuid>,
+}
+
+impl UsageQuery {
+    pub fn since(since: DateTime<Utc>) -> Self {
+        Self {
+            since: Some(since),
+            ..Self::default()
+        }
+    }
+}
+
+/// Column totals are grouped by
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UsageGrouping {
+    Model,
+    Session,
+    /// UTC calendar day
+    Day,
+}
+
+impl UsageGrouping {
+    fn key_sql(self) -> &'static str {
+        match self {
+            Self::Model => "model",
+            Self::Session => "COALESCE(session_id::text, '(none)')",
+            Self::Day => "to_char(recorded_at AT TIME ZONE 'UTC', 'YYYY-MM-DD')",
+        }
+    }
+}
+
+/// Summed usage for one group
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct UsageTotals {
+    pub key: String,
+    pub requests: i64,
+    pub prompt_tokens: i64,
+    pub completion_tokens: i64,
+    pub total_tokens: i64,
+    /// Requests without a reported cost count as $0
+    pub cost_usd: f64,
+    pub unpriced_requests: i64,
+}
+
+/// How long usage rows are kept
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UsageRetention {
+    /// `None` keeps rows forever
+    pub days: Option<u32>,
+}
+
+impl UsageRetention {
+    /// `0` keeps rows forever
+    pub fn days(days: u32) -> Self {
+        Self { days: (days > 0).then_some(days) }
+    }
+
+    /// Rows recorded before this are due for removal
+    pub fn cutoff(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        self.days.map(|days| now - Duration::days(days.into()))
+    }
+}
+
+// Unset parameters match everything, so one statement covers every filter
+const FILTER_SQL: &str = "($1::timestamptz IS NULL OR recorded_at >= $1)
+    AND ($2::timestamptz IS NULL OR recorded_at < $2)
+    AND ($3::text IS NULL OR model = $3)
+    AND ($4::uuid IS NULL OR session_id = $4)";
+
+// SUM over BIGINT is NUMERIC in Postgres, hence the casts back
+const TOTALS_SQL: &str = "COUNT(*) AS requests,
+    COALESCE(SUM(prompt_tokens), 0)::BIGINT AS prompt_tokens,
+    COALESCE(SUM(completion_tokens), 0)::BIGINT AS completion_tokens,
+    COALESCE(SUM(total_tokens), 0)::BIGINT AS total_tokens,
+    COALESCE(SUM(cost_usd), 0)::DOUBLE PRECISION AS cost_usd,
+    COUNT(*) FILTER (WHERE cost_usd IS NULL) AS unpriced_requests";
+
+/// `usage_log` table access
+#[derive(Debug, Clone)]
+pub struct PostgresUsageStore {
+    pool: Pool,
+}
+
+impl PostgresUsageStore {
+    /// Creates the table and its indexes when missing
+    pub async fn new(pool: Pool) -> Result<Self, UsageStoreError> {
+        let client = pool.get().await?;
+        client
+            .batch_execute(
+                "CREATE TABLE IF NOT EXISTS usage_log (
+                    id BIGSERIAL PRIMARY KEY,
+                    recorded_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                    session_id UUID,
+                    model TEXT NOT NULL,
+                    prompt_tokens BIGINT NOT NULL,
+                    completion_tokens BIGINT NOT NULL,
+                    total_tokens BIGINT NOT NULL,
+                    cost_usd DOUBLE PRECISION,
+                    latency_ms BIGINT
+                );
+                CREATE INDEX IF NOT EXISTS usage_log_recorded_at_idx ON usage_log (recorded_at);
+                CREATE INDEX IF NOT EXISTS usage_log_session_idx ON usage_log (session_id);",
+            )
+            .await?;
+        Ok(Self { pool })
+    }
+
+    #[instrument(skip(self, entry), fields(model = %entry.model))]
+    pub async fn record(&self, entry: &UsageEntry) -> Result<(), UsageStoreError> {
+        let client = self.pool.get().await?;
+        client
+            .execute(
+                "INSERT INTO usage_log
+                    (recorded_at, session_id, model, prompt_tokens, completion_tokens, total_tokens, cost_usd, latency_ms)
+                 VALUES ($1, $2, $3, $4, $5, $6, $7, $8)",
+                &[
+                    &entry.recorded_at,
+                    &entry.session_id,
+                    &entry.model,
+                    &entry.prompt_tokens,
+                    &entry.completion_tokens,
+                    &entry.total_tokens,
+                    &entry.cost_usd,
+                    &entry.latency_ms,
+                ],
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Matching rows, oldest first
+    pub async fn query(&self, query: &UsageQuery, limit: i64) -> Result<Vec<UsageEntry>, UsageStoreError> {
+        let client = self.pool.get().await?;
+        let rows = client
+            .query(
+                &format!(
+                    "SELECT recorded_at, session_id, model, prompt_tokens, completion_tokens,
+                            total_tokens, cost_usd, latency_ms
+                     FROM usage_log WHERE {} ORDER BY recorded_at LIMIT $5",
+                    FILTER_SQL
+                ),
+                &[&query.since, &query.until, &query.model, &query.session_id, &limit],
+            )
+            .await?;
+        Ok(rows.iter().map(UsageEntry::from_row).collect())
+    }
+
+    /// Totals per group, most expensive first
+    pub async fn totals(&self, query: &UsageQuery, group_by: UsageGrouping) -> Result<Vec<UsageTotals>, UsageStoreError> {
+        let client = self.pool.get().await?;
+        let rows = client
+            .query(
+                &format!(
+                    "SELECT {} AS key, {} FROM usage_log WHERE {} GROUP BY 1 ORDER BY cost_usd DESC, key",
+                    group_by.key_sql(),
+                    TOTALS_SQL,
+                    FILTER_SQL
+                ),
+                &[&query.since, &query.until, &query.model, &query.session_id],
+            )
+            .await?;
+        Ok(rows.iter().map(totals_from_row).collect())
+    }
+
+    /// All matching rows as one total
+    pub async fn total(&self, query: &UsageQuery) -> Result<UsageTotals, UsageStoreError> {
+        let client = self.pool.get().await?;
+        let row = client
+            .query_one(
+                &format!("SELECT 'total' AS key, {} FROM usage_log WHERE {}", TOTALS_SQL, FILTER_SQL),
+                &[&query.since, &query.until, &query.model, &query.session_id],
+            )
+            .await?;
+        Ok(totals_from_row(&row))
+    }
+
+    /// Delete rows recorded before `cutoff`; returns how many went
+    #[instrument(skip(self))]
+    pub async fn purge_before(&self, cutoff: DateTime<Utc>) -> Result<u64, UsageStoreError> {
+        let client = self.pool.get().await?;
+        let removed = client
+            .execute("DELETE FROM usage_log WHERE recorded_at < $1", &[&cutoff])
+            .await?;
+        Ok(removed)
+    }
+
+    /// Rows recorded in any of `sessions`
+    pub async fn count_sessions(&self, sessions: &[Uuid]) -> Result<u64, UsageStoreError> {
+        let client = self.pool.get().await?;
+        let row = client
+            .query_one("SELECT COUNT(*) AS rows FROM usage_log WHERE session_id = ANY($1)", &[&sessions])
+            .await?;
+        Ok(row.get::<_, i64>("rows") as u64)
+    }
+
+    /// Delete every row recorded in any of `sessions`; returns how many went
+    #[instrument(skip(self, sessions), fields(sessions = sessions.len()))]
+    pub async fn purge_sessions(&self, sessions: &[Uuid]) -> Result<u64, UsageStoreError> {
+        let client = self.pool.get().await?;
+        let removed = client
+            .execute("DELETE FROM usage_log WHERE session_id = ANY($1)", &[&sessions])
+            .await?;
+        Ok(removed)
+    }
+
+    /// Apply `retention` as of now; a no-op when rows are kept forever
+    pub async fn apply_retention(&self, retention: UsageRetention) -> Result<u64, UsageStoreError> {
+        match retention.cutoff(Utc::now()) {
+            Some(cutoff) => self.purge_before(cutoff).await,
+            None => Ok(0),
+        }
+    }
+}
+
+fn totals_from_row(row: &Row) -> UsageTotals {
+    UsageTotals {
+        key: row.get("key"),
+        requests: row.get("requests"),
+        prompt_tokens: row.get("prompt_tokens"),
+        completion_tokens: row.get("completion_tokens"),
+        total_tokens: row.get("total_tokens"),
+        cost_usd: row.get("cost_usd"),
+        unpriced_requests: row.get("unpriced_requests"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retention_cutoff() {
+        let now = Utc::now();
+        assert_eq!(UsageRetention::days(0).cutoff(now), None);
+        assert_eq!(UsageRetention::days(90).cutoff(now), Some(now - Duration::days(90)));
+    }
+
+    #[test]
+    fn test_grouping_serde() {
+        assert_eq!(serde_json::to_string(&UsageGrouping::Day).unwrap(), "\"day\"");
+        assert_eq!(serde_json::from_str::<UsageGrouping>("\"session\"").unwrap(), UsageGrouping::Session);
+    }
+}
@@ -0,0 +1,83 @@
+use chrono::{Duration as ChronoDuration, Utc};
+use jamey_core::{
+    ConnectionPools, MemoryType, PoolConfig, PostgresMemoryStore, PostgresPoolConfig, RedisPoolConfig,
+    SyncChange, SyncedMemory, Tombstone,
+};
+use serde_json::json;
+use std::time::Duration;
+use uuid::Uuid;
+
+async fn setup_store() -> PostgresMemoryStore {
+    let config = PoolConfig {
+        postgres: PostgresPoolConfig {
+            host: "localhost".to_string(),
+            port: 5432,
+            database: "jamey_test".to_string(),
+            user: "jamey".to_string(),
+            password: "test_password".to_string(),
+            max_connections: 5,
+            min_connections: 1,
+            connect_timeout: Duration::from_secs(5),
+            idle_timeout: Duration::from_secs(300),
+        },
+        redis: RedisPoolConfig {
+            url: "redis://localhost".to_string(),
+            max_connections: 5,
+            min_connections: 1,
+            connect_timeout: Duration::from_secs(5),
+            idle_timeout: Duration::from_secs(300),
+        },
+    };
+    let pools = ConnectionPools::new(config).await.unwrap();
+    PostgresMemoryStore::new(pools.postgres.clone(), 1536).await.unwrap()
+}
+
+fn upsert(id: Uuid, namespace: &str, content: &str, seconds: i64) -> SyncChange {
+    let created_at = Utc::now() - ChronoDuration::hours(1);
+    SyncChange::Upsert(SyncedMemory {
+        id,
+        memory_type: MemoryType::Knowledge,
+        content: content.to_string(),
+        embedding: vec![0.1; 1536],
+        metadata: json!({}),
+        namespace: namespace.to_string(),
+        created_at,
+        updated_at: created_at + ChronoDuration::seconds(seconds),
+    })
+}
+
+#[tokio::test]
+#[ignore = "needs PostgreSQL with pgvector, and partitions the test database's memories table for good"]
+async fn test_apply_changes_on_partitioned_table() {
+    let store = setup_store().await;
+    if !store.is_partitioned().await.unwrap() {
+        store.partition_by_namespace().await.unwrap();
+    }
+
+    let namespace = format!("partition-test-{}", Uuid::new_v4());
+    let other = format!("partition-test-{}", Uuid::new_v4());
+    let id = Uuid::new_v4();
+
+    let report = store.apply_changes(&[upsert(id, &namespace, "first", 1)]).await.unwrap();
+    assert_eq!(report.written, 1);
+    // Goes through the upsert's conflict path
+    let report = store.apply_changes(&[upsert(id, &namespace, "second", 2)]).await.unwrap();
+    assert_eq!(report.written, 1);
+    // The key would allow the same id in another namespace; sync doesn't
+    let report = store.apply_changes(&[upsert(id, &other, "elsewhere", 3)]).await.unwrap();
+    assert_eq!(report.skipped, 1);
+
+    let changes = store.changes_since(&namespace, None, 10).await.unwrap();
+    assert_eq!(changes.len(), 1);
+    match &changes[0] {
+        SyncChange::Upsert(memory) => assert_eq!(memory.content, "second"),
+        other => panic!("expected an upsert, got {:?}", other),
+    }
+    assert!(store.changes_since(&other, None, 10).await.unwrap().is_empty());
+
+    let report = store
+        .apply_changes(&[SyncChange::Delete(Tombstone { id, namespace: namespace.clone(), deleted_at: Utc::now() })])
+        .await
+        .unwrap();
+    assert_eq!(report.deleted, 1);
+}
@@ -0,0 +1,394 @@
+//! Agent-to-agent (A2A) task protocol
+//!
+//! Wire types for the [A2A protocol](https://a2a-protocol.org): an agent
+//! publishes an [`AgentCard`] at `/.well-known/agent-card.json` and takes
+//! JSON-RPC 2.0 calls at the card's `url`. `message/send` starts a [`Task`]
+//! (or answers with a plain [`A2aMessage`]), `message/stream` does the same
+//! as a Server-Sent Events stream of [`Event`]s, and `tasks/get` and
+//! `tasks/cancel` follow up on a task by id. Tasks produce [`Artifact`]s,
+//! their results.
+//!
+//! Jamey speaks it both ways: the orchestration connector sends tasks to
+//! A2A agents, and the runtime serves its own card and endpoint.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Protocol version spoken by both sides
+pub const PROTOCOL_VERSION: &str = "0.3.0";
+
+/// Where an agent's card is published, relative to its base URL
+pub const AGENT_CARD_PATH: &str = "/.well-known/agent-card.json";
+
+/// Where cards were published before 0.3
+pub const LEGACY_AGENT_CARD_PATH: &str = "/.well-known/agent.json";
+
+pub const METHOD_SEND: &str = "message/send";
+pub const METHOD_STREAM: &str = "message/stream";
+pub const METHOD_GET_TASK: &str = "tasks/get";
+pub const METHOD_CANCEL_TASK: &str = "tasks/cancel";
+
+pub const PARSE_ERROR: i64 = -32700;
+pub const METHOD_NOT_FOUND: i64 = -32601;
+pub const INVALID_PARAMS: i64 = -32602;
+pub const INTERNAL_ERROR: i64 = -32603;
+/// A2A error codes, beside the standard JSON-RPC ones
+pub const TASK_NOT_FOUND: i64 = -32001;
+pub const TASK_NOT_CANCELABLE: i64 = -32002;
+
+/// What an agent tells others about itself
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AgentCard {
+    pub name: String,
+    pub description: String,
+    /// JSON-RPC endpoint
+    pub url: String,
+    pub version: String,
+    #[serde(default)]
+    pub protocol_version: String,
+    #[serde(default)]
+    pub capabilities: AgentCardCapabilities,
+    #[serde(default)]
+    pub default_input_modes: Vec<String>,
+    #[serde(default)]
+    pub default_output_modes: Vec<String>,
+    #[serde(default)]
+    pub skills: Vec<AgentSkill>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AgentCardCapabilities {
+    #[serde(default)]
+    pub streaming: bool,
+    #[serde(default)]
+    pub push_notifications: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AgentSkill {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum A2aRole {
+    User,
+    Agent,
+}
+
+/// Content of a message or artifact
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum Part {
+    Text { text: String },
+    Data { data: Value },
+    File { file: Value },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct A2aMessage {
+    pub role: A2aRole,
+    pub parts: Vec<Part>,
+    pub message_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub task_id: Option<String>,
+}
+
+impl A2aMessage {
+    pub fn new(role: A2aRole, parts: Vec<Part>) -> Self {
+        Self {
+            role,
+            parts,
+            message_id: uuid::Uuid::new_v4().to_string(),
+            context_id: None,
+            task_id: None,
+        }
+    }
+
+    pub fn text(role: A2aRole, text: impl Into<String>) -> Self {
+        Self::new(role, vec![Part::Text { text: text.into() }])
+    }
+
+    /// The text parts joined, with data parts as JSON
+    pub fn content(&self) -> String {
+        parts_text(&self.parts)
+    }
+}
+
+fn parts_text(parts: &[Part]) -> String {
+    parts
+        .iter()
+        .filter_map(|part| match part {
+            Part::Text { text } => Some(text.clone()),
+            Part::Data { data } => Some(data.to_string()),
+            Part::File { .. } => None,
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TaskState {
+    Submitted,
+    Working,
+    InputRequired,
+    Completed,
+    Canceled,
+    Failed,
+    Rejected,
+    AuthRequired,
+    Unknown,
+}
+
+impl TaskState {
+    /// Whether the task is over and won't change again
+    pub fn is_terminal(self) -> bool {
+        matches!(self, TaskState::Completed | TaskState::Canceled | TaskState::Failed | TaskState::Rejected)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TaskStatus {
+    pub state: TaskState,
+    /// What the agent says about the state, such as why it failed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<A2aMessage>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<DateTime<Utc>>,
+}
+
+impl TaskStatus {
+    pub fn now(state: TaskState) -> Self {
+        Self {
+            state,
+            message: None,
+            timestamp: Some(Utc::now()),
+        }
+    }
+}
+
+/// Something a task produced
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Artifact {
+    pub artifact_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub parts: Vec<Part>,
+}
+
+impl Artifact {
+    pub fn content(&self) -> String {
+        parts_text(&self.parts)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Task {
+    pub id: String,
+    pub context_id: String,
+    pub status: TaskStatus,
+    #[serde(default)]
+    pub artifacts: Vec<Artifact>,
+    #[serde(default)]
+    pub history: Vec<A2aMessage>,
+}
+
+impl Task {
+    /// Fold a streamed event for this task into it
+    pub fn apply(&mut self, event: Event) {
+        match event {
+            Event::Task(task) => *self = task,
+            Event::Message(message) => self.history.push(message),
+            Event::StatusUpdate(update) => {
+                if let Some(message) = &update.status.message {
+                    self.history.push(message.clone());
+                }
+                self.status = update.status;
+            }
+            Event::ArtifactUpdate(update) => {
+                let existing = self
+                    .artifacts
+                    .iter_mut()
+                    .find(|artifact| artifact.artifact_id == update.artifact.artifact_id);
+                match existing {
+                    Some(artifact) if update.append => {
+                        for part in update.artifact.parts {
+                            // Streamed text continues the text before it
+                            match (artifact.parts.last_mut(), part) {
+                                (Some(Part::Text { text }), Part::Text { text: more }) => text.push_str(&more),
+                                (_, part) => artifact.parts.push(part),
+                            }
+                        }
+                    }
+                    Some(artifact) => *artifact = update.artifact,
+                    None => self.artifacts.push(update.artifact),
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskStatusUpdate {
+    pub task_id: String,
+    pub context_id: String,
+    pub status: TaskStatus,
+    /// The last event of the stream
+    #[serde(rename = "final", default)]
+    pub is_final: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskArtifactUpdate {
+    pub task_id: String,
+    pub context_id: String,
+    pub artifact: Artifact,
+    /// Add the parts to the artifact sent before rather than replace it
+    #[serde(default)]
+    pub append: bool,
+    #[serde(default)]
+    pub last_chunk: bool,
+}
+
+/// A result of `message/send` or `tasks/get`, or one event of a stream
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum Event {
+    Task(Task),
+    Message(A2aMessage),
+    StatusUpdate(TaskStatusUpdate),
+    ArtifactUpdate(TaskArtifactUpdate),
+}
+
+/// Parameters of `message/send` and `message/stream`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MessageSendParams {
+    pub message: A2aMessage,
+}
+
+/// Parameters of `tasks/get` and `tasks/cancel`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskQueryParams {
+    pub id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub history_length: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JsonRpcRequest {
+    pub jsonrpc: String,
+    #[serde(default)]
+    pub id: Value,
+    pub method: String,
+    #[serde(default)]
+    pub params: Value,
+}
+
+impl JsonRpcRequest {
+    pub fn new(method: &str, params: impl Serialize) -> Self {
+        Self {
+            jsonrpc: "2.0".to_string(),
+            id: Value::String(uuid::Uuid::new_v4().to_string()),
+            method: method.to_string(),
+            params: serde_json::to_value(params).unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JsonRpcError {
+    pub code: i64,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JsonRpcResponse {
+    pub jsonrpc: String,
+    pub id: Value,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<JsonRpcError>,
+}
+
+impl JsonRpcResponse {
+    pub fn result(id: Value, result: impl Serialize) -> Self {
+        Self {
+            jsonrpc: "2.0".to_string(),
+            id,
+            result: Some(serde_json::to_value(result).unwrap_or_default()),
+            error: None,
+        }
+    }
+
+    pub fn error(id: Value, code: i64, message: impl Into<String>) -> Self {
+        Self {
+            jsonrpc: "2.0".to_string(),
+            id,
+            result: None,
+            error: Some(JsonRpcError { code, message: message.into() }),
+        }
+    }
+
+    /// The result as an [`Event`], or the error the agent returned
+    pub fn into_event(self) -> Result<Event, JsonRpcError> {
+        if let Some(error) = self.error {
+            return Err(error);
+        }
+        serde_json::from_value(self.result.unwrap_or_default()).map_err(|e| JsonRpcError {
+            code: INTERNAL_ERROR,
+            message: format!("Unreadable result: {}", e),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stream_events_build_the_task() {
+        let mut task = Task {
+            id: "t1".to_string(),
+            context_id: "c1".to_string(),
+            status: TaskStatus::now(TaskState::Submitted),
+            artifacts: Vec::new(),
+            history: Vec::new(),
+        };
+        let stream = [
+            r#"{"kind":"status-update","taskId":"t1","contextId":"c1","status":{"state":"working"},"final":false}"#,
+            r#"{"kind":"artifact-update","taskId":"t1","contextId":"c1","artifact":{"artifactId":"a","parts":[{"kind":"text","text":"Hel"}]}}"#,
+            r#"{"kind":"artifact-update","taskId":"t1","contextId":"c1","append":true,"artifact":{"artifactId":"a","parts":[{"kind":"text","text":"lo"}]}}"#,
+            r#"{"kind":"status-update","taskId":"t1","contextId":"c1","status":{"state":"completed"},"final":true}"#,
+        ];
+        for event in stream {
+            task.apply(serde_json::from_str(event).unwrap());
+        }
+        assert_eq!(task.status.state, TaskState::Completed);
+        assert!(task.status.state.is_terminal());
+        assert_eq!(task.artifacts.len(), 1);
+        assert_eq!(task.artifacts[0].content(), "Hello");
+
+        let round_trip = serde_json::to_value(Event::Task(task.clone())).unwrap();
+        assert_eq!(round_trip["kind"], "task");
+        assert_eq!(round_trip["contextId"], "c1");
+        assert_eq!(serde_json::from_value::<Event>(round_trip).unwrap(), Event::Task(task));
+    }
+}
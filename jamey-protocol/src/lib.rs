@@ -9,6 +9,8 @@ use uuid::Uuid;
 use chrono::{DateTime, Utc};
 use validator::{Validate, ValidationError};
 
+pub mod a2a;
+
 #[derive(Debug, Error)]
 pub enum ProtocolError {
     #[error("Invalid message format: {0}")]
@@ -43,6 +45,58 @@ pub struct Message {
     #[validate(custom(function = "validate_metadata"))]
     #[serde(default = "default_metadata")]
     pub metadata: serde_json::Value,
+    /// Content beyond the text in `content`, such as attached files
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub parts: Vec<ContentPart>,
+    /// Memories that were in the prompt when this reply was written
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub citations: Vec<Citation>,
+}
+
+/// A retrieved memory a reply may draw on; `[n]` in the reply refers to
+/// the n-th citation
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Citation {
+    pub memory_id: Uuid,
+    /// Start of the memory's content
+    pub snippet: String,
+    /// Cosine similarity between the memory and the question
+    pub similarity: f32,
+}
+
+/// A non-text part of a message
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ContentPart {
+    /// Reference to an uploaded [`Attachment`]; the runtime resolves it to
+    /// the file's content when the message is sent to a model
+    Attachment {
+        attachment_id: Uuid,
+        name: String,
+        mime_type: String,
+    },
+}
+
+/// A file uploaded for use in a conversation
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Attachment {
+    pub id: Uuid,
+    /// Original file name, without its directory
+    pub name: String,
+    pub mime_type: String,
+    pub size_bytes: u64,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Attachment {
+    /// The content part referencing this attachment
+    pub fn part(&self) -> ContentPart {
+        ContentPart::Attachment {
+            attachment_id: self.id,
+            name: self.name.clone(),
+            mime_type: self.mime_type.clone(),
+        }
+    }
 }
 
 fn default_metadata() -> serde_json::Value {
@@ -83,9 +137,29 @@ impl Message {
             content,
             timestamp: Utc::now(),
             metadata: serde_json::json!({}),
+            parts: Vec::new(),
+            citations: Vec::new(),
         }
     }
 
+    /// Reference `attachments` from this message
+    pub fn with_attachments(mut self, attachments: &[Attachment]) -> Self {
+        self.parts.extend(attachments.iter().map(Attachment::part));
+        self
+    }
+
+    pub fn with_citations(mut self, citations: Vec<Citation>) -> Self {
+        self.citations = citations;
+        self
+    }
+
+    /// IDs of the attachments this message references
+    pub fn attachment_ids(&self) -> impl Iterator<Item = Uuid> + '_ {
+        self.parts.iter().map(|part| match part {
+            ContentPart::Attachment { attachment_id, .. } => *attachment_id,
+        })
+    }
+
     pub fn system(content: impl Into<String>) -> Self {
         Self::new(Role::System, content.into())
     }
@@ -160,6 +234,9 @@ pub struct ToolResult {
     pub success: bool,
     pub error: Option<String>,
     pub execution_time_ms: Option<u64>,
+    /// Files the tool produced, stored for the conversation
+    #[serde(default)]
+    pub attachments: Vec<Attachment>,
 }
 
 impl ToolResult {
@@ -171,6 +248,7 @@ impl ToolResult {
             success: true,
             error: None,
             execution_time_ms: None,
+            attachments: Vec::new(),
         }
     }
 
@@ -182,6 +260,7 @@ impl ToolResult {
             success: false,
             error: Some(error),
             execution_time_ms: None,
+            attachments: Vec::new(),
         }
     }
 }
@@ -203,8 +282,15 @@ pub struct SessionState {
 pub struct CreateSessionRequest {
     pub user_id: Option<String>,
     pub initial_context: Option<String>,
+    /// Connector IDs the session may use; empty allows every connector
     #[validate(length(min = 0, max = 100))]
     pub tool_preferences: Vec<String>,
+    /// Highest connector capability level the session may use (`read_only`,
+    /// `read_write`, ... `full_access`); unset means no cap
+    #[serde(default)]
+    pub max_capability: Option<String>,
+    /// Free-form; `timezone` (an IANA name such as `Europe/Berlin`) and
+    /// `locale` (such as `de-DE`) set the times the session works in
     #[validate(custom(function = "validate_metadata"))]
     #[serde(default = "default_metadata")]
     pub metadata: serde_json::Value,
@@ -287,6 +373,9 @@ pub struct ProcessMessageResponse {
     pub tool_calls: Vec<ToolCall>,
     pub tool_results: Vec<ToolResult>,
     pub memory_entries_added: u32,
+    /// Memories given to the model for this reply
+    #[serde(default)]
+    pub citations: Vec<Citation>,
     pub processing_time_ms: u64,
     pub usage: TokenUsage,
 }
@@ -357,7 +446,7 @@ pub trait SessionManager {
 /// Common re-exports
 pub mod prelude {
     pub use super::{
-        Message, Role, ToolSpec, ToolCall, ToolResult, SessionState,
+        Message, Role, ContentPart, Attachment, Citation, ToolSpec, ToolCall, ToolResult, SessionState,
         CreateSessionRequest, CreateSessionResponse, ProcessMessageRequest,
         ProcessMessageResponse, ProcessContext, TokenUsage, HealthCheckResponse,
         ComponentStatus, ProtocolError, ProtocolHandler, SessionManager,
@@ -378,6 +467,42 @@ mod tests {
         assert!(msg.id != Uuid::nil());
     }
 
+    #[test]
+    fn test_message_attachments() {
+        let attachment = Attachment {
+            id: Uuid::new_v4(),
+            name: "report.pdf".to_string(),
+            mime_type: "application/pdf".to_string(),
+            size_bytes: 1024,
+            created_at: Utc::now(),
+        };
+        let msg = Message::user("Summarize this").with_attachments(&[attachment.clone()]);
+        assert_eq!(msg.attachment_ids().collect::<Vec<_>>(), vec![attachment.id]);
+
+        let json = serde_json::to_value(&msg).unwrap();
+        assert_eq!(json["parts"][0]["type"], "attachment");
+        let restored: Message = serde_json::from_value(json).unwrap();
+        assert_eq!(restored.parts, msg.parts);
+
+        // Messages stored before parts existed still load
+        let plain = serde_json::to_value(Message::user("hi")).unwrap();
+        assert!(plain.get("parts").is_none());
+        assert!(serde_json::from_value::<Message>(plain).unwrap().parts.is_empty());
+    }
+
+    #[test]
+    fn test_message_citations() {
+        let citation = Citation {
+            memory_id: Uuid::new_v4(),
+            snippet: "Jamey runs on port 3000".to_string(),
+            similarity: 0.91,
+        };
+        let msg = Message::assistant("It listens on 3000 [1]").with_citations(vec![citation.clone()]);
+        let restored: Message = serde_json::from_value(serde_json::to_value(&msg).unwrap()).unwrap();
+        assert_eq!(restored.citations, vec![citation]);
+        assert!(serde_json::to_value(Message::assistant("hi")).unwrap().get("citations").is_none());
+    }
+
     #[test]
     fn test_tool_result() {
         let success = ToolResult::success("test_id".to_string(), "test_tool".to_string(), "Success".to_string());
@@ -0,0 +1,349 @@
+//! Speech providers
+//!
+//! [`AudioProvider`] is the speech side of Jamey: it turns reply text into
+//! audio and recorded speech into text. [`OpenAiAudioProvider`] speaks the
+//! OpenAI `/audio/speech` and `/audio/transcriptions` APIs, which OpenAI and
+//! most self-hosted speech servers accept.
+
+use async_trait::async_trait;
+use jamey_core::secure_logging::Masked;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+use thiserror::Error;
+use url::Url;
+
+/// Longest text one speech request may carry
+pub const MAX_SPEECH_CHARS: usize = 4096;
+
+/// Slowest and fastest speaking rate the API accepts
+pub const SPEED_RANGE: std::ops::RangeInclusive<f32> = 0.25..=4.0;
+
+/// Largest recording one transcription request may upload
+pub const MAX_TRANSCRIPTION_BYTES: usize = 25 * 1024 * 1024;
+
+#[derive(Debug, Error)]
+pub enum AudioError {
+    #[error("Invalid speech request: {0}")]
+    InvalidRequest(String),
+    #[error("Speech API error ({status}): {message}")]
+    Api { status: u16, message: String },
+    #[error("Speech request failed: {0}")]
+    Http(#[from] reqwest::Error),
+}
+
+/// Encoding of synthesized audio
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum AudioFormat {
+    #[default]
+    Mp3,
+    Opus,
+    Aac,
+    Flac,
+    Wav,
+    /// Raw 24 kHz 16-bit mono samples
+    Pcm,
+}
+
+impl AudioFormat {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AudioFormat::Mp3 => "mp3",
+            AudioFormat::Opus => "opus",
+            AudioFormat::Aac => "aac",
+            AudioFormat::Flac => "flac",
+            AudioFormat::Wav => "wav",
+            AudioFormat::Pcm => "pcm",
+        }
+    }
+
+    pub fn mime_type(self) -> &'static str {
+        match self {
+            AudioFormat::Mp3 => "audio/mpeg",
+            AudioFormat::Opus => "audio/ogg",
+            AudioFormat::Aac => "audio/aac",
+            AudioFormat::Flac => "audio/flac",
+            AudioFormat::Wav => "audio/wav",
+            AudioFormat::Pcm => "audio/pcm",
+        }
+    }
+
+    /// Whether two clips joined byte for byte still play as one; WAV and
+    /// FLAC files carry a header that only describes the first
+    pub fn concatenates(self) -> bool {
+        !matches!(self, AudioFormat::Wav | AudioFormat::Flac)
+    }
+}
+
+impl fmt::Display for AudioFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for AudioFormat {
+    type Err = AudioError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "mp3" => Ok(AudioFormat::Mp3),
+            "opus" => Ok(AudioFormat::Opus),
+            "aac" => Ok(AudioFormat::Aac),
+            "flac" => Ok(AudioFormat::Flac),
+            "wav" => Ok(AudioFormat::Wav),
+            "pcm" => Ok(AudioFormat::Pcm),
+            other => Err(AudioError::InvalidRequest(format!("Unknown audio format: {}", other))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpeechRequest {
+    pub text: String,
+    pub voice: String,
+    /// 1.0 is normal speed
+    pub speed: f32,
+    pub format: AudioFormat,
+}
+
+#[derive(Debug, Clone)]
+pub struct Speech {
+    pub audio: Vec<u8>,
+    pub format: AudioFormat,
+}
+
+#[derive(Debug, Clone)]
+pub struct TranscriptionRequest {
+    pub audio: Vec<u8>,
+    pub format: AudioFormat,
+    /// ISO-639-1 code; the provider guesses when unset
+    pub language: Option<String>,
+}
+
+#[async_trait]
+pub trait AudioProvider: Send + Sync {
+    async fn synthesize(&self, request: &SpeechRequest) -> Result<Speech, AudioError>;
+
+    /// Text spoken in a recording
+    async fn transcribe(&self, request: &TranscriptionRequest) -> Result<String, AudioError>;
+}
+
+#[derive(Clone)]
+pub struct AudioConfig {
+    /// Sent as a bearer token; local servers often need none
+    pub api_key: Option<String>,
+    pub api_base_url: Url,
+    pub speech_model: String,
+    pub transcription_model: String,
+    pub timeout_seconds: u64,
+}
+
+impl fmt::Debug for AudioConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AudioConfig")
+            .field("api_key", &Masked::opt(self.api_key.as_deref()))
+            .field("api_base_url", &self.api_base_url.as_str())
+            .field("speech_model", &self.speech_model)
+            .field("transcription_model", &self.transcription_model)
+            .field("timeout_seconds", &self.timeout_seconds)
+            .finish()
+    }
+}
+
+impl Default for AudioConfig {
+    fn default() -> Self {
+        Self {
+            api_key: None,
+            api_base_url: Url::parse("https://api.openai.com/v1").expect("Hardcoded OpenAI URL must be valid"),
+            speech_model: "tts-1".to_string(),
+            transcription_model: "whisper-1".to_string(),
+            timeout_seconds: 60,
+        }
+    }
+}
+
+pub struct OpenAiAudioProvider {
+    config: AudioConfig,
+    client: reqwest::Client,
+}
+
+impl OpenAiAudioProvider {
+    pub fn new(config: AudioConfig) -> Result<Self, AudioError> {
+        crate::openrouter::validate_api_url(&config.api_base_url).map_err(AudioError::InvalidRequest)?;
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(config.timeout_seconds))
+            .min_tls_version(reqwest::tls::Version::TLS_1_2)
+            .build()?;
+        Ok(Self { config, client })
+    }
+
+    fn endpoint(&self, path: &str) -> String {
+        format!("{}/{}", self.config.api_base_url.as_str().trim_end_matches('/'), path)
+    }
+
+    fn authorize(&self, request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        match self.config.api_key.as_deref().filter(|key| !key.is_empty()) {
+            Some(key) => request.bearer_auth(key),
+            None => request,
+        }
+    }
+
+    async fn check(response: reqwest::Response) -> Result<reqwest::Response, AudioError> {
+        let status = response.status();
+        if status.is_success() {
+            return Ok(response);
+        }
+        let message = response.text().await.unwrap_or_default();
+        Err(AudioError::Api {
+            status: status.as_u16(),
+            message: message.chars().take(500).collect(),
+        })
+    }
+}
+
+#[async_trait]
+impl AudioProvider for OpenAiAudioProvider {
+    async fn synthesize(&self, request: &SpeechRequest) -> Result<Speech, AudioError> {
+        if request.text.trim().is_empty() {
+            return Err(AudioError::InvalidRequest("Nothing to say".to_string()));
+        }
+        if request.text.chars().count() > MAX_SPEECH_CHARS {
+            return Err(AudioError::InvalidRequest(format!(
+                "Text is longer than {} characters",
+                MAX_SPEECH_CHARS
+            )));
+        }
+        if !SPEED_RANGE.contains(&request.speed) {
+            return Err(AudioError::InvalidRequest(format!(
+                "Speed {} is outside {}-{}",
+                request.speed,
+                SPEED_RANGE.start(),
+                SPEED_RANGE.end()
+            )));
+        }
+
+        let response = self
+            .authorize(self.client.post(self.endpoint("audio/speech")))
+            .json(&serde_json::json!({
+                "model": self.config.speech_model,
+                "input": request.text,
+                "voice": request.voice,
+                "speed": request.speed,
+                "response_format": request.format,
+            }))
+            .send()
+            .await?;
+        let response = Self::check(response).await?;
+        Ok(Speech {
+            audio: response.bytes().await?.to_vec(),
+            format: request.format,
+        })
+    }
+
+    async fn transcribe(&self, request: &TranscriptionRequest) -> Result<String, AudioError> {
+        if request.audio.is_empty() {
+            return Err(AudioError::InvalidRequest("Recording is empty".to_string()));
+        }
+        if request.audio.len() > MAX_TRANSCRIPTION_BYTES {
+            return Err(AudioError::InvalidRequest(format!(
+                "Recording is larger than {} MB",
+                MAX_TRANSCRIPTION_BYTES / (1024 * 1024)
+            )));
+        }
+
+        let file = reqwest::multipart::Part::bytes(request.audio.clone())
+            .file_name(format!("speech.{}", request.format))
+            .mime_str(request.format.mime_type())?;
+        let mut form = reqwest::multipart::Form::new()
+            .text("model", self.config.transcription_model.clone())
+            .text("response_format", "json")
+            .part("file", file);
+        if let Some(language) = &request.language {
+            form = form.text("language", language.clone());
+        }
+
+        let response = self
+            .authorize(self.client.post(self.endpoint("audio/transcriptions")))
+            .multipart(form)
+            .send()
+            .await?;
+        let response = Self::check(response).await?;
+        let body: serde_json::Value = response.json().await?;
+        body.get("text")
+            .and_then(|text| text.as_str())
+            .map(|text| text.trim().to_string())
+            .ok_or_else(|| AudioError::Api {
+                status: 200,
+                message: "Transcription response has no text".to_string(),
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::{matchers::*, Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_synthesize() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/audio/speech"))
+            .and(header("authorization", "Bearer test_key"))
+            .and(body_partial_json(serde_json::json!({ "voice": "nova", "response_format": "opus" })))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(vec![1u8, 2, 3]))
+            .mount(&server)
+            .await;
+
+        let provider = OpenAiAudioProvider::new(AudioConfig {
+            api_key: Some("test_key".to_string()),
+            api_base_url: Url::parse(&server.uri()).unwrap(),
+            ..Default::default()
+        })
+        .unwrap();
+        let mut request = SpeechRequest {
+            text: "Hello there".to_string(),
+            voice: "nova".to_string(),
+            speed: 1.0,
+            format: AudioFormat::Opus,
+        };
+        let speech = provider.synthesize(&request).await.unwrap();
+        assert_eq!(speech.audio, vec![1, 2, 3]);
+
+        request.speed = 5.0;
+        assert!(matches!(provider.synthesize(&request).await, Err(AudioError::InvalidRequest(_))));
+        request.speed = 1.0;
+        request.voice = "unknown".to_string();
+        assert!(matches!(provider.synthesize(&request).await, Err(AudioError::Api { status: 404, .. })));
+    }
+
+    #[tokio::test]
+    async fn test_transcribe() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/audio/transcriptions"))
+            .and(body_string_contains("whisper-1"))
+            .and(body_string_contains("filename=\"speech.wav\""))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({ "text": " What time is it? " })))
+            .mount(&server)
+            .await;
+
+        let provider = OpenAiAudioProvider::new(AudioConfig {
+            api_base_url: Url::parse(&server.uri()).unwrap(),
+            ..Default::default()
+        })
+        .unwrap();
+        let mut request = TranscriptionRequest {
+            audio: vec![0u8; 64],
+            format: AudioFormat::Wav,
+            language: Some("en".to_string()),
+        };
+        assert_eq!(provider.transcribe(&request).await.unwrap(), "What time is it?");
+
+        request.audio.clear();
+        assert!(matches!(provider.transcribe(&request).await, Err(AudioError::InvalidRequest(_))));
+    }
+}
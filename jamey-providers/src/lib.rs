@@ -1,9 +1,12 @@
 //! LLM Provider implementations for Digital Twin Jamey
 //! 
 //! This crate provides implementations for various LLM providers,
-//! starting with OpenRouter support for accessing multiple LLM models.
+//! starting with OpenRouter support for accessing multiple LLM models,
+//! plus speech synthesis for spoken replies.
 
+pub mod audio;
 pub mod openrouter;
+pub mod quota;
 
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
@@ -15,19 +18,24 @@ pub enum ProviderError {
     Provider(String),
     #[error(transparent)]
     OpenRouter(#[from] openrouter::OpenRouterError),
+    #[error(transparent)]
+    Audio(#[from] audio::AudioError),
 }
 
 /// Common traits and types used across providers
 pub mod prelude {
     pub use super::openrouter::{
-        ChatRequest, ChatResponse, LlmProvider, Message, OpenRouterConfig, OpenRouterProvider, Tool,
-        ToolCall,
+        ChatRequest, ChatResponse, ChatStream, LlmProvider, Message, OpenRouterConfig,
+        OpenRouterConfigBuilder, OpenRouterProvider, StreamEvent, Tool, ToolCall,
     };
+    pub use super::quota::ProviderQuota;
+    pub use super::audio::{AudioFormat, AudioProvider, OpenAiAudioProvider, SpeechRequest};
     pub use super::ProviderError;
 }
 
 /// Re-export main provider implementations
-pub use openrouter::{OpenRouterConfig, OpenRouterProvider};
+pub use openrouter::{OpenRouterConfig, OpenRouterConfigBuilder, OpenRouterProvider};
+pub use quota::ProviderQuota;
 
 /// Generic response type for tool calls
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -48,7 +56,7 @@ pub trait ToolHandler {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use openrouter::{ChatRequest, Message, OpenRouterConfig};
+    use openrouter::{ChatRequest, LlmProvider, Message, OpenRouterConfig};
     use url::Url;
     use wiremock::{matchers::*, Mock, MockServer, ResponseTemplate};
 
@@ -103,6 +111,24 @@ mod tests {
         assert_eq!(response.choices[0].message.content, "Test response");
 
         // Test embedding functionality
+        Mock::given(method("POST"))
+            .and(path("/embeddings"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "object": "list",
+                "data": [{
+                    "object": "embedding",
+                    "embedding": vec![0.1; 1536],
+                    "index": 0
+                }],
+                "model": "text-embedding-ada-002",
+                "usage": {
+                    "prompt_tokens": 2,
+                    "total_tokens": 2
+                }
+            })))
+            .mount(&mock_server)
+            .await;
+
         let embedding = provider.get_embedding("Test text").await?;
         assert_eq!(embedding.len(), 1536); // Expected embedding dimension
         Ok(())
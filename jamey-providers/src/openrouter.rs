@@ -1,7 +1,12 @@
+use crate::quota::{KeyInfo, ProviderQuota};
 use anyhow::Result;
 use async_trait::async_trait;
 use backoff::ExponentialBackoff;
+use jamey_core::cache::CacheManager;
+use jamey_core::secure_logging::Masked;
+use reqwest::header::HeaderMap;
 use serde::{Deserialize, Serialize};
+use std::time::{Duration, SystemTime};
 use thiserror::Error;
 use tiktoken_rs::CoreBPE;
 use tracing::error;
@@ -33,9 +38,44 @@ pub enum OpenRouterError {
     TlsError(String),
     #[error("Certificate validation error: {0}")]
     CertificateError(String),
+    /// The API couldn't be reached, or answered that it is down
+    #[error("Provider unreachable: {0}")]
+    Unreachable(String),
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Whether `error` means the provider couldn't be reached at all, as
+/// opposed to refusing or failing the request. Worth retrying once
+/// connectivity is back.
+pub fn is_unreachable(error: &anyhow::Error) -> bool {
+    matches!(error.downcast_ref::<OpenRouterError>(), Some(OpenRouterError::Unreachable(_)))
+}
+
+/// Error for a request that never got an answer
+fn send_error(error: reqwest::Error) -> OpenRouterError {
+    // reqwest's message leaves out the cause, such as a rejected certificate
+    let mut message = error.to_string();
+    let mut source = std::error::Error::source(&error);
+    while let Some(cause) = source {
+        message.push_str(": ");
+        message.push_str(&cause.to_string());
+        source = cause.source();
+    }
+    if error.is_connect() || error.is_timeout() {
+        OpenRouterError::Unreachable(message)
+    } else {
+        OpenRouterError::Api(message)
+    }
+}
+
+/// Gateway answers meaning the API behind it is down
+fn is_gateway_error(status: reqwest::StatusCode) -> bool {
+    matches!(
+        status,
+        reqwest::StatusCode::BAD_GATEWAY | reqwest::StatusCode::SERVICE_UNAVAILABLE | reqwest::StatusCode::GATEWAY_TIMEOUT
+    )
+}
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct OpenRouterConfig {
     pub api_key: String,
     pub api_base_url: Url,
@@ -45,6 +85,20 @@ pub struct OpenRouterConfig {
     pub max_retries: u32,
 }
 
+// Configs get logged; the key must not be
+impl std::fmt::Debug for OpenRouterConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OpenRouterConfig")
+            .field("api_key", &Masked::of(&self.api_key))
+            .field("api_base_url", &self.api_base_url.as_str())
+            .field("default_model", &self.default_model)
+            .field("allowed_models", &self.allowed_models)
+            .field("timeout_seconds", &self.timeout_seconds)
+            .field("max_retries", &self.max_retries)
+            .finish()
+    }
+}
+
 fn validate_api_key(key: &str) -> Result<(), String> {
     if key.is_empty() {
         return Err("API key cannot be empty".to_string());
@@ -58,8 +112,15 @@ fn validate_api_key(key: &str) -> Result<(), String> {
     Ok(())
 }
 
-fn validate_api_url(url: &Url) -> Result<(), String> {
-    if url.scheme() != "https" {
+pub(crate) fn validate_api_url(url: &Url) -> Result<(), String> {
+    // Plain HTTP is only allowed to reach a local proxy or mock server
+    let loopback = match url.host() {
+        Some(url::Host::Domain(domain)) => domain == "localhost",
+        Some(url::Host::Ipv4(ip)) => ip.is_loopback(),
+        Some(url::Host::Ipv6(ip)) => ip.is_loopback(),
+        None => false,
+    };
+    if url.scheme() != "https" && !(url.scheme() == "http" && loopback) {
         return Err("API URL must use HTTPS".to_string());
     }
     if url.host_str().is_none() {
@@ -72,15 +133,157 @@ fn validate_model_name(model: &str) -> Result<(), String> {
     if model.is_empty() {
         return Err("Model name cannot be empty".to_string());
     }
-    if model.len() > 50 {
+    if model.len() > 100 {
         return Err("Model name too long".to_string());
     }
-    if !model.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '.') {
+    // OpenRouter ids look like `anthropic/claude-3.5-sonnet:beta`
+    if !model.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '.' | '_' | '/' | ':')) {
         return Err("Model name contains invalid characters".to_string());
     }
     Ok(())
 }
 
+impl OpenRouterConfig {
+    /// Start from the defaults; [`build`](OpenRouterConfigBuilder::build)
+    /// fails until an API key is set
+    pub fn builder() -> OpenRouterConfigBuilder {
+        OpenRouterConfigBuilder::default()
+    }
+
+    /// Everything [`OpenRouterProvider::new`] and the API will reject
+    pub fn validate(&self) -> Result<(), OpenRouterError> {
+        let invalid = OpenRouterError::InvalidRequest;
+        validate_api_key(&self.api_key).map_err(invalid)?;
+        validate_api_url(&self.api_base_url).map_err(invalid)?;
+        if self.allowed_models.is_empty() {
+            return Err(invalid("At least one allowed model must be specified".to_string()));
+        }
+        for model in &self.allowed_models {
+            validate_model_name(model).map_err(|e| OpenRouterError::InvalidModel(format!("{}: {}", model, e)))?;
+        }
+        if !self.allowed_models.contains(&self.default_model) {
+            return Err(OpenRouterError::InvalidModel(format!(
+                "Default model {} is not in allowed_models",
+                self.default_model
+            )));
+        }
+        if self.timeout_seconds == 0 || self.timeout_seconds > 300 {
+            return Err(invalid("Timeout must be between 1 and 300 seconds".to_string()));
+        }
+        if self.max_retries > 10 {
+            return Err(invalid("max_retries too high (max 10)".to_string()));
+        }
+        Ok(())
+    }
+}
+
+/// Builds an [`OpenRouterConfig`] that is known to be valid
+///
+/// ```
+/// # use jamey_providers::OpenRouterConfig;
+/// let config = OpenRouterConfig::builder()
+///     .api_key("sk-or-v1-abc123")
+///     .model("anthropic/claude-3.5-sonnet")
+///     .build()
+///     .unwrap();
+/// assert_eq!(config.allowed_models, ["anthropic/claude-3.5-sonnet"]);
+/// ```
+#[derive(Clone, Default)]
+pub struct OpenRouterConfigBuilder {
+    api_key: Option<String>,
+    api_base_url: Option<String>,
+    model: Option<String>,
+    allowed_models: Option<Vec<String>>,
+    timeout_seconds: Option<u64>,
+    max_retries: Option<u32>,
+}
+
+impl std::fmt::Debug for OpenRouterConfigBuilder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OpenRouterConfigBuilder")
+            .field("api_key", &Masked::opt(self.api_key.as_deref()))
+            .field("api_base_url", &self.api_base_url)
+            .field("model", &self.model)
+            .field("allowed_models", &self.allowed_models)
+            .field("timeout_seconds", &self.timeout_seconds)
+            .field("max_retries", &self.max_retries)
+            .finish()
+    }
+}
+
+impl OpenRouterConfigBuilder {
+    pub fn api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.api_key = Some(api_key.into());
+        self
+    }
+
+    /// Parsed by [`build`](Self::build); HTTPS unless the host is loopback
+    pub fn api_base_url(mut self, url: impl Into<String>) -> Self {
+        self.api_base_url = Some(url.into());
+        self
+    }
+
+    /// The default model. Without [`allowed_models`](Self::allowed_models)
+    /// it is the only model allowed; otherwise it must be one of them.
+    pub fn model(mut self, model: impl Into<String>) -> Self {
+        self.model = Some(model.into());
+        self
+    }
+
+    pub fn allowed_models<I, M>(mut self, models: I) -> Self
+    where
+        I: IntoIterator<Item = M>,
+        M: Into<String>,
+    {
+        self.allowed_models = Some(models.into_iter().map(Into::into).collect());
+        self
+    }
+
+    pub fn timeout(mut self, timeout: std::time::Duration) -> Self {
+        self.timeout_seconds = Some(timeout.as_secs());
+        self
+    }
+
+    pub fn max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = Some(max_retries);
+        self
+    }
+
+    pub fn build(self) -> Result<OpenRouterConfig, OpenRouterError> {
+        let defaults = OpenRouterConfig::default();
+        let api_key = self
+            .api_key
+            .ok_or_else(|| OpenRouterError::InvalidRequest("API key is required".to_string()))?;
+        let api_base_url = match self.api_base_url {
+            Some(url) => Url::parse(&url)
+                .map_err(|e| OpenRouterError::InvalidRequest(format!("Invalid API URL {}: {}", url, e)))?,
+            None => defaults.api_base_url,
+        };
+        let (default_model, allowed_models) = match (self.model, self.allowed_models) {
+            (Some(model), Some(allowed)) => (model, allowed),
+            (Some(model), None) => (model.clone(), vec![model]),
+            (None, Some(allowed)) => {
+                let model = allowed.first().cloned().unwrap_or_default();
+                (model, allowed)
+            }
+            (None, None) => (defaults.default_model, defaults.allowed_models),
+        };
+        let config = OpenRouterConfig {
+            api_key,
+            api_base_url,
+            default_model,
+            allowed_models,
+            timeout_seconds: self.timeout_seconds.unwrap_or(defaults.timeout_seconds),
+            max_retries: self.max_retries.unwrap_or(defaults.max_retries),
+        };
+        config.validate()?;
+        Ok(config)
+    }
+}
+
+/// The defaults leave `api_key` empty, so a config built with
+/// `..Default::default()` is not usable until one is set; prefer
+/// [`OpenRouterConfig::builder`], which checks.
 impl Default for OpenRouterConfig {
     fn default() -> Self {
         Self {
@@ -204,6 +407,174 @@ pub struct TokenUsage {
     pub total_tokens: u32,
 }
 
+impl From<TokenUsage> for jamey_protocol::TokenUsage {
+    fn from(usage: TokenUsage) -> Self {
+        Self {
+            prompt_tokens: usage.prompt_tokens,
+            completion_tokens: usage.completion_tokens,
+            total_tokens: usage.total_tokens,
+        }
+    }
+}
+
+/// Incremental output from a streaming chat completion
+#[derive(Debug, Clone)]
+pub enum StreamEvent {
+    /// Assistant text as it is generated
+    Content(String),
+    /// Fragment of a tool call; `id` and `name` only arrive on the first
+    /// fragment for a given `index`, `arguments` must be concatenated
+    ToolCallDelta {
+        index: usize,
+        id: Option<String>,
+        name: Option<String>,
+        arguments: String,
+    },
+    /// Usage for the whole completion; OpenRouter also reports the charged cost in USD
+    Usage { usage: TokenUsage, cost: Option<f64> },
+    /// Why generation stopped ("stop", "tool_calls", "length", ...)
+    Finish(String),
+}
+
+#[derive(Debug, Deserialize)]
+struct StreamChunk {
+    #[serde(default)]
+    choices: Vec<StreamChoice>,
+    usage: Option<StreamUsage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct StreamChoice {
+    delta: Option<StreamDelta>,
+    finish_reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct StreamDelta {
+    content: Option<String>,
+    tool_calls: Option<Vec<StreamToolCall>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct StreamToolCall {
+    #[serde(default)]
+    index: usize,
+    id: Option<String>,
+    function: Option<StreamFunction>,
+}
+
+#[derive(Debug, Deserialize)]
+struct StreamFunction {
+    name: Option<String>,
+    arguments: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct StreamUsage {
+    prompt_tokens: u32,
+    completion_tokens: u32,
+    total_tokens: u32,
+    cost: Option<f64>,
+}
+
+/// Convert one SSE `data:` payload into stream events
+fn parse_stream_data(data: &str) -> Result<Vec<StreamEvent>, OpenRouterError> {
+    let chunk: StreamChunk = serde_json::from_str(data)
+        .map_err(|e| OpenRouterError::Api(format!("Invalid stream chunk: {}", e)))?;
+
+    let mut events = Vec::new();
+    for choice in chunk.choices {
+        if let Some(delta) = choice.delta {
+            if let Some(content) = delta.content.filter(|c| !c.is_empty()) {
+                events.push(StreamEvent::Content(content));
+            }
+            for call in delta.tool_calls.unwrap_or_default() {
+                let (name, arguments) = match call.function {
+                    Some(function) => (function.name, function.arguments.unwrap_or_default()),
+                    None => (None, String::new()),
+                };
+                events.push(StreamEvent::ToolCallDelta {
+                    index: call.index,
+                    id: call.id,
+                    name,
+                    arguments,
+                });
+            }
+        }
+        if let Some(reason) = choice.finish_reason {
+            events.push(StreamEvent::Finish(reason));
+        }
+    }
+    if let Some(usage) = chunk.usage {
+        events.push(StreamEvent::Usage {
+            usage: TokenUsage {
+                prompt_tokens: usage.prompt_tokens,
+                completion_tokens: usage.completion_tokens,
+                total_tokens: usage.total_tokens,
+            },
+            cost: usage.cost,
+        });
+    }
+    Ok(events)
+}
+
+/// Server-sent event stream of a chat completion
+pub struct ChatStream {
+    response: reqwest::Response,
+    buffer: Vec<u8>,
+    pending: std::collections::VecDeque<StreamEvent>,
+    done: bool,
+}
+
+impl ChatStream {
+    fn new(response: reqwest::Response) -> Self {
+        Self {
+            response,
+            buffer: Vec::new(),
+            pending: std::collections::VecDeque::new(),
+            done: false,
+        }
+    }
+
+    /// Next event, or `None` once the server sends `[DONE]` or closes the stream
+    pub async fn next_event(&mut self) -> Option<Result<StreamEvent, OpenRouterError>> {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Some(Ok(event));
+            }
+            if self.done {
+                return None;
+            }
+
+            if let Some(pos) = self.buffer.iter().position(|&b| b == b'\n') {
+                let line: Vec<u8> = self.buffer.drain(..=pos).collect();
+                let line = String::from_utf8_lossy(&line);
+                // Blank separators and ": keep-alive" comments carry no data
+                if let Some(data) = line.trim().strip_prefix("data:") {
+                    let data = data.trim();
+                    if data == "[DONE]" {
+                        self.done = true;
+                        continue;
+                    }
+                    match parse_stream_data(data) {
+                        Ok(events) => self.pending.extend(events),
+                        Err(e) => return Some(Err(e)),
+                    }
+                }
+                continue;
+            }
+
+            match self.response.chunk().await {
+                Ok(Some(bytes)) => self.buffer.extend_from_slice(&bytes),
+                Ok(None) if self.buffer.is_empty() => self.done = true,
+                // Flush a final line that was not newline-terminated
+                Ok(None) => self.buffer.push(b'\n'),
+                Err(e) => return Some(Err(OpenRouterError::Api(e.to_string()))),
+            }
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EmbeddingResponse {
     pub object: String,
@@ -225,11 +596,18 @@ pub struct EmbeddingUsage {
     pub total_tokens: u32,
 }
 
+/// Embedding model used unless a caller picks another
+pub const DEFAULT_EMBEDDING_MODEL: &str = "openai/text-embedding-ada-002";
+
 pub struct OpenRouterProvider {
     config: OpenRouterConfig,
+    // Kept outside `config` so a rotated key can be swapped in without restart
+    api_key: std::sync::RwLock<String>,
     client: reqwest::Client,
     tokenizer: CoreBPE,
     request_semaphore: tokio::sync::Semaphore,
+    quota: std::sync::Mutex<ProviderQuota>,
+    embedding_cache: Option<std::sync::Arc<CacheManager>>,
 }
 
 impl OpenRouterProvider {
@@ -263,13 +641,104 @@ impl OpenRouterProvider {
         let tokenizer = tiktoken_rs::cl100k_base()?;
 
         Ok(Self {
+            api_key: std::sync::RwLock::new(config.api_key.clone()),
             config,
             client,
             tokenizer,
             request_semaphore: tokio::sync::Semaphore::new(MAX_CONCURRENT_REQUESTS),
+            quota: std::sync::Mutex::new(ProviderQuota::default()),
+            embedding_cache: None,
         })
     }
 
+    /// Look embeddings up in `cache` before calling the API, and keep new
+    /// ones there
+    pub fn with_embedding_cache(mut self, cache: std::sync::Arc<CacheManager>) -> Self {
+        self.embedding_cache = Some(cache);
+        self
+    }
+
+    /// Replace the API key used for subsequent requests (e.g. after rotation)
+    pub fn set_api_key(&self, api_key: String) -> Result<(), OpenRouterError> {
+        validate_api_key(&api_key).map_err(OpenRouterError::InvalidRequest)?;
+        *self.api_key.write().unwrap_or_else(|e| e.into_inner()) = api_key;
+        tracing::info!("OpenRouter API key updated");
+        Ok(())
+    }
+
+    fn auth_header(&self) -> String {
+        format!("Bearer {}", self.api_key.read().unwrap_or_else(|e| e.into_inner()))
+    }
+
+    /// Rate limits and credits as of the last response or
+    /// [`refresh_quota`](Self::refresh_quota)
+    pub fn quota(&self) -> ProviderQuota {
+        self.quota.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Fetch the key's credit usage from `GET /key`
+    pub async fn refresh_quota(&self) -> Result<ProviderQuota> {
+        #[derive(Deserialize)]
+        struct KeyResponse {
+            data: KeyInfo,
+        }
+
+        let url = self.config.api_base_url.join("key")?;
+        let response = self.client
+            .get(url)
+            .header("Authorization", self.auth_header())
+            .send()
+            .await
+            .map_err(|e| OpenRouterError::Api(e.to_string()))?;
+        if !response.status().is_success() {
+            let error_text = response.text().await?;
+            return Err(OpenRouterError::Api(error_text).into());
+        }
+        let key: KeyResponse = response.json().await?;
+        let mut quota = self.quota.lock().unwrap_or_else(|e| e.into_inner());
+        quota.update_from_key(&key.data, SystemTime::now());
+        Ok(quota.clone())
+    }
+
+    fn observe_headers(&self, headers: &HeaderMap) {
+        self.quota
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .update_from_headers(headers, SystemTime::now());
+    }
+
+    fn observe_rate_limited(&self, headers: &HeaderMap) -> Option<Duration> {
+        let retry_after = headers
+            .get("retry-after")
+            .and_then(|h| h.to_str().ok())
+            .and_then(|s| s.parse::<u64>().ok())
+            .map(Duration::from_secs);
+        self.quota
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .record_rate_limited(retry_after, SystemTime::now());
+        retry_after
+    }
+
+    /// Hold a request back until the rate-limit window has room for it, so
+    /// it isn't spent on a 429. Waits longer than the request timeout fail
+    /// straight away.
+    async fn throttle(&self) -> Result<(), OpenRouterError> {
+        loop {
+            let wait = self.quota.lock().unwrap_or_else(|e| e.into_inner()).reserve(SystemTime::now());
+            match wait {
+                None => return Ok(()),
+                Some(wait) if wait > Duration::from_secs(self.config.timeout_seconds) => {
+                    return Err(OpenRouterError::RateLimit);
+                }
+                Some(wait) => {
+                    tracing::debug!("Rate limit window used up; waiting {:?}", wait);
+                    tokio::time::sleep(wait).await;
+                }
+            }
+        }
+    }
+
     fn validate_chat_request(&self, request: &mut ChatRequest) -> Result<(), OpenRouterError> {
         // Validate and set default model
         if request.model.is_empty() {
@@ -353,11 +822,12 @@ impl OpenRouterProvider {
         };
 
         let url = self.config.api_base_url.join("chat/completions")?;
-        let auth_header = format!("Bearer {}", self.config.api_key);
+        let auth_header = self.auth_header();
 
         tracing::debug!("Making chat completion request to OpenRouter API");
         
         let result = backoff::future::retry(backoff, || async {
+            self.throttle().await.map_err(backoff::Error::permanent)?;
             let request_future = self.client
                 .post(url.clone())
                 .header("Authorization", &auth_header)
@@ -370,8 +840,9 @@ impl OpenRouterProvider {
                 request_future
             )
             .await
-            .map_err(|_| backoff::Error::permanent(OpenRouterError::Api("Request timeout".to_string())))?
-            .map_err(|e| backoff::Error::transient(OpenRouterError::Api(e.to_string())))?;
+            .map_err(|_| backoff::Error::permanent(OpenRouterError::Unreachable("Request timeout".to_string())))?
+            .map_err(|e| backoff::Error::transient(send_error(e)))?;
+            self.observe_headers(response.headers());
 
             match response.status() {
                 reqwest::StatusCode::OK => {
@@ -382,16 +853,15 @@ impl OpenRouterProvider {
                         .map_err(|e| backoff::Error::permanent(OpenRouterError::Api(e.to_string())))
                 }
                 reqwest::StatusCode::TOO_MANY_REQUESTS => {
-                    // Get retry-after header if available
-                    let retry_after = response.headers()
-                        .get("retry-after")
-                        .and_then(|h| h.to_str().ok())
-                        .and_then(|s| s.parse::<u64>().ok())
-                        .unwrap_or(5);
-
-                    tokio::time::sleep(std::time::Duration::from_secs(retry_after)).await;
+                    // Without a retry-after the next attempt waits on the backoff
+                    if self.observe_rate_limited(response.headers()).is_none() {
+                        tokio::time::sleep(Duration::from_secs(5)).await;
+                    }
                     Err(backoff::Error::transient(OpenRouterError::RateLimit))
                 }
+                status if is_gateway_error(status) => {
+                    Err(backoff::Error::transient(OpenRouterError::Unreachable(status.to_string())))
+                }
                 _ => {
                     let error_text = response.text().await
                         .unwrap_or_else(|e| format!("Failed to read error response: {}", e));
@@ -405,46 +875,89 @@ impl OpenRouterProvider {
     }
 }
 
-#[async_trait]
-pub trait LlmProvider {
-    async fn chat(&self, request: ChatRequest) -> Result<ChatResponse>;
-    async fn get_embedding(&self, text: &str) -> Result<Vec<f32>>;
-}
+impl OpenRouterProvider {
+    /// Start a streaming chat completion. Tools are sent in the
+    /// OpenAI function-calling format so the model can stream tool calls.
+    pub async fn chat_stream(&self, mut request: ChatRequest) -> Result<ChatStream> {
+        // Streams legitimately outlive the client-wide request timeout
+        const STREAM_TIMEOUT_SECS: u64 = 600;
 
-#[async_trait]
-impl LlmProvider for OpenRouterProvider {
-    async fn chat(&self, mut request: ChatRequest) -> Result<ChatResponse> {
-        // Validate and normalize request
         self.validate_chat_request(&mut request)?;
 
-        // Count tokens and validate against model limits
-        let total_tokens: usize = request
-            .messages
-            .iter()
-            .map(|m| self.count_tokens(&m.content))
-            .sum();
+        let mut body = serde_json::to_value(&request)?;
+        body["stream"] = serde_json::Value::Bool(true);
+        body["usage"] = serde_json::json!({ "include": true });
+        if let Some(ref tools) = request.tools {
+            body["tools"] = tools
+                .iter()
+                .map(|tool| {
+                    serde_json::json!({
+                        "type": "function",
+                        "function": {
+                            "name": tool.name,
+                            "description": tool.description,
+                            "parameters": tool.parameters,
+                        }
+                    })
+                })
+                .collect();
+        }
 
-        // Example token limits - in production these would be configured per model
-        let token_limit = match request.model.as_str() {
-            "claude-3-sonnet" => 200_000,
-            "gpt-4" => 8_192,
-            "gpt-3.5-turbo" => 4_096,
-            _ => 4_096,
+        let url = self.config.api_base_url.join("chat/completions")?;
+        self.throttle().await?;
+        let response = {
+            let _permit = self.request_semaphore.acquire().await?;
+            tracing::debug!("Starting streaming chat completion request");
+            tokio::time::timeout(
+                std::time::Duration::from_secs(self.config.timeout_seconds),
+                self.client
+                    .post(url)
+                    .header("Authorization", self.auth_header())
+                    .timeout(std::time::Duration::from_secs(STREAM_TIMEOUT_SECS))
+                    .json(&body)
+                    .send(),
+            )
+            .await
+            .map_err(|_| OpenRouterError::Api("Request timeout".to_string()))?
+            .map_err(|e| OpenRouterError::Api(e.to_string()))?
         };
+        self.observe_headers(response.headers());
 
-        if total_tokens > token_limit {
-            return Err(OpenRouterError::TokenLimit {
-                model: request.model,
-                count: total_tokens,
-                limit: token_limit,
+        match response.status() {
+            reqwest::StatusCode::OK => Ok(ChatStream::new(response)),
+            reqwest::StatusCode::TOO_MANY_REQUESTS => {
+                self.observe_rate_limited(response.headers());
+                Err(OpenRouterError::RateLimit.into())
+            }
+            _ => {
+                let error_text = response.text().await
+                    .unwrap_or_else(|e| format!("Failed to read error response: {}", e));
+                Err(OpenRouterError::Api(error_text).into())
             }
-            .into());
         }
+    }
 
-        self.make_request(request).await
+    /// Embed `text` with a specific embedding model, e.g. when migrating
+    /// stored memories to a new one
+    pub async fn get_embedding_with_model(&self, text: &str, model: &str) -> Result<Vec<f32>> {
+        if let Some(cache) = &self.embedding_cache {
+            match cache.get_cached_embedding(model, text).await {
+                Ok(Some(embedding)) => return Ok(embedding),
+                Ok(None) => {}
+                Err(e) => tracing::warn!("Embedding cache lookup failed: {}", e),
+            }
+        }
+        let embedding = self.request_embedding(text, model).await?;
+        if let Some(cache) = &self.embedding_cache {
+            if let Err(e) = cache.cache_embedding(model, text, &embedding).await {
+                tracing::warn!("Failed to cache embedding: {}", e);
+            }
+        }
+        Ok(embedding)
     }
 
-    async fn get_embedding(&self, text: &str) -> Result<Vec<f32>> {
+    async fn request_embedding(&self, text: &str, model: &str) -> Result<Vec<f32>> {
+        self.throttle().await?;
         // Acquire semaphore permit
         let _permit = self.request_semaphore.acquire().await?;
         // Validate input
@@ -456,7 +969,7 @@ impl LlmProvider for OpenRouterProvider {
         let token_count = self.count_tokens(text);
         if token_count > 8192 {
             return Err(OpenRouterError::TokenLimit {
-                model: "text-embedding-ada-002".to_string(),
+                model: model.to_string(),
                 count: token_count,
                 limit: 8192,
             }.into());
@@ -468,11 +981,11 @@ impl LlmProvider for OpenRouterProvider {
         tracing::debug!("Generating embedding for text");
         
         let embedding_request = serde_json::json!({
-            "model": "openai/text-embedding-ada-002",
+            "model": model,
             "input": text
         });
 
-        let auth_header = format!("Bearer {}", self.config.api_key);
+        let auth_header = self.auth_header();
         let request_future = self.client
             .post(url)
             .header("Authorization", auth_header)
@@ -485,8 +998,9 @@ impl LlmProvider for OpenRouterProvider {
             request_future
         )
         .await
-        .map_err(|_| OpenRouterError::Api("Request timeout".to_string()))?
-        .map_err(|e| OpenRouterError::Api(e.to_string()))?;
+        .map_err(|_| OpenRouterError::Unreachable("Request timeout".to_string()))?
+        .map_err(send_error)?;
+        self.observe_headers(response.headers());
 
         match response.status() {
             reqwest::StatusCode::OK => {
@@ -499,8 +1013,10 @@ impl LlmProvider for OpenRouterProvider {
                 }
             }
             reqwest::StatusCode::TOO_MANY_REQUESTS => {
+                self.observe_rate_limited(response.headers());
                 Err(OpenRouterError::RateLimit.into())
             }
+            status if is_gateway_error(status) => Err(OpenRouterError::Unreachable(status.to_string()).into()),
             _ => {
                 let error_text = response.text().await?;
                 Err(OpenRouterError::Api(error_text).into())
@@ -509,24 +1025,107 @@ impl LlmProvider for OpenRouterProvider {
     }
 }
 
+#[async_trait]
+pub trait LlmProvider {
+    async fn chat(&self, request: ChatRequest) -> Result<ChatResponse>;
+    async fn get_embedding(&self, text: &str) -> Result<Vec<f32>>;
+}
+
+#[async_trait]
+impl LlmProvider for OpenRouterProvider {
+    async fn chat(&self, mut request: ChatRequest) -> Result<ChatResponse> {
+        // Validate and normalize request
+        self.validate_chat_request(&mut request)?;
+
+        // Count tokens and validate against model limits
+        let total_tokens: usize = request
+            .messages
+            .iter()
+            .map(|m| self.count_tokens(&m.content))
+            .sum();
+
+        // Example token limits - in production these would be configured per model
+        let token_limit = match request.model.as_str() {
+            "claude-3-sonnet" => 200_000,
+            "gpt-4" => 8_192,
+            "gpt-3.5-turbo" => 4_096,
+            _ => 4_096,
+        };
+
+        if total_tokens > token_limit {
+            return Err(OpenRouterError::TokenLimit {
+                model: request.model,
+                count: total_tokens,
+                limit: token_limit,
+            }
+            .into());
+        }
+
+        self.make_request(request).await
+    }
+
+    async fn get_embedding(&self, text: &str) -> Result<Vec<f32>> {
+        self.get_embedding_with_model(text, DEFAULT_EMBEDDING_MODEL).await
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use wiremock::{matchers::*, Mock, MockServer, ResponseTemplate};
 
+    #[test]
+    fn test_parse_stream_data() {
+        let events = parse_stream_data(r#"{"choices":[{"delta":{"content":"Hel"},"finish_reason":null}]}"#).unwrap();
+        assert!(matches!(&events[..], [StreamEvent::Content(text)] if text == "Hel"));
+
+        let events = parse_stream_data(
+            r#"{"choices":[{"delta":{"content":null,"tool_calls":[{"index":0,"id":"call_1","function":{"name":"network_web","arguments":"{\"act"}}]}}]}"#,
+        ).unwrap();
+        assert!(matches!(
+            &events[..],
+            [StreamEvent::ToolCallDelta { index: 0, id: Some(id), name: Some(name), arguments }]
+                if id == "call_1" && name == "network_web" && arguments == "{\"act"
+        ));
+
+        let events = parse_stream_data(
+            r#"{"choices":[{"delta":{},"finish_reason":"stop"}],"usage":{"prompt_tokens":10,"completion_tokens":5,"total_tokens":15,"cost":0.0004}}"#,
+        ).unwrap();
+        assert!(matches!(&events[0], StreamEvent::Finish(reason) if reason == "stop"));
+        assert!(matches!(
+            &events[1],
+            StreamEvent::Usage { usage, cost: Some(cost) } if usage.total_tokens == 15 && *cost == 0.0004
+        ));
+
+        assert!(parse_stream_data("not json").is_err());
+    }
+
+    #[test]
+    fn test_debug_masks_key() {
+        let config = OpenRouterConfig::builder()
+            .api_key("sk-or-v1-secret")
+            .model("anthropic/claude-3.5-sonnet")
+            .build()
+            .unwrap();
+        let debug = format!("{:?}", config);
+        assert!(!debug.contains("sk-or-v1-secret"));
+        assert!(debug.contains("api_key: ***REDACTED***"));
+    }
+
     #[tokio::test]
+    #[ignore = "needs network access to badssl.com"]
     async fn test_tls_configuration() -> Result<(), Box<dyn std::error::Error>> {
         // Test with invalid certificate
         let config = OpenRouterConfig {
             api_key: "test_key".to_string(),
-            api_base_url: Url::parse("https://invalid-cert-test.badssl.com/")?,
+            api_base_url: Url::parse("https://expired.badssl.com/")?,
             ..Default::default()
         };
 
-        // Should fail due to invalid certificate
-        let result = OpenRouterProvider::new(config);
-        assert!(result.is_err());
-        assert!(format!("{}", result.unwrap_err()).contains("certificate"));
+        // The handshake, not the client setup, rejects the certificate
+        let provider = OpenRouterProvider::new(config)?;
+        let result = provider.get_embedding("Test text").await;
+        assert!(matches!(result, Err(e) if e.to_string().contains("certificate")));
 
         Ok(())
     }
@@ -556,9 +1155,8 @@ mod tests {
             max_tokens: None,
         };
         assert!(matches!(
-            provider.chat(empty_message.clone()).await,
-            Err(anyhow::Error) if format!("{}", provider.chat(empty_message).await.unwrap_err())
-                .contains("Empty message content")
+            provider.chat(empty_message).await,
+            Err(e) if e.to_string().contains("Empty message content")
         ));
 
         let invalid_role = ChatRequest {
@@ -573,16 +1171,14 @@ mod tests {
             max_tokens: None,
         };
         assert!(matches!(
-            provider.chat(invalid_role.clone()).await,
-            Err(anyhow::Error) if format!("{}", provider.chat(invalid_role).await.unwrap_err())
-                .contains("Invalid role")
+            provider.chat(invalid_role).await,
+            Err(e) if e.to_string().contains("Invalid role")
         ));
 
         // Test empty embedding text
         assert!(matches!(
             provider.get_embedding("").await,
-            Err(anyhow::Error) if format!("{}", provider.get_embedding("").await.unwrap_err())
-                .contains("Empty content")
+            Err(e) if e.to_string().contains("Empty message content")
         ));
 
         // Mock successful response
@@ -623,4 +1219,98 @@ mod tests {
         assert_eq!(response.choices[0].message.content, "Test response");
         Ok(())
     }
-}
\ No newline at end of file
+    #[tokio::test]
+    async fn test_throttles_on_spent_window() -> Result<(), Box<dyn std::error::Error>> {
+        let mock_server = MockServer::start().await;
+        let provider = OpenRouterProvider::new(OpenRouterConfig {
+            api_key: "test_key".to_string(),
+            api_base_url: Url::parse(&mock_server.uri())?,
+            ..Default::default()
+        })?;
+
+        let reset = SystemTime::now() + Duration::from_secs(120);
+        let reset_ms = reset.duration_since(std::time::UNIX_EPOCH)?.as_millis().to_string();
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("x-ratelimit-limit", "10")
+                    .insert_header("x-ratelimit-remaining", "0")
+                    .insert_header("x-ratelimit-reset", reset_ms.as_str())
+                    .set_body_json(serde_json::json!({
+                        "id": "test_response",
+                        "model": "claude-3-sonnet",
+                        "choices": [{
+                            "message": {"role": "assistant", "content": "Test response"},
+                            "finish_reason": "stop"
+                        }],
+                        "usage": {"prompt_tokens": 10, "completion_tokens": 5, "total_tokens": 15}
+                    })),
+            )
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/key"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "data": {"label": "test", "usage": 2.5, "limit": 10.0, "is_free_tier": false}
+            })))
+            .mount(&mock_server)
+            .await;
+
+        let request = ChatRequest {
+            model: "claude-3-sonnet".to_string(),
+            messages: vec![Message {
+                role: "user".to_string(),
+                content: "Test message".to_string(),
+            }],
+            tools: None,
+            tool_choice: None,
+            temperature: None,
+            max_tokens: None,
+        };
+        provider.chat(request.clone()).await?;
+        let quota = provider.quota();
+        assert_eq!((quota.request_limit, quota.requests_remaining), (Some(10), Some(0)));
+
+        // The window is spent for longer than the request timeout, so the
+        // second call fails without reaching the server
+        let error = provider.chat(request).await.unwrap_err();
+        assert!(matches!(error.downcast_ref::<OpenRouterError>(), Some(OpenRouterError::RateLimit)));
+
+        let quota = provider.refresh_quota().await?;
+        assert_eq!(quota.credits_remaining, Some(7.5));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_unreachable_provider() -> Result<(), Box<dyn std::error::Error>> {
+        let mock_server = MockServer::start().await;
+        let provider = OpenRouterProvider::new(OpenRouterConfig {
+            api_key: "test_key".to_string(),
+            api_base_url: Url::parse(&mock_server.uri())?,
+            ..Default::default()
+        })?;
+        Mock::given(method("POST"))
+            .and(path("/embeddings"))
+            .respond_with(ResponseTemplate::new(503))
+            .mount(&mock_server)
+            .await;
+        assert!(is_unreachable(&provider.get_embedding("Test").await.unwrap_err()));
+
+        // A refusal is not an outage
+        let mock_server = MockServer::start().await;
+        let provider = OpenRouterProvider::new(OpenRouterConfig {
+            api_key: "test_key".to_string(),
+            api_base_url: Url::parse(&mock_server.uri())?,
+            ..Default::default()
+        })?;
+        Mock::given(method("POST"))
+            .and(path("/embeddings"))
+            .respond_with(ResponseTemplate::new(400).set_body_string("bad input"))
+            .mount(&mock_server)
+            .await;
+        assert!(!is_unreachable(&provider.get_embedding("Test").await.unwrap_err()));
+        Ok(())
+    }
+}
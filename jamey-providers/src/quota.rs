@@ -0,0 +1,190 @@
+//! Rate limit and credit tracking
+//!
+//! OpenRouter reports the request window on every response
+//! (`x-ratelimit-limit`, `x-ratelimit-remaining`, `x-ratelimit-reset`) and
+//! the key's credit usage at `GET /key`. [`ProviderQuota`] keeps the latest
+//! of both so the provider can hold requests back until the window resets
+//! rather than spend them on 429s, and so `jamey status` can show what is
+//! left.
+
+use reqwest::header::HeaderMap;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const LIMIT_HEADER: &str = "x-ratelimit-limit";
+const REMAINING_HEADER: &str = "x-ratelimit-remaining";
+const RESET_HEADER: &str = "x-ratelimit-reset";
+
+/// What the provider last said about the key's limits
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ProviderQuota {
+    /// Requests allowed per window
+    pub request_limit: Option<u64>,
+    /// Requests left in the current window, less those sent since
+    pub requests_remaining: Option<u64>,
+    /// When the current window ends
+    pub resets_at: Option<SystemTime>,
+    /// Spending cap on the key in USD; `None` when it has none
+    pub credit_limit: Option<f64>,
+    /// Spent on the key so far, in USD
+    pub credits_used: Option<f64>,
+    /// Left before the cap, in USD
+    pub credits_remaining: Option<f64>,
+    /// When the credit figures were fetched
+    pub credits_checked_at: Option<SystemTime>,
+}
+
+/// `data` of OpenRouter's `GET /key`
+#[derive(Debug, Clone, Deserialize)]
+pub struct KeyInfo {
+    #[serde(default)]
+    pub label: Option<String>,
+    pub usage: f64,
+    pub limit: Option<f64>,
+    #[serde(default)]
+    pub limit_remaining: Option<f64>,
+    #[serde(default)]
+    pub is_free_tier: bool,
+}
+
+impl ProviderQuota {
+    /// Take in the rate-limit headers of a response; absent headers leave
+    /// what was known before
+    pub fn update_from_headers(&mut self, headers: &HeaderMap, now: SystemTime) {
+        let number = |name: &str| {
+            headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.trim().parse::<f64>().ok())
+                .filter(|value| *value >= 0.0)
+        };
+        if let Some(limit) = number(LIMIT_HEADER) {
+            self.request_limit = Some(limit as u64);
+        }
+        if let Some(remaining) = number(REMAINING_HEADER) {
+            self.requests_remaining = Some(remaining as u64);
+        }
+        if let Some(reset) = number(RESET_HEADER) {
+            self.resets_at = Some(reset_time(reset, now));
+        }
+    }
+
+    /// A 429 means the window is spent, whatever the headers said
+    pub fn record_rate_limited(&mut self, retry_after: Option<Duration>, now: SystemTime) {
+        self.requests_remaining = Some(0);
+        if let Some(wait) = retry_after {
+            self.resets_at = Some(now + wait);
+        }
+    }
+
+    pub fn update_from_key(&mut self, key: &KeyInfo, now: SystemTime) {
+        self.credit_limit = key.limit;
+        self.credits_used = Some(key.usage);
+        self.credits_remaining = key
+            .limit_remaining
+            .or_else(|| key.limit.map(|limit| (limit - key.usage).max(0.0)));
+        self.credits_checked_at = Some(now);
+    }
+
+    /// Whether the credit figures are missing or older than `max_age`
+    pub fn credits_stale(&self, max_age: Duration, now: SystemTime) -> bool {
+        self.credits_checked_at
+            .and_then(|checked| now.duration_since(checked).ok())
+            .is_none_or(|age| age >= max_age)
+    }
+
+    /// Claim a request from the window. Returns how long to wait first
+    /// when the window is used up, in which case nothing is claimed.
+    pub fn reserve(&mut self, now: SystemTime) -> Option<Duration> {
+        if self.resets_at.is_some_and(|reset| reset <= now) {
+            // A fresh window; the next response will say how full it is
+            self.requests_remaining = self.request_limit;
+            self.resets_at = None;
+        }
+        match self.requests_remaining {
+            Some(0) => self.resets_at.and_then(|reset| reset.duration_since(now).ok()),
+            Some(remaining) => {
+                self.requests_remaining = Some(remaining - 1);
+                None
+            }
+            None => None,
+        }
+    }
+}
+
+/// `x-ratelimit-reset` is an epoch timestamp in milliseconds on OpenRouter;
+/// seconds since the epoch and seconds from now are accepted too
+fn reset_time(value: f64, now: SystemTime) -> SystemTime {
+    const EPOCH_MILLIS: f64 = 1e12;
+    const EPOCH_SECS: f64 = 1e9;
+    if value >= EPOCH_MILLIS {
+        UNIX_EPOCH + Duration::from_millis(value as u64)
+    } else if value >= EPOCH_SECS {
+        UNIX_EPOCH + Duration::from_secs_f64(value)
+    } else {
+        now + Duration::from_secs_f64(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    fn headers(remaining: &str, reset: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(LIMIT_HEADER, HeaderValue::from_static("20"));
+        headers.insert(REMAINING_HEADER, HeaderValue::from_str(remaining).unwrap());
+        headers.insert(RESET_HEADER, HeaderValue::from_str(reset).unwrap());
+        headers
+    }
+
+    #[test]
+    fn test_headers_and_reserve() {
+        let now = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let reset_ms = (1_700_000_000u64 + 10) * 1000;
+        let mut quota = ProviderQuota::default();
+        quota.update_from_headers(&headers("2", &reset_ms.to_string()), now);
+        assert_eq!(quota.request_limit, Some(20));
+        assert_eq!(quota.resets_at, Some(now + Duration::from_secs(10)));
+
+        assert_eq!(quota.reserve(now), None);
+        assert_eq!(quota.reserve(now), None);
+        assert_eq!(quota.reserve(now), Some(Duration::from_secs(10)));
+        assert_eq!(quota.requests_remaining, Some(0));
+
+        // Once the window resets requests flow again
+        let later = now + Duration::from_secs(11);
+        assert_eq!(quota.reserve(later), None);
+        assert_eq!(quota.requests_remaining, Some(19));
+    }
+
+    #[test]
+    fn test_rate_limited_and_unknown() {
+        let now = SystemTime::now();
+        let mut quota = ProviderQuota::default();
+        assert_eq!(quota.reserve(now), None);
+
+        quota.record_rate_limited(Some(Duration::from_secs(5)), now);
+        assert_eq!(quota.reserve(now), Some(Duration::from_secs(5)));
+
+        quota.update_from_headers(&headers("3", "30"), now);
+        assert_eq!(quota.resets_at, Some(now + Duration::from_secs(30)));
+        assert_eq!(quota.reserve(now), None);
+    }
+
+    #[test]
+    fn test_key_credits() {
+        let now = SystemTime::now();
+        let key: KeyInfo = serde_json::from_str(r#"{"label": "sk-or-...", "usage": 3.5, "limit": 10.0}"#).unwrap();
+        let mut quota = ProviderQuota::default();
+        assert!(quota.credits_stale(Duration::from_secs(60), now));
+        quota.update_from_key(&key, now);
+        assert_eq!(quota.credits_remaining, Some(6.5));
+        assert!(!quota.credits_stale(Duration::from_secs(60), now));
+
+        let unlimited: KeyInfo = serde_json::from_str(r#"{"usage": 1.0, "limit": null}"#).unwrap();
+        quota.update_from_key(&unlimited, now);
+        assert_eq!((quota.credit_limit, quota.credits_remaining), (None, None));
+    }
+}
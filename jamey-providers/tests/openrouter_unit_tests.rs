@@ -1,8 +1,8 @@
 //! Comprehensive unit tests for OpenRouter provider
 //! Tests API errors, rate limiting, validation, and edge cases
 
-use jamey_providers::{
-    ChatRequest, ChatResponse, LlmProvider, Message, OpenRouterConfig, 
+use jamey_providers::openrouter::{
+    ChatRequest, ChatResponse, LlmProvider, Message, OpenRouterConfig,
     OpenRouterError, OpenRouterProvider, Tool
 };
 use serde_json::json;
@@ -48,6 +48,59 @@ fn test_provider_creation_empty_allowed_models() {
     assert!(result.is_err());
 }
 
+#[test]
+fn test_config_builder() {
+    let config = OpenRouterConfig::builder()
+        .api_key("sk-or-v1-abc123")
+        .model("anthropic/claude-3.5-sonnet")
+        .max_retries(5)
+        .build()
+        .unwrap();
+    assert_eq!(config.default_model, "anthropic/claude-3.5-sonnet");
+    assert_eq!(config.allowed_models, vec!["anthropic/claude-3.5-sonnet".to_string()]);
+    assert_eq!(config.max_retries, 5);
+    assert_eq!(config.api_base_url.as_str(), "https://openrouter.ai/api/v1");
+
+    // Allowed models alone pick the first as the default
+    let config = OpenRouterConfig::builder()
+        .api_key("test_key")
+        .allowed_models(["gpt-4", "gpt-3.5-turbo"])
+        .api_base_url("http://127.0.0.1:8080")
+        .build()
+        .unwrap();
+    assert_eq!(config.default_model, "gpt-4");
+}
+
+#[test]
+fn test_config_builder_rejects_invalid() {
+    assert!(OpenRouterConfig::builder().build().is_err());
+    assert!(OpenRouterConfig::builder().api_key("").build().is_err());
+    assert!(OpenRouterConfig::builder().api_key("key with spaces").build().is_err());
+    assert!(OpenRouterConfig::builder()
+        .api_key("test_key")
+        .api_base_url("http://openrouter.ai/api/v1")
+        .build()
+        .is_err());
+    assert!(matches!(
+        OpenRouterConfig::builder()
+            .api_key("test_key")
+            .model("gpt-4")
+            .allowed_models(["gpt-3.5-turbo"])
+            .build(),
+        Err(OpenRouterError::InvalidModel(_))
+    ));
+    assert!(OpenRouterConfig::builder()
+        .api_key("test_key")
+        .allowed_models(Vec::<String>::new())
+        .build()
+        .is_err());
+    assert!(OpenRouterConfig::builder()
+        .api_key("test_key")
+        .timeout(std::time::Duration::ZERO)
+        .build()
+        .is_err());
+}
+
 #[tokio::test]
 async fn test_provider_creation_valid() {
     let config = OpenRouterConfig {
@@ -0,0 +1,64 @@
+//! Building the project context sent ahead of `jamey ask --context project`
+//!
+//!     cargo bench -p jamey-runtime --bench context_packing
+
+use chrono::Utc;
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use jamey_core::memory::{Memory, MemoryType};
+use jamey_runtime::project::{ChangeKind, FileChange, ProjectState};
+use std::path::PathBuf;
+use uuid::Uuid;
+
+fn project(changes: usize) -> ProjectState {
+    let mut state = ProjectState::new(PathBuf::from("/work/app"));
+    for i in 0..changes {
+        state.recent_changes.push_back(FileChange {
+            path: format!("src/module_{}.rs", i % 40),
+            kind: if i % 3 == 0 { ChangeKind::Added } else { ChangeKind::Modified },
+            at: Utc::now(),
+            chunks: 2,
+        });
+    }
+    state
+}
+
+/// Search results where every other hit belongs to another project
+fn search_results(count: usize, excerpt_len: usize) -> Vec<Memory> {
+    (0..count)
+        .map(|i| {
+            let namespace = if i % 2 == 0 { "project:app" } else { "project:other" };
+            Memory {
+                id: Uuid::new_v4(),
+                memory_type: MemoryType::Knowledge,
+                content: "fn example() { let value = compute(); }\n".repeat(excerpt_len / 40 + 1),
+                embedding: vec![0.0; 8],
+                metadata: serde_json::json!({
+                    "namespace": namespace,
+                    "source": format!("/work/app/src/module_{}.rs", i),
+                }),
+                created_at: Utc::now(),
+                last_accessed: Utc::now(),
+            }
+        })
+        .collect()
+}
+
+fn bench_context_packing(c: &mut Criterion) {
+    let mut group = c.benchmark_group("context_packing");
+
+    let state = project(100);
+    group.bench_function("summary", |b| {
+        b.iter(|| black_box(state.context_summary(black_box(20))));
+    });
+
+    for excerpt_len in [200, 2000, 8000] {
+        let memories = search_results(24, excerpt_len);
+        group.bench_with_input(BenchmarkId::new("pack", excerpt_len), &memories, |b, memories| {
+            b.iter(|| black_box(state.pack_context(black_box(memories))));
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_context_packing);
+criterion_main!(benches);
@@ -0,0 +1,195 @@
+//! A2A endpoint
+//!
+//! Lets other agents hand Jamey work over the [A2A task
+//! protocol](jamey_protocol::a2a). The web server publishes Jamey's agent
+//! card at `/.well-known/agent-card.json` and takes JSON-RPC calls at
+//! `POST /a2a`: `message/send` runs a chat turn as a task and answers with
+//! it once it's done, `message/stream` answers with a Server-Sent Events
+//! stream of its status and reply as they're written, and `tasks/get` and
+//! `tasks/cancel` follow up by task id. A task's `contextId` is the session
+//! it runs in, so later messages in the same context carry on that
+//! conversation, saved like any other session.
+//!
+//! Tasks are kept in memory; finished ones are forgotten after
+//! [`TASK_RETENTION`].
+
+use crate::config::RuntimeConfig;
+use chrono::Utc;
+use dashmap::DashMap;
+use jamey_protocol::a2a::{
+    self, A2aMessage, AgentCard, AgentCardCapabilities, AgentSkill, Event, Task, TaskState, TaskStatus, TaskStatusUpdate,
+};
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tokio::task::AbortHandle;
+use uuid::Uuid;
+
+/// How long a finished task can still be fetched
+pub const TASK_RETENTION: Duration = Duration::from_secs(60 * 60);
+
+/// Events buffered per task for slow streaming clients
+const EVENT_BUFFER: usize = 256;
+
+/// Jamey's card, advertising the endpoint at `base_url`
+pub fn agent_card(config: &RuntimeConfig, base_url: &str) -> AgentCard {
+    AgentCard {
+        name: config.project_name.clone(),
+        description: "Jamey, a personal assistant with memory and tools".to_string(),
+        url: format!("{}/a2a", base_url.trim_end_matches('/')),
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        protocol_version: a2a::PROTOCOL_VERSION.to_string(),
+        capabilities: AgentCardCapabilities {
+            streaming: true,
+            push_notifications: false,
+        },
+        default_input_modes: vec!["text/plain".to_string()],
+        default_output_modes: vec!["text/plain".to_string()],
+        skills: vec![AgentSkill {
+            id: "chat".to_string(),
+            name: "Chat".to_string(),
+            description: "Answer a message, using Jamey's memory and tools".to_string(),
+            tags: vec!["chat".to_string(), "research".to_string(), "memory".to_string()],
+        }],
+    }
+}
+
+struct TaskEntry {
+    task: Task,
+    events: broadcast::Sender<Event>,
+    /// Stops the turn behind the task
+    abort: Option<AbortHandle>,
+}
+
+/// Tasks started over A2A, running and recently finished
+#[derive(Default)]
+pub struct TaskStore {
+    tasks: DashMap<String, TaskEntry>,
+}
+
+impl TaskStore {
+    /// Start a task for `message` in session `context`, submitted and not
+    /// yet running
+    pub fn create(&self, context: Uuid, mut message: A2aMessage) -> Task {
+        self.forget_finished();
+        let id = Uuid::new_v4().to_string();
+        message.task_id = Some(id.clone());
+        message.context_id = Some(context.to_string());
+        let task = Task {
+            id,
+            context_id: context.to_string(),
+            status: TaskStatus::now(TaskState::Submitted),
+            artifacts: Vec::new(),
+            history: vec![message],
+        };
+        self.tasks.insert(
+            task.id.clone(),
+            TaskEntry {
+                task: task.clone(),
+                events: broadcast::channel(EVENT_BUFFER).0,
+                abort: None,
+            },
+        );
+        task
+    }
+
+    /// Task `id` with at most the last `history_length` messages
+    pub fn get(&self, id: &str, history_length: Option<usize>) -> Option<Task> {
+        let mut task = self.tasks.get(id)?.task.clone();
+        if let Some(keep) = history_length {
+            let skip = task.history.len().saturating_sub(keep);
+            task.history.drain(..skip);
+        }
+        Some(task)
+    }
+
+    /// Events published for task `id` from now on
+    pub fn subscribe(&self, id: &str) -> Option<broadcast::Receiver<Event>> {
+        Some(self.tasks.get(id)?.events.subscribe())
+    }
+
+    /// Let [`cancel`](Self::cancel) stop the turn running task `id`
+    pub fn set_abort(&self, id: &str, abort: AbortHandle) {
+        if let Some(mut entry) = self.tasks.get_mut(id) {
+            entry.abort = Some(abort);
+        }
+    }
+
+    /// Record `event` against its task and pass it on to the task's
+    /// streams; events for a task that is already over are dropped
+    pub fn publish(&self, id: &str, event: Event) {
+        let Some(mut entry) = self.tasks.get_mut(id) else {
+            return;
+        };
+        if entry.task.status.state.is_terminal() {
+            return;
+        }
+        entry.task.apply(event.clone());
+        // Nobody streaming the task isn't an error
+        let _ = entry.events.send(event);
+    }
+
+    /// Stop task `id`, or the A2A error code saying why it can't be
+    pub fn cancel(&self, id: &str) -> Result<Task, i64> {
+        let (abort, context_id) = {
+            let entry = self.tasks.get(id).ok_or(a2a::TASK_NOT_FOUND)?;
+            if entry.task.status.state.is_terminal() {
+                return Err(a2a::TASK_NOT_CANCELABLE);
+            }
+            (entry.abort.clone(), entry.task.context_id.clone())
+        };
+        if let Some(abort) = abort {
+            abort.abort();
+        }
+        self.publish(
+            id,
+            Event::StatusUpdate(TaskStatusUpdate {
+                task_id: id.to_string(),
+                context_id,
+                status: TaskStatus::now(TaskState::Canceled),
+                is_final: true,
+            }),
+        );
+        self.get(id, None).ok_or(a2a::TASK_NOT_FOUND)
+    }
+
+    fn forget_finished(&self) {
+        let now = Utc::now();
+        self.tasks.retain(|_, entry| {
+            let status = &entry.task.status;
+            !status.state.is_terminal()
+                || status
+                    .timestamp
+                    .is_none_or(|at| now.signed_duration_since(at).to_std().unwrap_or_default() < TASK_RETENTION)
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_task_lifecycle() {
+        let store = TaskStore::default();
+        let task = store.create(Uuid::new_v4(), A2aMessage::text(a2a::A2aRole::User, "Hi"));
+        assert_eq!(task.history[0].task_id.as_deref(), Some(task.id.as_str()));
+        let mut events = store.subscribe(&task.id).unwrap();
+
+        assert_eq!(store.cancel(&task.id).unwrap().status.state, TaskState::Canceled);
+        assert!(matches!(events.try_recv(), Ok(Event::StatusUpdate(update)) if update.is_final));
+        assert_eq!(store.cancel(&task.id).unwrap_err(), a2a::TASK_NOT_CANCELABLE);
+        assert_eq!(store.cancel("missing").unwrap_err(), a2a::TASK_NOT_FOUND);
+
+        // Late events from the stopped turn don't reopen the task
+        store.publish(
+            &task.id,
+            Event::StatusUpdate(TaskStatusUpdate {
+                task_id: task.id.clone(),
+                context_id: task.context_id.clone(),
+                status: TaskStatus::now(TaskState::Working),
+                is_final: false,
+            }),
+        );
+        assert_eq!(store.get(&task.id, None).unwrap().status.state, TaskState::Canceled);
+    }
+}
@@ -0,0 +1,368 @@
+//! Conversation analytics
+//!
+//! Turns the usage log into the numbers behind `jamey usage insights` and the
+//! TUI dashboard: how often each tool runs and fails, how long tool runs and
+//! model calls take (p50/p95/p99), and which topics recent sessions were
+//! about. Topics come from clustering an embedding of how each session opened,
+//! so they cost one embedding call per session and are only computed on
+//! request.
+
+use crate::session_store::{SessionRecord, SessionStore, SessionStoreError};
+use crate::usage::{ToolRecord, UsageError, UsageLog, UsageRecord};
+use chrono::{DateTime, Utc};
+use jamey_core::maintenance::Embedder;
+use jamey_core::memory::cosine_similarity;
+use jamey_protocol::Role;
+use serde::Serialize;
+use std::collections::HashMap;
+use thiserror::Error;
+use uuid::Uuid;
+
+/// Similarity a session must have with a topic's centroid to join it
+pub const DEFAULT_TOPIC_THRESHOLD: f32 = 0.75;
+
+/// Most recent sessions embedded for topics; bounds the embedding calls
+pub const DEFAULT_TOPIC_SESSIONS: usize = 200;
+
+/// Characters of the opening user message embedded with the title
+const OPENING_CHARS: usize = 500;
+
+/// Titles kept per topic to show what it covers
+const TOPIC_EXAMPLES: usize = 3;
+
+#[derive(Debug, Error)]
+pub enum AnalyticsError {
+    #[error("Usage log error: {0}")]
+    Usage(#[from] UsageError),
+    #[error("Session store error: {0}")]
+    Sessions(#[from] SessionStoreError),
+    #[error("Embedding failed: {0}")]
+    Embedding(String),
+}
+
+/// Latency distribution, nearest-rank
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Percentiles {
+    pub p50_ms: u64,
+    pub p95_ms: u64,
+    pub p99_ms: u64,
+}
+
+impl Percentiles {
+    /// `None` when there are no samples
+    pub fn of(mut samples: Vec<u64>) -> Option<Self> {
+        if samples.is_empty() {
+            return None;
+        }
+        samples.sort_unstable();
+        let rank = |p: usize| samples[(samples.len() * p).div_ceil(100).max(1) - 1];
+        Some(Self {
+            p50_ms: rank(50),
+            p95_ms: rank(95),
+            p99_ms: rank(99),
+        })
+    }
+}
+
+/// Runs of one connector action
+#[derive(Debug, Clone, Serialize)]
+pub struct ToolStats {
+    /// `connector:action`, or just the connector when no action was given
+    pub tool: String,
+    pub calls: u64,
+    pub failures: u64,
+    pub latency: Percentiles,
+}
+
+impl ToolStats {
+    pub fn failure_rate(&self) -> f64 {
+        if self.calls == 0 {
+            0.0
+        } else {
+            self.failures as f64 / self.calls as f64
+        }
+    }
+}
+
+/// Response times of one model
+#[derive(Debug, Clone, Serialize)]
+pub struct ModelLatency {
+    pub model: String,
+    pub calls: u64,
+    /// Missing when none of the calls recorded a latency
+    pub latency: Option<Percentiles>,
+}
+
+/// Sessions that opened on similar subjects
+#[derive(Debug, Clone, Serialize)]
+pub struct Topic {
+    /// Title of the session closest to the topic's centre
+    pub label: String,
+    pub session_ids: Vec<Uuid>,
+    /// A few member titles, label first
+    pub examples: Vec<String>,
+}
+
+/// Everything `jamey usage insights` reports
+#[derive(Debug, Clone, Serialize)]
+pub struct Insights {
+    pub since: Option<DateTime<Utc>>,
+    pub sessions: usize,
+    pub tool_calls: u64,
+    pub tool_failures: u64,
+    /// Most used first
+    pub tools: Vec<ToolStats>,
+    /// Most called first
+    pub models: Vec<ModelLatency>,
+    /// Largest first; empty unless requested with [`Insights::with_topics`]
+    pub topics: Vec<Topic>,
+}
+
+impl Insights {
+    /// Tool and latency figures from the usage log, at or after `since`
+    pub async fn gather(log: &UsageLog, since: Option<DateTime<Utc>>) -> Result<Self, AnalyticsError> {
+        let usage = log.query(since).await?;
+        let tools = log.query_tools(since).await?;
+        Ok(Self::from_records(since, &usage, &tools))
+    }
+
+    pub fn from_records(since: Option<DateTime<Utc>>, usage: &[UsageRecord], tools: &[ToolRecord]) -> Self {
+        let sessions = usage
+            .iter()
+            .filter_map(|r| r.session_id)
+            .chain(tools.iter().filter_map(|r| r.session_id))
+            .collect::<std::collections::HashSet<_>>()
+            .len();
+        Self {
+            since,
+            sessions,
+            tool_calls: tools.len() as u64,
+            tool_failures: tools.iter().filter(|r| !r.success).count() as u64,
+            tools: tool_stats(tools),
+            models: model_latency(usage),
+            topics: Vec::new(),
+        }
+    }
+
+    /// Add topics for the sessions updated since `self.since`
+    pub async fn with_topics(
+        mut self,
+        store: &SessionStore,
+        embedder: &dyn Embedder,
+        limit: usize,
+    ) -> Result<Self, AnalyticsError> {
+        let mut records = Vec::new();
+        for summary in store.list().await? {
+            if records.len() >= limit || self.since.is_some_and(|since| summary.updated_at < since) {
+                // Listed most recent first, so nothing older follows
+                break;
+            }
+            match store.load(summary.id).await {
+                Ok(record) => records.push(record),
+                Err(e) => tracing::warn!("Skipping session {} for topics: {}", summary.id, e),
+            }
+        }
+        self.topics = topics(&records, embedder, DEFAULT_TOPIC_THRESHOLD).await?;
+        Ok(self)
+    }
+
+    pub fn failure_rate(&self) -> f64 {
+        if self.tool_calls == 0 {
+            0.0
+        } else {
+            self.tool_failures as f64 / self.tool_calls as f64
+        }
+    }
+}
+
+/// Per-tool call counts, failures and latency, most used first
+pub fn tool_stats(records: &[ToolRecord]) -> Vec<ToolStats> {
+    let mut groups: HashMap<String, (u64, Vec<u64>)> = HashMap::new();
+    for record in records {
+        let tool = match &record.action {
+            Some(action) => format!("{}:{}", record.connector, action),
+            None => record.connector.clone(),
+        };
+        let (failures, durations) = groups.entry(tool).or_default();
+        if !record.success {
+            *failures += 1;
+        }
+        durations.push(record.duration_ms);
+    }
+
+    let mut stats: Vec<ToolStats> = groups
+        .into_iter()
+        .map(|(tool, (failures, durations))| ToolStats {
+            tool,
+            calls: durations.len() as u64,
+            failures,
+            latency: Percentiles::of(durations).expect("every group has a run"),
+        })
+        .collect();
+    stats.sort_by(|a, b| b.calls.cmp(&a.calls).then_with(|| a.tool.cmp(&b.tool)));
+    stats
+}
+
+/// Per-model call counts and response times, most called first
+pub fn model_latency(records: &[UsageRecord]) -> Vec<ModelLatency> {
+    let mut groups: HashMap<&str, (u64, Vec<u64>)> = HashMap::new();
+    for record in records {
+        let (calls, latencies) = groups.entry(record.model.as_str()).or_default();
+        *calls += 1;
+        latencies.extend(record.latency_ms);
+    }
+
+    let mut models: Vec<ModelLatency> = groups
+        .into_iter()
+        .map(|(model, (calls, latencies))| ModelLatency {
+            model: model.to_string(),
+            calls,
+            latency: Percentiles::of(latencies),
+        })
+        .collect();
+    models.sort_by(|a, b| b.calls.cmp(&a.calls).then_with(|| a.model.cmp(&b.model)));
+    models
+}
+
+/// Group sessions whose openings embed within `threshold` of each other
+pub async fn topics(
+    sessions: &[SessionRecord],
+    embedder: &dyn Embedder,
+    threshold: f32,
+) -> Result<Vec<Topic>, AnalyticsError> {
+    let mut members = Vec::new();
+    let mut vectors = Vec::new();
+    for session in sessions {
+        let Some(text) = opening(session) else {
+            continue;
+        };
+        let vector = embedder
+            .embed(&text)
+            .await
+            .map_err(|e| AnalyticsError::Embedding(e.to_string()))?;
+        members.push(session);
+        vectors.push(vector);
+    }
+
+    let mut topics: Vec<Topic> = cluster(&vectors, threshold)
+        .into_iter()
+        .map(|cluster| {
+            let titles = cluster.iter().map(|&i| title(members[i]));
+            Topic {
+                label: title(members[cluster[0]]),
+                session_ids: cluster.iter().map(|&i| members[i].id).collect(),
+                examples: titles.take(TOPIC_EXAMPLES).collect(),
+            }
+        })
+        .collect();
+    topics.sort_by_key(|topic| std::cmp::Reverse(topic.session_ids.len()));
+    Ok(topics)
+}
+
+/// What a session is embedded by: its title and opening request
+fn opening(session: &SessionRecord) -> Option<String> {
+    let first = session.messages.iter().find(|m| m.role == Role::User)?;
+    let opening: String = first.content.chars().take(OPENING_CHARS).collect();
+    Some(format!("{}\n{}", session.title, opening))
+}
+
+fn title(session: &SessionRecord) -> String {
+    if session.title.is_empty() {
+        session.id.to_string()
+    } else {
+        session.title.clone()
+    }
+}
+
+/// Greedy single pass: each vector joins the most similar centroid at or
+/// above `threshold`, or starts a cluster. Members are ordered by similarity
+/// to their final centroid, closest first.
+fn cluster(vectors: &[Vec<f32>], threshold: f32) -> Vec<Vec<usize>> {
+    let mut centroids: Vec<Vec<f32>> = Vec::new();
+    let mut clusters: Vec<Vec<usize>> = Vec::new();
+    for (i, vector) in vectors.iter().enumerate() {
+        let best = centroids
+            .iter()
+            .enumerate()
+            .map(|(c, centroid)| (c, cosine_similarity(vector, centroid)))
+            .filter(|&(_, similarity)| similarity >= threshold)
+            .max_by(|a, b| a.1.total_cmp(&b.1));
+        match best {
+            Some((c, _)) => {
+                let n = clusters[c].len() as f32;
+                for (value, x) in centroids[c].iter_mut().zip(vector) {
+                    *value = (*value * n + x) / (n + 1.0);
+                }
+                clusters[c].push(i);
+            }
+            None => {
+                centroids.push(vector.clone());
+                clusters.push(vec![i]);
+            }
+        }
+    }
+
+    for (members, centroid) in clusters.iter_mut().zip(&centroids) {
+        members.sort_by(|&a, &b| {
+            cosine_similarity(&vectors[b], centroid).total_cmp(&cosine_similarity(&vectors[a], centroid))
+        });
+    }
+    clusters
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn run(connector: &str, action: &str, success: bool, ms: u64) -> ToolRecord {
+        ToolRecord::new(connector, Some(action), None, success, Duration::from_millis(ms))
+    }
+
+    #[test]
+    fn test_percentiles() {
+        assert_eq!(Percentiles::of(Vec::new()), None);
+        let p = Percentiles::of((1..=100).rev().collect()).unwrap();
+        assert_eq!((p.p50_ms, p.p95_ms, p.p99_ms), (50, 95, 99));
+        let p = Percentiles::of(vec![7]).unwrap();
+        assert_eq!((p.p50_ms, p.p95_ms, p.p99_ms), (7, 7, 7));
+    }
+
+    #[test]
+    fn test_tool_stats() {
+        let records = vec![
+            run("git", "status", true, 10),
+            run("git", "status", false, 30),
+            run("git", "status", true, 20),
+            run("calculator", "evaluate", true, 5),
+        ];
+        let stats = tool_stats(&records);
+        assert_eq!(stats[0].tool, "git:status");
+        assert_eq!((stats[0].calls, stats[0].failures), (3, 1));
+        assert!((stats[0].failure_rate() - 1.0 / 3.0).abs() < 1e-9);
+        assert_eq!(stats[0].latency.p50_ms, 20);
+        assert_eq!(stats[1].tool, "calculator:evaluate");
+
+        let insights = Insights::from_records(None, &[], &records);
+        assert_eq!((insights.tool_calls, insights.tool_failures), (4, 1));
+        assert_eq!(insights.failure_rate(), 0.25);
+    }
+
+    #[test]
+    fn test_cluster() {
+        let vectors = vec![
+            vec![1.0, 0.0, 0.0],
+            vec![0.0, 1.0, 0.0],
+            vec![0.95, 0.05, 0.0],
+            vec![0.9, 0.0, 0.1],
+            vec![0.0, 0.0, 1.0],
+        ];
+        let clusters = cluster(&vectors, 0.8);
+        assert_eq!(clusters.len(), 3);
+        let mut first = clusters[0].clone();
+        first.sort();
+        assert_eq!(first, vec![0, 2, 3]);
+        assert_eq!(clusters[1], vec![1]);
+        assert_eq!(clusters[2], vec![4]);
+    }
+}
@@ -0,0 +1,418 @@
+//! Approval queue for connector actions
+//!
+//! Connectors flagged `requires_approval` park their calls here until a
+//! person decides. Requests are JSON files under `<approval_dir>/<uuid>.json`,
+//! so a runtime running headless can be supervised from another terminal
+//! with `jamey approvals`. Actions approved with "always allow" are recorded
+//! in `<approval_dir>/always_allow.json` and skip the queue from then on.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use thiserror::Error;
+use uuid::Uuid;
+
+/// How often a waiting call re-reads its request file
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// File in the queue directory listing always-allowed actions
+const ALLOW_LIST_FILE: &str = "always_allow.json";
+
+#[derive(Debug, Error)]
+pub enum ApprovalError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Serialization error: {0}")]
+    Serialization(#[from] serde_json::Error),
+    #[error("Approval request not found: {0}")]
+    NotFound(String),
+    #[error("Approval ID prefix is ambiguous: {0}")]
+    Ambiguous(String),
+    #[error("Approval request {id} was already {status}")]
+    AlreadyDecided { id: Uuid, status: ApprovalStatus },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ApprovalStatus {
+    Pending,
+    Approved,
+    Denied,
+    /// Nobody decided before the caller stopped waiting
+    Expired,
+}
+
+impl std::fmt::Display for ApprovalStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ApprovalStatus::Pending => write!(f, "pending"),
+            ApprovalStatus::Approved => write!(f, "approved"),
+            ApprovalStatus::Denied => write!(f, "denied"),
+            ApprovalStatus::Expired => write!(f, "expired"),
+        }
+    }
+}
+
+/// A connector call waiting for, or having received, a decision
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApprovalRequest {
+    pub id: Uuid,
+    pub connector_id: String,
+    pub action: String,
+    pub params: HashMap<String, String>,
+    /// Chat session the call came from, if any
+    pub session_id: Option<String>,
+    /// The connector's declared safety checks, for the reviewer
+    #[serde(default)]
+    pub safety_checks: Vec<String>,
+    pub status: ApprovalStatus,
+    pub requested_at: DateTime<Utc>,
+    pub decided_at: Option<DateTime<Utc>>,
+    pub decided_by: Option<String>,
+    /// Approver's note or reason for denial
+    pub note: Option<String>,
+}
+
+/// A connector action that no longer needs approval
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AllowRule {
+    pub connector_id: String,
+    pub action: String,
+}
+
+/// Directory-backed queue shared between the runtime and the CLI
+#[derive(Debug, Clone)]
+pub struct ApprovalQueue {
+    dir: PathBuf,
+}
+
+impl ApprovalQueue {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Queue at `JAMEY_APPROVAL_DIR`, or `./approvals` when unset
+    pub fn from_env() -> Self {
+        Self::new(default_approval_dir())
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn path(&self, id: Uuid) -> PathBuf {
+        self.dir.join(format!("{}.json", id))
+    }
+
+    /// Queue a connector call for review
+    pub async fn submit(
+        &self,
+        connector_id: &str,
+        params: HashMap<String, String>,
+        session_id: Option<String>,
+        safety_checks: Vec<String>,
+    ) -> Result<ApprovalRequest, ApprovalError> {
+        let request = ApprovalRequest {
+            id: Uuid::new_v4(),
+            connector_id: connector_id.to_string(),
+            action: params.get("action").cloned().unwrap_or_default(),
+            params,
+            session_id,
+            safety_checks,
+            status: ApprovalStatus::Pending,
+            requested_at: Utc::now(),
+            decided_at: None,
+            decided_by: None,
+            note: None,
+        };
+        self.save(&request).await?;
+        tracing::info!(
+            "Approval {} requested for {} {}",
+            request.id,
+            request.connector_id,
+            request.action
+        );
+        Ok(request)
+    }
+
+    /// Requests, oldest first, optionally limited to one status
+    pub async fn list(
+        &self,
+        status: Option<ApprovalStatus>,
+    ) -> Result<Vec<ApprovalRequest>, ApprovalError> {
+        let mut requests = Vec::new();
+        let mut entries = match tokio::fs::read_dir(&self.dir).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(requests),
+            Err(e) => return Err(e.into()),
+        };
+
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            let is_request = path.extension().and_then(|e| e.to_str()) == Some("json")
+                && path.file_stem().and_then(|s| s.to_str()).is_some_and(|s| Uuid::parse_str(s).is_ok());
+            if !is_request {
+                continue;
+            }
+            match read_request(&path).await {
+                Ok(request) if status.is_none_or(|s| request.status == s) => {
+                    requests.push(request)
+                }
+                Ok(_) => {}
+                Err(e) => tracing::warn!("Skipping unreadable approval {}: {}", path.display(), e),
+            }
+        }
+
+        requests.sort_by_key(|r| r.requested_at);
+        Ok(requests)
+    }
+
+    pub async fn get(&self, id: Uuid) -> Result<ApprovalRequest, ApprovalError> {
+        match read_request(&self.path(id)).await {
+            Err(ApprovalError::Io(e)) if e.kind() == std::io::ErrorKind::NotFound => {
+                Err(ApprovalError::NotFound(id.to_string()))
+            }
+            other => other,
+        }
+    }
+
+    /// Resolve a full ID or a unique prefix of one
+    pub async fn resolve(&self, id_or_prefix: &str) -> Result<Uuid, ApprovalError> {
+        if let Ok(id) = Uuid::parse_str(id_or_prefix) {
+            return Ok(id);
+        }
+
+        let prefix = id_or_prefix.to_lowercase();
+        let matches: Vec<Uuid> = self
+            .list(None)
+            .await?
+            .into_iter()
+            .map(|r| r.id)
+            .filter(|id| !prefix.is_empty() && id.to_string().starts_with(&prefix))
+            .collect();
+
+        match matches.as_slice() {
+            [id] => Ok(*id),
+            [] => Err(ApprovalError::NotFound(id_or_prefix.to_string())),
+            _ => Err(ApprovalError::Ambiguous(id_or_prefix.to_string())),
+        }
+    }
+
+    pub async fn approve(
+        &self,
+        id: Uuid,
+        decided_by: &str,
+        note: Option<String>,
+    ) -> Result<ApprovalRequest, ApprovalError> {
+        self.decide(id, ApprovalStatus::Approved, decided_by, note).await
+    }
+
+    /// Approve the request and let its connector action run unreviewed
+    /// from now on
+    pub async fn approve_always(
+        &self,
+        id: Uuid,
+        decided_by: &str,
+    ) -> Result<ApprovalRequest, ApprovalError> {
+        let request = self
+            .decide(id, ApprovalStatus::Approved, decided_by, Some("always allow".to_string()))
+            .await?;
+        self.allow(&request.connector_id, &request.action).await?;
+        Ok(request)
+    }
+
+    pub async fn deny(
+        &self,
+        id: Uuid,
+        decided_by: &str,
+        reason: Option<String>,
+    ) -> Result<ApprovalRequest, ApprovalError> {
+        self.decide(id, ApprovalStatus::Denied, decided_by, reason).await
+    }
+
+    async fn decide(
+        &self,
+        id: Uuid,
+        status: ApprovalStatus,
+        decided_by: &str,
+        note: Option<String>,
+    ) -> Result<ApprovalRequest, ApprovalError> {
+        let mut request = self.get(id).await?;
+        if request.status != ApprovalStatus::Pending {
+            return Err(ApprovalError::AlreadyDecided {
+                id,
+                status: request.status,
+            });
+        }
+
+        request.status = status;
+        request.decided_at = Some(Utc::now());
+        request.decided_by = Some(decided_by.to_string());
+        request.note = note;
+        self.save(&request).await?;
+        tracing::info!("Approval {} {} by {}", id, status, decided_by);
+        Ok(request)
+    }
+
+    /// Record `connector_id`/`action` as not needing approval
+    pub async fn allow(&self, connector_id: &str, action: &str) -> Result<(), ApprovalError> {
+        let rule = AllowRule {
+            connector_id: connector_id.to_string(),
+            action: action.to_string(),
+        };
+        let mut rules = self.allow_rules().await?;
+        if rules.contains(&rule) {
+            return Ok(());
+        }
+        rules.push(rule);
+
+        tokio::fs::create_dir_all(&self.dir).await?;
+        let path = self.dir.join(ALLOW_LIST_FILE);
+        let tmp = path.with_extension("json.tmp");
+        tokio::fs::write(&tmp, serde_json::to_vec_pretty(&rules)?).await?;
+        tokio::fs::rename(&tmp, &path).await?;
+        tracing::info!("Always allowing {} {}", connector_id, action);
+        Ok(())
+    }
+
+    pub async fn allow_rules(&self) -> Result<Vec<AllowRule>, ApprovalError> {
+        match tokio::fs::read(self.dir.join(ALLOW_LIST_FILE)).await {
+            Ok(bytes) => Ok(serde_json::from_slice(&bytes)?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Whether a call can skip the queue; unreadable rules allow nothing
+    pub async fn is_always_allowed(&self, connector_id: &str, action: &str) -> bool {
+        match self.allow_rules().await {
+            Ok(rules) => rules.iter().any(|r| r.connector_id == connector_id && r.action == action),
+            Err(e) => {
+                tracing::warn!("Ignoring unreadable approval allow list: {}", e);
+                false
+            }
+        }
+    }
+
+    /// Block until the request is decided, expiring it after `timeout`
+    pub async fn wait_for_decision(
+        &self,
+        id: Uuid,
+        timeout: Duration,
+    ) -> Result<ApprovalRequest, ApprovalError> {
+        let deadline = tokio::time::Instant::now() + timeout;
+        loop {
+            let request = self.get(id).await?;
+            if request.status != ApprovalStatus::Pending {
+                return Ok(request);
+            }
+            if tokio::time::Instant::now() >= deadline {
+                return match self.decide(id, ApprovalStatus::Expired, "timeout", None).await {
+                    // Decided between the read and the expiry; honour it
+                    Err(ApprovalError::AlreadyDecided { .. }) => self.get(id).await,
+                    other => other,
+                };
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    }
+
+    /// Write a request atomically (temp file + rename)
+    async fn save(&self, request: &ApprovalRequest) -> Result<(), ApprovalError> {
+        tokio::fs::create_dir_all(&self.dir).await?;
+        let path = self.path(request.id);
+        let tmp = path.with_extension("json.tmp");
+        tokio::fs::write(&tmp, serde_json::to_vec_pretty(request)?).await?;
+        tokio::fs::rename(&tmp, &path).await?;
+        Ok(())
+    }
+}
+
+pub(crate) fn default_approval_dir() -> PathBuf {
+    std::env::var("JAMEY_APPROVAL_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from("./approvals"))
+}
+
+async fn read_request(path: &Path) -> Result<ApprovalRequest, ApprovalError> {
+    let bytes = tokio::fs::read(path).await?;
+    Ok(serde_json::from_slice(&bytes)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn params(action: &str) -> HashMap<String, String> {
+        HashMap::from([("action".to_string(), action.to_string())])
+    }
+
+    #[tokio::test]
+    async fn test_submit_and_decide() {
+        let dir = TempDir::new().unwrap();
+        let queue = ApprovalQueue::new(dir.path());
+
+        let request = queue.submit("system_admin", params("kill_process"), None, Vec::new()).await.unwrap();
+        assert_eq!(request.action, "kill_process");
+        assert_eq!(queue.list(Some(ApprovalStatus::Pending)).await.unwrap().len(), 1);
+
+        let prefix = &request.id.to_string()[..8];
+        let id = queue.resolve(prefix).await.unwrap();
+        let approved = queue.approve(id, "alice", Some("ok".to_string())).await.unwrap();
+        assert_eq!(approved.status, ApprovalStatus::Approved);
+        assert!(queue.list(Some(ApprovalStatus::Pending)).await.unwrap().is_empty());
+
+        assert!(matches!(
+            queue.deny(id, "bob", None).await,
+            Err(ApprovalError::AlreadyDecided { status: ApprovalStatus::Approved, .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_decision() {
+        let dir = TempDir::new().unwrap();
+        let queue = ApprovalQueue::new(dir.path());
+
+        let request = queue.submit("iot", params("http_command"), None, Vec::new()).await.unwrap();
+        let expired = queue
+            .wait_for_decision(request.id, Duration::from_millis(10))
+            .await
+            .unwrap();
+        assert_eq!(expired.status, ApprovalStatus::Expired);
+
+        let request = queue.submit("iot", params("http_command"), None, Vec::new()).await.unwrap();
+        let approver = queue.clone();
+        let id = request.id;
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            approver.deny(id, "alice", Some("not now".to_string())).await.unwrap();
+        });
+        let denied = queue.wait_for_decision(id, Duration::from_secs(5)).await.unwrap();
+        assert_eq!(denied.status, ApprovalStatus::Denied);
+        assert_eq!(denied.note.as_deref(), Some("not now"));
+    }
+
+    #[tokio::test]
+    async fn test_approve_always() {
+        let dir = TempDir::new().unwrap();
+        let queue = ApprovalQueue::new(dir.path());
+        let checks = vec!["Process must exist".to_string()];
+
+        let request = queue
+            .submit("system_admin", params("kill_process"), None, checks.clone())
+            .await
+            .unwrap();
+        assert_eq!(queue.get(request.id).await.unwrap().safety_checks, checks);
+        assert!(!queue.is_always_allowed("system_admin", "kill_process").await);
+
+        queue.approve_always(request.id, "alice").await.unwrap();
+        assert!(queue.is_always_allowed("system_admin", "kill_process").await);
+        assert!(!queue.is_always_allowed("system_admin", "reboot").await);
+
+        // The allow list isn't mistaken for a request
+        assert_eq!(queue.list(None).await.unwrap().len(), 1);
+    }
+}
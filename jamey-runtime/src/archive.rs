@@ -0,0 +1,188 @@
+//! Archiving idle sessions
+//!
+//! A session idle past the cleanup threshold is ended, but what was said in
+//! it is kept: its saved transcript is summarized into a Conversation memory
+//! that later turns can recall, and the transcript is marked archived along
+//! with who the session belonged to. Reviving the session registers it
+//! again under the same ID and user so the conversation can carry on; if it
+//! goes idle again, the new summary replaces the old one.
+//!
+//! Sessions without a saved transcript have nothing to archive and are
+//! simply ended, as before.
+
+use crate::events;
+use crate::routing::RouteTask;
+use crate::session_store::{ArchiveInfo, SessionRecord, SessionStoreError};
+use crate::state::{RuntimeState, Session};
+use crate::summarize;
+use chrono::Utc;
+use jamey_core::memory::{Memory, MemoryStore, MemoryType};
+use jamey_providers::openrouter::{LlmProvider, DEFAULT_EMBEDDING_MODEL};
+use thiserror::Error;
+use uuid::Uuid;
+
+/// Fewest messages worth summarizing
+const MIN_MESSAGES: usize = 2;
+
+/// Estimated tokens of transcript given to the summarizer; a longer
+/// conversation is summarized from its latest messages
+const SUMMARY_INPUT_TOKENS: usize = 24_000;
+
+#[derive(Debug, Error)]
+pub enum ArchiveError {
+    #[error("Session store error: {0}")]
+    Sessions(#[from] SessionStoreError),
+    #[error("Could not summarize the session: {0}")]
+    Summary(String),
+    #[error("Memory store error: {0}")]
+    Memory(String),
+}
+
+impl RuntimeState {
+    /// End the sessions idle for `timeout` and archive those with a saved
+    /// transcript; returns how many were archived. A session that fails to
+    /// archive is still ended.
+    pub async fn archive_idle_sessions(&self, timeout: std::time::Duration) -> usize {
+        let mut archived = 0;
+        for session in self.session_manager.cleanup_expired_sessions(timeout) {
+            match self.archive_session(&session).await {
+                Ok(true) => archived += 1,
+                Ok(false) => tracing::debug!("Session {} ended with no transcript to archive", session.id),
+                Err(e) => tracing::warn!("Could not archive session {}: {}", session.id, e),
+            }
+        }
+        archived
+    }
+
+    /// Summarize `session`'s transcript into a memory and mark it archived;
+    /// false when it has no saved transcript
+    pub async fn archive_session(&self, session: &Session) -> Result<bool, ArchiveError> {
+        let mut record = match self.session_store.load(session.id).await {
+            Ok(record) => record,
+            Err(SessionStoreError::NotFound(_)) => return Ok(false),
+            Err(e) => return Err(e.into()),
+        };
+        let previous = record.archive.take();
+        let user = session.user_id.clone().or_else(|| previous.as_ref().and_then(|a| a.user.clone()));
+        let mut archive = ArchiveInfo {
+            archived_at: Utc::now(),
+            summary_memory: previous.as_ref().and_then(|a| a.summary_memory),
+            summarized_messages: previous.as_ref().map_or(0, |a| a.summarized_messages),
+            user,
+            revived_at: None,
+        };
+
+        // A revived session that said nothing new keeps its summary
+        if record.messages.len() >= MIN_MESSAGES && record.messages.len() != archive.summarized_messages {
+            let memory = self.remember_session(&record, archive.user.as_deref()).await?;
+            if let Some(stale) = archive.summary_memory.replace(memory) {
+                self.memory_store
+                    .delete_many(&[stale])
+                    .await
+                    .map_err(|e| ArchiveError::Memory(e.to_string()))?;
+            }
+            archive.summarized_messages = record.messages.len();
+        }
+
+        let summary_memory = archive.summary_memory;
+        record.archive = Some(archive);
+        self.session_store.save(&record).await?;
+        self.events.publish(
+            events::SESSION_ARCHIVED,
+            Some(session.id),
+            serde_json::json!({
+                "title": record.display_title(),
+                "messages": record.messages.len(),
+                "summary_memory": summary_memory,
+            }),
+        );
+        Ok(true)
+    }
+
+    /// Register a saved session again so it can carry on; an archived one
+    /// gets back the user it was attributed to
+    pub async fn revive_session(&self, id: Uuid) -> Result<SessionRecord, ArchiveError> {
+        let mut record = self.session_store.load(id).await?;
+        self.session_manager.resume_session(id);
+        if !record.is_archived() {
+            return Ok(record);
+        }
+        if let Some(archive) = record.archive.as_mut() {
+            archive.revived_at = Some(Utc::now());
+            if let Some(user) = &archive.user {
+                self.session_manager.set_user(id, user);
+            }
+        }
+        self.session_store.save(&record).await?;
+        tracing::info!("Revived archived session {}", id);
+        Ok(record)
+    }
+
+    /// Store a summary of `record` as a Conversation memory
+    async fn remember_session(&self, record: &SessionRecord, user: Option<&str>) -> Result<Uuid, ArchiveError> {
+        let messages = latest_within(&record.messages, SUMMARY_INPUT_TOKENS);
+        let model = self.model_for(RouteTask::Summarize, &summarize::transcript(messages));
+        let summary = summarize::summarize(&self.llm_provider, &model, messages)
+            .await
+            .map_err(|e| ArchiveError::Summary(e.to_string()))?;
+        let content = format!("Conversation \"{}\":\n{}", record.display_title(), summary);
+        let embedding = self
+            .llm_provider
+            .get_embedding(&content)
+            .await
+            .map_err(|e| ArchiveError::Memory(e.to_string()))?;
+        let now = Utc::now();
+        self.memory_store
+            .store(Memory {
+                id: Uuid::new_v4(),
+                memory_type: MemoryType::Conversation,
+                content,
+                embedding,
+                metadata: serde_json::json!({
+                    "source": "session_archive",
+                    "session_id": record.id,
+                    "user": user,
+                    "summarized_messages": record.messages.len(),
+                    "embedding_model": DEFAULT_EMBEDDING_MODEL,
+                }),
+                created_at: now,
+                last_accessed: now,
+            })
+            .await
+            .map_err(|e| ArchiveError::Memory(e.to_string()))
+    }
+}
+
+/// The longest tail of `messages` within `budget` estimated tokens, and at
+/// least the last message
+fn latest_within(messages: &[jamey_protocol::Message], budget: usize) -> &[jamey_protocol::Message] {
+    let mut used = 0;
+    let mut start = messages.len();
+    while start > 0 {
+        used += summarize::history_tokens(&messages[start - 1..start]);
+        if used > budget && start < messages.len() {
+            break;
+        }
+        start -= 1;
+    }
+    &messages[start..]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jamey_protocol::Message;
+
+    #[test]
+    fn test_latest_within() {
+        let messages = vec![
+            Message::user("old ".repeat(400)),
+            Message::assistant("short"),
+            Message::user("latest"),
+        ];
+        assert_eq!(latest_within(&messages, 100_000).len(), 3);
+        assert_eq!(latest_within(&messages, 50).len(), 2);
+        assert_eq!(latest_within(&messages, 0).len(), 1);
+        assert!(latest_within(&[], 50).is_empty());
+    }
+}
@@ -0,0 +1,277 @@
+//! Attachment storage
+//!
+//! Files attached to chat messages are copied into the store with their
+//! extracted text, so a transcript can refer to them by ID and a resumed
+//! session still has them after the original file moves. Before a turn is
+//! sent to the model, attachment parts are expanded into the message text.
+//! Images have no text; the model sees a placeholder, while frontends can
+//! show or export the stored original.
+
+use chrono::Utc;
+use jamey_protocol::{Attachment, ContentPart, Message};
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+use uuid::Uuid;
+
+/// Largest file accepted as an attachment
+pub const MAX_ATTACHMENT_BYTES: u64 = 20 * 1024 * 1024;
+
+/// Extracted text beyond this is cut before it is sent to the model
+const MAX_ATTACHMENT_CHARS: usize = 100_000;
+
+#[derive(Debug, Error)]
+pub enum AttachmentError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Serialization error: {0}")]
+    Serialization(#[from] serde_json::Error),
+    #[error("Attachment not found: {0}")]
+    NotFound(Uuid),
+    #[error("{0} is {1} bytes (limit {MAX_ATTACHMENT_BYTES})")]
+    TooLarge(String, u64),
+    #[error("Cannot read text from {0} ({1})")]
+    Unsupported(String, String),
+}
+
+/// Attachments kept as `<id>.json` metadata next to the `<id>.txt` text
+/// and the `<id>.bin` original
+#[derive(Debug, Clone)]
+pub struct AttachmentStore {
+    dir: PathBuf,
+}
+
+impl AttachmentStore {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Store at `JAMEY_ATTACHMENT_DIR`, or `./attachments` when unset
+    pub fn from_env() -> Self {
+        Self::new(default_attachment_dir())
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn meta_path(&self, id: Uuid) -> PathBuf {
+        self.dir.join(format!("{}.json", id))
+    }
+
+    fn text_path(&self, id: Uuid) -> PathBuf {
+        self.dir.join(format!("{}.txt", id))
+    }
+
+    fn original_path(&self, id: Uuid) -> PathBuf {
+        self.dir.join(format!("{}.bin", id))
+    }
+
+    /// Copy a file into the store, extracting its text up front so that
+    /// unreadable files are rejected before they are referenced
+    pub async fn upload(&self, path: &Path) -> Result<Attachment, AttachmentError> {
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| path.display().to_string());
+        let size_bytes = tokio::fs::metadata(path).await?.len();
+        if size_bytes > MAX_ATTACHMENT_BYTES {
+            return Err(AttachmentError::TooLarge(name, size_bytes));
+        }
+
+        let bytes = tokio::fs::read(path).await?;
+        self.upload_bytes(&name, bytes).await
+    }
+
+    /// Like [`upload`](Self::upload) for contents that aren't in a file,
+    /// such as a download; `name` decides the MIME type
+    pub async fn upload_bytes(&self, name: &str, bytes: Vec<u8>) -> Result<Attachment, AttachmentError> {
+        let name = name.to_string();
+        let size_bytes = bytes.len() as u64;
+        if size_bytes > MAX_ATTACHMENT_BYTES {
+            return Err(AttachmentError::TooLarge(name, size_bytes));
+        }
+
+        let mime_type = mime_guess::from_path(&name)
+            .first_or_octet_stream()
+            .essence_str()
+            .to_string();
+        let text = extract_text(&bytes, &mime_type)
+            .map_err(|reason| AttachmentError::Unsupported(name.clone(), reason))?;
+
+        let attachment = Attachment {
+            id: Uuid::new_v4(),
+            name,
+            mime_type,
+            size_bytes,
+            created_at: Utc::now(),
+        };
+        tokio::fs::create_dir_all(&self.dir).await?;
+        write_atomic(&self.original_path(attachment.id), &bytes).await?;
+        write_atomic(&self.text_path(attachment.id), text.as_bytes()).await?;
+        write_atomic(&self.meta_path(attachment.id), &serde_json::to_vec_pretty(&attachment)?).await?;
+        Ok(attachment)
+    }
+
+    pub async fn load(&self, id: Uuid) -> Result<Attachment, AttachmentError> {
+        match tokio::fs::read(self.meta_path(id)).await {
+            Ok(bytes) => Ok(serde_json::from_slice(&bytes)?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Err(AttachmentError::NotFound(id)),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Text extracted from the attachment when it was uploaded
+    pub async fn text(&self, id: Uuid) -> Result<String, AttachmentError> {
+        match tokio::fs::read_to_string(self.text_path(id)).await {
+            Ok(text) => Ok(text),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Err(AttachmentError::NotFound(id)),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// The file as it was uploaded. Attachments stored before originals were
+    /// kept report `NotFound`.
+    pub async fn original(&self, id: Uuid) -> Result<Vec<u8>, AttachmentError> {
+        match tokio::fs::read(self.original_path(id)).await {
+            Ok(bytes) => Ok(bytes),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Err(AttachmentError::NotFound(id)),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Copy the original into `dir` under its own name, adding " (1)", " (2)"
+    /// and so on rather than overwriting; returns where it was written
+    pub async fn export(&self, id: Uuid, dir: &Path) -> Result<PathBuf, AttachmentError> {
+        let attachment = self.load(id).await?;
+        let bytes = self.original(id).await?;
+        tokio::fs::create_dir_all(dir).await?;
+
+        let name = Path::new(&attachment.name);
+        let stem = name.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_else(|| id.to_string());
+        let extension = name.extension().map(|e| format!(".{}", e.to_string_lossy())).unwrap_or_default();
+        let mut dest = dir.join(format!("{}{}", stem, extension));
+        let mut n = 1;
+        while tokio::fs::try_exists(&dest).await? {
+            dest = dir.join(format!("{} ({}){}", stem, n, extension));
+            n += 1;
+        }
+        write_atomic(&dest, &bytes).await?;
+        Ok(dest)
+    }
+
+    /// Copy of `message` with each attachment's text appended to its content;
+    /// attachments that have gone missing are noted rather than failing the turn
+    pub async fn expand(&self, message: &Message) -> Message {
+        let mut expanded = message.clone();
+        for part in &message.parts {
+            let ContentPart::Attachment { attachment_id, name, mime_type } = part;
+            let body = match self.text(*attachment_id).await {
+                Ok(text) => truncate(&text, MAX_ATTACHMENT_CHARS),
+                Err(e) => {
+                    tracing::warn!("Attachment {} unavailable: {}", attachment_id, e);
+                    format!("[attachment unavailable: {}]", e)
+                }
+            };
+            expanded.content.push_str(&format!(
+                "\n\n<attachment name=\"{}\" type=\"{}\">\n{}\n</attachment>",
+                name, mime_type, body
+            ));
+        }
+        expanded.parts.clear();
+        expanded
+    }
+}
+
+/// Text content of a file, or why there is none
+fn extract_text(bytes: &[u8], mime_type: &str) -> Result<String, String> {
+    if mime_type.starts_with("image/") {
+        return Ok(format!("[{} image, {} bytes; not available as text]", mime_type, bytes.len()));
+    }
+    if mime_type == "application/pdf" {
+        let text = pdf_extract::extract_text_from_mem(bytes).map_err(|e| e.to_string())?;
+        if text.trim().is_empty() {
+            return Err("PDF has no text layer".to_string());
+        }
+        return Ok(text);
+    }
+    if bytes.iter().take(8192).any(|&b| b == 0) {
+        return Err(format!("binary {} file", mime_type));
+    }
+    Ok(String::from_utf8_lossy(bytes).into_owned())
+}
+
+fn truncate(text: &str, max_chars: usize) -> String {
+    match text.char_indices().nth(max_chars) {
+        Some((end, _)) => format!("{}\n[... truncated after {} characters]", &text[..end], max_chars),
+        None => text.to_string(),
+    }
+}
+
+async fn write_atomic(path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    let tmp = path.with_extension("tmp");
+    tokio::fs::write(&tmp, bytes).await?;
+    tokio::fs::rename(&tmp, path).await
+}
+
+pub(crate) fn default_attachment_dir() -> PathBuf {
+    std::env::var("JAMEY_ATTACHMENT_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from("./attachments"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_upload_and_expand() {
+        let dir = TempDir::new().unwrap();
+        let file = dir.path().join("notes.md");
+        std::fs::write(&file, "# Plan\nShip it on Friday").unwrap();
+
+        let store = AttachmentStore::new(dir.path().join("store"));
+        let attachment = store.upload(&file).await.unwrap();
+        assert_eq!(attachment.name, "notes.md");
+        assert_eq!(attachment.mime_type, "text/markdown");
+        assert_eq!(store.load(attachment.id).await.unwrap(), attachment);
+
+        let message = Message::user("When do we ship?").with_attachments(&[attachment]);
+        let expanded = store.expand(&message).await;
+        assert!(expanded.parts.is_empty());
+        assert!(expanded.content.starts_with("When do we ship?"));
+        assert!(expanded.content.contains("<attachment name=\"notes.md\""));
+        assert!(expanded.content.contains("Ship it on Friday"));
+    }
+
+    #[tokio::test]
+    async fn test_images_keep_original_and_export() {
+        let dir = TempDir::new().unwrap();
+        let file = dir.path().join("chart.png");
+        let png = [0x89u8, b'P', b'N', b'G', 0, 0, 0, 13];
+        std::fs::write(&file, png).unwrap();
+
+        let store = AttachmentStore::new(dir.path().join("store"));
+        let attachment = store.upload(&file).await.unwrap();
+        assert_eq!(attachment.mime_type, "image/png");
+        assert_eq!(store.original(attachment.id).await.unwrap(), png);
+        assert!(store.text(attachment.id).await.unwrap().contains("not available as text"));
+
+        let downloads = dir.path().join("downloads");
+        let first = store.export(attachment.id, &downloads).await.unwrap();
+        let second = store.export(attachment.id, &downloads).await.unwrap();
+        assert_eq!(first.file_name().unwrap(), "chart.png");
+        assert_eq!(second.file_name().unwrap(), "chart (1).png");
+        assert_eq!(std::fs::read(second).unwrap(), png);
+    }
+
+    #[tokio::test]
+    async fn test_rejects_binary_files() {
+        let dir = TempDir::new().unwrap();
+        let file = dir.path().join("blob.bin");
+        std::fs::write(&file, [0u8, 159, 146, 150]).unwrap();
+
+        let store = AttachmentStore::new(dir.path().join("store"));
+        assert!(matches!(store.upload(&file).await, Err(AttachmentError::Unsupported(..))));
+    }
+}
@@ -0,0 +1,441 @@
+//! Tamper-evident audit log
+//!
+//! Events logged with `target: "audit"`, such as tool runs, applied
+//! self-modifications, terminal sessions and guardrail hits, are appended to
+//! `<audit_dir>/audit.jsonl` as well as the ordinary log. Each entry records
+//! the hash of the entry before it and is signed with an ed25519 key kept in
+//! the secret manager under [`SIGNING_KEY_SECRET`], so an edited or
+//! reordered line, or one dropped from the middle, breaks the chain.
+//! `jamey audit verify` walks the file and reports the first entry that
+//! doesn't check out.
+//!
+//! Cutting entries off the end leaves a shorter chain that is still valid,
+//! and nothing in the file can show what is missing. Catching that takes a
+//! record of the head kept outside the log: `jamey audit verify` prints the
+//! sequence number and hash of the last entry, and a later head must still
+//! have an entry with that hash at that position.
+//!
+//! The key is generated on first start. Keep a copy of its public half
+//! (`jamey audit verify` prints it) somewhere the runtime can't write, so
+//! a log re-signed with a replaced key is caught too.
+//!
+//! Written entries can also be forwarded to syslog or the Windows Event Log
+//! through [`audit_sinks`](crate::audit_sinks).
+
+use crate::audit_sinks::AuditSink;
+use chrono::{DateTime, Utc};
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use jamey_core::redaction::Redactor;
+use jamey_core::secrets::{SecretError, SecretManager};
+use once_cell::sync::OnceCell;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use thiserror::Error;
+use tracing::field::{Field, Visit};
+use tracing::{Event, Subscriber};
+use tracing_subscriber::layer::Context;
+use tracing_subscriber::Layer;
+
+/// Tracing target captured into the audit log
+pub const TARGET: &str = "audit";
+/// Secret holding the hex-encoded ed25519 seed entries are signed with
+pub const SIGNING_KEY_SECRET: &str = "audit_signing_key";
+const FILE_NAME: &str = "audit.jsonl";
+
+#[derive(Debug, Error)]
+pub enum AuditError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Serialization error: {0}")]
+    Serialization(#[from] serde_json::Error),
+    #[error("Signing key unavailable: {0}")]
+    Secret(#[from] SecretError),
+    #[error("Invalid key: {0}")]
+    Key(String),
+}
+
+/// One line of the audit log
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// Position in the chain, from 0
+    pub seq: u64,
+    pub timestamp: DateTime<Utc>,
+    pub message: String,
+    /// The event's fields, redacted
+    pub fields: BTreeMap<String, String>,
+    /// `hash` of the previous entry; all zeros for the first
+    pub prev_hash: String,
+    /// SHA-256 over the fields above
+    pub hash: String,
+    /// ed25519 signature over `hash`
+    pub signature: String,
+}
+
+impl AuditEntry {
+    fn digest(&self) -> String {
+        let body = serde_json::json!([self.seq, self.timestamp, self.message, self.fields, self.prev_hash]);
+        hex::encode(Sha256::digest(body.to_string().as_bytes()))
+    }
+}
+
+fn genesis_hash() -> String {
+    "0".repeat(64)
+}
+
+/// The signing key from `secrets`, generated and stored on first use
+pub fn signing_key(secrets: &SecretManager) -> Result<SigningKey, AuditError> {
+    match secrets.get_secret(SIGNING_KEY_SECRET) {
+        Ok(seed) => Ok(SigningKey::from_bytes(&decode_key(&seed)?)),
+        Err(SecretError::NotFound(_)) => {
+            let seed: [u8; 32] = rand::random();
+            secrets.store_secret(SIGNING_KEY_SECRET, &hex::encode(seed))?;
+            Ok(SigningKey::from_bytes(&seed))
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// The public half of the key in `secrets`; never creates one
+pub fn verifying_key(secrets: &SecretManager) -> Result<VerifyingKey, AuditError> {
+    let seed = secrets.get_secret(SIGNING_KEY_SECRET)?;
+    Ok(SigningKey::from_bytes(&decode_key(&seed)?).verifying_key())
+}
+
+/// A public key as `jamey audit verify` prints it
+pub fn public_key_hex(key: &VerifyingKey) -> String {
+    hex::encode(key.to_bytes())
+}
+
+/// A public key as printed by [`public_key_hex`]
+pub fn parse_verifying_key(hex_key: &str) -> Result<VerifyingKey, AuditError> {
+    VerifyingKey::from_bytes(&decode_key(hex_key)?).map_err(|e| AuditError::Key(e.to_string()))
+}
+
+fn decode_key(hex_key: &str) -> Result<[u8; 32], AuditError> {
+    hex::decode(hex_key.trim())
+        .map_err(|e| AuditError::Key(e.to_string()))?
+        .try_into()
+        .map_err(|bytes: Vec<u8>| AuditError::Key(format!("expected 32 bytes, got {}", bytes.len())))
+}
+
+/// Append-only, hash-chained, signed log of audit events
+pub struct AuditLog {
+    path: PathBuf,
+    key: SigningKey,
+    redactor: Option<Arc<Redactor>>,
+    /// Where entries are copied once they are in the file
+    sinks: Vec<Arc<dyn AuditSink>>,
+    /// Sequence number and hash of the last entry written
+    head: Mutex<Option<(u64, String)>>,
+}
+
+impl AuditLog {
+    /// Open the log in `dir`, continuing the chain already there
+    pub fn open(dir: impl Into<PathBuf>, key: SigningKey) -> Result<Self, AuditError> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir)?;
+        let path = log_path(&dir);
+        let head = match last_entry(&path)? {
+            Some(entry) => Some((entry.seq, entry.hash)),
+            None => None,
+        };
+        Ok(Self {
+            path,
+            key,
+            redactor: None,
+            sinks: Vec::new(),
+            head: Mutex::new(head),
+        })
+    }
+
+    /// Scrub secrets from messages and fields before they are hashed
+    pub fn with_redactor(mut self, redactor: Arc<Redactor>) -> Self {
+        self.redactor = Some(redactor);
+        self
+    }
+
+    /// Forward every entry written from now on to `sinks`
+    pub fn with_sinks(mut self, sinks: Vec<Arc<dyn AuditSink>>) -> Self {
+        self.sinks.extend(sinks);
+        self
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn verifying_key(&self) -> VerifyingKey {
+        self.key.verifying_key()
+    }
+
+    /// Sign and append one entry
+    pub fn append(&self, message: &str, fields: BTreeMap<String, String>) -> Result<AuditEntry, AuditError> {
+        let redact = |text: &str| match &self.redactor {
+            Some(redactor) => redactor.redact(text).into_owned(),
+            None => text.to_string(),
+        };
+        let mut head = self.head.lock();
+        let (seq, prev_hash) = match &*head {
+            Some((seq, hash)) => (seq + 1, hash.clone()),
+            None => (0, genesis_hash()),
+        };
+        let mut entry = AuditEntry {
+            seq,
+            timestamp: Utc::now(),
+            message: redact(message),
+            fields: fields.into_iter().map(|(name, value)| (name, redact(&value))).collect(),
+            prev_hash,
+            hash: String::new(),
+            signature: String::new(),
+        };
+        entry.hash = entry.digest();
+        entry.signature = hex::encode(self.key.sign(entry.hash.as_bytes()).to_bytes());
+
+        let mut line = serde_json::to_string(&entry)?;
+        line.push('\n');
+        let mut file = std::fs::OpenOptions::new().create(true).append(true).open(&self.path)?;
+        file.write_all(line.as_bytes())?;
+        file.sync_data()?;
+        *head = Some((entry.seq, entry.hash.clone()));
+        drop(head);
+        for sink in &self.sinks {
+            sink.send(&entry);
+        }
+        Ok(entry)
+    }
+}
+
+/// The log file inside `dir`
+pub fn log_path(dir: &Path) -> PathBuf {
+    dir.join(FILE_NAME)
+}
+
+fn last_entry(path: &Path) -> Result<Option<AuditEntry>, AuditError> {
+    let file = match std::fs::File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    let mut last = None;
+    for line in std::io::BufReader::new(file).lines() {
+        let line = line?;
+        if !line.trim().is_empty() {
+            last = Some(line);
+        }
+    }
+    Ok(last.map(|line| serde_json::from_str(&line)).transpose()?)
+}
+
+/// Where a chain stopped checking out
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ChainBreak {
+    /// 1-based line in the file
+    pub line: usize,
+    pub seq: Option<u64>,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct VerifyReport {
+    /// Entries that verified before the first break
+    pub verified: u64,
+    /// Hash of the last entry that verified, to be recorded outside the log
+    pub head: Option<String>,
+    pub broken: Option<ChainBreak>,
+}
+
+impl VerifyReport {
+    pub fn is_valid(&self) -> bool {
+        self.broken.is_none()
+    }
+}
+
+/// Check every entry's sequence number, link to its predecessor, hash and
+/// signature, stopping at the first that fails
+pub fn verify(path: &Path, key: &VerifyingKey) -> Result<VerifyReport, AuditError> {
+    let file = std::fs::File::open(path)?;
+    let mut report = VerifyReport { verified: 0, head: None, broken: None };
+    let mut prev_hash = genesis_hash();
+    for (index, line) in std::io::BufReader::new(file).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let broken = |seq, reason: &str| ChainBreak {
+            line: index + 1,
+            seq,
+            reason: reason.to_string(),
+        };
+        let entry: AuditEntry = match serde_json::from_str(&line) {
+            Ok(entry) => entry,
+            Err(e) => {
+                report.broken = Some(broken(None, &format!("unreadable entry: {}", e)));
+                break;
+            }
+        };
+        let seq = Some(entry.seq);
+        let problem = if entry.seq != report.verified {
+            Some(format!("expected entry {}", report.verified))
+        } else if entry.prev_hash != prev_hash {
+            Some("does not follow the previous entry".to_string())
+        } else if entry.digest() != entry.hash {
+            Some("contents do not match its hash".to_string())
+        } else if !signature_matches(&entry, key) {
+            Some("signature does not verify".to_string())
+        } else {
+            None
+        };
+        if let Some(reason) = problem {
+            report.broken = Some(broken(seq, &reason));
+            break;
+        }
+        prev_hash = entry.hash;
+        report.verified += 1;
+    }
+    if report.verified > 0 {
+        report.head = Some(prev_hash);
+    }
+    Ok(report)
+}
+
+fn signature_matches(entry: &AuditEntry, key: &VerifyingKey) -> bool {
+    hex::decode(&entry.signature)
+        .ok()
+        .and_then(|bytes| Signature::from_slice(&bytes).ok())
+        .is_some_and(|signature| key.verify(entry.hash.as_bytes(), &signature).is_ok())
+}
+
+static SINK: OnceCell<Arc<AuditLog>> = OnceCell::new();
+
+/// Send audit events to `log` from now on. Only the first log installed in
+/// a process is used; returns whether this one was.
+pub fn install(log: Arc<AuditLog>) -> bool {
+    SINK.set(log).is_ok()
+}
+
+/// Tracing layer writing [`TARGET`] events to the installed [`AuditLog`].
+/// Give it no filter, so audit events are kept whatever the log level.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AuditLayer;
+
+impl<S: Subscriber> Layer<S> for AuditLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        if event.metadata().target() != TARGET {
+            return;
+        }
+        let Some(log) = SINK.get() else {
+            return;
+        };
+        let mut fields = FieldMap::default();
+        event.record(&mut fields);
+        let message = fields.0.remove("message").unwrap_or_default();
+        if let Err(e) = log.append(&message, fields.0) {
+            // Logging from inside the subscriber would come straight back here
+            eprintln!("Failed to write audit entry to {}: {}", log.path().display(), e);
+        }
+    }
+}
+
+#[derive(Default)]
+struct FieldMap(BTreeMap<String, String>);
+
+impl Visit for FieldMap {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_string(), value.to_string());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        self.0.insert(field.name().to_string(), format!("{:?}", value));
+    }
+}
+
+pub(crate) fn default_audit_dir() -> PathBuf {
+    std::env::var("JAMEY_AUDIT_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from("./audit"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn key() -> SigningKey {
+        SigningKey::from_bytes(&[7u8; 32])
+    }
+
+    fn write_entries(dir: &Path, count: usize) -> PathBuf {
+        let log = AuditLog::open(dir, key()).unwrap();
+        for i in 0..count {
+            let fields = BTreeMap::from([("tool".to_string(), format!("tool-{}", i))]);
+            log.append("Tool executed", fields).unwrap();
+        }
+        log.path().to_path_buf()
+    }
+
+    #[test]
+    fn test_chain_verifies_and_resumes() {
+        let temp = TempDir::new().unwrap();
+        write_entries(temp.path(), 3);
+        // Reopening continues the chain rather than starting a new one
+        let path = write_entries(temp.path(), 2);
+
+        let report = verify(&path, &key().verifying_key()).unwrap();
+        assert!(report.is_valid());
+        assert_eq!(report.verified, 5);
+    }
+
+    #[test]
+    fn test_tampering_is_detected() {
+        let temp = TempDir::new().unwrap();
+        let path = write_entries(temp.path(), 3);
+        let lines: Vec<String> = std::fs::read_to_string(&path).unwrap().lines().map(String::from).collect();
+
+        let edited = lines.join("\n").replacen("tool-1", "tool-9", 1);
+        std::fs::write(&path, edited).unwrap();
+        let report = verify(&path, &key().verifying_key()).unwrap();
+        assert_eq!(report.verified, 1);
+        assert_eq!(report.broken.unwrap().seq, Some(1));
+
+        std::fs::write(&path, [lines[0].as_str(), lines[2].as_str()].join("\n")).unwrap();
+        let broken = verify(&path, &key().verifying_key()).unwrap().broken.unwrap();
+        assert_eq!((broken.line, broken.seq), (2, Some(2)));
+
+        // A log signed with another key
+        std::fs::write(&path, lines.join("\n")).unwrap();
+        let other = SigningKey::from_bytes(&[8u8; 32]).verifying_key();
+        let broken = verify(&path, &other).unwrap().broken.unwrap();
+        assert_eq!(broken.seq, Some(0));
+        assert_eq!(broken.reason, "signature does not verify");
+    }
+
+    #[test]
+    fn test_truncation_needs_an_anchored_head() {
+        let temp = TempDir::new().unwrap();
+        let path = write_entries(temp.path(), 3);
+        let full = verify(&path, &key().verifying_key()).unwrap();
+        let lines: Vec<String> = std::fs::read_to_string(&path).unwrap().lines().map(String::from).collect();
+
+        // Dropping the last entry leaves a chain that verifies on its own
+        std::fs::write(&path, lines[..2].join("\n")).unwrap();
+        let truncated = verify(&path, &key().verifying_key()).unwrap();
+        assert!(truncated.is_valid());
+        assert_eq!(truncated.verified, 2);
+        // Only the head recorded before the cut gives it away
+        assert_ne!(truncated.head, full.head);
+        assert!(full.head.is_some());
+    }
+
+    #[test]
+    fn test_keys() {
+        let public = public_key_hex(&key().verifying_key());
+        assert_eq!(parse_verifying_key(&public).unwrap(), key().verifying_key());
+        assert!(matches!(parse_verifying_key("abcd"), Err(AuditError::Key(_))));
+        assert!(matches!(parse_verifying_key("not hex"), Err(AuditError::Key(_))));
+    }
+}
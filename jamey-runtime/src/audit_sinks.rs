@@ -0,0 +1,556 @@
+//! Forwarding audit events to a SOC
+//!
+//! Besides the signed file, each [audit entry](crate::audit::AuditEntry) can
+//! be sent to where security tooling collects events:
+//!
+//! - **syslog**: RFC 5424 messages over TCP or TLS (RFC 5425), octet-counted.
+//!   The entry's fields, sequence number and hash travel as structured data
+//!   so the collector can match them against the file. Messages wait in a
+//!   bounded queue while the collector is unreachable; once it is full, new
+//!   entries are dropped from the forward (never from the file) and counted.
+//! - **Windows Event Log**: one Information event per entry in the
+//!   Application log under the configured source. Register the source once,
+//!   as an administrator, with `New-EventLog -LogName Application -Source Jamey`.
+//!
+//! ```toml
+//! [api.audit_sinks.syslog]
+//! host = "siem.internal"
+//! transport = "tls"
+//! ca_cert_path = "/etc/jamey/siem-ca.pem"
+//!
+//! [api.audit_sinks.windows_event_log]
+//! source = "Jamey"
+//! ```
+
+use crate::audit::AuditEntry;
+use jamey_core::supervisor::Supervisor;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use thiserror::Error;
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+use tokio::sync::{mpsc, Mutex};
+
+/// Structured data ID the entry's fields are sent under; 32473 is the
+/// private enterprise number RFC 5612 sets aside for examples
+const SD_ID: &str = "jamey@32473";
+/// Syslog severity of audit entries: notice
+const SEVERITY: u8 = 5;
+const RECONNECT_MIN: Duration = Duration::from_secs(1);
+const RECONNECT_MAX: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Error)]
+pub enum AuditSinkError {
+    #[error("Failed to set up TLS for the syslog collector: {0}")]
+    Tls(String),
+    #[error("Failed to open the Windows Event Log source {0}: {1}")]
+    EventLog(String, String),
+    #[error("The Windows Event Log is only available on Windows")]
+    Unsupported,
+}
+
+/// Somewhere audit entries are copied to after they are written to the file.
+/// `send` runs inside the tracing subscriber, so it must not block or log.
+pub trait AuditSink: Send + Sync {
+    fn send(&self, entry: &AuditEntry);
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct AuditSinksConfig {
+    /// Forward to a syslog collector (`JAMEY_AUDIT_SYSLOG`, e.g.
+    /// `tls://siem.internal:6514`)
+    pub syslog: Option<SyslogConfig>,
+    /// Write to the Windows Event Log (`JAMEY_AUDIT_EVENT_LOG` names the
+    /// source)
+    pub windows_event_log: Option<EventLogConfig>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum SyslogTransport {
+    Tcp,
+    #[default]
+    Tls,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct SyslogConfig {
+    pub host: String,
+    /// 6514 for TLS and 601 for TCP when unset
+    #[serde(default)]
+    pub port: Option<u16>,
+    #[serde(default)]
+    pub transport: SyslogTransport,
+    /// CA to trust for the collector's certificate, on top of the public
+    /// roots
+    #[serde(default)]
+    pub ca_cert_path: Option<PathBuf>,
+    /// Syslog facility number; 13 is "log audit"
+    #[serde(default = "default_facility")]
+    pub facility: u8,
+    #[serde(default = "default_app_name")]
+    pub app_name: String,
+    /// Entries kept while the collector is unreachable
+    #[serde(default = "default_queue_size")]
+    pub queue_size: usize,
+}
+
+fn default_facility() -> u8 {
+    13
+}
+
+fn default_app_name() -> String {
+    "jamey".to_string()
+}
+
+fn default_queue_size() -> usize {
+    1024
+}
+
+impl SyslogConfig {
+    pub fn new(host: impl Into<String>, transport: SyslogTransport) -> Self {
+        Self {
+            host: host.into(),
+            port: None,
+            transport,
+            ca_cert_path: None,
+            facility: default_facility(),
+            app_name: default_app_name(),
+            queue_size: default_queue_size(),
+        }
+    }
+
+    /// A collector given as `tls://host[:port]` or `tcp://host[:port]`
+    pub fn from_url(url: &str) -> Result<Self, String> {
+        let parsed = url::Url::parse(url).map_err(|e| format!("Invalid syslog URL {}: {}", url, e))?;
+        let transport = match parsed.scheme() {
+            "tls" => SyslogTransport::Tls,
+            "tcp" => SyslogTransport::Tcp,
+            other => return Err(format!("Unknown syslog transport \"{}\" (tls or tcp)", other)),
+        };
+        let host = parsed.host_str().ok_or_else(|| format!("Syslog URL {} has no host", url))?;
+        Ok(Self {
+            port: parsed.port(),
+            ..Self::new(host, transport)
+        })
+    }
+
+    pub fn port(&self) -> u16 {
+        self.port.unwrap_or(match self.transport {
+            SyslogTransport::Tls => 6514,
+            SyslogTransport::Tcp => 601,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct EventLogConfig {
+    /// Event source the entries are reported under
+    #[serde(default = "default_event_source")]
+    pub source: String,
+    /// Event ID of every entry
+    #[serde(default = "default_event_id")]
+    pub event_id: u32,
+}
+
+fn default_event_source() -> String {
+    "Jamey".to_string()
+}
+
+fn default_event_id() -> u32 {
+    1000
+}
+
+impl Default for EventLogConfig {
+    fn default() -> Self {
+        Self {
+            source: default_event_source(),
+            event_id: default_event_id(),
+        }
+    }
+}
+
+impl AuditSinksConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(syslog) = &self.syslog {
+            if syslog.host.trim().is_empty() {
+                return Err("api.audit_sinks.syslog.host can't be empty".to_string());
+            }
+            if syslog.facility > 23 {
+                return Err("api.audit_sinks.syslog.facility must be between 0 and 23".to_string());
+            }
+            if syslog.queue_size == 0 {
+                return Err("api.audit_sinks.syslog.queue_size must be at least 1".to_string());
+            }
+            if syslog.ca_cert_path.is_some() && syslog.transport != SyslogTransport::Tls {
+                return Err("api.audit_sinks.syslog.ca_cert_path needs the tls transport".to_string());
+            }
+            if syslog.app_name.is_empty() || syslog.app_name.len() > 48 || !is_printable_ascii(&syslog.app_name) {
+                return Err("api.audit_sinks.syslog.app_name must be 1-48 printable ASCII characters".to_string());
+            }
+        }
+        if let Some(event_log) = &self.windows_event_log {
+            if !cfg!(windows) {
+                return Err("api.audit_sinks.windows_event_log is only available on Windows".to_string());
+            }
+            if event_log.source.trim().is_empty() {
+                return Err("api.audit_sinks.windows_event_log.source can't be empty".to_string());
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The sinks `config` asks for; syslog forwarding runs as a supervised task
+pub fn build(config: &AuditSinksConfig, supervisor: &Supervisor) -> Result<Vec<Arc<dyn AuditSink>>, AuditSinkError> {
+    let mut sinks: Vec<Arc<dyn AuditSink>> = Vec::new();
+    if let Some(syslog) = &config.syslog {
+        sinks.push(SyslogSink::start(syslog.clone(), supervisor)?);
+    }
+    if let Some(event_log) = &config.windows_event_log {
+        sinks.push(Arc::new(EventLogSink::open(event_log)?));
+    }
+    Ok(sinks)
+}
+
+fn is_printable_ascii(text: &str) -> bool {
+    text.bytes().all(|b| (33..=126).contains(&b))
+}
+
+/// `entry` as an RFC 5424 message, without framing
+pub fn format_rfc5424(entry: &AuditEntry, config: &SyslogConfig, hostname: &str) -> String {
+    let mut data = format!(
+        "[{} seq=\"{}\" hash=\"{}\"",
+        SD_ID,
+        entry.seq,
+        escape_param(&entry.hash)
+    );
+    for (name, value) in &entry.fields {
+        let name: String = name
+            .chars()
+            .filter(|c| c.is_ascii_graphic() && !matches!(c, '=' | ']' | '"'))
+            .take(32)
+            .collect();
+        if !name.is_empty() && name != "seq" && name != "hash" {
+            data.push_str(&format!(" {}=\"{}\"", name, escape_param(value)));
+        }
+    }
+    data.push(']');
+    format!(
+        "<{}>1 {} {} {} {} audit {} {}",
+        u16::from(config.facility) * 8 + u16::from(SEVERITY),
+        entry.timestamp.to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+        hostname,
+        config.app_name,
+        std::process::id(),
+        data,
+        entry.message
+    )
+}
+
+/// Escape the characters RFC 5424 reserves in parameter values
+fn escape_param(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '"' | '\\' | ']') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// This machine's name as syslog wants it, or the nil value
+fn hostname() -> String {
+    std::env::var("HOSTNAME")
+        .or_else(|_| std::env::var("COMPUTERNAME"))
+        .ok()
+        .or_else(|| std::fs::read_to_string("/etc/hostname").ok())
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty() && name.len() <= 255 && is_printable_ascii(name))
+        .unwrap_or_else(|| "-".to_string())
+}
+
+/// Queues formatted entries for a task that keeps a connection to the
+/// collector open
+pub struct SyslogSink {
+    config: SyslogConfig,
+    hostname: String,
+    queue: mpsc::Sender<String>,
+    dropped: AtomicU64,
+}
+
+impl SyslogSink {
+    /// Set up TLS, if used, and start forwarding under `supervisor`
+    pub fn start(config: SyslogConfig, supervisor: &Supervisor) -> Result<Arc<Self>, AuditSinkError> {
+        let tls = match config.transport {
+            SyslogTransport::Tls => Some(tls_connector(config.ca_cert_path.as_deref())?),
+            SyslogTransport::Tcp => None,
+        };
+        let (queue, messages) = mpsc::channel(config.queue_size);
+        let messages = Arc::new(Mutex::new(messages));
+        let (host, port) = (config.host.clone(), config.port());
+        supervisor.spawn("audit_syslog", move || {
+            forward(Arc::clone(&messages), host.clone(), port, tls.clone())
+        });
+        Ok(Arc::new(Self {
+            config,
+            hostname: hostname(),
+            queue,
+            dropped: AtomicU64::new(0),
+        }))
+    }
+
+    /// Entries not forwarded because the queue was full
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
+
+impl AuditSink for SyslogSink {
+    fn send(&self, entry: &AuditEntry) {
+        let message = format_rfc5424(entry, &self.config, &self.hostname);
+        if self.queue.try_send(message).is_err() {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+type SyslogStream = Box<dyn AsyncWrite + Send + Unpin>;
+
+fn tls_connector(ca_cert_path: Option<&std::path::Path>) -> Result<tokio_rustls::TlsConnector, AuditSinkError> {
+    let mut roots = rustls::RootCertStore::empty();
+    roots.add_trust_anchors(webpki_roots::TLS_SERVER_ROOTS.iter().map(|ta| {
+        rustls::OwnedTrustAnchor::from_subject_spki_name_constraints(ta.subject, ta.spki, ta.name_constraints)
+    }));
+    if let Some(path) = ca_cert_path {
+        let pem = std::fs::read(path)
+            .map_err(|e| AuditSinkError::Tls(format!("Failed to read {}: {}", path.display(), e)))?;
+        let certs = rustls_pemfile::certs(&mut std::io::Cursor::new(pem))
+            .map_err(|e| AuditSinkError::Tls(format!("Failed to parse {}: {}", path.display(), e)))?;
+        for cert in certs {
+            roots
+                .add(&rustls::Certificate(cert))
+                .map_err(|e| AuditSinkError::Tls(format!("Rejected CA certificate in {}: {}", path.display(), e)))?;
+        }
+    }
+    let config = rustls::ClientConfig::builder()
+        .with_safe_defaults()
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+    Ok(tokio_rustls::TlsConnector::from(Arc::new(config)))
+}
+
+async fn connect(host: &str, port: u16, tls: Option<&tokio_rustls::TlsConnector>) -> std::io::Result<SyslogStream> {
+    let tcp = tokio::net::TcpStream::connect((host, port)).await?;
+    match tls {
+        Some(tls) => {
+            let name = rustls::ServerName::try_from(host)
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?;
+            Ok(Box::new(tls.connect(name, tcp).await?))
+        }
+        None => Ok(Box::new(tcp)),
+    }
+}
+
+/// Send queued messages until the sink is dropped, reconnecting with backoff.
+/// A message whose write failed is sent again on the next connection.
+async fn forward(
+    messages: Arc<Mutex<mpsc::Receiver<String>>>,
+    host: String,
+    port: u16,
+    tls: Option<tokio_rustls::TlsConnector>,
+) {
+    let mut messages = messages.lock().await;
+    let mut pending: Option<String> = None;
+    let mut backoff = RECONNECT_MIN;
+    loop {
+        let mut stream = match connect(&host, port, tls.as_ref()).await {
+            Ok(stream) => {
+                backoff = RECONNECT_MIN;
+                stream
+            }
+            Err(e) => {
+                tracing::warn!("Can't reach syslog collector {}:{}: {}", host, port, e);
+                tokio::time::sleep(backoff).await;
+                backoff = (backoff * 2).min(RECONNECT_MAX);
+                continue;
+            }
+        };
+        loop {
+            let message = match pending.take() {
+                Some(message) => message,
+                None => match messages.recv().await {
+                    Some(message) => message,
+                    None => return,
+                },
+            };
+            let frame = format!("{} {}", message.len(), message);
+            let written = match stream.write_all(frame.as_bytes()).await {
+                Ok(()) => stream.flush().await,
+                Err(e) => Err(e),
+            };
+            if let Err(e) = written {
+                tracing::warn!("Lost connection to syslog collector {}:{}: {}", host, port, e);
+                pending = Some(message);
+                break;
+            }
+        }
+    }
+}
+
+/// Reports entries to the Windows Event Log
+pub struct EventLogSink {
+    #[cfg(windows)]
+    handle: windows::Win32::System::EventLog::EventSourceHandle,
+    #[cfg(windows)]
+    event_id: u32,
+}
+
+// Safety: event source handles may be used from any thread
+#[cfg(windows)]
+unsafe impl Send for EventLogSink {}
+#[cfg(windows)]
+unsafe impl Sync for EventLogSink {}
+
+impl EventLogSink {
+    #[cfg(windows)]
+    pub fn open(config: &EventLogConfig) -> Result<Self, AuditSinkError> {
+        use windows::core::{HSTRING, PCWSTR};
+        use windows::Win32::System::EventLog::RegisterEventSourceW;
+
+        let source = HSTRING::from(config.source.as_str());
+        // Safety: both strings outlive the call
+        let handle = unsafe { RegisterEventSourceW(PCWSTR::null(), &source) }
+            .map_err(|e| AuditSinkError::EventLog(config.source.clone(), e.to_string()))?;
+        Ok(Self {
+            handle,
+            event_id: config.event_id,
+        })
+    }
+
+    #[cfg(not(windows))]
+    pub fn open(_config: &EventLogConfig) -> Result<Self, AuditSinkError> {
+        Err(AuditSinkError::Unsupported)
+    }
+}
+
+impl AuditSink for EventLogSink {
+    #[cfg(windows)]
+    fn send(&self, entry: &AuditEntry) {
+        use windows::core::{HSTRING, PCWSTR};
+        use windows::Win32::Foundation::PSID;
+        use windows::Win32::System::EventLog::{ReportEventW, EVENTLOG_INFORMATION_TYPE};
+
+        let mut text = format!("{}\r\n\r\nseq: {}\r\nhash: {}", entry.message, entry.seq, entry.hash);
+        for (name, value) in &entry.fields {
+            text.push_str(&format!("\r\n{}: {}", name, value));
+        }
+        let text = HSTRING::from(text.as_str());
+        let strings = [PCWSTR(text.as_ptr())];
+        // Safety: `strings` points into `text`, which outlives the call
+        unsafe {
+            ReportEventW(
+                self.handle,
+                EVENTLOG_INFORMATION_TYPE,
+                0,
+                self.event_id,
+                PSID::default(),
+                0,
+                Some(&strings),
+                None,
+            );
+        }
+    }
+
+    #[cfg(not(windows))]
+    fn send(&self, _entry: &AuditEntry) {}
+}
+
+#[cfg(windows)]
+impl Drop for EventLogSink {
+    fn drop(&mut self) {
+        // Safety: the handle came from RegisterEventSourceW and is closed once
+        unsafe {
+            windows::Win32::System::EventLog::DeregisterEventSource(self.handle);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use std::collections::BTreeMap;
+    use tokio::io::AsyncReadExt;
+
+    fn entry() -> AuditEntry {
+        AuditEntry {
+            seq: 4,
+            timestamp: chrono::Utc.with_ymd_and_hms(2026, 3, 1, 12, 0, 0).unwrap(),
+            message: "Tool executed".to_string(),
+            fields: BTreeMap::from([
+                ("tool".to_string(), "shell".to_string()),
+                ("command".to_string(), "echo \"a]b\\c\"".to_string()),
+            ]),
+            prev_hash: "0".repeat(64),
+            hash: "ab".repeat(32),
+            signature: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_format_rfc5424() {
+        let config = SyslogConfig::new("siem.internal", SyslogTransport::Tls);
+        let message = format_rfc5424(&entry(), &config, "host-1");
+        let expected_prefix = "<109>1 2026-03-01T12:00:00.000Z host-1 jamey ";
+        assert!(message.starts_with(expected_prefix), "{}", message);
+        assert!(message.contains(&format!(
+            " audit [jamey@32473 seq=\"4\" hash=\"{}\" command=\"echo \\\"a\\]b\\\\c\\\"\" tool=\"shell\"] Tool executed",
+            "ab".repeat(32)
+        )), "{}", message);
+    }
+
+    #[test]
+    fn test_config() {
+        let config = SyslogConfig::from_url("tcp://siem.internal").unwrap();
+        assert_eq!((config.transport, config.port()), (SyslogTransport::Tcp, 601));
+        assert_eq!(SyslogConfig::from_url("tls://siem.internal:7514").unwrap().port(), 7514);
+        assert!(SyslogConfig::from_url("udp://siem.internal").is_err());
+
+        let mut sinks = AuditSinksConfig {
+            syslog: Some(config),
+            windows_event_log: None,
+        };
+        assert!(sinks.validate().is_ok());
+        sinks.syslog.as_mut().unwrap().ca_cert_path = Some(PathBuf::from("/etc/ca.pem"));
+        assert!(sinks.validate().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_syslog_forwarding() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let mut config = SyslogConfig::new("127.0.0.1", SyslogTransport::Tcp);
+        config.port = Some(listener.local_addr().unwrap().port());
+        let sink = SyslogSink::start(config, &Supervisor::default()).unwrap();
+        sink.send(&entry());
+
+        let (mut socket, _) = listener.accept().await.unwrap();
+        let mut received = Vec::new();
+        while !received.ends_with(b"Tool executed") {
+            let mut buffer = [0u8; 1024];
+            let read = socket.read(&mut buffer).await.unwrap();
+            assert!(read > 0);
+            received.extend_from_slice(&buffer[..read]);
+        }
+        let received = String::from_utf8(received).unwrap();
+        let (length, message) = received.split_once(' ').unwrap();
+        assert_eq!(length.parse::<usize>().unwrap(), message.len());
+        assert!(message.starts_with("<109>1 "));
+        assert_eq!(sink.dropped(), 0);
+    }
+}
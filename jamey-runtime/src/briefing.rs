@@ -0,0 +1,697 @@
+//! Scheduled briefings
+//!
+//! A briefing gathers the configured sources (today's calendar, news feeds,
+//! runtime telemetry and approvals waiting on someone), asks the model to
+//! turn them into a short summary and sends it to each configured channel.
+//! `briefing.schedule` adds a [`TaskKind::Briefing`] task to the scheduler;
+//! `jamey briefing --now` runs one on demand. The `cli` channel keeps
+//! briefings under `briefing.dir` for `jamey briefing` to show.
+
+use crate::approvals::ApprovalStatus;
+use crate::events;
+use crate::routing::RouteTask;
+use crate::scheduler::{self, Schedule, ScheduledTask, TaskKind};
+use crate::state::RuntimeState;
+use crate::usage;
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use jamey_core::secure_logging::Masked;
+use jamey_providers::openrouter::{self, ChatRequest, LlmProvider};
+use jamey_tools::oauth::OAuthProvider;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+use tracing::warn;
+use uuid::Uuid;
+
+const BRIEFING_PROMPT: &str = "You write the user's briefing from the notes below. Lead with what \
+needs their attention today, such as meetings and pending approvals, then news, then system health. \
+Use short Markdown sections with bullet points, leave out sections with nothing worth saying and \
+don't add anything that isn't in the notes.";
+
+const BRIEFING_MAX_TOKENS: u32 = 1200;
+
+/// Calendar events listed per briefing
+const MAX_EVENTS: usize = 20;
+
+/// Marker file holding when the newest briefing was read
+const LAST_READ_FILE: &str = ".last_read";
+
+#[derive(Debug, Error)]
+pub enum BriefingError {
+    #[error("Invalid briefing config: {0}")]
+    Config(String),
+    #[error("Briefing could not be delivered: {0}")]
+    Delivery(String),
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BriefingSource {
+    /// Today's events from the signed-in Google calendar
+    Calendar,
+    /// Newest items of `briefing.feeds`
+    Feeds,
+    /// Spend, token use and active sessions
+    Telemetry,
+    /// Connector calls waiting for approval
+    Approvals,
+}
+
+impl std::str::FromStr for BriefingSource {
+    type Err = BriefingError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "calendar" => Ok(Self::Calendar),
+            "feeds" => Ok(Self::Feeds),
+            "telemetry" => Ok(Self::Telemetry),
+            "approvals" => Ok(Self::Approvals),
+            other => Err(BriefingError::Config(format!(
+                "unknown source '{}' (expected calendar, feeds, telemetry or approvals)",
+                other
+            ))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BriefingChannel {
+    /// Saved for `jamey briefing`; `jamey chat` mentions unread ones
+    Cli,
+    Email,
+    /// Slack incoming webhook
+    Slack,
+    Telegram,
+}
+
+impl std::str::FromStr for BriefingChannel {
+    type Err = BriefingError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "cli" => Ok(Self::Cli),
+            "email" => Ok(Self::Email),
+            "slack" => Ok(Self::Slack),
+            "telegram" => Ok(Self::Telegram),
+            other => Err(BriefingError::Config(format!(
+                "unknown channel '{}' (expected cli, email, slack or telegram)",
+                other
+            ))),
+        }
+    }
+}
+
+impl std::fmt::Display for BriefingChannel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::Cli => "cli",
+            Self::Email => "email",
+            Self::Slack => "slack",
+            Self::Telegram => "telegram",
+        };
+        f.write_str(name)
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct BriefingConfig {
+    /// When to send one, as a cron expression with seconds in local time,
+    /// e.g. `0 30 7 * * Mon-Fri` (`JAMEY_BRIEFING_SCHEDULE`); off when unset
+    pub schedule: Option<String>,
+    /// What to gather (`JAMEY_BRIEFING_SOURCES`)
+    pub sources: Vec<BriefingSource>,
+    /// RSS or Atom feed URLs for the `feeds` source (`JAMEY_BRIEFING_FEEDS`)
+    pub feeds: Vec<String>,
+    /// Newest items taken from each feed
+    pub feed_items: usize,
+    /// Where briefings are sent (`JAMEY_BRIEFING_CHANNELS`)
+    pub channels: Vec<BriefingChannel>,
+    /// Where the `cli` channel keeps briefings (`JAMEY_BRIEFING_DIR`)
+    pub dir: PathBuf,
+    /// Recipients for the `email` channel (`JAMEY_BRIEFING_EMAIL_TO`)
+    pub email_to: Vec<String>,
+    /// Sender address (`JAMEY_BRIEFING_EMAIL_FROM`)
+    pub email_from: Option<String>,
+    /// SMTP server, reached with STARTTLS (`SMTP_HOST`)
+    pub smtp_host: Option<String>,
+    /// `SMTP_PORT`
+    pub smtp_port: u16,
+    /// `SMTP_USER`
+    pub smtp_user: Option<String>,
+    /// `SMTP_PASSWORD`; environment only
+    #[schemars(skip)]
+    pub smtp_password: Option<String>,
+    /// Slack incoming webhook URL (`SLACK_WEBHOOK_URL`); environment only
+    #[schemars(skip)]
+    pub slack_webhook_url: Option<String>,
+    /// Chat the `telegram` channel posts to; the first of
+    /// `tools.telegram_allowed_chats` when unset (`JAMEY_BRIEFING_TELEGRAM_CHAT`)
+    pub telegram_chat: Option<i64>,
+}
+
+impl fmt::Debug for BriefingConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BriefingConfig")
+            .field("schedule", &self.schedule)
+            .field("sources", &self.sources)
+            .field("feeds", &self.feeds)
+            .field("feed_items", &self.feed_items)
+            .field("channels", &self.channels)
+            .field("dir", &self.dir)
+            .field("email_to", &self.email_to)
+            .field("email_from", &self.email_from)
+            .field("smtp_host", &self.smtp_host)
+            .field("smtp_port", &self.smtp_port)
+            .field("smtp_user", &self.smtp_user)
+            .field("smtp_password", &Masked::opt(self.smtp_password.as_deref()))
+            .field("slack_webhook_url", &Masked::opt(self.slack_webhook_url.as_deref()))
+            .field("telegram_chat", &self.telegram_chat)
+            .finish()
+    }
+}
+
+impl Default for BriefingConfig {
+    fn default() -> Self {
+        Self {
+            schedule: None,
+            sources: vec![
+                BriefingSource::Calendar,
+                BriefingSource::Feeds,
+                BriefingSource::Telemetry,
+                BriefingSource::Approvals,
+            ],
+            feeds: Vec::new(),
+            feed_items: 5,
+            channels: vec![BriefingChannel::Cli],
+            dir: PathBuf::from("./briefings"),
+            email_to: Vec::new(),
+            email_from: None,
+            smtp_host: None,
+            smtp_port: 587,
+            smtp_user: None,
+            smtp_password: None,
+            slack_webhook_url: None,
+            telegram_chat: None,
+        }
+    }
+}
+
+impl BriefingConfig {
+    /// Check the schedule parses and each channel has what it needs; the
+    /// Telegram bot token is checked with the rest of the tools config
+    pub fn validate(&self) -> Result<(), BriefingError> {
+        if let Some(expression) = &self.schedule {
+            scheduler::parse_cron(expression).map_err(|e| BriefingError::Config(e.to_string()))?;
+        }
+        if self.channels.contains(&BriefingChannel::Email)
+            && (self.smtp_host.is_none() || self.email_from.is_none() || self.email_to.is_empty())
+        {
+            return Err(BriefingError::Config(
+                "the email channel needs smtp_host, email_from and email_to".to_string(),
+            ));
+        }
+        if self.channels.contains(&BriefingChannel::Slack) && self.slack_webhook_url.is_none() {
+            return Err(BriefingError::Config("the slack channel needs SLACK_WEBHOOK_URL".to_string()));
+        }
+        Ok(())
+    }
+
+    /// Scheduler task for `schedule`, if one is set, with its first run
+    /// worked out in `timezone`
+    pub fn scheduled_task(&self, timezone: Tz) -> Option<ScheduledTask> {
+        let schedule = Schedule::Cron {
+            expression: self.schedule.clone()?,
+        };
+        Some(ScheduledTask {
+            id: Uuid::new_v4(),
+            name: "briefing".to_string(),
+            kind: TaskKind::Briefing,
+            connector_id: String::new(),
+            params: Default::default(),
+            next_run: scheduler::first_run(&schedule, Utc::now(), timezone),
+            schedule,
+            enabled: true,
+            last_run: None,
+        })
+    }
+}
+
+/// A composed briefing and where it went
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Briefing {
+    pub at: DateTime<Utc>,
+    pub text: String,
+    /// Channels that accepted it
+    pub delivered: Vec<String>,
+    /// Sources that couldn't be read and channels that failed
+    pub warnings: Vec<String>,
+}
+
+/// Gathered notes from one source
+struct Section {
+    title: &'static str,
+    body: String,
+}
+
+/// Gather, compose and deliver a briefing. Sources that fail are noted and
+/// skipped; it is only an error when no channel accepts the result.
+pub async fn run(state: &RuntimeState) -> Result<Briefing, BriefingError> {
+    let config = &state.config.briefing;
+    let mut warnings = Vec::new();
+    let mut sections = Vec::new();
+    for source in &config.sources {
+        match gather(state, *source).await {
+            Ok(section) => sections.push(section),
+            Err(e) => {
+                warn!("Briefing source {:?} failed: {}", source, e);
+                warnings.push(format!("{:?}: {}", source, e));
+            }
+        }
+    }
+
+    let notes = sections
+        .iter()
+        .map(|s| format!("## {}\n{}", s.title, s.body))
+        .collect::<Vec<_>>()
+        .join("\n\n");
+    let text = match compose(state, &notes).await {
+        Ok(text) => text,
+        Err(e) => {
+            // The notes themselves are still worth sending
+            warn!("Composing the briefing failed, sending the notes instead: {}", e);
+            warnings.push(format!("summary: {}", e));
+            notes
+        }
+    };
+
+    let mut briefing = Briefing {
+        at: Utc::now(),
+        text,
+        delivered: Vec::new(),
+        warnings,
+    };
+    for channel in &config.channels {
+        match deliver(state, *channel, &briefing).await {
+            Ok(()) => briefing.delivered.push(channel.to_string()),
+            Err(e) => {
+                warn!("Delivering the briefing by {} failed: {}", channel, e);
+                briefing.warnings.push(format!("{}: {}", channel, e));
+            }
+        }
+    }
+    if briefing.delivered.is_empty() && !config.channels.is_empty() {
+        return Err(BriefingError::Delivery(briefing.warnings.join("; ")));
+    }
+    state.events.publish(
+        events::BRIEFING_DELIVERED,
+        None,
+        serde_json::json!({
+            "at": briefing.at,
+            "delivered": briefing.delivered,
+            // The first point is enough for a notification
+            "excerpt": briefing
+                .text
+                .lines()
+                .map(|line| line.trim().trim_start_matches(['-', '*']).trim())
+                .find(|line| !line.is_empty() && !line.starts_with('#'))
+                .unwrap_or_default(),
+        }),
+    );
+    Ok(briefing)
+}
+
+async fn gather(state: &RuntimeState, source: BriefingSource) -> anyhow::Result<Section> {
+    let (title, body) = match source {
+        BriefingSource::Calendar => ("Calendar", calendar(state).await?),
+        BriefingSource::Feeds => ("News", feeds(&state.config.briefing).await?),
+        BriefingSource::Telemetry => ("System", telemetry(state).await?),
+        BriefingSource::Approvals => ("Approvals", approvals(state).await?),
+    };
+    let body = if body.trim().is_empty() {
+        "Nothing to report.".to_string()
+    } else {
+        body
+    };
+    Ok(Section { title, body })
+}
+
+/// Today's events from the primary Google calendar, today being the
+/// runtime's [`TimeContext`](crate::locale::TimeContext) day
+async fn calendar(state: &RuntimeState) -> anyhow::Result<String> {
+    let oauth = state
+        .oauth
+        .as_ref()
+        .filter(|oauth| oauth.is_authorized(OAuthProvider::Google))
+        .ok_or_else(|| anyhow::anyhow!("not signed in to Google; run `jamey auth login google`"))?;
+    let token = oauth.access_token(OAuthProvider::Google).await?;
+
+    let (start, end) = state.time.day_bounds(state.time.today());
+    let response: serde_json::Value = reqwest::Client::new()
+        .get("https://www.googleapis.com/calendar/v3/calendars/primary/events")
+        .bearer_auth(token)
+        .query(&[
+            ("timeMin", start.to_rfc3339()),
+            ("timeMax", end.to_rfc3339()),
+            ("timeZone", state.time.timezone.name().to_string()),
+            ("singleEvents", "true".to_string()),
+            ("orderBy", "startTime".to_string()),
+            ("maxResults", MAX_EVENTS.to_string()),
+        ])
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+
+    let events = response["items"].as_array().cloned().unwrap_or_default();
+    Ok(events
+        .iter()
+        .map(|event| event_line(event, state.time.timezone))
+        .collect::<Vec<_>>()
+        .join("\n"))
+}
+
+/// `- 09:30 Standup (Room 4)` with the time in `timezone`, or `- all day ...`
+fn event_line(event: &serde_json::Value, timezone: Tz) -> String {
+    let when = event["start"]["dateTime"]
+        .as_str()
+        .and_then(|t| DateTime::parse_from_rfc3339(t).ok())
+        .map(|t| t.with_timezone(&timezone).format("%H:%M").to_string())
+        .unwrap_or_else(|| "all day".to_string());
+    let title = event["summary"].as_str().unwrap_or("(no title)");
+    match event["location"].as_str() {
+        Some(location) => format!("- {} {} ({})", when, title, location),
+        None => format!("- {} {}", when, title),
+    }
+}
+
+async fn feeds(config: &BriefingConfig) -> anyhow::Result<String> {
+    let client = reqwest::Client::builder()
+        .timeout(std::time::Duration::from_secs(20))
+        .build()?;
+    let mut lines = Vec::new();
+    for url in &config.feeds {
+        let bytes = match client.get(url).send().await.and_then(|r| r.error_for_status()) {
+            Ok(response) => response.bytes().await?,
+            Err(e) => {
+                lines.push(format!("- {} could not be fetched: {}", url, e));
+                continue;
+            }
+        };
+        match feed_rs::parser::parse(&bytes[..]) {
+            Ok(feed) => lines.extend(feed_lines(&feed, config.feed_items)),
+            Err(e) => lines.push(format!("- {} is not a readable feed: {}", url, e)),
+        }
+    }
+    Ok(lines.join("\n"))
+}
+
+/// Newest `limit` entries as `- Title (link) — Feed`
+fn feed_lines(feed: &feed_rs::model::Feed, limit: usize) -> Vec<String> {
+    let source = feed.title.as_ref().map(|t| t.content.trim().to_string());
+    let mut entries: Vec<_> = feed.entries.iter().collect();
+    entries.sort_by_key(|e| std::cmp::Reverse(e.published.or(e.updated)));
+    entries
+        .into_iter()
+        .take(limit)
+        .map(|entry| {
+            let title = entry
+                .title
+                .as_ref()
+                .map(|t| t.content.trim())
+                .unwrap_or("(untitled)");
+            let mut line = format!("- {}", title);
+            if let Some(link) = entry.links.first() {
+                line.push_str(&format!(" ({})", link.href));
+            }
+            if let Some(source) = &source {
+                line.push_str(&format!(" — {}", source));
+            }
+            line
+        })
+        .collect()
+}
+
+async fn telemetry(state: &RuntimeState) -> anyhow::Result<String> {
+    let since = Some(state.time.day_bounds(state.time.today()).0);
+    let records = state.usage_log.query(since).await?;
+    let total = usage::total(&records);
+
+    let spent = match state.budget.daily_limit() {
+        Some(limit) => format!("${:.2} of the ${:.2} daily budget", state.budget.spent_today(), limit),
+        None => format!("${:.2}", state.budget.spent_today()),
+    };
+    Ok([
+        format!("- Spent today: {}", spent),
+        format!("- Model calls today: {} ({} tokens)", total.requests, total.total_tokens),
+        format!("- Active sessions: {}", state.session_manager.active_count()),
+    ]
+    .join("\n"))
+}
+
+async fn approvals(state: &RuntimeState) -> anyhow::Result<String> {
+    let pending = state.approval_queue.list(Some(ApprovalStatus::Pending)).await?;
+    Ok(pending
+        .iter()
+        .map(|request| {
+            format!(
+                "- {} {} waiting since {} (`jamey approvals approve {}`)",
+                request.connector_id,
+                request.action,
+                state.time.local(request.requested_at).format("%a %H:%M"),
+                &request.id.to_string()[..8]
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n"))
+}
+
+async fn compose(state: &RuntimeState, notes: &str) -> anyhow::Result<String> {
+    let request = ChatRequest {
+        model: state.model_for(RouteTask::Briefing, notes),
+        messages: vec![
+            openrouter::Message {
+                role: "system".to_string(),
+                content: BRIEFING_PROMPT.to_string(),
+            },
+            openrouter::Message {
+                role: "user".to_string(),
+                content: format!("Today is {}.\n\n{}", state.time.now().format("%A %-d %B %Y"), notes),
+            },
+        ],
+        tools: None,
+        tool_choice: None,
+        temperature: Some(0.3),
+        max_tokens: Some(BRIEFING_MAX_TOKENS),
+    };
+    let response = {
+        let _permit = state.request_queue.background().await;
+        state.llm_provider.chat(request).await?
+    };
+    let text = response
+        .choices
+        .first()
+        .map(|c| c.message.content.trim().to_string())
+        .unwrap_or_default();
+    if text.is_empty() {
+        anyhow::bail!("model returned an empty briefing");
+    }
+    Ok(text)
+}
+
+async fn deliver(state: &RuntimeState, channel: BriefingChannel, briefing: &Briefing) -> anyhow::Result<()> {
+    let config = &state.config.briefing;
+    match channel {
+        BriefingChannel::Cli => {
+            BriefingStore::new(&config.dir).save(briefing).await?;
+        }
+        BriefingChannel::Email => send_email(config, briefing, state.time.timezone).await?,
+        BriefingChannel::Slack => {
+            let url = config
+                .slack_webhook_url
+                .as_deref()
+                .ok_or_else(|| anyhow::anyhow!("SLACK_WEBHOOK_URL is not set"))?;
+            reqwest::Client::new()
+                .post(url)
+                .json(&serde_json::json!({ "text": briefing.text }))
+                .send()
+                .await?
+                .error_for_status()?;
+        }
+        BriefingChannel::Telegram => {
+            let bot = state
+                .telegram
+                .as_ref()
+                .ok_or_else(|| anyhow::anyhow!("TELEGRAM_BOT_TOKEN is not set"))?;
+            let chat = config
+                .telegram_chat
+                .or_else(|| state.config.tools.telegram_allowed_chats.first().copied())
+                .ok_or_else(|| anyhow::anyhow!("no Telegram chat to send to"))?;
+            bot.connector.send_message(chat, &briefing.text).await?;
+        }
+    }
+    Ok(())
+}
+
+async fn send_email(config: &BriefingConfig, briefing: &Briefing, timezone: Tz) -> anyhow::Result<()> {
+    use lettre::message::header::ContentType;
+    use lettre::transport::smtp::authentication::Credentials;
+    use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+
+    let host = config
+        .smtp_host
+        .as_deref()
+        .ok_or_else(|| anyhow::anyhow!("SMTP_HOST is not set"))?;
+    let from = config
+        .email_from
+        .as_deref()
+        .ok_or_else(|| anyhow::anyhow!("no sender address"))?;
+    let mut email = Message::builder()
+        .from(from.parse()?)
+        .subject(format!("Briefing for {}", briefing.at.with_timezone(&timezone).format("%A %-d %B")))
+        .header(ContentType::TEXT_PLAIN);
+    for to in &config.email_to {
+        email = email.to(to.parse()?);
+    }
+    let email = email.body(briefing.text.clone())?;
+
+    let mut transport = AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(host)?.port(config.smtp_port);
+    if let (Some(user), Some(password)) = (&config.smtp_user, &config.smtp_password) {
+        transport = transport.credentials(Credentials::new(user.clone(), password.clone()));
+    }
+    transport.build().send(email).await?;
+    Ok(())
+}
+
+/// Briefings kept by the `cli` channel, one Markdown file each
+#[derive(Debug, Clone)]
+pub struct BriefingStore {
+    dir: PathBuf,
+}
+
+impl BriefingStore {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub async fn save(&self, briefing: &Briefing) -> Result<PathBuf, BriefingError> {
+        tokio::fs::create_dir_all(&self.dir).await?;
+        let path = self.dir.join(format!("{}.md", briefing.at.format("%Y%m%dT%H%M%SZ")));
+        tokio::fs::write(&path, &briefing.text).await?;
+        Ok(path)
+    }
+
+    /// The newest briefing and when it was written
+    pub async fn latest(&self) -> Result<Option<(DateTime<Utc>, String)>, BriefingError> {
+        let mut entries = match tokio::fs::read_dir(&self.dir).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let mut newest: Option<(DateTime<Utc>, PathBuf)> = None;
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            let Some(at) = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .and_then(|stem| chrono::NaiveDateTime::parse_from_str(stem, "%Y%m%dT%H%M%SZ").ok())
+                .map(|at| at.and_utc())
+            else {
+                continue;
+            };
+            if newest.as_ref().is_none_or(|(newest, _)| at > *newest) {
+                newest = Some((at, path));
+            }
+        }
+        match newest {
+            Some((at, path)) => Ok(Some((at, tokio::fs::read_to_string(path).await?))),
+            None => Ok(None),
+        }
+    }
+
+    /// When the newest briefing was written, if it hasn't been read yet
+    pub async fn unread(&self) -> Result<Option<DateTime<Utc>>, BriefingError> {
+        let Some((at, _)) = self.latest().await? else {
+            return Ok(None);
+        };
+        let last_read = tokio::fs::read_to_string(self.dir.join(LAST_READ_FILE))
+            .await
+            .ok()
+            .and_then(|t| DateTime::parse_from_rfc3339(t.trim()).ok());
+        Ok(match last_read {
+            Some(read) if read >= at => None,
+            _ => Some(at),
+        })
+    }
+
+    pub async fn mark_read(&self, at: DateTime<Utc>) -> Result<(), BriefingError> {
+        tokio::fs::create_dir_all(&self.dir).await?;
+        tokio::fs::write(self.dir.join(LAST_READ_FILE), at.to_rfc3339()).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_store_tracks_unread() {
+        let dir = TempDir::new().unwrap();
+        let store = BriefingStore::new(dir.path());
+        assert!(store.latest().await.unwrap().is_none());
+
+        let mut briefing = Briefing {
+            at: Utc::now() - chrono::Duration::hours(1),
+            text: "Old".to_string(),
+            delivered: Vec::new(),
+            warnings: Vec::new(),
+        };
+        store.save(&briefing).await.unwrap();
+        briefing.at = Utc::now();
+        briefing.text = "New".to_string();
+        store.save(&briefing).await.unwrap();
+
+        let (at, text) = store.latest().await.unwrap().unwrap();
+        assert_eq!(text, "New");
+        assert_eq!(store.unread().await.unwrap(), Some(at));
+        store.mark_read(at).await.unwrap();
+        assert!(store.unread().await.unwrap().is_none());
+    }
+
+    #[test]
+    fn test_feed_lines_and_events() {
+        let rss = r#"<rss version="2.0"><channel><title>Tech</title>
+            <item><title>Older</title><link>https://a/1</link><pubDate>Mon, 01 Jan 2024 08:00:00 GMT</pubDate></item>
+            <item><title>Newer</title><link>https://a/2</link><pubDate>Tue, 02 Jan 2024 08:00:00 GMT</pubDate></item>
+            </channel></rss>"#;
+        let feed = feed_rs::parser::parse(rss.as_bytes()).unwrap();
+        assert_eq!(feed_lines(&feed, 1), vec!["- Newer (https://a/2) — Tech".to_string()]);
+
+        let event = serde_json::json!({ "summary": "Offsite", "start": { "date": "2024-01-02" } });
+        assert_eq!(event_line(&event, Tz::UTC), "- all day Offsite");
+        let event = serde_json::json!({ "summary": "Standup", "start": { "dateTime": "2024-01-02T09:30:00Z" } });
+        assert_eq!(event_line(&event, chrono_tz::Europe::Paris), "- 10:30 Standup");
+
+        let config = BriefingConfig {
+            schedule: Some("0 30 7 * * Mon-Fri".to_string()),
+            channels: vec![BriefingChannel::Slack],
+            ..BriefingConfig::default()
+        };
+        assert!(config.validate().is_err());
+        assert_eq!(config.scheduled_task(Tz::UTC).unwrap().kind, TaskKind::Briefing);
+        assert!("pager".parse::<BriefingChannel>().is_err());
+    }
+}
@@ -0,0 +1,700 @@
+//! Certificate lifecycle for the runtime's TLS listener
+//!
+//! [`CertificateManager`] owns the certificate behind
+//! [`TlsConfig`](crate::tls::TlsConfig) and keeps it current. Where the
+//! certificate comes from depends on `api.tls_certificates.mode`:
+//!
+//! - `files` reads the PEM files at `api.tls_cert_path` and `api.tls_key_path`
+//!   and reloads them whenever they change on disk, e.g. after certbot renews.
+//! - `self_signed` generates a certificate for `domains` (localhost when
+//!   empty) and regenerates it before it expires. For development and LANs.
+//! - `acme` orders one from Let's Encrypt, or another ACME directory, with
+//!   the HTTP-01 challenge. The runtime answers the challenge itself on
+//!   `acme_http_port` while an order is open, so that port must be reachable
+//!   as port 80 of every domain.
+//!
+//! Generated certificates, keys and the ACME account are kept in `cache_dir`
+//! so restarts reuse them. The served certificate is swapped in place through
+//! [`CertResolver`]; connections already open keep the old one.
+
+use crate::tls::{load_certificates, load_private_key, TlsConfig, TlsError};
+use chrono::{DateTime, Datelike, TimeZone, Utc};
+use instant_acme::{
+    Account, AccountCredentials, AuthorizationStatus, ChallengeType, Identifier, NewAccount, NewOrder, OrderStatus,
+};
+use jamey_core::supervisor::Supervisor;
+use rustls::server::{ClientHello, ResolvesServerCert};
+use rustls::sign::CertifiedKey;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, SystemTime};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::sync::broadcast;
+
+/// Let's Encrypt's production directory
+pub const LETS_ENCRYPT: &str = "https://acme-v02.api.letsencrypt.org/directory";
+/// Let's Encrypt's staging directory, for trying a setup without hitting
+/// production rate limits
+pub const LETS_ENCRYPT_STAGING: &str = "https://acme-staging-v02.api.letsencrypt.org/directory";
+
+/// How often the renewal task looks at the certificate
+const CHECK_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// How long a self-signed certificate is valid for
+const SELF_SIGNED_DAYS: i64 = 365;
+
+/// Status polls for an ACME order before giving up, backing off from 1s to 30s
+const ORDER_POLL_ATTEMPTS: u32 = 20;
+
+const CHALLENGE_PATH: &str = "/.well-known/acme-challenge/";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CertificateMode {
+    /// Pre-provisioned PEM files, reloaded when they change
+    #[default]
+    Files,
+    /// A certificate generated and renewed by the runtime
+    SelfSigned,
+    /// A certificate ordered and renewed through ACME
+    Acme,
+}
+
+impl std::str::FromStr for CertificateMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "files" => Ok(Self::Files),
+            "self_signed" | "self-signed" => Ok(Self::SelfSigned),
+            "acme" => Ok(Self::Acme),
+            other => Err(format!("unknown certificate mode '{}' (expected files, self_signed or acme)", other)),
+        }
+    }
+}
+
+/// Where the HTTPS certificate comes from and when it is renewed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct CertificateConfig {
+    pub mode: CertificateMode,
+    /// Names the certificate covers; IP addresses are allowed for
+    /// self-signed certificates
+    pub domains: Vec<String>,
+    /// Contact address registered with the ACME account
+    pub acme_email: Option<String>,
+    pub acme_directory: String,
+    /// Port the HTTP-01 challenge is answered on while an order is open
+    pub acme_http_port: u16,
+    /// Where generated certificates, keys and the ACME account are kept
+    pub cache_dir: PathBuf,
+    /// Renew this many days before the certificate expires
+    pub renew_before_days: u32,
+}
+
+impl Default for CertificateConfig {
+    fn default() -> Self {
+        Self {
+            mode: CertificateMode::Files,
+            domains: Vec::new(),
+            acme_email: None,
+            acme_directory: LETS_ENCRYPT.to_string(),
+            acme_http_port: 80,
+            cache_dir: PathBuf::from("./data/certs"),
+            renew_before_days: 30,
+        }
+    }
+}
+
+impl CertificateConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.renew_before_days == 0 {
+            return Err("tls_certificates.renew_before_days must be at least 1".to_string());
+        }
+        if self.mode != CertificateMode::Acme {
+            return Ok(());
+        }
+        if self.domains.is_empty() {
+            return Err("tls_certificates.domains is required for ACME certificates".to_string());
+        }
+        if let Some(domain) = self.domains.iter().find(|d| d.contains('*') || d.parse::<std::net::IpAddr>().is_ok()) {
+            return Err(format!(
+                "tls_certificates.domains can't include '{}': HTTP-01 validation needs a plain host name",
+                domain
+            ));
+        }
+        if self.acme_email.as_deref().is_none_or(|e| !e.contains('@')) {
+            return Err("tls_certificates.acme_email is required for ACME certificates".to_string());
+        }
+        if !self.acme_directory.starts_with("https://") {
+            return Err("tls_certificates.acme_directory must be an https:// URL".to_string());
+        }
+        Ok(())
+    }
+
+    /// Names for a self-signed certificate
+    fn self_signed_names(&self) -> Vec<String> {
+        if self.domains.is_empty() {
+            vec!["localhost".to_string(), "127.0.0.1".to_string(), "::1".to_string()]
+        } else {
+            self.domains.clone()
+        }
+    }
+}
+
+/// Hands rustls whichever certificate is current. Install it with
+/// [`TlsConfig::build_server_config_with_resolver`].
+pub struct CertResolver {
+    current: RwLock<Arc<CertifiedKey>>,
+}
+
+impl CertResolver {
+    fn new(key: CertifiedKey) -> Self {
+        Self { current: RwLock::new(Arc::new(key)) }
+    }
+
+    fn swap(&self, key: CertifiedKey) {
+        *self.current.write().unwrap_or_else(|e| e.into_inner()) = Arc::new(key);
+    }
+
+    /// The certificate new handshakes get
+    pub fn current(&self) -> Arc<CertifiedKey> {
+        Arc::clone(&self.current.read().unwrap_or_else(|e| e.into_inner()))
+    }
+}
+
+impl ResolvesServerCert for CertResolver {
+    fn resolve(&self, _client_hello: ClientHello) -> Option<Arc<CertifiedKey>> {
+        Some(self.current())
+    }
+}
+
+#[derive(Debug, Default)]
+struct Installed {
+    expires_at: Option<DateTime<Utc>>,
+    /// Modification time of the certificate file last loaded
+    modified: Option<SystemTime>,
+}
+
+/// Issues, loads and renews the HTTPS certificate
+pub struct CertificateManager {
+    tls: TlsConfig,
+    config: CertificateConfig,
+    /// Host the ACME challenge listener binds to
+    host: String,
+    resolver: Arc<CertResolver>,
+    installed: Mutex<Installed>,
+}
+
+impl CertificateManager {
+    /// Load or obtain a certificate. An ACME order that fails here leaves a
+    /// temporary self-signed certificate in place, and the renewal task
+    /// keeps trying.
+    pub async fn new(tls: TlsConfig, config: CertificateConfig, host: &str) -> Result<Self, TlsError> {
+        config.validate().map_err(TlsError::ConfigError)?;
+        let (key, installed) = match config.mode {
+            CertificateMode::Files => {
+                tls.validate()?;
+                load_key_pair(&tls.cert_path, &tls.key_path)?
+            }
+            CertificateMode::SelfSigned | CertificateMode::Acme => {
+                match load_key_pair(&tls.cert_path, &tls.key_path) {
+                    Ok(loaded) => loaded,
+                    Err(e) => {
+                        if tls.cert_path.exists() {
+                            tracing::warn!("Replacing unreadable certificate {}: {}", tls.cert_path.display(), e);
+                        }
+                        // Placeholder until the first renewal writes a real one
+                        let (cert_pem, key_pem) = self_signed(&config.self_signed_names(), SELF_SIGNED_DAYS)?;
+                        (certified_key(&cert_pem, &key_pem)?, Installed::default())
+                    }
+                }
+            }
+        };
+
+        let manager = Self {
+            tls,
+            config,
+            host: host.to_string(),
+            resolver: Arc::new(CertResolver::new(key)),
+            installed: Mutex::new(installed),
+        };
+        if manager.config.mode != CertificateMode::Files && manager.renewal_due(Utc::now()) {
+            if let Err(e) = manager.renew().await {
+                if manager.config.mode == CertificateMode::SelfSigned {
+                    return Err(e);
+                }
+                tracing::warn!("ACME certificate order failed, serving a temporary self-signed certificate: {}", e);
+            }
+        }
+        Ok(manager)
+    }
+
+    /// A rustls config that always serves the current certificate
+    pub fn server_config(&self) -> anyhow::Result<Arc<rustls::ServerConfig>> {
+        self.tls.build_server_config_with_resolver(Arc::clone(&self.resolver) as Arc<dyn ResolvesServerCert>)
+    }
+
+    pub fn resolver(&self) -> Arc<CertResolver> {
+        Arc::clone(&self.resolver)
+    }
+
+    pub fn mode(&self) -> CertificateMode {
+        self.config.mode
+    }
+
+    /// When the served certificate expires; `None` while a temporary one is
+    /// in place
+    pub fn expires_at(&self) -> Option<DateTime<Utc>> {
+        self.installed.lock().unwrap_or_else(|e| e.into_inner()).expires_at
+    }
+
+    /// Whether the certificate is inside its renewal window at `now`
+    pub fn renewal_due(&self, now: DateTime<Utc>) -> bool {
+        match self.expires_at() {
+            Some(expires_at) => expires_at - chrono::Duration::days(i64::from(self.config.renew_before_days)) <= now,
+            None => true,
+        }
+    }
+
+    /// Renew or reload the certificate if it is due; returns whether a new
+    /// one was installed
+    pub async fn renew_if_due(&self) -> Result<bool, TlsError> {
+        if self.config.mode == CertificateMode::Files {
+            return self.reload_if_changed();
+        }
+        if !self.renewal_due(Utc::now()) {
+            return Ok(false);
+        }
+        self.renew().await?;
+        Ok(true)
+    }
+
+    /// Obtain a new certificate, write it to the cache and serve it
+    async fn renew(&self) -> Result<(), TlsError> {
+        let (cert_pem, key_pem) = match self.config.mode {
+            CertificateMode::Files => return Ok(()),
+            CertificateMode::SelfSigned => self_signed(&self.config.self_signed_names(), SELF_SIGNED_DAYS)?,
+            CertificateMode::Acme => self.order().await?,
+        };
+        write_private(&self.tls.key_path, key_pem.as_bytes())?;
+        write_private(&self.tls.cert_path, cert_pem.as_bytes())?;
+        let (key, installed) = load_key_pair(&self.tls.cert_path, &self.tls.key_path)?;
+        self.install(key, installed);
+        Ok(())
+    }
+
+    fn reload_if_changed(&self) -> Result<bool, TlsError> {
+        let modified = std::fs::metadata(&self.tls.cert_path).and_then(|m| m.modified()).ok();
+        let (unchanged, expires_at) = {
+            let installed = self.installed.lock().unwrap_or_else(|e| e.into_inner());
+            (modified.is_none() || modified == installed.modified, installed.expires_at)
+        };
+        if unchanged {
+            if self.renewal_due(Utc::now()) {
+                tracing::warn!(
+                    "TLS certificate {} expires {}; renew it, it will be picked up automatically",
+                    self.tls.cert_path.display(),
+                    expires_at.map_or_else(|| "soon".to_string(), |at| at.to_rfc3339())
+                );
+            }
+            return Ok(false);
+        }
+        let (key, installed) = load_key_pair(&self.tls.cert_path, &self.tls.key_path)?;
+        self.install(key, installed);
+        Ok(true)
+    }
+
+    fn install(&self, key: CertifiedKey, installed: Installed) {
+        tracing::info!(
+            "Serving TLS certificate from {} (expires {})",
+            self.tls.cert_path.display(),
+            installed.expires_at.map_or_else(|| "unknown".to_string(), |at| at.to_rfc3339())
+        );
+        self.resolver.swap(key);
+        *self.installed.lock().unwrap_or_else(|e| e.into_inner()) = installed;
+    }
+
+    /// Order a certificate for every configured domain
+    async fn order(&self) -> Result<(String, String), TlsError> {
+        let account = self.acme_account().await?;
+        let identifiers: Vec<Identifier> = self.config.domains.iter().cloned().map(Identifier::Dns).collect();
+        let mut order = account
+            .new_order(&NewOrder { identifiers: &identifiers })
+            .await
+            .map_err(acme_error)?;
+
+        let mut tokens = HashMap::new();
+        let mut ready = Vec::new();
+        for authorization in order.authorizations().await.map_err(acme_error)? {
+            match authorization.status {
+                AuthorizationStatus::Pending => {}
+                AuthorizationStatus::Valid => continue,
+                status => {
+                    return Err(TlsError::AcmeError(format!(
+                        "authorization for {:?} is {:?}",
+                        authorization.identifier, status
+                    )))
+                }
+            }
+            let challenge = authorization
+                .challenges
+                .iter()
+                .find(|c| c.r#type == ChallengeType::Http01)
+                .ok_or_else(|| {
+                    TlsError::AcmeError(format!("no HTTP-01 challenge offered for {:?}", authorization.identifier))
+                })?;
+            tokens.insert(challenge.token.clone(), order.key_authorization(challenge).as_str().to_string());
+            ready.push(challenge.url.clone());
+        }
+
+        // Held until the order is ready; dropping it stops the listener
+        let _challenges = match ready.is_empty() {
+            true => None,
+            false => Some(ChallengeServer::start(&self.host, self.config.acme_http_port, tokens).await?),
+        };
+        for url in &ready {
+            order.set_challenge_ready(url).await.map_err(acme_error)?;
+        }
+
+        let mut delay = Duration::from_secs(1);
+        let mut attempts = 0;
+        loop {
+            let status = order.refresh().await.map_err(acme_error)?.status;
+            match status {
+                OrderStatus::Ready => break,
+                OrderStatus::Invalid => {
+                    return Err(TlsError::AcmeError(
+                        "order is invalid; check that every domain reaches this host on port 80".to_string(),
+                    ))
+                }
+                _ => {}
+            }
+            attempts += 1;
+            if attempts >= ORDER_POLL_ATTEMPTS {
+                return Err(TlsError::AcmeError(format!("order still {:?} after {} checks", status, attempts)));
+            }
+            tokio::time::sleep(delay).await;
+            delay = (delay * 2).min(Duration::from_secs(30));
+        }
+
+        let (csr, key_pem) = signing_request(&self.config.domains)?;
+        order.finalize(&csr).await.map_err(acme_error)?;
+        for _ in 0..ORDER_POLL_ATTEMPTS {
+            if let Some(chain) = order.certificate().await.map_err(acme_error)? {
+                tracing::info!("Obtained ACME certificate for {}", self.config.domains.join(", "));
+                return Ok((chain, key_pem));
+            }
+            tokio::time::sleep(Duration::from_secs(1)).await;
+        }
+        Err(TlsError::AcmeError("certificate was not issued in time".to_string()))
+    }
+
+    /// The stored ACME account for this directory, registering one if needed
+    async fn acme_account(&self) -> Result<Account, TlsError> {
+        // Keyed by directory so switching from staging doesn't reuse its account
+        let directory = hex::encode(&Sha256::digest(self.config.acme_directory.as_bytes())[..6]);
+        let path = self.config.cache_dir.join(format!("acme-account-{}.json", directory));
+        if let Ok(json) = tokio::fs::read_to_string(&path).await {
+            let credentials: AccountCredentials = serde_json::from_str(&json)
+                .map_err(|e| TlsError::AcmeError(format!("{} is corrupt: {}", path.display(), e)))?;
+            return Account::from_credentials(credentials).await.map_err(acme_error);
+        }
+
+        let contact = format!("mailto:{}", self.config.acme_email.as_deref().unwrap_or_default());
+        let (account, credentials) = Account::create(
+            &NewAccount {
+                contact: &[&contact],
+                terms_of_service_agreed: true,
+                only_return_existing: false,
+            },
+            &self.config.acme_directory,
+            None,
+        )
+        .await
+        .map_err(acme_error)?;
+        let json = serde_json::to_vec_pretty(&credentials).map_err(|e| TlsError::AcmeError(e.to_string()))?;
+        write_private(&path, &json)?;
+        tracing::info!("Registered ACME account with {}", self.config.acme_directory);
+        Ok(account)
+    }
+}
+
+/// Check the certificate every hour until shutdown, renewing or reloading it
+/// when due
+pub(crate) fn spawn_certificate_renewal(
+    supervisor: &Supervisor,
+    manager: Arc<CertificateManager>,
+    shutdown: broadcast::Receiver<()>,
+) {
+    supervisor.spawn("certificate_renewal", move || {
+        let manager = Arc::clone(&manager);
+        let mut shutdown = shutdown.resubscribe();
+        async move {
+            let mut ticker = tokio::time::interval(CHECK_INTERVAL);
+            ticker.tick().await;
+            loop {
+                tokio::select! {
+                    _ = shutdown.recv() => break,
+                    _ = ticker.tick() => {
+                        if let Err(e) = manager.renew_if_due().await {
+                            tracing::warn!("TLS certificate renewal failed, retrying in an hour: {}", e);
+                        }
+                    }
+                }
+            }
+        }
+    });
+}
+
+/// Answers HTTP-01 challenges until dropped
+struct ChallengeServer {
+    task: tokio::task::JoinHandle<()>,
+}
+
+impl ChallengeServer {
+    async fn start(host: &str, port: u16, tokens: HashMap<String, String>) -> Result<Self, TlsError> {
+        let listener = tokio::net::TcpListener::bind((host, port)).await.map_err(|e| {
+            TlsError::AcmeError(format!("can't bind port {} for the HTTP-01 challenge: {}", port, e))
+        })?;
+        let tokens = Arc::new(tokens);
+        let task = tokio::spawn(async move {
+            loop {
+                let mut stream = match listener.accept().await {
+                    Ok((stream, _)) => stream,
+                    Err(e) => {
+                        tracing::debug!("ACME challenge listener: {}", e);
+                        tokio::time::sleep(Duration::from_millis(100)).await;
+                        continue;
+                    }
+                };
+                let tokens = Arc::clone(&tokens);
+                tokio::spawn(async move {
+                    let mut buf = [0u8; 4096];
+                    let n = match tokio::time::timeout(Duration::from_secs(10), stream.read(&mut buf)).await {
+                        Ok(Ok(n)) => n,
+                        _ => return,
+                    };
+                    let request = String::from_utf8_lossy(&buf[..n]);
+                    let body = request
+                        .lines()
+                        .next()
+                        .and_then(|line| line.strip_prefix("GET "))
+                        .and_then(|rest| rest.split(' ').next())
+                        .and_then(|path| path.strip_prefix(CHALLENGE_PATH))
+                        .and_then(|token| tokens.get(token));
+                    let response = match body {
+                        Some(body) => format!(
+                            "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                            body.len(),
+                            body
+                        ),
+                        None => "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string(),
+                    };
+                    let _ = stream.write_all(response.as_bytes()).await;
+                });
+            }
+        });
+        Ok(Self { task })
+    }
+}
+
+impl Drop for ChallengeServer {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+fn acme_error(e: instant_acme::Error) -> TlsError {
+    TlsError::AcmeError(e.to_string())
+}
+
+fn load_key_pair(cert_path: &Path, key_path: &Path) -> Result<(CertifiedKey, Installed), TlsError> {
+    let certs = load_certificates(cert_path)?;
+    let leaf = certs
+        .first()
+        .ok_or_else(|| TlsError::InvalidCertificate(format!("no certificates in {}", cert_path.display())))?;
+    let expires_at = expiry(&leaf.0)?;
+    let key = rustls::sign::any_supported_type(&load_private_key(key_path)?)
+        .map_err(|e| TlsError::InvalidPrivateKey(e.to_string()))?;
+    let installed = Installed {
+        expires_at: Some(expires_at),
+        modified: std::fs::metadata(cert_path).and_then(|m| m.modified()).ok(),
+    };
+    Ok((CertifiedKey::new(certs, key), installed))
+}
+
+fn certified_key(cert_pem: &str, key_pem: &str) -> Result<CertifiedKey, TlsError> {
+    let certs = rustls_pemfile::certs(&mut cert_pem.as_bytes())
+        .map_err(|e| TlsError::InvalidCertificate(e.to_string()))?
+        .into_iter()
+        .map(rustls::Certificate)
+        .collect();
+    let key = rustls_pemfile::pkcs8_private_keys(&mut key_pem.as_bytes())
+        .map_err(|e| TlsError::InvalidPrivateKey(e.to_string()))?
+        .into_iter()
+        .next()
+        .ok_or_else(|| TlsError::InvalidPrivateKey("no private key generated".to_string()))?;
+    let key = rustls::sign::any_supported_type(&rustls::PrivateKey(key))
+        .map_err(|e| TlsError::InvalidPrivateKey(e.to_string()))?;
+    Ok(CertifiedKey::new(certs, key))
+}
+
+/// `notAfter` of a DER certificate
+fn expiry(der: &[u8]) -> Result<DateTime<Utc>, TlsError> {
+    let (_, cert) = x509_parser::parse_x509_certificate(der)
+        .map_err(|e| TlsError::InvalidCertificate(e.to_string()))?;
+    Utc.timestamp_opt(cert.validity().not_after.timestamp(), 0)
+        .single()
+        .ok_or_else(|| TlsError::InvalidCertificate("expiry out of range".to_string()))
+}
+
+fn key_pair_params(names: &[String]) -> rcgen::CertificateParams {
+    let mut params = rcgen::CertificateParams::default();
+    params.subject_alt_names = names
+        .iter()
+        .map(|name| match name.parse() {
+            Ok(ip) => rcgen::SanType::IpAddress(ip),
+            Err(_) => rcgen::SanType::DnsName(name.clone()),
+        })
+        .collect();
+    params.distinguished_name = rcgen::DistinguishedName::new();
+    if let Some(name) = names.first() {
+        params.distinguished_name.push(rcgen::DnType::CommonName, name.clone());
+    }
+    params
+}
+
+/// A self-signed certificate and its PKCS#8 key, both PEM
+fn self_signed(names: &[String], days: i64) -> Result<(String, String), TlsError> {
+    let mut params = key_pair_params(names);
+    let now = Utc::now();
+    let until = now + chrono::Duration::days(days);
+    params.not_before = rcgen::date_time_ymd(now.year(), now.month() as u8, now.day() as u8);
+    params.not_after = rcgen::date_time_ymd(until.year(), until.month() as u8, until.day() as u8);
+    let cert = rcgen::Certificate::from_params(params).map_err(|e| TlsError::GenerationError(e.to_string()))?;
+    let cert_pem = cert.serialize_pem().map_err(|e| TlsError::GenerationError(e.to_string()))?;
+    Ok((cert_pem, cert.serialize_private_key_pem()))
+}
+
+/// A DER signing request for `names` and the PEM key it was made with
+fn signing_request(names: &[String]) -> Result<(Vec<u8>, String), TlsError> {
+    let cert = rcgen::Certificate::from_params(key_pair_params(names))
+        .map_err(|e| TlsError::GenerationError(e.to_string()))?;
+    let csr = cert.serialize_request_der().map_err(|e| TlsError::GenerationError(e.to_string()))?;
+    Ok((csr, cert.serialize_private_key_pem()))
+}
+
+/// Write `contents` readable only by the owner, creating parent directories
+fn write_private(path: &Path, contents: &[u8]) -> Result<(), TlsError> {
+    let io_error = |e: std::io::Error| TlsError::ConfigError(format!("can't write {}: {}", path.display(), e));
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(io_error)?;
+    }
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    std::io::Write::write_all(&mut options.open(path).map_err(io_error)?, contents).map_err(io_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn tls_in(dir: &Path) -> TlsConfig {
+        TlsConfig::new(dir.join("cert.pem"), dir.join("key.pem"))
+    }
+
+    #[tokio::test]
+    async fn test_self_signed_lifecycle() {
+        let temp = TempDir::new().unwrap();
+        let mut config = CertificateConfig {
+            mode: CertificateMode::SelfSigned,
+            cache_dir: temp.path().to_path_buf(),
+            ..Default::default()
+        };
+        let manager = CertificateManager::new(tls_in(temp.path()), config.clone(), "127.0.0.1").await.unwrap();
+        let expires_at = manager.expires_at().unwrap();
+        assert!(expires_at > Utc::now() + chrono::Duration::days(SELF_SIGNED_DAYS - 2));
+        assert!(!manager.renewal_due(Utc::now()));
+        assert!(!manager.renew_if_due().await.unwrap());
+        assert!(manager.server_config().is_ok());
+
+        // A restart reuses the stored certificate
+        let first = manager.resolver().current().cert[0].clone();
+        let restarted = CertificateManager::new(tls_in(temp.path()), config.clone(), "127.0.0.1").await.unwrap();
+        assert_eq!(restarted.resolver().current().cert[0], first);
+
+        // Inside the renewal window a new one is generated and swapped in
+        config.renew_before_days = SELF_SIGNED_DAYS as u32 + 1;
+        let renewing = CertificateManager::new(tls_in(temp.path()), config, "127.0.0.1").await.unwrap();
+        let resolver = renewing.resolver();
+        let before = resolver.current().cert[0].clone();
+        assert!(renewing.renew_if_due().await.unwrap());
+        assert_ne!(resolver.current().cert[0], before);
+    }
+
+    #[tokio::test]
+    async fn test_files_reload_on_change() {
+        let temp = TempDir::new().unwrap();
+        let (cert_pem, key_pem) = self_signed(&["example.test".to_string()], 30).unwrap();
+        write_private(&temp.path().join("cert.pem"), cert_pem.as_bytes()).unwrap();
+        write_private(&temp.path().join("key.pem"), key_pem.as_bytes()).unwrap();
+        let manager = CertificateManager::new(tls_in(temp.path()), CertificateConfig::default(), "127.0.0.1")
+            .await
+            .unwrap();
+        assert!(!manager.renew_if_due().await.unwrap());
+
+        let (cert_pem, key_pem) = self_signed(&["example.test".to_string()], 90).unwrap();
+        write_private(&temp.path().join("key.pem"), key_pem.as_bytes()).unwrap();
+        write_private(&temp.path().join("cert.pem"), cert_pem.as_bytes()).unwrap();
+        let file = std::fs::File::options().write(true).open(temp.path().join("cert.pem")).unwrap();
+        file.set_modified(SystemTime::now() + Duration::from_secs(5)).unwrap();
+        assert!(manager.renew_if_due().await.unwrap());
+        assert!(manager.expires_at().unwrap() > Utc::now() + chrono::Duration::days(80));
+    }
+
+    #[tokio::test]
+    async fn test_challenge_server() {
+        let probe = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = probe.local_addr().unwrap().port();
+        drop(probe);
+        let tokens = HashMap::from([("abc".to_string(), "abc.thumbprint".to_string())]);
+        let server = ChallengeServer::start("127.0.0.1", port, tokens).await.unwrap();
+
+        let fetch = |path: &'static str| async move {
+            let mut stream = tokio::net::TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+            stream.write_all(format!("GET {} HTTP/1.1\r\nHost: example.test\r\n\r\n", path).as_bytes()).await.unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).await.unwrap();
+            response
+        };
+        let found = fetch("/.well-known/acme-challenge/abc").await;
+        assert!(found.starts_with("HTTP/1.1 200"));
+        assert!(found.ends_with("\r\n\r\nabc.thumbprint"));
+        assert!(fetch("/.well-known/acme-challenge/other").await.starts_with("HTTP/1.1 404"));
+        drop(server);
+    }
+
+    #[test]
+    fn test_config_validation() {
+        assert!(CertificateConfig::default().validate().is_ok());
+        let acme = CertificateConfig {
+            mode: CertificateMode::Acme,
+            domains: vec!["jamey.example.com".to_string()],
+            acme_email: Some("ops@example.com".to_string()),
+            ..Default::default()
+        };
+        assert!(acme.validate().is_ok());
+        assert!(CertificateConfig { acme_email: None, ..acme.clone() }.validate().is_err());
+        assert!(CertificateConfig { domains: vec!["*.example.com".to_string()], ..acme.clone() }.validate().is_err());
+        assert!(CertificateConfig { domains: Vec::new(), ..acme }.validate().is_err());
+        assert_eq!("self-signed".parse::<CertificateMode>(), Ok(CertificateMode::SelfSigned));
+    }
+}
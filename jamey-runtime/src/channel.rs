@@ -0,0 +1,85 @@
+//! Chat turns for messaging bridges
+//!
+//! The Telegram and Matrix bots keep one saved session per chat or room
+//! and answer each message through [`run_turn`].
+
+use crate::chat::TurnEvent;
+use crate::session_store::{SessionRecord, SessionStoreError};
+use crate::state::RuntimeState;
+use crate::summarize;
+use jamey_protocol::Message;
+use std::future::Future;
+use uuid::Uuid;
+
+/// Forget the conversation saved in `session_id`
+pub(crate) async fn reset(state: &RuntimeState, session_id: Uuid) -> anyhow::Result<()> {
+    match state.session_store.delete(session_id).await {
+        Ok(()) | Err(SessionStoreError::NotFound(_)) => Ok(()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Answer `question` in the session `session_id`, saving it under `title`
+/// the first time, and return the reply with any tool failures appended.
+/// `notify` posts approval requests to the chat while the turn waits.
+pub(crate) async fn run_turn<F, Fut>(
+    state: &RuntimeState,
+    session_id: Uuid,
+    title: String,
+    question: Message,
+    notify: F,
+) -> anyhow::Result<String>
+where
+    F: Fn(String) -> Fut,
+    Fut: Future<Output = ()>,
+{
+    let mut record = match state.session_store.load(session_id).await {
+        Ok(record) => record,
+        Err(SessionStoreError::NotFound(_)) => SessionRecord::new(session_id),
+        Err(e) => return Err(e.into()),
+    };
+    if record.title.is_empty() {
+        record.title = title;
+    }
+
+    // Earlier turns that were already summarized stay in the transcript but
+    // aren't sent again
+    let start = record.messages.iter().rposition(summarize::is_summary).unwrap_or(0);
+    let mut history = record.messages[start..].to_vec();
+    history.push(question.clone());
+
+    let mut turn = state.stream_session_turn(session_id, history);
+    let mut notices = Vec::new();
+    let reply = loop {
+        match turn.next().await {
+            Some(TurnEvent::Summarized(compaction)) => {
+                record.messages.insert(start + compaction.replaced, compaction.summary);
+            }
+            Some(TurnEvent::AwaitingApproval(request)) => {
+                let short = &request.id.to_string()[..8];
+                notify(format!("Waiting for approval {} (run `jamey approvals approve {}`)", short, short)).await;
+            }
+            Some(TurnEvent::ToolResult(result)) => {
+                if let Some(error) = result.error {
+                    notices.push(format!("{} failed: {}", result.name, error));
+                }
+            }
+            Some(TurnEvent::Completed(reply)) => break reply,
+            Some(TurnEvent::Failed(e)) => anyhow::bail!(e),
+            Some(_) => {}
+            None => anyhow::bail!("The turn ended without a reply"),
+        }
+    };
+
+    let text = if notices.is_empty() {
+        reply.content.clone()
+    } else {
+        format!("{}\n\n({})", reply.content, notices.join("; "))
+    };
+    record.messages.push(question);
+    record.messages.push(reply);
+    record.model = Some(state.config.llm.openrouter_default_model.clone());
+    record.updated_at = chrono::Utc::now();
+    state.session_store.save(&record).await?;
+    Ok(text)
+}
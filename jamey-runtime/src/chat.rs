@@ -0,0 +1,1182 @@
+//! Streaming conversation turns
+//!
+//! Drives a single user turn against the LLM: tokens are forwarded as they
+//! arrive, tool calls are executed through the hybrid orchestrator and their
+//! results fed back to the model until it produces a final answer. Memories
+//! recalled for the question go in the prompt and are cited on the reply.
+//! The session's persona supplies the system prompt and may narrow the
+//! tools offered; the model is the persona's or the one routing rules pick.
+//! The prompt also gives the local date and time in the session's
+//! [timezone](crate::locale), so relative times resolve where the user is.
+//! When output guardrails apply to the session the reply is held back until
+//! it has passed them. A session attached to a workspace has its tools,
+//! file paths and recalled memories confined to that project.
+//! Each turn waits for a slot in the runtime's [request queue](crate::queue)
+//! before it starts. In a [cluster](crate::cluster), a session's tool calls
+//! hold its lock while they run. When the provider fails or the budget runs
+//! low, interactive turns [degrade](crate::degradation) rather than fail.
+
+use crate::approvals::{ApprovalQueue, ApprovalRequest, ApprovalStatus};
+use crate::attachments::AttachmentStore;
+use crate::cluster::Cluster;
+use crate::degradation::{Degradation, DegradationLevel};
+use crate::events::{self, EventBus};
+use crate::feedback::PreferenceStore;
+use crate::generation::{self, GenerationStrategy};
+use crate::guardrails::{self, Guardrails, Strictness};
+use crate::persona::{Persona, PersonaStore};
+use crate::prompt_trace::{PromptTrace, PromptTraceLog, TracedCall};
+use crate::hybrid_orchestrator::HybridOrchestrator;
+use crate::inflight::{WorkGuard, WorkKind};
+use crate::locale::TimeContext;
+use crate::queue::{Priority, QueueFull};
+use crate::recall::{self, Recalled};
+use crate::rollback::UndoLog;
+use crate::routing::{ModelRouter, RouteRequest, RouteTask};
+use crate::state::RuntimeState;
+use crate::status::{self, BudgetTracker};
+use crate::summarize::{self, Compaction};
+use crate::usage::{ToolRecord, UsageLog, UsageRecord};
+use crate::workspace::Workspace;
+use chrono::Utc;
+use jamey_core::memory::{Memory, MemoryStore, MemoryType, PostgresMemoryStore};
+use jamey_protocol::{Message, Role, TokenUsage, ToolCall, ToolResult};
+use jamey_providers::openrouter::{self, ChatRequest, LlmProvider, OpenRouterProvider, StreamEvent, Tool, DEFAULT_EMBEDDING_MODEL};
+use jamey_tools::connector::ToolPolicy;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use tokio::sync::{mpsc, Mutex};
+use tokio::task::JoinHandle;
+use uuid::Uuid;
+
+/// Model calls allowed to request tools before it must answer in text
+const MAX_TOOL_ROUNDS: usize = 5;
+
+/// How long a tool call waits for someone to approve it
+const APPROVAL_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(600);
+
+
+/// Progress of a streaming turn
+#[derive(Debug, Clone)]
+pub enum TurnEvent {
+    /// The history was over the context budget and its oldest messages were
+    /// summarized for this turn; callers keeping the history should
+    /// [`apply`](Compaction::apply) this so later turns start from it
+    Summarized(Compaction),
+    /// Assistant text as it is generated
+    Token(String),
+    /// The model asked for a tool; the matching `ToolResult` follows
+    ToolCall(ToolCall),
+    /// The tool requires approval and is waiting in the approval queue
+    AwaitingApproval(ApprovalRequest),
+    ToolResult(ToolResult),
+    /// Usage summed over every model call made during the turn
+    Usage {
+        usage: TokenUsage,
+        cost_usd: Option<f64>,
+    },
+    /// Final assistant reply; the last event of a successful turn
+    Completed(Message),
+    Failed(String),
+}
+
+/// Handle to an in-flight turn
+pub struct ChatTurn {
+    id: Uuid,
+    events: mpsc::Receiver<TurnEvent>,
+    task: JoinHandle<()>,
+}
+
+impl ChatTurn {
+    /// Identifies the turn to [`RuntimeState::rollback`]
+    pub fn id(&self) -> Uuid {
+        self.id
+    }
+
+    /// Next event, or `None` once the turn has finished or been cancelled
+    pub async fn next(&mut self) -> Option<TurnEvent> {
+        self.events.recv().await
+    }
+
+    /// Abort the turn, including any running tool call. The session is
+    /// unaffected and can start a new turn immediately.
+    pub fn cancel(&self) {
+        self.task.abort();
+    }
+}
+
+impl Drop for ChatTurn {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+impl RuntimeState {
+    /// Run one conversation turn over `history` (oldest first, ending with
+    /// the new user message), streaming events as they happen
+    pub fn stream_turn(&self, history: Vec<Message>) -> ChatTurn {
+        self.start_turn(None, history, Priority::Interactive).unwrap_or_else(rejected_turn)
+    }
+
+    /// Like [`stream_turn`](Self::stream_turn), attributing usage and
+    /// approval requests to `session_id`
+    pub fn stream_session_turn(&self, session_id: Uuid, history: Vec<Message>) -> ChatTurn {
+        self.start_turn(Some(session_id), history, Priority::Interactive).unwrap_or_else(rejected_turn)
+    }
+
+    /// Like [`stream_session_turn`](Self::stream_session_turn), but a full
+    /// request queue is reported here rather than as a failed turn, for
+    /// callers that answer it with a 429
+    pub fn try_stream_session_turn(&self, session_id: Uuid, history: Vec<Message>) -> Result<ChatTurn, QueueFull> {
+        self.start_turn(Some(session_id), history, Priority::Interactive)
+    }
+
+    /// A turn run as background work, such as an evaluation: it gives way
+    /// to interactive turns and waits for room in a full queue
+    pub fn stream_background_turn(&self, history: Vec<Message>) -> ChatTurn {
+        self.start_turn(None, history, Priority::Background).unwrap_or_else(rejected_turn)
+    }
+
+    fn start_turn(&self, session_id: Option<Uuid>, history: Vec<Message>, priority: Priority) -> Result<ChatTurn, QueueFull> {
+        let ticket = match priority {
+            Priority::Interactive => Some(self.request_queue.enter(priority, session_id)?),
+            Priority::Background => None,
+        };
+        let queue = self.request_queue.clone();
+        // A session in use must not be archived as idle
+        if let Some(id) = session_id {
+            self.session_manager.touch(id);
+        }
+        let (tx, events) = mpsc::channel(256);
+        let id = Uuid::new_v4();
+        let ctx = TurnContext {
+            turn_id: id,
+            work: self.in_flight.begin(
+                WorkKind::Turn,
+                if priority == Priority::Interactive { "chat" } else { "background" },
+                session_id,
+            ),
+            llm: Arc::clone(&self.llm_provider),
+            orchestrator: Arc::clone(&self.hybrid_orchestrator),
+            memory_store: Arc::clone(&self.memory_store),
+            approvals: Arc::clone(&self.approval_queue),
+            budget: Arc::clone(&self.budget),
+            usage_log: Arc::clone(&self.usage_log),
+            traces: Some(Arc::clone(&self.prompt_traces)).filter(|traces| traces.samples(id)),
+            attachments: Arc::clone(&self.attachment_store),
+            preferences: Arc::clone(&self.preference_store),
+            personas: Arc::clone(&self.persona_store),
+            undo_log: Arc::clone(&self.undo_log),
+            router: Arc::clone(&self.router),
+            cluster: self.cluster.clone(),
+            degradation: Arc::clone(&self.degradation),
+            degrade: priority == Priority::Interactive,
+            budget_degraded: false,
+            generation: self.config.llm.generation.clone(),
+            guardrails: Arc::clone(&self.guardrails),
+            strictness: session_id
+                .and_then(|id| self.session_manager.strictness(id))
+                .unwrap_or_else(|| self.guardrails.strictness()),
+            persona: session_id.and_then(|id| self.session_manager.persona(id)),
+            default_persona: self.config.default_persona.clone(),
+            workspace: session_id.and_then(|id| self.session_manager.workspace(id)),
+            time: session_id
+                .and_then(|id| self.session_manager.time(id))
+                .unwrap_or_else(|| self.time.clone()),
+            events: self.events.clone(),
+            session_id,
+            user: session_id.and_then(|id| self.session_manager.user(id)),
+            tool_policy: session_id
+                .map(|id| self.session_manager.tool_policy(id))
+                .unwrap_or_default(),
+            model: self.config.llm.openrouter_default_model.clone(),
+            context_budget: self.config.llm.context_budget_tokens,
+            context_memories: self.config.memory.context_memories,
+            pinned_budget: self.config.memory.pinned_budget_tokens,
+            min_similarity: self.config.memory.vector_similarity_threshold,
+        };
+
+        let cancel = ctx.work.cancellation();
+        let task = tokio::spawn(async move {
+            let bus = ctx.events.clone();
+            let failed = tx.clone();
+            let turn = async move {
+                let _permit = match ticket {
+                    Some(ticket) => ticket.ready().await,
+                    None => queue.background().await,
+                };
+                let ctx = ctx.prepare(&history).await;
+                if let Err(e) = run_turn(&ctx, &history, &tx).await {
+                    if ctx.degrade && ctx.degradation.enabled() && !tx.is_closed() {
+                        let _ = answer_degraded(&ctx, &history, &e.to_string(), &tx).await;
+                        return;
+                    }
+                    ctx.events.publish(events::TURN_FAILED, ctx.session_id, serde_json::json!({ "error": e.to_string() }));
+                    let _ = tx.send(TurnEvent::Failed(e.to_string())).await;
+                }
+            };
+            tokio::select! {
+                biased;
+                _ = cancel.cancelled() => {
+                    let error = "Turn cancelled";
+                    bus.publish(events::TURN_FAILED, session_id, serde_json::json!({ "error": error }));
+                    let _ = failed.send(TurnEvent::Failed(error.to_string())).await;
+                }
+                _ = turn => {}
+            }
+        });
+
+        Ok(ChatTurn { id, events, task })
+    }
+}
+
+/// A turn the queue turned away: it fails straight away
+fn rejected_turn(full: QueueFull) -> ChatTurn {
+    let (tx, events) = mpsc::channel(1);
+    let task = tokio::spawn(async move {
+        let _ = tx.send(TurnEvent::Failed(full.to_string())).await;
+    });
+    ChatTurn { id: Uuid::new_v4(), events, task }
+}
+
+/// Runtime handles a turn holds on to while it runs
+struct TurnContext {
+    turn_id: Uuid,
+    /// The turn's entry in the task inspector; model calls and tools
+    /// register under it
+    work: WorkGuard,
+    llm: Arc<OpenRouterProvider>,
+    orchestrator: Arc<Mutex<HybridOrchestrator>>,
+    memory_store: Arc<PostgresMemoryStore>,
+    approvals: Arc<ApprovalQueue>,
+    budget: Arc<BudgetTracker>,
+    usage_log: Arc<UsageLog>,
+    /// Where this turn's model calls are traced, when it was sampled
+    traces: Option<Arc<PromptTraceLog>>,
+    attachments: Arc<AttachmentStore>,
+    preferences: Arc<PreferenceStore>,
+    personas: Arc<PersonaStore>,
+    undo_log: Arc<UndoLog>,
+    router: Arc<ModelRouter>,
+    cluster: Option<Arc<Cluster>>,
+    degradation: Arc<Degradation>,
+    /// Whether the turn may end on a cached or canned reply; background
+    /// work would rather fail
+    degrade: bool,
+    /// Moved to a fallback model by `prepare` because the budget is tight
+    budget_degraded: bool,
+    generation: GenerationStrategy,
+    guardrails: Arc<Guardrails>,
+    strictness: Strictness,
+    /// The session's persona; resolved to the default by `prepare`
+    persona: Option<Persona>,
+    default_persona: Option<String>,
+    workspace: Option<Workspace>,
+    /// The session's timezone and locale, or the runtime's
+    time: TimeContext,
+    events: EventBus,
+    session_id: Option<Uuid>,
+    user: Option<String>,
+    tool_policy: ToolPolicy,
+    model: String,
+    context_budget: usize,
+    context_memories: usize,
+    pinned_budget: usize,
+    min_similarity: f32,
+}
+
+impl TurnContext {
+    /// Settle the turn's persona, apply its and the workspace's tool scope
+    /// and pick the model: the persona's own if it names one, otherwise the
+    /// routing rules'
+    async fn prepare(mut self, history: &[Message]) -> Self {
+        let persona = match self.persona.take() {
+            Some(persona) => persona,
+            None => self.personas.resolve(self.default_persona.as_deref()).await,
+        };
+        self.tool_policy = persona.scope(self.tool_policy);
+        if let Some(workspace) = &self.workspace {
+            self.tool_policy = workspace.tool_policy(self.tool_policy);
+        }
+        if let Some(model) = &persona.model {
+            self.model = model.clone();
+        } else {
+            let tools = !connector_tools(&self.orchestrator, &self.tool_policy).await.is_empty();
+            let text = history
+                .iter()
+                .rev()
+                .find(|m| m.role == Role::User)
+                .map(|m| m.content.as_str())
+                .unwrap_or_default();
+            let request = RouteRequest {
+                task: RouteTask::Chat,
+                text,
+                tools,
+                persona: Some(&persona.name),
+                budget_share: self.budget.used_share(),
+            };
+            if let Some(model) = self.router.route(&request) {
+                self.model = model.to_string();
+            }
+        }
+        if let Some(model) = self.degradation.budget_model(self.budget.used_share()) {
+            self.model = model.to_string();
+            self.budget_degraded = true;
+        }
+        self.persona = Some(persona);
+        self
+    }
+
+    /// Whose cached answers the turn may reuse
+    fn answer_scope(&self) -> String {
+        match (&self.user, self.session_id) {
+            (Some(user), _) => format!("user:{}", user),
+            (None, Some(id)) => format!("session:{}", id),
+            (None, None) => String::new(),
+        }
+    }
+}
+
+#[derive(Default)]
+struct PendingCall {
+    id: String,
+    name: String,
+    arguments: String,
+}
+
+async fn emit(tx: &mpsc::Sender<TurnEvent>, event: TurnEvent) -> anyhow::Result<()> {
+    tx.send(event)
+        .await
+        .map_err(|_| anyhow::anyhow!("Turn receiver dropped"))
+}
+
+async fn run_turn(
+    ctx: &TurnContext,
+    history: &[Message],
+    tx: &mpsc::Sender<TurnEvent>,
+) -> anyhow::Result<()> {
+    let orchestrator = &ctx.orchestrator;
+    let compacted;
+    let history = match compact_history(ctx, history).await {
+        Some(compaction) => {
+            compacted = compaction.apply(history);
+            emit(tx, TurnEvent::Summarized(compaction)).await?;
+            compacted.as_slice()
+        }
+        None => history,
+    };
+
+    let mut messages = vec![openrouter::Message {
+        role: "system".to_string(),
+        content: ctx.persona.as_ref().map(Persona::prompt).unwrap_or_default(),
+    }];
+    messages.push(openrouter::Message {
+        role: "system".to_string(),
+        content: ctx.time.prompt(Utc::now()),
+    });
+    if let Some(workspace) = &ctx.workspace {
+        messages.push(openrouter::Message {
+            role: "system".to_string(),
+            content: workspace.prompt(),
+        });
+    }
+    if let Some(prompt) = preference_prompt(ctx).await {
+        messages.push(openrouter::Message {
+            role: "system".to_string(),
+            content: prompt,
+        });
+    }
+    let recalled = recall_memories(ctx, history).await;
+    if !recalled.is_empty() {
+        messages.push(openrouter::Message {
+            role: "system".to_string(),
+            content: recall::prompt(&recalled),
+        });
+    }
+    let citations: Vec<_> = recalled.iter().map(Recalled::citation).collect();
+    for message in history {
+        let message = if message.parts.is_empty() {
+            to_provider_message(message)
+        } else {
+            to_provider_message(&ctx.attachments.expand(message).await)
+        };
+        messages.extend(message);
+    }
+
+    let mut spend = Spend::default();
+    if ctx.budget_degraded {
+        degrade(ctx, &mut spend, DegradationLevel::SmallerModel, "budget", Some(&ctx.model));
+    }
+    if ctx.degrade && ctx.degradation.budget_exhausted(ctx.budget.used_share()) {
+        return answer_degraded(ctx, history, "budget exhausted", tx).await;
+    }
+    let tools = connector_tools(orchestrator, &ctx.tool_policy).await;
+    // Drafts are held back until the turn's model has checked them, and
+    // guarded replies until they have passed the filters
+    let drafting = ctx.generation.drafting_model(&ctx.model).to_string();
+    let guarded = ctx.guardrails.applies(ctx.strictness);
+    let tokens = if ctx.generation.verifies() || guarded { Tokens::Hold } else { Tokens::Stream };
+
+    for round in 0..=MAX_TOOL_ROUNDS {
+        // The final round withholds tools so the model has to answer
+        let offer_tools = round < MAX_TOOL_ROUNDS && !tools.is_empty();
+        let request = ChatRequest {
+            model: drafting.clone(),
+            messages: messages.clone(),
+            tools: offer_tools.then(|| tools.clone()),
+            tool_choice: offer_tools.then(|| "auto".to_string()),
+            temperature: Some(0.7),
+            max_tokens: Some(4000),
+        };
+        let (content, calls) = call_model(ctx, &drafting, request, tokens, &mut spend, tx).await?;
+
+        if calls.is_empty() {
+            let content = if ctx.generation.verifies() {
+                let tokens = if guarded { Tokens::Hold } else { Tokens::UnlessApproved };
+                verify(ctx, &messages, content, tokens, &mut spend, tx).await?
+            } else {
+                content
+            };
+            let content = if guarded {
+                let content = guard(ctx, &messages, content, &mut spend, tx).await;
+                emit(tx, TurnEvent::Token(content.clone())).await?;
+                content
+            } else {
+                content
+            };
+            let Spend { usage, cost_usd, degradation } = spend;
+            if degradation == DegradationLevel::None && ctx.degrade {
+                ctx.degradation.remember(&ctx.answer_scope(), last_question(history), &content);
+            }
+            ctx.events.publish(
+                events::TURN_COMPLETED,
+                ctx.session_id,
+                serde_json::json!({
+                    "reply": content,
+                    "usage": usage,
+                    "cost_usd": cost_usd,
+                    "citations": citations,
+                    "degradation": degradation,
+                }),
+            );
+            emit(tx, TurnEvent::Usage { usage, cost_usd }).await?;
+            let reply = annotated(Message::assistant(content).with_citations(citations), degradation);
+            emit(tx, TurnEvent::Completed(reply)).await?;
+            return Ok(());
+        }
+
+        // Provider messages only carry role and content, so the tool
+        // exchange is replayed to the model as plain text
+        if !content.trim().is_empty() {
+            messages.push(openrouter::Message {
+                role: "assistant".to_string(),
+                content,
+            });
+        }
+        for (index, call) in calls {
+            let args = serde_json::from_str(&call.arguments)
+                .unwrap_or_else(|_| serde_json::json!({}));
+            let tool_call = ToolCall {
+                id: if call.id.is_empty() { format!("call_{}_{}", round, index) } else { call.id },
+                name: call.name,
+                args,
+            };
+            emit(tx, TurnEvent::ToolCall(tool_call.clone())).await?;
+
+            let result = execute_tool(ctx, &tool_call, tx).await;
+            emit(tx, TurnEvent::ToolResult(result.clone())).await?;
+
+            messages.push(openrouter::Message {
+                role: "assistant".to_string(),
+                content: format!("Calling tool {} with {}", tool_call.name, tool_call.args),
+            });
+            messages.push(openrouter::Message {
+                role: "user".to_string(),
+                content: match result.error {
+                    Some(error) => format!("Tool {} failed: {}", result.name, error),
+                    None => format!("Tool {} returned:\n{}", result.name, result.output),
+                },
+            });
+        }
+    }
+
+    anyhow::bail!("No final answer after {} tool rounds", MAX_TOOL_ROUNDS)
+}
+
+/// How a model call's text reaches the user
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Tokens {
+    Stream,
+    Hold,
+    /// Held while it could still be the verifier's approval, streamed from
+    /// the moment it can't
+    UnlessApproved,
+}
+
+/// Usage summed over every model call of a turn, and how far the turn had
+/// to degrade to make them
+struct Spend {
+    usage: TokenUsage,
+    cost_usd: Option<f64>,
+    degradation: DegradationLevel,
+}
+
+impl Default for Spend {
+    fn default() -> Self {
+        Self {
+            usage: TokenUsage {
+                prompt_tokens: 0,
+                completion_tokens: 0,
+                total_tokens: 0,
+            },
+            cost_usd: None,
+            degradation: DegradationLevel::None,
+        }
+    }
+}
+
+/// Record that the turn stepped down to `level`, and tell subscribers why
+fn degrade(ctx: &TurnContext, spend: &mut Spend, level: DegradationLevel, reason: &str, model: Option<&str>) {
+    spend.degradation = spend.degradation.max(level);
+    ctx.events.publish(
+        events::TURN_DEGRADED,
+        ctx.session_id,
+        serde_json::json!({ "level": level, "reason": reason, "model": model }),
+    );
+}
+
+/// `reply` marked with the degradation level it was written at
+fn annotated(mut reply: Message, level: DegradationLevel) -> Message {
+    if level != DegradationLevel::None {
+        reply.metadata["degradation"] = serde_json::json!(level);
+    }
+    reply
+}
+
+fn last_question(history: &[Message]) -> &str {
+    history
+        .iter()
+        .rev()
+        .find(|m| m.role == Role::User)
+        .map(|m| m.content.as_str())
+        .unwrap_or_default()
+}
+
+/// End the turn on a cached answer or the canned reply, once `reason` has
+/// left no model to write one
+async fn answer_degraded(
+    ctx: &TurnContext,
+    history: &[Message],
+    reason: &str,
+    tx: &mpsc::Sender<TurnEvent>,
+) -> anyhow::Result<()> {
+    let (content, level) = ctx.degradation.fallback_reply(&ctx.answer_scope(), last_question(history));
+    tracing::warn!("Answering with a {} reply: {}", level.as_str(), reason);
+    degrade(ctx, &mut Spend::default(), level, reason, None);
+    ctx.events.publish(
+        events::TURN_COMPLETED,
+        ctx.session_id,
+        serde_json::json!({ "reply": content, "degradation": level }),
+    );
+    emit(tx, TurnEvent::Token(content.clone())).await?;
+    emit(tx, TurnEvent::Completed(annotated(Message::assistant(content), level))).await
+}
+
+/// Stream one model call, recording its usage and, in a sampled turn, its
+/// trace; returns the text and any tool calls it made
+async fn call_model(
+    ctx: &TurnContext,
+    model: &str,
+    request: ChatRequest,
+    tokens: Tokens,
+    spend: &mut Spend,
+    tx: &mpsc::Sender<TurnEvent>,
+) -> anyhow::Result<(String, BTreeMap<usize, PendingCall>)> {
+    let work = ctx.work.child(WorkKind::ModelCall, model);
+    let traced = ctx.traces.as_ref().map(|_| (request.clone(), std::time::Instant::now()));
+    let result = match work.run(stream_model(ctx, model, request, tokens, spend, tx)).await {
+        Ok(result) => result,
+        Err(cancelled) => Err(cancelled.into()),
+    };
+    if let (Some(traces), Some((request, started))) = (&ctx.traces, traced) {
+        let mut trace = PromptTrace::new(ctx.turn_id, ctx.session_id, request, started.elapsed());
+        match &result {
+            Ok((content, calls)) => {
+                trace.completion = content.clone();
+                trace.tool_calls = calls
+                    .values()
+                    .map(|call| TracedCall { name: call.name.clone(), arguments: call.arguments.clone() })
+                    .collect();
+            }
+            Err(e) => trace.error = Some(e.to_string()),
+        }
+        if let Err(e) = traces.record(&trace).await {
+            tracing::warn!("Failed to record prompt trace: {}", e);
+        }
+    }
+    result
+}
+
+async fn stream_model(
+    ctx: &TurnContext,
+    model: &str,
+    mut request: ChatRequest,
+    tokens: Tokens,
+    spend: &mut Spend,
+    tx: &mpsc::Sender<TurnEvent>,
+) -> anyhow::Result<(String, BTreeMap<usize, PendingCall>)> {
+    let mut model = model.to_string();
+    // A request that fails before anything streams can go to a fallback
+    // model without the user seeing the first attempt
+    let (mut stream, started) = loop {
+        let started = std::time::Instant::now();
+        let stream = ctx.llm.chat_stream(request.clone()).await;
+        status::record_provider_call(&model, started.elapsed(), stream.is_ok());
+        match stream {
+            Ok(stream) => {
+                ctx.router.record_latency(&model, started.elapsed());
+                break (stream, started);
+            }
+            Err(e) => match ctx.degradation.fallback_after(&model) {
+                Some(next) => {
+                    tracing::warn!("{} failed, falling back to {}: {}", model, next, e);
+                    degrade(ctx, spend, DegradationLevel::SmallerModel, &e.to_string(), Some(next));
+                    model = next.to_string();
+                    request.model = model.clone();
+                }
+                None => return Err(e),
+            },
+        }
+    };
+    let model = model.as_str();
+    let mut content = String::new();
+    let mut calls: BTreeMap<usize, PendingCall> = BTreeMap::new();
+    let mut streaming = tokens == Tokens::Stream;
+
+    while let Some(event) = stream.next_event().await {
+        match event? {
+            StreamEvent::Content(text) => {
+                content.push_str(&text);
+                if streaming {
+                    emit(tx, TurnEvent::Token(text)).await?;
+                } else if tokens == Tokens::UnlessApproved && !generation::may_be_approval(&content) {
+                    streaming = true;
+                    emit(tx, TurnEvent::Token(content.clone())).await?;
+                }
+            }
+            StreamEvent::ToolCallDelta { index, id, name, arguments } => {
+                let call = calls.entry(index).or_default();
+                if let Some(id) = id {
+                    call.id = id;
+                }
+                if let Some(name) = name {
+                    call.name.push_str(&name);
+                }
+                call.arguments.push_str(&arguments);
+            }
+            StreamEvent::Usage { usage: delta, cost } => {
+                let delta = TokenUsage::from(delta);
+                status::record_provider_tokens(model, &delta);
+                let record = UsageRecord::new(model, ctx.session_id, &delta, cost).with_latency(started.elapsed());
+                if let Err(e) = ctx.usage_log.record(&record).await {
+                    tracing::warn!("Failed to record usage: {}", e);
+                }
+                spend.usage.prompt_tokens += delta.prompt_tokens;
+                spend.usage.completion_tokens += delta.completion_tokens;
+                spend.usage.total_tokens += delta.total_tokens;
+                if let Some(cost) = cost {
+                    *spend.cost_usd.get_or_insert(0.0) += cost;
+                    let spent = ctx.budget.record(cost);
+                    if let Some(share) = ctx.budget.crossed_share(spent - cost, spent) {
+                        ctx.events.publish(
+                            events::BUDGET_WARNING,
+                            ctx.session_id,
+                            serde_json::json!({
+                                "share": share,
+                                "spent_usd": spent,
+                                "daily_limit_usd": ctx.budget.daily_limit(),
+                            }),
+                        );
+                    }
+                }
+            }
+            StreamEvent::Finish(_) => {}
+        }
+    }
+    Ok((content, calls))
+}
+
+/// Have the turn's model check `draft` and return the reply to send: the
+/// draft when approved, otherwise the model's correction. Unless `tokens`
+/// holds it, whichever it is has been shown by the time this returns. If
+/// the check itself fails the draft is sent unchecked rather than losing
+/// the turn.
+async fn verify(
+    ctx: &TurnContext,
+    messages: &[openrouter::Message],
+    draft: String,
+    tokens: Tokens,
+    spend: &mut Spend,
+    tx: &mpsc::Sender<TurnEvent>,
+) -> anyhow::Result<String> {
+    let request = ChatRequest {
+        model: ctx.model.clone(),
+        messages: generation::verify_messages(messages, &draft),
+        tools: None,
+        tool_choice: None,
+        temperature: Some(0.2),
+        max_tokens: Some(4000),
+    };
+    let reply = match call_model(ctx, &ctx.model, request, tokens, spend, tx).await {
+        Ok((reply, _)) => reply,
+        Err(e) => {
+            tracing::warn!("Could not verify the draft, sending it unchecked: {}", e);
+            String::new()
+        }
+    };
+    let shown = tokens != Tokens::Hold;
+    if reply.trim().is_empty() || generation::is_approval(&reply) {
+        if shown {
+            emit(tx, TurnEvent::Token(draft.clone())).await?;
+        }
+        return Ok(draft);
+    }
+    if shown && generation::may_be_approval(&reply) {
+        // Held back the whole time, but not an approval after all
+        emit(tx, TurnEvent::Token(reply.clone())).await?;
+    }
+    Ok(reply)
+}
+
+/// Run `reply` through the output guardrails and return what may be sent.
+/// A strict session first has the turn's model rewrite a reply that breaks
+/// a rule; whatever still breaks one is repaired by the filters, and a
+/// strict session's reply is withheld if that isn't enough.
+async fn guard(
+    ctx: &TurnContext,
+    messages: &[openrouter::Message],
+    mut reply: String,
+    spend: &mut Spend,
+    tx: &mpsc::Sender<TurnEvent>,
+) -> String {
+    let strict = ctx.strictness == Strictness::Strict;
+    let mut reprompts = 0;
+    loop {
+        let violations = ctx.guardrails.check(&reply);
+        if violations.is_empty() {
+            return reply;
+        }
+        if strict && reprompts < ctx.guardrails.reprompts() {
+            reprompts += 1;
+            guardrails::audit(ctx.session_id, &violations, "reprompted");
+            let mut messages = messages.to_vec();
+            messages.push(openrouter::Message {
+                role: "assistant".to_string(),
+                content: reply.clone(),
+            });
+            messages.push(openrouter::Message {
+                role: "system".to_string(),
+                content: guardrails::reprompt(&violations),
+            });
+            let request = ChatRequest {
+                model: ctx.model.clone(),
+                messages,
+                tools: None,
+                tool_choice: None,
+                temperature: Some(0.2),
+                max_tokens: Some(4000),
+            };
+            match call_model(ctx, &ctx.model, request, Tokens::Hold, spend, tx).await {
+                Ok((rewrite, _)) if !rewrite.trim().is_empty() => {
+                    reply = rewrite;
+                    continue;
+                }
+                Ok(_) => {}
+                Err(e) => tracing::warn!("Could not have the reply rewritten: {}", e),
+            }
+        }
+
+        let repaired = ctx.guardrails.repair(&reply);
+        let remaining = ctx.guardrails.check(&repaired);
+        if strict && !remaining.is_empty() {
+            guardrails::audit(ctx.session_id, &remaining, "withheld");
+            return guardrails::WITHHELD.to_string();
+        }
+        // A lenient session gets the reply even if a rule couldn't be fixed
+        let action = if remaining.is_empty() { "repaired" } else { "delivered" };
+        guardrails::audit(ctx.session_id, &violations, action);
+        return repaired;
+    }
+}
+
+/// The session user's preference profile as a prompt; a profile that can't
+/// be read is skipped rather than failing the turn
+async fn preference_prompt(ctx: &TurnContext) -> Option<String> {
+    let user = ctx.user.as_deref()?;
+    match ctx.preferences.load(user).await {
+        Ok(profile) => profile.prompt(),
+        Err(e) => {
+            tracing::warn!("Skipping preferences for {}: {}", user, e);
+            None
+        }
+    }
+}
+
+/// Pinned memories, then those close to the latest question. Recall is best
+/// effort: a turn goes ahead without memories if embedding or search fails.
+async fn recall_memories(ctx: &TurnContext, history: &[Message]) -> Vec<Recalled> {
+    let pinned = pinned_memories(ctx).await;
+    if ctx.context_memories == 0 && pinned.is_empty() {
+        return Vec::new();
+    }
+    let embedding = match recall::query(history) {
+        Some(query) => match ctx.llm.get_embedding(query).await {
+            Ok(embedding) => Some(embedding),
+            Err(e) => {
+                tracing::warn!("Skipping memory recall, embedding failed: {}", e);
+                None
+            }
+        },
+        None => None,
+    };
+    let recalled = match &embedding {
+        Some(embedding) if ctx.context_memories > 0 => search_memories(ctx, embedding).await,
+        _ => Vec::new(),
+    };
+    let recalled = recall::with_pins(pinned, embedding.as_deref(), recalled, ctx.pinned_budget);
+    // Recalled memories count as accessed, which feeds their retrieval score
+    let ids: Vec<Uuid> = recalled.iter().map(|r| r.memory.id).collect();
+    if let Err(e) = ctx.memory_store.record_access(&ids).await {
+        tracing::warn!("Could not record memory access: {}", e);
+    }
+    recalled
+}
+
+async fn search_memories(ctx: &TurnContext, embedding: &[f32]) -> Vec<Recalled> {
+    // Over-fetch in a workspace, since other projects' memories compete
+    // for the same slots
+    let fetch = if ctx.workspace.is_some() { ctx.context_memories * 4 } else { ctx.context_memories };
+    match ctx.memory_store.search(embedding, fetch).await {
+        Ok(mut memories) => {
+            if let Some(workspace) = &ctx.workspace {
+                memories.retain(|m| workspace.includes(m));
+            }
+            recall::select(embedding, memories, ctx.min_similarity, ctx.context_memories)
+        }
+        Err(e) => {
+            tracing::warn!("Skipping memory recall, search failed: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+/// Pinned memories the turn may see; none when their budget is 0
+async fn pinned_memories(ctx: &TurnContext) -> Vec<Memory> {
+    if ctx.pinned_budget == 0 {
+        return Vec::new();
+    }
+    match ctx.memory_store.pinned().await {
+        Ok(mut pinned) => {
+            if let Some(workspace) = &ctx.workspace {
+                pinned.retain(|m| workspace.includes(m));
+            }
+            pinned
+        }
+        Err(e) => {
+            tracing::warn!("Skipping pinned memories: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+/// Summarize the oldest turns when the history is over budget. If the model
+/// can't produce a summary the full history is sent instead, so nothing is
+/// dropped without being summarized first.
+async fn compact_history(ctx: &TurnContext, history: &[Message]) -> Option<Compaction> {
+    let count = summarize::messages_to_summarize(history, ctx.context_budget)?;
+    let transcript = summarize::transcript(&history[..count]);
+    let request = RouteRequest {
+        task: RouteTask::Summarize,
+        text: &transcript,
+        tools: false,
+        persona: ctx.persona.as_ref().map(|p| p.name.as_str()),
+        budget_share: ctx.budget.used_share(),
+    };
+    let model = ctx.router.route(&request).unwrap_or(&ctx.model);
+    let summary = match summarize::summarize(&ctx.llm, model, &history[..count]).await {
+        Ok(summary) => summary,
+        Err(e) => {
+            tracing::warn!("Could not summarize {} earlier messages, sending them all: {}", count, e);
+            return None;
+        }
+    };
+    let compaction = Compaction::new(summary, count);
+    if let Err(e) = remember_summary(ctx, &compaction).await {
+        tracing::warn!("Failed to store conversation summary: {}", e);
+    }
+    Some(compaction)
+}
+
+/// Keep the summary as a Knowledge memory so it stays searchable after the
+/// session ends
+async fn remember_summary(ctx: &TurnContext, compaction: &Compaction) -> anyhow::Result<()> {
+    let content = compaction.summary.content.clone();
+    let embedding = ctx.llm.get_embedding(&content).await?;
+    let now = Utc::now();
+    ctx.memory_store
+        .store(Memory {
+            id: Uuid::new_v4(),
+            memory_type: MemoryType::Knowledge,
+            content,
+            embedding,
+            metadata: serde_json::json!({
+                "source": "conversation_summary",
+                "session_id": ctx.session_id,
+                "summarized_messages": compaction.replaced,
+                "embedding_model": DEFAULT_EMBEDDING_MODEL,
+            }),
+            created_at: now,
+            last_accessed: now,
+        })
+        .await?;
+    Ok(())
+}
+
+fn to_provider_message(message: &Message) -> Option<openrouter::Message> {
+    if message.content.trim().is_empty() {
+        return None;
+    }
+    let (role, content) = match message.role {
+        Role::System => ("system", message.content.clone()),
+        Role::User => ("user", message.content.clone()),
+        Role::Assistant => ("assistant", message.content.clone()),
+        Role::Tool => ("user", format!("Tool output:\n{}", message.content)),
+    };
+    Some(openrouter::Message {
+        role: role.to_string(),
+        content,
+    })
+}
+
+/// Every connector the session's policy permits is offered as a tool taking
+/// string parameters
+async fn connector_tools(orchestrator: &Mutex<HybridOrchestrator>, policy: &ToolPolicy) -> Vec<Tool> {
+    let connectors = orchestrator.lock().await.get_registry().list_permitted(policy).await;
+    connectors
+        .into_iter()
+        .take(20)
+        .map(|meta| Tool {
+            name: meta.id,
+            description: meta.description,
+            parameters: serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "action": { "type": "string", "description": "Connector action to perform" }
+                },
+                "required": ["action"],
+                "additionalProperties": { "type": "string" }
+            }),
+        })
+        .collect()
+}
+
+async fn execute_tool(ctx: &TurnContext, call: &ToolCall, tx: &mpsc::Sender<TurnEvent>) -> ToolResult {
+    let (orchestrator, approvals, session_id) = (&ctx.orchestrator, &ctx.approvals, ctx.session_id);
+    let started = std::time::Instant::now();
+    let mut params: HashMap<String, String> = call
+        .args
+        .as_object()
+        .map(|args| {
+            args.iter()
+                // Confirmation must come from the user, never from the model
+                .filter(|(key, _)| key.as_str() != "confirmed")
+                .map(|(key, value)| {
+                    let value = match value {
+                        serde_json::Value::String(s) => s.clone(),
+                        other => other.to_string(),
+                    };
+                    (key.clone(), value)
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let meta = orchestrator.lock().await
+        .get_registry()
+        .list()
+        .await
+        .into_iter()
+        .find(|meta| meta.id == call.name);
+    // The model may name a tool it was never offered; refuse before anyone
+    // is asked to approve it
+    if let Some(Err(e)) = meta.as_ref().map(|meta| ctx.tool_policy.check(meta)) {
+        status::record_connector_execution(&call.name, None, "denied");
+        return ToolResult::error(call.id.clone(), call.name.clone(), e.to_string());
+    }
+    if let Some(meta) = meta.filter(|meta| meta.requires_approval) {
+        let action = params.get("action").map(String::as_str).unwrap_or_default();
+        if !approvals.is_always_allowed(&call.name, action).await {
+            let checks = meta.safety_checks;
+            if let Err(reason) = await_approval(ctx, call, params.clone(), checks, tx).await {
+                status::record_connector_execution(&call.name, None, "denied");
+                return ToolResult::error(call.id.clone(), call.name.clone(), reason);
+            }
+        }
+        // A person signed off on this call, or on all calls like it, which
+        // is the confirmation
+        params.insert("confirmed".to_string(), "true".to_string());
+    }
+
+    // Another runtime may be running tools for the same session
+    let lock = match (&ctx.cluster, session_id) {
+        (Some(cluster), Some(id)) => match cluster.lock(&format!("tools:{}", id)).await {
+            Ok(lock) => Some(lock),
+            Err(e) => {
+                status::record_connector_execution(&call.name, None, "locked");
+                return ToolResult::error(call.id.clone(), call.name.clone(), e.to_string());
+            }
+        },
+        _ => None,
+    };
+    let action = params.get("action").cloned();
+    let work = ctx.work.child(WorkKind::Tool, &call.name);
+    let outcome = work
+        .run(async {
+            orchestrator.lock().await
+                .execute_connector_for(
+                    &call.name,
+                    params,
+                    &ctx.tool_policy,
+                    ctx.workspace.as_ref().map(Workspace::scope),
+                    session_id,
+                )
+                .await
+        })
+        .await
+        .unwrap_or_else(|cancelled| Err(cancelled.into()));
+    drop(work);
+    drop(lock);
+    if let Ok(result) = &outcome {
+        if let Err(e) = ctx.undo_log.record(ctx.turn_id, session_id, &result.compensations).await {
+            tracing::warn!("Could not record how to undo {}: {}", call.name, e);
+        }
+    }
+    let mut result = match outcome {
+        Ok(result) if result.success => {
+            let mut tool_result = ToolResult::success(call.id.clone(), call.name.clone(), result.output);
+            attach_artifacts(ctx, &result.artifacts, &mut tool_result).await;
+            tool_result
+        }
+        Ok(result) => {
+            let error = if result.errors.is_empty() {
+                "Tool reported failure".to_string()
+            } else {
+                result.errors.join("; ")
+            };
+            ToolResult::error(call.id.clone(), call.name.clone(), error)
+        }
+        Err(e) => ToolResult::error(call.id.clone(), call.name.clone(), e.to_string()),
+    };
+    result.execution_time_ms = Some(started.elapsed().as_millis() as u64);
+    tracing::info!(
+        target: "audit",
+        tool = %call.name,
+        action = action.as_deref().unwrap_or_default(),
+        session = ?session_id,
+        success = result.error.is_none(),
+        "Tool executed"
+    );
+    let record = ToolRecord::new(
+        &call.name,
+        action.as_deref(),
+        session_id,
+        result.error.is_none(),
+        started.elapsed(),
+    );
+    if let Err(e) = ctx.usage_log.record_tool(&record).await {
+        tracing::warn!("Failed to record tool run: {}", e);
+    }
+    ctx.events.publish(
+        events::TOOL_EXECUTED,
+        session_id,
+        serde_json::json!({
+            "tool": result.name,
+            "action": action,
+            "success": result.error.is_none(),
+            "error": result.error,
+            "execution_time_ms": result.execution_time_ms,
+        }),
+    );
+    result
+}
+
+/// Store the files a tool produced as attachments, and tell the model
+/// their ids so it can refer to them
+async fn attach_artifacts(ctx: &TurnContext, artifacts: &[std::path::PathBuf], result: &mut ToolResult) {
+    for path in artifacts {
+        match ctx.attachments.upload(path).await {
+            Ok(attachment) => {
+                result.output.push_str(&format!("\n\nAttached {} as {}", attachment.name, attachment.id));
+                result.attachments.push(attachment);
+            }
+            Err(e) => {
+                tracing::warn!("Could not attach {}: {}", path.display(), e);
+                result.output.push_str(&format!("\n\nCould not attach {}: {}", path.display(), e));
+            }
+        }
+    }
+}
+
+/// Queue the call and wait for a decision; `Err` carries why it may not run
+async fn await_approval(
+    ctx: &TurnContext,
+    call: &ToolCall,
+    params: HashMap<String, String>,
+    safety_checks: Vec<String>,
+    tx: &mpsc::Sender<TurnEvent>,
+) -> Result<(), String> {
+    let request = ctx
+        .approvals
+        .submit(&call.name, params, ctx.session_id.map(|id| id.to_string()), safety_checks)
+        .await
+        .map_err(|e| format!("Could not queue approval: {}", e))?;
+    let id = request.id;
+    ctx.events.publish(
+        events::APPROVAL_REQUESTED,
+        ctx.session_id,
+        serde_json::json!({
+            "id": id,
+            "connector": request.connector_id,
+            "action": request.action,
+        }),
+    );
+    let _ = tx.send(TurnEvent::AwaitingApproval(request)).await;
+
+    let decided = ctx
+        .approvals
+        .wait_for_decision(id, APPROVAL_TIMEOUT)
+        .await
+        .map_err(|e| format!("Approval {} failed: {}", id, e))?;
+    match decided.status {
+        ApprovalStatus::Approved => Ok(()),
+        status => Err(match decided.note {
+            Some(note) => format!("Approval {} was {}: {}", id, status, note),
+            None => format!("Approval {} was {}", id, status),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_provider_message() {
+        let message = to_provider_message(&Message::tool("42")).unwrap();
+        assert_eq!(message.role, "user");
+        assert!(message.content.ends_with("42"));
+
+        assert!(to_provider_message(&Message::assistant("  ")).is_none());
+        assert_eq!(to_provider_message(&Message::assistant("hi")).unwrap().role, "assistant");
+    }
+}
@@ -0,0 +1,618 @@
+//! Running several runtimes as one service
+//!
+//! With `[cluster] enabled`, runtimes pointed at the same Redis and Postgres
+//! serve the same sessions, so the service can grow by adding instances:
+//!
+//! - A session's state (tool policy, user, persona, strictness, workspace
+//!   and time context) is written to Redis after it changes, and an instance that
+//!   hasn't seen the session picks it up from there.
+//! - Transcripts are kept in Postgres instead of the session directory.
+//! - Each instance has a node ID, which the web server returns as an
+//!   `X-Jamey-Node` header and `jamey_node` cookie. Load balancers should
+//!   route on either so a session's event stream and the messages posted to
+//!   it reach the same instance.
+//! - Tool calls take a lock on their session in Redis, so two instances
+//!   never run tools for one session at the same time.
+//! - One instance is elected leader, through a lease in Redis or an
+//!   advisory lock in Postgres. Recurring scheduled tasks and the usage
+//!   retention reaper only run on the leader; if it goes away, another
+//!   instance takes over within `leader_ttl_secs`.
+//!
+//! ```toml
+//! [cluster]
+//! enabled = true
+//! node_id = "jamey-1"
+//! redis_url = "redis://redis:6379"
+//! leader_election = "postgres"
+//! ```
+
+use crate::events::{self, EventBus};
+use crate::guardrails::Strictness;
+use crate::locale::TimeContext;
+use crate::persona::Persona;
+use crate::state::RuntimeState;
+use crate::workspace::Workspace;
+use chrono::{DateTime, Utc};
+use deadpool_postgres::{Object, Pool};
+use jamey_core::secure_logging::mask_url_credentials;
+use jamey_core::supervisor::Supervisor;
+use jamey_tools::connector::ToolPolicy;
+use redis::aio::ConnectionManager;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use thiserror::Error;
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+use uuid::Uuid;
+
+/// Pause between attempts to take a held lock
+const LOCK_RETRY: Duration = Duration::from_millis(100);
+
+/// Deletes a lock only while it still holds this holder's token, so a lock
+/// that expired and was taken by someone else is left alone
+const RELEASE_SCRIPT: &str = r#"
+if redis.call("GET", KEYS[1]) == ARGV[1] then
+    return redis.call("DEL", KEYS[1])
+end
+return 0
+"#;
+
+/// Extends a lock or lease for as long as this holder still has it
+const RENEW_SCRIPT: &str = r#"
+if redis.call("GET", KEYS[1]) == ARGV[1] then
+    return redis.call("PEXPIRE", KEYS[1], ARGV[2])
+end
+return 0
+"#;
+
+#[derive(Debug, Error)]
+pub enum ClusterError {
+    #[error("Redis error: {0}")]
+    Redis(#[from] redis::RedisError),
+    #[error("Serialization error: {0}")]
+    Serialization(#[from] serde_json::Error),
+    #[error("Timed out waiting for lock {0}")]
+    LockTimeout(String),
+}
+
+/// Where the leader holds its claim
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum LeaderElection {
+    /// A lease key the leader keeps renewing
+    #[default]
+    Redis,
+    /// A session-level advisory lock, freed as soon as the leader's
+    /// connection drops
+    Postgres,
+}
+
+impl std::str::FromStr for LeaderElection {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "redis" => Ok(Self::Redis),
+            "postgres" => Ok(Self::Postgres),
+            other => Err(format!("Unknown leader election backend '{}' (expected redis or postgres)", other)),
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct ClusterConfig {
+    /// Share sessions with other runtimes (`JAMEY_CLUSTER`)
+    pub enabled: bool,
+    /// This instance's name in routing hints (`JAMEY_NODE_ID`); the host
+    /// name when unset
+    pub node_id: Option<String>,
+    /// Where session state and locks are kept (`REDIS_URL`)
+    pub redis_url: String,
+    /// Prepended to every key, for several clusters sharing one Redis
+    pub key_prefix: String,
+    /// How long shared state outlives a session's last turn
+    pub session_ttl_secs: u64,
+    /// How long a lock outlives a holder that stopped renewing it
+    pub lock_ttl_secs: u64,
+    /// How long a tool call waits for its session's lock
+    pub lock_wait_secs: u64,
+    /// How the leader is chosen (`JAMEY_LEADER_ELECTION`)
+    pub leader_election: LeaderElection,
+    /// How long the cluster may go without a leader when it fails
+    pub leader_ttl_secs: u64,
+}
+
+impl fmt::Debug for ClusterConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ClusterConfig")
+            .field("enabled", &self.enabled)
+            .field("node_id", &self.node_id)
+            .field("redis_url", &mask_url_credentials(&self.redis_url))
+            .field("key_prefix", &self.key_prefix)
+            .field("session_ttl_secs", &self.session_ttl_secs)
+            .field("lock_ttl_secs", &self.lock_ttl_secs)
+            .field("lock_wait_secs", &self.lock_wait_secs)
+            .field("leader_election", &self.leader_election)
+            .field("leader_ttl_secs", &self.leader_ttl_secs)
+            .finish()
+    }
+}
+
+impl Default for ClusterConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            node_id: None,
+            redis_url: "redis://localhost:6379".to_string(),
+            key_prefix: "jamey".to_string(),
+            session_ttl_secs: 24 * 60 * 60,
+            lock_ttl_secs: 30,
+            lock_wait_secs: 30,
+            leader_election: LeaderElection::Redis,
+            leader_ttl_secs: 15,
+        }
+    }
+}
+
+impl ClusterConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.enabled {
+            return Ok(());
+        }
+        if !self.redis_url.starts_with("redis://") && !self.redis_url.starts_with("rediss://") {
+            return Err("cluster.redis_url must be a redis:// or rediss:// URL".to_string());
+        }
+        if self.node_id.as_deref().is_some_and(|id| !valid_node_id(id)) {
+            return Err("cluster.node_id may only contain letters, digits, '-', '_' and '.'".to_string());
+        }
+        if self.session_ttl_secs == 0 || self.lock_ttl_secs < 3 || self.leader_ttl_secs < 3 {
+            return Err(
+                "cluster.session_ttl_secs must be above 0, and lock_ttl_secs and leader_ttl_secs at least 3".to_string(),
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Node IDs end up in headers and cookies
+fn valid_node_id(id: &str) -> bool {
+    !id.is_empty() && id.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+}
+
+/// What another instance needs to carry on a session
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SharedSession {
+    pub tool_policy: ToolPolicy,
+    pub user_id: Option<String>,
+    pub persona: Option<Persona>,
+    pub strictness: Option<Strictness>,
+    pub workspace: Option<Workspace>,
+    /// Sessions shared before time contexts existed have none
+    #[serde(default)]
+    pub time: Option<TimeContext>,
+    /// Instance that last ran a turn in the session
+    pub node: String,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// This instance's connection to the rest of the cluster
+pub struct Cluster {
+    node_id: String,
+    redis: ConnectionManager,
+    key_prefix: String,
+    session_ttl: Duration,
+    lock_ttl: Duration,
+    lock_wait: Duration,
+    election: LeaderElection,
+    leader_ttl: Duration,
+    leader: AtomicBool,
+}
+
+impl Cluster {
+    pub async fn connect(config: &ClusterConfig) -> Result<Self, ClusterError> {
+        let client = redis::Client::open(config.redis_url.as_str())?;
+        let redis = client.get_connection_manager().await?;
+        let node_id = config.node_id.clone().unwrap_or_else(default_node_id);
+        tracing::info!("Joined cluster as node {}", node_id);
+        Ok(Self {
+            node_id,
+            redis,
+            key_prefix: config.key_prefix.clone(),
+            session_ttl: Duration::from_secs(config.session_ttl_secs),
+            lock_ttl: Duration::from_secs(config.lock_ttl_secs),
+            lock_wait: Duration::from_secs(config.lock_wait_secs),
+            election: config.leader_election,
+            leader_ttl: Duration::from_secs(config.leader_ttl_secs),
+            leader: AtomicBool::new(false),
+        })
+    }
+
+    pub fn node_id(&self) -> &str {
+        &self.node_id
+    }
+
+    /// Whether this instance currently runs the cluster's singleton jobs
+    pub fn is_leader(&self) -> bool {
+        self.leader.load(Ordering::Acquire)
+    }
+
+    fn key(&self, kind: &str, name: &str) -> String {
+        format!("{}:{}:{}", self.key_prefix, kind, name)
+    }
+
+    pub async fn publish_session(&self, id: Uuid, session: &SharedSession) -> Result<(), ClusterError> {
+        redis::cmd("SET")
+            .arg(self.key("session", &id.to_string()))
+            .arg(serde_json::to_vec(session)?)
+            .arg("EX")
+            .arg(self.session_ttl.as_secs())
+            .query_async::<()>(&mut self.redis.clone())
+            .await?;
+        Ok(())
+    }
+
+    pub async fn load_session(&self, id: Uuid) -> Result<Option<SharedSession>, ClusterError> {
+        let value: Option<Vec<u8>> = redis::cmd("GET")
+            .arg(self.key("session", &id.to_string()))
+            .query_async(&mut self.redis.clone())
+            .await?;
+        Ok(value.map(|v| serde_json::from_slice(&v)).transpose()?)
+    }
+
+    /// Take the cluster-wide lock `name`, waiting up to `lock_wait_secs`
+    /// for its holder to let go
+    pub async fn lock(&self, name: &str) -> Result<ClusterLock, ClusterError> {
+        let deadline = tokio::time::Instant::now() + self.lock_wait;
+        loop {
+            if let Some(lock) = self.try_lock(name).await? {
+                return Ok(lock);
+            }
+            if tokio::time::Instant::now() >= deadline {
+                return Err(ClusterError::LockTimeout(name.to_string()));
+            }
+            tokio::time::sleep(LOCK_RETRY).await;
+        }
+    }
+
+    /// Take the lock `name` if nobody holds it. It is renewed until
+    /// dropped, so it can guard work of any length.
+    pub async fn try_lock(&self, name: &str) -> Result<Option<ClusterLock>, ClusterError> {
+        let key = self.key("lock", name);
+        let token = format!("{}:{}", self.node_id, Uuid::new_v4());
+        if !claim(&mut self.redis.clone(), &key, &token, self.lock_ttl).await? {
+            return Ok(None);
+        }
+        let renewal = {
+            let (mut redis, key, token, ttl) = (self.redis.clone(), key.clone(), token.clone(), self.lock_ttl);
+            tokio::spawn(async move {
+                let mut ticker = tokio::time::interval(ttl / 3);
+                ticker.tick().await;
+                loop {
+                    ticker.tick().await;
+                    match renew(&mut redis, &key, &token, ttl).await {
+                        Ok(true) => {}
+                        Ok(false) => {
+                            tracing::warn!("Lost lock {} while holding it", key);
+                            break;
+                        }
+                        Err(e) => tracing::warn!("Failed to renew lock {}: {}", key, e),
+                    }
+                }
+            })
+        };
+        Ok(Some(ClusterLock { redis: self.redis.clone(), key, token, renewal }))
+    }
+
+    /// Keep trying to become leader, and keep the claim while leading,
+    /// until shutdown. `database` is needed for Postgres elections.
+    pub fn spawn_election(
+        self: &Arc<Self>,
+        supervisor: &Supervisor,
+        database: Option<Pool>,
+        events: EventBus,
+        shutdown: broadcast::Receiver<()>,
+    ) {
+        let cluster = Arc::clone(self);
+        match (cluster.election, database) {
+            (LeaderElection::Postgres, Some(pool)) => {
+                supervisor.spawn("leader_election", move || {
+                    let (cluster, pool, events) = (Arc::clone(&cluster), pool.clone(), events.clone());
+                    let shutdown = shutdown.resubscribe();
+                    async move { cluster.elect_with_postgres(pool, events, shutdown).await }
+                });
+            }
+            (LeaderElection::Postgres, None) => {
+                tracing::error!("Postgres leader election needs the database pool; this instance will never lead");
+            }
+            (LeaderElection::Redis, _) => {
+                supervisor.spawn("leader_election", move || {
+                    let (cluster, events) = (Arc::clone(&cluster), events.clone());
+                    let shutdown = shutdown.resubscribe();
+                    async move { cluster.elect_with_redis(events, shutdown).await }
+                });
+            }
+        }
+    }
+
+    async fn elect_with_redis(&self, events: EventBus, mut shutdown: broadcast::Receiver<()>) {
+        let key = self.key("leader", "lease");
+        let token = format!("{}:{}", self.node_id, Uuid::new_v4());
+        let mut redis = self.redis.clone();
+        let mut ticker = tokio::time::interval(self.leader_ttl / 3);
+        loop {
+            tokio::select! {
+                _ = shutdown.recv() => break,
+                _ = ticker.tick() => {}
+            }
+            let leading = if self.is_leader() {
+                renew(&mut redis, &key, &token, self.leader_ttl).await
+            } else {
+                claim(&mut redis, &key, &token, self.leader_ttl).await
+            };
+            // Without an answer from Redis the lease can't be vouched for
+            let leading = leading.unwrap_or_else(|e| {
+                tracing::warn!("Leader election failed: {}", e);
+                false
+            });
+            self.set_leader(leading, &events);
+        }
+        if self.is_leader() {
+            // Hand over straight away rather than when the lease runs out
+            let _ = release(&mut redis, &key, &token).await;
+            self.set_leader(false, &events);
+        }
+    }
+
+    async fn elect_with_postgres(&self, pool: Pool, events: EventBus, mut shutdown: broadcast::Receiver<()>) {
+        let lock_id = advisory_lock_id(&self.key_prefix);
+        let mut held: Option<Object> = None;
+        let mut ticker = tokio::time::interval(self.leader_ttl / 3);
+        loop {
+            tokio::select! {
+                _ = shutdown.recv() => break,
+                _ = ticker.tick() => {}
+            }
+            held = match held.take() {
+                // The lock lives as long as this connection does
+                Some(client) => match client.simple_query("SELECT 1").await {
+                    Ok(_) => Some(client),
+                    Err(e) => {
+                        tracing::warn!("Lost the leader's database connection: {}", e);
+                        // Closed rather than pooled, which frees the lock
+                        drop(Object::take(client));
+                        None
+                    }
+                },
+                None => match try_advisory_lock(&pool, lock_id).await {
+                    Ok(client) => client,
+                    Err(e) => {
+                        tracing::warn!("Leader election failed: {}", e);
+                        None
+                    }
+                },
+            };
+            self.set_leader(held.is_some(), &events);
+        }
+        if let Some(client) = held {
+            let _ = client.execute("SELECT pg_advisory_unlock($1)", &[&lock_id]).await;
+            self.set_leader(false, &events);
+        }
+    }
+
+    fn set_leader(&self, leading: bool, events: &EventBus) {
+        if self.leader.swap(leading, Ordering::AcqRel) == leading {
+            return;
+        }
+        if leading {
+            tracing::info!("Node {} is now the cluster leader", self.node_id);
+        } else {
+            tracing::info!("Node {} is no longer the cluster leader", self.node_id);
+        }
+        events.publish(
+            events::LEADER_CHANGED,
+            None,
+            serde_json::json!({ "node": self.node_id, "leader": leading }),
+        );
+    }
+}
+
+/// `SET NX` with an expiry; whether the key was free
+async fn claim(redis: &mut ConnectionManager, key: &str, token: &str, ttl: Duration) -> Result<bool, ClusterError> {
+    let taken: Option<String> = redis::cmd("SET")
+        .arg(key)
+        .arg(token)
+        .arg("NX")
+        .arg("PX")
+        .arg(ttl.as_millis() as u64)
+        .query_async(redis)
+        .await?;
+    Ok(taken.is_some())
+}
+
+/// Whether `token` still held `key` and had it extended
+async fn renew(redis: &mut ConnectionManager, key: &str, token: &str, ttl: Duration) -> Result<bool, ClusterError> {
+    let renewed: i64 = redis::Script::new(RENEW_SCRIPT)
+        .key(key)
+        .arg(token)
+        .arg(ttl.as_millis() as u64)
+        .invoke_async(redis)
+        .await?;
+    Ok(renewed == 1)
+}
+
+async fn release(redis: &mut ConnectionManager, key: &str, token: &str) -> Result<(), ClusterError> {
+    redis::Script::new(RELEASE_SCRIPT)
+        .key(key)
+        .arg(token)
+        .invoke_async::<i64>(redis)
+        .await?;
+    Ok(())
+}
+
+/// A connection holding the advisory lock, or `None` if someone else has it
+async fn try_advisory_lock(pool: &Pool, lock_id: i64) -> Result<Option<Object>, Box<dyn std::error::Error + Send + Sync>> {
+    let client = pool.get().await?;
+    let row = client.query_one("SELECT pg_try_advisory_lock($1)", &[&lock_id]).await?;
+    Ok(row.get::<_, bool>(0).then_some(client))
+}
+
+/// Advisory lock key for the cluster named by `key_prefix`
+fn advisory_lock_id(key_prefix: &str) -> i64 {
+    let digest = Sha256::digest(format!("jamey-leader:{}", key_prefix).as_bytes());
+    i64::from_be_bytes(digest[..8].try_into().expect("SHA-256 digests are 32 bytes"))
+}
+
+fn default_node_id() -> String {
+    std::env::var("HOSTNAME")
+        .ok()
+        .filter(|host| valid_node_id(host))
+        .unwrap_or_else(|| Uuid::new_v4().simple().to_string()[..8].to_string())
+}
+
+/// A held cluster lock; dropping it releases the lock
+pub struct ClusterLock {
+    redis: ConnectionManager,
+    key: String,
+    token: String,
+    renewal: JoinHandle<()>,
+}
+
+impl Drop for ClusterLock {
+    fn drop(&mut self) {
+        self.renewal.abort();
+        let mut redis = self.redis.clone();
+        let key = std::mem::take(&mut self.key);
+        let token = std::mem::take(&mut self.token);
+        // Should this fail, the lock lapses after lock_ttl_secs
+        tokio::spawn(async move {
+            if let Err(e) = release(&mut redis, &key, &token).await {
+                tracing::warn!("Failed to release lock {}: {}", key, e);
+            }
+        });
+    }
+}
+
+impl RuntimeState {
+    /// Whether singleton jobs should run here: on the leader in a cluster,
+    /// always otherwise
+    pub fn is_leader(&self) -> bool {
+        self.cluster.as_ref().is_none_or(|cluster| cluster.is_leader())
+    }
+
+    /// Publish session `id`'s state for the other instances; a no-op
+    /// outside a cluster
+    pub async fn share_session(&self, id: Uuid) {
+        let Some(cluster) = &self.cluster else {
+            return;
+        };
+        let manager = &self.session_manager;
+        if !manager.contains(id) {
+            return;
+        }
+        let session = SharedSession {
+            tool_policy: manager.tool_policy(id),
+            user_id: manager.user(id),
+            persona: manager.persona(id),
+            strictness: manager.strictness(id),
+            workspace: manager.workspace(id),
+            time: manager.time(id),
+            node: cluster.node_id().to_string(),
+            updated_at: Utc::now(),
+        };
+        if let Err(e) = cluster.publish_session(id, &session).await {
+            tracing::warn!("Failed to share session {}: {}", id, e);
+        }
+    }
+
+    /// Take up session `id` as another instance left it, if this one
+    /// doesn't know it yet; returns whether there was shared state to load
+    pub async fn adopt_session(&self, id: Uuid) -> bool {
+        let Some(cluster) = &self.cluster else {
+            return false;
+        };
+        if self.session_manager.contains(id) {
+            return false;
+        }
+        let shared = match cluster.load_session(id).await {
+            Ok(Some(shared)) => shared,
+            Ok(None) => return false,
+            Err(e) => {
+                tracing::warn!("Failed to load shared session {}: {}", id, e);
+                return false;
+            }
+        };
+        let manager = &self.session_manager;
+        manager.resume_session(id);
+        manager.set_tool_policy(id, shared.tool_policy);
+        if let Some(user) = &shared.user_id {
+            manager.set_user(id, user);
+        }
+        if let Some(persona) = shared.persona {
+            manager.set_persona(id, persona);
+        }
+        if let Some(strictness) = shared.strictness {
+            manager.set_strictness(id, strictness);
+        }
+        manager.set_workspace(id, shared.workspace);
+        manager.set_time(id, shared.time);
+        tracing::debug!("Adopted session {} from node {}", id, shared.node);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate() {
+        assert!(ClusterConfig::default().validate().is_ok());
+
+        let mut config = ClusterConfig { enabled: true, ..Default::default() };
+        assert!(config.validate().is_ok());
+
+        config.redis_url = "http://redis:6379".to_string();
+        assert!(config.validate().is_err());
+
+        config.redis_url = "rediss://redis:6380".to_string();
+        config.node_id = Some("node 1".to_string());
+        assert!(config.validate().is_err());
+        config.node_id = Some("jamey-1.eu".to_string());
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_leader_election_parsing_and_lock_ids() {
+        assert_eq!("Postgres".parse::<LeaderElection>().unwrap(), LeaderElection::Postgres);
+        assert!("etcd".parse::<LeaderElection>().is_err());
+
+        // Stable per cluster, distinct between clusters
+        assert_eq!(advisory_lock_id("jamey"), advisory_lock_id("jamey"));
+        assert_ne!(advisory_lock_id("jamey"), advisory_lock_id("staging"));
+    }
+
+    #[test]
+    fn test_shared_session_round_trip() {
+        let session = SharedSession {
+            tool_policy: ToolPolicy::unrestricted(),
+            user_id: Some("alice".to_string()),
+            persona: None,
+            strictness: Some(Strictness::default()),
+            workspace: None,
+            time: None,
+            node: "jamey-1".to_string(),
+            updated_at: Utc::now(),
+        };
+        let json = serde_json::to_vec(&session).unwrap();
+        let back: SharedSession = serde_json::from_slice(&json).unwrap();
+        assert_eq!(back.user_id.as_deref(), Some("alice"));
+        assert_eq!(back.tool_policy, session.tool_policy);
+        assert_eq!(back.node, "jamey-1");
+    }
+}
@@ -1,11 +1,19 @@
 use anyhow::Result;
 use jamey_core::cache::CacheConfig;
 use jamey_core::prelude::{SecretManager, redact_sensitive_data};
+use jamey_core::quantization::QuantizationConfig;
+use jamey_core::scoring::RetrievalWeights;
+use jamey_core::secure_logging::{mask_url_credentials, redact_json_secrets, Masked};
+use crate::logging::{LogFileConfig, LoggingConfig};
+use jamey_providers::audio::AudioFormat;
 use jamey_providers::openrouter::OpenRouterConfig;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
 use thiserror::Error;
-use tracing_honeycomb::SensitiveValue;
+use validator::{Validate, ValidationError, ValidationErrors, ValidationErrorsKind};
 use tracing;
 
 #[derive(Debug, Error)]
@@ -22,50 +30,211 @@ pub enum ConfigError {
     Secret(#[from] jamey_core::prelude::SecretError),
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// A secret held in the configuration. It reads and writes as the plain
+/// value; `Debug` never shows it.
+#[derive(Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct SensitiveValue<T>(pub T);
+
+impl<T> fmt::Debug for SensitiveValue<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(jamey_core::secure_logging::REDACTED)
+    }
+}
+
+/// Everything the runtime is configured with. `Debug` output masks
+/// secrets, since each section holding one writes its own `Debug`.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, Validate)]
 pub struct RuntimeConfig {
     #[serde(default = "default_project_name")]
+    #[validate(length(min = 1, max = 64))]
     pub project_name: String,
+    #[validate(nested)]
     pub memory: MemoryConfig,
     pub cache: CacheConfig,
+    #[validate(nested)]
     pub llm: LlmConfig,
     pub api: ApiConfig,
     pub security: SecurityConfig,
     pub tools: ToolConfig,
+    /// Where conversation transcripts are persisted (`JAMEY_SESSION_DIR`)
+    #[serde(default = "crate::session_store::default_session_dir")]
+    pub session_dir: PathBuf,
+    /// Where pending connector approvals are queued (`JAMEY_APPROVAL_DIR`)
+    #[serde(default = "crate::approvals::default_approval_dir")]
+    pub approval_dir: PathBuf,
+    /// Where per-call token and cost records are appended (`JAMEY_USAGE_DIR`)
+    #[serde(default = "crate::usage::default_usage_dir")]
+    pub usage_dir: PathBuf,
+    /// Where sampled prompts and completions are appended (`JAMEY_TRACE_DIR`)
+    #[serde(default = "crate::prompt_trace::default_trace_dir")]
+    pub trace_dir: PathBuf,
+    /// Where the signed audit log is appended (`JAMEY_AUDIT_DIR`)
+    #[serde(default = "crate::audit::default_audit_dir")]
+    pub audit_dir: PathBuf,
+    /// Where `jamey watch` keeps project indexes and change logs (`JAMEY_PROJECT_DIR`)
+    #[serde(default = "crate::project::default_project_dir")]
+    pub project_dir: PathBuf,
+    /// Where files attached to chat messages are kept (`JAMEY_ATTACHMENT_DIR`)
+    #[serde(default = "crate::attachments::default_attachment_dir")]
+    pub attachment_dir: PathBuf,
+    /// Per-user profiles built from feedback on replies (`JAMEY_PREFERENCE_DIR`)
+    #[serde(default = "crate::feedback::default_preference_dir")]
+    pub preference_dir: PathBuf,
+    /// Per-turn records of tool side effects that can be undone (`JAMEY_UNDO_DIR`)
+    #[serde(default = "crate::rollback::default_undo_dir")]
+    pub undo_dir: PathBuf,
+    /// Saved personas (`JAMEY_PERSONA_DIR`)
+    #[serde(default = "crate::persona::default_persona_dir")]
+    pub persona_dir: PathBuf,
+    /// Saved workspaces (`JAMEY_WORKSPACE_DIR`)
+    #[serde(default = "crate::workspace::default_workspace_dir")]
+    pub workspace_dir: PathBuf,
+    /// Persona for sessions that don't pick one (`JAMEY_PERSONA`); `default`
+    /// when unset
+    #[serde(default)]
+    pub default_persona: Option<String>,
+    /// Where registered webhooks and their secrets are kept (`JAMEY_WEBHOOK_DIR`)
+    #[serde(default = "crate::webhooks::default_webhook_dir")]
+    pub webhook_dir: PathBuf,
+    /// Background provider requests waiting for the provider to come back
+    /// (`JAMEY_OFFLINE_DIR`)
+    #[serde(default = "crate::offline::default_offline_dir")]
+    pub offline_dir: PathBuf,
+    /// Matrix login and encryption keys (`JAMEY_MATRIX_DIR`)
+    #[serde(default = "crate::matrix::default_matrix_dir")]
+    pub matrix_dir: PathBuf,
+    /// Log format, per-module filters and file output; the level is `api.log_level`
+    #[serde(default)]
+    pub logging: LoggingConfig,
+    /// Speech API, voice and player for spoken replies
+    #[serde(default)]
+    pub voice: crate::voice::VoiceConfig,
+    /// Sources, schedule and delivery of briefings
+    #[serde(default)]
+    pub briefing: crate::briefing::BriefingConfig,
+    /// Which events raise desktop notifications in `jamey chat` and the TUI
+    #[serde(default)]
+    pub notifications: crate::notifications::NotificationConfig,
+    /// Rules picking a model per request; set in the config file
+    #[serde(default)]
+    pub routing: crate::routing::RoutingConfig,
+    /// Filters assistant replies must pass before delivery
+    #[serde(default)]
+    pub guardrails: crate::guardrails::GuardrailConfig,
+    /// Limits on requests running and waiting, and who goes first
+    #[serde(default)]
+    pub queue: crate::queue::QueueConfig,
+    /// What a turn falls back to when the provider fails or the budget is tight
+    #[serde(default)]
+    pub degradation: crate::degradation::DegradationConfig,
+    /// Putting off background model calls while the provider is unreachable
+    #[serde(default)]
+    pub offline_queue: crate::offline::OfflineQueueConfig,
+    /// Sharing sessions with other runtimes behind one load balancer
+    #[serde(default)]
+    pub cluster: crate::cluster::ClusterConfig,
+    /// Keeping memory namespaces the same on two instances
+    #[serde(default)]
+    pub sync: crate::sync::SyncConfig,
+    /// Restarting background tasks that panic
+    #[serde(default)]
+    pub supervisor: jamey_core::SupervisorConfig,
+    /// Recording full prompts and completions for debugging; off by default
+    #[serde(default)]
+    pub prompt_trace: crate::prompt_trace::PromptTraceConfig,
+    /// Timezone and locale used when a session doesn't give its own
+    #[serde(default)]
+    pub locale: crate::locale::LocaleConfig,
 }
 
 fn default_project_name() -> String {
     "jamey".to_string()
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize, JsonSchema, Validate)]
 pub struct MemoryConfig {
     #[serde(default = "default_postgres_host")]
+    #[validate(length(max = 255))]
     pub postgres_host: String,
     #[serde(default = "default_postgres_port")]
     pub postgres_port: u16,
     #[serde(default = "default_postgres_db")]
+    #[validate(length(max = 64))]
     pub postgres_db: String,
     #[serde(default = "default_postgres_user")]
+    #[validate(length(max = 64))]
     pub postgres_user: String,
+    #[schemars(skip)]
+    #[validate(custom(function = "validate_postgres_password"))]
     pub postgres_password: SensitiveValue<String>,
     #[serde(default = "default_postgres_max_connections")]
-    #[serde(validate(range(min = 1, max = 100)))]
+    #[validate(range(min = 1, max = 100))]
     pub postgres_max_connections: u32,
     #[serde(default = "default_vector_dimension")]
-    #[serde(validate(range(min = 1, max = 4096)))]
+    #[validate(range(min = 1, max = 4096))]
     pub vector_dimension: usize,
     #[serde(default = "default_vector_similarity_threshold")]
-    #[serde(validate(range(min = 0.0, max = 1.0)))]
+    #[validate(range(min = 0.0, max = 1.0))]
     pub vector_similarity_threshold: f32,
+    /// `ivfflat` or `hnsw`
     #[serde(default = "default_vector_index_type")]
+    #[validate(custom(function = "validate_vector_index_type"))]
     pub vector_index_type: String,
     #[serde(default = "default_max_memory_entries")]
-    #[serde(validate(range(min = 1, max = 10000)))]
+    #[validate(range(min = 1, max = 10000))]
     pub max_memory_entries: usize,
     #[serde(default = "default_memory_retention_days")]
-    #[serde(validate(range(min = 1, max = 365)))]
+    #[validate(range(min = 1, max = 365))]
     pub memory_retention_days: u32,
+    /// Memories recalled into each chat turn and cited with the reply
+    /// (`JAMEY_CONTEXT_MEMORIES`); 0 turns recall off
+    #[serde(default = "default_context_memories")]
+    pub context_memories: usize,
+    /// Estimated tokens of pinned memories put in every chat turn
+    /// (`JAMEY_PINNED_BUDGET_TOKENS`); pins past it are left out, oldest
+    /// kept first. 0 leaves pins out of the prompt
+    #[serde(default = "default_pinned_budget_tokens")]
+    pub pinned_budget_tokens: usize,
+    /// How search ranks memories: weights for similarity, recency,
+    /// importance and access frequency (`[memory.retrieval]`)
+    #[serde(default)]
+    pub retrieval: RetrievalWeights,
+    /// Days rows are kept in the `usage_log` table
+    /// (`JAMEY_USAGE_RETENTION_DAYS`); 0 keeps them forever
+    #[serde(default = "default_usage_retention_days")]
+    pub usage_retention_days: u32,
+    /// Days unread before a memory is archived to the compressed cold table
+    /// (`JAMEY_COLD_AFTER_DAYS`); 0 never archives
+    #[serde(default)]
+    pub cold_after_days: u32,
+    /// Embedding precision per memory type (`[memory.quantization]`)
+    #[serde(default)]
+    pub quantization: QuantizationConfig,
+}
+
+impl fmt::Debug for MemoryConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MemoryConfig")
+            .field("postgres_host", &self.postgres_host)
+            .field("postgres_port", &self.postgres_port)
+            .field("postgres_db", &self.postgres_db)
+            .field("postgres_user", &self.postgres_user)
+            .field("postgres_password", &Masked::of(&self.postgres_password.0))
+            .field("postgres_max_connections", &self.postgres_max_connections)
+            .field("vector_dimension", &self.vector_dimension)
+            .field("vector_similarity_threshold", &self.vector_similarity_threshold)
+            .field("vector_index_type", &self.vector_index_type)
+            .field("max_memory_entries", &self.max_memory_entries)
+            .field("memory_retention_days", &self.memory_retention_days)
+            .field("context_memories", &self.context_memories)
+            .field("pinned_budget_tokens", &self.pinned_budget_tokens)
+            .field("retrieval", &self.retrieval)
+            .field("usage_retention_days", &self.usage_retention_days)
+            .field("cold_after_days", &self.cold_after_days)
+            .field("quantization", &self.quantization)
+            .finish()
+    }
 }
 
 fn default_postgres_host() -> String { "localhost".to_string() }
@@ -78,17 +247,83 @@ fn default_vector_similarity_threshold() -> f32 { 0.8 }
 fn default_vector_index_type() -> String { "ivfflat".to_string() }
 fn default_max_memory_entries() -> usize { 1000 }
 fn default_memory_retention_days() -> u32 { 30 }
+fn default_context_memories() -> usize { 5 }
+fn default_pinned_budget_tokens() -> usize { 1000 }
+fn default_usage_retention_days() -> u32 { 400 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+fn validate_postgres_password(password: &SensitiveValue<String>) -> Result<(), ValidationError> {
+    if password.0 == "change_me_in_production" {
+        return Err(ValidationError::new("placeholder").with_message("the default password must be changed".into()));
+    }
+    Ok(())
+}
+
+fn validate_vector_index_type(index_type: &str) -> Result<(), ValidationError> {
+    if !["ivfflat", "hnsw"].contains(&index_type) {
+        return Err(ValidationError::new("index_type").with_message("must be ivfflat or hnsw".into()));
+    }
+    Ok(())
+}
+
+#[derive(Clone, Serialize, Deserialize, JsonSchema, Validate)]
 pub struct LlmConfig {
+    #[schemars(skip)]
+    #[validate(custom(function = "validate_required_secret"))]
     pub openrouter_api_key: SensitiveValue<String>,
     pub openrouter_default_model: String,
     pub openrouter_allowed_models: Vec<String>,
+    #[validate(range(min = 1, max = 300))]
     pub openrouter_timeout_seconds: u64,
+    #[validate(range(max = 10))]
     pub openrouter_max_retries: u32,
+    /// Spend per UTC day reported against by `jamey status` (`JAMEY_DAILY_BUDGET_USD`)
+    #[serde(default)]
+    pub daily_budget_usd: Option<f64>,
+    /// History size, in estimated tokens, past which the oldest turns are
+    /// summarized (`JAMEY_CONTEXT_BUDGET_TOKENS`); 0 never summarizes
+    #[serde(default = "default_context_budget_tokens")]
+    #[validate(custom(function = "validate_context_budget"))]
+    pub context_budget_tokens: usize,
+    /// Answer directly, or have a cheaper model draft each reply for the
+    /// chat model to verify (`JAMEY_DRAFT_MODEL`)
+    #[serde(default)]
+    pub generation: crate::generation::GenerationStrategy,
+}
+
+impl fmt::Debug for LlmConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LlmConfig")
+            .field("openrouter_api_key", &Masked::of(&self.openrouter_api_key.0))
+            .field("openrouter_default_model", &self.openrouter_default_model)
+            .field("openrouter_allowed_models", &self.openrouter_allowed_models)
+            .field("openrouter_timeout_seconds", &self.openrouter_timeout_seconds)
+            .field("openrouter_max_retries", &self.openrouter_max_retries)
+            .field("daily_budget_usd", &self.daily_budget_usd)
+            .field("context_budget_tokens", &self.context_budget_tokens)
+            .field("generation", &self.generation)
+            .finish()
+    }
+}
+
+fn default_context_budget_tokens() -> usize {
+    24_000
+}
+
+fn validate_required_secret(secret: &SensitiveValue<String>) -> Result<(), ValidationError> {
+    if secret.0.is_empty() {
+        return Err(ValidationError::new("required"));
+    }
+    Ok(())
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+fn validate_context_budget(tokens: usize) -> Result<(), ValidationError> {
+    if tokens != 0 && tokens < 1000 {
+        return Err(ValidationError::new("budget").with_message("must be 0 (off) or at least 1000".into()));
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ApiConfig {
     pub host: String,
     pub http_port: u16,
@@ -102,21 +337,56 @@ pub struct ApiConfig {
     pub hsts_include_subdomains: bool,
     pub hsts_preload: bool,
     pub enable_https: bool,
+    /// Where the HTTPS certificate comes from: the files above, a generated
+    /// self-signed one, or ACME (`API_TLS_CERT_MODE`)
+    #[serde(default)]
+    pub tls_certificates: crate::certificates::CertificateConfig,
     pub redirect_http_to_https: bool,
     pub log_level: String,
     pub allowed_origins: Vec<String>,
     pub enable_cors: bool,
     pub metrics_port: Option<u16>,
     pub health_check_port: Option<u16>,
+    /// Accept inbound `/hooks/{name}` requests on `http_port` (`JAMEY_HOOKS`)
+    #[serde(default)]
+    pub hooks: bool,
+    /// Serve the browser chat on `http_port` (`JAMEY_WEB_UI`); needs a build
+    /// with the `web-ui` feature
+    #[serde(default = "default_web_ui")]
+    pub web_ui: bool,
+    /// Where audit events are forwarded besides the signed file
+    /// (`JAMEY_AUDIT_SYSLOG`, `JAMEY_AUDIT_EVENT_LOG`)
+    #[serde(default)]
+    pub audit_sinks: crate::audit_sinks::AuditSinksConfig,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+fn default_web_ui() -> bool {
+    true
+}
+
+#[derive(Clone, Serialize, Deserialize, JsonSchema)]
 pub struct SecurityConfig {
+    #[schemars(skip)]
     pub api_key_required: bool,
+    #[schemars(skip)]
     pub api_key: Option<SensitiveValue<String>>,
+    /// Secrets and identifiers replaced before memories, transcripts, events
+    /// and logs are written (`JAMEY_REDACTION` turns it on or off)
+    #[serde(default)]
+    pub redaction: jamey_core::redaction::RedactionConfig,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl fmt::Debug for SecurityConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SecurityConfig")
+            .field("api_key_required", &self.api_key_required)
+            .field("api_key", &Masked::opt(self.api_key.as_ref().map(|k| k.0.as_str())))
+            .field("redaction", &self.redaction)
+            .finish()
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ToolConfig {
     pub enable_registry_tool: bool,
     pub backup_dir: PathBuf,
@@ -130,10 +400,95 @@ pub struct ToolConfig {
     pub linkedin_token: Option<String>,
     pub web_search_api_key: Option<String>,
     pub mcp_server_url: Option<String>,
+    /// OAuth apps used instead of pasted tokens when no token is configured
+    #[serde(default)]
+    pub oauth_clients: Vec<jamey_tools::oauth::OAuthClientConfig>,
+    /// Runs Jamey as a Telegram bot when set (`TELEGRAM_BOT_TOKEN`)
+    #[serde(default)]
+    pub telegram_bot_token: Option<String>,
+    /// Chat IDs the bot answers (`TELEGRAM_ALLOWED_CHATS`, comma-separated);
+    /// it answers nobody when empty
+    #[serde(default)]
+    pub telegram_allowed_chats: Vec<i64>,
+    /// Public base URL Telegram posts updates to, routed to `/telegram` on
+    /// `api.http_port` when `api.hooks` is on (`TELEGRAM_WEBHOOK_URL`); long
+    /// polling when unset
+    #[serde(default)]
+    pub telegram_webhook_url: Option<String>,
+    /// Homeserver URL; answers Matrix rooms when set and the `matrix`
+    /// feature is built in (`MATRIX_HOMESERVER`)
+    #[serde(default)]
+    pub matrix_homeserver: Option<String>,
+    /// Bot account, as `@user:server` or a localpart (`MATRIX_USER`)
+    #[serde(default)]
+    pub matrix_user: Option<String>,
+    /// Only needed for the first login; later starts reuse the saved device (`MATRIX_PASSWORD`)
+    #[serde(default)]
+    pub matrix_password: Option<String>,
+    /// Room IDs or aliases to join and answer in (`MATRIX_ROOMS`, comma-separated)
+    #[serde(default)]
+    pub matrix_rooms: Vec<String>,
+    /// Encrypts the stored encryption keys (`MATRIX_STORE_PASSPHRASE`)
+    #[serde(default)]
+    pub matrix_store_passphrase: Option<String>,
+    /// User and resource limits for the commands connectors start
+    /// (`JAMEY_SANDBOX_*`)
+    #[serde(default)]
+    pub sandbox: jamey_tools::sandbox::SandboxConfig,
+    /// Which files file-access tools and ingestion may read and write
+    /// (`JAMEY_READ_PATHS`, `JAMEY_WRITE_PATHS`, `JAMEY_DENY_PATHS`,
+    /// `JAMEY_MAX_FILE_BYTES`)
+    #[serde(default)]
+    pub path_policy: jamey_tools::path_policy::PathPolicyConfig,
+    /// How long each connector call may run before it is abandoned
+    /// (`JAMEY_TOOL_TIMEOUT_SECS` sets the default)
+    #[serde(default)]
+    pub timeouts: jamey_tools::timeouts::ToolTimeoutConfig,
+    /// Limits on the per-session `scratchpad` tool
+    /// (`JAMEY_SCRATCHPAD_TTL_SECS` sets the default TTL)
+    #[serde(default)]
+    pub scratchpad: jamey_tools::scratchpad::ScratchpadConfig,
     pub enable_24_7: bool,
     pub scheduler_enabled: bool,
 }
 
+impl fmt::Debug for ToolConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ToolConfig")
+            .field("enable_registry_tool", &self.enable_registry_tool)
+            .field("backup_dir", &self.backup_dir)
+            .field("process_tool_enabled", &self.process_tool_enabled)
+            .field("process_tool_max_list", &self.process_tool_max_list)
+            .field("self_modify_backup_count", &self.self_modify_backup_count)
+            .field("download_dir", &self.download_dir)
+            .field("system_root", &self.system_root)
+            .field("github_token", &Masked::opt(self.github_token.as_deref()))
+            .field("linkedin_token", &Masked::opt(self.linkedin_token.as_deref()))
+            .field("web_search_api_key", &Masked::opt(self.web_search_api_key.as_deref()))
+            .field("mcp_server_url", &self.mcp_server_url.as_deref().map(mask_url_credentials))
+            .field("oauth_clients", &self.oauth_clients)
+            .field("telegram_bot_token", &Masked::opt(self.telegram_bot_token.as_deref()))
+            .field("telegram_allowed_chats", &self.telegram_allowed_chats)
+            .field("telegram_webhook_url", &self.telegram_webhook_url)
+            .field("matrix_homeserver", &self.matrix_homeserver)
+            .field("matrix_user", &self.matrix_user)
+            .field("matrix_password", &Masked::opt(self.matrix_password.as_deref()))
+            .field("matrix_rooms", &self.matrix_rooms)
+            .field("matrix_store_passphrase", &Masked::opt(self.matrix_store_passphrase.as_deref()))
+            .field("sandbox", &self.sandbox)
+            .field("path_policy", &self.path_policy)
+            .field("timeouts", &self.timeouts)
+            .field("scratchpad", &self.scratchpad)
+            .field("enable_24_7", &self.enable_24_7)
+            .field("scheduler_enabled", &self.scheduler_enabled)
+            .finish()
+    }
+}
+
+/// Placeholders for the secrets (an empty OpenRouter key, a known database
+/// password) make the default config fail [`validate`](RuntimeConfig::validate);
+/// use [`RuntimeConfig::builder`] or [`RuntimeConfig::from_env`] to get a
+/// usable one.
 impl Default for RuntimeConfig {
     fn default() -> Self {
         Self {
@@ -144,6 +499,35 @@ impl Default for RuntimeConfig {
             api: ApiConfig::default(),
             security: SecurityConfig::default(),
             tools: ToolConfig::default(),
+            session_dir: crate::session_store::default_session_dir(),
+            approval_dir: crate::approvals::default_approval_dir(),
+            usage_dir: crate::usage::default_usage_dir(),
+            trace_dir: crate::prompt_trace::default_trace_dir(),
+            audit_dir: crate::audit::default_audit_dir(),
+            project_dir: crate::project::default_project_dir(),
+            attachment_dir: crate::attachments::default_attachment_dir(),
+            preference_dir: crate::feedback::default_preference_dir(),
+            undo_dir: crate::rollback::default_undo_dir(),
+            persona_dir: crate::persona::default_persona_dir(),
+            workspace_dir: crate::workspace::default_workspace_dir(),
+            default_persona: None,
+            webhook_dir: crate::webhooks::default_webhook_dir(),
+            offline_dir: crate::offline::default_offline_dir(),
+            matrix_dir: crate::matrix::default_matrix_dir(),
+            logging: LoggingConfig::default(),
+            voice: crate::voice::VoiceConfig::default(),
+            briefing: crate::briefing::BriefingConfig::default(),
+            notifications: crate::notifications::NotificationConfig::default(),
+            routing: crate::routing::RoutingConfig::default(),
+            guardrails: crate::guardrails::GuardrailConfig::default(),
+            queue: crate::queue::QueueConfig::default(),
+            degradation: crate::degradation::DegradationConfig::default(),
+            offline_queue: crate::offline::OfflineQueueConfig::default(),
+            cluster: crate::cluster::ClusterConfig::default(),
+            sync: crate::sync::SyncConfig::default(),
+            supervisor: jamey_core::SupervisorConfig::default(),
+            prompt_trace: crate::prompt_trace::PromptTraceConfig::default(),
+            locale: crate::locale::LocaleConfig::default(),
         }
     }
 }
@@ -162,6 +546,12 @@ impl Default for MemoryConfig {
             vector_index_type: "ivfflat".to_string(),
             max_memory_entries: 1000,
             memory_retention_days: 30,
+            context_memories: default_context_memories(),
+            pinned_budget_tokens: default_pinned_budget_tokens(),
+            retrieval: RetrievalWeights::default(),
+            usage_retention_days: default_usage_retention_days(),
+            cold_after_days: 0,
+            quantization: QuantizationConfig::default(),
         }
     }
 }
@@ -178,6 +568,9 @@ impl Default for LlmConfig {
             ],
             openrouter_timeout_seconds: 30,
             openrouter_max_retries: 3,
+            daily_budget_usd: None,
+            context_budget_tokens: default_context_budget_tokens(),
+            generation: crate::generation::GenerationStrategy::default(),
         }
     }
 }
@@ -197,12 +590,16 @@ impl Default for ApiConfig {
             hsts_include_subdomains: true,
             hsts_preload: false,
             enable_https: false,
+            tls_certificates: crate::certificates::CertificateConfig::default(),
             redirect_http_to_https: false,
             log_level: "info".to_string(),
             allowed_origins: vec!["http://localhost:3000".to_string()],
             enable_cors: true,
             metrics_port: Some(9090),
             health_check_port: Some(8081),
+            hooks: false,
+            web_ui: true,
+            audit_sinks: crate::audit_sinks::AuditSinksConfig::default(),
         }
     }
 }
@@ -212,6 +609,7 @@ impl Default for SecurityConfig {
         Self {
             api_key_required: true,
             api_key: None,
+            redaction: Default::default(),
         }
     }
 }
@@ -230,19 +628,234 @@ impl Default for ToolConfig {
             linkedin_token: None,
             web_search_api_key: None,
             mcp_server_url: None,
+            oauth_clients: Vec::new(),
+            telegram_bot_token: None,
+            telegram_allowed_chats: Vec::new(),
+            telegram_webhook_url: None,
+            matrix_homeserver: None,
+            matrix_user: None,
+            matrix_password: None,
+            matrix_rooms: Vec::new(),
+            matrix_store_passphrase: None,
+            sandbox: jamey_tools::sandbox::SandboxConfig::default(),
+            path_policy: jamey_tools::path_policy::PathPolicyConfig::default(),
+            timeouts: jamey_tools::timeouts::ToolTimeoutConfig::default(),
+            scratchpad: jamey_tools::scratchpad::ScratchpadConfig::default(),
             enable_24_7: false,
             scheduler_enabled: false,
         }
     }
 }
 
+/// Environment variable holding the config file path; the CLI sets it from `--config`
+pub const CONFIG_FILE_ENV: &str = "JAMEY_CONFIG";
+
+/// Keys a config file may not set, with the variable to use instead. Secrets
+/// stay out of files that tend to end up in dotfile repos.
+const ENV_ONLY_KEYS: &[(&str, &str)] = &[
+    ("memory.postgres_password", "POSTGRES_PASSWORD"),
+    ("llm.openrouter_api_key", "OPENROUTER_API_KEY"),
+    ("security.api_key", "API_KEY"),
+    ("security.api_key_required", "API_KEY_REQUIRED"),
+    ("briefing.smtp_password", "SMTP_PASSWORD"),
+    ("briefing.slack_webhook_url", "SLACK_WEBHOOK_URL"),
+    ("sync.secret", "JAMEY_SYNC_SECRET"),
+];
+
+/// Which layer a configuration value came from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigOrigin {
+    Default,
+    File(PathBuf),
+    Env(String),
+}
+
+impl fmt::Display for ConfigOrigin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigOrigin::Default => write!(f, "default"),
+            ConfigOrigin::File(path) => write!(f, "file {}", path.display()),
+            ConfigOrigin::Env(var) => write!(f, "env {}", var),
+        }
+    }
+}
+
+/// Origin of every dotted key (`api.http_port`) in a loaded [`RuntimeConfig`]
+#[derive(Debug, Clone, Default)]
+pub struct ConfigOrigins {
+    origins: BTreeMap<String, ConfigOrigin>,
+    file: Option<PathBuf>,
+}
+
+impl ConfigOrigins {
+    /// The config file that was merged, if one existed
+    pub fn file(&self) -> Option<&Path> {
+        self.file.as_deref()
+    }
+
+    /// Origin of `key`, falling back to the nearest recorded parent so
+    /// `logging.file.dir` reports wherever `logging.file` was set
+    pub fn get(&self, key: &str) -> &ConfigOrigin {
+        let mut key = key;
+        loop {
+            if let Some(origin) = self.origins.get(key) {
+                return origin;
+            }
+            match key.rsplit_once('.') {
+                Some((parent, _)) => key = parent,
+                None => return &ConfigOrigin::Default,
+            }
+        }
+    }
+
+    fn set(&mut self, key: String, origin: ConfigOrigin) {
+        // A later layer replaces everything recorded beneath the key it sets
+        let prefix = format!("{}.", key);
+        self.origins.retain(|k, _| !k.starts_with(&prefix));
+        self.origins.insert(key, origin);
+    }
+
+    fn env(&mut self, key: &str, var: &str) {
+        self.set(key.to_string(), ConfigOrigin::Env(var.to_string()));
+    }
+}
+
+/// Flatten nested tables into dotted keys; arrays are kept as single values
+fn flatten_into(prefix: &str, value: &serde_json::Value, out: &mut BTreeMap<String, serde_json::Value>) {
+    match value {
+        serde_json::Value::Object(map) => {
+            for (key, value) in map {
+                let key = if prefix.is_empty() { key.clone() } else { format!("{}.{}", prefix, key) };
+                flatten_into(&key, value, out);
+            }
+        }
+        _ => {
+            out.insert(prefix.to_string(), value.clone());
+        }
+    }
+}
+
+fn flatten_keys(value: &serde_json::Value) -> Vec<String> {
+    let mut out = BTreeMap::new();
+    flatten_into("", value, &mut out);
+    out.into_keys().collect()
+}
+
+/// Every failed field check with its dotted key, in key order
+fn field_problems(errors: &ValidationErrors) -> Vec<(String, &ValidationError)> {
+    fn collect<'a>(prefix: &str, errors: &'a ValidationErrors, out: &mut Vec<(String, &'a ValidationError)>) {
+        for (field, kind) in errors.errors() {
+            let key = if prefix.is_empty() { field.to_string() } else { format!("{}.{}", prefix, field) };
+            match kind {
+                ValidationErrorsKind::Field(failed) => out.extend(failed.iter().map(|e| (key.clone(), e))),
+                ValidationErrorsKind::Struct(nested) => collect(&key, nested, out),
+                ValidationErrorsKind::List(items) => {
+                    for (index, nested) in items {
+                        collect(&format!("{}.{}", key, index), nested, out);
+                    }
+                }
+            }
+        }
+    }
+    let mut out = Vec::new();
+    collect("", errors, &mut out);
+    out.sort_by(|a, b| a.0.cmp(&b.0));
+    out
+}
+
+/// What a failed check wants, in words
+fn describe(error: &ValidationError) -> String {
+    if let Some(message) = &error.message {
+        return message.to_string();
+    }
+    let param = |name: &str| error.params.get(name).map(|v| v.to_string());
+    match (error.code.as_ref(), param("min"), param("max")) {
+        ("required", ..) => "must be set".to_string(),
+        ("range", Some(min), Some(max)) => format!("must be between {} and {}", min, max),
+        ("range", None, Some(max)) => format!("must be at most {}", max),
+        ("range", Some(min), None) => format!("must be at least {}", min),
+        ("length", Some(min), Some(max)) => format!("must be {} to {} characters", min, max),
+        ("length", None, Some(max)) => format!("must be at most {} characters", max),
+        ("length", Some(min), None) => format!("must be at least {} characters", min),
+        (code, ..) => format!("failed the {} check", code),
+    }
+}
+
+/// Drop every `required` list, leaving all keys optional
+fn strip_required(schema: &mut serde_json::Value) {
+    match schema {
+        serde_json::Value::Object(map) => {
+            if map.get("required").is_some_and(|v| v.is_array()) {
+                map.remove("required");
+            }
+            map.values_mut().for_each(strip_required);
+        }
+        serde_json::Value::Array(items) => items.iter_mut().for_each(strip_required),
+        _ => {}
+    }
+}
+
+/// Non-empty entries of a comma-separated environment value
+fn list(value: &str) -> impl Iterator<Item = &str> {
+    value.split(',').map(str::trim).filter(|entry| !entry.is_empty())
+}
+
 impl RuntimeConfig {
+    /// Start from the defaults; [`build`](RuntimeConfigBuilder::build) fails
+    /// until the database password and OpenRouter key are set and either an
+    /// API key is given or [`without_api_key`](RuntimeConfigBuilder::without_api_key)
+    /// is called
+    pub fn builder() -> RuntimeConfigBuilder {
+        RuntimeConfigBuilder {
+            config: Self::default(),
+            model: None,
+            allowed_models: None,
+        }
+    }
+
+    /// Every setting as a dotted key with secrets masked, for `jamey system
+    /// config show`
+    pub fn flattened(&self) -> BTreeMap<String, serde_json::Value> {
+        let mut out = BTreeMap::new();
+        flatten_into("", &self.to_redacted_json(), &mut out);
+        out
+    }
+
+    /// The config as JSON with every secret masked: the environment-only
+    /// keys, fields named like secrets, and passwords in URLs
+    pub fn to_redacted_json(&self) -> serde_json::Value {
+        let mut value = serde_json::to_value(self).unwrap_or_default();
+        for (key, _) in ENV_ONLY_KEYS {
+            let pointer = format!("/{}", key.replace('.', "/"));
+            if let Some(serde_json::Value::String(secret)) = value.pointer_mut(&pointer) {
+                if !secret.is_empty() {
+                    *secret = jamey_core::secure_logging::REDACTED.to_string();
+                }
+            }
+        }
+        redact_json_secrets(&mut value);
+        value
+    }
+
+    /// Load from the file named by `JAMEY_CONFIG` (the CLI sets it from
+    /// `--config`) with environment variables layered on top
     pub fn from_env() -> Result<Self, ConfigError> {
+        let path = std::env::var_os(CONFIG_FILE_ENV).map(PathBuf::from);
+        Self::load_with_origins(path.as_deref()).map(|(config, _)| config)
+    }
+
+    /// Defaults, then the TOML or YAML file at `path` if it exists, then
+    /// environment variables, recording which layer set each key. Secrets
+    /// are only ever read from the environment.
+    pub fn load_with_origins(path: Option<&Path>) -> Result<(Self, ConfigOrigins), ConfigError> {
         // Load .env file if it exists, but don't fail if it doesn't
         dotenv::dotenv().ok();
 
-        // Initialize secret manager
-        let secret_manager = SecretManager::new("jamey_runtime");
+        // Initialize secret manager with the backend selected by JAMEY_SECRETS_BACKEND
+        let secret_manager = SecretManager::with_backend(
+            "jamey_runtime",
+            jamey_core::backend_from_env()?,
+        )?;
 
         // Load required environment variables and store them securely
         let postgres_password = std::env::var("POSTGRES_PASSWORD")
@@ -266,146 +879,636 @@ impl RuntimeConfig {
         };
 
 
-        // Create base config from defaults
-        let mut config = Self::default();
+        // Defaults with the config file, if any, merged over them
+        let mut origins = ConfigOrigins::default();
+        let mut config = Self::from_file(path, &mut origins)?;
 
         // Update with securely stored values
         config.memory.postgres_password = SensitiveValue(secret_manager.get_secret("postgres_password")?);
+        origins.env("memory.postgres_password", "POSTGRES_PASSWORD");
         config.llm.openrouter_api_key = SensitiveValue(secret_manager.get_secret("openrouter_api_key")?);
+        origins.env("llm.openrouter_api_key", "OPENROUTER_API_KEY");
         if api_key.is_some() {
             config.security.api_key = Some(SensitiveValue(secret_manager.get_secret("api_key")?));
+            origins.env("security.api_key", "API_KEY");
         }
         config.security.api_key_required = std::env::var("API_KEY_REQUIRED")
             .map(|v| v == "true")
             .unwrap_or(true);
+        if std::env::var_os("API_KEY_REQUIRED").is_some() {
+            origins.env("security.api_key_required", "API_KEY_REQUIRED");
+        }
 
         // Load optional environment variables
         if let Ok(host) = std::env::var("API_HOST") {
             config.api.host = host;
+            origins.env("api.host", "API_HOST");
         }
         if let Ok(port) = std::env::var("API_HTTP_PORT").and_then(|p| p.parse().map_err(|_| std::env::VarError::NotPresent)) {
             config.api.http_port = port;
+            origins.env("api.http_port", "API_HTTP_PORT");
         }
         if let Ok(port) = std::env::var("API_HTTPS_PORT").and_then(|p| p.parse().map_err(|_| std::env::VarError::NotPresent)) {
             config.api.https_port = port;
+            origins.env("api.https_port", "API_HTTPS_PORT");
+        }
+        if let Ok(port) = std::env::var("METRICS_PORT").and_then(|p| p.parse().map_err(|_| std::env::VarError::NotPresent)) {
+            config.api.metrics_port = Some(port);
+            origins.env("api.metrics_port", "METRICS_PORT");
+        }
+        if let Ok(hooks) = std::env::var("JAMEY_HOOKS").and_then(|v| v.parse().map_err(|_| std::env::VarError::NotPresent)) {
+            config.api.hooks = hooks;
+            origins.env("api.hooks", "JAMEY_HOOKS");
+        }
+        if let Ok(web_ui) = std::env::var("JAMEY_WEB_UI").and_then(|v| v.parse().map_err(|_| std::env::VarError::NotPresent)) {
+            config.api.web_ui = web_ui;
+            origins.env("api.web_ui", "JAMEY_WEB_UI");
+        }
+        if let Ok(url) = std::env::var("JAMEY_AUDIT_SYSLOG") {
+            config.api.audit_sinks.syslog = Some(crate::audit_sinks::SyslogConfig::from_url(&url).map_err(ConfigError::InvalidValue)?);
+            origins.env("api.audit_sinks.syslog", "JAMEY_AUDIT_SYSLOG");
+        }
+        if let Ok(source) = std::env::var("JAMEY_AUDIT_EVENT_LOG") {
+            config.api.audit_sinks.windows_event_log = Some(crate::audit_sinks::EventLogConfig {
+                source,
+                ..Default::default()
+            });
+            origins.env("api.audit_sinks.windows_event_log", "JAMEY_AUDIT_EVENT_LOG");
         }
         if let Ok(cert_path) = std::env::var("API_TLS_CERT_PATH") {
             config.api.tls_cert_path = Some(PathBuf::from(cert_path));
+            origins.env("api.tls_cert_path", "API_TLS_CERT_PATH");
         }
         if let Ok(key_path) = std::env::var("API_TLS_KEY_PATH") {
             config.api.tls_key_path = Some(PathBuf::from(key_path));
+            origins.env("api.tls_key_path", "API_TLS_KEY_PATH");
         }
         if let Ok(ca_cert_path) = std::env::var("API_TLS_CA_CERT_PATH") {
             config.api.tls_ca_cert_path = Some(PathBuf::from(ca_cert_path));
+            origins.env("api.tls_ca_cert_path", "API_TLS_CA_CERT_PATH");
+        }
+        if let Ok(mode) = std::env::var("API_TLS_CERT_MODE") {
+            config.api.tls_certificates.mode = mode.parse().map_err(ConfigError::InvalidValue)?;
+            origins.env("api.tls_certificates.mode", "API_TLS_CERT_MODE");
+        }
+        if let Ok(domains) = std::env::var("API_TLS_DOMAINS") {
+            config.api.tls_certificates.domains = domains
+                .split(',')
+                .map(|d| d.trim().to_string())
+                .filter(|d| !d.is_empty())
+                .collect();
+            origins.env("api.tls_certificates.domains", "API_TLS_DOMAINS");
+        }
+        if let Ok(email) = std::env::var("API_TLS_ACME_EMAIL") {
+            config.api.tls_certificates.acme_email = Some(email);
+            origins.env("api.tls_certificates.acme_email", "API_TLS_ACME_EMAIL");
+        }
+        if let Ok(directory) = std::env::var("API_TLS_ACME_DIRECTORY") {
+            config.api.tls_certificates.acme_directory = directory;
+            origins.env("api.tls_certificates.acme_directory", "API_TLS_ACME_DIRECTORY");
         }
         if let Ok(tls_version) = std::env::var("API_TLS_MIN_VERSION") {
             config.api.tls_min_version = tls_version;
+            origins.env("api.tls_min_version", "API_TLS_MIN_VERSION");
         }
         if let Ok(enable_hsts) = std::env::var("API_ENABLE_HSTS") {
             config.api.enable_hsts = enable_hsts == "true" || enable_hsts == "1";
+            origins.env("api.enable_hsts", "API_ENABLE_HSTS");
         }
         if let Ok(hsts_max_age) = std::env::var("API_HSTS_MAX_AGE").and_then(|m| m.parse().map_err(|_| std::env::VarError::NotPresent)) {
             config.api.hsts_max_age = hsts_max_age;
+            origins.env("api.hsts_max_age", "API_HSTS_MAX_AGE");
         }
         if let Ok(hsts_subdomains) = std::env::var("API_HSTS_INCLUDE_SUBDOMAINS") {
             config.api.hsts_include_subdomains = hsts_subdomains == "true" || hsts_subdomains == "1";
+            origins.env("api.hsts_include_subdomains", "API_HSTS_INCLUDE_SUBDOMAINS");
         }
         if let Ok(hsts_preload) = std::env::var("API_HSTS_PRELOAD") {
             config.api.hsts_preload = hsts_preload == "true" || hsts_preload == "1";
+            origins.env("api.hsts_preload", "API_HSTS_PRELOAD");
         }
         if let Ok(enable_https) = std::env::var("API_ENABLE_HTTPS") {
             config.api.enable_https = enable_https == "true" || enable_https == "1";
+            origins.env("api.enable_https", "API_ENABLE_HTTPS");
         }
         if let Ok(redirect_https) = std::env::var("API_REDIRECT_HTTP_TO_HTTPS") {
             config.api.redirect_http_to_https = redirect_https == "true" || redirect_https == "1";
+            origins.env("api.redirect_http_to_https", "API_REDIRECT_HTTP_TO_HTTPS");
         }
         
+        if let Ok(level) = std::env::var("LOG_LEVEL") {
+            config.api.log_level = level;
+            origins.env("api.log_level", "LOG_LEVEL");
+        }
+        if let Ok(format) = std::env::var("LOG_FORMAT") {
+            config.logging.format = format.parse().map_err(ConfigError::InvalidValue)?;
+            origins.env("logging.format", "LOG_FORMAT");
+        }
+        if let Ok(filters) = std::env::var("LOG_FILTERS") {
+            config.logging.filters = filters.split(',').map(|f| f.trim().to_string()).filter(|f| !f.is_empty()).collect();
+            origins.env("logging.filters", "LOG_FILTERS");
+        }
+        if let Ok(dir) = std::env::var("LOG_DIR") {
+            let mut file = LogFileConfig::new(dir);
+            if let Ok(rotation) = std::env::var("LOG_ROTATION") {
+                file.rotation = rotation.parse().map_err(ConfigError::InvalidValue)?;
+            }
+            config.logging.file = Some(file);
+            origins.env("logging.file", "LOG_DIR");
+        }
+        if let Ok(redact) = std::env::var("LOG_REDACT") {
+            config.logging.redact = !(redact == "false" || redact == "0");
+            origins.env("logging.redact", "LOG_REDACT");
+        }
+
+        if let Ok(key) = std::env::var("JAMEY_TTS_API_KEY") {
+            config.voice.api_key = Some(key);
+            origins.env("voice.api_key", "JAMEY_TTS_API_KEY");
+        } else if let Ok(key) = std::env::var("OPENAI_API_KEY") {
+            config.voice.api_key = Some(key);
+            origins.env("voice.api_key", "OPENAI_API_KEY");
+        }
+        if let Ok(url) = std::env::var("JAMEY_TTS_URL") {
+            config.voice.api_base_url = url;
+            origins.env("voice.api_base_url", "JAMEY_TTS_URL");
+        }
+        if let Ok(model) = std::env::var("JAMEY_TTS_MODEL") {
+            config.voice.model = model;
+            origins.env("voice.model", "JAMEY_TTS_MODEL");
+        }
+        if let Ok(voice) = std::env::var("JAMEY_VOICE") {
+            config.voice.voice = voice;
+            origins.env("voice.voice", "JAMEY_VOICE");
+        }
+        if let Ok(rate) = std::env::var("JAMEY_VOICE_RATE") {
+            config.voice.rate = rate;
+            origins.env("voice.rate", "JAMEY_VOICE_RATE");
+        }
+        if let Ok(format) = std::env::var("JAMEY_VOICE_FORMAT") {
+            config.voice.format = format.parse::<AudioFormat>().map_err(|e| ConfigError::InvalidValue(e.to_string()))?;
+            origins.env("voice.format", "JAMEY_VOICE_FORMAT");
+        }
+        if let Ok(dir) = std::env::var("JAMEY_VOICE_DIR") {
+            config.voice.output_dir = PathBuf::from(dir);
+            origins.env("voice.output_dir", "JAMEY_VOICE_DIR");
+        }
+        if let Ok(player) = std::env::var("JAMEY_AUDIO_PLAYER") {
+            config.voice.player = Some(player);
+            origins.env("voice.player", "JAMEY_AUDIO_PLAYER");
+        }
+        if let Ok(model) = std::env::var("JAMEY_STT_MODEL") {
+            config.voice.transcription_model = model;
+            origins.env("voice.transcription_model", "JAMEY_STT_MODEL");
+        }
+        if let Ok(language) = std::env::var("JAMEY_VOICE_LANGUAGE") {
+            config.voice.language = Some(language);
+            origins.env("voice.language", "JAMEY_VOICE_LANGUAGE");
+        }
+
+        if let Ok(persona) = std::env::var("JAMEY_PERSONA") {
+            config.default_persona = Some(persona);
+            origins.env("default_persona", "JAMEY_PERSONA");
+        }
+
+        if let Ok(schedule) = std::env::var("JAMEY_BRIEFING_SCHEDULE") {
+            config.briefing.schedule = Some(schedule);
+            origins.env("briefing.schedule", "JAMEY_BRIEFING_SCHEDULE");
+        }
+        if let Ok(sources) = std::env::var("JAMEY_BRIEFING_SOURCES") {
+            config.briefing.sources = list(&sources)
+                .map(|source| source.parse())
+                .collect::<Result<_, _>>()
+                .map_err(|e: crate::briefing::BriefingError| ConfigError::InvalidValue(e.to_string()))?;
+            origins.env("briefing.sources", "JAMEY_BRIEFING_SOURCES");
+        }
+        if let Ok(feeds) = std::env::var("JAMEY_BRIEFING_FEEDS") {
+            config.briefing.feeds = list(&feeds).map(String::from).collect();
+            origins.env("briefing.feeds", "JAMEY_BRIEFING_FEEDS");
+        }
+        if let Ok(channels) = std::env::var("JAMEY_BRIEFING_CHANNELS") {
+            config.briefing.channels = list(&channels)
+                .map(|channel| channel.parse())
+                .collect::<Result<_, _>>()
+                .map_err(|e: crate::briefing::BriefingError| ConfigError::InvalidValue(e.to_string()))?;
+            origins.env("briefing.channels", "JAMEY_BRIEFING_CHANNELS");
+        }
+        if let Ok(dir) = std::env::var("JAMEY_BRIEFING_DIR") {
+            config.briefing.dir = PathBuf::from(dir);
+            origins.env("briefing.dir", "JAMEY_BRIEFING_DIR");
+        }
+        if let Ok(to) = std::env::var("JAMEY_BRIEFING_EMAIL_TO") {
+            config.briefing.email_to = list(&to).map(String::from).collect();
+            origins.env("briefing.email_to", "JAMEY_BRIEFING_EMAIL_TO");
+        }
+        if let Ok(from) = std::env::var("JAMEY_BRIEFING_EMAIL_FROM") {
+            config.briefing.email_from = Some(from);
+            origins.env("briefing.email_from", "JAMEY_BRIEFING_EMAIL_FROM");
+        }
+        if let Ok(host) = std::env::var("SMTP_HOST") {
+            config.briefing.smtp_host = Some(host);
+            origins.env("briefing.smtp_host", "SMTP_HOST");
+        }
+        if let Ok(port) = std::env::var("SMTP_PORT").and_then(|p| p.parse().map_err(|_| std::env::VarError::NotPresent)) {
+            config.briefing.smtp_port = port;
+            origins.env("briefing.smtp_port", "SMTP_PORT");
+        }
+        if let Ok(user) = std::env::var("SMTP_USER") {
+            config.briefing.smtp_user = Some(user);
+            origins.env("briefing.smtp_user", "SMTP_USER");
+        }
+        if let Ok(password) = std::env::var("SMTP_PASSWORD") {
+            config.briefing.smtp_password = Some(password);
+            origins.env("briefing.smtp_password", "SMTP_PASSWORD");
+        }
+        if let Ok(url) = std::env::var("SLACK_WEBHOOK_URL") {
+            config.briefing.slack_webhook_url = Some(url);
+            origins.env("briefing.slack_webhook_url", "SLACK_WEBHOOK_URL");
+        }
+        if let Ok(chat) = std::env::var("JAMEY_BRIEFING_TELEGRAM_CHAT").and_then(|c| c.parse().map_err(|_| std::env::VarError::NotPresent)) {
+            config.briefing.telegram_chat = Some(chat);
+            origins.env("briefing.telegram_chat", "JAMEY_BRIEFING_TELEGRAM_CHAT");
+        }
+
+        if let Ok(enabled) = std::env::var("JAMEY_NOTIFICATIONS") {
+            config.notifications.enabled = enabled == "true" || enabled == "1";
+            origins.env("notifications.enabled", "JAMEY_NOTIFICATIONS");
+        }
+        if let Ok(events) = std::env::var("JAMEY_NOTIFY_EVENTS") {
+            config.notifications.events = list(&events)
+                .map(|event| event.parse())
+                .collect::<Result<_, _>>()
+                .map_err(|e: crate::notifications::NotificationError| ConfigError::InvalidValue(e.to_string()))?;
+            origins.env("notifications.events", "JAMEY_NOTIFY_EVENTS");
+        }
+
+        if let Ok(strictness) = std::env::var("JAMEY_GUARDRAILS") {
+            config.guardrails.strictness = strictness
+                .parse()
+                .map_err(|e: crate::guardrails::GuardrailError| ConfigError::InvalidValue(e.to_string()))?;
+            origins.env("guardrails.strictness", "JAMEY_GUARDRAILS");
+        }
+        if let Ok(pii) = std::env::var("JAMEY_GUARDRAIL_PII") {
+            config.guardrails.pii = pii == "true" || pii == "1";
+            origins.env("guardrails.pii", "JAMEY_GUARDRAIL_PII");
+        }
+        if let Ok(max_chars) = std::env::var("JAMEY_GUARDRAIL_MAX_CHARS").and_then(|m| m.parse().map_err(|_| std::env::VarError::NotPresent)) {
+            config.guardrails.max_chars = Some(max_chars);
+            origins.env("guardrails.max_chars", "JAMEY_GUARDRAIL_MAX_CHARS");
+        }
+        if let Ok(concurrency) = std::env::var("JAMEY_QUEUE_CONCURRENCY").and_then(|c| c.parse().map_err(|_| std::env::VarError::NotPresent)) {
+            config.queue.max_concurrent = concurrency;
+            origins.env("queue.max_concurrent", "JAMEY_QUEUE_CONCURRENCY");
+        }
+        if let Ok(limit) = std::env::var("JAMEY_QUEUE_LIMIT").and_then(|l| l.parse().map_err(|_| std::env::VarError::NotPresent)) {
+            config.queue.max_queued = limit;
+            origins.env("queue.max_queued", "JAMEY_QUEUE_LIMIT");
+        }
+        if let Ok(enabled) = std::env::var("JAMEY_PROMPT_TRACE") {
+            config.prompt_trace.enabled = enabled == "true" || enabled == "1";
+            origins.env("prompt_trace.enabled", "JAMEY_PROMPT_TRACE");
+        }
+        if let Ok(rate) = std::env::var("JAMEY_PROMPT_TRACE_SAMPLE").and_then(|r| r.parse().map_err(|_| std::env::VarError::NotPresent)) {
+            config.prompt_trace.sample_rate = rate;
+            origins.env("prompt_trace.sample_rate", "JAMEY_PROMPT_TRACE_SAMPLE");
+        }
+        if let Ok(timezone) = std::env::var("JAMEY_TIMEZONE") {
+            config.locale.timezone = Some(timezone);
+            origins.env("locale.timezone", "JAMEY_TIMEZONE");
+        }
+        if let Ok(locale) = std::env::var("JAMEY_LOCALE") {
+            config.locale.locale = locale;
+            origins.env("locale.locale", "JAMEY_LOCALE");
+        }
+        if let Ok(enabled) = std::env::var("JAMEY_DEGRADATION") {
+            config.degradation.enabled = enabled == "true" || enabled == "1";
+            origins.env("degradation.enabled", "JAMEY_DEGRADATION");
+        }
+        if let Ok(models) = std::env::var("JAMEY_FALLBACK_MODELS") {
+            config.degradation.fallback_models = list(&models).map(str::to_string).collect();
+            origins.env("degradation.fallback_models", "JAMEY_FALLBACK_MODELS");
+        }
+        if let Ok(enabled) = std::env::var("JAMEY_OFFLINE_QUEUE") {
+            config.offline_queue.enabled = enabled == "true" || enabled == "1";
+            origins.env("offline_queue.enabled", "JAMEY_OFFLINE_QUEUE");
+        }
+        if let Ok(enabled) = std::env::var("JAMEY_CLUSTER") {
+            config.cluster.enabled = enabled == "true" || enabled == "1";
+            origins.env("cluster.enabled", "JAMEY_CLUSTER");
+        }
+        if let Ok(node_id) = std::env::var("JAMEY_NODE_ID") {
+            config.cluster.node_id = Some(node_id);
+            origins.env("cluster.node_id", "JAMEY_NODE_ID");
+        }
+        if let Ok(url) = std::env::var("REDIS_URL") {
+            config.cluster.redis_url = url;
+            origins.env("cluster.redis_url", "REDIS_URL");
+        }
+        if let Ok(election) = std::env::var("JAMEY_LEADER_ELECTION") {
+            config.cluster.leader_election = election.parse().map_err(ConfigError::InvalidValue)?;
+            origins.env("cluster.leader_election", "JAMEY_LEADER_ELECTION");
+        }
+        if let Ok(enabled) = std::env::var("JAMEY_SYNC") {
+            config.sync.enabled = enabled == "true" || enabled == "1";
+            origins.env("sync.enabled", "JAMEY_SYNC");
+        }
+        if let Ok(peer) = std::env::var("JAMEY_SYNC_PEER") {
+            config.sync.peer_url = Some(peer).filter(|peer| !peer.is_empty());
+            origins.env("sync.peer_url", "JAMEY_SYNC_PEER");
+        }
+        if let Ok(secret) = std::env::var("JAMEY_SYNC_SECRET") {
+            config.sync.secret = Some(SensitiveValue(secret));
+            origins.env("sync.secret", "JAMEY_SYNC_SECRET");
+        }
+        if let Ok(namespaces) = std::env::var("JAMEY_SYNC_NAMESPACES") {
+            config.sync.namespaces = list(&namespaces).map(str::to_string).collect();
+            origins.env("sync.namespaces", "JAMEY_SYNC_NAMESPACES");
+        }
+
         if let Ok(host) = std::env::var("POSTGRES_HOST") {
             config.memory.postgres_host = host;
+            origins.env("memory.postgres_host", "POSTGRES_HOST");
         }
         if let Ok(port) = std::env::var("POSTGRES_PORT").and_then(|p| p.parse().map_err(|_| std::env::VarError::NotPresent)) {
             config.memory.postgres_port = port;
+            origins.env("memory.postgres_port", "POSTGRES_PORT");
         }
         if let Ok(db) = std::env::var("POSTGRES_DB") {
             config.memory.postgres_db = db;
+            origins.env("memory.postgres_db", "POSTGRES_DB");
         }
         if let Ok(user) = std::env::var("POSTGRES_USER") {
             config.memory.postgres_user = user;
+            origins.env("memory.postgres_user", "POSTGRES_USER");
+        }
+        if let Ok(budget) = std::env::var("JAMEY_DAILY_BUDGET_USD").and_then(|b| b.parse().map_err(|_| std::env::VarError::NotPresent)) {
+            config.llm.daily_budget_usd = Some(budget);
+            origins.env("llm.daily_budget_usd", "JAMEY_DAILY_BUDGET_USD");
+        }
+        if let Ok(budget) = std::env::var("JAMEY_CONTEXT_BUDGET_TOKENS").and_then(|b| b.parse().map_err(|_| std::env::VarError::NotPresent)) {
+            config.llm.context_budget_tokens = budget;
+            origins.env("llm.context_budget_tokens", "JAMEY_CONTEXT_BUDGET_TOKENS");
+        }
+        if let Ok(draft_model) = std::env::var("JAMEY_DRAFT_MODEL") {
+            config.llm.generation = if draft_model.is_empty() {
+                crate::generation::GenerationStrategy::Direct
+            } else {
+                crate::generation::GenerationStrategy::DraftThenVerify { draft_model }
+            };
+            origins.env("llm.generation", "JAMEY_DRAFT_MODEL");
+        }
+        if let Ok(count) = std::env::var("JAMEY_CONTEXT_MEMORIES").and_then(|c| c.parse().map_err(|_| std::env::VarError::NotPresent)) {
+            config.memory.context_memories = count;
+            origins.env("memory.context_memories", "JAMEY_CONTEXT_MEMORIES");
+        }
+        if let Ok(budget) = std::env::var("JAMEY_PINNED_BUDGET_TOKENS").and_then(|b| b.parse().map_err(|_| std::env::VarError::NotPresent)) {
+            config.memory.pinned_budget_tokens = budget;
+            origins.env("memory.pinned_budget_tokens", "JAMEY_PINNED_BUDGET_TOKENS");
+        }
+        if let Ok(enabled) = std::env::var("JAMEY_REDACTION") {
+            config.security.redaction.enabled = enabled != "false" && enabled != "0";
+            origins.env("security.redaction.enabled", "JAMEY_REDACTION");
+        }
+        if let Ok(days) = std::env::var("JAMEY_USAGE_RETENTION_DAYS").and_then(|d| d.parse().map_err(|_| std::env::VarError::NotPresent)) {
+            config.memory.usage_retention_days = days;
+            origins.env("memory.usage_retention_days", "JAMEY_USAGE_RETENTION_DAYS");
+        }
+        if let Ok(days) = std::env::var("JAMEY_COLD_AFTER_DAYS").and_then(|d| d.parse().map_err(|_| std::env::VarError::NotPresent)) {
+            config.memory.cold_after_days = days;
+            origins.env("memory.cold_after_days", "JAMEY_COLD_AFTER_DAYS");
         }
         if let Ok(max_conn) = std::env::var("POSTGRES_MAX_CONNECTIONS").and_then(|m| m.parse().map_err(|_| std::env::VarError::NotPresent)) {
             config.memory.postgres_max_connections = max_conn;
+            origins.env("memory.postgres_max_connections", "POSTGRES_MAX_CONNECTIONS");
         }
 
         // Load full access configuration
         if let Ok(download_dir) = std::env::var("DOWNLOAD_DIR") {
             config.tools.download_dir = PathBuf::from(download_dir);
+            origins.env("tools.download_dir", "DOWNLOAD_DIR");
         }
         if let Ok(system_root) = std::env::var("SYSTEM_ROOT") {
             config.tools.system_root = PathBuf::from(system_root);
+            origins.env("tools.system_root", "SYSTEM_ROOT");
+        }
+        if let Ok(user) = std::env::var("JAMEY_SANDBOX_USER") {
+            config.tools.sandbox.user = Some(user);
+            origins.env("tools.sandbox.user", "JAMEY_SANDBOX_USER");
+        }
+        if let Ok(secs) = std::env::var("JAMEY_SANDBOX_CPU_SECS").and_then(|s| s.parse().map_err(|_| std::env::VarError::NotPresent)) {
+            config.tools.sandbox.cpu_secs = Some(secs);
+            origins.env("tools.sandbox.cpu_secs", "JAMEY_SANDBOX_CPU_SECS");
+        }
+        if let Ok(mb) = std::env::var("JAMEY_SANDBOX_MEMORY_MB").and_then(|m| m.parse().map_err(|_| std::env::VarError::NotPresent)) {
+            config.tools.sandbox.memory_mb = Some(mb);
+            origins.env("tools.sandbox.memory_mb", "JAMEY_SANDBOX_MEMORY_MB");
+        }
+        if let Ok(secs) = std::env::var("JAMEY_SANDBOX_WALL_CLOCK_SECS").and_then(|s| s.parse().map_err(|_| std::env::VarError::NotPresent)) {
+            config.tools.sandbox.wall_clock_secs = Some(secs);
+            origins.env("tools.sandbox.wall_clock_secs", "JAMEY_SANDBOX_WALL_CLOCK_SECS");
+        }
+        if let Ok(paths) = std::env::var("JAMEY_READ_PATHS") {
+            config.tools.path_policy.read = list(&paths).map(PathBuf::from).collect();
+            origins.env("tools.path_policy.read", "JAMEY_READ_PATHS");
+        }
+        if let Ok(paths) = std::env::var("JAMEY_WRITE_PATHS") {
+            config.tools.path_policy.write = list(&paths).map(PathBuf::from).collect();
+            origins.env("tools.path_policy.write", "JAMEY_WRITE_PATHS");
+        }
+        if let Ok(patterns) = std::env::var("JAMEY_DENY_PATHS") {
+            config.tools.path_policy.deny = list(&patterns).map(str::to_string).collect();
+            origins.env("tools.path_policy.deny", "JAMEY_DENY_PATHS");
+        }
+        if let Ok(bytes) = std::env::var("JAMEY_MAX_FILE_BYTES").and_then(|b| b.parse().map_err(|_| std::env::VarError::NotPresent)) {
+            config.tools.path_policy.max_file_bytes = Some(bytes);
+            origins.env("tools.path_policy.max_file_bytes", "JAMEY_MAX_FILE_BYTES");
+        }
+        if let Ok(secs) = std::env::var("JAMEY_TOOL_TIMEOUT_SECS").and_then(|s| s.parse().map_err(|_| std::env::VarError::NotPresent)) {
+            config.tools.timeouts.default_secs = secs;
+            origins.env("tools.timeouts.default_secs", "JAMEY_TOOL_TIMEOUT_SECS");
+        }
+        if let Ok(secs) = std::env::var("JAMEY_SCRATCHPAD_TTL_SECS").and_then(|s| s.parse().map_err(|_| std::env::VarError::NotPresent)) {
+            config.tools.scratchpad.default_ttl_secs = secs;
+            origins.env("tools.scratchpad.default_ttl_secs", "JAMEY_SCRATCHPAD_TTL_SECS");
         }
         if let Ok(github_token) = std::env::var("GITHUB_TOKEN") {
             config.tools.github_token = Some(github_token);
+            origins.env("tools.github_token", "GITHUB_TOKEN");
         }
         if let Ok(linkedin_token) = std::env::var("LINKEDIN_TOKEN") {
             config.tools.linkedin_token = Some(linkedin_token);
+            origins.env("tools.linkedin_token", "LINKEDIN_TOKEN");
         }
         if let Ok(web_search_key) = std::env::var("WEB_SEARCH_API_KEY") {
             config.tools.web_search_api_key = Some(web_search_key);
+            origins.env("tools.web_search_api_key", "WEB_SEARCH_API_KEY");
         }
         if let Ok(mcp_url) = std::env::var("MCP_SERVER_URL") {
             config.tools.mcp_server_url = Some(mcp_url);
+            origins.env("tools.mcp_server_url", "MCP_SERVER_URL");
+        }
+        let oauth_clients: Vec<_> = jamey_tools::oauth::OAuthProvider::ALL
+            .into_iter()
+            .filter_map(jamey_tools::oauth::OAuthClientConfig::from_env)
+            .collect();
+        if !oauth_clients.is_empty() {
+            config.tools.oauth_clients = oauth_clients;
+            origins.env("tools.oauth_clients", "JAMEY_<PROVIDER>_CLIENT_ID");
+        }
+        if let Ok(token) = std::env::var("TELEGRAM_BOT_TOKEN") {
+            config.tools.telegram_bot_token = Some(token);
+            origins.env("tools.telegram_bot_token", "TELEGRAM_BOT_TOKEN");
+        }
+        if let Ok(chats) = std::env::var("TELEGRAM_ALLOWED_CHATS") {
+            config.tools.telegram_allowed_chats = chats
+                .split(',')
+                .map(str::trim)
+                .filter(|id| !id.is_empty())
+                .map(|id| id.parse().map_err(|_| ConfigError::InvalidValue(format!("Invalid Telegram chat ID: {}", id))))
+                .collect::<Result<_, _>>()?;
+            origins.env("tools.telegram_allowed_chats", "TELEGRAM_ALLOWED_CHATS");
+        }
+        if let Ok(url) = std::env::var("TELEGRAM_WEBHOOK_URL") {
+            config.tools.telegram_webhook_url = Some(url);
+            origins.env("tools.telegram_webhook_url", "TELEGRAM_WEBHOOK_URL");
+        }
+        if let Ok(homeserver) = std::env::var("MATRIX_HOMESERVER") {
+            config.tools.matrix_homeserver = Some(homeserver);
+            origins.env("tools.matrix_homeserver", "MATRIX_HOMESERVER");
+        }
+        if let Ok(user) = std::env::var("MATRIX_USER") {
+            config.tools.matrix_user = Some(user);
+            origins.env("tools.matrix_user", "MATRIX_USER");
+        }
+        if let Ok(password) = std::env::var("MATRIX_PASSWORD") {
+            config.tools.matrix_password = Some(password);
+            origins.env("tools.matrix_password", "MATRIX_PASSWORD");
+        }
+        if let Ok(rooms) = std::env::var("MATRIX_ROOMS") {
+            config.tools.matrix_rooms = rooms
+                .split(',')
+                .map(str::trim)
+                .filter(|room| !room.is_empty())
+                .map(String::from)
+                .collect();
+            origins.env("tools.matrix_rooms", "MATRIX_ROOMS");
+        }
+        if let Ok(passphrase) = std::env::var("MATRIX_STORE_PASSPHRASE") {
+            config.tools.matrix_store_passphrase = Some(passphrase);
+            origins.env("tools.matrix_store_passphrase", "MATRIX_STORE_PASSPHRASE");
         }
         if let Ok(enable_24_7) = std::env::var("ENABLE_24_7") {
             config.tools.enable_24_7 = enable_24_7 == "true" || enable_24_7 == "1";
+            origins.env("tools.enable_24_7", "ENABLE_24_7");
         }
         if let Ok(scheduler_enabled) = std::env::var("SCHEDULER_ENABLED") {
             config.tools.scheduler_enabled = scheduler_enabled == "true" || scheduler_enabled == "1";
+            origins.env("tools.scheduler_enabled", "SCHEDULER_ENABLED");
         }
         
         // Validate the configuration
         config.validate()?;
 
-        Ok(config)
+        Ok((config, origins))
     }
 
-    pub fn validate(&self) -> Result<(), ConfigError> {
-        // Validate project name
-        if self.project_name.is_empty() {
-            return Err(ConfigError::MissingConfig("project_name".to_string()));
+    /// Merge the file at `path` over the defaults. A missing file is not an
+    /// error, so a fresh install runs on environment variables alone.
+    fn from_file(path: Option<&Path>, origins: &mut ConfigOrigins) -> Result<Self, ConfigError> {
+        let defaults = Self::default();
+        for key in flatten_keys(&serde_json::to_value(&defaults).unwrap_or_default()) {
+            origins.set(key, ConfigOrigin::Default);
+        }
+        let Some(path) = path.filter(|p| p.is_file()) else {
+            return Ok(defaults);
+        };
+        origins.file = Some(path.to_path_buf());
+
+        let file = config::Config::builder()
+            .add_source(config::File::from(path))
+            .build()?;
+        let values: serde_json::Value = file.clone().try_deserialize()?;
+        let keys = flatten_keys(&values);
+        if let Some((key, var)) = ENV_ONLY_KEYS.iter().find(|(key, _)| keys.iter().any(|k| k == key)) {
+            return Err(ConfigError::InvalidValue(format!(
+                "{} must not be set in {}; use the {} environment variable",
+                key,
+                path.display(),
+                var
+            )));
         }
-        if self.project_name.len() > 64 {
-            return Err(ConfigError::InvalidValue("project_name too long (max 64 chars)".to_string()));
+        for key in keys {
+            origins.set(key, ConfigOrigin::File(path.to_path_buf()));
         }
 
-        // Validate memory config
-        if self.memory.postgres_password == "change_me_in_production" {
+        let merged = config::Config::builder()
+            .add_source(config::Config::try_from(&defaults)?)
+            .add_source(file)
+            .build()?;
+        Ok(merged.try_deserialize()?)
+    }
+
+    /// Check every setting: the field checks declared on the config types,
+    /// then the checks spanning sections
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if let Err(errors) = Validate::validate(self) {
+            let problems = field_problems(&errors);
+            if let Some((key, _)) = problems.iter().find(|(_, e)| e.code == "required") {
+                return Err(ConfigError::MissingConfig(key.clone()));
+            }
             return Err(ConfigError::InvalidValue(
-                "Default postgres password must be changed".to_string(),
+                problems.iter().map(|(key, e)| format!("{}: {}", key, describe(e))).collect::<Vec<_>>().join("; "),
             ));
         }
-        if self.memory.postgres_host.len() > 255 {
-            return Err(ConfigError::InvalidValue("postgres_host too long".to_string()));
-        }
-        if self.memory.postgres_db.len() > 64 {
-            return Err(ConfigError::InvalidValue("postgres_db name too long".to_string()));
-        }
-        if self.memory.postgres_user.len() > 64 {
-            return Err(ConfigError::InvalidValue("postgres_user name too long".to_string()));
-        }
-        if !["ivfflat", "hnsw"].contains(&self.memory.vector_index_type.as_str()) {
-            return Err(ConfigError::InvalidValue("Invalid vector_index_type".to_string()));
-        }
+        self.validate_sections()
+    }
 
-        // Validate LLM config
-        if self.llm.openrouter_api_key.is_empty() {
-            return Err(ConfigError::MissingConfig("openrouter_api_key".to_string()));
+    /// Check the config file at `path` on its own, for `jamey system config
+    /// validate`. Secrets only ever come from the environment, so stand-ins
+    /// take their place. Returns every field that fails its checks or, when
+    /// they all pass, the first failing check spanning sections; empty when
+    /// the file is good.
+    pub fn check_file(path: &Path) -> Result<Vec<String>, ConfigError> {
+        if !path.is_file() {
+            return Err(ConfigError::MissingConfig(format!("config file {}", path.display())));
         }
-        if self.llm.openrouter_timeout_seconds == 0 || self.llm.openrouter_timeout_seconds > 300 {
-            return Err(ConfigError::InvalidValue("Invalid timeout value (1-300 seconds)".to_string()));
-        }
-        if self.llm.openrouter_max_retries > 10 {
-            return Err(ConfigError::InvalidValue("max_retries too high (max 10)".to_string()));
+        let mut config = Self::from_file(Some(path), &mut ConfigOrigins::default())?;
+        let stand_in = || SensitiveValue("stand-in-for-an-environment-secret".to_string());
+        config.memory.postgres_password = stand_in();
+        config.llm.openrouter_api_key = stand_in();
+        config.security.api_key = Some(stand_in());
+        config.sync.secret = Some(stand_in());
+        config.briefing.slack_webhook_url = Some(stand_in().0);
+
+        let mut problems = match Validate::validate(&config) {
+            Ok(()) => Vec::new(),
+            Err(errors) => field_problems(&errors)
+                .into_iter()
+                .map(|(key, e)| format!("{}: {}", key, describe(e)))
+                .collect(),
+        };
+        if problems.is_empty() {
+            if let Err(e) = config.validate_sections() {
+                problems.push(e.to_string());
+            }
         }
+        Ok(problems)
+    }
+
+    /// JSON Schema of the config file. Every key is optional, since the
+    /// file is merged over the defaults, and the environment-only secrets
+    /// are left out.
+    pub fn json_schema() -> serde_json::Value {
+        let mut schema = serde_json::to_value(schemars::schema_for!(RuntimeConfig)).unwrap_or_default();
+        strip_required(&mut schema);
+        schema
+    }
+
+    /// Checks that need more than one field, or code outside the config types
+    fn validate_sections(&self) -> Result<(), ConfigError> {
+        self.memory
+            .retrieval
+            .validate()
+            .map_err(|e| ConfigError::InvalidValue(format!("memory.retrieval: {}", e)))?;
 
         // Validate security config
         if self.security.api_key_required && self.security.api_key.is_none() {
@@ -413,6 +1516,12 @@ impl RuntimeConfig {
                 "API key required but not provided".to_string(),
             ));
         }
+        jamey_core::redaction::Redactor::from_config(&self.security.redaction)
+            .map_err(|e| ConfigError::InvalidValue(e.to_string()))?;
+
+        // Validate logging config
+        crate::logging::env_filter(&self.api.log_level, &self.logging.filters)
+            .map_err(|e| ConfigError::InvalidValue(e.to_string()))?;
 
         // Validate API config
         if let Some(metrics_port) = self.api.metrics_port {
@@ -425,8 +1534,75 @@ impl RuntimeConfig {
                 return Err(ConfigError::InvalidValue("health_check_port conflicts with other ports".to_string()));
             }
         }
-
+        if let Some(url) = &self.tools.telegram_webhook_url {
+            if !url.starts_with("https://") {
+                return Err(ConfigError::InvalidValue("telegram_webhook_url must be an https URL".to_string()));
+            }
+            if !self.api.hooks {
+                return Err(ConfigError::MissingConfig(
+                    "telegram_webhook_url needs api.hooks to receive updates".to_string(),
+                ));
+            }
+        }
+        crate::voice::parse_rate(&self.voice.rate).map_err(|e| ConfigError::InvalidValue(e.to_string()))?;
+        self.briefing.validate().map_err(|e| ConfigError::InvalidValue(e.to_string()))?;
+        self.routing.validate().map_err(|e| ConfigError::InvalidValue(e.to_string()))?;
+        self.guardrails.validate().map_err(|e| ConfigError::InvalidValue(e.to_string()))?;
+        self.queue.validate().map_err(ConfigError::InvalidValue)?;
+        self.degradation.validate().map_err(ConfigError::InvalidValue)?;
+        self.offline_queue.validate().map_err(ConfigError::InvalidValue)?;
+        self.cluster.validate().map_err(ConfigError::InvalidValue)?;
+        self.sync.validate().map_err(ConfigError::InvalidValue)?;
+        if self.sync.enabled && self.sync.peer_url.is_none() && !self.api.hooks {
+            return Err(ConfigError::MissingConfig(
+                "sync without sync.peer_url needs api.hooks to take the peer's requests".to_string(),
+            ));
+        }
+        self.supervisor.validate().map_err(ConfigError::InvalidValue)?;
+        self.prompt_trace.validate().map_err(ConfigError::InvalidValue)?;
+        self.locale.validate().map_err(ConfigError::InvalidValue)?;
+        self.tools.sandbox.validate().map_err(ConfigError::InvalidValue)?;
+        self.tools.path_policy.validate().map_err(ConfigError::InvalidValue)?;
+        self.tools.timeouts.validate().map_err(ConfigError::InvalidValue)?;
+        self.tools.scratchpad.validate().map_err(ConfigError::InvalidValue)?;
+        self.api.audit_sinks.validate().map_err(ConfigError::InvalidValue)?;
+        if self.briefing.channels.contains(&crate::briefing::BriefingChannel::Telegram)
+            && self.tools.telegram_bot_token.is_none()
+        {
+            return Err(ConfigError::MissingConfig(
+                "the telegram briefing channel needs TELEGRAM_BOT_TOKEN".to_string(),
+            ));
+        }
+        if let Some(homeserver) = &self.tools.matrix_homeserver {
+            if !homeserver.starts_with("https://") && !homeserver.starts_with("http://") {
+                return Err(ConfigError::InvalidValue("matrix_homeserver must be an http(s) URL".to_string()));
+            }
+            if self.tools.matrix_user.as_deref().is_none_or(|u| u.trim().is_empty()) {
+                return Err(ConfigError::MissingConfig("matrix_homeserver needs matrix_user".to_string()));
+            }
+            if self.tools.matrix_rooms.iter().any(|r| !r.starts_with('!') && !r.starts_with('#')) {
+                return Err(ConfigError::InvalidValue(
+                    "matrix_rooms must be room IDs (!id:server) or aliases (#alias:server)".to_string(),
+                ));
+            }
+        }
         // Validate TLS configuration
+        if self.api.enable_https {
+            self.api.tls_certificates.validate().map_err(ConfigError::InvalidValue)?;
+            let files = self.api.tls_certificates.mode == crate::certificates::CertificateMode::Files;
+            if files && self.api.tls_cert_path.is_none() {
+                return Err(ConfigError::MissingConfig("TLS certificate path required when HTTPS is enabled".to_string()));
+            }
+            if files && self.api.tls_key_path.is_none() {
+                return Err(ConfigError::MissingConfig("TLS private key path required when HTTPS is enabled".to_string()));
+            }
+            if !["1.2", "1.3"].contains(&self.api.tls_min_version.as_str()) {
+                return Err(ConfigError::InvalidValue("TLS version must be 1.2 or 1.3".to_string()));
+            }
+        }
+
+        Ok(())
+    }
 
     /// Convert API configuration to TLS configuration
     pub fn into_tls_config(&self) -> Result<Option<crate::tls::TlsConfig>, ConfigError> {
@@ -434,10 +1610,20 @@ impl RuntimeConfig {
             return Ok(None);
         }
 
-        let cert_path = self.api.tls_cert_path.clone()
-            .ok_or_else(|| ConfigError::MissingConfig("TLS certificate path required".to_string()))?;
-        let key_path = self.api.tls_key_path.clone()
-            .ok_or_else(|| ConfigError::MissingConfig("TLS private key path required".to_string()))?;
+        // Generated certificates live in the cache unless paths are given
+        let certificates = &self.api.tls_certificates;
+        let (cert_path, key_path) = match certificates.mode {
+            crate::certificates::CertificateMode::Files => (
+                self.api.tls_cert_path.clone()
+                    .ok_or_else(|| ConfigError::MissingConfig("TLS certificate path required".to_string()))?,
+                self.api.tls_key_path.clone()
+                    .ok_or_else(|| ConfigError::MissingConfig("TLS private key path required".to_string()))?,
+            ),
+            _ => (
+                self.api.tls_cert_path.clone().unwrap_or_else(|| certificates.cache_dir.join("cert.pem")),
+                self.api.tls_key_path.clone().unwrap_or_else(|| certificates.cache_dir.join("key.pem")),
+            ),
+        };
 
         let tls_version = match self.api.tls_min_version.as_str() {
             "1.2" => crate::tls::TlsVersion::Tls12,
@@ -460,24 +1646,10 @@ impl RuntimeConfig {
 
         Ok(Some(tls_config))
     }
-        if self.api.enable_https {
-            if self.api.tls_cert_path.is_none() {
-                return Err(ConfigError::MissingConfig("TLS certificate path required when HTTPS is enabled".to_string()));
-            }
-            if self.api.tls_key_path.is_none() {
-                return Err(ConfigError::MissingConfig("TLS private key path required when HTTPS is enabled".to_string()));
-            }
-            if !["1.2", "1.3"].contains(&self.api.tls_min_version.as_str()) {
-                return Err(ConfigError::InvalidValue("TLS version must be 1.2 or 1.3".to_string()));
-            }
-        }
-
-        Ok(())
-    }
 
     pub fn into_openrouter_config(&self) -> Result<OpenRouterConfig, ConfigError> {
         Ok(OpenRouterConfig {
-            api_key: self.llm.openrouter_api_key.clone(),
+            api_key: self.llm.openrouter_api_key.0.clone(),
             api_base_url: url::Url::parse("https://openrouter.ai/api/v1")
                 .map_err(|_| ConfigError::InvalidValue("Invalid OpenRouter API URL".to_string()))?,
             default_model: self.llm.openrouter_default_model.clone(),
@@ -488,6 +1660,156 @@ impl RuntimeConfig {
     }
 }
 
+/// Builds a [`RuntimeConfig`] for embedding the runtime, validating it the
+/// same way [`RuntimeConfig::from_env`] does
+///
+/// ```no_run
+/// # use jamey_runtime::config::RuntimeConfig;
+/// let config = RuntimeConfig::builder()
+///     .postgres_password("s3cret")
+///     .openrouter_api_key("sk-or-v1-abc123")
+///     .model("anthropic/claude-3.5-sonnet")
+///     .without_api_key()
+///     .build()?;
+/// # Ok::<(), jamey_runtime::config::ConfigError>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct RuntimeConfigBuilder {
+    config: RuntimeConfig,
+    model: Option<String>,
+    allowed_models: Option<Vec<String>>,
+}
+
+impl RuntimeConfigBuilder {
+    pub fn project_name(mut self, name: impl Into<String>) -> Self {
+        self.config.project_name = name.into();
+        self
+    }
+
+    pub fn postgres_host(mut self, host: impl Into<String>) -> Self {
+        self.config.memory.postgres_host = host.into();
+        self
+    }
+
+    pub fn postgres_port(mut self, port: u16) -> Self {
+        self.config.memory.postgres_port = port;
+        self
+    }
+
+    pub fn postgres_db(mut self, db: impl Into<String>) -> Self {
+        self.config.memory.postgres_db = db.into();
+        self
+    }
+
+    pub fn postgres_user(mut self, user: impl Into<String>) -> Self {
+        self.config.memory.postgres_user = user.into();
+        self
+    }
+
+    pub fn postgres_password(mut self, password: impl Into<String>) -> Self {
+        self.config.memory.postgres_password = SensitiveValue(password.into());
+        self
+    }
+
+    pub fn openrouter_api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.config.llm.openrouter_api_key = SensitiveValue(api_key.into());
+        self
+    }
+
+    /// The default model. Without [`allowed_models`](Self::allowed_models)
+    /// it is the only model allowed; otherwise it must be one of them.
+    pub fn model(mut self, model: impl Into<String>) -> Self {
+        self.model = Some(model.into());
+        self
+    }
+
+    pub fn allowed_models<I, M>(mut self, models: I) -> Self
+    where
+        I: IntoIterator<Item = M>,
+        M: Into<String>,
+    {
+        self.allowed_models = Some(models.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Require this key from API clients
+    pub fn api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.config.security.api_key_required = true;
+        self.config.security.api_key = Some(SensitiveValue(api_key.into()));
+        self
+    }
+
+    /// Serve the API without client authentication
+    pub fn without_api_key(mut self) -> Self {
+        self.config.security.api_key_required = false;
+        self.config.security.api_key = None;
+        self
+    }
+
+    pub fn log_level(mut self, level: impl Into<String>) -> Self {
+        self.config.api.log_level = level.into();
+        self
+    }
+
+    /// Replace a whole section; the field setters above still apply on top
+    /// when called afterwards
+    pub fn memory(mut self, memory: MemoryConfig) -> Self {
+        self.config.memory = memory;
+        self
+    }
+
+    pub fn cache(mut self, cache: CacheConfig) -> Self {
+        self.config.cache = cache;
+        self
+    }
+
+    pub fn llm(mut self, llm: LlmConfig) -> Self {
+        self.config.llm = llm;
+        self
+    }
+
+    pub fn api(mut self, api: ApiConfig) -> Self {
+        self.config.api = api;
+        self
+    }
+
+    pub fn tools(mut self, tools: ToolConfig) -> Self {
+        self.config.tools = tools;
+        self
+    }
+
+    pub fn logging(mut self, logging: LoggingConfig) -> Self {
+        self.config.logging = logging;
+        self
+    }
+
+    pub fn build(mut self) -> Result<RuntimeConfig, ConfigError> {
+        let llm = &mut self.config.llm;
+        match (self.model, self.allowed_models) {
+            (Some(model), Some(allowed)) => {
+                llm.openrouter_default_model = model;
+                llm.openrouter_allowed_models = allowed;
+            }
+            (Some(model), None) => {
+                llm.openrouter_allowed_models = vec![model.clone()];
+                llm.openrouter_default_model = model;
+            }
+            (None, Some(allowed)) => {
+                llm.openrouter_default_model = allowed.first().cloned().unwrap_or_default();
+                llm.openrouter_allowed_models = allowed;
+            }
+            (None, None) => {}
+        }
+
+        self.config.validate()?;
+        self.config
+            .into_openrouter_config()?
+            .validate()
+            .map_err(|e| ConfigError::InvalidValue(e.to_string()))?;
+        Ok(self.config)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -520,6 +1842,159 @@ mod tests {
         assert!(config.validate().is_ok());
     }
 
+    #[test]
+    fn test_field_checks() {
+        let mut config = RuntimeConfig::default();
+        config.llm.openrouter_api_key = SensitiveValue("test_key".to_string());
+        config.memory.postgres_password = SensitiveValue("secure_password".to_string());
+        config.security.api_key = Some(SensitiveValue("api_key".to_string()));
+        config.memory.postgres_max_connections = 0;
+        config.llm.context_budget_tokens = 500;
+
+        let err = config.validate().unwrap_err().to_string();
+        assert!(err.contains("llm.context_budget_tokens: must be 0 (off) or at least 1000"), "{}", err);
+        assert!(err.contains("memory.postgres_max_connections: must be between 1 and 100"), "{}", err);
+
+        config.llm.openrouter_api_key = SensitiveValue(String::new());
+        assert!(matches!(config.validate(), Err(ConfigError::MissingConfig(key)) if key == "llm.openrouter_api_key"));
+    }
+
+    #[test]
+    fn test_check_file() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(&path, "[memory]\nvector_index_type = \"flat\"\nmemory_retention_days = 0\n").unwrap();
+        let problems = RuntimeConfig::check_file(&path).unwrap();
+        assert_eq!(
+            problems,
+            vec![
+                "memory.memory_retention_days: must be between 1 and 365".to_string(),
+                "memory.vector_index_type: must be ivfflat or hnsw".to_string(),
+            ]
+        );
+
+        // Secrets aren't needed to check a file
+        std::fs::write(&path, "[api]\nhttp_port = 8088\n").unwrap();
+        assert!(RuntimeConfig::check_file(&path).unwrap().is_empty());
+        assert!(RuntimeConfig::check_file(&dir.path().join("absent.toml")).is_err());
+    }
+
+    #[test]
+    fn test_json_schema() {
+        let schema = RuntimeConfig::json_schema();
+        let text = schema.to_string();
+        assert!(!text.contains("\"required\""));
+        assert!(!text.contains("openrouter_api_key"));
+        assert!(!text.contains("postgres_password"));
+        let memory = &schema["definitions"]["MemoryConfig"]["properties"];
+        assert_eq!(memory["postgres_max_connections"]["maximum"], 100.0);
+    }
+
+    #[test]
+    fn test_secrets_masked() {
+        let mut config = RuntimeConfig::default();
+        config.llm.openrouter_api_key = SensitiveValue("sk-or-v1-leak".to_string());
+        config.memory.postgres_password = SensitiveValue("pg-leak".to_string());
+        config.tools.github_token = Some("ghp-leak".to_string());
+        config.briefing.slack_webhook_url = Some("https://hooks.slack.com/services/leak".to_string());
+        config.cluster.redis_url = "redis://:redis-leak@cache:6379".to_string();
+
+        let debug = format!("{:?}", config);
+        let json = config.to_redacted_json().to_string();
+        for output in [&debug, &json] {
+            assert!(!output.contains("leak"), "{}", output);
+        }
+        assert_eq!(config.flattened()["cluster.redis_url"], "redis://:***REDACTED***@cache:6379");
+        assert_eq!(config.flattened()["security.api_key_required"], true);
+    }
+
+    #[test]
+    fn test_builder() {
+        let config = RuntimeConfig::builder()
+            .postgres_password("secure_password")
+            .openrouter_api_key("test_key")
+            .model("anthropic/claude-3.5-sonnet")
+            .api_key("client_key")
+            .postgres_host("db.internal")
+            .build()
+            .unwrap();
+        assert_eq!(config.memory.postgres_host, "db.internal");
+        assert_eq!(config.llm.openrouter_default_model, "anthropic/claude-3.5-sonnet");
+        assert_eq!(config.llm.openrouter_allowed_models, vec!["anthropic/claude-3.5-sonnet".to_string()]);
+        assert!(config.security.api_key_required);
+
+        let config = RuntimeConfig::builder()
+            .postgres_password("secure_password")
+            .openrouter_api_key("test_key")
+            .without_api_key()
+            .build()
+            .unwrap();
+        assert_eq!(config.llm.openrouter_default_model, "claude-3-sonnet");
+        assert!(config.security.api_key.is_none());
+    }
+
+    #[test]
+    fn test_builder_rejects_defaults() {
+        let complete = || {
+            RuntimeConfig::builder()
+                .postgres_password("secure_password")
+                .openrouter_api_key("test_key")
+                .without_api_key()
+        };
+        assert!(complete().build().is_ok());
+
+        // Each secret left at its placeholder fails the build
+        assert!(RuntimeConfig::builder().openrouter_api_key("test_key").without_api_key().build().is_err());
+        assert!(RuntimeConfig::builder().postgres_password("secure_password").without_api_key().build().is_err());
+        assert!(RuntimeConfig::builder().postgres_password("secure_password").openrouter_api_key("test_key").build().is_err());
+
+        assert!(complete().model("gpt-4").allowed_models(["gpt-3.5-turbo"]).build().is_err());
+        assert!(complete().openrouter_api_key("bad key!").build().is_err());
+        assert!(complete().log_level("loud").build().is_err());
+    }
+
+    #[test]
+    fn test_file_layer_and_origins() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(
+            &path,
+            "[api]\nhttp_port = 8088\n\n[memory]\npostgres_host = \"db.internal\"\n",
+        )
+        .unwrap();
+
+        let mut origins = ConfigOrigins::default();
+        let config = RuntimeConfig::from_file(Some(&path), &mut origins).unwrap();
+        assert_eq!(config.api.http_port, 8088);
+        assert_eq!(config.memory.postgres_host, "db.internal");
+        assert_eq!(config.memory.postgres_port, 5432);
+        assert_eq!(origins.file(), Some(path.as_path()));
+        assert_eq!(origins.get("api.http_port"), &ConfigOrigin::File(path.clone()));
+        assert_eq!(origins.get("memory.postgres_port"), &ConfigOrigin::Default);
+
+        // Env wins over the file, and covers everything beneath the key it set
+        origins.env("api.http_port", "API_HTTP_PORT");
+        origins.env("logging.file", "LOG_DIR");
+        assert_eq!(origins.get("api.http_port"), &ConfigOrigin::Env("API_HTTP_PORT".to_string()));
+        assert_eq!(origins.get("logging.file.dir"), &ConfigOrigin::Env("LOG_DIR".to_string()));
+
+        // A missing file leaves the defaults in place
+        let mut origins = ConfigOrigins::default();
+        let config = RuntimeConfig::from_file(Some(&dir.path().join("absent.yaml")), &mut origins).unwrap();
+        assert_eq!(config.api.http_port, RuntimeConfig::default().api.http_port);
+        assert!(origins.file().is_none());
+    }
+
+    #[test]
+    fn test_file_rejects_secrets() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("config.yaml");
+        std::fs::write(&path, "llm:\n  openrouter_api_key: sk-or-123\n").unwrap();
+
+        let err = RuntimeConfig::from_file(Some(&path), &mut ConfigOrigins::default()).unwrap_err();
+        assert!(err.to_string().contains("OPENROUTER_API_KEY"));
+    }
+
     #[test]
     fn test_env_override() {
         env::set_var("PROJECT_NAME", "test_project");
@@ -0,0 +1,232 @@
+//! Degraded answers when the provider can't give a full one
+//!
+//! With `[degradation] enabled`, a chat turn steps down a ladder instead of
+//! failing outright:
+//!
+//! 1. **Smaller model**: when a model request fails, it is retried with each
+//!    of `fallback_models` in turn. Past `budget_share` of the daily budget,
+//!    turns start on the first fallback model.
+//! 2. **Cached answer**: when no model answers, or the daily budget is used
+//!    up, the last full answer to the same question is sent again. Answers
+//!    are only reused for the user (or session) they were written for.
+//! 3. **Canned reply**: failing that, `message` says Jamey is running in
+//!    degraded mode.
+//!
+//! Each step down publishes a `turn.degraded` event, and the reply carries
+//! the level it was reached at in its metadata under `degradation`.
+//! Background work such as evaluations only takes the first step; its
+//! turns still fail when no model answers.
+//!
+//! ```toml
+//! [degradation]
+//! enabled = true
+//! fallback_models = ["openai/gpt-4o-mini", "meta-llama/llama-3.1-8b-instruct"]
+//! budget_share = 0.9
+//! ```
+
+use parking_lot::Mutex;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct DegradationConfig {
+    /// Step down rather than fail (`JAMEY_DEGRADATION`)
+    pub enabled: bool,
+    /// Models tried, in order, when a request fails
+    /// (`JAMEY_FALLBACK_MODELS`, comma-separated)
+    pub fallback_models: Vec<String>,
+    /// Share of the daily budget past which turns start on the first
+    /// fallback model
+    pub budget_share: f64,
+    /// Full answers remembered for reuse; 0 turns the cached tier off
+    pub cached_answers: usize,
+    /// The canned reply, the last step of the ladder
+    pub message: String,
+}
+
+impl Default for DegradationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            fallback_models: Vec::new(),
+            budget_share: 0.9,
+            cached_answers: 256,
+            message: "I can't reach my language model right now, so I'm running in degraded mode. \
+                      Please try again in a few minutes."
+                .to_string(),
+        }
+    }
+}
+
+impl DegradationConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(0.0..=1.0).contains(&self.budget_share) {
+            return Err("degradation.budget_share must be between 0 and 1".to_string());
+        }
+        if self.fallback_models.iter().any(|m| m.trim().is_empty()) {
+            return Err("degradation.fallback_models can't contain an empty model".to_string());
+        }
+        if self.message.trim().is_empty() {
+            return Err("degradation.message can't be empty".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// How far down the ladder a reply came from
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DegradationLevel {
+    #[default]
+    None,
+    SmallerModel,
+    Cached,
+    Canned,
+}
+
+impl DegradationLevel {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::SmallerModel => "smaller_model",
+            Self::Cached => "cached",
+            Self::Canned => "canned",
+        }
+    }
+}
+
+/// The configured ladder and the answers kept for its cached tier
+pub struct Degradation {
+    config: DegradationConfig,
+    answers: Mutex<VecDeque<(String, String)>>,
+}
+
+impl Degradation {
+    pub fn new(config: DegradationConfig) -> Self {
+        Self { config, answers: Mutex::new(VecDeque::new()) }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    /// The model to start a turn on when the budget is tight, given the
+    /// share of it used so far
+    pub fn budget_model(&self, used_share: Option<f64>) -> Option<&str> {
+        if !self.config.enabled || used_share.is_none_or(|share| share < self.config.budget_share) {
+            return None;
+        }
+        self.config.fallback_models.first().map(String::as_str)
+    }
+
+    /// Whether today's budget is spent, leaving only the cached and canned
+    /// tiers
+    pub fn budget_exhausted(&self, used_share: Option<f64>) -> bool {
+        self.config.enabled && used_share.is_some_and(|share| share >= 1.0)
+    }
+
+    /// The model to try after `model` failed: the next fallback, or the
+    /// first one if `model` isn't a fallback itself
+    pub fn fallback_after(&self, model: &str) -> Option<&str> {
+        if !self.config.enabled {
+            return None;
+        }
+        let models = &self.config.fallback_models;
+        let next = match models.iter().position(|m| m == model) {
+            Some(index) => index + 1,
+            None => 0,
+        };
+        models.get(next).map(String::as_str)
+    }
+
+    /// Keep a full answer to `question`, asked by `scope`, for reuse
+    pub fn remember(&self, scope: &str, question: &str, answer: &str) {
+        if !self.config.enabled || self.config.cached_answers == 0 {
+            return;
+        }
+        let key = answer_key(scope, question);
+        let mut answers = self.answers.lock();
+        answers.retain(|(k, _)| *k != key);
+        answers.push_back((key, answer.to_string()));
+        while answers.len() > self.config.cached_answers {
+            answers.pop_front();
+        }
+    }
+
+    /// The last full answer to `question` asked by `scope`
+    pub fn cached(&self, scope: &str, question: &str) -> Option<String> {
+        let key = answer_key(scope, question);
+        self.answers
+            .lock()
+            .iter()
+            .rev()
+            .find(|(k, _)| *k == key)
+            .map(|(_, answer)| answer.clone())
+    }
+
+    /// The reply for when nothing else is left, and the level it is at
+    pub fn fallback_reply(&self, scope: &str, question: &str) -> (String, DegradationLevel) {
+        match self.cached(scope, question) {
+            Some(answer) => (answer, DegradationLevel::Cached),
+            None => (self.config.message.clone(), DegradationLevel::Canned),
+        }
+    }
+}
+
+/// Questions match when they differ only in case, spacing and trailing
+/// punctuation
+fn answer_key(scope: &str, question: &str) -> String {
+    let words: Vec<String> = question.split_whitespace().map(str::to_lowercase).collect();
+    let question = words.join(" ");
+    format!("{}\n{}", scope, question.trim_end_matches(['?', '!', '.']))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ladder() -> Degradation {
+        Degradation::new(DegradationConfig {
+            enabled: true,
+            fallback_models: vec!["small".to_string(), "tiny".to_string()],
+            cached_answers: 2,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_fallback_models_in_order() {
+        let ladder = ladder();
+        assert_eq!(ladder.fallback_after("primary"), Some("small"));
+        assert_eq!(ladder.fallback_after("small"), Some("tiny"));
+        assert_eq!(ladder.fallback_after("tiny"), None);
+
+        assert_eq!(ladder.budget_model(Some(0.5)), None);
+        assert_eq!(ladder.budget_model(Some(0.95)), Some("small"));
+        assert_eq!(ladder.budget_model(None), None);
+        assert!(ladder.budget_exhausted(Some(1.0)));
+
+        let off = Degradation::new(DegradationConfig::default());
+        assert_eq!(off.fallback_after("primary"), None);
+        assert!(!off.budget_exhausted(Some(2.0)));
+    }
+
+    #[test]
+    fn test_cached_answers_are_scoped_and_bounded() {
+        let ladder = ladder();
+        ladder.remember("alice", "What's the VPN address?", "vpn.example.com");
+        assert_eq!(ladder.cached("alice", "  what's the  VPN address "), Some("vpn.example.com".to_string()));
+        assert_eq!(ladder.cached("bob", "What's the VPN address?"), None);
+
+        ladder.remember("alice", "second", "2");
+        ladder.remember("alice", "third", "3");
+        assert_eq!(ladder.cached("alice", "What's the VPN address?"), None);
+
+        let (reply, level) = ladder.fallback_reply("alice", "third");
+        assert_eq!((reply.as_str(), level), ("3", DegradationLevel::Cached));
+        let (_, level) = ladder.fallback_reply("alice", "unknown");
+        assert_eq!(level, DegradationLevel::Canned);
+    }
+}
@@ -0,0 +1,84 @@
+//! Runtime event bus
+//!
+//! Turns, tool calls, approvals, scheduled jobs and inbound hooks publish
+//! [`RuntimeEvent`]s here; outbound webhooks and desktop notifications
+//! subscribe to them. Publishing never blocks, and events
+//! nobody is listening for are dropped. Payloads are redacted on publish, so
+//! secrets in tool arguments or errors never reach a subscriber.
+
+use chrono::{DateTime, Utc};
+use jamey_core::redaction::Redactor;
+use serde::Serialize;
+use std::sync::Arc;
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+pub const TURN_COMPLETED: &str = "turn.completed";
+pub const TURN_FAILED: &str = "turn.failed";
+/// A turn fell back to a smaller model, a cached answer or the canned reply
+pub const TURN_DEGRADED: &str = "turn.degraded";
+pub const TOOL_EXECUTED: &str = "tool.executed";
+pub const HOOK_RECEIVED: &str = "hook.received";
+pub const APPROVAL_REQUESTED: &str = "approval.requested";
+pub const JOB_COMPLETED: &str = "job.completed";
+pub const JOB_FAILED: &str = "job.failed";
+/// Today's spend crossed the warning share of the daily budget
+pub const BUDGET_WARNING: &str = "budget.warning";
+pub const BRIEFING_DELIVERED: &str = "briefing.delivered";
+/// An idle session was summarized into memory and archived
+pub const SESSION_ARCHIVED: &str = "session.archived";
+/// This instance became, or stopped being, its cluster's leader
+pub const LEADER_CHANGED: &str = "cluster.leader_changed";
+/// A supervised background task panicked; it's restarted unless it has
+/// panicked too often in a row
+pub const TASK_PANICKED: &str = "task.panicked";
+
+/// Events a slow subscriber may fall behind by before it misses some
+const CAPACITY: usize = 256;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RuntimeEvent {
+    /// Dotted name such as `turn.completed`
+    pub kind: String,
+    pub session_id: Option<Uuid>,
+    pub data: serde_json::Value,
+    pub at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone)]
+pub struct EventBus {
+    tx: broadcast::Sender<RuntimeEvent>,
+    redactor: Arc<Redactor>,
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        let (tx, _) = broadcast::channel(CAPACITY);
+        Self { tx, redactor: Arc::new(Redactor::disabled()) }
+    }
+
+    pub fn with_redactor(mut self, redactor: Arc<Redactor>) -> Self {
+        self.redactor = redactor;
+        self
+    }
+
+    pub fn publish(&self, kind: &str, session_id: Option<Uuid>, mut data: serde_json::Value) {
+        self.redactor.redact_json(&mut data);
+        let _ = self.tx.send(RuntimeEvent {
+            kind: kind.to_string(),
+            session_id,
+            data,
+            at: Utc::now(),
+        });
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<RuntimeEvent> {
+        self.tx.subscribe()
+    }
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
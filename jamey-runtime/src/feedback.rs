@@ -0,0 +1,430 @@
+//! Feedback on replies and the preference profile built from it
+//!
+//! A thumbs-up, thumbs-down or correction on a reply is kept twice: as a
+//! `Preference` memory linked to the session and message, so it can be
+//! recalled like any other memory, and in the user's profile
+//! `<preference_dir>/<user>.json`. The newest profile entries are turned into
+//! a system prompt for that user's later turns.
+
+use crate::session_store::SessionStoreError;
+use crate::state::RuntimeState;
+use chrono::{DateTime, Utc};
+use jamey_core::memory::{Memory, MemoryStore, MemoryType};
+use jamey_protocol::Role;
+use jamey_providers::openrouter::{LlmProvider, DEFAULT_EMBEDDING_MODEL};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+use uuid::Uuid;
+
+/// Entries kept per profile; older feedback stays in memory only
+const PROFILE_ENTRIES: usize = 100;
+
+/// Corrections quoted in the prompt
+const PROMPT_CORRECTIONS: usize = 10;
+
+/// Liked and disliked replies quoted in the prompt, each
+const PROMPT_EXAMPLES: usize = 3;
+
+/// Characters of the question and reply kept with each entry
+const EXCERPT_CHARS: usize = 200;
+
+#[derive(Debug, Error)]
+pub enum FeedbackError {
+    #[error("Feedback needs a rating or a correction")]
+    Empty,
+    #[error("Invalid user name: {0:?}")]
+    InvalidUser(String),
+    #[error("Message {0} is not a reply in this session")]
+    NotAReply(Uuid),
+    #[error(transparent)]
+    Session(#[from] SessionStoreError),
+    #[error("Failed to store preference memory: {0}")]
+    Memory(String),
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Serialization error: {0}")]
+    Serialization(#[from] serde_json::Error),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Rating {
+    Up,
+    Down,
+}
+
+/// What the user thought of a reply
+#[derive(Debug, Clone, Default)]
+pub struct Feedback {
+    pub rating: Option<Rating>,
+    /// What the reply should have said or done instead
+    pub correction: Option<String>,
+}
+
+impl Feedback {
+    /// Parse the chat commands `/up [note]`, `/down [correction]` and
+    /// `/correct <correction>`; `None` for any other input
+    pub fn from_command(input: &str) -> Option<Self> {
+        let (command, rest) = input.split_once(' ').unwrap_or((input, ""));
+        let rating = match command {
+            "/up" => Some(Rating::Up),
+            "/down" => Some(Rating::Down),
+            "/correct" => None,
+            _ => return None,
+        };
+        let rest = rest.trim();
+        Some(Self {
+            rating,
+            correction: (!rest.is_empty()).then(|| rest.to_string()),
+        })
+    }
+}
+
+/// One piece of feedback as recorded in a profile
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeedbackEntry {
+    pub at: DateTime<Utc>,
+    pub session_id: Uuid,
+    pub message_id: Uuid,
+    /// The `Preference` memory stored for this feedback
+    pub memory_id: Uuid,
+    pub rating: Option<Rating>,
+    pub correction: Option<String>,
+    /// Start of the question that was answered
+    pub question: String,
+    /// Start of the reply the feedback is about
+    pub reply: String,
+}
+
+impl FeedbackEntry {
+    /// Text of the `Preference` memory for this entry
+    fn memory_content(&self) -> String {
+        let verdict = match self.rating {
+            Some(Rating::Up) => "liked",
+            Some(Rating::Down) => "disliked",
+            None => "corrected",
+        };
+        let mut content = format!(
+            "The user {} this answer to \"{}\": {}",
+            verdict, self.question, self.reply
+        );
+        if let Some(correction) = &self.correction {
+            content.push_str(&format!("\nTheir correction: {}", correction));
+        }
+        content
+    }
+}
+
+/// Feedback a user has given, newest last
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PreferenceProfile {
+    pub user: String,
+    pub entries: Vec<FeedbackEntry>,
+}
+
+impl PreferenceProfile {
+    /// System prompt describing how the user wants to be answered, or `None`
+    /// until they've given feedback
+    pub fn prompt(&self) -> Option<String> {
+        if self.entries.is_empty() {
+            return None;
+        }
+        let mut prompt = String::from(
+            "The user has given feedback on earlier replies. Follow it unless they ask otherwise.\n",
+        );
+        let corrections: Vec<&str> = self
+            .entries
+            .iter()
+            .rev()
+            .filter_map(|e| e.correction.as_deref())
+            .take(PROMPT_CORRECTIONS)
+            .collect();
+        if !corrections.is_empty() {
+            prompt.push_str("\nCorrections, newest first:\n");
+            for correction in corrections {
+                prompt.push_str(&format!("- {}\n", correction));
+            }
+        }
+        for (rating, heading) in [
+            (Rating::Down, "Replies they disliked"),
+            (Rating::Up, "Replies they liked"),
+        ] {
+            let examples: Vec<&FeedbackEntry> = self
+                .entries
+                .iter()
+                .rev()
+                .filter(|e| e.rating == Some(rating) && e.correction.is_none())
+                .take(PROMPT_EXAMPLES)
+                .collect();
+            if examples.is_empty() {
+                continue;
+            }
+            prompt.push_str(&format!("\n{}:\n", heading));
+            for entry in examples {
+                prompt.push_str(&format!("- To \"{}\": {}\n", entry.question, entry.reply));
+            }
+        }
+        Some(prompt)
+    }
+}
+
+pub(crate) fn default_preference_dir() -> PathBuf {
+    std::env::var("JAMEY_PREFERENCE_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from("./preferences"))
+}
+
+/// Name feedback is filed under for the local CLI and TUI
+pub fn local_user() -> String {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_else(|_| "local".to_string())
+}
+
+/// Directory of per-user preference profiles
+#[derive(Debug, Clone)]
+pub struct PreferenceStore {
+    dir: PathBuf,
+}
+
+impl PreferenceStore {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// User names become file names, so only plain ones are accepted
+    fn path(&self, user: &str) -> Result<PathBuf, FeedbackError> {
+        let valid = !user.is_empty()
+            && user.len() <= 64
+            && !user.starts_with('.')
+            && user
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+        if !valid {
+            return Err(FeedbackError::InvalidUser(user.to_string()));
+        }
+        Ok(self.dir.join(format!("{}.json", user)))
+    }
+
+    /// `user`'s profile; empty if they've never given feedback
+    pub async fn load(&self, user: &str) -> Result<PreferenceProfile, FeedbackError> {
+        match tokio::fs::read(self.path(user)?).await {
+            Ok(bytes) => Ok(serde_json::from_slice(&bytes)?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(PreferenceProfile {
+                user: user.to_string(),
+                entries: Vec::new(),
+            }),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Add `entry` to `user`'s profile, dropping the oldest past
+    /// [`PROFILE_ENTRIES`]
+    pub async fn add(&self, user: &str, entry: FeedbackEntry) -> Result<PreferenceProfile, FeedbackError> {
+        let mut profile = self.load(user).await?;
+        profile.entries.push(entry);
+        let excess = profile.entries.len().saturating_sub(PROFILE_ENTRIES);
+        profile.entries.drain(..excess);
+
+        self.write(&profile).await?;
+        Ok(profile)
+    }
+
+    async fn write(&self, profile: &PreferenceProfile) -> Result<(), FeedbackError> {
+        tokio::fs::create_dir_all(&self.dir).await?;
+        let path = self.path(&profile.user)?;
+        let tmp = path.with_extension("json.tmp");
+        tokio::fs::write(&tmp, serde_json::to_vec_pretty(profile)?).await?;
+        tokio::fs::rename(&tmp, &path).await?;
+        Ok(())
+    }
+
+    /// Delete `user`'s profile; returns how many entries it held
+    pub async fn remove(&self, user: &str, dry_run: bool) -> Result<usize, FeedbackError> {
+        let entries = self.load(user).await?.entries.len();
+        if !dry_run {
+            match tokio::fs::remove_file(self.path(user)?).await {
+                Ok(()) => {}
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Ok(entries)
+    }
+
+    /// Drop the entries about `sessions` from every profile; returns how
+    /// many there were
+    pub async fn prune_sessions(&self, sessions: &HashSet<Uuid>, dry_run: bool) -> Result<usize, FeedbackError> {
+        let mut entries = match tokio::fs::read_dir(&self.dir).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(e.into()),
+        };
+        let mut removed = 0;
+        while let Some(entry) = entries.next_entry().await? {
+            let name = entry.file_name();
+            let Some(user) = name.to_str().and_then(|n| n.strip_suffix(".json")) else {
+                continue;
+            };
+            let mut profile = self.load(user).await?;
+            let before = profile.entries.len();
+            profile.entries.retain(|e| !sessions.contains(&e.session_id));
+            let pruned = before - profile.entries.len();
+            if pruned > 0 && !dry_run {
+                self.write(&profile).await?;
+            }
+            removed += pruned;
+        }
+        Ok(removed)
+    }
+}
+
+impl RuntimeState {
+    /// Record `user`'s feedback on reply `message_id` of a saved session: it
+    /// is stored as a `Preference` memory and added to their profile
+    pub async fn record_feedback(
+        &self,
+        session_id: Uuid,
+        message_id: Uuid,
+        user: &str,
+        feedback: Feedback,
+    ) -> Result<FeedbackEntry, FeedbackError> {
+        let correction = feedback
+            .correction
+            .map(|c| c.trim().to_string())
+            .filter(|c| !c.is_empty());
+        if feedback.rating.is_none() && correction.is_none() {
+            return Err(FeedbackError::Empty);
+        }
+        // Fail on a bad name before anything is stored
+        self.preference_store.path(user)?;
+
+        let record = self.session_store.load(session_id).await?;
+        let position = record
+            .messages
+            .iter()
+            .position(|m| m.id == message_id && m.role == Role::Assistant)
+            .ok_or(FeedbackError::NotAReply(message_id))?;
+        let question = record.messages[..position]
+            .iter()
+            .rev()
+            .find(|m| m.role == Role::User)
+            .map(|m| excerpt(&m.content))
+            .unwrap_or_default();
+
+        let mut entry = FeedbackEntry {
+            at: Utc::now(),
+            session_id,
+            message_id,
+            memory_id: Uuid::new_v4(),
+            rating: feedback.rating,
+            correction,
+            question,
+            reply: excerpt(&record.messages[position].content),
+        };
+        let content = entry.memory_content();
+        let embedding = self
+            .llm_provider
+            .get_embedding(&content)
+            .await
+            .map_err(|e| FeedbackError::Memory(e.to_string()))?;
+        entry.memory_id = self
+            .memory_store
+            .store(Memory {
+                id: entry.memory_id,
+                memory_type: MemoryType::Preference,
+                content,
+                embedding,
+                metadata: serde_json::json!({
+                    "source": "feedback",
+                    "session_id": session_id,
+                    "message_id": message_id,
+                    "user": user,
+                    "rating": entry.rating,
+                    "correction": entry.correction,
+                    "embedding_model": DEFAULT_EMBEDDING_MODEL,
+                }),
+                created_at: entry.at,
+                last_accessed: entry.at,
+            })
+            .await
+            .map_err(|e| FeedbackError::Memory(e.to_string()))?;
+
+        self.preference_store.add(user, entry.clone()).await?;
+        Ok(entry)
+    }
+}
+
+/// `text` on one line, cut to [`EXCERPT_CHARS`]
+fn excerpt(text: &str) -> String {
+    let flat = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if flat.chars().count() <= EXCERPT_CHARS {
+        return flat;
+    }
+    let mut cut: String = flat.chars().take(EXCERPT_CHARS - 1).collect();
+    cut.push('…');
+    cut
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn entry(rating: Option<Rating>, correction: Option<&str>, reply: &str) -> FeedbackEntry {
+        FeedbackEntry {
+            at: Utc::now(),
+            session_id: Uuid::new_v4(),
+            message_id: Uuid::new_v4(),
+            memory_id: Uuid::new_v4(),
+            rating,
+            correction: correction.map(String::from),
+            question: "How do I list files?".to_string(),
+            reply: reply.to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_profile_round_trip_and_prompt() {
+        let dir = TempDir::new().unwrap();
+        let store = PreferenceStore::new(dir.path());
+        assert!(store.load("alice").await.unwrap().prompt().is_none());
+        assert!(matches!(store.load("../etc").await, Err(FeedbackError::InvalidUser(_))));
+
+        store.add("alice", entry(Some(Rating::Up), None, "Use `ls -la`.")).await.unwrap();
+        store
+            .add("alice", entry(Some(Rating::Down), Some("Show the command first"), "There are many ways..."))
+            .await
+            .unwrap();
+        store.add("alice", entry(Some(Rating::Down), None, "It depends.")).await.unwrap();
+
+        let profile = store.load("alice").await.unwrap();
+        assert_eq!(profile.entries.len(), 3);
+        let prompt = profile.prompt().unwrap();
+        assert!(prompt.contains("- Show the command first"));
+        assert!(prompt.contains("Replies they disliked:\n- To \"How do I list files?\": It depends."));
+        assert!(prompt.contains("Replies they liked:\n- To \"How do I list files?\": Use `ls -la`."));
+        assert!(!prompt.contains("There are many ways"));
+
+        for _ in 0..PROFILE_ENTRIES {
+            store.add("alice", entry(Some(Rating::Up), None, "ok")).await.unwrap();
+        }
+        assert_eq!(store.load("alice").await.unwrap().entries.len(), PROFILE_ENTRIES);
+
+        let command = Feedback::from_command("/down  too long ").unwrap();
+        assert_eq!(command.rating, Some(Rating::Down));
+        assert_eq!(command.correction.as_deref(), Some("too long"));
+        assert!(Feedback::from_command("/correct").unwrap().rating.is_none());
+        assert!(Feedback::from_command("/upload x").is_none());
+
+        let memory = entry(None, Some("Prefer fd"), "Use find.").memory_content();
+        assert!(memory.starts_with("The user corrected this answer to \"How do I list files?\""));
+        assert!(memory.ends_with("Their correction: Prefer fd"));
+    }
+}
@@ -0,0 +1,187 @@
+//! Deleting everything kept about a user, a session or a pattern
+//!
+//! Deletion requests (GDPR "right to erasure" and the like) have to reach
+//! every store: memories, saved transcripts, live sessions and the memories
+//! they have cached, usage records in the daily files and the `usage_log`
+//! table, prompt traces, and feedback in preference profiles. [`RuntimeState::forget`]
+//! covers all of them, and a dry run reports the same counts without
+//! deleting anything.
+//!
+//! A target first resolves to a set of sessions. A session is just itself.
+//! A user owns the sessions they are attributed to, gave feedback in or have
+//! memories from. A pattern covers every transcript that mentions it.
+//! Everything recorded in those sessions is removed, along with the memories
+//! the target matches directly: the user's feedback memories, or any memory
+//! whose content matches the pattern.
+
+use crate::feedback::FeedbackError;
+use crate::prompt_trace::TraceError;
+use crate::session_store::SessionStoreError;
+use crate::state::RuntimeState;
+use crate::usage::{ForgottenUsage, UsageError};
+use jamey_core::maintenance::MemorySelector;
+use regex::Regex;
+use serde::Serialize;
+use std::collections::HashSet;
+use thiserror::Error;
+use uuid::Uuid;
+
+#[derive(Debug, Error)]
+pub enum ForgetError {
+    #[error("Invalid pattern: {0}")]
+    InvalidPattern(#[from] regex::Error),
+    #[error("Memory store error: {0}")]
+    Memory(String),
+    #[error("Session store error: {0}")]
+    Sessions(#[from] SessionStoreError),
+    #[error("Usage log error: {0}")]
+    Usage(#[from] UsageError),
+    #[error("Prompt trace error: {0}")]
+    Traces(#[from] TraceError),
+    #[error("Preference store error: {0}")]
+    Preferences(#[from] FeedbackError),
+}
+
+/// What to forget
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ForgetTarget {
+    /// A user name as given to feedback and session attribution
+    User(String),
+    Session(Uuid),
+    /// Regular expression matched against memory and transcript text
+    Pattern(String),
+}
+
+impl std::fmt::Display for ForgetTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::User(user) => write!(f, "user {}", user),
+            Self::Session(id) => write!(f, "session {}", id),
+            Self::Pattern(pattern) => write!(f, "pattern {:?}", pattern),
+        }
+    }
+}
+
+/// What [`RuntimeState::forget`] removed, or on a dry run would remove
+#[derive(Debug, Clone, Serialize)]
+pub struct ForgetReport {
+    pub target: String,
+    pub dry_run: bool,
+    /// Sessions the target resolved to
+    pub sessions: Vec<Uuid>,
+    /// Saved transcripts among them
+    pub transcripts: Vec<Uuid>,
+    pub memories: Vec<Uuid>,
+    /// Sessions that were live in this process
+    pub live_sessions: usize,
+    /// Copies of the memories cached by live sessions
+    pub cached_memories: usize,
+    pub usage: ForgottenUsage,
+    /// Traced model calls
+    pub prompt_traces: usize,
+    /// Feedback entries in preference profiles
+    pub preference_entries: usize,
+}
+
+impl ForgetReport {
+    /// Nothing was found for the target
+    pub fn is_empty(&self) -> bool {
+        self.transcripts.is_empty()
+            && self.memories.is_empty()
+            && self.live_sessions == 0
+            && self.preference_entries == 0
+            && self.prompt_traces == 0
+            && self.usage == ForgottenUsage::default()
+    }
+}
+
+impl RuntimeState {
+    /// Delete every memory, transcript, cached entry, usage record, prompt
+    /// trace and piece of feedback tied to `target`
+    pub async fn forget(&self, target: &ForgetTarget, dry_run: bool) -> Result<ForgetReport, ForgetError> {
+        let mut sessions = HashSet::new();
+        let mut selector = MemorySelector::default();
+        match target {
+            ForgetTarget::Session(id) => {
+                sessions.insert(*id);
+            }
+            ForgetTarget::User(user) => {
+                sessions.extend(self.session_manager.sessions_of(user));
+                let profile = self.preference_store.load(user).await?;
+                sessions.extend(profile.entries.iter().map(|e| e.session_id));
+                selector.user = Some(user.clone());
+                // The user's own memories name sessions the runtime may no longer know about
+                let own = self.memory_store.select(&selector).await.map_err(|e| ForgetError::Memory(e.to_string()))?;
+                sessions.extend(own.iter().filter_map(|m| m.session_id));
+            }
+            ForgetTarget::Pattern(pattern) => {
+                let pattern = Regex::new(pattern)?;
+                sessions.extend(self.session_store.find_mentions(&pattern).await?);
+                selector.pattern = Some(pattern);
+            }
+        }
+        selector.sessions = sessions.clone();
+
+        let memories: Vec<Uuid> = self
+            .memory_store
+            .select(&selector)
+            .await
+            .map_err(|e| ForgetError::Memory(e.to_string()))?
+            .into_iter()
+            .map(|m| m.id)
+            .collect();
+        let saved: HashSet<Uuid> = self.session_store.list().await?.into_iter().map(|s| s.id).collect();
+        let transcripts: Vec<Uuid> = sessions.iter().copied().filter(|id| saved.contains(id)).collect();
+
+        let cached_memories = self
+            .session_manager
+            .forget_memories(&memories.iter().copied().collect(), dry_run);
+        let live_sessions = self.session_manager.end_sessions(&sessions, dry_run);
+        let usage = self.usage_log.forget_sessions(&sessions, dry_run).await?;
+        let prompt_traces = self.prompt_traces.forget_sessions(&sessions, dry_run).await?;
+        // A user's entries are all about their own sessions, so pruning
+        // empties their profile and removing it adds nothing to the count
+        let preference_entries = self.preference_store.prune_sessions(&sessions, dry_run).await?;
+        if let ForgetTarget::User(user) = target {
+            self.preference_store.remove(user, dry_run).await?;
+        }
+        if !dry_run {
+            self.memory_store
+                .delete_many(&memories)
+                .await
+                .map_err(|e| ForgetError::Memory(e.to_string()))?;
+            for id in &transcripts {
+                match self.session_store.delete(*id).await {
+                    Ok(()) | Err(SessionStoreError::NotFound(_)) => {}
+                    Err(e) => return Err(e.into()),
+                }
+            }
+        }
+
+        let mut sessions: Vec<Uuid> = sessions.into_iter().collect();
+        sessions.sort();
+        let report = ForgetReport {
+            target: target.to_string(),
+            dry_run,
+            sessions,
+            transcripts,
+            memories,
+            live_sessions,
+            cached_memories,
+            usage,
+            prompt_traces,
+            preference_entries,
+        };
+        if !dry_run {
+            tracing::info!(
+                target: "audit",
+                sessions = report.sessions.len(),
+                memories = report.memories.len(),
+                transcripts = report.transcripts.len(),
+                "Forgot {}",
+                report.target
+            );
+        }
+        Ok(report)
+    }
+}
@@ -0,0 +1,99 @@
+//! Generation strategies
+//!
+//! How a chat turn produces its answer. [`GenerationStrategy::Direct`] has
+//! the turn's model answer straight away. With
+//! [`GenerationStrategy::DraftThenVerify`] a cheaper model runs the turn,
+//! tool calls included, and the turn's model then checks the finished
+//! draft: it either approves it with a single word or writes the corrected
+//! answer. Long replies that need no correction cost only the cheap model's
+//! output plus a short verdict.
+
+use jamey_providers::openrouter;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// What the verifier says when the draft can be sent as it is
+pub const APPROVED: &str = "APPROVED";
+
+const VERIFY_PROMPT: &str = "The last assistant message is a draft reply written by a faster model. \
+Check it against the conversation for mistakes, omissions and unsupported claims. If it is correct and \
+complete, reply with exactly APPROVED and nothing else. Otherwise reply with the full corrected answer, \
+written to the user, without mentioning the draft or this review.";
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum GenerationStrategy {
+    /// The turn's model answers
+    #[default]
+    Direct,
+    /// `draft_model` answers and the turn's model verifies or edits it
+    DraftThenVerify { draft_model: String },
+}
+
+impl GenerationStrategy {
+    /// Model that runs the turn's rounds when the turn's model is `model`
+    pub fn drafting_model<'a>(&'a self, model: &'a str) -> &'a str {
+        match self {
+            Self::Direct => model,
+            Self::DraftThenVerify { draft_model } => draft_model,
+        }
+    }
+
+    pub fn verifies(&self) -> bool {
+        matches!(self, Self::DraftThenVerify { .. })
+    }
+}
+
+/// The conversation with the draft and the review instructions appended,
+/// for the verifying model
+pub fn verify_messages(messages: &[openrouter::Message], draft: &str) -> Vec<openrouter::Message> {
+    let mut messages = messages.to_vec();
+    messages.push(openrouter::Message {
+        role: "assistant".to_string(),
+        content: draft.to_string(),
+    });
+    messages.push(openrouter::Message {
+        role: "system".to_string(),
+        content: VERIFY_PROMPT.to_string(),
+    });
+    messages
+}
+
+/// Whether the verifier's output so far could still turn out to be the
+/// approval, so it shouldn't be shown yet
+pub fn may_be_approval(reply: &str) -> bool {
+    let reply = reply.trim();
+    APPROVED.starts_with(reply) || is_approval(reply)
+}
+
+/// Whether the verifier's reply approves the draft; trailing punctuation
+/// is tolerated
+pub fn is_approval(reply: &str) -> bool {
+    reply.trim().trim_end_matches(['.', '!']) == APPROVED
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verdict() {
+        assert!(is_approval("APPROVED"));
+        assert!(is_approval(" APPROVED.\n"));
+        assert!(!is_approval("APPROVED, but the date is wrong"));
+        assert!(!is_approval("The answer is 42."));
+
+        assert!(may_be_approval(""));
+        assert!(may_be_approval("APPR"));
+        assert!(may_be_approval("APPROVED."));
+        assert!(!may_be_approval("The"));
+        assert!(!may_be_approval("APPROVED, but"));
+
+        let strategy = GenerationStrategy::DraftThenVerify { draft_model: "cheap".to_string() };
+        assert_eq!(strategy.drafting_model("premium"), "cheap");
+        assert_eq!(GenerationStrategy::Direct.drafting_model("premium"), "premium");
+        let messages = verify_messages(&[], "draft");
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].content, "draft");
+    }
+}
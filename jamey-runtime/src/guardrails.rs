@@ -0,0 +1,359 @@
+//! Output guardrails
+//!
+//! A chain of filters checks each assistant reply before it is delivered:
+//! deny-list patterns, personal and secret data, a length limit and JSON
+//! validity. What happens on a violation depends on the session's
+//! [`Strictness`]: a lenient session gets the reply with whatever the filters
+//! can fix fixed, a strict one first has the model rewrite it and never gets
+//! a reply that still breaks a rule. Violations are logged to the `audit`
+//! target. With no filter configured replies stream as before; otherwise
+//! they are held until checked.
+//!
+//! ```toml
+//! [guardrails]
+//! strictness = "strict"
+//! deny = ["(?i)internal\\.example\\.com", "(?i)project bluebird"]
+//! pii = true
+//! max_chars = 4000
+//! reprompts = 1
+//! ```
+
+use jamey_core::secure_logging::redact_sensitive_data;
+use regex::Regex;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use uuid::Uuid;
+
+/// Put in place of deny-list matches
+const REMOVED: &str = "[removed]";
+
+/// Sent instead of a strict session's reply that could not be fixed
+pub const WITHHELD: &str = "I can't share that reply: it breaks this session's output rules.";
+
+#[derive(Debug, Error)]
+pub enum GuardrailError {
+    #[error("Invalid deny pattern {0:?}: {1}")]
+    InvalidPattern(String, regex::Error),
+    #[error("Unknown strictness '{0}' (expected off, lenient or strict)")]
+    UnknownStrictness(String),
+}
+
+/// How a session's replies are held to the filters
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Strictness {
+    /// Replies are not checked
+    Off,
+    /// Violations are logged and repaired where a filter can; the reply is
+    /// always delivered
+    #[default]
+    Lenient,
+    /// The model is asked to fix violations, and a reply that still breaks
+    /// a rule after repairs is withheld
+    Strict,
+}
+
+impl std::str::FromStr for Strictness {
+    type Err = GuardrailError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "off" => Ok(Self::Off),
+            "lenient" => Ok(Self::Lenient),
+            "strict" => Ok(Self::Strict),
+            other => Err(GuardrailError::UnknownStrictness(other.to_string())),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct GuardrailConfig {
+    /// Strictness of sessions that haven't picked one (`JAMEY_GUARDRAILS`)
+    pub strictness: Strictness,
+    /// Regular expressions replies must not match; matches are removed
+    pub deny: Vec<String>,
+    /// Redact e-mail addresses, card numbers, keys, tokens and the like
+    /// (`JAMEY_GUARDRAIL_PII`)
+    pub pii: bool,
+    /// Longest reply, in characters; longer ones are cut
+    /// (`JAMEY_GUARDRAIL_MAX_CHARS`)
+    pub max_chars: Option<usize>,
+    /// Replies must be a JSON document, optionally in one code fence
+    pub json: bool,
+    /// Rewrites a strict session asks for before repairing the reply itself
+    pub reprompts: usize,
+}
+
+impl Default for GuardrailConfig {
+    fn default() -> Self {
+        Self {
+            strictness: Strictness::default(),
+            deny: Vec::new(),
+            pii: false,
+            max_chars: None,
+            json: false,
+            reprompts: 1,
+        }
+    }
+}
+
+impl GuardrailConfig {
+    pub fn validate(&self) -> Result<(), GuardrailError> {
+        Guardrails::new(self).map(|_| ())
+    }
+}
+
+/// One rule a reply broke
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    pub filter: &'static str,
+    /// What is wrong, without quoting the offending text
+    pub detail: String,
+}
+
+/// A check run over every reply
+pub trait OutputFilter: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// What is wrong with `output`, if anything
+    fn check(&self, output: &str) -> Option<String>;
+
+    /// `output` with the problem fixed, for filters that can fix it
+    fn repair(&self, output: &str) -> Option<String>;
+}
+
+/// Removes text matching any of its patterns
+struct DenyList {
+    patterns: Vec<Regex>,
+}
+
+impl OutputFilter for DenyList {
+    fn name(&self) -> &'static str {
+        "deny_list"
+    }
+
+    fn check(&self, output: &str) -> Option<String> {
+        let pattern = self.patterns.iter().find(|p| p.is_match(output))?;
+        Some(format!("matches deny pattern {:?}", pattern.as_str()))
+    }
+
+    fn repair(&self, output: &str) -> Option<String> {
+        let mut output = output.to_string();
+        for pattern in &self.patterns {
+            output = pattern.replace_all(&output, REMOVED).into_owned();
+        }
+        Some(output)
+    }
+}
+
+/// Redacts what the secure log writer would
+struct Pii;
+
+impl OutputFilter for Pii {
+    fn name(&self) -> &'static str {
+        "pii"
+    }
+
+    fn check(&self, output: &str) -> Option<String> {
+        (redact_sensitive_data(output) != output).then(|| "contains personal or secret data".to_string())
+    }
+
+    fn repair(&self, output: &str) -> Option<String> {
+        Some(redact_sensitive_data(output))
+    }
+}
+
+struct MaxLength {
+    max_chars: usize,
+}
+
+impl OutputFilter for MaxLength {
+    fn name(&self) -> &'static str {
+        "max_length"
+    }
+
+    fn check(&self, output: &str) -> Option<String> {
+        let chars = output.chars().count();
+        (chars > self.max_chars).then(|| format!("{} characters, over the limit of {}", chars, self.max_chars))
+    }
+
+    fn repair(&self, output: &str) -> Option<String> {
+        let cut: String = output.chars().take(self.max_chars.saturating_sub(1)).collect();
+        Some(format!("{}…", cut.trim_end()))
+    }
+}
+
+/// Requires a JSON document; nothing can be repaired
+struct Json;
+
+impl OutputFilter for Json {
+    fn name(&self) -> &'static str {
+        "json"
+    }
+
+    fn check(&self, output: &str) -> Option<String> {
+        let text = output.trim();
+        let text = text
+            .strip_prefix("```json")
+            .or_else(|| text.strip_prefix("```"))
+            .and_then(|t| t.strip_suffix("```"))
+            .unwrap_or(text);
+        serde_json::from_str::<serde_json::Value>(text)
+            .err()
+            .map(|e| format!("not valid JSON: {}", e))
+    }
+
+    fn repair(&self, _output: &str) -> Option<String> {
+        None
+    }
+}
+
+/// The configured filter chain
+pub struct Guardrails {
+    filters: Vec<Box<dyn OutputFilter>>,
+    strictness: Strictness,
+    reprompts: usize,
+}
+
+impl Guardrails {
+    pub fn new(config: &GuardrailConfig) -> Result<Self, GuardrailError> {
+        let mut filters: Vec<Box<dyn OutputFilter>> = Vec::new();
+        if !config.deny.is_empty() {
+            let patterns = config
+                .deny
+                .iter()
+                .map(|p| Regex::new(p).map_err(|e| GuardrailError::InvalidPattern(p.clone(), e)))
+                .collect::<Result<_, _>>()?;
+            filters.push(Box::new(DenyList { patterns }));
+        }
+        if config.pii {
+            filters.push(Box::new(Pii));
+        }
+        if let Some(max_chars) = config.max_chars {
+            filters.push(Box::new(MaxLength { max_chars }));
+        }
+        if config.json {
+            filters.push(Box::new(Json));
+        }
+        Ok(Self {
+            filters,
+            strictness: config.strictness,
+            reprompts: config.reprompts,
+        })
+    }
+
+    /// Strictness of sessions that haven't picked one
+    pub fn strictness(&self) -> Strictness {
+        self.strictness
+    }
+
+    pub fn reprompts(&self) -> usize {
+        self.reprompts
+    }
+
+    /// Whether replies under `strictness` are checked at all, and so must
+    /// be held back until they have been
+    pub fn applies(&self, strictness: Strictness) -> bool {
+        strictness != Strictness::Off && !self.filters.is_empty()
+    }
+
+    pub fn check(&self, output: &str) -> Vec<Violation> {
+        self.filters
+            .iter()
+            .filter_map(|filter| {
+                filter.check(output).map(|detail| Violation {
+                    filter: filter.name(),
+                    detail,
+                })
+            })
+            .collect()
+    }
+
+    /// `output` passed through every filter that can repair what it finds
+    pub fn repair(&self, output: &str) -> String {
+        self.filters.iter().fold(output.to_string(), |output, filter| {
+            if filter.check(&output).is_some() {
+                filter.repair(&output).unwrap_or(output)
+            } else {
+                output
+            }
+        })
+    }
+}
+
+/// Log `violations` to the audit log along with what was done about them
+pub fn audit(session_id: Option<Uuid>, violations: &[Violation], action: &str) {
+    for violation in violations {
+        tracing::warn!(
+            target: "audit",
+            session_id = ?session_id,
+            filter = violation.filter,
+            action,
+            "Reply broke an output rule: {}",
+            violation.detail
+        );
+    }
+}
+
+/// Instructions asking the model to rewrite a reply that broke `violations`
+pub fn reprompt(violations: &[Violation]) -> String {
+    let rules: Vec<String> = violations.iter().map(|v| format!("- {}", v.detail)).collect();
+    format!(
+        "Your last reply can't be delivered because it breaks these output rules:\n{}\n\
+         Write the reply again so that it follows them. Reply with the new answer only.",
+        rules.join("\n")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn guardrails(config: GuardrailConfig) -> Guardrails {
+        Guardrails::new(&config).unwrap()
+    }
+
+    #[test]
+    fn test_filters_check_and_repair() {
+        let rails = guardrails(GuardrailConfig {
+            deny: vec!["(?i)bluebird".to_string()],
+            pii: true,
+            max_chars: Some(40),
+            ..Default::default()
+        });
+        assert!(rails.check("All good here.").is_empty());
+
+        let reply = "Project Bluebird ships soon, mail jane@example.com";
+        let filters: Vec<_> = rails.check(reply).into_iter().map(|v| v.filter).collect();
+        assert_eq!(filters, ["deny_list", "pii", "max_length"]);
+        let repaired = rails.repair(reply);
+        assert!(rails.check(&repaired).is_empty(), "{}", repaired);
+        assert!(repaired.starts_with("Project [removed] ships soon"));
+        assert!(!repaired.contains("jane@example.com"));
+    }
+
+    #[test]
+    fn test_json_is_not_repairable() {
+        let rails = guardrails(GuardrailConfig { json: true, ..Default::default() });
+        assert!(rails.check("{\"ok\": true}").is_empty());
+        assert!(rails.check("```json\n{\"ok\": true}\n```").is_empty());
+        assert_eq!(rails.check("ok: true")[0].filter, "json");
+        assert_eq!(rails.repair("ok: true"), "ok: true");
+    }
+
+    #[test]
+    fn test_strictness_and_config() {
+        let none = guardrails(GuardrailConfig::default());
+        assert!(!none.applies(Strictness::Strict));
+        let rails = guardrails(GuardrailConfig { pii: true, ..Default::default() });
+        assert!(rails.applies(Strictness::Lenient));
+        assert!(!rails.applies(Strictness::Off));
+
+        assert_eq!("Strict".parse::<Strictness>().unwrap(), Strictness::Strict);
+        assert!("loose".parse::<Strictness>().is_err());
+        let invalid = GuardrailConfig { deny: vec!["(".to_string()], ..Default::default() };
+        assert!(matches!(invalid.validate(), Err(GuardrailError::InvalidPattern(..))));
+    }
+}
@@ -3,13 +3,17 @@
 //! Combines system administration and self-improvement capabilities
 //! with all full-access connectors
 
-use jamey_tools::connector::{Connector, ConnectorRegistry, ConnectorResult, ExecutionContext};
+use crate::status;
+use jamey_tools::connector::{Compensation, ConnectorRegistry, ConnectorResult, ExecutionContext, ToolPolicy, WorkspaceScope};
 use std::collections::HashMap;
 use std::path::PathBuf;
 use anyhow::Result;
 use tracing::{info, warn, error};
 use serde::{Deserialize, Serialize};
 
+/// How often registered agents are asked for their capabilities
+const AGENT_HEALTH_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+
 #[derive(Debug, Clone)]
 pub enum SafetyMode {
     Development,  // All connectors enabled, minimal restrictions
@@ -35,6 +39,39 @@ pub struct FullAccessConfig {
     pub linkedin_token: Option<String>,
     pub web_search_api_key: Option<String>,
     pub mcp_server_url: Option<String>,
+    /// Supplies tokens for connectors whose token is not configured directly
+    pub oauth: Option<std::sync::Arc<jamey_tools::oauth::OAuthManager>>,
+    /// Confines the connectors that start programs
+    pub sandbox: jamey_tools::sandbox::ExecutionSandbox,
+    /// Which files the file connectors may read and write
+    pub path_policy: jamey_tools::path_policy::PathPolicy,
+    /// Restarts connectors' background loops, such as MQTT event loops, after a panic
+    pub supervisor: jamey_core::supervisor::Supervisor,
+    /// Where the LinkedIn connector keeps post drafts; it can't draft without
+    pub linkedin_drafts: Option<jamey_tools::connectors::linkedin::DraftMemory>,
+    /// How long each connector call may run before it is abandoned
+    pub timeouts: jamey_tools::timeouts::ToolTimeoutConfig,
+}
+
+impl FullAccessConfig {
+    /// Configured token, or one obtained through a completed OAuth login
+    async fn resolve_token(
+        &self,
+        configured: &Option<String>,
+        provider: jamey_tools::oauth::OAuthProvider,
+    ) -> Option<String> {
+        if configured.is_some() {
+            return configured.clone();
+        }
+        let oauth = self.oauth.as_ref()?;
+        match oauth.access_token(provider).await {
+            Ok(token) => Some(token),
+            Err(e) => {
+                warn!("No {} token available via OAuth: {}", provider.as_str(), e);
+                None
+            }
+        }
+    }
 }
 
 pub struct HybridOrchestrator {
@@ -53,6 +90,9 @@ impl HybridOrchestrator {
             file_system_root: system_root,
             allowed_hosts: Vec::new(), // Empty = all hosts
             credentials: HashMap::new(),
+            tool_policy: ToolPolicy::unrestricted(),
+            workspace: None,
+            partial_output: jamey_tools::timeouts::PartialOutput::new(),
         };
 
         Self {
@@ -63,17 +103,33 @@ impl HybridOrchestrator {
         }
     }
 
+    /// Hand a rotated credential to the connectors that use it
+    pub async fn propagate_credential(&self, key: &str, value: &str) -> Result<usize> {
+        self.connector_registry.propagate_credential(key, value).await
+    }
+
     /// Register all connectors with full access configuration
     pub async fn register_all_connectors(&self, config: &FullAccessConfig) -> Result<()> {
+        self.connector_registry.set_timeouts(config.timeouts.clone()).await;
+
         // System Admin
         let sys_admin = Box::new(jamey_tools::connectors::SystemAdminConnector::new());
         self.connector_registry.register(sys_admin).await?;
         info!("System Admin connector registered");
 
+        use jamey_tools::oauth::OAuthProvider;
+        let github_token = config.resolve_token(&config.github_token, OAuthProvider::GitHub).await;
+        let linkedin_token = config.resolve_token(&config.linkedin_token, OAuthProvider::LinkedIn).await;
+
         // Self Improvement
-        let self_improve = Box::new(
+        let mut self_improve =
             jamey_tools::connectors::SelfImproveConnector::new(config.backup_dir.clone(), 5)?
-        );
+                .with_sandbox(config.sandbox.clone())
+                .with_path_policy(config.path_policy.clone());
+        if let Some(ref token) = github_token {
+            self_improve = self_improve.with_github_token(token.clone())?;
+        }
+        let self_improve = Box::new(self_improve);
         self.connector_registry.register(self_improve).await?;
         info!("Self-Improvement connector registered");
 
@@ -88,7 +144,7 @@ impl HybridOrchestrator {
         info!("Network & Web connector registered");
 
         // GitHub
-        if let Some(ref token) = config.github_token {
+        if let Some(ref token) = github_token {
             let github = Box::new(
                 jamey_tools::connectors::GitHubConnector::new(token.clone())?
             );
@@ -97,18 +153,21 @@ impl HybridOrchestrator {
         }
 
         // LinkedIn
-        if let Some(ref token) = config.linkedin_token {
-            let linkedin = Box::new(
-                jamey_tools::connectors::LinkedInConnector::new(token.clone())?
-            );
+        if let Some(ref token) = linkedin_token {
+            let mut linkedin = jamey_tools::connectors::LinkedInConnector::new(token.clone())?;
+            if let Some(ref drafts) = config.linkedin_drafts {
+                linkedin = linkedin.with_drafts(drafts.clone());
+            }
+            let linkedin = Box::new(linkedin);
             self.connector_registry.register(linkedin).await?;
             info!("LinkedIn connector registered");
         }
 
         // Agent Orchestration
-        let agent_orch = Box::new(
-            jamey_tools::connectors::AgentOrchestrationConnector::new()
-        );
+        let agent_orch = jamey_tools::connectors::AgentOrchestrationConnector::new()?;
+        // Re-handshake with agents so routing sees which are back
+        agent_orch.spawn_health_checks(&config.supervisor, AGENT_HEALTH_CHECK_INTERVAL);
+        let agent_orch = Box::new(agent_orch);
         self.connector_registry.register(agent_orch).await?;
         info!("Agent Orchestration connector registered");
 
@@ -124,13 +183,71 @@ impl HybridOrchestrator {
         // Full System Access
         let full_sys = Box::new(
             jamey_tools::connectors::FullSystemConnector::new(config.system_root.clone())
+                .with_backup_dir(config.backup_dir.join("undo"))
+                .with_sandbox(config.sandbox.clone())
+                .with_path_policy(config.path_policy.clone())
         );
         self.connector_registry.register(full_sys).await?;
         info!("Full System Access connector registered");
 
+        // Git
+        let git = Box::new(
+            jamey_tools::connectors::GitConnector::new(config.system_root.clone())
+        );
+        self.connector_registry.register(git).await?;
+        info!("Git connector registered");
+
+        // Code Search
+        let code_search = Box::new(
+            jamey_tools::connectors::CodeSearchConnector::new(config.system_root.clone())
+        );
+        self.connector_registry.register(code_search).await?;
+        info!("Code Search connector registered");
+
+        // Language servers
+        let lsp = Box::new(
+            jamey_tools::connectors::LspConnector::new(config.system_root.clone())
+        );
+        self.connector_registry.register(lsp).await?;
+        info!("Language Server connector registered");
+
+        // Test Runner
+        let test_runner = Box::new(
+            jamey_tools::connectors::TestRunnerConnector::new(config.system_root.clone())
+                .with_sandbox(config.sandbox.clone())
+        );
+        self.connector_registry.register(test_runner).await?;
+        info!("Test Runner connector registered");
+
+        // Terminal
+        let terminal = Box::new(
+            jamey_tools::connectors::TerminalConnector::new(config.system_root.clone())
+                .with_sandbox(config.sandbox.clone())
+        );
+        self.connector_registry.register(terminal).await?;
+        info!("Terminal connector registered");
+
+        // Code Interpreter
+        let code_interpreter = Box::new(
+            jamey_tools::connectors::CodeInterpreterConnector::new(std::env::temp_dir().join("jamey-interpreter"))
+        );
+        self.connector_registry.register(code_interpreter).await?;
+        info!("Code Interpreter connector registered");
+
+        // Calculator
+        let calculator = Box::new(jamey_tools::connectors::CalculatorConnector::new());
+        self.connector_registry.register(calculator).await?;
+        info!("Calculator connector registered");
+
+        // Hardware inventory
+        let hardware = Box::new(jamey_tools::connectors::HardwareConnector::new());
+        self.connector_registry.register(hardware).await?;
+        info!("Hardware connector registered");
+
         // IoT Device Connector
         let iot = Box::new(
             jamey_tools::connectors::IoTConnector::new()?
+                .with_supervisor(config.supervisor.clone())
         );
         self.connector_registry.register(iot).await?;
         info!("IoT Device connector registered");
@@ -144,9 +261,58 @@ impl HybridOrchestrator {
         connector_id: &str,
         params: HashMap<String, String>,
     ) -> Result<ConnectorResult> {
+        let context = self.context.clone();
+        self.execute_in_context(connector_id, params, &context).await
+    }
+
+    /// Execute a connector on behalf of a session; the registry refuses
+    /// connectors outside `policy`, file connectors stay inside `workspace`
+    /// when the session is attached to one, and session-scoped connectors
+    /// such as the scratchpad key their state by `session_id`
+    pub async fn execute_connector_for(
+        &mut self,
+        connector_id: &str,
+        params: HashMap<String, String>,
+        policy: &ToolPolicy,
+        workspace: Option<WorkspaceScope>,
+        session_id: Option<uuid::Uuid>,
+    ) -> Result<ConnectorResult> {
+        let context = ExecutionContext {
+            tool_policy: policy.clone(),
+            workspace,
+            session_id: session_id.map_or_else(|| self.context.session_id.clone(), |id| id.to_string()),
+            ..self.context.clone()
+        };
+        self.execute_in_context(connector_id, params, &context).await
+    }
+
+    /// Undo a side effect recorded by an earlier connector call
+    pub async fn compensate(&self, compensation: &Compensation, policy: &ToolPolicy) -> Result<()> {
+        let context = ExecutionContext {
+            tool_policy: policy.clone(),
+            ..self.context.clone()
+        };
+        self.connector_registry.compensate(compensation, &context).await
+    }
+
+    async fn execute_in_context(
+        &mut self,
+        connector_id: &str,
+        params: HashMap<String, String>,
+        context: &ExecutionContext,
+    ) -> Result<ConnectorResult> {
+        let started = std::time::Instant::now();
         let result = self.connector_registry
-            .execute_connector(connector_id, params.clone(), &self.context)
-            .await?;
+            .execute_connector(connector_id, params.clone(), context)
+            .await;
+        let outcome = match &result {
+            Ok(result) if result.success => "success",
+            Ok(result) if result.metadata.contains_key("timed_out") => "timeout",
+            Ok(_) => "failure",
+            Err(_) => "error",
+        };
+        status::record_connector_execution(connector_id, Some(started.elapsed()), outcome);
+        let result = result?;
 
         // Record execution
         self.execution_history.push(ExecutionRecord {
@@ -191,7 +357,7 @@ impl HybridOrchestrator {
             params.insert("action".to_string(), "list_processes".to_string());
 
             let sys_result = self.execute_connector("system_admin", params).await?;
-            results.add_result("system_analysis", sys_result);
+            results.add_result("system_analysis".to_string(), sys_result);
         }
 
         // Step 2: Code optimization (self-improvement)
@@ -202,7 +368,7 @@ impl HybridOrchestrator {
             params.insert("pattern".to_string(), "**/*.rs".to_string());
 
             let code_result = self.execute_connector("self_improve", params).await?;
-            results.add_result("code_analysis", code_result);
+            results.add_result("code_analysis".to_string(), code_result);
         }
 
         Ok(results)
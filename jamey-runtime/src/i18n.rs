@@ -0,0 +1,183 @@
+//! Localized text for the CLI and TUI
+//!
+//! Each frontend ships its strings as [Fluent](https://projectfluent.org)
+//! resources, one per language, and looks them up through a [`Localizer`]
+//! built for the language the user asked for. `JAMEY_LANGUAGE` wins, then
+//! the frontend's own `language` setting, then the system locale (`LC_ALL`,
+//! `LC_MESSAGES`, `LANG`). A language without resources falls back to one
+//! that shares its primary subtag (`es-MX` to `es`), then to English, and
+//! a message a translation lacks is taken from English.
+//!
+//! ```ignore
+//! let text = jamey_runtime::tr!(localizer, "chat-resumed", "count" => previous.len());
+//! ```
+
+use fluent_bundle::concurrent::FluentBundle;
+use fluent_bundle::FluentResource;
+use unic_langid::LanguageIdentifier;
+
+pub use fluent_bundle::{FluentArgs, FluentValue};
+
+/// Overrides every other way of picking the language
+pub const LANGUAGE_ENV: &str = "JAMEY_LANGUAGE";
+
+/// What every frontend has resources for
+pub const FALLBACK_LANGUAGE: &str = "en";
+
+/// Look up `id` in a [`Localizer`], with `"name" => value` arguments
+#[macro_export]
+macro_rules! tr {
+    ($localizer:expr, $id:expr $(,)?) => {
+        $localizer.format($id, None)
+    };
+    ($localizer:expr, $id:expr, $($key:literal => $value:expr),+ $(,)?) => {{
+        let mut args = $crate::i18n::FluentArgs::new();
+        $(args.set($key, $value);)+
+        $localizer.format($id, Some(&args))
+    }};
+}
+
+/// Messages in one language, with English behind them
+pub struct Localizer {
+    language: LanguageIdentifier,
+    bundles: Vec<FluentBundle<FluentResource>>,
+}
+
+impl std::fmt::Debug for Localizer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Localizer").field("language", &self.language).finish()
+    }
+}
+
+impl Localizer {
+    /// Localizer for the best match to `requested` among `resources`,
+    /// given as `(language tag, Fluent source)` pairs
+    pub fn new(requested: &str, resources: &[(&str, &str)]) -> Self {
+        let available: Vec<LanguageIdentifier> = resources
+            .iter()
+            .filter_map(|(tag, _)| tag.parse().ok())
+            .collect();
+        let fallback: LanguageIdentifier = FALLBACK_LANGUAGE.parse().unwrap_or_default();
+        let language = negotiate(requested, &available).unwrap_or_else(|| fallback.clone());
+
+        let mut chain = vec![language.clone()];
+        if language != fallback {
+            chain.push(fallback);
+        }
+        let bundles = chain
+            .into_iter()
+            .filter_map(|wanted| {
+                let (_, source) = resources
+                    .iter()
+                    .find(|(tag, _)| tag.parse::<LanguageIdentifier>().ok().as_ref() == Some(&wanted))?;
+                Some(bundle(wanted, source))
+            })
+            .collect();
+        Self { language, bundles }
+    }
+
+    /// The language messages come from, unless they're missing from it
+    pub fn language(&self) -> &LanguageIdentifier {
+        &self.language
+    }
+
+    /// `id` formatted with `args`; the id itself when no language has it
+    pub fn format(&self, id: &str, args: Option<&FluentArgs>) -> String {
+        for bundle in &self.bundles {
+            let Some(pattern) = bundle.get_message(id).and_then(|message| message.value()) else {
+                continue;
+            };
+            let mut errors = Vec::new();
+            let text = bundle.format_pattern(pattern, args, &mut errors);
+            if !errors.is_empty() {
+                tracing::debug!("Formatting message {} failed: {:?}", id, errors);
+            }
+            return text.into_owned();
+        }
+        tracing::warn!("No message {} in any language", id);
+        id.to_string()
+    }
+}
+
+/// The language to show, from [`LANGUAGE_ENV`], then `configured`, then the
+/// system locale
+pub fn requested_language(configured: Option<&str>) -> String {
+    let from_env = |name: &str| std::env::var(name).ok().filter(|value| !value.trim().is_empty());
+    from_env(LANGUAGE_ENV)
+        .or_else(|| configured.map(str::to_string))
+        .or_else(|| ["LC_ALL", "LC_MESSAGES", "LANG"].into_iter().find_map(from_env))
+        .unwrap_or_else(|| FALLBACK_LANGUAGE.to_string())
+}
+
+/// Message ids defined in a Fluent `source`, for checking a translation
+/// has everything English does
+pub fn message_ids(source: &str) -> Vec<String> {
+    let resource = match FluentResource::try_new(source.to_string()) {
+        Ok(resource) => resource,
+        Err((resource, _)) => resource,
+    };
+    resource
+        .entries()
+        .filter_map(|entry| match entry {
+            fluent_syntax::ast::Entry::Message(message) => Some(message.id.name.to_string()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// `requested` matched against `available`: the same tag, or failing that
+/// the same language
+fn negotiate(requested: &str, available: &[LanguageIdentifier]) -> Option<LanguageIdentifier> {
+    // POSIX locales look like `es_MX.UTF-8@euro`
+    let tag = requested
+        .split(['.', '@'])
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .replace('_', "-");
+    let requested: LanguageIdentifier = tag.parse().ok()?;
+    available
+        .iter()
+        .find(|language| **language == requested)
+        .or_else(|| available.iter().find(|language| language.language == requested.language))
+        .cloned()
+}
+
+fn bundle(language: LanguageIdentifier, source: &str) -> FluentBundle<FluentResource> {
+    let resource = FluentResource::try_new(source.to_string()).unwrap_or_else(|(resource, errors)| {
+        tracing::warn!("Errors in the {} messages: {:?}", language, errors);
+        resource
+    });
+    let mut bundle = FluentBundle::new_concurrent(vec![language]);
+    // Isolation marks show up as stray characters in most terminals
+    bundle.set_use_isolating(false);
+    if let Err(errors) = bundle.add_resource(resource) {
+        tracing::warn!("Duplicate messages: {:?}", errors);
+    }
+    bundle
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EN: &str = "greeting = Hello, { $name }!\nfarewell = Goodbye\n";
+    const ES: &str = "greeting = ¡Hola, { $name }!\n";
+
+    #[test]
+    fn test_negotiates_and_falls_back() {
+        let resources = [("en", EN), ("es", ES)];
+
+        let spanish = Localizer::new("es_MX.UTF-8", &resources);
+        assert_eq!(spanish.language().to_string(), "es");
+        assert_eq!(crate::tr!(spanish, "greeting", "name" => "Ana"), "¡Hola, Ana!");
+        // Missing from the translation, so English
+        assert_eq!(crate::tr!(spanish, "farewell"), "Goodbye");
+        assert_eq!(crate::tr!(spanish, "no-such-message"), "no-such-message");
+
+        for requested in ["fr-FR", "C", ""] {
+            assert_eq!(Localizer::new(requested, &resources).language().to_string(), "en");
+        }
+        assert_eq!(message_ids(EN), vec!["greeting".to_string(), "farewell".to_string()]);
+    }
+}
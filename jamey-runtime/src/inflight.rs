@@ -0,0 +1,238 @@
+//! In-flight work
+//!
+//! Chat turns, the model calls and tool executions inside them, and
+//! scheduled jobs register here while they run, so a call that hangs shows
+//! up, with how long it has been going, well before its timeout. Each entry
+//! can be cancelled by id; cancelling a turn cancels the calls running under
+//! it, and cancelling a model call fails the turn the way any model error
+//! would.
+//!
+//! The web server lists the entries at `GET /tasks`, alongside the request
+//! queue's depths and the health of the supervised background tasks, and
+//! cancels one with `DELETE /tasks/{id}`. `jamey tasks` calls both.
+
+use crate::queue::QueueStats;
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use jamey_core::supervisor::SupervisedTask;
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::sync::Arc;
+use thiserror::Error;
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
+
+/// What a piece of in-flight work is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WorkKind {
+    /// A chat turn, from waiting for a queue slot to its final reply
+    Turn,
+    /// One streamed request to the model provider
+    ModelCall,
+    /// A connector call made by a turn
+    Tool,
+    /// A scheduled task
+    Job,
+}
+
+impl std::fmt::Display for WorkKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            WorkKind::Turn => "turn",
+            WorkKind::ModelCall => "model_call",
+            WorkKind::Tool => "tool",
+            WorkKind::Job => "job",
+        })
+    }
+}
+
+/// One piece of in-flight work as listed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkInfo {
+    pub id: Uuid,
+    pub kind: WorkKind,
+    /// The model, connector or task name
+    pub label: String,
+    pub session: Option<Uuid>,
+    /// The turn a model call or tool execution belongs to
+    pub parent: Option<Uuid>,
+    pub started_at: DateTime<Utc>,
+    /// Seconds since it started, as of the listing
+    #[serde(default)]
+    pub age_secs: f64,
+}
+
+/// What `GET /tasks` returns
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InFlightReport {
+    /// Oldest first
+    pub tasks: Vec<WorkInfo>,
+    pub queue: QueueStats,
+    /// Background loops, and jobs that panicked, by name
+    #[serde(default)]
+    pub supervised: Vec<SupervisedTask>,
+}
+
+/// Returned by work that was cancelled through [`InFlight::cancel`]
+#[derive(Debug, Clone, Copy, Error)]
+#[error("{kind} {id} was cancelled")]
+pub struct Cancelled {
+    pub id: Uuid,
+    pub kind: WorkKind,
+}
+
+/// The runtime's in-flight work; clones share it
+#[derive(Clone, Default)]
+pub struct InFlight {
+    entries: Arc<DashMap<Uuid, Entry>>,
+}
+
+struct Entry {
+    info: WorkInfo,
+    cancel: CancellationToken,
+}
+
+impl InFlight {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register work that runs until the returned guard is dropped
+    pub fn begin(&self, kind: WorkKind, label: &str, session: Option<Uuid>) -> WorkGuard {
+        self.register(kind, label, session, None, CancellationToken::new())
+    }
+
+    fn register(
+        &self,
+        kind: WorkKind,
+        label: &str,
+        session: Option<Uuid>,
+        parent: Option<Uuid>,
+        cancel: CancellationToken,
+    ) -> WorkGuard {
+        let id = Uuid::new_v4();
+        let info = WorkInfo {
+            id,
+            kind,
+            label: label.to_string(),
+            session,
+            parent,
+            started_at: Utc::now(),
+            age_secs: 0.0,
+        };
+        self.entries.insert(id, Entry { info, cancel: cancel.clone() });
+        WorkGuard { id, kind, session, cancel, in_flight: self.clone() }
+    }
+
+    /// Everything running, oldest first
+    pub fn list(&self) -> Vec<WorkInfo> {
+        let now = Utc::now();
+        let mut tasks: Vec<WorkInfo> = self
+            .entries
+            .iter()
+            .map(|entry| {
+                let mut info = entry.info.clone();
+                info.age_secs = (now - info.started_at).num_milliseconds().max(0) as f64 / 1000.0;
+                info
+            })
+            .collect();
+        tasks.sort_by_key(|info| info.started_at);
+        tasks
+    }
+
+    /// Cancel `id` and anything running under it; false when nothing with
+    /// that id is running
+    pub fn cancel(&self, id: Uuid) -> bool {
+        match self.entries.get(&id) {
+            Some(entry) => {
+                tracing::info!("Cancelling {} {} ({})", entry.info.kind, id, entry.info.label);
+                entry.cancel.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn report(&self, queue: QueueStats, supervised: Vec<SupervisedTask>) -> InFlightReport {
+        InFlightReport { tasks: self.list(), queue, supervised }
+    }
+}
+
+/// A registration in [`InFlight`], removed when dropped
+pub struct WorkGuard {
+    id: Uuid,
+    kind: WorkKind,
+    session: Option<Uuid>,
+    cancel: CancellationToken,
+    in_flight: InFlight,
+}
+
+impl WorkGuard {
+    pub fn id(&self) -> Uuid {
+        self.id
+    }
+
+    /// Register work running under this one, cancelled along with it
+    pub fn child(&self, kind: WorkKind, label: &str) -> WorkGuard {
+        self.in_flight
+            .register(kind, label, self.session, Some(self.id), self.cancel.child_token())
+    }
+
+    /// Run `work` until it finishes or this registration is cancelled
+    pub async fn run<F: Future>(&self, work: F) -> Result<F::Output, Cancelled> {
+        tokio::select! {
+            biased;
+            _ = self.cancel.cancelled() => Err(Cancelled { id: self.id, kind: self.kind }),
+            output = work => Ok(output),
+        }
+    }
+
+    /// The token cancelled along with this registration, for work that
+    /// can't be wrapped in [`run`](Self::run)
+    pub fn cancellation(&self) -> CancellationToken {
+        self.cancel.clone()
+    }
+}
+
+impl Drop for WorkGuard {
+    fn drop(&mut self) {
+        self.in_flight.entries.remove(&self.id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_cancel_reaches_children() {
+        let in_flight = InFlight::new();
+        let session = Some(Uuid::new_v4());
+        let turn = in_flight.begin(WorkKind::Turn, "chat", session);
+        let call = turn.child(WorkKind::ModelCall, "openai/gpt-4o");
+
+        let listed = in_flight.list();
+        assert_eq!(listed.len(), 2);
+        let listed_call = listed.iter().find(|info| info.id == call.id()).unwrap();
+        assert_eq!(listed_call.parent, Some(turn.id()));
+        assert_eq!(listed_call.session, session);
+
+        assert!(in_flight.cancel(turn.id()));
+        let hung = call.run(tokio::time::sleep(Duration::from_secs(60))).await;
+        assert!(matches!(hung, Err(Cancelled { kind: WorkKind::ModelCall, .. })));
+
+        drop(call);
+        drop(turn);
+        assert!(in_flight.list().is_empty());
+        assert!(!in_flight.cancel(Uuid::new_v4()));
+    }
+
+    #[tokio::test]
+    async fn test_run_passes_output_through() {
+        let in_flight = InFlight::new();
+        let job = in_flight.begin(WorkKind::Job, "nightly", None);
+        assert_eq!(job.run(async { 7 }).await.unwrap(), 7);
+    }
+}
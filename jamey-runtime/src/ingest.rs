@@ -0,0 +1,525 @@
+//! Document ingestion
+//!
+//! Loads files, directories, glob matches or web pages, splits them into
+//! overlapping chunks and stores each chunk as an embedded memory tagged with
+//! its source, namespace and user-supplied tags. With the offline queue on,
+//! chunks that can't be embedded because the provider is unreachable are
+//! queued rather than failed.
+
+use crate::offline::DeferredRequest;
+use crate::state::RuntimeState;
+use chrono::Utc;
+use jamey_core::memory::{Memory, MemoryStore, MemoryType};
+use jamey_providers::openrouter::{is_unreachable, LlmProvider, DEFAULT_EMBEDDING_MODEL};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use thiserror::Error;
+use uuid::Uuid;
+
+/// Upper bound on chunk size, in characters; keeps each chunk well inside the
+/// embedding model's 8k token window
+pub const MAX_CHUNK_SIZE: usize = 8000;
+
+/// Files larger than this are skipped rather than chunked
+const MAX_SOURCE_BYTES: u64 = 10 * 1024 * 1024;
+
+/// text-embedding-ada-002 list price
+const EMBEDDING_USD_PER_MILLION_TOKENS: f64 = 0.10;
+
+/// Rough English average used for cost estimates
+const CHARS_PER_TOKEN: usize = 4;
+
+const URL_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Error)]
+pub enum IngestError {
+    #[error("Invalid ingest options: {0}")]
+    InvalidOptions(String),
+    #[error("Invalid glob pattern: {0}")]
+    Pattern(#[from] glob::PatternError),
+    #[error("Nothing to ingest at: {0}")]
+    NoSources(String),
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// How ingested documents are chunked and labelled
+#[derive(Debug, Clone)]
+pub struct IngestOptions {
+    /// Target chunk length in characters
+    pub chunk_size: usize,
+    /// Characters repeated between consecutive chunks
+    pub chunk_overlap: usize,
+    pub memory_type: MemoryType,
+    pub namespace: Option<String>,
+    pub tags: Vec<String>,
+    /// Chunk and estimate cost without embedding or storing anything
+    pub dry_run: bool,
+}
+
+impl Default for IngestOptions {
+    fn default() -> Self {
+        Self {
+            chunk_size: 1500,
+            chunk_overlap: 200,
+            memory_type: MemoryType::Knowledge,
+            namespace: None,
+            tags: Vec::new(),
+            dry_run: false,
+        }
+    }
+}
+
+impl IngestOptions {
+    fn validate(&self) -> Result<(), IngestError> {
+        if self.chunk_size < 100 || self.chunk_size > MAX_CHUNK_SIZE {
+            return Err(IngestError::InvalidOptions(format!(
+                "chunk size must be between 100 and {} characters (got {})",
+                MAX_CHUNK_SIZE, self.chunk_size
+            )));
+        }
+        if self.chunk_overlap >= self.chunk_size / 2 {
+            return Err(IngestError::InvalidOptions(format!(
+                "chunk overlap must be less than half the chunk size (got {})",
+                self.chunk_overlap
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Outcome for one file or URL
+#[derive(Debug, Clone, Serialize)]
+pub struct SourceReport {
+    pub source: String,
+    pub chunks: usize,
+    pub memories_created: usize,
+    /// IDs of the stored chunks, so a later re-ingest can replace them
+    pub memory_ids: Vec<Uuid>,
+    /// Chunks put in the offline queue because the provider was unreachable
+    pub queued: usize,
+    pub errors: Vec<String>,
+}
+
+/// Summary of an ingestion run
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct IngestReport {
+    pub sources: Vec<SourceReport>,
+    /// Sources that could not be read, with the reason
+    pub skipped: Vec<(String, String)>,
+    pub chunks: usize,
+    pub memories_created: usize,
+    /// Chunks left to embed once the provider is back
+    pub queued: usize,
+    pub estimated_tokens: usize,
+    pub estimated_cost_usd: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Source {
+    File(PathBuf),
+    Url(String),
+}
+
+impl Source {
+    fn label(&self) -> String {
+        match self {
+            Source::File(path) => path.display().to_string(),
+            Source::Url(url) => url.clone(),
+        }
+    }
+}
+
+impl RuntimeState {
+    /// Ingest a file, directory, glob pattern or http(s) URL into memory
+    pub async fn ingest(
+        &self,
+        target: &str,
+        options: &IngestOptions,
+    ) -> Result<IngestReport, IngestError> {
+        options.validate()?;
+        let sources = resolve_sources(target)?;
+        if sources.is_empty() {
+            return Err(IngestError::NoSources(target.to_string()));
+        }
+
+        let client = reqwest::Client::builder()
+            .timeout(URL_TIMEOUT)
+            .build()
+            .map_err(|e| IngestError::InvalidOptions(e.to_string()))?;
+        let mut report = IngestReport::default();
+        // Once the provider is found unreachable, the remaining chunks go
+        // straight to the offline queue
+        let mut offline = false;
+
+        for source in sources {
+            let label = source.label();
+            if let Source::File(path) = &source {
+                if let Err(e) = self.path_policy.check_read(path) {
+                    report.skipped.push((label, e.to_string()));
+                    continue;
+                }
+            }
+            let text = match load_source(&client, &source).await {
+                Ok(text) if !text.trim().is_empty() => text,
+                Ok(_) => {
+                    report.skipped.push((label, "no text content".to_string()));
+                    continue;
+                }
+                Err(e) => {
+                    report.skipped.push((label, e.to_string()));
+                    continue;
+                }
+            };
+
+            let chunks = chunk_text(&text, options.chunk_size, options.chunk_overlap);
+            let mut source_report = SourceReport {
+                source: label.clone(),
+                chunks: chunks.len(),
+                memories_created: 0,
+                memory_ids: Vec::new(),
+                queued: 0,
+                errors: Vec::new(),
+            };
+            report.chunks += chunks.len();
+            report.estimated_tokens += chunks.iter().map(|c| estimate_tokens(c)).sum::<usize>();
+
+            if !options.dry_run {
+                for (index, chunk) in chunks.iter().enumerate() {
+                    let error = if offline {
+                        None
+                    } else {
+                        match self.store_chunk(chunk, &label, index, chunks.len(), options).await {
+                            Ok(id) => {
+                                source_report.memories_created += 1;
+                                source_report.memory_ids.push(id);
+                                continue;
+                            }
+                            Err(e) if is_unreachable(&e) => {
+                                offline = true;
+                                Some(e)
+                            }
+                            Err(e) => {
+                                source_report.errors.push(format!("chunk {}: {}", index, e));
+                                continue;
+                            }
+                        }
+                    };
+                    let request = DeferredRequest::IngestChunk {
+                        chunk: chunk.clone(),
+                        source: label.clone(),
+                        index,
+                        total: chunks.len(),
+                        memory_type: options.memory_type.clone(),
+                        namespace: options.namespace.clone(),
+                        tags: options.tags.clone(),
+                    };
+                    match self.defer(request).await {
+                        Some(_) => source_report.queued += 1,
+                        None => {
+                            // Without the queue an outage fails every chunk alike
+                            let reason = error.map_or_else(|| "provider unreachable".to_string(), |e| e.to_string());
+                            source_report.errors.push(format!("chunk {}: {}", index, reason));
+                        }
+                    }
+                }
+            }
+
+            report.memories_created += source_report.memories_created;
+            report.queued += source_report.queued;
+            report.sources.push(source_report);
+        }
+
+        report.estimated_cost_usd =
+            report.estimated_tokens as f64 / 1_000_000.0 * EMBEDDING_USD_PER_MILLION_TOKENS;
+        tracing::info!(
+            "Ingested {}: {} chunks, {} memories created, {} queued",
+            target,
+            report.chunks,
+            report.memories_created,
+            report.queued
+        );
+        Ok(report)
+    }
+
+    pub(crate) async fn store_chunk(
+        &self,
+        chunk: &str,
+        source: &str,
+        index: usize,
+        total: usize,
+        options: &IngestOptions,
+    ) -> anyhow::Result<Uuid> {
+        let embedding = {
+            // Bulk work; chat turns go first
+            let _permit = self.request_queue.background().await;
+            self.llm_provider.get_embedding(chunk).await?
+        };
+        let now = Utc::now();
+        let memory = Memory {
+            id: Uuid::new_v4(),
+            memory_type: options.memory_type.clone(),
+            content: chunk.to_string(),
+            embedding,
+            metadata: serde_json::json!({
+                "source": source,
+                "chunk_index": index,
+                "chunk_count": total,
+                "namespace": options.namespace,
+                "tags": options.tags,
+                "ingested_at": now.to_rfc3339(),
+                "embedding_model": DEFAULT_EMBEDDING_MODEL,
+            }),
+            created_at: now,
+            last_accessed: now,
+        };
+        self.memory_store.store(memory).await
+    }
+}
+
+/// Expand an ingest target into the files or URL it names
+fn resolve_sources(target: &str) -> Result<Vec<Source>, IngestError> {
+    if target.starts_with("http://") || target.starts_with("https://") {
+        return Ok(vec![Source::Url(target.to_string())]);
+    }
+
+    let mut files = Vec::new();
+    if target.contains(['*', '?', '[']) {
+        for entry in glob::glob(target)? {
+            match entry {
+                Ok(path) if path.is_file() => files.push(path),
+                Ok(_) => {}
+                Err(e) => tracing::warn!("Skipping unreadable glob match: {}", e),
+            }
+        }
+    } else {
+        let path = Path::new(target);
+        if path.is_dir() {
+            collect_files(path, &mut files)?;
+        } else if path.is_file() {
+            files.push(path.to_path_buf());
+        } else {
+            return Err(IngestError::NoSources(target.to_string()));
+        }
+    }
+
+    files.sort();
+    Ok(files.into_iter().map(Source::File).collect())
+}
+
+/// Recursively list files under `dir`, skipping hidden entries
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        if entry.file_name().to_string_lossy().starts_with('.') {
+            continue;
+        }
+        let path = entry.path();
+        if path.is_dir() {
+            collect_files(&path, files)?;
+        } else if path.is_file() {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+async fn load_source(client: &reqwest::Client, source: &Source) -> anyhow::Result<String> {
+    match source {
+        Source::File(path) => {
+            let size = tokio::fs::metadata(path).await?.len();
+            if size > MAX_SOURCE_BYTES {
+                anyhow::bail!("file is {} bytes (limit {})", size, MAX_SOURCE_BYTES);
+            }
+            let bytes = tokio::fs::read(path).await?;
+            if bytes.iter().take(8192).any(|&b| b == 0) {
+                anyhow::bail!("binary file");
+            }
+            Ok(String::from_utf8_lossy(&bytes).into_owned())
+        }
+        Source::Url(url) => {
+            let response = client.get(url).send().await?.error_for_status()?;
+            let is_html = response
+                .headers()
+                .get(reqwest::header::CONTENT_TYPE)
+                .and_then(|v| v.to_str().ok())
+                .is_some_and(|v| v.contains("html"));
+            if response.content_length().is_some_and(|len| len > MAX_SOURCE_BYTES) {
+                anyhow::bail!("response exceeds {} bytes", MAX_SOURCE_BYTES);
+            }
+            let body = response.text().await?;
+            Ok(if is_html { html_to_text(&body) } else { body })
+        }
+    }
+}
+
+pub(crate) fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(CHARS_PER_TOKEN)
+}
+
+/// Split `text` into chunks of at most `chunk_size` characters, each
+/// repeating the last `overlap` characters of the previous one. Breaks prefer
+/// paragraph, then line, then word boundaries near the end of the window.
+pub fn chunk_text(text: &str, chunk_size: usize, overlap: usize) -> Vec<String> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut chunks = Vec::new();
+    let mut start = 0;
+
+    while start < chars.len() {
+        let mut end = (start + chunk_size).min(chars.len());
+        if end < chars.len() {
+            if let Some(offset) = break_point(&chars[start..end]) {
+                end = start + offset;
+            }
+        }
+
+        let chunk: String = chars[start..end].iter().collect();
+        let chunk = chunk.trim();
+        if !chunk.is_empty() {
+            chunks.push(chunk.to_string());
+        }
+
+        if end == chars.len() {
+            break;
+        }
+        start = end.saturating_sub(overlap).max(start + 1);
+    }
+    chunks
+}
+
+/// Offset just past the best boundary in the last fifth of `window`
+fn break_point(window: &[char]) -> Option<usize> {
+    let floor = (window.len() * 4 / 5).max(1);
+    let search = || (floor..window.len()).rev();
+
+    search()
+        .find(|&i| window[i] == '\n' && window[i - 1] == '\n')
+        .or_else(|| search().find(|&i| window[i] == '\n'))
+        .or_else(|| search().find(|&i| window[i].is_whitespace()))
+        .map(|i| i + 1)
+}
+
+/// Crude HTML to text: drops scripts, styles and tags, keeps block breaks
+pub(crate) fn html_to_text(html: &str) -> String {
+    const BLOCK_TAGS: &[&str] = &[
+        "p", "div", "br", "li", "tr", "h1", "h2", "h3", "h4", "h5", "h6", "section", "article",
+        "pre", "blockquote",
+    ];
+
+    let mut text = String::with_capacity(html.len() / 2);
+    let mut rest = html;
+
+    while let Some(open) = rest.find('<') {
+        text.push_str(&rest[..open]);
+        rest = &rest[open..];
+
+        let Some(close) = rest.find('>') else {
+            rest = "";
+            break;
+        };
+        let tag = rest[1..close].trim_start_matches('/').to_ascii_lowercase();
+        let name = tag.split(|c: char| c.is_whitespace() || c == '/').next().unwrap_or("");
+
+        if name == "script" || name == "style" {
+            let end_tag = format!("</{}", name);
+            rest = match rest.to_ascii_lowercase().find(&end_tag) {
+                Some(i) => rest[i..].find('>').map_or("", |j| &rest[i + j + 1..]),
+                None => "",
+            };
+            continue;
+        }
+
+        text.push(if BLOCK_TAGS.contains(&name) { '\n' } else { ' ' });
+        rest = &rest[close + 1..];
+    }
+    text.push_str(rest);
+
+    let text = text
+        .replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&");
+
+    let mut out = String::new();
+    let mut blank = true;
+    for line in text.lines() {
+        let line = line.split_whitespace().collect::<Vec<_>>().join(" ");
+        if line.is_empty() {
+            if !blank {
+                out.push('\n');
+                blank = true;
+            }
+        } else {
+            out.push_str(&line);
+            out.push('\n');
+            blank = false;
+        }
+    }
+    out.trim_end().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_chunk_text_overlaps_and_breaks_on_whitespace() {
+        let text = "alpha beta gamma delta ".repeat(40);
+        let chunks = chunk_text(&text, 100, 20);
+
+        assert!(chunks.len() > 1);
+        assert!(chunks.iter().all(|c| c.chars().count() <= 100));
+        // Every chunk ends on a whole word
+        assert!(chunks.iter().all(|c| c.ends_with("alpha")
+            || c.ends_with("beta")
+            || c.ends_with("gamma")
+            || c.ends_with("delta")));
+        assert!(chunk_text("   ", 100, 20).is_empty());
+        assert_eq!(chunk_text("short", 100, 20), vec!["short"]);
+    }
+
+    #[test]
+    fn test_html_to_text() {
+        let html = "<html><head><style>p{}</style><script>var x = '<p>';</script></head>\
+                    <body><h1>Title</h1><p>Fish &amp; <b>chips</b></p></body></html>";
+        assert_eq!(html_to_text(html), "Title\n\nFish & chips");
+    }
+
+    #[test]
+    fn test_resolve_sources() {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(dir.path().join("a.md"), "a").unwrap();
+        std::fs::write(dir.path().join("b.txt"), "b").unwrap();
+        std::fs::create_dir(dir.path().join("sub")).unwrap();
+        std::fs::write(dir.path().join("sub/c.md"), "c").unwrap();
+        std::fs::write(dir.path().join(".hidden"), "h").unwrap();
+
+        let all = resolve_sources(dir.path().to_str().unwrap()).unwrap();
+        assert_eq!(all.len(), 3);
+
+        let pattern = format!("{}/**/*.md", dir.path().display());
+        assert_eq!(resolve_sources(&pattern).unwrap().len(), 2);
+
+        assert_eq!(
+            resolve_sources("https://example.com/doc").unwrap(),
+            vec![Source::Url("https://example.com/doc".to_string())]
+        );
+        assert!(matches!(
+            resolve_sources("/definitely/not/here"),
+            Err(IngestError::NoSources(_))
+        ));
+    }
+
+    #[test]
+    fn test_options_validation() {
+        assert!(IngestOptions::default().validate().is_ok());
+        let options = IngestOptions { chunk_size: 50, ..Default::default() };
+        assert!(options.validate().is_err());
+        let options = IngestOptions { chunk_overlap: 1000, ..Default::default() };
+        assert!(options.validate().is_err());
+    }
+}
@@ -3,22 +3,71 @@
 //! This crate provides the runtime environment that coordinates all components,
 //! including memory management, LLM providers, and system tools.
 
+pub mod a2a;
+pub mod analytics;
+pub mod approvals;
+pub mod archive;
+pub mod attachments;
+pub mod audit;
+pub mod audit_sinks;
+pub mod briefing;
+pub mod certificates;
+pub mod channel;
+pub mod chat;
+pub mod cluster;
 pub mod config;
+pub mod degradation;
+pub mod eval;
+pub mod events;
+pub mod feedback;
+pub mod forget;
+pub mod generation;
+pub mod guardrails;
+pub mod i18n;
 pub mod state;
 pub mod scheduler;
 pub mod hybrid_orchestrator;
+pub mod inflight;
+pub mod ingest;
+pub mod locale;
+pub mod logging;
+pub mod maintenance;
+pub mod project;
+pub mod prompt_trace;
+pub mod queue;
+pub mod recall;
+pub mod research;
+pub mod rollback;
+pub mod routing;
 pub mod service;
+pub mod session_store;
+pub mod status;
+pub mod summarize;
+pub mod sync;
+pub mod telegram;
+pub mod matrix;
+pub mod notifications;
+pub mod offline;
+pub mod openapi;
+pub mod persona;
 pub mod tls;
+pub mod usage;
+pub mod voice;
+pub mod web;
+pub mod webhooks;
+pub mod workspace;
+
+pub use config::RuntimeConfig;
 
 use anyhow::Result;
-use config::{ConfigError, RuntimeConfig};
-use metrics_exporter_prometheus::PrometheusBuilder;
+use config::ConfigError;
+use metrics_exporter_prometheus::{PrometheusBuilder, PrometheusHandle};
 use state::{RuntimeError, RuntimeState};
+use status::StatusProbe;
 use std::sync::Arc;
 use thiserror::Error;
 use tokio::sync::broadcast;
-use tracing::{debug, error, info, Level};
-use tracing_subscriber::FmtSubscriber;
+use tracing::{debug, error, info};
 
 #[derive(Debug, Error)]
 pub enum Error {
@@ -34,6 +83,8 @@ pub enum Error {
 pub struct Runtime {
     state: Arc<RuntimeState>,
     shutdown_rx: broadcast::Receiver<()>,
+    metrics: PrometheusHandle,
+    started: std::time::Instant,
 }
 
 impl Runtime {
@@ -43,27 +94,40 @@ impl Runtime {
         config.validate()?;
 
         // Initialize logging
-        FmtSubscriber::builder()
-            .with_max_level(match config.api.log_level.as_str() {
-                "debug" => Level::DEBUG,
-                "info" => Level::INFO,
-                "warn" => Level::WARN,
-                "error" => Level::ERROR,
-                _ => Level::INFO,
-            })
-            .with_target(false)
-            .with_thread_ids(true)
-            .with_file(true)
-            .with_line_number(true)
-            .pretty()
-            .try_init()
-            .map_err(|e| Error::Init(format!("Failed to initialize logging: {}", e)))?;
-
-        // Initialize metrics
+        let redactor = jamey_core::redaction::Redactor::from_config(&config.security.redaction)
+            .map_err(|e| Error::Init(e.to_string()))?;
+        match logging::init(&config.api.log_level, &config.logging, Arc::new(redactor)) {
+            Ok(()) => {}
+            // Embedders such as the CLI install their own subscriber first
+            Err(logging::LoggingError::AlreadyInstalled(e)) => debug!("Keeping existing logging setup: {}", e),
+            Err(e) => return Err(Error::Init(e.to_string())),
+        }
+
+        // Initialize metrics, served for `jamey status` when a port is configured.
+        // The handle also lets embedders read them in-process.
         let builder = PrometheusBuilder::new();
-        builder
-            .install()
-            .map_err(|e| Error::Init(format!("Failed to initialize metrics: {}", e)))?;
+        let metrics = match config.api.metrics_port {
+            Some(port) => {
+                let host: std::net::IpAddr = config.api.host.parse()
+                    .unwrap_or(std::net::IpAddr::from([127, 0, 0, 1]));
+                let (recorder, exporter) = builder
+                    .with_http_listener((host, port))
+                    .build()
+                    .map_err(|e| Error::Init(format!("Failed to initialize metrics: {}", e)))?;
+                let handle = recorder.handle();
+                metrics::set_boxed_recorder(Box::new(recorder))
+                    .map_err(|e| Error::Init(format!("Failed to initialize metrics: {}", e)))?;
+                tokio::spawn(async move {
+                    if let Err(e) = exporter.await {
+                        error!("Metrics listener stopped: {}", e);
+                    }
+                });
+                handle
+            }
+            None => builder
+                .install_recorder()
+                .map_err(|e| Error::Init(format!("Failed to initialize metrics: {}", e)))?,
+        };
 
         // Initialize runtime state
         let state = RuntimeState::new(config).await?;
@@ -76,6 +140,8 @@ impl Runtime {
         Ok(Self {
             state: state_arc,
             shutdown_rx,
+            metrics,
+            started: std::time::Instant::now(),
         })
     }
 
@@ -83,28 +149,66 @@ impl Runtime {
     pub async fn run(&mut self) -> Result<(), Error> {
         info!("Starting Digital Twin Jamey runtime...");
 
-        // Start session cleanup task
-        let session_manager = self.state.session_manager.clone();
-        tracing::debug!("Cloned session_manager Arc for cleanup task, strong count: {}", Arc::strong_count(&session_manager));
-        let mut cleanup_interval = tokio::time::interval(std::time::Duration::from_secs(300)); // 5 minutes
-        let mut shutdown_rx = self.shutdown_rx.resubscribe();
-
-        tokio::spawn(async move {
-            loop {
-                tokio::select! {
-                    _ = cleanup_interval.tick() => {
-                        session_manager.cleanup_expired_sessions(
-                            std::time::Duration::from_secs(3600) // 1 hour
-                        );
-                    }
-                    _ = shutdown_rx.recv() => {
-                        debug!("Shutting down session cleanup task");
-                        break;
+        // Start session cleanup task; idle sessions are archived, not dropped
+        let state = Arc::clone(&self.state);
+        tracing::debug!("Cloned state Arc for cleanup task, strong count: {}", Arc::strong_count(&state));
+        let shutdown = self.shutdown_rx.resubscribe();
+
+        self.state.supervisor.spawn("session_cleanup", move || {
+            let state = Arc::clone(&state);
+            let mut cleanup_interval = tokio::time::interval(std::time::Duration::from_secs(300)); // 5 minutes
+            let mut shutdown_rx = shutdown.resubscribe();
+            async move {
+                loop {
+                    tokio::select! {
+                        _ = cleanup_interval.tick() => {
+                            let archived = state.archive_idle_sessions(
+                                std::time::Duration::from_secs(3600) // 1 hour
+                            ).await;
+                            if archived > 0 {
+                                info!("Archived {} idle sessions", archived);
+                            }
+                        }
+                        _ = shutdown_rx.recv() => {
+                            debug!("Shutting down session cleanup task");
+                            break;
+                        }
                     }
                 }
             }
         });
 
+        status::spawn_status_reporter(self.status_probe(), self.shutdown_rx.resubscribe());
+
+        let web_ui = cfg!(feature = "web-ui") && self.state.config.api.web_ui;
+        if web_ui || self.state.config.api.hooks {
+            let port = self.state.config.api.http_port;
+            let listener = tokio::net::TcpListener::bind((self.state.config.api.host.as_str(), port))
+                .await
+                .map_err(|e| Error::Init(format!("Failed to bind HTTP port {}: {}", port, e)))?;
+            if web_ui {
+                info!("Serving the web chat on port {}", port);
+            }
+            if self.state.config.api.hooks {
+                info!("Accepting inbound webhooks on port {}", port);
+            }
+            web::spawn_server(Arc::clone(&self.state), listener, self.shutdown_rx.resubscribe());
+        }
+
+        self.start_scheduler().await;
+        offline::spawn_offline_drain(Arc::clone(&self.state), self.shutdown_rx.resubscribe());
+
+        if let Some(bot) = &self.state.telegram {
+            telegram::spawn_bot(Arc::clone(&self.state), Arc::clone(bot), self.shutdown_rx.resubscribe());
+        }
+
+        if self.state.config.tools.matrix_homeserver.is_some() {
+            #[cfg(feature = "matrix")]
+            matrix::spawn_bot(Arc::clone(&self.state), self.shutdown_rx.resubscribe());
+            #[cfg(not(feature = "matrix"))]
+            tracing::warn!("tools.matrix_homeserver is set, but this build has no Matrix support (build with --features matrix)");
+        }
+
         // Wait for shutdown signal
         let _ = self.shutdown_rx.recv().await;
         info!("Shutting down runtime...");
@@ -112,11 +216,38 @@ impl Runtime {
         Ok(())
     }
 
+    /// Run scheduled tasks, including the briefing when `briefing.schedule`
+    /// is set, in this process; [`run`](Self::run) does this itself
+    pub async fn start_scheduler(&self) {
+        if let Some(task) = self.state.config.briefing.scheduled_task(self.state.time.timezone) {
+            self.state.scheduler.lock().await.add_task(task);
+        }
+        if self.state.config.tools.scheduler_enabled || self.state.config.briefing.schedule.is_some() {
+            scheduler::spawn_scheduler(Arc::clone(&self.state), self.shutdown_rx.resubscribe());
+        }
+    }
+
+    /// Raise desktop notifications for this runtime's events until shutdown,
+    /// as `notifications` allows
+    pub fn notify_desktop(&self) {
+        notifications::spawn_desktop_notifications(
+            &self.state.supervisor,
+            self.state.config.notifications.clone(),
+            &self.state.events,
+            self.shutdown_rx.resubscribe(),
+        );
+    }
+
     /// Get a reference to the runtime state
     pub fn state(&self) -> &RuntimeState {
         &self.state
     }
 
+    /// Reads this runtime's status without going through the metrics port
+    pub fn status_probe(&self) -> StatusProbe {
+        StatusProbe::new(Arc::clone(&self.state), self.metrics.clone(), self.started)
+    }
+
     /// Request runtime shutdown
     pub async fn shutdown(&self) {
         self.state.shutdown().await;
@@ -125,14 +256,34 @@ impl Runtime {
 
 /// Re-export common types
 pub mod prelude {
+    pub use super::analytics::{Insights, ModelLatency, Percentiles, ToolStats, Topic};
+    pub use super::approvals::{AllowRule, ApprovalQueue, ApprovalRequest, ApprovalStatus};
+    pub use super::archive::ArchiveError;
+    pub use super::attachments::AttachmentStore;
+    pub use super::audit::{AuditEntry, AuditError, AuditLayer, AuditLog, VerifyReport};
+    pub use super::audit_sinks::{AuditSink, AuditSinksConfig, EventLogConfig, SyslogConfig, SyslogTransport};
+    pub use super::certificates::{CertResolver, CertificateConfig, CertificateManager, CertificateMode};
+    pub use super::chat::{ChatTurn, TurnEvent};
+    pub use super::cluster::{Cluster, ClusterConfig, ClusterError, ClusterLock, LeaderElection};
     pub use super::config::{
-        ApiConfig, ConfigError, LlmConfig, MemoryConfig, RuntimeConfig, SecurityConfig, ToolConfig,
+        ApiConfig, ConfigError, ConfigOrigin, ConfigOrigins, LlmConfig, MemoryConfig, RuntimeConfig,
+        RuntimeConfigBuilder, SecurityConfig, ToolConfig,
     };
+    pub use super::degradation::{Degradation, DegradationConfig, DegradationLevel};
+    pub use super::eval::{EvalReport, EvalRunner, EvalSuite};
+    pub use super::forget::{ForgetReport, ForgetTarget};
     pub use super::state::{RuntimeError, RuntimeState, Session, SessionManager, ToolRegistry};
+    pub use super::ingest::{IngestOptions, IngestReport};
+    pub use super::logging::{LogFileConfig, LogFormat, LogRotation, LoggingConfig};
+    pub use super::project::{ProjectState, ProjectStore, WatchOptions, WatchUpdate};
+    pub use super::queue::{Priority, QueueConfig, QueueFull, RequestQueue};
     pub use super::service::{JameyService, ServiceStatus};
+    pub use super::session_store::{ArchiveInfo, SessionRecord, SessionStore, SessionSummary};
+    pub use super::status::{BudgetTracker, RuntimeStatus, StatusProbe};
     pub use super::tls::{
         FrameOptions, SecurityHeaders, TlsConfig, TlsError, TlsVersion,
     };
+    pub use super::usage::{ForgottenUsage, GroupBy, ToolRecord, UsageLog, UsageRecord, UsageSummary};
     pub use super::{Error, Runtime};
 }
 
@@ -149,12 +300,13 @@ mod tests {
         config.tools.download_dir = temp_dir.path().join("downloads");
         config.tools.system_root = temp_dir.path().to_path_buf();
         // Test secrets - do not use in production
-        use tracing_honeycomb::SensitiveValue;
+        use crate::config::SensitiveValue;
         config.memory.postgres_password = SensitiveValue("test_password".to_string());
         config.llm.openrouter_api_key = SensitiveValue("test_key".to_string());
         
         // Create runtime
-        let mut runtime = Runtime::new(config).await?;
+        let runtime = Runtime::new(config).await?;
+        let state = Arc::clone(&runtime.state);
         
         // Start runtime in background
         let runtime_handle = tokio::spawn({
@@ -168,11 +320,11 @@ mod tests {
         tokio::time::sleep(std::time::Duration::from_millis(100)).await;
 
         // Test session creation
-        let session_id = runtime.state().session_manager.create_session();
-        assert!(runtime.state().session_manager.get_session(session_id).is_some());
+        let session_id = state.session_manager.create_session();
+        assert!(state.session_manager.get_session(session_id).is_some());
 
         // Shutdown runtime
-        runtime.shutdown().await;
+        state.shutdown().await;
         runtime_handle.await?;
         Ok(())
     }
@@ -191,7 +343,7 @@ mod tests {
         config.tools.download_dir = temp_dir.path().join("downloads");
         config.tools.system_root = temp_dir.path().to_path_buf();
         // Test secrets - do not use in production
-        use tracing_honeycomb::SensitiveValue;
+        use crate::config::SensitiveValue;
         config.memory.postgres_password = SensitiveValue("test_password".to_string());
         config.llm.openrouter_api_key = SensitiveValue("test_key".to_string());
         assert!(Runtime::new(config).await.is_ok());
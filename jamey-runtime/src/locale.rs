@@ -0,0 +1,212 @@
+//! Locale and timezone
+//!
+//! A [`TimeContext`] is the timezone and locale times are read and written
+//! in. The runtime's comes from `[locale]`, falling back to the system
+//! timezone; a session can bring its own through the `timezone` and
+//! `locale` keys of its `CreateSessionRequest` metadata. Turns tell the
+//! model the local date and time, so "tomorrow at 9" means 9 where the user
+//! is, and the scheduler and the briefing's calendar work out days and cron
+//! slots in the runtime's timezone rather than the host's.
+//!
+//! ```toml
+//! [locale]
+//! timezone = "Europe/Berlin"
+//! locale = "de-DE"
+//! ```
+
+use chrono::{DateTime, LocalResult, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum LocaleError {
+    #[error("Unknown timezone '{0}'; use an IANA name such as Europe/Berlin")]
+    Timezone(String),
+    #[error("Invalid locale '{0}'; use a language tag such as en-US")]
+    Locale(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct LocaleConfig {
+    /// IANA timezone name; the system's when unset
+    pub timezone: Option<String>,
+    /// BCP 47 language tag
+    pub locale: String,
+}
+
+impl Default for LocaleConfig {
+    fn default() -> Self {
+        Self {
+            timezone: None,
+            locale: "en-US".to_string(),
+        }
+    }
+}
+
+impl LocaleConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        self.time_context().map(|_| ()).map_err(|e| format!("locale: {}", e))
+    }
+
+    /// The runtime's default [`TimeContext`]
+    pub fn time_context(&self) -> Result<TimeContext, LocaleError> {
+        let timezone = match &self.timezone {
+            Some(name) => parse_timezone(name)?,
+            None => system_timezone(),
+        };
+        Ok(TimeContext {
+            timezone,
+            locale: parse_locale(&self.locale)?,
+        })
+    }
+}
+
+/// The timezone and locale a session's times are in
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimeContext {
+    pub timezone: Tz,
+    pub locale: String,
+}
+
+impl Default for TimeContext {
+    fn default() -> Self {
+        Self {
+            timezone: Tz::UTC,
+            locale: "en-US".to_string(),
+        }
+    }
+}
+
+impl TimeContext {
+    /// This context with whichever of `timezone` and `locale` are given
+    /// swapped in
+    pub fn with(&self, timezone: Option<&str>, locale: Option<&str>) -> Result<Self, LocaleError> {
+        Ok(Self {
+            timezone: timezone.map(parse_timezone).transpose()?.unwrap_or(self.timezone),
+            locale: match locale {
+                Some(locale) => parse_locale(locale)?,
+                None => self.locale.clone(),
+            },
+        })
+    }
+
+    pub fn now(&self) -> DateTime<Tz> {
+        self.local(Utc::now())
+    }
+
+    pub fn local(&self, at: DateTime<Utc>) -> DateTime<Tz> {
+        at.with_timezone(&self.timezone)
+    }
+
+    pub fn today(&self) -> NaiveDate {
+        self.now().date_naive()
+    }
+
+    /// `local` wall-clock time as an instant. A time skipped by a DST change
+    /// moves forward an hour; one that happens twice is the first.
+    pub fn resolve(&self, local: NaiveDateTime) -> DateTime<Utc> {
+        let resolved = match self.timezone.from_local_datetime(&local) {
+            LocalResult::None => self
+                .timezone
+                .from_local_datetime(&(local + chrono::Duration::hours(1)))
+                .earliest(),
+            result => result.earliest(),
+        };
+        resolved
+            .map(|at| at.with_timezone(&Utc))
+            .unwrap_or_else(|| Utc.from_utc_datetime(&local))
+    }
+
+    /// Start and end of `day` here, which aren't always 24 hours apart
+    pub fn day_bounds(&self, day: NaiveDate) -> (DateTime<Utc>, DateTime<Utc>) {
+        let start = self.resolve(day.and_hms_opt(0, 0, 0).unwrap_or_default());
+        let end = day
+            .succ_opt()
+            .map(|next| self.resolve(next.and_hms_opt(0, 0, 0).unwrap_or_default()))
+            .unwrap_or(start + chrono::Duration::days(1));
+        (start, end)
+    }
+
+    /// System prompt line giving the model the local date and time
+    pub fn prompt(&self, now: DateTime<Utc>) -> String {
+        let local = self.local(now);
+        format!(
+            "The current date and time for the user is {} ({}, UTC{}). Read relative times such \
+as \"tomorrow at 9\" in this timezone. The user's locale is {}; write dates, times and numbers \
+the way it does.",
+            local.format("%A %-d %B %Y, %H:%M"),
+            self.timezone.name(),
+            local.format("%:z"),
+            self.locale
+        )
+    }
+}
+
+pub fn parse_timezone(name: &str) -> Result<Tz, LocaleError> {
+    name.trim()
+        .parse::<Tz>()
+        .map_err(|_| LocaleError::Timezone(name.to_string()))
+}
+
+/// `locale` as a language tag such as `en-US`, accepting `en_US` too
+pub fn parse_locale(locale: &str) -> Result<String, LocaleError> {
+    let tag = locale.trim().replace('_', "-");
+    let mut subtags = tag.split('-');
+    let language_ok = subtags
+        .next()
+        .is_some_and(|l| (2..=3).contains(&l.len()) && l.chars().all(|c| c.is_ascii_alphabetic()));
+    let rest_ok = subtags.all(|s| (1..=8).contains(&s.len()) && s.chars().all(|c| c.is_ascii_alphanumeric()));
+    if !language_ok || !rest_ok {
+        return Err(LocaleError::Locale(locale.to_string()));
+    }
+    Ok(tag)
+}
+
+/// The host's timezone, or UTC when it can't be read
+fn system_timezone() -> Tz {
+    iana_time_zone::get_timezone()
+        .ok()
+        .and_then(|name| name.parse().ok())
+        .unwrap_or(Tz::UTC)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_across_dst() {
+        let berlin = TimeContext::default().with(Some("Europe/Berlin"), Some("de_DE")).unwrap();
+        assert_eq!(berlin.locale, "de-DE");
+
+        let day = NaiveDate::from_ymd_opt(2026, 3, 29).unwrap();
+        // 02:30 doesn't exist that night; it becomes 03:30 CEST
+        let skipped = berlin.resolve(day.and_hms_opt(2, 30, 0).unwrap());
+        assert_eq!(skipped, Utc.with_ymd_and_hms(2026, 3, 29, 1, 30, 0).unwrap());
+        let (start, end) = berlin.day_bounds(day);
+        assert_eq!(start, Utc.with_ymd_and_hms(2026, 3, 28, 23, 0, 0).unwrap());
+        assert_eq!(end - start, chrono::Duration::hours(23));
+
+        let nine = berlin.resolve(NaiveDate::from_ymd_opt(2026, 7, 1).unwrap().and_hms_opt(9, 0, 0).unwrap());
+        assert_eq!(nine, Utc.with_ymd_and_hms(2026, 7, 1, 7, 0, 0).unwrap());
+        assert!(berlin.prompt(nine).contains("Wednesday 1 July 2026, 09:00 (Europe/Berlin, UTC+02:00)"));
+    }
+
+    #[test]
+    fn test_rejects_unknown_names() {
+        let ctx = TimeContext::default();
+        assert!(matches!(ctx.with(Some("Mars/Olympus"), None), Err(LocaleError::Timezone(_))));
+        assert!(matches!(ctx.with(None, Some("english please")), Err(LocaleError::Locale(_))));
+        assert!(parse_locale("zh-Hant-TW").is_ok());
+
+        let config = LocaleConfig {
+            timezone: Some("America/New_York".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(config.time_context().unwrap().timezone, chrono_tz::America::New_York);
+        assert!(LocaleConfig { locale: "x".to_string(), ..Default::default() }.validate().is_err());
+    }
+}
@@ -0,0 +1,275 @@
+//! Log output for the runtime
+//!
+//! Console output is pretty, compact or JSON; an optional rolling file gets
+//! its own format (JSON by default, for log shippers). Levels come from
+//! `api.log_level` plus per-module `EnvFilter` directives in
+//! `logging.filters`, e.g. `jamey_core::memory=debug,hyper=warn`. `RUST_LOG`
+//! replaces the configured filters when set. Unless `logging.redact` is
+//! turned off, every line passes through
+//! [`redact_log_line`](jamey_core::secure_logging::redact_log_line) and the
+//! configured [`Redactor`] before it is written. Events with the `audit`
+//! target also go to the signed [audit log](crate::audit).
+
+use crate::audit::AuditLayer;
+use jamey_core::redaction::Redactor;
+use jamey_core::secure_logging::RedactingMakeWriter;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::Arc;
+use thiserror::Error;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::filter::{Directive, EnvFilter, LevelFilter};
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::prelude::*;
+use tracing_subscriber::{Layer, Registry};
+
+#[derive(Debug, Error)]
+pub enum LoggingError {
+    #[error("Invalid log level \"{0}\" (trace, debug, info, warn or error)")]
+    InvalidLevel(String),
+    #[error("Invalid log filter \"{directive}\": {reason}")]
+    InvalidFilter { directive: String, reason: String },
+    #[error("Failed to open log directory {0}: {1}")]
+    File(PathBuf, String),
+    #[error("A logger is already installed: {0}")]
+    AlreadyInstalled(String),
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    #[default]
+    Pretty,
+    /// One line per event
+    Compact,
+    /// One JSON object per line, event fields flattened to the top level
+    Json,
+}
+
+impl std::str::FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "pretty" => Ok(Self::Pretty),
+            "compact" => Ok(Self::Compact),
+            "json" => Ok(Self::Json),
+            other => Err(format!("Unknown log format \"{}\" (pretty, compact or json)", other)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum LogRotation {
+    Minutely,
+    Hourly,
+    #[default]
+    Daily,
+    Never,
+}
+
+impl std::str::FromStr for LogRotation {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "minutely" => Ok(Self::Minutely),
+            "hourly" => Ok(Self::Hourly),
+            "daily" => Ok(Self::Daily),
+            "never" => Ok(Self::Never),
+            other => Err(format!("Unknown log rotation \"{}\" (minutely, hourly, daily or never)", other)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct LoggingConfig {
+    /// Console format (`LOG_FORMAT`)
+    #[serde(default)]
+    pub format: LogFormat,
+    /// Per-module `EnvFilter` directives layered over `api.log_level` (`LOG_FILTERS`, comma separated)
+    #[serde(default)]
+    pub filters: Vec<String>,
+    /// Also write to rolling files (`LOG_DIR`)
+    #[serde(default)]
+    pub file: Option<LogFileConfig>,
+    /// Scrub secrets and PII from every line (`LOG_REDACT`)
+    #[serde(default = "default_redact")]
+    pub redact: bool,
+}
+
+fn default_redact() -> bool {
+    true
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            format: LogFormat::default(),
+            filters: Vec::new(),
+            file: None,
+            redact: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct LogFileConfig {
+    pub dir: PathBuf,
+    /// Files are named `<prefix>.<date>.log`
+    #[serde(default = "default_file_prefix")]
+    pub prefix: String,
+    /// `LOG_ROTATION`
+    #[serde(default)]
+    pub rotation: LogRotation,
+    /// Oldest files beyond this many are deleted; unset keeps them all
+    #[serde(default)]
+    pub max_files: Option<usize>,
+    #[serde(default = "default_file_format")]
+    pub format: LogFormat,
+}
+
+fn default_file_prefix() -> String {
+    "jamey".to_string()
+}
+
+fn default_file_format() -> LogFormat {
+    LogFormat::Json
+}
+
+impl LogFileConfig {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            prefix: default_file_prefix(),
+            rotation: LogRotation::default(),
+            max_files: None,
+            format: default_file_format(),
+        }
+    }
+}
+
+/// The filter built from `level` and the configured directives
+pub fn env_filter(level: &str, filters: &[String]) -> Result<EnvFilter, LoggingError> {
+    let level: LevelFilter = level
+        .parse()
+        .map_err(|_| LoggingError::InvalidLevel(level.to_string()))?;
+    let mut filter = EnvFilter::builder().with_default_directive(level.into()).parse_lossy("");
+    for directive in filters.iter().map(|d| d.trim()).filter(|d| !d.is_empty()) {
+        filter = filter.add_directive(parse_directive(directive)?);
+    }
+    Ok(filter)
+}
+
+fn parse_directive(directive: &str) -> Result<Directive, LoggingError> {
+    directive.parse().map_err(|e: tracing_subscriber::filter::ParseError| LoggingError::InvalidFilter {
+        directive: directive.to_string(),
+        reason: e.to_string(),
+    })
+}
+
+/// Install the global subscriber. Fails with
+/// [`AlreadyInstalled`](LoggingError::AlreadyInstalled) when the embedder set
+/// one up first.
+pub fn init(level: &str, config: &LoggingConfig, redactor: Arc<Redactor>) -> Result<(), LoggingError> {
+    let filter = match std::env::var("RUST_LOG") {
+        Ok(directives) if !directives.trim().is_empty() => EnvFilter::new(directives),
+        _ => env_filter(level, &config.filters)?,
+    };
+
+    let redactor = config.redact.then_some(redactor);
+    let mut layers = vec![fmt_layer(config.format, std::io::stdout, redactor.clone(), true)];
+    if let Some(file) = &config.file {
+        let mut builder = RollingFileAppender::builder()
+            .rotation(match file.rotation {
+                LogRotation::Minutely => Rotation::MINUTELY,
+                LogRotation::Hourly => Rotation::HOURLY,
+                LogRotation::Daily => Rotation::DAILY,
+                LogRotation::Never => Rotation::NEVER,
+            })
+            .filename_prefix(&file.prefix)
+            .filename_suffix("log");
+        if let Some(max_files) = file.max_files {
+            builder = builder.max_log_files(max_files);
+        }
+        let appender = builder
+            .build(&file.dir)
+            .map_err(|e| LoggingError::File(file.dir.clone(), e.to_string()))?;
+        layers.push(fmt_layer(file.format, appender, redactor, false));
+    }
+
+    // The level filter applies to the output layers only, so audit events
+    // reach the audit log however quiet the console is
+    tracing_subscriber::registry()
+        .with(layers.with_filter(filter))
+        .with(AuditLayer)
+        .try_init()
+        .map_err(|e| LoggingError::AlreadyInstalled(e.to_string()))
+}
+
+/// `ansi` only matters for the pretty format; the others are meant for machines
+/// Lines are redacted when there is a `redactor`
+fn fmt_layer<W>(
+    format: LogFormat,
+    writer: W,
+    redactor: Option<Arc<Redactor>>,
+    ansi: bool,
+) -> Box<dyn Layer<Registry> + Send + Sync>
+where
+    W: for<'a> MakeWriter<'a> + Send + Sync + 'static,
+{
+    match redactor {
+        Some(redactor) => formatted(format, RedactingMakeWriter::new(writer).with_redactor(redactor), ansi),
+        None => formatted(format, writer, ansi),
+    }
+}
+
+fn formatted<W>(format: LogFormat, writer: W, ansi: bool) -> Box<dyn Layer<Registry> + Send + Sync>
+where
+    W: for<'a> MakeWriter<'a> + Send + Sync + 'static,
+{
+    let layer = tracing_subscriber::fmt::layer()
+        .with_writer(writer)
+        .with_thread_ids(true)
+        .with_line_number(true);
+    match format {
+        LogFormat::Pretty => layer.pretty().with_ansi(ansi).with_file(true).with_target(false).boxed(),
+        LogFormat::Compact => layer.compact().with_ansi(false).boxed(),
+        LogFormat::Json => layer
+            .json()
+            .with_ansi(false)
+            .flatten_event(true)
+            .with_current_span(true)
+            .boxed(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_env_filter() {
+        let filter = env_filter("warn", &["jamey_core::memory=debug".to_string(), " ".to_string()]).unwrap();
+        let text = filter.to_string();
+        assert!(text.contains("jamey_core::memory=debug"));
+        assert!(text.contains("warn"));
+
+        assert!(matches!(env_filter("loud", &[]), Err(LoggingError::InvalidLevel(_))));
+        assert!(matches!(
+            env_filter("info", &["jamey_core=[".to_string()]),
+            Err(LoggingError::InvalidFilter { .. })
+        ));
+    }
+
+    #[test]
+    fn test_config_defaults() {
+        let config: LoggingConfig = serde_json::from_str(r#"{"file": {"dir": "/var/log/jamey"}}"#).unwrap();
+        assert_eq!(config.format, LogFormat::Pretty);
+        assert!(config.redact);
+        let file = config.file.unwrap();
+        assert_eq!((file.prefix.as_str(), file.rotation, file.format), ("jamey", LogRotation::Daily, LogFormat::Json));
+    }
+}
@@ -0,0 +1,138 @@
+//! Model-backed pieces of the core memory maintenance jobs
+//!
+//! `jamey_core::maintenance` does the database work; this module supplies the
+//! embedding and summarization it delegates, using the runtime's provider,
+//! and puts merges off while the provider is unreachable.
+
+use crate::offline::DeferredRequest;
+use crate::routing::RouteTask;
+use crate::state::RuntimeState;
+use async_trait::async_trait;
+use jamey_core::maintenance::{ConsolidateReport, Consolidator, Embedder, JobProgress, ProgressFn};
+use jamey_providers::openrouter::{
+    self, is_unreachable, ChatRequest, LlmProvider, OpenRouterProvider, DEFAULT_EMBEDDING_MODEL,
+};
+use std::sync::Arc;
+
+const CONSOLIDATE_PROMPT: &str = "You merge overlapping notes from a personal knowledge base. \
+Combine the notes below into a single self-contained note that keeps every distinct fact, name, \
+number and instruction, drops repetition, and adds nothing new. Reply with the merged note only.";
+
+/// Longest merged note requested from the model, in tokens
+const CONSOLIDATE_MAX_TOKENS: u32 = 2000;
+
+/// Embeds through the runtime's provider with a chosen model
+pub struct ProviderEmbedder {
+    llm: Arc<OpenRouterProvider>,
+    model: String,
+}
+
+impl ProviderEmbedder {
+    pub fn new(llm: Arc<OpenRouterProvider>, model: impl Into<String>) -> Self {
+        Self { llm, model: model.into() }
+    }
+}
+
+#[async_trait]
+impl Embedder for ProviderEmbedder {
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    async fn embed(&self, text: &str) -> anyhow::Result<Vec<f32>> {
+        self.llm.get_embedding_with_model(text, &self.model).await
+    }
+}
+
+/// Asks the chat model to merge a cluster of memories
+pub struct LlmConsolidator {
+    llm: Arc<OpenRouterProvider>,
+    model: String,
+}
+
+#[async_trait]
+impl Consolidator for LlmConsolidator {
+    async fn merge(&self, contents: &[String]) -> anyhow::Result<String> {
+        let notes = contents
+            .iter()
+            .enumerate()
+            .map(|(i, c)| format!("Note {}:\n{}", i + 1, c.trim()))
+            .collect::<Vec<_>>()
+            .join("\n\n");
+        let request = ChatRequest {
+            model: self.model.clone(),
+            messages: vec![
+                openrouter::Message {
+                    role: "system".to_string(),
+                    content: CONSOLIDATE_PROMPT.to_string(),
+                },
+                openrouter::Message {
+                    role: "user".to_string(),
+                    content: notes,
+                },
+            ],
+            tools: None,
+            tool_choice: None,
+            temperature: Some(0.2),
+            max_tokens: Some(CONSOLIDATE_MAX_TOKENS),
+        };
+
+        let response = self.llm.chat(request).await?;
+        let merged = response
+            .choices
+            .first()
+            .map(|c| c.message.content.trim().to_string())
+            .unwrap_or_default();
+        if merged.is_empty() {
+            anyhow::bail!("model returned an empty merge");
+        }
+        Ok(merged)
+    }
+}
+
+impl RuntimeState {
+    /// Embedder for `model`, or the default embedding model
+    pub fn embedder(&self, model: Option<&str>) -> ProviderEmbedder {
+        ProviderEmbedder::new(Arc::clone(&self.llm_provider), model.unwrap_or(DEFAULT_EMBEDDING_MODEL))
+    }
+
+    /// Consolidator using the model routed consolidation goes to
+    pub fn consolidator(&self) -> LlmConsolidator {
+        LlmConsolidator {
+            llm: Arc::clone(&self.llm_provider),
+            model: self.model_for(RouteTask::Consolidate, ""),
+        }
+    }
+
+    /// Merge the clusters of a consolidation dry run like
+    /// [`PostgresMemoryStore::merge_clusters`](jamey_core::PostgresMemoryStore::merge_clusters), except that with the offline
+    /// queue on, clusters the provider couldn't be reached for are queued
+    /// instead of failed. Returns how many were queued.
+    pub async fn merge_clusters(&self, report: &mut ConsolidateReport, progress: ProgressFn<'_>) -> usize {
+        let (consolidator, embedder) = (self.consolidator(), self.embedder(None));
+        report.dry_run = false;
+        let mut queued = 0;
+        let total = report.clusters.len() as u64;
+        for (index, cluster) in report.clusters.iter().enumerate() {
+            progress(JobProgress { stage: "merging", done: index as u64, total });
+            match self.memory_store.merge_cluster(cluster, &consolidator, &embedder).await {
+                Ok((id, removed)) => {
+                    report.created.push(id);
+                    report.removed += removed;
+                }
+                Err(e) => {
+                    if is_unreachable(&e)
+                        && self.defer(DeferredRequest::Consolidate { cluster: cluster.clone() }).await.is_some()
+                    {
+                        queued += 1;
+                        continue;
+                    }
+                    tracing::warn!("Failed to consolidate cluster around {}: {}", cluster.keep, e);
+                    report.errors.push(format!("{}: {}", cluster.keep, e));
+                }
+            }
+        }
+        progress(JobProgress { stage: "merging", done: total, total });
+        queued
+    }
+}
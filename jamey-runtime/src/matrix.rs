@@ -0,0 +1,169 @@
+//! Matrix bot
+//!
+//! With `tools.matrix_homeserver` set and the `matrix` feature built in, the
+//! runtime joins the rooms on `tools.matrix_rooms` and answers messages that
+//! mention it, plus everything in direct chats. Each room is one saved
+//! session shared by everyone in it, and messages are labelled with their
+//! sender, so replies draw on the whole room conversation. The login and
+//! encryption keys are kept in `matrix_dir`, which lets encrypted rooms keep
+//! working across restarts.
+
+use std::path::PathBuf;
+use uuid::Uuid;
+
+#[cfg(feature = "matrix")]
+pub(crate) use bot::spawn_bot;
+
+/// High half of every Matrix session ID ("jamey_mx"); the low half comes from the room ID
+const SESSION_ID_PREFIX: u64 = 0x6a61_6d65_795f_6d78;
+
+pub(crate) fn default_matrix_dir() -> PathBuf {
+    std::env::var("JAMEY_MATRIX_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from("./matrix"))
+}
+
+/// The session a room's conversation is saved in
+pub fn room_session_id(room_id: &str) -> Uuid {
+    use sha2::{Digest, Sha256};
+    let digest = Sha256::digest(room_id.as_bytes());
+    let mut low = [0u8; 8];
+    low.copy_from_slice(&digest[..8]);
+    Uuid::from_u64_pair(SESSION_ID_PREFIX, u64::from_be_bytes(low))
+}
+
+#[cfg(feature = "matrix")]
+mod bot {
+    use super::room_session_id;
+    use crate::channel;
+    use crate::state::RuntimeState;
+    use dashmap::DashMap;
+    use jamey_protocol::Message;
+    use jamey_tools::connectors::matrix::{IncomingMessage, MatrixConfig};
+    use jamey_tools::connectors::MatrixConnector;
+    use std::sync::Arc;
+    use tokio::sync::{broadcast, mpsc, Mutex};
+
+    const HELP: &str = "Hi, I'm Jamey. Mention me in this room (or message me directly) and I'll reply. \
+Send !reset to start a new conversation for this room.";
+
+    /// Log in, then answer room messages until shutdown
+    pub(crate) fn spawn_bot(state: Arc<RuntimeState>, shutdown: broadcast::Receiver<()>) {
+        let supervisor = state.supervisor.clone();
+        supervisor.spawn("matrix", move || {
+            let state = Arc::clone(&state);
+            let mut shutdown = shutdown.resubscribe();
+            async move {
+                let tools = &state.config.tools;
+                let config = MatrixConfig {
+                    homeserver: tools.matrix_homeserver.clone().unwrap_or_default(),
+                    user: tools.matrix_user.clone().unwrap_or_default(),
+                    password: tools.matrix_password.clone(),
+                    rooms: tools.matrix_rooms.clone(),
+                    store_dir: state.config.matrix_dir.clone(),
+                    store_passphrase: tools.matrix_store_passphrase.clone(),
+                };
+                let connector = match MatrixConnector::connect(config).await {
+                    Ok(connector) => connector,
+                    Err(e) => {
+                        tracing::error!("Failed to start the Matrix bot: {:#}", e);
+                        return;
+                    }
+                };
+                let registered = state
+                    .hybrid_orchestrator
+                    .lock()
+                    .await
+                    .get_registry()
+                    .register(Box::new(connector.clone()))
+                    .await;
+                if let Err(e) = registered {
+                    tracing::warn!("Failed to register Matrix connector: {}", e);
+                }
+
+                let (tx, mut rx) = mpsc::unbounded_channel();
+                let listener = connector.clone();
+                let mut sync = tokio::spawn(async move { listener.listen(tx).await });
+                // One lock per room, so a room's messages are answered in order
+                let rooms: Arc<DashMap<String, Arc<Mutex<()>>>> = Arc::new(DashMap::new());
+                tracing::info!("Listening for Matrix messages");
+                loop {
+                    tokio::select! {
+                        _ = shutdown.recv() => break,
+                        stopped = &mut sync => {
+                            match stopped {
+                                Ok(Err(e)) => tracing::error!("Matrix sync stopped: {:#}", e),
+                                Ok(Ok(())) => tracing::warn!("Matrix sync stopped"),
+                                // Let the supervisor log in again
+                                Err(e) if e.is_panic() => std::panic::resume_unwind(e.into_panic()),
+                                Err(e) => tracing::error!("Matrix sync task failed: {}", e),
+                            }
+                            return;
+                        }
+                        Some(message) = rx.recv() => {
+                            let lock = Arc::clone(rooms.entry(message.room_id.to_string()).or_default().value());
+                            tokio::spawn(handle_message(Arc::clone(&state), connector.clone(), lock, message));
+                        }
+                    }
+                }
+                sync.abort();
+                tracing::debug!("Matrix bot stopped");
+            }
+        });
+    }
+
+    async fn handle_message(
+        state: Arc<RuntimeState>,
+        connector: MatrixConnector,
+        lock: Arc<Mutex<()>>,
+        message: IncomingMessage,
+    ) {
+        let _turn = lock.lock().await;
+        let _ = connector.set_typing(&message.room_id, true).await;
+        let reply = match respond(&state, &connector, &message).await {
+            Ok(reply) => reply,
+            Err(e) => format!("Sorry, something went wrong: {}", e),
+        };
+        let _ = connector.set_typing(&message.room_id, false).await;
+        if let Err(e) = connector.send_message(&message.room_id, &reply).await {
+            tracing::warn!("Failed to reply in Matrix room {}: {}", message.room_id, e);
+        }
+    }
+
+    async fn respond(
+        state: &RuntimeState,
+        connector: &MatrixConnector,
+        message: &IncomingMessage,
+    ) -> anyhow::Result<String> {
+        let session_id = room_session_id(message.room_id.as_str());
+        match message.body.as_str() {
+            "" | "!help" => return Ok(HELP.to_string()),
+            "!reset" => {
+                channel::reset(state, session_id).await?;
+                return Ok("Started a new conversation.".to_string());
+            }
+            _ => {}
+        }
+
+        // Several people share a room, so the model needs to know who is asking
+        let question = Message::user(format!("{}: {}", message.sender, message.body));
+        let title = format!("Matrix {}", message.room_id);
+        channel::run_turn(state, session_id, title, question, |notice| async move {
+            let _ = connector.send_message(&message.room_id, &notice).await;
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_room_session_ids_are_stable() {
+        let id = room_session_id("!abc:example.org");
+        assert_eq!(id, room_session_id("!abc:example.org"));
+        assert_ne!(id, room_session_id("!abd:example.org"));
+        assert_eq!(id.as_u64_pair().0, SESSION_ID_PREFIX);
+    }
+}
@@ -0,0 +1,244 @@
+//! Desktop notifications
+//!
+//! While `jamey chat` or the TUI is open, [`spawn_desktop_notifications`]
+//! listens on the event bus and raises a desktop notification for approvals
+//! waiting on the user, finished scheduled jobs, budget warnings and new
+//! briefings. notify-rust shows them through the freedesktop notification
+//! service on Linux, as toasts on Windows and in Notification Center on
+//! macOS. Each kind can be switched off with `notifications.events`.
+
+use crate::events::{self, RuntimeEvent};
+use jamey_core::supervisor::Supervisor;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tokio::sync::broadcast;
+
+/// Name the notifications are shown under
+const APP_NAME: &str = "Jamey";
+
+/// Characters of an output or excerpt kept in the notification body
+const MAX_BODY_CHARS: usize = 160;
+
+#[derive(Debug, Error)]
+pub enum NotificationError {
+    #[error("Invalid notification config: {0}")]
+    Config(String),
+}
+
+/// Kinds of event that can raise a notification
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum NotifyEvent {
+    /// A tool call is waiting for approval
+    Approvals,
+    /// A scheduled task completed or failed
+    Jobs,
+    /// Today's spend reached the warning share or the whole daily budget
+    Budget,
+    /// A scheduled briefing was delivered
+    Briefings,
+}
+
+impl NotifyEvent {
+    const ALL: [NotifyEvent; 4] = [Self::Approvals, Self::Jobs, Self::Budget, Self::Briefings];
+
+    /// The kind a runtime event belongs to, if it can raise a notification
+    fn of(kind: &str) -> Option<Self> {
+        match kind {
+            events::APPROVAL_REQUESTED => Some(Self::Approvals),
+            events::JOB_COMPLETED | events::JOB_FAILED => Some(Self::Jobs),
+            events::BUDGET_WARNING => Some(Self::Budget),
+            events::BRIEFING_DELIVERED => Some(Self::Briefings),
+            _ => None,
+        }
+    }
+}
+
+impl std::str::FromStr for NotifyEvent {
+    type Err = NotificationError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "approvals" => Ok(Self::Approvals),
+            "jobs" => Ok(Self::Jobs),
+            "budget" => Ok(Self::Budget),
+            "briefings" => Ok(Self::Briefings),
+            other => Err(NotificationError::Config(format!(
+                "unknown event '{}' (expected approvals, jobs, budget or briefings)",
+                other
+            ))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct NotificationConfig {
+    /// Show desktop notifications at all (`JAMEY_NOTIFICATIONS`)
+    pub enabled: bool,
+    /// Kinds that raise one (`JAMEY_NOTIFY_EVENTS`); all of them by default
+    pub events: Vec<NotifyEvent>,
+}
+
+impl Default for NotificationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            events: NotifyEvent::ALL.to_vec(),
+        }
+    }
+}
+
+/// What a notification says
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Notice {
+    pub summary: String,
+    pub body: String,
+}
+
+impl NotificationConfig {
+    /// The notice `event` raises under this config, if any
+    pub fn notice(&self, event: &RuntimeEvent) -> Option<Notice> {
+        let kind = NotifyEvent::of(&event.kind)?;
+        if !self.enabled || !self.events.contains(&kind) {
+            return None;
+        }
+        let data = &event.data;
+        let text = |key: &str| data[key].as_str().unwrap_or_default().to_string();
+        let (summary, body) = match event.kind.as_str() {
+            events::APPROVAL_REQUESTED => {
+                let action = text("action");
+                let call = if action.is_empty() {
+                    text("connector")
+                } else {
+                    format!("{} ({})", text("connector"), action)
+                };
+                ("Approval needed".to_string(), format!("{} is waiting for approval", call))
+            }
+            events::JOB_COMPLETED => (format!("{} finished", text("name")), text("output")),
+            events::JOB_FAILED => (format!("{} failed", text("name")), text("error")),
+            events::BUDGET_WARNING => {
+                let spent = data["spent_usd"].as_f64().unwrap_or_default();
+                let limit = data["daily_limit_usd"].as_f64().unwrap_or_default();
+                let summary = if data["share"].as_f64().unwrap_or_default() >= 1.0 {
+                    "Daily budget used up"
+                } else {
+                    "Daily budget almost used up"
+                };
+                (summary.to_string(), format!("${:.2} of ${:.2} spent today", spent, limit))
+            }
+            events::BRIEFING_DELIVERED => ("New briefing".to_string(), text("excerpt")),
+            _ => return None,
+        };
+        Some(Notice {
+            summary,
+            body: truncate(&body),
+        })
+    }
+}
+
+fn truncate(body: &str) -> String {
+    let body = body.trim();
+    if body.chars().count() <= MAX_BODY_CHARS {
+        return body.to_string();
+    }
+    let cut: String = body.chars().take(MAX_BODY_CHARS - 1).collect();
+    format!("{}…", cut.trim_end())
+}
+
+/// Show notices for events on `bus` until shutdown. Does nothing when
+/// notifications are off.
+pub fn spawn_desktop_notifications(
+    supervisor: &Supervisor,
+    config: NotificationConfig,
+    bus: &events::EventBus,
+    shutdown: broadcast::Receiver<()>,
+) {
+    if !config.enabled || config.events.is_empty() {
+        return;
+    }
+    let bus = bus.clone();
+    supervisor.spawn("desktop_notifications", move || {
+        let config = config.clone();
+        let mut events = bus.subscribe();
+        let mut shutdown = shutdown.resubscribe();
+        async move {
+            loop {
+                let event = tokio::select! {
+                    _ = shutdown.recv() => break,
+                    event = events.recv() => match event {
+                        Ok(event) => event,
+                        Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                            tracing::debug!("Desktop notifications missed {} runtime events", skipped);
+                            continue;
+                        }
+                        Err(broadcast::error::RecvError::Closed) => break,
+                    },
+                };
+                let Some(notice) = config.notice(&event) else {
+                    continue;
+                };
+                // Talking to the notification service blocks
+                tokio::task::spawn_blocking(move || {
+                    if let Err(e) = notify_rust::Notification::new()
+                        .appname(APP_NAME)
+                        .summary(&notice.summary)
+                        .body(&notice.body)
+                        .show()
+                    {
+                        tracing::debug!("Could not show a desktop notification: {}", e);
+                    }
+                });
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn event(kind: &str, data: serde_json::Value) -> RuntimeEvent {
+        RuntimeEvent {
+            kind: kind.to_string(),
+            session_id: None,
+            data,
+            at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_notice_per_event_kind() {
+        let approval = event(
+            events::APPROVAL_REQUESTED,
+            serde_json::json!({ "connector": "system_admin", "action": "restart" }),
+        );
+        let budget = event(
+            events::BUDGET_WARNING,
+            serde_json::json!({ "share": 0.8, "spent_usd": 4.0, "daily_limit_usd": 5.0 }),
+        );
+        let config = NotificationConfig::default();
+        assert_eq!(
+            config.notice(&approval).unwrap().body,
+            "system_admin (restart) is waiting for approval"
+        );
+        let notice = config.notice(&budget).unwrap();
+        assert_eq!(notice.summary, "Daily budget almost used up");
+        assert_eq!(notice.body, "$4.00 of $5.00 spent today");
+        assert!(config.notice(&event(events::TURN_COMPLETED, serde_json::json!({}))).is_none());
+
+        let output = "x".repeat(500);
+        let job = event(events::JOB_COMPLETED, serde_json::json!({ "name": "backup", "output": output }));
+        assert_eq!(config.notice(&job).unwrap().body.chars().count(), MAX_BODY_CHARS);
+
+        let config = NotificationConfig {
+            enabled: true,
+            events: vec!["jobs".parse().unwrap()],
+        };
+        assert!(config.notice(&approval).is_none());
+        assert!(config.notice(&job).is_some());
+        assert!("email".parse::<NotifyEvent>().is_err());
+    }
+}
@@ -0,0 +1,374 @@
+//! Offline queue for background provider requests
+//!
+//! With `offline_queue.enabled`, background work that finds the provider
+//! unreachable queues the requests it couldn't make instead of failing:
+//! ingestion queues the chunks it couldn't embed, consolidation the clusters
+//! it couldn't merge. Chat turns never queue; they have someone waiting.
+//!
+//! Queued requests are kept one file each under `offline_dir`, so work
+//! queued by a CLI command is picked up by the runtime. While it runs, the
+//! runtime retries the oldest request every `offline_queue.retry_secs`;
+//! once one gets through, it works through the rest in order and publishes
+//! `job.completed`. A request that fails for any other reason is dropped
+//! and reported in `job.failed`, so it can't hold up the rest.
+//!
+//! ```toml
+//! [offline_queue]
+//! enabled = true
+//! retry_secs = 60
+//! max_pending = 10000
+//! ```
+
+use crate::events;
+use crate::ingest::IngestOptions;
+use crate::state::RuntimeState;
+use crate::status;
+use chrono::{DateTime, Utc};
+use jamey_core::maintenance::SimilarGroup;
+use jamey_core::memory::MemoryType;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use thiserror::Error;
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+pub(crate) fn default_offline_dir() -> PathBuf {
+    std::env::var("JAMEY_OFFLINE_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from("./offline"))
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct OfflineQueueConfig {
+    /// Queue background requests while the provider is unreachable
+    /// (`JAMEY_OFFLINE_QUEUE`)
+    pub enabled: bool,
+    /// How often the runtime checks whether the provider is back
+    pub retry_secs: u64,
+    /// Requests kept at most; past this, work fails as it would without
+    /// the queue
+    pub max_pending: usize,
+}
+
+impl Default for OfflineQueueConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            retry_secs: 60,
+            max_pending: 10_000,
+        }
+    }
+}
+
+impl OfflineQueueConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.retry_secs == 0 || self.max_pending == 0 {
+            return Err("offline_queue.retry_secs and max_pending must be above 0".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum OfflineError {
+    #[error("Offline queue is full ({0} requests)")]
+    Full(usize),
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Serialization error: {0}")]
+    Serialization(#[from] serde_json::Error),
+}
+
+/// A provider request put off until the provider is back
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum DeferredRequest {
+    /// Embed and store one chunk of an ingested document
+    IngestChunk {
+        chunk: String,
+        source: String,
+        index: usize,
+        total: usize,
+        memory_type: MemoryType,
+        namespace: Option<String>,
+        tags: Vec<String>,
+    },
+    /// Merge a cluster found by consolidation
+    Consolidate { cluster: SimilarGroup },
+}
+
+impl DeferredRequest {
+    fn label(&self) -> String {
+        match self {
+            DeferredRequest::IngestChunk { source, index, .. } => format!("ingest {} chunk {}", source, index),
+            DeferredRequest::Consolidate { cluster } => format!("consolidate {}", cluster.keep),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueuedRequest {
+    pub id: Uuid,
+    pub queued_at: DateTime<Utc>,
+    pub request: DeferredRequest,
+}
+
+/// What a pass over the queue did
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DrainReport {
+    pub completed: usize,
+    /// Requests dropped because they failed for a reason other than the
+    /// provider being unreachable, with the error
+    pub failed: Vec<(String, String)>,
+    /// Still queued, because the provider went away again
+    pub remaining: usize,
+}
+
+/// Queued requests, one JSON file each, named so they list oldest first
+pub struct OfflineQueue {
+    dir: PathBuf,
+    max_pending: usize,
+}
+
+impl OfflineQueue {
+    pub fn new(dir: impl Into<PathBuf>, max_pending: usize) -> Self {
+        Self {
+            dir: dir.into(),
+            max_pending,
+        }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn path(&self, request: &QueuedRequest) -> PathBuf {
+        self.dir.join(format!(
+            "{:020}-{}.json",
+            request.queued_at.timestamp_micros().max(0),
+            request.id
+        ))
+    }
+
+    async fn files(&self) -> Result<Vec<PathBuf>, OfflineError> {
+        let mut entries = match tokio::fs::read_dir(&self.dir).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        let mut files = Vec::new();
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if path.extension().is_some_and(|ext| ext == "json") {
+                files.push(path);
+            }
+        }
+        files.sort();
+        Ok(files)
+    }
+
+    /// Queue `request`, unless `max_pending` are already waiting
+    pub async fn push(&self, request: DeferredRequest) -> Result<Uuid, OfflineError> {
+        let pending = self.files().await?.len();
+        if pending >= self.max_pending {
+            return Err(OfflineError::Full(pending));
+        }
+        let queued = QueuedRequest {
+            id: Uuid::new_v4(),
+            queued_at: Utc::now(),
+            request,
+        };
+        tokio::fs::create_dir_all(&self.dir).await?;
+        let path = self.path(&queued);
+        let tmp = path.with_extension("json.tmp");
+        tokio::fs::write(&tmp, serde_json::to_vec_pretty(&queued)?).await?;
+        tokio::fs::rename(&tmp, &path).await?;
+        status::record_queue_depth("offline_requests", pending + 1);
+        Ok(queued.id)
+    }
+
+    /// Every queued request, oldest first. Unreadable files are skipped.
+    pub async fn pending(&self) -> Result<Vec<QueuedRequest>, OfflineError> {
+        let mut pending = Vec::new();
+        for path in self.files().await? {
+            match tokio::fs::read(&path).await {
+                Ok(bytes) => match serde_json::from_slice(&bytes) {
+                    Ok(request) => pending.push(request),
+                    Err(e) => tracing::warn!("Skipping unreadable queued request {}: {}", path.display(), e),
+                },
+                // Taken by another drain since the listing
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Ok(pending)
+    }
+
+    pub async fn remove(&self, request: &QueuedRequest) -> Result<(), OfflineError> {
+        match tokio::fs::remove_file(self.path(request)).await {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl RuntimeState {
+    /// Queue `request` for when the provider is back. `None` when the queue
+    /// is off or full, in which case the caller fails as usual.
+    pub(crate) async fn defer(&self, request: DeferredRequest) -> Option<Uuid> {
+        if !self.config.offline_queue.enabled {
+            return None;
+        }
+        let label = request.label();
+        match self.offline_queue.push(request).await {
+            Ok(id) => {
+                tracing::info!("Provider unreachable; queued {}", label);
+                Some(id)
+            }
+            Err(e) => {
+                tracing::warn!("Could not queue {}: {}", label, e);
+                None
+            }
+        }
+    }
+
+    /// Run queued requests oldest first, stopping at the first that still
+    /// can't reach the provider
+    pub async fn drain_offline_queue(&self) -> Result<DrainReport, OfflineError> {
+        let pending = self.offline_queue.pending().await?;
+        let mut report = DrainReport::default();
+        for (index, queued) in pending.iter().enumerate() {
+            match self.run_deferred(&queued.request).await {
+                Ok(()) => report.completed += 1,
+                Err(e) if jamey_providers::openrouter::is_unreachable(&e) => {
+                    report.remaining = pending.len() - index;
+                    break;
+                }
+                Err(e) => {
+                    tracing::warn!("Dropping queued {}: {}", queued.request.label(), e);
+                    report.failed.push((queued.request.label(), e.to_string()));
+                }
+            }
+            self.offline_queue.remove(queued).await?;
+        }
+        status::record_queue_depth("offline_requests", report.remaining);
+        Ok(report)
+    }
+
+    async fn run_deferred(&self, request: &DeferredRequest) -> anyhow::Result<()> {
+        match request {
+            DeferredRequest::IngestChunk {
+                chunk,
+                source,
+                index,
+                total,
+                memory_type,
+                namespace,
+                tags,
+            } => {
+                let options = IngestOptions {
+                    memory_type: memory_type.clone(),
+                    namespace: namespace.clone(),
+                    tags: tags.clone(),
+                    ..Default::default()
+                };
+                self.store_chunk(chunk, source, *index, *total, &options).await?;
+            }
+            DeferredRequest::Consolidate { cluster } => {
+                let _permit = self.request_queue.background().await;
+                let (consolidator, embedder) = (self.consolidator(), self.embedder(None));
+                self.memory_store.merge_cluster(cluster, &consolidator, &embedder).await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Retry the offline queue every `retry_secs` while it has requests
+pub(crate) fn spawn_offline_drain(state: Arc<RuntimeState>, shutdown: broadcast::Receiver<()>) {
+    if !state.config.offline_queue.enabled {
+        return;
+    }
+    let supervisor = state.supervisor.clone();
+    supervisor.spawn("offline_queue", move || {
+        let state = Arc::clone(&state);
+        let mut shutdown = shutdown.resubscribe();
+        async move {
+            let mut ticker = tokio::time::interval(Duration::from_secs(state.config.offline_queue.retry_secs));
+            loop {
+                tokio::select! {
+                    _ = shutdown.recv() => break,
+                    _ = ticker.tick() => {}
+                }
+                let report = match state.drain_offline_queue().await {
+                    Ok(report) => report,
+                    Err(e) => {
+                        tracing::warn!("Reading the offline queue from {} failed: {}", state.offline_queue.dir().display(), e);
+                        continue;
+                    }
+                };
+                if report.completed > 0 {
+                    tracing::info!("Provider is back; ran {} queued requests", report.completed);
+                    state.events.publish(
+                        events::JOB_COMPLETED,
+                        None,
+                        serde_json::json!({
+                            "name": "offline_queue",
+                            "completed": report.completed,
+                            "remaining": report.remaining,
+                        }),
+                    );
+                }
+                if !report.failed.is_empty() {
+                    state.events.publish(
+                        events::JOB_FAILED,
+                        None,
+                        serde_json::json!({
+                            "name": "offline_queue",
+                            "failed": report.failed,
+                        }),
+                    );
+                }
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(index: usize) -> DeferredRequest {
+        DeferredRequest::IngestChunk {
+            chunk: format!("chunk {}", index),
+            source: "notes.md".to_string(),
+            index,
+            total: 3,
+            memory_type: MemoryType::Knowledge,
+            namespace: None,
+            tags: Vec::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_queue_order_and_limit() {
+        let dir = tempfile::tempdir().unwrap();
+        let queue = OfflineQueue::new(dir.path(), 2);
+        assert!(queue.pending().await.unwrap().is_empty());
+
+        queue.push(chunk(0)).await.unwrap();
+        queue.push(chunk(1)).await.unwrap();
+        assert!(matches!(queue.push(chunk(2)).await, Err(OfflineError::Full(2))));
+
+        let pending = queue.pending().await.unwrap();
+        assert_eq!(pending.iter().map(|q| q.request.clone()).collect::<Vec<_>>(), vec![chunk(0), chunk(1)]);
+        queue.remove(&pending[0]).await.unwrap();
+        // Removing twice is harmless
+        queue.remove(&pending[0]).await.unwrap();
+        assert_eq!(queue.pending().await.unwrap()[0].request, chunk(1));
+    }
+}
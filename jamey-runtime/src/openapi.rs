@@ -0,0 +1,622 @@
+//! OpenAPI description of the runtime's HTTP surface
+//!
+//! Integrators talk to the runtime through the web chat's `/ws` socket (or
+//! its event-stream fallback), the task inspector at `/tasks`, A2A at `/a2a`
+//! and the inbound hooks, all on `api.http_port` next to the chat page and
+//! memory sync. [`document`] describes every route as an OpenAPI 3.1
+//! document, including the JSON messages exchanged over the socket, and the
+//! web UI serves it at `/openapi.json` with a readable rendering at `/docs`.
+//!
+//! The document is written out by hand, so the tests check its paths
+//! against the routes the server mounts and its schemas against what the
+//! types actually serialize to.
+
+use crate::config::RuntimeConfig;
+use serde_json::{json, Value};
+
+/// The OpenAPI document for a runtime running with `config`
+pub fn document(config: &RuntimeConfig) -> Value {
+    let host = match config.api.host.as_str() {
+        "0.0.0.0" | "::" => "localhost",
+        host => host,
+    };
+    let agent_card = json!({
+        "get": {
+            "tags": ["a2a"],
+            "summary": "A2A agent card",
+            "description": "Who Jamey is to other agents, per the A2A protocol: its skills and the JSON-RPC endpoint taking its tasks. Served at `/.well-known/agent-card.json` and, for older clients, `/.well-known/agent.json`.",
+            "security": [{ "bearer": [] }, { "token": [] }],
+            "responses": {
+                "200": {
+                    "description": "The card",
+                    "content": { "application/json": { "schema": { "type": "object", "description": "An A2A `AgentCard`" } } },
+                },
+                "401": { "$ref": "#/components/responses/Failed" },
+            },
+        },
+    });
+
+    json!({
+        "openapi": "3.1.0",
+        "info": {
+            "title": "Jamey runtime",
+            "version": env!("CARGO_PKG_VERSION"),
+            "description": "Chat with the runtime over the `/ws` WebSocket, or Server-Sent Events where WebSockets are blocked; push payloads into sessions and jobs with inbound hooks.",
+        },
+        "tags": [
+            { "name": "chat" },
+            { "name": "tasks" },
+            { "name": "a2a" },
+            { "name": "hooks", "description": "Only served when `api.hooks` is on" },
+            { "name": "ui", "description": "Only served when `api.web_ui` is on" },
+        ],
+        "servers": [{ "url": format!("http://{}:{}", host, config.api.http_port), "description": "api.http_port" }],
+        "paths": {
+            "/ws": {
+                "get": {
+                    "tags": ["chat"],
+                    "summary": "Chat over a WebSocket",
+                    "description": "Upgrade to a WebSocket carrying JSON text messages. Send a `ClientHello` first; the server answers with a `SessionMessage`, or `unauthorized` and closes. Each `ClientMessage` then streams `token`, `tool_call`, `tool_result` and `approval` messages, ending with `completed` or `error`. Browsers must connect from the page's own origin or one in `api.allowed_origins`.",
+                    "parameters": [
+                        { "name": "Upgrade", "in": "header", "required": true, "schema": { "const": "websocket" } },
+                        { "name": "Sec-WebSocket-Key", "in": "header", "required": true, "schema": { "type": "string" } },
+                    ],
+                    "responses": {
+                        "101": {
+                            "description": "Switched to the WebSocket protocol",
+                            "content": {
+                                "application/json": {
+                                    "schema": { "$ref": "#/components/schemas/ServerMessage" },
+                                },
+                            },
+                        },
+                        "400": { "description": "Not a WebSocket upgrade" },
+                        "403": { "description": "Origin not allowed" },
+                    },
+                },
+            },
+            "/sessions/{id}/stream": {
+                "get": {
+                    "tags": ["chat"],
+                    "summary": "Follow a session as Server-Sent Events",
+                    "description": "For clients behind proxies that block WebSocket upgrades. Each event's `data` is one of the `ServerMessage`s the socket would send, starting with a `SessionMessage`; turns started with `POST /sessions/{id}/messages` stream here. Comments are sent while idle to keep proxies from closing the stream.",
+                    "security": [{ "bearer": [] }, { "token": [] }],
+                    "parameters": [session_id()],
+                    "responses": {
+                        "200": {
+                            "description": "An event stream of `ServerMessage`s",
+                            "content": { "text/event-stream": { "schema": { "$ref": "#/components/schemas/ServerMessage" } } },
+                        },
+                        "401": { "$ref": "#/components/responses/Failed" },
+                        "403": { "$ref": "#/components/responses/Failed" },
+                    },
+                },
+            },
+            "/sessions/{id}/messages": {
+                "post": {
+                    "tags": ["chat"],
+                    "summary": "Start a turn in a session",
+                    "description": "The reply is streamed to the session's `/stream` followers, so open the stream first.",
+                    "security": [{ "bearer": [] }, { "token": [] }],
+                    "parameters": [session_id()],
+                    "requestBody": {
+                        "required": true,
+                        "content": { "application/json": { "schema": { "$ref": "#/components/schemas/PostedMessage" } } },
+                    },
+                    "responses": {
+                        "202": {
+                            "description": "Turn started",
+                            "content": { "application/json": { "schema": { "$ref": "#/components/schemas/TurnStarted" } } },
+                        },
+                        "400": { "$ref": "#/components/responses/Failed" },
+                        "401": { "$ref": "#/components/responses/Failed" },
+                        "409": { "$ref": "#/components/responses/Failed" },
+                        "429": { "$ref": "#/components/responses/Failed" },
+                    },
+                },
+            },
+            "/tasks": {
+                "get": {
+                    "tags": ["tasks"],
+                    "summary": "List in-flight work",
+                    "description": "Chat turns, the model calls and tool executions running under them, and scheduled jobs, oldest first, with the request queue's depths and the health of supervised background tasks.",
+                    "security": [{ "bearer": [] }, { "token": [] }],
+                    "responses": {
+                        "200": {
+                            "description": "Running work",
+                            "content": { "application/json": { "schema": { "$ref": "#/components/schemas/InFlightReport" } } },
+                        },
+                        "401": { "$ref": "#/components/responses/Failed" },
+                        "403": { "$ref": "#/components/responses/Failed" },
+                    },
+                },
+            },
+            "/tasks/{id}": {
+                "delete": {
+                    "tags": ["tasks"],
+                    "summary": "Cancel in-flight work",
+                    "description": "Cancels the task and anything running under it. A cancelled turn ends with an `error` message; a cancelled model call or tool fails like any other.",
+                    "security": [{ "bearer": [] }, { "token": [] }],
+                    "parameters": [session_id()],
+                    "responses": {
+                        "200": {
+                            "description": "Cancelled",
+                            "content": { "application/json": { "schema": { "$ref": "#/components/schemas/TaskCancelled" } } },
+                        },
+                        "401": { "$ref": "#/components/responses/Failed" },
+                        "404": { "$ref": "#/components/responses/Failed" },
+                    },
+                },
+            },
+            "/.well-known/agent-card.json": agent_card.clone(),
+            "/.well-known/agent.json": agent_card,
+            "/a2a": {
+                "post": {
+                    "tags": ["a2a"],
+                    "summary": "A2A JSON-RPC endpoint",
+                    "description": "JSON-RPC 2.0 calls from other agents: `message/send` runs a turn as a task and returns it once done, `message/stream` returns the task's status and reply artifact as Server-Sent Events, and `tasks/get` and `tasks/cancel` follow up by task id. A task's `contextId` is the session it runs in.",
+                    "security": [{ "bearer": [] }, { "token": [] }],
+                    "requestBody": {
+                        "required": true,
+                        "content": { "application/json": { "schema": { "type": "object", "description": "A JSON-RPC 2.0 request" } } },
+                    },
+                    "responses": {
+                        "200": {
+                            "description": "A JSON-RPC response, errors included, or for `message/stream` an event stream of them",
+                            "content": {
+                                "application/json": { "schema": { "type": "object" } },
+                                "text/event-stream": { "schema": { "type": "string" } },
+                            },
+                        },
+                        "401": { "$ref": "#/components/responses/Failed" },
+                        "403": { "$ref": "#/components/responses/Failed" },
+                    },
+                },
+            },
+            "/hooks/{name}": {
+                "post": {
+                    "tags": ["hooks"],
+                    "summary": "Deliver a payload to an inbound hook",
+                    "description": "A session hook appends the payload to its session as a user message; a queue hook runs its connector once, with a JSON object's fields as parameters or anything else as `payload`.",
+                    "security": [{ "bearer": [] }, { "signature": [] }],
+                    "parameters": [
+                        { "name": "name", "in": "path", "required": true, "schema": { "type": "string" } },
+                    ],
+                    "requestBody": {
+                        "content": {
+                            "application/json": { "schema": {} },
+                            "text/plain": { "schema": { "type": "string" } },
+                        },
+                    },
+                    "responses": {
+                        "202": {
+                            "description": "Accepted",
+                            "content": { "application/json": { "schema": { "$ref": "#/components/schemas/HookAccepted" } } },
+                        },
+                        "400": { "$ref": "#/components/responses/Rejected" },
+                        "401": { "$ref": "#/components/responses/Rejected" },
+                        "404": { "$ref": "#/components/responses/Rejected" },
+                        "413": { "$ref": "#/components/responses/Rejected" },
+                    },
+                },
+            },
+            "/telegram": {
+                "post": {
+                    "tags": ["hooks"],
+                    "summary": "Receive Telegram bot updates",
+                    "description": "Only served when `telegram_webhook_url` is set; Telegram sends its secret token header.",
+                    "parameters": [
+                        { "name": "X-Telegram-Bot-Api-Secret-Token", "in": "header", "required": true, "schema": { "type": "string" } },
+                    ],
+                    "requestBody": { "content": { "application/json": { "schema": { "type": "object" } } } },
+                    "responses": {
+                        "200": { "description": "Update accepted" },
+                        "401": { "$ref": "#/components/responses/Rejected" },
+                    },
+                },
+            },
+            "/sync": {
+                "post": {
+                    "tags": ["hooks"],
+                    "summary": "Exchange memory changes with a sync peer",
+                    "description": "Only answered when `sync.enabled` is on. Applies the peer's changes to one namespace and replies with this side's changes since `since`, signed with the shared secret. Requests sent more than five minutes ago are refused.",
+                    "security": [{ "signature": [] }],
+                    "requestBody": {
+                        "required": true,
+                        "content": { "application/json": { "schema": { "$ref": "#/components/schemas/SyncRequest" } } },
+                    },
+                    "responses": {
+                        "200": {
+                            "description": "Applied; this side's changes in return",
+                            "headers": { "X-Jamey-Signature": { "schema": { "type": "string" } } },
+                            "content": { "application/json": { "schema": { "$ref": "#/components/schemas/SyncResponse" } } },
+                        },
+                        "400": { "$ref": "#/components/responses/SyncFailed" },
+                        "401": { "$ref": "#/components/responses/SyncFailed" },
+                        "403": { "$ref": "#/components/responses/SyncFailed" },
+                        "404": { "$ref": "#/components/responses/SyncFailed" },
+                        "413": { "$ref": "#/components/responses/SyncFailed" },
+                    },
+                },
+            },
+            "/": page("The chat page", "text/html"),
+            "/index.html": page("The chat page", "text/html"),
+            "/app.js": page("The chat page's script", "text/javascript"),
+            "/style.css": page("The chat page's styles", "text/css"),
+            "/docs": page("A readable rendering of this document", "text/html"),
+            "/docs.js": page("The rendering's script", "text/javascript"),
+            "/openapi.json": {
+                "get": {
+                    "tags": ["ui"],
+                    "summary": "This document",
+                    "responses": {
+                        "200": { "description": "The OpenAPI document", "content": { "application/json": { "schema": { "type": "object" } } } },
+                    },
+                },
+            },
+        },
+        "components": {
+            "securitySchemes": {
+                "bearer": {
+                    "type": "http",
+                    "scheme": "bearer",
+                    "description": "`security.api_key` for sessions; the hook's token for hooks",
+                },
+                "token": {
+                    "type": "apiKey",
+                    "in": "query",
+                    "name": "token",
+                    "description": "`security.api_key`, for clients that can't set headers",
+                },
+                "signature": {
+                    "type": "apiKey",
+                    "in": "header",
+                    "name": "X-Jamey-Signature",
+                    "description": "`sha256=` and the hex HMAC-SHA256 of the body under the hook's secret",
+                },
+            },
+            "responses": {
+                "Failed": {
+                    "description": "Failed",
+                    "content": {
+                        "application/json": { "schema": { "$ref": "#/components/schemas/ErrorMessage" } },
+                    },
+                },
+                "Rejected": {
+                    "description": "Rejected",
+                    "content": { "application/json": { "schema": { "$ref": "#/components/schemas/HookRejected" } } },
+                },
+                "SyncFailed": {
+                    "description": "Refused",
+                    "content": {
+                        "application/json": {
+                            "schema": { "type": "object", "required": ["error"], "properties": { "error": { "type": "string" } } },
+                        },
+                    },
+                },
+            },
+            "schemas": schemas(),
+        },
+    })
+}
+
+fn schemas() -> Value {
+    let uuid = json!({ "type": "string", "format": "uuid" });
+    let time = json!({ "type": "string", "format": "date-time" });
+    json!({
+        "Role": { "enum": ["system", "user", "assistant", "tool"] },
+        "Citation": {
+            "type": "object",
+            "required": ["memory_id", "snippet", "similarity"],
+            "properties": {
+                "memory_id": uuid,
+                "snippet": { "type": "string", "description": "Start of the memory's content" },
+                "similarity": { "type": "number" },
+            },
+        },
+        "Message": {
+            "type": "object",
+            "required": ["role", "content"],
+            "properties": {
+                "id": uuid,
+                "role": { "$ref": "#/components/schemas/Role" },
+                "content": { "type": "string", "maxLength": 32768 },
+                "timestamp": time,
+                "metadata": { "type": "object" },
+                "parts": { "type": "array", "items": { "type": "object" }, "description": "Attached files and other non-text content" },
+                "citations": { "type": "array", "items": { "$ref": "#/components/schemas/Citation" } },
+            },
+        },
+        "ClientHello": {
+            "type": "object",
+            "required": ["type"],
+            "properties": {
+                "type": { "const": "hello" },
+                "session": { "type": ["string", "null"], "format": "uuid", "description": "Session to continue; a new one when omitted" },
+                "token": { "type": ["string", "null"], "description": "`security.api_key`, when `security.api_key_required`" },
+            },
+        },
+        "ClientMessage": {
+            "type": "object",
+            "required": ["type", "content"],
+            "properties": {
+                "type": { "const": "message" },
+                "content": { "type": "string" },
+            },
+        },
+        "SessionMessage": {
+            "type": "object",
+            "required": ["type", "id", "title", "messages"],
+            "properties": {
+                "type": { "const": "session" },
+                "id": uuid,
+                "title": { "type": "string" },
+                "messages": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "role": { "enum": ["system", "user", "assistant"] },
+                            "content": { "type": "string" },
+                        },
+                    },
+                },
+            },
+        },
+        "ServerMessage": {
+            "oneOf": [
+                { "$ref": "#/components/schemas/SessionMessage" },
+                event("unauthorized", json!({})),
+                event("token", json!({ "text": { "type": "string" } })),
+                event("tool_call", json!({ "name": { "type": "string" } })),
+                event("tool_result", json!({ "name": { "type": "string" }, "error": { "type": ["string", "null"] } })),
+                event("approval", json!({
+                    "id": uuid,
+                    "connector": { "type": "string" },
+                    "action": { "type": "string" },
+                })),
+                event("completed", json!({
+                    "content": { "type": "string" },
+                    "citations": { "type": "array", "items": { "$ref": "#/components/schemas/Citation" } },
+                    "degradation": {
+                        "enum": ["smaller_model", "cached", "canned", null],
+                        "description": "Set when the reply came from a fallback rather than the turn's own model",
+                    },
+                    "title": { "type": "string" },
+                })),
+                { "$ref": "#/components/schemas/ErrorMessage" },
+            ],
+            "discriminator": { "propertyName": "type" },
+        },
+        "ErrorMessage": event("error", json!({
+            "message": { "type": "string" },
+            "error": { "const": "queue_full", "description": "Set when the request queue turned the message away" },
+            "scope": { "enum": ["global", "session"] },
+            "limit": { "type": "integer" },
+            "retry_after_secs": { "type": "integer" },
+        })),
+        "PostedMessage": {
+            "type": "object",
+            "required": ["content"],
+            "properties": { "content": { "type": "string" } },
+        },
+        "TurnStarted": {
+            "type": "object",
+            "required": ["accepted", "session_id"],
+            "properties": {
+                "accepted": { "const": true },
+                "session_id": uuid,
+            },
+        },
+        "HookAccepted": {
+            "type": "object",
+            "required": ["accepted", "hook"],
+            "properties": {
+                "accepted": { "const": true },
+                "hook": { "type": "string" },
+                "session_id": { "type": "string", "format": "uuid", "description": "Session hooks" },
+                "task_id": { "type": "string", "format": "uuid", "description": "Queue hooks" },
+            },
+        },
+        "SyncRequest": {
+            "type": "object",
+            "required": ["namespace", "sent_at", "changes"],
+            "properties": {
+                "namespace": { "type": "string" },
+                "sent_at": time,
+                "since": { "type": ["object", "null"], "description": "After the last of the peer's changes the sender has applied" },
+                "changes": { "type": "array", "items": { "type": "object", "description": "A memory write or tombstone" } },
+            },
+        },
+        "SyncResponse": {
+            "type": "object",
+            "required": ["sent_at", "applied", "changes", "more"],
+            "properties": {
+                "sent_at": time,
+                "applied": {
+                    "type": "object",
+                    "description": "What applying the request's changes did",
+                    "properties": {
+                        "written": { "type": "integer" },
+                        "deleted": { "type": "integer" },
+                        "skipped": { "type": "integer" },
+                    },
+                },
+                "changes": { "type": "array", "items": { "type": "object", "description": "A memory write or tombstone" } },
+                "more": { "type": "boolean", "description": "Whether the peer has further changes after these" },
+            },
+        },
+        "WorkInfo": {
+            "type": "object",
+            "required": ["id", "kind", "label", "started_at", "age_secs"],
+            "properties": {
+                "id": uuid,
+                "kind": { "enum": ["turn", "model_call", "tool", "job"] },
+                "label": { "type": "string", "description": "Model, connector or task name" },
+                "session": { "type": ["string", "null"], "format": "uuid" },
+                "parent": { "type": ["string", "null"], "format": "uuid", "description": "Turn a model call or tool runs under" },
+                "started_at": time,
+                "age_secs": { "type": "number" },
+            },
+        },
+        "QueueStats": {
+            "type": "object",
+            "properties": {
+                "running": { "type": "integer" },
+                "running_background": { "type": "integer" },
+                "waiting_interactive": { "type": "integer" },
+                "waiting_background": { "type": "integer" },
+            },
+        },
+        "InFlightReport": {
+            "type": "object",
+            "required": ["tasks", "queue", "supervised"],
+            "properties": {
+                "tasks": { "type": "array", "items": { "$ref": "#/components/schemas/WorkInfo" } },
+                "queue": { "$ref": "#/components/schemas/QueueStats" },
+                "supervised": { "type": "array", "items": { "$ref": "#/components/schemas/SupervisedTask" } },
+            },
+        },
+        "SupervisedTask": {
+            "type": "object",
+            "required": ["name", "state", "panics", "restarts"],
+            "properties": {
+                "name": { "type": "string", "description": "Background loop, or `job:{name}` for a scheduled task" },
+                "state": { "enum": ["running", "restarting", "finished", "failed"] },
+                "panics": { "type": "integer" },
+                "restarts": { "type": "integer" },
+                "last_panic": { "type": ["string", "null"] },
+                "last_panic_at": { "type": ["string", "null"], "format": "date-time" },
+            },
+        },
+        "TaskCancelled": {
+            "type": "object",
+            "required": ["cancelled"],
+            "properties": { "cancelled": uuid },
+        },
+        "HookRejected": {
+            "type": "object",
+            "required": ["accepted", "error"],
+            "properties": {
+                "accepted": { "const": false },
+                "error": { "type": "string" },
+            },
+        },
+    })
+}
+
+/// A static file of the web UI
+fn page(summary: &str, content_type: &str) -> Value {
+    json!({
+        "get": {
+            "tags": ["ui"],
+            "summary": summary,
+            "responses": { "200": { "description": summary, "content": { content_type: { "schema": { "type": "string" } } } } },
+        },
+    })
+}
+
+fn session_id() -> Value {
+    json!({ "name": "id", "in": "path", "required": true, "schema": { "type": "string", "format": "uuid" } })
+}
+
+/// A server message tagged `kind` with `properties` besides the tag
+fn event(kind: &str, mut properties: Value) -> Value {
+    properties["type"] = json!({ "const": kind });
+    json!({ "type": "object", "required": ["type"], "properties": properties })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jamey_protocol::{Citation, Message};
+
+    /// Every field `value` serializes is in `schema`'s properties
+    fn assert_covers(schema: &Value, value: &Value) {
+        let properties = schema["properties"].as_object().unwrap();
+        for key in value.as_object().unwrap().keys() {
+            assert!(properties.contains_key(key), "schema is missing `{}`", key);
+        }
+    }
+
+    #[test]
+    fn test_schemas_match_types() {
+        let schemas = schemas();
+        let citation = Citation {
+            memory_id: uuid::Uuid::new_v4(),
+            snippet: "Jamey listens on 3000".to_string(),
+            similarity: 0.9,
+        };
+        let message = Message::assistant("It listens on 3000 [1]").with_citations(vec![citation.clone()]);
+        assert_covers(&schemas["Message"], &serde_json::to_value(&message).unwrap());
+        assert_covers(&schemas["Citation"], &serde_json::to_value(&citation).unwrap());
+
+        let in_flight = crate::inflight::InFlight::new();
+        let _turn = in_flight.begin(crate::inflight::WorkKind::Turn, "chat", None);
+        let supervised = jamey_core::supervisor::SupervisedTask {
+            name: "scheduler".to_string(),
+            state: jamey_core::supervisor::TaskState::Running,
+            panics: 1,
+            restarts: 1,
+            last_panic: Some("boom".to_string()),
+            last_panic_at: Some(chrono::Utc::now()),
+        };
+        let report = serde_json::to_value(in_flight.report(Default::default(), vec![supervised])).unwrap();
+        assert_covers(&schemas["InFlightReport"], &report);
+        assert_covers(&schemas["WorkInfo"], &report["tasks"][0]);
+        assert_covers(&schemas["QueueStats"], &report["queue"]);
+        assert_covers(&schemas["SupervisedTask"], &report["supervised"][0]);
+
+        let request = crate::sync::SyncRequest {
+            namespace: "notes".to_string(),
+            sent_at: chrono::Utc::now(),
+            since: None,
+            changes: Vec::new(),
+        };
+        assert_covers(&schemas["SyncRequest"], &serde_json::to_value(&request).unwrap());
+        let response = crate::sync::SyncResponse {
+            sent_at: chrono::Utc::now(),
+            applied: Default::default(),
+            changes: Vec::new(),
+            more: false,
+        };
+        let response = serde_json::to_value(&response).unwrap();
+        assert_covers(&schemas["SyncResponse"], &response);
+        assert_covers(&schemas["SyncResponse"]["properties"]["applied"], &response["applied"]);
+    }
+
+    #[cfg(feature = "web-ui")]
+    #[test]
+    fn test_document_covers_routes() {
+        use std::collections::BTreeSet;
+
+        let document = document(&RuntimeConfig::default());
+        let documented: BTreeSet<&str> = document["paths"].as_object().unwrap().keys().map(String::as_str).collect();
+        // axum writes parameters as `:id`, OpenAPI as `{id}`
+        let routed: Vec<String> = crate::web::paths()
+            .iter()
+            .map(|path| {
+                let segments = path.split('/').map(|segment| match segment.strip_prefix(':') {
+                    Some(name) => format!("{{{}}}", name),
+                    None => segment.to_string(),
+                });
+                segments.collect::<Vec<_>>().join("/")
+            })
+            .collect();
+        assert_eq!(documented, routed.iter().map(String::as_str).collect());
+    }
+
+    #[test]
+    fn test_document_references_resolve() {
+        let document = document(&RuntimeConfig::default());
+        assert_eq!(document["openapi"], "3.1.0");
+        assert!(document["paths"]["/ws"]["get"].is_object());
+
+        let text = document.to_string();
+        for reference in text.split("\"$ref\":\"#/").skip(1) {
+            let path = reference.split('"').next().unwrap();
+            let target = path.split('/').fold(&document, |node, key| &node[key]);
+            assert!(!target.is_null(), "dangling reference #/{}", path);
+        }
+    }
+}
@@ -0,0 +1,247 @@
+//! Personas
+//!
+//! A persona bundles what shapes a conversation: the system prompt, a tone,
+//! the connectors the model may use and a preferred model. They live as
+//! `<persona_dir>/<name>.json` and are managed with `jamey persona`. A
+//! session runs under the persona picked for it (`jamey chat --persona ops`),
+//! otherwise under `default_persona`, otherwise under `default`, which falls
+//! back to the built-in prompt until a persona of that name is saved.
+
+use jamey_tools::connector::ToolPolicy;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+/// Persona used when nothing else is picked
+pub const DEFAULT_PERSONA: &str = "default";
+
+/// System prompt of the built-in `default` persona
+const BUILTIN_PROMPT: &str = "You are Jamey, a helpful AI assistant. Be concise, accurate, and helpful.";
+
+#[derive(Debug, Error)]
+pub enum PersonaError {
+    #[error("Persona not found: {0}")]
+    NotFound(String),
+    #[error("Invalid persona name: {0:?}")]
+    InvalidName(String),
+    #[error("Persona {0} has no system prompt")]
+    EmptyPrompt(String),
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Serialization error: {0}")]
+    Serialization(#[from] serde_json::Error),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Persona {
+    pub name: String,
+    /// One line shown by `jamey persona list`
+    #[serde(default)]
+    pub description: String,
+    pub system_prompt: String,
+    /// How replies should sound, e.g. "terse and technical"
+    #[serde(default)]
+    pub tone: Option<String>,
+    /// Connector IDs the persona may use; empty leaves the session's own
+    /// policy as it is
+    #[serde(default)]
+    pub tools: Vec<String>,
+    /// Model used instead of the configured default
+    #[serde(default)]
+    pub model: Option<String>,
+}
+
+impl Persona {
+    /// The `default` persona before one has been saved
+    pub fn builtin() -> Self {
+        Self {
+            name: DEFAULT_PERSONA.to_string(),
+            description: "General-purpose assistant".to_string(),
+            system_prompt: BUILTIN_PROMPT.to_string(),
+            tone: None,
+            tools: Vec::new(),
+            model: None,
+        }
+    }
+
+    /// The system message the persona's turns start with
+    pub fn prompt(&self) -> String {
+        match &self.tone {
+            Some(tone) => format!("{}\n\nTone: {}", self.system_prompt.trim(), tone.trim()),
+            None => self.system_prompt.trim().to_string(),
+        }
+    }
+
+    /// `policy` narrowed to the persona's tools; a persona can take tools
+    /// away from a session but never grant ones its policy refuses
+    pub fn scope(&self, policy: ToolPolicy) -> ToolPolicy {
+        policy.narrowed(&self.tools)
+    }
+}
+
+pub(crate) fn default_persona_dir() -> PathBuf {
+    std::env::var("JAMEY_PERSONA_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from("./personas"))
+}
+
+/// Directory of saved personas
+#[derive(Debug, Clone)]
+pub struct PersonaStore {
+    dir: PathBuf,
+}
+
+impl PersonaStore {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Persona names become file names, so only plain ones are accepted
+    fn path(&self, name: &str) -> Result<PathBuf, PersonaError> {
+        let valid = !name.is_empty()
+            && name.len() <= 64
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_'));
+        if !valid {
+            return Err(PersonaError::InvalidName(name.to_string()));
+        }
+        Ok(self.dir.join(format!("{}.json", name)))
+    }
+
+    /// Saved personas by name, with the built-in `default` if it hasn't
+    /// been overridden
+    pub async fn list(&self) -> Result<Vec<Persona>, PersonaError> {
+        let mut personas = Vec::new();
+        match tokio::fs::read_dir(&self.dir).await {
+            Ok(mut entries) => {
+                while let Some(entry) = entries.next_entry().await? {
+                    let path = entry.path();
+                    if path.extension().and_then(|e| e.to_str()) != Some("json") {
+                        continue;
+                    }
+                    match serde_json::from_slice(&tokio::fs::read(&path).await?) {
+                        Ok(persona) => personas.push(persona),
+                        Err(e) => tracing::warn!("Skipping unreadable persona {}: {}", path.display(), e),
+                    }
+                }
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e.into()),
+        }
+        if !personas.iter().any(|p: &Persona| p.name == DEFAULT_PERSONA) {
+            personas.push(Persona::builtin());
+        }
+        personas.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(personas)
+    }
+
+    pub async fn get(&self, name: &str) -> Result<Persona, PersonaError> {
+        match tokio::fs::read(self.path(name)?).await {
+            Ok(bytes) => Ok(serde_json::from_slice(&bytes)?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                if name == DEFAULT_PERSONA {
+                    Ok(Persona::builtin())
+                } else {
+                    Err(PersonaError::NotFound(name.to_string()))
+                }
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Create `persona`, or replace the one with its name
+    pub async fn save(&self, persona: &Persona) -> Result<(), PersonaError> {
+        let path = self.path(&persona.name)?;
+        if persona.system_prompt.trim().is_empty() {
+            return Err(PersonaError::EmptyPrompt(persona.name.clone()));
+        }
+        tokio::fs::create_dir_all(&self.dir).await?;
+        let tmp = path.with_extension("json.tmp");
+        tokio::fs::write(&tmp, serde_json::to_vec_pretty(persona)?).await?;
+        tokio::fs::rename(&tmp, &path).await?;
+        Ok(())
+    }
+
+    /// Delete a saved persona; deleting `default` restores the built-in one
+    pub async fn delete(&self, name: &str) -> Result<(), PersonaError> {
+        match tokio::fs::remove_file(self.path(name)?).await {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Err(PersonaError::NotFound(name.to_string())),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// `name`, or the configured fallback when no persona was picked. A
+    /// configured fallback that has gone missing is logged and the
+    /// built-in persona used, so turns keep working.
+    pub async fn resolve(&self, name: Option<&str>) -> Persona {
+        let name = name.unwrap_or(DEFAULT_PERSONA);
+        match self.get(name).await {
+            Ok(persona) => persona,
+            Err(e) => {
+                tracing::warn!("Using the built-in persona: {}", e);
+                Persona::builtin()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_persona_store_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = PersonaStore::new(dir.path());
+        assert_eq!(store.get(DEFAULT_PERSONA).await.unwrap(), Persona::builtin());
+        assert!(matches!(store.get("ops").await, Err(PersonaError::NotFound(_))));
+        assert!(matches!(store.get("../etc").await, Err(PersonaError::InvalidName(_))));
+
+        let ops = Persona {
+            name: "ops".to_string(),
+            description: "On-call helper".to_string(),
+            system_prompt: "You help keep production running.".to_string(),
+            tone: Some("terse".to_string()),
+            tools: vec!["system_admin".to_string(), "network_web".to_string()],
+            model: Some("gpt-4".to_string()),
+        };
+        store.save(&ops).await.unwrap();
+        assert_eq!(store.get("ops").await.unwrap(), ops);
+        let names: Vec<_> = store.list().await.unwrap().into_iter().map(|p| p.name).collect();
+        assert_eq!(names, ["default", "ops"]);
+        assert_eq!(ops.prompt(), "You help keep production running.\n\nTone: terse");
+
+        store.delete("ops").await.unwrap();
+        assert_eq!(store.resolve(Some("ops")).await, Persona::builtin());
+    }
+
+    #[test]
+    fn test_scope_only_narrows() {
+        let ops = Persona {
+            tools: vec!["system_admin".to_string(), "network_web".to_string()],
+            ..Persona::builtin()
+        };
+        let scoped = ops.scope(ToolPolicy::unrestricted());
+        assert_eq!(scoped.allowed, ["system_admin", "network_web"]);
+
+        let session = ToolPolicy {
+            allowed: vec!["network_web".to_string(), "github".to_string()],
+            ..ToolPolicy::unrestricted()
+        };
+        assert_eq!(ops.scope(session).allowed, ["network_web"]);
+
+        let disjoint = ToolPolicy {
+            allowed: vec!["github".to_string()],
+            ..ToolPolicy::unrestricted()
+        };
+        let scoped = ops.scope(disjoint);
+        assert!(!scoped.allowed.is_empty() && !scoped.allowed.contains(&"github".to_string()));
+        assert_eq!(Persona::builtin().scope(ToolPolicy::unrestricted()), ToolPolicy::unrestricted());
+    }
+}
@@ -0,0 +1,669 @@
+//! Project watch mode
+//!
+//! `jamey watch <dir>` keeps a project's files indexed as memories in a
+//! `project:<name>` namespace and re-ingests them as they change. The index,
+//! a log of recent changes and the project's background session ID are kept
+//! in `<project_dir>/<key>.json`, so `jamey ask --context project` can use
+//! them from another process while the watcher runs.
+
+use crate::ingest::{IngestError, IngestOptions};
+use crate::session_store::{SessionRecord, SessionStoreError};
+use crate::state::RuntimeState;
+use chrono::{DateTime, Utc};
+use jamey_core::memory::{Memory, MemoryStore};
+use jamey_protocol::Message;
+use jamey_providers::openrouter::LlmProvider;
+use notify::{EventKind, RecursiveMode, Watcher};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use thiserror::Error;
+use tokio::sync::{broadcast, mpsc};
+use uuid::Uuid;
+
+/// Changes kept in the project's change log
+const MAX_RECENT_CHANGES: usize = 100;
+
+/// Project memories pulled into a `--context project` prompt
+const CONTEXT_MEMORIES: usize = 6;
+
+/// Build output and vendored dependencies, never worth indexing
+const IGNORED_DIRS: &[&str] = &["target", "node_modules", "dist", "build", "__pycache__", "venv"];
+
+#[derive(Debug, Error)]
+pub enum ProjectError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Serialization error: {0}")]
+    Serialization(#[from] serde_json::Error),
+    #[error("File watcher error: {0}")]
+    Watch(#[from] notify::Error),
+    #[error("Ingest error: {0}")]
+    Ingest(#[from] IngestError),
+    #[error("Session error: {0}")]
+    Session(#[from] SessionStoreError),
+    #[error("Not a directory: {0}")]
+    NotADirectory(PathBuf),
+    #[error(transparent)]
+    Policy(#[from] jamey_tools::path_policy::PathPolicyError),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeKind {
+    Added,
+    Modified,
+    Removed,
+}
+
+impl std::fmt::Display for ChangeKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ChangeKind::Added => write!(f, "added"),
+            ChangeKind::Modified => write!(f, "modified"),
+            ChangeKind::Removed => write!(f, "removed"),
+        }
+    }
+}
+
+/// One file the watcher re-indexed or dropped
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FileChange {
+    /// Relative to the project root
+    pub path: String,
+    pub kind: ChangeKind,
+    pub at: DateTime<Utc>,
+    /// Chunks stored for the new contents (0 when removed)
+    pub chunks: usize,
+}
+
+/// A file's memories as of its last ingest
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexedFile {
+    pub modified: DateTime<Utc>,
+    pub memory_ids: Vec<Uuid>,
+}
+
+/// Everything known about a watched project
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectState {
+    /// Canonical project root
+    pub root: PathBuf,
+    pub namespace: String,
+    /// Background session that `ask --context project` continues
+    pub session_id: Uuid,
+    pub created_at: DateTime<Utc>,
+    pub last_sync: Option<DateTime<Utc>>,
+    /// Indexed files keyed by path relative to the root
+    pub files: BTreeMap<String, IndexedFile>,
+    /// Oldest first
+    pub recent_changes: VecDeque<FileChange>,
+}
+
+impl ProjectState {
+    pub fn new(root: PathBuf) -> Self {
+        let name = root
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "root".to_string());
+        Self {
+            root,
+            namespace: format!("project:{}", name),
+            session_id: Uuid::new_v4(),
+            created_at: Utc::now(),
+            last_sync: None,
+            files: BTreeMap::new(),
+            recent_changes: VecDeque::new(),
+        }
+    }
+
+    fn record(&mut self, change: FileChange) {
+        self.recent_changes.push_back(change);
+        while self.recent_changes.len() > MAX_RECENT_CHANGES {
+            self.recent_changes.pop_front();
+        }
+    }
+
+    /// Newest `limit` changes, most recent first, collapsed to the latest
+    /// change per file
+    pub fn latest_changes(&self, limit: usize) -> Vec<&FileChange> {
+        let mut seen = BTreeSet::new();
+        self.recent_changes
+            .iter()
+            .rev()
+            .filter(|c| seen.insert(c.path.as_str()))
+            .take(limit)
+            .collect()
+    }
+
+    /// Plain-text overview of the project for a system prompt
+    pub fn context_summary(&self, limit: usize) -> String {
+        let mut out = format!(
+            "You are helping with the project at {} ({} files indexed",
+            self.root.display(),
+            self.files.len()
+        );
+        if let Some(last_sync) = self.last_sync {
+            out.push_str(&format!(", last synced {}", last_sync.format("%Y-%m-%d %H:%M UTC")));
+        }
+        out.push_str(").");
+
+        let changes = self.latest_changes(limit);
+        if changes.is_empty() {
+            out.push_str("\nNo file changes have been seen since watching started.");
+        } else {
+            out.push_str("\nRecent file changes, newest first:");
+            for change in changes {
+                out.push_str(&format!(
+                    "\n- {} {} ({})",
+                    change.kind,
+                    change.path,
+                    change.at.format("%Y-%m-%d %H:%M:%S UTC")
+                ));
+            }
+        }
+        out
+    }
+
+    /// [`context_summary`](Self::context_summary) followed by the best of
+    /// `memories` (search results, best first) that belong to this project
+    pub fn pack_context(&self, memories: &[Memory]) -> String {
+        let mut context = self.context_summary(20);
+        let relevant: Vec<_> = memories
+            .iter()
+            .filter(|m| m.metadata.get("namespace").and_then(|n| n.as_str()) == Some(self.namespace.as_str()))
+            .take(CONTEXT_MEMORIES)
+            .collect();
+        if !relevant.is_empty() {
+            context.push_str("\n\nRelevant project excerpts:");
+            for memory in relevant {
+                let source = memory
+                    .metadata
+                    .get("source")
+                    .and_then(|s| s.as_str())
+                    .map(|s| relative_path(&self.root, Path::new(s)))
+                    .unwrap_or_else(|| "unknown".to_string());
+                context.push_str(&format!("\n\n--- {} ---\n{}", source, memory.content));
+            }
+        }
+        context
+    }
+}
+
+/// Directory of project states shared by the watcher and `jamey ask`
+#[derive(Debug, Clone)]
+pub struct ProjectStore {
+    dir: PathBuf,
+}
+
+impl ProjectStore {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Store at `JAMEY_PROJECT_DIR`, or `./projects` when unset
+    pub fn from_env() -> Self {
+        Self::new(default_project_dir())
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn path(&self, root: &Path) -> PathBuf {
+        self.dir.join(format!("{}.json", project_key(root)))
+    }
+
+    /// State for the canonical `root`, if it has been watched before
+    pub async fn load(&self, root: &Path) -> Result<Option<ProjectState>, ProjectError> {
+        match tokio::fs::read(self.path(root)).await {
+            Ok(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Write a state atomically (temp file + rename)
+    pub async fn save(&self, state: &ProjectState) -> Result<(), ProjectError> {
+        tokio::fs::create_dir_all(&self.dir).await?;
+        let path = self.path(&state.root);
+        let tmp = path.with_extension("json.tmp");
+        tokio::fs::write(&tmp, serde_json::to_vec_pretty(state)?).await?;
+        tokio::fs::rename(&tmp, &path).await?;
+        Ok(())
+    }
+
+    /// The innermost watched project containing `path`
+    pub async fn find_for(&self, path: &Path) -> Result<Option<ProjectState>, ProjectError> {
+        let path = tokio::fs::canonicalize(path).await?;
+        let mut entries = match tokio::fs::read_dir(&self.dir).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut best: Option<ProjectState> = None;
+        while let Some(entry) = entries.next_entry().await? {
+            let file = entry.path();
+            if file.extension().and_then(|e| e.to_str()) != Some("json") {
+                continue;
+            }
+            let state: ProjectState = match tokio::fs::read(&file)
+                .await
+                .map_err(ProjectError::from)
+                .and_then(|bytes| Ok(serde_json::from_slice(&bytes)?))
+            {
+                Ok(state) => state,
+                Err(e) => {
+                    tracing::warn!("Skipping unreadable project state {}: {}", file.display(), e);
+                    continue;
+                }
+            };
+            let deeper = best
+                .as_ref()
+                .is_none_or(|b| state.root.components().count() > b.root.components().count());
+            if path.starts_with(&state.root) && deeper {
+                best = Some(state);
+            }
+        }
+        Ok(best)
+    }
+}
+
+/// Tuning for [`RuntimeState::watch_project`]
+#[derive(Debug, Clone)]
+pub struct WatchOptions {
+    /// Chunking and tags; the namespace is always the project's
+    pub ingest: IngestOptions,
+    /// Quiet period after the last event before changed files are re-ingested
+    pub debounce: Duration,
+    /// Extra directory or file names to skip, on top of hidden entries and
+    /// build output
+    pub ignore: Vec<String>,
+}
+
+impl Default for WatchOptions {
+    fn default() -> Self {
+        Self {
+            ingest: IngestOptions::default(),
+            debounce: Duration::from_millis(1500),
+            ignore: Vec::new(),
+        }
+    }
+}
+
+/// Progress reported while watching
+#[derive(Debug, Clone)]
+pub enum WatchUpdate {
+    /// The initial scan finished and file events are being watched
+    Ready {
+        namespace: String,
+        session_id: Uuid,
+        indexed: usize,
+        changed: usize,
+    },
+    Changed(FileChange),
+    /// A file could not be indexed; the watcher keeps going
+    Skipped { path: String, reason: String },
+}
+
+impl RuntimeState {
+    /// Index `root`, then keep its memories current until `shutdown` fires.
+    /// Returns the final project state.
+    pub async fn watch_project(
+        &self,
+        root: &Path,
+        options: &WatchOptions,
+        updates: mpsc::UnboundedSender<WatchUpdate>,
+        mut shutdown: broadcast::Receiver<()>,
+    ) -> Result<ProjectState, ProjectError> {
+        let root = tokio::fs::canonicalize(root).await?;
+        if !root.is_dir() {
+            return Err(ProjectError::NotADirectory(root));
+        }
+        self.path_policy.check_read(&root)?;
+
+        let mut state = match self.project_store.load(&root).await? {
+            Some(state) => state,
+            None => ProjectState::new(root.clone()),
+        };
+        self.ensure_project_session(&state).await?;
+
+        // Start watching before the scan so edits made during it aren't lost
+        let (event_tx, mut events) = mpsc::unbounded_channel();
+        let mut watcher = notify::recommended_watcher(move |event| {
+            let _ = event_tx.send(event);
+        })?;
+        watcher.watch(&root, RecursiveMode::Recursive)?;
+
+        let changed = self.sync_project(&mut state, options, &updates).await?;
+        let _ = updates.send(WatchUpdate::Ready {
+            namespace: state.namespace.clone(),
+            session_id: state.session_id,
+            indexed: state.files.len(),
+            changed,
+        });
+
+        let mut pending: BTreeSet<PathBuf> = BTreeSet::new();
+        loop {
+            let quiet = tokio::time::sleep(options.debounce);
+            tokio::select! {
+                _ = shutdown.recv() => break,
+                event = events.recv() => match event {
+                    Some(Ok(event)) => {
+                        if !matches!(event.kind, EventKind::Access(_)) {
+                            pending.extend(event.paths.into_iter().filter(|p| {
+                                !is_ignored(&root, p, &options.ignore) && !self.path_policy.is_denied(p)
+                            }));
+                        }
+                    }
+                    Some(Err(e)) => tracing::warn!("File watcher error: {}", e),
+                    None => break,
+                },
+                _ = quiet, if !pending.is_empty() => {
+                    for path in std::mem::take(&mut pending) {
+                        self.refresh_file(&mut state, &path, options, &updates).await;
+                    }
+                    state.last_sync = Some(Utc::now());
+                    self.project_store.save(&state).await?;
+                }
+            }
+        }
+
+        self.project_store.save(&state).await?;
+        Ok(state)
+    }
+
+    /// Re-ingest files modified since the last run and drop deleted ones;
+    /// returns the number of files that changed
+    async fn sync_project(
+        &self,
+        state: &mut ProjectState,
+        options: &WatchOptions,
+        updates: &mpsc::UnboundedSender<WatchUpdate>,
+    ) -> Result<usize, ProjectError> {
+        let root = state.root.clone();
+        let mut files = Vec::new();
+        walk_project(&root, &root, &options.ignore, &mut files)?;
+        // Denied files are never indexed, and lose what was indexed before
+        files.retain(|path| !self.path_policy.is_denied(path));
+
+        let mut changed = 0;
+        let present: BTreeSet<String> = files.iter().map(|p| relative_path(&root, p)).collect();
+        let removed: Vec<String> = state
+            .files
+            .keys()
+            .filter(|path| !present.contains(*path))
+            .cloned()
+            .collect();
+        for path in removed {
+            self.refresh_file(state, &root.join(&path), options, updates).await;
+            changed += 1;
+        }
+
+        for path in files {
+            let unchanged = match (state.files.get(&relative_path(&root, &path)), modified_at(&path)) {
+                (Some(indexed), Some(modified)) => indexed.modified >= modified,
+                _ => false,
+            };
+            if !unchanged {
+                self.refresh_file(state, &path, options, updates).await;
+                changed += 1;
+            }
+        }
+
+        state.last_sync = Some(Utc::now());
+        self.project_store.save(state).await?;
+        Ok(changed)
+    }
+
+    /// Replace the memories for one file with its current contents, or drop
+    /// them when the file is gone
+    async fn refresh_file(
+        &self,
+        state: &mut ProjectState,
+        path: &Path,
+        options: &WatchOptions,
+        updates: &mpsc::UnboundedSender<WatchUpdate>,
+    ) {
+        let relative = relative_path(&state.root, path);
+        let previous = state.files.remove(&relative);
+        let existed = previous.is_some();
+        if let Some(previous) = previous {
+            for id in previous.memory_ids {
+                if let Err(e) = self.memory_store.delete(id).await {
+                    tracing::debug!("Could not delete stale memory {}: {}", id, e);
+                }
+            }
+        }
+
+        if !path.is_file() {
+            if existed {
+                let change = FileChange {
+                    path: relative,
+                    kind: ChangeKind::Removed,
+                    at: Utc::now(),
+                    chunks: 0,
+                };
+                state.record(change.clone());
+                let _ = updates.send(WatchUpdate::Changed(change));
+            }
+            return;
+        }
+
+        let mut ingest = options.ingest.clone();
+        ingest.namespace = Some(state.namespace.clone());
+        ingest.dry_run = false;
+        if !ingest.tags.iter().any(|t| t == "project") {
+            ingest.tags.push("project".to_string());
+        }
+
+        let report = match self.ingest(&path.to_string_lossy(), &ingest).await {
+            Ok(report) => report,
+            Err(e) => {
+                let _ = updates.send(WatchUpdate::Skipped { path: relative, reason: e.to_string() });
+                return;
+            }
+        };
+        if let Some((_, reason)) = report.skipped.first() {
+            let _ = updates.send(WatchUpdate::Skipped { path: relative, reason: reason.clone() });
+            return;
+        }
+
+        let memory_ids: Vec<Uuid> = report.sources.iter().flat_map(|s| s.memory_ids.clone()).collect();
+        if let Some(error) = report.sources.iter().flat_map(|s| &s.errors).next() {
+            tracing::warn!("Partially indexed {}: {}", relative, error);
+        }
+        let change = FileChange {
+            path: relative.clone(),
+            kind: if existed { ChangeKind::Modified } else { ChangeKind::Added },
+            at: Utc::now(),
+            chunks: memory_ids.len(),
+        };
+        state.files.insert(
+            relative,
+            IndexedFile {
+                modified: modified_at(path).unwrap_or(change.at),
+                memory_ids,
+            },
+        );
+        state.record(change.clone());
+        let _ = updates.send(WatchUpdate::Changed(change));
+    }
+
+    /// Create the project's background session on first watch
+    async fn ensure_project_session(&self, state: &ProjectState) -> Result<(), ProjectError> {
+        match self.session_store.load(state.session_id).await {
+            Ok(_) => Ok(()),
+            Err(SessionStoreError::NotFound(_)) => {
+                let mut record = SessionRecord::new(state.session_id);
+                record.title = format!("Project: {}", state.root.display());
+                self.session_store.save(&record).await?;
+                Ok(())
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// System message describing the project's recent changes and the indexed
+    /// content most relevant to `question`
+    pub async fn project_context(&self, state: &ProjectState, question: &str) -> anyhow::Result<Message> {
+        let embedding = self.llm_provider.get_embedding(question).await?;
+        let memories = self
+            .memory_store
+            .search_namespace(&state.namespace, &embedding, CONTEXT_MEMORIES)
+            .await?;
+        Ok(Message::system(state.pack_context(&memories)))
+    }
+}
+
+/// Recursively list indexable files under `dir`
+fn walk_project(root: &Path, dir: &Path, ignore: &[String], files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if is_ignored(root, &path, ignore) {
+            continue;
+        }
+        if path.is_dir() {
+            walk_project(root, &path, ignore, files)?;
+        } else if path.is_file() {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Hidden entries, build output and user-ignored names anywhere under `root`
+fn is_ignored(root: &Path, path: &Path, ignore: &[String]) -> bool {
+    let Ok(relative) = path.strip_prefix(root) else {
+        return true;
+    };
+    relative.components().any(|c| {
+        let name = c.as_os_str().to_string_lossy();
+        name.starts_with('.') || IGNORED_DIRS.contains(&name.as_ref()) || ignore.iter().any(|i| *i == name)
+    })
+}
+
+fn relative_path(root: &Path, path: &Path) -> String {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+fn modified_at(path: &Path) -> Option<DateTime<Utc>> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok().map(DateTime::from)
+}
+
+/// File name for a project: its directory name plus an FNV-1a hash of the
+/// full path, so same-named projects don't collide
+fn project_key(root: &Path) -> String {
+    let hash = root
+        .to_string_lossy()
+        .bytes()
+        .fold(0xcbf2_9ce4_8422_2325_u64, |h, b| (h ^ u64::from(b)).wrapping_mul(0x0100_0000_01b3));
+    let name: String = root
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect();
+    format!("{}-{:016x}", name, hash)
+}
+
+pub(crate) fn default_project_dir() -> PathBuf {
+    std::env::var("JAMEY_PROJECT_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from("./projects"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn change(path: &str, kind: ChangeKind) -> FileChange {
+        FileChange {
+            path: path.to_string(),
+            kind,
+            at: Utc::now(),
+            chunks: 1,
+        }
+    }
+
+    #[test]
+    fn test_is_ignored() {
+        let root = Path::new("/work/app");
+        let ignore = vec!["fixtures".to_string()];
+        assert!(!is_ignored(root, Path::new("/work/app/src/main.rs"), &ignore));
+        assert!(is_ignored(root, Path::new("/work/app/.git/HEAD"), &ignore));
+        assert!(is_ignored(root, Path::new("/work/app/target/debug/app"), &ignore));
+        assert!(is_ignored(root, Path::new("/work/app/tests/fixtures/a.json"), &ignore));
+        assert!(is_ignored(root, Path::new("/elsewhere/main.rs"), &ignore));
+    }
+
+    #[test]
+    fn test_latest_changes_collapse_per_file() {
+        let mut state = ProjectState::new(PathBuf::from("/work/app"));
+        assert_eq!(state.namespace, "project:app");
+        state.record(change("src/a.rs", ChangeKind::Added));
+        state.record(change("src/b.rs", ChangeKind::Added));
+        state.record(change("src/a.rs", ChangeKind::Modified));
+
+        let latest = state.latest_changes(10);
+        assert_eq!(latest.len(), 2);
+        assert_eq!(latest[0].path, "src/a.rs");
+        assert_eq!(latest[0].kind, ChangeKind::Modified);
+        assert!(state.context_summary(10).contains("- modified src/a.rs"));
+
+        for i in 0..MAX_RECENT_CHANGES + 5 {
+            state.record(change(&format!("f{}", i), ChangeKind::Added));
+        }
+        assert_eq!(state.recent_changes.len(), MAX_RECENT_CHANGES);
+    }
+
+    #[test]
+    fn test_pack_context_keeps_own_namespace() {
+        let state = ProjectState::new(PathBuf::from("/work/app"));
+        let excerpt = |namespace: &str, source: &str, content: &str| Memory {
+            id: Uuid::new_v4(),
+            memory_type: jamey_core::memory::MemoryType::Knowledge,
+            content: content.to_string(),
+            embedding: vec![0.0; 4],
+            metadata: serde_json::json!({"namespace": namespace, "source": source}),
+            created_at: Utc::now(),
+            last_accessed: Utc::now(),
+        };
+        let mut memories = vec![excerpt("project:other", "/work/other/x.rs", "not ours")];
+        for i in 0..CONTEXT_MEMORIES + 2 {
+            memories.push(excerpt("project:app", &format!("/work/app/src/{}.rs", i), "ours"));
+        }
+
+        let context = state.pack_context(&memories);
+        assert!(context.starts_with("You are helping with the project at /work/app"));
+        assert!(context.contains("--- src/0.rs ---\nours"));
+        assert!(!context.contains("not ours"));
+        assert_eq!(context.matches("\n--- ").count(), CONTEXT_MEMORIES);
+        assert_eq!(state.pack_context(&[]), state.context_summary(20));
+    }
+
+    #[tokio::test]
+    async fn test_store_finds_innermost_project() {
+        let dir = TempDir::new().unwrap();
+        let outer = tokio::fs::canonicalize(dir.path()).await.unwrap();
+        let inner = outer.join("crates/core");
+        std::fs::create_dir_all(inner.join("src")).unwrap();
+
+        let store = ProjectStore::new(outer.join(".jamey-projects"));
+        assert!(store.find_for(&inner).await.unwrap().is_none());
+        store.save(&ProjectState::new(outer.clone())).await.unwrap();
+        store.save(&ProjectState::new(inner.clone())).await.unwrap();
+
+        let found = store.find_for(&inner.join("src")).await.unwrap().unwrap();
+        assert_eq!(found.root, inner);
+        assert_eq!(store.find_for(&outer).await.unwrap().unwrap().root, outer);
+        assert!(store.load(&outer).await.unwrap().is_some());
+    }
+}
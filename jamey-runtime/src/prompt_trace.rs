@@ -0,0 +1,363 @@
+//! Prompt tracing
+//!
+//! Off by default. With `prompt_trace.enabled`, each model call a chat turn
+//! makes appends one JSON line to `<trace_dir>/traces-YYYY-MM-DD.jsonl`
+//! (UTC): the full prompt, the tools offered, the completion and any tool
+//! calls, after the same redaction transcripts get. That's enough to see
+//! why a reply went wrong without turning on debug logs for everything.
+//!
+//! `sample_rate` is the share of turns traced; a sampled turn has all of its
+//! calls traced, so a trace always reads as a whole turn. Daily files older
+//! than `retention_days` are deleted once a day. `jamey traces` reads the
+//! files directly, so no running runtime is needed.
+//!
+//! ```toml
+//! [prompt_trace]
+//! enabled = true
+//! sample_rate = 0.1
+//! retention_days = 7
+//! ```
+
+use chrono::{DateTime, NaiveDate, Utc};
+use jamey_core::redaction::Redactor;
+use jamey_providers::openrouter::{ChatRequest, Message};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use thiserror::Error;
+use tokio::io::AsyncWriteExt;
+use uuid::Uuid;
+
+const FILE_PREFIX: &str = "traces-";
+const FILE_SUFFIX: &str = ".jsonl";
+
+#[derive(Debug, Error)]
+pub enum TraceError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Serialization error: {0}")]
+    Serialization(#[from] serde_json::Error),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct PromptTraceConfig {
+    pub enabled: bool,
+    /// Share of turns traced, from 0 to 1
+    pub sample_rate: f64,
+    /// Days of traces kept; 0 keeps them all
+    pub retention_days: u32,
+}
+
+impl Default for PromptTraceConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            sample_rate: 1.0,
+            retention_days: 7,
+        }
+    }
+}
+
+impl PromptTraceConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(0.0..=1.0).contains(&self.sample_rate) {
+            return Err("prompt_trace.sample_rate must be between 0 and 1".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// A tool call the model asked for
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TracedCall {
+    pub name: String,
+    /// As streamed, which isn't always valid JSON
+    pub arguments: String,
+}
+
+/// One model call
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PromptTrace {
+    pub id: Uuid,
+    pub turn_id: Uuid,
+    pub timestamp: DateTime<Utc>,
+    pub session_id: Option<Uuid>,
+    /// As requested; a fallback model may have answered instead
+    pub model: String,
+    pub messages: Vec<Message>,
+    /// Names of the tools offered
+    #[serde(default)]
+    pub tools: Vec<String>,
+    pub temperature: Option<f32>,
+    pub completion: String,
+    #[serde(default)]
+    pub tool_calls: Vec<TracedCall>,
+    pub latency_ms: u64,
+    /// Why the call failed, when it did
+    pub error: Option<String>,
+}
+
+impl PromptTrace {
+    pub fn new(turn_id: Uuid, session_id: Option<Uuid>, request: ChatRequest, latency: Duration) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            turn_id,
+            timestamp: Utc::now(),
+            session_id,
+            model: request.model,
+            messages: request.messages,
+            tools: request.tools.unwrap_or_default().into_iter().map(|tool| tool.name).collect(),
+            temperature: request.temperature,
+            completion: String::new(),
+            tool_calls: Vec::new(),
+            latency_ms: latency.as_millis() as u64,
+            error: None,
+        }
+    }
+}
+
+/// Which traces [`PromptTraceLog::query`] returns
+#[derive(Debug, Clone, Default)]
+pub struct TraceQuery {
+    pub since: Option<DateTime<Utc>>,
+    pub session_id: Option<Uuid>,
+    pub turn_id: Option<Uuid>,
+}
+
+impl TraceQuery {
+    fn matches(&self, trace: &PromptTrace) -> bool {
+        self.since.is_none_or(|since| trace.timestamp >= since)
+            && self.session_id.is_none_or(|id| trace.session_id == Some(id))
+            && self.turn_id.is_none_or(|id| trace.turn_id == id)
+    }
+}
+
+/// Append-only trace files shared between the runtime and the CLI
+#[derive(Debug, Clone)]
+pub struct PromptTraceLog {
+    dir: PathBuf,
+    config: PromptTraceConfig,
+    redactor: Arc<Redactor>,
+}
+
+impl PromptTraceLog {
+    pub fn new(dir: impl Into<PathBuf>, config: PromptTraceConfig) -> Self {
+        Self {
+            dir: dir.into(),
+            config,
+            redactor: Arc::new(Redactor::disabled()),
+        }
+    }
+
+    /// Redact traces before they're written
+    pub fn with_redactor(mut self, redactor: Arc<Redactor>) -> Self {
+        self.redactor = redactor;
+        self
+    }
+
+    /// Traces at `JAMEY_TRACE_DIR`, or `./traces` when unset, for reading
+    pub fn from_env() -> Self {
+        Self::new(default_trace_dir(), PromptTraceConfig::default())
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Whether the calls of turn `turn_id` are traced. Decided from the
+    /// turn's id, so every call in it gets the same answer.
+    pub fn samples(&self, turn_id: Uuid) -> bool {
+        if !self.config.enabled {
+            return false;
+        }
+        // The top two bits of the low half are the UUID variant, always 10
+        let random = u64::MAX >> 2;
+        let (_, low) = turn_id.as_u64_pair();
+        ((low & random) as f64 / random as f64) < self.config.sample_rate
+    }
+
+    fn path(&self, day: NaiveDate) -> PathBuf {
+        self.dir.join(format!("{}{}{}", FILE_PREFIX, day.format("%Y-%m-%d"), FILE_SUFFIX))
+    }
+
+    pub async fn record(&self, trace: &PromptTrace) -> Result<(), TraceError> {
+        tokio::fs::create_dir_all(&self.dir).await?;
+        let mut value = serde_json::to_value(trace)?;
+        self.redactor.redact_json(&mut value);
+        let mut line = serde_json::to_vec(&value)?;
+        line.push(b'\n');
+
+        // One write per line keeps concurrent appends from interleaving
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.path(trace.timestamp.date_naive()))
+            .await?;
+        file.write_all(&line).await?;
+        Ok(())
+    }
+
+    /// Traces `query` matches, oldest first
+    pub async fn query(&self, query: &TraceQuery) -> Result<Vec<PromptTrace>, TraceError> {
+        let mut traces = Vec::new();
+        for (day, path) in self.files().await? {
+            if query.since.is_some_and(|since| day < since.date_naive()) {
+                continue;
+            }
+            let contents = tokio::fs::read_to_string(&path).await?;
+            for (number, line) in contents.lines().enumerate() {
+                if line.trim().is_empty() {
+                    continue;
+                }
+                match serde_json::from_str::<PromptTrace>(line) {
+                    Ok(trace) if query.matches(&trace) => traces.push(trace),
+                    Ok(_) => {}
+                    // A crash mid-append leaves a partial last line
+                    Err(e) => tracing::warn!("Skipping {}:{}: {}", path.display(), number + 1, e),
+                }
+            }
+        }
+        traces.sort_by_key(|trace| trace.timestamp);
+        Ok(traces)
+    }
+
+    pub async fn get(&self, id: Uuid) -> Result<Option<PromptTrace>, TraceError> {
+        Ok(self.query(&TraceQuery::default()).await?.into_iter().find(|trace| trace.id == id))
+    }
+
+    /// Delete the daily files older than `retention_days`; returns how many
+    pub async fn apply_retention(&self) -> Result<usize, TraceError> {
+        if self.config.retention_days == 0 {
+            return Ok(0);
+        }
+        let oldest = Utc::now().date_naive() - chrono::Days::new(u64::from(self.config.retention_days) - 1);
+        let mut removed = 0;
+        for (day, path) in self.files().await? {
+            if day < oldest {
+                tokio::fs::remove_file(&path).await?;
+                removed += 1;
+            }
+        }
+        Ok(removed)
+    }
+
+    /// Remove every trace of one of `sessions`; returns how many there were
+    pub async fn forget_sessions(&self, sessions: &HashSet<Uuid>, dry_run: bool) -> Result<usize, TraceError> {
+        let mut removed = 0;
+        if sessions.is_empty() {
+            return Ok(removed);
+        }
+        for (_, path) in self.files().await? {
+            removed += crate::usage::strip_sessions(&path, sessions, dry_run).await?;
+        }
+        Ok(removed)
+    }
+
+    /// Daily files, oldest first
+    async fn files(&self) -> Result<Vec<(NaiveDate, PathBuf)>, TraceError> {
+        let mut files = Vec::new();
+        let mut entries = match tokio::fs::read_dir(&self.dir).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(files),
+            Err(e) => return Err(e.into()),
+        };
+        while let Some(entry) = entries.next_entry().await? {
+            let name = entry.file_name();
+            let day = name
+                .to_str()
+                .and_then(|n| n.strip_prefix(FILE_PREFIX)?.strip_suffix(FILE_SUFFIX))
+                .and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok());
+            if let Some(day) = day {
+                files.push((day, entry.path()));
+            }
+        }
+        files.sort();
+        Ok(files)
+    }
+}
+
+pub(crate) fn default_trace_dir() -> PathBuf {
+    std::env::var("JAMEY_TRACE_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from("./traces"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jamey_core::redaction::RedactionConfig;
+    use tempfile::TempDir;
+
+    fn trace(session_id: Option<Uuid>, prompt: &str) -> PromptTrace {
+        let request = ChatRequest {
+            model: "openai/gpt-4o".to_string(),
+            messages: vec![Message { role: "user".to_string(), content: prompt.to_string() }],
+            tools: None,
+            tool_choice: None,
+            temperature: Some(0.7),
+            max_tokens: None,
+        };
+        let mut trace = PromptTrace::new(Uuid::new_v4(), session_id, request, Duration::from_millis(40));
+        trace.completion = "Done.".to_string();
+        trace
+    }
+
+    #[tokio::test]
+    async fn test_record_redacts_and_queries() {
+        let dir = TempDir::new().unwrap();
+        let redactor = Redactor::from_config(&RedactionConfig::default()).unwrap();
+        let log = PromptTraceLog::new(dir.path(), PromptTraceConfig { enabled: true, ..Default::default() })
+            .with_redactor(Arc::new(redactor));
+        let (session, other) = (Uuid::new_v4(), Uuid::new_v4());
+
+        let secret = trace(Some(session), "my key is sk-ant-REDACTED");
+        log.record(&secret).await.unwrap();
+        log.record(&trace(Some(other), "hello")).await.unwrap();
+
+        let found = log.query(&TraceQuery { session_id: Some(session), ..Default::default() }).await.unwrap();
+        assert_eq!(found.len(), 1);
+        assert!(!found[0].messages[0].content.contains("abcdefghijklmnop"));
+        assert_eq!(log.get(secret.id).await.unwrap().unwrap().turn_id, secret.turn_id);
+
+        let sessions = HashSet::from([session]);
+        assert_eq!(log.forget_sessions(&sessions, false).await.unwrap(), 1);
+        assert_eq!(log.query(&TraceQuery::default()).await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_retention_drops_old_days() {
+        let dir = TempDir::new().unwrap();
+        let log = PromptTraceLog::new(dir.path(), PromptTraceConfig { retention_days: 2, ..Default::default() });
+        let mut old = trace(None, "old");
+        old.timestamp = Utc::now() - chrono::Duration::days(3);
+        log.record(&old).await.unwrap();
+        log.record(&trace(None, "new")).await.unwrap();
+
+        assert_eq!(log.apply_retention().await.unwrap(), 1);
+        let left = log.query(&TraceQuery::default()).await.unwrap();
+        assert_eq!(left.len(), 1);
+        assert_eq!(left[0].messages[0].content, "new");
+    }
+
+    #[test]
+    fn test_sampling() {
+        let disabled = PromptTraceLog::new("traces", PromptTraceConfig::default());
+        assert!(!disabled.samples(Uuid::new_v4()));
+
+        let config = |sample_rate| PromptTraceConfig { enabled: true, sample_rate, retention_days: 7 };
+        let all = PromptTraceLog::new("traces", config(1.0));
+        let none = PromptTraceLog::new("traces", config(0.0));
+        let half = PromptTraceLog::new("traces", config(0.5));
+        let turn = Uuid::new_v4();
+        assert!(all.samples(turn));
+        assert!(!none.samples(turn));
+        assert_eq!(half.samples(turn), half.samples(turn));
+        let sampled = (0..1000).filter(|_| half.samples(Uuid::new_v4())).count();
+        assert!((350..650).contains(&sampled), "sampled {} of 1000", sampled);
+        assert!(config(1.5).validate().is_err());
+    }
+}
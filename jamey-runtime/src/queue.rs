@@ -0,0 +1,424 @@
+//! Request queue
+//!
+//! Every chat turn, and each model call made by background work such as
+//! ingestion, briefings and evaluations, takes a slot in one queue before
+//! it runs. At most `max_concurrent` run at once. When a slot frees up,
+//! interactive requests waiting for one go before background ones, and
+//! background work never holds the last `interactive_reserved` slots, so a
+//! bulk ingestion job can't starve chat.
+//!
+//! The queue is bounded: past `max_queued` waiting requests, or
+//! `max_per_session` requests for one session, new interactive requests are
+//! turned away with [`QueueFull`], which the HTTP endpoints answer as a 429
+//! carrying the same fields. Background work waits for room instead.
+//!
+//! ```toml
+//! [queue]
+//! max_concurrent = 8
+//! max_queued = 64
+//! max_per_session = 2
+//! interactive_reserved = 2
+//! ```
+
+use parking_lot::Mutex;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::oneshot;
+use uuid::Uuid;
+
+/// Suggested wait before retrying a rejected request
+const RETRY_AFTER: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct QueueConfig {
+    /// Requests running at once (`JAMEY_QUEUE_CONCURRENCY`)
+    pub max_concurrent: usize,
+    /// Requests waiting for a slot before new ones are rejected
+    /// (`JAMEY_QUEUE_LIMIT`)
+    pub max_queued: usize,
+    /// Requests one session may have running or waiting
+    pub max_per_session: usize,
+    /// Slots background work leaves free for interactive requests
+    pub interactive_reserved: usize,
+}
+
+impl Default for QueueConfig {
+    fn default() -> Self {
+        Self {
+            max_concurrent: 8,
+            max_queued: 64,
+            max_per_session: 2,
+            interactive_reserved: 2,
+        }
+    }
+}
+
+impl QueueConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.max_concurrent == 0 || self.max_per_session == 0 {
+            return Err("queue.max_concurrent and queue.max_per_session must be at least 1".to_string());
+        }
+        if self.interactive_reserved >= self.max_concurrent {
+            return Err("queue.interactive_reserved must be less than queue.max_concurrent".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Which requests go first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Priority {
+    /// Someone is waiting on the reply
+    Interactive,
+    /// Ingestion, scheduled jobs, evaluations
+    Background,
+}
+
+/// What was full when a request was turned away
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QueueScope {
+    Global,
+    Session,
+}
+
+/// A request turned away because the queue is full
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct QueueFull {
+    pub scope: QueueScope,
+    pub limit: usize,
+    pub retry_after_secs: u64,
+}
+
+impl std::fmt::Display for QueueFull {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let what = match self.scope {
+            QueueScope::Global => "requests waiting",
+            QueueScope::Session => "requests in this session",
+        };
+        write!(f, "Too many {} ({}); retry in {}s", what, self.limit, self.retry_after_secs)
+    }
+}
+
+impl std::error::Error for QueueFull {}
+
+impl QueueFull {
+    /// HTTP status to answer with
+    pub fn status(&self) -> u16 {
+        429
+    }
+
+    /// Response body: the error's fields plus a message
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "error",
+            "error": "queue_full",
+            "message": self.to_string(),
+            "scope": self.scope,
+            "limit": self.limit,
+            "retry_after_secs": self.retry_after_secs,
+        })
+    }
+}
+
+/// Requests running and waiting, for status reports
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QueueStats {
+    pub running: usize,
+    pub running_background: usize,
+    pub waiting_interactive: usize,
+    pub waiting_background: usize,
+}
+
+/// The runtime's request queue; clones share it
+#[derive(Clone)]
+pub struct RequestQueue {
+    shared: Arc<Shared>,
+}
+
+struct Shared {
+    config: QueueConfig,
+    inner: Mutex<Inner>,
+}
+
+#[derive(Default)]
+struct Inner {
+    next_id: u64,
+    running: usize,
+    running_background: usize,
+    interactive: VecDeque<Waiter>,
+    background: VecDeque<Waiter>,
+    /// Requests running or waiting per session
+    sessions: HashMap<Uuid, usize>,
+}
+
+struct Waiter {
+    id: u64,
+    priority: Priority,
+    session: Option<Uuid>,
+    grant: oneshot::Sender<QueuePermit>,
+}
+
+/// A place in the queue; [`ready`](Self::ready) resolves once it is this
+/// request's turn to run. Dropping it gives up the place.
+pub struct Ticket {
+    id: u64,
+    receiver: oneshot::Receiver<QueuePermit>,
+    shared: Arc<Shared>,
+}
+
+/// A running request's slot, freed when dropped
+pub struct QueuePermit {
+    priority: Priority,
+    session: Option<Uuid>,
+    shared: Arc<Shared>,
+}
+
+impl RequestQueue {
+    pub fn new(config: QueueConfig) -> Self {
+        Self {
+            shared: Arc::new(Shared {
+                config,
+                inner: Mutex::new(Inner::default()),
+            }),
+        }
+    }
+
+    /// Take a place in the queue, or be turned away if it's full. A request
+    /// for a session counts against that session until its permit is dropped.
+    pub fn enter(&self, priority: Priority, session: Option<Uuid>) -> Result<Ticket, QueueFull> {
+        let config = &self.shared.config;
+        let mut inner = self.shared.inner.lock();
+        if let Some(id) = session {
+            if inner.sessions.get(&id).copied().unwrap_or(0) >= config.max_per_session {
+                return Err(QueueFull {
+                    scope: QueueScope::Session,
+                    limit: config.max_per_session,
+                    retry_after_secs: RETRY_AFTER.as_secs(),
+                });
+            }
+        }
+        if inner.interactive.len() + inner.background.len() >= config.max_queued && !inner.has_slot(config, priority) {
+            return Err(QueueFull {
+                scope: QueueScope::Global,
+                limit: config.max_queued,
+                retry_after_secs: RETRY_AFTER.as_secs(),
+            });
+        }
+
+        if let Some(id) = session {
+            *inner.sessions.entry(id).or_default() += 1;
+        }
+        inner.next_id += 1;
+        let id = inner.next_id;
+        let (grant, receiver) = oneshot::channel();
+        let waiter = Waiter { id, priority, session, grant };
+        match priority {
+            Priority::Interactive => inner.interactive.push_back(waiter),
+            Priority::Background => inner.background.push_back(waiter),
+        }
+        let unclaimed = inner.dispatch(&self.shared);
+        drop(inner);
+        drop(unclaimed);
+        Ok(Ticket {
+            id,
+            receiver,
+            shared: Arc::clone(&self.shared),
+        })
+    }
+
+    /// A slot for background work, waiting for room in the queue as well as
+    /// for the slot itself; for jobs that would rather be late than fail
+    pub async fn background(&self) -> QueuePermit {
+        loop {
+            match self.enter(Priority::Background, None) {
+                Ok(ticket) => return ticket.ready().await,
+                Err(full) => tokio::time::sleep(Duration::from_secs(full.retry_after_secs)).await,
+            }
+        }
+    }
+
+    pub fn stats(&self) -> QueueStats {
+        let inner = self.shared.inner.lock();
+        QueueStats {
+            running: inner.running,
+            running_background: inner.running_background,
+            waiting_interactive: inner.interactive.len(),
+            waiting_background: inner.background.len(),
+        }
+    }
+}
+
+impl Inner {
+    fn has_slot(&self, config: &QueueConfig, priority: Priority) -> bool {
+        match priority {
+            Priority::Interactive => self.running < config.max_concurrent,
+            Priority::Background => {
+                self.running < config.max_concurrent
+                    && self.running_background < config.max_concurrent - config.interactive_reserved
+            }
+        }
+    }
+
+    /// Start waiting requests while there are slots, interactive first.
+    /// Returns permits whose ticket was dropped meanwhile; the caller drops
+    /// them once the lock is released.
+    fn dispatch(&mut self, shared: &Arc<Shared>) -> Vec<QueuePermit> {
+        let mut unclaimed = Vec::new();
+        loop {
+            let waiter = if !self.interactive.is_empty() && self.has_slot(&shared.config, Priority::Interactive) {
+                self.interactive.pop_front()
+            } else if !self.background.is_empty() && self.has_slot(&shared.config, Priority::Background) {
+                self.background.pop_front()
+            } else {
+                None
+            };
+            let Some(waiter) = waiter else {
+                return unclaimed;
+            };
+            self.running += 1;
+            if waiter.priority == Priority::Background {
+                self.running_background += 1;
+            }
+            let permit = QueuePermit {
+                priority: waiter.priority,
+                session: waiter.session,
+                shared: Arc::clone(shared),
+            };
+            if let Err(permit) = waiter.grant.send(permit) {
+                unclaimed.push(permit);
+            }
+        }
+    }
+
+    fn leave_session(&mut self, session: Option<Uuid>) {
+        let Some(id) = session else { return };
+        if let Some(count) = self.sessions.get_mut(&id) {
+            *count -= 1;
+            if *count == 0 {
+                self.sessions.remove(&id);
+            }
+        }
+    }
+}
+
+impl Ticket {
+    /// Wait for this request's slot
+    pub async fn ready(mut self) -> QueuePermit {
+        (&mut self.receiver)
+            .await
+            .expect("a queued request is granted a permit or removed by its ticket")
+    }
+}
+
+impl Drop for Ticket {
+    fn drop(&mut self) {
+        let mut inner = self.shared.inner.lock();
+        let id = self.id;
+        let position = |queue: &VecDeque<Waiter>| queue.iter().position(|w| w.id == id);
+        let waiter = match (position(&inner.interactive), position(&inner.background)) {
+            (Some(i), _) => inner.interactive.remove(i),
+            (_, Some(i)) => inner.background.remove(i),
+            // Already granted: the permit in the channel frees the slot
+            _ => None,
+        };
+        if let Some(waiter) = waiter {
+            inner.leave_session(waiter.session);
+        }
+    }
+}
+
+impl Drop for QueuePermit {
+    fn drop(&mut self) {
+        let mut inner = self.shared.inner.lock();
+        inner.running -= 1;
+        if self.priority == Priority::Background {
+            inner.running_background -= 1;
+        }
+        inner.leave_session(self.session);
+        let unclaimed = inner.dispatch(&self.shared);
+        drop(inner);
+        drop(unclaimed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn queue(max_concurrent: usize, max_queued: usize) -> RequestQueue {
+        RequestQueue::new(QueueConfig {
+            max_concurrent,
+            max_queued,
+            max_per_session: 2,
+            interactive_reserved: 1,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_interactive_goes_first_and_keeps_reserved_slots() {
+        let queue = queue(2, 8);
+        // Background work may only use one of the two slots
+        let first = queue.enter(Priority::Background, None).unwrap().ready().await;
+        let waiting_background = queue.enter(Priority::Background, None).unwrap();
+        let chat = queue.enter(Priority::Interactive, None).unwrap().ready().await;
+        assert_eq!(queue.stats().waiting_background, 1);
+
+        let waiting_chat = queue.enter(Priority::Interactive, None).unwrap();
+        drop(first);
+        // The freed slot goes to the chat request that arrived later
+        let _second_chat = waiting_chat.ready().await;
+        assert_eq!(queue.stats().waiting_background, 1);
+
+        drop(chat);
+        let _background = waiting_background.ready().await;
+        assert_eq!(queue.stats().running_background, 1);
+    }
+
+    #[tokio::test]
+    async fn test_rejects_when_full() {
+        let queue = queue(1, 1);
+        let session = Uuid::new_v4();
+        let _running = queue.enter(Priority::Interactive, Some(session)).unwrap().ready().await;
+        let _waiting = queue.enter(Priority::Interactive, Some(session)).unwrap();
+
+        let full = queue.enter(Priority::Interactive, None).err().unwrap();
+        assert_eq!(full.scope, QueueScope::Global);
+        assert_eq!(full.status(), 429);
+        assert_eq!(full.to_json()["error"], "queue_full");
+
+        let busy = queue.enter(Priority::Interactive, Some(session)).err().unwrap();
+        assert_eq!(busy.scope, QueueScope::Session);
+    }
+
+    #[tokio::test]
+    async fn test_dropped_tickets_give_up_their_place() {
+        let queue = queue(1, 4);
+        let session = Uuid::new_v4();
+        let running = queue.enter(Priority::Interactive, None).unwrap().ready().await;
+        let abandoned = queue.enter(Priority::Interactive, Some(session)).unwrap();
+        let next = queue.enter(Priority::Interactive, None).unwrap();
+        drop(abandoned);
+        assert_eq!(queue.stats().waiting_interactive, 1);
+
+        drop(running);
+        let _next = next.ready().await;
+        // The session's count was released with the abandoned ticket
+        assert!(queue.enter(Priority::Interactive, Some(session)).is_ok());
+    }
+
+    #[test]
+    fn test_config_validation() {
+        assert!(QueueConfig::default().validate().is_ok());
+        let config = QueueConfig {
+            interactive_reserved: 8,
+            ..QueueConfig::default()
+        };
+        assert!(config.validate().is_err());
+    }
+}
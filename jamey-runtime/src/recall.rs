@@ -0,0 +1,167 @@
+//! Memory recall for chat turns
+//!
+//! Before a turn the latest user message is embedded and the best ranked
+//! memories at or above `memory.vector_similarity_threshold` are put in the
+//! prompt as numbered notes. Ranking is the store's, which weighs recency,
+//! importance and access frequency as well as similarity
+//! (`[memory.retrieval]`). The same list comes back on the reply as its
+//! [`Citation`]s, so `[n]` in an answer can be traced to the memory it came
+//! from. Pinned memories come first in every turn, whatever the question,
+//! as far as their token budget allows.
+
+use crate::ingest::estimate_tokens;
+use jamey_core::memory::{cosine_similarity, Memory};
+use jamey_protocol::{Citation, Message, Role};
+use std::collections::HashSet;
+
+/// Characters of a memory kept in its citation
+const SNIPPET_CHARS: usize = 160;
+
+/// Characters of each memory given to the model
+const PROMPT_MEMORY_CHARS: usize = 1500;
+
+/// A memory chosen for the prompt and how close it is to the question
+#[derive(Debug, Clone)]
+pub struct Recalled {
+    pub memory: Memory,
+    pub similarity: f32,
+}
+
+impl Recalled {
+    pub fn citation(&self) -> Citation {
+        Citation {
+            memory_id: self.memory.id,
+            snippet: snippet(&self.memory.content),
+            similarity: self.similarity,
+        }
+    }
+}
+
+/// Text memory is searched with: the latest user message
+pub fn query(history: &[Message]) -> Option<&str> {
+    history
+        .iter()
+        .rev()
+        .find(|m| m.role == Role::User)
+        .map(|m| m.content.as_str())
+        .filter(|content| !content.trim().is_empty())
+}
+
+/// Up to `limit` of `memories` at least `threshold` similar to the query,
+/// in the order the store ranked them
+pub fn select(query_embedding: &[f32], memories: Vec<Memory>, threshold: f32, limit: usize) -> Vec<Recalled> {
+    let mut recalled: Vec<Recalled> = memories
+        .into_iter()
+        .map(|memory| Recalled {
+            similarity: cosine_similarity(query_embedding, &memory.embedding),
+            memory,
+        })
+        .filter(|r| r.similarity >= threshold)
+        .collect();
+    recalled.truncate(limit);
+    recalled
+}
+
+/// `pinned` ahead of `recalled`, keeping the oldest pins that fit in
+/// `budget` estimated tokens. A pin that was also recalled is listed once.
+pub fn with_pins(pinned: Vec<Memory>, query_embedding: Option<&[f32]>, recalled: Vec<Recalled>, budget: usize) -> Vec<Recalled> {
+    let mut selected = Vec::new();
+    let mut used = 0;
+    for memory in pinned {
+        used += estimate_tokens(&prompt_text(&memory.content));
+        if used > budget {
+            break;
+        }
+        selected.push(Recalled {
+            similarity: query_embedding.map_or(0.0, |q| cosine_similarity(q, &memory.embedding)),
+            memory,
+        });
+    }
+    let pinned: HashSet<_> = selected.iter().map(|r| r.memory.id).collect();
+    selected.extend(recalled.into_iter().filter(|r| !pinned.contains(&r.memory.id)));
+    selected
+}
+
+/// System prompt listing the recalled memories as `[1]`, `[2]`, ...
+pub fn prompt(recalled: &[Recalled]) -> String {
+    let mut prompt = String::from(
+        "Notes from your memory that may be relevant. When your answer relies on one, \
+         cite it inline as [n]; ignore notes that don't apply.\n",
+    );
+    for (i, r) in recalled.iter().enumerate() {
+        prompt.push_str(&format!("\n[{}] {}\n", i + 1, prompt_text(&r.memory.content)));
+    }
+    prompt
+}
+
+/// What the model is given of a memory
+fn prompt_text(content: &str) -> String {
+    content.chars().take(PROMPT_MEMORY_CHARS).collect::<String>().trim().to_string()
+}
+
+/// `content` on one line, cut to [`SNIPPET_CHARS`]
+fn snippet(content: &str) -> String {
+    let flat = content.split_whitespace().collect::<Vec<_>>().join(" ");
+    if flat.chars().count() <= SNIPPET_CHARS {
+        return flat;
+    }
+    let mut cut: String = flat.chars().take(SNIPPET_CHARS - 1).collect();
+    cut.push('…');
+    cut
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jamey_core::memory::MemoryType;
+
+    fn memory(content: &str, embedding: Vec<f32>) -> Memory {
+        Memory {
+            id: uuid::Uuid::new_v4(),
+            memory_type: MemoryType::Knowledge,
+            content: content.to_string(),
+            embedding,
+            metadata: serde_json::json!({}),
+            created_at: chrono::Utc::now(),
+            last_accessed: chrono::Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_select_and_cite() {
+        // As ranked by the store, which may put a less similar memory first
+        let memories = vec![
+            memory("exact\n\nmatch", vec![1.0, 0.0]),
+            memory("unrelated", vec![0.0, 1.0]),
+            memory("close", vec![0.9, 0.1]),
+        ];
+        let recalled = select(&[1.0, 0.0], memories, 0.8, 5);
+        assert_eq!(recalled.len(), 2);
+        assert_eq!(recalled[0].memory.content, "exact\n\nmatch");
+        assert_eq!(recalled[0].citation().snippet, "exact match");
+        assert!(prompt(&recalled).contains("[2] close"));
+
+        let long = memory(&"word ".repeat(100), vec![1.0]);
+        assert_eq!(snippet(&long.content).chars().count(), SNIPPET_CHARS);
+
+        let history = vec![Message::user("where is it?"), Message::assistant("here")];
+        assert_eq!(query(&history), Some("where is it?"));
+    }
+
+    #[test]
+    fn test_pins_come_first_within_budget() {
+        let mut pin = memory("always answer in metric", vec![0.0, 1.0]);
+        pin.metadata = serde_json::json!({"pinned": true});
+        let big_pin = memory(&"x".repeat(400), vec![0.0, 1.0]);
+        let close = memory("close", vec![1.0, 0.0]);
+        let recalled = select(&[1.0, 0.0], vec![close.clone(), pin.clone()], 0.0, 5);
+
+        let merged = with_pins(vec![pin.clone(), big_pin], Some(&[1.0, 0.0]), recalled, 50);
+        let ids: Vec<_> = merged.iter().map(|r| r.memory.id).collect();
+        // The pin is listed once, first; the second pin doesn't fit
+        assert_eq!(ids, vec![pin.id, close.id]);
+        assert!(merged[0].memory.is_pinned());
+
+        assert_eq!(with_pins(vec![pin], None, Vec::new(), 0).len(), 0);
+    }
+}
@@ -0,0 +1,595 @@
+//! Deep research
+//!
+//! [`ResearchWorkflow`] breaks a question into web search queries, reads the
+//! top pages for each alongside related memories, and has the model write a
+//! report citing those sources by number. The report is stored as a
+//! Knowledge memory. It backs `jamey research` and the `research` connector.
+
+use crate::config::RuntimeConfig;
+use crate::ingest::html_to_text;
+use crate::routing::RouteTask;
+use crate::state::RuntimeState;
+use chrono::Utc;
+use futures_util::future::join_all;
+use jamey_core::memory::{Memory, MemoryStore, MemoryType, PostgresMemoryStore};
+use jamey_providers::openrouter::{self, ChatRequest, LlmProvider, OpenRouterProvider, DEFAULT_EMBEDDING_MODEL};
+use jamey_tools::connector::{
+    CapabilityLevel, Connector, ConnectorMetadata, ConnectorResult, ExecutionContext,
+};
+use jamey_tools::connectors::{NetworkWebConnector, SearchHit};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use thiserror::Error;
+use uuid::Uuid;
+
+const PLAN_PROMPT: &str = "You plan web research. Break the question into distinct web search \
+queries that together cover it, most important first. Reply with a JSON array of query strings \
+and nothing else.";
+
+const REPORT_PROMPT: &str = "You write research reports. Answer the question using only the \
+numbered sources provided. Cite every claim with its source number in square brackets, like [2]. \
+Point out where sources disagree or the evidence is thin. Use Markdown headings and bullet points. \
+Do not list the sources at the end; that is added for you.";
+
+/// Characters of each page or memory passed to the model
+const EXCERPT_CHARS: usize = 1500;
+
+/// Longest report requested from the model, in tokens
+const REPORT_MAX_TOKENS: u32 = 3000;
+
+/// Longest accepted research question, in characters
+const MAX_TOPIC_CHARS: usize = 500;
+
+#[derive(Debug, Error)]
+pub enum ResearchError {
+    #[error("Invalid research request: {0}")]
+    InvalidRequest(String),
+    #[error("No sources found for: {0}")]
+    NoSources(String),
+    #[error("Model error: {0}")]
+    Model(String),
+    #[error("Memory error: {0}")]
+    Memory(String),
+    #[error("Web access error: {0}")]
+    Web(String),
+}
+
+/// How wide a research run fans out
+#[derive(Debug, Clone)]
+pub struct ResearchOptions {
+    /// Search queries the question is broken into
+    pub max_queries: usize,
+    /// Pages read for each query
+    pub results_per_query: usize,
+    /// Related memories included as sources
+    pub memory_hits: usize,
+    /// Store the report as a Knowledge memory
+    pub store: bool,
+}
+
+impl Default for ResearchOptions {
+    fn default() -> Self {
+        Self {
+            max_queries: 4,
+            results_per_query: 3,
+            memory_hits: 5,
+            store: true,
+        }
+    }
+}
+
+impl ResearchOptions {
+    fn validate(&self) -> Result<(), ResearchError> {
+        if !(1..=8).contains(&self.max_queries) {
+            return Err(ResearchError::InvalidRequest(format!(
+                "queries must be between 1 and 8 (got {})",
+                self.max_queries
+            )));
+        }
+        if !(1..=5).contains(&self.results_per_query) {
+            return Err(ResearchError::InvalidRequest(format!(
+                "results per query must be between 1 and 5 (got {})",
+                self.results_per_query
+            )));
+        }
+        if self.memory_hits > 20 {
+            return Err(ResearchError::InvalidRequest(format!(
+                "memory hits must be at most 20 (got {})",
+                self.memory_hits
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SourceKind {
+    Web,
+    Memory,
+}
+
+/// A page or memory the report may cite; citation `[n]` is the n-th source
+#[derive(Debug, Clone, Serialize)]
+pub struct ResearchSource {
+    pub kind: SourceKind,
+    pub title: String,
+    /// URL for web pages, memory ID for memories
+    pub location: String,
+    pub excerpt: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ResearchReport {
+    pub topic: String,
+    pub queries: Vec<String>,
+    pub sources: Vec<ResearchSource>,
+    /// Markdown report followed by the numbered source list
+    pub report: String,
+    /// Searches or pages that failed; the report was written without them
+    pub warnings: Vec<String>,
+    /// Knowledge memory holding the report, when stored
+    pub memory_id: Option<Uuid>,
+}
+
+/// Decompose, fan out, synthesize, remember
+pub struct ResearchWorkflow {
+    llm: Arc<OpenRouterProvider>,
+    memory_store: Arc<PostgresMemoryStore>,
+    web: NetworkWebConnector,
+    model: String,
+}
+
+impl ResearchWorkflow {
+    pub fn new(
+        llm: Arc<OpenRouterProvider>,
+        memory_store: Arc<PostgresMemoryStore>,
+        config: &RuntimeConfig,
+    ) -> Result<Self, ResearchError> {
+        // A connector of its own, so research run as a tool call doesn't
+        // need the orchestrator that is already busy running it
+        let web = NetworkWebConnector::new(
+            config.tools.download_dir.clone(),
+            config.tools.web_search_api_key.clone(),
+        )
+        .map_err(|e| ResearchError::Web(e.to_string()))?;
+        Ok(Self {
+            llm,
+            memory_store,
+            web,
+            model: config.llm.openrouter_default_model.clone(),
+        })
+    }
+
+    pub async fn run(&self, topic: &str, options: &ResearchOptions) -> Result<ResearchReport, ResearchError> {
+        let topic = topic.trim();
+        if topic.is_empty() {
+            return Err(ResearchError::InvalidRequest("topic is empty".to_string()));
+        }
+        if topic.chars().count() > MAX_TOPIC_CHARS {
+            return Err(ResearchError::InvalidRequest(format!(
+                "topic is longer than {} characters",
+                MAX_TOPIC_CHARS
+            )));
+        }
+        options.validate()?;
+
+        let mut warnings = Vec::new();
+        let queries = match self.plan(topic, options.max_queries).await {
+            Ok(queries) => queries,
+            Err(e) => {
+                warnings.push(format!("Could not plan queries, searching the topic as given: {}", e));
+                vec![topic.to_string()]
+            }
+        };
+
+        let ((mut sources, web_warnings), memory_sources) = tokio::join!(
+            self.search_web(&queries, options.results_per_query),
+            self.search_memory(topic, options.memory_hits),
+        );
+        warnings.extend(web_warnings);
+        match memory_sources {
+            Ok(memories) => sources.extend(memories),
+            Err(e) => warnings.push(format!("Memory search failed: {}", e)),
+        }
+        if sources.is_empty() {
+            return Err(ResearchError::NoSources(topic.to_string()));
+        }
+
+        let body = self.synthesize(topic, &sources).await?;
+        let report = format!("{}\n\n{}", body.trim(), render_sources(&sources));
+        let mut result = ResearchReport {
+            topic: topic.to_string(),
+            queries,
+            sources,
+            report,
+            warnings,
+            memory_id: None,
+        };
+        if options.store {
+            result.memory_id = Some(self.remember(&result).await?);
+        }
+        Ok(result)
+    }
+
+    async fn plan(&self, topic: &str, max_queries: usize) -> Result<Vec<String>, ResearchError> {
+        if max_queries == 1 {
+            return Ok(vec![topic.to_string()]);
+        }
+        let reply = self
+            .ask(
+                PLAN_PROMPT,
+                format!("At most {} queries.\n\nQuestion: {}", max_queries, topic),
+                300,
+            )
+            .await?;
+        Ok(parse_queries(&reply, topic, max_queries))
+    }
+
+    /// Search every query at once, then read every hit at once
+    async fn search_web(&self, queries: &[String], per_query: usize) -> (Vec<ResearchSource>, Vec<String>) {
+        let mut warnings = Vec::new();
+        let mut hits: Vec<SearchHit> = Vec::new();
+        let searches = join_all(queries.iter().map(|q| self.web_search(q))).await;
+        for (query, found) in queries.iter().zip(searches) {
+            match found {
+                Ok(found) => {
+                    let fresh: Vec<SearchHit> = found
+                        .into_iter()
+                        .filter(|hit| !hits.iter().any(|h| h.url == hit.url))
+                        .take(per_query)
+                        .collect();
+                    hits.extend(fresh);
+                }
+                Err(e) => warnings.push(format!("Search '{}' failed: {}", query, e)),
+            }
+        }
+
+        let pages = join_all(hits.iter().map(|hit| self.fetch(&hit.url))).await;
+        let mut sources = Vec::new();
+        for (hit, page) in hits.into_iter().zip(pages) {
+            let excerpt = match page {
+                Ok(text) => excerpt(&text),
+                Err(e) => {
+                    warnings.push(format!("Could not read {}: {}", hit.url, e));
+                    hit.snippet.clone()
+                }
+            };
+            if !excerpt.trim().is_empty() {
+                sources.push(ResearchSource {
+                    kind: SourceKind::Web,
+                    title: hit.title,
+                    location: hit.url,
+                    excerpt,
+                });
+            }
+        }
+        (sources, warnings)
+    }
+
+    async fn web_search(&self, query: &str) -> Result<Vec<SearchHit>, ResearchError> {
+        let output = self
+            .web_action(&[("action", "web_search"), ("query", query), ("format", "json")])
+            .await?;
+        serde_json::from_str(&output).map_err(|e| ResearchError::Web(e.to_string()))
+    }
+
+    async fn fetch(&self, url: &str) -> Result<String, ResearchError> {
+        let html = self.web_action(&[("action", "fetch_url"), ("url", url)]).await?;
+        Ok(html_to_text(&html))
+    }
+
+    async fn web_action(&self, params: &[(&str, &str)]) -> Result<String, ResearchError> {
+        let params = params.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        let result = self
+            .web
+            .execute(params, &ExecutionContext::default())
+            .await
+            .map_err(|e| ResearchError::Web(e.to_string()))?;
+        if !result.success {
+            return Err(ResearchError::Web(result.errors.join("; ")));
+        }
+        Ok(result.output)
+    }
+
+    async fn search_memory(&self, topic: &str, limit: usize) -> Result<Vec<ResearchSource>, ResearchError> {
+        if limit == 0 {
+            return Ok(Vec::new());
+        }
+        let embedding = self
+            .llm
+            .get_embedding(topic)
+            .await
+            .map_err(|e| ResearchError::Model(e.to_string()))?;
+        let memories = self
+            .memory_store
+            .search(&embedding, limit)
+            .await
+            .map_err(|e| ResearchError::Memory(e.to_string()))?;
+        Ok(memories
+            .into_iter()
+            .map(|memory| ResearchSource {
+                kind: SourceKind::Memory,
+                title: memory.content.lines().next().unwrap_or_default().chars().take(80).collect(),
+                location: memory.id.to_string(),
+                excerpt: excerpt(&memory.content),
+            })
+            .collect())
+    }
+
+    async fn synthesize(&self, topic: &str, sources: &[ResearchSource]) -> Result<String, ResearchError> {
+        let numbered = sources
+            .iter()
+            .enumerate()
+            .map(|(i, s)| format!("[{}] {} ({})\n{}", i + 1, s.title, s.location, s.excerpt))
+            .collect::<Vec<_>>()
+            .join("\n\n");
+        self.ask(
+            REPORT_PROMPT,
+            format!("Question: {}\n\nSources:\n\n{}", topic, numbered),
+            REPORT_MAX_TOKENS,
+        )
+        .await
+    }
+
+    async fn ask(&self, system: &str, user: String, max_tokens: u32) -> Result<String, ResearchError> {
+        let request = ChatRequest {
+            model: self.model.clone(),
+            messages: vec![
+                openrouter::Message {
+                    role: "system".to_string(),
+                    content: system.to_string(),
+                },
+                openrouter::Message {
+                    role: "user".to_string(),
+                    content: user,
+                },
+            ],
+            tools: None,
+            tool_choice: None,
+            temperature: Some(0.3),
+            max_tokens: Some(max_tokens),
+        };
+        let response = self.llm.chat(request).await.map_err(|e| ResearchError::Model(e.to_string()))?;
+        let reply = response
+            .choices
+            .first()
+            .map(|c| c.message.content.trim().to_string())
+            .unwrap_or_default();
+        if reply.is_empty() {
+            return Err(ResearchError::Model("model returned an empty reply".to_string()));
+        }
+        Ok(reply)
+    }
+
+    async fn remember(&self, report: &ResearchReport) -> Result<Uuid, ResearchError> {
+        let embedding = self
+            .llm
+            .get_embedding(&report.report)
+            .await
+            .map_err(|e| ResearchError::Model(e.to_string()))?;
+        let urls: Vec<&str> = report
+            .sources
+            .iter()
+            .filter(|s| s.kind == SourceKind::Web)
+            .map(|s| s.location.as_str())
+            .collect();
+        let now = Utc::now();
+        self.memory_store
+            .store(Memory {
+                id: Uuid::new_v4(),
+                memory_type: MemoryType::Knowledge,
+                content: format!("# Research: {}\n\n{}", report.topic, report.report),
+                embedding,
+                metadata: serde_json::json!({
+                    "source": "research",
+                    "topic": report.topic,
+                    "queries": report.queries,
+                    "urls": urls,
+                    "researched_at": now.to_rfc3339(),
+                    "embedding_model": DEFAULT_EMBEDDING_MODEL,
+                }),
+                created_at: now,
+                last_accessed: now,
+            })
+            .await
+            .map_err(|e| ResearchError::Memory(e.to_string()))
+    }
+}
+
+impl RuntimeState {
+    /// Research workflow using the runtime's memory and web settings and
+    /// the model research is routed to
+    pub fn research_workflow(&self) -> Result<ResearchWorkflow, ResearchError> {
+        let mut workflow =
+            ResearchWorkflow::new(Arc::clone(&self.llm_provider), Arc::clone(&self.memory_store), &self.config)?;
+        workflow.model = self.model_for(RouteTask::Research, "");
+        Ok(workflow)
+    }
+}
+
+/// Queries from the model's JSON array, deduplicated and capped; the topic
+/// itself when the reply can't be used
+fn parse_queries(reply: &str, topic: &str, max: usize) -> Vec<String> {
+    let parsed: Vec<String> = match (reply.find('['), reply.rfind(']')) {
+        (Some(start), Some(end)) if start < end => serde_json::from_str(&reply[start..=end]).unwrap_or_default(),
+        _ => Vec::new(),
+    };
+    let mut queries: Vec<String> = Vec::new();
+    for query in parsed.into_iter().map(|q| q.trim().to_string()) {
+        if !query.is_empty() && !queries.iter().any(|q| q.eq_ignore_ascii_case(&query)) {
+            queries.push(query);
+        }
+    }
+    queries.truncate(max);
+    if queries.is_empty() {
+        queries.push(topic.to_string());
+    }
+    queries
+}
+
+fn excerpt(text: &str) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    match text.char_indices().nth(EXCERPT_CHARS) {
+        Some((cut, _)) => format!("{}…", &text[..cut]),
+        None => text,
+    }
+}
+
+fn render_sources(sources: &[ResearchSource]) -> String {
+    let mut out = String::from("## Sources\n");
+    for (i, source) in sources.iter().enumerate() {
+        out.push_str(&match source.kind {
+            SourceKind::Web => format!("\n{}. [{}]({})", i + 1, source.title, source.location),
+            SourceKind::Memory => format!("\n{}. Memory {}: {}", i + 1, source.location, source.title),
+        });
+    }
+    out
+}
+
+/// Runs [`ResearchWorkflow`] as the `research` connector
+pub struct ResearchConnector {
+    metadata: ConnectorMetadata,
+    workflow: ResearchWorkflow,
+}
+
+impl ResearchConnector {
+    pub fn new(workflow: ResearchWorkflow) -> Self {
+        Self {
+            metadata: ConnectorMetadata {
+                id: "research".to_string(),
+                name: "Deep Research".to_string(),
+                version: "1.0.0".to_string(),
+                description: "Research a topic across web search, web pages and memory, returning a cited report"
+                    .to_string(),
+                capability_level: CapabilityLevel::WebAccess,
+                requires_approval: false,
+                safety_checks: vec![
+                    "Pages fetched through network_web URL validation".to_string(),
+                    "Reports stored as Knowledge memories".to_string(),
+                ],
+            },
+            workflow,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Connector for ResearchConnector {
+    fn metadata(&self) -> &ConnectorMetadata {
+        &self.metadata
+    }
+
+    async fn execute(
+        &self,
+        params: HashMap<String, String>,
+        _context: &ExecutionContext,
+    ) -> anyhow::Result<ConnectorResult> {
+        let mut result = ConnectorResult::new();
+        let action = params.get("action").map(String::as_str).unwrap_or_default();
+        if action != "research" {
+            result.errors.push(format!("Unknown action: {}", action));
+            return Ok(result);
+        }
+        let topic = params
+            .get("topic")
+            .ok_or_else(|| anyhow::anyhow!("Missing 'topic' parameter"))?;
+
+        let mut options = ResearchOptions::default();
+        if let Some(queries) = params.get("max_queries") {
+            options.max_queries = queries.parse().map_err(|_| anyhow::anyhow!("Invalid 'max_queries' parameter"))?;
+        }
+        if let Some(store) = params.get("store") {
+            options.store = store != "false";
+        }
+
+        let report = self.workflow.run(topic, &options).await?;
+        result.success = true;
+        result.output = report.report;
+        result.warnings = report.warnings;
+        result.metadata.insert("sources".to_string(), report.sources.len().to_string());
+        if let Some(id) = report.memory_id {
+            result.metadata.insert("memory_id".to_string(), id.to_string());
+        }
+        result.network_requests.extend(
+            report
+                .sources
+                .iter()
+                .filter(|s| s.kind == SourceKind::Web)
+                .map(|s| jamey_tools::connector::NetworkRequest {
+                    url: s.location.clone(),
+                    method: "GET".to_string(),
+                    status_code: None,
+                    timestamp: Utc::now(),
+                }),
+        );
+        Ok(result)
+    }
+
+    fn validate(&self, params: &HashMap<String, String>) -> anyhow::Result<()> {
+        if !params.contains_key("action") {
+            return Err(anyhow::anyhow!("Missing required parameter: action"));
+        }
+        Ok(())
+    }
+
+    fn required_params(&self) -> Vec<String> {
+        vec!["action".to_string(), "topic".to_string()]
+    }
+
+    fn actions(&self) -> Vec<String> {
+        vec!["research".to_string()]
+    }
+
+    fn is_enabled(&self) -> bool {
+        true
+    }
+
+    fn safety_checks(&self) -> Vec<String> {
+        self.metadata.safety_checks.clone()
+    }
+
+    fn requires_network(&self) -> bool {
+        true
+    }
+
+    fn requires_credentials(&self) -> Vec<String> {
+        vec![]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_queries() {
+        let reply = "Here you go:\n[\"rust async runtimes\", \"tokio vs async-std\", \"Rust async runtimes\", \"\"]";
+        assert_eq!(
+            parse_queries(reply, "topic", 4),
+            vec!["rust async runtimes".to_string(), "tokio vs async-std".to_string()]
+        );
+        assert_eq!(parse_queries(reply, "topic", 1), vec!["rust async runtimes".to_string()]);
+        assert_eq!(parse_queries("I can't help with that", "topic", 4), vec!["topic".to_string()]);
+    }
+
+    #[test]
+    fn test_render_sources_numbers_citations() {
+        let source = |kind, title: &str, location: &str| ResearchSource {
+            kind,
+            title: title.to_string(),
+            location: location.to_string(),
+            excerpt: String::new(),
+        };
+        let rendered = render_sources(&[
+            source(SourceKind::Web, "Tokio", "https://tokio.rs/"),
+            source(SourceKind::Memory, "Runtime notes", "1b4e28ba-2fa1-11d2-883f-0016d3cca427"),
+        ]);
+        assert_eq!(
+            rendered,
+            "## Sources\n\n1. [Tokio](https://tokio.rs/)\n2. Memory 1b4e28ba-2fa1-11d2-883f-0016d3cca427: Runtime notes"
+        );
+        assert!(excerpt(&"word ".repeat(1000)).ends_with('…'));
+    }
+}
@@ -0,0 +1,225 @@
+//! Undoing tool side effects
+//!
+//! Connectors report how to reverse what a call changed: a file restored
+//! from its backup, a download deleted, a system setting put back. The
+//! compensations of each turn are kept in `<undo_dir>/<turn_id>.json`, and
+//! [`RuntimeState::rollback`] replays them newest first. Effects that can't
+//! be reversed, such as a sent message, are never recorded.
+
+use crate::state::RuntimeState;
+use chrono::{DateTime, Utc};
+use jamey_tools::connector::Compensation;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+#[derive(Debug, Error)]
+pub enum RollbackError {
+    #[error("Turn {0} has nothing to undo")]
+    NotFound(Uuid),
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Serialization error: {0}")]
+    Serialization(#[from] serde_json::Error),
+}
+
+/// What one turn changed that can be undone, oldest first
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TurnEffects {
+    pub turn_id: Uuid,
+    pub session_id: Option<Uuid>,
+    /// When the last effect was recorded
+    pub at: DateTime<Utc>,
+    pub effects: Vec<Compensation>,
+}
+
+/// Outcome of [`RuntimeState::rollback`], by compensation description
+#[derive(Debug, Clone, Default)]
+pub struct RollbackReport {
+    pub undone: Vec<String>,
+    /// Effects that could not be undone, with why; they stay recorded so
+    /// the rollback can be retried
+    pub failed: Vec<(String, String)>,
+}
+
+pub(crate) fn default_undo_dir() -> PathBuf {
+    std::env::var("JAMEY_UNDO_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from("./undo"))
+}
+
+/// Per-turn records of reversible effects
+#[derive(Debug)]
+pub struct UndoLog {
+    dir: PathBuf,
+    /// Recording reads and rewrites a turn's file
+    write_lock: Mutex<()>,
+}
+
+impl UndoLog {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            write_lock: Mutex::new(()),
+        }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn path(&self, turn_id: Uuid) -> PathBuf {
+        self.dir.join(format!("{}.json", turn_id))
+    }
+
+    /// Add `effects` to what turn `turn_id` can undo
+    pub async fn record(
+        &self,
+        turn_id: Uuid,
+        session_id: Option<Uuid>,
+        effects: &[Compensation],
+    ) -> Result<(), RollbackError> {
+        if effects.is_empty() {
+            return Ok(());
+        }
+        let _guard = self.write_lock.lock().await;
+        let mut record = match self.load(turn_id).await {
+            Ok(record) => record,
+            Err(RollbackError::NotFound(_)) => TurnEffects {
+                turn_id,
+                session_id,
+                at: Utc::now(),
+                effects: Vec::new(),
+            },
+            Err(e) => return Err(e),
+        };
+        record.effects.extend_from_slice(effects);
+        record.at = Utc::now();
+        self.save(&record).await
+    }
+
+    pub async fn load(&self, turn_id: Uuid) -> Result<TurnEffects, RollbackError> {
+        match tokio::fs::read(self.path(turn_id)).await {
+            Ok(bytes) => Ok(serde_json::from_slice(&bytes)?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Err(RollbackError::NotFound(turn_id)),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// The latest turn of `session_id` that still has something to undo
+    pub async fn last_for(&self, session_id: Uuid) -> Result<Option<Uuid>, RollbackError> {
+        let mut entries = match tokio::fs::read_dir(&self.dir).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let mut last: Option<TurnEffects> = None;
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("json") {
+                continue;
+            }
+            let record: TurnEffects = match serde_json::from_slice(&tokio::fs::read(&path).await?) {
+                Ok(record) => record,
+                Err(e) => {
+                    tracing::warn!("Skipping unreadable undo record {}: {}", path.display(), e);
+                    continue;
+                }
+            };
+            if record.session_id == Some(session_id) && last.as_ref().is_none_or(|l| record.at > l.at) {
+                last = Some(record);
+            }
+        }
+        Ok(last.map(|record| record.turn_id))
+    }
+
+    async fn save(&self, record: &TurnEffects) -> Result<(), RollbackError> {
+        tokio::fs::create_dir_all(&self.dir).await?;
+        let path = self.path(record.turn_id);
+        let tmp = path.with_extension("json.tmp");
+        tokio::fs::write(&tmp, serde_json::to_vec_pretty(record)?).await?;
+        tokio::fs::rename(&tmp, &path).await?;
+        Ok(())
+    }
+
+    async fn remove(&self, turn_id: Uuid) -> Result<(), RollbackError> {
+        match tokio::fs::remove_file(self.path(turn_id)).await {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+impl RuntimeState {
+    /// Reverse the reversible side effects of turn `turn_id`, newest first,
+    /// through the connectors that caused them. The session's tool policy
+    /// still applies, so a rollback can't reach connectors the turn couldn't.
+    pub async fn rollback(&self, turn_id: Uuid) -> Result<RollbackReport, RollbackError> {
+        let _guard = self.undo_log.write_lock.lock().await;
+        let mut record = self.undo_log.load(turn_id).await?;
+        let policy = record
+            .session_id
+            .map(|id| self.session_manager.tool_policy(id))
+            .unwrap_or_default();
+
+        let mut report = RollbackReport::default();
+        let mut remaining = Vec::new();
+        let orchestrator = self.hybrid_orchestrator.lock().await;
+        for effect in record.effects.drain(..).rev() {
+            match orchestrator.compensate(&effect, &policy).await {
+                Ok(()) => report.undone.push(effect.description),
+                Err(e) => {
+                    tracing::warn!("Could not undo \"{}\": {}", effect.description, e);
+                    report.failed.push((effect.description.clone(), e.to_string()));
+                    remaining.push(effect);
+                }
+            }
+        }
+        drop(orchestrator);
+
+        if remaining.is_empty() {
+            self.undo_log.remove(turn_id).await?;
+        } else {
+            remaining.reverse();
+            record.effects = remaining;
+            self.undo_log.save(&record).await?;
+        }
+        tracing::info!(
+            "Rolled back turn {}: {} undone, {} failed",
+            turn_id,
+            report.undone.len(),
+            report.failed.len()
+        );
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_undo_log_records_per_turn() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = UndoLog::new(dir.path());
+        let (session, turn, later) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        assert_eq!(log.last_for(session).await.unwrap(), None);
+
+        let write = Compensation::new("full_system", "restore_file", "Restore notes.txt")
+            .with_param("path", "/tmp/notes.txt");
+        let download = Compensation::new("network_web", "reject_download", "Delete download ab12")
+            .with_param("id", "ab12");
+        log.record(turn, Some(session), &[write.clone()]).await.unwrap();
+        log.record(turn, Some(session), &[download.clone()]).await.unwrap();
+        log.record(later, Some(Uuid::new_v4()), &[write.clone()]).await.unwrap();
+        log.record(Uuid::new_v4(), Some(session), &[]).await.unwrap();
+
+        assert_eq!(log.load(turn).await.unwrap().effects, [write, download]);
+        assert_eq!(log.last_for(session).await.unwrap(), Some(turn));
+        log.remove(turn).await.unwrap();
+        assert!(matches!(log.load(turn).await, Err(RollbackError::NotFound(_))));
+    }
+}
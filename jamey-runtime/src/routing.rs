@@ -0,0 +1,272 @@
+//! Model routing
+//!
+//! `routing.rules` pick the model for each request instead of always using
+//! `llm.openrouter_default_model`: a cheap model for summaries, a premium
+//! one for coding questions, a fast one while the premium model is slow.
+//! Rules are tried in order and the first whose conditions all hold wins;
+//! when none does the default model is used. Rules are set in the config
+//! file:
+//!
+//! ```toml
+//! [[routing.rules]]
+//! name = "cheap summaries"
+//! model = "openai/gpt-4o-mini"
+//! tasks = ["summarize", "consolidate"]
+//!
+//! [[routing.rules]]
+//! name = "coding"
+//! model = "anthropic/claude-3-opus"
+//! contains = ["```", "stack trace", "compile"]
+//! max_budget_share = 0.8
+//! max_latency_ms = 8000
+//! ```
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+use thiserror::Error;
+
+/// Weight of the newest call in a model's running latency
+const LATENCY_WEIGHT: f64 = 0.3;
+
+#[derive(Debug, Error)]
+pub enum RoutingError {
+    #[error("Invalid routing rule {0:?}: {1}")]
+    InvalidRule(String, String),
+}
+
+/// What a model is being asked to do
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RouteTask {
+    /// A conversation turn
+    Chat,
+    /// Condensing older turns of a long conversation
+    Summarize,
+    /// Merging near-duplicate memories
+    Consolidate,
+    Research,
+    Briefing,
+    /// Grading answers in an evaluation run
+    Judge,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct RoutingConfig {
+    pub rules: Vec<RouteRule>,
+}
+
+impl RoutingConfig {
+    pub fn validate(&self) -> Result<(), RoutingError> {
+        for (i, rule) in self.rules.iter().enumerate() {
+            let invalid = |reason: &str| {
+                let name = if rule.name.is_empty() { format!("#{}", i + 1) } else { rule.name.clone() };
+                Err(RoutingError::InvalidRule(name, reason.to_string()))
+            };
+            if rule.model.trim().is_empty() {
+                return invalid("model is empty");
+            }
+            if let (Some(min), Some(max)) = (rule.min_chars, rule.max_chars) {
+                if min > max {
+                    return invalid("min_chars is above max_chars");
+                }
+            }
+            if rule.max_budget_share.is_some_and(|share| share <= 0.0) {
+                return invalid("max_budget_share must be above 0");
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Conditions a request must meet for `model` to handle it; those left
+/// unset or empty match anything
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct RouteRule {
+    /// Shown in logs when the rule picks a model
+    pub name: String,
+    pub model: String,
+    pub tasks: Vec<RouteTask>,
+    /// Length of the message, in characters
+    pub min_chars: Option<usize>,
+    pub max_chars: Option<usize>,
+    /// Whether tools are offered with the request
+    pub tools: Option<bool>,
+    /// Personas the session runs under
+    pub personas: Vec<String>,
+    /// The message mentions any of these, ignoring case
+    pub contains: Vec<String>,
+    /// Cost ceiling: the rule stops applying once today's spend reaches
+    /// this share of `llm.daily_budget_usd`
+    pub max_budget_share: Option<f64>,
+    /// Latency target: the rule is skipped while its model has recently
+    /// taken longer than this to start responding
+    pub max_latency_ms: Option<u64>,
+}
+
+/// The request being routed
+#[derive(Debug, Clone, Copy)]
+pub struct RouteRequest<'a> {
+    pub task: RouteTask,
+    /// The message the model is answering; its length is what `min_chars`
+    /// and `max_chars` compare
+    pub text: &'a str,
+    pub tools: bool,
+    pub persona: Option<&'a str>,
+    /// Share of the daily budget spent so far, if there is a budget
+    pub budget_share: Option<f64>,
+}
+
+/// Picks models by [`RouteRule`] and keeps the latencies latency targets
+/// are checked against
+#[derive(Debug, Default)]
+pub struct ModelRouter {
+    rules: Vec<RouteRule>,
+    /// Running time to first token per model, in milliseconds
+    latency_ms: Mutex<HashMap<String, f64>>,
+}
+
+impl ModelRouter {
+    pub fn new(config: &RoutingConfig) -> Self {
+        Self {
+            rules: config.rules.clone(),
+            latency_ms: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Model of the first rule `request` meets, if any
+    pub fn route(&self, request: &RouteRequest) -> Option<&str> {
+        let rule = self.rules.iter().find(|rule| self.matches(rule, request))?;
+        tracing::debug!(
+            "Routing {:?} request to {} (rule {:?})",
+            request.task,
+            rule.model,
+            rule.name
+        );
+        Some(&rule.model)
+    }
+
+    fn matches(&self, rule: &RouteRule, request: &RouteRequest) -> bool {
+        let chars = request.text.chars().count();
+        let text = request.text.to_lowercase();
+        (rule.tasks.is_empty() || rule.tasks.contains(&request.task))
+            && rule.min_chars.is_none_or(|min| chars >= min)
+            && rule.max_chars.is_none_or(|max| chars <= max)
+            && rule.tools.is_none_or(|tools| tools == request.tools)
+            && (rule.personas.is_empty()
+                || request.persona.is_some_and(|p| rule.personas.iter().any(|name| name == p)))
+            && (rule.contains.is_empty()
+                || rule.contains.iter().any(|needle| text.contains(&needle.to_lowercase())))
+            && rule
+                .max_budget_share
+                .is_none_or(|max| request.budget_share.is_none_or(|share| share < max))
+            && rule.max_latency_ms.is_none_or(|max| {
+                self.latency(&rule.model).is_none_or(|latency| latency <= max as f64)
+            })
+    }
+
+    /// Note how long `model` took to start responding
+    pub fn record_latency(&self, model: &str, elapsed: Duration) {
+        let ms = elapsed.as_secs_f64() * 1000.0;
+        let mut latency = self.latency_ms.lock().unwrap_or_else(|e| e.into_inner());
+        latency
+            .entry(model.to_string())
+            .and_modify(|mean| *mean += LATENCY_WEIGHT * (ms - *mean))
+            .or_insert(ms);
+    }
+
+    fn latency(&self, model: &str) -> Option<f64> {
+        let latency = self.latency_ms.lock().unwrap_or_else(|e| e.into_inner());
+        latency.get(model).copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(task: RouteTask, text: &str) -> RouteRequest<'_> {
+        RouteRequest {
+            task,
+            text,
+            tools: true,
+            persona: None,
+            budget_share: None,
+        }
+    }
+
+    fn router() -> ModelRouter {
+        ModelRouter::new(&RoutingConfig {
+            rules: vec![
+                RouteRule {
+                    name: "cheap summaries".to_string(),
+                    model: "cheap".to_string(),
+                    tasks: vec![RouteTask::Summarize, RouteTask::Consolidate],
+                    ..Default::default()
+                },
+                RouteRule {
+                    name: "coding".to_string(),
+                    model: "premium".to_string(),
+                    contains: vec!["```".to_string(), "Compile".to_string()],
+                    max_budget_share: Some(0.8),
+                    max_latency_ms: Some(5000),
+                    ..Default::default()
+                },
+                RouteRule {
+                    name: "short chats".to_string(),
+                    model: "fast".to_string(),
+                    tasks: vec![RouteTask::Chat],
+                    max_chars: Some(40),
+                    tools: Some(false),
+                    ..Default::default()
+                },
+            ],
+        })
+    }
+
+    #[test]
+    fn test_first_matching_rule_wins() {
+        let router = router();
+        assert_eq!(router.route(&request(RouteTask::Summarize, "a long transcript")), Some("cheap"));
+        assert_eq!(router.route(&request(RouteTask::Chat, "why won't this compile?")), Some("premium"));
+        assert_eq!(router.route(&request(RouteTask::Chat, "hi")), None);
+        let no_tools = RouteRequest { tools: false, ..request(RouteTask::Chat, "hi") };
+        assert_eq!(router.route(&no_tools), Some("fast"));
+    }
+
+    #[test]
+    fn test_cost_ceiling_and_latency_target() {
+        let router = router();
+        let coding = request(RouteTask::Chat, "why won't this compile?");
+        let over_budget = RouteRequest { budget_share: Some(0.9), ..coding };
+        assert_eq!(router.route(&over_budget), None);
+        assert_eq!(router.route(&RouteRequest { budget_share: Some(0.5), ..coding }), Some("premium"));
+
+        router.record_latency("premium", Duration::from_secs(4));
+        assert_eq!(router.route(&coding), Some("premium"));
+        // A few slow responses push the running latency over the target
+        for _ in 0..5 {
+            router.record_latency("premium", Duration::from_secs(12));
+        }
+        assert_eq!(router.route(&coding), None);
+    }
+
+    #[test]
+    fn test_validate() {
+        let rule = |model: &str, min_chars, max_chars| RoutingConfig {
+            rules: vec![RouteRule {
+                model: model.to_string(),
+                min_chars,
+                max_chars,
+                ..Default::default()
+            }],
+        };
+        assert!(rule("cheap", Some(10), Some(100)).validate().is_ok());
+        assert!(rule("", None, None).validate().is_err());
+        assert!(rule("cheap", Some(100), Some(10)).validate().is_err());
+    }
+}
@@ -1,10 +1,21 @@
 //! Task Scheduler for 24/7 Operation
 //! 
-//! Provides scheduling capabilities for continuous operation
+//! Provides scheduling capabilities for continuous operation. Tasks either
+//! run a connector or compose a briefing; [`spawn_scheduler`] runs whatever
+//! is due against the runtime.
 
+use crate::briefing;
+use crate::events;
+use crate::inflight::WorkKind;
+use crate::state::RuntimeState;
+use crate::status;
 use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::sync::broadcast;
 use tokio::time::{sleep, Duration};
 use anyhow::Result;
 use uuid::Uuid;
@@ -14,6 +25,9 @@ use tracing::{info, error, debug};
 pub struct ScheduledTask {
     pub id: Uuid,
     pub name: String,
+    /// What running the task does; older tasks without one run a connector
+    #[serde(default)]
+    pub kind: TaskKind,
     pub connector_id: String,
     pub params: HashMap<String, String>,
     pub schedule: Schedule,
@@ -22,9 +36,21 @@ pub struct ScheduledTask {
     pub next_run: DateTime<Utc>,
 }
 
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskKind {
+    /// Execute `connector_id` with `params`
+    #[default]
+    Connector,
+    /// Compose a briefing and deliver it to the configured channels
+    Briefing,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Schedule {
     Interval { seconds: u64 },
+    /// Cron expression with seconds (`sec min hour day month weekday`), in
+    /// the scheduler's timezone
     Cron { expression: String },
     OneTime { when: DateTime<Utc> },
     Continuous, // Run continuously
@@ -33,6 +59,8 @@ pub enum Schedule {
 pub struct TaskScheduler {
     tasks: HashMap<Uuid, ScheduledTask>,
     running: bool,
+    /// What cron expressions' hours and days are read in
+    timezone: Tz,
 }
 
 impl TaskScheduler {
@@ -40,18 +68,38 @@ impl TaskScheduler {
         Self {
             tasks: HashMap::new(),
             running: false,
+            timezone: Tz::UTC,
         }
     }
 
+    /// Read cron expressions in `timezone`, such as the runtime's
+    /// [`TimeContext`](crate::locale::TimeContext)
+    pub fn with_timezone(mut self, timezone: Tz) -> Self {
+        self.timezone = timezone;
+        self
+    }
+
+    pub fn timezone(&self) -> Tz {
+        self.timezone
+    }
+
     pub fn add_task(&mut self, task: ScheduledTask) {
         let task_id = task.id;
         let task_name = task.name.clone();
         self.tasks.insert(task_id, task);
+        self.report_depth();
         info!("Added scheduled task: {}", task_name);
     }
 
     pub fn remove_task(&mut self, id: Uuid) -> Option<ScheduledTask> {
-        self.tasks.remove(&id)
+        let removed = self.tasks.remove(&id);
+        self.report_depth();
+        removed
+    }
+
+    /// Enabled tasks still to run, for the queue depth gauge
+    fn report_depth(&self) {
+        status::record_queue_depth("scheduled_tasks", self.tasks.values().filter(|t| t.enabled).count());
     }
 
     pub fn get_task(&self, id: Uuid) -> Option<&ScheduledTask> {
@@ -62,55 +110,48 @@ impl TaskScheduler {
         self.tasks.values().collect()
     }
 
+    /// Tasks due at `now`, marked as run and rescheduled. One-off tasks,
+    /// such as jobs queued by inbound webhooks, are disabled once taken.
+    pub fn take_due(&mut self, now: DateTime<Utc>) -> Vec<ScheduledTask> {
+        let mut due = Vec::new();
+        for task in self.tasks.values_mut() {
+            if !task.enabled || now < task.next_run {
+                continue;
+            }
+            debug!("Scheduled task due: {}", task.name);
+            task.last_run = Some(now);
+            task.next_run = next_run(&task.schedule, now, self.timezone);
+            if matches!(task.schedule, Schedule::OneTime { .. }) {
+                task.enabled = false;
+            }
+            due.push(task.clone());
+        }
+        self.report_depth();
+        due
+    }
+
     pub async fn start<F>(&mut self, executor: F)
     where
         F: Fn(String, HashMap<String, String>) -> Result<String> + Send + Sync + 'static,
     {
         self.running = true;
         info!("Task scheduler started");
-        
+
         while self.running {
-            let now = Utc::now();
-            
-            for (id, task) in self.tasks.iter_mut() {
-                if !task.enabled {
-                    continue;
-                }
-                
-                if now >= task.next_run {
-                    debug!("Executing scheduled task: {}", task.name);
-                    
-                    // Execute task
-                    match executor(task.connector_id.clone(), task.params.clone()) {
-                        Ok(output) => {
-                            info!("Task {} completed: {}", task.name, output);
-                        }
-                        Err(e) => {
-                            error!("Task {} failed: {}", task.name, e);
-                        }
+            for task in self.take_due(Utc::now()) {
+                match executor(task.connector_id.clone(), task.params.clone()) {
+                    Ok(output) => {
+                        info!("Task {} completed: {}", task.name, output);
+                    }
+                    Err(e) => {
+                        error!("Task {} failed: {}", task.name, e);
                     }
-                    
-                    task.last_run = Some(now);
-                    task.next_run = self.calculate_next_run(&task.schedule, now);
                 }
             }
-            
             sleep(Duration::from_secs(1)).await;
         }
-        
-        info!("Task scheduler stopped");
-    }
 
-    fn calculate_next_run(&self, schedule: &Schedule, now: DateTime<Utc>) -> DateTime<Utc> {
-        match schedule {
-            Schedule::Interval { seconds } => now + chrono::Duration::seconds(*seconds as i64),
-            Schedule::Cron { expression: _ } => {
-                // Use cron parser library - simplified for now
-                now + chrono::Duration::minutes(1)
-            }
-            Schedule::OneTime { when } => *when,
-            Schedule::Continuous => now, // Run immediately again
-        }
+        info!("Task scheduler stopped");
     }
 
     pub fn stop(&mut self) {
@@ -128,3 +169,179 @@ impl Default for TaskScheduler {
     }
 }
 
+/// Parse an expression as [`Schedule::Cron`] takes it
+pub fn parse_cron(expression: &str) -> Result<cron::Schedule> {
+    cron::Schedule::from_str(expression)
+        .map_err(|e| anyhow::anyhow!("Invalid cron expression '{}': {}", expression, e))
+}
+
+/// When a task on `schedule` that ran at `now` runs next, reading cron
+/// expressions in `timezone`
+fn next_run(schedule: &Schedule, now: DateTime<Utc>, timezone: Tz) -> DateTime<Utc> {
+    match schedule {
+        Schedule::Interval { seconds } => now + chrono::Duration::seconds(*seconds as i64),
+        Schedule::Cron { expression } => match parse_cron(expression) {
+            Ok(cron) => cron
+                .after(&now.with_timezone(&timezone))
+                .next()
+                .map(|next| next.with_timezone(&Utc))
+                .unwrap_or(DateTime::<Utc>::MAX_UTC),
+            Err(e) => {
+                error!("{}; the task will not run again", e);
+                DateTime::<Utc>::MAX_UTC
+            }
+        },
+        Schedule::OneTime { when } => *when,
+        Schedule::Continuous => now, // Run immediately again
+    }
+}
+
+/// First run of a task on `schedule` created at `now`: cron tasks wait for
+/// their next slot in `timezone`, everything else starts straight away
+pub fn first_run(schedule: &Schedule, now: DateTime<Utc>, timezone: Tz) -> DateTime<Utc> {
+    match schedule {
+        Schedule::Cron { .. } => next_run(schedule, now, timezone),
+        _ => now,
+    }
+}
+
+/// Run due tasks every second until shutdown: connectors through the hybrid
+/// orchestrator, briefings through [`briefing::run`]. Each task runs on its
+/// own so a slow one doesn't hold up the rest, and publishes `job.completed`
+/// or `job.failed` when it's done; one that panics is recorded by the
+/// [supervisor](RuntimeState::supervisor) as `job:{name}`. In a cluster, recurring tasks only run on
+/// the [leader](RuntimeState::is_leader); the others still advance their
+/// schedules, so whoever takes over carries on from the next slot.
+pub fn spawn_scheduler(state: Arc<RuntimeState>, shutdown: broadcast::Receiver<()>) {
+    let supervisor = state.supervisor.clone();
+    supervisor.spawn("scheduler", move || {
+        let state = Arc::clone(&state);
+        let mut shutdown = shutdown.resubscribe();
+        async move {
+            let mut tick = tokio::time::interval(Duration::from_secs(1));
+            info!("Task scheduler started");
+            loop {
+                tokio::select! {
+                    _ = tick.tick() => {}
+                    _ = shutdown.recv() => break,
+                }
+                let due = state.scheduler.lock().await.take_due(Utc::now());
+                let leader = state.is_leader();
+                for task in due {
+                    // One-off tasks were queued on this instance, by a hook it received
+                    if !leader && !matches!(task.schedule, Schedule::OneTime { .. }) {
+                        debug!("Leaving task {} to the cluster leader", task.name);
+                        continue;
+                    }
+                    let state = Arc::clone(&state);
+                    state.supervisor.clone().spawn_once(format!("job:{}", task.name), async move {
+                        let work = state.in_flight.begin(WorkKind::Job, &task.name, None);
+                        let outcome = work.run(run_task(&state, &task)).await.unwrap_or_else(|cancelled| Err(cancelled.into()));
+                        drop(work);
+                        let (kind, data) = match outcome {
+                            Ok(output) => {
+                                info!("Task {} completed: {}", task.name, output);
+                                (events::JOB_COMPLETED, serde_json::json!({
+                                    "task_id": task.id,
+                                    "name": task.name,
+                                    "kind": task.kind,
+                                    "output": output,
+                                }))
+                            }
+                            Err(e) => {
+                                error!("Task {} failed: {}", task.name, e);
+                                (events::JOB_FAILED, serde_json::json!({
+                                    "task_id": task.id,
+                                    "name": task.name,
+                                    "kind": task.kind,
+                                    "error": e.to_string(),
+                                }))
+                            }
+                        };
+                        state.events.publish(kind, None, data);
+                    });
+                }
+            }
+            info!("Task scheduler stopped");
+        }
+    });
+}
+
+async fn run_task(state: &RuntimeState, task: &ScheduledTask) -> Result<String> {
+    match task.kind {
+        TaskKind::Connector => {
+            let result = state
+                .hybrid_orchestrator
+                .lock()
+                .await
+                .execute_connector(&task.connector_id, task.params.clone())
+                .await?;
+            if !result.success {
+                anyhow::bail!("{}", result.errors.join("; "));
+            }
+            Ok(result.output)
+        }
+        TaskKind::Briefing => {
+            let briefing = briefing::run(state).await?;
+            Ok(format!("briefing delivered to {}", briefing.delivered.join(", ")))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task(schedule: Schedule, next_run: DateTime<Utc>) -> ScheduledTask {
+        ScheduledTask {
+            id: Uuid::new_v4(),
+            name: "test".to_string(),
+            kind: TaskKind::Connector,
+            connector_id: "network_web".to_string(),
+            params: HashMap::new(),
+            schedule,
+            enabled: true,
+            last_run: None,
+            next_run,
+        }
+    }
+
+    #[test]
+    fn test_take_due() {
+        let now = Utc::now();
+        let mut scheduler = TaskScheduler::new();
+        scheduler.add_task(task(Schedule::OneTime { when: now }, now));
+        scheduler.add_task(task(Schedule::Interval { seconds: 60 }, now));
+        scheduler.add_task(task(Schedule::Interval { seconds: 60 }, now + chrono::Duration::hours(1)));
+
+        assert_eq!(scheduler.take_due(now).len(), 2);
+        assert!(scheduler.take_due(now).is_empty());
+        // The interval task comes round again; the one-off doesn't
+        assert_eq!(scheduler.take_due(now + chrono::Duration::seconds(60)).len(), 1);
+
+        let daily = Schedule::Cron { expression: "0 30 7 * * *".to_string() };
+        let next = first_run(&daily, now, Tz::UTC);
+        assert_eq!(next.format("%H:%M:%S").to_string(), "07:30:00");
+        assert!(next > now && next <= now + chrono::Duration::days(1));
+        assert!(parse_cron("every morning").is_err());
+    }
+
+    #[test]
+    fn test_cron_in_timezone() {
+        use chrono::TimeZone;
+
+        let daily = Schedule::Cron { expression: "0 0 9 * * *".to_string() };
+        // 22:00 UTC is already the next morning in Tokyo
+        let now = Utc.with_ymd_and_hms(2026, 1, 14, 22, 0, 0).unwrap();
+        let tokyo = first_run(&daily, now, chrono_tz::Asia::Tokyo);
+        assert_eq!(tokyo, Utc.with_ymd_and_hms(2026, 1, 15, 0, 0, 0).unwrap());
+
+        let mut scheduler = TaskScheduler::new().with_timezone(chrono_tz::America::New_York);
+        scheduler.add_task(task(daily, now));
+        let due = scheduler.take_due(now);
+        assert_eq!(due.len(), 1);
+        // 9:00 EST
+        let task = scheduler.get_task(due[0].id).unwrap();
+        assert_eq!(task.next_run, Utc.with_ymd_and_hms(2026, 1, 15, 14, 0, 0).unwrap());
+    }
+}
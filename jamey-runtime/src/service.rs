@@ -3,7 +3,7 @@
 //! Provides a service wrapper that enables Jamey 2.0 to run continuously
 //! with scheduler integration and graceful shutdown handling
 
-use crate::scheduler::{TaskScheduler, ScheduledTask, Schedule};
+use crate::scheduler::{ScheduledTask, Schedule, TaskKind};
 use crate::hybrid_orchestrator::HybridOrchestrator;
 use crate::state::RuntimeState;
 use anyhow::Result;
@@ -109,6 +109,7 @@ impl JameyService {
         let task = ScheduledTask {
             id: Uuid::new_v4(),
             name,
+            kind: TaskKind::Connector,
             connector_id,
             params,
             schedule,
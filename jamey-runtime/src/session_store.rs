@@ -0,0 +1,557 @@
+//! Persisted conversation transcripts
+//!
+//! Each session is a JSON file `<session_dir>/<uuid>.json` holding its full
+//! message history, so conversations survive restarts and can be listed,
+//! exported or resumed from the CLI without starting the runtime. Runtimes
+//! in a [cluster](crate::cluster) keep the same records in the
+//! `session_transcripts` table instead, so every instance sees them.
+
+use chrono::{DateTime, Utc};
+use deadpool_postgres::Pool;
+use jamey_core::redaction::Redactor;
+use jamey_protocol::{Message, Role};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use thiserror::Error;
+use uuid::Uuid;
+
+/// Longest auto-generated title, in characters
+const TITLE_LEN: usize = 60;
+
+#[derive(Debug, Error)]
+pub enum SessionStoreError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Serialization error: {0}")]
+    Serialization(#[from] serde_json::Error),
+    #[error("Session not found: {0}")]
+    NotFound(String),
+    #[error("Session ID prefix is ambiguous: {0}")]
+    Ambiguous(String),
+    #[error("Database error: {0}")]
+    Database(#[from] tokio_postgres::Error),
+    #[error("Pool error: {0}")]
+    Pool(#[from] deadpool_postgres::PoolError),
+}
+
+/// A stored conversation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionRecord {
+    pub id: Uuid,
+    pub title: String,
+    pub model: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub messages: Vec<Message>,
+    /// Set once the session has gone idle and been archived
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub archive: Option<ArchiveInfo>,
+}
+
+/// How an idle session was archived
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchiveInfo {
+    pub archived_at: DateTime<Utc>,
+    /// Memory holding the summary of the transcript
+    pub summary_memory: Option<Uuid>,
+    /// Messages that summary covers
+    pub summarized_messages: usize,
+    /// Who the session was attributed to, restored on revival
+    pub user: Option<String>,
+    /// When the session was taken up again; it stays archived until then
+    pub revived_at: Option<DateTime<Utc>>,
+}
+
+impl SessionRecord {
+    pub fn new(id: Uuid) -> Self {
+        let now = Utc::now();
+        Self {
+            id,
+            title: String::new(),
+            model: None,
+            created_at: now,
+            updated_at: now,
+            messages: Vec::new(),
+            archive: None,
+        }
+    }
+
+    /// Archived and not revived since
+    pub fn is_archived(&self) -> bool {
+        self.archive.as_ref().is_some_and(|a| a.revived_at.is_none())
+    }
+
+    /// Whether the title, any message or any cited snippet matches `pattern`
+    pub fn mentions(&self, pattern: &regex::Regex) -> bool {
+        pattern.is_match(&self.title)
+            || self.messages.iter().any(|m| {
+                pattern.is_match(&m.content) || m.citations.iter().any(|c| pattern.is_match(&c.snippet))
+            })
+    }
+
+    pub fn summary(&self) -> SessionSummary {
+        SessionSummary {
+            id: self.id,
+            title: self.title.clone(),
+            model: self.model.clone(),
+            message_count: self.messages.len(),
+            created_at: self.created_at,
+            updated_at: self.updated_at,
+            archived: self.is_archived(),
+        }
+    }
+
+    /// Render the transcript as Markdown
+    pub fn to_markdown(&self) -> String {
+        let mut out = format!("# {}\n\n", self.display_title());
+        out.push_str(&format!("- Session: `{}`\n", self.id));
+        out.push_str(&format!("- Created: {}\n", self.created_at.format("%Y-%m-%d %H:%M UTC")));
+        out.push_str(&format!("- Updated: {}\n", self.updated_at.format("%Y-%m-%d %H:%M UTC")));
+        if let Some(ref model) = self.model {
+            out.push_str(&format!("- Model: {}\n", model));
+        }
+
+        for message in &self.messages {
+            let speaker = match message.role {
+                Role::User => "You",
+                Role::Assistant => "Jamey",
+                Role::System => "System",
+                Role::Tool => "Tool",
+            };
+            out.push_str(&format!(
+                "\n## {} — {}\n\n{}\n",
+                speaker,
+                message.timestamp.format("%Y-%m-%d %H:%M:%S"),
+                message.content.trim_end()
+            ));
+        }
+        out
+    }
+
+    pub fn display_title(&self) -> &str {
+        if self.title.is_empty() {
+            "Untitled session"
+        } else {
+            &self.title
+        }
+    }
+}
+
+/// Listing entry without the message bodies
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionSummary {
+    pub id: Uuid,
+    pub title: String,
+    pub model: Option<String>,
+    pub message_count: usize,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    #[serde(default)]
+    pub archived: bool,
+}
+
+/// Directory of session transcripts, or their table when shared
+#[derive(Clone)]
+pub struct SessionStore {
+    dir: PathBuf,
+    redactor: Arc<Redactor>,
+    database: Option<Pool>,
+}
+
+impl std::fmt::Debug for SessionStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SessionStore")
+            .field("dir", &self.dir)
+            .field("database", &self.database.is_some())
+            .finish()
+    }
+}
+
+impl SessionStore {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into(), redactor: Arc::new(Redactor::disabled()), database: None }
+    }
+
+    /// Keep transcripts in `pool`'s database rather than the directory,
+    /// creating the table when missing
+    pub async fn with_postgres(mut self, pool: Pool) -> Result<Self, SessionStoreError> {
+        let client = pool.get().await?;
+        client
+            .batch_execute(
+                "CREATE TABLE IF NOT EXISTS session_transcripts (
+                    id UUID PRIMARY KEY,
+                    record JSONB NOT NULL,
+                    updated_at TIMESTAMPTZ NOT NULL
+                );
+                CREATE INDEX IF NOT EXISTS session_transcripts_updated_idx ON session_transcripts (updated_at);",
+            )
+            .await?;
+        drop(client);
+        self.database = Some(pool);
+        Ok(self)
+    }
+
+    /// Redact titles and messages with `redactor` before they are written
+    pub fn with_redactor(mut self, redactor: Arc<Redactor>) -> Self {
+        self.redactor = redactor;
+        self
+    }
+
+    /// Store at `JAMEY_SESSION_DIR`, or `./sessions` when unset
+    pub fn from_env() -> Self {
+        Self::new(default_session_dir())
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn path(&self, id: Uuid) -> PathBuf {
+        self.dir.join(format!("{}.json", id))
+    }
+
+    /// All sessions, most recently updated first
+    pub async fn list(&self) -> Result<Vec<SessionSummary>, SessionStoreError> {
+        let mut summaries: Vec<SessionSummary> = self.read_all().await?.iter().map(SessionRecord::summary).collect();
+        summaries.sort_by_key(|summary| std::cmp::Reverse(summary.updated_at));
+        Ok(summaries)
+    }
+
+    /// Sessions whose transcript [mentions](SessionRecord::mentions) `pattern`
+    pub async fn find_mentions(&self, pattern: &regex::Regex) -> Result<Vec<Uuid>, SessionStoreError> {
+        Ok(self
+            .read_all()
+            .await?
+            .iter()
+            .filter(|record| record.mentions(pattern))
+            .map(|record| record.id)
+            .collect())
+    }
+
+    /// Every readable record, in directory order
+    async fn read_all(&self) -> Result<Vec<SessionRecord>, SessionStoreError> {
+        if let Some(pool) = &self.database {
+            let rows = pool.get().await?.query("SELECT id, record FROM session_transcripts", &[]).await?;
+            return Ok(rows
+                .iter()
+                .filter_map(|row| match serde_json::from_value(row.get(1)) {
+                    Ok(record) => Some(record),
+                    Err(e) => {
+                        tracing::warn!("Skipping unreadable session {}: {}", row.get::<_, Uuid>(0), e);
+                        None
+                    }
+                })
+                .collect());
+        }
+        let mut records = Vec::new();
+        let mut entries = match tokio::fs::read_dir(&self.dir).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(records),
+            Err(e) => return Err(e.into()),
+        };
+
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("json") {
+                continue;
+            }
+            match read_record(&path).await {
+                Ok(record) => records.push(record),
+                Err(e) => tracing::warn!("Skipping unreadable session {}: {}", path.display(), e),
+            }
+        }
+        Ok(records)
+    }
+
+    pub async fn load(&self, id: Uuid) -> Result<SessionRecord, SessionStoreError> {
+        if let Some(pool) = &self.database {
+            let row = pool
+                .get()
+                .await?
+                .query_opt("SELECT record FROM session_transcripts WHERE id = $1", &[&id])
+                .await?
+                .ok_or_else(|| SessionStoreError::NotFound(id.to_string()))?;
+            return Ok(serde_json::from_value(row.get(0))?);
+        }
+        match read_record(&self.path(id)).await {
+            Err(SessionStoreError::Io(e)) if e.kind() == std::io::ErrorKind::NotFound => {
+                Err(SessionStoreError::NotFound(id.to_string()))
+            }
+            other => other,
+        }
+    }
+
+    /// Write a session atomically (temp file + rename, or one upsert)
+    pub async fn save(&self, record: &SessionRecord) -> Result<(), SessionStoreError> {
+        if let Some(pool) = &self.database {
+            let value = if self.redactor.is_enabled() {
+                serde_json::to_value(self.redacted(record))?
+            } else {
+                serde_json::to_value(record)?
+            };
+            pool.get()
+                .await?
+                .execute(
+                    "INSERT INTO session_transcripts (id, record, updated_at) VALUES ($1, $2, $3)
+                     ON CONFLICT (id) DO UPDATE SET record = EXCLUDED.record, updated_at = EXCLUDED.updated_at",
+                    &[&record.id, &value, &record.updated_at],
+                )
+                .await?;
+            return Ok(());
+        }
+        tokio::fs::create_dir_all(&self.dir).await?;
+        let path = self.path(record.id);
+        let tmp = path.with_extension("json.tmp");
+        let contents = if self.redactor.is_enabled() {
+            serde_json::to_vec_pretty(&self.redacted(record))?
+        } else {
+            serde_json::to_vec_pretty(record)?
+        };
+        tokio::fs::write(&tmp, contents).await?;
+        tokio::fs::rename(&tmp, &path).await?;
+        Ok(())
+    }
+
+    fn redacted(&self, record: &SessionRecord) -> SessionRecord {
+        let mut record = record.clone();
+        self.redactor.redact_string(&mut record.title);
+        for message in &mut record.messages {
+            self.redactor.redact_string(&mut message.content);
+            self.redactor.redact_json(&mut message.metadata);
+            for citation in &mut message.citations {
+                self.redactor.redact_string(&mut citation.snippet);
+            }
+        }
+        record
+    }
+
+    /// Append messages to a session, creating it on first use. The title
+    /// defaults to the opening user message.
+    pub async fn append(
+        &self,
+        id: Uuid,
+        messages: &[Message],
+        model: Option<&str>,
+    ) -> Result<SessionRecord, SessionStoreError> {
+        let mut record = match self.load(id).await {
+            Ok(record) => record,
+            Err(SessionStoreError::NotFound(_)) => SessionRecord::new(id),
+            Err(e) => return Err(e),
+        };
+
+        record.messages.extend_from_slice(messages);
+        if record.title.is_empty() {
+            if let Some(first) = record.messages.iter().find(|m| m.role == Role::User) {
+                record.title = title_from(&first.content);
+            }
+        }
+        if let Some(model) = model {
+            record.model = Some(model.to_string());
+        }
+        record.updated_at = Utc::now();
+
+        self.save(&record).await?;
+        Ok(record)
+    }
+
+    /// Set a session's title, creating an empty session if nothing has been
+    /// said in it yet
+    pub async fn rename(&self, id: Uuid, title: &str) -> Result<SessionRecord, SessionStoreError> {
+        let mut record = match self.load(id).await {
+            Ok(record) => record,
+            Err(SessionStoreError::NotFound(_)) => SessionRecord::new(id),
+            Err(e) => return Err(e),
+        };
+        record.title = title.trim().to_string();
+        record.updated_at = Utc::now();
+        self.save(&record).await?;
+        Ok(record)
+    }
+
+    pub async fn delete(&self, id: Uuid) -> Result<(), SessionStoreError> {
+        if let Some(pool) = &self.database {
+            let deleted = pool
+                .get()
+                .await?
+                .execute("DELETE FROM session_transcripts WHERE id = $1", &[&id])
+                .await?;
+            if deleted == 0 {
+                return Err(SessionStoreError::NotFound(id.to_string()));
+            }
+            return Ok(());
+        }
+        match tokio::fs::remove_file(self.path(id)).await {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                Err(SessionStoreError::NotFound(id.to_string()))
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Resolve a full ID or a unique prefix of one (as shown by `list`)
+    pub async fn resolve(&self, id_or_prefix: &str) -> Result<Uuid, SessionStoreError> {
+        if let Ok(id) = Uuid::parse_str(id_or_prefix) {
+            return Ok(id);
+        }
+
+        let prefix = id_or_prefix.to_lowercase();
+        let matches: Vec<Uuid> = self
+            .list()
+            .await?
+            .into_iter()
+            .map(|s| s.id)
+            .filter(|id| !prefix.is_empty() && id.to_string().starts_with(&prefix))
+            .collect();
+
+        match matches.as_slice() {
+            [id] => Ok(*id),
+            [] => Err(SessionStoreError::NotFound(id_or_prefix.to_string())),
+            _ => Err(SessionStoreError::Ambiguous(id_or_prefix.to_string())),
+        }
+    }
+}
+
+pub(crate) fn default_session_dir() -> PathBuf {
+    std::env::var("JAMEY_SESSION_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from("./sessions"))
+}
+
+async fn read_record(path: &Path) -> Result<SessionRecord, SessionStoreError> {
+    let bytes = tokio::fs::read(path).await?;
+    Ok(serde_json::from_slice(&bytes)?)
+}
+
+pub(crate) fn title_from(content: &str) -> String {
+    let line = content.lines().find(|l| !l.trim().is_empty()).unwrap_or_default().trim();
+    if line.chars().count() <= TITLE_LEN {
+        line.to_string()
+    } else {
+        let truncated: String = line.chars().take(TITLE_LEN - 1).collect();
+        format!("{}…", truncated.trim_end())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_append_list_and_delete() {
+        let dir = TempDir::new().unwrap();
+        let store = SessionStore::new(dir.path());
+        assert!(store.list().await.unwrap().is_empty());
+
+        let id = Uuid::new_v4();
+        store
+            .append(id, &[Message::user("How do I rotate logs?\nDetails...")], Some("gpt-4"))
+            .await
+            .unwrap();
+        let record = store
+            .append(id, &[Message::assistant("Use logrotate.")], None)
+            .await
+            .unwrap();
+        assert_eq!(record.title, "How do I rotate logs?");
+        assert_eq!(record.messages.len(), 2);
+        assert_eq!(record.model.as_deref(), Some("gpt-4"));
+
+        let listed = store.list().await.unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].message_count, 2);
+
+        let prefix = &id.to_string()[..8];
+        assert_eq!(store.resolve(prefix).await.unwrap(), id);
+
+        store.delete(id).await.unwrap();
+        assert!(matches!(store.load(id).await, Err(SessionStoreError::NotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_redacts_before_writing() {
+        let dir = TempDir::new().unwrap();
+        let store = SessionStore::new(dir.path()).with_redactor(Arc::new(Redactor::default()));
+        let id = Uuid::new_v4();
+        let record = store
+            .append(id, &[Message::user("my password: hunter22")], None)
+            .await
+            .unwrap();
+        // The caller keeps the original; only what is written is redacted
+        assert_eq!(record.messages[0].content, "my password: hunter22");
+
+        let stored = store.load(id).await.unwrap();
+        assert_eq!(stored.title, "my password: [REDACTED:password]");
+        assert_eq!(stored.messages[0].content, "my password: [REDACTED:password]");
+        let raw = std::fs::read_to_string(dir.path().join(format!("{}.json", id))).unwrap();
+        assert!(!raw.contains("hunter22"));
+    }
+
+    #[tokio::test]
+    async fn test_find_mentions() {
+        let dir = TempDir::new().unwrap();
+        let store = SessionStore::new(dir.path());
+        let (mentioned, other) = (Uuid::new_v4(), Uuid::new_v4());
+        store.append(mentioned, &[Message::user("Email alice@example.com the notes")], None).await.unwrap();
+        store.append(other, &[Message::user("Email the team the notes")], None).await.unwrap();
+
+        let pattern = regex::Regex::new(r"(?i)alice@example\.com").unwrap();
+        assert_eq!(store.find_mentions(&pattern).await.unwrap(), vec![mentioned]);
+    }
+
+    #[tokio::test]
+    async fn test_rename() {
+        let dir = TempDir::new().unwrap();
+        let store = SessionStore::new(dir.path());
+
+        // Renaming before the first message creates the session
+        let id = Uuid::new_v4();
+        store.rename(id, "  Release planning ").await.unwrap();
+        let record = store.append(id, &[Message::user("What ships first?")], None).await.unwrap();
+        assert_eq!(record.title, "Release planning");
+        assert_eq!(record.messages.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_archive_round_trip() {
+        let dir = TempDir::new().unwrap();
+        let store = SessionStore::new(dir.path());
+        let id = Uuid::new_v4();
+        let mut record = store.append(id, &[Message::user("hello")], None).await.unwrap();
+        assert!(!store.list().await.unwrap()[0].archived);
+
+        record.archive = Some(ArchiveInfo {
+            archived_at: Utc::now(),
+            summary_memory: None,
+            summarized_messages: 1,
+            user: Some("alice".to_string()),
+            revived_at: None,
+        });
+        store.save(&record).await.unwrap();
+        assert!(store.load(id).await.unwrap().is_archived());
+        assert!(store.list().await.unwrap()[0].archived);
+
+        record.archive.as_mut().unwrap().revived_at = Some(Utc::now());
+        assert!(!record.is_archived());
+    }
+
+    #[test]
+    fn test_markdown_export() {
+        let mut record = SessionRecord::new(Uuid::new_v4());
+        record.messages.push(Message::user("hello"));
+        record.messages.push(Message::assistant("hi there"));
+
+        let markdown = record.to_markdown();
+        assert!(markdown.starts_with("# Untitled session"));
+        assert!(markdown.contains("## You"));
+        assert!(markdown.contains("## Jamey"));
+        assert!(markdown.contains("hi there"));
+    }
+
+    #[test]
+    fn test_title_truncation() {
+        let long = "x".repeat(100);
+        assert_eq!(title_from(&long).chars().count(), TITLE_LEN);
+    }
+}
@@ -1,11 +1,50 @@
+use crate::approvals::ApprovalQueue;
+use crate::attachments::AttachmentStore;
+use crate::audit::AuditLog;
+use crate::certificates::{spawn_certificate_renewal, CertificateManager};
+use crate::cluster::Cluster;
 use crate::config::RuntimeConfig;
+use crate::degradation::Degradation;
+use crate::events::{EventBus, TASK_PANICKED};
+use crate::feedback::PreferenceStore;
+use crate::guardrails::{Guardrails, Strictness};
+use crate::maintenance::ProviderEmbedder;
+use crate::offline::OfflineQueue;
+use crate::persona::{Persona, PersonaStore};
+use crate::workspace::{Workspace, WorkspaceStore};
+use crate::rollback::UndoLog;
+use crate::routing::{ModelRouter, RouteRequest, RouteTask};
 use crate::hybrid_orchestrator::{HybridOrchestrator, SafetyMode, FullAccessConfig};
 use crate::scheduler::TaskScheduler;
+use crate::session_store::SessionStore;
+use crate::telegram::TelegramBot;
+use crate::status::{self, BudgetTracker};
+use crate::project::ProjectStore;
+use crate::prompt_trace::PromptTraceLog;
+use crate::inflight::InFlight;
+use crate::locale::TimeContext;
+use crate::queue::RequestQueue;
+use crate::research::{ResearchConnector, ResearchWorkflow};
+use crate::usage::UsageLog;
+use crate::webhooks;
 use anyhow::Result;
 use dashmap::DashMap;
-use jamey_core::memory::{Memory, PostgresMemoryStore};
-use jamey_providers::openrouter::OpenRouterProvider;
-use jamey_tools::system::{ProcessTool, SelfModifyTool};
+use jamey_core::cache::CacheManager;
+use jamey_core::maintenance::Embedder;
+use jamey_core::memory::{Memory, MemoryStore, PostgresMemoryStore};
+use jamey_core::redaction::Redactor;
+use jamey_core::secrets::SecretManager;
+use jamey_core::supervisor::Supervisor;
+use jamey_core::usage::{PostgresUsageStore, UsageRetention};
+use jamey_providers::openrouter::{OpenRouterProvider, DEFAULT_EMBEDDING_MODEL};
+use jamey_protocol::CreateSessionRequest;
+use jamey_tools::connector::{CapabilityLevel, ToolPolicy};
+use jamey_tools::connectors::{ScratchpadConnector, TelegramConnector, WebhookConnector};
+use jamey_tools::path_policy::PathPolicy;
+use jamey_tools::scratchpad::Scratchpad;
+use jamey_tools::oauth::{access_token_from_secret, OAuthManager, OAuthProvider};
+use jamey_tools::system::{ProcessTool, SelfModifyTool, SystemConfigTool};
+use std::collections::HashSet;
 use std::sync::Arc;
 use thiserror::Error;
 use tokio::sync::broadcast;
@@ -24,6 +63,8 @@ pub enum RuntimeError {
     Tool(#[from] jamey_tools::ToolError),
     #[error("Session not found: {0}")]
     SessionNotFound(Uuid),
+    #[error("Invalid session request: {0}")]
+    InvalidSessionRequest(String),
 }
 
 /// Manages active user sessions and their state
@@ -42,14 +83,34 @@ pub struct Session {
     pub id: Uuid,
     pub memory_context: DashMap<Uuid, Memory>,
     pub last_activity: std::time::Instant,
+    /// Connectors this session may be offered and may call
+    pub tool_policy: ToolPolicy,
+    /// Whose preference profile shapes the session's replies
+    pub user_id: Option<String>,
+    /// Picked for this session; turns use the configured default otherwise
+    pub persona: Option<Persona>,
+    /// How strictly replies are held to the output guardrails; the
+    /// configured default when unset
+    pub strictness: Option<Strictness>,
+    /// Project the session works in; file tools and memory recall are
+    /// confined to it
+    pub workspace: Option<Workspace>,
+    /// Timezone and locale the user gave; the runtime's otherwise
+    pub time: Option<TimeContext>,
 }
 
 impl Session {
-    fn new(id: Uuid) -> Self {
+    fn new(id: Uuid, tool_policy: ToolPolicy) -> Self {
         Self {
             id,
             memory_context: DashMap::new(),
             last_activity: std::time::Instant::now(),
+            tool_policy,
+            user_id: None,
+            persona: None,
+            strictness: None,
+            workspace: None,
+            time: None,
         }
     }
 
@@ -117,11 +178,134 @@ impl SessionManager {
     }
 
     pub fn create_session(&self) -> Uuid {
+        self.create_session_with_policy(ToolPolicy::unrestricted())
+    }
+
+    /// Create a session that can only see and call connectors `policy` permits
+    pub fn create_session_with_policy(&self, policy: ToolPolicy) -> Uuid {
         let session_id = Uuid::new_v4();
-        self.sessions.insert(session_id, Session::new(session_id));
+        self.sessions.insert(session_id, Session::new(session_id, policy));
+        status::record_session_started("new");
         session_id
     }
 
+    /// Create a session from a protocol request, turning its tool
+    /// preferences and capability cap into the session's [`ToolPolicy`] and
+    /// the `timezone` and `locale` metadata keys into its [`TimeContext`]
+    pub fn create_session_from(&self, request: &CreateSessionRequest) -> Result<Uuid, RuntimeError> {
+        let policy = tool_policy(request)?;
+        let time = self.time_context(request)?;
+        let id = self.create_session_with_policy(policy);
+        if let Some(user) = &request.user_id {
+            self.set_user(id, user);
+        }
+        if time.is_some() {
+            self.set_time(id, time);
+        }
+        Ok(id)
+    }
+
+    /// The runtime's [`TimeContext`] with the request's metadata applied,
+    /// if it sets either key
+    fn time_context(&self, request: &CreateSessionRequest) -> Result<Option<TimeContext>, RuntimeError> {
+        let timezone = request.metadata.get("timezone").and_then(|v| v.as_str());
+        let locale = request.metadata.get("locale").and_then(|v| v.as_str());
+        if timezone.is_none() && locale.is_none() {
+            return Ok(None);
+        }
+        self.config
+            .locale
+            .time_context()
+            .unwrap_or_default()
+            .with(timezone, locale)
+            .map(Some)
+            .map_err(|e| RuntimeError::InvalidSessionRequest(e.to_string()))
+    }
+
+    /// Re-register a persisted session so a conversation can continue under its ID
+    pub fn resume_session(&self, id: Uuid) -> Uuid {
+        self.sessions.entry(id).or_insert_with(|| {
+            status::record_session_started("resumed");
+            Session::new(id, ToolPolicy::unrestricted())
+        });
+        id
+    }
+
+    /// Policy for `id`; sessions this manager doesn't know get no limits,
+    /// matching turns run without a session
+    pub fn tool_policy(&self, id: Uuid) -> ToolPolicy {
+        self.sessions
+            .get(&id)
+            .map(|s| s.tool_policy.clone())
+            .unwrap_or_default()
+    }
+
+    /// Replace `id`'s policy, as when taking it over from another runtime
+    pub fn set_tool_policy(&self, id: Uuid, policy: ToolPolicy) {
+        if let Some(mut session) = self.sessions.get_mut(&id) {
+            session.tool_policy = policy;
+        }
+    }
+
+    pub fn contains(&self, id: Uuid) -> bool {
+        self.sessions.contains_key(&id)
+    }
+
+    /// Attribute `id` to `user`, whose preferences then apply to its turns
+    pub fn set_user(&self, id: Uuid, user: &str) {
+        if let Some(mut session) = self.sessions.get_mut(&id) {
+            session.user_id = Some(user.to_string());
+        }
+    }
+
+    pub fn user(&self, id: Uuid) -> Option<String> {
+        self.sessions.get(&id).and_then(|s| s.user_id.clone())
+    }
+
+    /// Run `id`'s turns under `persona` from now on
+    pub fn set_persona(&self, id: Uuid, persona: Persona) {
+        if let Some(mut session) = self.sessions.get_mut(&id) {
+            session.persona = Some(persona);
+        }
+    }
+
+    pub fn persona(&self, id: Uuid) -> Option<Persona> {
+        self.sessions.get(&id).and_then(|s| s.persona.clone())
+    }
+
+    /// Hold `id`'s replies to the output guardrails at `strictness`
+    pub fn set_strictness(&self, id: Uuid, strictness: Strictness) {
+        if let Some(mut session) = self.sessions.get_mut(&id) {
+            session.strictness = Some(strictness);
+        }
+    }
+
+    pub fn strictness(&self, id: Uuid) -> Option<Strictness> {
+        self.sessions.get(&id).and_then(|s| s.strictness)
+    }
+
+    /// Attach `id` to `workspace`, or detach it with `None`
+    pub fn set_workspace(&self, id: Uuid, workspace: Option<Workspace>) {
+        if let Some(mut session) = self.sessions.get_mut(&id) {
+            session.workspace = workspace;
+        }
+    }
+
+    pub fn workspace(&self, id: Uuid) -> Option<Workspace> {
+        self.sessions.get(&id).and_then(|s| s.workspace.clone())
+    }
+
+    /// Read and write `id`'s times in `time`, or the runtime's with `None`
+    pub fn set_time(&self, id: Uuid, time: Option<TimeContext>) {
+        if let Some(mut session) = self.sessions.get_mut(&id) {
+            session.time = time;
+        }
+    }
+
+    pub fn time(&self, id: Uuid) -> Option<TimeContext> {
+        self.sessions.get(&id).and_then(|s| s.time.clone())
+    }
+
     pub fn get_session(&self, id: Uuid) -> Option<Session> {
         // Optimize: Update last_activity in-place instead of cloning entire session
         self.sessions.get_mut(&id).map(|mut s| {
@@ -130,12 +314,83 @@ impl SessionManager {
         })
     }
 
-    pub fn cleanup_expired_sessions(&self, timeout: std::time::Duration) {
+    /// Sessions that have not yet expired
+    pub fn active_count(&self) -> usize {
+        self.sessions.len()
+    }
+
+    /// Count `id` as active now
+    pub fn touch(&self, id: Uuid) {
+        if let Some(mut session) = self.sessions.get_mut(&id) {
+            session.last_activity = std::time::Instant::now();
+        }
+    }
+
+    /// End the sessions idle for `timeout` and return them, for
+    /// [`RuntimeState::archive_idle_sessions`] to archive
+    pub fn cleanup_expired_sessions(&self, timeout: std::time::Duration) -> Vec<Session> {
         let now = std::time::Instant::now();
-        self.sessions.retain(|_, session| {
-            now.duration_since(session.last_activity) < timeout
-        });
+        let expired: Vec<Uuid> = self
+            .sessions
+            .iter()
+            .filter(|s| now.duration_since(s.last_activity) >= timeout)
+            .map(|s| s.id)
+            .collect();
+        expired
+            .into_iter()
+            .filter_map(|id| {
+                self.sessions
+                    .remove_if(&id, |_, s| now.duration_since(s.last_activity) >= timeout)
+                    .map(|(_, session)| session)
+            })
+            .collect()
+    }
+
+    /// Live sessions attributed to `user`
+    pub fn sessions_of(&self, user: &str) -> Vec<Uuid> {
+        self.sessions
+            .iter()
+            .filter(|s| s.user_id.as_deref() == Some(user))
+            .map(|s| s.id)
+            .collect()
+    }
+
+    /// Drop the cached copies of `memories` held by live sessions; returns
+    /// how many there were
+    pub fn forget_memories(&self, memories: &HashSet<Uuid>, dry_run: bool) -> usize {
+        let mut cached = 0;
+        for session in self.sessions.iter() {
+            if dry_run {
+                cached += session.memory_context.iter().filter(|m| memories.contains(m.key())).count();
+            } else {
+                let before = session.memory_context.len();
+                session.memory_context.retain(|id, _| !memories.contains(id));
+                cached += before - session.memory_context.len();
+            }
+        }
+        cached
     }
+
+    /// End whichever of `ids` are live; returns how many were
+    pub fn end_sessions(&self, ids: &HashSet<Uuid>, dry_run: bool) -> usize {
+        if dry_run {
+            return ids.iter().filter(|id| self.sessions.contains_key(id)).count();
+        }
+        ids.iter().filter(|id| self.sessions.remove(id).is_some()).count()
+    }
+}
+
+fn tool_policy(request: &CreateSessionRequest) -> Result<ToolPolicy, RuntimeError> {
+    let max_capability = match &request.max_capability {
+        Some(level) => level
+            .parse::<CapabilityLevel>()
+            .map_err(RuntimeError::InvalidSessionRequest)?,
+        None => CapabilityLevel::FullAccess,
+    };
+    Ok(ToolPolicy {
+        allowed: request.tool_preferences.clone(),
+        max_capability,
+    })
 }
 
 /// Manages tool registration and access
@@ -143,6 +398,7 @@ pub struct ToolRegistry {
     process_tool: Option<ProcessTool>,
     #[cfg(windows)]
     registry_tool: Option<jamey_tools::RegistryTool>,
+    system_config_tool: Option<SystemConfigTool>,
     self_modify_tool: Option<SelfModifyTool>,
 }
 
@@ -161,13 +417,24 @@ impl ToolRegistry {
             None
         };
 
-        let self_modify_tool = SelfModifyTool::new(&config.tools.backup_dir)
+        // Cross-platform counterpart of the registry tool, gated by the same flag
+        let system_config_tool = if config.tools.enable_registry_tool {
+            Some(SystemConfigTool::default())
+        } else {
+            None
+        };
+
+        let path_policy = PathPolicy::new(&config.tools.path_policy)
             .map_err(|e| RuntimeError::Initialization(e.to_string()))?;
+        let self_modify_tool = SelfModifyTool::new(&config.tools.backup_dir)
+            .map_err(|e| RuntimeError::Initialization(e.to_string()))?
+            .with_path_policy(path_policy);
 
         Ok(Self {
             process_tool,
             #[cfg(windows)]
             registry_tool,
+            system_config_tool,
             self_modify_tool: Some(self_modify_tool),
         })
     }
@@ -181,6 +448,10 @@ impl ToolRegistry {
         self.registry_tool.as_ref()
     }
 
+    pub fn get_system_config_tool(&self) -> Option<&SystemConfigTool> {
+        self.system_config_tool.as_ref()
+    }
+
     pub fn get_self_modify_tool(&self) -> Option<&SelfModifyTool> {
         self.self_modify_tool.as_ref()
     }
@@ -196,6 +467,33 @@ impl ToolRegistry {
 /// - tool_registry: Shared read-only tool instances
 /// - hybrid_orchestrator: Shared mutable orchestrator state (Mutex for interior mutability)
 /// - scheduler: Shared mutable scheduler state (Mutex for interior mutability)
+/// - secret_manager: Shared so rotations reach the propagation task
+/// - session_store: Shared transcript persistence, stateless apart from its directory
+/// - approval_queue: Shared handle to the on-disk approval queue
+/// - budget: Shared spend counter updated by every chat turn
+/// - audit_log: Shared with the tracing layer that signs audit events into it
+/// - usage_log: Shared handle to the on-disk token and cost log and its database ledger
+/// - prompt_traces: Shared handle to the sampled prompt and completion traces, written during turns
+/// - project_store: Shared handle to watched-project indexes
+/// - attachment_store: Shared handle to uploaded message attachments
+/// - preference_store: Shared handle to per-user feedback profiles
+/// - persona_store: Shared handle to saved personas, read at every turn
+/// - workspace_store: Shared handle to saved workspaces
+/// - undo_log: Shared record of reversible tool effects, written during turns
+/// - offline_queue: Shared handle to background requests waiting for the provider
+/// - router: Shared so every turn feeds the latencies routing rules check
+/// - guardrails: Shared output filters, compiled once and run on every reply
+/// - degradation: Shared fallback ladder, holding the answers kept for its cached tier
+/// - path_policy: Shared file access rules, checked by ingestion and project watching
+/// - in_flight: Registry of running turns, model calls, tools and jobs, shared with the task inspector
+/// - time: The runtime's timezone and locale, for sessions that don't give their own
+/// - supervisor: Restarts background loops that panic; clones share their health with the task inspector
+/// - events: Broadcast bus for turn, tool and hook events
+/// - webhooks: Registered webhooks, shared with the `webhook` connector
+/// - telegram: Telegram bot, when a bot token is configured
+/// - oauth: OAuth logins, shared with the refresh task, when clients are configured
+/// - cluster: Connection to the other runtimes, when clustering is enabled
+/// - certificates: HTTPS certificate, renewed in the background, when HTTPS is enabled
 pub struct RuntimeState {
     pub config: Arc<RuntimeConfig>,
     pub session_manager: Arc<SessionManager>,
@@ -204,6 +502,35 @@ pub struct RuntimeState {
     pub tool_registry: Arc<ToolRegistry>,
     pub hybrid_orchestrator: Arc<tokio::sync::Mutex<HybridOrchestrator>>,
     pub scheduler: Arc<tokio::sync::Mutex<TaskScheduler>>,
+    pub secret_manager: Arc<SecretManager>,
+    pub session_store: Arc<SessionStore>,
+    pub approval_queue: Arc<ApprovalQueue>,
+    pub budget: Arc<BudgetTracker>,
+    pub audit_log: Arc<AuditLog>,
+    pub usage_log: Arc<UsageLog>,
+    pub prompt_traces: Arc<PromptTraceLog>,
+    pub project_store: Arc<ProjectStore>,
+    pub attachment_store: Arc<AttachmentStore>,
+    pub preference_store: Arc<PreferenceStore>,
+    pub persona_store: Arc<PersonaStore>,
+    pub workspace_store: Arc<WorkspaceStore>,
+    pub undo_log: Arc<UndoLog>,
+    pub offline_queue: Arc<OfflineQueue>,
+    pub router: Arc<ModelRouter>,
+    pub guardrails: Arc<Guardrails>,
+    pub degradation: Arc<Degradation>,
+    pub path_policy: Arc<PathPolicy>,
+    /// Admits chat turns and background model calls, interactive first
+    pub request_queue: RequestQueue,
+    pub in_flight: InFlight,
+    pub time: TimeContext,
+    pub supervisor: Supervisor,
+    pub events: EventBus,
+    pub webhooks: WebhookConnector,
+    pub telegram: Option<Arc<TelegramBot>>,
+    pub oauth: Option<Arc<OAuthManager>>,
+    pub cluster: Option<Arc<Cluster>>,
+    pub certificates: Option<Arc<CertificateManager>>,
     pub shutdown_signal: broadcast::Sender<()>,
 }
 
@@ -217,32 +544,75 @@ impl RuntimeState {
             port: Some(config.memory.postgres_port),
             dbname: Some(config.memory.postgres_db.clone()),
             user: Some(config.memory.postgres_user.clone()),
-            password: Some(config.memory.postgres_password.clone()),
+            password: Some(config.memory.postgres_password.0.clone()),
             ..Default::default()
         }
         .create_pool(Some(deadpool_postgres::Runtime::Tokio1), tokio_postgres::NoTls)
         .map_err(|e| RuntimeError::Initialization(e.to_string()))?;
 
+        // Applied wherever text is persisted or leaves the process
+        let redactor = Arc::new(
+            Redactor::from_config(&config.security.redaction)
+                .map_err(|e| RuntimeError::Initialization(e.to_string()))?
+        );
+        let events = EventBus::new().with_redactor(Arc::clone(&redactor));
+        let supervisor = {
+            let events = events.clone();
+            Supervisor::new(config.supervisor.clone()).with_panic_hook(Arc::new(move |task, message| {
+                events.publish(
+                    TASK_PANICKED,
+                    None,
+                    serde_json::json!({ "task": task, "message": message }),
+                );
+            }))
+        };
+
         // Initialize components
         tracing::debug!("Creating PostgresMemoryStore Arc");
-        let memory_store = Arc::new(
-            PostgresMemoryStore::new(pool, config.memory.vector_dimension)
+        let usage_ledger = Arc::new(
+            PostgresUsageStore::new(pool.clone())
                 .await
-                .map_err(|e| RuntimeError::Initialization(format!("Failed to create memory store: {}", e)))?
+                .map_err(|e| RuntimeError::Initialization(format!("Failed to create usage ledger: {}", e)))?
         );
-        tracing::debug!("PostgresMemoryStore Arc strong count: {}", Arc::strong_count(&memory_store));
+        let cluster_pool = config.cluster.enabled.then(|| pool.clone());
 
         tracing::debug!("Creating OpenRouterProvider Arc");
         // Optimize: Use reference to config instead of cloning Arc
+        // Re-ingesting a document only pays for the chunks that changed;
+        // session scratchpads share the same cache
+        let cache = Arc::new(
+            CacheManager::new(config.cache.clone())
+                .await
+                .map_err(|e| RuntimeError::Initialization(format!("Failed to create cache: {}", e)))?
+        );
         let llm_provider = Arc::new(
             OpenRouterProvider::new((*config).clone().into_openrouter_config()
                 .map_err(|e| RuntimeError::Initialization(format!("Failed to create OpenRouter config: {}", e)))?)
                 .map_err(|e| RuntimeError::Initialization(format!("Failed to create OpenRouter provider: {}", e)))?
+                .with_embedding_cache(Arc::clone(&cache))
         );
         tracing::debug!("OpenRouterProvider Arc strong count: {}", Arc::strong_count(&llm_provider));
+        // Archived memories come back re-embedded with the default model
+        let rehydrator: Arc<dyn Embedder> = Arc::new(ProviderEmbedder::new(Arc::clone(&llm_provider), DEFAULT_EMBEDDING_MODEL));
+        let memory_store = Arc::new(
+            PostgresMemoryStore::new(pool, config.memory.vector_dimension)
+                .await
+                .map_err(|e| RuntimeError::Initialization(format!("Failed to create memory store: {}", e)))?
+                .with_redactor(Arc::clone(&redactor))
+                .with_retrieval(config.memory.retrieval)
+                .with_rehydration(Arc::clone(&rehydrator))
+                .with_quantization(config.memory.quantization)
+                .await
+                .map_err(|e| RuntimeError::Initialization(format!("Failed to set up embedding quantization: {}", e)))?
+        );
+        tracing::debug!("PostgresMemoryStore Arc strong count: {}", Arc::strong_count(&memory_store));
 
         tracing::debug!("Creating ToolRegistry Arc");
         let tool_registry = Arc::new(ToolRegistry::new(&config)?);
+        let path_policy = Arc::new(
+            PathPolicy::new(&config.tools.path_policy)
+                .map_err(|e| RuntimeError::Initialization(format!("Failed to load the path policy: {}", e)))?,
+        );
         tracing::debug!("ToolRegistry Arc strong count: {}", Arc::strong_count(&tool_registry));
         tracing::debug!("Creating SessionManager Arc");
         // Arc clone is necessary here as SessionManager needs to own the config
@@ -258,6 +628,38 @@ impl RuntimeState {
         };
         let mut hybrid_orch = HybridOrchestrator::new(safety_mode, config.tools.system_root.clone());
         
+        let secret_manager = Arc::new(
+            SecretManager::with_backend("jamey_runtime", jamey_core::backend_from_env()
+                .map_err(|e| RuntimeError::Initialization(format!("Failed to open secrets backend: {}", e)))?)
+                .map_err(|e| RuntimeError::Initialization(e.to_string()))?
+        );
+
+        let audit_key = crate::audit::signing_key(&secret_manager)
+            .map_err(|e| RuntimeError::Initialization(format!("Failed to load the audit signing key: {}", e)))?;
+        let audit_log = Arc::new(
+            AuditLog::open(config.audit_dir.clone(), audit_key)
+                .map_err(|e| RuntimeError::Initialization(format!("Failed to open the audit log: {}", e)))?
+                .with_redactor(Arc::clone(&redactor))
+                .with_sinks(
+                    crate::audit_sinks::build(&config.api.audit_sinks, &supervisor)
+                        .map_err(|e| RuntimeError::Initialization(e.to_string()))?,
+                ),
+        );
+        if !crate::audit::install(Arc::clone(&audit_log)) {
+            tracing::debug!("An audit log is already installed; keeping it");
+        }
+
+        let oauth = if config.tools.oauth_clients.is_empty() {
+            None
+        } else {
+            let mut manager = OAuthManager::new(Arc::clone(&secret_manager))
+                .map_err(|e| RuntimeError::Initialization(format!("Failed to create OAuth manager: {}", e)))?;
+            for client in &config.tools.oauth_clients {
+                manager = manager.with_client(client.clone());
+            }
+            Some(Arc::new(manager))
+        };
+
         // Register all connectors
         let full_access_config = FullAccessConfig {
             backup_dir: config.tools.backup_dir.clone(),
@@ -267,18 +669,148 @@ impl RuntimeState {
             linkedin_token: config.tools.linkedin_token.clone(),
             web_search_api_key: config.tools.web_search_api_key.clone(),
             mcp_server_url: config.tools.mcp_server_url.clone(),
+            oauth: oauth.clone(),
+            sandbox: jamey_tools::sandbox::ExecutionSandbox::new(config.tools.sandbox.clone())
+                .map_err(|e| RuntimeError::Initialization(format!("Failed to set up the execution sandbox: {}", e)))?,
+            path_policy: (*path_policy).clone(),
+            supervisor: supervisor.clone(),
+            linkedin_drafts: Some(jamey_tools::connectors::linkedin::DraftMemory::new(
+                memory_store.clone() as Arc<dyn MemoryStore + Send + Sync>,
+                Arc::clone(&rehydrator),
+            )),
+            timeouts: config.tools.timeouts.clone(),
         };
         hybrid_orch.register_all_connectors(&full_access_config).await
             .map_err(|e| RuntimeError::Initialization(format!("Failed to register connectors: {}", e)))?;
+        let research = ResearchWorkflow::new(Arc::clone(&llm_provider), Arc::clone(&memory_store), &config)
+            .map_err(|e| RuntimeError::Initialization(format!("Failed to create research workflow: {}", e)))?;
+        hybrid_orch.get_registry().register(Box::new(ResearchConnector::new(research))).await
+            .map_err(|e| RuntimeError::Initialization(format!("Failed to register research connector: {}", e)))?;
+        let scratchpad = Arc::new(Scratchpad::new(Arc::clone(&cache), config.tools.scratchpad.clone()));
+        hybrid_orch.get_registry().register(Box::new(ScratchpadConnector::new(scratchpad))).await
+            .map_err(|e| RuntimeError::Initialization(format!("Failed to register scratchpad connector: {}", e)))?;
+        let webhooks = WebhookConnector::new(config.webhook_dir.clone())
+            .map_err(|e| RuntimeError::Initialization(format!("Failed to load webhooks: {}", e)))?;
+        hybrid_orch.get_registry().register(Box::new(webhooks.clone())).await
+            .map_err(|e| RuntimeError::Initialization(format!("Failed to register webhook connector: {}", e)))?;
+        let telegram = match &config.tools.telegram_bot_token {
+            Some(token) => {
+                let connector = TelegramConnector::new(token.clone(), config.tools.telegram_allowed_chats.clone())
+                    .map_err(|e| RuntimeError::Initialization(format!("Failed to create Telegram bot: {}", e)))?;
+                hybrid_orch.get_registry().register(Box::new(connector.clone())).await
+                    .map_err(|e| RuntimeError::Initialization(format!("Failed to register Telegram connector: {}", e)))?;
+                Some(Arc::new(TelegramBot::new(connector)))
+            }
+            None => None,
+        };
         
         let hybrid_orchestrator = Arc::new(tokio::sync::Mutex::new(hybrid_orch));
 
         // Initialize Scheduler
         tracing::debug!("Creating TaskScheduler");
-        let scheduler = Arc::new(tokio::sync::Mutex::new(TaskScheduler::new()));
+        let time = config
+            .locale
+            .time_context()
+            .map_err(|e| RuntimeError::Initialization(e.to_string()))?;
+        let scheduler = Arc::new(tokio::sync::Mutex::new(TaskScheduler::new().with_timezone(time.timezone)));
 
         let (shutdown_tx, _) = broadcast::channel(1);
 
+        spawn_secret_propagation(
+            &supervisor,
+            Arc::clone(&secret_manager),
+            Arc::clone(&llm_provider),
+            Arc::clone(&hybrid_orchestrator),
+            shutdown_tx.subscribe(),
+        );
+        if let Some(oauth) = &oauth {
+            spawn_oauth_refresh(&supervisor, Arc::clone(oauth), shutdown_tx.subscribe());
+        }
+        let certificates = match config.into_tls_config().map_err(|e| RuntimeError::Initialization(e.to_string()))? {
+            Some(tls) => {
+                let manager = CertificateManager::new(tls, config.api.tls_certificates.clone(), &config.api.host)
+                    .await
+                    .map_err(|e| RuntimeError::Initialization(format!("Failed to set up the TLS certificate: {}", e)))?;
+                let manager = Arc::new(manager);
+                spawn_certificate_renewal(&supervisor, Arc::clone(&manager), shutdown_tx.subscribe());
+                Some(manager)
+            }
+            None => None,
+        };
+        webhooks::spawn_event_delivery(&supervisor, webhooks.clone(), &events, shutdown_tx.subscribe());
+
+        let prompt_traces = Arc::new(
+            PromptTraceLog::new(config.trace_dir.clone(), config.prompt_trace.clone()).with_redactor(Arc::clone(&redactor)),
+        );
+        if config.prompt_trace.enabled {
+            spawn_trace_retention(&supervisor, Arc::clone(&prompt_traces), shutdown_tx.subscribe());
+        }
+        let mut session_store = SessionStore::new(config.session_dir.clone()).with_redactor(redactor);
+        // Instances of a cluster share transcripts through the database
+        if let Some(pool) = &cluster_pool {
+            session_store = session_store
+                .with_postgres(pool.clone())
+                .await
+                .map_err(|e| RuntimeError::Initialization(format!("Failed to create transcript table: {}", e)))?;
+        }
+        let session_store = Arc::new(session_store);
+        let cluster = if config.cluster.enabled {
+            let cluster = Arc::new(
+                Cluster::connect(&config.cluster)
+                    .await
+                    .map_err(|e| RuntimeError::Initialization(format!("Failed to join cluster: {}", e)))?,
+            );
+            cluster.spawn_election(&supervisor, cluster_pool, events.clone(), shutdown_tx.subscribe());
+            Some(cluster)
+        } else {
+            None
+        };
+        let approval_queue = Arc::new(ApprovalQueue::new(config.approval_dir.clone()));
+        let usage_log = Arc::new(UsageLog::new(config.usage_dir.clone()).with_ledger(Arc::clone(&usage_ledger)));
+        spawn_usage_retention(
+            &supervisor,
+            usage_ledger,
+            UsageRetention::days(config.memory.usage_retention_days),
+            cluster.clone(),
+            shutdown_tx.subscribe(),
+        );
+        spawn_cold_tiering(
+            &supervisor,
+            Arc::clone(&memory_store),
+            config.memory.cold_after_days,
+            cluster.clone(),
+            shutdown_tx.subscribe(),
+        );
+        crate::sync::spawn_memory_sync(
+            &supervisor,
+            Arc::clone(&memory_store),
+            config.sync.clone(),
+            cluster.clone(),
+            shutdown_tx.subscribe(),
+        );
+        let project_store = Arc::new(ProjectStore::new(config.project_dir.clone()));
+        let attachment_store = Arc::new(AttachmentStore::new(config.attachment_dir.clone()));
+        let preference_store = Arc::new(PreferenceStore::new(config.preference_dir.clone()));
+        let persona_store = Arc::new(PersonaStore::new(config.persona_dir.clone()));
+        let workspace_store = Arc::new(WorkspaceStore::new(config.workspace_dir.clone()));
+        let undo_log = Arc::new(UndoLog::new(config.undo_dir.clone()));
+        let offline_queue = Arc::new(OfflineQueue::new(config.offline_dir.clone(), config.offline_queue.max_pending));
+        let router = Arc::new(ModelRouter::new(&config.routing));
+        let guardrails = Arc::new(
+            Guardrails::new(&config.guardrails)
+                .map_err(|e| RuntimeError::Initialization(format!("Failed to set up output guardrails: {}", e)))?,
+        );
+        let degradation = Arc::new(Degradation::new(config.degradation.clone()));
+        let request_queue = RequestQueue::new(config.queue.clone());
+        let budget = Arc::new(BudgetTracker::new(config.llm.daily_budget_usd));
+        // Carry today's spend over a restart
+        match usage_log.spent_today().await {
+            Ok(spent) => {
+                budget.record(spent);
+            }
+            Err(e) => tracing::warn!("Could not read today's usage from {}: {}", usage_log.dir().display(), e),
+        }
+
         Ok(Self {
             config,
             session_manager,
@@ -287,14 +819,257 @@ impl RuntimeState {
             tool_registry,
             hybrid_orchestrator,
             scheduler,
+            secret_manager,
+            session_store,
+            approval_queue,
+            budget,
+            audit_log,
+            usage_log,
+            prompt_traces,
+            project_store,
+            attachment_store,
+            preference_store,
+            persona_store,
+            workspace_store,
+            undo_log,
+            offline_queue,
+            router,
+            guardrails,
+            degradation,
+            path_policy,
+            request_queue,
+            in_flight: InFlight::new(),
+            time,
+            supervisor,
+            events,
+            webhooks,
+            telegram,
+            oauth,
+            cluster,
+            certificates,
             shutdown_signal: shutdown_tx,
         })
     }
 
     pub async fn shutdown(&self) {
         let _ = self.shutdown_signal.send(());
-        // Additional cleanup if needed
+        self.supervisor.stop();
     }
+
+    /// Model for a `task` request about `text` outside a chat session: the
+    /// routing rules' pick, or the configured default
+    pub fn model_for(&self, task: RouteTask, text: &str) -> String {
+        let request = RouteRequest {
+            task,
+            text,
+            tools: false,
+            persona: None,
+            budget_share: self.budget.used_share(),
+        };
+        self.router
+            .route(&request)
+            .unwrap_or(&self.config.llm.openrouter_default_model)
+            .to_string()
+    }
+}
+
+/// Apply rotated secrets to the LLM provider and connectors without a restart
+fn spawn_secret_propagation(
+    supervisor: &Supervisor,
+    secret_manager: Arc<SecretManager>,
+    llm_provider: Arc<OpenRouterProvider>,
+    hybrid_orchestrator: Arc<tokio::sync::Mutex<HybridOrchestrator>>,
+    shutdown: broadcast::Receiver<()>,
+) {
+    supervisor.spawn("secret_propagation", move || {
+        let secret_manager = Arc::clone(&secret_manager);
+        let llm_provider = Arc::clone(&llm_provider);
+        let hybrid_orchestrator = Arc::clone(&hybrid_orchestrator);
+        let mut rotations = secret_manager.subscribe();
+        let mut shutdown = shutdown.resubscribe();
+        async move {
+            loop {
+                let rotation = tokio::select! {
+                    _ = shutdown.recv() => break,
+                    event = rotations.recv() => match event {
+                        Ok(rotation) => rotation,
+                        Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                            tracing::warn!("Missed {} secret rotation events", skipped);
+                            continue;
+                        }
+                        Err(broadcast::error::RecvError::Closed) => break,
+                    },
+                };
+
+                let value = match secret_manager.get_secret(&rotation.key) {
+                    Ok(value) => value,
+                    Err(e) => {
+                        tracing::error!("Failed to load rotated secret {}: {}", rotation.key, e);
+                        continue;
+                    }
+                };
+
+                // OAuth logins/refreshes store a token bundle; connectors want the access token
+                let (key, value) = match OAuthProvider::from_secret_key(&rotation.key) {
+                    Some(provider) => match access_token_from_secret(&value) {
+                        Some(token) => (provider.credential_key(), token),
+                        None => continue,
+                    },
+                    None => (rotation.key.clone(), value),
+                };
+
+                if key == "openrouter_api_key" {
+                    if let Err(e) = llm_provider.set_api_key(value) {
+                        tracing::error!("Failed to apply rotated OpenRouter key: {}", e);
+                    }
+                    continue;
+                }
+
+                match hybrid_orchestrator.lock().await.propagate_credential(&key, &value).await {
+                    Ok(count) => tracing::info!(
+                        "Propagated {} (v{}) to {} connector(s)",
+                        key,
+                        rotation.version,
+                        count
+                    ),
+                    Err(e) => tracing::error!("Failed to propagate {}: {}", key, e),
+                }
+            }
+        }
+    });
+}
+
+/// Trim the usage ledger to its retention at startup and daily after. In a
+/// cluster only the leader trims; a new leader starts with a trim of its own.
+fn spawn_usage_retention(
+    supervisor: &Supervisor,
+    ledger: Arc<PostgresUsageStore>,
+    retention: UsageRetention,
+    cluster: Option<Arc<Cluster>>,
+    shutdown: broadcast::Receiver<()>,
+) {
+    const DAY: std::time::Duration = std::time::Duration::from_secs(24 * 60 * 60);
+    if retention.days.is_none() {
+        return;
+    }
+    supervisor.spawn("usage_retention", move || {
+        let ledger = Arc::clone(&ledger);
+        let cluster = cluster.clone();
+        let mut shutdown = shutdown.resubscribe();
+        async move {
+            // Checked more often than it runs so leadership changes are noticed
+            let mut ticker = tokio::time::interval(std::time::Duration::from_secs(60));
+            let mut last_run: Option<std::time::Instant> = None;
+            loop {
+                tokio::select! {
+                    _ = shutdown.recv() => break,
+                    _ = ticker.tick() => {}
+                }
+                if !cluster.as_ref().is_none_or(|cluster| cluster.is_leader()) {
+                    last_run = None;
+                    continue;
+                }
+                if last_run.is_some_and(|at| at.elapsed() < DAY) {
+                    continue;
+                }
+                last_run = Some(std::time::Instant::now());
+                match ledger.apply_retention(retention).await {
+                    Ok(0) => {}
+                    Ok(removed) => tracing::info!("Removed {} usage rows past retention", removed),
+                    Err(e) => tracing::warn!("Usage retention failed: {}", e),
+                }
+            }
+        }
+    });
+}
+
+/// Archive memories unread for `cold_after_days` at startup and daily after,
+/// leader only in a cluster like the usage trim. 0 turns tiering off.
+fn spawn_cold_tiering(
+    supervisor: &Supervisor,
+    memory_store: Arc<PostgresMemoryStore>,
+    cold_after_days: u32,
+    cluster: Option<Arc<Cluster>>,
+    shutdown: broadcast::Receiver<()>,
+) {
+    const DAY: std::time::Duration = std::time::Duration::from_secs(24 * 60 * 60);
+    if cold_after_days == 0 {
+        return;
+    }
+    supervisor.spawn("cold_tiering", move || {
+        let memory_store = Arc::clone(&memory_store);
+        let cluster = cluster.clone();
+        let mut shutdown = shutdown.resubscribe();
+        async move {
+            let mut ticker = tokio::time::interval(std::time::Duration::from_secs(60));
+            let mut last_run: Option<std::time::Instant> = None;
+            loop {
+                tokio::select! {
+                    _ = shutdown.recv() => break,
+                    _ = ticker.tick() => {}
+                }
+                if !cluster.as_ref().is_none_or(|cluster| cluster.is_leader()) {
+                    last_run = None;
+                    continue;
+                }
+                if last_run.is_some_and(|at| at.elapsed() < DAY) {
+                    continue;
+                }
+                last_run = Some(std::time::Instant::now());
+                let noop = |_: jamey_core::maintenance::JobProgress| {};
+                if let Err(e) = memory_store.archive_cold(cold_after_days, false, &noop).await {
+                    tracing::warn!("Cold tiering failed: {}", e);
+                }
+            }
+        }
+    });
+}
+
+/// Delete trace files past their retention at startup and daily after.
+/// Every instance keeps its own traces, so each trims its own.
+fn spawn_trace_retention(supervisor: &Supervisor, traces: Arc<PromptTraceLog>, shutdown: broadcast::Receiver<()>) {
+    supervisor.spawn("trace_retention", move || {
+        let traces = Arc::clone(&traces);
+        let mut shutdown = shutdown.resubscribe();
+        async move {
+            let mut ticker = tokio::time::interval(std::time::Duration::from_secs(24 * 60 * 60));
+            loop {
+                tokio::select! {
+                    _ = shutdown.recv() => break,
+                    _ = ticker.tick() => {}
+                }
+                match traces.apply_retention().await {
+                    Ok(0) => {}
+                    Ok(removed) => tracing::info!("Removed {} prompt trace file(s) past retention", removed),
+                    Err(e) => tracing::warn!("Prompt trace retention failed: {}", e),
+                }
+            }
+        }
+    });
+}
+
+/// Keep OAuth access tokens fresh; refreshed tokens reach connectors through
+/// the secret rotation events handled by `spawn_secret_propagation`
+fn spawn_oauth_refresh(supervisor: &Supervisor, oauth: Arc<OAuthManager>, shutdown: broadcast::Receiver<()>) {
+    supervisor.spawn("oauth_refresh", move || {
+        let oauth = Arc::clone(&oauth);
+        let mut shutdown = shutdown.resubscribe();
+        async move {
+            let mut ticker = tokio::time::interval(std::time::Duration::from_secs(300));
+            loop {
+                tokio::select! {
+                    _ = shutdown.recv() => break,
+                    _ = ticker.tick() => {
+                        for provider in oauth.providers() {
+                            if let Err(e) = oauth.access_token(provider).await {
+                                tracing::debug!("OAuth refresh skipped for {}: {}", provider.as_str(), e);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    });
 }
 
 #[cfg(test)]
@@ -310,7 +1085,7 @@ mod tests {
         config.tools.download_dir = temp_dir.path().join("downloads");
         config.tools.system_root = temp_dir.path().to_path_buf();
         // Test secrets - do not use in production
-        use tracing_honeycomb::SensitiveValue;
+        use crate::config::SensitiveValue;
         config.memory.postgres_password = SensitiveValue("test_password".to_string());
         config.llm.openrouter_api_key = SensitiveValue("test_key".to_string());
         
@@ -340,9 +1115,68 @@ mod tests {
         assert!(session.is_some());
         assert_eq!(session.unwrap().id, session_id);
 
+        assert_eq!(manager.tool_policy(session_id), ToolPolicy::unrestricted());
+        assert_eq!(manager.user(session_id), None);
+        manager.set_user(session_id, "alice");
+        assert_eq!(manager.user(session_id).as_deref(), Some("alice"));
+
         // Test session cleanup
         std::thread::sleep(std::time::Duration::from_millis(100));
-        manager.cleanup_expired_sessions(std::time::Duration::from_millis(50));
+        let expired = manager.cleanup_expired_sessions(std::time::Duration::from_millis(50));
+        assert_eq!(expired.len(), 1);
+        assert_eq!(expired[0].user_id.as_deref(), Some("alice"));
         assert!(manager.get_session(session_id).is_none());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_session_tool_policy() {
+        let manager = SessionManager::new(Arc::new(RuntimeConfig::default()));
+        let request = CreateSessionRequest {
+            user_id: None,
+            initial_context: None,
+            tool_preferences: vec!["network_web".to_string()],
+            max_capability: Some("read_only".to_string()),
+            metadata: serde_json::json!({}),
+        };
+
+        let id = manager.create_session_from(&request).unwrap();
+        let policy = manager.tool_policy(id);
+        assert_eq!(policy.allowed, vec!["network_web".to_string()]);
+        assert_eq!(policy.max_capability, CapabilityLevel::ReadOnly);
+
+        let request = CreateSessionRequest {
+            max_capability: Some("root".to_string()),
+            ..request
+        };
+        assert!(matches!(
+            manager.create_session_from(&request),
+            Err(RuntimeError::InvalidSessionRequest(_))
+        ));
+    }
+
+    #[test]
+    fn test_session_time_context() {
+        let manager = SessionManager::new(Arc::new(RuntimeConfig::default()));
+        let mut request = CreateSessionRequest {
+            user_id: None,
+            initial_context: None,
+            tool_preferences: Vec::new(),
+            max_capability: None,
+            metadata: serde_json::json!({}),
+        };
+        let id = manager.create_session_from(&request).unwrap();
+        assert!(manager.time(id).is_none());
+
+        request.metadata = serde_json::json!({ "timezone": "Asia/Tokyo", "locale": "ja_JP" });
+        let id = manager.create_session_from(&request).unwrap();
+        let time = manager.time(id).unwrap();
+        assert_eq!(time.timezone, chrono_tz::Asia::Tokyo);
+        assert_eq!(time.locale, "ja-JP");
+
+        request.metadata = serde_json::json!({ "timezone": "Tokyo" });
+        assert!(matches!(
+            manager.create_session_from(&request),
+            Err(RuntimeError::InvalidSessionRequest(_))
+        ));
+    }
+}
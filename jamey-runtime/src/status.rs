@@ -0,0 +1,668 @@
+//! Runtime status reporting
+//!
+//! A running runtime publishes its health as Prometheus metrics on the
+//! metrics listener (`api.metrics_port`). `jamey status` scrapes that endpoint
+//! and turns the samples back into a [`RuntimeStatus`]; both sides use the
+//! metric names defined here. Embedders running the runtime in-process read
+//! the same samples through a [`StatusProbe`].
+//!
+//! Metric names follow `jamey_<subsystem>_<quantity>[_<unit>]`: counters end
+//! in `_total`, durations are histograms in `_seconds`, and gauges are named
+//! for the value they hold. Labels are kept to small, fixed sets (`model`,
+//! `connector`, `operation`, `result`, `kind`, `queue`, `gpu`); session and request
+//! IDs never become labels. Memory store timings and cache lookups are
+//! recorded inside `jamey-core` and re-exported here so this module lists
+//! every name.
+
+use crate::approvals::ApprovalStatus;
+use crate::state::RuntimeState;
+use chrono::{NaiveDate, Utc};
+use jamey_protocol::TokenUsage;
+use jamey_tools::connector::ConnectorInfo;
+use jamey_tools::hardware::HardwareInventoryTool;
+use metrics_exporter_prometheus::PrometheusHandle;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast;
+
+pub const UP: &str = "jamey_up";
+pub const UPTIME_SECONDS: &str = "jamey_uptime_seconds";
+pub const SESSIONS_ACTIVE: &str = "jamey_sessions_active";
+pub const DB_UP: &str = "jamey_db_up";
+pub const DB_POOL_MAX: &str = "jamey_db_pool_max_size";
+pub const DB_POOL_SIZE: &str = "jamey_db_pool_size";
+pub const DB_POOL_AVAILABLE: &str = "jamey_db_pool_available";
+pub const DB_POOL_WAITING: &str = "jamey_db_pool_waiting";
+pub use jamey_core::profiling::{CACHE_HITS, CACHE_MISSES, OPERATION_DURATION};
+pub use jamey_core::supervisor::{TASK_PANICS, TASK_RESTARTS};
+/// Time until the provider starts streaming a response, labelled by model
+pub const PROVIDER_LATENCY: &str = "jamey_provider_request_duration_seconds";
+pub const PROVIDER_ERRORS: &str = "jamey_provider_errors_total";
+/// Tokens billed, labelled by `model` and `kind` (prompt or completion)
+pub const PROVIDER_TOKENS: &str = "jamey_provider_tokens_total";
+/// Connector runs, labelled by `connector` and `result`: success, failure
+/// (the connector reported it), error (it couldn't run), timeout or denied
+pub const CONNECTOR_EXECUTIONS: &str = "jamey_connector_executions_total";
+pub const CONNECTOR_DURATION: &str = "jamey_connector_execution_duration_seconds";
+/// Sessions opened, labelled by `kind` (new or resumed)
+pub const SESSIONS_STARTED: &str = "jamey_sessions_started_total";
+/// Items waiting, labelled by `queue`: pending approvals and enabled
+/// scheduled tasks
+pub const QUEUE_DEPTH: &str = "jamey_queue_depth";
+pub const LLM_SPEND_TODAY: &str = "jamey_llm_spend_today_usd";
+pub const LLM_DAILY_BUDGET: &str = "jamey_llm_daily_budget_usd";
+/// Requests left in the provider's current rate-limit window
+pub const PROVIDER_REQUESTS_REMAINING: &str = "jamey_provider_rate_limit_remaining";
+pub const PROVIDER_REQUEST_LIMIT: &str = "jamey_provider_rate_limit";
+/// Credits left on the provider key, when it has a limit
+pub const PROVIDER_CREDITS_REMAINING: &str = "jamey_provider_credits_remaining_usd";
+pub const PROVIDER_CREDITS_USED: &str = "jamey_provider_credits_used_usd";
+/// Share of time each GPU was busy, 0 to 1, labelled by `gpu` index
+pub const GPU_UTILIZATION: &str = "jamey_gpu_utilization_ratio";
+pub const GPU_MEMORY_USED: &str = "jamey_gpu_memory_used_bytes";
+pub const GPU_MEMORY_TOTAL: &str = "jamey_gpu_memory_total_bytes";
+
+/// How often the gauges are refreshed
+const REPORT_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Upper bound on the database ping so a hung pool can't stall reporting
+const DB_PING_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How often the provider key's credit usage is fetched; rate limits come
+/// with every response and need no polling
+const CREDITS_REFRESH: Duration = Duration::from_secs(300);
+
+/// Kept for the life of the process so NVML is loaded once
+static HARDWARE: Lazy<HardwareInventoryTool> = Lazy::new(HardwareInventoryTool::new);
+
+/// Share of the daily budget at which a `budget.warning` event goes out,
+/// besides the one when the budget itself is used up
+pub const BUDGET_WARNING_SHARE: f64 = 0.8;
+
+/// LLM spend for the current UTC day, checked against an optional budget
+#[derive(Debug)]
+pub struct BudgetTracker {
+    daily_limit_usd: Option<f64>,
+    today: Mutex<(NaiveDate, f64)>,
+}
+
+impl BudgetTracker {
+    pub fn new(daily_limit_usd: Option<f64>) -> Self {
+        Self {
+            daily_limit_usd,
+            today: Mutex::new((Utc::now().date_naive(), 0.0)),
+        }
+    }
+
+    /// Add a call's cost; returns the total spent today
+    pub fn record(&self, cost_usd: f64) -> f64 {
+        let mut today = self.today.lock().unwrap_or_else(|e| e.into_inner());
+        roll_over(&mut today);
+        today.1 += cost_usd;
+        today.1
+    }
+
+    pub fn spent_today(&self) -> f64 {
+        let mut today = self.today.lock().unwrap_or_else(|e| e.into_inner());
+        roll_over(&mut today);
+        today.1
+    }
+
+    pub fn daily_limit(&self) -> Option<f64> {
+        self.daily_limit_usd
+    }
+
+    /// Share of the daily limit spent today, when there is a limit
+    pub fn used_share(&self) -> Option<f64> {
+        let limit = self.daily_limit_usd.filter(|limit| *limit > 0.0)?;
+        Some(self.spent_today() / limit)
+    }
+
+    /// The share of the daily limit, [`BUDGET_WARNING_SHARE`] or all of it,
+    /// that today's spend went past when it rose from `before` to `after`
+    pub fn crossed_share(&self, before: f64, after: f64) -> Option<f64> {
+        let limit = self.daily_limit_usd?;
+        [1.0, BUDGET_WARNING_SHARE]
+            .into_iter()
+            .find(|share| before < limit * share && after >= limit * share)
+    }
+}
+
+fn roll_over(today: &mut (NaiveDate, f64)) {
+    let date = Utc::now().date_naive();
+    if today.0 != date {
+        *today = (date, 0.0);
+    }
+}
+
+/// Record one model request for the latency and error metrics
+pub(crate) fn record_provider_call(model: &str, elapsed: Duration, ok: bool) {
+    metrics::histogram!(PROVIDER_LATENCY, elapsed.as_secs_f64(), "model" => model.to_string());
+    if !ok {
+        metrics::increment_counter!(PROVIDER_ERRORS, "model" => model.to_string());
+    }
+}
+
+/// Count the tokens from one usage report
+pub(crate) fn record_provider_tokens(model: &str, usage: &TokenUsage) {
+    metrics::counter!(PROVIDER_TOKENS, usage.prompt_tokens.into(), "model" => model.to_string(), "kind" => "prompt");
+    metrics::counter!(
+        PROVIDER_TOKENS,
+        usage.completion_tokens.into(),
+        "model" => model.to_string(),
+        "kind" => "completion"
+    );
+}
+
+/// Record one connector run; `result` is one of the [`CONNECTOR_EXECUTIONS`] results
+pub(crate) fn record_connector_execution(connector: &str, elapsed: Option<Duration>, result: &'static str) {
+    metrics::increment_counter!(CONNECTOR_EXECUTIONS, "connector" => connector.to_string(), "result" => result);
+    if let Some(elapsed) = elapsed {
+        metrics::histogram!(CONNECTOR_DURATION, elapsed.as_secs_f64(), "connector" => connector.to_string());
+    }
+}
+
+pub(crate) fn record_session_started(kind: &'static str) {
+    metrics::increment_counter!(SESSIONS_STARTED, "kind" => kind);
+}
+
+pub(crate) fn record_queue_depth(queue: &'static str, depth: usize) {
+    metrics::gauge!(QUEUE_DEPTH, depth as f64, "queue" => queue);
+}
+
+/// In-process view of the metrics `jamey status` would scrape
+#[derive(Clone)]
+pub struct StatusProbe {
+    state: Arc<RuntimeState>,
+    metrics: PrometheusHandle,
+    started: Instant,
+}
+
+impl StatusProbe {
+    pub(crate) fn new(state: Arc<RuntimeState>, metrics: PrometheusHandle, started: Instant) -> Self {
+        Self { state, metrics, started }
+    }
+
+    /// Refresh the gauges and read every sample back
+    pub async fn status(&self) -> RuntimeStatus {
+        report(&self.state, self.started).await;
+        RuntimeStatus::from_samples(&parse_prometheus(&self.metrics.render()))
+    }
+
+    /// Registered connectors and whether each is enabled
+    pub async fn connectors(&self) -> Vec<ConnectorInfo> {
+        self.state.hybrid_orchestrator.lock().await.get_registry().describe().await
+    }
+}
+
+/// Refresh the status gauges until shutdown
+pub(crate) fn spawn_status_reporter(
+    probe: StatusProbe,
+    shutdown_rx: broadcast::Receiver<()>,
+) {
+    let supervisor = probe.state.supervisor.clone();
+    supervisor.spawn("status_reporter", move || {
+        let probe = probe.clone();
+        let mut interval = tokio::time::interval(REPORT_INTERVAL);
+        let mut shutdown_rx = shutdown_rx.resubscribe();
+        async move {
+            loop {
+                tokio::select! {
+                    _ = interval.tick() => report(&probe.state, probe.started).await,
+                    _ = shutdown_rx.recv() => {
+                        metrics::gauge!(UP, 0.0);
+                        tracing::debug!("Shutting down status reporter");
+                        break;
+                    }
+                }
+            }
+        }
+    });
+}
+
+async fn report(state: &RuntimeState, started: Instant) {
+    metrics::gauge!(UP, 1.0);
+    metrics::gauge!(UPTIME_SECONDS, started.elapsed().as_secs_f64());
+    metrics::gauge!(SESSIONS_ACTIVE, state.session_manager.active_count() as f64);
+
+    let pool = state.memory_store.pool_status();
+    metrics::gauge!(DB_POOL_MAX, pool.max_size as f64);
+    metrics::gauge!(DB_POOL_SIZE, pool.size as f64);
+    // deadpool reports waiters as negative availability
+    metrics::gauge!(DB_POOL_AVAILABLE, pool.available.max(0) as f64);
+    metrics::gauge!(DB_POOL_WAITING, (-pool.available).max(0) as f64);
+
+    let db_up = matches!(
+        tokio::time::timeout(DB_PING_TIMEOUT, state.memory_store.ping()).await,
+        Ok(Ok(()))
+    );
+    if !db_up {
+        tracing::warn!("Status check: database ping failed");
+    }
+    metrics::gauge!(DB_UP, if db_up { 1.0 } else { 0.0 });
+
+    match state.approval_queue.list(Some(ApprovalStatus::Pending)).await {
+        Ok(pending) => record_queue_depth("approvals", pending.len()),
+        Err(e) => tracing::debug!("Status check: can't read approval queue: {}", e),
+    }
+
+    metrics::gauge!(LLM_SPEND_TODAY, state.budget.spent_today());
+    if let Some(limit) = state.budget.daily_limit() {
+        metrics::gauge!(LLM_DAILY_BUDGET, limit);
+    }
+
+    if state.llm_provider.quota().credits_stale(CREDITS_REFRESH, std::time::SystemTime::now()) {
+        match tokio::time::timeout(DB_PING_TIMEOUT, state.llm_provider.refresh_quota()).await {
+            Ok(Ok(_)) => {}
+            Ok(Err(e)) => tracing::debug!("Status check: can't read provider credits: {}", e),
+            Err(_) => tracing::debug!("Status check: provider credits timed out"),
+        }
+    }
+    let quota = state.llm_provider.quota();
+    let gauges = [
+        (PROVIDER_REQUESTS_REMAINING, quota.requests_remaining.map(|n| n as f64)),
+        (PROVIDER_REQUEST_LIMIT, quota.request_limit.map(|n| n as f64)),
+        (PROVIDER_CREDITS_REMAINING, quota.credits_remaining),
+        (PROVIDER_CREDITS_USED, quota.credits_used),
+    ];
+    for (name, value) in gauges {
+        if let Some(value) = value {
+            metrics::gauge!(name, value);
+        }
+    }
+
+    match tokio::task::spawn_blocking(|| HARDWARE.snapshot()).await {
+        Ok(inventory) => {
+            for gpu in inventory.gpus {
+                let label = gpu.index.to_string();
+                if let Some(percent) = gpu.utilization_percent {
+                    metrics::gauge!(GPU_UTILIZATION, f64::from(percent) / 100.0, "gpu" => label.clone());
+                }
+                if let Some(used) = gpu.memory_used_bytes {
+                    metrics::gauge!(GPU_MEMORY_USED, used as f64, "gpu" => label.clone());
+                }
+                if let Some(total) = gpu.memory_total_bytes {
+                    metrics::gauge!(GPU_MEMORY_TOTAL, total as f64, "gpu" => label);
+                }
+            }
+        }
+        Err(e) => tracing::debug!("Status check: hardware inventory failed: {}", e),
+    }
+}
+
+/// One line of the Prometheus text exposition format
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Sample {
+    pub name: String,
+    pub labels: BTreeMap<String, String>,
+    pub value: f64,
+}
+
+/// Parse Prometheus text exposition, skipping comments and malformed lines
+pub fn parse_prometheus(text: &str) -> Vec<Sample> {
+    text.lines().filter_map(parse_sample).collect()
+}
+
+fn parse_sample(line: &str) -> Option<Sample> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+
+    let (name, labels, rest) = match line.find(['{', ' ']) {
+        Some(i) if line.as_bytes()[i] == b'{' => {
+            let (labels, consumed) = parse_labels(&line[i + 1..])?;
+            (&line[..i], labels, &line[i + 1 + consumed..])
+        }
+        Some(i) => (&line[..i], BTreeMap::new(), &line[i..]),
+        None => return None,
+    };
+
+    // An optional timestamp may follow the value
+    let value = rest.split_whitespace().next()?;
+    let value = match value {
+        "+Inf" => f64::INFINITY,
+        "-Inf" => f64::NEG_INFINITY,
+        other => other.parse().ok()?,
+    };
+
+    Some(Sample {
+        name: name.to_string(),
+        labels,
+        value,
+    })
+}
+
+/// Parse `key="value",...}`; returns the labels and bytes consumed
+fn parse_labels(input: &str) -> Option<(BTreeMap<String, String>, usize)> {
+    let mut labels = BTreeMap::new();
+    let mut chars = input.char_indices().peekable();
+
+    loop {
+        while chars.next_if(|(_, c)| *c == ',' || c.is_whitespace()).is_some() {}
+        match chars.peek() {
+            Some((i, '}')) => return Some((labels, i + 1)),
+            Some(_) => {}
+            None => return None,
+        }
+
+        let mut key = String::new();
+        for (_, c) in chars.by_ref() {
+            if c == '=' {
+                break;
+            }
+            key.push(c);
+        }
+        if chars.next()?.1 != '"' {
+            return None;
+        }
+
+        let mut value = String::new();
+        loop {
+            match chars.next()?.1 {
+                '"' => break,
+                '\\' => match chars.next()?.1 {
+                    'n' => value.push('\n'),
+                    other => value.push(other),
+                },
+                c => value.push(c),
+            }
+        }
+        labels.insert(key.trim().to_string(), value);
+    }
+}
+
+/// Health of a running runtime as seen through its metrics endpoint
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RuntimeStatus {
+    pub up: bool,
+    pub uptime_seconds: Option<f64>,
+    pub sessions_active: Option<u64>,
+    pub database: DatabaseStatus,
+    /// Hits over lookups, when the cache layer reports any
+    pub cache_hit_rate: Option<f64>,
+    pub providers: Vec<ProviderLatency>,
+    pub budget: BudgetStatus,
+    #[serde(default)]
+    pub quota: QuotaStatus,
+    /// Items waiting in each queue
+    #[serde(default)]
+    pub queues: BTreeMap<String, u64>,
+    /// Load and memory of each GPU that reports them
+    #[serde(default)]
+    pub gpus: Vec<GpuStatus>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GpuStatus {
+    pub gpu: String,
+    pub utilization: Option<f64>,
+    pub memory_used_bytes: Option<u64>,
+    pub memory_total_bytes: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DatabaseStatus {
+    pub up: Option<bool>,
+    pub pool_max_size: Option<u64>,
+    pub pool_size: Option<u64>,
+    pub pool_available: Option<u64>,
+    pub pool_waiting: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProviderLatency {
+    pub model: String,
+    pub requests: u64,
+    pub errors: u64,
+    pub mean_seconds: Option<f64>,
+    pub p50_seconds: Option<f64>,
+    pub p99_seconds: Option<f64>,
+    #[serde(default)]
+    pub prompt_tokens: u64,
+    #[serde(default)]
+    pub completion_tokens: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BudgetStatus {
+    pub spent_today_usd: Option<f64>,
+    pub daily_limit_usd: Option<f64>,
+}
+
+/// Provider rate limit and key credits
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct QuotaStatus {
+    pub requests_remaining: Option<u64>,
+    pub request_limit: Option<u64>,
+    pub credits_remaining_usd: Option<f64>,
+    pub credits_used_usd: Option<f64>,
+}
+
+impl BudgetStatus {
+    /// Fraction of today's budget already spent
+    pub fn used_fraction(&self) -> Option<f64> {
+        match (self.spent_today_usd, self.daily_limit_usd) {
+            (Some(spent), Some(limit)) if limit > 0.0 => Some(spent / limit),
+            _ => None,
+        }
+    }
+}
+
+impl RuntimeStatus {
+    pub fn from_samples(samples: &[Sample]) -> Self {
+        let value = |name: &str| {
+            samples
+                .iter()
+                .find(|s| s.name == name && s.labels.is_empty())
+                .map(|s| s.value)
+        };
+        let count = |name: &str| value(name).map(|v| v.max(0.0) as u64);
+
+        let cache_hit_rate = match (value(CACHE_HITS), value(CACHE_MISSES)) {
+            (Some(hits), Some(misses)) if hits + misses > 0.0 => Some(hits / (hits + misses)),
+            _ => None,
+        };
+
+        Self {
+            up: value(UP) == Some(1.0),
+            uptime_seconds: value(UPTIME_SECONDS),
+            sessions_active: count(SESSIONS_ACTIVE),
+            database: DatabaseStatus {
+                up: value(DB_UP).map(|v| v == 1.0),
+                pool_max_size: count(DB_POOL_MAX),
+                pool_size: count(DB_POOL_SIZE),
+                pool_available: count(DB_POOL_AVAILABLE),
+                pool_waiting: count(DB_POOL_WAITING),
+            },
+            cache_hit_rate,
+            providers: provider_latencies(samples),
+            budget: BudgetStatus {
+                spent_today_usd: value(LLM_SPEND_TODAY),
+                daily_limit_usd: value(LLM_DAILY_BUDGET),
+            },
+            quota: QuotaStatus {
+                requests_remaining: count(PROVIDER_REQUESTS_REMAINING),
+                request_limit: count(PROVIDER_REQUEST_LIMIT),
+                credits_remaining_usd: value(PROVIDER_CREDITS_REMAINING),
+                credits_used_usd: value(PROVIDER_CREDITS_USED),
+            },
+            queues: samples
+                .iter()
+                .filter(|s| s.name == QUEUE_DEPTH)
+                .filter_map(|s| Some((s.labels.get("queue")?.clone(), s.value.max(0.0) as u64)))
+                .collect(),
+            gpus: gpu_statuses(samples),
+        }
+    }
+}
+
+/// Group the GPU gauges by `gpu` label, in index order
+fn gpu_statuses(samples: &[Sample]) -> Vec<GpuStatus> {
+    let mut by_gpu: BTreeMap<u64, GpuStatus> = BTreeMap::new();
+    for sample in samples {
+        let Some(gpu) = sample.labels.get("gpu") else {
+            continue;
+        };
+        let Ok(index) = gpu.parse() else {
+            continue;
+        };
+        let entry = by_gpu.entry(index).or_insert_with(|| GpuStatus {
+            gpu: gpu.clone(),
+            ..Default::default()
+        });
+        match sample.name.as_str() {
+            GPU_UTILIZATION => entry.utilization = Some(sample.value),
+            GPU_MEMORY_USED => entry.memory_used_bytes = Some(sample.value.max(0.0) as u64),
+            GPU_MEMORY_TOTAL => entry.memory_total_bytes = Some(sample.value.max(0.0) as u64),
+            _ => {}
+        }
+    }
+    by_gpu.into_values().collect()
+}
+
+/// Group the latency summary and error counter by model
+fn provider_latencies(samples: &[Sample]) -> Vec<ProviderLatency> {
+    let sum_name = format!("{}_sum", PROVIDER_LATENCY);
+    let count_name = format!("{}_count", PROVIDER_LATENCY);
+    let mut by_model: BTreeMap<String, (ProviderLatency, Option<f64>)> = BTreeMap::new();
+
+    for sample in samples {
+        let Some(model) = sample.labels.get("model") else {
+            continue;
+        };
+        let entry = by_model.entry(model.clone()).or_insert_with(|| {
+            (
+                ProviderLatency {
+                    model: model.clone(),
+                    ..Default::default()
+                },
+                None,
+            )
+        });
+
+        if sample.name == PROVIDER_LATENCY {
+            match sample.labels.get("quantile").map(String::as_str) {
+                Some("0.5") => entry.0.p50_seconds = Some(sample.value),
+                Some("0.99") => entry.0.p99_seconds = Some(sample.value),
+                _ => {}
+            }
+        } else if sample.name == sum_name {
+            entry.1 = Some(sample.value);
+        } else if sample.name == count_name {
+            entry.0.requests = sample.value as u64;
+        } else if sample.name == PROVIDER_ERRORS {
+            entry.0.errors = sample.value as u64;
+        } else if sample.name == PROVIDER_TOKENS {
+            match sample.labels.get("kind").map(String::as_str) {
+                Some("prompt") => entry.0.prompt_tokens = sample.value as u64,
+                Some("completion") => entry.0.completion_tokens = sample.value as u64,
+                _ => {}
+            }
+        }
+    }
+
+    by_model
+        .into_values()
+        .map(|(mut latency, sum)| {
+            if latency.requests > 0 {
+                latency.mean_seconds = sum.map(|sum| sum / latency.requests as f64);
+            }
+            latency
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCRAPE: &str = r#"
+# TYPE jamey_up gauge
+jamey_up 1
+jamey_uptime_seconds 3600.5
+jamey_sessions_active 3
+jamey_db_up 1
+jamey_db_pool_max_size 16
+jamey_db_pool_size 4
+jamey_db_pool_available 2
+jamey_db_pool_waiting 0
+jamey_cache_hits_total 30
+jamey_cache_misses_total 10
+# TYPE jamey_provider_request_duration_seconds summary
+jamey_provider_request_duration_seconds{model="gpt-4",quantile="0.5"} 0.8
+jamey_provider_request_duration_seconds{model="gpt-4",quantile="0.99"} 2.5
+jamey_provider_request_duration_seconds_sum{model="gpt-4"} 10
+jamey_provider_request_duration_seconds_count{model="gpt-4"} 8
+jamey_provider_errors_total{model="gpt-4"} 1
+jamey_provider_tokens_total{model="gpt-4",kind="prompt"} 1200
+jamey_provider_tokens_total{model="gpt-4",kind="completion"} 300
+jamey_queue_depth{queue="approvals"} 2
+jamey_queue_depth{queue="scheduled_tasks"} 0
+jamey_llm_spend_today_usd 1.25
+jamey_llm_daily_budget_usd 5
+jamey_provider_rate_limit_remaining 18
+jamey_provider_rate_limit 20
+jamey_provider_credits_remaining_usd 7.5
+jamey_gpu_utilization_ratio{gpu="0"} 0.4
+jamey_gpu_memory_used_bytes{gpu="0"} 2147483648
+jamey_gpu_memory_total_bytes{gpu="0"} 8589934592
+jamey_gpu_memory_total_bytes{gpu="1"} 4294967296
+"#;
+
+    #[test]
+    fn test_parse_labels_with_escapes() {
+        let samples = parse_prometheus(r#"m{a="x\"y",b="1,2"} 4 1700000000"#);
+        assert_eq!(samples.len(), 1);
+        assert_eq!(samples[0].labels["a"], "x\"y");
+        assert_eq!(samples[0].labels["b"], "1,2");
+        assert_eq!(samples[0].value, 4.0);
+        assert!(parse_prometheus("# HELP m help\nbroken{a=\"1\" 2").is_empty());
+    }
+
+    #[test]
+    fn test_status_from_scrape() {
+        let status = RuntimeStatus::from_samples(&parse_prometheus(SCRAPE));
+        assert!(status.up);
+        assert_eq!(status.sessions_active, Some(3));
+        assert_eq!(status.database.up, Some(true));
+        assert_eq!(status.database.pool_size, Some(4));
+        assert_eq!(status.cache_hit_rate, Some(0.75));
+        assert_eq!(status.budget.used_fraction(), Some(0.25));
+        assert_eq!((status.quota.requests_remaining, status.quota.request_limit), (Some(18), Some(20)));
+        assert_eq!(status.quota.credits_remaining_usd, Some(7.5));
+        assert_eq!(status.quota.credits_used_usd, None);
+
+        let gpt4 = &status.providers[0];
+        assert_eq!(gpt4.model, "gpt-4");
+        assert_eq!(gpt4.requests, 8);
+        assert_eq!(gpt4.errors, 1);
+        assert_eq!(gpt4.mean_seconds, Some(1.25));
+        assert_eq!(gpt4.p99_seconds, Some(2.5));
+        assert_eq!((gpt4.prompt_tokens, gpt4.completion_tokens), (1200, 300));
+        assert_eq!(status.queues["approvals"], 2);
+        assert_eq!(status.queues["scheduled_tasks"], 0);
+        assert_eq!(status.gpus.len(), 2);
+        assert_eq!(status.gpus[0].utilization, Some(0.4));
+        assert_eq!(status.gpus[0].memory_used_bytes, Some(2 << 30));
+        assert_eq!((status.gpus[1].utilization, status.gpus[1].memory_total_bytes), (None, Some(4 << 30)));
+    }
+
+    #[test]
+    fn test_budget_tracker() {
+        let budget = BudgetTracker::new(Some(2.0));
+        budget.record(0.5);
+        assert_eq!(budget.record(0.25), 0.75);
+        assert_eq!(budget.spent_today(), 0.75);
+        assert_eq!(budget.daily_limit(), Some(2.0));
+        assert_eq!(budget.used_share(), Some(0.375));
+
+        assert_eq!(budget.crossed_share(1.5, 1.7), Some(BUDGET_WARNING_SHARE));
+        assert_eq!(budget.crossed_share(1.5, 2.5), Some(1.0));
+        assert_eq!(budget.crossed_share(1.7, 1.9), None);
+        assert_eq!(BudgetTracker::new(None).crossed_share(0.0, 100.0), None);
+    }
+}
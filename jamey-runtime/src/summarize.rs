@@ -0,0 +1,192 @@
+//! Rolling conversation summaries
+//!
+//! When a session's history grows past the context budget, the oldest turns
+//! are folded into one pinned summary message that replaces them in the
+//! prompt. A previous summary is simply one of the oldest messages, so
+//! summaries roll forward as the conversation keeps growing.
+
+use crate::ingest::estimate_tokens;
+use jamey_protocol::{Message, Role};
+use jamey_providers::openrouter::{self, ChatRequest, LlmProvider, OpenRouterProvider};
+
+const SUMMARIZE_PROMPT: &str = "You condense the earlier part of a conversation so it can \
+continue without the original messages. Keep every fact, decision, name, number, file path, \
+instruction and open question, and any tool result the conversation relies on. Write concise \
+bullet points and add nothing new. Reply with the summary only.";
+
+/// Longest summary requested from the model, in tokens
+const SUMMARY_MAX_TOKENS: u32 = 1500;
+
+/// Most recent messages that are always sent verbatim
+const KEEP_RECENT: usize = 6;
+
+/// Per-message framing the provider adds on top of the content
+const MESSAGE_OVERHEAD_TOKENS: usize = 4;
+
+/// The oldest `replaced` messages of a history, folded into `summary`
+#[derive(Debug, Clone)]
+pub struct Compaction {
+    pub summary: Message,
+    pub replaced: usize,
+}
+
+impl Compaction {
+    pub fn new(summary: impl Into<String>, replaced: usize) -> Self {
+        let mut message = Message::system(format!(
+            "Summary of the earlier conversation:\n{}",
+            summary.into().trim()
+        ));
+        message.metadata = serde_json::json!({
+            "summary": true,
+            "summarized_messages": replaced,
+        });
+        Self { summary: message, replaced }
+    }
+
+    /// `history` with the summarized messages swapped for the summary
+    pub fn apply(&self, history: &[Message]) -> Vec<Message> {
+        std::iter::once(self.summary.clone())
+            .chain(history.iter().skip(self.replaced).cloned())
+            .collect()
+    }
+}
+
+/// Whether `message` is a summary produced by [`Compaction`]
+pub fn is_summary(message: &Message) -> bool {
+    message.metadata.get("summary").and_then(|v| v.as_bool()) == Some(true)
+}
+
+pub fn history_tokens(history: &[Message]) -> usize {
+    history.iter().map(message_tokens).sum()
+}
+
+fn message_tokens(message: &Message) -> usize {
+    estimate_tokens(&message.content) + MESSAGE_OVERHEAD_TOKENS
+}
+
+/// How many of the oldest messages to summarize so `history` fits in
+/// `budget` tokens, or `None` when it already fits (or `budget` is 0)
+///
+/// Enough is taken to bring the history down to half the budget, so a
+/// summary isn't needed again on the very next turn. The cut stops short of
+/// the last few messages and lands just before a user message, so a
+/// question is never separated from its answer.
+pub fn messages_to_summarize(history: &[Message], budget: usize) -> Option<usize> {
+    let mut remaining = history_tokens(history);
+    if budget == 0 || remaining <= budget {
+        return None;
+    }
+    let keep_from = history.len().saturating_sub(KEEP_RECENT);
+    let mut cut = 0;
+    while cut < keep_from && remaining > budget / 2 {
+        remaining -= message_tokens(&history[cut]);
+        cut += 1;
+    }
+    while cut < keep_from && history[cut].role != Role::User {
+        cut += 1;
+    }
+    // Re-summarizing a lone summary gains nothing
+    (cut >= 2).then_some(cut)
+}
+
+/// Ask the model for a summary of `messages`
+pub async fn summarize(
+    llm: &OpenRouterProvider,
+    model: &str,
+    messages: &[Message],
+) -> anyhow::Result<String> {
+    let request = ChatRequest {
+        model: model.to_string(),
+        messages: vec![
+            openrouter::Message {
+                role: "system".to_string(),
+                content: SUMMARIZE_PROMPT.to_string(),
+            },
+            openrouter::Message {
+                role: "user".to_string(),
+                content: transcript(messages),
+            },
+        ],
+        tools: None,
+        tool_choice: None,
+        temperature: Some(0.2),
+        max_tokens: Some(SUMMARY_MAX_TOKENS),
+    };
+
+    let response = llm.chat(request).await?;
+    let summary = response
+        .choices
+        .first()
+        .map(|c| c.message.content.trim().to_string())
+        .unwrap_or_default();
+    if summary.is_empty() {
+        anyhow::bail!("model returned an empty summary");
+    }
+    Ok(summary)
+}
+
+/// `messages` as the plain-text transcript the model is asked to summarize
+pub fn transcript(messages: &[Message]) -> String {
+    messages
+        .iter()
+        .map(|message| {
+            let speaker = match message.role {
+                _ if is_summary(message) => "Earlier summary",
+                Role::System => "System",
+                Role::User => "User",
+                Role::Assistant => "Assistant",
+                Role::Tool => "Tool",
+            };
+            format!("{}: {}", speaker, message.content.trim())
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn conversation(turns: usize, words: usize) -> Vec<Message> {
+        (0..turns)
+            .flat_map(|i| {
+                [
+                    Message::user(format!("question {} {}", i, "word ".repeat(words))),
+                    Message::assistant(format!("answer {} {}", i, "word ".repeat(words))),
+                ]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_short_history_is_left_alone() {
+        let history = conversation(3, 10);
+        assert_eq!(messages_to_summarize(&history, 10_000), None);
+        assert_eq!(messages_to_summarize(&conversation(50, 100), 0), None);
+    }
+
+    #[test]
+    fn test_cut_lands_before_a_user_message() {
+        let history = conversation(20, 100);
+        let budget = history_tokens(&history) / 2;
+        let cut = messages_to_summarize(&history, budget).unwrap();
+
+        assert!(cut >= 2);
+        assert!(cut <= history.len() - KEEP_RECENT);
+        assert_eq!(history[cut].role, Role::User);
+        assert!(history_tokens(&history[cut..]) <= budget);
+    }
+
+    #[test]
+    fn test_compaction_replaces_oldest_messages() {
+        let history = conversation(10, 100);
+        let compaction = Compaction::new("- the user asked ten questions", 8);
+        let compacted = compaction.apply(&history);
+
+        assert_eq!(compacted.len(), history.len() - 8 + 1);
+        assert!(is_summary(&compacted[0]));
+        assert_eq!(compacted[1].content, history[8].content);
+        assert!(!is_summary(&compacted[1]));
+        assert!(transcript(&compacted[..2]).starts_with("Earlier summary: Summary of"));
+    }
+}
@@ -0,0 +1,407 @@
+//! Memory sync between two instances
+//!
+//! Keeps the memories in the configured namespaces the same on two
+//! instances, e.g. a desktop and a homelab server. The instance with
+//! `sync.peer_url` set drives it: every `sync.interval_secs` it posts the
+//! writes and deletions it has made since the last round to the peer's
+//! [`PATH`] on its web server, and the reply carries the peer's own in
+//! return. Both sides apply what they receive with
+//! [`apply_changes`](PostgresMemoryStore::apply_changes), so the newer
+//! version of a memory wins and deletions travel as tombstones.
+//!
+//! Requests and replies are signed with HMAC-SHA256 over the body using the
+//! shared `sync.secret` and carry the time they were sent; anything unsigned,
+//! signed with another secret or more than [`MAX_CLOCK_SKEW`] old is
+//! refused.
+
+use crate::config::{RuntimeConfig, SensitiveValue};
+use crate::state::RuntimeState;
+use chrono::{DateTime, Utc};
+use jamey_core::secure_logging::Masked;
+use jamey_core::supervisor::Supervisor;
+use jamey_core::sync::{ApplyReport, SyncChange, SyncCursor};
+use jamey_core::PostgresMemoryStore;
+use jamey_tools::connectors::webhook::{sign_payload, verify_signature, SIGNATURE_HEADER};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast;
+
+/// Where the web server takes sync requests when `api.hooks` is on
+pub const PATH: &str = "/sync";
+
+/// Largest sync request the web server reads
+pub const MAX_BODY_BYTES: usize = 32 * 1024 * 1024;
+
+/// How far a request's or reply's send time may be from the local clock
+pub const MAX_CLOCK_SKEW: Duration = Duration::from_secs(5 * 60);
+
+/// Changes sent each way per request
+const BATCH: usize = 200;
+
+/// Requests per namespace per round, so a large backlog can't hold the loop
+const MAX_ROUNDS: usize = 50;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+#[derive(Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct SyncConfig {
+    /// Take part in sync (`JAMEY_SYNC`); the peer side only needs this,
+    /// `secret` and `namespaces`
+    pub enabled: bool,
+    /// Base URL of the other instance's web server (`JAMEY_SYNC_PEER`);
+    /// set on the side that starts each round
+    pub peer_url: Option<String>,
+    /// Shared with the peer (`JAMEY_SYNC_SECRET`); may not be set in the
+    /// config file
+    #[schemars(skip)]
+    pub secret: Option<SensitiveValue<String>>,
+    /// Namespaces kept in sync (`JAMEY_SYNC_NAMESPACES`); `""` is the
+    /// default namespace
+    pub namespaces: Vec<String>,
+    pub interval_secs: u64,
+    /// How long deletions are remembered. An instance that doesn't sync for
+    /// longer than this can bring deleted memories back.
+    pub tombstone_retention_days: u32,
+}
+
+impl fmt::Debug for SyncConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SyncConfig")
+            .field("enabled", &self.enabled)
+            .field("peer_url", &self.peer_url)
+            .field("secret", &Masked::opt(self.secret.as_ref().map(|s| s.0.as_str())))
+            .field("namespaces", &self.namespaces)
+            .field("interval_secs", &self.interval_secs)
+            .field("tombstone_retention_days", &self.tombstone_retention_days)
+            .finish()
+    }
+}
+
+impl Default for SyncConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            peer_url: None,
+            secret: None,
+            namespaces: Vec::new(),
+            interval_secs: 300,
+            tombstone_retention_days: 90,
+        }
+    }
+}
+
+impl SyncConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.enabled {
+            return Ok(());
+        }
+        if self.secret.as_ref().is_none_or(|secret| secret.0.len() < 16) {
+            return Err("sync needs JAMEY_SYNC_SECRET, at least 16 characters".to_string());
+        }
+        if self.namespaces.is_empty() {
+            return Err("sync.namespaces must name at least one namespace".to_string());
+        }
+        if self.interval_secs < 10 {
+            return Err("sync.interval_secs must be at least 10".to_string());
+        }
+        if self.tombstone_retention_days == 0 {
+            return Err("sync.tombstone_retention_days must be above 0".to_string());
+        }
+        if let Some(peer) = &self.peer_url {
+            let url = url::Url::parse(peer).map_err(|e| format!("sync.peer_url is not a URL: {}", e))?;
+            let loopback = match url.host() {
+                Some(url::Host::Domain(host)) => host == "localhost",
+                Some(url::Host::Ipv4(ip)) => ip.is_loopback(),
+                Some(url::Host::Ipv6(ip)) => ip.is_loopback(),
+                None => false,
+            };
+            if url.scheme() != "https" && !(url.scheme() == "http" && loopback) {
+                return Err("sync.peer_url must use https unless it is on this machine".to_string());
+            }
+        }
+        Ok(())
+    }
+
+    fn secret(&self) -> &str {
+        self.secret.as_ref().map(|secret| secret.0.as_str()).unwrap_or_default()
+    }
+}
+
+/// A round's request: the sender's changes, and where it wants the peer's
+/// to start
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncRequest {
+    pub namespace: String,
+    pub sent_at: DateTime<Utc>,
+    /// After the last of the peer's changes the sender has applied
+    pub since: Option<SyncCursor>,
+    pub changes: Vec<SyncChange>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncResponse {
+    pub sent_at: DateTime<Utc>,
+    /// What applying the request's changes did
+    pub applied: ApplyReport,
+    pub changes: Vec<SyncChange>,
+    /// Whether the peer has further changes after these
+    pub more: bool,
+}
+
+fn fresh(sent_at: DateTime<Utc>) -> bool {
+    Utc::now().signed_duration_since(sent_at).abs().to_std().unwrap_or(Duration::MAX) <= MAX_CLOCK_SKEW
+}
+
+/// Answer a sync request taken by the web server. `signature` is the
+/// request's [`SIGNATURE_HEADER`].
+pub(crate) async fn serve(
+    state: &RuntimeState,
+    method: &str,
+    signature: Option<&str>,
+    body: &[u8],
+) -> (u16, serde_json::Value) {
+    let config = &state.config.sync;
+    if !config.enabled {
+        return (404, serde_json::json!({ "error": "Not found" }));
+    }
+    if method != "POST" {
+        return (405, serde_json::json!({ "error": "Use POST" }));
+    }
+    if !signature.is_some_and(|signature| verify_signature(config.secret(), body, signature)) {
+        return (401, serde_json::json!({ "error": "Invalid signature" }));
+    }
+    let request: SyncRequest = match serde_json::from_slice(body) {
+        Ok(request) => request,
+        Err(e) => return (400, serde_json::json!({ "error": format!("Invalid sync request: {}", e) })),
+    };
+    if !fresh(request.sent_at) {
+        return (401, serde_json::json!({ "error": "Request is too old; check both clocks" }));
+    }
+    if !config.namespaces.contains(&request.namespace)
+        || request.changes.iter().any(|change| change.namespace() != request.namespace)
+    {
+        return (403, serde_json::json!({ "error": "Namespace is not synced here" }));
+    }
+
+    let store = &state.memory_store;
+    let applied = match store.apply_changes(&request.changes).await {
+        Ok(report) => report,
+        Err(e) => {
+            tracing::warn!("Applying synced changes to '{}' failed: {}", request.namespace, e);
+            return (500, serde_json::json!({ "error": "Could not apply changes" }));
+        }
+    };
+    match store.changes_since(&request.namespace, request.since, BATCH + 1).await {
+        Ok(mut changes) => {
+            let more = changes.len() > BATCH;
+            changes.truncate(BATCH);
+            let response = SyncResponse { sent_at: Utc::now(), applied, changes, more };
+            (200, serde_json::to_value(response).unwrap_or_default())
+        }
+        Err(e) => {
+            tracing::warn!("Listing changes to '{}' failed: {}", request.namespace, e);
+            (500, serde_json::json!({ "error": "Could not list changes" }))
+        }
+    }
+}
+
+/// Signature for a reply to a sync request, when sync is on
+pub(crate) fn sign_response(config: &RuntimeConfig, body: &str) -> Option<String> {
+    config.sync.enabled.then(|| sign_payload(config.sync.secret(), body.as_bytes()))
+}
+
+/// Totals for one namespace's round
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SyncRound {
+    pub sent: usize,
+    pub received: usize,
+    /// What applying the received changes did here
+    pub applied: ApplyReport,
+}
+
+/// The side of sync that starts rounds
+pub struct MemorySync {
+    store: Arc<PostgresMemoryStore>,
+    client: reqwest::Client,
+    config: SyncConfig,
+    endpoint: String,
+}
+
+impl MemorySync {
+    /// `None` unless sync is on with a peer to reach
+    pub fn new(store: Arc<PostgresMemoryStore>, config: &SyncConfig) -> Option<Self> {
+        let peer = config.peer_url.as_deref().filter(|_| config.enabled)?;
+        let client = reqwest::Client::builder().timeout(REQUEST_TIMEOUT).build().ok()?;
+        Some(Self {
+            store,
+            client,
+            config: config.clone(),
+            endpoint: format!("{}{}", peer.trim_end_matches('/'), PATH),
+        })
+    }
+
+    /// Exchange changes in `namespace` with the peer until neither side has
+    /// more, saving how far each direction got after every request
+    pub async fn sync_namespace(&self, namespace: &str) -> anyhow::Result<SyncRound> {
+        let (mut pushed, mut pulled) = self.store.sync_cursors(&self.endpoint, namespace).await?;
+        let mut round = SyncRound::default();
+        for _ in 0..MAX_ROUNDS {
+            let changes = self.store.changes_since(namespace, pushed, BATCH).await?;
+            let sending = changes.len();
+            let request = SyncRequest {
+                namespace: namespace.to_string(),
+                sent_at: Utc::now(),
+                since: pulled,
+                changes,
+            };
+            let response = self.exchange(&request).await?;
+
+            let applied = self.store.apply_changes(&response.changes).await?;
+            round.sent += sending;
+            round.received += response.changes.len();
+            round.applied.written += applied.written;
+            round.applied.deleted += applied.deleted;
+            round.applied.skipped += applied.skipped;
+            pushed = request.changes.last().map(SyncChange::cursor).or(pushed);
+            pulled = response.changes.last().map(SyncChange::cursor).or(pulled);
+            self.store.save_sync_cursors(&self.endpoint, namespace, pushed, pulled).await?;
+
+            if sending < BATCH && !response.more {
+                break;
+            }
+        }
+        Ok(round)
+    }
+
+    async fn exchange(&self, request: &SyncRequest) -> anyhow::Result<SyncResponse> {
+        let secret = self.config.secret();
+        let body = serde_json::to_vec(request)?;
+        let response = self
+            .client
+            .post(&self.endpoint)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header(SIGNATURE_HEADER, sign_payload(secret, &body))
+            .body(body)
+            .send()
+            .await?;
+        let status = response.status();
+        let signature = response
+            .headers()
+            .get(SIGNATURE_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+        let body = response.bytes().await?;
+        if !status.is_success() {
+            anyhow::bail!("Peer answered {}: {}", status, String::from_utf8_lossy(&body));
+        }
+        if !signature.is_some_and(|signature| verify_signature(secret, &body, &signature)) {
+            anyhow::bail!("Peer's reply is not signed with the sync secret");
+        }
+        let response: SyncResponse = serde_json::from_slice(&body)?;
+        if !fresh(response.sent_at) {
+            anyhow::bail!("Peer's reply is too old; check both clocks");
+        }
+        if response.changes.iter().any(|change| change.namespace() != request.namespace) {
+            anyhow::bail!("Peer sent changes outside '{}'", request.namespace);
+        }
+        Ok(response)
+    }
+}
+
+/// Sync every configured namespace with the peer each `interval_secs` and
+/// forget old tombstones daily, leader only in a cluster
+pub(crate) fn spawn_memory_sync(
+    supervisor: &Supervisor,
+    memory_store: Arc<PostgresMemoryStore>,
+    config: SyncConfig,
+    cluster: Option<Arc<crate::cluster::Cluster>>,
+    shutdown: broadcast::Receiver<()>,
+) {
+    const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+    if !config.enabled {
+        return;
+    }
+    let sync = MemorySync::new(Arc::clone(&memory_store), &config).map(Arc::new);
+    supervisor.spawn("memory_sync", move || {
+        let memory_store = Arc::clone(&memory_store);
+        let (sync, config, cluster) = (sync.clone(), config.clone(), cluster.clone());
+        let mut shutdown = shutdown.resubscribe();
+        async move {
+            let interval = Duration::from_secs(config.interval_secs);
+            let mut ticker = tokio::time::interval(Duration::from_secs(60).min(interval));
+            let mut last_sync: Option<std::time::Instant> = None;
+            let mut last_prune: Option<std::time::Instant> = None;
+            loop {
+                tokio::select! {
+                    _ = shutdown.recv() => break,
+                    _ = ticker.tick() => {}
+                }
+                if !cluster.as_ref().is_none_or(|cluster| cluster.is_leader()) {
+                    last_sync = None;
+                    last_prune = None;
+                    continue;
+                }
+                if last_prune.is_none_or(|at| at.elapsed() >= DAY) {
+                    last_prune = Some(std::time::Instant::now());
+                    if let Err(e) = memory_store.prune_tombstones(config.tombstone_retention_days).await {
+                        tracing::warn!("Pruning memory tombstones failed: {}", e);
+                    }
+                }
+                let Some(sync) = &sync else {
+                    continue;
+                };
+                if last_sync.is_some_and(|at| at.elapsed() < interval) {
+                    continue;
+                }
+                last_sync = Some(std::time::Instant::now());
+                for namespace in &config.namespaces {
+                    match sync.sync_namespace(namespace).await {
+                        Ok(round) => tracing::debug!(
+                            "Synced '{}': sent {}, received {} ({} written, {} deleted, {} older)",
+                            namespace,
+                            round.sent,
+                            round.received,
+                            round.applied.written,
+                            round.applied.deleted,
+                            round.applied.skipped
+                        ),
+                        Err(e) => tracing::warn!("Syncing memory namespace '{}' failed: {}", namespace, e),
+                    }
+                }
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_validation() {
+        let mut config = SyncConfig { enabled: true, ..Default::default() };
+        assert!(config.validate().is_err());
+        config.secret = Some(SensitiveValue("0123456789abcdef".to_string()));
+        assert!(config.validate().is_err());
+        config.namespaces = vec![String::new()];
+        assert!(config.validate().is_ok());
+
+        config.peer_url = Some("http://homelab.lan:8081".to_string());
+        assert!(config.validate().is_err());
+        config.peer_url = Some("http://127.0.0.1:8081".to_string());
+        assert!(config.validate().is_ok());
+        config.peer_url = Some("https://homelab.lan:8081".to_string());
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_freshness() {
+        assert!(fresh(Utc::now()));
+        assert!(fresh(Utc::now() + chrono::Duration::seconds(60)));
+        assert!(!fresh(Utc::now() - chrono::Duration::minutes(10)));
+    }
+}
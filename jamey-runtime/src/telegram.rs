@@ -0,0 +1,182 @@
+//! Telegram bot
+//!
+//! With `tools.telegram_bot_token` set, the runtime answers Telegram
+//! messages from the chats on `tools.telegram_allowed_chats`. Each chat is
+//! its own saved session, so `jamey sessions` shows Telegram conversations
+//! too. Photos and documents go through the attachment store. Updates
+//! arrive by long polling, or at `/telegram` on the web server when
+//! `tools.telegram_webhook_url` is set.
+
+use crate::channel;
+use crate::state::RuntimeState;
+use dashmap::DashMap;
+use jamey_protocol::Message;
+use jamey_tools::connectors::telegram::{TelegramMessage, Update};
+use jamey_tools::connectors::TelegramConnector;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{broadcast, Mutex};
+use uuid::Uuid;
+
+/// Seconds a `getUpdates` call waits for new messages
+const POLL_TIMEOUT_SECS: u64 = 50;
+
+/// Pause after a failed poll before trying again
+const POLL_RETRY_DELAY: Duration = Duration::from_secs(5);
+
+/// High half of every Telegram session ID ("jamey_tg"); the chat ID is the low half
+const SESSION_ID_PREFIX: u64 = 0x6a61_6d65_795f_7467;
+
+/// Path on the web server that receives webhook updates
+pub const WEBHOOK_PATH: &str = "/telegram";
+
+const HELP: &str = "Hi, I'm Jamey. Send me a message, photo or document and I'll reply. \
+Send /reset to start a new conversation.";
+
+/// The bot client plus one lock per chat, so a chat's messages are answered
+/// in order while different chats are answered concurrently
+pub struct TelegramBot {
+    pub connector: TelegramConnector,
+    chats: DashMap<i64, Arc<Mutex<()>>>,
+}
+
+impl TelegramBot {
+    pub fn new(connector: TelegramConnector) -> Self {
+        Self {
+            connector,
+            chats: DashMap::new(),
+        }
+    }
+}
+
+/// The session a chat's conversation is saved in
+pub fn chat_session_id(chat_id: i64) -> Uuid {
+    Uuid::from_u64_pair(SESSION_ID_PREFIX, chat_id as u64)
+}
+
+/// Register the webhook, or long poll until shutdown
+pub(crate) fn spawn_bot(state: Arc<RuntimeState>, bot: Arc<TelegramBot>, shutdown: broadcast::Receiver<()>) {
+    let supervisor = state.supervisor.clone();
+    supervisor.spawn("telegram", move || {
+        let state = Arc::clone(&state);
+        let bot = Arc::clone(&bot);
+        let mut shutdown = shutdown.resubscribe();
+        async move {
+            if let Some(base) = state.config.tools.telegram_webhook_url.clone() {
+                let url = format!("{}{}", base.trim_end_matches('/'), WEBHOOK_PATH);
+                match bot.connector.set_webhook(&url).await {
+                    Ok(()) => tracing::info!("Telegram updates will be posted to {}", url),
+                    Err(e) => tracing::error!("Failed to register Telegram webhook: {}", e),
+                }
+                return;
+            }
+
+            // getUpdates is refused while a webhook is registered
+            if let Err(e) = bot.connector.delete_webhook().await {
+                tracing::warn!("Could not clear Telegram webhook: {}", e);
+            }
+            tracing::info!("Polling Telegram for updates");
+            let mut offset = 0;
+            loop {
+                let updates = tokio::select! {
+                    _ = shutdown.recv() => break,
+                    updates = bot.connector.get_updates(offset, POLL_TIMEOUT_SECS) => updates,
+                };
+                match updates {
+                    Ok(updates) => {
+                        for update in updates {
+                            offset = offset.max(update.update_id + 1);
+                            tokio::spawn(handle_update(Arc::clone(&state), Arc::clone(&bot), update));
+                        }
+                    }
+                    Err(e) => {
+                        tracing::warn!("Telegram poll failed: {}", e);
+                        tokio::time::sleep(POLL_RETRY_DELAY).await;
+                    }
+                }
+            }
+            tracing::debug!("Telegram polling stopped");
+        }
+    });
+}
+
+/// Answer one update; failures are reported to the chat where possible
+pub(crate) async fn handle_update(state: Arc<RuntimeState>, bot: Arc<TelegramBot>, update: Update) {
+    let Some(message) = update.message else {
+        return;
+    };
+    let chat_id = message.chat.id;
+    if !bot.connector.is_allowed(chat_id) {
+        tracing::info!("Ignoring Telegram chat {} (not on the allowlist)", chat_id);
+        let _ = bot
+            .connector
+            .send_message(chat_id, &format!("This chat ({}) is not allowed to talk to this bot.", chat_id))
+            .await;
+        return;
+    }
+
+    let lock = Arc::clone(bot.chats.entry(chat_id).or_default().value());
+    let _turn = lock.lock().await;
+    let reply = match respond(&state, &bot.connector, &message).await {
+        Ok(Some(reply)) => reply,
+        Ok(None) => return,
+        Err(e) => format!("Sorry, something went wrong: {}", e),
+    };
+    if let Err(e) = bot.connector.send_message(chat_id, &reply).await {
+        tracing::warn!("Failed to reply to Telegram chat {}: {}", chat_id, e);
+    }
+}
+
+/// The reply to `message`, or `None` when there is nothing to answer
+async fn respond(
+    state: &RuntimeState,
+    bot: &TelegramConnector,
+    message: &TelegramMessage,
+) -> anyhow::Result<Option<String>> {
+    let chat_id = message.chat.id;
+    let session_id = chat_session_id(chat_id);
+    match message.body().trim() {
+        "/start" | "/help" => return Ok(Some(HELP.to_string())),
+        "/reset" => {
+            channel::reset(state, session_id).await?;
+            return Ok(Some("Started a new conversation.".to_string()));
+        }
+        _ => {}
+    }
+
+    let mut attachments = Vec::new();
+    for (file_id, name) in message.files() {
+        let bytes = bot.download_file(&file_id).await?;
+        match state.attachment_store.upload_bytes(&name, bytes).await {
+            Ok(attachment) => attachments.push(attachment),
+            Err(e) => return Ok(Some(format!("I can't read {}: {}", name, e))),
+        }
+    }
+    if message.body().trim().is_empty() && attachments.is_empty() {
+        return Ok(None);
+    }
+
+    let title = match message.from.as_ref().and_then(|u| u.username.as_ref()) {
+        Some(username) => format!("Telegram @{}", username),
+        None => format!("Telegram chat {}", chat_id),
+    };
+    let question = Message::user(message.body().trim()).with_attachments(&attachments);
+    let _ = bot.send_typing(chat_id).await;
+    let reply = channel::run_turn(state, session_id, title, question, |notice| async move {
+        let _ = bot.send_message(chat_id, &notice).await;
+    })
+    .await?;
+    Ok(Some(reply))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chat_session_ids_are_stable() {
+        assert_eq!(chat_session_id(42), chat_session_id(42));
+        assert_ne!(chat_session_id(42), chat_session_id(-42));
+        assert_eq!(chat_session_id(-1001).as_u64_pair(), (SESSION_ID_PREFIX, -1001i64 as u64));
+    }
+}
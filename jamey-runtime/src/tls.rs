@@ -1,10 +1,13 @@
 //! TLS/HTTPS configuration and certificate management
 //!
 //! This module provides secure TLS configuration for production deployments,
-//! including certificate loading, validation, and security headers.
+//! including certificate loading, validation, and security headers. Issuing
+//! and renewing the certificate itself is handled by
+//! [`certificates`](crate::certificates).
 
 use anyhow::{Context, Result};
-use rustls::{Certificate, PrivateKey, ServerConfig};
+use rustls::server::ResolvesServerCert;
+use rustls::{Certificate, PrivateKey, ServerConfig, SupportedProtocolVersion};
 use rustls_pemfile::{certs, pkcs8_private_keys};
 use std::fs::File;
 use std::io::BufReader;
@@ -13,6 +16,10 @@ use std::sync::Arc;
 use thiserror::Error;
 use tracing::{debug, info, warn};
 
+/// Protocol versions offered for each minimum version
+static TLS12_UP: &[&SupportedProtocolVersion] = &[&rustls::version::TLS12, &rustls::version::TLS13];
+static TLS13_ONLY: &[&SupportedProtocolVersion] = &[&rustls::version::TLS13];
+
 #[derive(Debug, Error)]
 pub enum TlsError {
     #[error("Certificate file not found: {0}")]
@@ -29,6 +36,10 @@ pub enum TlsError {
     InvalidPrivateKey(String),
     #[error("TLS configuration error: {0}")]
     ConfigError(String),
+    #[error("Failed to generate certificate: {0}")]
+    GenerationError(String),
+    #[error("ACME error: {0}")]
+    AcmeError(String),
 }
 
 /// TLS configuration for HTTPS server
@@ -153,15 +164,7 @@ impl TlsConfig {
             .context("Failed to load private key")?;
 
         // Build rustls config
-        let mut config = ServerConfig::builder()
-            .with_safe_default_cipher_suites()
-            .with_safe_default_kx_groups()
-            .with_protocol_versions(match self.min_tls_version {
-                TlsVersion::Tls12 => &[&rustls::version::TLS12, &rustls::version::TLS13],
-                TlsVersion::Tls13 => &[&rustls::version::TLS13],
-            })
-            .map_err(|e| TlsError::ConfigError(e.to_string()))?
-            .with_no_client_auth()
+        let mut config = self.config_builder()?
             .with_single_cert(certs, key)
             .map_err(|e| TlsError::ConfigError(e.to_string()))?;
 
@@ -175,6 +178,30 @@ impl TlsConfig {
         Ok(Arc::new(config))
     }
 
+    /// Build a rustls ServerConfig that asks `resolver` for the certificate
+    /// on every handshake, so a renewed certificate is served without
+    /// rebuilding the config or restarting the listener
+    pub fn build_server_config_with_resolver(
+        &self,
+        resolver: Arc<dyn ResolvesServerCert>,
+    ) -> Result<Arc<ServerConfig>> {
+        let mut config = self.config_builder()?.with_cert_resolver(resolver);
+        config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+        Ok(Arc::new(config))
+    }
+
+    fn config_builder(&self) -> Result<rustls::ConfigBuilder<ServerConfig, rustls::server::WantsServerCert>, TlsError> {
+        Ok(ServerConfig::builder()
+            .with_safe_default_cipher_suites()
+            .with_safe_default_kx_groups()
+            .with_protocol_versions(match self.min_tls_version {
+                TlsVersion::Tls12 => TLS12_UP,
+                TlsVersion::Tls13 => TLS13_ONLY,
+            })
+            .map_err(|e| TlsError::ConfigError(e.to_string()))?
+            .with_no_client_auth())
+    }
+
     /// Get the HSTS header value
     pub fn hsts_header(&self) -> Option<String> {
         if !self.enable_hsts {
@@ -196,7 +223,7 @@ impl TlsConfig {
 }
 
 /// Load certificates from a PEM file
-fn load_certificates(path: &Path) -> Result<Vec<Certificate>, TlsError> {
+pub(crate) fn load_certificates(path: &Path) -> Result<Vec<Certificate>, TlsError> {
     let file = File::open(path)
         .map_err(|e| TlsError::CertificateLoadError(e.to_string()))?;
     let mut reader = BufReader::new(file);
@@ -211,7 +238,7 @@ fn load_certificates(path: &Path) -> Result<Vec<Certificate>, TlsError> {
 }
 
 /// Load a private key from a PEM file
-fn load_private_key(path: &Path) -> Result<PrivateKey, TlsError> {
+pub(crate) fn load_private_key(path: &Path) -> Result<PrivateKey, TlsError> {
     let file = File::open(path)
         .map_err(|e| TlsError::PrivateKeyLoadError(e.to_string()))?;
     let mut reader = BufReader::new(file);
@@ -294,7 +321,7 @@ impl SecurityHeaders {
             FrameOptions::SameOrigin => {
                 headers.push(("X-Frame-Options", "SAMEORIGIN".to_string()));
             }
-            FrameOptions::AllowFrom(_) => {
+            FrameOptions::AllowFrom() => {
                 // Note: ALLOW-FROM is deprecated, use CSP frame-ancestors instead
                 warn!("X-Frame-Options ALLOW-FROM is deprecated");
             }
@@ -0,0 +1,524 @@
+//! Token and cost tracking
+//!
+//! Every model call appends one JSON line to `<usage_dir>/usage-YYYY-MM-DD.jsonl`
+//! (UTC), and every connector run one to `tools-YYYY-MM-DD.jsonl` beside it.
+//! Daily files keep range queries cheap and make old usage easy to archive;
+//! `jamey usage` reads them directly, so no running runtime is needed.
+//! When the runtime runs, model calls are also written to the database's
+//! `usage_log` table (see [`jamey_core::usage`]) for billing reconciliation.
+
+use chrono::{DateTime, NaiveDate, Utc};
+use jamey_core::usage::{PostgresUsageStore, UsageEntry, UsageStoreError};
+use jamey_protocol::TokenUsage;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use thiserror::Error;
+use tokio::io::AsyncWriteExt;
+use uuid::Uuid;
+
+const FILE_PREFIX: &str = "usage-";
+const TOOL_FILE_PREFIX: &str = "tools-";
+const FILE_SUFFIX: &str = ".jsonl";
+
+#[derive(Debug, Error)]
+pub enum UsageError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Serialization error: {0}")]
+    Serialization(#[from] serde_json::Error),
+    #[error("Usage ledger error: {0}")]
+    Ledger(#[from] UsageStoreError),
+}
+
+/// One model call
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UsageRecord {
+    pub timestamp: DateTime<Utc>,
+    pub session_id: Option<Uuid>,
+    pub model: String,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub total_tokens: u64,
+    /// Charged cost, when the provider reports one
+    pub cost_usd: Option<f64>,
+    /// From sending the request to the usage report at the end of the
+    /// response; missing from records written before it was tracked
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latency_ms: Option<u64>,
+}
+
+impl UsageRecord {
+    pub fn new(model: &str, session_id: Option<Uuid>, usage: &TokenUsage, cost_usd: Option<f64>) -> Self {
+        Self {
+            timestamp: Utc::now(),
+            session_id,
+            model: model.to_string(),
+            prompt_tokens: usage.prompt_tokens.into(),
+            completion_tokens: usage.completion_tokens.into(),
+            total_tokens: usage.total_tokens.into(),
+            cost_usd,
+            latency_ms: None,
+        }
+    }
+
+    pub fn with_latency(mut self, latency: Duration) -> Self {
+        self.latency_ms = Some(latency.as_millis() as u64);
+        self
+    }
+
+    /// The same call as a `usage_log` row
+    pub fn ledger_entry(&self) -> UsageEntry {
+        UsageEntry {
+            recorded_at: self.timestamp,
+            session_id: self.session_id,
+            model: self.model.clone(),
+            prompt_tokens: self.prompt_tokens as i64,
+            completion_tokens: self.completion_tokens as i64,
+            total_tokens: self.total_tokens as i64,
+            cost_usd: self.cost_usd,
+            latency_ms: self.latency_ms.map(|ms| ms as i64),
+        }
+    }
+}
+
+/// One connector run made on the model's behalf
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolRecord {
+    pub timestamp: DateTime<Utc>,
+    pub session_id: Option<Uuid>,
+    pub connector: String,
+    pub action: Option<String>,
+    pub success: bool,
+    pub duration_ms: u64,
+}
+
+impl ToolRecord {
+    pub fn new(connector: &str, action: Option<&str>, session_id: Option<Uuid>, success: bool, duration: Duration) -> Self {
+        Self {
+            timestamp: Utc::now(),
+            session_id,
+            connector: connector.to_string(),
+            action: action.map(str::to_string),
+            success,
+            duration_ms: duration.as_millis() as u64,
+        }
+    }
+}
+
+/// Records kept in daily files
+trait Timestamped {
+    fn timestamp(&self) -> DateTime<Utc>;
+}
+
+impl Timestamped for UsageRecord {
+    fn timestamp(&self) -> DateTime<Utc> {
+        self.timestamp
+    }
+}
+
+impl Timestamped for ToolRecord {
+    fn timestamp(&self) -> DateTime<Utc> {
+        self.timestamp
+    }
+}
+
+/// How `summarize` buckets records
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GroupBy {
+    Model,
+    Session,
+    Day,
+}
+
+impl std::str::FromStr for GroupBy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "model" => Ok(GroupBy::Model),
+            "session" => Ok(GroupBy::Session),
+            "day" => Ok(GroupBy::Day),
+            other => Err(format!("Invalid group: {}. Must be 'model', 'session' or 'day'", other)),
+        }
+    }
+}
+
+/// Totals for one group
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct UsageSummary {
+    pub key: String,
+    pub requests: u64,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub total_tokens: u64,
+    pub cost_usd: f64,
+    /// Calls the provider reported no cost for, so `cost_usd` undercounts
+    pub unpriced_requests: u64,
+}
+
+impl UsageSummary {
+    fn add(&mut self, record: &UsageRecord) {
+        self.requests += 1;
+        self.prompt_tokens += record.prompt_tokens;
+        self.completion_tokens += record.completion_tokens;
+        self.total_tokens += record.total_tokens;
+        match record.cost_usd {
+            Some(cost) => self.cost_usd += cost,
+            None => self.unpriced_requests += 1,
+        }
+    }
+}
+
+/// Records [`UsageLog::forget_sessions`] removed, or would remove
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ForgottenUsage {
+    pub model_calls: usize,
+    pub tool_runs: usize,
+    /// `usage_log` rows; always 0 without a ledger
+    pub ledger_rows: u64,
+}
+
+/// Append-only usage log shared between the runtime and the CLI
+#[derive(Debug, Clone)]
+pub struct UsageLog {
+    dir: PathBuf,
+    ledger: Option<Arc<PostgresUsageStore>>,
+}
+
+impl UsageLog {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into(), ledger: None }
+    }
+
+    /// Also write model calls to the database ledger
+    pub fn with_ledger(mut self, ledger: Arc<PostgresUsageStore>) -> Self {
+        self.ledger = Some(ledger);
+        self
+    }
+
+    pub fn ledger(&self) -> Option<&PostgresUsageStore> {
+        self.ledger.as_deref()
+    }
+
+    /// Log at `JAMEY_USAGE_DIR`, or `./usage` when unset
+    pub fn from_env() -> Self {
+        Self::new(default_usage_dir())
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn path(&self, prefix: &str, day: NaiveDate) -> PathBuf {
+        self.dir.join(format!("{}{}{}", prefix, day.format("%Y-%m-%d"), FILE_SUFFIX))
+    }
+
+    pub async fn record(&self, record: &UsageRecord) -> Result<(), UsageError> {
+        self.append(FILE_PREFIX, record).await?;
+        if let Some(ledger) = &self.ledger {
+            ledger.record(&record.ledger_entry()).await?;
+        }
+        Ok(())
+    }
+
+    pub async fn record_tool(&self, record: &ToolRecord) -> Result<(), UsageError> {
+        self.append(TOOL_FILE_PREFIX, record).await
+    }
+
+    /// Records at or after `since` (everything when `None`), oldest first
+    pub async fn query(&self, since: Option<DateTime<Utc>>) -> Result<Vec<UsageRecord>, UsageError> {
+        self.read(FILE_PREFIX, since).await
+    }
+
+    /// Connector runs at or after `since`, oldest first
+    pub async fn query_tools(&self, since: Option<DateTime<Utc>>) -> Result<Vec<ToolRecord>, UsageError> {
+        self.read(TOOL_FILE_PREFIX, since).await
+    }
+
+    async fn append<T: Serialize + Timestamped>(&self, prefix: &str, record: &T) -> Result<(), UsageError> {
+        tokio::fs::create_dir_all(&self.dir).await?;
+        let mut line = serde_json::to_vec(record)?;
+        line.push(b'\n');
+
+        // One write per line keeps concurrent appends from interleaving
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.path(prefix, record.timestamp().date_naive()))
+            .await?;
+        file.write_all(&line).await?;
+        Ok(())
+    }
+
+    async fn read<T: DeserializeOwned + Timestamped>(
+        &self,
+        prefix: &str,
+        since: Option<DateTime<Utc>>,
+    ) -> Result<Vec<T>, UsageError> {
+        let mut days = Vec::new();
+        let mut entries = match tokio::fs::read_dir(&self.dir).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        while let Some(entry) = entries.next_entry().await? {
+            let name = entry.file_name();
+            let Some(day) = name
+                .to_str()
+                .and_then(|n| n.strip_prefix(prefix)?.strip_suffix(FILE_SUFFIX))
+                .and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok())
+            else {
+                continue;
+            };
+            if since.is_none_or(|since| day >= since.date_naive()) {
+                days.push(day);
+            }
+        }
+        days.sort();
+
+        let mut records = Vec::new();
+        for day in days {
+            let path = self.path(prefix, day);
+            let contents = tokio::fs::read_to_string(&path).await?;
+            for (number, line) in contents.lines().enumerate() {
+                if line.trim().is_empty() {
+                    continue;
+                }
+                match serde_json::from_str::<T>(line) {
+                    Ok(record) if since.is_none_or(|since| record.timestamp() >= since) => {
+                        records.push(record)
+                    }
+                    Ok(_) => {}
+                    // A crash mid-append leaves a partial last line
+                    Err(e) => tracing::warn!("Skipping {}:{}: {}", path.display(), number + 1, e),
+                }
+            }
+        }
+        records.sort_by_key(|r| r.timestamp());
+        Ok(records)
+    }
+
+    /// Remove every record made in one of `sessions`, from the daily files
+    /// and the ledger. Files are rewritten through a temporary copy, so a
+    /// record appended to the same file meanwhile may be lost.
+    pub async fn forget_sessions(&self, sessions: &HashSet<Uuid>, dry_run: bool) -> Result<ForgottenUsage, UsageError> {
+        let mut forgotten = ForgottenUsage::default();
+        if sessions.is_empty() {
+            return Ok(forgotten);
+        }
+
+        self.forget_in_files(sessions, dry_run, &mut forgotten).await?;
+        if let Some(ledger) = &self.ledger {
+            let ids: Vec<Uuid> = sessions.iter().copied().collect();
+            forgotten.ledger_rows = if dry_run {
+                ledger.count_sessions(&ids).await?
+            } else {
+                ledger.purge_sessions(&ids).await?
+            };
+        }
+        Ok(forgotten)
+    }
+
+    async fn forget_in_files(
+        &self,
+        sessions: &HashSet<Uuid>,
+        dry_run: bool,
+        forgotten: &mut ForgottenUsage,
+    ) -> Result<(), UsageError> {
+        let mut entries = match tokio::fs::read_dir(&self.dir).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(e.into()),
+        };
+        while let Some(entry) = entries.next_entry().await? {
+            let name = entry.file_name();
+            let Some(name) = name.to_str().filter(|n| n.ends_with(FILE_SUFFIX)) else {
+                continue;
+            };
+            let count = if name.starts_with(FILE_PREFIX) {
+                &mut forgotten.model_calls
+            } else if name.starts_with(TOOL_FILE_PREFIX) {
+                &mut forgotten.tool_runs
+            } else {
+                continue;
+            };
+            *count += strip_sessions(&entry.path(), sessions, dry_run).await?;
+        }
+        Ok(())
+    }
+
+    /// Cost recorded so far today (UTC)
+    pub async fn spent_today(&self) -> Result<f64, UsageError> {
+        let midnight = Utc::now().date_naive().and_hms_opt(0, 0, 0).map(|t| t.and_utc());
+        Ok(self
+            .query(midnight)
+            .await?
+            .iter()
+            .filter_map(|r| r.cost_usd)
+            .sum())
+    }
+}
+
+/// Drop the lines of `path` recorded in one of `sessions`; returns how many
+/// there were. Lines that don't parse are kept.
+pub(crate) async fn strip_sessions(path: &Path, sessions: &HashSet<Uuid>, dry_run: bool) -> std::io::Result<usize> {
+    #[derive(Deserialize)]
+    struct SessionOnly {
+        session_id: Option<Uuid>,
+    }
+
+    let contents = tokio::fs::read_to_string(path).await?;
+    let mut kept = String::with_capacity(contents.len());
+    let mut removed = 0;
+    for line in contents.lines() {
+        let forgotten = serde_json::from_str::<SessionOnly>(line)
+            .ok()
+            .and_then(|r| r.session_id)
+            .is_some_and(|id| sessions.contains(&id));
+        if forgotten {
+            removed += 1;
+        } else {
+            kept.push_str(line);
+            kept.push('\n');
+        }
+    }
+    if removed > 0 && !dry_run {
+        let tmp = path.with_extension("jsonl.tmp");
+        tokio::fs::write(&tmp, kept).await?;
+        tokio::fs::rename(&tmp, path).await?;
+    }
+    Ok(removed)
+}
+
+/// Group records, most expensive first (chronological for `GroupBy::Day`)
+pub fn summarize(records: &[UsageRecord], group_by: GroupBy) -> Vec<UsageSummary> {
+    let mut groups: HashMap<String, UsageSummary> = HashMap::new();
+    for record in records {
+        let key = match group_by {
+            GroupBy::Model => record.model.clone(),
+            GroupBy::Session => record
+                .session_id
+                .map(|id| id.to_string())
+                .unwrap_or_else(|| "(none)".to_string()),
+            GroupBy::Day => record.timestamp.format("%Y-%m-%d").to_string(),
+        };
+        groups
+            .entry(key.clone())
+            .or_insert_with(|| UsageSummary { key, ..Default::default() })
+            .add(record);
+    }
+
+    let mut summaries: Vec<UsageSummary> = groups.into_values().collect();
+    match group_by {
+        GroupBy::Day => summaries.sort_by(|a, b| a.key.cmp(&b.key)),
+        _ => summaries.sort_by(|a, b| {
+            b.cost_usd
+                .total_cmp(&a.cost_usd)
+                .then(b.total_tokens.cmp(&a.total_tokens))
+                .then(a.key.cmp(&b.key))
+        }),
+    }
+    summaries
+}
+
+/// Sum of every record, keyed `total`
+pub fn total(records: &[UsageRecord]) -> UsageSummary {
+    let mut total = UsageSummary {
+        key: "total".to_string(),
+        ..Default::default()
+    };
+    records.iter().for_each(|r| total.add(r));
+    total
+}
+
+pub(crate) fn default_usage_dir() -> PathBuf {
+    std::env::var("JAMEY_USAGE_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from("./usage"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn record(model: &str, tokens: u32, cost: Option<f64>) -> UsageRecord {
+        let usage = TokenUsage {
+            prompt_tokens: tokens,
+            completion_tokens: tokens,
+            total_tokens: tokens * 2,
+        };
+        UsageRecord::new(model, None, &usage, cost)
+    }
+
+    #[tokio::test]
+    async fn test_record_and_query() {
+        let dir = TempDir::new().unwrap();
+        let log = UsageLog::new(dir.path());
+
+        let mut old = record("gpt-4", 10, Some(0.5));
+        old.timestamp = Utc::now() - chrono::Duration::days(10);
+        log.record(&old).await.unwrap();
+        log.record(&record("gpt-4", 20, Some(0.25))).await.unwrap();
+        log.record(&record("claude-3-sonnet", 5, None)).await.unwrap();
+
+        assert_eq!(log.query(None).await.unwrap().len(), 3);
+        let recent = log.query(Some(Utc::now() - chrono::Duration::days(7))).await.unwrap();
+        assert_eq!(recent.len(), 2);
+        assert_eq!(log.spent_today().await.unwrap(), 0.25);
+
+        // Tool runs live in their own files and don't show up as model calls
+        let run = ToolRecord::new("calculator", Some("evaluate"), None, true, Duration::from_millis(12));
+        log.record_tool(&run).await.unwrap();
+        assert_eq!(log.query_tools(None).await.unwrap(), vec![run]);
+        assert_eq!(log.query(None).await.unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_forget_sessions() {
+        let dir = TempDir::new().unwrap();
+        let log = UsageLog::new(dir.path());
+        let (forgotten, kept) = (Uuid::new_v4(), Uuid::new_v4());
+        let usage = TokenUsage { prompt_tokens: 1, completion_tokens: 1, total_tokens: 2 };
+        for session in [forgotten, kept, forgotten] {
+            log.record(&UsageRecord::new("gpt-4", Some(session), &usage, None)).await.unwrap();
+        }
+        let run = ToolRecord::new("web", None, Some(forgotten), true, Duration::from_millis(5));
+        log.record_tool(&run).await.unwrap();
+
+        let sessions = HashSet::from([forgotten]);
+        let report = log.forget_sessions(&sessions, true).await.unwrap();
+        assert_eq!((report.model_calls, report.tool_runs), (2, 1));
+        assert_eq!(log.query(None).await.unwrap().len(), 3);
+
+        log.forget_sessions(&sessions, false).await.unwrap();
+        let left = log.query(None).await.unwrap();
+        assert_eq!(left.len(), 1);
+        assert_eq!(left[0].session_id, Some(kept));
+        assert!(log.query_tools(None).await.unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_summarize_by_model() {
+        let records = vec![
+            record("gpt-4", 10, Some(0.5)),
+            record("claude-3-sonnet", 100, None),
+            record("gpt-4", 20, Some(0.25)),
+        ];
+        let summaries = summarize(&records, GroupBy::Model);
+        assert_eq!(summaries[0].key, "gpt-4");
+        assert_eq!(summaries[0].requests, 2);
+        assert_eq!(summaries[0].total_tokens, 60);
+        assert_eq!(summaries[0].cost_usd, 0.75);
+        assert_eq!(summaries[1].unpriced_requests, 1);
+
+        let total = total(&records);
+        assert_eq!(total.requests, 3);
+        assert_eq!(total.total_tokens, 260);
+    }
+}
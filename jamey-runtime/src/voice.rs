@@ -0,0 +1,489 @@
+//! Spoken replies
+//!
+//! [`VoiceOutput`] reads replies aloud. Markdown is flattened to plain
+//! sentences with code blocks left out, long replies are synthesized in
+//! pieces, and the audio is saved under `voice.output_dir` before being
+//! handed to a local player. Without a player the files are still saved.
+//!
+//! [`VoiceInput`] goes the other way, turning a microphone recording into
+//! the text of the next message.
+
+use jamey_core::secure_logging::Masked;
+use jamey_providers::audio::{
+    AudioConfig, AudioError, AudioFormat, AudioProvider, OpenAiAudioProvider, SpeechRequest, TranscriptionRequest,
+    MAX_SPEECH_CHARS, SPEED_RANGE,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::Arc;
+use thiserror::Error;
+
+/// Players tried in order when `voice.player` is unset, with the arguments
+/// that make them play one file without a window and exit
+const PLAYERS: &[&[&str]] = &[
+    &["afplay"],
+    &["mpv", "--no-video", "--really-quiet"],
+    &["ffplay", "-nodisp", "-autoexit", "-loglevel", "quiet"],
+    &["cvlc", "--play-and-exit", "--quiet"],
+];
+
+#[derive(Debug, Error)]
+pub enum VoiceError {
+    #[error("Invalid voice rate \"{0}\": use x-slow, slow, medium, fast, x-fast, a percentage or a number")]
+    InvalidRate(String),
+    #[error("Invalid speech URL: {0}")]
+    InvalidUrl(String),
+    #[error(transparent)]
+    Audio(#[from] AudioError),
+    #[error("Failed to save audio: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Audio player failed: {0}")]
+    Player(String),
+}
+
+#[derive(Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct VoiceConfig {
+    /// Key for the speech API (`JAMEY_TTS_API_KEY`, else `OPENAI_API_KEY`)
+    pub api_key: Option<String>,
+    /// OpenAI-compatible API root (`JAMEY_TTS_URL`)
+    pub api_base_url: String,
+    /// Speech model (`JAMEY_TTS_MODEL`)
+    pub model: String,
+    /// Voice name understood by the model (`JAMEY_VOICE`)
+    pub voice: String,
+    /// Speaking rate as in SSML `<prosody rate>`: `x-slow` to `x-fast`, a
+    /// percentage such as `120%`, or a multiplier such as `1.2` (`JAMEY_VOICE_RATE`)
+    pub rate: String,
+    /// `JAMEY_VOICE_FORMAT`
+    pub format: AudioFormat,
+    /// Where spoken replies are saved (`JAMEY_VOICE_DIR`)
+    pub output_dir: PathBuf,
+    /// Command that plays a file given as its last argument (`JAMEY_AUDIO_PLAYER`);
+    /// the first installed of afplay, mpv, ffplay and cvlc when unset
+    pub player: Option<String>,
+    /// Speech-to-text model for `jamey chat --voice` (`JAMEY_STT_MODEL`)
+    pub transcription_model: String,
+    /// Language spoken to Jamey as an ISO-639-1 code (`JAMEY_VOICE_LANGUAGE`);
+    /// detected from the recording when unset
+    pub language: Option<String>,
+}
+
+impl fmt::Debug for VoiceConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("VoiceConfig")
+            .field("api_key", &Masked::opt(self.api_key.as_deref()))
+            .field("api_base_url", &self.api_base_url)
+            .field("model", &self.model)
+            .field("voice", &self.voice)
+            .field("rate", &self.rate)
+            .field("format", &self.format)
+            .field("output_dir", &self.output_dir)
+            .field("player", &self.player)
+            .field("transcription_model", &self.transcription_model)
+            .field("language", &self.language)
+            .finish()
+    }
+}
+
+impl Default for VoiceConfig {
+    fn default() -> Self {
+        Self {
+            api_key: None,
+            api_base_url: "https://api.openai.com/v1".to_string(),
+            model: "tts-1".to_string(),
+            voice: "alloy".to_string(),
+            rate: "medium".to_string(),
+            format: AudioFormat::default(),
+            output_dir: PathBuf::from("./voice"),
+            player: None,
+            transcription_model: "whisper-1".to_string(),
+            language: None,
+        }
+    }
+}
+
+/// Speed multiplier for an SSML-style rate
+pub fn parse_rate(rate: &str) -> Result<f32, VoiceError> {
+    let rate = rate.trim().to_lowercase();
+    let speed = match rate.as_str() {
+        "x-slow" => 0.5,
+        "slow" => 0.75,
+        "medium" | "default" | "" => 1.0,
+        "fast" => 1.25,
+        "x-fast" => 1.75,
+        other => match other.strip_suffix('%') {
+            Some(percent) => percent.trim().parse::<f32>().map(|p| p / 100.0),
+            None => other.parse::<f32>(),
+        }
+        .map_err(|_| VoiceError::InvalidRate(rate.clone()))?,
+    };
+    if !SPEED_RANGE.contains(&speed) {
+        return Err(VoiceError::InvalidRate(rate));
+    }
+    Ok(speed)
+}
+
+/// Audio saved for one reply, and whether a player read it out
+#[derive(Debug, Clone)]
+pub struct Spoken {
+    pub files: Vec<PathBuf>,
+    pub played: bool,
+}
+
+pub struct VoiceOutput {
+    provider: Arc<dyn AudioProvider>,
+    config: VoiceConfig,
+    speed: f32,
+}
+
+/// The configured OpenAI-compatible speech API
+fn provider(config: &VoiceConfig) -> Result<Arc<dyn AudioProvider>, VoiceError> {
+    let api_base_url = url::Url::parse(&config.api_base_url).map_err(|e| VoiceError::InvalidUrl(e.to_string()))?;
+    let provider = OpenAiAudioProvider::new(AudioConfig {
+        api_key: config.api_key.clone(),
+        api_base_url,
+        speech_model: config.model.clone(),
+        transcription_model: config.transcription_model.clone(),
+        ..Default::default()
+    })?;
+    Ok(Arc::new(provider))
+}
+
+impl VoiceOutput {
+    pub fn new(config: &VoiceConfig) -> Result<Self, VoiceError> {
+        Self::with_provider(provider(config)?, config)
+    }
+
+    pub fn with_provider(provider: Arc<dyn AudioProvider>, config: &VoiceConfig) -> Result<Self, VoiceError> {
+        Ok(Self {
+            provider,
+            speed: parse_rate(&config.rate)?,
+            config: config.clone(),
+        })
+    }
+
+    /// Synthesize `markdown` and save it; the files are in speaking order
+    pub async fn synthesize(&self, markdown: &str) -> Result<Vec<PathBuf>, VoiceError> {
+        let text = speakable_text(markdown);
+        if text.is_empty() {
+            return Ok(Vec::new());
+        }
+        let format = self.config.format;
+        let mut clips = Vec::new();
+        for chunk in split_for_speech(&text, MAX_SPEECH_CHARS) {
+            let request = SpeechRequest {
+                text: chunk,
+                voice: self.config.voice.clone(),
+                speed: self.speed,
+                format,
+            };
+            clips.push(self.provider.synthesize(&request).await?.audio);
+        }
+        if format.concatenates() {
+            clips = vec![clips.concat()];
+        }
+
+        tokio::fs::create_dir_all(&self.config.output_dir).await?;
+        let stem = format!("reply-{}", chrono::Utc::now().format("%Y%m%d-%H%M%S%.3f"));
+        let mut files = Vec::new();
+        for (i, clip) in clips.iter().enumerate() {
+            let name = match clips.len() {
+                1 => format!("{}.{}", stem, format),
+                _ => format!("{}-{}.{}", stem, i + 1, format),
+            };
+            let path = self.config.output_dir.join(name);
+            tokio::fs::write(&path, clip).await?;
+            files.push(path);
+        }
+        Ok(files)
+    }
+
+    /// Synthesize `markdown`, save it and play it through the local player
+    pub async fn speak(&self, markdown: &str) -> Result<Spoken, VoiceError> {
+        let files = self.synthesize(markdown).await?;
+        let played = self.play(&files).await?;
+        Ok(Spoken { files, played })
+    }
+
+    /// Play saved replies one after another; false when no player is
+    /// installed. Dropping the future stops playback.
+    pub async fn play(&self, files: &[PathBuf]) -> Result<bool, VoiceError> {
+        let Some(player) = self.player() else {
+            return Ok(false);
+        };
+        for file in files {
+            play(&player, file).await?;
+        }
+        Ok(true)
+    }
+
+    fn player(&self) -> Option<Vec<String>> {
+        if let Some(command) = &self.config.player {
+            let words: Vec<String> = command.split_whitespace().map(String::from).collect();
+            return (!words.is_empty()).then_some(words);
+        }
+        PLAYERS
+            .iter()
+            .find(|command| on_path(command[0]))
+            .map(|command| command.iter().map(|w| w.to_string()).collect())
+    }
+}
+
+pub struct VoiceInput {
+    provider: Arc<dyn AudioProvider>,
+    language: Option<String>,
+}
+
+impl VoiceInput {
+    pub fn new(config: &VoiceConfig) -> Result<Self, VoiceError> {
+        Ok(Self::with_provider(provider(config)?, config))
+    }
+
+    pub fn with_provider(provider: Arc<dyn AudioProvider>, config: &VoiceConfig) -> Self {
+        Self {
+            provider,
+            language: config.language.clone().filter(|l| !l.trim().is_empty()),
+        }
+    }
+
+    /// What was said in a mono recording; empty when nothing was
+    pub async fn transcribe(&self, samples: &[i16], sample_rate: u32) -> Result<String, VoiceError> {
+        let request = TranscriptionRequest {
+            audio: encode_wav(samples, sample_rate),
+            format: AudioFormat::Wav,
+            language: self.language.clone(),
+        };
+        Ok(self.provider.transcribe(&request).await?)
+    }
+}
+
+/// 16-bit mono PCM samples as a WAV file
+pub fn encode_wav(samples: &[i16], sample_rate: u32) -> Vec<u8> {
+    let data_len = (samples.len() * 2) as u32;
+    let mut wav = Vec::with_capacity(44 + samples.len() * 2);
+    wav.extend_from_slice(b"RIFF");
+    wav.extend_from_slice(&(36 + data_len).to_le_bytes());
+    wav.extend_from_slice(b"WAVEfmt ");
+    wav.extend_from_slice(&16u32.to_le_bytes());
+    wav.extend_from_slice(&1u16.to_le_bytes()); // PCM
+    wav.extend_from_slice(&1u16.to_le_bytes()); // mono
+    wav.extend_from_slice(&sample_rate.to_le_bytes());
+    wav.extend_from_slice(&(sample_rate * 2).to_le_bytes()); // bytes per second
+    wav.extend_from_slice(&2u16.to_le_bytes()); // bytes per frame
+    wav.extend_from_slice(&16u16.to_le_bytes());
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&data_len.to_le_bytes());
+    for sample in samples {
+        wav.extend_from_slice(&sample.to_le_bytes());
+    }
+    wav
+}
+
+async fn play(player: &[String], file: &Path) -> Result<(), VoiceError> {
+    let status = tokio::process::Command::new(&player[0])
+        .args(&player[1..])
+        .arg(file)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .status()
+        .await
+        .map_err(|e| VoiceError::Player(format!("{}: {}", player[0], e)))?;
+    if !status.success() {
+        return Err(VoiceError::Player(format!("{} exited with {}", player[0], status)));
+    }
+    Ok(())
+}
+
+fn on_path(program: &str) -> bool {
+    let Some(path) = std::env::var_os("PATH") else {
+        return false;
+    };
+    std::env::split_paths(&path).any(|dir| {
+        dir.join(program).is_file() || (cfg!(windows) && dir.join(format!("{}.exe", program)).is_file())
+    })
+}
+
+/// `markdown` as it should be read aloud: code blocks are replaced by a
+/// short note and formatting marks, link targets and table rules dropped
+pub fn speakable_text(markdown: &str) -> String {
+    let mut out = String::new();
+    let mut in_code = false;
+    for line in markdown.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            if !in_code {
+                out.push_str("(code omitted)\n");
+            }
+            in_code = !in_code;
+            continue;
+        }
+        if in_code {
+            continue;
+        }
+        // Table separator rows such as |---|:--:|
+        if !trimmed.is_empty() && trimmed.chars().all(|c| matches!(c, '|' | '-' | ':' | ' ')) && trimmed.contains('-') {
+            continue;
+        }
+        let line = trimmed.trim_start_matches('#').trim_start_matches('>').trim_start();
+        let line = ["- ", "* ", "+ "]
+            .iter()
+            .find_map(|bullet| line.strip_prefix(bullet))
+            .unwrap_or(line);
+        let line = strip_inline(line);
+        let line = line.trim_matches('|').replace(" | ", ", ").replace('|', ", ");
+        out.push_str(line.trim());
+        out.push('\n');
+    }
+    // Collapse runs of blank lines into one paragraph break
+    let mut text = String::new();
+    for paragraph in out.split("\n\n").map(str::trim).filter(|p| !p.is_empty()) {
+        if !text.is_empty() {
+            text.push_str("\n\n");
+        }
+        text.push_str(paragraph);
+    }
+    text
+}
+
+/// Drop emphasis marks and backticks, and keep only the text of links and images
+fn strip_inline(line: &str) -> String {
+    let mut out = String::with_capacity(line.len());
+    let mut rest = line;
+    while !rest.is_empty() {
+        let link = rest.strip_prefix("![").or_else(|| rest.strip_prefix('['));
+        if let Some(after) = link {
+            if let Some((label, tail)) = after.split_once("](") {
+                if let Some(end) = tail.find(')') {
+                    out.push_str(&strip_inline(label));
+                    rest = &tail[end + 1..];
+                    continue;
+                }
+            }
+        }
+        let mut chars = rest.chars();
+        let c = chars.next().unwrap_or_default();
+        let next = chars.next();
+        match (c, next) {
+            ('*', _) | ('`', _) => {}
+            ('_', Some('_')) | ('~', Some('~')) => {
+                rest = &rest[2..];
+                continue;
+            }
+            _ => out.push(c),
+        }
+        rest = &rest[c.len_utf8()..];
+    }
+    out
+}
+
+/// Break `text` into pieces of at most `max` characters, at paragraph ends,
+/// then sentence ends, then spaces
+fn split_for_speech(text: &str, max: usize) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut rest = text.trim();
+    while rest.chars().count() > max {
+        let limit = rest.char_indices().nth(max).map(|(i, _)| i).unwrap_or(rest.len());
+        let window = &rest[..limit];
+        let cut = window
+            .rfind("\n\n")
+            .or_else(|| ['.', '!', '?'].iter().filter_map(|end| window.rfind(&format!("{} ", end))).max().map(|i| i + 1))
+            .or_else(|| window.rfind(' '))
+            .filter(|&i| i > 0)
+            .unwrap_or(limit);
+        chunks.push(rest[..cut].trim().to_string());
+        rest = rest[cut..].trim_start();
+    }
+    if !rest.is_empty() {
+        chunks.push(rest.to_string());
+    }
+    chunks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jamey_providers::audio::Speech;
+    use std::sync::Mutex;
+
+    struct Recorder(Mutex<Vec<SpeechRequest>>);
+
+    #[async_trait::async_trait]
+    impl AudioProvider for Recorder {
+        async fn synthesize(&self, request: &SpeechRequest) -> Result<Speech, AudioError> {
+            self.0.lock().unwrap().push(request.clone());
+            Ok(Speech { audio: request.text.as_bytes().to_vec(), format: request.format })
+        }
+
+        async fn transcribe(&self, request: &TranscriptionRequest) -> Result<String, AudioError> {
+            Ok(format!("{} bytes of {}", request.audio.len(), request.format))
+        }
+    }
+
+    #[test]
+    fn test_parse_rate() {
+        assert_eq!(parse_rate("medium").unwrap(), 1.0);
+        assert_eq!(parse_rate("X-Fast").unwrap(), 1.75);
+        assert_eq!(parse_rate("120%").unwrap(), 1.2);
+        assert_eq!(parse_rate("0.8").unwrap(), 0.8);
+        assert!(matches!(parse_rate("warp"), Err(VoiceError::InvalidRate(_))));
+        assert!(parse_rate("500%").is_err());
+    }
+
+    #[test]
+    fn test_speakable_text() {
+        let markdown = "# Result\n\nRun **this** with `cargo`:\n\n```bash\ncargo test\n```\n\n\
+                        - See [the docs](https://example.org) for snake_case names\n\n\
+                        | a | b |\n|---|---|\n| 1 | 2 |";
+        assert_eq!(
+            speakable_text(markdown),
+            "Result\n\nRun this with cargo:\n\n(code omitted)\n\nSee the docs for snake_case names\n\na, b\n1, 2"
+        );
+    }
+
+    #[test]
+    fn test_split_for_speech() {
+        assert_eq!(split_for_speech("One. Two. Three.", 10), vec!["One. Two.", "Three."]);
+        assert!(split_for_speech(&"word ".repeat(100), 30).iter().all(|c| c.chars().count() <= 30));
+    }
+
+    #[tokio::test]
+    async fn test_synthesize_saves_audio() {
+        let dir = tempfile::tempdir().unwrap();
+        let recorder = Arc::new(Recorder(Mutex::new(Vec::new())));
+        let config = VoiceConfig {
+            voice: "nova".to_string(),
+            rate: "fast".to_string(),
+            output_dir: dir.path().to_path_buf(),
+            ..Default::default()
+        };
+        let voice = VoiceOutput::with_provider(recorder.clone(), &config).unwrap();
+
+        let files = voice.synthesize("**Hello** there").await.unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].extension().unwrap(), "mp3");
+        assert_eq!(std::fs::read(&files[0]).unwrap(), b"Hello there");
+        let request = recorder.0.lock().unwrap()[0].clone();
+        assert_eq!((request.voice.as_str(), request.speed), ("nova", 1.25));
+
+        assert_eq!(voice.synthesize("```\nonly code\n").await.unwrap().len(), 1);
+        assert!(voice.synthesize("  ").await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_transcribe_sends_wav() {
+        let input = VoiceInput::with_provider(Arc::new(Recorder(Mutex::new(Vec::new()))), &VoiceConfig::default());
+        let text = input.transcribe(&[0, 1, -1, i16::MAX], 16_000).await.unwrap();
+        assert_eq!(text, "52 bytes of wav");
+
+        let wav = encode_wav(&[i16::MIN], 8_000);
+        assert_eq!(&wav[..4], b"RIFF");
+        assert_eq!(u32::from_le_bytes(wav[24..28].try_into().unwrap()), 8_000);
+        assert_eq!(&wav[44..], &i16::MIN.to_le_bytes());
+    }
+}
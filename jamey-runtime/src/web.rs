@@ -0,0 +1,931 @@
+//! Browser chat and the runtime's HTTP server
+//!
+//! With the `web-ui` feature built in and `api.web_ui` on (the default), the
+//! runtime serves a chat page on `api.http_port` with its `/ws` socket, a
+//! Server-Sent Events fallback for proxies that block upgrades, the
+//! [in-flight work](crate::inflight) at `/tasks`, [A2A](crate::a2a) for other
+//! agents and the [`openapi`](crate::openapi) description of all of them.
+//! The same server takes the [inbound hooks](crate::webhooks) when
+//! `api.hooks` is on.
+
+use crate::config::RuntimeConfig;
+use crate::state::RuntimeState;
+use crate::webhooks;
+use axum::http::header::AsHeaderName;
+use axum::http::HeaderMap;
+use axum::routing::MethodRouter;
+use axum::Router;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio::sync::broadcast;
+
+/// How long a client gets to send its request headers
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Where the chat page is served; `None` when this build has no web UI or
+/// `api.web_ui` is off
+pub fn address(config: &RuntimeConfig) -> Option<String> {
+    if !cfg!(feature = "web-ui") || !config.api.web_ui {
+        return None;
+    }
+    let host = match config.api.host.as_str() {
+        "0.0.0.0" | "::" => "localhost",
+        host => host,
+    };
+    Some(format!("http://{}:{}", host, config.api.http_port))
+}
+
+/// Serve the chat page and the inbound hooks, whichever are on, on
+/// `listener` until shutdown
+pub(crate) fn spawn_server(state: Arc<RuntimeState>, listener: TcpListener, shutdown: broadcast::Receiver<()>) {
+    let listener = match listener.into_std() {
+        Ok(listener) => listener,
+        Err(e) => return tracing::warn!("HTTP listener unusable: {}", e),
+    };
+    let router = router(&state);
+    state.supervisor.spawn("web_server", move || {
+        // A restarted server keeps the bound port
+        let listener = listener.try_clone();
+        let router = router.clone();
+        let mut shutdown = shutdown.resubscribe();
+        async move {
+            let server = match listener.map(axum::Server::from_tcp) {
+                Ok(Ok(server)) => server
+                    .http1_header_read_timeout(READ_TIMEOUT)
+                    .serve(router.into_make_service_with_connect_info::<SocketAddr>()),
+                Ok(Err(e)) => return tracing::warn!("HTTP server failed to start: {}", e),
+                Err(e) => return tracing::warn!("HTTP server failed to start: {}", e),
+            };
+            tokio::select! {
+                _ = shutdown.recv() => {}
+                served = server => {
+                    if let Err(e) = served {
+                        tracing::warn!("HTTP server failed: {}", e);
+                    }
+                }
+            }
+        }
+    });
+}
+
+fn router(state: &Arc<RuntimeState>) -> Router {
+    let mut router = Router::new();
+    #[cfg(feature = "web-ui")]
+    if state.config.api.web_ui {
+        router = router.merge(server::routes(Arc::clone(state)));
+    }
+    if state.config.api.hooks {
+        router = router.merge(webhooks::routes(Arc::clone(state)));
+    }
+    router
+}
+
+/// Paths and their handlers, kept as lists so tests can see what is mounted
+pub(crate) type Routes<S> = Vec<(&'static str, MethodRouter<S>)>;
+
+pub(crate) fn mount<S: Clone + Send + Sync + 'static>(routes: Routes<S>) -> Router<S> {
+    routes.into_iter().fold(Router::new(), |router, (path, route)| router.route(path, route))
+}
+
+/// Every path the server can serve, in axum's `:param` form
+#[cfg(all(test, feature = "web-ui"))]
+pub(crate) fn paths() -> Vec<&'static str> {
+    let hooks = webhooks::hook_routes().into_iter().chain(webhooks::sync_routes());
+    hooks.map(|(path, _)| path).chain(server::paths()).collect()
+}
+
+/// A header's value, when it's text
+pub(crate) fn header(headers: &HeaderMap, name: impl AsHeaderName) -> Option<&str> {
+    headers.get(name).and_then(|value| value.to_str().ok())
+}
+
+#[cfg(feature = "web-ui")]
+mod server {
+    use crate::a2a::{self, TaskStore};
+    use crate::archive::ArchiveError;
+    use crate::chat::{ChatTurn, TurnEvent};
+    use crate::config::RuntimeConfig;
+    use crate::openapi;
+    use crate::queue::QueueFull;
+    use crate::session_store::{self, SessionRecord, SessionStoreError};
+    use crate::state::RuntimeState;
+    use crate::summarize;
+    use crate::web::{header, mount, Routes};
+    use async_trait::async_trait;
+    use axum::body::Bytes;
+    use axum::extract::ws::{self, WebSocket, WebSocketUpgrade};
+    use axum::extract::{ConnectInfo, DefaultBodyLimit, Path, State};
+    use axum::http::{header, HeaderMap, HeaderValue, Request, StatusCode};
+    use axum::middleware::{self, Next};
+    use axum::response::sse::{self, KeepAlive, Sse};
+    use axum::response::{IntoResponse, Response};
+    use axum::routing::{delete, get, post, MethodRouter};
+    use axum::{Json, Router};
+    use dashmap::{DashMap, DashSet};
+    use futures_util::stream::{self, Stream, StreamExt};
+    use jamey_protocol::a2a::{
+        A2aMessage, A2aRole, AgentCard, Artifact, Event, JsonRpcRequest, JsonRpcResponse, MessageSendParams, Part, Task,
+        TaskArtifactUpdate, TaskQueryParams, TaskState, TaskStatus, TaskStatusUpdate,
+    };
+    use jamey_protocol::{a2a as protocol, Message, Role};
+    use serde::Deserialize;
+    use std::convert::Infallible;
+    use std::io;
+    use std::net::SocketAddr;
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tokio::sync::broadcast;
+    use uuid::Uuid;
+
+    const INDEX_HTML: &str = include_str!("../web/index.html");
+    const APP_JS: &str = include_str!("../web/app.js");
+    const STYLE_CSS: &str = include_str!("../web/style.css");
+    const DOCS_HTML: &str = include_str!("../web/docs.html");
+    const DOCS_JS: &str = include_str!("../web/docs.js");
+
+    /// Largest request body or socket message accepted from the page
+    const MAX_MESSAGE_BYTES: usize = 256 * 1024;
+    /// Events buffered per followed session for slow event-stream clients
+    const STREAM_BUFFER: usize = 256;
+    /// Comment sent on an idle event stream so proxies don't drop it
+    const KEEPALIVE_INTERVAL: Duration = Duration::from_secs(15);
+
+    #[derive(Clone)]
+    struct App {
+        state: Arc<RuntimeState>,
+        streams: Arc<Streams>,
+        tasks: Arc<TaskStore>,
+    }
+
+    /// The page, its assets, the API description and the socket, which
+    /// checks keys and origins itself
+    fn open_routes() -> Routes<App> {
+        vec![
+            ("/", asset("text/html; charset=utf-8", INDEX_HTML)),
+            ("/index.html", asset("text/html; charset=utf-8", INDEX_HTML)),
+            ("/app.js", asset("text/javascript; charset=utf-8", APP_JS)),
+            ("/style.css", asset("text/css; charset=utf-8", STYLE_CSS)),
+            ("/docs", asset("text/html; charset=utf-8", DOCS_HTML)),
+            ("/docs.js", asset("text/javascript; charset=utf-8", DOCS_JS)),
+            ("/openapi.json", get(openapi_document)),
+            ("/ws", get(socket)),
+        ]
+    }
+
+    /// Agent cards are fetched by other agents, not pages, so only need the key
+    fn keyed_routes() -> Routes<App> {
+        vec![(protocol::AGENT_CARD_PATH, get(agent_card)), (protocol::LEGACY_AGENT_CARD_PATH, get(agent_card))]
+    }
+
+    /// Routes needing the key and an allowed origin
+    fn guarded_routes() -> Routes<App> {
+        vec![
+            ("/a2a", post(a2a_call)),
+            ("/tasks", get(list_tasks)),
+            ("/tasks/:id", delete(cancel_task)),
+            ("/sessions/:id/stream", get(follow)),
+            ("/sessions/:id/messages", post(post_message)),
+        ]
+    }
+
+    #[cfg(test)]
+    pub(super) fn paths() -> Vec<&'static str> {
+        [open_routes(), keyed_routes(), guarded_routes()].into_iter().flatten().map(|(path, _)| path).collect()
+    }
+
+    /// Every route the web UI serves
+    pub(super) fn routes(state: Arc<RuntimeState>) -> Router {
+        let app = App { state, streams: Arc::default(), tasks: Arc::default() };
+        let keyed = mount(keyed_routes()).route_layer(middleware::from_fn_with_state(app.clone(), require_key));
+        let guarded = mount(guarded_routes())
+            .route_layer(middleware::from_fn_with_state(app.clone(), require_key))
+            .route_layer(middleware::from_fn_with_state(app.clone(), require_origin));
+        mount(open_routes())
+            .merge(keyed)
+            .merge(guarded)
+            .layer(DefaultBodyLimit::max(MAX_MESSAGE_BYTES))
+            .layer(middleware::map_response(common_headers))
+            .with_state(app)
+    }
+
+    fn asset(content_type: &'static str, body: &'static str) -> MethodRouter<App> {
+        get(move || async move { ([(header::CONTENT_TYPE, content_type)], body) })
+    }
+
+    async fn common_headers<B>(mut response: Response<B>) -> Response<B> {
+        let headers = response.headers_mut();
+        headers.insert(header::CACHE_CONTROL, HeaderValue::from_static("no-cache"));
+        headers.insert(header::X_CONTENT_TYPE_OPTIONS, HeaderValue::from_static("nosniff"));
+        headers.insert(header::CONTENT_SECURITY_POLICY, HeaderValue::from_static("default-src 'self'"));
+        response
+    }
+
+    /// Turn away pages from origins other than the chat's own
+    async fn require_origin<B>(State(app): State<App>, request: Request<B>, next: Next<B>) -> Response {
+        if !origin_allowed(&app.state.config, request.headers()) {
+            return failure(StatusCode::FORBIDDEN);
+        }
+        next.run(request).await
+    }
+
+    async fn require_key<B>(
+        State(app): State<App>,
+        ConnectInfo(peer): ConnectInfo<SocketAddr>,
+        request: Request<B>,
+        next: Next<B>,
+    ) -> Response {
+        let token = token(request.headers(), request.uri().query());
+        if !authorized(&app.state.config, token.as_deref(), peer) {
+            return failure(StatusCode::UNAUTHORIZED);
+        }
+        next.run(request).await
+    }
+
+    fn failure(status: StatusCode) -> Response {
+        (status, Json(error(status.canonical_reason().unwrap_or_default()))).into_response()
+    }
+
+    /// Sessions followed over event streams, and those with a turn started
+    /// through `POST /sessions/{id}/messages` still running
+    #[derive(Default)]
+    struct Streams {
+        channels: DashMap<Uuid, broadcast::Sender<serde_json::Value>>,
+        busy: DashSet<Uuid>,
+    }
+
+    impl Streams {
+        fn channel(&self, id: Uuid) -> broadcast::Sender<serde_json::Value> {
+            self.channels
+                .entry(id)
+                .or_insert_with(|| broadcast::channel(STREAM_BUFFER).0)
+                .clone()
+        }
+
+        /// Drop `id`'s channel once nobody follows it
+        fn release(&self, id: Uuid) {
+            self.channels.remove_if(&id, |_, channel| channel.receiver_count() == 0);
+        }
+    }
+
+    /// One event-stream client of a session, releasing its channel when
+    /// the client goes away
+    struct Follower {
+        events: Option<broadcast::Receiver<serde_json::Value>>,
+        streams: Arc<Streams>,
+        id: Uuid,
+    }
+
+    impl Drop for Follower {
+        fn drop(&mut self) {
+            self.events.take();
+            self.streams.release(self.id);
+        }
+    }
+
+    async fn openapi_document(State(app): State<App>) -> Json<serde_json::Value> {
+        Json(openapi::document(&app.state.config))
+    }
+
+    async fn socket(
+        State(app): State<App>,
+        ConnectInfo(peer): ConnectInfo<SocketAddr>,
+        headers: HeaderMap,
+        upgrade: WebSocketUpgrade,
+    ) -> Response {
+        if !origin_allowed(&app.state.config, &headers) {
+            return (StatusCode::FORBIDDEN, "Forbidden").into_response();
+        }
+        let node = node_headers(&app.state);
+        let upgraded = upgrade.max_message_size(MAX_MESSAGE_BYTES).on_upgrade(move |socket| async move {
+            if let Err(e) = chat(&app.state, &mut Socket(socket), peer).await {
+                tracing::debug!("Web UI connection from {} failed: {}", peer, e);
+            }
+        });
+        (node, upgraded).into_response()
+    }
+
+    async fn agent_card(State(app): State<App>, headers: HeaderMap) -> Json<AgentCard> {
+        Json(a2a::agent_card(&app.state.config, &base_url(&app.state.config, &headers)))
+    }
+
+    async fn list_tasks(State(app): State<App>) -> Response {
+        let report = app.state.in_flight.report(app.state.request_queue.stats(), app.state.supervisor.tasks());
+        Json(report).into_response()
+    }
+
+    async fn cancel_task(State(app): State<App>, Path(id): Path<Uuid>) -> Response {
+        if !app.state.in_flight.cancel(id) {
+            return (StatusCode::NOT_FOUND, Json(error("No running task with that id"))).into_response();
+        }
+        Json(serde_json::json!({ "cancelled": id })).into_response()
+    }
+
+    /// Sticky routing hints naming this instance; empty outside a cluster
+    fn node_headers(state: &RuntimeState) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        if let Some(cluster) = &state.cluster {
+            let node = cluster.node_id();
+            let cookie = format!("jamey_node={}; Path=/; HttpOnly; SameSite=Lax", node);
+            if let (Ok(node), Ok(cookie)) = (HeaderValue::from_str(node), HeaderValue::from_str(&cookie)) {
+                headers.insert("x-jamey-node", node);
+                headers.insert(header::SET_COOKIE, cookie);
+            }
+        }
+        headers
+    }
+
+    /// Where clients reached this server, as seen through any proxy in front
+    fn base_url(config: &RuntimeConfig, headers: &HeaderMap) -> String {
+        match header(headers, header::HOST) {
+            Some(host) => {
+                let scheme = header(headers, "x-forwarded-proto").unwrap_or("http");
+                format!("{}://{}", scheme, host)
+            }
+            None => crate::web::address(config).unwrap_or_default(),
+        }
+    }
+
+    /// The bearer token, or the `token` query parameter
+    fn token(headers: &HeaderMap, query: Option<&str>) -> Option<String> {
+        if let Some(token) = header(headers, header::AUTHORIZATION).and_then(|v| v.strip_prefix("Bearer ")) {
+            return Some(token.to_string());
+        }
+        url::form_urlencoded::parse(query.unwrap_or_default().as_bytes())
+            .find(|(name, _)| name == "token")
+            .map(|(_, value)| value.into_owned())
+    }
+
+    /// The page's own origin, or one listed in `api.allowed_origins`.
+    /// Clients other than browsers send no origin.
+    fn origin_allowed(config: &RuntimeConfig, headers: &HeaderMap) -> bool {
+        let Some(origin) = headers.get(header::ORIGIN) else {
+            return true;
+        };
+        let origin = origin.to_str().unwrap_or_default();
+        let own = header(headers, header::HOST).is_some_and(|host| {
+            let origin_host = origin.strip_prefix("http://").or_else(|| origin.strip_prefix("https://"));
+            origin_host == Some(host)
+        });
+        own || config.api.allowed_origins.iter().any(|allowed| allowed == origin)
+    }
+
+    fn authorized(config: &RuntimeConfig, token: Option<&str>, peer: SocketAddr) -> bool {
+        if !config.security.api_key_required {
+            return peer.ip().is_loopback();
+        }
+        match (&config.security.api_key, token) {
+            (Some(key), Some(token)) => key.0 == token,
+            _ => false,
+        }
+    }
+
+    #[derive(Debug, Deserialize)]
+    #[serde(tag = "type", rename_all = "snake_case")]
+    enum ClientMessage {
+        Hello {
+            session: Option<Uuid>,
+            token: Option<String>,
+        },
+        Message {
+            content: String,
+        },
+    }
+
+    /// Run one page's conversation until it disconnects
+    async fn chat(state: &RuntimeState, socket: &mut Socket, peer: SocketAddr) -> io::Result<()> {
+        let Some(ClientMessage::Hello { session, token }) = socket.receive_json().await? else {
+            return socket.close().await;
+        };
+        if !authorized(&state.config, token.as_deref(), peer) {
+            socket.send_json(&serde_json::json!({ "type": "unauthorized" })).await?;
+            return socket.close().await;
+        }
+
+        let mut record = match open_session(state, session.unwrap_or_else(Uuid::new_v4)).await {
+            Ok(record) => record,
+            Err(e) => {
+                socket.send_json(&error(&e.to_string())).await?;
+                return socket.close().await;
+            }
+        };
+        socket.send_json(&session_message(&record)).await?;
+
+        while let Some(message) = socket.receive_json().await? {
+            match message {
+                ClientMessage::Message { content } if !content.trim().is_empty() => {
+                    match begin(state, &record, content.trim()) {
+                        Ok(started) => turn(state, socket, &mut record, started).await?,
+                        Err(full) => socket.send_json(&full.to_json()).await?,
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Server-Sent Events carrying `events`, with comments while idle
+    fn event_stream(events: impl Stream<Item = serde_json::Value> + Send + 'static) -> Response {
+        let events = events.map(|event| Ok::<_, Infallible>(sse::Event::default().data(event.to_string())));
+        let keepalive = KeepAlive::new().interval(KEEPALIVE_INTERVAL).text("keepalive");
+        ([("x-accel-buffering", "no")], Sse::new(events).keep_alive(keepalive)).into_response()
+    }
+
+    /// Follow session `id` as an event stream until the client goes away
+    async fn follow(State(app): State<App>, Path(id): Path<Uuid>) -> Response {
+        let record = match open_session(&app.state, id).await {
+            Ok(record) => record,
+            Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, Json(error(&e.to_string()))).into_response(),
+        };
+        let follower = Follower {
+            events: Some(app.streams.channel(id).subscribe()),
+            streams: Arc::clone(&app.streams),
+            id,
+        };
+        let events = stream::unfold(follower, |mut follower| async move {
+            loop {
+                match follower.events.as_mut()?.recv().await {
+                    Ok(event) => return Some((event, follower)),
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        tracing::debug!("Event stream for session {} skipped {} events", follower.id, skipped);
+                    }
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                }
+            }
+        });
+        let opening = stream::once(async move { session_message(&record) });
+        (node_headers(&app.state), event_stream(opening.chain(events))).into_response()
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct PostedMessage {
+        content: String,
+    }
+
+    async fn post_message(State(app): State<App>, Path(id): Path<Uuid>, body: Bytes) -> Response {
+        let node = node_headers(&app.state);
+        let (status, body) = start_message(app, id, &body).await;
+        (status, node, Json(body)).into_response()
+    }
+
+    /// Start a turn in session `id` whose events go to its followers
+    async fn start_message(app: App, id: Uuid, body: &[u8]) -> (StatusCode, serde_json::Value) {
+        let App { state, streams, .. } = app;
+        let content = match serde_json::from_slice::<PostedMessage>(body) {
+            Ok(message) if !message.content.trim().is_empty() => message.content.trim().to_string(),
+            Ok(_) => return (StatusCode::BAD_REQUEST, error("Message is empty")),
+            Err(e) => return (StatusCode::BAD_REQUEST, error(&format!("Malformed message: {}", e))),
+        };
+        if !streams.busy.insert(id) {
+            return (StatusCode::CONFLICT, error("A reply is already being written in this session"));
+        }
+        let mut record = match open_session(&state, id).await {
+            Ok(record) => record,
+            Err(e) => {
+                streams.busy.remove(&id);
+                return (StatusCode::INTERNAL_SERVER_ERROR, error(&e.to_string()));
+            }
+        };
+        let started = match begin(&state, &record, &content) {
+            Ok(started) => started,
+            Err(full) => {
+                streams.busy.remove(&id);
+                let status = StatusCode::from_u16(full.status()).unwrap_or(StatusCode::TOO_MANY_REQUESTS);
+                return (status, full.to_json());
+            }
+        };
+        tokio::spawn(async move {
+            let mut outbox = streams.channel(id);
+            // Broadcasting can't fail, so neither can the turn's delivery
+            let _ = turn(&state, &mut outbox, &mut record, started).await;
+            streams.busy.remove(&id);
+            drop(outbox);
+            streams.release(id);
+        });
+        (StatusCode::ACCEPTED, serde_json::json!({ "accepted": true, "session_id": id }))
+    }
+
+    /// Answer one A2A JSON-RPC call; errors go out as 200s
+    async fn a2a_call(State(app): State<App>, body: Bytes) -> Response {
+        let App { state, streams, tasks } = app;
+        let call = match serde_json::from_slice::<JsonRpcRequest>(&body) {
+            Ok(call) => call,
+            Err(e) => {
+                return Json(JsonRpcResponse::error(serde_json::Value::Null, protocol::PARSE_ERROR, e.to_string()))
+                    .into_response();
+            }
+        };
+        let id = call.id.clone();
+        let response = match call.method.as_str() {
+            method @ (protocol::METHOD_SEND | protocol::METHOD_STREAM) => {
+                let params = match serde_json::from_value::<MessageSendParams>(call.params) {
+                    Ok(params) => params,
+                    Err(e) => return Json(JsonRpcResponse::error(id, protocol::INVALID_PARAMS, e.to_string())).into_response(),
+                };
+                let (task, mut events) = match start_task(&state, &streams, &tasks, params.message).await {
+                    Ok(started) => started,
+                    Err((code, message)) => return Json(JsonRpcResponse::error(id, code, message)).into_response(),
+                };
+                if method == protocol::METHOD_STREAM {
+                    return stream_task(id, task, events);
+                }
+                while !is_final(events.recv().await) {}
+                JsonRpcResponse::result(id, Event::Task(tasks.get(&task.id, None).unwrap_or(task)))
+            }
+            protocol::METHOD_GET_TASK | protocol::METHOD_CANCEL_TASK => {
+                let query = match serde_json::from_value::<TaskQueryParams>(call.params) {
+                    Ok(query) => query,
+                    Err(e) => return Json(JsonRpcResponse::error(id, protocol::INVALID_PARAMS, e.to_string())).into_response(),
+                };
+                let outcome = if call.method == protocol::METHOD_GET_TASK {
+                    tasks.get(&query.id, query.history_length).ok_or(protocol::TASK_NOT_FOUND)
+                } else {
+                    tasks.cancel(&query.id).inspect(|task| {
+                        if let Ok(session) = task.context_id.parse::<Uuid>() {
+                            streams.busy.remove(&session);
+                        }
+                    })
+                };
+                match outcome {
+                    Ok(task) => JsonRpcResponse::result(id, Event::Task(task)),
+                    Err(protocol::TASK_NOT_CANCELABLE) => {
+                        JsonRpcResponse::error(id, protocol::TASK_NOT_CANCELABLE, "The task has already finished")
+                    }
+                    Err(code) => JsonRpcResponse::error(id, code, "No task with that id"),
+                }
+            }
+            method => JsonRpcResponse::error(id, protocol::METHOD_NOT_FOUND, format!("Unknown method: {}", method)),
+        };
+        Json(response).into_response()
+    }
+
+    /// Whether a task's stream is over
+    fn is_final(event: Result<Event, broadcast::error::RecvError>) -> bool {
+        match event {
+            Ok(Event::StatusUpdate(update)) => update.is_final,
+            Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => false,
+            Err(broadcast::error::RecvError::Closed) => true,
+        }
+    }
+
+    /// Run `message` as a task in the session its context names, or a new
+    /// one; fails with a JSON-RPC error code and message
+    async fn start_task(
+        state: &Arc<RuntimeState>,
+        streams: &Arc<Streams>,
+        tasks: &Arc<TaskStore>,
+        message: A2aMessage,
+    ) -> Result<(Task, broadcast::Receiver<Event>), (i64, String)> {
+        let content = message.content().trim().to_string();
+        if content.is_empty() {
+            return Err((protocol::INVALID_PARAMS, "Message is empty".to_string()));
+        }
+        let session = message
+            .context_id
+            .as_deref()
+            .and_then(|context| context.parse().ok())
+            .unwrap_or_else(Uuid::new_v4);
+        if !streams.busy.insert(session) {
+            return Err((protocol::INTERNAL_ERROR, "A reply is already being written in this context".to_string()));
+        }
+        let started = match open_session(state, session).await {
+            Ok(record) => begin(state, &record, &content).map(|started| (record, started)).map_err(|full| full.to_string()),
+            Err(e) => Err(e.to_string()),
+        };
+        let (mut record, started) = match started {
+            Ok(started) => started,
+            Err(e) => {
+                streams.busy.remove(&session);
+                return Err((protocol::INTERNAL_ERROR, e));
+            }
+        };
+
+        let task = tasks.create(session, message);
+        let events = tasks.subscribe(&task.id).expect("task was just created");
+        let mut outbox = TaskOutbox {
+            tasks: Arc::clone(tasks),
+            task_id: task.id.clone(),
+            context_id: task.context_id.clone(),
+            replying: false,
+        };
+        outbox.status(TaskState::Working, None);
+        let (state, streams) = (Arc::clone(state), Arc::clone(streams));
+        let running = tokio::spawn(async move {
+            // The task store can't fail, so neither can the turn's delivery
+            let _ = turn(&state, &mut outbox, &mut record, started).await;
+            streams.busy.remove(&session);
+        });
+        tasks.set_abort(&task.id, running.abort_handle());
+        Ok((task, events))
+    }
+
+    /// Answer `message/stream`: the task, then its events until it's over
+    fn stream_task(id: serde_json::Value, task: Task, events: broadcast::Receiver<Event>) -> Response {
+        let updates = stream::unfold(Some(events), |events| async move {
+            let mut events = events?;
+            loop {
+                match events.recv().await {
+                    Ok(event) => {
+                        let last = matches!(&event, Event::StatusUpdate(update) if update.is_final);
+                        return Some((event, (!last).then_some(events)));
+                    }
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        tracing::debug!("A2A stream skipped {} events", skipped);
+                    }
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                }
+            }
+        });
+        let events = stream::once(async move { Event::Task(task) })
+            .chain(updates)
+            .map(move |event| serde_json::json!(JsonRpcResponse::result(id.clone(), event)));
+        event_stream(events)
+    }
+
+    /// Load session `id` to carry on with it, or start it if it's new
+    async fn open_session(state: &RuntimeState, id: Uuid) -> Result<SessionRecord, ArchiveError> {
+        state.adopt_session(id).await;
+        match state.revive_session(id).await {
+            Err(ArchiveError::Sessions(SessionStoreError::NotFound(_))) => {
+                state.session_manager.resume_session(id);
+                Ok(SessionRecord::new(id))
+            }
+            result => result,
+        }
+    }
+
+    /// The `session` message opening a conversation: its transcript so far
+    fn session_message(record: &SessionRecord) -> serde_json::Value {
+        let history: Vec<_> = record
+            .messages
+            .iter()
+            .map(|m| serde_json::json!({ "role": role(m), "content": m.content }))
+            .collect();
+        serde_json::json!({
+            "type": "session",
+            "id": record.id,
+            "title": record.display_title(),
+            "messages": history,
+        })
+    }
+
+    /// Where a turn's events are delivered
+    #[async_trait]
+    trait Outbox: Send {
+        async fn send(&mut self, event: &serde_json::Value) -> io::Result<()>;
+    }
+
+    #[async_trait]
+    impl Outbox for Socket {
+        async fn send(&mut self, event: &serde_json::Value) -> io::Result<()> {
+            self.send_json(event).await
+        }
+    }
+
+    /// Every stream following the session; having none isn't an error
+    #[async_trait]
+    impl Outbox for broadcast::Sender<serde_json::Value> {
+        async fn send(&mut self, event: &serde_json::Value) -> io::Result<()> {
+            let _ = broadcast::Sender::send(self, event.clone());
+            Ok(())
+        }
+    }
+
+    /// An A2A task, whose status and reply artifact follow the turn
+    struct TaskOutbox {
+        tasks: Arc<TaskStore>,
+        task_id: String,
+        context_id: String,
+        /// Part of the reply has been sent
+        replying: bool,
+    }
+
+    impl TaskOutbox {
+        fn status(&self, state: TaskState, text: Option<String>) {
+            let message = text.map(|text| A2aMessage {
+                task_id: Some(self.task_id.clone()),
+                context_id: Some(self.context_id.clone()),
+                ..A2aMessage::text(A2aRole::Agent, text)
+            });
+            self.tasks.publish(
+                &self.task_id,
+                Event::StatusUpdate(TaskStatusUpdate {
+                    task_id: self.task_id.clone(),
+                    context_id: self.context_id.clone(),
+                    status: TaskStatus { message, ..TaskStatus::now(state) },
+                    is_final: state.is_terminal(),
+                }),
+            );
+        }
+
+        fn reply(&self, text: &str, append: bool, last_chunk: bool) {
+            self.tasks.publish(
+                &self.task_id,
+                Event::ArtifactUpdate(TaskArtifactUpdate {
+                    task_id: self.task_id.clone(),
+                    context_id: self.context_id.clone(),
+                    artifact: Artifact {
+                        artifact_id: "reply".to_string(),
+                        name: Some("reply".to_string()),
+                        parts: vec![Part::Text { text: text.to_string() }],
+                    },
+                    append,
+                    last_chunk,
+                }),
+            );
+        }
+    }
+
+    #[async_trait]
+    impl Outbox for TaskOutbox {
+        async fn send(&mut self, event: &serde_json::Value) -> io::Result<()> {
+            let text = |field: &str| event[field].as_str().unwrap_or_default().to_string();
+            match event["type"].as_str() {
+                Some("token") => {
+                    self.reply(&text("text"), self.replying, false);
+                    self.replying = true;
+                }
+                Some("tool_call") => self.status(TaskState::Working, Some(format!("Using {}", text("name")))),
+                Some("approval") => self.status(
+                    TaskState::Working,
+                    Some(format!("Waiting for approval of {} {}", text("connector"), text("action"))),
+                ),
+                Some("completed") => {
+                    // The saved reply replaces what was streamed
+                    self.reply(&text("content"), false, true);
+                    self.status(TaskState::Completed, None);
+                }
+                Some("error") => self.status(TaskState::Failed, Some(text("message"))),
+                _ => {}
+            }
+            Ok(())
+        }
+    }
+
+    /// A turn under way in a session, and what's needed to record it
+    struct Started {
+        turn: ChatTurn,
+        question: Message,
+        /// Where the history sent with the turn starts in the transcript
+        start: usize,
+    }
+
+    /// Start answering `content` in `record`'s session, unless the request
+    /// queue is full
+    fn begin(state: &RuntimeState, record: &SessionRecord, content: &str) -> Result<Started, QueueFull> {
+        let question = Message::user(content);
+        // Earlier turns that were already summarized stay in the transcript
+        // but aren't sent again
+        let start = record.messages.iter().rposition(summarize::is_summary).unwrap_or(0);
+        let mut history = record.messages[start..].to_vec();
+        history.push(question.clone());
+        let turn = state.try_stream_session_turn(record.id, history)?;
+        Ok(Started { turn, question, start })
+    }
+
+    /// Stream a started turn to `outbox` and save the exchange
+    async fn turn(
+        state: &RuntimeState,
+        outbox: &mut dyn Outbox,
+        record: &mut SessionRecord,
+        started: Started,
+    ) -> io::Result<()> {
+        let Started { mut turn, question, start } = started;
+        let reply = loop {
+            let event = match turn.next().await {
+                Some(TurnEvent::Summarized(compaction)) => {
+                    record.messages.insert(start + compaction.replaced, compaction.summary);
+                    continue;
+                }
+                Some(TurnEvent::Token(text)) => serde_json::json!({ "type": "token", "text": text }),
+                Some(TurnEvent::ToolCall(call)) => serde_json::json!({ "type": "tool_call", "name": call.name }),
+                Some(TurnEvent::AwaitingApproval(request)) => serde_json::json!({
+                    "type": "approval",
+                    "id": request.id,
+                    "connector": request.connector_id,
+                    "action": request.action,
+                }),
+                Some(TurnEvent::ToolResult(result)) => serde_json::json!({
+                    "type": "tool_result",
+                    "name": result.name,
+                    "error": result.error,
+                }),
+                Some(TurnEvent::Usage { .. }) => continue,
+                Some(TurnEvent::Completed(reply)) => break reply,
+                Some(TurnEvent::Failed(e)) => return outbox.send(&error(&e)).await,
+                None => return outbox.send(&error("The turn ended without a reply")).await,
+            };
+            outbox.send(&event).await?;
+        };
+
+        if record.title.is_empty() {
+            record.title = session_store::title_from(&question.content);
+        }
+        record.messages.push(question);
+        record.messages.push(reply.clone());
+        record.model = Some(state.config.llm.openrouter_default_model.clone());
+        record.updated_at = chrono::Utc::now();
+        if let Err(e) = state.session_store.save(record).await {
+            tracing::warn!("Failed to save web session {}: {}", record.id, e);
+        }
+        state.share_session(record.id).await;
+        outbox
+            .send(&serde_json::json!({
+                "type": "completed",
+                "content": reply.content,
+                "citations": reply.citations,
+                "degradation": reply.metadata.get("degradation"),
+                "title": record.display_title(),
+            }))
+            .await
+    }
+
+    fn role(message: &Message) -> &'static str {
+        match message.role {
+            Role::User => "user",
+            Role::Assistant => "assistant",
+            Role::System | Role::Tool => "system",
+        }
+    }
+
+    fn error(message: &str) -> serde_json::Value {
+        serde_json::json!({ "type": "error", "message": message })
+    }
+
+
+    /// The page's end of `/ws`
+    struct Socket(WebSocket);
+
+    impl Socket {
+        /// Next text message; `None` once the page closes. Pings are
+        /// answered by the socket itself.
+        async fn receive(&mut self) -> io::Result<Option<String>> {
+            while let Some(message) = self.0.recv().await {
+                match message.map_err(io::Error::other)? {
+                    ws::Message::Text(text) => return Ok(Some(text)),
+                    ws::Message::Close(_) => return Ok(None),
+                    _ => {}
+                }
+            }
+            Ok(None)
+        }
+
+        /// Next message parsed as JSON; malformed ones are answered with an
+        /// error and skipped
+        async fn receive_json(&mut self) -> io::Result<Option<ClientMessage>> {
+            while let Some(text) = self.receive().await? {
+                match serde_json::from_str(&text) {
+                    Ok(message) => return Ok(Some(message)),
+                    Err(e) => self.send_json(&error(&format!("Malformed message: {}", e))).await?,
+                }
+            }
+            Ok(None)
+        }
+
+        async fn send_json(&mut self, value: &serde_json::Value) -> io::Result<()> {
+            self.0.send(ws::Message::Text(value.to_string())).await.map_err(io::Error::other)
+        }
+
+        async fn close(&mut self) -> io::Result<()> {
+            self.0.send(ws::Message::Close(None)).await.map_err(io::Error::other)
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn headers(pairs: &[(&'static str, &'static str)]) -> HeaderMap {
+            pairs
+                .iter()
+                .map(|(name, value)| (header::HeaderName::from_static(name), HeaderValue::from_static(value)))
+                .collect()
+        }
+
+        #[test]
+        fn test_origin_check() {
+            let config = RuntimeConfig::default();
+            let host = ("host", "localhost:3000");
+            assert!(origin_allowed(&config, &headers(&[host])));
+            assert!(origin_allowed(&config, &headers(&[host, ("origin", "http://localhost:3000")])));
+            assert!(!origin_allowed(&config, &headers(&[host, ("origin", "https://evil.example")])));
+        }
+
+        #[test]
+        fn test_tokens() {
+            assert_eq!(token(&HeaderMap::new(), Some("token=a%2Bb")).as_deref(), Some("a+b"));
+            assert_eq!(token(&headers(&[("authorization", "Bearer secret")]), None).as_deref(), Some("secret"));
+            assert_eq!(token(&HeaderMap::new(), Some("x=1")), None);
+        }
+
+        #[test]
+        fn test_authorized_without_key() {
+            let mut config = RuntimeConfig::default();
+            config.security.api_key_required = false;
+            assert!(authorized(&config, None, "127.0.0.1:5000".parse().unwrap()));
+            assert!(!authorized(&config, None, "192.0.2.1:5000".parse().unwrap()));
+        }
+    }
+}
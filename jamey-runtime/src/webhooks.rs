@@ -0,0 +1,291 @@
+//! Webhook delivery and inbound hooks
+//!
+//! Every [`RuntimeEvent`] is offered to the outbound hooks registered with
+//! the `webhook` connector. With `api.hooks` on, the [web server](crate::web)
+//! also accepts `POST /hooks/{name}`: a payload sent to a session hook is
+//! appended to that session, one sent to a queue hook becomes a one-off
+//! scheduler task for the hook's connector. Telegram bot updates arrive at
+//! [`telegram::WEBHOOK_PATH`] on the same server, and a sync peer's requests
+//! at [`sync::PATH`].
+
+use crate::events::{self, EventBus, RuntimeEvent};
+use crate::scheduler::{Schedule, ScheduledTask, TaskKind};
+use crate::state::RuntimeState;
+use crate::sync;
+use crate::telegram;
+use crate::web;
+use axum::body::Bytes;
+use axum::extract::rejection::BytesRejection;
+use axum::extract::{DefaultBodyLimit, Path, State};
+use axum::http::{header, HeaderMap, Method, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::any;
+use axum::{Json, Router};
+use chrono::Utc;
+use jamey_core::supervisor::Supervisor;
+use jamey_protocol::Message;
+use jamey_tools::connectors::telegram::SECRET_TOKEN_HEADER;
+use jamey_tools::connectors::webhook::{InboundHook, SIGNATURE_HEADER};
+use jamey_tools::connectors::{HookTarget, WebhookConnector};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+/// Largest inbound payload accepted
+const MAX_BODY_BYTES: usize = 1024 * 1024;
+
+pub(crate) fn default_webhook_dir() -> PathBuf {
+    std::env::var("JAMEY_WEBHOOK_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from("./webhooks"))
+}
+
+/// Forward bus events to outbound hooks until shutdown
+pub(crate) fn spawn_event_delivery(
+    supervisor: &Supervisor,
+    webhooks: WebhookConnector,
+    bus: &EventBus,
+    shutdown: broadcast::Receiver<()>,
+) {
+    let bus = bus.clone();
+    supervisor.spawn("event_delivery", move || {
+        let webhooks = webhooks.clone();
+        let mut events = bus.subscribe();
+        let mut shutdown = shutdown.resubscribe();
+        async move {
+            loop {
+                let event: RuntimeEvent = tokio::select! {
+                    _ = shutdown.recv() => break,
+                    event = events.recv() => match event {
+                        Ok(event) => event,
+                        Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                            tracing::warn!("Webhooks missed {} runtime events", skipped);
+                            continue;
+                        }
+                        Err(broadcast::error::RecvError::Closed) => break,
+                    },
+                };
+                // Retries can take a while; don't hold up the events behind this one
+                let webhooks = webhooks.clone();
+                tokio::spawn(async move {
+                    let payload = serde_json::json!({
+                        "session_id": event.session_id,
+                        "occurred_at": event.at,
+                        "data": event.data,
+                    });
+                    webhooks.dispatch(&event.kind, &payload).await;
+                });
+            }
+        }
+    });
+}
+
+/// The inbound hooks, the Telegram webhook and sync requests, for the
+/// [web server](crate::web) to mount
+pub(crate) fn routes(state: Arc<RuntimeState>) -> Router {
+    web::mount(hook_routes())
+        .layer(DefaultBodyLimit::max(MAX_BODY_BYTES))
+        .merge(web::mount(sync_routes()).layer(DefaultBodyLimit::max(sync::MAX_BODY_BYTES)))
+        .with_state(state)
+}
+
+/// Inbound hooks and the Telegram webhook, under the hooks' body limit
+pub(crate) fn hook_routes() -> web::Routes<Arc<RuntimeState>> {
+    vec![("/hooks/:name", any(hook)), (telegram::WEBHOOK_PATH, any(telegram_update))]
+}
+
+/// Sync batches are far larger than hook payloads
+pub(crate) fn sync_routes() -> web::Routes<Arc<RuntimeState>> {
+    vec![(sync::PATH, any(sync_request))]
+}
+
+type Rejection = (u16, serde_json::Value);
+
+/// A body too large or cut short, in the hooks' own error shape
+fn rejected(rejection: BytesRejection) -> Rejection {
+    (rejection.status().as_u16(), error_body(&rejection.body_text()))
+}
+
+fn respond((status, body): Rejection) -> Response {
+    (StatusCode::from_u16(status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR), Json(body)).into_response()
+}
+
+async fn hook(
+    State(state): State<Arc<RuntimeState>>,
+    method: Method,
+    Path(name): Path<String>,
+    headers: HeaderMap,
+    body: Result<Bytes, BytesRejection>,
+) -> Response {
+    respond(match body {
+        Ok(body) => handle(&state, &method, &name, &headers, &body).await,
+        Err(rejection) => rejected(rejection),
+    })
+}
+
+async fn sync_request(
+    State(state): State<Arc<RuntimeState>>,
+    method: Method,
+    headers: HeaderMap,
+    body: Result<Bytes, BytesRejection>,
+) -> Response {
+    let (status, body) = match body {
+        Ok(body) => sync::serve(&state, method.as_str(), web::header(&headers, SIGNATURE_HEADER), &body).await,
+        Err(rejection) => rejected(rejection),
+    };
+    let body = body.to_string();
+    // Sync peers only trust replies signed with the shared secret
+    let signature = sync::sign_response(&state.config, &body).map(|signature| [(SIGNATURE_HEADER, signature)]);
+    let status = StatusCode::from_u16(status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+    (status, signature, [(header::CONTENT_TYPE, "application/json")], body).into_response()
+}
+
+async fn handle(state: &RuntimeState, method: &Method, name: &str, headers: &HeaderMap, body: &[u8]) -> Rejection {
+    if method != Method::POST {
+        return (405, error_body("Use POST"));
+    }
+    // Unknown hooks and bad secrets look the same from outside
+    let Some(hook) = state.webhooks.inbound_hook(name).await.filter(|hook| authenticated(hook, headers, body)) else {
+        return (401, error_body("Unknown hook or invalid credentials"));
+    };
+
+    let accepted = match &hook.target {
+        HookTarget::Session { id } => deliver_to_session(state, &hook, *id, body).await,
+        HookTarget::Queue { connector } => queue_job(state, &hook, connector, body).await,
+    };
+    match accepted {
+        Ok(detail) => {
+            state.events.publish(
+                events::HOOK_RECEIVED,
+                match hook.target {
+                    HookTarget::Session { id } => Some(id),
+                    HookTarget::Queue { .. } => None,
+                },
+                serde_json::json!({ "hook": hook.name, "bytes": body.len() }),
+            );
+            let mut body = serde_json::json!({ "accepted": true, "hook": hook.name });
+            body.as_object_mut().expect("object literal").extend(detail);
+            (202, body)
+        }
+        Err(rejection) => rejection,
+    }
+}
+
+/// Hand a Telegram update to the bot; Telegram only needs to hear that it arrived
+async fn telegram_update(
+    State(state): State<Arc<RuntimeState>>,
+    headers: HeaderMap,
+    body: Result<Bytes, BytesRejection>,
+) -> Response {
+    let Some(bot) = state.telegram.clone().filter(|_| state.config.tools.telegram_webhook_url.is_some()) else {
+        return respond((404, error_body("Not found")));
+    };
+    if web::header(&headers, SECRET_TOKEN_HEADER) != Some(bot.connector.webhook_secret().as_str()) {
+        return respond((401, error_body("Invalid secret token")));
+    }
+    let body = match body {
+        Ok(body) => body,
+        Err(rejection) => return respond(rejected(rejection)),
+    };
+    respond(match serde_json::from_slice(&body) {
+        Ok(update) => {
+            tokio::spawn(telegram::handle_update(state, bot, update));
+            (200, serde_json::json!({ "accepted": true }))
+        }
+        Err(e) => (400, error_body(&format!("Invalid update: {}", e))),
+    })
+}
+
+fn authenticated(hook: &InboundHook, headers: &HeaderMap, body: &[u8]) -> bool {
+    let bearer = web::header(headers, header::AUTHORIZATION).and_then(|value| value.strip_prefix("Bearer "));
+    hook.authenticate(bearer, web::header(headers, SIGNATURE_HEADER), body)
+}
+
+async fn deliver_to_session(
+    state: &RuntimeState,
+    hook: &InboundHook,
+    session_id: Uuid,
+    body: &[u8],
+) -> Result<serde_json::Map<String, serde_json::Value>, Rejection> {
+    let payload = String::from_utf8(body.to_vec()).map_err(|_| (400, error_body("Payload must be UTF-8")))?;
+    let mut message = Message::user(format!("Webhook '{}' delivered:\n{}", hook.name, payload));
+    message.metadata = serde_json::json!({ "webhook": hook.name });
+    state
+        .session_store
+        .append(session_id, &[message], None)
+        .await
+        .map_err(|e| (500, error_body(&e.to_string())))?;
+    Ok(serde_json::Map::from_iter([("session_id".to_string(), session_id.to_string().into())]))
+}
+
+async fn queue_job(
+    state: &RuntimeState,
+    hook: &InboundHook,
+    connector: &str,
+    body: &[u8],
+) -> Result<serde_json::Map<String, serde_json::Value>, Rejection> {
+    let params = job_params(body)?;
+    let now = Utc::now();
+    let task = ScheduledTask {
+        id: Uuid::new_v4(),
+        name: format!("hook:{}", hook.name),
+        kind: TaskKind::Connector,
+        connector_id: connector.to_string(),
+        params,
+        schedule: Schedule::OneTime { when: now },
+        enabled: true,
+        last_run: None,
+        next_run: now,
+    };
+    let id = task.id;
+    state.scheduler.lock().await.add_task(task);
+    Ok(serde_json::Map::from_iter([("task_id".to_string(), id.to_string().into())]))
+}
+
+/// A JSON object's fields as connector parameters; anything else is passed
+/// whole as `payload`
+fn job_params(body: &[u8]) -> Result<HashMap<String, String>, Rejection> {
+    if body.is_empty() {
+        return Ok(HashMap::new());
+    }
+    match serde_json::from_slice(body) {
+        Ok(serde_json::Value::Object(fields)) => Ok(fields
+            .into_iter()
+            // Confirmation is for people, not payloads
+            .filter(|(key, _)| key != "confirmed")
+            .map(|(key, value)| {
+                let value = match value {
+                    serde_json::Value::String(s) => s,
+                    other => other.to_string(),
+                };
+                (key, value)
+            })
+            .collect()),
+        _ => {
+            let payload = String::from_utf8(body.to_vec()).map_err(|_| (400, error_body("Payload must be UTF-8")))?;
+            Ok(HashMap::from([("payload".to_string(), payload)]))
+        }
+    }
+}
+
+fn error_body(message: &str) -> serde_json::Value {
+    serde_json::json!({ "accepted": false, "error": message })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_job_params() {
+        let params = job_params(br#"{"action": "search", "limit": 3, "confirmed": true}"#).unwrap();
+        assert_eq!(params["action"], "search");
+        assert_eq!(params["limit"], "3");
+        assert!(!params.contains_key("confirmed"));
+
+        assert_eq!(job_params(b"plain text").unwrap()["payload"], "plain text");
+        assert!(job_params(b"").unwrap().is_empty());
+    }
+}
@@ -0,0 +1,235 @@
+//! Workspaces
+//!
+//! A workspace is a project a session works in: a root directory, names
+//! file tools must stay out of, the memory namespace its files are indexed
+//! under and the connectors the project needs. They live as
+//! `<workspace_dir>/<name>.json` and are managed with `jamey workspace`.
+//! While a session is attached to one (`jamey chat --workspace app`), file
+//! tools resolve paths against its root and memory recall only draws on the
+//! workspace's namespace and memories that belong to no namespace.
+//!
+//! The namespace defaults to the one `jamey watch` indexes the root under,
+//! so watching a workspace's root (with the same `--ignore` names) keeps
+//! its memories current.
+
+use jamey_core::memory::Memory;
+use jamey_tools::connector::{ToolPolicy, WorkspaceScope};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum WorkspaceError {
+    #[error("Workspace not found: {0}")]
+    NotFound(String),
+    #[error("Invalid workspace name: {0:?}")]
+    InvalidName(String),
+    #[error("Not a directory: {0}")]
+    NotADirectory(PathBuf),
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Serialization error: {0}")]
+    Serialization(#[from] serde_json::Error),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Workspace {
+    pub name: String,
+    /// Canonical root directory
+    pub root: PathBuf,
+    /// Directory or file names file tools may not touch anywhere below the
+    /// root
+    #[serde(default)]
+    pub ignore: Vec<String>,
+    /// Memory namespace the project's files are indexed under
+    pub namespace: String,
+    /// Connector IDs the workspace allows; empty leaves the session's own
+    /// policy as it is
+    #[serde(default)]
+    pub tools: Vec<String>,
+}
+
+impl Workspace {
+    /// A workspace rooted at the directory `root`
+    pub async fn open(name: &str, root: &Path) -> Result<Self, WorkspaceError> {
+        let root = tokio::fs::canonicalize(root).await?;
+        if !root.is_dir() {
+            return Err(WorkspaceError::NotADirectory(root));
+        }
+        let dir_name = root
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "root".to_string());
+        Ok(Self {
+            name: name.to_string(),
+            namespace: format!("project:{}", dir_name),
+            root,
+            ignore: Vec::new(),
+            tools: Vec::new(),
+        })
+    }
+
+    /// What connectors are told about the workspace
+    pub fn scope(&self) -> WorkspaceScope {
+        WorkspaceScope {
+            root: self.root.clone(),
+            ignore: self.ignore.clone(),
+        }
+    }
+
+    /// `policy` narrowed to the workspace's tools
+    pub fn tool_policy(&self, policy: ToolPolicy) -> ToolPolicy {
+        policy.narrowed(&self.tools)
+    }
+
+    /// Whether `memory` may be recalled in the workspace: it is indexed
+    /// under the workspace's namespace or under none at all
+    pub fn includes(&self, memory: &Memory) -> bool {
+        match memory.metadata.get("namespace").and_then(|n| n.as_str()) {
+            Some(namespace) => namespace == self.namespace,
+            None => true,
+        }
+    }
+
+    /// A system prompt line telling the model where it is working
+    pub fn prompt(&self) -> String {
+        format!(
+            "You are working in the {} workspace at {}. File paths given to tools are relative to that directory.",
+            self.name,
+            self.root.display()
+        )
+    }
+}
+
+pub(crate) fn default_workspace_dir() -> PathBuf {
+    std::env::var("JAMEY_WORKSPACE_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from("./workspaces"))
+}
+
+/// Directory of saved workspaces
+#[derive(Debug, Clone)]
+pub struct WorkspaceStore {
+    dir: PathBuf,
+}
+
+impl WorkspaceStore {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Workspace names become file names, so only plain ones are accepted
+    fn path(&self, name: &str) -> Result<PathBuf, WorkspaceError> {
+        let valid = !name.is_empty()
+            && name.len() <= 64
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_'));
+        if !valid {
+            return Err(WorkspaceError::InvalidName(name.to_string()));
+        }
+        Ok(self.dir.join(format!("{}.json", name)))
+    }
+
+    /// Saved workspaces by name
+    pub async fn list(&self) -> Result<Vec<Workspace>, WorkspaceError> {
+        let mut workspaces = Vec::new();
+        let mut entries = match tokio::fs::read_dir(&self.dir).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(workspaces),
+            Err(e) => return Err(e.into()),
+        };
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("json") {
+                continue;
+            }
+            match serde_json::from_slice(&tokio::fs::read(&path).await?) {
+                Ok(workspace) => workspaces.push(workspace),
+                Err(e) => tracing::warn!("Skipping unreadable workspace {}: {}", path.display(), e),
+            }
+        }
+        workspaces.sort_by(|a: &Workspace, b| a.name.cmp(&b.name));
+        Ok(workspaces)
+    }
+
+    pub async fn get(&self, name: &str) -> Result<Workspace, WorkspaceError> {
+        match tokio::fs::read(self.path(name)?).await {
+            Ok(bytes) => Ok(serde_json::from_slice(&bytes)?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Err(WorkspaceError::NotFound(name.to_string())),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Create `workspace`, or replace the one with its name
+    pub async fn save(&self, workspace: &Workspace) -> Result<(), WorkspaceError> {
+        let path = self.path(&workspace.name)?;
+        tokio::fs::create_dir_all(&self.dir).await?;
+        let tmp = path.with_extension("json.tmp");
+        tokio::fs::write(&tmp, serde_json::to_vec_pretty(workspace)?).await?;
+        tokio::fs::rename(&tmp, &path).await?;
+        Ok(())
+    }
+
+    /// Forget a workspace; its files and memories are left alone
+    pub async fn delete(&self, name: &str) -> Result<(), WorkspaceError> {
+        match tokio::fs::remove_file(self.path(name)?).await {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Err(WorkspaceError::NotFound(name.to_string())),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_workspace_store_and_scope() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = dir.path().join("app");
+        std::fs::create_dir(&project).unwrap();
+        let store = WorkspaceStore::new(dir.path().join("workspaces"));
+        assert!(store.list().await.unwrap().is_empty());
+
+        let mut workspace = Workspace::open("app", &project).await.unwrap();
+        assert_eq!(workspace.namespace, "project:app");
+        assert!(matches!(
+            Workspace::open("x", &project.join("missing")).await,
+            Err(WorkspaceError::Io(_))
+        ));
+        workspace.ignore = vec!["secrets".to_string()];
+        workspace.tools = vec!["full_system".to_string()];
+        store.save(&workspace).await.unwrap();
+        assert_eq!(store.get("app").await.unwrap(), workspace);
+        assert!(matches!(store.get("../app").await, Err(WorkspaceError::InvalidName(_))));
+
+        let policy = workspace.tool_policy(ToolPolicy::unrestricted());
+        assert_eq!(policy.allowed, ["full_system"]);
+        assert!(workspace.scope().is_ignored(&workspace.root.join("secrets/key")));
+
+        let memory = |namespace: Option<&str>| Memory {
+            id: uuid::Uuid::new_v4(),
+            memory_type: jamey_core::memory::MemoryType::Knowledge,
+            content: "excerpt".to_string(),
+            embedding: vec![0.0; 4],
+            metadata: match namespace {
+                Some(namespace) => serde_json::json!({ "namespace": namespace }),
+                None => serde_json::json!({}),
+            },
+            created_at: chrono::Utc::now(),
+            last_accessed: chrono::Utc::now(),
+        };
+        assert!(workspace.includes(&memory(Some("project:app"))));
+        assert!(workspace.includes(&memory(None)));
+        assert!(!workspace.includes(&memory(Some("project:other"))));
+
+        store.delete("app").await.unwrap();
+        assert!(matches!(store.delete("app").await, Err(WorkspaceError::NotFound(_))));
+    }
+}
@@ -0,0 +1,244 @@
+// Browser chat for the Jamey runtime. Talks to /ws: a "hello" picks the
+// session, then each "message" streams back tokens, tool activity and the
+// completed reply. When the socket can't connect (some proxies block
+// WebSocket upgrades), the same messages come from the session's event
+// stream instead, and messages are POSTed. The session ID and access key are
+// kept in localStorage.
+"use strict";
+
+const messages = document.getElementById("messages");
+const input = document.getElementById("input");
+const send = document.getElementById("send");
+const status = document.getElementById("status");
+const title = document.getElementById("title");
+
+// Notes for replies that came from a fallback
+const DEGRADED = {
+  smaller_model: "Answered by a fallback model",
+  cached: "Repeated from an earlier answer; the model is unavailable",
+  canned: "The model is unavailable",
+};
+
+let socket = null;
+let stream = null;
+let useStream = false;
+let reply = null;
+let busy = false;
+
+function add(kind, text) {
+  const element = document.createElement("div");
+  element.className = "message " + kind;
+  element.textContent = text;
+  messages.appendChild(element);
+  messages.scrollTop = messages.scrollHeight;
+  return element;
+}
+
+function setReady(ready) {
+  input.disabled = !ready;
+  send.disabled = !ready || busy;
+  if (ready) input.focus();
+}
+
+function newSessionId() {
+  if (crypto.randomUUID) return crypto.randomUUID();
+  const bytes = crypto.getRandomValues(new Uint8Array(16));
+  bytes[6] = (bytes[6] & 0x0f) | 0x40;
+  bytes[8] = (bytes[8] & 0x3f) | 0x80;
+  const hex = Array.from(bytes, (b) => b.toString(16).padStart(2, "0")).join("");
+  return [hex.slice(0, 8), hex.slice(8, 12), hex.slice(12, 16), hex.slice(16, 20), hex.slice(20)].join("-");
+}
+
+function connected() {
+  return useStream ? stream !== null : socket && socket.readyState === WebSocket.OPEN;
+}
+
+function connect() {
+  if (useStream) {
+    connectStream();
+    return;
+  }
+  const scheme = location.protocol === "https:" ? "wss:" : "ws:";
+  socket = new WebSocket(scheme + "//" + location.host + "/ws");
+  status.textContent = "Connecting…";
+  let opened = false;
+
+  socket.onopen = () => {
+    opened = true;
+    socket.send(JSON.stringify({
+      type: "hello",
+      session: localStorage.getItem("jamey.session"),
+      token: localStorage.getItem("jamey.token"),
+    }));
+  };
+
+  socket.onmessage = (event) => handle(JSON.parse(event.data));
+
+  socket.onclose = () => {
+    if (!opened) {
+      useStream = true;
+      connectStream();
+      return;
+    }
+    status.textContent = "Disconnected";
+    setReady(false);
+  };
+}
+
+function streamUrl(id) {
+  const token = localStorage.getItem("jamey.token");
+  return "/sessions/" + id + "/stream" + (token ? "?token=" + encodeURIComponent(token) : "");
+}
+
+function connectStream() {
+  let id = localStorage.getItem("jamey.session");
+  if (!id) {
+    id = newSessionId();
+    localStorage.setItem("jamey.session", id);
+  }
+  const url = streamUrl(id);
+  const source = new EventSource(url);
+  stream = source;
+  status.textContent = "Connecting…";
+  let received = false;
+
+  source.onmessage = (event) => {
+    received = true;
+    handle(JSON.parse(event.data));
+  };
+
+  source.onerror = () => {
+    if (received) {
+      // EventSource reconnects by itself
+      status.textContent = "Reconnecting…";
+      return;
+    }
+    source.close();
+    stream = null;
+    setReady(false);
+    // EventSource doesn't say why it failed; ask again to find out
+    fetch(url).then((response) => {
+      if (response.body) response.body.cancel();
+      if (response.status === 401) handle({ type: "unauthorized" });
+      else status.textContent = "Disconnected";
+    }, () => {
+      status.textContent = "Disconnected";
+    });
+  };
+}
+
+function sendMessage(content) {
+  if (!useStream) {
+    socket.send(JSON.stringify({ type: "message", content }));
+    return;
+  }
+  const headers = { "Content-Type": "application/json" };
+  const token = localStorage.getItem("jamey.token");
+  if (token) headers.Authorization = "Bearer " + token;
+  const id = localStorage.getItem("jamey.session");
+  fetch("/sessions/" + id + "/messages", { method: "POST", headers, body: JSON.stringify({ content }) })
+    .then((response) => response.ok ? null : response.json().then(handle))
+    .catch((error) => handle({ type: "error", message: String(error) }));
+}
+
+function handle(event) {
+  switch (event.type) {
+    case "session":
+      localStorage.setItem("jamey.session", event.id);
+      title.textContent = event.title;
+      messages.replaceChildren();
+      for (const message of event.messages) add(message.role, message.content);
+      status.textContent = "Connected";
+      setReady(true);
+      break;
+    case "unauthorized": {
+      const token = prompt("Access key (security.api_key):");
+      if (token) {
+        localStorage.setItem("jamey.token", token);
+        connect();
+      } else {
+        status.textContent = "Access key required";
+      }
+      break;
+    }
+    case "token":
+      if (!reply) reply = add("assistant", "");
+      reply.textContent += event.text;
+      messages.scrollTop = messages.scrollHeight;
+      break;
+    case "tool_call":
+      add("system", "Running " + event.name + "…");
+      break;
+    case "tool_result":
+      if (event.error) add("error", event.name + " failed: " + event.error);
+      break;
+    case "approval":
+      add("system", "Waiting for approval " + event.id.slice(0, 8) +
+        " (run `jamey approvals approve " + event.id.slice(0, 8) + "`)");
+      break;
+    case "completed":
+      if (!reply) reply = add("assistant", "");
+      reply.textContent = event.content;
+      if (event.citations && event.citations.length) {
+        const notes = document.createElement("div");
+        notes.className = "citations";
+        notes.textContent = event.citations
+          .map((c, i) => "[" + (i + 1) + "] " + c.snippet)
+          .join("\n");
+        reply.appendChild(notes);
+      }
+      if (event.degradation) {
+        const note = document.createElement("div");
+        note.className = "degraded";
+        note.textContent = DEGRADED[event.degradation] || "Degraded reply";
+        reply.appendChild(note);
+      }
+      if (event.title) title.textContent = event.title;
+      finishTurn();
+      break;
+    case "error":
+      add("error", event.message);
+      finishTurn();
+      break;
+  }
+}
+
+function finishTurn() {
+  reply = null;
+  busy = false;
+  setReady(connected());
+}
+
+document.getElementById("composer").addEventListener("submit", (event) => {
+  event.preventDefault();
+  const content = input.value.trim();
+  if (!content || busy) return;
+  add("user", content);
+  sendMessage(content);
+  input.value = "";
+  busy = true;
+  setReady(true);
+});
+
+input.addEventListener("keydown", (event) => {
+  if (event.key === "Enter" && !event.shiftKey) {
+    event.preventDefault();
+    document.getElementById("composer").requestSubmit();
+  }
+});
+
+document.getElementById("new-chat").addEventListener("click", () => {
+  localStorage.removeItem("jamey.session");
+  if (socket) {
+    socket.onclose = null;
+    socket.close();
+  }
+  if (stream) {
+    stream.close();
+    stream = null;
+  }
+  finishTurn();
+  connect();
+});
+
+connect();
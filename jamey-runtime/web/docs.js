@@ -0,0 +1,66 @@
+// Renders /openapi.json for reading; the JSON itself is what tools should use.
+"use strict";
+
+const docs = document.getElementById("docs");
+
+function element(tag, className, text) {
+  const node = document.createElement(tag);
+  if (className) node.className = className;
+  if (text !== undefined) node.textContent = text;
+  return node;
+}
+
+function block(value) {
+  return element("pre", null, JSON.stringify(value, null, 2));
+}
+
+function renderOperation(path, method, operation, servers) {
+  const section = element("section", "operation");
+  section.appendChild(element("h3", null, method.toUpperCase() + " " + path));
+  if (operation.summary) section.appendChild(element("p", "summary", operation.summary));
+  if (operation.description) section.appendChild(element("p", null, operation.description));
+  section.appendChild(element("p", "server", "Served at " + servers.map((s) => s.url).join(", ")));
+  if (operation.parameters) {
+    section.appendChild(element("h4", null, "Parameters"));
+    const list = element("ul");
+    for (const p of operation.parameters) {
+      list.appendChild(element("li", null, p.name + " (" + p.in + (p.required ? ", required" : "") + ")"));
+    }
+    section.appendChild(list);
+  }
+  if (operation.requestBody) {
+    section.appendChild(element("h4", null, "Body"));
+    section.appendChild(block(operation.requestBody.content));
+  }
+  section.appendChild(element("h4", null, "Responses"));
+  const list = element("ul");
+  for (const [status, response] of Object.entries(operation.responses)) {
+    const ref = response.$ref ? response.$ref.split("/").pop() : response.description;
+    list.appendChild(element("li", null, status + ": " + ref));
+  }
+  section.appendChild(list);
+  return section;
+}
+
+function render(spec) {
+  document.getElementById("title").textContent = spec.info.version;
+  docs.appendChild(element("p", null, spec.info.description));
+  for (const [path, item] of Object.entries(spec.paths)) {
+    for (const [method, operation] of Object.entries(item)) {
+      if (method === "servers") continue;
+      docs.appendChild(renderOperation(path, method, operation, item.servers || spec.servers));
+    }
+  }
+  docs.appendChild(element("h2", null, "Schemas"));
+  for (const [name, schema] of Object.entries(spec.components.schemas)) {
+    const section = element("section", "operation");
+    section.appendChild(element("h3", null, name));
+    section.appendChild(block(schema));
+    docs.appendChild(section);
+  }
+}
+
+fetch("/openapi.json")
+  .then((response) => response.json())
+  .then(render)
+  .catch((error) => docs.appendChild(element("p", "message error", "Could not load the API description: " + error)));
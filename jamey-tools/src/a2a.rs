@@ -0,0 +1,172 @@
+//! A2A client
+//!
+//! Talks to agents that speak the [A2A task protocol](jamey_protocol::a2a):
+//! fetches their card, sends messages as tasks and follows a task's status
+//! and artifacts as they stream in. The orchestration connector uses it
+//! for agents whose capability handshake found an A2A card.
+
+use anyhow::{Context, Result};
+use jamey_core::secure_logging::Masked;
+use jamey_protocol::a2a::{
+    self, A2aMessage, AgentCard, Event, JsonRpcRequest, JsonRpcResponse, MessageSendParams, Task, TaskQueryParams,
+};
+use reqwest::{Client, StatusCode};
+
+/// Card published by the agent at `base_url`, trying the pre-0.3 location
+/// when the current one is missing
+pub async fn fetch_card(client: &Client, base_url: &str, api_key: &str) -> Result<AgentCard> {
+    let base = base_url.trim_end_matches('/');
+    for path in [a2a::AGENT_CARD_PATH, a2a::LEGACY_AGENT_CARD_PATH] {
+        let response = client
+            .get(format!("{}{}", base, path))
+            .bearer_auth(api_key)
+            .send()
+            .await
+            .context("Failed to fetch the agent card")?;
+        if response.status() == StatusCode::NOT_FOUND {
+            continue;
+        }
+        if !response.status().is_success() {
+            anyhow::bail!("Agent returned error status: {}", response.status());
+        }
+        return response.json().await.context("Agent sent an unreadable agent card");
+    }
+    anyhow::bail!("Agent at {} publishes no A2A card", base)
+}
+
+/// JSON-RPC connection to one A2A agent
+#[derive(Clone)]
+pub struct A2aClient {
+    client: Client,
+    endpoint: String,
+    api_key: String,
+}
+
+impl std::fmt::Debug for A2aClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("A2aClient")
+            .field("endpoint", &self.endpoint)
+            .field("api_key", &Masked::of(&self.api_key))
+            .finish_non_exhaustive()
+    }
+}
+
+impl A2aClient {
+    /// Client for the JSON-RPC `endpoint` from an agent's card
+    pub fn new(client: Client, endpoint: impl Into<String>, api_key: impl Into<String>) -> Self {
+        Self {
+            client,
+            endpoint: endpoint.into(),
+            api_key: api_key.into(),
+        }
+    }
+
+    async fn call(&self, method: &str, params: impl serde::Serialize) -> Result<reqwest::Response> {
+        let response = self
+            .client
+            .post(&self.endpoint)
+            .bearer_auth(&self.api_key)
+            .json(&JsonRpcRequest::new(method, params))
+            .send()
+            .await
+            .with_context(|| format!("Failed to call {} on the agent", method))?;
+        if !response.status().is_success() {
+            anyhow::bail!("Agent returned error status: {}", response.status());
+        }
+        Ok(response)
+    }
+
+    async fn event(&self, method: &str, params: impl serde::Serialize) -> Result<Event> {
+        let response: JsonRpcResponse = self
+            .call(method, params)
+            .await?
+            .json()
+            .await
+            .context("Agent sent an unreadable reply")?;
+        response
+            .into_event()
+            .map_err(|e| anyhow::anyhow!("Agent refused {} ({}): {}", method, e.code, e.message))
+    }
+
+    /// Send `message`; agents answer with a task or, for quick replies, a
+    /// message
+    pub async fn send(&self, message: A2aMessage) -> Result<Event> {
+        self.event(a2a::METHOD_SEND, MessageSendParams { message }).await
+    }
+
+    /// Send `message` and follow the task it starts until the stream ends,
+    /// handing each event to `on_event` as it arrives
+    pub async fn stream(&self, message: A2aMessage, mut on_event: impl FnMut(&Event)) -> Result<Task> {
+        let mut response = self.call(a2a::METHOD_STREAM, MessageSendParams { message }).await?;
+        let mut task: Option<Task> = None;
+        let mut buffer: Vec<u8> = Vec::new();
+        let mut finished = false;
+        while !finished {
+            let Some(pos) = buffer.iter().position(|&b| b == b'\n') else {
+                match response.chunk().await.context("The agent's stream broke off")? {
+                    Some(bytes) => buffer.extend_from_slice(&bytes),
+                    None if buffer.is_empty() => break,
+                    // Flush a final line that was not newline-terminated
+                    None => buffer.push(b'\n'),
+                }
+                continue;
+            };
+            let line: Vec<u8> = buffer.drain(..=pos).collect();
+            let line = String::from_utf8_lossy(&line);
+            // Blank separators and keepalive comments carry no data
+            let Some(data) = line.trim().strip_prefix("data:") else {
+                continue;
+            };
+            let response: JsonRpcResponse =
+                serde_json::from_str(data.trim()).context("Agent sent an unreadable stream event")?;
+            let event = response
+                .into_event()
+                .map_err(|e| anyhow::anyhow!("Agent failed the task ({}): {}", e.code, e.message))?;
+            on_event(&event);
+            finished = match &event {
+                Event::StatusUpdate(update) => update.is_final,
+                Event::Task(task) => task.status.state.is_terminal(),
+                _ => false,
+            };
+            if let Some(task) = task.as_mut() {
+                task.apply(event);
+                continue;
+            }
+            match event {
+                Event::Task(first) => task = Some(first),
+                Event::Message(reply) => {
+                    // A plain reply ends the exchange without a task
+                    let id = reply.task_id.clone().unwrap_or_default();
+                    let context_id = reply.context_id.clone().unwrap_or_default();
+                    let mut done = Task {
+                        id,
+                        context_id,
+                        status: a2a::TaskStatus::now(a2a::TaskState::Completed),
+                        artifacts: Vec::new(),
+                        history: Vec::new(),
+                    };
+                    done.apply(Event::Message(reply));
+                    return Ok(done);
+                }
+                _ => anyhow::bail!("Agent streamed updates before the task they belong to"),
+            }
+        }
+        task.ok_or_else(|| anyhow::anyhow!("Agent closed the stream without starting a task"))
+    }
+
+    pub async fn get_task(&self, id: &str) -> Result<Task> {
+        let params = TaskQueryParams { id: id.to_string(), history_length: None };
+        match self.event(a2a::METHOD_GET_TASK, params).await? {
+            Event::Task(task) => Ok(task),
+            other => anyhow::bail!("Agent answered tasks/get with {:?}", other),
+        }
+    }
+
+    pub async fn cancel_task(&self, id: &str) -> Result<Task> {
+        let params = TaskQueryParams { id: id.to_string(), history_length: None };
+        match self.event(a2a::METHOD_CANCEL_TASK, params).await? {
+            Event::Task(task) => Ok(task),
+            other => anyhow::bail!("Agent answered tasks/cancel with {:?}", other),
+        }
+    }
+}
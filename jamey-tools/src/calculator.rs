@@ -0,0 +1,105 @@
+//! Arithmetic, unit and date calculations
+//!
+//! [`CalculatorTool`] evaluates queries with [rink](https://rinkcalc.app),
+//! so numbers the model reports come from exact rational arithmetic rather
+//! than from the model itself. Queries use rink's syntax:
+//!
+//! - `2^10 / 3` and `sqrt(2) * pi`
+//! - `60 mph -> km/h` and `5 feet + 11 inches -> cm`
+//! - `#2024-03-01# - #2023-12-25# -> days` and `now + 90 days`
+//!
+//! Currencies need live exchange rates and are not loaded.
+
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use thiserror::Error;
+
+/// Longest query accepted
+pub const MAX_QUERY_LEN: usize = 1000;
+
+#[derive(Debug, Error)]
+pub enum CalculatorError {
+    #[error("Empty query")]
+    Empty,
+    #[error("Query is {0} characters (limit {MAX_QUERY_LEN})")]
+    TooLong(usize),
+    #[error("{0}")]
+    Query(String),
+    #[error("Failed to load unit definitions: {0}")]
+    Definitions(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Calculation {
+    pub query: String,
+    /// Rink's answer, e.g. `8.04672 kilometer (length)` for `5 miles -> km`
+    pub result: String,
+}
+
+thread_local! {
+    // Loading the definitions takes tens of milliseconds, and a context
+    // can't move between threads, so each blocking thread keeps its own
+    static CONTEXT: RefCell<Option<rink_core::Context>> = const { RefCell::new(None) };
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CalculatorTool;
+
+impl CalculatorTool {
+    pub fn new() -> Self {
+        Self
+    }
+
+    pub async fn evaluate(&self, query: &str) -> Result<Calculation, CalculatorError> {
+        let query = query.to_string();
+        tokio::task::spawn_blocking(move || Self::evaluate_blocking(&query))
+            .await
+            .map_err(|e| CalculatorError::Query(format!("Calculation failed: {}", e)))?
+    }
+
+    pub fn evaluate_blocking(query: &str) -> Result<Calculation, CalculatorError> {
+        let query = query.trim();
+        if query.is_empty() {
+            return Err(CalculatorError::Empty);
+        }
+        if query.chars().count() > MAX_QUERY_LEN {
+            return Err(CalculatorError::TooLong(query.chars().count()));
+        }
+        CONTEXT.with(|cell| {
+            let mut cell = cell.borrow_mut();
+            if cell.is_none() {
+                *cell = Some(rink_core::simple_context().map_err(CalculatorError::Definitions)?);
+            }
+            let context = cell.as_mut().expect("context was just loaded");
+            let result = rink_core::one_line(context, query).map_err(CalculatorError::Query)?;
+            Ok(Calculation {
+                query: query.to_string(),
+                result,
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval(query: &str) -> String {
+        CalculatorTool::evaluate_blocking(query).unwrap().result
+    }
+
+    #[test]
+    fn test_arithmetic_units_and_dates() {
+        assert_eq!(eval("2 + 3 * 4"), "14 (dimensionless)");
+        assert!(eval("1/3 + 1/6").contains("0.5"));
+        assert_eq!(eval("5 miles -> km"), "8.04672 kilometer (length)");
+        assert!(eval("#2024-03-01# - #2024-02-01# -> days").contains("29 day"));
+
+        assert!(CalculatorTool::evaluate_blocking("3 meters + 2 seconds").is_err());
+        assert!(matches!(CalculatorTool::evaluate_blocking("  "), Err(CalculatorError::Empty)));
+        assert!(matches!(
+            CalculatorTool::evaluate_blocking(&"1+".repeat(MAX_QUERY_LEN)),
+            Err(CalculatorError::TooLong(_))
+        ));
+    }
+}
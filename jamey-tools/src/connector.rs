@@ -6,10 +6,11 @@
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use chrono::{DateTime, Utc};
+use crate::timeouts::{PartialOutput, ToolTimeoutConfig};
 
 /// Connector capability levels for full access
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -25,6 +26,129 @@ pub enum CapabilityLevel {
     FullAccess,
 }
 
+impl CapabilityLevel {
+    pub const ALL: [CapabilityLevel; 9] = [
+        CapabilityLevel::ReadOnly,
+        CapabilityLevel::ReadWrite,
+        CapabilityLevel::WebAccess,
+        CapabilityLevel::NetworkAccess,
+        CapabilityLevel::CloudAccess,
+        CapabilityLevel::AgentOrchestration,
+        CapabilityLevel::SystemAdmin,
+        CapabilityLevel::SelfModify,
+        CapabilityLevel::FullAccess,
+    ];
+
+    /// Position in [`ALL`](Self::ALL), from least to most reach
+    pub fn rank(self) -> usize {
+        Self::ALL.iter().position(|level| *level == self).unwrap_or(usize::MAX)
+    }
+
+    /// Whether a session capped at `self` may use a connector at `level`
+    pub fn permits(self, level: CapabilityLevel) -> bool {
+        level.rank() <= self.rank()
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CapabilityLevel::ReadOnly => "read_only",
+            CapabilityLevel::ReadWrite => "read_write",
+            CapabilityLevel::SystemAdmin => "system_admin",
+            CapabilityLevel::SelfModify => "self_modify",
+            CapabilityLevel::NetworkAccess => "network_access",
+            CapabilityLevel::WebAccess => "web_access",
+            CapabilityLevel::CloudAccess => "cloud_access",
+            CapabilityLevel::AgentOrchestration => "agent_orchestration",
+            CapabilityLevel::FullAccess => "full_access",
+        }
+    }
+}
+
+impl std::fmt::Display for CapabilityLevel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for CapabilityLevel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|level| level.as_str() == s)
+            .ok_or_else(|| {
+                let names: Vec<&str> = Self::ALL.iter().map(|level| level.as_str()).collect();
+                format!("Unknown capability level '{}' (expected one of {})", s, names.join(", "))
+            })
+    }
+}
+
+/// Which connectors a session may see and call
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ToolPolicy {
+    /// Connector IDs the session may use; empty allows every connector
+    pub allowed: Vec<String>,
+    /// Connectors above this level are hidden and refused
+    pub max_capability: CapabilityLevel,
+}
+
+impl ToolPolicy {
+    /// A policy that restricts nothing
+    pub fn unrestricted() -> Self {
+        Self {
+            allowed: Vec::new(),
+            max_capability: CapabilityLevel::FullAccess,
+        }
+    }
+
+    pub fn permits(&self, metadata: &ConnectorMetadata) -> bool {
+        self.max_capability.permits(metadata.capability_level)
+            && (self.allowed.is_empty() || self.allowed.contains(&metadata.id))
+    }
+
+    /// This policy limited to `tools`, for narrower scopes within a
+    /// session. Narrowing can take connectors away but never grant ones the
+    /// policy refuses; an empty `tools` leaves it as it is.
+    pub fn narrowed(mut self, tools: &[String]) -> Self {
+        if tools.is_empty() {
+            return self;
+        }
+        self.allowed = if self.allowed.is_empty() {
+            tools.to_vec()
+        } else {
+            self.allowed.into_iter().filter(|id| tools.contains(id)).collect()
+        };
+        if self.allowed.is_empty() {
+            // Nothing left in common; an empty list would allow everything
+            self.allowed.push(String::new());
+        }
+        self
+    }
+
+    /// `Err` explains why `metadata` is off limits
+    pub fn check(&self, metadata: &ConnectorMetadata) -> Result<()> {
+        if !self.max_capability.permits(metadata.capability_level) {
+            return Err(anyhow::anyhow!(
+                "Connector {} needs {} but this session is limited to {}",
+                metadata.id,
+                metadata.capability_level,
+                self.max_capability
+            ));
+        }
+        if !self.permits(metadata) {
+            return Err(anyhow::anyhow!("Connector {} is not enabled for this session", metadata.id));
+        }
+        Ok(())
+    }
+}
+
+impl Default for ToolPolicy {
+    fn default() -> Self {
+        Self::unrestricted()
+    }
+}
+
 /// Connector metadata
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ConnectorMetadata {
@@ -46,6 +170,34 @@ pub struct ExecutionContext {
     pub file_system_root: PathBuf,
     pub allowed_hosts: Vec<String>, // Empty = all hosts allowed
     pub credentials: HashMap<String, String>, // Encrypted credentials
+    /// Connectors this execution may reach; enforced by the registry
+    pub tool_policy: ToolPolicy,
+    /// Project the execution is confined to; file connectors resolve paths
+    /// against its root instead of their own
+    pub workspace: Option<WorkspaceScope>,
+    /// Where long-running connectors record progress; reported in place of
+    /// the result when the call times out
+    pub partial_output: PartialOutput,
+}
+
+/// What connectors see of the workspace a session is attached to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorkspaceScope {
+    /// Canonical root directory
+    pub root: PathBuf,
+    /// Directory or file names that are off limits anywhere below the root
+    pub ignore: Vec<String>,
+}
+
+impl WorkspaceScope {
+    /// Whether `path`, under the root, passes through an ignored name
+    pub fn is_ignored(&self, path: &Path) -> bool {
+        path.strip_prefix(&self.root).is_ok_and(|relative| {
+            relative
+                .components()
+                .any(|c| self.ignore.iter().any(|i| c.as_os_str() == i.as_str()))
+        })
+    }
 }
 
 impl Default for ExecutionContext {
@@ -57,6 +209,9 @@ impl Default for ExecutionContext {
             file_system_root: PathBuf::from(if cfg!(windows) { "C:\\" } else { "/" }),
             allowed_hosts: Vec::new(), // Empty = all hosts
             credentials: HashMap::new(),
+            tool_policy: ToolPolicy::unrestricted(),
+            workspace: None,
+            partial_output: PartialOutput::new(),
         }
     }
 }
@@ -81,6 +236,12 @@ pub struct ConnectorResult {
     pub network_requests: Vec<NetworkRequest>,
     pub files_accessed: Vec<String>,
     pub agents_contacted: Vec<String>,
+    /// How to undo the side effects of the call, for connectors that can
+    #[serde(default)]
+    pub compensations: Vec<Compensation>,
+    /// Files produced for the user; the runtime turns them into attachments
+    #[serde(default)]
+    pub artifacts: Vec<PathBuf>,
 }
 
 impl ConnectorResult {
@@ -94,10 +255,54 @@ impl ConnectorResult {
             network_requests: Vec::new(),
             files_accessed: Vec::new(),
             agents_contacted: Vec::new(),
+            compensations: Vec::new(),
+            artifacts: Vec::new(),
         }
     }
 }
 
+/// A reversible side effect of a connector call and what the connector
+/// needs to reverse it; handed back to [`Connector::compensate`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Compensation {
+    pub connector_id: String,
+    /// What undoing does, e.g. "Restore /home/jamey/notes.md"
+    pub description: String,
+    /// Connector-specific; `action` names the undo step
+    pub params: HashMap<String, String>,
+}
+
+impl Compensation {
+    pub fn new(connector_id: &str, action: &str, description: impl Into<String>) -> Self {
+        Self {
+            connector_id: connector_id.to_string(),
+            description: description.into(),
+            params: HashMap::from([("action".to_string(), action.to_string())]),
+        }
+    }
+
+    pub fn with_param(mut self, key: &str, value: impl Into<String>) -> Self {
+        self.params.insert(key.to_string(), value.into());
+        self
+    }
+
+    /// Value of `key`, or an error naming the missing parameter
+    pub fn param(&self, key: &str) -> Result<&str> {
+        self.params
+            .get(key)
+            .map(String::as_str)
+            .ok_or_else(|| anyhow::anyhow!("Compensation is missing '{}'", key))
+    }
+}
+
+/// `params[key]` parsed, or `None` when absent
+pub fn parse_param<T: std::str::FromStr>(params: &HashMap<String, String>, key: &str) -> Result<Option<T>> {
+    params
+        .get(key)
+        .map(|value| value.trim().parse().map_err(|_| anyhow::anyhow!("Invalid {}: {}", key, value)))
+        .transpose()
+}
+
 /// Base trait for all connectors
 #[async_trait::async_trait]
 pub trait Connector: Send + Sync {
@@ -117,6 +322,12 @@ pub trait Connector: Send + Sync {
     /// Get required parameters
     fn required_params(&self) -> Vec<String>;
     
+    /// Values accepted by the `action` parameter, for discovery in
+    /// `jamey tool list`; empty when the connector doesn't enumerate them
+    fn actions(&self) -> Vec<String> {
+        Vec::new()
+    }
+    
     /// Check if connector is enabled
     fn is_enabled(&self) -> bool;
     
@@ -128,6 +339,29 @@ pub trait Connector: Send + Sync {
     
     /// Get required credential keys
     fn requires_credentials(&self) -> Vec<String>;
+    
+    /// Apply a rotated credential in place; connectors that cache
+    /// credentials override this so rotation needs no restart
+    fn update_credential(&self, _key: &str, _value: &str) -> Result<()> {
+        Ok(())
+    }
+
+    /// Reverse a side effect this connector reported in
+    /// [`ConnectorResult::compensations`]; connectors with reversible
+    /// actions override this
+    async fn compensate(&self, _compensation: &Compensation, _context: &ExecutionContext) -> Result<()> {
+        Err(anyhow::anyhow!("Connector {} cannot undo its actions", self.metadata().id))
+    }
+}
+
+/// Registry view of a connector with what is needed to call it by hand
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConnectorInfo {
+    pub metadata: ConnectorMetadata,
+    pub actions: Vec<String>,
+    pub required_params: Vec<String>,
+    pub required_credentials: Vec<String>,
+    pub enabled: bool,
 }
 
 /// Connector registry for dynamic registration
@@ -135,6 +369,7 @@ pub struct ConnectorRegistry {
     connectors: Arc<RwLock<HashMap<String, Box<dyn Connector>>>>,
     enabled_connectors: Arc<RwLock<Vec<String>>>,
     locked: Arc<RwLock<bool>>,
+    timeouts: Arc<RwLock<ToolTimeoutConfig>>,
 }
 
 impl ConnectorRegistry {
@@ -143,6 +378,7 @@ impl ConnectorRegistry {
             connectors: Arc::new(RwLock::new(HashMap::new())),
             enabled_connectors: Arc::new(RwLock::new(Vec::new())),
             locked: Arc::new(RwLock::new(false)),
+            timeouts: Arc::new(RwLock::new(ToolTimeoutConfig::default())),
         }
     }
     
@@ -174,6 +410,32 @@ impl ConnectorRegistry {
             .collect()
     }
     
+    /// Connectors `policy` lets a session see
+    pub async fn list_permitted(&self, policy: &ToolPolicy) -> Vec<ConnectorMetadata> {
+        let connectors = self.connectors.read().await;
+        connectors.values()
+            .map(|c| c.metadata())
+            .filter(|meta| policy.permits(meta))
+            .cloned()
+            .collect()
+    }
+    
+    /// Every connector with its actions and parameters, sorted by ID
+    pub async fn describe(&self) -> Vec<ConnectorInfo> {
+        let connectors = self.connectors.read().await;
+        let mut infos: Vec<ConnectorInfo> = connectors.values()
+            .map(|c| ConnectorInfo {
+                metadata: c.metadata().clone(),
+                actions: c.actions(),
+                required_params: c.required_params(),
+                required_credentials: c.requires_credentials(),
+                enabled: c.is_enabled(),
+            })
+            .collect();
+        infos.sort_by(|a, b| a.metadata.id.cmp(&b.metadata.id));
+        infos
+    }
+    
     pub async fn execute_connector(
         &self,
         id: &str,
@@ -184,8 +446,57 @@ impl ConnectorRegistry {
         let connector = connectors.get(id)
             .ok_or_else(|| anyhow::anyhow!("Connector not found: {}", id))?;
         
+        context.tool_policy.check(connector.metadata())?;
         connector.validate(&params)?;
-        connector.execute(params, context).await
+
+        let limit = self.timeouts.read().await.timeout_for(id, params.get("action").map(String::as_str));
+        let Some(limit) = limit else {
+            return connector.execute(params, context).await;
+        };
+        let context = ExecutionContext {
+            partial_output: PartialOutput::new(),
+            ..context.clone()
+        };
+        match tokio::time::timeout(limit, connector.execute(params, &context)).await {
+            Ok(result) => result,
+            Err(_) => {
+                tracing::warn!("Connector {} timed out after {}s", id, limit.as_secs());
+                let mut result = ConnectorResult::new();
+                result.output = context.partial_output.take();
+                result.errors.push(format!("Timed out after {}s", limit.as_secs()));
+                result.metadata.insert("timed_out".to_string(), "true".to_string());
+                Ok(result)
+            }
+        }
+    }
+
+    /// Replace the time limits calls run under
+    pub async fn set_timeouts(&self, timeouts: ToolTimeoutConfig) {
+        *self.timeouts.write().await = timeouts;
+    }
+
+    /// Undo `compensation` through the connector that recorded it
+    pub async fn compensate(&self, compensation: &Compensation, context: &ExecutionContext) -> Result<()> {
+        let connectors = self.connectors.read().await;
+        let connector = connectors.get(&compensation.connector_id)
+            .ok_or_else(|| anyhow::anyhow!("Connector not found: {}", compensation.connector_id))?;
+
+        context.tool_policy.check(connector.metadata())?;
+        connector.compensate(compensation, context).await
+    }
+    
+    /// Push a rotated credential to every connector that requires it,
+    /// returning how many connectors were updated
+    pub async fn propagate_credential(&self, key: &str, value: &str) -> Result<usize> {
+        let connectors = self.connectors.read().await;
+        let mut updated = 0;
+        for connector in connectors.values() {
+            if connector.requires_credentials().iter().any(|k| k == key) {
+                connector.update_credential(key, value)?;
+                updated += 1;
+            }
+        }
+        Ok(updated)
     }
     
     pub async fn lock(&self) {
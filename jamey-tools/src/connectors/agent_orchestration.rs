@@ -1,27 +1,222 @@
 //! Agent-to-Agent Orchestration Connector
 //!
-//! Orchestrates tasks across multiple agents with full communication
+//! Orchestrates tasks across multiple agents with full communication.
+//!
+//! Before routing work to an agent the connector asks it what it offers:
+//! `GET {url}/api/v1/capabilities` returns its protocol version, tools and
+//! capabilities. The answer is cached for [`CAPABILITY_TTL`] and refreshed
+//! by [`AgentOrchestrationConnector::spawn_health_checks`]. `route_task`
+//! sends a task to the healthiest compatible agent offering a capability
+//! and fails over to the next when an agent can't be reached; agents that
+//! keep failing are tried last until a probe succeeds again.
+//!
+//! Agents without the Jamey handshake are asked for an A2A agent card
+//! instead; their skills stand in for capabilities and tasks go to them as
+//! A2A messages, streamed when the agent supports it, with
+//! `get_task`/`cancel_task` to follow up on long-running ones.
 
+use crate::a2a::{self, A2aClient};
 use crate::connector::*;
-use reqwest::{Client, ClientBuilder};
+use chrono::{DateTime, Utc};
+use jamey_core::secure_logging::Masked;
+use jamey_core::supervisor::Supervisor;
+use jamey_protocol::a2a::{A2aMessage, A2aRole, AgentCard, Part, Task, TaskState};
+use reqwest::{Client, ClientBuilder, StatusCode};
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::RwLock;
+use tokio::task::JoinHandle;
 use anyhow::{Result, Context};
 use serde_json::Value;
 
-#[derive(Debug, Clone)]
+/// Agent protocol this connector speaks; agents must share the major version
+pub const PROTOCOL_VERSION: &str = "1.0";
+
+/// How long a capability handshake is trusted before it is repeated
+pub const CAPABILITY_TTL: Duration = Duration::from_secs(10 * 60);
+
+/// Failures in a row after which an agent is only tried as a last resort
+const UNHEALTHY_AFTER: u32 = 3;
+
+#[derive(Clone)]
 pub struct AgentEndpoint {
     pub id: String,
     pub name: String,
     pub url: String,
     pub api_key: String,  // Made required (not optional)
+    /// Declared at registration; used until the agent answers a handshake
     pub capabilities: Vec<String>,
 }
 
+impl std::fmt::Debug for AgentEndpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AgentEndpoint")
+            .field("id", &self.id)
+            .field("name", &self.name)
+            .field("url", &self.url)
+            .field("api_key", &Masked::of(&self.api_key))
+            .field("capabilities", &self.capabilities)
+            .finish()
+    }
+}
+
+/// What an agent said it offers in the capability handshake
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AgentCapabilities {
+    pub protocol_version: String,
+    #[serde(default)]
+    pub tools: Vec<String>,
+    #[serde(default)]
+    pub capabilities: Vec<String>,
+    /// Set when the agent speaks A2A rather than the Jamey protocol
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub a2a: Option<A2aEndpoint>,
+}
+
+/// Where an A2A agent takes tasks, from its agent card
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct A2aEndpoint {
+    pub url: String,
+    pub streaming: bool,
+}
+
+impl AgentCapabilities {
+    /// What an A2A agent's card offers: skill ids and tags stand in for
+    /// tools and capabilities
+    pub fn from_card(card: &AgentCard) -> Self {
+        let mut capabilities: Vec<String> = card.skills.iter().flat_map(|skill| skill.tags.iter().cloned()).collect();
+        capabilities.sort();
+        capabilities.dedup();
+        Self {
+            protocol_version: card.protocol_version.clone(),
+            tools: card.skills.iter().map(|skill| skill.id.clone()).collect(),
+            capabilities,
+            a2a: Some(A2aEndpoint {
+                url: card.url.clone(),
+                streaming: card.capabilities.streaming,
+            }),
+        }
+    }
+
+    /// Whether the agent speaks a protocol version this connector does
+    pub fn is_compatible(&self) -> bool {
+        let ours = match self.a2a {
+            Some(_) => jamey_protocol::a2a::PROTOCOL_VERSION,
+            None => PROTOCOL_VERSION,
+        };
+        major(&self.protocol_version).is_some() && major(&self.protocol_version) == major(ours)
+    }
+
+    pub fn offers(&self, capability: &str) -> bool {
+        self.capabilities.iter().chain(&self.tools).any(|c| c == capability)
+    }
+}
+
+fn major(version: &str) -> Option<u32> {
+    version.trim().split('.').next()?.parse().ok()
+}
+
+/// Recent outcomes of contacting an agent
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct AgentHealth {
+    pub consecutive_failures: u32,
+    pub last_success: Option<DateTime<Utc>>,
+    pub last_failure: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+}
+
+impl AgentHealth {
+    pub fn is_healthy(&self) -> bool {
+        self.consecutive_failures < UNHEALTHY_AFTER
+    }
+
+    fn succeeded(&mut self) {
+        self.consecutive_failures = 0;
+        self.last_success = Some(Utc::now());
+    }
+
+    fn failed(&mut self, error: &str) {
+        self.consecutive_failures += 1;
+        self.last_failure = Some(Utc::now());
+        self.last_error = Some(error.to_string());
+    }
+}
+
+/// A registered agent with what the connector has learned about it
+#[derive(Debug, Clone)]
+struct AgentState {
+    endpoint: AgentEndpoint,
+    negotiated: Option<(AgentCapabilities, DateTime<Utc>)>,
+    health: AgentHealth,
+}
+
+impl AgentState {
+    fn offers(&self, capability: &str) -> bool {
+        match &self.negotiated {
+            Some((capabilities, _)) => capabilities.is_compatible() && capabilities.offers(capability),
+            None => self.endpoint.capabilities.iter().any(|c| c == capability),
+        }
+    }
+
+    fn is_stale(&self, now: DateTime<Utc>) -> bool {
+        self.negotiated.as_ref().is_none_or(|(_, at)| {
+            now.signed_duration_since(*at).to_std().unwrap_or_default() >= CAPABILITY_TTL
+        })
+    }
+
+    fn describe(&self) -> Value {
+        serde_json::json!({
+            "agent_id": self.endpoint.id,
+            "name": self.endpoint.name,
+            "url": self.endpoint.url,
+            "declared_capabilities": self.endpoint.capabilities,
+            "negotiated": self.negotiated.as_ref().map(|(capabilities, at)| serde_json::json!({
+                "protocol_version": capabilities.protocol_version,
+                "compatible": capabilities.is_compatible(),
+                "tools": capabilities.tools,
+                "capabilities": capabilities.capabilities,
+                "a2a": capabilities.a2a,
+                "at": at.to_rfc3339(),
+            })),
+            "healthy": self.health.is_healthy(),
+            "health": self.health,
+        })
+    }
+}
+
+/// Agents offering `capability`, healthy ones first and fewest recent
+/// failures first within each group
+fn route_order<'a>(agents: impl IntoIterator<Item = &'a AgentState>, capability: &str) -> Vec<String> {
+    let mut candidates: Vec<&AgentState> = agents.into_iter().filter(|agent| agent.offers(capability)).collect();
+    candidates.sort_by(|a, b| {
+        (!a.health.is_healthy(), a.health.consecutive_failures, &a.endpoint.id)
+            .cmp(&(!b.health.is_healthy(), b.health.consecutive_failures, &b.endpoint.id))
+    });
+    candidates.into_iter().map(|agent| agent.endpoint.id.clone()).collect()
+}
+
+/// Why a call to an agent failed
+#[derive(Debug)]
+enum DispatchError {
+    /// The agent couldn't be reached or failed itself; try another
+    Unavailable(anyhow::Error),
+    /// The agent turned the task down; another won't do better
+    Rejected(anyhow::Error),
+}
+
+impl From<DispatchError> for anyhow::Error {
+    fn from(e: DispatchError) -> Self {
+        match e {
+            DispatchError::Unavailable(e) | DispatchError::Rejected(e) => e,
+        }
+    }
+}
+
 pub struct AgentOrchestrationConnector {
     metadata: ConnectorMetadata,
-    registered_agents: Arc<RwLock<HashMap<String, AgentEndpoint>>>,
+    registered_agents: Arc<RwLock<HashMap<String, AgentState>>>,
     client: Client,
     enabled: bool,
 }
@@ -35,13 +230,13 @@ impl AgentOrchestrationConnector {
             .timeout(std::time::Duration::from_secs(30))
             .build()
             .context("Failed to build secure HTTP client")?;
-        
+
         Ok(Self {
             metadata: ConnectorMetadata {
                 id: "agent_orchestration".to_string(),
                 name: "Agent-to-Agent Orchestration".to_string(),
-                version: "1.0.0".to_string(),
-                description: "Orchestrate tasks across multiple agents with full communication".to_string(),
+                version: "1.1.0".to_string(),
+                description: "Orchestrate tasks across multiple agents, routed by negotiated capability".to_string(),
                 capability_level: CapabilityLevel::AgentOrchestration,
                 requires_approval: false,
                 safety_checks: vec![
@@ -49,6 +244,7 @@ impl AgentOrchestrationConnector {
                     "TLS 1.2+ minimum enforced".to_string(),
                     "Certificate validation enabled".to_string(),
                     "Task validation before delegation".to_string(),
+                    "Protocol version checked before routing".to_string(),
                 ],
             },
             registered_agents: Arc::new(RwLock::new(HashMap::new())),
@@ -61,7 +257,7 @@ impl AgentOrchestrationConnector {
         // Validate agent URL
         let parsed_url = url::Url::parse(&agent.url)
             .context("Invalid agent URL")?;
-        
+
         // Only allow HTTPS for agent communication
         if parsed_url.scheme() != "https" {
             anyhow::bail!(
@@ -69,65 +265,326 @@ impl AgentOrchestrationConnector {
                 parsed_url.scheme()
             );
         }
-        
+
         // Validate API key is not empty
         if agent.api_key.trim().is_empty() {
             anyhow::bail!("Security violation: API key cannot be empty");
         }
-        
+
         tracing::info!("Registering agent: {} at {}", agent.name, agent.url);
         let mut agents = self.registered_agents.write().await;
-        agents.insert(agent.id.clone(), agent);
+        agents.insert(agent.id.clone(), AgentState {
+            endpoint: agent,
+            negotiated: None,
+            health: AgentHealth::default(),
+        });
         Ok(())
     }
 
-    async fn send_task_to_agent(&self, agent_id: &str, task: &str, params: &HashMap<String, String>) -> Result<Value> {
-        let agents = self.registered_agents.read().await;
-        let agent = agents.get(agent_id)
-            .ok_or_else(|| anyhow::anyhow!("Agent not found: {}", agent_id))?;
-        
+    /// Probe every agent's capabilities every `every` under `supervisor`,
+    /// so failed agents are brought back once they answer again
+    pub fn spawn_health_checks(&self, supervisor: &Supervisor, every: Duration) -> JoinHandle<()> {
+        let agents = Arc::clone(&self.registered_agents);
+        let client = self.client.clone();
+        supervisor.spawn("agent_health_checks", move || {
+            let agents = Arc::clone(&agents);
+            let client = client.clone();
+            async move {
+                let mut interval = tokio::time::interval(every);
+                interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+                loop {
+                    interval.tick().await;
+                    let ids: Vec<String> = agents.read().await.keys().cloned().collect();
+                    for id in ids {
+                        if let Err(e) = negotiate(&client, &agents, &id).await {
+                            tracing::debug!("Agent {} failed its health check: {}", id, e);
+                        }
+                    }
+                }
+            }
+        })
+    }
+
+    async fn endpoint(&self, agent_id: &str) -> Result<AgentEndpoint> {
+        self.registered_agents
+            .read()
+            .await
+            .get(agent_id)
+            .map(|agent| agent.endpoint.clone())
+            .ok_or_else(|| anyhow::anyhow!("Agent not found: {}", agent_id))
+    }
+
+    async fn record(&self, agent_id: &str, outcome: Result<(), &str>) {
+        if let Some(agent) = self.registered_agents.write().await.get_mut(agent_id) {
+            match outcome {
+                Ok(()) => agent.health.succeeded(),
+                Err(e) => agent.health.failed(e),
+            }
+        }
+    }
+
+    async fn dispatch(&self, agent_id: &str, task: &str, params: &HashMap<String, String>) -> Result<Value, DispatchError> {
+        let agent = self.endpoint(agent_id).await.map_err(DispatchError::Rejected)?;
+
         tracing::info!("Sending task to agent {}: {}", agent_id, task);
-        
+
+        if let Some(endpoint) = self.a2a_endpoint(agent_id).await {
+            let outcome = self.dispatch_a2a(&agent, &endpoint, task, params).await;
+            match &outcome {
+                Err(DispatchError::Unavailable(e)) => self.record(agent_id, Err(&e.to_string())).await,
+                _ => self.record(agent_id, Ok(())).await,
+            }
+            return outcome;
+        }
+
         // API key is now required (not optional)
-        let request = self.client.post(&format!("{}/api/v1/tasks", agent.url))
+        let request = self.client.post(format!("{}/api/v1/tasks", agent.url))
             .header("Authorization", format!("Bearer {}", agent.api_key))
+            .header("X-Agent-Protocol", PROTOCOL_VERSION)
             .json(&serde_json::json!({
                 "task": task,
                 "params": params
             }));
-        
-        let response = request.send().await
-            .context("Failed to send task to agent")?;
-        
-        if !response.status().is_success() {
-            anyhow::bail!(
-                "Agent returned error status: {}",
-                response.status()
-            );
+
+        let outcome = async {
+            let response = request.send().await
+                .context("Failed to send task to agent")
+                .map_err(DispatchError::Unavailable)?;
+            let status = response.status();
+            if status.is_server_error() {
+                return Err(DispatchError::Unavailable(anyhow::anyhow!("Agent returned error status: {}", status)));
+            }
+            if !status.is_success() {
+                return Err(DispatchError::Rejected(anyhow::anyhow!("Agent returned error status: {}", status)));
+            }
+            response.json::<Value>().await
+                .context("Agent sent an unreadable reply")
+                .map_err(DispatchError::Unavailable)
+        }
+        .await;
+
+        match &outcome {
+            Err(DispatchError::Unavailable(e)) => self.record(agent_id, Err(&e.to_string())).await,
+            // The agent answered, even if only to say no
+            _ => self.record(agent_id, Ok(())).await,
         }
-        
-        Ok(response.json().await?)
+        outcome
+    }
+
+    async fn a2a_endpoint(&self, agent_id: &str) -> Option<A2aEndpoint> {
+        self.registered_agents
+            .read()
+            .await
+            .get(agent_id)?
+            .negotiated
+            .as_ref()?
+            .0
+            .a2a
+            .clone()
+    }
+
+    async fn a2a_client(&self, agent_id: &str) -> Result<A2aClient> {
+        let agent = self.endpoint(agent_id).await?;
+        let endpoint = self
+            .a2a_endpoint(agent_id)
+            .await
+            .ok_or_else(|| anyhow::anyhow!("Agent {} hasn't negotiated A2A", agent_id))?;
+        Ok(A2aClient::new(self.client.clone(), endpoint.url, agent.api_key))
+    }
+
+    /// Send `task` as an A2A message and return the task it started
+    async fn dispatch_a2a(
+        &self,
+        agent: &AgentEndpoint,
+        endpoint: &A2aEndpoint,
+        task: &str,
+        params: &HashMap<String, String>,
+    ) -> Result<Value, DispatchError> {
+        let client = A2aClient::new(self.client.clone(), endpoint.url.clone(), agent.api_key.clone());
+        let mut parts = vec![Part::Text { text: task.to_string() }];
+        if !params.is_empty() {
+            parts.push(Part::Data { data: serde_json::json!(params) });
+        }
+        let message = A2aMessage::new(A2aRole::User, parts);
+
+        let task: Task = if endpoint.streaming {
+            client
+                .stream(message, |event| tracing::debug!("Agent {} streamed {:?}", agent.id, event))
+                .await
+                .map_err(DispatchError::Unavailable)?
+        } else {
+            match client.send(message).await.map_err(DispatchError::Unavailable)? {
+                jamey_protocol::a2a::Event::Message(reply) => {
+                    return Ok(serde_json::json!({ "message": reply.content() }));
+                }
+                jamey_protocol::a2a::Event::Task(task) => task,
+                other => {
+                    return Err(DispatchError::Unavailable(anyhow::anyhow!(
+                        "Agent answered message/send with {:?}",
+                        other
+                    )))
+                }
+            }
+        };
+
+        if matches!(task.status.state, TaskState::Failed | TaskState::Rejected) {
+            let reason = task.status.message.as_ref().map(A2aMessage::content).unwrap_or_default();
+            return Err(DispatchError::Rejected(anyhow::anyhow!(
+                "Agent {} task {} {:?}: {}",
+                agent.id,
+                task.id,
+                task.status.state,
+                reason
+            )));
+        }
+        serde_json::to_value(&task)
+            .context("Failed to encode the agent's task")
+            .map_err(DispatchError::Rejected)
+    }
+
+    async fn send_task_to_agent(&self, agent_id: &str, task: &str, params: &HashMap<String, String>) -> Result<Value> {
+        Ok(self.dispatch(agent_id, task, params).await?)
     }
 
     async fn broadcast_task(&self, task: &str, params: &HashMap<String, String>) -> Result<HashMap<String, Value>> {
-        let agents = self.registered_agents.read().await;
+        let ids: Vec<String> = self.registered_agents.read().await.keys().cloned().collect();
         let mut results = HashMap::new();
-        
-        for (agent_id, _) in agents.iter() {
-            match self.send_task_to_agent(agent_id, task, params).await {
+
+        for agent_id in ids {
+            match self.send_task_to_agent(&agent_id, task, params).await {
                 Ok(result) => {
-                    results.insert(agent_id.clone(), result);
+                    results.insert(agent_id, result);
                 }
                 Err(e) => {
-                    results.insert(agent_id.clone(), serde_json::json!({
+                    results.insert(agent_id, serde_json::json!({
                         "error": e.to_string()
                     }));
                 }
             }
         }
-        
+
         Ok(results)
     }
+
+    /// Handshake with `agent_id`, or with every agent
+    async fn negotiate_agents(&self, agent_id: Option<&str>) -> Result<Value> {
+        let ids: Vec<String> = match agent_id {
+            Some(id) => vec![self.endpoint(id).await?.id],
+            None => self.registered_agents.read().await.keys().cloned().collect(),
+        };
+        let mut results = serde_json::Map::new();
+        for id in ids {
+            let outcome = match negotiate(&self.client, &self.registered_agents, &id).await {
+                Ok(capabilities) => serde_json::to_value(&capabilities)?,
+                Err(e) => serde_json::json!({ "error": e.to_string() }),
+            };
+            results.insert(id, outcome);
+        }
+        Ok(Value::Object(results))
+    }
+
+    /// Send `task` to the best agent offering `capability`, moving on to
+    /// the next when one is unavailable
+    async fn route_task(&self, capability: &str, task: &str, params: &HashMap<String, String>) -> Result<(String, Value, Vec<String>)> {
+        let now = Utc::now();
+        let stale: Vec<String> = self.registered_agents.read().await
+            .values()
+            .filter(|agent| agent.is_stale(now))
+            .map(|agent| agent.endpoint.id.clone())
+            .collect();
+        for id in stale {
+            if let Err(e) = negotiate(&self.client, &self.registered_agents, &id).await {
+                tracing::debug!("Capability handshake with agent {} failed: {}", id, e);
+            }
+        }
+
+        let order = route_order(self.registered_agents.read().await.values(), capability);
+        if order.is_empty() {
+            anyhow::bail!("No compatible agent offers '{}'", capability);
+        }
+        let mut failed = Vec::new();
+        for agent_id in order {
+            match self.dispatch(&agent_id, task, params).await {
+                Ok(response) => return Ok((agent_id, response, failed)),
+                Err(DispatchError::Rejected(e)) => return Err(e.context(format!("Agent {} rejected the task", agent_id))),
+                Err(DispatchError::Unavailable(e)) => {
+                    tracing::warn!("Agent {} is unavailable, failing over: {}", agent_id, e);
+                    failed.push(format!("{}: {}", agent_id, e));
+                }
+            }
+        }
+        anyhow::bail!("Every agent offering '{}' failed: {}", capability, failed.join("; "))
+    }
+}
+
+/// Ask `agent_id` what it offers and cache the answer
+async fn negotiate(
+    client: &Client,
+    agents: &RwLock<HashMap<String, AgentState>>,
+    agent_id: &str,
+) -> Result<AgentCapabilities> {
+    let endpoint = agents
+        .read()
+        .await
+        .get(agent_id)
+        .map(|agent| agent.endpoint.clone())
+        .ok_or_else(|| anyhow::anyhow!("Agent not found: {}", agent_id))?;
+
+    let outcome = async {
+        let response = client
+            .get(format!("{}/api/v1/capabilities", endpoint.url))
+            .header("Authorization", format!("Bearer {}", endpoint.api_key))
+            .header("X-Agent-Protocol", PROTOCOL_VERSION)
+            .send()
+            .await
+            .context("Capability handshake failed")?;
+        // Agents without the handshake may still speak A2A
+        if response.status() == StatusCode::NOT_FOUND {
+            let card = a2a::fetch_card(client, &endpoint.url, &endpoint.api_key).await?;
+            if url::Url::parse(&card.url).map(|url| url.scheme() != "https").unwrap_or(true) {
+                anyhow::bail!("Security violation: A2A endpoint must use HTTPS. Got: {}", card.url);
+            }
+            return Ok(AgentCapabilities::from_card(&card));
+        }
+        if !response.status().is_success() {
+            anyhow::bail!("Agent returned error status: {}", response.status());
+        }
+        response
+            .json::<AgentCapabilities>()
+            .await
+            .context("Agent sent an unreadable capability list")
+    }
+    .await;
+
+    let mut agents = agents.write().await;
+    let Some(agent) = agents.get_mut(agent_id) else {
+        return outcome;
+    };
+    match &outcome {
+        Ok(capabilities) => {
+            if !capabilities.is_compatible() {
+                tracing::warn!(
+                    "Agent {} speaks {} protocol {}, which this connector doesn't; it won't be routed to",
+                    agent_id,
+                    if capabilities.a2a.is_some() { "A2A" } else { "agent" },
+                    capabilities.protocol_version
+                );
+            }
+            agent.negotiated = Some((capabilities.clone(), Utc::now()));
+            agent.health.succeeded();
+        }
+        Err(e) => agent.health.failed(&e.to_string()),
+    }
+    outcome
+}
+
+/// `param_*` entries of `params`, without the prefix
+fn task_params(params: &HashMap<String, String>) -> HashMap<String, String> {
+    params.iter()
+        .filter_map(|(k, v)| {
+            k.strip_prefix("param_").map(|stripped| (stripped.to_string(), v.clone()))
+        })
+        .collect()
 }
 
 #[async_trait::async_trait]
@@ -135,7 +592,7 @@ impl Connector for AgentOrchestrationConnector {
     fn metadata(&self) -> &ConnectorMetadata {
         &self.metadata
     }
-    
+
     async fn execute(
         &self,
         params: HashMap<String, String>,
@@ -143,9 +600,9 @@ impl Connector for AgentOrchestrationConnector {
     ) -> Result<ConnectorResult> {
         let action = params.get("action")
             .ok_or_else(|| anyhow::anyhow!("Missing 'action' parameter"))?;
-        
+
         let mut result = ConnectorResult::new();
-        
+
         match action.as_str() {
             "register_agent" => {
                 let agent = AgentEndpoint {
@@ -163,31 +620,58 @@ impl Connector for AgentOrchestrationConnector {
                 result.output = "Agent registered successfully".to_string();
                 result.success = true;
             }
+            "negotiate" => {
+                let negotiated = self.negotiate_agents(params.get("agent_id").map(|s| s.as_str())).await?;
+                result.output = serde_json::to_string_pretty(&negotiated)?;
+                result.success = true;
+            }
+            "list_agents" => {
+                let agents = self.registered_agents.read().await;
+                let mut described: Vec<Value> = agents.values().map(AgentState::describe).collect();
+                described.sort_by(|a, b| a["agent_id"].as_str().cmp(&b["agent_id"].as_str()));
+                result.output = serde_json::to_string_pretty(&described)?;
+                result.success = true;
+            }
             "send_task" => {
                 let agent_id = params.get("agent_id").ok_or_else(|| anyhow::anyhow!("Missing agent_id"))?;
                 let task = params.get("task").ok_or_else(|| anyhow::anyhow!("Missing task"))?;
-                let task_params: HashMap<String, String> = params.iter()
-                    .filter(|(k, _)| k.starts_with("param_"))
-                    .filter_map(|(k, v)| {
-                        k.strip_prefix("param_").map(|stripped| (stripped.to_string(), v.clone()))
-                    })
-                    .collect();
-                
-                let response = self.send_task_to_agent(agent_id, task, &task_params).await?;
+                let response = self.send_task_to_agent(agent_id, task, &task_params(&params)).await?;
                 result.output = serde_json::to_string_pretty(&response)?;
                 result.success = true;
                 result.agents_contacted.push(agent_id.clone());
             }
+            "route_task" => {
+                let capability = params.get("capability").ok_or_else(|| anyhow::anyhow!("Missing capability"))?;
+                let task = params.get("task").ok_or_else(|| anyhow::anyhow!("Missing task"))?;
+                let (agent_id, response, failed) = self.route_task(capability, task, &task_params(&params)).await?;
+                result.output = serde_json::to_string_pretty(&response)?;
+                result.success = true;
+                result.metadata.insert("agent_id".to_string(), agent_id.clone());
+                for failure in failed {
+                    result.warnings.push(format!("Failed over from {}", failure));
+                }
+                result.agents_contacted.push(agent_id);
+            }
+            "get_task" | "cancel_task" => {
+                let agent_id = params.get("agent_id").ok_or_else(|| anyhow::anyhow!("Missing agent_id"))?;
+                let task_id = params.get("task_id").ok_or_else(|| anyhow::anyhow!("Missing task_id"))?;
+                let client = self.a2a_client(agent_id).await?;
+                let task = if action == "get_task" {
+                    client.get_task(task_id).await?
+                } else {
+                    client.cancel_task(task_id).await?
+                };
+                result.metadata.insert("state".to_string(), serde_json::to_value(task.status.state)?
+                    .as_str()
+                    .unwrap_or_default()
+                    .to_string());
+                result.output = serde_json::to_string_pretty(&task)?;
+                result.success = true;
+                result.agents_contacted.push(agent_id.clone());
+            }
             "broadcast" => {
                 let task = params.get("task").ok_or_else(|| anyhow::anyhow!("Missing task"))?;
-                let task_params: HashMap<String, String> = params.iter()
-                    .filter(|(k, _)| k.starts_with("param_"))
-                    .filter_map(|(k, v)| {
-                        k.strip_prefix("param_").map(|stripped| (stripped.to_string(), v.clone()))
-                    })
-                    .collect();
-                
-                let results = self.broadcast_task(task, &task_params).await?;
+                let results = self.broadcast_task(task, &task_params(&params)).await?;
                 result.output = serde_json::to_string_pretty(&results)?;
                 result.success = true;
             }
@@ -195,35 +679,120 @@ impl Connector for AgentOrchestrationConnector {
                 result.errors.push(format!("Unknown action: {}", action));
             }
         }
-        
+
         Ok(result)
     }
-    
+
     fn validate(&self, params: &HashMap<String, String>) -> Result<()> {
         if !params.contains_key("action") {
             return Err(anyhow::anyhow!("Missing required parameter: action"));
         }
         Ok(())
     }
-    
+
     fn required_params(&self) -> Vec<String> {
         vec!["action".to_string()]
     }
-    
+
+    fn actions(&self) -> Vec<String> {
+        [
+            "register_agent",
+            "negotiate",
+            "list_agents",
+            "send_task",
+            "route_task",
+            "get_task",
+            "cancel_task",
+            "broadcast",
+        ]
+            .into_iter()
+            .map(String::from)
+            .collect()
+    }
+
     fn is_enabled(&self) -> bool {
         self.enabled
     }
-    
+
     fn safety_checks(&self) -> Vec<String> {
         self.metadata.safety_checks.clone()
     }
-    
+
     fn requires_network(&self) -> bool {
         true
     }
-    
+
     fn requires_credentials(&self) -> Vec<String> {
         vec![] // Depends on agent configuration
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn agent(id: &str, declared: &[&str], negotiated: Option<(&str, &[&str])>, failures: u32) -> AgentState {
+        AgentState {
+            endpoint: AgentEndpoint {
+                id: id.to_string(),
+                name: id.to_string(),
+                url: format!("https://{}.example.com", id),
+                api_key: "key".to_string(),
+                capabilities: declared.iter().map(|c| c.to_string()).collect(),
+            },
+            negotiated: negotiated.map(|(version, tools)| {
+                let capabilities = AgentCapabilities {
+                    protocol_version: version.to_string(),
+                    tools: tools.iter().map(|t| t.to_string()).collect(),
+                    capabilities: Vec::new(),
+                    a2a: None,
+                };
+                (capabilities, Utc::now())
+            }),
+            health: AgentHealth { consecutive_failures: failures, ..Default::default() },
+        }
+    }
+
+    #[test]
+    fn test_route_order() {
+        let agents = [
+            agent("flaky", &[], Some(("1.2", &["summarize"])), UNHEALTHY_AFTER),
+            agent("steady", &[], Some(("1.0", &["summarize"])), 0),
+            agent("unprobed", &["summarize"], None, 1),
+            // Negotiation replaces what was declared
+            agent("renamed", &["summarize"], Some(("1.0", &["translate"])), 0),
+            agent("future", &[], Some(("2.0", &["summarize"])), 0),
+        ];
+        assert_eq!(route_order(&agents, "summarize"), vec!["steady", "unprobed", "flaky"]);
+        assert!(route_order(&agents, "search").is_empty());
+
+        assert!(agents[0].is_stale(Utc::now() + chrono::Duration::minutes(11)));
+        assert!(!agents[0].is_stale(Utc::now()));
+    }
+
+    #[test]
+    fn test_a2a_card_capabilities() {
+        let card: AgentCard = serde_json::from_value(serde_json::json!({
+            "name": "Researcher",
+            "description": "Finds things out",
+            "url": "https://researcher.example.com/a2a",
+            "version": "2.1.0",
+            "protocolVersion": "0.3.0",
+            "capabilities": { "streaming": true },
+            "skills": [
+                { "id": "search", "name": "Search", "tags": ["research", "web"] },
+                { "id": "cite", "name": "Cite", "tags": ["research"] },
+            ],
+        }))
+        .unwrap();
+        let capabilities = AgentCapabilities::from_card(&card);
+        assert!(capabilities.is_compatible());
+        assert!(capabilities.offers("search") && capabilities.offers("web"));
+        assert_eq!(capabilities.capabilities, vec!["research", "web"]);
+        assert!(capabilities.a2a.as_ref().is_some_and(|a2a| a2a.streaming));
+
+        // An A2A version isn't read as the Jamey protocol's
+        let jamey = AgentCapabilities { a2a: None, ..capabilities };
+        assert!(!jamey.is_compatible());
+    }
+}
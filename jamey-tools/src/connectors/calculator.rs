@@ -0,0 +1,113 @@
+//! Calculator Connector
+//!
+//! Gives the model [`CalculatorTool`] so arithmetic, unit conversions and
+//! date differences are computed instead of guessed. It touches nothing
+//! outside the process, which is why it sits at the lowest capability level
+//! and is available to every session.
+
+use crate::calculator::CalculatorTool;
+use crate::connector::*;
+use anyhow::Result;
+use std::collections::HashMap;
+
+pub struct CalculatorConnector {
+    metadata: ConnectorMetadata,
+    tool: CalculatorTool,
+    enabled: bool,
+}
+
+impl CalculatorConnector {
+    pub fn new() -> Self {
+        Self {
+            metadata: ConnectorMetadata {
+                id: "calculator".to_string(),
+                name: "Calculator".to_string(),
+                version: "1.0.0".to_string(),
+                description: "Exact arithmetic, unit conversion and date calculation; use it for any number \
+                    you report. Actions: evaluate (query), e.g. \"2^32 / 7\", \"60 mph -> km/h\", \
+                    \"3.5 kWh / 40 minutes -> W\", \"#2024-03-01# - #2023-12-25# -> days\", \"now + 90 days\"."
+                    .to_string(),
+                capability_level: CapabilityLevel::ReadOnly,
+                requires_approval: false,
+                safety_checks: vec![
+                    "Pure computation with no file, network or process access".to_string(),
+                ],
+            },
+            tool: CalculatorTool::new(),
+            enabled: true,
+        }
+    }
+}
+
+impl Default for CalculatorConnector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait::async_trait]
+impl Connector for CalculatorConnector {
+    fn metadata(&self) -> &ConnectorMetadata {
+        &self.metadata
+    }
+
+    async fn execute(
+        &self,
+        params: HashMap<String, String>,
+        _context: &ExecutionContext,
+    ) -> Result<ConnectorResult> {
+        let action = params.get("action")
+            .ok_or_else(|| anyhow::anyhow!("Missing 'action' parameter"))?;
+
+        let mut result = ConnectorResult::new();
+        match action.as_str() {
+            "evaluate" => {
+                let query = params.get("query").ok_or_else(|| anyhow::anyhow!("Missing query"))?;
+                match self.tool.evaluate(query).await {
+                    Ok(calculation) => {
+                        result.output = calculation.result;
+                        result.success = true;
+                    }
+                    // Rink's message says what it couldn't make sense of
+                    Err(e) => result.errors.push(e.to_string()),
+                }
+            }
+            _ => {
+                result.errors.push(format!("Unknown action: {}", action));
+            }
+        }
+
+        Ok(result)
+    }
+
+    fn validate(&self, params: &HashMap<String, String>) -> Result<()> {
+        if !params.contains_key("action") {
+            return Err(anyhow::anyhow!("Missing required parameter: action"));
+        }
+        Ok(())
+    }
+
+    fn required_params(&self) -> Vec<String> {
+        vec!["action".to_string()]
+    }
+
+    fn actions(&self) -> Vec<String> {
+        vec!["evaluate".to_string()]
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    fn safety_checks(&self) -> Vec<String> {
+        self.metadata.safety_checks.clone()
+    }
+
+    fn requires_network(&self) -> bool {
+        false
+    }
+
+    fn requires_credentials(&self) -> Vec<String> {
+        vec![]
+    }
+}
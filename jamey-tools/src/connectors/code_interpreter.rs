@@ -0,0 +1,131 @@
+//! Code Interpreter Connector
+//!
+//! Lets the model run Python or JavaScript through [`CodeInterpreterTool`].
+//! The code never sees the host: each run gets an empty directory in a
+//! container without network access. Files the code writes there are
+//! returned as artifacts, which the runtime attaches to the conversation.
+
+use crate::connector::*;
+use crate::interpreter::{CodeInterpreterTool, InterpreterConfig, Language};
+use anyhow::Result;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::Duration;
+
+pub struct CodeInterpreterConnector {
+    metadata: ConnectorMetadata,
+    tool: CodeInterpreterTool,
+    enabled: bool,
+}
+
+impl CodeInterpreterConnector {
+    /// Runs happen in fresh directories under `work_root`
+    pub fn new(work_root: PathBuf) -> Self {
+        Self {
+            metadata: ConnectorMetadata {
+                id: "code_interpreter".to_string(),
+                name: "Code Interpreter".to_string(),
+                version: "1.0.0".to_string(),
+                description: "Run a Python or JavaScript program in an isolated sandbox without network \
+                    access and get its stdout, stderr and exit code as JSON. Files the program writes to its \
+                    working directory are attached to the conversation. Actions: run (language=python|javascript, \
+                    code=<program>, timeout_secs)."
+                    .to_string(),
+                capability_level: CapabilityLevel::ReadWrite,
+                requires_approval: false,
+                safety_checks: vec![
+                    "Runs in a container without network access or host files".to_string(),
+                    "CPU, memory, process and time limits".to_string(),
+                ],
+            },
+            tool: CodeInterpreterTool::new(work_root),
+            enabled: true,
+        }
+    }
+
+    pub fn with_config(mut self, config: InterpreterConfig) -> Self {
+        self.tool = self.tool.with_config(config);
+        self
+    }
+}
+
+#[async_trait::async_trait]
+impl Connector for CodeInterpreterConnector {
+    fn metadata(&self) -> &ConnectorMetadata {
+        &self.metadata
+    }
+
+    async fn execute(
+        &self,
+        params: HashMap<String, String>,
+        context: &ExecutionContext,
+    ) -> Result<ConnectorResult> {
+        let action = params.get("action")
+            .ok_or_else(|| anyhow::anyhow!("Missing 'action' parameter"))?;
+
+        let mut result = ConnectorResult::new();
+        match action.as_str() {
+            "run" => {
+                let language = params.get("language")
+                    .ok_or_else(|| anyhow::anyhow!("Missing language"))?
+                    .parse::<Language>()
+                    .map_err(|e| anyhow::anyhow!(e))?;
+                let code = params.get("code").ok_or_else(|| anyhow::anyhow!("Missing code"))?;
+                let timeout = params.get("timeout_secs")
+                    .map(|secs| secs.trim().parse::<u64>().map_err(|_| anyhow::anyhow!("Invalid timeout_secs: {}", secs)))
+                    .transpose()?
+                    .map(Duration::from_secs);
+
+                tracing::info!(target: "audit", session = %context.session_id, ?language, bytes = code.len(), "Sandboxed code run");
+                let output = self.tool.run(language, code, timeout).await?;
+                result.metadata.insert("exit_code".to_string(), output.exit_code.map_or("none".to_string(), |c| c.to_string()));
+                if output.timed_out {
+                    result.warnings.push("Program was stopped at its time limit".to_string());
+                }
+                if output.truncated {
+                    result.warnings.push("Some output or files were left out".to_string());
+                }
+                result.artifacts = output.files.iter().map(|f| f.path.clone()).collect();
+                result.output = serde_json::to_string_pretty(&output)?;
+                // A program that fails still ran; stderr and the exit code say why
+                result.success = true;
+            }
+            _ => {
+                result.errors.push(format!("Unknown action: {}", action));
+            }
+        }
+
+        Ok(result)
+    }
+
+    fn validate(&self, params: &HashMap<String, String>) -> Result<()> {
+        if !params.contains_key("action") {
+            return Err(anyhow::anyhow!("Missing required parameter: action"));
+        }
+        Ok(())
+    }
+
+    fn required_params(&self) -> Vec<String> {
+        vec!["action".to_string()]
+    }
+
+    fn actions(&self) -> Vec<String> {
+        vec!["run".to_string()]
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    fn safety_checks(&self) -> Vec<String> {
+        self.metadata.safety_checks.clone()
+    }
+
+    fn requires_network(&self) -> bool {
+        false
+    }
+
+    fn requires_credentials(&self) -> Vec<String> {
+        vec![]
+    }
+}
@@ -0,0 +1,142 @@
+//! Code Search Connector
+//!
+//! Lets the model find code with [`CodeSearchTool`] instead of listing and
+//! reading directories through the Full System connector. Searches cover the
+//! session's workspace when it has one, otherwise the connector's root, and
+//! skip whatever the workspace ignores.
+
+use crate::connector::*;
+use crate::search::{CodeSearchTool, SearchRequest, DEFAULT_MAX_MATCHES};
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// Most matches one search returns
+const MAX_MATCHES: usize = 500;
+
+/// Most context lines around a match
+const MAX_CONTEXT_LINES: usize = 10;
+
+pub struct CodeSearchConnector {
+    metadata: ConnectorMetadata,
+    root_path: PathBuf,
+    enabled: bool,
+}
+
+impl CodeSearchConnector {
+    pub fn new(root_path: PathBuf) -> Self {
+        Self {
+            metadata: ConnectorMetadata {
+                id: "code_search".to_string(),
+                name: "Code Search".to_string(),
+                version: "1.0.0".to_string(),
+                description: "Search file contents in the workspace, ripgrep-style, results as JSON. Action: \
+                    search (pattern=<regex>, literal=true for plain text, ignore_case=true, \
+                    glob=<comma-separated globs, !glob to exclude>, path=<subdirectory>, context=<lines>, \
+                    max_results). Honours .gitignore and skips binary files."
+                    .to_string(),
+                capability_level: CapabilityLevel::ReadOnly,
+                requires_approval: false,
+                safety_checks: vec![
+                    "Searches stay inside the root or workspace".to_string(),
+                    "Results are capped".to_string(),
+                ],
+            },
+            root_path,
+            enabled: true,
+        }
+    }
+
+    /// The tool for this call: rooted at the workspace, which must lie
+    /// inside the connector's root, or at the root itself
+    fn tool(&self, context: &ExecutionContext) -> Result<CodeSearchTool> {
+        let root = self.root_path.canonicalize()
+            .with_context(|| format!("Failed to canonicalize root: {}", self.root_path.display()))?;
+        let Some(workspace) = &context.workspace else {
+            return Ok(CodeSearchTool::new(root));
+        };
+        if !workspace.root.starts_with(&root) {
+            anyhow::bail!("Security violation: Workspace {} is outside the root directory", workspace.root.display());
+        }
+        Ok(CodeSearchTool::new(&workspace.root).with_ignore(workspace.ignore.clone()))
+    }
+}
+
+#[async_trait::async_trait]
+impl Connector for CodeSearchConnector {
+    fn metadata(&self) -> &ConnectorMetadata {
+        &self.metadata
+    }
+
+    async fn execute(
+        &self,
+        params: HashMap<String, String>,
+        context: &ExecutionContext,
+    ) -> Result<ConnectorResult> {
+        let action = params.get("action")
+            .ok_or_else(|| anyhow::anyhow!("Missing 'action' parameter"))?;
+        let mut result = ConnectorResult::new();
+        if action != "search" {
+            result.errors.push(format!("Unknown action: {}", action));
+            return Ok(result);
+        }
+
+        let tool = self.tool(context)?;
+        let path = match params.get("path").map(|p| p.trim()).filter(|p| !p.is_empty() && *p != ".") {
+            Some(path) => {
+                let dir = super::full_system::sanitize_path(tool.root(), path).context("Path validation failed")?;
+                Some(dir.strip_prefix(tool.root()).unwrap_or(&dir).to_path_buf())
+            }
+            None => None,
+        };
+        let request = SearchRequest {
+            pattern: params.get("pattern").ok_or_else(|| anyhow::anyhow!("Missing pattern"))?.clone(),
+            literal: parse_param(&params, "literal")?.unwrap_or(false),
+            case_insensitive: parse_param(&params, "ignore_case")?.unwrap_or(false),
+            globs: params
+                .get("glob")
+                .map(|globs| globs.split(',').map(str::trim).filter(|g| !g.is_empty()).map(String::from).collect())
+                .unwrap_or_default(),
+            path,
+            context_lines: parse_param(&params, "context")?.unwrap_or(0).min(MAX_CONTEXT_LINES),
+            max_matches: Some(parse_param(&params, "max_results")?.unwrap_or(DEFAULT_MAX_MATCHES).min(MAX_MATCHES)),
+        };
+
+        // Walking a large tree blocks, so it runs off the async threads
+        let results = tokio::task::spawn_blocking(move || tool.search(&request)).await??;
+        result.output = serde_json::to_string_pretty(&results)?;
+        result.success = true;
+        Ok(result)
+    }
+
+    fn validate(&self, params: &HashMap<String, String>) -> Result<()> {
+        if !params.contains_key("action") {
+            return Err(anyhow::anyhow!("Missing required parameter: action"));
+        }
+        Ok(())
+    }
+
+    fn required_params(&self) -> Vec<String> {
+        vec!["action".to_string()]
+    }
+
+    fn actions(&self) -> Vec<String> {
+        vec!["search".to_string()]
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    fn safety_checks(&self) -> Vec<String> {
+        self.metadata.safety_checks.clone()
+    }
+
+    fn requires_network(&self) -> bool {
+        false
+    }
+
+    fn requires_credentials(&self) -> Vec<String> {
+        vec![]
+    }
+}
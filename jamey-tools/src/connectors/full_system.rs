@@ -3,13 +3,17 @@
 //! Provides complete access to entire laptop filesystem, network, and system resources
 
 use crate::connector::*;
+use crate::system::{FileBackup, SelfModifyTool};
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use anyhow::{Result, Context};
-use std::process::Command;
+use crate::path_policy::PathPolicy;
+use crate::sandbox::ExecutionSandbox;
+use std::process::Stdio;
+use tokio::process::Command;
 
 /// List of allowed commands for execution
-const ALLOWED_COMMANDS: &[&str] = &[
+pub(crate) const ALLOWED_COMMANDS: &[&str] = &[
     "ls", "dir", "cat", "type", "echo", "pwd", "cd",
     "git", "npm", "cargo", "python", "node", "rustc",
     "grep", "find", "which", "where", "whoami",
@@ -33,7 +37,7 @@ const BLOCKED_FLAGS: &[&str] = &[
 /// ```
 /// let safe_path = sanitize_path(&root, "data/file.txt")?;
 /// ```
-fn sanitize_path(root: &Path, user_path: &str) -> Result<PathBuf> {
+pub(crate) fn sanitize_path(root: &Path, user_path: &str) -> Result<PathBuf> {
     // Reject absolute paths
     if Path::new(user_path).is_absolute() {
         anyhow::bail!("Security violation: Absolute paths are not allowed. Path: {}", user_path);
@@ -66,6 +70,24 @@ fn sanitize_path(root: &Path, user_path: &str) -> Result<PathBuf> {
     Ok(canonical_path)
 }
 
+/// Resolve `path` against the root of the workspace the call is confined
+/// to, which must lie inside `root_path`, or against `root_path` outside one
+pub(crate) fn resolve_in_workspace(root_path: &Path, path: &str, context: &ExecutionContext) -> Result<PathBuf> {
+    let Some(workspace) = &context.workspace else {
+        return sanitize_path(root_path, path);
+    };
+    let root = root_path.canonicalize()
+        .with_context(|| format!("Failed to canonicalize root: {}", root_path.display()))?;
+    if !workspace.root.starts_with(&root) {
+        anyhow::bail!("Security violation: Workspace {} is outside the root directory", workspace.root.display());
+    }
+    let safe_path = sanitize_path(&workspace.root, path)?;
+    if workspace.is_ignored(&safe_path) {
+        anyhow::bail!("{} is ignored in this workspace", path);
+    }
+    Ok(safe_path)
+}
+
 /// Validates a command before execution to prevent dangerous operations
 ///
 /// # Security Checks
@@ -77,7 +99,7 @@ fn sanitize_path(root: &Path, user_path: &str) -> Result<PathBuf> {
 /// ```
 /// validate_command("git", &["status"])?;
 /// ```
-fn validate_command(command: &str, args: &[String]) -> Result<()> {
+pub(crate) fn validate_command(command: &str, args: &[String]) -> Result<()> {
     // Check if command is in whitelist
     let command_name = Path::new(command)
         .file_name()
@@ -109,6 +131,12 @@ fn validate_command(command: &str, args: &[String]) -> Result<()> {
 pub struct FullSystemConnector {
     metadata: ConnectorMetadata,
     root_path: PathBuf,
+    /// Where overwritten files are copied so writes can be undone
+    backup_dir: Option<PathBuf>,
+    /// User and limits `execute_command` runs under
+    sandbox: ExecutionSandbox,
+    /// Which files may be read, written and listed
+    policy: PathPolicy,
     enabled: bool,
 }
 
@@ -128,9 +156,51 @@ impl FullSystemConnector {
                 ],
             },
             root_path,
+            backup_dir: None,
+            sandbox: ExecutionSandbox::default(),
+            policy: PathPolicy::default(),
             enabled: true,
         }
     }
+
+    /// Back up files before overwriting them so the write can be undone;
+    /// without this only writes that create a file are reversible
+    pub fn with_backup_dir(mut self, backup_dir: PathBuf) -> Self {
+        self.backup_dir = Some(backup_dir);
+        self
+    }
+
+    /// Run commands as the sandbox user and within its limits
+    pub fn with_sandbox(mut self, sandbox: ExecutionSandbox) -> Self {
+        self.sandbox = sandbox;
+        self
+    }
+
+    /// Only read, write and list what `policy` allows
+    pub fn with_path_policy(mut self, policy: PathPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// How to undo writing `path`: restore a backup of what was there, or
+    /// delete it if it didn't exist. `None` when an existing file can't be
+    /// backed up.
+    fn prepare_write(&self, path: &Path) -> Option<Compensation> {
+        let undo = Compensation::new(&self.metadata.id, "restore_file", format!("Restore {}", path.display()))
+            .with_param("path", path.to_string_lossy());
+        if !path.exists() {
+            return Some(undo);
+        }
+        // A directory per write, as backup names only carry the second
+        let dir = self.backup_dir.as_ref()?.join(uuid::Uuid::new_v4().to_string());
+        match SelfModifyTool::new(&dir).and_then(|tool| Ok(tool.create_backup(path)?)) {
+            Ok(backup) => Some(undo.with_param("backup", backup.backup_path.to_string_lossy())),
+            Err(e) => {
+                tracing::warn!("Could not back up {} before writing it: {}", path.display(), e);
+                None
+            }
+        }
+    }
 }
 
 #[async_trait::async_trait]
@@ -142,7 +212,7 @@ impl Connector for FullSystemConnector {
     async fn execute(
         &self,
         params: HashMap<String, String>,
-        _context: &ExecutionContext,
+        context: &ExecutionContext,
     ) -> Result<ConnectorResult> {
         let action = params.get("action")
             .ok_or_else(|| anyhow::anyhow!("Missing 'action' parameter"))?;
@@ -154,8 +224,9 @@ impl Connector for FullSystemConnector {
                 let path = params.get("path").ok_or_else(|| anyhow::anyhow!("Missing path"))?;
                 
                 // Sanitize path to prevent traversal attacks
-                let safe_path = sanitize_path(&self.root_path, path)
+                let safe_path = resolve_in_workspace(&self.root_path, path, context)
                     .context("Path validation failed")?;
+                self.policy.check_read(&safe_path)?;
                 
                 let content = tokio::fs::read_to_string(&safe_path).await
                     .context("Failed to read file")?;
@@ -169,8 +240,9 @@ impl Connector for FullSystemConnector {
                 let content = params.get("content").ok_or_else(|| anyhow::anyhow!("Missing content"))?;
                 
                 // Sanitize path to prevent traversal attacks
-                let safe_path = sanitize_path(&self.root_path, path)
+                let safe_path = resolve_in_workspace(&self.root_path, path, context)
                     .context("Path validation failed")?;
+                self.policy.check_write(&safe_path, content.len() as u64)?;
                 
                 // Create parent directories if needed
                 if let Some(parent) = safe_path.parent() {
@@ -178,11 +250,16 @@ impl Connector for FullSystemConnector {
                         .context("Failed to create parent directories")?;
                 }
                 
+                let undo = self.prepare_write(&safe_path);
                 tokio::fs::write(&safe_path, content).await
                     .context("Failed to write file")?;
                 result.output = format!("File written: {}", safe_path.display());
                 result.success = true;
                 result.files_accessed.push(safe_path.to_string_lossy().to_string());
+                match undo {
+                    Some(undo) => result.compensations.push(undo),
+                    None => result.warnings.push("The previous contents were not backed up; this write can't be undone".to_string()),
+                }
                 tracing::info!("File written: {}", safe_path.display());
             }
             "execute_command" => {
@@ -196,29 +273,48 @@ impl Connector for FullSystemConnector {
                     .context("Command validation failed")?;
                 
                 tracing::warn!("Executing command: {} {:?}", command, args);
+                let working_dir = context.workspace.as_ref().map(|w| w.root.clone());
                 
-                // Execute in a blocking way with cleared environment
-                let output = tokio::task::spawn_blocking(move || {
-                    let mut cmd = Command::new(&command);
-                    cmd.args(&args);
-                    
-                    // On Windows, we need to preserve some environment variables
-                    #[cfg(windows)]
-                    {
-                        cmd.env_clear()
-                            .env("SystemRoot", std::env::var("SystemRoot").unwrap_or_default())
-                            .env("PATH", std::env::var("PATH").unwrap_or_default());
-                    }
-                    
-                    // On Unix, we can be more restrictive
-                    #[cfg(not(windows))]
-                    {
-                        cmd.env_clear()
-                            .env("PATH", "/usr/local/bin:/usr/bin:/bin");
-                    }
-                    
-                    cmd.output()
-                }).await??;
+                // Execute with a cleared environment, inside the sandbox
+                let mut cmd = Command::new(&command);
+                cmd.args(&args)
+                    .stdin(Stdio::null())
+                    .stdout(Stdio::piped())
+                    .stderr(Stdio::piped())
+                    .kill_on_drop(true);
+                if let Some(dir) = working_dir {
+                    cmd.current_dir(dir);
+                }
+                
+                // On Windows, we need to preserve some environment variables
+                #[cfg(windows)]
+                {
+                    cmd.env_clear()
+                        .env("SystemRoot", std::env::var("SystemRoot").unwrap_or_default())
+                        .env("PATH", std::env::var("PATH").unwrap_or_default());
+                }
+                
+                // On Unix, we can be more restrictive
+                #[cfg(not(windows))]
+                {
+                    cmd.env_clear()
+                        .env("PATH", "/usr/local/bin:/usr/bin:/bin");
+                }
+                self.sandbox.apply(&mut cmd);
+                
+                let child = cmd.spawn().with_context(|| format!("Failed to start {}", command))?;
+                let _confinement = self.sandbox.confine(child.id())?;
+                let output = match self.sandbox.wall_clock() {
+                    Some(limit) => match tokio::time::timeout(limit, child.wait_with_output()).await {
+                        Ok(output) => output?,
+                        // Dropping the wait killed the command
+                        Err(_) => {
+                            result.errors.push(format!("{} was stopped after {}s", command, limit.as_secs()));
+                            return Ok(result);
+                        }
+                    },
+                    None => child.wait_with_output().await?,
+                };
                 
                 result.output = String::from_utf8_lossy(&output.stdout).to_string();
                 if !output.stderr.is_empty() {
@@ -231,15 +327,20 @@ impl Connector for FullSystemConnector {
                 let path = params.get("path").unwrap_or(&default_path);
                 
                 // Sanitize path to prevent traversal attacks
-                let safe_path = sanitize_path(&self.root_path, path)
+                let safe_path = resolve_in_workspace(&self.root_path, path, context)
                     .context("Path validation failed")?;
+                self.policy.check_read(&safe_path)?;
                 
                 let mut entries = tokio::fs::read_dir(&safe_path).await
                     .context("Failed to read directory")?;
                 let mut entry_list = Vec::new();
                 
                 while let Some(entry) = entries.next_entry().await? {
-                    entry_list.push(entry.path().to_string_lossy().to_string());
+                    let path = entry.path();
+                    if context.workspace.as_ref().is_some_and(|w| w.is_ignored(&path)) || self.policy.is_denied(&path) {
+                        continue;
+                    }
+                    entry_list.push(path.to_string_lossy().to_string());
                 }
                 
                 result.output = serde_json::to_string_pretty(&entry_list)?;
@@ -265,6 +366,13 @@ impl Connector for FullSystemConnector {
         vec!["action".to_string()]
     }
     
+    fn actions(&self) -> Vec<String> {
+        ["read_file", "write_file", "execute_command", "list_directory"]
+            .into_iter()
+            .map(String::from)
+            .collect()
+    }
+    
     fn is_enabled(&self) -> bool {
         self.enabled
     }
@@ -277,6 +385,44 @@ impl Connector for FullSystemConnector {
         false
     }
     
+    async fn compensate(&self, compensation: &Compensation, _context: &ExecutionContext) -> Result<()> {
+        match compensation.param("action")? {
+            "restore_file" => {
+                let path = PathBuf::from(compensation.param("path")?);
+                let root = self.root_path.canonicalize()
+                    .with_context(|| format!("Failed to canonicalize root: {}", self.root_path.display()))?;
+                if !path.starts_with(&root) {
+                    anyhow::bail!("Security violation: {} is outside the root directory", path.display());
+                }
+                match compensation.params.get("backup") {
+                    Some(backup) => {
+                        let backup_path = PathBuf::from(backup);
+                        let backup_dir = self.backup_dir.as_ref()
+                            .filter(|dir| backup_path.starts_with(dir))
+                            .ok_or_else(|| anyhow::anyhow!("Backup {} is outside the backup directory", backup_path.display()))?;
+                        let backup = FileBackup {
+                            original_path: path.clone(),
+                            backup_path,
+                            timestamp: chrono::Utc::now(),
+                        };
+                        SelfModifyTool::new(backup_dir)?.restore_backup(&backup)?;
+                        if let Some(dir) = backup.backup_path.parent() {
+                            let _ = tokio::fs::remove_dir_all(dir).await;
+                        }
+                    }
+                    None => match tokio::fs::remove_file(&path).await {
+                        Ok(()) => {}
+                        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                        Err(e) => return Err(e).with_context(|| format!("Failed to delete {}", path.display())),
+                    },
+                }
+                tracing::info!("File restored: {}", path.display());
+                Ok(())
+            }
+            other => anyhow::bail!("Unknown compensation: {}", other),
+        }
+    }
+
     fn requires_credentials(&self) -> Vec<String> {
         vec![]
     }
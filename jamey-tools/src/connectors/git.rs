@@ -0,0 +1,179 @@
+//! Git Connector
+//!
+//! Gives the model [`GitTool`]'s read-only view of repositories under its
+//! root, or under the session's workspace when it has one. Results are
+//! returned as JSON.
+
+use crate::connector::*;
+use crate::git::{DiffRequest, GitTool, LogRequest, DEFAULT_DIFF_LINES, DEFAULT_LOG_LIMIT};
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Most commits one `log` call returns
+const MAX_LOG_LIMIT: usize = 200;
+
+/// Most diff lines one `diff` call returns
+const MAX_DIFF_LINES: usize = 5000;
+
+pub struct GitConnector {
+    metadata: ConnectorMetadata,
+    root_path: PathBuf,
+    enabled: bool,
+}
+
+impl GitConnector {
+    pub fn new(root_path: PathBuf) -> Self {
+        Self {
+            metadata: ConnectorMetadata {
+                id: "git".to_string(),
+                name: "Git".to_string(),
+                version: "1.0.0".to_string(),
+                description: "Inspect a local git repository (read-only), results as JSON. Actions: \
+                    status; diff (staged=true for the index, base=<rev> against a revision, path=<pathspec>, \
+                    context=<lines>, max_lines); blame (path, start, end); log (rev, path, limit); branches. \
+                    repo=<dir> picks the repository, default the workspace root."
+                    .to_string(),
+                capability_level: CapabilityLevel::ReadOnly,
+                requires_approval: false,
+                safety_checks: vec![
+                    "Repositories must be inside the root or workspace".to_string(),
+                    "Never changes the repository".to_string(),
+                ],
+            },
+            root_path,
+            enabled: true,
+        }
+    }
+}
+
+/// The repository `params["repo"]` names, resolved against the workspace
+/// root or the connector's own, and which must lie inside it
+fn open(root_path: &Path, params: &HashMap<String, String>, workspace: Option<&WorkspaceScope>) -> Result<GitTool> {
+    let root = match workspace {
+        Some(workspace) => workspace.root.clone(),
+        None => root_path.canonicalize()
+            .with_context(|| format!("Failed to canonicalize root: {}", root_path.display()))?,
+    };
+    let dir = params.get("repo").map(String::as_str).unwrap_or(".");
+    let dir = super::full_system::sanitize_path(&root, dir).context("Path validation failed")?;
+    let git = GitTool::open(&dir)?;
+    let workdir = git.workdir().canonicalize()?;
+    if !workdir.starts_with(&root) {
+        anyhow::bail!("Security violation: Repository {} is outside the root directory", workdir.display());
+    }
+    Ok(git)
+}
+
+#[async_trait::async_trait]
+impl Connector for GitConnector {
+    fn metadata(&self) -> &ConnectorMetadata {
+        &self.metadata
+    }
+
+    async fn execute(
+        &self,
+        params: HashMap<String, String>,
+        context: &ExecutionContext,
+    ) -> Result<ConnectorResult> {
+        let action = params.get("action")
+            .ok_or_else(|| anyhow::anyhow!("Missing 'action' parameter"))?
+            .clone();
+        let root_path = self.root_path.clone();
+        let workspace = context.workspace.clone();
+
+        // libgit2 blocks, so the repository is read off the async threads
+        let output = tokio::task::spawn_blocking(move || -> Result<Option<String>> {
+            let git = open(&root_path, &params, workspace.as_ref())?;
+            let workdir = git.workdir().canonicalize()?;
+            // Files the workspace puts off limits are left out of the results
+            let hidden = |path: &str| workspace.as_ref().is_some_and(|w| w.is_ignored(&workdir.join(path)));
+            let json = match params["action"].as_str() {
+                "status" => {
+                    let mut status = git.status()?;
+                    status.entries.retain(|entry| !hidden(&entry.path));
+                    serde_json::to_string_pretty(&status)?
+                }
+                "diff" => {
+                    let request = DiffRequest {
+                        staged: parse_param(&params, "staged")?.unwrap_or(false),
+                        base: params.get("base").cloned(),
+                        paths: params.get("path").cloned().into_iter().collect(),
+                        context_lines: parse_param(&params, "context")?,
+                        max_lines: Some(parse_param(&params, "max_lines")?.unwrap_or(DEFAULT_DIFF_LINES).min(MAX_DIFF_LINES)),
+                    };
+                    let mut diff = git.diff(&request)?;
+                    diff.files.retain(|file| !hidden(&file.path));
+                    serde_json::to_string_pretty(&diff)?
+                }
+                "blame" => {
+                    let path = params.get("path").ok_or_else(|| anyhow::anyhow!("Missing path"))?;
+                    if hidden(path) {
+                        anyhow::bail!("{} is ignored in this workspace", path);
+                    }
+                    let lines = match (parse_param(&params, "start")?, parse_param(&params, "end")?) {
+                        (Some(start), end) => Some((start, end.unwrap_or(usize::MAX))),
+                        (None, Some(end)) => Some((1, end)),
+                        (None, None) => None,
+                    };
+                    serde_json::to_string_pretty(&git.blame(path, lines)?)?
+                }
+                "log" => {
+                    let request = LogRequest {
+                        rev: params.get("rev").cloned(),
+                        path: params.get("path").cloned(),
+                        limit: Some(parse_param(&params, "limit")?.unwrap_or(DEFAULT_LOG_LIMIT).min(MAX_LOG_LIMIT)),
+                    };
+                    serde_json::to_string_pretty(&git.log(&request)?)?
+                }
+                "branches" => serde_json::to_string_pretty(&git.branches()?)?,
+                _ => return Ok(None),
+            };
+            Ok(Some(json))
+        }).await??;
+
+        let mut result = ConnectorResult::new();
+        match output {
+            Some(output) => {
+                result.output = output;
+                result.success = true;
+            }
+            None => result.errors.push(format!("Unknown action: {}", action)),
+        }
+        Ok(result)
+    }
+
+    fn validate(&self, params: &HashMap<String, String>) -> Result<()> {
+        if !params.contains_key("action") {
+            return Err(anyhow::anyhow!("Missing required parameter: action"));
+        }
+        Ok(())
+    }
+
+    fn required_params(&self) -> Vec<String> {
+        vec!["action".to_string()]
+    }
+
+    fn actions(&self) -> Vec<String> {
+        ["status", "diff", "blame", "log", "branches"]
+            .into_iter()
+            .map(String::from)
+            .collect()
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    fn safety_checks(&self) -> Vec<String> {
+        self.metadata.safety_checks.clone()
+    }
+
+    fn requires_network(&self) -> bool {
+        false
+    }
+
+    fn requires_credentials(&self) -> Vec<String> {
+        vec![]
+    }
+}
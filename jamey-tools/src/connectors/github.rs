@@ -11,13 +11,13 @@ use base64::{Engine as _, engine::general_purpose};
 
 pub struct GitHubConnector {
     metadata: ConnectorMetadata,
-    client: Client,
-    token: String,
+    // Rebuilt when the token is rotated; reqwest clients are cheap to clone
+    client: std::sync::RwLock<Client>,
     enabled: bool,
 }
 
 impl GitHubConnector {
-    pub fn new(token: String) -> Result<Self> {
+    fn build_client(token: &str) -> Result<Client> {
         let mut headers = reqwest::header::HeaderMap::new();
         headers.insert(
             reqwest::header::AUTHORIZATION,
@@ -32,9 +32,13 @@ impl GitHubConnector {
             "application/vnd.github.v3+json".parse()?
         );
 
-        let client = Client::builder()
+        Ok(Client::builder()
             .default_headers(headers)
-            .build()?;
+            .build()?)
+    }
+
+    pub fn new(token: String) -> Result<Self> {
+        let client = Self::build_client(&token)?;
 
         Ok(Self {
             metadata: ConnectorMetadata {
@@ -49,15 +53,18 @@ impl GitHubConnector {
                     "Repository access validated".to_string(),
                 ],
             },
-            client,
-            token,
+            client: std::sync::RwLock::new(client),
             enabled: true,
         })
     }
 
+    fn client(&self) -> Client {
+        self.client.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
     async fn get_repo(&self, owner: &str, repo: &str) -> Result<Value> {
         let url = format!("https://api.github.com/repos/{}/{}", owner, repo);
-        let response = self.client.get(&url).send().await?;
+        let response = self.client().get(&url).send().await?;
         Ok(response.json().await?)
     }
 
@@ -67,7 +74,7 @@ impl GitHubConnector {
             "title": title,
             "body": body
         });
-        let response = self.client.post(&url).json(&payload).send().await?;
+        let response = self.client().post(&url).json(&payload).send().await?;
         Ok(response.json().await?)
     }
 
@@ -79,14 +86,14 @@ impl GitHubConnector {
             "base": base,
             "body": body
         });
-        let response = self.client.post(&url).json(&payload).send().await?;
+        let response = self.client().post(&url).json(&payload).send().await?;
         Ok(response.json().await?)
     }
 
     async fn get_file_content(&self, owner: &str, repo: &str, path: &str, branch: Option<&str>) -> Result<String> {
         let branch = branch.unwrap_or("main");
         let url = format!("https://api.github.com/repos/{}/{}/contents/{}?ref={}", owner, repo, path, branch);
-        let response = self.client.get(&url).send().await?;
+        let response = self.client().get(&url).send().await?;
         let json: Value = response.json().await?;
         
         if let Some(content) = json.get("content").and_then(|c| c.as_str()) {
@@ -102,7 +109,7 @@ impl GitHubConnector {
         // First get the file to get its SHA
         let branch = branch.unwrap_or("main");
         let get_url = format!("https://api.github.com/repos/{}/{}/contents/{}?ref={}", owner, repo, path, branch);
-        let get_response = self.client.get(&get_url).send().await?;
+        let get_response = self.client().get(&get_url).send().await?;
         let file_info: Value = get_response.json().await?;
         let sha = file_info.get("sha")
             .and_then(|s| s.as_str())
@@ -119,7 +126,7 @@ impl GitHubConnector {
             "sha": sha,
             "branch": branch
         });
-        let response = self.client.put(&url).json(&payload).send().await?;
+        let response = self.client().put(&url).json(&payload).send().await?;
         Ok(response.json().await?)
     }
 }
@@ -210,6 +217,13 @@ impl Connector for GitHubConnector {
         vec!["action".to_string()]
     }
     
+    fn actions(&self) -> Vec<String> {
+        ["get_repo", "create_issue", "get_file", "update_file", "create_pr"]
+            .into_iter()
+            .map(String::from)
+            .collect()
+    }
+    
     fn is_enabled(&self) -> bool {
         self.enabled
     }
@@ -225,5 +239,14 @@ impl Connector for GitHubConnector {
     fn requires_credentials(&self) -> Vec<String> {
         vec!["github_token".to_string()]
     }
+
+    fn update_credential(&self, key: &str, value: &str) -> Result<()> {
+        if key == "github_token" {
+            let client = Self::build_client(value)?;
+            *self.client.write().unwrap_or_else(|e| e.into_inner()) = client;
+            tracing::info!("GitHub connector token updated");
+        }
+        Ok(())
+    }
 }
 
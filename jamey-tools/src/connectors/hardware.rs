@@ -0,0 +1,136 @@
+//! Hardware Connector
+//!
+//! Lets the model see which GPUs the machine has, how much memory they have
+//! free and how busy they are, so it can answer "can this box run a 70B
+//! model?" from the actual hardware. It only reads driver state.
+
+use crate::connector::*;
+use crate::hardware::HardwareInventoryTool;
+use anyhow::Result;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Bits per weight assumed by `model_fit` when none is given; about what
+/// Q4_K_M quantization takes
+const DEFAULT_BITS_PER_WEIGHT: f64 = 4.5;
+
+pub struct HardwareConnector {
+    metadata: ConnectorMetadata,
+    tool: Arc<HardwareInventoryTool>,
+    enabled: bool,
+}
+
+impl HardwareConnector {
+    pub fn new() -> Self {
+        Self::with_tool(Arc::new(HardwareInventoryTool::new()))
+    }
+
+    /// Connector sharing an existing tool, so NVML is only loaded once
+    pub fn with_tool(tool: Arc<HardwareInventoryTool>) -> Self {
+        Self {
+            metadata: ConnectorMetadata {
+                id: "hardware".to_string(),
+                name: "Hardware Inventory".to_string(),
+                version: "1.0.0".to_string(),
+                description: "GPUs and accelerators with VRAM, driver version, utilization and temperature, \
+                    plus CPU and memory. Actions: inventory, model_fit (bits_per_weight, default 4.5) for \
+                    the largest local model the free memory can hold."
+                    .to_string(),
+                capability_level: CapabilityLevel::ReadOnly,
+                requires_approval: false,
+                safety_checks: vec![
+                    "Reads driver and system information only".to_string(),
+                ],
+            },
+            tool,
+            enabled: true,
+        }
+    }
+}
+
+impl Default for HardwareConnector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait::async_trait]
+impl Connector for HardwareConnector {
+    fn metadata(&self) -> &ConnectorMetadata {
+        &self.metadata
+    }
+
+    async fn execute(
+        &self,
+        params: HashMap<String, String>,
+        _context: &ExecutionContext,
+    ) -> Result<ConnectorResult> {
+        let action = params.get("action")
+            .ok_or_else(|| anyhow::anyhow!("Missing 'action' parameter"))?;
+
+        let mut result = ConnectorResult::new();
+        match action.as_str() {
+            "inventory" => {
+                let tool = self.tool.clone();
+                let inventory = tokio::task::spawn_blocking(move || tool.snapshot()).await?;
+                result.output = serde_json::to_string_pretty(&inventory)?;
+                result.metadata.insert("gpu_count".to_string(), inventory.gpus.len().to_string());
+                result.success = true;
+            }
+            "model_fit" => {
+                let bits_per_weight = match params.get("bits_per_weight") {
+                    Some(bits) => bits.parse::<f64>()
+                        .ok()
+                        .filter(|bits| *bits > 0.0)
+                        .ok_or_else(|| anyhow::anyhow!("Invalid bits_per_weight: {}", bits))?,
+                    None => DEFAULT_BITS_PER_WEIGHT,
+                };
+                let tool = self.tool.clone();
+                let inventory = tokio::task::spawn_blocking(move || tool.snapshot()).await?;
+                result.output = serde_json::to_string_pretty(&serde_json::json!({
+                    "memory_budget_bytes": inventory.model_memory_budget(),
+                    "bits_per_weight": bits_per_weight,
+                    "max_params": inventory.max_model_params(bits_per_weight),
+                    "gpus": inventory.gpus.len(),
+                }))?;
+                result.success = true;
+            }
+            _ => {
+                result.errors.push(format!("Unknown action: {}", action));
+            }
+        }
+
+        Ok(result)
+    }
+
+    fn validate(&self, params: &HashMap<String, String>) -> Result<()> {
+        if !params.contains_key("action") {
+            return Err(anyhow::anyhow!("Missing required parameter: action"));
+        }
+        Ok(())
+    }
+
+    fn required_params(&self) -> Vec<String> {
+        vec!["action".to_string()]
+    }
+
+    fn actions(&self) -> Vec<String> {
+        vec!["inventory".to_string(), "model_fit".to_string()]
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    fn safety_checks(&self) -> Vec<String> {
+        self.metadata.safety_checks.clone()
+    }
+
+    fn requires_network(&self) -> bool {
+        false
+    }
+
+    fn requires_credentials(&self) -> Vec<String> {
+        vec![]
+    }
+}
@@ -16,14 +16,16 @@ use anyhow::{Result, Context};
 use serde::{Serialize, Deserialize};
 use serde_json::Value;
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use tokio::sync::{Mutex, RwLock};
 use chrono::{DateTime, Utc};
 use url::Url;
-use rumqttc::{AsyncClient, MqttOptions, QoS, Event, Incoming};
+use rumqttc::{AsyncClient, EventLoop, MqttOptions, QoS, Event, Incoming};
 use std::time::Duration;
 use tokio::task::JoinHandle;
 use mdns_sd::{ServiceDaemon, ServiceInfo};
 use jamey_core::secrets::SecretManager;
+use jamey_core::secure_logging::Masked;
+use jamey_core::supervisor::Supervisor;
 use base64::{Engine as _, engine::general_purpose};
 use rustls::{ClientConfig, RootCertStore, Certificate, PrivateKey};
 use rustls_pemfile::{certs, pkcs8_private_keys};
@@ -65,7 +67,7 @@ pub enum DeviceStatus {
 }
 
 /// MQTT connection configuration
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 struct MqttConfig {
     broker: String,
     port: u16,
@@ -78,6 +80,23 @@ struct MqttConfig {
     client_key: Option<Vec<u8>>,
 }
 
+// Certificates are shown as present or not; the password and key never
+impl std::fmt::Debug for MqttConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MqttConfig")
+            .field("broker", &self.broker)
+            .field("port", &self.port)
+            .field("client_id", &self.client_id)
+            .field("username", &self.username)
+            .field("password", &Masked::opt(self.password.as_deref()))
+            .field("use_tls", &self.use_tls)
+            .field("ca_cert", &self.ca_cert.is_some())
+            .field("client_cert", &self.client_cert.is_some())
+            .field("client_key", &Masked::opt(self.client_key.as_ref().map(|_| "key")))
+            .finish()
+    }
+}
+
 /// Active MQTT connection handle
 struct MqttConnection {
     client: AsyncClient,
@@ -92,6 +111,8 @@ pub struct IoTConnector {
     mqtt_connections: Arc<RwLock<HashMap<String, MqttConnection>>>,
     mqtt_configs: Arc<RwLock<HashMap<String, MqttConfig>>>,
     secret_manager: SecretManager,
+    /// Restarts a device's MQTT event loop if it panics
+    supervisor: Supervisor,
     enabled: bool,
 }
 
@@ -128,10 +149,17 @@ impl IoTConnector {
             mqtt_connections: Arc::new(RwLock::new(HashMap::new())),
             mqtt_configs: Arc::new(RwLock::new(HashMap::new())),
             secret_manager,
+            supervisor: Supervisor::default(),
             enabled: true,
         })
     }
 
+    /// Run MQTT event loops under `supervisor` rather than one of its own
+    pub fn with_supervisor(mut self, supervisor: Supervisor) -> Self {
+        self.supervisor = supervisor;
+        self
+    }
+
     /// Build rustls ClientConfig for mTLS with custom certificates
     fn build_mtls_config(
         &self,
@@ -412,7 +440,7 @@ impl IoTConnector {
                 }
                 
                 // Create MQTT client
-                let (client, mut eventloop) = AsyncClient::new(mqtt_options, 10);
+                let (client, eventloop) = AsyncClient::new(mqtt_options, 10);
                 
                 // Store connection config (credentials are in secure storage, not here)
                 let mqtt_config = MqttConfig {
@@ -439,32 +467,39 @@ impl IoTConnector {
                 let subscriptions = Arc::new(RwLock::new(Vec::new()));
                 let subscriptions_clone = subscriptions.clone();
                 
-                let handle = tokio::spawn(async move {
-                    loop {
-                        match eventloop.poll().await {
-                            Ok(Event::Incoming(Incoming::Publish(packet))) => {
-                                tracing::info!(
-                                    "Received MQTT message on topic {} for device {}: {}",
-                                    packet.topic,
-                                    device_id_clone,
-                                    String::from_utf8_lossy(&packet.payload)
-                                );
-                            }
-                            Ok(Event::Incoming(Incoming::ConnAck(_))) => {
-                                tracing::info!("MQTT connection acknowledged for device {}", device_id_clone);
-                            }
-                            Ok(Event::Incoming(Incoming::SubAck(_))) => {
-                                tracing::info!("MQTT subscription acknowledged for device {}", device_id_clone);
-                            }
-                            Ok(Event::Outgoing(_)) => {
-                                // Outgoing events handled by client
-                            }
-                            Ok(Event::Incoming(_)) => {
-                                // Handle other incoming events silently
-                            }
-                            Err(e) => {
-                                tracing::error!("MQTT event loop error for device {}: {}", device_id_clone, e);
-                                break;
+                // Shared so a restarted loop picks up the same connection
+                let eventloop: Arc<Mutex<EventLoop>> = Arc::new(Mutex::new(eventloop));
+                let handle = self.supervisor.spawn(format!("mqtt:{}", device_id), move || {
+                    let eventloop = Arc::clone(&eventloop);
+                    let device_id_clone = device_id_clone.clone();
+                    async move {
+                        let mut eventloop = eventloop.lock().await;
+                        loop {
+                            match eventloop.poll().await {
+                                Ok(Event::Incoming(Incoming::Publish(packet))) => {
+                                    tracing::info!(
+                                        "Received MQTT message on topic {} for device {}: {}",
+                                        packet.topic,
+                                        device_id_clone,
+                                        String::from_utf8_lossy(&packet.payload)
+                                    );
+                                }
+                                Ok(Event::Incoming(Incoming::ConnAck(_))) => {
+                                    tracing::info!("MQTT connection acknowledged for device {}", device_id_clone);
+                                }
+                                Ok(Event::Incoming(Incoming::SubAck(_))) => {
+                                    tracing::info!("MQTT subscription acknowledged for device {}", device_id_clone);
+                                }
+                                Ok(Event::Outgoing(_)) => {
+                                    // Outgoing events handled by client
+                                }
+                                Ok(Event::Incoming(_)) => {
+                                    // Handle other incoming events silently
+                                }
+                                Err(e) => {
+                                    tracing::error!("MQTT event loop error for device {}: {}", device_id_clone, e);
+                                    break;
+                                }
                             }
                         }
                     }
@@ -858,7 +893,7 @@ impl Connector for IoTConnector {
     async fn execute(
         &self,
         params: HashMap<String, String>,
-        _context: &ExecutionContext,
+        context: &ExecutionContext,
     ) -> Result<ConnectorResult> {
         let action = params.get("action")
             .ok_or_else(|| anyhow::anyhow!("Missing 'action' parameter"))?;
@@ -926,6 +961,7 @@ impl Connector for IoTConnector {
                 let headers = params.get("headers")
                     .and_then(|s| serde_json::from_str::<HashMap<String, String>>(s).ok());
                 
+                context.partial_output.push_line(&format!("Sending {} {} to {}", method, path, device_id));
                 let response = self.send_http_command(device_id, method, path, body, headers).await?;
                 result.output = serde_json::to_string_pretty(&response)?;
                 result.success = true;
@@ -952,6 +988,7 @@ impl Connector for IoTConnector {
                     .and_then(|s| s.parse::<u8>().ok())
                     .unwrap_or(0);
                 
+                context.partial_output.push_line(&format!("Publishing to {} on {} (QoS {})", topic, device_id, qos));
                 self.publish_mqtt(device_id, topic, payload, qos).await?;
                 result.output = format!("Message published to topic: {}", topic);
                 result.success = true;
@@ -1008,6 +1045,17 @@ impl Connector for IoTConnector {
         vec!["action".to_string()]
     }
     
+    fn actions(&self) -> Vec<String> {
+        [
+            "register_device", "connect_device", "disconnect_device", "remove_device",
+            "list_devices", "get_status", "http_command", "mqtt_publish", "mqtt_subscribe",
+            "discover", "update_status",
+        ]
+            .into_iter()
+            .map(String::from)
+            .collect()
+    }
+    
     fn is_enabled(&self) -> bool {
         self.enabled
     }
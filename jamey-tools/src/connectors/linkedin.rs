@@ -1,17 +1,171 @@
 //! LinkedIn Connector
-//! 
-//! Provides LinkedIn API access for profile, posts, and networking
+//!
+//! Drafts, publishes and reads posts for the member and the pages they
+//! administer, and fetches engagement stats for them.
+//!
+//! Drafts are kept as memories (with [`DraftMemory`]) so they show up in
+//! recall and survive restarts; publishing a draft replaces it with a
+//! memory of the published post. Publishing always needs a person's
+//! approval, and feeds and page stats are limited to the authors the token
+//! owns: the member and organizations they administer. Pages need the
+//! `r_organization_social`, `w_organization_social` and
+//! `rw_organization_admin` scopes on top of the defaults.
 
 use crate::connector::*;
+use jamey_core::maintenance::Embedder;
+use jamey_core::memory::{Memory, MemoryStore, MemoryType};
 use reqwest::Client;
 use std::collections::HashMap;
-use anyhow::Result;
+use std::sync::Arc;
+use anyhow::{Context, Result};
+use serde::Serialize;
 use serde_json::Value;
+use uuid::Uuid;
+
+const API: &str = "https://api.linkedin.com";
+
+/// Versioned REST API release the requests are written against
+const API_VERSION: &str = "202405";
+
+/// LinkedIn's limit on post commentary
+const MAX_POST_CHARS: usize = 3000;
+
+/// Most posts returned by `read_feed`
+const MAX_FEED_POSTS: usize = 50;
+
+/// `source` and `kind` of draft memories
+const MEMORY_SOURCE: &str = "linkedin";
+const DRAFT_KIND: &str = "draft";
+const PUBLISHED_KIND: &str = "published_post";
+
+/// Where drafts are kept, and how they are embedded for recall
+#[derive(Clone)]
+pub struct DraftMemory {
+    store: Arc<dyn MemoryStore + Send + Sync>,
+    embedder: Arc<dyn Embedder>,
+}
+
+impl std::fmt::Debug for DraftMemory {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DraftMemory").field("embedding_model", &self.embedder.model()).finish()
+    }
+}
+
+impl DraftMemory {
+    pub fn new(store: Arc<dyn MemoryStore + Send + Sync>, embedder: Arc<dyn Embedder>) -> Self {
+        Self { store, embedder }
+    }
+
+    async fn store(&self, kind: &str, text: &str, mut metadata: Value) -> Result<Uuid> {
+        let embedding = self.embedder.embed(text).await?;
+        if let Some(fields) = metadata.as_object_mut() {
+            fields.insert("source".to_string(), MEMORY_SOURCE.into());
+            fields.insert("kind".to_string(), kind.into());
+            fields.insert("embedding_model".to_string(), self.embedder.model().into());
+        }
+        let now = chrono::Utc::now();
+        self.store
+            .store(Memory {
+                id: Uuid::new_v4(),
+                memory_type: MemoryType::Knowledge,
+                content: text.to_string(),
+                embedding,
+                metadata,
+                created_at: now,
+                last_accessed: now,
+            })
+            .await
+    }
+
+    async fn draft(&self, id: Uuid) -> Result<Memory> {
+        let memory = self.store.retrieve(id).await?;
+        if !is_draft(&memory) {
+            anyhow::bail!("Memory {} is not a LinkedIn draft", id);
+        }
+        Ok(memory)
+    }
+
+    /// Every unpublished draft, newest first
+    async fn drafts(&self) -> Result<Vec<Memory>> {
+        const PAGE: usize = 200;
+        let mut drafts = Vec::new();
+        let mut offset = 0;
+        loop {
+            let (page, total) = self.store.list_paginated(PAGE, offset).await?;
+            let fetched = page.len();
+            drafts.extend(page.into_iter().filter(is_draft));
+            offset += fetched;
+            if fetched == 0 || offset as i64 >= total {
+                break;
+            }
+        }
+        drafts.sort_by_key(|draft| std::cmp::Reverse(draft.created_at));
+        Ok(drafts)
+    }
+}
+
+fn is_draft(memory: &Memory) -> bool {
+    memory.metadata.get("source").and_then(Value::as_str) == Some(MEMORY_SOURCE)
+        && memory.metadata.get("kind").and_then(Value::as_str) == Some(DRAFT_KIND)
+}
+
+/// Likes, comments and shares of a post, or a page's lifetime totals
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Engagement {
+    pub likes: u64,
+    pub comments: u64,
+    /// Only reported for pages
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub shares: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub impressions: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub clicks: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub engagement_rate: Option<f64>,
+}
+
+impl Engagement {
+    /// From a `socialActions/{post}` response
+    fn from_social_actions(summary: &Value) -> Self {
+        let count = |path: &[&str]| {
+            path.iter()
+                .try_fold(summary, |value, key| value.get(key))
+                .and_then(Value::as_u64)
+                .unwrap_or(0)
+        };
+        Self {
+            likes: count(&["likesSummary", "totalLikes"]),
+            comments: count(&["commentsSummary", "aggregatedTotalComments"]),
+            ..Default::default()
+        }
+    }
+
+    /// From an `organizationalEntityShareStatistics` response
+    fn from_share_statistics(response: &Value) -> Self {
+        let Some(stats) = response
+            .pointer("/elements/0/totalShareStatistics")
+            .filter(|stats| stats.is_object())
+        else {
+            return Self::default();
+        };
+        let count = |key: &str| stats.get(key).and_then(Value::as_u64);
+        Self {
+            likes: count("likeCount").unwrap_or(0),
+            comments: count("commentCount").unwrap_or(0),
+            shares: count("shareCount"),
+            impressions: count("impressionCount"),
+            clicks: count("clickCount"),
+            engagement_rate: stats.get("engagement").and_then(Value::as_f64),
+        }
+    }
+}
 
 pub struct LinkedInConnector {
     metadata: ConnectorMetadata,
     client: Client,
-    access_token: String,
+    access_token: std::sync::RwLock<String>,
+    drafts: Option<DraftMemory>,
     enabled: bool,
 }
 
@@ -25,61 +179,256 @@ impl LinkedInConnector {
             metadata: ConnectorMetadata {
                 id: "linkedin".to_string(),
                 name: "LinkedIn Integration".to_string(),
-                version: "1.0.0".to_string(),
-                description: "LinkedIn API access for profile, posts, and networking".to_string(),
+                version: "1.1.0".to_string(),
+                description: "LinkedIn post drafting and publishing, page feeds, and engagement stats".to_string(),
                 capability_level: CapabilityLevel::CloudAccess,
-                requires_approval: false,
+                requires_approval: true,
                 safety_checks: vec![
                     "LinkedIn API rate limits enforced".to_string(),
                     "OAuth token validation".to_string(),
+                    "Publishing requires confirmation".to_string(),
+                    "Feeds and stats limited to the member and pages they administer".to_string(),
                 ],
             },
             client,
-            access_token,
+            access_token: std::sync::RwLock::new(access_token),
+            drafts: None,
             enabled: true,
         })
     }
 
+    /// Keep drafts as memories in `drafts`; without it drafting is refused
+    pub fn with_drafts(mut self, drafts: DraftMemory) -> Self {
+        self.drafts = Some(drafts);
+        self
+    }
+
+    fn token(&self) -> String {
+        self.access_token.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    fn request(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        self.client
+            .request(method, format!("{}{}", API, path))
+            .bearer_auth(self.token())
+            .header("LinkedIn-Version", API_VERSION)
+            .header("X-Restli-Protocol-Version", "2.0.0")
+    }
+
+    async fn get_json(&self, path: &str) -> Result<Value> {
+        let response = self.request(reqwest::Method::GET, path).send().await?;
+        let status = response.status();
+        let body: Value = response.json().await.unwrap_or(Value::Null);
+        if !status.is_success() {
+            anyhow::bail!("LinkedIn returned {} for {}: {}", status, path, body);
+        }
+        Ok(body)
+    }
+
+    fn drafts(&self) -> Result<&DraftMemory> {
+        self.drafts
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("LinkedIn drafts need the memory store, which isn't available"))
+    }
+
     async fn get_profile(&self) -> Result<Value> {
-        let url = "https://api.linkedin.com/v2/me";
-        let response = self.client
-            .get(url)
-            .header("Authorization", format!("Bearer {}", self.access_token))
-            .send()
+        self.get_json("/v2/userinfo").await
+    }
+
+    async fn member_urn(&self) -> Result<String> {
+        let profile = self.get_profile().await?;
+        let id = profile
+            .get("sub")
+            .and_then(Value::as_str)
+            .ok_or_else(|| anyhow::anyhow!("LinkedIn profile has no member id"))?;
+        Ok(format!("urn:li:person:{}", id))
+    }
+
+    /// The member, then the organizations they administer
+    async fn owned_authors(&self) -> Result<Vec<String>> {
+        let mut authors = vec![self.member_urn().await?];
+        match self
+            .get_json("/rest/organizationAcls?q=roleAssignee&role=ADMINISTRATOR&state=APPROVED")
+            .await
+        {
+            Ok(acls) => authors.extend(administered_pages(&acls)),
+            // Tokens without the organization scopes only own the member
+            Err(e) => tracing::debug!("No LinkedIn pages available: {}", e),
+        }
+        Ok(authors)
+    }
+
+    /// `author`, or the member when none is given, if the token owns it
+    async fn owned_author(&self, author: Option<&str>) -> Result<String> {
+        let owned = self.owned_authors().await?;
+        match author {
+            None => Ok(owned[0].clone()),
+            Some(author) if owned.iter().any(|urn| urn == author) => Ok(author.to_string()),
+            Some(author) => Err(anyhow::anyhow!(
+                "{} is not the member or a page they administer ({})",
+                author,
+                owned.join(", ")
+            )),
+        }
+    }
+
+    async fn draft_post(&self, text: &str, author: Option<&str>, visibility: &str) -> Result<Value> {
+        check_post(text, visibility)?;
+        let author = self.owned_author(author).await?;
+        let drafted_at = chrono::Utc::now();
+        let id = self
+            .drafts()?
+            .store(
+                DRAFT_KIND,
+                text,
+                serde_json::json!({
+                    "author": author,
+                    "visibility": visibility,
+                    "drafted_at": drafted_at.to_rfc3339(),
+                }),
+            )
             .await?;
-        Ok(response.json().await?)
+        Ok(serde_json::json!({
+            "draft_id": id,
+            "author": author,
+            "visibility": visibility,
+            "characters": text.chars().count(),
+        }))
+    }
+
+    async fn list_drafts(&self) -> Result<Value> {
+        let drafts = self.drafts()?.drafts().await?;
+        Ok(Value::Array(
+            drafts
+                .iter()
+                .map(|draft| {
+                    serde_json::json!({
+                        "draft_id": draft.id,
+                        "author": draft.metadata.get("author"),
+                        "visibility": draft.metadata.get("visibility"),
+                        "drafted_at": draft.created_at.to_rfc3339(),
+                        "text": draft.content,
+                    })
+                })
+                .collect(),
+        ))
     }
 
-    async fn create_post(&self, text: &str, person_urn: Option<&str>) -> Result<Value> {
-        // LinkedIn API v2 for posts
-        let url = "https://api.linkedin.com/v2/ugcPosts";
-        let person_urn = person_urn.unwrap_or("urn:li:person:YOUR_PERSON_URN");
-        
+    /// Publish `text` as `author`, returning the new post's URN
+    async fn publish(&self, text: &str, author: &str, visibility: &str) -> Result<String> {
         let payload = serde_json::json!({
-            "author": person_urn,
-            "lifecycleState": "PUBLISHED",
-            "specificContent": {
-                "com.linkedin.ugc.ShareContent": {
-                    "shareCommentary": {
-                        "text": text
-                    },
-                    "shareMediaCategory": "NONE"
-                }
+            "author": author,
+            "commentary": text,
+            "visibility": visibility,
+            "distribution": {
+                "feedDistribution": "MAIN_FEED",
+                "targetEntities": [],
+                "thirdPartyDistributionChannels": []
             },
-            "visibility": {
-                "com.linkedin.ugc.MemberNetworkVisibility": "PUBLIC"
-            }
+            "lifecycleState": "PUBLISHED",
+            "isReshareDisabledByAuthor": false
         });
-        
-        let response = self.client
-            .post(url)
-            .header("Authorization", format!("Bearer {}", self.access_token))
-            .header("Content-Type", "application/json")
+        let response = self
+            .request(reqwest::Method::POST, "/rest/posts")
             .json(&payload)
             .send()
             .await?;
-        Ok(response.json().await?)
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            anyhow::bail!("LinkedIn refused the post ({}): {}", status, body);
+        }
+        response
+            .headers()
+            .get("x-restli-id")
+            .and_then(|id| id.to_str().ok())
+            .map(str::to_string)
+            .ok_or_else(|| anyhow::anyhow!("LinkedIn published the post but didn't return its id"))
+    }
+
+    async fn delete_post(&self, post_urn: &str) -> Result<()> {
+        let path = format!("/rest/posts/{}", urlencoding::encode(post_urn));
+        let response = self.request(reqwest::Method::DELETE, &path).send().await?;
+        let status = response.status();
+        // Already gone is as good as deleted
+        if !status.is_success() && status != reqwest::StatusCode::NOT_FOUND {
+            anyhow::bail!("LinkedIn refused to delete {} ({})", post_urn, status);
+        }
+        Ok(())
+    }
+
+    /// Recent posts by an owned author, newest first
+    async fn read_feed(&self, author: Option<&str>, count: usize) -> Result<Value> {
+        let author = self.owned_author(author).await?;
+        let path = format!(
+            "/rest/posts?q=author&author={}&count={}&sortBy=LAST_MODIFIED",
+            urlencoding::encode(&author),
+            count.clamp(1, MAX_FEED_POSTS)
+        );
+        let response = self.get_json(&path).await?;
+        let posts: Vec<Value> = response
+            .get("elements")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .map(|post| {
+                serde_json::json!({
+                    "post_urn": post.get("id"),
+                    "author": post.get("author"),
+                    "text": post.get("commentary"),
+                    "visibility": post.get("visibility"),
+                    "published_at": post.get("publishedAt"),
+                })
+            })
+            .collect();
+        Ok(serde_json::json!({ "author": author, "posts": posts }))
     }
+
+    async fn post_stats(&self, post_urn: &str) -> Result<Engagement> {
+        let path = format!("/rest/socialActions/{}", urlencoding::encode(post_urn));
+        Ok(Engagement::from_social_actions(&self.get_json(&path).await?))
+    }
+
+    async fn page_stats(&self, organization: &str) -> Result<Engagement> {
+        let organization = self.owned_author(Some(organization)).await?;
+        if !organization.starts_with("urn:li:organization:") {
+            anyhow::bail!("Page stats are for organizations; use post_urn for the member's posts");
+        }
+        let path = format!(
+            "/rest/organizationalEntityShareStatistics?q=organizationalEntity&organizationalEntity={}",
+            urlencoding::encode(&organization)
+        );
+        Ok(Engagement::from_share_statistics(&self.get_json(&path).await?))
+    }
+}
+
+/// Organizations an `organizationAcls` response says the member administers
+fn administered_pages(acls: &Value) -> Vec<String> {
+    acls.get("elements")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter(|acl| {
+            acl.get("role").and_then(Value::as_str) == Some("ADMINISTRATOR")
+                && acl.get("state").and_then(Value::as_str).is_none_or(|state| state == "APPROVED")
+        })
+        .filter_map(|acl| acl.get("organization").and_then(Value::as_str))
+        .map(str::to_string)
+        .collect()
+}
+
+fn check_post(text: &str, visibility: &str) -> Result<()> {
+    if text.trim().is_empty() {
+        anyhow::bail!("Post text is empty");
+    }
+    let length = text.chars().count();
+    if length > MAX_POST_CHARS {
+        anyhow::bail!("Post is {} characters; LinkedIn allows {}", length, MAX_POST_CHARS);
+    }
+    if !["PUBLIC", "CONNECTIONS", "LOGGED_IN"].contains(&visibility) {
+        anyhow::bail!("Unknown visibility '{}' (expected PUBLIC, CONNECTIONS or LOGGED_IN)", visibility);
+    }
+    Ok(())
 }
 
 #[async_trait::async_trait]
@@ -87,7 +436,7 @@ impl Connector for LinkedInConnector {
     fn metadata(&self) -> &ConnectorMetadata {
         &self.metadata
     }
-    
+
     async fn execute(
         &self,
         params: HashMap<String, String>,
@@ -95,55 +444,201 @@ impl Connector for LinkedInConnector {
     ) -> Result<ConnectorResult> {
         let action = params.get("action")
             .ok_or_else(|| anyhow::anyhow!("Missing 'action' parameter"))?;
-        
+        let author = params.get("author").map(|s| s.as_str());
+        let visibility = params.get("visibility").map(|s| s.as_str()).unwrap_or("PUBLIC");
+
         let mut result = ConnectorResult::new();
-        
+
         match action.as_str() {
             "get_profile" => {
                 let profile = self.get_profile().await?;
                 result.output = serde_json::to_string_pretty(&profile)?;
                 result.success = true;
             }
-            "create_post" => {
+            "list_pages" => {
+                let authors = self.owned_authors().await?;
+                result.output = serde_json::to_string_pretty(&authors)?;
+                result.success = true;
+            }
+            "draft_post" => {
                 let text = params.get("text").ok_or_else(|| anyhow::anyhow!("Missing text"))?;
-                let person_urn = params.get("person_urn").map(|s| s.as_str());
-                let post = self.create_post(text, person_urn).await?;
-                result.output = serde_json::to_string_pretty(&post)?;
+                let draft = self.draft_post(text, author, visibility).await?;
+                result.output = serde_json::to_string_pretty(&draft)?;
+                result.success = true;
+            }
+            "list_drafts" => {
+                let drafts = self.list_drafts().await?;
+                result.output = serde_json::to_string_pretty(&drafts)?;
+                result.success = true;
+            }
+            "publish_post" => {
+                // Safety check: a person must have approved this post
+                if !params.contains_key("confirmed") {
+                    result.errors.push("Publishing to LinkedIn requires confirmation".to_string());
+                    return Ok(result);
+                }
+                let draft = match params.get("draft_id") {
+                    Some(id) => Some(self.drafts()?.draft(id.parse().context("Invalid draft_id")?).await?),
+                    None => None,
+                };
+                let (text, author, visibility) = match &draft {
+                    Some(draft) => (
+                        draft.content.clone(),
+                        draft.metadata.get("author").and_then(Value::as_str).map(str::to_string),
+                        draft.metadata.get("visibility").and_then(Value::as_str).unwrap_or(visibility).to_string(),
+                    ),
+                    None => (
+                        params.get("text").cloned().ok_or_else(|| anyhow::anyhow!("Missing text or draft_id"))?,
+                        author.map(str::to_string),
+                        visibility.to_string(),
+                    ),
+                };
+                check_post(&text, &visibility)?;
+                let author = self.owned_author(author.as_deref()).await?;
+                let post_urn = self.publish(&text, &author, &visibility).await?;
+
+                if let Some(drafts) = &self.drafts {
+                    let published = serde_json::json!({
+                        "author": author,
+                        "visibility": visibility,
+                        "post_urn": post_urn,
+                        "published_at": chrono::Utc::now().to_rfc3339(),
+                    });
+                    if let Err(e) = drafts.store(PUBLISHED_KIND, &text, published).await {
+                        result.warnings.push(format!("Published, but not remembered: {}", e));
+                    }
+                    if let Some(draft) = &draft {
+                        if let Err(e) = drafts.store.delete(draft.id).await {
+                            result.warnings.push(format!("Published, but draft {} is still saved: {}", draft.id, e));
+                        }
+                    }
+                }
+                result.compensations.push(
+                    Compensation::new(&self.metadata.id, "delete_post", format!("Delete LinkedIn post {}", post_urn))
+                        .with_param("post_urn", post_urn.clone()),
+                );
+                result.metadata.insert("post_urn".to_string(), post_urn.clone());
+                result.output = serde_json::to_string_pretty(&serde_json::json!({
+                    "post_urn": post_urn,
+                    "author": author,
+                    "visibility": visibility,
+                }))?;
+                result.success = true;
+            }
+            "read_feed" => {
+                let count = params.get("count").map(|c| c.parse::<usize>()).transpose()?.unwrap_or(10);
+                let feed = self.read_feed(author, count).await?;
+                result.output = serde_json::to_string_pretty(&feed)?;
+                result.success = true;
+            }
+            "get_stats" => {
+                let stats = match (params.get("post_urn"), params.get("organization")) {
+                    (Some(post_urn), _) => self.post_stats(post_urn).await?,
+                    (None, Some(organization)) => self.page_stats(organization).await?,
+                    (None, None) => anyhow::bail!("Missing post_urn or organization"),
+                };
+                result.output = serde_json::to_string_pretty(&stats)?;
                 result.success = true;
             }
             _ => {
                 result.errors.push(format!("Unknown action: {}", action));
             }
         }
-        
+
         Ok(result)
     }
-    
+
     fn validate(&self, params: &HashMap<String, String>) -> Result<()> {
         if !params.contains_key("action") {
             return Err(anyhow::anyhow!("Missing required parameter: action"));
         }
         Ok(())
     }
-    
+
     fn required_params(&self) -> Vec<String> {
         vec!["action".to_string()]
     }
-    
+
+    fn actions(&self) -> Vec<String> {
+        ["get_profile", "list_pages", "draft_post", "list_drafts", "publish_post", "read_feed", "get_stats"]
+            .into_iter()
+            .map(String::from)
+            .collect()
+    }
+
     fn is_enabled(&self) -> bool {
         self.enabled
     }
-    
+
     fn safety_checks(&self) -> Vec<String> {
         self.metadata.safety_checks.clone()
     }
-    
+
     fn requires_network(&self) -> bool {
         true
     }
-    
+
     fn requires_credentials(&self) -> Vec<String> {
         vec!["linkedin_access_token".to_string()]
     }
+
+    fn update_credential(&self, key: &str, value: &str) -> Result<()> {
+        if key == "linkedin_access_token" {
+            *self.access_token.write().unwrap_or_else(|e| e.into_inner()) = value.to_string();
+            tracing::info!("LinkedIn connector token updated");
+        }
+        Ok(())
+    }
+
+    async fn compensate(&self, compensation: &Compensation, _context: &ExecutionContext) -> Result<()> {
+        match compensation.param("action")? {
+            "delete_post" => self.delete_post(compensation.param("post_urn")?).await,
+            other => Err(anyhow::anyhow!("Unknown LinkedIn compensation: {}", other)),
+        }
+    }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_publish_requires_confirmation() {
+        let connector = LinkedInConnector::new("token".to_string()).unwrap();
+        let params = HashMap::from([
+            ("action".to_string(), "publish_post".to_string()),
+            ("text".to_string(), "Hello".to_string()),
+        ]);
+        let result = connector.execute(params, &ExecutionContext::default()).await.unwrap();
+        assert!(!result.success);
+        assert!(result.errors[0].contains("requires confirmation"));
+
+        assert!(check_post(&"a".repeat(MAX_POST_CHARS + 1), "PUBLIC").is_err());
+        assert!(check_post("Hi", "EVERYONE").is_err());
+    }
+
+    #[test]
+    fn test_parses_pages_and_stats() {
+        let acls = serde_json::json!({"elements": [
+            {"organization": "urn:li:organization:1", "role": "ADMINISTRATOR", "state": "APPROVED"},
+            {"organization": "urn:li:organization:2", "role": "ANALYST", "state": "APPROVED"},
+            {"organization": "urn:li:organization:3", "role": "ADMINISTRATOR", "state": "REQUESTED"},
+        ]});
+        assert_eq!(administered_pages(&acls), vec!["urn:li:organization:1".to_string()]);
+
+        let post = serde_json::json!({
+            "likesSummary": {"totalLikes": 12},
+            "commentsSummary": {"aggregatedTotalComments": 3},
+        });
+        assert_eq!(
+            Engagement::from_social_actions(&post),
+            Engagement { likes: 12, comments: 3, ..Default::default() }
+        );
+        let page = serde_json::json!({"elements": [{"totalShareStatistics": {
+            "likeCount": 40, "commentCount": 5, "shareCount": 2,
+            "impressionCount": 1000, "clickCount": 30, "engagement": 0.077
+        }}]});
+        let stats = Engagement::from_share_statistics(&page);
+        assert_eq!((stats.likes, stats.shares, stats.impressions), (40, Some(2), Some(1000)));
+    }
+}
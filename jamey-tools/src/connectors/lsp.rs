@@ -0,0 +1,244 @@
+//! Language Server Connector
+//!
+//! Gives the model semantic answers about code — hover, definitions,
+//! references and diagnostics — from language servers such as rust-analyzer
+//! and pyright, so edits can be planned from what a symbol is rather than
+//! from text matches. A server is started the first time a file of its
+//! language is asked about and kept running for the project, so later calls
+//! attach to it instead of paying for indexing again.
+
+use crate::connector::*;
+use crate::lsp::{LspClient, LspServerConfig};
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+pub struct LspConnector {
+    metadata: ConnectorMetadata,
+    root_path: PathBuf,
+    servers: Vec<LspServerConfig>,
+    /// Running servers by language and project root
+    running: Mutex<HashMap<(String, PathBuf), Arc<LspClient>>>,
+    enabled: bool,
+}
+
+impl LspConnector {
+    pub fn new(root_path: PathBuf) -> Self {
+        Self {
+            metadata: ConnectorMetadata {
+                id: "lsp".to_string(),
+                name: "Language Server".to_string(),
+                version: "1.0.0".to_string(),
+                description: "Semantic code intelligence from language servers (rust-analyzer, pyright), results \
+                    as JSON. Lines and columns count from 1. Actions: hover, definition, references \
+                    (path, line, column); diagnostics (path); start or stop (language, or a path of that \
+                    language); servers."
+                    .to_string(),
+                capability_level: CapabilityLevel::ReadWrite,
+                requires_approval: false,
+                safety_checks: vec![
+                    "Only configured server commands are run".to_string(),
+                    "Files must be inside the root or workspace".to_string(),
+                ],
+            },
+            root_path,
+            servers: LspServerConfig::defaults(),
+            running: Mutex::new(HashMap::new()),
+            enabled: true,
+        }
+    }
+
+    /// Use `config` for its language, replacing the default server
+    pub fn with_server(mut self, config: LspServerConfig) -> Self {
+        self.servers.retain(|server| server.language != config.language);
+        self.servers.push(config);
+        self
+    }
+
+    /// The project root for this call: the workspace, which must lie inside
+    /// the connector's root, or the root itself
+    fn project_root(&self, context: &ExecutionContext) -> Result<PathBuf> {
+        let root = self.root_path.canonicalize()
+            .with_context(|| format!("Failed to canonicalize root: {}", self.root_path.display()))?;
+        match &context.workspace {
+            Some(workspace) if !workspace.root.starts_with(&root) => {
+                anyhow::bail!("Security violation: Workspace {} is outside the root directory", workspace.root.display())
+            }
+            Some(workspace) => Ok(workspace.root.clone()),
+            None => Ok(root),
+        }
+    }
+
+    /// `params["path"]` as a file inside the project
+    fn file(&self, root: &Path, params: &HashMap<String, String>, context: &ExecutionContext) -> Result<PathBuf> {
+        let path = params.get("path").ok_or_else(|| anyhow::anyhow!("Missing path"))?;
+        let file = super::full_system::sanitize_path(root, path).context("Path validation failed")?;
+        if context.workspace.as_ref().is_some_and(|w| w.is_ignored(&file)) {
+            anyhow::bail!("{} is ignored in this workspace", path);
+        }
+        Ok(file)
+    }
+
+    /// The server named by `params["language"]`, or the one for `params["path"]`
+    fn server(&self, root: &Path, params: &HashMap<String, String>, context: &ExecutionContext) -> Result<&LspServerConfig> {
+        if let Some(language) = params.get("language") {
+            return self.servers.iter()
+                .find(|server| &server.language == language)
+                .ok_or_else(|| anyhow::anyhow!("No language server configured for {}", language));
+        }
+        let file = self.file(root, params, context)?;
+        self.servers.iter()
+            .find(|server| server.handles(&file))
+            .ok_or_else(|| anyhow::anyhow!("No language server configured for {}", file.display()))
+    }
+
+    /// The running server for `config` in `root`, starting one if needed;
+    /// true when it was already running
+    async fn attach(&self, config: &LspServerConfig, root: &Path) -> Result<(Arc<LspClient>, bool)> {
+        let key = (config.language.clone(), root.to_path_buf());
+        let mut running = self.running.lock().await;
+        if let Some(client) = running.get(&key).filter(|client| client.is_running()) {
+            return Ok((client.clone(), true));
+        }
+        tracing::info!("Starting {} for {}", config.command, root.display());
+        let client = Arc::new(LspClient::start(config, root).await?);
+        running.insert(key, client.clone());
+        Ok((client, false))
+    }
+}
+
+/// `params[key]` as a line or column number
+fn position(params: &HashMap<String, String>, key: &str) -> Result<usize> {
+    let value = params.get(key).ok_or_else(|| anyhow::anyhow!("Missing {}", key))?;
+    match value.trim().parse() {
+        Ok(n) if n > 0 => Ok(n),
+        _ => Err(anyhow::anyhow!("Invalid {}: {} (lines and columns count from 1)", key, value)),
+    }
+}
+
+#[async_trait::async_trait]
+impl Connector for LspConnector {
+    fn metadata(&self) -> &ConnectorMetadata {
+        &self.metadata
+    }
+
+    async fn execute(
+        &self,
+        params: HashMap<String, String>,
+        context: &ExecutionContext,
+    ) -> Result<ConnectorResult> {
+        let action = params.get("action")
+            .ok_or_else(|| anyhow::anyhow!("Missing 'action' parameter"))?;
+
+        let mut result = ConnectorResult::new();
+        let root = self.project_root(context)?;
+        // Locations the workspace puts off limits are left out of the results
+        let hidden = |path: &str| context.workspace.as_ref().is_some_and(|w| w.is_ignored(&root.join(path)));
+
+        match action.as_str() {
+            "servers" => {
+                let running = self.running.lock().await;
+                let servers: Vec<_> = self.servers.iter().map(|server| {
+                    let roots: Vec<_> = running.iter()
+                        .filter(|((language, _), client)| language == &server.language && client.is_running())
+                        .map(|((_, root), _)| root.display().to_string())
+                        .collect();
+                    serde_json::json!({
+                        "language": server.language,
+                        "command": server.command,
+                        "extensions": server.extensions,
+                        "running_in": roots,
+                    })
+                }).collect();
+                result.output = serde_json::to_string_pretty(&servers)?;
+                result.success = true;
+            }
+            "start" => {
+                let server = self.server(&root, &params, context)?;
+                let (_, attached) = self.attach(server, &root).await?;
+                let verb = if attached { "Attached to running" } else { "Started" };
+                result.output = format!("{} {} for {}", verb, server.command, root.display());
+                result.success = true;
+            }
+            "stop" => {
+                let server = self.server(&root, &params, context)?;
+                let client = self.running.lock().await.remove(&(server.language.clone(), root.clone()));
+                match client {
+                    Some(client) => {
+                        client.shutdown().await?;
+                        result.output = format!("Stopped {} for {}", server.command, root.display());
+                    }
+                    None => result.output = format!("{} is not running for {}", server.command, root.display()),
+                }
+                result.success = true;
+            }
+            "hover" | "definition" | "references" => {
+                let file = self.file(&root, &params, context)?;
+                let (line, column) = (position(&params, "line")?, position(&params, "column")?);
+                let (client, _) = self.attach(self.server(&root, &params, context)?, &root).await?;
+                result.output = match action.as_str() {
+                    "hover" => client.hover(&file, line, column).await?
+                        .unwrap_or_else(|| "No information at this position".to_string()),
+                    "definition" => {
+                        let mut locations = client.definition(&file, line, column).await?;
+                        locations.retain(|location| !hidden(&location.path));
+                        serde_json::to_string_pretty(&locations)?
+                    }
+                    _ => {
+                        let mut locations = client.references(&file, line, column).await?;
+                        locations.retain(|location| !hidden(&location.path));
+                        serde_json::to_string_pretty(&locations)?
+                    }
+                };
+                result.success = true;
+            }
+            "diagnostics" => {
+                let file = self.file(&root, &params, context)?;
+                let (client, _) = self.attach(self.server(&root, &params, context)?, &root).await?;
+                result.output = serde_json::to_string_pretty(&client.diagnostics(&file).await?)?;
+                result.success = true;
+            }
+            _ => {
+                result.errors.push(format!("Unknown action: {}", action));
+            }
+        }
+
+        Ok(result)
+    }
+
+    fn validate(&self, params: &HashMap<String, String>) -> Result<()> {
+        if !params.contains_key("action") {
+            return Err(anyhow::anyhow!("Missing required parameter: action"));
+        }
+        Ok(())
+    }
+
+    fn required_params(&self) -> Vec<String> {
+        vec!["action".to_string()]
+    }
+
+    fn actions(&self) -> Vec<String> {
+        ["hover", "definition", "references", "diagnostics", "start", "stop", "servers"]
+            .into_iter()
+            .map(String::from)
+            .collect()
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    fn safety_checks(&self) -> Vec<String> {
+        self.metadata.safety_checks.clone()
+    }
+
+    fn requires_network(&self) -> bool {
+        false
+    }
+
+    fn requires_credentials(&self) -> Vec<String> {
+        vec![]
+    }
+}
@@ -0,0 +1,371 @@
+//! Matrix Connector
+//!
+//! A Matrix client built on matrix-sdk, enabled with the `matrix` feature.
+//! It logs in once and keeps its device and encryption keys in a store
+//! directory, so encrypted rooms keep working across restarts. It joins the
+//! rooms on its allowlist at startup and hands the runtime every text
+//! message that mentions it, or any message in a direct chat. As a connector
+//! it lets Jamey post to those rooms. Rooms not on the allowlist are
+//! ignored, and an empty allowlist ignores everything.
+
+use crate::connector::*;
+use anyhow::{Context, Result};
+use jamey_core::secure_logging::Masked;
+use matrix_sdk::config::SyncSettings;
+use matrix_sdk::matrix_auth::MatrixSession;
+use matrix_sdk::room::Room;
+use matrix_sdk::ruma::events::room::message::{MessageType, OriginalSyncRoomMessageEvent, RoomMessageEventContent};
+use matrix_sdk::ruma::{OwnedRoomId, OwnedRoomOrAliasId, OwnedUserId, RoomId};
+use matrix_sdk::{Client, RoomState};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+use tokio::sync::mpsc;
+
+/// Login details saved next to the crypto store, so restarts reuse the device
+const SESSION_FILE: &str = "session.json";
+
+/// How the client logs in and where it keeps its state
+#[derive(Clone)]
+pub struct MatrixConfig {
+    pub homeserver: String,
+    /// Full user ID such as `@jamey:example.org`, or just the localpart
+    pub user: String,
+    /// Only used when there is no saved login yet
+    pub password: Option<String>,
+    /// Room IDs (`!abc:example.org`) or aliases (`#ops:example.org`) to join and answer in
+    pub rooms: Vec<String>,
+    pub store_dir: PathBuf,
+    /// Encrypts the crypto store at rest when set
+    pub store_passphrase: Option<String>,
+}
+
+impl std::fmt::Debug for MatrixConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MatrixConfig")
+            .field("homeserver", &self.homeserver)
+            .field("user", &self.user)
+            .field("password", &Masked::opt(self.password.as_deref()))
+            .field("rooms", &self.rooms)
+            .field("store_dir", &self.store_dir)
+            .field("store_passphrase", &Masked::opt(self.store_passphrase.as_deref()))
+            .finish()
+    }
+}
+
+/// A text message addressed to Jamey
+#[derive(Debug, Clone)]
+pub struct IncomingMessage {
+    pub room_id: OwnedRoomId,
+    pub sender: OwnedUserId,
+    /// Message text with a leading mention of Jamey removed
+    pub body: String,
+}
+
+/// Logged-in Matrix client; clones share the client and the joined rooms
+#[derive(Clone)]
+pub struct MatrixConnector {
+    metadata: ConnectorMetadata,
+    client: Client,
+    rooms: Vec<String>,
+    /// IDs of the allowlisted rooms that were joined
+    joined: Arc<RwLock<HashSet<OwnedRoomId>>>,
+}
+
+impl MatrixConnector {
+    /// Log in, or restore the saved login, and join the allowlisted rooms
+    pub async fn connect(config: MatrixConfig) -> Result<Self> {
+        std::fs::create_dir_all(&config.store_dir)
+            .with_context(|| format!("Failed to create {}", config.store_dir.display()))?;
+        let client = Client::builder()
+            .homeserver_url(&config.homeserver)
+            .sqlite_store(&config.store_dir, config.store_passphrase.as_deref())
+            .build()
+            .await
+            .context("Failed to create Matrix client")?;
+
+        let session_path = config.store_dir.join(SESSION_FILE);
+        match std::fs::read_to_string(&session_path) {
+            Ok(saved) => {
+                let session: MatrixSession = serde_json::from_str(&saved)
+                    .with_context(|| format!("Invalid Matrix session in {}", session_path.display()))?;
+                client.restore_session(session).await.context("Failed to restore Matrix session")?;
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                let password = config
+                    .password
+                    .as_deref()
+                    .ok_or_else(|| anyhow::anyhow!("A Matrix password is needed for the first login"))?;
+                client
+                    .matrix_auth()
+                    .login_username(&config.user, password)
+                    .initial_device_display_name("Jamey")
+                    .send()
+                    .await
+                    .context("Matrix login failed")?;
+                let session = client
+                    .matrix_auth()
+                    .session()
+                    .ok_or_else(|| anyhow::anyhow!("Matrix login returned no session"))?;
+                std::fs::write(&session_path, serde_json::to_string(&session)?)
+                    .with_context(|| format!("Failed to save {}", session_path.display()))?;
+            }
+            Err(e) => return Err(e).with_context(|| format!("Failed to read {}", session_path.display())),
+        }
+
+        let connector = Self {
+            metadata: ConnectorMetadata {
+                id: "matrix".to_string(),
+                name: "Matrix".to_string(),
+                version: "1.0.0".to_string(),
+                description: "Send Matrix messages to allowed rooms".to_string(),
+                capability_level: CapabilityLevel::WebAccess,
+                requires_approval: false,
+                safety_checks: vec![
+                    "Room allowlist".to_string(),
+                    "Password and access token never included in output".to_string(),
+                ],
+            },
+            client,
+            rooms: config.rooms,
+            joined: Arc::new(RwLock::new(HashSet::new())),
+        };
+        for room in connector.rooms.clone() {
+            if let Err(e) = connector.join_room(&room).await {
+                tracing::warn!("Could not join Matrix room {}: {}", room, e);
+            }
+        }
+        Ok(connector)
+    }
+
+    pub fn is_allowed(&self, room_id: &RoomId) -> bool {
+        self.joined.read().unwrap_or_else(|e| e.into_inner()).contains(room_id)
+    }
+
+    /// Join an allowlisted room by ID or alias
+    pub async fn join_room(&self, room: &str) -> Result<OwnedRoomId> {
+        if !self.rooms.iter().any(|r| r == room) {
+            anyhow::bail!("Room {} is not on the Matrix allowlist", room);
+        }
+        let target = OwnedRoomOrAliasId::try_from(room).with_context(|| format!("Invalid room: {}", room))?;
+        let joined = self.client.join_room_by_id_or_alias(&target, &[]).await?;
+        let room_id = joined.room_id().to_owned();
+        self.joined.write().unwrap_or_else(|e| e.into_inner()).insert(room_id.clone());
+        tracing::info!("Joined Matrix room {} ({})", room, room_id);
+        Ok(room_id)
+    }
+
+    fn room(&self, room_id: &RoomId) -> Result<Room> {
+        if !self.is_allowed(room_id) {
+            anyhow::bail!("Room {} is not on the Matrix allowlist", room_id);
+        }
+        self.client
+            .get_room(room_id)
+            .ok_or_else(|| anyhow::anyhow!("Not a member of Matrix room {}", room_id))
+    }
+
+    /// Post `text` to a room; encrypted rooms are encrypted for transparently
+    pub async fn send_message(&self, room_id: &RoomId, text: &str) -> Result<()> {
+        self.room(room_id)?.send(RoomMessageEventContent::text_plain(text)).await?;
+        Ok(())
+    }
+
+    /// Show or clear "Jamey is typing..." in a room
+    pub async fn set_typing(&self, room_id: &RoomId, typing: bool) -> Result<()> {
+        self.room(room_id)?.typing_notice(typing).await?;
+        Ok(())
+    }
+
+    /// Send messages addressed to Jamey in joined rooms to `tx`, and keep
+    /// syncing until the connection fails. Messages sent before the call are
+    /// skipped.
+    pub async fn listen(&self, tx: mpsc::UnboundedSender<IncomingMessage>) -> Result<()> {
+        let response = self.client.sync_once(SyncSettings::default()).await?;
+        let user_id = self
+            .client
+            .user_id()
+            .ok_or_else(|| anyhow::anyhow!("Matrix client is not logged in"))?
+            .to_owned();
+        let display_name = self.client.account().get_display_name().await.ok().flatten();
+
+        let connector = self.clone();
+        self.client.add_event_handler(move |event: OriginalSyncRoomMessageEvent, room: Room| {
+            let (connector, tx, user_id, display_name) =
+                (connector.clone(), tx.clone(), user_id.clone(), display_name.clone());
+            async move {
+                if room.state() != RoomState::Joined || event.sender == user_id || !connector.is_allowed(room.room_id()) {
+                    return;
+                }
+                let MessageType::Text(text) = event.content.msgtype else {
+                    return;
+                };
+                let direct = room.is_direct().await.unwrap_or(false);
+                let names = mention_names(&user_id, display_name.as_deref());
+                let body = match strip_mention(&text.body, &names) {
+                    Some(body) => body,
+                    None if direct => text.body.trim().to_string(),
+                    None if contains_mention(&text.body, &names) => text.body.trim().to_string(),
+                    None => return,
+                };
+                let _ = tx.send(IncomingMessage {
+                    room_id: room.room_id().to_owned(),
+                    sender: event.sender,
+                    body,
+                });
+            }
+        });
+
+        self.client.sync(SyncSettings::default().token(response.next_batch)).await?;
+        Ok(())
+    }
+}
+
+/// Ways a message can name Jamey: the full user ID, the localpart and the
+/// display name
+fn mention_names(user_id: &OwnedUserId, display_name: Option<&str>) -> Vec<String> {
+    let mut names = vec![user_id.to_string(), user_id.localpart().to_string()];
+    if let Some(name) = display_name.filter(|n| !n.trim().is_empty()) {
+        names.push(name.trim().to_string());
+    }
+    names
+}
+
+/// The rest of `body` when it starts by addressing one of `names`, as
+/// clients do when completing a mention ("Jamey: hi", "@jamey hi")
+pub fn strip_mention(body: &str, names: &[String]) -> Option<String> {
+    let trimmed = body.trim_start();
+    // "Jamey Bot: hi" should lose the whole display name, not just "jamey"
+    let mut names: Vec<&String> = names.iter().collect();
+    names.sort_by_key(|name| std::cmp::Reverse(name.len()));
+    names.into_iter().find_map(|name| {
+        let rest = trimmed.strip_prefix('@').unwrap_or(trimmed);
+        let head = rest.get(..name.trim_start_matches('@').len())?;
+        if !head.eq_ignore_ascii_case(name.trim_start_matches('@')) {
+            return None;
+        }
+        let tail = &rest[head.len()..];
+        if tail.chars().next().is_some_and(|c| c.is_alphanumeric()) {
+            return None;
+        }
+        Some(tail.trim_start_matches([':', ',']).trim().to_string())
+    })
+}
+
+/// Whether one of `names` appears anywhere in `body` as a whole word
+pub fn contains_mention(body: &str, names: &[String]) -> bool {
+    let lower = body.to_lowercase();
+    names.iter().any(|name| {
+        let name = name.to_lowercase();
+        lower.match_indices(&name).any(|(i, _)| {
+            let before = lower[..i].chars().next_back();
+            let after = lower[i + name.len()..].chars().next();
+            !before.is_some_and(|c| c.is_alphanumeric()) && !after.is_some_and(|c| c.is_alphanumeric())
+        })
+    })
+}
+
+#[async_trait::async_trait]
+impl Connector for MatrixConnector {
+    fn metadata(&self) -> &ConnectorMetadata {
+        &self.metadata
+    }
+
+    async fn execute(
+        &self,
+        params: HashMap<String, String>,
+        _context: &ExecutionContext,
+    ) -> Result<ConnectorResult> {
+        let action = params.get("action")
+            .ok_or_else(|| anyhow::anyhow!("Missing 'action' parameter"))?;
+
+        let mut result = ConnectorResult::new();
+        match action.as_str() {
+            "send_message" => {
+                let room = params.get("room_id").ok_or_else(|| anyhow::anyhow!("Missing room_id"))?;
+                let text = params.get("text").ok_or_else(|| anyhow::anyhow!("Missing text"))?;
+                let room_id = RoomId::parse(room).with_context(|| format!("Invalid room_id: {}", room))?;
+                if !self.is_allowed(&room_id) {
+                    result.errors.push(format!("Room {} is not on the Matrix allowlist", room_id));
+                    return Ok(result);
+                }
+                self.send_message(&room_id, text).await?;
+                result.output = format!("Sent message to room {}", room_id);
+                result.success = true;
+            }
+            "join_room" => {
+                let room = params.get("room").ok_or_else(|| anyhow::anyhow!("Missing room"))?;
+                let room_id = self.join_room(room).await?;
+                result.output = format!("Joined room {}", room_id);
+                result.success = true;
+            }
+            "list_rooms" => {
+                let joined: Vec<String> = self
+                    .joined
+                    .read()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .iter()
+                    .map(|id| id.to_string())
+                    .collect();
+                result.output = serde_json::to_string(&joined)?;
+                result.success = true;
+            }
+            _ => {
+                result.errors.push(format!("Unknown action: {}", action));
+            }
+        }
+
+        Ok(result)
+    }
+
+    fn validate(&self, params: &HashMap<String, String>) -> Result<()> {
+        if !params.contains_key("action") {
+            return Err(anyhow::anyhow!("Missing required parameter: action"));
+        }
+        Ok(())
+    }
+
+    fn required_params(&self) -> Vec<String> {
+        vec!["action".to_string()]
+    }
+
+    fn actions(&self) -> Vec<String> {
+        ["send_message", "join_room", "list_rooms"].into_iter().map(String::from).collect()
+    }
+
+    fn is_enabled(&self) -> bool {
+        true
+    }
+
+    fn safety_checks(&self) -> Vec<String> {
+        self.metadata.safety_checks.clone()
+    }
+
+    fn requires_network(&self) -> bool {
+        true
+    }
+
+    fn requires_credentials(&self) -> Vec<String> {
+        vec!["matrix_password".to_string()]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mentions() {
+        let user_id = OwnedUserId::try_from("@jamey:example.org").unwrap();
+        let names = mention_names(&user_id, Some("Jamey Bot"));
+        assert_eq!(names, vec!["@jamey:example.org", "jamey", "Jamey Bot"]);
+
+        assert_eq!(strip_mention("Jamey Bot: what's up?", &names).as_deref(), Some("what's up?"));
+        assert_eq!(strip_mention("@jamey:example.org hello", &names).as_deref(), Some("hello"));
+        assert_eq!(strip_mention("jamey, deploy it", &names).as_deref(), Some("deploy it"));
+        assert_eq!(strip_mention("jameson: hi", &names), None);
+        assert_eq!(strip_mention("hello everyone", &names), None);
+
+        assert!(contains_mention("can Jamey look at this?", &names));
+        assert!(!contains_mention("ask jameson instead", &names));
+    }
+}
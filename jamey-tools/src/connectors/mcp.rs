@@ -127,6 +127,13 @@ impl Connector for MCPConnector {
         vec!["action".to_string()]
     }
     
+    fn actions(&self) -> Vec<String> {
+        ["list_resources", "read_resource", "call_tool"]
+            .into_iter()
+            .map(String::from)
+            .collect()
+    }
+    
     fn is_enabled(&self) -> bool {
         self.enabled
     }
@@ -8,6 +8,18 @@
 //! - LinkedIn integration
 //! - Agent orchestration
 //! - MCP protocol
+//! - Git repository analysis
+//! - Code search
+//! - Language servers
+//! - Test runs
+//! - Terminal sessions
+//! - Sandboxed code execution
+//! - Calculations
+//! - Hardware inventory
+//! - Session scratchpads
+//! - Webhooks
+//! - Telegram bots
+//! - Matrix rooms (with the `matrix` feature)
 //! - Full system access
 
 pub mod system_admin;
@@ -18,15 +30,41 @@ pub mod linkedin;
 pub mod agent_orchestration;
 pub mod mcp;
 pub mod full_system;
+pub mod git;
+pub mod code_search;
+pub mod lsp;
+pub mod test_runner;
+pub mod terminal;
+pub mod code_interpreter;
+pub mod calculator;
+pub mod hardware;
+pub mod scratchpad;
 pub mod iot;
+pub mod webhook;
+pub mod telegram;
+#[cfg(feature = "matrix")]
+pub mod matrix;
 
 pub use system_admin::SystemAdminConnector;
 pub use self_improve::SelfImproveConnector;
-pub use network_web::NetworkWebConnector;
+pub use network_web::{NetworkWebConnector, SearchHit};
 pub use github::GitHubConnector;
 pub use linkedin::LinkedInConnector;
 pub use agent_orchestration::AgentOrchestrationConnector;
 pub use mcp::MCPConnector;
 pub use full_system::FullSystemConnector;
+pub use git::GitConnector;
+pub use code_search::CodeSearchConnector;
+pub use lsp::LspConnector;
+pub use test_runner::TestRunnerConnector;
+pub use terminal::TerminalConnector;
+pub use code_interpreter::CodeInterpreterConnector;
+pub use calculator::CalculatorConnector;
+pub use hardware::HardwareConnector;
+pub use scratchpad::ScratchpadConnector;
 pub use iot::IoTConnector;
+pub use telegram::TelegramConnector;
+#[cfg(feature = "matrix")]
+pub use matrix::MatrixConnector;
+pub use webhook::{HookTarget, InboundHook, OutboundHook, WebhookConnector};
 
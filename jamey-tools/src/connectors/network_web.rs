@@ -1,12 +1,15 @@
 //! Network & Web Access Connector
 //!
-//! Provides web search, downloads, and browser automation
+//! Provides web search, downloads, and browser automation. Downloads go
+//! through a [`DownloadManager`] and stay quarantined until approved.
 
 use crate::connector::*;
+use crate::downloads::{DownloadConfig, DownloadManager, DownloadRequest};
 use reqwest::{Client, ClientBuilder};
 use std::collections::HashMap;
 use std::path::PathBuf;
 use anyhow::{Result, Context};
+use serde::{Deserialize, Serialize};
 use urlencoding::encode;
 use std::net::IpAddr;
 
@@ -23,7 +26,7 @@ use std::net::IpAddr;
 /// ```
 /// validate_url("https://example.com")?;
 /// ```
-fn validate_url(url: &str) -> Result<()> {
+pub(crate) fn validate_url(url: &str) -> Result<()> {
     let parsed = url::Url::parse(url)
         .context("Invalid URL format")?;
     
@@ -110,10 +113,84 @@ fn is_private_ip(ip: &IpAddr) -> bool {
     }
 }
 
+/// One organic result from a web search
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SearchHit {
+    pub title: String,
+    pub url: String,
+    pub snippet: String,
+}
+
+/// Pull the organic results out of a DuckDuckGo HTML results page, resolving
+/// its redirect links to the target URL. Ads are skipped.
+pub fn parse_search_results(html: &str) -> Vec<SearchHit> {
+    let mut hits = Vec::new();
+    for block in html.split("class=\"result__a\"").skip(1) {
+        let Some(href) = attribute(block, "href") else { continue };
+        let Some(url) = resolve_result_url(&href) else { continue };
+        let title = element_text(block);
+        let snippet = block
+            .find("class=\"result__snippet\"")
+            .map(|i| element_text(&block[i..]))
+            .unwrap_or_default();
+        if !title.is_empty() && !hits.iter().any(|h: &SearchHit| h.url == url) {
+            hits.push(SearchHit { title, url, snippet });
+        }
+    }
+    hits
+}
+
+fn attribute(tag: &str, name: &str) -> Option<String> {
+    let start = tag.find(&format!("{}=\"", name))? + name.len() + 2;
+    let end = tag[start..].find('"')?;
+    Some(decode_entities(&tag[start..start + end]))
+}
+
+/// Text between the end of the current tag and the next `</a>`
+fn element_text(html: &str) -> String {
+    let Some(open) = html.find('>') else { return String::new() };
+    let inner = &html[open + 1..];
+    let inner = &inner[..inner.find("</a>").unwrap_or(inner.len())];
+    let mut text = String::new();
+    let mut in_tag = false;
+    for c in inner.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' => in_tag = false,
+            c if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    decode_entities(&text).split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn decode_entities(text: &str) -> String {
+    text.replace("&quot;", "\"")
+        .replace("&#x27;", "'")
+        .replace("&#39;", "'")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&nbsp;", " ")
+        .replace("&amp;", "&")
+}
+
+fn resolve_result_url(href: &str) -> Option<String> {
+    let href = if href.starts_with("//") { format!("https:{}", href) } else { href.to_string() };
+    let parsed = url::Url::parse(&href).ok()?;
+    if parsed.host_str().is_some_and(|h| h.ends_with("duckduckgo.com")) {
+        // Ads go through /y.js; organic results through /l/?uddg=<target>
+        return parsed
+            .query_pairs()
+            .find(|(key, _)| key == "uddg")
+            .map(|(_, target)| target.into_owned());
+    }
+    Some(href)
+}
+
 pub struct NetworkWebConnector {
     metadata: ConnectorMetadata,
     client: Client,
-    download_dir: PathBuf,
+    downloads: DownloadManager,
     enabled: bool,
     search_api_key: Option<String>,
 }
@@ -137,15 +214,23 @@ impl NetworkWebConnector {
                 safety_checks: vec![
                     "Rate limiting enforced".to_string(),
                     "Download size limits".to_string(),
+                    "SHA-256 verification of downloads".to_string(),
+                    "Downloads quarantined until approved".to_string(),
                 ],
             },
+            downloads: DownloadManager::new(client.clone(), DownloadConfig::new(download_dir)),
             client,
-            download_dir,
             enabled: true,
             search_api_key,
         })
     }
 
+    /// Replace the default download limits and quarantine location
+    pub fn with_download_config(mut self, config: DownloadConfig) -> Self {
+        self.downloads = DownloadManager::new(self.client.clone(), config);
+        self
+    }
+
     async fn web_search(&self, query: &str) -> Result<String> {
         // Use DuckDuckGo HTML search (no API key needed)
         let url = format!("https://html.duckduckgo.com/html/?q={}", encode(query));
@@ -164,26 +249,6 @@ impl NetworkWebConnector {
         Ok(html)
     }
 
-    async fn download_file(&self, url: &str, filename: Option<String>) -> Result<String> {
-        // Validate URL before downloading
-        validate_url(url)
-            .context("Download URL validation failed")?;
-        
-        tracing::warn!("Downloading file from: {}", url);
-        let response = self.client.get(url).send().await
-            .context("Failed to download file")?;
-        let bytes = response.bytes().await?;
-        
-        let filename = filename.unwrap_or_else(|| {
-            url.split('/').last().unwrap_or("download").to_string()
-        });
-        let filepath = self.download_dir.join(&filename);
-        
-        tokio::fs::write(&filepath, &bytes).await
-            .context("Failed to write downloaded file")?;
-        Ok(filepath.to_string_lossy().to_string())
-    }
-
     async fn fetch_url(&self, url: &str) -> Result<String> {
         // Validate URL before fetching
         validate_url(url)
@@ -205,7 +270,7 @@ impl Connector for NetworkWebConnector {
     async fn execute(
         &self,
         params: HashMap<String, String>,
-        _context: &ExecutionContext,
+        context: &ExecutionContext,
     ) -> Result<ConnectorResult> {
         let action = params.get("action")
             .ok_or_else(|| anyhow::anyhow!("Missing 'action' parameter"))?;
@@ -216,8 +281,13 @@ impl Connector for NetworkWebConnector {
             "web_search" => {
                 let query = params.get("query")
                     .ok_or_else(|| anyhow::anyhow!("Missing 'query' parameter"))?;
+                context.partial_output.push_line(&format!("Searching the web for {}", query));
                 let search_results = self.web_search(query).await?;
-                result.output = search_results;
+                // format=json returns parsed hits instead of the raw page
+                result.output = match params.get("format").map(String::as_str) {
+                    Some("json") => serde_json::to_string_pretty(&parse_search_results(&search_results))?,
+                    _ => search_results,
+                };
                 result.success = true;
                 result.network_requests.push(NetworkRequest {
                     url: format!("https://html.duckduckgo.com/html/?q={}", encode(query)),
@@ -229,11 +299,36 @@ impl Connector for NetworkWebConnector {
             "download" => {
                 let url = params.get("url")
                     .ok_or_else(|| anyhow::anyhow!("Missing 'url' parameter"))?;
-                let filename = params.get("filename").cloned();
-                let filepath = self.download_file(url, filename).await?;
-                result.output = format!("Downloaded to: {}", filepath);
+                validate_url(url)
+                    .context("Download URL validation failed")?;
+
+                let request = DownloadRequest {
+                    url: url.clone(),
+                    filename: params.get("filename").cloned(),
+                    expected_sha256: params.get("sha256").cloned(),
+                    max_bytes: params.get("max_bytes")
+                        .map(|v| v.parse())
+                        .transpose()
+                        .context("Invalid 'max_bytes' parameter")?,
+                };
+                context.partial_output.push_line(&format!("Downloading {}", url));
+                let record = self.downloads.download(&request).await?;
+                let quarantined = self.downloads.config().quarantine_dir
+                    .join(&record.id)
+                    .join(&record.filename);
+
+                result.output = serde_json::to_string_pretty(&record)?;
                 result.success = true;
-                result.files_accessed.push(filepath.clone());
+                result.warnings.extend(record.warnings.iter().cloned());
+                result.warnings.push(format!(
+                    "Download {} is quarantined; use approve_download to move it into the workspace",
+                    record.id
+                ));
+                result.files_accessed.push(quarantined.to_string_lossy().to_string());
+                result.compensations.push(
+                    Compensation::new(&self.metadata.id, "reject_download", format!("Delete download {}", record.id))
+                        .with_param("id", record.id.clone()),
+                );
                 result.network_requests.push(NetworkRequest {
                     url: url.clone(),
                     method: "GET".to_string(),
@@ -241,9 +336,38 @@ impl Connector for NetworkWebConnector {
                     timestamp: chrono::Utc::now(),
                 });
             }
+            "list_downloads" => {
+                let records = self.downloads.list_quarantined().await?;
+                result.output = serde_json::to_string_pretty(&records)?;
+                result.success = true;
+            }
+            "approve_download" => {
+                let id = params.get("id")
+                    .ok_or_else(|| anyhow::anyhow!("Missing 'id' parameter"))?;
+                if !params.contains_key("confirmed") {
+                    result.errors.push("Releasing a quarantined download requires explicit confirmation".to_string());
+                    return Ok(result);
+                }
+                let path = self.downloads.approve(id).await?;
+                result.output = format!("Approved download moved to: {}", path.display());
+                result.success = true;
+                result.files_accessed.push(path.to_string_lossy().to_string());
+                result.compensations.push(
+                    Compensation::new(&self.metadata.id, "delete_file", format!("Delete {}", path.display()))
+                        .with_param("path", path.to_string_lossy()),
+                );
+            }
+            "reject_download" => {
+                let id = params.get("id")
+                    .ok_or_else(|| anyhow::anyhow!("Missing 'id' parameter"))?;
+                self.downloads.reject(id).await?;
+                result.output = format!("Rejected and deleted download {}", id);
+                result.success = true;
+            }
             "fetch_url" => {
                 let url = params.get("url")
                     .ok_or_else(|| anyhow::anyhow!("Missing 'url' parameter"))?;
+                context.partial_output.push_line(&format!("Fetching {}", url));
                 let content = self.fetch_url(url).await?;
                 result.output = content;
                 result.success = true;
@@ -280,6 +404,16 @@ impl Connector for NetworkWebConnector {
         vec!["action".to_string()]
     }
     
+    fn actions(&self) -> Vec<String> {
+        [
+            "web_search", "download", "list_downloads", "approve_download", "reject_download",
+            "fetch_url", "browser_action",
+        ]
+            .into_iter()
+            .map(String::from)
+            .collect()
+    }
+    
     fn is_enabled(&self) -> bool {
         self.enabled
     }
@@ -295,5 +429,47 @@ impl Connector for NetworkWebConnector {
     fn requires_credentials(&self) -> Vec<String> {
         vec![] // Web search can work without API key
     }
+
+    async fn compensate(&self, compensation: &Compensation, _context: &ExecutionContext) -> Result<()> {
+        match compensation.param("action")? {
+            "reject_download" => {
+                self.downloads.reject(compensation.param("id")?).await?;
+                Ok(())
+            }
+            "delete_file" => {
+                // Only released downloads, which live in the workspace
+                let path = PathBuf::from(compensation.param("path")?);
+                if !path.starts_with(&self.downloads.config().workspace_dir) {
+                    anyhow::bail!("{} is not in the download workspace", path.display());
+                }
+                tokio::fs::remove_file(&path).await
+                    .with_context(|| format!("Failed to delete {}", path.display()))?;
+                tracing::info!("Released download deleted: {}", path.display());
+                Ok(())
+            }
+            other => anyhow::bail!("Unknown compensation: {}", other),
+        }
+    }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_search_results() {
+        let html = r#"
+            <div class="result results_links"><a rel="nofollow" class="result__a" href="//duckduckgo.com/l/?uddg=https%3A%2F%2Fwww.rust%2Dlang.org%2F&amp;rut=abc">Rust <b>Programming</b> Language</a>
+            <a class="result__snippet" href="//duckduckgo.com/l/?uddg=x">A language empowering everyone to build reliable &amp; efficient software.</a></div>
+            <div class="result result--ad"><a rel="nofollow" class="result__a" href="https://duckduckgo.com/y.js?ad_provider=x">Buy Rust</a></div>
+            <div class="result"><a rel="nofollow" class="result__a" href="https://doc.rust-lang.org/book/">The Book</a></div>
+        "#;
+        let hits = parse_search_results(html);
+        assert_eq!(hits.len(), 2);
+        assert_eq!(hits[0].url, "https://www.rust-lang.org/");
+        assert_eq!(hits[0].title, "Rust Programming Language");
+        assert_eq!(hits[0].snippet, "A language empowering everyone to build reliable & efficient software.");
+        assert_eq!(hits[1].url, "https://doc.rust-lang.org/book/");
+        assert!(hits[1].snippet.is_empty());
+    }
+}
@@ -0,0 +1,155 @@
+//! Scratchpad Connector
+//!
+//! Gives the model a [`Scratchpad`] to pass intermediate results between
+//! the steps of a workflow, keyed by the calling session. Entries expire on
+//! their own and never reach long-term memory. It only touches the
+//! session's own short-lived state, so it sits at the lowest capability
+//! level.
+
+use crate::connector::*;
+use crate::scratchpad::{Scratchpad, ValueType};
+use anyhow::Result;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+pub struct ScratchpadConnector {
+    metadata: ConnectorMetadata,
+    scratchpad: Arc<Scratchpad>,
+    enabled: bool,
+}
+
+impl ScratchpadConnector {
+    pub fn new(scratchpad: Arc<Scratchpad>) -> Self {
+        Self {
+            metadata: ConnectorMetadata {
+                id: "scratchpad".to_string(),
+                name: "Scratchpad".to_string(),
+                version: "1.0.0".to_string(),
+                description: "Short-lived notes for this session, for handing results between steps of a \
+                    task; use memory for anything worth keeping. Actions: set (key, value, type: string, \
+                    number, boolean or json; ttl_secs), get (key, optional type), delete (key), list, clear."
+                    .to_string(),
+                capability_level: CapabilityLevel::ReadOnly,
+                requires_approval: false,
+                safety_checks: vec![
+                    "Entries are visible only to the session that wrote them".to_string(),
+                    "Entries expire and are size-limited".to_string(),
+                ],
+            },
+            scratchpad,
+            enabled: true,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Connector for ScratchpadConnector {
+    fn metadata(&self) -> &ConnectorMetadata {
+        &self.metadata
+    }
+
+    async fn execute(
+        &self,
+        params: HashMap<String, String>,
+        context: &ExecutionContext,
+    ) -> Result<ConnectorResult> {
+        let action = params.get("action")
+            .ok_or_else(|| anyhow::anyhow!("Missing 'action' parameter"))?;
+        let session = context.session_id.as_str();
+        let key = || params.get("key").map(String::as_str).ok_or_else(|| anyhow::anyhow!("Missing key"));
+        let value_type = || -> Result<Option<ValueType>> {
+            params.get("type").map(|t| t.parse().map_err(anyhow::Error::msg)).transpose()
+        };
+
+        let mut result = ConnectorResult::new();
+        let outcome = match action.as_str() {
+            "set" => {
+                let key = key()?;
+                let value = params.get("value").ok_or_else(|| anyhow::anyhow!("Missing value"))?;
+                let value_type = value_type()?.unwrap_or(ValueType::String);
+                let ttl = match params.get("ttl_secs") {
+                    Some(secs) => Some(Duration::from_secs(
+                        secs.parse().map_err(|_| anyhow::anyhow!("Invalid ttl_secs: {}", secs))?,
+                    )),
+                    None => None,
+                };
+                match value_type.parse(value) {
+                    Ok(value) => self.scratchpad
+                        .set_value(session, key, value, value_type, ttl)
+                        .await
+                        .map(|entry| format!("Stored {} '{}' until {}", value_type, key, entry.expires_at.to_rfc3339())),
+                    Err(e) => Err(e),
+                }
+            }
+            "get" => {
+                let key = key()?;
+                let entry = match value_type()? {
+                    Some(expected) => self.scratchpad.get_typed(session, key, expected).await,
+                    None => self.scratchpad.entry(session, key).await,
+                };
+                match entry {
+                    Ok(Some(entry)) => Ok(serde_json::to_string(&entry)?),
+                    Ok(None) => Ok(format!("No entry named '{}'", key)),
+                    Err(e) => Err(e),
+                }
+            }
+            "delete" => {
+                let key = key()?;
+                self.scratchpad.delete(session, key).await.map(|deleted| match deleted {
+                    true => format!("Deleted '{}'", key),
+                    false => format!("No entry named '{}'", key),
+                })
+            }
+            "list" => match self.scratchpad.list(session).await {
+                Ok(keys) => Ok(serde_json::to_string(&keys)?),
+                Err(e) => Err(e),
+            },
+            "clear" => self.scratchpad.clear(session).await.map(|n| format!("Cleared {} entries", n)),
+            _ => {
+                result.errors.push(format!("Unknown action: {}", action));
+                return Ok(result);
+            }
+        };
+
+        match outcome {
+            Ok(output) => {
+                result.output = output;
+                result.success = true;
+            }
+            Err(e) => result.errors.push(e.to_string()),
+        }
+        Ok(result)
+    }
+
+    fn validate(&self, params: &HashMap<String, String>) -> Result<()> {
+        if !params.contains_key("action") {
+            return Err(anyhow::anyhow!("Missing required parameter: action"));
+        }
+        Ok(())
+    }
+
+    fn required_params(&self) -> Vec<String> {
+        vec!["action".to_string()]
+    }
+
+    fn actions(&self) -> Vec<String> {
+        ["set", "get", "delete", "list", "clear"].iter().map(|a| a.to_string()).collect()
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    fn safety_checks(&self) -> Vec<String> {
+        self.metadata.safety_checks.clone()
+    }
+
+    fn requires_network(&self) -> bool {
+        false
+    }
+
+    fn requires_credentials(&self) -> Vec<String> {
+        vec![]
+    }
+}
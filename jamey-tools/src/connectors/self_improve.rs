@@ -1,18 +1,115 @@
 //! Self-Improvement Connector
 //! 
-//! Provides read and modify source code capabilities with automatic backups
+//! Provides read and modify source code capabilities with automatic backups,
+//! plus a proposal workflow that lands changes on a git branch and opens a
+//! pull request instead of editing the running binary in place.
+//!
+//! Workflow: `propose_change` -> `create_branch` -> `apply_proposal` ->
+//! `run_tests` -> `open_pr` (or `run_workflow` for all steps at once).
+//! Every step is recorded on the proposal and emitted to the `audit`
+//! tracing target.
+//!
+//! The proposal's branch is checked out in a `git worktree` of its own, so
+//! edits and test runs never touch the checkout the runtime is working in.
+//! The worktree is removed after each action and added back from the branch
+//! by the next one.
 
 use crate::connector::*;
+use crate::connectors::github::GitHubConnector;
+use crate::path_policy::PathPolicy;
+use crate::sandbox::ExecutionSandbox;
 use crate::system::SelfModifyTool;
+use crate::test_runner::{TestFramework, TestReport, TestRequest, TestRunnerTool};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::path::PathBuf;
-use anyhow::Result;
+use std::path::{Component, Path, PathBuf};
+use std::process::Output;
+use std::time::Duration;
+use tokio::process::Command;
+use tokio::sync::RwLock;
+use anyhow::{Context, Result};
+
+/// Default timeout for `cargo test` during a proposal run
+const DEFAULT_TEST_TIMEOUT_SECS: u64 = 900;
+
+/// Prefix for branches created by the proposal workflow
+const BRANCH_PREFIX: &str = "self-improve/";
+
+/// Lifecycle of an improvement proposal
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProposalStatus {
+    Planned,
+    BranchCreated,
+    Applied,
+    TestsPassed,
+    TestsFailed,
+    PrOpened,
+}
+
+/// A single file edit within a proposal
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProposedEdit {
+    pub file_path: String,
+    pub content: String,
+}
+
+/// One recorded step of the proposal workflow
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub step: String,
+    pub success: bool,
+    pub detail: String,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// A change plan produced by `propose_change`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImprovementProposal {
+    pub id: String,
+    pub title: String,
+    pub description: String,
+    pub branch: String,
+    pub edits: Vec<ProposedEdit>,
+    pub status: ProposalStatus,
+    pub pr_url: Option<String>,
+    /// Outcome of the latest test run
+    #[serde(default)]
+    pub test_report: Option<TestReport>,
+    pub audit_log: Vec<AuditEntry>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl ImprovementProposal {
+    fn record(&mut self, step: &str, success: bool, detail: impl Into<String>) {
+        let detail = detail.into();
+        tracing::info!(
+            target: "audit",
+            proposal_id = %self.id,
+            step,
+            success,
+            "{}",
+            detail
+        );
+        self.audit_log.push(AuditEntry {
+            step: step.to_string(),
+            success,
+            detail,
+            timestamp: Utc::now(),
+        });
+    }
+}
 
 pub struct SelfImproveConnector {
     metadata: ConnectorMetadata,
     modify_tool: SelfModifyTool,
     enabled: bool,
     backup_count: usize,
+    repo_root: PathBuf,
+    github: Option<GitHubConnector>,
+    sandbox: ExecutionSandbox,
+    proposals: RwLock<HashMap<String, ImprovementProposal>>,
 }
 
 impl SelfImproveConnector {
@@ -29,13 +126,355 @@ impl SelfImproveConnector {
                     "Automatic backup before modification".to_string(),
                     "Source file validation".to_string(),
                     "Rollback capability".to_string(),
+                    "Proposals applied on a git branch, never the running binary".to_string(),
+                    "Tests must pass before a pull request is opened".to_string(),
                 ],
             },
             modify_tool: SelfModifyTool::new(&backup_dir)?,
             enabled: true,
             backup_count,
+            repo_root: std::env::current_dir()?,
+            github: None,
+            sandbox: ExecutionSandbox::default(),
+            proposals: RwLock::new(HashMap::new()),
         })
     }
+
+    /// Set the git repository the proposal workflow operates on
+    pub fn with_repo_root(mut self, repo_root: PathBuf) -> Self {
+        self.repo_root = repo_root;
+        self
+    }
+
+    /// Enable opening pull requests through the GitHub connector
+    pub fn with_github_token(mut self, token: String) -> Result<Self> {
+        self.github = Some(GitHubConnector::new(token)?);
+        Ok(self)
+    }
+
+    /// Only read and edit the files `policy` allows
+    pub fn with_path_policy(mut self, policy: PathPolicy) -> Self {
+        self.modify_tool = self.modify_tool.with_path_policy(policy);
+        self
+    }
+
+    /// Run proposal tests as the sandbox user and within its limits
+    pub fn with_sandbox(mut self, sandbox: ExecutionSandbox) -> Self {
+        self.sandbox = sandbox;
+        self
+    }
+
+    /// Resolve a proposal edit path against `root` and make sure it stays
+    /// inside it, symlinks included, and away from build output or the
+    /// running executable.
+    fn validate_edit_path(&self, root: &Path, file_path: &str) -> Result<PathBuf> {
+        let relative = Path::new(file_path);
+        if relative.is_absolute() {
+            anyhow::bail!("Proposal edits must use repository-relative paths: {}", file_path);
+        }
+        if relative.components().any(|c| matches!(c, Component::ParentDir)) {
+            anyhow::bail!("Parent directory traversal (..) is not allowed: {}", file_path);
+        }
+        if relative.components().next() == Some(Component::Normal("target".as_ref())) {
+            anyhow::bail!("Build output under target/ cannot be modified: {}", file_path);
+        }
+
+        let root = root
+            .canonicalize()
+            .with_context(|| format!("Repository root not found: {}", root.display()))?;
+        // The file may not exist yet, so resolve its deepest existing
+        // ancestor; a symlink anywhere along the way is followed there
+        let mut existing = root.join(relative);
+        let mut missing = Vec::new();
+        while existing.symlink_metadata().is_err() {
+            if let Some(name) = existing.file_name() {
+                missing.push(name.to_os_string());
+            }
+            if !existing.pop() {
+                break;
+            }
+        }
+        let mut full_path = existing
+            .canonicalize()
+            .with_context(|| format!("Cannot resolve {}", file_path))?;
+        if !full_path.starts_with(&root) {
+            anyhow::bail!("Path resolves outside the repository: {}", file_path);
+        }
+        full_path.extend(missing.iter().rev());
+
+        if let Ok(current_exe) = std::env::current_exe() {
+            let current_exe = current_exe.canonicalize().unwrap_or(current_exe);
+            if full_path == current_exe {
+                anyhow::bail!("Refusing to modify the running binary: {}", file_path);
+            }
+        }
+
+        Ok(full_path)
+    }
+
+    async fn run_git(&self, dir: &Path, args: &[&str]) -> Result<Output> {
+        Command::new("git")
+            .args(args)
+            .current_dir(dir)
+            .kill_on_drop(true)
+            .output()
+            .await
+            .with_context(|| format!("Failed to run git {}", args.join(" ")))
+    }
+
+    /// Where the proposal's branch is checked out
+    fn worktree_path(&self, proposal: &ImprovementProposal) -> PathBuf {
+        std::env::temp_dir().join("jamey-self-improve").join(&proposal.id)
+    }
+
+    /// The proposal's worktree, added back from its branch if an earlier
+    /// action removed it
+    async fn worktree(&self, proposal: &ImprovementProposal) -> Result<PathBuf> {
+        if proposal.status == ProposalStatus::Planned {
+            anyhow::bail!("Create the proposal's branch first");
+        }
+        let path = self.worktree_path(proposal);
+        if path.exists() {
+            return Ok(path);
+        }
+        let worktree = path.to_string_lossy();
+        let output = self.run_git(&self.repo_root, &["worktree", "add", &worktree, &proposal.branch]).await?;
+        if !output.status.success() {
+            anyhow::bail!(
+                "Failed to check out {}: {}",
+                proposal.branch,
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        Ok(path)
+    }
+
+    async fn remove_worktree(&self, proposal: &ImprovementProposal) {
+        let path = self.worktree_path(proposal);
+        if !path.exists() {
+            return;
+        }
+        let worktree = path.to_string_lossy();
+        match self.run_git(&self.repo_root, &["worktree", "remove", "--force", &worktree]).await {
+            Ok(output) if output.status.success() => {}
+            Ok(output) => tracing::warn!(
+                "Failed to remove worktree {}: {}",
+                path.display(),
+                String::from_utf8_lossy(&output.stderr).trim()
+            ),
+            Err(e) => tracing::warn!("Failed to remove worktree {}: {}", path.display(), e),
+        }
+    }
+
+    async fn get_proposal(&self, proposal_id: &str) -> Result<ImprovementProposal> {
+        self.proposals
+            .read()
+            .await
+            .get(proposal_id)
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("Unknown proposal: {}", proposal_id))
+    }
+
+    async fn save_proposal(&self, proposal: ImprovementProposal) {
+        self.proposals
+            .write()
+            .await
+            .insert(proposal.id.clone(), proposal);
+    }
+
+    /// Build a change plan from the requested edits without touching disk
+    async fn propose_change(
+        &self,
+        title: &str,
+        description: &str,
+        edits: Vec<ProposedEdit>,
+    ) -> Result<ImprovementProposal> {
+        if edits.is_empty() {
+            anyhow::bail!("A proposal needs at least one edit");
+        }
+        for edit in &edits {
+            self.validate_edit_path(&self.repo_root, &edit.file_path)?;
+        }
+
+        let id = uuid::Uuid::new_v4().to_string();
+        let mut proposal = ImprovementProposal {
+            branch: format!("{}{}", BRANCH_PREFIX, &id[..8]),
+            id,
+            title: title.to_string(),
+            description: description.to_string(),
+            edits,
+            status: ProposalStatus::Planned,
+            pr_url: None,
+            test_report: None,
+            audit_log: Vec::new(),
+            created_at: Utc::now(),
+        };
+        let files: Vec<&str> = proposal.edits.iter().map(|e| e.file_path.as_str()).collect();
+        let detail = format!("Planned changes to {}", files.join(", "));
+        proposal.record("plan", true, detail);
+
+        self.save_proposal(proposal.clone()).await;
+        Ok(proposal)
+    }
+
+    async fn create_branch(&self, proposal: &mut ImprovementProposal) -> Result<()> {
+        let path = self.worktree_path(proposal);
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        let worktree = path.to_string_lossy();
+        let output = self
+            .run_git(&self.repo_root, &["worktree", "add", "-b", &proposal.branch, &worktree])
+            .await?;
+        let success = output.status.success();
+        let detail = if success {
+            format!("Created branch {} in worktree {}", proposal.branch, worktree)
+        } else {
+            String::from_utf8_lossy(&output.stderr).trim().to_string()
+        };
+        proposal.record("create_branch", success, detail.clone());
+        if !success {
+            anyhow::bail!("Failed to create branch: {}", detail);
+        }
+        proposal.status = ProposalStatus::BranchCreated;
+        Ok(())
+    }
+
+    async fn apply_proposal(&self, proposal: &mut ImprovementProposal) -> Result<Vec<String>> {
+        let worktree = self.worktree(proposal).await?;
+
+        let mut backups = Vec::new();
+        for edit in &proposal.edits {
+            let path = self.validate_edit_path(&worktree, &edit.file_path)?;
+            if path.exists() {
+                let backup = self.modify_tool.modify_file(&path, &edit.content)?;
+                backups.push(backup.backup_path.to_string_lossy().to_string());
+            } else {
+                if let Some(parent) = path.parent() {
+                    tokio::fs::create_dir_all(parent).await?;
+                }
+                tokio::fs::write(&path, &edit.content).await?;
+                crate::system::audit_modification(&path, &edit.content);
+            }
+        }
+
+        let mut add_args = vec!["add", "--"];
+        add_args.extend(proposal.edits.iter().map(|e| e.file_path.as_str()));
+        let add = self.run_git(&worktree, &add_args).await?;
+        let commit = if add.status.success() {
+            Some(self.run_git(&worktree, &["commit", "-m", &proposal.title, "-m", &proposal.description]).await?)
+        } else {
+            None
+        };
+
+        match commit {
+            Some(output) if output.status.success() => {
+                proposal.record(
+                    "apply",
+                    true,
+                    format!("Applied {} edit(s) and committed", proposal.edits.len()),
+                );
+                proposal.status = ProposalStatus::Applied;
+                Ok(backups)
+            }
+            Some(output) => {
+                let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+                proposal.record("apply", false, stderr.clone());
+                anyhow::bail!("Failed to commit proposal: {}", stderr)
+            }
+            None => {
+                let stderr = String::from_utf8_lossy(&add.stderr).trim().to_string();
+                proposal.record("apply", false, stderr.clone());
+                anyhow::bail!("Failed to stage proposal: {}", stderr)
+            }
+        }
+    }
+
+    async fn run_tests(
+        &self,
+        proposal: &mut ImprovementProposal,
+        package: Option<&str>,
+        timeout_secs: u64,
+    ) -> Result<String> {
+        if proposal.status == ProposalStatus::Planned || proposal.status == ProposalStatus::BranchCreated {
+            anyhow::bail!("Proposal must be applied before running tests");
+        }
+
+        let request = TestRequest {
+            framework: Some(TestFramework::Cargo),
+            package: package.map(String::from),
+            timeout: Some(Duration::from_secs(timeout_secs)),
+            ..Default::default()
+        };
+        let worktree = self.worktree(proposal).await?;
+        let report = TestRunnerTool::new(&worktree).with_sandbox(self.sandbox.clone()).run(&request).await?;
+        let summary = report.summary();
+        let timed_out = report.timed_out;
+        proposal.record("test", report.success, summary.clone());
+        proposal.status = if report.success {
+            ProposalStatus::TestsPassed
+        } else {
+            ProposalStatus::TestsFailed
+        };
+        // Kept on the proposal so the failures reach the model with it
+        proposal.test_report = Some(report);
+        if timed_out {
+            anyhow::bail!("Tests timed out after {}s", timeout_secs);
+        }
+
+        Ok(summary)
+    }
+
+    async fn open_pr(
+        &self,
+        proposal: &mut ImprovementProposal,
+        owner: &str,
+        repo: &str,
+        base: &str,
+        context: &ExecutionContext,
+    ) -> Result<String> {
+        if proposal.status != ProposalStatus::TestsPassed {
+            anyhow::bail!("Tests must pass before a pull request is opened");
+        }
+        let github = self.github.as_ref()
+            .ok_or_else(|| anyhow::anyhow!("GitHub connector not configured"))?;
+
+        let push = self.run_git(&self.repo_root, &["push", "--set-upstream", "origin", &proposal.branch]).await?;
+        if !push.status.success() {
+            let stderr = String::from_utf8_lossy(&push.stderr).trim().to_string();
+            proposal.record("push", false, stderr.clone());
+            anyhow::bail!("Failed to push branch: {}", stderr);
+        }
+        proposal.record("push", true, format!("Pushed {} to origin", proposal.branch));
+
+        let mut pr_params = HashMap::new();
+        pr_params.insert("action".to_string(), "create_pr".to_string());
+        pr_params.insert("owner".to_string(), owner.to_string());
+        pr_params.insert("repo".to_string(), repo.to_string());
+        pr_params.insert("title".to_string(), proposal.title.clone());
+        pr_params.insert("head".to_string(), proposal.branch.clone());
+        pr_params.insert("base".to_string(), base.to_string());
+        pr_params.insert("body".to_string(), proposal.description.clone());
+
+        let pr_result = github.execute(pr_params, context).await?;
+        let pr: serde_json::Value = serde_json::from_str(&pr_result.output).unwrap_or_default();
+        match pr.get("html_url").and_then(|u| u.as_str()) {
+            Some(url) => {
+                proposal.record("open_pr", true, format!("Opened {}", url));
+                proposal.pr_url = Some(url.to_string());
+                proposal.status = ProposalStatus::PrOpened;
+                Ok(url.to_string())
+            }
+            None => {
+                let message = pr.get("message")
+                    .and_then(|m| m.as_str())
+                    .unwrap_or("unexpected GitHub response")
+                    .to_string();
+                proposal.record("open_pr", false, message.clone());
+                anyhow::bail!("Failed to open pull request: {}", message)
+            }
+        }
+    }
 }
 
 #[async_trait::async_trait]
@@ -47,7 +486,7 @@ impl Connector for SelfImproveConnector {
     async fn execute(
         &self,
         params: HashMap<String, String>,
-        _context: &ExecutionContext,
+        context: &ExecutionContext,
     ) -> Result<ConnectorResult> {
         let action = params.get("action")
             .ok_or_else(|| anyhow::anyhow!("Missing 'action' parameter"))?;
@@ -100,6 +539,94 @@ impl Connector for SelfImproveConnector {
                 result.success = true;
                 result.metadata.insert("file_count".to_string(), files.len().to_string());
             }
+            "propose_change" => {
+                let title = params.get("title")
+                    .ok_or_else(|| anyhow::anyhow!("Missing 'title' parameter"))?;
+                let edits_json = params.get("edits")
+                    .ok_or_else(|| anyhow::anyhow!("Missing 'edits' parameter"))?;
+                let default_description = String::new();
+                let description = params.get("description").unwrap_or(&default_description);
+                let edits: Vec<ProposedEdit> = serde_json::from_str(edits_json)
+                    .context("'edits' must be a JSON array of {file_path, content}")?;
+
+                let proposal = self.propose_change(title, description, edits).await?;
+                result.output = serde_json::to_string_pretty(&proposal)?;
+                result.success = true;
+                result.metadata.insert("proposal_id".to_string(), proposal.id.clone());
+                result.metadata.insert("branch".to_string(), proposal.branch.clone());
+            }
+            "create_branch" | "apply_proposal" | "run_tests" | "open_pr" | "run_workflow" => {
+                let proposal_id = params.get("proposal_id")
+                    .ok_or_else(|| anyhow::anyhow!("Missing 'proposal_id' parameter"))?;
+                let mut proposal = self.get_proposal(proposal_id).await?;
+
+                let applies_edits = action == "apply_proposal" || action == "run_workflow";
+                if applies_edits && !params.contains_key("confirmed") {
+                    result.errors.push("Self-modification requires explicit confirmation".to_string());
+                    return Ok(result);
+                }
+
+                let timeout_secs = params.get("timeout_secs")
+                    .and_then(|t| t.parse().ok())
+                    .unwrap_or(DEFAULT_TEST_TIMEOUT_SECS);
+                let package = params.get("package").map(|s| s.as_str());
+                let default_base = "main".to_string();
+                let base = params.get("base").unwrap_or(&default_base);
+
+                let outcome: Result<()> = async {
+                    if action == "create_branch" || action == "run_workflow" {
+                        self.create_branch(&mut proposal).await?;
+                    }
+                    if applies_edits {
+                        let backups = self.apply_proposal(&mut proposal).await?;
+                        result.files_accessed.extend(proposal.edits.iter().map(|e| e.file_path.clone()));
+                        result.metadata.insert("backup_count".to_string(), backups.len().to_string());
+                    }
+                    if action == "run_tests" || action == "run_workflow" {
+                        let summary = self.run_tests(&mut proposal, package, timeout_secs).await?;
+                        result.metadata.insert("test_summary".to_string(), summary);
+                    }
+                    let wants_pr = action == "open_pr"
+                        || (action == "run_workflow" && params.contains_key("owner"));
+                    if wants_pr && proposal.status == ProposalStatus::TestsPassed {
+                        let owner = params.get("owner")
+                            .ok_or_else(|| anyhow::anyhow!("Missing 'owner' parameter"))?;
+                        let repo = params.get("repo")
+                            .ok_or_else(|| anyhow::anyhow!("Missing 'repo' parameter"))?;
+                        let url = self.open_pr(&mut proposal, owner, repo, base, context).await?;
+                        result.metadata.insert("pr_url".to_string(), url);
+                    } else if action == "open_pr" {
+                        anyhow::bail!("Tests must pass before a pull request is opened");
+                    }
+                    Ok(())
+                }.await;
+
+                if let Err(e) = outcome {
+                    result.errors.push(e.to_string());
+                }
+                // Everything worth keeping is committed on the branch
+                self.remove_worktree(&proposal).await;
+                result.success = result.errors.is_empty()
+                    && proposal.status != ProposalStatus::TestsFailed;
+                result.output = serde_json::to_string_pretty(&proposal)?;
+                result.metadata.insert("proposal_id".to_string(), proposal.id.clone());
+                result.metadata.insert("status".to_string(), format!("{:?}", proposal.status));
+                self.save_proposal(proposal).await;
+            }
+            "get_proposal" => {
+                let proposal_id = params.get("proposal_id")
+                    .ok_or_else(|| anyhow::anyhow!("Missing 'proposal_id' parameter"))?;
+                let proposal = self.get_proposal(proposal_id).await?;
+                result.output = serde_json::to_string_pretty(&proposal)?;
+                result.success = true;
+            }
+            "list_proposals" => {
+                let proposals: Vec<ImprovementProposal> =
+                    self.proposals.read().await.values().cloned().collect();
+                result.output = serde_json::to_string_pretty(&proposals)?;
+                result.success = true;
+                result.metadata.insert("proposal_count".to_string(), proposals.len().to_string());
+            }
             "restore_backup" => {
                 let _backup_path = params.get("backup_path")
                     .ok_or_else(|| anyhow::anyhow!("Missing 'backup_path' parameter"))?;
@@ -126,6 +653,17 @@ impl Connector for SelfImproveConnector {
         vec!["action".to_string()]
     }
     
+    fn actions(&self) -> Vec<String> {
+        [
+            "read_file", "modify_file", "list_source_files", "propose_change", "create_branch",
+            "apply_proposal", "run_tests", "open_pr", "run_workflow", "get_proposal",
+            "list_proposals", "restore_backup",
+        ]
+            .into_iter()
+            .map(String::from)
+            .collect()
+    }
+    
     fn is_enabled(&self) -> bool {
         self.enabled
     }
@@ -135,7 +673,8 @@ impl Connector for SelfImproveConnector {
     }
     
     fn requires_network(&self) -> bool {
-        false
+        // Only pushing a proposal branch and opening a PR touch the network
+        self.github.is_some()
     }
     
     fn requires_credentials(&self) -> Vec<String> {
@@ -143,3 +682,131 @@ impl Connector for SelfImproveConnector {
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn connector(repo_root: &Path, backups: &Path) -> SelfImproveConnector {
+        SelfImproveConnector::new(backups.to_path_buf(), 5)
+            .unwrap()
+            .with_repo_root(repo_root.to_path_buf())
+    }
+
+    #[test]
+    fn test_validate_edit_path_rejects_unsafe_paths() {
+        let repo = TempDir::new().unwrap();
+        let backups = TempDir::new().unwrap();
+        let connector = connector(repo.path(), backups.path());
+
+        let root = repo.path();
+
+        assert!(connector.validate_edit_path(root, "src/lib.rs").is_ok());
+        assert!(connector.validate_edit_path(root, "../outside.rs").is_err());
+        assert!(connector.validate_edit_path(root, "/etc/passwd").is_err());
+        assert!(connector.validate_edit_path(root, "target/release/jamey").is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_validate_edit_path_rejects_symlinks_out_of_repo() {
+        let repo = TempDir::new().unwrap();
+        let outside = TempDir::new().unwrap();
+        let backups = TempDir::new().unwrap();
+        let connector = connector(repo.path(), backups.path());
+        std::os::unix::fs::symlink(outside.path(), repo.path().join("linked")).unwrap();
+        std::os::unix::fs::symlink(outside.path().join("gone.rs"), repo.path().join("dangling.rs")).unwrap();
+
+        assert!(connector.validate_edit_path(repo.path(), "linked/lib.rs").is_err());
+        assert!(connector.validate_edit_path(repo.path(), "dangling.rs").is_err());
+        let inside = connector.validate_edit_path(repo.path(), "src/new/lib.rs").unwrap();
+        assert!(inside.starts_with(repo.path().canonicalize().unwrap()));
+    }
+
+    #[tokio::test]
+    async fn test_propose_change_records_plan() {
+        let repo = TempDir::new().unwrap();
+        let backups = TempDir::new().unwrap();
+        let connector = connector(repo.path(), backups.path());
+
+        let edits = vec![ProposedEdit {
+            file_path: "src/lib.rs".to_string(),
+            content: "// improved".to_string(),
+        }];
+        let proposal = connector
+            .propose_change("Tidy lib", "Small cleanup", edits)
+            .await
+            .unwrap();
+
+        assert_eq!(proposal.status, ProposalStatus::Planned);
+        assert!(proposal.branch.starts_with(BRANCH_PREFIX));
+        assert_eq!(proposal.audit_log.len(), 1);
+        assert_eq!(proposal.audit_log[0].step, "plan");
+        assert!(connector.get_proposal(&proposal.id).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_apply_proposal_leaves_checkout_untouched() {
+        let repo = TempDir::new().unwrap();
+        let backups = TempDir::new().unwrap();
+        let git = |args: &[&str]| {
+            let status = std::process::Command::new("git").args(args).current_dir(repo.path()).status().unwrap();
+            assert!(status.success(), "git {:?}", args);
+        };
+        git(&["init", "-q", "-b", "main"]);
+        git(&["config", "user.email", "jamey@example.com"]);
+        git(&["config", "user.name", "Jamey"]);
+        std::fs::write(repo.path().join("README.md"), "old").unwrap();
+        git(&["add", "README.md"]);
+        git(&["commit", "-q", "-m", "init"]);
+        let connector = connector(repo.path(), backups.path());
+
+        let edits = vec![ProposedEdit {
+            file_path: "README.md".to_string(),
+            content: "new".to_string(),
+        }];
+        let proposal = connector.propose_change("Docs", "", edits).await.unwrap();
+        for action in ["create_branch", "apply_proposal"] {
+            let mut params = HashMap::new();
+            params.insert("action".to_string(), action.to_string());
+            params.insert("proposal_id".to_string(), proposal.id.clone());
+            params.insert("confirmed".to_string(), "true".to_string());
+            let result = connector.execute(params, &ExecutionContext::default()).await.unwrap();
+            assert!(result.success, "{}: {:?}", action, result.errors);
+        }
+
+        assert_eq!(connector.get_proposal(&proposal.id).await.unwrap().status, ProposalStatus::Applied);
+        assert_eq!(std::fs::read_to_string(repo.path().join("README.md")).unwrap(), "old");
+        let committed = std::process::Command::new("git")
+            .args(["show", &format!("{}:README.md", proposal.branch)])
+            .current_dir(repo.path())
+            .output()
+            .unwrap();
+        assert_eq!(String::from_utf8_lossy(&committed.stdout), "new");
+        assert!(!connector.worktree_path(&proposal).exists());
+    }
+
+    #[tokio::test]
+    async fn test_open_pr_requires_passing_tests() {
+        let repo = TempDir::new().unwrap();
+        let backups = TempDir::new().unwrap();
+        let connector = connector(repo.path(), backups.path());
+
+        let edits = vec![ProposedEdit {
+            file_path: "README.md".to_string(),
+            content: "docs".to_string(),
+        }];
+        let proposal = connector.propose_change("Docs", "", edits).await.unwrap();
+
+        let mut params = HashMap::new();
+        params.insert("action".to_string(), "open_pr".to_string());
+        params.insert("proposal_id".to_string(), proposal.id.clone());
+        params.insert("owner".to_string(), "jamey".to_string());
+        params.insert("repo".to_string(), "jamey-code".to_string());
+
+        let result = connector.execute(params, &ExecutionContext::default()).await.unwrap();
+        assert!(!result.success);
+        assert!(result.errors.iter().any(|e| e.contains("Tests must pass")));
+    }
+}
+
@@ -1,9 +1,10 @@
 //! System Administration Connector
 //!
-//! Provides process management, system monitoring, and resource control
+//! Provides process management, system monitoring, resource control, and
+//! allowlisted system configuration access on every platform
 
 use crate::connector::*;
-use crate::system::ProcessTool;
+use crate::system::{ProcessTool, SystemConfigKey, SystemConfigTool};
 use std::collections::HashMap;
 use anyhow::Result;
 
@@ -43,6 +44,7 @@ pub struct SystemAdminConnector {
     metadata: ConnectorMetadata,
     #[cfg(windows)]
     registry_tool: Option<crate::system::RegistryTool>,
+    config_tool: SystemConfigTool,
     enabled: bool,
 }
 
@@ -59,13 +61,31 @@ impl SystemAdminConnector {
                 safety_checks: vec![
                     "Process kill operations require confirmation".to_string(),
                     "System resource limits enforced".to_string(),
+                    "System config keys restricted to an allowlist".to_string(),
                 ],
             },
             #[cfg(windows)]
             registry_tool: Some(crate::system::RegistryTool::new()),
+            config_tool: SystemConfigTool::default(),
             enabled: true,
         }
     }
+
+    /// Replace the default system config allowlist
+    pub fn with_config_tool(mut self, config_tool: SystemConfigTool) -> Self {
+        self.config_tool = config_tool;
+        self
+    }
+}
+
+fn system_config_key(params: &HashMap<String, String>) -> Result<SystemConfigKey> {
+    let backend = params.get("backend")
+        .ok_or_else(|| anyhow::anyhow!("Missing 'backend' parameter"))?
+        .parse()?;
+    let name = params.get("name")
+        .ok_or_else(|| anyhow::anyhow!("Missing 'name' parameter"))?;
+    let domain = params.get("domain").cloned().unwrap_or_default();
+    Ok(SystemConfigKey::new(backend, domain, name.clone()))
 }
 
 #[async_trait::async_trait]
@@ -135,6 +155,53 @@ impl Connector for SystemAdminConnector {
                 result.output = serde_json::to_string_pretty(&info)?;
                 result.success = true;
             }
+            "process_tree" => {
+                let root = params.get("pid").map(|pid| pid.parse::<u32>()).transpose()?;
+                let mut tool = ProcessTool::new();
+                let tree = tool.process_tree(root)
+                    .map_err(|e| anyhow::anyhow!("Failed to build process tree: {}", e))?;
+                result.output = serde_json::to_string_pretty(&tree)?;
+                result.success = true;
+            }
+            "process_ancestry" => {
+                let pid = params.get("pid")
+                    .ok_or_else(|| anyhow::anyhow!("Missing 'pid' parameter"))?
+                    .parse::<u32>()?;
+                let mut tool = ProcessTool::new();
+                let ancestry = tool.ancestry(pid)
+                    .map_err(|e| anyhow::anyhow!("Failed to trace process ancestry: {}", e))?;
+                result.output = serde_json::to_string_pretty(&ancestry)?;
+                result.success = true;
+            }
+            "process_resources" => {
+                let pid = params.get("pid")
+                    .ok_or_else(|| anyhow::anyhow!("Missing 'pid' parameter"))?
+                    .parse::<u32>()?;
+                let mut tool = ProcessTool::new();
+                let resources = tool.open_resources(pid)
+                    .map_err(|e| anyhow::anyhow!("Failed to inspect process: {}", e))?;
+                if let Some(reason) = &resources.files_unavailable {
+                    result.warnings.push(format!("Open files not listed: {}", reason));
+                }
+                result.output = serde_json::to_string_pretty(&resources)?;
+                result.success = true;
+            }
+            "find_by_port" => {
+                let port = params.get("port")
+                    .ok_or_else(|| anyhow::anyhow!("Missing 'port' parameter"))?
+                    .parse::<u16>()?;
+                let mut tool = ProcessTool::new();
+                let owners = tool.find_by_port(port)
+                    .map_err(|e| anyhow::anyhow!("Failed to look up port {}: {}", port, e))?;
+                if owners.iter().any(|owner| owner.process.is_none()) {
+                    result.warnings.push(
+                        "Some sockets belong to processes this user can't inspect".to_string(),
+                    );
+                }
+                result.output = serde_json::to_string_pretty(&owners)?;
+                result.success = true;
+                result.metadata.insert("socket_count".to_string(), owners.len().to_string());
+            }
             #[cfg(windows)]
             "read_registry" => {
                 let key = params.get("key")
@@ -151,6 +218,45 @@ impl Connector for SystemAdminConnector {
                     result.errors.push("Registry tool not available".to_string());
                 }
             }
+            "read_system_config" => {
+                let key = system_config_key(&params)?;
+                let value = self.config_tool.read(&key)
+                    .map_err(|e| anyhow::anyhow!("System config read failed: {}", e))?;
+                result.output = value;
+                result.success = true;
+                result.metadata.insert("key".to_string(), key.id());
+            }
+            "write_system_config" => {
+                let key = system_config_key(&params)?;
+                let value = params.get("value")
+                    .ok_or_else(|| anyhow::anyhow!("Missing 'value' parameter"))?;
+
+                if !params.contains_key("confirmed") {
+                    result.errors.push("System config writes require explicit confirmation".to_string());
+                    return Ok(result);
+                }
+
+                // Read first so the write can be undone
+                let previous = self.config_tool.read(&key).ok();
+                self.config_tool.write(&key, value)
+                    .map_err(|e| anyhow::anyhow!("System config write failed: {}", e))?;
+                tracing::warn!("System config {} set to {}", key.id(), value);
+                result.output = format!("{} updated", key.id());
+                result.success = true;
+                result.metadata.insert("key".to_string(), key.id());
+                if let Some(previous) = previous {
+                    let undo = Compensation::new(
+                        &self.metadata.id,
+                        "write_system_config",
+                        format!("Set {} back to {}", key.id(), previous),
+                    )
+                    .with_param("backend", key.backend.as_str())
+                    .with_param("domain", key.domain.clone())
+                    .with_param("name", key.name.clone())
+                    .with_param("value", previous);
+                    result.compensations.push(undo);
+                }
+            }
             _ => {
                 result.errors.push(format!("Unknown action: {}", action));
             }
@@ -170,6 +276,17 @@ impl Connector for SystemAdminConnector {
         vec!["action".to_string()]
     }
     
+    fn actions(&self) -> Vec<String> {
+        [
+            "list_processes", "kill_process", "get_process_info", "process_tree",
+            "process_ancestry", "process_resources", "find_by_port", "read_registry",
+            "read_system_config", "write_system_config",
+        ]
+            .into_iter()
+            .map(String::from)
+            .collect()
+    }
+    
     fn is_enabled(&self) -> bool {
         self.enabled
     }
@@ -182,6 +299,21 @@ impl Connector for SystemAdminConnector {
         false
     }
     
+    async fn compensate(&self, compensation: &Compensation, _context: &ExecutionContext) -> Result<()> {
+        match compensation.param("action")? {
+            "write_system_config" => {
+                // The allowlist is checked again, as for any write
+                let key = system_config_key(&compensation.params)?;
+                let value = compensation.param("value")?;
+                self.config_tool.write(&key, value)
+                    .map_err(|e| anyhow::anyhow!("System config write failed: {}", e))?;
+                tracing::warn!("System config {} restored to {}", key.id(), value);
+                Ok(())
+            }
+            other => anyhow::bail!("Unknown compensation: {}", other),
+        }
+    }
+
     fn requires_credentials(&self) -> Vec<String> {
         vec![]
     }
@@ -0,0 +1,430 @@
+//! Telegram Connector
+//!
+//! A Telegram Bot API client. The runtime uses it to run Jamey as a bot,
+//! receiving updates by long polling or through a webhook; as a connector it
+//! lets Jamey send messages to the chats on its allowlist. Chats not on the
+//! allowlist are refused, and an empty allowlist refuses everyone.
+
+use crate::connector::*;
+use anyhow::{Context, Result};
+use chrono::Utc;
+use reqwest::Client;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+const API_BASE: &str = "https://api.telegram.org";
+
+/// Longest text Telegram accepts in one message
+pub const MAX_MESSAGE_CHARS: usize = 4096;
+
+/// Largest file bots may download
+pub const MAX_DOWNLOAD_BYTES: u64 = 20 * 1024 * 1024;
+
+/// Header Telegram sends with webhook updates, holding the secret given to `setWebhook`
+pub const SECRET_TOKEN_HEADER: &str = "X-Telegram-Bot-Api-Secret-Token";
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Update {
+    pub update_id: i64,
+    #[serde(default)]
+    pub message: Option<TelegramMessage>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TelegramMessage {
+    pub message_id: i64,
+    pub chat: Chat,
+    #[serde(default)]
+    pub from: Option<User>,
+    #[serde(default)]
+    pub text: Option<String>,
+    #[serde(default)]
+    pub caption: Option<String>,
+    /// The same photo at several sizes, smallest first
+    #[serde(default)]
+    pub photo: Vec<PhotoSize>,
+    #[serde(default)]
+    pub document: Option<Document>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Chat {
+    pub id: i64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct User {
+    pub id: i64,
+    #[serde(default)]
+    pub username: Option<String>,
+    #[serde(default)]
+    pub first_name: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PhotoSize {
+    pub file_id: String,
+    #[serde(default)]
+    pub file_size: Option<u64>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Document {
+    pub file_id: String,
+    #[serde(default)]
+    pub file_name: Option<String>,
+    #[serde(default)]
+    pub file_size: Option<u64>,
+}
+
+impl TelegramMessage {
+    /// Text of the message, or the caption of a photo or document
+    pub fn body(&self) -> &str {
+        self.text.as_deref().or(self.caption.as_deref()).unwrap_or_default()
+    }
+
+    /// Files sent with the message as `(file_id, file name)`; for a photo
+    /// only the largest size
+    pub fn files(&self) -> Vec<(String, String)> {
+        let mut files = Vec::new();
+        if let Some(photo) = self.photo.last() {
+            files.push((photo.file_id.clone(), format!("photo_{}.jpg", self.message_id)));
+        }
+        if let Some(document) = &self.document {
+            let name = document
+                .file_name
+                .clone()
+                .unwrap_or_else(|| format!("document_{}", self.message_id));
+            files.push((document.file_id.clone(), name));
+        }
+        files
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ApiResponse<T> {
+    ok: bool,
+    result: Option<T>,
+    #[serde(default)]
+    description: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct File {
+    #[serde(default)]
+    file_path: Option<String>,
+    #[serde(default)]
+    file_size: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+struct SendMessage<'a> {
+    chat_id: i64,
+    text: &'a str,
+}
+
+/// Telegram bot client; clones share the token, so a rotated token reaches
+/// the polling loop as well as the registered connector
+#[derive(Clone)]
+pub struct TelegramConnector {
+    metadata: ConnectorMetadata,
+    token: Arc<RwLock<String>>,
+    allowed_chats: Arc<Vec<i64>>,
+    client: Client,
+}
+
+impl TelegramConnector {
+    pub fn new(token: String, allowed_chats: Vec<i64>) -> Result<Self> {
+        if token.trim().is_empty() {
+            anyhow::bail!("Telegram bot token is empty");
+        }
+        // Long polls hold the request open for up to a minute
+        let client = Client::builder()
+            .timeout(Duration::from_secs(90))
+            .user_agent("Jamey-Telegram/1.0")
+            .build()?;
+
+        Ok(Self {
+            metadata: ConnectorMetadata {
+                id: "telegram".to_string(),
+                name: "Telegram".to_string(),
+                version: "1.0.0".to_string(),
+                description: "Send Telegram messages to allowed chats".to_string(),
+                capability_level: CapabilityLevel::WebAccess,
+                requires_approval: false,
+                safety_checks: vec![
+                    "Chat ID allowlist".to_string(),
+                    "Bot token never included in output".to_string(),
+                ],
+            },
+            token: Arc::new(RwLock::new(token)),
+            allowed_chats: Arc::new(allowed_chats),
+            client,
+        })
+    }
+
+    pub fn is_allowed(&self, chat_id: i64) -> bool {
+        self.allowed_chats.contains(&chat_id)
+    }
+
+    /// Secret for `setWebhook`, derived from the bot token so it needs no storage
+    pub fn webhook_secret(&self) -> String {
+        let digest = Sha256::digest(format!("jamey-webhook:{}", self.token()).as_bytes());
+        digest.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    fn token(&self) -> String {
+        self.token.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    async fn call<T: DeserializeOwned>(&self, method: &str, body: &impl Serialize) -> Result<T> {
+        let url = format!("{}/bot{}/{}", API_BASE, self.token(), method);
+        let response: ApiResponse<T> = self
+            .client
+            .post(&url)
+            .json(body)
+            .send()
+            .await
+            // The URL contains the token, so errors don't carry it
+            .map_err(|e| anyhow::anyhow!("Telegram {} request failed: {}", method, e.without_url()))?
+            .json()
+            .await
+            .with_context(|| format!("Invalid Telegram {} response", method))?;
+        match response {
+            ApiResponse { ok: true, result: Some(result), .. } => Ok(result),
+            ApiResponse { description, .. } => anyhow::bail!(
+                "Telegram {} failed: {}",
+                method,
+                description.unwrap_or_else(|| "no description".to_string())
+            ),
+        }
+    }
+
+    /// Updates after `offset`, waiting up to `timeout_secs` for one to arrive
+    pub async fn get_updates(&self, offset: i64, timeout_secs: u64) -> Result<Vec<Update>> {
+        self.call(
+            "getUpdates",
+            &serde_json::json!({
+                "offset": offset,
+                "timeout": timeout_secs,
+                "allowed_updates": ["message"],
+            }),
+        )
+        .await
+    }
+
+    /// Send `text`, split into several messages when it is too long for one
+    pub async fn send_message(&self, chat_id: i64, text: &str) -> Result<()> {
+        for chunk in split_message(text, MAX_MESSAGE_CHARS) {
+            self.call::<serde_json::Value>("sendMessage", &SendMessage { chat_id, text: &chunk })
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Show "typing..." in the chat while a reply is being prepared
+    pub async fn send_typing(&self, chat_id: i64) -> Result<()> {
+        self.call::<bool>("sendChatAction", &serde_json::json!({ "chat_id": chat_id, "action": "typing" }))
+            .await
+            .map(|_| ())
+    }
+
+    /// Contents of a file sent to the bot
+    pub async fn download_file(&self, file_id: &str) -> Result<Vec<u8>> {
+        let file: File = self.call("getFile", &serde_json::json!({ "file_id": file_id })).await?;
+        if file.file_size.unwrap_or(0) > MAX_DOWNLOAD_BYTES {
+            anyhow::bail!("File is larger than {} MB", MAX_DOWNLOAD_BYTES / (1024 * 1024));
+        }
+        let path = file.file_path.ok_or_else(|| anyhow::anyhow!("Telegram returned no file path"))?;
+        let url = format!("{}/file/bot{}/{}", API_BASE, self.token(), path);
+        let response = self
+            .client
+            .get(&url)
+            .send()
+            .await
+            .map_err(|e| anyhow::anyhow!("File download failed: {}", e.without_url()))?;
+        if !response.status().is_success() {
+            anyhow::bail!("File download failed: HTTP {}", response.status());
+        }
+        Ok(response.bytes().await?.to_vec())
+    }
+
+    /// Have Telegram POST updates to `url` instead of waiting for `getUpdates`
+    pub async fn set_webhook(&self, url: &str) -> Result<()> {
+        self.call::<bool>(
+            "setWebhook",
+            &serde_json::json!({
+                "url": url,
+                "secret_token": self.webhook_secret(),
+                "allowed_updates": ["message"],
+            }),
+        )
+        .await
+        .map(|_| ())
+    }
+
+    /// Switch back to long polling
+    pub async fn delete_webhook(&self) -> Result<()> {
+        self.call::<bool>("deleteWebhook", &serde_json::json!({})).await.map(|_| ())
+    }
+}
+
+/// Split `text` into pieces of at most `max` characters, preferring to break
+/// at a blank line, then a newline, then a space
+pub fn split_message(text: &str, max: usize) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut rest = text.trim();
+    while rest.chars().count() > max {
+        let limit = rest.char_indices().nth(max).map(|(i, _)| i).unwrap_or(rest.len());
+        // A separator just past the limit still leaves `max` characters before it
+        let window = &rest[..rest[limit..].chars().next().map_or(limit, |c| limit + c.len_utf8())];
+        let cut = ["\n\n", "\n", " "]
+            .iter()
+            .find_map(|sep| window.rfind(sep).filter(|&i| i > 0))
+            .unwrap_or(limit);
+        chunks.push(rest[..cut].trim_end().to_string());
+        rest = rest[cut..].trim_start();
+    }
+    if !rest.is_empty() || chunks.is_empty() {
+        chunks.push(rest.to_string());
+    }
+    chunks
+}
+
+#[async_trait::async_trait]
+impl Connector for TelegramConnector {
+    fn metadata(&self) -> &ConnectorMetadata {
+        &self.metadata
+    }
+
+    async fn execute(
+        &self,
+        params: HashMap<String, String>,
+        _context: &ExecutionContext,
+    ) -> Result<ConnectorResult> {
+        let action = params.get("action")
+            .ok_or_else(|| anyhow::anyhow!("Missing 'action' parameter"))?;
+
+        let mut result = ConnectorResult::new();
+        match action.as_str() {
+            "send_message" => {
+                let chat_id: i64 = params
+                    .get("chat_id")
+                    .ok_or_else(|| anyhow::anyhow!("Missing chat_id"))?
+                    .parse()
+                    .context("Invalid chat_id")?;
+                let text = params.get("text").ok_or_else(|| anyhow::anyhow!("Missing text"))?;
+                if !self.is_allowed(chat_id) {
+                    result.errors.push(format!("Chat {} is not on the Telegram allowlist", chat_id));
+                    return Ok(result);
+                }
+                self.send_message(chat_id, text).await?;
+                result.network_requests.push(NetworkRequest {
+                    url: format!("{}/bot<token>/sendMessage", API_BASE),
+                    method: "POST".to_string(),
+                    status_code: Some(200),
+                    timestamp: Utc::now(),
+                });
+                result.output = format!("Sent message to chat {}", chat_id);
+                result.success = true;
+            }
+            "list_chats" => {
+                result.output = serde_json::to_string(&*self.allowed_chats)?;
+                result.success = true;
+            }
+            _ => {
+                result.errors.push(format!("Unknown action: {}", action));
+            }
+        }
+
+        Ok(result)
+    }
+
+    fn validate(&self, params: &HashMap<String, String>) -> Result<()> {
+        if !params.contains_key("action") {
+            return Err(anyhow::anyhow!("Missing required parameter: action"));
+        }
+        Ok(())
+    }
+
+    fn required_params(&self) -> Vec<String> {
+        vec!["action".to_string()]
+    }
+
+    fn actions(&self) -> Vec<String> {
+        ["send_message", "list_chats"].into_iter().map(String::from).collect()
+    }
+
+    fn is_enabled(&self) -> bool {
+        true
+    }
+
+    fn safety_checks(&self) -> Vec<String> {
+        self.metadata.safety_checks.clone()
+    }
+
+    fn requires_network(&self) -> bool {
+        true
+    }
+
+    fn requires_credentials(&self) -> Vec<String> {
+        vec!["telegram_bot_token".to_string()]
+    }
+
+    fn update_credential(&self, key: &str, value: &str) -> Result<()> {
+        if key == "telegram_bot_token" {
+            *self.token.write().unwrap_or_else(|e| e.into_inner()) = value.to_string();
+            tracing::info!("Telegram bot token updated");
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_message() {
+        assert_eq!(split_message("hello", 10), vec!["hello"]);
+        assert_eq!(split_message("", 10), vec![""]);
+        assert_eq!(split_message("first part\n\nsecond part", 15), vec!["first part", "second part"]);
+        assert_eq!(split_message("aaaa bbbb cccc", 9), vec!["aaaa bbbb", "cccc"]);
+
+        let long = "x".repeat(25);
+        let chunks = split_message(&long, 10);
+        assert_eq!(chunks.len(), 3);
+        assert!(chunks.iter().all(|c| c.chars().count() <= 10));
+    }
+
+    #[test]
+    fn test_update_parsing_and_allowlist() {
+        let update: Update = serde_json::from_value(serde_json::json!({
+            "update_id": 7,
+            "message": {
+                "message_id": 42,
+                "chat": { "id": -1001, "type": "group" },
+                "caption": "what is this?",
+                "photo": [
+                    { "file_id": "small", "file_size": 100, "width": 90, "height": 90 },
+                    { "file_id": "large", "file_size": 9000, "width": 800, "height": 800 }
+                ]
+            }
+        }))
+        .unwrap();
+        let message = update.message.unwrap();
+        assert_eq!(message.body(), "what is this?");
+        assert_eq!(message.files(), vec![("large".to_string(), "photo_42.jpg".to_string())]);
+
+        let bot = TelegramConnector::new("123:abc".to_string(), vec![-1001]).unwrap();
+        assert!(bot.is_allowed(-1001));
+        assert!(!bot.is_allowed(5));
+        assert!(!TelegramConnector::new("123:abc".to_string(), vec![]).unwrap().is_allowed(-1001));
+        assert_eq!(bot.webhook_secret().len(), 64);
+        assert!(TelegramConnector::new(" ".to_string(), vec![]).is_err());
+    }
+}
@@ -0,0 +1,173 @@
+//! Terminal Connector
+//!
+//! Exposes [`PtyTool`] shells to the model. A shell belongs to the chat
+//! session that opened it and starts in that session's workspace, or under
+//! the connector's root. Sending a line also reads what it printed, so most
+//! steps take one call.
+
+use super::full_system::resolve_in_workspace;
+use crate::connector::*;
+use crate::pty::{PtySignal, PtyTool, DEFAULT_IDLE, DEFAULT_MAX_WAIT};
+use crate::sandbox::ExecutionSandbox;
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Longest a single read may wait
+const MAX_WAIT_SECS: u64 = 120;
+
+pub struct TerminalConnector {
+    metadata: ConnectorMetadata,
+    root_path: PathBuf,
+    tool: PtyTool,
+    enabled: bool,
+}
+
+impl TerminalConnector {
+    pub fn new(root_path: PathBuf) -> Self {
+        Self {
+            metadata: ConnectorMetadata {
+                id: "terminal".to_string(),
+                name: "Terminal".to_string(),
+                version: "1.0.0".to_string(),
+                description: "Interactive shell in a pseudo-terminal, for programs that need a terminal or \
+                    keep running. Actions: open (path=<directory>) returns a terminal id; send (id, input: one \
+                    line, a whitelisted command at the prompt or an answer to the running program) and read \
+                    (id) return new output once the prompt is back or output goes quiet (idle_ms, wait_secs); \
+                    signal (id, signal=interrupt|eof); transcript (id); close (id); list."
+                    .to_string(),
+                capability_level: CapabilityLevel::FullAccess,
+                requires_approval: false,
+                safety_checks: vec![
+                    "Whitelisted commands only, checked at the prompt and by the shell".to_string(),
+                    "Shells start inside the root or workspace".to_string(),
+                    "Full transcript written to the audit log".to_string(),
+                ],
+            },
+            root_path,
+            tool: PtyTool::new(),
+            enabled: true,
+        }
+    }
+
+    /// Start shells as the sandbox user and within its limits
+    pub fn with_sandbox(mut self, sandbox: ExecutionSandbox) -> Self {
+        self.tool = PtyTool::new().with_sandbox(sandbox);
+        self
+    }
+
+    /// Where a new shell starts: `params["path"]` under the workspace, which
+    /// must lie inside the connector's root, or under the root
+    fn start_dir(&self, params: &HashMap<String, String>, context: &ExecutionContext) -> Result<PathBuf> {
+        let path = params.get("path").map(String::as_str).unwrap_or(".");
+        resolve_in_workspace(&self.root_path, path, context).context("Path validation failed")
+    }
+}
+
+#[async_trait::async_trait]
+impl Connector for TerminalConnector {
+    fn metadata(&self) -> &ConnectorMetadata {
+        &self.metadata
+    }
+
+    async fn execute(
+        &self,
+        params: HashMap<String, String>,
+        context: &ExecutionContext,
+    ) -> Result<ConnectorResult> {
+        let action = params.get("action")
+            .ok_or_else(|| anyhow::anyhow!("Missing 'action' parameter"))?;
+        let owner = context.session_id.as_str();
+        let idle = parse_param(&params, "idle_ms")?.map(Duration::from_millis).unwrap_or(DEFAULT_IDLE);
+        let wait = parse_param(&params, "wait_secs")?
+            .map(|secs: u64| Duration::from_secs(secs.min(MAX_WAIT_SECS)))
+            .unwrap_or(DEFAULT_MAX_WAIT);
+        let session = |params: &HashMap<String, String>| {
+            let id = params.get("id").ok_or_else(|| anyhow::anyhow!("Missing id"))?;
+            Ok::<_, anyhow::Error>(self.tool.get(owner, id)?)
+        };
+
+        let mut result = ConnectorResult::new();
+        match action.as_str() {
+            "open" => {
+                let dir = self.start_dir(&params, context)?;
+                let session = self.tool.open(owner, &dir).await?;
+                tracing::info!(target: "audit", terminal = %session.id(), session = %owner, "Terminal opened in {}", dir.display());
+                result.output = serde_json::to_string_pretty(&session.info())?;
+                result.metadata.insert("id".to_string(), session.id().to_string());
+                result.success = true;
+            }
+            "send" | "read" | "signal" => {
+                let session = session(&params)?;
+                match action.as_str() {
+                    "send" => {
+                        let input = params.get("input").ok_or_else(|| anyhow::anyhow!("Missing input"))?;
+                        session.send(input)?;
+                    }
+                    "signal" => {
+                        let signal = params.get("signal").map(String::as_str).unwrap_or("interrupt");
+                        session.signal(signal.parse::<PtySignal>().map_err(|e| anyhow::anyhow!(e))?)?;
+                    }
+                    _ => {}
+                }
+                let output = session.read(idle, wait).await;
+                result.output = serde_json::to_string_pretty(&output)?;
+                result.success = true;
+            }
+            "transcript" => {
+                result.output = serde_json::to_string_pretty(&session(&params)?.transcript())?;
+                result.success = true;
+            }
+            "close" => {
+                let id = params.get("id").ok_or_else(|| anyhow::anyhow!("Missing id"))?;
+                let transcript = self.tool.close(owner, id)?;
+                result.output = format!("Closed terminal {} ({} transcript entries)", id, transcript.len());
+                result.success = true;
+            }
+            "list" => {
+                result.output = serde_json::to_string_pretty(&self.tool.list(owner))?;
+                result.success = true;
+            }
+            _ => {
+                result.errors.push(format!("Unknown action: {}", action));
+            }
+        }
+
+        Ok(result)
+    }
+
+    fn validate(&self, params: &HashMap<String, String>) -> Result<()> {
+        if !params.contains_key("action") {
+            return Err(anyhow::anyhow!("Missing required parameter: action"));
+        }
+        Ok(())
+    }
+
+    fn required_params(&self) -> Vec<String> {
+        vec!["action".to_string()]
+    }
+
+    fn actions(&self) -> Vec<String> {
+        ["open", "send", "read", "signal", "transcript", "close", "list"]
+            .into_iter()
+            .map(String::from)
+            .collect()
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    fn safety_checks(&self) -> Vec<String> {
+        self.metadata.safety_checks.clone()
+    }
+
+    fn requires_network(&self) -> bool {
+        false
+    }
+
+    fn requires_credentials(&self) -> Vec<String> {
+        vec![]
+    }
+}
@@ -0,0 +1,151 @@
+//! Test Runner Connector
+//!
+//! Runs the test suite of the workspace, or of a project under the
+//! connector's root, through [`TestRunnerTool`] and hands the model a
+//! structured report instead of the raw log.
+
+use super::full_system::resolve_in_workspace;
+use crate::connector::*;
+use crate::sandbox::ExecutionSandbox;
+use crate::test_runner::{TestFramework, TestRequest, TestRunnerTool, DEFAULT_TEST_TIMEOUT};
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Longest run a caller may ask for
+const MAX_TIMEOUT_SECS: u64 = 3600;
+
+pub struct TestRunnerConnector {
+    metadata: ConnectorMetadata,
+    root_path: PathBuf,
+    sandbox: ExecutionSandbox,
+    enabled: bool,
+}
+
+impl TestRunnerConnector {
+    pub fn new(root_path: PathBuf) -> Self {
+        Self {
+            metadata: ConnectorMetadata {
+                id: "test_runner".to_string(),
+                name: "Test Runner".to_string(),
+                version: "1.0.0".to_string(),
+                description: "Run a project's tests (cargo test, pytest or npm test) and get pass/fail counts \
+                    and failure snippets as JSON. Actions: run (framework=cargo|pytest|npm, detected when \
+                    omitted; filter=<test name>; package=<cargo package or pytest path>; path=<project \
+                    subdirectory>; timeout_secs); detect (path)."
+                    .to_string(),
+                capability_level: CapabilityLevel::FullAccess,
+                requires_approval: false,
+                safety_checks: vec![
+                    "Projects must be inside the root or workspace".to_string(),
+                    "Runs are killed at their timeout".to_string(),
+                ],
+            },
+            root_path,
+            sandbox: ExecutionSandbox::default(),
+            enabled: true,
+        }
+    }
+
+    /// Run suites as the sandbox user and within its limits
+    pub fn with_sandbox(mut self, sandbox: ExecutionSandbox) -> Self {
+        self.sandbox = sandbox;
+        self
+    }
+
+    /// The project directory for this call: `params["path"]` under the
+    /// workspace, which must lie inside the connector's root, or under the root
+    fn project(&self, params: &HashMap<String, String>, context: &ExecutionContext) -> Result<PathBuf> {
+        let path = params.get("path").map(String::as_str).unwrap_or(".");
+        resolve_in_workspace(&self.root_path, path, context).context("Path validation failed")
+    }
+}
+
+#[async_trait::async_trait]
+impl Connector for TestRunnerConnector {
+    fn metadata(&self) -> &ConnectorMetadata {
+        &self.metadata
+    }
+
+    async fn execute(
+        &self,
+        params: HashMap<String, String>,
+        context: &ExecutionContext,
+    ) -> Result<ConnectorResult> {
+        let action = params.get("action")
+            .ok_or_else(|| anyhow::anyhow!("Missing 'action' parameter"))?;
+
+        let mut result = ConnectorResult::new();
+        match action.as_str() {
+            "detect" => {
+                let dir = self.project(&params, context)?;
+                result.output = serde_json::to_string_pretty(&TestFramework::detect(&dir))?;
+                result.success = true;
+            }
+            "run" => {
+                let dir = self.project(&params, context)?;
+                let framework = params.get("framework")
+                    .map(|f| f.parse::<TestFramework>())
+                    .transpose()
+                    .map_err(|e| anyhow::anyhow!(e))?;
+                let timeout_secs = match params.get("timeout_secs") {
+                    Some(secs) => secs.trim().parse::<u64>()
+                        .map_err(|_| anyhow::anyhow!("Invalid timeout_secs: {}", secs))?,
+                    None => DEFAULT_TEST_TIMEOUT.as_secs(),
+                };
+                let request = TestRequest {
+                    framework,
+                    filter: params.get("filter").cloned(),
+                    package: params.get("package").cloned(),
+                    timeout: Some(Duration::from_secs(timeout_secs.min(MAX_TIMEOUT_SECS))),
+                };
+
+                let report = TestRunnerTool::new(&dir).with_sandbox(self.sandbox.clone()).run(&request).await?;
+                result.metadata.insert("summary".to_string(), report.summary());
+                result.output = serde_json::to_string_pretty(&report)?;
+                // A failing suite is still a successful run; the report says what failed
+                result.success = true;
+                if !report.success {
+                    result.warnings.push(report.summary());
+                }
+            }
+            _ => {
+                result.errors.push(format!("Unknown action: {}", action));
+            }
+        }
+
+        Ok(result)
+    }
+
+    fn validate(&self, params: &HashMap<String, String>) -> Result<()> {
+        if !params.contains_key("action") {
+            return Err(anyhow::anyhow!("Missing required parameter: action"));
+        }
+        Ok(())
+    }
+
+    fn required_params(&self) -> Vec<String> {
+        vec!["action".to_string()]
+    }
+
+    fn actions(&self) -> Vec<String> {
+        vec!["run".to_string(), "detect".to_string()]
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    fn safety_checks(&self) -> Vec<String> {
+        self.metadata.safety_checks.clone()
+    }
+
+    fn requires_network(&self) -> bool {
+        false
+    }
+
+    fn requires_credentials(&self) -> Vec<String> {
+        vec![]
+    }
+}
@@ -0,0 +1,585 @@
+//! Webhook Connector
+//!
+//! Outbound hooks POST runtime events (`turn.completed`, `tool.executed`,
+//! ...) to a URL, signed with HMAC-SHA256 in [`SIGNATURE_HEADER`] and
+//! retried with backoff. Inbound hooks are named endpoints the runtime
+//! serves at `/hooks/{name}`; callers authenticate with the hook's secret.
+//! Both kinds are kept in `<dir>/webhooks.json`.
+
+use crate::connector::*;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use jamey_core::secure_logging::{mask_url_credentials, Masked};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+/// Header carrying `sha256=<hex HMAC of the body>`
+pub const SIGNATURE_HEADER: &str = "X-Jamey-Signature";
+/// Header carrying the event name on outbound deliveries
+pub const EVENT_HEADER: &str = "X-Jamey-Event";
+
+const STORE_FILE: &str = "webhooks.json";
+const DEFAULT_MAX_RETRIES: u32 = 3;
+const MAX_RETRIES: u32 = 10;
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(500);
+
+/// Where events matching `events` are delivered
+#[derive(Clone, Serialize, Deserialize)]
+pub struct OutboundHook {
+    pub name: String,
+    pub url: String,
+    /// Event names, `prefix.*` patterns or `*` for everything
+    pub events: Vec<String>,
+    /// Signs deliveries when set
+    #[serde(default)]
+    pub secret: Option<String>,
+    pub max_retries: u32,
+    pub created_at: DateTime<Utc>,
+}
+
+impl std::fmt::Debug for OutboundHook {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OutboundHook")
+            .field("name", &self.name)
+            .field("url", &mask_url_credentials(&self.url))
+            .field("events", &self.events)
+            .field("secret", &Masked::opt(self.secret.as_deref()))
+            .field("max_retries", &self.max_retries)
+            .field("created_at", &self.created_at)
+            .finish()
+    }
+}
+
+impl OutboundHook {
+    pub fn wants(&self, event: &str) -> bool {
+        self.events.iter().any(|pattern| event_matches(pattern, event))
+    }
+}
+
+/// What an inbound hook does with the payloads it receives
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum HookTarget {
+    /// Append the payload to a session as a user message
+    Session { id: Uuid },
+    /// Queue a run of `connector` with the payload's fields as parameters
+    Queue { connector: String },
+}
+
+impl std::str::FromStr for HookTarget {
+    type Err = anyhow::Error;
+
+    /// `session:<uuid>` or `queue:<connector id>`
+    fn from_str(s: &str) -> Result<Self> {
+        match s.split_once(':') {
+            Some(("session", id)) => Ok(HookTarget::Session {
+                id: id.parse().context("Invalid session ID in hook target")?,
+            }),
+            Some(("queue", connector)) if !connector.is_empty() => Ok(HookTarget::Queue {
+                connector: connector.to_string(),
+            }),
+            _ => anyhow::bail!("Invalid hook target '{}': expected session:<id> or queue:<connector>", s),
+        }
+    }
+}
+
+/// A `/hooks/{name}` endpoint
+#[derive(Clone, Serialize, Deserialize)]
+pub struct InboundHook {
+    pub name: String,
+    pub secret: String,
+    pub target: HookTarget,
+    pub created_at: DateTime<Utc>,
+}
+
+impl std::fmt::Debug for InboundHook {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("InboundHook")
+            .field("name", &self.name)
+            .field("secret", &Masked::of(&self.secret))
+            .field("target", &self.target)
+            .field("created_at", &self.created_at)
+            .finish()
+    }
+}
+
+impl InboundHook {
+    /// Accepts either `Authorization: Bearer <secret>` or a
+    /// [`SIGNATURE_HEADER`] computed over `body` with the secret
+    pub fn authenticate(&self, bearer: Option<&str>, signature: Option<&str>, body: &[u8]) -> bool {
+        if let Some(signature) = signature {
+            return verify_signature(&self.secret, body, signature);
+        }
+        bearer.is_some_and(|token| constant_time_eq(token.as_bytes(), self.secret.as_bytes()))
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct HookFile {
+    #[serde(default)]
+    outbound: Vec<OutboundHook>,
+    #[serde(default)]
+    inbound: Vec<InboundHook>,
+}
+
+/// Outcome of sending one event to one outbound hook
+#[derive(Debug, Clone, Serialize)]
+pub struct Delivery {
+    pub hook: String,
+    pub url: String,
+    pub event: String,
+    pub status: Option<u16>,
+    pub attempts: u32,
+    pub error: Option<String>,
+}
+
+impl Delivery {
+    pub fn delivered(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// `sha256=<hex>` HMAC of `body` keyed with `secret`
+pub fn sign_payload(secret: &str, body: &[u8]) -> String {
+    let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(body);
+    let digest: String = mac.finalize().into_bytes().iter().map(|b| format!("{:02x}", b)).collect();
+    format!("sha256={}", digest)
+}
+
+pub fn verify_signature(secret: &str, body: &[u8], signature: &str) -> bool {
+    constant_time_eq(sign_payload(secret, body).as_bytes(), signature.trim().as_bytes())
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+/// `*` matches everything, `session.*` every event starting `session.`
+pub fn event_matches(pattern: &str, event: &str) -> bool {
+    match pattern.strip_suffix('*') {
+        Some(prefix) => event.starts_with(prefix),
+        None => pattern == event,
+    }
+}
+
+fn validate_hook_name(name: &str) -> Result<()> {
+    if name.is_empty()
+        || name.len() > 64
+        || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        anyhow::bail!("Hook names must be 1-64 letters, digits, '-' or '_'");
+    }
+    Ok(())
+}
+
+/// Registers webhooks and delivers events to them. Clones share one store,
+/// so the runtime can keep a handle for dispatching events while another
+/// sits in the connector registry.
+#[derive(Clone)]
+pub struct WebhookConnector {
+    metadata: ConnectorMetadata,
+    path: PathBuf,
+    hooks: Arc<RwLock<HookFile>>,
+    client: Client,
+}
+
+impl WebhookConnector {
+    pub fn new(dir: PathBuf) -> Result<Self> {
+        let path = dir.join(STORE_FILE);
+        let hooks = match std::fs::read(&path) {
+            Ok(bytes) => serde_json::from_slice(&bytes)
+                .with_context(|| format!("Invalid webhook store {}", path.display()))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => HookFile::default(),
+            Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+        };
+        let client = Client::builder()
+            .timeout(DELIVERY_TIMEOUT)
+            .user_agent("Jamey-Webhooks/1.0")
+            .build()?;
+
+        Ok(Self {
+            metadata: ConnectorMetadata {
+                id: "webhook".to_string(),
+                name: "Webhooks".to_string(),
+                version: "1.0.0".to_string(),
+                description: "Send runtime events to outbound webhooks and accept payloads on inbound hooks"
+                    .to_string(),
+                capability_level: CapabilityLevel::NetworkAccess,
+                requires_approval: true,
+                safety_checks: vec![
+                    "Outbound URL validation (no private or metadata hosts)".to_string(),
+                    "HMAC-SHA256 signed deliveries".to_string(),
+                    "Inbound hooks require their secret".to_string(),
+                ],
+            },
+            path,
+            hooks: Arc::new(RwLock::new(hooks)),
+            client,
+        })
+    }
+
+    pub async fn outbound(&self) -> Vec<OutboundHook> {
+        self.hooks.read().await.outbound.clone()
+    }
+
+    pub async fn inbound(&self) -> Vec<InboundHook> {
+        self.hooks.read().await.inbound.clone()
+    }
+
+    pub async fn inbound_hook(&self, name: &str) -> Option<InboundHook> {
+        self.hooks.read().await.inbound.iter().find(|h| h.name == name).cloned()
+    }
+
+    /// Add or replace an outbound hook
+    pub async fn register_outbound(&self, hook: OutboundHook) -> Result<()> {
+        validate_hook_name(&hook.name)?;
+        super::network_web::validate_url(&hook.url)?;
+        if hook.events.is_empty() {
+            anyhow::bail!("An outbound hook needs at least one event");
+        }
+        if hook.max_retries > MAX_RETRIES {
+            anyhow::bail!("max_retries must be at most {}", MAX_RETRIES);
+        }
+        let mut hooks = self.hooks.write().await;
+        hooks.outbound.retain(|h| h.name != hook.name);
+        hooks.outbound.push(hook);
+        self.save(&hooks).await
+    }
+
+    /// Add or replace an inbound hook, returning it with a fresh secret
+    pub async fn register_inbound(&self, name: &str, target: HookTarget) -> Result<InboundHook> {
+        validate_hook_name(name)?;
+        let hook = InboundHook {
+            name: name.to_string(),
+            secret: format!("whsec_{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple()),
+            target,
+            created_at: Utc::now(),
+        };
+        let mut hooks = self.hooks.write().await;
+        hooks.inbound.retain(|h| h.name != name);
+        hooks.inbound.push(hook.clone());
+        self.save(&hooks).await?;
+        Ok(hook)
+    }
+
+    /// Remove the outbound or inbound hook called `name`; false if neither exists
+    pub async fn remove(&self, name: &str) -> Result<bool> {
+        let mut hooks = self.hooks.write().await;
+        let before = hooks.outbound.len() + hooks.inbound.len();
+        hooks.outbound.retain(|h| h.name != name);
+        hooks.inbound.retain(|h| h.name != name);
+        if hooks.outbound.len() + hooks.inbound.len() == before {
+            return Ok(false);
+        }
+        self.save(&hooks).await?;
+        Ok(true)
+    }
+
+    /// Deliver `event` to every outbound hook subscribed to it
+    pub async fn dispatch(&self, event: &str, payload: &serde_json::Value) -> Vec<Delivery> {
+        let hooks: Vec<OutboundHook> = self.outbound().await.into_iter().filter(|h| h.wants(event)).collect();
+        if hooks.is_empty() {
+            return Vec::new();
+        }
+        let body = serde_json::json!({
+            "event": event,
+            "delivered_at": Utc::now().to_rfc3339(),
+            "data": payload,
+        })
+        .to_string();
+        futures_util::future::join_all(hooks.iter().map(|hook| self.deliver(hook, event, &body))).await
+    }
+
+    /// POST `body`, retrying connection failures, 429s and 5xxs with
+    /// exponential backoff
+    async fn deliver(&self, hook: &OutboundHook, event: &str, body: &str) -> Delivery {
+        let mut delivery = Delivery {
+            hook: hook.name.clone(),
+            url: hook.url.clone(),
+            event: event.to_string(),
+            status: None,
+            attempts: 0,
+            error: None,
+        };
+        loop {
+            delivery.attempts += 1;
+            let mut request = self
+                .client
+                .post(&hook.url)
+                .header("Content-Type", "application/json")
+                .header(EVENT_HEADER, event)
+                .body(body.to_string());
+            if let Some(secret) = &hook.secret {
+                request = request.header(SIGNATURE_HEADER, sign_payload(secret, body.as_bytes()));
+            }
+
+            let retryable = match request.send().await {
+                Ok(response) => {
+                    let status = response.status();
+                    delivery.status = Some(status.as_u16());
+                    if status.is_success() {
+                        delivery.error = None;
+                        return delivery;
+                    }
+                    delivery.error = Some(format!("HTTP {}", status));
+                    status.is_server_error() || status.as_u16() == 429
+                }
+                Err(e) => {
+                    delivery.error = Some(e.to_string());
+                    true
+                }
+            };
+            if !retryable || delivery.attempts > hook.max_retries {
+                tracing::warn!(
+                    "Webhook {} failed for {} after {} attempts: {}",
+                    hook.name,
+                    event,
+                    delivery.attempts,
+                    delivery.error.as_deref().unwrap_or_default()
+                );
+                return delivery;
+            }
+            tokio::time::sleep(RETRY_BASE_DELAY * 2u32.pow(delivery.attempts - 1)).await;
+        }
+    }
+
+    async fn save(&self, hooks: &HookFile) -> Result<()> {
+        if let Some(dir) = self.path.parent() {
+            tokio::fs::create_dir_all(dir).await?;
+        }
+        let tmp = self.path.with_extension("json.tmp");
+        tokio::fs::write(&tmp, serde_json::to_vec_pretty(hooks)?).await?;
+        // Secrets live in this file
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            tokio::fs::set_permissions(&tmp, std::fs::Permissions::from_mode(0o600)).await?;
+        }
+        tokio::fs::rename(&tmp, &self.path).await?;
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl Connector for WebhookConnector {
+    fn metadata(&self) -> &ConnectorMetadata {
+        &self.metadata
+    }
+
+    async fn execute(
+        &self,
+        params: HashMap<String, String>,
+        context: &ExecutionContext,
+    ) -> Result<ConnectorResult> {
+        let action = params.get("action")
+            .ok_or_else(|| anyhow::anyhow!("Missing 'action' parameter"))?;
+        let param = |key: &str| {
+            params.get(key).ok_or_else(|| anyhow::anyhow!("Missing '{}' parameter", key))
+        };
+
+        let mut result = ConnectorResult::new();
+        match action.as_str() {
+            "register" => {
+                let max_retries = match params.get("max_retries") {
+                    Some(n) => n.parse().context("Invalid 'max_retries' parameter")?,
+                    None => DEFAULT_MAX_RETRIES,
+                };
+                let hook = OutboundHook {
+                    name: param("name")?.clone(),
+                    url: param("url")?.clone(),
+                    events: param("events")?
+                        .split(',')
+                        .map(|e| e.trim().to_string())
+                        .filter(|e| !e.is_empty())
+                        .collect(),
+                    secret: params.get("secret").cloned(),
+                    max_retries,
+                    created_at: Utc::now(),
+                };
+                result.output = format!("Outbound hook '{}' sends {} to {}", hook.name, hook.events.join(", "), hook.url);
+                self.register_outbound(hook).await?;
+                result.success = true;
+            }
+            "register_inbound" => {
+                let target: HookTarget = param("target")?.parse()?;
+                let hook = self.register_inbound(param("name")?, target).await?;
+                result.output = format!("Inbound hook available at /hooks/{}", hook.name);
+                result.metadata.insert("secret".to_string(), hook.secret);
+                result.success = true;
+            }
+            "remove" => {
+                let name = param("name")?;
+                if self.remove(name).await? {
+                    result.output = format!("Removed hook '{}'", name);
+                    result.success = true;
+                } else {
+                    result.errors.push(format!("No hook named '{}'", name));
+                }
+            }
+            "list" => {
+                let hooks = self.hooks.read().await;
+                // Inbound secrets are shown once, when the hook is created
+                let inbound: Vec<_> = hooks
+                    .inbound
+                    .iter()
+                    .map(|h| serde_json::json!({ "name": h.name, "target": h.target, "created_at": h.created_at }))
+                    .collect();
+                let outbound: Vec<_> = hooks
+                    .outbound
+                    .iter()
+                    .map(|h| serde_json::json!({
+                        "name": h.name,
+                        "url": h.url,
+                        "events": h.events,
+                        "signed": h.secret.is_some(),
+                        "max_retries": h.max_retries,
+                    }))
+                    .collect();
+                result.output = serde_json::to_string_pretty(&serde_json::json!({
+                    "outbound": outbound,
+                    "inbound": inbound,
+                }))?;
+                result.success = true;
+            }
+            "send" => {
+                let event = param("event")?;
+                let payload = match params.get("payload") {
+                    Some(p) => serde_json::from_str(p).unwrap_or_else(|_| serde_json::Value::String(p.clone())),
+                    None => serde_json::json!({}),
+                };
+                context.partial_output.push_line(&format!("Delivering {} to its hooks", event));
+                let deliveries = self.dispatch(event, &payload).await;
+                for delivery in &deliveries {
+                    if let Some(error) = &delivery.error {
+                        result.errors.push(format!("{}: {}", delivery.hook, error));
+                    }
+                    result.network_requests.push(NetworkRequest {
+                        url: delivery.url.clone(),
+                        method: "POST".to_string(),
+                        status_code: delivery.status,
+                        timestamp: Utc::now(),
+                    });
+                }
+                result.success = deliveries.iter().all(Delivery::delivered);
+                result.output = format!(
+                    "Delivered {} to {}/{} hooks",
+                    event,
+                    deliveries.iter().filter(|d| d.delivered()).count(),
+                    deliveries.len()
+                );
+            }
+            _ => {
+                result.errors.push(format!("Unknown action: {}", action));
+            }
+        }
+
+        Ok(result)
+    }
+
+    fn validate(&self, params: &HashMap<String, String>) -> Result<()> {
+        if !params.contains_key("action") {
+            return Err(anyhow::anyhow!("Missing required parameter: action"));
+        }
+        Ok(())
+    }
+
+    fn required_params(&self) -> Vec<String> {
+        vec!["action".to_string()]
+    }
+
+    fn actions(&self) -> Vec<String> {
+        ["register", "register_inbound", "remove", "list", "send"]
+            .into_iter()
+            .map(String::from)
+            .collect()
+    }
+
+    fn is_enabled(&self) -> bool {
+        true
+    }
+
+    fn safety_checks(&self) -> Vec<String> {
+        self.metadata.safety_checks.clone()
+    }
+
+    fn requires_network(&self) -> bool {
+        true
+    }
+
+    fn requires_credentials(&self) -> Vec<String> {
+        vec![]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_signatures_and_patterns() {
+        let signature = sign_payload("secret", b"{\"a\":1}");
+        assert!(signature.starts_with("sha256="));
+        assert_eq!(signature.len(), "sha256=".len() + 64);
+        assert!(verify_signature("secret", b"{\"a\":1}", &signature));
+        assert!(!verify_signature("other", b"{\"a\":1}", &signature));
+        assert!(!verify_signature("secret", b"{\"a\":2}", &signature));
+
+        assert!(event_matches("*", "turn.completed"));
+        assert!(event_matches("turn.*", "turn.failed"));
+        assert!(!event_matches("turn.*", "tool.executed"));
+        assert!(event_matches("tool.executed", "tool.executed"));
+    }
+
+    #[tokio::test]
+    async fn test_hooks_persist_and_authenticate() {
+        let dir = TempDir::new().unwrap();
+        let connector = WebhookConnector::new(dir.path().to_path_buf()).unwrap();
+
+        let target: HookTarget = "queue:research".parse().unwrap();
+        let hook = connector.register_inbound("ci-builds", target.clone()).await.unwrap();
+        assert!(connector.register_inbound("bad name", target).await.is_err());
+        assert!("mailbox:x".parse::<HookTarget>().is_err());
+
+        let reloaded = WebhookConnector::new(dir.path().to_path_buf()).unwrap();
+        let stored = reloaded.inbound_hook("ci-builds").await.unwrap();
+        assert_eq!(stored.target, HookTarget::Queue { connector: "research".to_string() });
+
+        let body = b"{\"topic\":\"rust\"}";
+        assert!(stored.authenticate(Some(&hook.secret), None, body));
+        assert!(stored.authenticate(None, Some(&sign_payload(&hook.secret, body)), body));
+        assert!(!stored.authenticate(Some("whsec_wrong"), None, body));
+        assert!(!stored.authenticate(None, None, body));
+
+        assert!(reloaded.remove("ci-builds").await.unwrap());
+        assert!(!reloaded.remove("ci-builds").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_outbound_url_validated() {
+        let dir = TempDir::new().unwrap();
+        let connector = WebhookConnector::new(dir.path().to_path_buf()).unwrap();
+        let hook = |url: &str| OutboundHook {
+            name: "notify".to_string(),
+            url: url.to_string(),
+            events: vec!["turn.*".to_string()],
+            secret: None,
+            max_retries: 1,
+            created_at: Utc::now(),
+        };
+        assert!(connector.register_outbound(hook("http://169.254.169.254/latest")).await.is_err());
+        connector.register_outbound(hook("https://hooks.example.com/jamey")).await.unwrap();
+        assert!(connector.outbound().await[0].wants("turn.completed"));
+        assert!(connector.dispatch("tool.executed", &serde_json::json!({})).await.is_empty());
+    }
+}
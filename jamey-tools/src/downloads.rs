@@ -0,0 +1,796 @@
+//! Download manager
+//!
+//! Segmented, resumable HTTP downloads with SHA-256 verification, size
+//! limits and MIME sniffing. Completed downloads are held in a quarantine
+//! directory and only move into the workspace once explicitly approved.
+//!
+//! Layout under the quarantine directory:
+//! - `.partial/<id>/part-<n>` — in-flight segments, kept between attempts so
+//!   an interrupted download resumes where it stopped
+//! - `<id>/<filename>` and `<id>/download.json` — finished, awaiting approval
+
+use chrono::{DateTime, Utc};
+use reqwest::{header, Client, StatusCode};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+const PARTIAL_DIR: &str = ".partial";
+const RECORD_FILE: &str = "download.json";
+const SNIFF_BYTES: usize = 512;
+
+#[derive(Debug, Error)]
+pub enum DownloadError {
+    #[error("HTTP error: {0}")]
+    Http(#[from] reqwest::Error),
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Serialization error: {0}")]
+    Serialization(#[from] serde_json::Error),
+    #[error("Unexpected response status: {0}")]
+    Status(StatusCode),
+    #[error("Server ignored range request for segment starting at byte {0}")]
+    RangeIgnored(u64),
+    #[error("Download exceeds size limit: {size} > {limit} bytes")]
+    TooLarge { size: u64, limit: u64 },
+    #[error("Incomplete segment: expected {expected} bytes, got {actual}")]
+    Incomplete { expected: u64, actual: u64 },
+    #[error("Checksum mismatch: expected {expected}, got {actual}")]
+    ChecksumMismatch { expected: String, actual: String },
+    #[error("Blocked content type: {0}")]
+    BlockedType(String),
+    #[error("Invalid file name: {0}")]
+    InvalidName(String),
+    #[error("Quarantined download not found: {0}")]
+    NotFound(String),
+    #[error("Destination already exists: {0}")]
+    AlreadyExists(String),
+    #[error("Segment task failed: {0}")]
+    Task(String),
+}
+
+/// Download limits and locations
+#[derive(Debug, Clone)]
+pub struct DownloadConfig {
+    /// Approved downloads are moved here
+    pub workspace_dir: PathBuf,
+    /// Downloads wait here until approved or rejected
+    pub quarantine_dir: PathBuf,
+    /// Hard upper bound; per-request limits can only lower it
+    pub max_bytes: u64,
+    /// Maximum number of concurrent range requests per download
+    pub segments: usize,
+    /// Files smaller than this per segment are not split further
+    pub min_segment_bytes: u64,
+    /// MIME types that are refused outright (declared or sniffed)
+    pub blocked_mime_types: Vec<String>,
+}
+
+impl DownloadConfig {
+    pub fn new(workspace_dir: PathBuf) -> Self {
+        Self {
+            quarantine_dir: workspace_dir.join(".quarantine"),
+            workspace_dir,
+            max_bytes: 500 * 1024 * 1024,
+            segments: 4,
+            min_segment_bytes: 1024 * 1024,
+            blocked_mime_types: Vec::new(),
+        }
+    }
+
+    pub fn with_quarantine_dir(mut self, dir: PathBuf) -> Self {
+        self.quarantine_dir = dir;
+        self
+    }
+
+    pub fn with_max_bytes(mut self, max_bytes: u64) -> Self {
+        self.max_bytes = max_bytes;
+        self
+    }
+
+    pub fn with_segments(mut self, segments: usize, min_segment_bytes: u64) -> Self {
+        self.segments = segments.max(1);
+        self.min_segment_bytes = min_segment_bytes.max(1);
+        self
+    }
+
+    pub fn block_mime_type(mut self, mime: impl Into<String>) -> Self {
+        self.blocked_mime_types.push(mime.into());
+        self
+    }
+}
+
+/// A single download job
+#[derive(Debug, Clone, Default)]
+pub struct DownloadRequest {
+    pub url: String,
+    /// Defaults to the last path segment of the URL
+    pub filename: Option<String>,
+    /// Hex-encoded SHA-256 the finished file must match
+    pub expected_sha256: Option<String>,
+    /// Lower the configured size limit for this download
+    pub max_bytes: Option<u64>,
+}
+
+impl DownloadRequest {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            ..Default::default()
+        }
+    }
+}
+
+/// A finished download awaiting approval
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuarantinedDownload {
+    pub id: String,
+    pub url: String,
+    pub filename: String,
+    pub size: u64,
+    pub sha256: String,
+    /// True when an expected hash was supplied and matched
+    pub verified: bool,
+    pub declared_mime: Option<String>,
+    pub sniffed_mime: String,
+    pub warnings: Vec<String>,
+    pub segments: usize,
+    /// Bytes recovered from an earlier interrupted attempt
+    pub resumed_bytes: u64,
+    pub downloaded_at: DateTime<Utc>,
+}
+
+struct Probe {
+    length: Option<u64>,
+    accepts_ranges: bool,
+    content_type: Option<String>,
+}
+
+pub struct DownloadManager {
+    client: Client,
+    config: DownloadConfig,
+}
+
+impl DownloadManager {
+    pub fn new(client: Client, config: DownloadConfig) -> Self {
+        Self { client, config }
+    }
+
+    pub fn config(&self) -> &DownloadConfig {
+        &self.config
+    }
+
+    /// Download into quarantine, resuming any segments left by a previous attempt
+    pub async fn download(&self, request: &DownloadRequest) -> Result<QuarantinedDownload, DownloadError> {
+        let filename = match &request.filename {
+            Some(name) => sanitize_filename(name)?,
+            None => sanitize_filename(&filename_from_url(&request.url))?,
+        };
+        let limit = request
+            .max_bytes
+            .map_or(self.config.max_bytes, |max| max.min(self.config.max_bytes));
+        let id = download_id(&request.url);
+
+        let probe = self.probe(&request.url).await;
+        if let Some(length) = probe.length {
+            if length > limit {
+                return Err(DownloadError::TooLarge { size: length, limit });
+            }
+        }
+        if let Some(ref declared) = probe.content_type {
+            self.check_blocked(declared)?;
+        }
+
+        let staging = self.config.quarantine_dir.join(PARTIAL_DIR).join(&id);
+        tokio::fs::create_dir_all(&staging).await?;
+
+        let plan = plan_segments(
+            probe.length,
+            probe.accepts_ranges,
+            self.config.segments,
+            self.config.min_segment_bytes,
+        );
+        tracing::info!(
+            "Downloading {} in {} segment(s) (id {})",
+            request.url,
+            plan.len(),
+            id
+        );
+
+        let mut tasks = tokio::task::JoinSet::new();
+        for (index, (start, end)) in plan.iter().copied().enumerate() {
+            let client = self.client.clone();
+            let url = request.url.clone();
+            let part = staging.join(format!("part-{}", index));
+            let resumable = probe.accepts_ranges;
+            tasks.spawn(async move {
+                fetch_segment(&client, &url, &part, start, end, resumable, limit).await
+            });
+        }
+
+        let mut resumed_bytes = 0;
+        while let Some(joined) = tasks.join_next().await {
+            let resumed = joined.map_err(|e| DownloadError::Task(e.to_string()))??;
+            resumed_bytes += resumed;
+        }
+
+        let entry_dir = self.config.quarantine_dir.join(&id);
+        tokio::fs::create_dir_all(&entry_dir).await?;
+        let target = entry_dir.join(&filename);
+        let (size, sha256, head) = assemble(&staging, plan.len(), &target).await?;
+        tokio::fs::remove_dir_all(&staging).await?;
+
+        if size > limit {
+            tokio::fs::remove_dir_all(&entry_dir).await?;
+            return Err(DownloadError::TooLarge { size, limit });
+        }
+
+        let verified = match request.expected_sha256 {
+            Some(ref expected) => {
+                if !expected.trim().eq_ignore_ascii_case(&sha256) {
+                    tokio::fs::remove_dir_all(&entry_dir).await?;
+                    return Err(DownloadError::ChecksumMismatch {
+                        expected: expected.trim().to_lowercase(),
+                        actual: sha256,
+                    });
+                }
+                true
+            }
+            None => false,
+        };
+
+        let sniffed_mime = sniff_mime(&head).to_string();
+        if let Err(e) = self.check_blocked(&sniffed_mime) {
+            tokio::fs::remove_dir_all(&entry_dir).await?;
+            return Err(e);
+        }
+
+        let mut warnings = Vec::new();
+        if !verified {
+            warnings.push("No expected SHA-256 supplied; integrity not verified".to_string());
+        }
+        if let Some(ref declared) = probe.content_type {
+            if mime_mismatch(declared, &sniffed_mime) {
+                warnings.push(format!(
+                    "Declared content type {} does not match sniffed type {}",
+                    declared, sniffed_mime
+                ));
+            }
+        }
+        if is_executable_mime(&sniffed_mime) {
+            warnings.push(format!("File contains executable content ({})", sniffed_mime));
+        }
+
+        let record = QuarantinedDownload {
+            id,
+            url: request.url.clone(),
+            filename,
+            size,
+            sha256,
+            verified,
+            declared_mime: probe.content_type,
+            sniffed_mime,
+            warnings,
+            segments: plan.len(),
+            resumed_bytes,
+            downloaded_at: Utc::now(),
+        };
+        tokio::fs::write(entry_dir.join(RECORD_FILE), serde_json::to_vec_pretty(&record)?).await?;
+
+        tracing::warn!(
+            "Download {} quarantined ({} bytes, {}), awaiting approval",
+            record.id,
+            record.size,
+            record.sniffed_mime
+        );
+        Ok(record)
+    }
+
+    /// Downloads currently awaiting approval
+    pub async fn list_quarantined(&self) -> Result<Vec<QuarantinedDownload>, DownloadError> {
+        let mut records = Vec::new();
+        let mut entries = match tokio::fs::read_dir(&self.config.quarantine_dir).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(records),
+            Err(e) => return Err(e.into()),
+        };
+        while let Some(entry) = entries.next_entry().await? {
+            let record_path = entry.path().join(RECORD_FILE);
+            if let Ok(bytes) = tokio::fs::read(&record_path).await {
+                records.push(serde_json::from_slice(&bytes)?);
+            }
+        }
+        records.sort_by_key(|record: &QuarantinedDownload| record.downloaded_at);
+        Ok(records)
+    }
+
+    pub async fn get_quarantined(&self, id: &str) -> Result<QuarantinedDownload, DownloadError> {
+        let record_path = self.entry_dir(id)?.join(RECORD_FILE);
+        match tokio::fs::read(&record_path).await {
+            Ok(bytes) => Ok(serde_json::from_slice(&bytes)?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                Err(DownloadError::NotFound(id.to_string()))
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Move a quarantined download into the workspace
+    pub async fn approve(&self, id: &str) -> Result<PathBuf, DownloadError> {
+        let record = self.get_quarantined(id).await?;
+        let entry_dir = self.entry_dir(id)?;
+        let destination = self.config.workspace_dir.join(&record.filename);
+        if tokio::fs::try_exists(&destination).await? {
+            return Err(DownloadError::AlreadyExists(destination.display().to_string()));
+        }
+
+        tokio::fs::create_dir_all(&self.config.workspace_dir).await?;
+        let source = entry_dir.join(&record.filename);
+        if tokio::fs::rename(&source, &destination).await.is_err() {
+            // Quarantine may live on a different filesystem
+            tokio::fs::copy(&source, &destination).await?;
+        }
+        tokio::fs::remove_dir_all(&entry_dir).await?;
+
+        tracing::info!("Download {} approved: {}", id, destination.display());
+        Ok(destination)
+    }
+
+    /// Delete a quarantined download
+    pub async fn reject(&self, id: &str) -> Result<(), DownloadError> {
+        let entry_dir = self.entry_dir(id)?;
+        if !tokio::fs::try_exists(&entry_dir).await? {
+            return Err(DownloadError::NotFound(id.to_string()));
+        }
+        tokio::fs::remove_dir_all(&entry_dir).await?;
+        tracing::info!("Download {} rejected", id);
+        Ok(())
+    }
+
+    fn entry_dir(&self, id: &str) -> Result<PathBuf, DownloadError> {
+        // IDs are hex digests; anything else could escape the quarantine dir
+        if id.is_empty() || !id.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(DownloadError::NotFound(id.to_string()));
+        }
+        Ok(self.config.quarantine_dir.join(id))
+    }
+
+    fn check_blocked(&self, mime: &str) -> Result<(), DownloadError> {
+        let base = base_mime(mime);
+        if self
+            .config
+            .blocked_mime_types
+            .iter()
+            .any(|blocked| blocked.eq_ignore_ascii_case(&base))
+        {
+            return Err(DownloadError::BlockedType(base));
+        }
+        Ok(())
+    }
+
+    /// HEAD the URL for size, range support and declared type. Servers that
+    /// reject HEAD just get a single unsegmented download.
+    async fn probe(&self, url: &str) -> Probe {
+        let unknown = Probe {
+            length: None,
+            accepts_ranges: false,
+            content_type: None,
+        };
+        let response = match self.client.head(url).send().await {
+            Ok(response) if response.status().is_success() => response,
+            _ => return unknown,
+        };
+        let headers = response.headers();
+        Probe {
+            length: headers
+                .get(header::CONTENT_LENGTH)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.parse().ok()),
+            accepts_ranges: headers
+                .get(header::ACCEPT_RANGES)
+                .and_then(|v| v.to_str().ok())
+                .is_some_and(|v| v.eq_ignore_ascii_case("bytes")),
+            content_type: headers
+                .get(header::CONTENT_TYPE)
+                .and_then(|v| v.to_str().ok())
+                .map(|v| v.to_string()),
+        }
+    }
+}
+
+/// Fetch one segment into `part`, appending to what an earlier attempt left.
+/// Returns the number of bytes that were reused.
+async fn fetch_segment(
+    client: &Client,
+    url: &str,
+    part: &Path,
+    start: u64,
+    end: Option<u64>,
+    resumable: bool,
+    limit: u64,
+) -> Result<u64, DownloadError> {
+    let expected = end.map(|end| end - start + 1);
+    let mut existing = match tokio::fs::metadata(part).await {
+        Ok(meta) if resumable => meta.len(),
+        _ => 0,
+    };
+    if let Some(expected) = expected {
+        if existing == expected {
+            return Ok(existing);
+        }
+        if existing > expected {
+            existing = 0;
+        }
+    }
+
+    let offset = start + existing;
+    let mut request = client.get(url);
+    let ranged = offset > 0 || end.is_some();
+    if ranged {
+        let range = match end {
+            Some(end) => format!("bytes={}-{}", offset, end),
+            None => format!("bytes={}-", offset),
+        };
+        request = request.header(header::RANGE, range);
+    }
+
+    let mut response = request.send().await?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(DownloadError::Status(status));
+    }
+
+    let (mut file, mut written, resumed) = if ranged && status == StatusCode::PARTIAL_CONTENT {
+        let file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(part)
+            .await?;
+        (file, existing, existing)
+    } else if start == 0 && end.is_none() {
+        // Whole-file fallback: the server sent everything from byte 0
+        (tokio::fs::File::create(part).await?, 0, 0)
+    } else {
+        return Err(DownloadError::RangeIgnored(start));
+    };
+
+    while let Some(chunk) = response.chunk().await? {
+        written += chunk.len() as u64;
+        if written > limit || expected.is_some_and(|expected| written > expected) {
+            return Err(DownloadError::TooLarge {
+                size: written,
+                limit: expected.unwrap_or(limit).min(limit),
+            });
+        }
+        file.write_all(&chunk).await?;
+    }
+    file.flush().await?;
+
+    if let Some(expected) = expected {
+        if written != expected {
+            return Err(DownloadError::Incomplete { expected, actual: written });
+        }
+    }
+    Ok(resumed)
+}
+
+/// Concatenate segments into `target`, returning size, SHA-256 and the
+/// leading bytes used for sniffing
+async fn assemble(
+    staging: &Path,
+    segments: usize,
+    target: &Path,
+) -> Result<(u64, String, Vec<u8>), DownloadError> {
+    let mut output = tokio::fs::File::create(target).await?;
+    let mut hasher = Sha256::new();
+    let mut head = Vec::with_capacity(SNIFF_BYTES);
+    let mut size = 0u64;
+    let mut buffer = vec![0u8; 64 * 1024];
+
+    for index in 0..segments {
+        let mut input = tokio::fs::File::open(staging.join(format!("part-{}", index))).await?;
+        loop {
+            let read = input.read(&mut buffer).await?;
+            if read == 0 {
+                break;
+            }
+            let chunk = &buffer[..read];
+            if head.len() < SNIFF_BYTES {
+                let take = (SNIFF_BYTES - head.len()).min(read);
+                head.extend_from_slice(&chunk[..take]);
+            }
+            hasher.update(chunk);
+            output.write_all(chunk).await?;
+            size += read as u64;
+        }
+    }
+    output.flush().await?;
+
+    let sha256 = hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    Ok((size, sha256, head))
+}
+
+/// Split a download into inclusive byte ranges. Unknown length or no range
+/// support means a single open-ended segment.
+fn plan_segments(
+    length: Option<u64>,
+    accepts_ranges: bool,
+    max_segments: usize,
+    min_segment_bytes: u64,
+) -> Vec<(u64, Option<u64>)> {
+    let length = match length {
+        Some(length) if accepts_ranges && length > 0 => length,
+        _ => return vec![(0, None)],
+    };
+    let by_size = length.div_ceil(min_segment_bytes.max(1)) as usize;
+    let count = by_size.clamp(1, max_segments.max(1));
+    let chunk = length.div_ceil(count as u64);
+
+    (0..count as u64)
+        .map(|i| i * chunk)
+        .take_while(|&start| start < length)
+        .map(|start| (start, Some((start + chunk).min(length) - 1)))
+        .collect()
+}
+
+/// Stable ID per URL so a retried download finds its partial segments
+fn download_id(url: &str) -> String {
+    Sha256::digest(url.as_bytes())
+        .iter()
+        .take(8)
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+fn filename_from_url(url: &str) -> String {
+    url::Url::parse(url)
+        .ok()
+        .and_then(|parsed| {
+            parsed
+                .path_segments()
+                .and_then(|mut segments| segments.rfind(|s| !s.is_empty()).map(|s| s.to_string()))
+        })
+        .map(|name| urlencoding::decode(&name).map(|n| n.into_owned()).unwrap_or(name))
+        .unwrap_or_else(|| "download".to_string())
+}
+
+/// Reduce a user- or URL-supplied name to a single safe path component
+fn sanitize_filename(name: &str) -> Result<String, DownloadError> {
+    let name = name
+        .rsplit(['/', '\\'])
+        .next()
+        .unwrap_or_default()
+        .trim();
+    if name.is_empty() || name == "." || name == ".." || name.chars().any(|c| c.is_control()) {
+        return Err(DownloadError::InvalidName(name.to_string()));
+    }
+    Ok(name.to_string())
+}
+
+fn base_mime(mime: &str) -> String {
+    mime.split(';').next().unwrap_or_default().trim().to_ascii_lowercase()
+}
+
+/// Identify content from its leading bytes
+fn sniff_mime(head: &[u8]) -> &'static str {
+    const SIGNATURES: &[(&[u8], &str)] = &[
+        (b"%PDF-", "application/pdf"),
+        (b"\x89PNG\r\n\x1a\n", "image/png"),
+        (b"\xff\xd8\xff", "image/jpeg"),
+        (b"GIF87a", "image/gif"),
+        (b"GIF89a", "image/gif"),
+        (b"PK\x03\x04", "application/zip"),
+        (b"\x1f\x8b", "application/gzip"),
+        (b"BZh", "application/x-bzip2"),
+        (b"\xfd7zXZ\x00", "application/x-xz"),
+        (b"7z\xbc\xaf\x27\x1c", "application/x-7z-compressed"),
+        (b"\x7fELF", "application/x-executable"),
+        (b"MZ", "application/x-msdownload"),
+        (b"\xcf\xfa\xed\xfe", "application/x-mach-binary"),
+        (b"\xfe\xed\xfa\xcf", "application/x-mach-binary"),
+        (b"\xca\xfe\xba\xbe", "application/x-mach-binary"),
+        (b"#!", "text/x-shellscript"),
+        (b"\x00asm", "application/wasm"),
+    ];
+
+    if let Some((_, mime)) = SIGNATURES.iter().find(|(magic, _)| head.starts_with(magic)) {
+        return mime;
+    }
+    if head.len() >= 12 && &head[0..4] == b"RIFF" && &head[8..12] == b"WEBP" {
+        return "image/webp";
+    }
+
+    let text = String::from_utf8_lossy(head);
+    let trimmed = text.trim_start().to_ascii_lowercase();
+    if trimmed.starts_with("<!doctype html") || trimmed.starts_with("<html") {
+        return "text/html";
+    }
+    if trimmed.starts_with("<?xml") || trimmed.starts_with("<svg") {
+        return "application/xml";
+    }
+    if (trimmed.starts_with('{') || trimmed.starts_with('[')) && std::str::from_utf8(head).is_ok() {
+        return "application/json";
+    }
+    if std::str::from_utf8(head).is_ok() && !head.contains(&0) {
+        return "text/plain";
+    }
+    "application/octet-stream"
+}
+
+fn is_executable_mime(mime: &str) -> bool {
+    matches!(
+        mime,
+        "application/x-executable"
+            | "application/x-msdownload"
+            | "application/x-mach-binary"
+            | "text/x-shellscript"
+    )
+}
+
+/// Whether a declared type disagrees with what the bytes look like. Generic
+/// declarations and generic sniff results are not treated as mismatches.
+fn mime_mismatch(declared: &str, sniffed: &str) -> bool {
+    let declared = base_mime(declared);
+    if declared.is_empty()
+        || declared == "application/octet-stream"
+        || sniffed == "application/octet-stream"
+        || declared == sniffed
+    {
+        return false;
+    }
+    let family = |mime: &str| mime.split('/').next().unwrap_or_default().to_string();
+    match sniffed {
+        // Text-like content is legitimately served under many text types
+        "text/plain" | "application/json" | "application/xml" => {
+            !(family(&declared) == "text" || declared.ends_with("json") || declared.ends_with("xml"))
+        }
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    /// Minimal HTTP server that honours HEAD and single Range requests
+    async fn serve(body: Vec<u8>) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            loop {
+                let (mut socket, _) = match listener.accept().await {
+                    Ok(conn) => conn,
+                    Err(_) => return,
+                };
+                let body = body.clone();
+                tokio::spawn(async move {
+                    let mut buf = vec![0u8; 4096];
+                    let n = socket.read(&mut buf).await.unwrap_or(0);
+                    let request = String::from_utf8_lossy(&buf[..n]).to_string();
+                    let range = request
+                        .lines()
+                        .find_map(|l| l.to_ascii_lowercase().strip_prefix("range: bytes=").map(|r| r.to_string()));
+                    let (status, slice) = match range {
+                        Some(range) => {
+                            let (start, end) = range.split_once('-').unwrap();
+                            let start: usize = start.trim().parse().unwrap();
+                            let end: usize = end.trim().parse().unwrap_or(body.len() - 1);
+                            ("206 Partial Content", body[start..=end].to_vec())
+                        }
+                        None => ("200 OK", body.clone()),
+                    };
+                    let head = format!(
+                        "HTTP/1.1 {}\r\nContent-Length: {}\r\nAccept-Ranges: bytes\r\nContent-Type: text/plain\r\nConnection: close\r\n\r\n",
+                        status,
+                        slice.len()
+                    );
+                    let _ = socket.write_all(head.as_bytes()).await;
+                    if !request.starts_with("HEAD") {
+                        let _ = socket.write_all(&slice).await;
+                    }
+                });
+            }
+        });
+        format!("http://{}/files/notes.txt", addr)
+    }
+
+    fn manager(dir: &TempDir) -> DownloadManager {
+        let config = DownloadConfig::new(dir.path().join("workspace")).with_segments(4, 16);
+        DownloadManager::new(Client::new(), config)
+    }
+
+    #[test]
+    fn test_plan_segments() {
+        assert_eq!(plan_segments(None, true, 4, 10), vec![(0, None)]);
+        assert_eq!(plan_segments(Some(100), false, 4, 10), vec![(0, None)]);
+        assert_eq!(plan_segments(Some(5), true, 4, 10), vec![(0, Some(4))]);
+
+        let plan = plan_segments(Some(100), true, 4, 10);
+        assert_eq!(plan, vec![(0, Some(24)), (25, Some(49)), (50, Some(74)), (75, Some(99))]);
+    }
+
+    #[test]
+    fn test_sniff_and_mismatch() {
+        assert_eq!(sniff_mime(b"%PDF-1.7 ..."), "application/pdf");
+        assert_eq!(sniff_mime(b"\x7fELF\x02\x01"), "application/x-executable");
+        assert_eq!(sniff_mime(b"hello world"), "text/plain");
+        assert_eq!(sniff_mime(b"\x00\x01\x02\xff"), "application/octet-stream");
+
+        assert!(mime_mismatch("application/pdf", "application/x-msdownload"));
+        assert!(!mime_mismatch("text/csv; charset=utf-8", "text/plain"));
+        assert!(!mime_mismatch("application/octet-stream", "application/zip"));
+    }
+
+    #[test]
+    fn test_sanitize_filename() {
+        assert_eq!(sanitize_filename("../../etc/passwd").unwrap(), "passwd");
+        assert_eq!(sanitize_filename("C:\\temp\\a.zip").unwrap(), "a.zip");
+        assert!(sanitize_filename("..").is_err());
+        assert!(sanitize_filename("dir/").is_err());
+        assert_eq!(filename_from_url("https://example.com/a/my%20file.tar.gz?x=1"), "my file.tar.gz");
+    }
+
+    #[tokio::test]
+    async fn test_segmented_download_resumes_and_quarantines() {
+        let dir = TempDir::new().unwrap();
+        let body: Vec<u8> = (0..100u8).map(|i| b'a' + (i % 26)).collect();
+        let url = serve(body.clone()).await;
+        let manager = manager(&dir);
+
+        // Simulate an interrupted first attempt that left half of segment 0
+        let staging = manager.config().quarantine_dir.join(PARTIAL_DIR).join(download_id(&url));
+        tokio::fs::create_dir_all(&staging).await.unwrap();
+        tokio::fs::write(staging.join("part-0"), &body[..12]).await.unwrap();
+
+        let expected: String = Sha256::digest(&body).iter().map(|b| format!("{:02x}", b)).collect();
+        let mut request = DownloadRequest::new(url);
+        request.expected_sha256 = Some(expected.to_uppercase());
+        let record = manager.download(&request).await.unwrap();
+
+        assert_eq!(record.size, 100);
+        assert_eq!(record.segments, 4);
+        assert_eq!(record.resumed_bytes, 12);
+        assert!(record.verified);
+        assert_eq!(record.filename, "notes.txt");
+        assert_eq!(record.sniffed_mime, "text/plain");
+        assert!(!manager.config().workspace_dir.join("notes.txt").exists());
+
+        let listed = manager.list_quarantined().await.unwrap();
+        assert_eq!(listed.len(), 1);
+
+        let approved = manager.approve(&record.id).await.unwrap();
+        assert_eq!(tokio::fs::read(&approved).await.unwrap(), body);
+        assert!(manager.list_quarantined().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_checksum_and_size_limits() {
+        let dir = TempDir::new().unwrap();
+        let url = serve(vec![b'x'; 64]).await;
+        let manager = manager(&dir);
+
+        let mut request = DownloadRequest::new(url.clone());
+        request.expected_sha256 = Some("00".repeat(32));
+        assert!(matches!(
+            manager.download(&request).await,
+            Err(DownloadError::ChecksumMismatch { .. })
+        ));
+        assert!(manager.list_quarantined().await.unwrap().is_empty());
+
+        let mut request = DownloadRequest::new(url);
+        request.max_bytes = Some(10);
+        assert!(matches!(
+            manager.download(&request).await,
+            Err(DownloadError::TooLarge { size: 64, limit: 10 })
+        ));
+
+        assert!(manager.reject("../etc").await.is_err());
+    }
+}
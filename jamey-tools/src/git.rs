@@ -0,0 +1,545 @@
+//! Git repository analysis
+//!
+//! [`GitTool`] reads a local repository through libgit2: working tree
+//! status, diffs, blame, history and branches. Every result is plain data
+//! that serializes to JSON, so a model can reason over it without parsing
+//! `git` output. Nothing here changes the repository.
+
+use chrono::{DateTime, Utc};
+use git2::{BlameOptions, BranchType, Delta, DiffOptions, Patch, Repository, Sort, Status, StatusOptions};
+use serde::{Deserialize, Serialize};
+use std::path::{Component, Path, PathBuf};
+use thiserror::Error;
+
+/// Diff lines returned when the request doesn't say
+pub const DEFAULT_DIFF_LINES: usize = 2000;
+
+/// Commits returned by [`GitTool::log`] when the request doesn't say
+pub const DEFAULT_LOG_LIMIT: usize = 20;
+
+#[derive(Debug, Error)]
+pub enum GitError {
+    #[error("Git error: {0}")]
+    Git(#[from] git2::Error),
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Repository has no working directory")]
+    Bare,
+    #[error("Path must be relative to the repository and stay inside it: {0}")]
+    InvalidPath(String),
+}
+
+/// How a file differs between two states
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    Added,
+    Modified,
+    Deleted,
+    Renamed,
+    Copied,
+    TypeChange,
+    Untracked,
+    Conflicted,
+}
+
+impl ChangeKind {
+    fn from_delta(delta: Delta) -> Option<Self> {
+        match delta {
+            Delta::Added => Some(Self::Added),
+            Delta::Modified => Some(Self::Modified),
+            Delta::Deleted => Some(Self::Deleted),
+            Delta::Renamed => Some(Self::Renamed),
+            Delta::Copied => Some(Self::Copied),
+            Delta::Typechange => Some(Self::TypeChange),
+            Delta::Untracked => Some(Self::Untracked),
+            Delta::Conflicted => Some(Self::Conflicted),
+            Delta::Unmodified | Delta::Ignored | Delta::Unreadable => None,
+        }
+    }
+}
+
+/// One changed path in `git status`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StatusEntry {
+    pub path: String,
+    /// Change staged in the index, relative to HEAD
+    pub staged: Option<ChangeKind>,
+    /// Change in the working tree, relative to the index
+    pub unstaged: Option<ChangeKind>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RepoStatus {
+    /// Checked-out branch; `None` when HEAD is detached or unborn
+    pub branch: Option<String>,
+    pub upstream: Option<String>,
+    /// Commits on the branch that its upstream lacks
+    pub ahead: usize,
+    /// Commits on the upstream that the branch lacks
+    pub behind: usize,
+    pub entries: Vec<StatusEntry>,
+}
+
+/// Which two states [`GitTool::diff`] compares
+#[derive(Debug, Clone, Default)]
+pub struct DiffRequest {
+    /// Compare HEAD with the index instead of the index with the working tree
+    pub staged: bool,
+    /// Compare this revision with the working tree (staged changes included);
+    /// overrides `staged`
+    pub base: Option<String>,
+    /// Limit the diff to these paths or globs
+    pub paths: Vec<String>,
+    /// Unchanged lines around each change; git's default of 3 when `None`
+    pub context_lines: Option<u32>,
+    /// Stop adding lines after this many; [`DEFAULT_DIFF_LINES`] when `None`
+    pub max_lines: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiffLine {
+    /// `+` added, `-` removed, ` ` context
+    pub origin: char,
+    pub content: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiffHunk {
+    /// The `@@ -a,b +c,d @@` line
+    pub header: String,
+    pub old_start: u32,
+    pub old_lines: u32,
+    pub new_start: u32,
+    pub new_lines: u32,
+    pub lines: Vec<DiffLine>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileDiff {
+    pub path: String,
+    /// Path before a rename or copy
+    pub old_path: Option<String>,
+    pub change: ChangeKind,
+    pub binary: bool,
+    pub additions: usize,
+    pub deletions: usize,
+    pub hunks: Vec<DiffHunk>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RepoDiff {
+    pub files: Vec<FileDiff>,
+    /// Some lines were left out to stay within `max_lines`; the counts are
+    /// still complete
+    pub truncated: bool,
+}
+
+/// Consecutive lines last changed by the same commit
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlameHunk {
+    /// First line, counting from 1
+    pub start_line: usize,
+    /// `None` for lines that aren't committed yet
+    pub commit: Option<String>,
+    pub author: Option<String>,
+    pub time: Option<DateTime<Utc>>,
+    pub summary: Option<String>,
+    pub lines: Vec<String>,
+}
+
+/// What [`GitTool::log`] walks
+#[derive(Debug, Clone, Default)]
+pub struct LogRequest {
+    /// Start from this revision instead of HEAD
+    pub rev: Option<String>,
+    /// Only commits that changed this path
+    pub path: Option<String>,
+    /// [`DEFAULT_LOG_LIMIT`] when `None`
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CommitInfo {
+    pub id: String,
+    pub short_id: String,
+    pub summary: String,
+    pub author: String,
+    pub email: String,
+    pub time: Option<DateTime<Utc>>,
+    pub parents: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BranchInfo {
+    pub name: String,
+    pub remote: bool,
+    /// Checked out
+    pub head: bool,
+    pub upstream: Option<String>,
+    /// Short ID of the commit the branch points at
+    pub commit: Option<String>,
+    pub summary: Option<String>,
+}
+
+/// Read-only access to one repository
+pub struct GitTool {
+    repo: Repository,
+}
+
+impl GitTool {
+    /// The repository containing `path`
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, GitError> {
+        let repo = Repository::discover(path)?;
+        if repo.workdir().is_none() {
+            return Err(GitError::Bare);
+        }
+        Ok(Self { repo })
+    }
+
+    /// Top of the working tree
+    pub fn workdir(&self) -> &Path {
+        self.repo.workdir().unwrap_or_else(|| self.repo.path())
+    }
+
+    pub fn status(&self) -> Result<RepoStatus, GitError> {
+        let mut options = StatusOptions::new();
+        options
+            .include_untracked(true)
+            .recurse_untracked_dirs(true)
+            .renames_head_to_index(true);
+        let entries = self
+            .repo
+            .statuses(Some(&mut options))?
+            .iter()
+            .filter(|entry| !entry.status().contains(Status::IGNORED))
+            .map(|entry| {
+                let (staged, unstaged) = split_status(entry.status());
+                StatusEntry {
+                    path: String::from_utf8_lossy(entry.path_bytes()).into_owned(),
+                    staged,
+                    unstaged,
+                }
+            })
+            .collect();
+
+        let mut status = RepoStatus {
+            branch: None,
+            upstream: None,
+            ahead: 0,
+            behind: 0,
+            entries,
+        };
+        let head = match self.repo.head() {
+            Ok(head) if head.is_branch() => head,
+            // Detached or no commits yet
+            _ => return Ok(status),
+        };
+        status.branch = head.shorthand().map(String::from);
+        let branch = git2::Branch::wrap(head);
+        if let Ok(upstream) = branch.upstream() {
+            status.upstream = upstream.name()?.map(String::from);
+            if let (Some(local), Some(remote)) = (branch.get().target(), upstream.get().target()) {
+                (status.ahead, status.behind) = self.repo.graph_ahead_behind(local, remote)?;
+            }
+        }
+        Ok(status)
+    }
+
+    pub fn diff(&self, request: &DiffRequest) -> Result<RepoDiff, GitError> {
+        let mut options = DiffOptions::new();
+        if let Some(lines) = request.context_lines {
+            options.context_lines(lines);
+        }
+        for path in &request.paths {
+            options.pathspec(path);
+        }
+        let mut diff = if let Some(base) = &request.base {
+            let tree = self.repo.revparse_single(base)?.peel_to_tree()?;
+            self.repo.diff_tree_to_workdir_with_index(Some(&tree), Some(&mut options))?
+        } else if request.staged {
+            let head = match self.repo.head() {
+                Ok(head) => Some(head.peel_to_tree()?),
+                Err(_) => None,
+            };
+            self.repo.diff_tree_to_index(head.as_ref(), None, Some(&mut options))?
+        } else {
+            self.repo.diff_index_to_workdir(None, Some(&mut options))?
+        };
+        diff.find_similar(None)?;
+
+        let mut budget = request.max_lines.unwrap_or(DEFAULT_DIFF_LINES);
+        let mut result = RepoDiff {
+            files: Vec::new(),
+            truncated: false,
+        };
+        for (idx, delta) in diff.deltas().enumerate() {
+            let Some(change) = ChangeKind::from_delta(delta.status()) else {
+                continue;
+            };
+            let path = |file: git2::DiffFile| file.path().map(|p| p.to_string_lossy().into_owned());
+            let new_path = path(delta.new_file());
+            let old_path = path(delta.old_file());
+            let mut file = FileDiff {
+                path: new_path.clone().or_else(|| old_path.clone()).unwrap_or_default(),
+                old_path: old_path.filter(|old| Some(old) != new_path.as_ref()),
+                change,
+                binary: delta.flags().is_binary(),
+                additions: 0,
+                deletions: 0,
+                hunks: Vec::new(),
+            };
+            if let Some(patch) = Patch::from_diff(&diff, idx)? {
+                let (_, additions, deletions) = patch.line_stats()?;
+                file.additions = additions;
+                file.deletions = deletions;
+                for h in 0..patch.num_hunks() {
+                    if budget == 0 {
+                        result.truncated = true;
+                        break;
+                    }
+                    let (hunk, count) = patch.hunk(h)?;
+                    let mut lines = Vec::new();
+                    for l in 0..count {
+                        if budget == 0 {
+                            result.truncated = true;
+                            break;
+                        }
+                        let line = patch.line_in_hunk(h, l)?;
+                        lines.push(DiffLine {
+                            origin: line.origin(),
+                            content: String::from_utf8_lossy(line.content()).trim_end_matches('\n').to_string(),
+                        });
+                        budget -= 1;
+                    }
+                    file.hunks.push(DiffHunk {
+                        header: String::from_utf8_lossy(hunk.header()).trim_end().to_string(),
+                        old_start: hunk.old_start(),
+                        old_lines: hunk.old_lines(),
+                        new_start: hunk.new_start(),
+                        new_lines: hunk.new_lines(),
+                        lines,
+                    });
+                }
+            }
+            result.files.push(file);
+        }
+        Ok(result)
+    }
+
+    /// Who last changed each line of `path`, optionally only lines
+    /// `start..=end` (counting from 1)
+    pub fn blame(&self, path: &str, lines: Option<(usize, usize)>) -> Result<Vec<BlameHunk>, GitError> {
+        let relative = relative_path(path)?;
+        let mut options = BlameOptions::new();
+        if let Some((start, end)) = lines {
+            options.min_line(start).max_line(end);
+        }
+        let blame = self.repo.blame_file(&relative, Some(&mut options))?;
+        let content = std::fs::read_to_string(self.workdir().join(&relative))?;
+        let content: Vec<&str> = content.lines().collect();
+
+        let mut hunks = Vec::new();
+        for hunk in blame.iter() {
+            let start = hunk.final_start_line();
+            let lines = content
+                .iter()
+                .skip(start.saturating_sub(1))
+                .take(hunk.lines_in_hunk())
+                .map(|line| line.to_string())
+                .collect();
+            let id = hunk.final_commit_id();
+            let commit = if id.is_zero() { None } else { self.repo.find_commit(id).ok() };
+            let signature = hunk.final_signature();
+            hunks.push(BlameHunk {
+                start_line: start,
+                commit: commit.as_ref().map(|c| short_id(c.id())),
+                author: commit.as_ref().and_then(|_| signature.name().map(String::from)),
+                time: commit.as_ref().and_then(|_| timestamp(signature.when())),
+                summary: commit.as_ref().and_then(|c| c.summary().map(String::from)),
+                lines,
+            });
+        }
+        Ok(hunks)
+    }
+
+    /// Commits newest first
+    pub fn log(&self, request: &LogRequest) -> Result<Vec<CommitInfo>, GitError> {
+        let limit = request.limit.unwrap_or(DEFAULT_LOG_LIMIT);
+        let path = request.path.as_deref().map(relative_path).transpose()?;
+        let mut walk = self.repo.revwalk()?;
+        walk.set_sorting(Sort::TIME)?;
+        match &request.rev {
+            Some(rev) => walk.push(self.repo.revparse_single(rev)?.peel_to_commit()?.id())?,
+            None => match walk.push_head() {
+                Ok(()) => {}
+                // No commits yet
+                Err(e) if e.code() == git2::ErrorCode::UnbornBranch => return Ok(Vec::new()),
+                Err(e) => return Err(e.into()),
+            },
+        }
+
+        let mut commits = Vec::new();
+        for id in walk {
+            if commits.len() >= limit {
+                break;
+            }
+            let commit = self.repo.find_commit(id?)?;
+            if let Some(path) = &path {
+                if !touches(&commit, path) {
+                    continue;
+                }
+            }
+            let author = commit.author();
+            commits.push(CommitInfo {
+                id: commit.id().to_string(),
+                short_id: short_id(commit.id()),
+                summary: commit.summary().unwrap_or_default().to_string(),
+                author: author.name().unwrap_or_default().to_string(),
+                email: author.email().unwrap_or_default().to_string(),
+                time: timestamp(commit.time()),
+                parents: commit.parent_ids().map(short_id).collect(),
+            });
+        }
+        Ok(commits)
+    }
+
+    /// Local branches, then remote-tracking ones
+    pub fn branches(&self) -> Result<Vec<BranchInfo>, GitError> {
+        let mut branches = Vec::new();
+        for branch in self.repo.branches(None)? {
+            let (branch, kind) = branch?;
+            let commit = branch.get().peel_to_commit().ok();
+            branches.push(BranchInfo {
+                name: branch.name()?.unwrap_or_default().to_string(),
+                remote: kind == BranchType::Remote,
+                head: branch.is_head(),
+                upstream: branch
+                    .upstream()
+                    .ok()
+                    .and_then(|upstream| upstream.name().ok().flatten().map(String::from)),
+                commit: commit.as_ref().map(|c| short_id(c.id())),
+                summary: commit.as_ref().and_then(|c| c.summary().map(String::from)),
+            });
+        }
+        branches.sort_by(|a, b| a.remote.cmp(&b.remote).then_with(|| a.name.cmp(&b.name)));
+        Ok(branches)
+    }
+}
+
+/// Staged and unstaged halves of a status entry
+fn split_status(status: Status) -> (Option<ChangeKind>, Option<ChangeKind>) {
+    if status.contains(Status::CONFLICTED) {
+        return (None, Some(ChangeKind::Conflicted));
+    }
+    let staged = [
+        (Status::INDEX_NEW, ChangeKind::Added),
+        (Status::INDEX_MODIFIED, ChangeKind::Modified),
+        (Status::INDEX_DELETED, ChangeKind::Deleted),
+        (Status::INDEX_RENAMED, ChangeKind::Renamed),
+        (Status::INDEX_TYPECHANGE, ChangeKind::TypeChange),
+    ];
+    let unstaged = [
+        (Status::WT_NEW, ChangeKind::Untracked),
+        (Status::WT_MODIFIED, ChangeKind::Modified),
+        (Status::WT_DELETED, ChangeKind::Deleted),
+        (Status::WT_RENAMED, ChangeKind::Renamed),
+        (Status::WT_TYPECHANGE, ChangeKind::TypeChange),
+    ];
+    let pick = |flags: &[(Status, ChangeKind)]| {
+        flags.iter().find(|(flag, _)| status.contains(*flag)).map(|(_, kind)| *kind)
+    };
+    (pick(&staged), pick(&unstaged))
+}
+
+/// `path` as a repository-relative path, refusing ones that could leave
+/// the working tree
+fn relative_path(path: &str) -> Result<PathBuf, GitError> {
+    let relative = PathBuf::from(path);
+    if relative.components().all(|c| matches!(c, Component::Normal(_) | Component::CurDir)) {
+        Ok(relative)
+    } else {
+        Err(GitError::InvalidPath(path.to_string()))
+    }
+}
+
+/// Whether `commit` changed `path` compared with every parent, the way
+/// `git log -- <path>` simplifies merges
+fn touches(commit: &git2::Commit, path: &Path) -> bool {
+    let entry = |tree: Result<git2::Tree, git2::Error>| tree.ok().and_then(|t| t.get_path(path).ok()).map(|e| e.id());
+    let own = entry(commit.tree());
+    if commit.parent_count() == 0 {
+        return own.is_some();
+    }
+    commit.parents().all(|parent| entry(parent.tree()) != own)
+}
+
+fn short_id(id: git2::Oid) -> String {
+    id.to_string()[..7].to_string()
+}
+
+fn timestamp(time: git2::Time) -> Option<DateTime<Utc>> {
+    DateTime::from_timestamp(time.seconds(), 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use git2::Signature;
+
+    fn commit(repo: &Repository, file: &str, content: &str, message: &str) -> git2::Oid {
+        std::fs::write(repo.workdir().unwrap().join(file), content).unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new(file)).unwrap();
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let signature = Signature::now("Jamey", "jamey@example.com").unwrap();
+        let parents: Vec<_> = repo.head().ok().and_then(|h| h.peel_to_commit().ok()).into_iter().collect();
+        let parents: Vec<_> = parents.iter().collect();
+        repo.commit(Some("HEAD"), &signature, &signature, message, &tree, &parents).unwrap()
+    }
+
+    #[test]
+    fn test_status_diff_blame_log() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+        commit(&repo, "main.rs", "fn main() {}\n", "Add main");
+        commit(&repo, "lib.rs", "pub fn one() {}\n", "Add lib");
+        std::fs::write(dir.path().join("main.rs"), "fn main() {\n    run();\n}\n").unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "todo\n").unwrap();
+
+        let git = GitTool::open(dir.path()).unwrap();
+        let status = git.status().unwrap();
+        assert!(status.branch.is_some());
+        let entry = |path: &str| status.entries.iter().find(|e| e.path == path).unwrap();
+        assert_eq!(entry("main.rs").unstaged, Some(ChangeKind::Modified));
+        assert_eq!(entry("notes.txt").unstaged, Some(ChangeKind::Untracked));
+        assert_eq!(entry("main.rs").staged, None);
+
+        let diff = git.diff(&DiffRequest::default()).unwrap();
+        assert_eq!(diff.files.len(), 1);
+        let file = &diff.files[0];
+        assert_eq!((file.path.as_str(), file.change), ("main.rs", ChangeKind::Modified));
+        assert_eq!((file.additions, file.deletions), (3, 1));
+        assert!(file.hunks[0].lines.iter().any(|l| l.origin == '+' && l.content == "    run();"));
+        let capped = git.diff(&DiffRequest { max_lines: Some(1), ..Default::default() }).unwrap();
+        assert!(capped.truncated);
+        assert!(git.diff(&DiffRequest { staged: true, ..Default::default() }).unwrap().files.is_empty());
+
+        let log = git.log(&LogRequest::default()).unwrap();
+        let summaries: Vec<_> = log.iter().map(|c| c.summary.as_str()).collect();
+        assert_eq!(summaries, ["Add lib", "Add main"]);
+        let only_main = git.log(&LogRequest { path: Some("main.rs".into()), ..Default::default() }).unwrap();
+        assert_eq!(only_main.len(), 1);
+        assert_eq!(only_main[0].summary, "Add main");
+
+        let blame = git.blame("lib.rs", None).unwrap();
+        assert_eq!(blame[0].summary.as_deref(), Some("Add lib"));
+        assert_eq!(blame[0].lines, ["pub fn one() {}"]);
+        assert!(matches!(git.blame("../etc/passwd", None), Err(GitError::InvalidPath(_))));
+
+        let branches = git.branches().unwrap();
+        assert_eq!(branches.len(), 1);
+        assert!(branches[0].head && !branches[0].remote);
+    }
+}
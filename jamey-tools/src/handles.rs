@@ -0,0 +1,362 @@
+//! Sockets and open files of running processes
+//!
+//! [`ProcessTool`](crate::system::ProcessTool) uses these to answer "what's
+//! listening on this port" and "what does this process have open". What is
+//! visible depends on the platform and on Jamey's privileges:
+//!
+//! - **Linux**: `/proc/net/{tcp,tcp6,udp,udp6}` list every socket, and the
+//!   owner is found through `/proc/<pid>/fd`. Sockets of processes owned by
+//!   other users have no owner unless Jamey runs as root.
+//! - **macOS** and other Unixes: `lsof`, with the same limits.
+//! - **Windows**: `netstat -ano` lists sockets with their owners. Open files
+//!   aren't available.
+
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Protocol {
+    Tcp,
+    Udp,
+}
+
+/// One socket and, when it could be seen, the process holding it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Socket {
+    pub protocol: Protocol,
+    pub local: SocketAddr,
+    /// Peer of a connected socket
+    pub remote: Option<SocketAddr>,
+    /// TCP state such as `LISTEN` or `ESTABLISHED`; `None` for UDP
+    pub state: Option<String>,
+    pub pid: Option<u32>,
+}
+
+impl Socket {
+    /// A TCP socket accepting connections, or a bound, unconnected UDP socket
+    pub fn is_listening(&self) -> bool {
+        match self.protocol {
+            Protocol::Tcp => self.state.as_deref() == Some("LISTEN"),
+            Protocol::Udp => self.remote.is_none(),
+        }
+    }
+}
+
+/// Every TCP and UDP socket on the machine
+pub fn sockets() -> io::Result<Vec<Socket>> {
+    platform::sockets()
+}
+
+/// Files `pid` has open. Fails with [`io::ErrorKind::PermissionDenied`] when
+/// the process belongs to someone else, and [`io::ErrorKind::Unsupported`]
+/// where the platform can't tell.
+pub fn open_files(pid: u32) -> io::Result<Vec<PathBuf>> {
+    platform::open_files(pid)
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use super::*;
+    use std::collections::HashMap;
+    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+    pub fn sockets() -> io::Result<Vec<Socket>> {
+        let owners = socket_owners();
+        let mut sockets = Vec::new();
+        for (file, protocol) in [
+            ("tcp", Protocol::Tcp),
+            ("tcp6", Protocol::Tcp),
+            ("udp", Protocol::Udp),
+            ("udp6", Protocol::Udp),
+        ] {
+            let table = match std::fs::read_to_string(format!("/proc/net/{}", file)) {
+                Ok(table) => table,
+                // No IPv6 on this machine
+                Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e),
+            };
+            sockets.extend(
+                parse_proc_net(&table, protocol)
+                    .into_iter()
+                    .map(|(mut socket, inode)| {
+                        socket.pid = owners.get(&inode).copied();
+                        socket
+                    }),
+            );
+        }
+        Ok(sockets)
+    }
+
+    pub fn open_files(pid: u32) -> io::Result<Vec<PathBuf>> {
+        let mut files: Vec<PathBuf> = fd_targets(pid)?
+            .into_iter()
+            .filter(|target| target.is_absolute())
+            .collect();
+        files.sort();
+        files.dedup();
+        Ok(files)
+    }
+
+    fn fd_targets(pid: u32) -> io::Result<Vec<PathBuf>> {
+        let mut targets = Vec::new();
+        for fd in std::fs::read_dir(format!("/proc/{}/fd", pid))? {
+            if let Ok(target) = std::fs::read_link(fd?.path()) {
+                targets.push(target);
+            }
+        }
+        Ok(targets)
+    }
+
+    /// Socket inode to the process holding it, for the processes we may
+    /// look into
+    fn socket_owners() -> HashMap<u64, u32> {
+        let mut owners = HashMap::new();
+        let Ok(entries) = std::fs::read_dir("/proc") else {
+            return owners;
+        };
+        for entry in entries.flatten() {
+            let Some(pid) = entry.file_name().to_str().and_then(|name| name.parse::<u32>().ok()) else {
+                continue;
+            };
+            for target in fd_targets(pid).unwrap_or_default() {
+                let inode = target
+                    .to_str()
+                    .and_then(|t| t.strip_prefix("socket:["))
+                    .and_then(|t| t.strip_suffix(']'))
+                    .and_then(|t| t.parse().ok());
+                if let Some(inode) = inode {
+                    owners.entry(inode).or_insert(pid);
+                }
+            }
+        }
+        owners
+    }
+
+    /// Sockets in a `/proc/net` table with their inodes
+    pub(super) fn parse_proc_net(table: &str, protocol: Protocol) -> Vec<(Socket, u64)> {
+        table
+            .lines()
+            .skip(1)
+            .filter_map(|line| {
+                let columns: Vec<&str> = line.split_whitespace().collect();
+                let local = parse_hex_addr(columns.get(1)?)?;
+                let remote = parse_hex_addr(columns.get(2)?)?;
+                let state = u8::from_str_radix(columns.get(3)?, 16).ok()?;
+                let inode = columns.get(9)?.parse().ok()?;
+                let socket = Socket {
+                    protocol,
+                    local,
+                    remote: (remote.port() != 0).then_some(remote),
+                    state: match protocol {
+                        Protocol::Tcp => Some(tcp_state(state).to_string()),
+                        Protocol::Udp => None,
+                    },
+                    pid: None,
+                };
+                Some((socket, inode))
+            })
+            .collect()
+    }
+
+    /// `0100007F:1F90` or its 32-digit IPv6 form; the address is in host
+    /// byte order, 32 bits at a time
+    fn parse_hex_addr(text: &str) -> Option<SocketAddr> {
+        let (addr, port) = text.split_once(':')?;
+        let port = u16::from_str_radix(port, 16).ok()?;
+        let words = (0..addr.len() / 8)
+            .map(|i| u32::from_str_radix(&addr[i * 8..i * 8 + 8], 16).map(|w| w.to_ne_bytes()))
+            .collect::<Result<Vec<_>, _>>()
+            .ok()?;
+        let ip = match words.as_slice() {
+            [a] => IpAddr::V4(Ipv4Addr::from(*a)),
+            [a, b, c, d] => {
+                let mut octets = [0u8; 16];
+                for (chunk, word) in octets.chunks_mut(4).zip([a, b, c, d]) {
+                    chunk.copy_from_slice(word);
+                }
+                IpAddr::V6(Ipv6Addr::from(octets))
+            }
+            _ => return None,
+        };
+        Some(SocketAddr::new(ip, port))
+    }
+
+    fn tcp_state(code: u8) -> &'static str {
+        match code {
+            0x01 => "ESTABLISHED",
+            0x02 => "SYN_SENT",
+            0x03 => "SYN_RECV",
+            0x04 => "FIN_WAIT1",
+            0x05 => "FIN_WAIT2",
+            0x06 => "TIME_WAIT",
+            0x07 => "CLOSE",
+            0x08 => "CLOSE_WAIT",
+            0x09 => "LAST_ACK",
+            0x0A => "LISTEN",
+            0x0B => "CLOSING",
+            _ => "UNKNOWN",
+        }
+    }
+}
+
+#[cfg(all(unix, not(target_os = "linux")))]
+mod platform {
+    use super::*;
+    use std::net::{IpAddr, Ipv4Addr};
+
+    pub fn sockets() -> io::Result<Vec<Socket>> {
+        Ok(parse_lsof(&lsof(&["-nP", "-i", "-F", "pPnT"])?))
+    }
+
+    pub fn open_files(pid: u32) -> io::Result<Vec<PathBuf>> {
+        let output = lsof(&["-nP", "-p", &pid.to_string(), "-F", "tn"])?;
+        let mut files = Vec::new();
+        let mut regular = false;
+        for line in output.lines() {
+            match line.split_at(line.len().min(1)) {
+                ("t", kind) => regular = kind == "REG",
+                ("n", name) if regular => files.push(PathBuf::from(name)),
+                _ => {}
+            }
+        }
+        if files.is_empty() && !output.lines().any(|line| line.starts_with('p')) {
+            return Err(io::Error::new(io::ErrorKind::PermissionDenied, format!("can't inspect process {}", pid)));
+        }
+        files.sort();
+        files.dedup();
+        Ok(files)
+    }
+
+    fn lsof(args: &[&str]) -> io::Result<String> {
+        // lsof exits with 1 when some processes couldn't be inspected
+        let output = std::process::Command::new("lsof").args(args).output()?;
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+
+    /// `lsof -F pPnT` output: a `p` line per process, then `P`, `n` and
+    /// `T` lines per socket
+    pub(super) fn parse_lsof(output: &str) -> Vec<Socket> {
+        let mut sockets = Vec::new();
+        let mut pid = None;
+        let mut protocol = None;
+        for line in output.lines() {
+            let (field, value) = line.split_at(line.len().min(1));
+            match field {
+                "p" => pid = value.parse().ok(),
+                "P" => {
+                    protocol = match value {
+                        "TCP" => Some(Protocol::Tcp),
+                        "UDP" => Some(Protocol::Udp),
+                        _ => None,
+                    }
+                }
+                "n" => {
+                    let Some(protocol) = protocol else { continue };
+                    let (local, remote) = match value.split_once("->") {
+                        Some((local, remote)) => (local, parse_lsof_addr(remote)),
+                        None => (value, None),
+                    };
+                    if let Some(local) = parse_lsof_addr(local) {
+                        sockets.push(Socket { protocol, local, remote, state: None, pid });
+                    }
+                }
+                "T" => {
+                    if let (Some(socket), Some(state)) = (sockets.last_mut(), value.strip_prefix("ST=")) {
+                        if socket.protocol == Protocol::Tcp {
+                            socket.state = Some(state.to_string());
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        sockets
+    }
+
+    /// `127.0.0.1:8080`, `[::1]:8080` or `*:8080`
+    fn parse_lsof_addr(text: &str) -> Option<SocketAddr> {
+        let (host, port) = text.rsplit_once(':')?;
+        let port = port.parse().ok()?;
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+        let ip = if host == "*" { IpAddr::V4(Ipv4Addr::UNSPECIFIED) } else { host.parse().ok()? };
+        Some(SocketAddr::new(ip, port))
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use super::*;
+
+    pub fn sockets() -> io::Result<Vec<Socket>> {
+        let output = std::process::Command::new("netstat").args(["-ano"]).output()?;
+        if !output.status.success() {
+            return Err(io::Error::new(io::ErrorKind::Other, "netstat failed"));
+        }
+        Ok(parse_netstat(&String::from_utf8_lossy(&output.stdout)))
+    }
+
+    pub fn open_files(_pid: u32) -> io::Result<Vec<PathBuf>> {
+        Err(io::Error::new(io::ErrorKind::Unsupported, "open files aren't listed on Windows"))
+    }
+
+    /// `netstat -ano` rows: protocol, local, remote, state (TCP only), pid
+    pub(super) fn parse_netstat(output: &str) -> Vec<Socket> {
+        output
+            .lines()
+            .filter_map(|line| {
+                let columns: Vec<&str> = line.split_whitespace().collect();
+                let protocol = match *columns.first()? {
+                    "TCP" => Protocol::Tcp,
+                    "UDP" => Protocol::Udp,
+                    _ => return None,
+                };
+                let local: SocketAddr = columns.get(1)?.parse().ok()?;
+                let remote = columns.get(2).and_then(|r| r.parse::<SocketAddr>().ok()).filter(|r| r.port() != 0);
+                let (state, pid) = match protocol {
+                    Protocol::Tcp => (Some(columns.get(3)?.replace("LISTENING", "LISTEN")), columns.get(4)),
+                    Protocol::Udp => (None, columns.get(3)),
+                };
+                Some(Socket { protocol, local, remote, state, pid: pid.and_then(|p| p.parse().ok()) })
+            })
+            .collect()
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_proc_net() {
+        let table = "  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode\n\
+            \x20  0: 0100007F:1F90 00000000:0000 0A 00000000:00000000 00:00000000 00000000  1000        0 4242 1 0000000000000000 100 0 0 10 0\n\
+            \x20  1: 0100007F:1F90 0100007F:D431 01 00000000:00000000 00:00000000 00000000  1000        0 4243 1 0000000000000000 20 4 30 10 -1\n";
+        let sockets = platform::parse_proc_net(table, Protocol::Tcp);
+        assert_eq!(sockets.len(), 2);
+        let (listener, inode) = &sockets[0];
+        assert_eq!(*inode, 4242);
+        assert_eq!(listener.local, "127.0.0.1:8080".parse().unwrap());
+        assert!(listener.is_listening());
+        assert_eq!(sockets[1].0.remote, Some("127.0.0.1:54321".parse().unwrap()));
+        assert!(!sockets[1].0.is_listening());
+
+        let table6 = "header\n   0: 00000000000000000000000001000000:0050 00000000000000000000000000000000:0000 0A 0 0 0 0 0 77\n";
+        let (socket, _) = &platform::parse_proc_net(table6, Protocol::Tcp)[0];
+        assert_eq!(socket.local, "[::1]:80".parse().unwrap());
+    }
+
+    #[test]
+    fn test_own_listener_is_found() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let socket = sockets()
+            .unwrap()
+            .into_iter()
+            .find(|s| s.local.port() == port && s.is_listening())
+            .unwrap();
+        assert_eq!(socket.pid, Some(std::process::id()));
+    }
+}
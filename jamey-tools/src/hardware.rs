@@ -0,0 +1,460 @@
+//! GPU and accelerator inventory
+//!
+//! [`HardwareInventoryTool`] reports the machine's GPUs with their memory,
+//! driver and current load, alongside CPU and system memory. The status
+//! display shows the snapshot, and a local model provider can size its model
+//! with [`HardwareInventory::max_model_params`].
+//!
+//! GPUs are found through:
+//!
+//! - **NVML** (the `nvml` feature, on by default) for NVIDIA cards on every
+//!   platform. The library ships with the driver and is loaded at runtime,
+//!   so machines without it simply report no NVIDIA GPUs.
+//! - **sysfs** on Linux for the rest; AMD cards report VRAM and load there.
+//! - **WMI** (`Win32_VideoController`) on Windows. It can't report load, and
+//!   its memory figure stops at 4 GiB.
+//! - **system_profiler** on macOS. Apple silicon shares system memory with
+//!   the GPU, so it has no VRAM of its own.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sysinfo::{CpuExt, System, SystemExt};
+
+/// Share of the memory budget left for the KV cache and runtime when
+/// sizing a model
+const MODEL_HEADROOM: f64 = 0.2;
+
+/// Share of system memory macOS lets the GPU wire on unified memory machines
+const UNIFIED_GPU_SHARE: f64 = 0.75;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GpuVendor {
+    Nvidia,
+    Amd,
+    Intel,
+    Apple,
+    Other,
+}
+
+impl GpuVendor {
+    /// Vendor from a PCI vendor ID
+    pub fn from_pci_id(id: u16) -> Self {
+        match id {
+            0x10de => Self::Nvidia,
+            0x1002 | 0x1022 => Self::Amd,
+            0x8086 => Self::Intel,
+            0x106b => Self::Apple,
+            _ => Self::Other,
+        }
+    }
+
+    /// Vendor from a name such as "NVIDIA" or "Advanced Micro Devices, Inc."
+    pub fn from_name(name: &str) -> Self {
+        let name = name.to_lowercase();
+        if name.contains("nvidia") {
+            Self::Nvidia
+        } else if name.contains("amd") || name.contains("advanced micro devices") || name.contains("ati ") {
+            Self::Amd
+        } else if name.contains("intel") {
+            Self::Intel
+        } else if name.contains("apple") {
+            Self::Apple
+        } else {
+            Self::Other
+        }
+    }
+}
+
+/// One GPU; fields its source can't report are `None`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GpuInfo {
+    /// Position among the GPUs found, from 0
+    pub index: usize,
+    pub name: String,
+    pub vendor: GpuVendor,
+    pub uuid: Option<String>,
+    pub driver_version: Option<String>,
+    pub memory_total_bytes: Option<u64>,
+    pub memory_used_bytes: Option<u64>,
+    /// Share of time the GPU was busy over the driver's last sample, 0-100
+    pub utilization_percent: Option<u32>,
+    pub temperature_celsius: Option<u32>,
+    /// Whether the GPU uses system memory instead of its own
+    pub unified_memory: bool,
+    /// Where the figures came from: nvml, sysfs, wmi or system_profiler
+    pub source: String,
+}
+
+impl GpuInfo {
+    pub fn memory_free_bytes(&self) -> Option<u64> {
+        Some(self.memory_total_bytes?.saturating_sub(self.memory_used_bytes.unwrap_or(0)))
+    }
+}
+
+/// A snapshot of the machine's compute resources
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HardwareInventory {
+    pub taken_at: DateTime<Utc>,
+    pub cpu_brand: String,
+    pub cpu_cores: usize,
+    pub memory_total_bytes: u64,
+    pub memory_available_bytes: u64,
+    pub gpus: Vec<GpuInfo>,
+}
+
+impl HardwareInventory {
+    /// Bytes a local model may occupy: free memory on the roomiest GPU, the
+    /// GPU's share of system memory on unified memory machines, or
+    /// available system memory when there is no usable GPU
+    pub fn model_memory_budget(&self) -> u64 {
+        let dedicated = self.gpus.iter().filter(|gpu| !gpu.unified_memory).filter_map(GpuInfo::memory_free_bytes).max();
+        if let Some(free) = dedicated {
+            return free;
+        }
+        if self.gpus.iter().any(|gpu| gpu.unified_memory) {
+            return (self.memory_available_bytes as f64 * UNIFIED_GPU_SHARE) as u64;
+        }
+        self.memory_available_bytes
+    }
+
+    /// Largest model, in parameters, whose weights fit
+    /// [`model_memory_budget`](Self::model_memory_budget) at
+    /// `bits_per_weight` (16 for fp16, about 4.5 for Q4_K_M), keeping some
+    /// room for the KV cache
+    pub fn max_model_params(&self, bits_per_weight: f64) -> u64 {
+        if bits_per_weight <= 0.0 {
+            return 0;
+        }
+        let usable = self.model_memory_budget() as f64 * (1.0 - MODEL_HEADROOM);
+        (usable * 8.0 / bits_per_weight) as u64
+    }
+}
+
+/// Reads the hardware inventory. Holding on to one keeps NVML loaded
+/// between snapshots.
+pub struct HardwareInventoryTool {
+    #[cfg(feature = "nvml")]
+    nvml: Option<nvml_wrapper::Nvml>,
+}
+
+impl HardwareInventoryTool {
+    pub fn new() -> Self {
+        Self {
+            #[cfg(feature = "nvml")]
+            nvml: match nvml_wrapper::Nvml::init() {
+                Ok(nvml) => Some(nvml),
+                Err(e) => {
+                    tracing::debug!("NVML unavailable, NVIDIA GPUs won't be listed: {}", e);
+                    None
+                }
+            },
+        }
+    }
+
+    /// Take a snapshot; this blocks while the drivers are queried
+    pub fn snapshot(&self) -> HardwareInventory {
+        let mut system = System::new();
+        system.refresh_memory();
+        system.refresh_cpu();
+
+        let mut gpus = self.nvml_gpus();
+        let have_nvml = !gpus.is_empty();
+        gpus.extend(
+            platform::gpus()
+                .into_iter()
+                .filter(|gpu| !(have_nvml && gpu.vendor == GpuVendor::Nvidia)),
+        );
+        for (index, gpu) in gpus.iter_mut().enumerate() {
+            gpu.index = index;
+        }
+
+        HardwareInventory {
+            taken_at: Utc::now(),
+            cpu_brand: system.global_cpu_info().brand().trim().to_string(),
+            cpu_cores: system.physical_core_count().unwrap_or_else(|| system.cpus().len()),
+            memory_total_bytes: system.total_memory(),
+            memory_available_bytes: system.available_memory(),
+            gpus,
+        }
+    }
+
+    #[cfg(feature = "nvml")]
+    fn nvml_gpus(&self) -> Vec<GpuInfo> {
+        use nvml_wrapper::enum_wrappers::device::TemperatureSensor;
+
+        let Some(nvml) = &self.nvml else {
+            return Vec::new();
+        };
+        let driver_version = nvml.sys_driver_version().ok();
+        let count = nvml.device_count().unwrap_or(0);
+        (0..count)
+            .filter_map(|i| nvml.device_by_index(i).ok())
+            .map(|device| {
+                let memory = device.memory_info().ok();
+                GpuInfo {
+                    index: 0,
+                    name: device.name().unwrap_or_else(|_| "NVIDIA GPU".to_string()),
+                    vendor: GpuVendor::Nvidia,
+                    uuid: device.uuid().ok(),
+                    driver_version: driver_version.clone(),
+                    memory_total_bytes: memory.as_ref().map(|m| m.total),
+                    memory_used_bytes: memory.as_ref().map(|m| m.used),
+                    utilization_percent: device.utilization_rates().ok().map(|u| u.gpu),
+                    temperature_celsius: device.temperature(TemperatureSensor::Gpu).ok(),
+                    unified_memory: false,
+                    source: "nvml".to_string(),
+                }
+            })
+            .collect()
+    }
+
+    #[cfg(not(feature = "nvml"))]
+    fn nvml_gpus(&self) -> Vec<GpuInfo> {
+        Vec::new()
+    }
+}
+
+impl Default for HardwareInventoryTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn blank_gpu(name: String, vendor: GpuVendor, source: &str) -> GpuInfo {
+    GpuInfo {
+        index: 0,
+        name,
+        vendor,
+        uuid: None,
+        driver_version: None,
+        memory_total_bytes: None,
+        memory_used_bytes: None,
+        utilization_percent: None,
+        temperature_celsius: None,
+        unified_memory: false,
+        source: source.to_string(),
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use super::*;
+    use std::path::Path;
+
+    /// GPUs under `/sys/class/drm`; connectors such as `card0-HDMI-A-1` are
+    /// skipped
+    pub fn gpus() -> Vec<GpuInfo> {
+        let Ok(entries) = std::fs::read_dir("/sys/class/drm") else {
+            return Vec::new();
+        };
+        let mut cards: Vec<_> = entries
+            .flatten()
+            .filter(|entry| {
+                let name = entry.file_name();
+                let name = name.to_string_lossy();
+                name.strip_prefix("card").is_some_and(|n| !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()))
+            })
+            .map(|entry| entry.path())
+            .collect();
+        cards.sort();
+        cards.iter().filter_map(|card| sysfs_gpu(&card.join("device"))).collect()
+    }
+
+    fn read(path: &Path) -> Option<String> {
+        std::fs::read_to_string(path).ok().map(|s| s.trim().to_string())
+    }
+
+    fn read_hex(path: &Path) -> Option<u16> {
+        u16::from_str_radix(read(path)?.trim_start_matches("0x"), 16).ok()
+    }
+
+    pub(super) fn sysfs_gpu(device: &Path) -> Option<GpuInfo> {
+        let vendor_id = read_hex(&device.join("vendor"))?;
+        let vendor = GpuVendor::from_pci_id(vendor_id);
+        let device_id = read_hex(&device.join("device")).unwrap_or(0);
+        let name = read(&device.join("product_name"))
+            .filter(|name| !name.is_empty())
+            .unwrap_or_else(|| format!("{:?} GPU {:04x}:{:04x}", vendor, vendor_id, device_id));
+        let driver = std::fs::read_link(device.join("driver"))
+            .ok()
+            .and_then(|link| link.file_name().map(|n| n.to_string_lossy().into_owned()));
+        let driver_version = driver.as_ref().and_then(|driver| {
+            read(&Path::new("/sys/module").join(driver).join("version"))
+                .or_else(|| Some(driver.clone()))
+        });
+        Some(GpuInfo {
+            driver_version,
+            memory_total_bytes: read(&device.join("mem_info_vram_total")).and_then(|v| v.parse().ok()),
+            memory_used_bytes: read(&device.join("mem_info_vram_used")).and_then(|v| v.parse().ok()),
+            utilization_percent: read(&device.join("gpu_busy_percent")).and_then(|v| v.parse().ok()),
+            ..blank_gpu(name, vendor, "sysfs")
+        })
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use super::*;
+
+    #[derive(Deserialize)]
+    #[serde(rename = "Win32_VideoController", rename_all = "PascalCase")]
+    struct VideoController {
+        name: Option<String>,
+        adapter_compatibility: Option<String>,
+        #[serde(rename = "AdapterRAM")]
+        adapter_ram: Option<u32>,
+        driver_version: Option<String>,
+        #[serde(rename = "PNPDeviceID")]
+        pnp_device_id: Option<String>,
+    }
+
+    pub fn gpus() -> Vec<GpuInfo> {
+        let controllers: Vec<VideoController> = match wmi::COMLibrary::new()
+            .and_then(|com| wmi::WMIConnection::new(com.into()))
+            .and_then(|wmi| wmi.query())
+        {
+            Ok(controllers) => controllers,
+            Err(e) => {
+                tracing::debug!("Can't query video controllers through WMI: {}", e);
+                return Vec::new();
+            }
+        };
+        controllers
+            .into_iter()
+            // Remote desktop and other virtual adapters have no PCI ID
+            .filter(|c| c.pnp_device_id.as_deref().is_some_and(|id| id.starts_with("PCI\\")))
+            .map(|c| {
+                let vendor = c.adapter_compatibility.as_deref().map(GpuVendor::from_name).unwrap_or(GpuVendor::Other);
+                GpuInfo {
+                    driver_version: c.driver_version,
+                    memory_total_bytes: c.adapter_ram.map(u64::from),
+                    ..blank_gpu(c.name.unwrap_or_else(|| "GPU".to_string()), vendor, "wmi")
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use super::*;
+
+    pub fn gpus() -> Vec<GpuInfo> {
+        let output = match std::process::Command::new("system_profiler")
+            .args(["SPDisplaysDataType", "-json"])
+            .output()
+        {
+            Ok(output) if output.status.success() => output.stdout,
+            _ => return Vec::new(),
+        };
+        serde_json::from_slice(&output).map(|report| parse_displays(&report)).unwrap_or_default()
+    }
+
+    pub(super) fn parse_displays(report: &serde_json::Value) -> Vec<GpuInfo> {
+        let Some(displays) = report["SPDisplaysDataType"].as_array() else {
+            return Vec::new();
+        };
+        displays
+            .iter()
+            .map(|display| {
+                let name = display["sppci_model"].as_str().unwrap_or("GPU").to_string();
+                let vendor = display["spdisplays_vendor"].as_str().map(GpuVendor::from_name).unwrap_or(GpuVendor::Other);
+                let vram = display["spdisplays_vram"].as_str().and_then(parse_size);
+                GpuInfo {
+                    memory_total_bytes: vram,
+                    unified_memory: vram.is_none(),
+                    ..blank_gpu(name, vendor, "system_profiler")
+                }
+            })
+            .collect()
+    }
+
+    /// "1536 MB" or "8 GB"
+    fn parse_size(text: &str) -> Option<u64> {
+        let (number, unit) = text.trim().split_once(' ')?;
+        let number: u64 = number.parse().ok()?;
+        match unit {
+            "MB" => Some(number << 20),
+            "GB" => Some(number << 30),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+mod platform {
+    use super::*;
+
+    pub fn gpus() -> Vec<GpuInfo> {
+        Vec::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn inventory(gpus: Vec<GpuInfo>) -> HardwareInventory {
+        HardwareInventory {
+            taken_at: Utc::now(),
+            cpu_brand: "Test CPU".to_string(),
+            cpu_cores: 8,
+            memory_total_bytes: 32 << 30,
+            memory_available_bytes: 16 << 30,
+            gpus,
+        }
+    }
+
+    #[test]
+    fn test_model_memory_budget() {
+        assert_eq!(inventory(Vec::new()).model_memory_budget(), 16 << 30);
+
+        let mut small = blank_gpu("small".to_string(), GpuVendor::Amd, "sysfs");
+        small.memory_total_bytes = Some(8 << 30);
+        small.memory_used_bytes = Some(1 << 30);
+        let mut large = blank_gpu("large".to_string(), GpuVendor::Nvidia, "nvml");
+        large.memory_total_bytes = Some(24 << 30);
+        large.memory_used_bytes = Some(4 << 30);
+        let machine = inventory(vec![small, large]);
+        assert_eq!(machine.model_memory_budget(), 20 << 30);
+        // 20 GiB less headroom at 4 bits a weight is about 34 billion parameters
+        let params = machine.max_model_params(4.0);
+        assert!((34_000_000_000..35_000_000_000).contains(&params), "{}", params);
+        assert_eq!(machine.max_model_params(0.0), 0);
+
+        let mut apple = blank_gpu("Apple M2".to_string(), GpuVendor::Apple, "system_profiler");
+        apple.unified_memory = true;
+        assert_eq!(inventory(vec![apple]).model_memory_budget(), 12 << 30);
+    }
+
+    #[test]
+    fn test_vendor() {
+        assert_eq!(GpuVendor::from_pci_id(0x10de), GpuVendor::Nvidia);
+        assert_eq!(GpuVendor::from_name("Advanced Micro Devices, Inc."), GpuVendor::Amd);
+        assert_eq!(GpuVendor::from_name("Matrox"), GpuVendor::Other);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_sysfs_gpu() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let device = temp.path();
+        for (file, value) in [
+            ("vendor", "0x1002\n"),
+            ("device", "0x73bf\n"),
+            ("mem_info_vram_total", "17163091968\n"),
+            ("mem_info_vram_used", "1073741824\n"),
+            ("gpu_busy_percent", "37\n"),
+        ] {
+            std::fs::write(device.join(file), value).unwrap();
+        }
+        let gpu = platform::sysfs_gpu(device).unwrap();
+        assert_eq!(gpu.vendor, GpuVendor::Amd);
+        assert_eq!(gpu.name, "Amd GPU 1002:73bf");
+        assert_eq!(gpu.memory_total_bytes, Some(17163091968));
+        assert_eq!(gpu.memory_free_bytes(), Some(17163091968 - 1073741824));
+        assert_eq!(gpu.utilization_percent, Some(37));
+        assert_eq!(gpu.driver_version, None);
+    }
+}
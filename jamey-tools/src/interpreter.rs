@@ -0,0 +1,352 @@
+//! Sandboxed code execution
+//!
+//! [`CodeInterpreterTool`] runs model-written Python or JavaScript in a
+//! throwaway container: no network, a read-only filesystem apart from its
+//! working directory and `/tmp`, no capabilities, an unprivileged user, and
+//! CPU, memory, process and time limits. Files the code writes to its
+//! working directory are handed back, so a chart or a CSV can become an
+//! attachment.
+//!
+//! Docker is used by default; Podman takes the same arguments. The images
+//! are pulled on first use unless they are already present.
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+use thiserror::Error;
+use tokio::process::Command;
+
+/// Output kept from each of stdout and stderr
+const MAX_OUTPUT_BYTES: usize = 64 * 1024;
+
+/// Generated files handed back from one run
+const MAX_GENERATED_FILES: usize = 20;
+
+/// Generated files larger than this are left out
+const MAX_GENERATED_BYTES: u64 = 10 * 1024 * 1024;
+
+#[derive(Debug, Error)]
+pub enum InterpreterError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Failed to run {runtime}: {source}")]
+    Runtime {
+        runtime: String,
+        source: std::io::Error,
+    },
+    #[error("Code is {0} bytes (limit {1})")]
+    TooLarge(usize, usize),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Language {
+    Python,
+    JavaScript,
+}
+
+impl Language {
+    fn script(self) -> &'static str {
+        match self {
+            Self::Python => "main.py",
+            Self::JavaScript => "main.js",
+        }
+    }
+
+    fn interpreter(self) -> &'static str {
+        match self {
+            Self::Python => "python",
+            Self::JavaScript => "node",
+        }
+    }
+}
+
+impl std::str::FromStr for Language {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "python" | "py" | "python3" => Ok(Self::Python),
+            "javascript" | "js" | "node" => Ok(Self::JavaScript),
+            other => Err(format!("Unsupported language: {} (expected python or javascript)", other)),
+        }
+    }
+}
+
+/// What a run may use
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SandboxLimits {
+    pub cpus: f64,
+    pub memory_mb: u64,
+    pub pids: u32,
+    pub timeout: Duration,
+    /// Longest accepted program
+    pub max_code_bytes: usize,
+}
+
+impl Default for SandboxLimits {
+    fn default() -> Self {
+        Self {
+            cpus: 1.0,
+            memory_mb: 512,
+            pids: 128,
+            timeout: Duration::from_secs(30),
+            max_code_bytes: 256 * 1024,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InterpreterConfig {
+    /// `docker` or `podman`
+    pub runtime: String,
+    pub python_image: String,
+    pub node_image: String,
+    pub limits: SandboxLimits,
+}
+
+impl Default for InterpreterConfig {
+    fn default() -> Self {
+        Self {
+            runtime: "docker".to_string(),
+            python_image: "python:3.12-slim".to_string(),
+            node_image: "node:20-slim".to_string(),
+            limits: SandboxLimits::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GeneratedFile {
+    /// Relative to the run's working directory
+    pub name: String,
+    /// Where the file was left on the host
+    pub path: PathBuf,
+    pub size_bytes: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExecutionOutput {
+    pub stdout: String,
+    pub stderr: String,
+    /// `None` when the run was stopped
+    pub exit_code: Option<i32>,
+    pub timed_out: bool,
+    pub duration_ms: u64,
+    pub files: Vec<GeneratedFile>,
+    /// Output or files were left out to stay within the limits
+    pub truncated: bool,
+}
+
+/// Runs code in containers, each in a fresh directory under `work_root`
+pub struct CodeInterpreterTool {
+    config: InterpreterConfig,
+    work_root: PathBuf,
+}
+
+impl CodeInterpreterTool {
+    pub fn new(work_root: impl Into<PathBuf>) -> Self {
+        Self {
+            config: InterpreterConfig::default(),
+            work_root: work_root.into(),
+        }
+    }
+
+    pub fn with_config(mut self, config: InterpreterConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    pub fn config(&self) -> &InterpreterConfig {
+        &self.config
+    }
+
+    /// Run `code`, stopping it after `timeout` or the configured limit,
+    /// whichever is shorter
+    pub async fn run(&self, language: Language, code: &str, timeout: Option<Duration>) -> Result<ExecutionOutput, InterpreterError> {
+        let limits = &self.config.limits;
+        if code.len() > limits.max_code_bytes {
+            return Err(InterpreterError::TooLarge(code.len(), limits.max_code_bytes));
+        }
+        let run_id = uuid::Uuid::new_v4().to_string();
+        let dir = self.work_root.join(&run_id);
+        tokio::fs::create_dir_all(&dir).await?;
+        tokio::fs::write(dir.join(language.script()), code).await?;
+        // The sandbox user isn't us, and needs to write its output here
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            tokio::fs::set_permissions(&dir, std::fs::Permissions::from_mode(0o777)).await?;
+        }
+
+        let name = format!("jamey-sandbox-{}", &run_id[..8]);
+        let args = self.container_args(language, &dir.canonicalize()?, &name);
+        let child = Command::new(&self.config.runtime)
+            .args(&args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|source| InterpreterError::Runtime {
+                runtime: self.config.runtime.clone(),
+                source,
+            })?;
+
+        let started = Instant::now();
+        let timeout = timeout.map_or(limits.timeout, |t| t.min(limits.timeout));
+        let waited = tokio::time::timeout(timeout, child.wait_with_output()).await;
+        let duration_ms = started.elapsed().as_millis() as u64;
+        let mut output = match waited {
+            Ok(output) => {
+                let output = output?;
+                let (stdout, stdout_cut) = clip(&output.stdout);
+                let (stderr, stderr_cut) = clip(&output.stderr);
+                ExecutionOutput {
+                    stdout,
+                    stderr,
+                    exit_code: output.status.code(),
+                    timed_out: false,
+                    duration_ms,
+                    files: Vec::new(),
+                    truncated: stdout_cut || stderr_cut,
+                }
+            }
+            Err(_) => {
+                // Killing the client leaves the container running
+                let _ = Command::new(&self.config.runtime)
+                    .args(["rm", "--force", &name])
+                    .stdout(Stdio::null())
+                    .stderr(Stdio::null())
+                    .status()
+                    .await;
+                ExecutionOutput {
+                    stdout: String::new(),
+                    stderr: format!("Stopped after {}s", timeout.as_secs()),
+                    exit_code: None,
+                    timed_out: true,
+                    duration_ms,
+                    files: Vec::new(),
+                    truncated: false,
+                }
+            }
+        };
+
+        let (files, files_cut) = generated_files(&dir, language.script())?;
+        output.files = files;
+        output.truncated |= files_cut;
+        Ok(output)
+    }
+
+    /// Arguments to `docker`/`podman` for one run in `dir`
+    fn container_args(&self, language: Language, dir: &Path, name: &str) -> Vec<String> {
+        let limits = &self.config.limits;
+        let image = match language {
+            Language::Python => &self.config.python_image,
+            Language::JavaScript => &self.config.node_image,
+        };
+        let mut args: Vec<String> = [
+            "run", "--rm", "--name", name,
+            "--network", "none",
+            "--read-only",
+            "--tmpfs", "/tmp:rw,size=64m",
+            "--cap-drop", "ALL",
+            "--security-opt", "no-new-privileges",
+            "--user", "65534:65534",
+            "--workdir", "/work",
+            "--env", "HOME=/tmp",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+        args.extend([
+            "--cpus".to_string(),
+            limits.cpus.to_string(),
+            "--memory".to_string(),
+            format!("{}m", limits.memory_mb),
+            // Same as --memory, so the limit can't be dodged by swapping
+            "--memory-swap".to_string(),
+            format!("{}m", limits.memory_mb),
+            "--pids-limit".to_string(),
+            limits.pids.to_string(),
+            "--volume".to_string(),
+            format!("{}:/work:rw", dir.display()),
+            image.clone(),
+            language.interpreter().to_string(),
+            format!("/work/{}", language.script()),
+        ]);
+        args
+    }
+}
+
+/// Files the run left in `dir` besides its own script, and whether some
+/// were left out
+fn generated_files(dir: &Path, script: &str) -> Result<(Vec<GeneratedFile>, bool), InterpreterError> {
+    let mut files = Vec::new();
+    let mut truncated = false;
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(current) = pending.pop() {
+        let mut entries: Vec<_> = std::fs::read_dir(&current)?.collect::<Result<_, _>>()?;
+        entries.sort_by_key(|entry| entry.file_name());
+        for entry in entries {
+            let file_type = entry.file_type()?;
+            let path = entry.path();
+            if file_type.is_dir() {
+                pending.push(path);
+                continue;
+            }
+            // Links could point anywhere on the host
+            if !file_type.is_file() {
+                continue;
+            }
+            let name = path.strip_prefix(dir).unwrap_or(&path).to_string_lossy().replace('\\', "/");
+            if name == script {
+                continue;
+            }
+            let size_bytes = entry.metadata()?.len();
+            if size_bytes > MAX_GENERATED_BYTES || files.len() >= MAX_GENERATED_FILES {
+                truncated = true;
+                continue;
+            }
+            files.push(GeneratedFile { name, path, size_bytes });
+        }
+    }
+    Ok((files, truncated))
+}
+
+fn clip(bytes: &[u8]) -> (String, bool) {
+    if bytes.len() <= MAX_OUTPUT_BYTES {
+        return (String::from_utf8_lossy(bytes).into_owned(), false);
+    }
+    let mut text = String::from_utf8_lossy(&bytes[..MAX_OUTPUT_BYTES]).into_owned();
+    text.push_str(&format!("\n… {} more bytes", bytes.len() - MAX_OUTPUT_BYTES));
+    (text, true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_container_is_isolated_and_limited() {
+        let tool = CodeInterpreterTool::new("/tmp/sandbox");
+        let args = tool.container_args(Language::Python, Path::new("/tmp/sandbox/run"), "jamey-sandbox-1");
+        let joined = args.join(" ");
+        for expected in ["--network none", "--read-only", "--cap-drop ALL", "--memory 512m", "--memory-swap 512m", "--pids-limit 128"] {
+            assert!(joined.contains(expected), "{} missing from {}", expected, joined);
+        }
+        assert!(joined.ends_with("python:3.12-slim python /work/main.py"));
+        assert!(joined.contains("/tmp/sandbox/run:/work:rw"));
+
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("main.py"), "print(1)").unwrap();
+        std::fs::create_dir(dir.path().join("out")).unwrap();
+        std::fs::write(dir.path().join("out/chart.svg"), "<svg/>").unwrap();
+        std::fs::write(dir.path().join("data.csv"), "a,b\n").unwrap();
+        let (files, truncated) = generated_files(dir.path(), "main.py").unwrap();
+        let names: Vec<_> = files.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, vec!["data.csv", "out/chart.svg"]);
+        assert!(!truncated);
+    }
+}
@@ -1,12 +1,36 @@
 //! System tools implementation for Digital Twin Jamey
 //! 
-//! This crate provides system-level tools for process management,
-//! Windows registry access, self-modification capabilities, and
-//! extensible connector architecture for full system access.
+//! This crate provides system-level tools for process management and
+//! inspection (process trees, open ports and files), GPU and accelerator
+//! inventory,
+//! system configuration (Windows registry, macOS defaults, Linux
+//! sysctl/dconf), self-modification capabilities, quarantined downloads,
+//! git repository analysis, code search, language-server code intelligence,
+//! test runs with structured results, interactive terminal sessions,
+//! sandboxed Python/JavaScript execution, resource limits for the commands
+//! tools start, per-connector execution timeouts, session scratchpads, a shared filesystem access policy, unit-aware calculations, and extensible connector
+//! architecture for full system access, with OAuth2 sign-in for cloud
+//! connectors and an A2A client for delegating tasks to other agents.
 
 pub mod system;
+pub mod handles;
+pub mod hardware;
 pub mod connector;
 pub mod connectors;
+pub mod oauth;
+pub mod downloads;
+pub mod git;
+pub mod search;
+pub mod lsp;
+pub mod test_runner;
+pub mod pty;
+pub mod interpreter;
+pub mod calculator;
+pub mod sandbox;
+pub mod path_policy;
+pub mod a2a;
+pub mod timeouts;
+pub mod scratchpad;
 
 use thiserror::Error;
 
@@ -21,21 +45,39 @@ pub enum ToolError {
 /// Common traits and types used across tools
 pub mod prelude {
     pub use super::system::{
-        FileBackup, ProcessInfo, ProcessTool, SelfModifyTool,
+        ConfigBackend, FileBackup, KeyAccess, PortOwner, ProcessInfo, ProcessNode, ProcessResources,
+        ProcessTool, SelfModifyTool, SystemConfigKey, SystemConfigTool,
     };
+    pub use super::handles::{Protocol, Socket};
+    pub use super::hardware::{GpuInfo, GpuVendor, HardwareInventory, HardwareInventoryTool};
     #[cfg(windows)]
     pub use super::system::RegistryTool;
     pub use super::connector::{
-        Connector, ConnectorRegistry, ConnectorMetadata, ConnectorResult,
-        ExecutionContext, CapabilityLevel, NetworkRequest,
+        Compensation, Connector, ConnectorInfo, ConnectorRegistry, ConnectorMetadata, ConnectorResult,
+        ExecutionContext, CapabilityLevel, NetworkRequest, ToolPolicy, WorkspaceScope,
     };
     pub use super::connectors::*;
+    pub use super::oauth::{OAuthManager, OAuthProvider, OAuthClientConfig, OAuthToken};
+    pub use super::downloads::{DownloadConfig, DownloadManager, DownloadRequest, QuarantinedDownload};
+    pub use super::git::{DiffRequest, GitTool, LogRequest};
+    pub use super::search::{CodeSearchTool, SearchRequest, SearchResults};
+    pub use super::lsp::{LspClient, LspServerConfig};
+    pub use super::test_runner::{TestFramework, TestReport, TestRequest, TestRunnerTool};
+    pub use super::pty::{PtyOutput, PtySession, PtyTool};
+    pub use super::interpreter::{CodeInterpreterTool, ExecutionOutput, InterpreterConfig, Language, SandboxLimits};
+    pub use super::calculator::{Calculation, CalculatorTool};
+    pub use super::sandbox::{ExecutionSandbox, SandboxConfig};
+    pub use super::path_policy::{PathPolicy, PathPolicyConfig};
+    pub use super::a2a::A2aClient;
+    pub use super::timeouts::{PartialOutput, ToolTimeoutConfig};
+    pub use super::scratchpad::{ScratchEntry, ScratchKey, Scratchpad, ScratchpadConfig, ScratchpadError, ValueType};
     pub use super::ToolError;
 }
 
 /// Re-export main tool implementations
 pub use system::{
-    FileBackup, ProcessInfo, ProcessTool, SelfModifyTool,
+    ConfigBackend, FileBackup, KeyAccess, ProcessInfo, ProcessTool, SelfModifyTool,
+    SystemConfigKey, SystemConfigTool,
 };
 #[cfg(windows)]
 pub use system::RegistryTool;
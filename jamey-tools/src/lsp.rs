@@ -0,0 +1,679 @@
+//! Language server client
+//!
+//! [`LspClient`] talks the Language Server Protocol to a server such as
+//! rust-analyzer or pyright over its stdin and stdout, and turns hover,
+//! go-to-definition, find-references and diagnostics into plain data. Lines
+//! and columns are counted from 1 in characters, as an editor shows them;
+//! the conversion to the protocol's 0-based UTF-16 positions happens here.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use thiserror::Error;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::process::{Child, Command};
+use tokio::sync::{mpsc, oneshot, Notify};
+
+/// How long a request may take before it is given up on
+pub const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Error)]
+pub enum LspError {
+    #[error("Failed to start {command}: {source}")]
+    Spawn {
+        command: String,
+        source: std::io::Error,
+    },
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Invalid message: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("Protocol error: {0}")]
+    Protocol(String),
+    #[error("Language server error {code}: {message}")]
+    Server { code: i64, message: String },
+    #[error("{0} timed out")]
+    Timeout(String),
+    #[error("Language server exited")]
+    Closed,
+    #[error("Not a file under the project: {0}")]
+    InvalidPath(PathBuf),
+}
+
+/// How to run the server for one language
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LspServerConfig {
+    /// Also the `languageId` documents are opened with
+    pub language: String,
+    pub command: String,
+    pub args: Vec<String>,
+    /// File extensions the server handles, without the dot
+    pub extensions: Vec<String>,
+}
+
+impl LspServerConfig {
+    pub fn rust_analyzer() -> Self {
+        Self {
+            language: "rust".to_string(),
+            command: "rust-analyzer".to_string(),
+            args: Vec::new(),
+            extensions: vec!["rs".to_string()],
+        }
+    }
+
+    pub fn pyright() -> Self {
+        Self {
+            language: "python".to_string(),
+            command: "pyright-langserver".to_string(),
+            args: vec!["--stdio".to_string()],
+            extensions: vec!["py".to_string(), "pyi".to_string()],
+        }
+    }
+
+    pub fn defaults() -> Vec<Self> {
+        vec![Self::rust_analyzer(), Self::pyright()]
+    }
+
+    pub fn handles(&self, path: &Path) -> bool {
+        path.extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|ext| self.extensions.iter().any(|e| e == ext))
+    }
+}
+
+/// A span in a file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Location {
+    /// Relative to the project root when inside it, absolute otherwise
+    pub path: String,
+    pub line: usize,
+    pub column: usize,
+    pub end_line: usize,
+    pub end_column: usize,
+    /// The text of `line`, when the file could be read
+    pub preview: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Diagnostic {
+    pub path: String,
+    /// `error`, `warning`, `information` or `hint`
+    pub severity: Option<String>,
+    pub line: usize,
+    pub column: usize,
+    pub end_line: usize,
+    pub end_column: usize,
+    pub message: String,
+    /// What reported it, e.g. `rustc` or `clippy`
+    pub source: Option<String>,
+    pub code: Option<String>,
+}
+
+type Reply = oneshot::Sender<Result<Value, LspError>>;
+
+/// State the reader task fills in
+#[derive(Default)]
+struct Shared {
+    pending: Mutex<HashMap<i64, Reply>>,
+    /// Latest diagnostics per document URI, and how many times each was published
+    diagnostics: Mutex<HashMap<String, (u64, Vec<Value>)>>,
+    published: Notify,
+    closed: std::sync::atomic::AtomicBool,
+}
+
+/// A document as last sent to the server
+struct OpenDocument {
+    version: i64,
+    text: String,
+}
+
+/// One running language server, rooted at a project directory
+pub struct LspClient {
+    root: PathBuf,
+    language: String,
+    outgoing: mpsc::UnboundedSender<Value>,
+    shared: Arc<Shared>,
+    next_id: AtomicI64,
+    documents: tokio::sync::Mutex<HashMap<PathBuf, OpenDocument>>,
+    child: tokio::sync::Mutex<Option<Child>>,
+    timeout: Duration,
+}
+
+impl LspClient {
+    /// Run the server `config` describes for the project at `root` and
+    /// initialize it
+    pub async fn start(config: &LspServerConfig, root: &Path) -> Result<Self, LspError> {
+        let mut child = Command::new(&config.command)
+            .args(&config.args)
+            .current_dir(root)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|source| LspError::Spawn {
+                command: config.command.clone(),
+                source,
+            })?;
+        let stdin = child.stdin.take().ok_or(LspError::Closed)?;
+        let stdout = child.stdout.take().ok_or(LspError::Closed)?;
+        let mut client = Self::connect(root, &config.language, stdout, stdin);
+        client.child = tokio::sync::Mutex::new(Some(child));
+        client.initialize().await?;
+        Ok(client)
+    }
+
+    /// A client over an already connected transport; [`initialize`](Self::initialize)
+    /// must be called before anything else
+    pub fn connect<R, W>(root: &Path, language: &str, reader: R, writer: W) -> Self
+    where
+        R: AsyncRead + Send + Unpin + 'static,
+        W: AsyncWrite + Send + Unpin + 'static,
+    {
+        let shared = Arc::new(Shared::default());
+        let (outgoing, incoming) = mpsc::unbounded_channel();
+        tokio::spawn(write_loop(writer, incoming));
+        tokio::spawn(read_loop(BufReader::new(reader), shared.clone(), outgoing.clone()));
+        Self {
+            root: root.to_path_buf(),
+            language: language.to_string(),
+            outgoing,
+            shared,
+            next_id: AtomicI64::new(1),
+            documents: tokio::sync::Mutex::new(HashMap::new()),
+            child: tokio::sync::Mutex::new(None),
+            timeout: DEFAULT_REQUEST_TIMEOUT,
+        }
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn language(&self) -> &str {
+        &self.language
+    }
+
+    /// Whether the server is still there to answer
+    pub fn is_running(&self) -> bool {
+        !self.shared.closed.load(Ordering::SeqCst)
+    }
+
+    pub async fn initialize(&self) -> Result<(), LspError> {
+        let root_uri = uri(&self.root)?;
+        let name = self.root.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+        let params = json!({
+            "processId": std::process::id(),
+            "rootUri": root_uri,
+            "workspaceFolders": [{ "uri": root_uri, "name": name }],
+            "capabilities": {
+                "textDocument": {
+                    "hover": { "contentFormat": ["markdown", "plaintext"] },
+                    "definition": { "linkSupport": true },
+                    "references": {},
+                    "publishDiagnostics": {},
+                    "synchronization": {},
+                },
+                "workspace": { "configuration": true, "workspaceFolders": true },
+            },
+        });
+        self.request("initialize", params).await?;
+        self.notify("initialized", json!({}))
+    }
+
+    /// Documentation and type of the symbol at `line`:`column`
+    pub async fn hover(&self, path: &Path, line: usize, column: usize) -> Result<Option<String>, LspError> {
+        let params = self.position_params(path, line, column).await?;
+        let hover = self.request("textDocument/hover", params).await?;
+        let text = hover_text(&hover["contents"]);
+        Ok(Some(text).filter(|t| !t.trim().is_empty()))
+    }
+
+    /// Where the symbol at `line`:`column` is defined
+    pub async fn definition(&self, path: &Path, line: usize, column: usize) -> Result<Vec<Location>, LspError> {
+        let params = self.position_params(path, line, column).await?;
+        let result = self.request("textDocument/definition", params).await?;
+        Ok(self.locations(&result))
+    }
+
+    /// Every use of the symbol at `line`:`column`, its declaration included
+    pub async fn references(&self, path: &Path, line: usize, column: usize) -> Result<Vec<Location>, LspError> {
+        let mut params = self.position_params(path, line, column).await?;
+        params["context"] = json!({ "includeDeclaration": true });
+        let result = self.request("textDocument/references", params).await?;
+        Ok(self.locations(&result))
+    }
+
+    /// The server's diagnostics for `path`, waiting for it to publish them
+    /// when the file is new to it or has changed since
+    pub async fn diagnostics(&self, path: &Path) -> Result<Vec<Diagnostic>, LspError> {
+        let key = uri(path)?;
+        let seen = self.publish_count(&key);
+        let notified = self.shared.published.notified();
+        tokio::pin!(notified);
+        notified.as_mut().enable();
+        let changed = self.sync(path).await?;
+
+        if changed || seen == 0 {
+            let wait = async {
+                while self.publish_count(&key) == seen && self.is_running() {
+                    notified.as_mut().await;
+                    notified.set(self.shared.published.notified());
+                }
+            };
+            // A server that stays quiet has nothing to report
+            let _ = tokio::time::timeout(self.timeout, wait).await;
+        }
+
+        let raw = self.shared.diagnostics.lock().unwrap().get(&key).map(|(_, d)| d.clone()).unwrap_or_default();
+        let mut lines = FileLines::default();
+        Ok(raw
+            .iter()
+            .map(|d| {
+                let (line, column, end_line, end_column) = lines.range(path, &d["range"]);
+                Diagnostic {
+                    path: self.display(path),
+                    severity: d["severity"].as_u64().and_then(severity).map(String::from),
+                    line,
+                    column,
+                    end_line,
+                    end_column,
+                    message: d["message"].as_str().unwrap_or_default().to_string(),
+                    source: d["source"].as_str().map(String::from),
+                    code: match &d["code"] {
+                        Value::String(code) => Some(code.clone()),
+                        Value::Number(code) => Some(code.to_string()),
+                        _ => None,
+                    },
+                }
+            })
+            .collect())
+    }
+
+    /// Ask the server to exit, and stop it if it doesn't
+    pub async fn shutdown(&self) -> Result<(), LspError> {
+        if self.is_running() {
+            let _ = tokio::time::timeout(Duration::from_secs(5), self.request("shutdown", Value::Null)).await;
+            let _ = self.notify("exit", Value::Null);
+        }
+        if let Some(mut child) = self.child.lock().await.take() {
+            if tokio::time::timeout(Duration::from_secs(2), child.wait()).await.is_err() {
+                child.kill().await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn request(&self, method: &str, params: Value) -> Result<Value, LspError> {
+        if !self.is_running() {
+            return Err(LspError::Closed);
+        }
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        let (reply, response) = oneshot::channel();
+        self.shared.pending.lock().unwrap().insert(id, reply);
+        self.send(json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params }))?;
+
+        match tokio::time::timeout(self.timeout, response).await {
+            Ok(Ok(result)) => result,
+            Ok(Err(_)) => Err(LspError::Closed),
+            Err(_) => {
+                self.shared.pending.lock().unwrap().remove(&id);
+                let _ = self.notify("$/cancelRequest", json!({ "id": id }));
+                Err(LspError::Timeout(method.to_string()))
+            }
+        }
+    }
+
+    fn notify(&self, method: &str, params: Value) -> Result<(), LspError> {
+        self.send(json!({ "jsonrpc": "2.0", "method": method, "params": params }))
+    }
+
+    fn send(&self, message: Value) -> Result<(), LspError> {
+        self.outgoing.send(message).map_err(|_| LspError::Closed)
+    }
+
+    /// Send the file's current text if the server hasn't seen it; true when
+    /// something was sent
+    async fn sync(&self, path: &Path) -> Result<bool, LspError> {
+        if !path.starts_with(&self.root) || !path.is_file() {
+            return Err(LspError::InvalidPath(path.to_path_buf()));
+        }
+        let text = tokio::fs::read_to_string(path).await?;
+        let key = uri(path)?;
+        let mut documents = self.documents.lock().await;
+        match documents.get_mut(path) {
+            Some(open) if open.text == text => return Ok(false),
+            Some(open) => {
+                open.version += 1;
+                self.notify("textDocument/didChange", json!({
+                    "textDocument": { "uri": key, "version": open.version },
+                    "contentChanges": [{ "text": text }],
+                }))?;
+                open.text = text;
+            }
+            None => {
+                self.notify("textDocument/didOpen", json!({
+                    "textDocument": { "uri": key, "languageId": self.language, "version": 1, "text": text },
+                }))?;
+                documents.insert(path.to_path_buf(), OpenDocument { version: 1, text });
+            }
+        }
+        Ok(true)
+    }
+
+    async fn position_params(&self, path: &Path, line: usize, column: usize) -> Result<Value, LspError> {
+        self.sync(path).await?;
+        let documents = self.documents.lock().await;
+        let text = documents.get(path).map(|d| d.text.as_str()).unwrap_or_default();
+        let line_text = text.lines().nth(line.saturating_sub(1)).unwrap_or_default();
+        Ok(json!({
+            "textDocument": { "uri": uri(path)? },
+            "position": { "line": line.saturating_sub(1), "character": utf16_offset(line_text, column) },
+        }))
+    }
+
+    fn publish_count(&self, key: &str) -> u64 {
+        self.shared.diagnostics.lock().unwrap().get(key).map_or(0, |(count, _)| *count)
+    }
+
+    /// `Location`, `Location[]` or `LocationLink[]`, whichever the server sent
+    fn locations(&self, result: &Value) -> Vec<Location> {
+        let items = match result {
+            Value::Array(items) => items.clone(),
+            Value::Null => Vec::new(),
+            single => vec![single.clone()],
+        };
+        let mut lines = FileLines::default();
+        items
+            .iter()
+            .filter_map(|item| {
+                let (target, range) = match item.get("targetUri") {
+                    Some(target) => (target, &item["targetSelectionRange"]),
+                    None => (&item["uri"], &item["range"]),
+                };
+                let path = url::Url::parse(target.as_str()?).ok()?.to_file_path().ok()?;
+                let (line, column, end_line, end_column) = lines.range(&path, range);
+                Some(Location {
+                    preview: lines.line(&path, line).map(|l| l.trim().to_string()),
+                    path: self.display(&path),
+                    line,
+                    column,
+                    end_line,
+                    end_column,
+                })
+            })
+            .collect()
+    }
+
+    fn display(&self, path: &Path) -> String {
+        path.strip_prefix(&self.root).unwrap_or(path).to_string_lossy().replace('\\', "/")
+    }
+}
+
+/// Lines of the files a result mentions, read once per result
+#[derive(Default)]
+struct FileLines {
+    files: HashMap<PathBuf, Option<Vec<String>>>,
+}
+
+impl FileLines {
+    fn line(&mut self, path: &Path, line: usize) -> Option<&str> {
+        let lines = self
+            .files
+            .entry(path.to_path_buf())
+            .or_insert_with(|| std::fs::read_to_string(path).ok().map(|t| t.lines().map(String::from).collect()));
+        lines.as_ref()?.get(line.checked_sub(1)?).map(String::as_str)
+    }
+
+    /// A protocol range as 1-based lines and character columns
+    fn range(&mut self, path: &Path, range: &Value) -> (usize, usize, usize, usize) {
+        let mut point = |position: &Value| {
+            let line = position["line"].as_u64().unwrap_or(0) as usize + 1;
+            let character = position["character"].as_u64().unwrap_or(0) as usize;
+            let column = match self.line(path, line) {
+                Some(text) => char_column(text, character),
+                None => character + 1,
+            };
+            (line, column)
+        };
+        let (line, column) = point(&range["start"]);
+        let (end_line, end_column) = point(&range["end"]);
+        (line, column, end_line, end_column)
+    }
+}
+
+fn uri(path: &Path) -> Result<String, LspError> {
+    url::Url::from_file_path(path)
+        .map(String::from)
+        .map_err(|_| LspError::InvalidPath(path.to_path_buf()))
+}
+
+/// UTF-16 offset of the 1-based character `column` in `line`
+fn utf16_offset(line: &str, column: usize) -> usize {
+    line.chars().take(column.saturating_sub(1)).map(char::len_utf16).sum()
+}
+
+/// 1-based character column of the UTF-16 offset `offset` in `line`
+fn char_column(line: &str, offset: usize) -> usize {
+    let mut units = 0;
+    for (idx, c) in line.chars().enumerate() {
+        if units >= offset {
+            return idx + 1;
+        }
+        units += c.len_utf16();
+    }
+    line.chars().count() + 1
+}
+
+fn severity(level: u64) -> Option<&'static str> {
+    match level {
+        1 => Some("error"),
+        2 => Some("warning"),
+        3 => Some("information"),
+        4 => Some("hint"),
+        _ => None,
+    }
+}
+
+/// `MarkupContent`, a `MarkedString` or an array of them, as text
+fn hover_text(contents: &Value) -> String {
+    match contents {
+        Value::String(text) => text.clone(),
+        Value::Array(items) => items.iter().map(hover_text).collect::<Vec<_>>().join("\n\n"),
+        Value::Object(object) => match (object.get("language"), object.get("value")) {
+            (Some(language), Some(value)) => {
+                format!("```{}\n{}\n```", language.as_str().unwrap_or_default(), value.as_str().unwrap_or_default())
+            }
+            (None, Some(value)) => value.as_str().unwrap_or_default().to_string(),
+            _ => String::new(),
+        },
+        _ => String::new(),
+    }
+}
+
+async fn write_loop<W: AsyncWrite + Unpin>(mut writer: W, mut incoming: mpsc::UnboundedReceiver<Value>) {
+    while let Some(message) = incoming.recv().await {
+        if let Err(e) = write_message(&mut writer, &message).await {
+            tracing::warn!("Could not write to language server: {}", e);
+            break;
+        }
+    }
+}
+
+async fn read_loop<R: AsyncBufRead + Unpin>(mut reader: R, shared: Arc<Shared>, outgoing: mpsc::UnboundedSender<Value>) {
+    loop {
+        let message = match read_message(&mut reader).await {
+            Ok(Some(message)) => message,
+            Ok(None) => break,
+            Err(e) => {
+                tracing::warn!("Language server sent an unreadable message: {}", e);
+                break;
+            }
+        };
+        match (message.get("id"), message.get("method").and_then(Value::as_str)) {
+            // A response to one of our requests
+            (Some(id), None) => {
+                let Some(reply) = id.as_i64().and_then(|id| shared.pending.lock().unwrap().remove(&id)) else {
+                    continue;
+                };
+                let result = match message.get("error") {
+                    Some(error) => Err(LspError::Server {
+                        code: error["code"].as_i64().unwrap_or_default(),
+                        message: error["message"].as_str().unwrap_or_default().to_string(),
+                    }),
+                    None => Ok(message.get("result").cloned().unwrap_or(Value::Null)),
+                };
+                let _ = reply.send(result);
+            }
+            // A request from the server; nothing is configured, so every
+            // setting it asks for is left at its default
+            (Some(id), Some(method)) => {
+                let result = match method {
+                    "workspace/configuration" => {
+                        let items = message["params"]["items"].as_array().map_or(0, Vec::len);
+                        Value::Array(vec![Value::Null; items])
+                    }
+                    _ => Value::Null,
+                };
+                let _ = outgoing.send(json!({ "jsonrpc": "2.0", "id": id, "result": result }));
+            }
+            (None, Some("textDocument/publishDiagnostics")) => {
+                let params = &message["params"];
+                if let Some(key) = params["uri"].as_str() {
+                    let diagnostics = params["diagnostics"].as_array().cloned().unwrap_or_default();
+                    let mut published = shared.diagnostics.lock().unwrap();
+                    let entry = published.entry(key.to_string()).or_default();
+                    *entry = (entry.0 + 1, diagnostics);
+                }
+                shared.published.notify_waiters();
+            }
+            _ => {}
+        }
+    }
+
+    shared.closed.store(true, Ordering::SeqCst);
+    for (_, reply) in shared.pending.lock().unwrap().drain() {
+        let _ = reply.send(Err(LspError::Closed));
+    }
+    shared.published.notify_waiters();
+}
+
+/// One `Content-Length`-framed message, or `None` at end of stream
+async fn read_message<R: AsyncBufRead + Unpin>(reader: &mut R) -> Result<Option<Value>, LspError> {
+    let mut length = None;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header).await? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("Content-Length") {
+                length = Some(value.trim().parse::<usize>().map_err(|_| LspError::Protocol(header.to_string()))?);
+            }
+        }
+    }
+    let length = length.ok_or_else(|| LspError::Protocol("missing Content-Length".to_string()))?;
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).await?;
+    Ok(Some(serde_json::from_slice(&body)?))
+}
+
+async fn write_message<W: AsyncWrite + Unpin>(writer: &mut W, message: &Value) -> Result<(), LspError> {
+    let body = serde_json::to_vec(message)?;
+    writer.write_all(format!("Content-Length: {}\r\n\r\n", body.len()).as_bytes()).await?;
+    writer.write_all(&body).await?;
+    writer.flush().await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Answers like a language server that knows one symbol
+    async fn fake_server(stream: tokio::io::DuplexStream) {
+        let (reader, mut writer) = tokio::io::split(stream);
+        let mut reader = BufReader::new(reader);
+        while let Ok(Some(message)) = read_message(&mut reader).await {
+            let method = message["method"].as_str().unwrap_or_default().to_string();
+            let params = &message["params"];
+            let reply = |result: Value| json!({ "jsonrpc": "2.0", "id": message["id"], "result": result });
+            let out = match method.as_str() {
+                "initialize" => reply(json!({ "capabilities": {} })),
+                "textDocument/didOpen" => {
+                    let uri = &params["textDocument"]["uri"];
+                    json!({ "jsonrpc": "2.0", "method": "textDocument/publishDiagnostics", "params": {
+                        "uri": uri,
+                        "diagnostics": [{
+                            "range": { "start": { "line": 1, "character": 4 }, "end": { "line": 1, "character": 7 } },
+                            "severity": 2, "message": "unused", "source": "rustc", "code": "unused_variables",
+                        }],
+                    }})
+                }
+                "textDocument/hover" => {
+                    assert_eq!(params["position"], json!({ "line": 1, "character": 4 }));
+                    reply(json!({ "contents": { "kind": "markdown", "value": "let one: i32" } }))
+                }
+                "textDocument/definition" => reply(json!([{ "uri": params["textDocument"]["uri"], "range": {
+                    "start": { "line": 1, "character": 4 }, "end": { "line": 1, "character": 7 },
+                }}])),
+                _ => continue,
+            };
+            write_message(&mut writer, &out).await.unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_hover_definition_and_diagnostics() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        let file = root.join("main.rs");
+        std::fs::write(&file, "fn main() {\n    one = 1;\n}\n").unwrap();
+
+        let (client_end, server_end) = tokio::io::duplex(64 * 1024);
+        tokio::spawn(fake_server(server_end));
+        let (reader, writer) = tokio::io::split(client_end);
+        let client = LspClient::connect(&root, "rust", reader, writer).with_timeout(Duration::from_secs(5));
+        client.initialize().await.unwrap();
+
+        let diagnostics = client.diagnostics(&file).await.unwrap();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].path, "main.rs");
+        assert_eq!((diagnostics[0].line, diagnostics[0].column), (2, 5));
+        assert_eq!(diagnostics[0].severity.as_deref(), Some("warning"));
+
+        let hover = client.hover(&file, 2, 5).await.unwrap();
+        assert_eq!(hover.as_deref(), Some("let one: i32"));
+
+        let definitions = client.definition(&file, 2, 5).await.unwrap();
+        assert_eq!(definitions.len(), 1);
+        assert_eq!(definitions[0].preview.as_deref(), Some("one = 1;"));
+        assert_eq!((definitions[0].end_line, definitions[0].end_column), (2, 8));
+
+        assert!(client.hover(&root.join("../elsewhere.rs"), 1, 1).await.is_err());
+    }
+
+    #[test]
+    fn test_utf16_columns() {
+        let line = "let é = \"😀x\";";
+        assert_eq!(utf16_offset(line, 1), 0);
+        // The emoji is two UTF-16 units but one character
+        let x = line.chars().position(|c| c == 'x').unwrap() + 1;
+        assert_eq!(char_column(line, utf16_offset(line, x)), x);
+        assert_eq!(utf16_offset(line, x), x);
+    }
+}
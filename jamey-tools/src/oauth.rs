@@ -0,0 +1,677 @@
+//! OAuth2 authorization for connectors
+//!
+//! Runs the device-code flow (GitHub, Google) or a localhost-redirect
+//! authorization-code flow with PKCE (LinkedIn, Slack, and Google as a
+//! fallback), persists the resulting tokens through [`SecretManager`], and
+//! refreshes access tokens on demand so connectors never need hand-pasted,
+//! long-lived tokens.
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use jamey_core::secrets::{SecretError, SecretManager};
+use jamey_core::secure_logging::Masked;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use thiserror::Error;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::sync::RwLock;
+
+/// Refresh tokens this long before they actually expire
+const EXPIRY_SKEW_SECS: i64 = 60;
+/// How long the localhost redirect listener waits for the browser
+const REDIRECT_TIMEOUT_SECS: u64 = 300;
+
+#[derive(Debug, Error)]
+pub enum OAuthError {
+    #[error("OAuth client not configured for provider: {0}")]
+    NotConfigured(String),
+    #[error("Provider does not support the device flow: {0}")]
+    DeviceFlowUnsupported(String),
+    #[error("Authorization denied: {0}")]
+    Denied(String),
+    #[error("Authorization expired before it was completed")]
+    Expired,
+    #[error("No stored token for provider: {0} (run the login flow first)")]
+    NotAuthorized(String),
+    #[error("Invalid token response: {0}")]
+    InvalidResponse(String),
+    #[error("Redirect flow failed: {0}")]
+    Redirect(String),
+    #[error("HTTP error: {0}")]
+    Http(#[from] reqwest::Error),
+    #[error("Secret storage error: {0}")]
+    Secret(#[from] SecretError),
+}
+
+/// Identity providers with built-in endpoint definitions
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum OAuthProvider {
+    GitHub,
+    Google,
+    LinkedIn,
+    Slack,
+}
+
+impl OAuthProvider {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OAuthProvider::GitHub => "github",
+            OAuthProvider::Google => "google",
+            OAuthProvider::LinkedIn => "linkedin",
+            OAuthProvider::Slack => "slack",
+        }
+    }
+
+    fn device_code_url(&self) -> Option<&'static str> {
+        match self {
+            OAuthProvider::GitHub => Some("https://github.com/login/device/code"),
+            OAuthProvider::Google => Some("https://oauth2.googleapis.com/device/code"),
+            OAuthProvider::LinkedIn | OAuthProvider::Slack => None,
+        }
+    }
+
+    fn authorize_url(&self) -> &'static str {
+        match self {
+            OAuthProvider::GitHub => "https://github.com/login/oauth/authorize",
+            OAuthProvider::Google => "https://accounts.google.com/o/oauth2/v2/auth",
+            OAuthProvider::LinkedIn => "https://www.linkedin.com/oauth/v2/authorization",
+            OAuthProvider::Slack => "https://slack.com/oauth/v2/authorize",
+        }
+    }
+
+    fn token_url(&self) -> &'static str {
+        match self {
+            OAuthProvider::GitHub => "https://github.com/login/oauth/access_token",
+            OAuthProvider::Google => "https://oauth2.googleapis.com/token",
+            OAuthProvider::LinkedIn => "https://www.linkedin.com/oauth/v2/accessToken",
+            OAuthProvider::Slack => "https://slack.com/api/oauth.v2.access",
+        }
+    }
+
+    /// Scopes requested when the client config does not override them
+    pub fn default_scopes(&self) -> Vec<String> {
+        let scopes: &[&str] = match self {
+            OAuthProvider::GitHub => &["repo", "read:user"],
+            OAuthProvider::Google => &["openid", "email", "https://www.googleapis.com/auth/calendar.readonly"],
+            OAuthProvider::LinkedIn => &["openid", "profile", "w_member_social"],
+            OAuthProvider::Slack => &["chat:write", "channels:read"],
+        };
+        scopes.iter().map(|s| s.to_string()).collect()
+    }
+
+    /// Credential key connectors expect for this provider's access token
+    pub fn credential_key(&self) -> String {
+        format!("{}_token", self.as_str())
+    }
+
+    fn secret_key(&self) -> String {
+        format!("oauth_{}", self.as_str())
+    }
+
+    pub const ALL: [OAuthProvider; 4] = [
+        OAuthProvider::GitHub,
+        OAuthProvider::Google,
+        OAuthProvider::LinkedIn,
+        OAuthProvider::Slack,
+    ];
+
+    /// Recover the provider from the SecretManager key its token is stored under
+    pub fn from_secret_key(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|p| p.secret_key() == key)
+    }
+}
+
+/// Extract the access token from a stored token secret
+pub fn access_token_from_secret(raw: &str) -> Option<String> {
+    serde_json::from_str::<OAuthToken>(raw).ok().map(|t| t.access_token)
+}
+
+impl std::str::FromStr for OAuthProvider {
+    type Err = OAuthError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "github" => Ok(OAuthProvider::GitHub),
+            "google" => Ok(OAuthProvider::Google),
+            "linkedin" => Ok(OAuthProvider::LinkedIn),
+            "slack" => Ok(OAuthProvider::Slack),
+            other => Err(OAuthError::NotConfigured(other.to_string())),
+        }
+    }
+}
+
+/// Registered OAuth application for one provider
+#[derive(Clone, Serialize, Deserialize, JsonSchema)]
+pub struct OAuthClientConfig {
+    pub provider: OAuthProvider,
+    pub client_id: String,
+    /// Not serialized; load from the environment or SecretManager
+    #[serde(skip_serializing, default)]
+    pub client_secret: Option<String>,
+    /// Empty = provider defaults
+    #[serde(default)]
+    pub scopes: Vec<String>,
+    /// Port for the localhost redirect listener
+    #[serde(default = "default_redirect_port")]
+    pub redirect_port: u16,
+}
+
+fn default_redirect_port() -> u16 {
+    8765
+}
+
+impl std::fmt::Debug for OAuthClientConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OAuthClientConfig")
+            .field("provider", &self.provider)
+            .field("client_id", &self.client_id)
+            .field("client_secret", &Masked::opt(self.client_secret.as_deref()))
+            .field("scopes", &self.scopes)
+            .field("redirect_port", &self.redirect_port)
+            .finish()
+    }
+}
+
+impl OAuthClientConfig {
+    pub fn new(provider: OAuthProvider, client_id: impl Into<String>) -> Self {
+        Self {
+            provider,
+            client_id: client_id.into(),
+            client_secret: None,
+            scopes: Vec::new(),
+            redirect_port: default_redirect_port(),
+        }
+    }
+
+    /// Read `JAMEY_<PROVIDER>_CLIENT_ID` (and optional `_CLIENT_SECRET`)
+    pub fn from_env(provider: OAuthProvider) -> Option<Self> {
+        let prefix = format!("JAMEY_{}", provider.as_str().to_uppercase());
+        let client_id = std::env::var(format!("{}_CLIENT_ID", prefix)).ok()?;
+        let mut config = Self::new(provider, client_id);
+        config.client_secret = std::env::var(format!("{}_CLIENT_SECRET", prefix)).ok();
+        Some(config)
+    }
+
+    pub fn with_client_secret(mut self, secret: impl Into<String>) -> Self {
+        self.client_secret = Some(secret.into());
+        self
+    }
+
+    pub fn with_scopes(mut self, scopes: Vec<String>) -> Self {
+        self.scopes = scopes;
+        self
+    }
+
+    fn scopes(&self) -> Vec<String> {
+        if self.scopes.is_empty() {
+            self.provider.default_scopes()
+        } else {
+            self.scopes.clone()
+        }
+    }
+
+    fn redirect_uri(&self) -> String {
+        format!("http://127.0.0.1:{}/callback", self.redirect_port)
+    }
+}
+
+/// Tokens persisted per provider
+#[derive(Clone, Serialize, Deserialize)]
+pub struct OAuthToken {
+    pub access_token: String,
+    pub refresh_token: Option<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub scope: Option<String>,
+}
+
+impl std::fmt::Debug for OAuthToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OAuthToken")
+            .field("access_token", &Masked::of(&self.access_token))
+            .field("refresh_token", &Masked::opt(self.refresh_token.as_deref()))
+            .field("expires_at", &self.expires_at)
+            .field("scope", &self.scope)
+            .finish()
+    }
+}
+
+impl OAuthToken {
+    /// True when the access token is expired or about to expire
+    pub fn needs_refresh(&self) -> bool {
+        self.expires_at
+            .map(|at| at - ChronoDuration::seconds(EXPIRY_SKEW_SECS) <= Utc::now())
+            .unwrap_or(false)
+    }
+}
+
+/// Pending device authorization shown to the user
+#[derive(Debug, Clone, Deserialize)]
+pub struct DeviceAuthorization {
+    pub device_code: String,
+    pub user_code: String,
+    #[serde(alias = "verification_url")]
+    pub verification_uri: String,
+    pub expires_in: u64,
+    #[serde(default = "default_poll_interval")]
+    pub interval: u64,
+}
+
+fn default_poll_interval() -> u64 {
+    5
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    access_token: Option<String>,
+    refresh_token: Option<String>,
+    expires_in: Option<i64>,
+    scope: Option<String>,
+    error: Option<String>,
+    error_description: Option<String>,
+}
+
+impl TokenResponse {
+    fn into_token(self, previous_refresh: Option<String>) -> Result<OAuthToken, OAuthError> {
+        let access_token = self.access_token.ok_or_else(|| {
+            OAuthError::InvalidResponse(
+                self.error_description
+                    .or(self.error)
+                    .unwrap_or_else(|| "missing access_token".to_string()),
+            )
+        })?;
+        Ok(OAuthToken {
+            access_token,
+            // Providers that don't rotate refresh tokens omit them on refresh
+            refresh_token: self.refresh_token.or(previous_refresh),
+            expires_at: self.expires_in.map(|secs| Utc::now() + ChronoDuration::seconds(secs)),
+            scope: self.scope,
+        })
+    }
+}
+
+/// Runs OAuth flows and hands out fresh access tokens
+pub struct OAuthManager {
+    http: reqwest::Client,
+    secrets: Arc<SecretManager>,
+    clients: HashMap<OAuthProvider, OAuthClientConfig>,
+    tokens: RwLock<HashMap<OAuthProvider, OAuthToken>>,
+}
+
+// Tokens and client secrets stay out of debug output
+impl std::fmt::Debug for OAuthManager {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OAuthManager")
+            .field("providers", &self.providers())
+            .finish_non_exhaustive()
+    }
+}
+
+impl OAuthManager {
+    pub fn new(secrets: Arc<SecretManager>) -> Result<Self, OAuthError> {
+        let http = reqwest::Client::builder()
+            .timeout(Duration::from_secs(30))
+            .user_agent("Jamey-2.0-Agent/1.0")
+            .build()?;
+
+        Ok(Self {
+            http,
+            secrets,
+            clients: HashMap::new(),
+            tokens: RwLock::new(HashMap::new()),
+        })
+    }
+
+    /// Register an OAuth application
+    pub fn with_client(mut self, client: OAuthClientConfig) -> Self {
+        self.clients.insert(client.provider, client);
+        self
+    }
+
+    /// Providers with a registered client
+    pub fn providers(&self) -> Vec<OAuthProvider> {
+        self.clients.keys().copied().collect()
+    }
+
+    fn client(&self, provider: OAuthProvider) -> Result<&OAuthClientConfig, OAuthError> {
+        self.clients
+            .get(&provider)
+            .ok_or_else(|| OAuthError::NotConfigured(provider.as_str().to_string()))
+    }
+
+    /// Begin a device-code authorization; show `user_code` and
+    /// `verification_uri` to the user, then call [`Self::complete_device_flow`]
+    pub async fn start_device_flow(&self, provider: OAuthProvider) -> Result<DeviceAuthorization, OAuthError> {
+        let client = self.client(provider)?;
+        let url = provider
+            .device_code_url()
+            .ok_or_else(|| OAuthError::DeviceFlowUnsupported(provider.as_str().to_string()))?;
+
+        let scope = client.scopes().join(" ");
+        let response = self.http
+            .post(url)
+            .header(reqwest::header::ACCEPT, "application/json")
+            .form(&[("client_id", client.client_id.as_str()), ("scope", scope.as_str())])
+            .send()
+            .await?
+            .error_for_status()?;
+
+        response
+            .json::<DeviceAuthorization>()
+            .await
+            .map_err(|e| OAuthError::InvalidResponse(e.to_string()))
+    }
+
+    /// Poll until the user approves the device authorization, then store the token
+    pub async fn complete_device_flow(
+        &self,
+        provider: OAuthProvider,
+        authorization: &DeviceAuthorization,
+    ) -> Result<OAuthToken, OAuthError> {
+        let client = self.client(provider)?;
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(authorization.expires_in);
+        let mut interval = authorization.interval.max(1);
+
+        loop {
+            tokio::time::sleep(Duration::from_secs(interval)).await;
+            if tokio::time::Instant::now() >= deadline {
+                return Err(OAuthError::Expired);
+            }
+
+            let mut form = vec![
+                ("client_id", client.client_id.clone()),
+                ("device_code", authorization.device_code.clone()),
+                ("grant_type", "urn:ietf:params:oauth:grant-type:device_code".to_string()),
+            ];
+            if let Some(secret) = &client.client_secret {
+                form.push(("client_secret", secret.clone()));
+            }
+
+            let response: TokenResponse = self.http
+                .post(provider.token_url())
+                .header(reqwest::header::ACCEPT, "application/json")
+                .form(&form)
+                .send()
+                .await?
+                .json()
+                .await?;
+
+            match response.error.as_deref() {
+                None => {
+                    let token = response.into_token(None)?;
+                    self.save_token(provider, token.clone()).await?;
+                    return Ok(token);
+                }
+                Some("authorization_pending") => continue,
+                Some("slow_down") => interval += 5,
+                Some("expired_token") => return Err(OAuthError::Expired),
+                Some(other) => {
+                    return Err(OAuthError::Denied(
+                        response.error_description.unwrap_or_else(|| other.to_string()),
+                    ))
+                }
+            }
+        }
+    }
+
+    /// Authorization URL, PKCE verifier and state for the redirect flow
+    fn authorization_request(&self, client: &OAuthClientConfig) -> (String, String, String) {
+        let state = uuid::Uuid::new_v4().simple().to_string();
+        let verifier = format!(
+            "{}{}",
+            uuid::Uuid::new_v4().simple(),
+            uuid::Uuid::new_v4().simple()
+        );
+        let challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes()));
+
+        let scope = client.scopes().join(if client.provider == OAuthProvider::Slack { "," } else { " " });
+        let mut url = url::Url::parse(client.provider.authorize_url()).expect("static URL");
+        url.query_pairs_mut()
+            .append_pair("client_id", &client.client_id)
+            .append_pair("redirect_uri", &client.redirect_uri())
+            .append_pair("response_type", "code")
+            .append_pair("scope", &scope)
+            .append_pair("state", &state)
+            .append_pair("code_challenge", &challenge)
+            .append_pair("code_challenge_method", "S256");
+        if client.provider == OAuthProvider::Google {
+            // Needed to receive a refresh token
+            url.query_pairs_mut().append_pair("access_type", "offline").append_pair("prompt", "consent");
+        }
+
+        (url.to_string(), verifier, state)
+    }
+
+    /// Run the localhost-redirect flow: `open_browser` receives the URL the
+    /// user must visit; a one-shot listener on 127.0.0.1 captures the code.
+    pub async fn authorize_with_redirect<F>(
+        &self,
+        provider: OAuthProvider,
+        open_browser: F,
+    ) -> Result<OAuthToken, OAuthError>
+    where
+        F: FnOnce(&str),
+    {
+        let client = self.client(provider)?;
+        let listener = tokio::net::TcpListener::bind(("127.0.0.1", client.redirect_port))
+            .await
+            .map_err(|e| OAuthError::Redirect(format!("Failed to bind port {}: {}", client.redirect_port, e)))?;
+
+        let (auth_url, verifier, state) = self.authorization_request(client);
+        open_browser(&auth_url);
+
+        let accept = async {
+            let (mut stream, _) = listener.accept().await?;
+            let mut buf = vec![0u8; 8192];
+            let n = stream.read(&mut buf).await?;
+            let request = String::from_utf8_lossy(&buf[..n]).to_string();
+            let body = "<html><body><h3>Jamey is authorized. You can close this window.</h3></body></html>";
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            stream.write_all(response.as_bytes()).await?;
+            Ok::<String, std::io::Error>(request)
+        };
+
+        let request = tokio::time::timeout(Duration::from_secs(REDIRECT_TIMEOUT_SECS), accept)
+            .await
+            .map_err(|_| OAuthError::Expired)?
+            .map_err(|e| OAuthError::Redirect(e.to_string()))?;
+
+        let params = parse_callback(&request)?;
+        if params.get("state") != Some(&state) {
+            return Err(OAuthError::Redirect("State mismatch (possible CSRF)".to_string()));
+        }
+        if let Some(error) = params.get("error") {
+            return Err(OAuthError::Denied(error.clone()));
+        }
+        let code = params
+            .get("code")
+            .ok_or_else(|| OAuthError::Redirect("Callback did not include a code".to_string()))?;
+
+        let mut form = vec![
+            ("client_id", client.client_id.clone()),
+            ("code", code.clone()),
+            ("code_verifier", verifier),
+            ("grant_type", "authorization_code".to_string()),
+            ("redirect_uri", client.redirect_uri()),
+        ];
+        if let Some(secret) = &client.client_secret {
+            form.push(("client_secret", secret.clone()));
+        }
+
+        let token = self.request_token(provider, &form, None).await?;
+        self.save_token(provider, token.clone()).await?;
+        Ok(token)
+    }
+
+    /// A valid access token, refreshed transparently when close to expiry
+    pub async fn access_token(&self, provider: OAuthProvider) -> Result<String, OAuthError> {
+        if let Some(token) = self.tokens.read().await.get(&provider) {
+            if !token.needs_refresh() {
+                return Ok(token.access_token.clone());
+            }
+        }
+
+        let token = match self.load_token(provider)? {
+            Some(token) if !token.needs_refresh() => token,
+            Some(token) => self.refresh(provider, &token).await?,
+            None => return Err(OAuthError::NotAuthorized(provider.as_str().to_string())),
+        };
+
+        let access = token.access_token.clone();
+        self.tokens.write().await.insert(provider, token);
+        Ok(access)
+    }
+
+    /// When the cached token for `provider` next needs refreshing, if ever
+    pub async fn expires_at(&self, provider: OAuthProvider) -> Option<DateTime<Utc>> {
+        self.tokens.read().await.get(&provider).and_then(|t| t.expires_at)
+    }
+
+    async fn refresh(&self, provider: OAuthProvider, token: &OAuthToken) -> Result<OAuthToken, OAuthError> {
+        let client = self.client(provider)?;
+        let refresh_token = token
+            .refresh_token
+            .clone()
+            .ok_or_else(|| OAuthError::NotAuthorized(provider.as_str().to_string()))?;
+
+        let mut form = vec![
+            ("client_id", client.client_id.clone()),
+            ("grant_type", "refresh_token".to_string()),
+            ("refresh_token", refresh_token.clone()),
+        ];
+        if let Some(secret) = &client.client_secret {
+            form.push(("client_secret", secret.clone()));
+        }
+
+        let refreshed = self.request_token(provider, &form, Some(refresh_token)).await?;
+        self.save_token(provider, refreshed.clone()).await?;
+        tracing::info!("Refreshed {} OAuth access token", provider.as_str());
+        Ok(refreshed)
+    }
+
+    async fn request_token(
+        &self,
+        provider: OAuthProvider,
+        form: &[(&str, String)],
+        previous_refresh: Option<String>,
+    ) -> Result<OAuthToken, OAuthError> {
+        let response: TokenResponse = self.http
+            .post(provider.token_url())
+            .header(reqwest::header::ACCEPT, "application/json")
+            .form(form)
+            .send()
+            .await?
+            .json()
+            .await?;
+        response.into_token(previous_refresh)
+    }
+
+    fn load_token(&self, provider: OAuthProvider) -> Result<Option<OAuthToken>, OAuthError> {
+        match self.secrets.get_secret(&provider.secret_key()) {
+            Ok(raw) => serde_json::from_str(&raw)
+                .map(Some)
+                .map_err(|e| OAuthError::InvalidResponse(format!("Corrupt stored token: {}", e))),
+            Err(SecretError::NotFound(_)) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Persist a token and publish it so connectors pick it up without restart
+    async fn save_token(&self, provider: OAuthProvider, token: OAuthToken) -> Result<(), OAuthError> {
+        let raw = serde_json::to_string(&token)
+            .map_err(|e| OAuthError::InvalidResponse(e.to_string()))?;
+        self.secrets.rotate_secret_to(&provider.secret_key(), &raw)?;
+        self.tokens.write().await.insert(provider, token);
+        Ok(())
+    }
+
+    /// Forget stored tokens for a provider
+    pub async fn logout(&self, provider: OAuthProvider) -> Result<(), OAuthError> {
+        self.tokens.write().await.remove(&provider);
+        match self.secrets.delete_secret(&provider.secret_key()) {
+            Ok(()) | Err(SecretError::NotFound(_)) => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Whether a token (possibly expired but refreshable) is stored
+    pub fn is_authorized(&self, provider: OAuthProvider) -> bool {
+        matches!(self.load_token(provider), Ok(Some(_)))
+    }
+}
+
+/// Parse query parameters from the first line of an HTTP callback request
+fn parse_callback(request: &str) -> Result<HashMap<String, String>, OAuthError> {
+    let path = request
+        .lines()
+        .next()
+        .and_then(|line| line.split_whitespace().nth(1))
+        .ok_or_else(|| OAuthError::Redirect("Malformed callback request".to_string()))?;
+    let url = url::Url::parse(&format!("http://127.0.0.1{}", path))
+        .map_err(|e| OAuthError::Redirect(e.to_string()))?;
+    Ok(url.query_pairs().into_owned().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_callback() {
+        let request = "GET /callback?code=abc123&state=xyz HTTP/1.1\r\nHost: 127.0.0.1:8765\r\n\r\n";
+        let params = parse_callback(request).unwrap();
+        assert_eq!(params.get("code").map(String::as_str), Some("abc123"));
+        assert_eq!(params.get("state").map(String::as_str), Some("xyz"));
+    }
+
+    #[test]
+    fn test_token_refresh_window() {
+        let fresh = OAuthToken {
+            access_token: "a".to_string(),
+            refresh_token: None,
+            expires_at: Some(Utc::now() + ChronoDuration::hours(1)),
+            scope: None,
+        };
+        assert!(!fresh.needs_refresh());
+
+        let expiring = OAuthToken {
+            expires_at: Some(Utc::now() + ChronoDuration::seconds(10)),
+            ..fresh.clone()
+        };
+        assert!(expiring.needs_refresh());
+
+        let non_expiring = OAuthToken { expires_at: None, ..fresh };
+        assert!(!non_expiring.needs_refresh());
+    }
+
+    #[test]
+    fn test_refresh_keeps_previous_refresh_token() {
+        let response = TokenResponse {
+            access_token: Some("new".to_string()),
+            refresh_token: None,
+            expires_in: Some(3600),
+            scope: None,
+            error: None,
+            error_description: None,
+        };
+        let token = response.into_token(Some("r1".to_string())).unwrap();
+        assert_eq!(token.refresh_token.as_deref(), Some("r1"));
+        assert!(token.expires_at.is_some());
+    }
+
+    #[test]
+    fn test_device_flow_only_where_supported() {
+        assert!(OAuthProvider::GitHub.device_code_url().is_some());
+        assert!(OAuthProvider::Google.device_code_url().is_some());
+        assert!(OAuthProvider::Slack.device_code_url().is_none());
+        assert_eq!(OAuthProvider::GitHub.credential_key(), "github_token");
+    }
+}
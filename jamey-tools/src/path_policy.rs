@@ -0,0 +1,275 @@
+//! Filesystem access policy
+//!
+//! [`PathPolicy`] is the one place that decides which files tools may read
+//! and write, so the Full System connector, [`SelfModifyTool`], ingestion
+//! and project watching all refuse the same paths:
+//!
+//! - `read` and `write` list the directories each kind of access is
+//!   limited to; an empty list leaves it to the tool's own root.
+//! - `deny` globs are refused for reading and writing alike, wherever they
+//!   are.
+//! - `max_file_bytes` caps the size of a file read or written.
+//!
+//! Paths are compared after resolving symlinks, so a link inside an allowed
+//! directory can't reach outside it.
+//!
+//! ```toml
+//! [tools.path_policy]
+//! read = ["/home/jamey/projects", "/home/jamey/notes"]
+//! write = ["/home/jamey/projects"]
+//! deny = ["**/.ssh/**", "**/.env", "*.pem"]
+//! max_file_bytes = 5242880
+//! ```
+//!
+//! [`SelfModifyTool`]: crate::system::SelfModifyTool
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::path::{Component, Path, PathBuf};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum PathPolicyError {
+    #[error("Invalid deny pattern {pattern}: {source}")]
+    Pattern {
+        pattern: String,
+        source: glob::PatternError,
+    },
+    #[error("{0} is outside the readable directories")]
+    NotReadable(PathBuf),
+    #[error("{0} is outside the writable directories")]
+    NotWritable(PathBuf),
+    #[error("{path} is denied by {pattern}")]
+    Denied { path: PathBuf, pattern: String },
+    #[error("{path} is {size} bytes (limit {limit})")]
+    TooLarge { path: PathBuf, size: u64, limit: u64 },
+}
+
+/// Where tools may read and write; the default allows everything
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct PathPolicyConfig {
+    /// Directories files may be read from
+    pub read: Vec<PathBuf>,
+    /// Directories files may be written to
+    pub write: Vec<PathBuf>,
+    /// Glob patterns never read or written
+    pub deny: Vec<String>,
+    /// Largest file read or written, in bytes
+    pub max_file_bytes: Option<u64>,
+}
+
+impl PathPolicyConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        for pattern in &self.deny {
+            glob::Pattern::new(pattern)
+                .map_err(|e| format!("tools.path_policy.deny has an invalid pattern {}: {}", pattern, e))?;
+        }
+        if self.max_file_bytes == Some(0) {
+            return Err("tools.path_policy.max_file_bytes must be at least 1".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// A [`PathPolicyConfig`] with its directories resolved and its patterns
+/// compiled
+#[derive(Debug, Clone, Default)]
+pub struct PathPolicy {
+    read: Vec<PathBuf>,
+    write: Vec<PathBuf>,
+    deny: Vec<glob::Pattern>,
+    max_file_bytes: Option<u64>,
+}
+
+impl PathPolicy {
+    pub fn new(config: &PathPolicyConfig) -> Result<Self, PathPolicyError> {
+        let deny = config
+            .deny
+            .iter()
+            .map(|pattern| {
+                glob::Pattern::new(pattern).map_err(|source| PathPolicyError::Pattern {
+                    pattern: pattern.clone(),
+                    source,
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            read: config.read.iter().map(|dir| resolve(dir)).collect(),
+            write: config.write.iter().map(|dir| resolve(dir)).collect(),
+            deny,
+            max_file_bytes: config.max_file_bytes,
+        })
+    }
+
+    /// Whether `path` matches a deny pattern
+    pub fn is_denied(&self, path: &Path) -> bool {
+        self.denied_by(&resolve(path)).is_some()
+    }
+
+    /// Check that `path`, a file or directory, may be read
+    pub fn check_read(&self, path: &Path) -> Result<(), PathPolicyError> {
+        let path = resolve(path);
+        self.check_denied(&path)?;
+        if !self.read.is_empty() && !self.read.iter().any(|dir| path.starts_with(dir)) {
+            return Err(PathPolicyError::NotReadable(path));
+        }
+        if let Some(limit) = self.max_file_bytes {
+            let size = std::fs::metadata(&path).ok().filter(|m| m.is_file()).map_or(0, |m| m.len());
+            if size > limit {
+                return Err(PathPolicyError::TooLarge { path, size, limit });
+            }
+        }
+        Ok(())
+    }
+
+    /// Check that `size` bytes may be written to `path`, which need not
+    /// exist yet. Deleting a file counts as writing nothing to it.
+    pub fn check_write(&self, path: &Path, size: u64) -> Result<(), PathPolicyError> {
+        let path = resolve(path);
+        self.check_denied(&path)?;
+        if !self.write.is_empty() && !self.write.iter().any(|dir| path.starts_with(dir)) {
+            return Err(PathPolicyError::NotWritable(path));
+        }
+        match self.max_file_bytes {
+            Some(limit) if size > limit => Err(PathPolicyError::TooLarge { path, size, limit }),
+            _ => Ok(()),
+        }
+    }
+
+    fn check_denied(&self, path: &Path) -> Result<(), PathPolicyError> {
+        match self.denied_by(path) {
+            Some(pattern) => Err(PathPolicyError::Denied {
+                path: path.to_path_buf(),
+                pattern: pattern.as_str().to_string(),
+            }),
+            None => Ok(()),
+        }
+    }
+
+    fn denied_by(&self, path: &Path) -> Option<&glob::Pattern> {
+        self.deny.iter().find(|pattern| pattern.matches_path(path))
+    }
+}
+
+/// `path` with symlinks resolved, as far up as it exists; a file that is
+/// about to be created resolves through its nearest existing parent. `..`
+/// below that parent is applied lexically, since nothing there can be a
+/// symlink, so `allowed/new/../../etc` can't pass for a path under
+/// `allowed`.
+fn resolve(path: &Path) -> PathBuf {
+    let mut existing = path;
+    let mut rest = Vec::new();
+    let base = loop {
+        let dir = if existing.as_os_str().is_empty() { Path::new(".") } else { existing };
+        if let Ok(resolved) = dir.canonicalize() {
+            break resolved;
+        }
+        match (existing.parent(), existing.components().next_back()) {
+            (Some(parent), Some(last)) => {
+                rest.push(last);
+                existing = parent;
+            }
+            _ => {
+                rest.extend(existing.components().rev());
+                break PathBuf::new();
+            }
+        }
+    };
+    rest.iter().rev().fold(base, |mut path, part| {
+        match part {
+            Component::ParentDir => {
+                path.pop();
+            }
+            Component::CurDir => {}
+            part => path.push(part),
+        }
+        path
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn policy(dir: &Path) -> PathPolicy {
+        PathPolicy::new(&PathPolicyConfig {
+            read: vec![dir.to_path_buf()],
+            write: vec![dir.join("out")],
+            deny: vec!["**/.ssh/**".to_string(), "*.pem".to_string()],
+            max_file_bytes: Some(16),
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_allowlists_and_size() {
+        let temp = TempDir::new().unwrap();
+        std::fs::create_dir(temp.path().join("out")).unwrap();
+        std::fs::write(temp.path().join("small.txt"), "hello").unwrap();
+        std::fs::write(temp.path().join("big.txt"), "x".repeat(64)).unwrap();
+        let policy = policy(temp.path());
+
+        assert!(policy.check_read(&temp.path().join("small.txt")).is_ok());
+        assert!(policy.check_read(temp.path()).is_ok());
+        assert!(matches!(policy.check_read(&temp.path().join("big.txt")), Err(PathPolicyError::TooLarge { .. })));
+        assert!(matches!(policy.check_read(Path::new("/etc/hostname")), Err(PathPolicyError::NotReadable(_))));
+
+        assert!(policy.check_write(&temp.path().join("out/new/file.txt"), 5).is_ok());
+        assert!(matches!(policy.check_write(&temp.path().join("small.txt"), 5), Err(PathPolicyError::NotWritable(_))));
+        assert!(matches!(policy.check_write(&temp.path().join("out/a.txt"), 17), Err(PathPolicyError::TooLarge { .. })));
+    }
+
+    #[test]
+    fn test_deny_patterns() {
+        let temp = TempDir::new().unwrap();
+        let policy = policy(temp.path());
+        let key = temp.path().join(".ssh/id_ed25519");
+        assert!(policy.is_denied(&key));
+        assert!(matches!(policy.check_read(&key), Err(PathPolicyError::Denied { .. })));
+        assert!(policy.check_write(&temp.path().join("out/cert.pem"), 1).is_err());
+        assert!(!policy.is_denied(&temp.path().join("notes.md")));
+
+        let open = PathPolicy::default();
+        assert!(open.check_read(&key).is_ok());
+        assert!(open.check_write(&key, u64::MAX).is_ok());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlinks_are_resolved() {
+        let temp = TempDir::new().unwrap();
+        let outside = TempDir::new().unwrap();
+        std::fs::write(outside.path().join("secret.txt"), "s").unwrap();
+        std::os::unix::fs::symlink(outside.path(), temp.path().join("link")).unwrap();
+        let policy = policy(temp.path());
+        assert!(matches!(
+            policy.check_read(&temp.path().join("link/secret.txt")),
+            Err(PathPolicyError::NotReadable(_))
+        ));
+    }
+
+    #[test]
+    fn test_parent_dirs_below_missing_directories() {
+        let temp = TempDir::new().unwrap();
+        std::fs::create_dir(temp.path().join("out")).unwrap();
+        let policy = policy(temp.path());
+
+        assert!(policy.check_write(&temp.path().join("out/new/../a.txt"), 1).is_ok());
+        assert!(matches!(
+            policy.check_write(&temp.path().join("out/new/../../a.txt"), 1),
+            Err(PathPolicyError::NotWritable(_))
+        ));
+        let escape = temp.path().join("new/deeper/../../../../../../etc/hostname");
+        assert!(matches!(policy.check_read(&escape), Err(PathPolicyError::NotReadable(_))));
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(PathPolicyConfig::default().validate().is_ok());
+        let bad = PathPolicyConfig { deny: vec!["[".to_string()], ..Default::default() };
+        assert!(bad.validate().is_err());
+        assert!(PathPolicy::new(&bad).is_err());
+    }
+}
@@ -0,0 +1,410 @@
+//! Execution sandbox for the commands connectors start
+//!
+//! [`ExecutionSandbox`] confines the programs Jamey runs on the model's
+//! behalf (`execute_command`, test runs and terminal shells) to a
+//! low-privilege user and a set of resource limits:
+//!
+//! - **Unix**: CPU time, address space, open files and processes are
+//!   `setrlimit` limits set in the child before it execs, and the user
+//!   switch is a `setgid`/`setuid` there too. Shells in a pseudo-terminal
+//!   can't run code before exec, so they are wrapped in `setpriv` (Linux)
+//!   and a `ulimit` line instead. Switching user needs Jamey to run as root
+//!   or with `CAP_SETUID`/`CAP_SETGID`.
+//! - **Windows**: the process is put in a Job Object with the CPU, memory
+//!   and process limits, and killed when the job is closed. `user` isn't
+//!   supported.
+//!
+//! The wall-clock limit caps the caller's own timeout on every platform.
+//!
+//! ```toml
+//! [tools.sandbox]
+//! user = "jamey-sandbox"
+//! cpu_secs = 300
+//! memory_mb = 2048
+//! max_open_files = 256
+//! wall_clock_secs = 900
+//! ```
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::ffi::OsString;
+use std::time::Duration;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum SandboxError {
+    #[error("Unknown sandbox user: {0}")]
+    UnknownUser(String),
+    #[error("Running commands as another user is not supported on this platform")]
+    UserUnsupported,
+    #[error("Failed to confine the process: {0}")]
+    Confine(#[from] std::io::Error),
+}
+
+/// Limits for every command a connector starts; unset fields are not
+/// limited
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct SandboxConfig {
+    /// Run commands as this user instead of Jamey's own (Unix only)
+    pub user: Option<String>,
+    /// CPU seconds a command may use
+    pub cpu_secs: Option<u64>,
+    /// Address space (Unix) or committed memory (Windows) of each process,
+    /// in MiB
+    pub memory_mb: Option<u64>,
+    /// Open file descriptors per process (Unix only)
+    pub max_open_files: Option<u64>,
+    /// Processes a command may run at once. On Unix this counts every
+    /// process of the user, so it is best combined with `user`
+    pub max_processes: Option<u64>,
+    /// Seconds before a command is killed, however long its caller would
+    /// wait
+    pub wall_clock_secs: Option<u64>,
+}
+
+impl SandboxConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.user.as_deref().is_some_and(|user| user.trim().is_empty()) {
+            return Err("tools.sandbox.user can't be empty".to_string());
+        }
+        let limits = [
+            ("cpu_secs", self.cpu_secs),
+            ("memory_mb", self.memory_mb),
+            ("max_open_files", self.max_open_files),
+            ("max_processes", self.max_processes),
+            ("wall_clock_secs", self.wall_clock_secs),
+        ];
+        if let Some((name, _)) = limits.iter().find(|(_, limit)| *limit == Some(0)) {
+            return Err(format!("tools.sandbox.{} must be at least 1", name));
+        }
+        if self.user.is_some() && cfg!(windows) {
+            return Err("tools.sandbox.user is not supported on Windows".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// A [`SandboxConfig`] with its user looked up, ready to apply to commands
+#[derive(Debug, Clone, Default)]
+pub struct ExecutionSandbox {
+    config: SandboxConfig,
+    /// uid and gid of `config.user`
+    ids: Option<(u32, u32)>,
+}
+
+impl ExecutionSandbox {
+    pub fn new(config: SandboxConfig) -> Result<Self, SandboxError> {
+        let ids = match &config.user {
+            Some(user) => Some(lookup_user(user)?),
+            None => None,
+        };
+        Ok(Self { config, ids })
+    }
+
+    pub fn config(&self) -> &SandboxConfig {
+        &self.config
+    }
+
+    pub fn wall_clock(&self) -> Option<Duration> {
+        self.config.wall_clock_secs.map(Duration::from_secs)
+    }
+
+    /// `requested`, shortened to the wall-clock limit
+    pub fn timeout(&self, requested: Duration) -> Duration {
+        match self.config.wall_clock_secs {
+            Some(secs) => requested.min(Duration::from_secs(secs)),
+            None => requested,
+        }
+    }
+
+    /// Set up `command` to drop to the sandbox user and take the limits
+    /// when it starts. On Windows the limits come from [`confine`](Self::confine)
+    /// once it is running.
+    pub fn apply(&self, command: &mut tokio::process::Command) {
+        #[cfg(unix)]
+        {
+            if let Some((uid, gid)) = self.ids {
+                command.gid(gid).uid(uid);
+            }
+            let limits = self.rlimits();
+            if !limits.is_empty() {
+                // Safety: setrlimit is async-signal-safe and `limits` is
+                // built before the fork
+                unsafe {
+                    command.pre_exec(move || limits.set());
+                }
+            }
+        }
+        #[cfg(not(unix))]
+        let _ = command;
+    }
+
+    /// `argv` wrapped so it starts as the sandbox user with the limits in
+    /// place, for launchers that can't run code before exec, like a
+    /// pseudo-terminal. The limits are set by `bash`'s `ulimit`, and a
+    /// limit that can't be set stops the command from starting.
+    pub fn wrap(&self, argv: Vec<OsString>) -> Vec<OsString> {
+        if cfg!(windows) {
+            return argv;
+        }
+        let mut wrapped: Vec<OsString> = Vec::new();
+        if let Some((uid, gid)) = self.ids {
+            wrapped.extend(
+                [
+                    "setpriv".to_string(),
+                    format!("--reuid={}", uid),
+                    format!("--regid={}", gid),
+                    "--init-groups".to_string(),
+                    "--".to_string(),
+                ]
+                .map(OsString::from),
+            );
+        }
+        let ulimits = self.ulimit_line();
+        if !ulimits.is_empty() {
+            let script = format!("{} && exec \"$@\"", ulimits);
+            wrapped.extend(["bash", "-c", &script, "bash"].map(OsString::from));
+        }
+        wrapped.extend(argv);
+        wrapped
+    }
+
+    /// Hold the running process `pid` to the limits until the returned
+    /// [`Confinement`] is dropped, which kills it. Only Windows needs this;
+    /// elsewhere the limits are already set by [`apply`](Self::apply).
+    pub fn confine(&self, pid: Option<u32>) -> Result<Confinement, SandboxError> {
+        #[cfg(windows)]
+        {
+            let limited = self.config.cpu_secs.is_some()
+                || self.config.memory_mb.is_some()
+                || self.config.max_processes.is_some();
+            match pid {
+                Some(pid) if limited => return windows_job::confine(&self.config, pid),
+                _ => {}
+            }
+        }
+        let _ = pid;
+        Ok(Confinement::default())
+    }
+
+    #[cfg(unix)]
+    fn rlimits(&self) -> Rlimits {
+        Rlimits {
+            cpu: self.config.cpu_secs,
+            memory: self.config.memory_mb.map(|mb| mb.saturating_mul(1024 * 1024)),
+            files: self.config.max_open_files,
+            processes: self.config.max_processes,
+        }
+    }
+
+    fn ulimit_line(&self) -> String {
+        let limits = [
+            ("-t", self.config.cpu_secs),
+            ("-v", self.config.memory_mb.map(|mb| mb.saturating_mul(1024))),
+            ("-n", self.config.max_open_files),
+            ("-u", self.config.max_processes),
+        ];
+        limits
+            .iter()
+            .filter_map(|(flag, limit)| limit.map(|limit| format!("ulimit {} {}", flag, limit)))
+            .collect::<Vec<_>>()
+            .join(" && ")
+    }
+}
+
+/// Keeps a process inside its Job Object; dropping it closes the job and
+/// kills whatever is still running in it
+#[derive(Debug, Default)]
+pub struct Confinement {
+    #[cfg(windows)]
+    job: Option<windows::Win32::Foundation::HANDLE>,
+}
+
+#[cfg(windows)]
+impl Drop for Confinement {
+    fn drop(&mut self) {
+        if let Some(job) = self.job.take() {
+            unsafe {
+                windows::Win32::Foundation::CloseHandle(job);
+            }
+        }
+    }
+}
+
+#[cfg(unix)]
+#[derive(Debug, Clone, Copy)]
+struct Rlimits {
+    cpu: Option<u64>,
+    memory: Option<u64>,
+    files: Option<u64>,
+    processes: Option<u64>,
+}
+
+#[cfg(unix)]
+impl Rlimits {
+    fn is_empty(&self) -> bool {
+        self.cpu.is_none() && self.memory.is_none() && self.files.is_none() && self.processes.is_none()
+    }
+
+    /// Runs in the forked child, so it must not allocate
+    fn set(&self) -> std::io::Result<()> {
+        let limits = [
+            (libc::RLIMIT_CPU, self.cpu),
+            (libc::RLIMIT_AS, self.memory),
+            (libc::RLIMIT_NOFILE, self.files),
+            (libc::RLIMIT_NPROC, self.processes),
+        ];
+        for (resource, limit) in limits {
+            let Some(limit) = limit else { continue };
+            let limit = libc::rlimit {
+                rlim_cur: limit as libc::rlim_t,
+                rlim_max: limit as libc::rlim_t,
+            };
+            if unsafe { libc::setrlimit(resource, &limit) } != 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(unix)]
+fn lookup_user(name: &str) -> Result<(u32, u32), SandboxError> {
+    let c_name = std::ffi::CString::new(name).map_err(|_| SandboxError::UnknownUser(name.to_string()))?;
+    let mut passwd: libc::passwd = unsafe { std::mem::zeroed() };
+    let mut buffer = vec![0 as libc::c_char; 16 * 1024];
+    let mut found: *mut libc::passwd = std::ptr::null_mut();
+    let status = unsafe {
+        libc::getpwnam_r(c_name.as_ptr(), &mut passwd, buffer.as_mut_ptr(), buffer.len(), &mut found)
+    };
+    if status != 0 {
+        return Err(SandboxError::Confine(std::io::Error::from_raw_os_error(status)));
+    }
+    if found.is_null() {
+        return Err(SandboxError::UnknownUser(name.to_string()));
+    }
+    Ok((passwd.pw_uid, passwd.pw_gid))
+}
+
+#[cfg(not(unix))]
+fn lookup_user(_name: &str) -> Result<(u32, u32), SandboxError> {
+    Err(SandboxError::UserUnsupported)
+}
+
+#[cfg(windows)]
+mod windows_job {
+    use super::{Confinement, SandboxConfig, SandboxError};
+    use windows::core::PCWSTR;
+    use windows::Win32::Foundation::CloseHandle;
+    use windows::Win32::System::JobObjects::{
+        AssignProcessToJobObject, CreateJobObjectW, JobObjectExtendedLimitInformation, SetInformationJobObject,
+        JOBOBJECT_EXTENDED_LIMIT_INFORMATION, JOB_OBJECT_LIMIT_ACTIVE_PROCESS, JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE,
+        JOB_OBJECT_LIMIT_PROCESS_MEMORY, JOB_OBJECT_LIMIT_PROCESS_TIME,
+    };
+    use windows::Win32::System::Threading::{OpenProcess, PROCESS_SET_QUOTA, PROCESS_TERMINATE};
+
+    fn os_error(error: windows::core::Error) -> SandboxError {
+        SandboxError::Confine(std::io::Error::new(std::io::ErrorKind::Other, error.to_string()))
+    }
+
+    pub(super) fn confine(config: &SandboxConfig, pid: u32) -> Result<Confinement, SandboxError> {
+        let mut info = JOBOBJECT_EXTENDED_LIMIT_INFORMATION::default();
+        let mut flags = JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE;
+        if let Some(secs) = config.cpu_secs {
+            // In 100ns ticks
+            info.BasicLimitInformation.PerProcessUserTimeLimit = secs.saturating_mul(10_000_000) as i64;
+            flags |= JOB_OBJECT_LIMIT_PROCESS_TIME;
+        }
+        if let Some(mb) = config.memory_mb {
+            info.ProcessMemoryLimit = mb.saturating_mul(1024 * 1024) as usize;
+            flags |= JOB_OBJECT_LIMIT_PROCESS_MEMORY;
+        }
+        if let Some(processes) = config.max_processes {
+            info.BasicLimitInformation.ActiveProcessLimit = processes.min(u32::MAX as u64) as u32;
+            flags |= JOB_OBJECT_LIMIT_ACTIVE_PROCESS;
+        }
+        info.BasicLimitInformation.LimitFlags = flags;
+
+        unsafe {
+            let job = CreateJobObjectW(None, PCWSTR::null()).map_err(os_error)?;
+            let confinement = Confinement { job: Some(job) };
+            SetInformationJobObject(
+                job,
+                JobObjectExtendedLimitInformation,
+                &info as *const _ as *const std::ffi::c_void,
+                std::mem::size_of::<JOBOBJECT_EXTENDED_LIMIT_INFORMATION>() as u32,
+            )
+            .ok()
+            .map_err(os_error)?;
+            let process = OpenProcess(PROCESS_SET_QUOTA | PROCESS_TERMINATE, false, pid).map_err(os_error)?;
+            let assigned = AssignProcessToJobObject(job, process);
+            CloseHandle(process);
+            assigned.ok().map_err(os_error)?;
+            Ok(confinement)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limited() -> ExecutionSandbox {
+        ExecutionSandbox::new(SandboxConfig {
+            cpu_secs: Some(30),
+            max_open_files: Some(64),
+            wall_clock_secs: Some(60),
+            ..Default::default()
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(SandboxConfig::default().validate().is_ok());
+        let zero = SandboxConfig { memory_mb: Some(0), ..Default::default() };
+        assert_eq!(zero.validate().unwrap_err(), "tools.sandbox.memory_mb must be at least 1");
+        let blank = SandboxConfig { user: Some(" ".to_string()), ..Default::default() };
+        assert!(blank.validate().is_err());
+    }
+
+    #[test]
+    fn test_wall_clock_caps_timeout() {
+        let sandbox = limited();
+        assert_eq!(sandbox.timeout(Duration::from_secs(600)), Duration::from_secs(60));
+        assert_eq!(sandbox.timeout(Duration::from_secs(5)), Duration::from_secs(5));
+        assert_eq!(ExecutionSandbox::default().timeout(Duration::from_secs(600)), Duration::from_secs(600));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_unknown_user() {
+        let config = SandboxConfig { user: Some("no-such-jamey-user".to_string()), ..Default::default() };
+        assert!(matches!(ExecutionSandbox::new(config), Err(SandboxError::UnknownUser(_))));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_limits_reach_the_child() {
+        let mut command = tokio::process::Command::new("sh");
+        command.args(["-c", "ulimit -n; ulimit -t"]);
+        limited().apply(&mut command);
+        let output = command.output().await.unwrap();
+        assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "64\n30");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_wrap_sets_limits_before_exec() {
+        let argv = vec![OsString::from("bash"), OsString::from("-i")];
+        assert_eq!(ExecutionSandbox::default().wrap(argv.clone()), argv);
+
+        let wrapped = limited().wrap(argv);
+        let wrapped: Vec<_> = wrapped.iter().map(|arg| arg.to_string_lossy().into_owned()).collect();
+        assert_eq!(
+            wrapped,
+            ["bash", "-c", "ulimit -t 30 && ulimit -n 64 && exec \"$@\"", "bash", "bash", "-i"]
+        );
+    }
+}
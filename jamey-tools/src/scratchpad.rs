@@ -0,0 +1,415 @@
+//! Session-scoped scratchpad for multi-step tool workflows
+//!
+//! A [`Scratchpad`] holds short-lived, typed values a session's tool calls
+//! hand to each other, such as a file list found in one step and processed
+//! in the next. Long-term memory isn't cluttered with them. Entries live in
+//! the cache layer ([`CacheManager`]), so they are shared between runtimes
+//! when Redis is configured. They expire after their TTL and may be evicted
+//! early when the in-memory cache is full, so nothing that must survive
+//! belongs here.
+//!
+//! Every session sees only its own keys. Besides the entries themselves,
+//! each session has an index entry listing its keys, which is what
+//! [`Scratchpad::list`] and [`Scratchpad::clear`] read.
+
+use chrono::{DateTime, Utc};
+use jamey_core::cache::{CacheError, CacheManager};
+use schemars::JsonSchema;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Duration;
+use thiserror::Error;
+
+const KEY_PREFIX: &str = "scratchpad";
+const MAX_KEY_LEN: usize = 128;
+
+#[derive(Debug, Error)]
+pub enum ScratchpadError {
+    #[error("Cache error: {0}")]
+    Cache(#[from] CacheError),
+    #[error("Invalid key '{0}': use up to 128 letters, digits, '.', '_' or '-'")]
+    InvalidKey(String),
+    #[error("'{key}' holds a {found}, not a {expected}")]
+    TypeMismatch { key: String, expected: ValueType, found: ValueType },
+    #[error("Invalid {0} value: {1}")]
+    InvalidValue(ValueType, String),
+    #[error("Value is {0} bytes; the limit is {1}")]
+    TooLarge(usize, usize),
+    #[error("Scratchpad is full ({0} entries); delete or clear some first")]
+    Full(usize),
+}
+
+/// Type an entry was stored as; reads asking for another type fail
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ValueType {
+    String,
+    Number,
+    Boolean,
+    Json,
+}
+
+impl ValueType {
+    /// Type of an arbitrary JSON value
+    pub fn of(value: &serde_json::Value) -> Self {
+        match value {
+            serde_json::Value::String(_) => Self::String,
+            serde_json::Value::Number(_) => Self::Number,
+            serde_json::Value::Bool(_) => Self::Boolean,
+            _ => Self::Json,
+        }
+    }
+
+    /// Parse tool input as this type
+    pub fn parse(self, text: &str) -> Result<serde_json::Value, ScratchpadError> {
+        let invalid = |e: String| ScratchpadError::InvalidValue(self, e);
+        match self {
+            Self::String => Ok(serde_json::Value::String(text.to_string())),
+            Self::Number => {
+                let text = text.trim();
+                if let Ok(n) = text.parse::<i64>() {
+                    return Ok(n.into());
+                }
+                text.parse::<f64>()
+                    .ok()
+                    .and_then(serde_json::Number::from_f64)
+                    .map(serde_json::Value::Number)
+                    .ok_or_else(|| invalid(format!("'{}' is not a finite number", text)))
+            }
+            Self::Boolean => text.trim().parse::<bool>().map(serde_json::Value::Bool).map_err(|e| invalid(e.to_string())),
+            Self::Json => serde_json::from_str(text).map_err(|e| invalid(e.to_string())),
+        }
+    }
+}
+
+impl std::fmt::Display for ValueType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::String => "string",
+            Self::Number => "number",
+            Self::Boolean => "boolean",
+            Self::Json => "json",
+        })
+    }
+}
+
+impl std::str::FromStr for ValueType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "string" | "str" => Ok(Self::String),
+            "number" | "int" | "float" => Ok(Self::Number),
+            "boolean" | "bool" => Ok(Self::Boolean),
+            "json" | "object" | "array" => Ok(Self::Json),
+            other => Err(format!("unknown type '{}' (expected string, number, boolean or json)", other)),
+        }
+    }
+}
+
+/// A stored value and when it goes away
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScratchEntry {
+    pub value: serde_json::Value,
+    #[serde(rename = "type")]
+    pub value_type: ValueType,
+    pub updated_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// What [`Scratchpad::list`] reports for a key, without its value
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScratchKey {
+    pub key: String,
+    #[serde(rename = "type")]
+    pub value_type: ValueType,
+    pub bytes: usize,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Scratchpad limits, per session
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct ScratchpadConfig {
+    /// Seconds an entry lives when the caller gives no TTL
+    pub default_ttl_secs: u64,
+    /// Longest TTL a caller may ask for
+    pub max_ttl_secs: u64,
+    pub max_entries: usize,
+    /// Largest value, as serialized JSON
+    pub max_value_bytes: usize,
+}
+
+impl Default for ScratchpadConfig {
+    fn default() -> Self {
+        Self {
+            default_ttl_secs: 3600,
+            max_ttl_secs: 86400,
+            max_entries: 100,
+            max_value_bytes: 64 * 1024,
+        }
+    }
+}
+
+impl ScratchpadConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.default_ttl_secs == 0 || self.max_ttl_secs == 0 {
+            return Err("tools.scratchpad TTLs must be at least 1 second".to_string());
+        }
+        if self.default_ttl_secs > self.max_ttl_secs {
+            return Err("tools.scratchpad.default_ttl_secs can't exceed max_ttl_secs".to_string());
+        }
+        if self.max_entries == 0 || self.max_value_bytes == 0 {
+            return Err("tools.scratchpad.max_entries and max_value_bytes must be at least 1".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Keys a session holds, stored next to its entries
+type Index = BTreeMap<String, ScratchKey>;
+
+/// Typed key-value store with TTLs, one namespace per session
+pub struct Scratchpad {
+    cache: Arc<CacheManager>,
+    config: ScratchpadConfig,
+    /// Serializes index updates within this process
+    index_lock: tokio::sync::Mutex<()>,
+}
+
+impl Scratchpad {
+    pub fn new(cache: Arc<CacheManager>, config: ScratchpadConfig) -> Self {
+        Self {
+            cache,
+            config,
+            index_lock: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    /// Store `value` under `key` for `ttl` (the default when `None`, capped
+    /// at the maximum)
+    pub async fn set<T: Serialize>(
+        &self,
+        session: &str,
+        key: &str,
+        value: &T,
+        ttl: Option<Duration>,
+    ) -> Result<ScratchEntry, ScratchpadError> {
+        let value = serde_json::to_value(value).map_err(CacheError::from)?;
+        let value_type = ValueType::of(&value);
+        self.set_value(session, key, value, value_type, ttl).await
+    }
+
+    /// Store a JSON value as `value_type`; `json` may hold any value, the
+    /// other types must match the value
+    pub async fn set_value(
+        &self,
+        session: &str,
+        key: &str,
+        value: serde_json::Value,
+        value_type: ValueType,
+        ttl: Option<Duration>,
+    ) -> Result<ScratchEntry, ScratchpadError> {
+        check_key(key)?;
+        let found = ValueType::of(&value);
+        if value_type != ValueType::Json && found != value_type {
+            return Err(ScratchpadError::TypeMismatch { key: key.to_string(), expected: value_type, found });
+        }
+        let bytes = serde_json::to_vec(&value).map_err(CacheError::from)?.len();
+        if bytes > self.config.max_value_bytes {
+            return Err(ScratchpadError::TooLarge(bytes, self.config.max_value_bytes));
+        }
+
+        let ttl = ttl
+            .unwrap_or(Duration::from_secs(self.config.default_ttl_secs))
+            .min(Duration::from_secs(self.config.max_ttl_secs))
+            .max(Duration::from_secs(1));
+        let now = Utc::now();
+        let entry = ScratchEntry {
+            value,
+            value_type,
+            updated_at: now,
+            expires_at: now + chrono::Duration::from_std(ttl).unwrap_or_else(|_| chrono::Duration::zero()),
+        };
+
+        let _guard = self.index_lock.lock().await;
+        let mut index = self.index(session).await?;
+        if !index.contains_key(key) && index.len() >= self.config.max_entries {
+            return Err(ScratchpadError::Full(self.config.max_entries));
+        }
+        self.cache.set_value(&entry_key(session, key), &entry, ttl).await?;
+        index.insert(
+            key.to_string(),
+            ScratchKey { key: key.to_string(), value_type, bytes, expires_at: entry.expires_at },
+        );
+        self.save_index(session, &index).await?;
+        Ok(entry)
+    }
+
+    /// Entry under `key`, if it exists and hasn't expired
+    pub async fn entry(&self, session: &str, key: &str) -> Result<Option<ScratchEntry>, ScratchpadError> {
+        check_key(key)?;
+        let entry: Option<ScratchEntry> = self.cache.get_value(&entry_key(session, key)).await?;
+        // The memory cache applies its default TTL when repopulating Redis
+        Ok(entry.filter(|entry| entry.expires_at > Utc::now()))
+    }
+
+    /// Value under `key` as `T`
+    pub async fn get<T: DeserializeOwned>(&self, session: &str, key: &str) -> Result<Option<T>, ScratchpadError> {
+        match self.entry(session, key).await? {
+            Some(entry) => Ok(Some(serde_json::from_value(entry.value).map_err(CacheError::from)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Value under `key`, failing if it was stored as another type
+    pub async fn get_typed(
+        &self,
+        session: &str,
+        key: &str,
+        expected: ValueType,
+    ) -> Result<Option<ScratchEntry>, ScratchpadError> {
+        match self.entry(session, key).await? {
+            Some(entry) if entry.value_type != expected => Err(ScratchpadError::TypeMismatch {
+                key: key.to_string(),
+                expected,
+                found: entry.value_type,
+            }),
+            entry => Ok(entry),
+        }
+    }
+
+    /// Remove `key`; returns whether it was there
+    pub async fn delete(&self, session: &str, key: &str) -> Result<bool, ScratchpadError> {
+        check_key(key)?;
+        let _guard = self.index_lock.lock().await;
+        let mut index = self.index(session).await?;
+        let listed = index.remove(key).is_some();
+        let deleted = self.cache.delete_value(&entry_key(session, key)).await?;
+        self.save_index(session, &index).await?;
+        Ok(listed || deleted)
+    }
+
+    /// Keys the session holds, without their values
+    pub async fn list(&self, session: &str) -> Result<Vec<ScratchKey>, ScratchpadError> {
+        Ok(self.index(session).await?.into_values().collect())
+    }
+
+    /// Remove every entry the session holds; returns how many there were
+    pub async fn clear(&self, session: &str) -> Result<usize, ScratchpadError> {
+        let _guard = self.index_lock.lock().await;
+        let index = self.index(session).await?;
+        for key in index.keys() {
+            self.cache.delete_value(&entry_key(session, key)).await?;
+        }
+        self.cache.delete_value(&index_key(session)).await?;
+        Ok(index.len())
+    }
+
+    /// The session's index, without keys that have expired
+    async fn index(&self, session: &str) -> Result<Index, ScratchpadError> {
+        let now = Utc::now();
+        let mut index: Index = self.cache.get_value(&index_key(session)).await?.unwrap_or_default();
+        index.retain(|_, key| key.expires_at > now);
+        Ok(index)
+    }
+
+    /// Store the index for as long as its longest-lived entry
+    async fn save_index(&self, session: &str, index: &Index) -> Result<(), ScratchpadError> {
+        let Some(last) = index.values().map(|key| key.expires_at).max() else {
+            self.cache.delete_value(&index_key(session)).await?;
+            return Ok(());
+        };
+        let ttl = (last - Utc::now()).to_std().unwrap_or(Duration::from_secs(1));
+        self.cache.set_value(&index_key(session), index, ttl).await?;
+        Ok(())
+    }
+}
+
+fn check_key(key: &str) -> Result<(), ScratchpadError> {
+    let valid = !key.is_empty()
+        && key.len() <= MAX_KEY_LEN
+        && key.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'));
+    if valid {
+        Ok(())
+    } else {
+        Err(ScratchpadError::InvalidKey(key.to_string()))
+    }
+}
+
+fn entry_key(session: &str, key: &str) -> String {
+    format!("{}:{}:entry:{}", KEY_PREFIX, session, key)
+}
+
+fn index_key(session: &str) -> String {
+    format!("{}:{}:index", KEY_PREFIX, session)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jamey_core::cache::CacheConfig;
+
+    async fn scratchpad(config: ScratchpadConfig) -> Scratchpad {
+        let cache = CacheManager::new(CacheConfig::default()).await.unwrap();
+        Scratchpad::new(Arc::new(cache), config)
+    }
+
+    #[tokio::test]
+    async fn test_typed_values_per_session() {
+        let pad = scratchpad(ScratchpadConfig::default()).await;
+        pad.set("s1", "files", &vec!["a.rs", "b.rs"], None).await.unwrap();
+        pad.set_value("s1", "count", ValueType::Number.parse("42").unwrap(), ValueType::Number, None)
+            .await
+            .unwrap();
+
+        assert_eq!(pad.get::<Vec<String>>("s1", "files").await.unwrap().unwrap(), vec!["a.rs", "b.rs"]);
+        assert_eq!(pad.get::<i64>("s1", "count").await.unwrap(), Some(42));
+        assert!(pad.get::<i64>("s2", "count").await.unwrap().is_none());
+        assert!(matches!(
+            pad.get_typed("s1", "count", ValueType::String).await,
+            Err(ScratchpadError::TypeMismatch { found: ValueType::Number, .. })
+        ));
+        assert!(pad
+            .set_value("s1", "flag", serde_json::json!("yes"), ValueType::Boolean, None)
+            .await
+            .is_err());
+
+        let keys: Vec<String> = pad.list("s1").await.unwrap().into_iter().map(|k| k.key).collect();
+        assert_eq!(keys, vec!["count", "files"]);
+        assert!(pad.delete("s1", "files").await.unwrap());
+        assert_eq!(pad.clear("s1").await.unwrap(), 1);
+        assert!(pad.list("s1").await.unwrap().is_empty());
+        assert!(pad.get::<i64>("s1", "count").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_limits_and_expiry() {
+        let pad = scratchpad(ScratchpadConfig {
+            max_entries: 1,
+            max_value_bytes: 16,
+            ..Default::default()
+        })
+        .await;
+        assert!(matches!(pad.set("s", "bad key", &1, None).await, Err(ScratchpadError::InvalidKey(_))));
+        assert!(matches!(pad.set("s", "big", &"x".repeat(32), None).await, Err(ScratchpadError::TooLarge(34, 16))));
+
+        pad.set("s", "short", &true, Some(Duration::from_secs(1))).await.unwrap();
+        assert!(matches!(pad.set("s", "other", &1, None).await, Err(ScratchpadError::Full(1))));
+        tokio::time::sleep(Duration::from_millis(1100)).await;
+        assert!(pad.get::<bool>("s", "short").await.unwrap().is_none());
+        // The expired key no longer counts against the limit
+        pad.set("s", "other", &1, None).await.unwrap();
+    }
+
+    #[test]
+    fn test_parse_values() {
+        assert_eq!(ValueType::Number.parse("2.5").unwrap(), serde_json::json!(2.5));
+        assert!(ValueType::Number.parse("NaN").is_err());
+        assert_eq!(ValueType::Boolean.parse(" true ").unwrap(), serde_json::json!(true));
+        assert_eq!(ValueType::Json.parse(r#"{"a":[1]}"#).unwrap(), serde_json::json!({"a": [1]}));
+        assert_eq!("bool".parse::<ValueType>(), Ok(ValueType::Boolean));
+    }
+}
@@ -0,0 +1,244 @@
+//! Code search
+//!
+//! [`CodeSearchTool`] finds lines matching a regex or literal under a
+//! directory, the way ripgrep does: `.gitignore` rules and hidden files are
+//! respected, binary and oversized files are skipped, and results are capped
+//! so one broad pattern can't flood a model's context.
+
+use ignore::overrides::OverrideBuilder;
+use ignore::WalkBuilder;
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+/// Matches returned when the request doesn't say
+pub const DEFAULT_MAX_MATCHES: usize = 100;
+
+/// Files larger than this are not searched
+const MAX_FILE_BYTES: u64 = 1024 * 1024;
+
+/// Matched and context lines are cut to this many characters, so minified
+/// files don't return one enormous line
+const MAX_LINE_CHARS: usize = 300;
+
+/// Bytes sniffed for a NUL to tell binary files apart
+const BINARY_SNIFF_BYTES: usize = 8192;
+
+#[derive(Debug, Error)]
+pub enum SearchError {
+    #[error("Invalid pattern: {0}")]
+    Pattern(#[from] regex::Error),
+    #[error("Invalid glob: {0}")]
+    Glob(#[from] ignore::Error),
+    #[error("Not a directory: {0}")]
+    NotADirectory(PathBuf),
+}
+
+/// What [`CodeSearchTool::search`] looks for
+#[derive(Debug, Clone, Default)]
+pub struct SearchRequest {
+    pub pattern: String,
+    /// Treat `pattern` as plain text rather than a regex
+    pub literal: bool,
+    pub case_insensitive: bool,
+    /// Only files matching these globs; a leading `!` excludes instead
+    pub globs: Vec<String>,
+    /// Search this directory, relative to the tool's root, instead of all of it
+    pub path: Option<PathBuf>,
+    /// Lines shown before and after each match
+    pub context_lines: usize,
+    /// [`DEFAULT_MAX_MATCHES`] when `None`
+    pub max_matches: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SearchMatch {
+    /// Relative to the tool's root
+    pub path: String,
+    /// Counting from 1
+    pub line_number: usize,
+    pub line: String,
+    pub before: Vec<String>,
+    pub after: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SearchResults {
+    pub matches: Vec<SearchMatch>,
+    pub files_searched: usize,
+    /// The search stopped at `max_matches`; more lines may match
+    pub truncated: bool,
+}
+
+/// Searches the files under one directory
+pub struct CodeSearchTool {
+    root: PathBuf,
+    ignore: Vec<String>,
+}
+
+impl CodeSearchTool {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            ignore: Vec::new(),
+        }
+    }
+
+    /// Also skip any file or directory with one of these names, on top of
+    /// what `.gitignore` excludes
+    pub fn with_ignore(mut self, names: Vec<String>) -> Self {
+        self.ignore = names;
+        self
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn search(&self, request: &SearchRequest) -> Result<SearchResults, SearchError> {
+        let matcher = matcher(request)?;
+        let start = match &request.path {
+            Some(path) => self.root.join(path),
+            None => self.root.clone(),
+        };
+        if !start.is_dir() {
+            return Err(SearchError::NotADirectory(start));
+        }
+
+        let mut overrides = OverrideBuilder::new(&self.root);
+        for glob in &request.globs {
+            overrides.add(glob)?;
+        }
+        let ignore = self.ignore.clone();
+        let walker = WalkBuilder::new(&start)
+            .overrides(overrides.build()?)
+            .sort_by_file_name(|a, b| a.cmp(b))
+            .filter_entry(move |entry| !ignore.iter().any(|name| entry.file_name() == name.as_str()))
+            .build();
+
+        let limit = request.max_matches.unwrap_or(DEFAULT_MAX_MATCHES);
+        let mut results = SearchResults {
+            matches: Vec::new(),
+            files_searched: 0,
+            truncated: false,
+        };
+        for entry in walker {
+            // Unreadable entries are skipped, as ripgrep does
+            let Ok(entry) = entry else { continue };
+            if !entry.file_type().is_some_and(|t| t.is_file()) {
+                continue;
+            }
+            let Some(content) = read_text(entry.path()) else { continue };
+            results.files_searched += 1;
+
+            let path = entry.path().strip_prefix(&self.root).unwrap_or(entry.path());
+            let path = path.to_string_lossy().replace('\\', "/");
+            let lines: Vec<&str> = content.lines().collect();
+            for (idx, line) in lines.iter().enumerate() {
+                if !matcher.is_match(line) {
+                    continue;
+                }
+                if results.matches.len() >= limit {
+                    results.truncated = true;
+                    return Ok(results);
+                }
+                let context = request.context_lines;
+                results.matches.push(SearchMatch {
+                    path: path.clone(),
+                    line_number: idx + 1,
+                    line: clip(line),
+                    before: lines[idx.saturating_sub(context)..idx].iter().map(|l| clip(l)).collect(),
+                    after: lines[idx + 1..(idx + 1 + context).min(lines.len())].iter().map(|l| clip(l)).collect(),
+                });
+            }
+        }
+        Ok(results)
+    }
+}
+
+fn matcher(request: &SearchRequest) -> Result<Regex, SearchError> {
+    let pattern = if request.literal {
+        regex::escape(&request.pattern)
+    } else {
+        request.pattern.clone()
+    };
+    Ok(RegexBuilder::new(&pattern)
+        .case_insensitive(request.case_insensitive)
+        .build()?)
+}
+
+/// The file as text, or `None` when it is too large, binary or unreadable
+fn read_text(path: &Path) -> Option<String> {
+    if std::fs::metadata(path).ok()?.len() > MAX_FILE_BYTES {
+        return None;
+    }
+    let mut bytes = Vec::new();
+    std::fs::File::open(path).ok()?.read_to_end(&mut bytes).ok()?;
+    if bytes.iter().take(BINARY_SNIFF_BYTES).any(|&b| b == 0) {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+fn clip(line: &str) -> String {
+    match line.char_indices().nth(MAX_LINE_CHARS) {
+        Some((end, _)) => format!("{}…", &line[..end]),
+        None => line.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_search_globs_context_and_caps() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::create_dir_all(root.join("target")).unwrap();
+        std::fs::write(root.join("src/main.rs"), "fn main() {\n    run();\n}\n\nfn run() {}\n").unwrap();
+        std::fs::write(root.join("src/notes.md"), "run the tests\n").unwrap();
+        std::fs::write(root.join("target/out.rs"), "fn run() {}\n").unwrap();
+        std::fs::write(root.join("blob.bin"), b"run\0run").unwrap();
+
+        let tool = CodeSearchTool::new(root).with_ignore(vec!["target".to_string()]);
+        let request = SearchRequest {
+            pattern: r"\brun\b".to_string(),
+            ..Default::default()
+        };
+        let results = tool.search(&request).unwrap();
+        let found: Vec<_> = results.matches.iter().map(|m| (m.path.as_str(), m.line_number)).collect();
+        assert_eq!(found, vec![("src/main.rs", 2), ("src/main.rs", 5), ("src/notes.md", 1)]);
+
+        let request = SearchRequest {
+            pattern: "RUN();".to_string(),
+            literal: true,
+            case_insensitive: true,
+            globs: vec!["*.rs".to_string()],
+            context_lines: 1,
+            ..Default::default()
+        };
+        let results = tool.search(&request).unwrap();
+        assert_eq!(results.matches.len(), 1);
+        assert_eq!(results.matches[0].before, vec!["fn main() {"]);
+        assert_eq!(results.matches[0].after, vec!["}"]);
+
+        let request = SearchRequest {
+            pattern: "run".to_string(),
+            max_matches: Some(2),
+            ..Default::default()
+        };
+        let results = tool.search(&request).unwrap();
+        assert_eq!(results.matches.len(), 2);
+        assert!(results.truncated);
+
+        let request = SearchRequest {
+            pattern: "(".to_string(),
+            ..Default::default()
+        };
+        assert!(matches!(tool.search(&request), Err(SearchError::Pattern(_))));
+    }
+}
@@ -1,10 +1,14 @@
+use crate::handles::{self, Socket};
+use crate::path_policy::{PathPolicy, PathPolicyError};
 use anyhow::Result;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use sysinfo::{PidExt, ProcessExt, System, SystemExt};
+use sha2::{Digest, Sha256};
+use sysinfo::{PidExt, Process, ProcessExt, System, SystemExt};
 use thiserror::Error;
 use tracing::error;
 use std::{
+    collections::{HashMap, HashSet},
     fs,
     path::{Path, PathBuf},
     time::SystemTime,
@@ -20,6 +24,14 @@ pub enum SystemToolError {
     FileOperation(String),
     #[error("Backup error: {0}")]
     Backup(String),
+    #[error("System config error: {0}")]
+    SystemConfig(String),
+    #[error("Access denied: {0}")]
+    AccessDenied(String),
+    #[error("Failed to inspect processes: {0}")]
+    Inspect(String),
+    #[error(transparent)]
+    Policy(#[from] PathPolicyError),
 }
 
 // Process Management
@@ -31,6 +43,36 @@ pub struct ProcessInfo {
     pub cpu_usage: f32,
     pub memory_usage: u64,
     pub start_time: DateTime<Utc>,
+    #[serde(default)]
+    pub parent_pid: Option<u32>,
+}
+
+/// A process and everything it started, recursively
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProcessNode {
+    #[serde(flatten)]
+    pub info: ProcessInfo,
+    pub children: Vec<ProcessNode>,
+}
+
+/// Sockets and files a process holds
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProcessResources {
+    pub pid: u32,
+    pub sockets: Vec<Socket>,
+    pub files: Vec<PathBuf>,
+    /// Why `files` is empty when they couldn't be listed, e.g. the process
+    /// belongs to another user
+    pub files_unavailable: Option<String>,
+}
+
+/// A socket on a port, the process holding it and the processes that
+/// started that one, nearest first
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PortOwner {
+    pub socket: Socket,
+    pub process: Option<ProcessInfo>,
+    pub ancestry: Vec<ProcessInfo>,
 }
 
 pub struct ProcessTool {
@@ -46,20 +88,7 @@ impl ProcessTool {
 
     pub fn list_processes(&mut self) -> Vec<ProcessInfo> {
         self.system.refresh_all();
-        self.system
-            .processes()
-            .values()
-            .map(|process| {
-                let name = process.name();
-                ProcessInfo {
-                    pid: process.pid().as_u32(),
-                    name: name.into(),
-                    cpu_usage: process.cpu_usage(),
-                    memory_usage: process.memory(),
-                    start_time: DateTime::from(SystemTime::now()), // Placeholder - actual start time if available
-                }
-            })
-            .collect()
+        self.system.processes().values().map(process_info).collect()
     }
 
     pub fn kill_process(&mut self, pid: u32) -> Result<(), SystemToolError> {
@@ -77,17 +106,127 @@ impl ProcessTool {
 
     pub fn get_process_info(&mut self, pid: u32) -> Result<ProcessInfo, SystemToolError> {
         self.system.refresh_all();
-        if let Some(process) = self.system.process(sysinfo::Pid::from(pid as usize)) {
-            Ok(ProcessInfo {
-                pid: process.pid().as_u32(),
-                name: process.name().to_string(),
-                cpu_usage: process.cpu_usage(),
-                memory_usage: process.memory(),
-                start_time: DateTime::from(SystemTime::now()), // Placeholder
+        self.lookup(pid).ok_or(SystemToolError::ProcessNotFound(pid))
+    }
+
+    fn lookup(&self, pid: u32) -> Option<ProcessInfo> {
+        self.system.process(sysinfo::Pid::from(pid as usize)).map(process_info)
+    }
+
+    /// Processes arranged under their parents, from `root` or, when it is
+    /// `None`, from every process whose parent is gone
+    pub fn process_tree(&mut self, root: Option<u32>) -> Result<Vec<ProcessNode>, SystemToolError> {
+        let processes = self.list_processes();
+        let mut children: HashMap<u32, Vec<&ProcessInfo>> = HashMap::new();
+        let pids: HashSet<u32> = processes.iter().map(|p| p.pid).collect();
+        for process in &processes {
+            if let Some(parent) = process.parent_pid.filter(|parent| *parent != process.pid) {
+                children.entry(parent).or_default().push(process);
+            }
+        }
+        let roots: Vec<&ProcessInfo> = match root {
+            Some(pid) => vec![processes
+                .iter()
+                .find(|p| p.pid == pid)
+                .ok_or(SystemToolError::ProcessNotFound(pid))?],
+            None => processes
+                .iter()
+                .filter(|p| p.parent_pid.is_none_or(|parent| !pids.contains(&parent) || parent == p.pid))
+                .collect(),
+        };
+        let mut seen = HashSet::new();
+        let mut tree: Vec<ProcessNode> = roots
+            .into_iter()
+            .map(|process| build_node(process, &children, &mut seen))
+            .collect();
+        tree.sort_by_key(|node| node.info.pid);
+        Ok(tree)
+    }
+
+    /// The processes that started `pid`: its parent first, up to the root
+    pub fn ancestry(&mut self, pid: u32) -> Result<Vec<ProcessInfo>, SystemToolError> {
+        self.system.refresh_all();
+        let process = self.lookup(pid).ok_or(SystemToolError::ProcessNotFound(pid))?;
+        Ok(self.ancestors_of(&process))
+    }
+
+    fn ancestors_of(&self, process: &ProcessInfo) -> Vec<ProcessInfo> {
+        let mut ancestors = Vec::new();
+        // A reused pid can make the chain loop
+        let mut seen = HashSet::from([process.pid]);
+        let mut next = process.parent_pid;
+        while let Some(parent) = next.and_then(|pid| seen.insert(pid).then(|| self.lookup(pid)).flatten()) {
+            next = parent.parent_pid;
+            ancestors.push(parent);
+        }
+        ancestors
+    }
+
+    /// Sockets and open files of `pid`, as far as this user may see them
+    pub fn open_resources(&mut self, pid: u32) -> Result<ProcessResources, SystemToolError> {
+        self.get_process_info(pid)?;
+        let sockets = handles::sockets()
+            .map_err(|e| SystemToolError::Inspect(e.to_string()))?
+            .into_iter()
+            .filter(|socket| socket.pid == Some(pid))
+            .collect();
+        let (files, files_unavailable) = match handles::open_files(pid) {
+            Ok(files) => (files, None),
+            Err(e) => (Vec::new(), Some(e.to_string())),
+        };
+        Ok(ProcessResources {
+            pid,
+            sockets,
+            files,
+            files_unavailable,
+        })
+    }
+
+    /// Sockets bound to local `port`, listeners first, with who holds each
+    /// and who started that process
+    pub fn find_by_port(&mut self, port: u16) -> Result<Vec<PortOwner>, SystemToolError> {
+        let mut sockets: Vec<Socket> = handles::sockets()
+            .map_err(|e| SystemToolError::Inspect(e.to_string()))?
+            .into_iter()
+            .filter(|socket| socket.local.port() == port)
+            .collect();
+        sockets.sort_by_key(|socket| !socket.is_listening());
+        self.system.refresh_all();
+        Ok(sockets
+            .into_iter()
+            .map(|socket| {
+                let process = socket.pid.and_then(|pid| self.lookup(pid));
+                let ancestry = process.as_ref().map(|p| self.ancestors_of(p)).unwrap_or_default();
+                PortOwner { socket, process, ancestry }
             })
-        } else {
-            Err(SystemToolError::ProcessNotFound(pid))
+            .collect())
+    }
+}
+
+fn process_info(process: &Process) -> ProcessInfo {
+    ProcessInfo {
+        pid: process.pid().as_u32(),
+        name: process.name().to_string(),
+        cpu_usage: process.cpu_usage(),
+        memory_usage: process.memory(),
+        start_time: DateTime::from(SystemTime::now()), // Placeholder - actual start time if available
+        parent_pid: process.parent().map(|pid| pid.as_u32()),
+    }
+}
+
+fn build_node(process: &ProcessInfo, children: &HashMap<u32, Vec<&ProcessInfo>>, seen: &mut HashSet<u32>) -> ProcessNode {
+    seen.insert(process.pid);
+    let mut nodes = Vec::new();
+    for child in children.get(&process.pid).into_iter().flatten() {
+        if seen.contains(&child.pid) {
+            continue;
         }
+        nodes.push(build_node(child, children, seen));
+    }
+    nodes.sort_by_key(|node| node.info.pid);
+    ProcessNode {
+        info: process.clone(),
+        children: nodes,
     }
 }
 
@@ -145,6 +284,334 @@ impl RegistryTool {
             Ok(String::from_utf16_lossy(&buffer[..string_len]).trim_end_matches('\0').to_string())
         }
     }
+
+    pub fn write_value(&self, key: &str, value_name: &str, value: &str) -> Result<(), SystemToolError> {
+        use windows::Win32::System::Registry::*;
+        use windows::Win32::Foundation::WIN32_ERROR;
+
+        unsafe {
+            let mut key_handle = HKEY::default();
+            let result = RegOpenKeyExW(
+                HKEY_LOCAL_MACHINE,
+                &windows::core::HSTRING::from(key),
+                0,
+                KEY_SET_VALUE,
+                &mut key_handle,
+            );
+
+            if result != WIN32_ERROR(0) {
+                return Err(SystemToolError::Registry(format!("Failed to open registry key: {:#x}", result.0)));
+            }
+
+            let data: Vec<u8> = value
+                .encode_utf16()
+                .chain(std::iter::once(0))
+                .flat_map(|c| c.to_le_bytes())
+                .collect();
+            let set_result = RegSetValueExW(
+                key_handle,
+                &windows::core::HSTRING::from(value_name),
+                0,
+                REG_SZ,
+                Some(&data),
+            );
+
+            RegCloseKey(key_handle);
+
+            if set_result != WIN32_ERROR(0) {
+                return Err(SystemToolError::Registry(format!("Failed to set registry value: {:#x}", set_result.0)));
+            }
+
+            Ok(())
+        }
+    }
+}
+
+// System Configuration Tool
+//
+// Cross-platform counterpart to RegistryTool: Windows registry, macOS
+// `defaults`, Linux sysctl and dconf/gsettings behind one read/write API.
+// Every key must match an allowlist entry before it is touched.
+
+/// Backing store for a system configuration key
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConfigBackend {
+    /// Windows registry (HKEY_LOCAL_MACHINE)
+    WindowsRegistry,
+    /// macOS `defaults` domains
+    MacDefaults,
+    /// Linux kernel parameters (`/proc/sys`, `sysctl -w`)
+    Sysctl,
+    /// GNOME settings schemas via `gsettings`
+    Gsettings,
+    /// Raw dconf paths via `dconf`
+    Dconf,
+}
+
+impl ConfigBackend {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ConfigBackend::WindowsRegistry => "registry",
+            ConfigBackend::MacDefaults => "defaults",
+            ConfigBackend::Sysctl => "sysctl",
+            ConfigBackend::Gsettings => "gsettings",
+            ConfigBackend::Dconf => "dconf",
+        }
+    }
+
+    /// Whether this backend exists on the current platform
+    pub fn is_supported(&self) -> bool {
+        match self {
+            ConfigBackend::WindowsRegistry => cfg!(windows),
+            ConfigBackend::MacDefaults => cfg!(target_os = "macos"),
+            ConfigBackend::Sysctl | ConfigBackend::Gsettings | ConfigBackend::Dconf => {
+                cfg!(target_os = "linux")
+            }
+        }
+    }
+
+    /// Backends available on the current platform
+    pub fn native() -> Vec<ConfigBackend> {
+        [
+            ConfigBackend::WindowsRegistry,
+            ConfigBackend::MacDefaults,
+            ConfigBackend::Sysctl,
+            ConfigBackend::Gsettings,
+            ConfigBackend::Dconf,
+        ]
+        .into_iter()
+        .filter(|b| b.is_supported())
+        .collect()
+    }
+}
+
+impl std::str::FromStr for ConfigBackend {
+    type Err = SystemToolError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "registry" | "windows_registry" => Ok(ConfigBackend::WindowsRegistry),
+            "defaults" | "mac_defaults" => Ok(ConfigBackend::MacDefaults),
+            "sysctl" => Ok(ConfigBackend::Sysctl),
+            "gsettings" => Ok(ConfigBackend::Gsettings),
+            "dconf" => Ok(ConfigBackend::Dconf),
+            other => Err(SystemToolError::SystemConfig(format!("Unknown backend: {other}"))),
+        }
+    }
+}
+
+/// A fully-qualified configuration key
+///
+/// `domain` is the registry key path, `defaults` domain, gsettings schema or
+/// dconf directory; it is empty for sysctl, where `name` is the dotted
+/// parameter (e.g. `vm.swappiness`).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SystemConfigKey {
+    pub backend: ConfigBackend,
+    pub domain: String,
+    pub name: String,
+}
+
+impl SystemConfigKey {
+    pub fn new(backend: ConfigBackend, domain: impl Into<String>, name: impl Into<String>) -> Self {
+        Self {
+            backend,
+            domain: domain.into(),
+            name: name.into(),
+        }
+    }
+
+    /// Canonical identifier used for allowlist matching, e.g.
+    /// `gsettings:org.gnome.desktop.interface/gtk-theme` or `sysctl:vm.swappiness`
+    pub fn id(&self) -> String {
+        if self.domain.is_empty() {
+            format!("{}:{}", self.backend.as_str(), self.name)
+        } else {
+            format!("{}:{}/{}", self.backend.as_str(), self.domain, self.name)
+        }
+    }
+
+    fn validate(&self) -> Result<(), SystemToolError> {
+        let valid = |s: &str| {
+            s.chars().all(|c| c.is_ascii_alphanumeric() || "._-/\\ ".contains(c))
+        };
+        if self.name.is_empty() || !valid(&self.name) || !valid(&self.domain) {
+            return Err(SystemToolError::SystemConfig(format!("Invalid key: {}", self.id())));
+        }
+        if self.name.contains("..") || self.domain.contains("..") {
+            return Err(SystemToolError::SystemConfig(format!("Invalid key: {}", self.id())));
+        }
+        if self.backend != ConfigBackend::Sysctl && self.domain.is_empty() {
+            return Err(SystemToolError::SystemConfig(format!(
+                "{} keys require a domain",
+                self.backend.as_str()
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Access granted to keys matching an allowlist pattern
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum KeyAccess {
+    Read,
+    ReadWrite,
+}
+
+#[derive(Debug, Clone)]
+struct AllowlistEntry {
+    pattern: glob::Pattern,
+    access: KeyAccess,
+}
+
+pub struct SystemConfigTool {
+    allowlist: Vec<AllowlistEntry>,
+}
+
+impl Default for SystemConfigTool {
+    fn default() -> Self {
+        let mut tool = Self::new();
+        // Harmless, read-only keys so diagnostics work out of the box
+        for pattern in [
+            r"registry:SOFTWARE\Microsoft\Windows NT\CurrentVersion/*",
+            "defaults:NSGlobalDomain/AppleInterfaceStyle",
+            "defaults:NSGlobalDomain/AppleLocale",
+            "sysctl:kernel.hostname",
+            "sysctl:kernel.osrelease",
+            "sysctl:vm.swappiness",
+            "gsettings:org.gnome.desktop.interface/*",
+        ] {
+            tool = tool
+                .allow(pattern, KeyAccess::Read)
+                .expect("default allowlist patterns are valid");
+        }
+        tool
+    }
+}
+
+impl SystemConfigTool {
+    /// Create a tool with an empty allowlist (every key denied)
+    pub fn new() -> Self {
+        Self { allowlist: Vec::new() }
+    }
+
+    /// Allow keys whose id matches `pattern` (glob syntax, see [`SystemConfigKey::id`])
+    pub fn allow(mut self, pattern: &str, access: KeyAccess) -> Result<Self, SystemToolError> {
+        let pattern = glob::Pattern::new(pattern)
+            .map_err(|e| SystemToolError::SystemConfig(format!("Invalid allowlist pattern: {e}")))?;
+        self.allowlist.push(AllowlistEntry { pattern, access });
+        Ok(self)
+    }
+
+    /// Most permissive access granted to `key`, if any
+    pub fn access_for(&self, key: &SystemConfigKey) -> Option<KeyAccess> {
+        let id = key.id();
+        self.allowlist
+            .iter()
+            .filter(|entry| entry.pattern.matches(&id))
+            .map(|entry| entry.access)
+            .max_by_key(|access| *access == KeyAccess::ReadWrite)
+    }
+
+    fn check_access(&self, key: &SystemConfigKey, write: bool) -> Result<(), SystemToolError> {
+        key.validate()?;
+        if !key.backend.is_supported() {
+            return Err(SystemToolError::SystemConfig(format!(
+                "Backend {} is not available on this platform",
+                key.backend.as_str()
+            )));
+        }
+        match self.access_for(key) {
+            Some(KeyAccess::ReadWrite) => Ok(()),
+            Some(KeyAccess::Read) if !write => Ok(()),
+            Some(KeyAccess::Read) => Err(SystemToolError::AccessDenied(format!("{} is read-only", key.id()))),
+            None => Err(SystemToolError::AccessDenied(format!("{} is not allowlisted", key.id()))),
+        }
+    }
+
+    pub fn read(&self, key: &SystemConfigKey) -> Result<String, SystemToolError> {
+        self.check_access(key, false)?;
+
+        match key.backend {
+            ConfigBackend::WindowsRegistry => {
+                #[cfg(windows)]
+                {
+                    RegistryTool::new().read_value(&key.domain, &key.name)
+                }
+                #[cfg(not(windows))]
+                {
+                    unreachable!("registry backend is only supported on Windows")
+                }
+            }
+            ConfigBackend::MacDefaults => run_config_command("defaults", &["read", &key.domain, &key.name]),
+            ConfigBackend::Sysctl => {
+                let path = Path::new("/proc/sys").join(key.name.replace('.', "/"));
+                fs::read_to_string(&path)
+                    .map(|v| v.trim().to_string())
+                    .map_err(|e| SystemToolError::SystemConfig(format!("Failed to read {}: {e}", key.id())))
+            }
+            ConfigBackend::Gsettings => run_config_command("gsettings", &["get", &key.domain, &key.name]),
+            ConfigBackend::Dconf => {
+                let path = dconf_path(key);
+                run_config_command("dconf", &["read", &path])
+            }
+        }
+    }
+
+    pub fn write(&self, key: &SystemConfigKey, value: &str) -> Result<(), SystemToolError> {
+        self.check_access(key, true)?;
+        if value.contains('\n') || value.contains('\0') {
+            return Err(SystemToolError::SystemConfig("Values must be a single line".to_string()));
+        }
+
+        match key.backend {
+            ConfigBackend::WindowsRegistry => {
+                #[cfg(windows)]
+                {
+                    RegistryTool::new().write_value(&key.domain, &key.name, value)
+                }
+                #[cfg(not(windows))]
+                {
+                    unreachable!("registry backend is only supported on Windows")
+                }
+            }
+            ConfigBackend::MacDefaults => {
+                run_config_command("defaults", &["write", &key.domain, &key.name, value]).map(|_| ())
+            }
+            ConfigBackend::Sysctl => {
+                let assignment = format!("{}={}", key.name, value);
+                run_config_command("sysctl", &["-w", &assignment]).map(|_| ())
+            }
+            ConfigBackend::Gsettings => {
+                run_config_command("gsettings", &["set", &key.domain, &key.name, value]).map(|_| ())
+            }
+            ConfigBackend::Dconf => {
+                let path = dconf_path(key);
+                run_config_command("dconf", &["write", &path, value]).map(|_| ())
+            }
+        }
+    }
+}
+
+fn dconf_path(key: &SystemConfigKey) -> String {
+    format!("/{}/{}", key.domain.trim_matches('/'), key.name)
+}
+
+/// Run a platform config utility without a shell and return trimmed stdout
+fn run_config_command(program: &str, args: &[&str]) -> Result<String, SystemToolError> {
+    let output = std::process::Command::new(program)
+        .args(args)
+        .output()
+        .map_err(|e| SystemToolError::SystemConfig(format!("Failed to run {program}: {e}")))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        error!("{} {:?} failed: {}", program, args, stderr.trim());
+        return Err(SystemToolError::SystemConfig(format!("{program} failed: {}", stderr.trim())));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
 }
 
 // Self Modification Tool
@@ -158,17 +625,25 @@ pub struct FileBackup {
 
 pub struct SelfModifyTool {
     backup_dir: PathBuf,
+    policy: PathPolicy,
 }
 
 impl SelfModifyTool {
     pub fn new<P: AsRef<Path>>(backup_dir: P) -> Result<Self> {
         let backup_dir = backup_dir.as_ref().to_path_buf();
         fs::create_dir_all(&backup_dir)?;
-        Ok(Self { backup_dir })
+        Ok(Self { backup_dir, policy: PathPolicy::default() })
+    }
+
+    /// Only read, modify and restore files `policy` allows
+    pub fn with_path_policy(mut self, policy: PathPolicy) -> Self {
+        self.policy = policy;
+        self
     }
 
     pub fn create_backup<P: AsRef<Path>>(&self, file_path: P) -> Result<FileBackup, SystemToolError> {
         let file_path = file_path.as_ref();
+        self.policy.check_read(file_path)?;
         let timestamp = Utc::now();
         let file_name = file_path
             .file_name()
@@ -197,16 +672,20 @@ impl SelfModifyTool {
         file_path: P,
         new_content: &str,
     ) -> Result<FileBackup, SystemToolError> {
+        self.policy.check_write(file_path.as_ref(), new_content.len() as u64)?;
         let backup = self.create_backup(&file_path)?;
 
         fs::write(&file_path, new_content).map_err(|e| {
             SystemToolError::FileOperation(format!("Failed to write file: {e}"))
         })?;
+        audit_modification(file_path.as_ref(), new_content);
 
         Ok(backup)
     }
 
     pub fn restore_backup(&self, backup: &FileBackup) -> Result<(), SystemToolError> {
+        let size = fs::metadata(&backup.backup_path).map_or(0, |m| m.len());
+        self.policy.check_write(&backup.original_path, size)?;
         fs::copy(&backup.backup_path, &backup.original_path).map_err(|e| {
             SystemToolError::Backup(format!("Failed to restore backup: {e}"))
         })?;
@@ -221,11 +700,24 @@ impl SelfModifyTool {
             .collect::<Vec<_>>()
             .into_iter()
             .filter(|p| p.extension().map_or(false, |ext| ext == "rs"))
+            .filter(|p| self.policy.check_read(p).is_ok())
             .map(Ok)
             .collect()
     }
 }
 
+/// Record a source change in the audit log, with a hash of what was written
+/// so the file can later be checked against it
+pub(crate) fn audit_modification(path: &Path, content: &str) {
+    tracing::info!(
+        target: "audit",
+        path = %path.display(),
+        sha256 = %format!("{:x}", Sha256::digest(content.as_bytes())),
+        bytes = content.len(),
+        "Source file modified"
+    );
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -240,6 +732,41 @@ mod tests {
         assert!(!processes.is_empty());
     }
 
+    #[test]
+    fn test_process_tree_and_ancestry() {
+        let mut tool = ProcessTool::new();
+        let pid = std::process::id();
+        let info = tool.get_process_info(pid).unwrap();
+        let parent = info.parent_pid.unwrap();
+
+        let ancestry = tool.ancestry(pid).unwrap();
+        assert_eq!(ancestry.first().map(|p| p.pid), Some(parent));
+        assert!(ancestry.iter().all(|p| p.pid != pid));
+
+        let tree = tool.process_tree(Some(parent)).unwrap();
+        assert_eq!(tree.len(), 1);
+        assert!(tree[0].children.iter().any(|child| child.info.pid == pid));
+        assert!(matches!(tool.process_tree(Some(u32::MAX)), Err(SystemToolError::ProcessNotFound(_))));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_find_by_port() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let mut tool = ProcessTool::new();
+
+        let owners = tool.find_by_port(port).unwrap();
+        let owner = owners.first().unwrap();
+        assert!(owner.socket.is_listening());
+        assert_eq!(owner.process.as_ref().map(|p| p.pid), Some(std::process::id()));
+        assert!(!owner.ancestry.is_empty());
+
+        let resources = tool.open_resources(std::process::id()).unwrap();
+        assert!(resources.sockets.iter().any(|s| s.local.port() == port));
+        assert!(resources.files_unavailable.is_none());
+    }
+
     #[test]
     fn test_self_modify_tool() {
         let temp_dir = TempDir::new().unwrap();
@@ -267,6 +794,49 @@ mod tests {
         assert_eq!(restored_content, "original content");
     }
 
+    #[test]
+    fn test_system_config_allowlist() {
+        let tool = SystemConfigTool::new()
+            .allow("gsettings:org.gnome.desktop.interface/*", KeyAccess::Read)
+            .unwrap()
+            .allow("gsettings:org.gnome.desktop.interface/gtk-theme", KeyAccess::ReadWrite)
+            .unwrap();
+
+        let theme = SystemConfigKey::new(ConfigBackend::Gsettings, "org.gnome.desktop.interface", "gtk-theme");
+        let font = SystemConfigKey::new(ConfigBackend::Gsettings, "org.gnome.desktop.interface", "font-name");
+        let other = SystemConfigKey::new(ConfigBackend::Gsettings, "org.gnome.shell", "favorite-apps");
+
+        assert_eq!(tool.access_for(&theme), Some(KeyAccess::ReadWrite));
+        assert_eq!(tool.access_for(&font), Some(KeyAccess::Read));
+        assert_eq!(tool.access_for(&other), None);
+        assert!(matches!(tool.write(&other, "x"), Err(SystemToolError::AccessDenied(_)) | Err(SystemToolError::SystemConfig(_))));
+    }
+
+    #[test]
+    fn test_system_config_key_validation() {
+        let sysctl = SystemConfigKey::new(ConfigBackend::Sysctl, "", "vm.swappiness");
+        assert_eq!(sysctl.id(), "sysctl:vm.swappiness");
+        assert!(sysctl.validate().is_ok());
+
+        let traversal = SystemConfigKey::new(ConfigBackend::Sysctl, "", "../../etc/shadow");
+        assert!(traversal.validate().is_err());
+
+        let no_domain = SystemConfigKey::new(ConfigBackend::MacDefaults, "", "AppleLocale");
+        assert!(no_domain.validate().is_err());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_system_config_sysctl_read() {
+        let tool = SystemConfigTool::default();
+        let key = SystemConfigKey::new(ConfigBackend::Sysctl, "", "kernel.osrelease");
+        let value = tool.read(&key).unwrap();
+        assert!(!value.is_empty());
+
+        let denied = SystemConfigKey::new(ConfigBackend::Sysctl, "", "kernel.osrelease");
+        assert!(matches!(tool.write(&denied, "1"), Err(SystemToolError::AccessDenied(_))));
+    }
+
     #[cfg(windows)]
     #[test]
     fn test_registry_tool() {
@@ -0,0 +1,470 @@
+//! Test runner
+//!
+//! [`TestRunnerTool`] runs a project's test suite — `cargo test`, `pytest`
+//! or `npm test` — under a timeout and reads the output into a
+//! [`TestReport`]: counts, the name of every failing test, and the part of
+//! the output that explains each failure. A model gets what it needs to fix
+//! the failure without wading through the whole log.
+
+use crate::sandbox::{ExecutionSandbox, SandboxError};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+use thiserror::Error;
+use tokio::process::Command;
+
+/// How long a run may take when the request doesn't say
+pub const DEFAULT_TEST_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// Lines kept from the output explaining one failure
+const MAX_SNIPPET_LINES: usize = 40;
+
+/// Failures reported in full; the rest are only counted
+const MAX_FAILURES: usize = 20;
+
+/// Lines kept from the end of the output, for failures nothing else explains
+const OUTPUT_TAIL_LINES: usize = 40;
+
+#[derive(Debug, Error)]
+pub enum TestRunnerError {
+    #[error("No test suite found in {0}")]
+    NoSuite(PathBuf),
+    #[error("Failed to run {command}: {source}")]
+    Spawn {
+        command: String,
+        source: std::io::Error,
+    },
+    #[error(transparent)]
+    Sandbox(#[from] SandboxError),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TestFramework {
+    Cargo,
+    Pytest,
+    Npm,
+}
+
+impl TestFramework {
+    /// The suite a project directory has, judged by its manifest files
+    pub fn detect(dir: &Path) -> Option<Self> {
+        if dir.join("Cargo.toml").is_file() {
+            return Some(Self::Cargo);
+        }
+        let python = ["pytest.ini", "pyproject.toml", "setup.cfg", "conftest.py", "tox.ini"];
+        if python.iter().any(|file| dir.join(file).is_file()) {
+            return Some(Self::Pytest);
+        }
+        if dir.join("package.json").is_file() {
+            return Some(Self::Npm);
+        }
+        None
+    }
+}
+
+impl std::str::FromStr for TestFramework {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "cargo" | "rust" => Ok(Self::Cargo),
+            "pytest" | "python" => Ok(Self::Pytest),
+            "npm" | "node" | "js" => Ok(Self::Npm),
+            other => Err(format!("Unknown test framework: {} (expected cargo, pytest or npm)", other)),
+        }
+    }
+}
+
+/// What [`TestRunnerTool::run`] runs
+#[derive(Debug, Clone, Default)]
+pub struct TestRequest {
+    /// Detected from the project's files when `None`
+    pub framework: Option<TestFramework>,
+    /// Only tests whose names match: a name filter for cargo, `-k` for pytest
+    pub filter: Option<String>,
+    /// A cargo package, or a pytest file or directory; the whole suite when `None`
+    pub package: Option<String>,
+    /// [`DEFAULT_TEST_TIMEOUT`] when `None`
+    pub timeout: Option<Duration>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TestFailure {
+    pub name: String,
+    /// The assertion, panic or traceback, cut to a few dozen lines
+    pub snippet: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TestReport {
+    pub framework: TestFramework,
+    pub command: String,
+    /// The suite ran to completion and every test passed
+    pub success: bool,
+    pub timed_out: bool,
+    pub duration_ms: u64,
+    pub passed: usize,
+    pub failed: usize,
+    pub ignored: usize,
+    pub failures: Vec<TestFailure>,
+    /// End of the output, when failures alone don't explain the result,
+    /// e.g. a build error before any test ran
+    pub output_tail: Option<String>,
+}
+
+impl TestReport {
+    /// One line for logs and audit trails
+    pub fn summary(&self) -> String {
+        if self.timed_out {
+            return format!("{} timed out after {}s", self.command, self.duration_ms / 1000);
+        }
+        let mut summary = format!("{} passed, {} failed, {} ignored", self.passed, self.failed, self.ignored);
+        if !self.failures.is_empty() {
+            let names: Vec<&str> = self.failures.iter().map(|f| f.name.as_str()).collect();
+            summary.push_str(&format!(" (failing: {})", names.join(", ")));
+        } else if !self.success {
+            summary.push_str(" (the suite did not finish; see output_tail)");
+        }
+        summary
+    }
+}
+
+/// Runs the test suite of one project directory
+pub struct TestRunnerTool {
+    root: PathBuf,
+    sandbox: ExecutionSandbox,
+}
+
+impl TestRunnerTool {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            sandbox: ExecutionSandbox::default(),
+        }
+    }
+
+    /// Run suites as the sandbox user and within its limits; its wall-clock
+    /// limit caps the request's timeout
+    pub fn with_sandbox(mut self, sandbox: ExecutionSandbox) -> Self {
+        self.sandbox = sandbox;
+        self
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub async fn run(&self, request: &TestRequest) -> Result<TestReport, TestRunnerError> {
+        let framework = match request.framework {
+            Some(framework) => framework,
+            None => TestFramework::detect(&self.root).ok_or_else(|| TestRunnerError::NoSuite(self.root.clone()))?,
+        };
+        let (program, args) = command(framework, request);
+        let command_line = std::iter::once(program).chain(args.iter().map(String::as_str)).collect::<Vec<_>>().join(" ");
+
+        let mut command = Command::new(program);
+        command
+            .args(&args)
+            .current_dir(&self.root)
+            .env("CARGO_TERM_COLOR", "never")
+            .env("NO_COLOR", "1")
+            // Keeps jest and friends out of watch mode
+            .env("CI", "true")
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+        self.sandbox.apply(&mut command);
+        let child = command.spawn().map_err(|source| TestRunnerError::Spawn {
+            command: command_line.clone(),
+            source,
+        })?;
+        let _confinement = self.sandbox.confine(child.id())?;
+
+        let started = Instant::now();
+        let timeout = self.sandbox.timeout(request.timeout.unwrap_or(DEFAULT_TEST_TIMEOUT));
+        let output = tokio::time::timeout(timeout, child.wait_with_output()).await;
+        let duration_ms = started.elapsed().as_millis() as u64;
+        let output = match output {
+            Ok(Ok(output)) => output,
+            Ok(Err(source)) => return Err(TestRunnerError::Spawn { command: command_line, source }),
+            // Dropping the wait killed the suite
+            Err(_) => {
+                return Ok(TestReport {
+                    framework,
+                    command: command_line,
+                    success: false,
+                    timed_out: true,
+                    duration_ms,
+                    passed: 0,
+                    failed: 0,
+                    ignored: 0,
+                    failures: Vec::new(),
+                    output_tail: None,
+                })
+            }
+        };
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let mut report = match framework {
+            TestFramework::Cargo => parse_cargo(&stdout),
+            TestFramework::Pytest => parse_pytest(&stdout),
+            TestFramework::Npm => parse_npm(&format!("{}\n{}", stdout, stderr)),
+        };
+        report.framework = framework;
+        report.command = command_line;
+        report.duration_ms = duration_ms;
+        report.success = output.status.success() && report.failed == 0;
+        if !report.success && report.failures.is_empty() {
+            report.output_tail = Some(tail(&format!("{}\n{}", stdout, stderr), OUTPUT_TAIL_LINES));
+        }
+        Ok(report)
+    }
+}
+
+fn command(framework: TestFramework, request: &TestRequest) -> (&'static str, Vec<String>) {
+    let mut args = Vec::new();
+    match framework {
+        TestFramework::Cargo => {
+            args.push("test".to_string());
+            match &request.package {
+                Some(package) => args.extend(["--package".to_string(), package.clone()]),
+                None => args.push("--workspace".to_string()),
+            }
+            // Report every failing test, not just the first crate's
+            args.push("--no-fail-fast".to_string());
+            args.extend(request.filter.clone());
+            ("cargo", args)
+        }
+        TestFramework::Pytest => {
+            args.extend(["-m", "pytest", "-rA", "--tb=short", "--color=no"].map(String::from));
+            args.extend(request.package.clone());
+            if let Some(filter) = &request.filter {
+                args.extend(["-k".to_string(), filter.clone()]);
+            }
+            (if cfg!(windows) { "python" } else { "python3" }, args)
+        }
+        TestFramework::Npm => {
+            args.extend(["test".to_string(), "--silent".to_string()]);
+            if let Some(filter) = &request.filter {
+                args.extend(["--".to_string(), filter.clone()]);
+            }
+            (if cfg!(windows) { "npm.cmd" } else { "npm" }, args)
+        }
+    }
+}
+
+fn empty_report() -> TestReport {
+    TestReport {
+        framework: TestFramework::Cargo,
+        command: String::new(),
+        success: false,
+        timed_out: false,
+        duration_ms: 0,
+        passed: 0,
+        failed: 0,
+        ignored: 0,
+        failures: Vec::new(),
+        output_tail: None,
+    }
+}
+
+/// `test name ... ok` lines for the counts, `---- name stdout ----`
+/// sections for what each failure printed
+fn parse_cargo(stdout: &str) -> TestReport {
+    let mut report = empty_report();
+    let mut failing = Vec::new();
+    for line in stdout.lines() {
+        let Some(rest) = line.strip_prefix("test ") else { continue };
+        let Some((name, outcome)) = rest.rsplit_once(" ... ") else { continue };
+        match outcome.trim() {
+            "ok" => report.passed += 1,
+            "FAILED" => {
+                report.failed += 1;
+                failing.push(name.trim().to_string());
+            }
+            outcome if outcome.starts_with("ignored") => report.ignored += 1,
+            _ => {}
+        }
+    }
+
+    let lines: Vec<&str> = stdout.lines().collect();
+    for name in failing.into_iter().take(MAX_FAILURES) {
+        let header = format!("---- {} stdout ----", name);
+        let snippet = lines
+            .iter()
+            .position(|line| line.trim() == header)
+            .map(|start| {
+                lines[start + 1..]
+                    .iter()
+                    .take_while(|line| !line.starts_with("---- ") && line.trim() != "failures:")
+                    .copied()
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        report.failures.push(TestFailure { name, snippet: clip(&snippet) });
+    }
+    report
+}
+
+/// The `-rA` short summary for names and counts, and the `___ name ___`
+/// sections of `--tb=short` for tracebacks
+fn parse_pytest(stdout: &str) -> TestReport {
+    let mut report = empty_report();
+    let mut failing = Vec::new();
+    for line in stdout.lines() {
+        if line.starts_with("PASSED ") {
+            report.passed += 1;
+        } else if let Some(rest) = line.strip_prefix("FAILED ").or_else(|| line.strip_prefix("ERROR ")) {
+            report.failed += 1;
+            let name = rest.split(" - ").next().unwrap_or(rest).trim();
+            failing.push(name.to_string());
+        } else if line.starts_with("SKIPPED ") || line.starts_with("XFAIL ") {
+            report.ignored += 1;
+        }
+    }
+
+    let lines: Vec<&str> = stdout.lines().collect();
+    for name in failing.into_iter().take(MAX_FAILURES) {
+        // Section headers carry the test's own name, without its file
+        let short = name.rsplit("::").next().unwrap_or(&name).to_string();
+        let snippet = lines
+            .iter()
+            .position(|line| line.starts_with('_') && line.trim_matches(|c| c == '_' || c == ' ') == short)
+            .map(|start| {
+                lines[start + 1..]
+                    .iter()
+                    .take_while(|line| !line.starts_with("___") && !line.starts_with("==="))
+                    .copied()
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        report.failures.push(TestFailure { name, snippet: clip(&snippet) });
+    }
+    report
+}
+
+/// npm runs whatever the project's `test` script is, so only the common
+/// Jest/Vitest shapes are recognised: a `Tests:` count line and `●`
+/// headers over each failure
+fn parse_npm(output: &str) -> TestReport {
+    let mut report = empty_report();
+    let lines: Vec<&str> = output.lines().collect();
+    for line in &lines {
+        let Some(counts) = line.trim().strip_prefix("Tests:") else { continue };
+        for part in counts.split(',') {
+            let mut words = part.split_whitespace();
+            let (Some(count), Some(kind)) = (words.next().and_then(|n| n.parse::<usize>().ok()), words.next()) else {
+                continue;
+            };
+            match kind {
+                "passed" => report.passed += count,
+                "failed" => report.failed += count,
+                "skipped" | "todo" => report.ignored += count,
+                _ => {}
+            }
+        }
+    }
+
+    for (idx, line) in lines.iter().enumerate() {
+        if report.failures.len() >= MAX_FAILURES {
+            break;
+        }
+        let Some(name) = line.trim().strip_prefix("● ") else { continue };
+        let snippet: Vec<&str> = lines[idx + 1..]
+            .iter()
+            .take_while(|line| !line.trim().starts_with("● ") && !line.trim().starts_with("Test Suites:"))
+            .copied()
+            .collect();
+        report.failures.push(TestFailure {
+            name: name.trim().to_string(),
+            snippet: clip(&snippet),
+        });
+    }
+    report
+}
+
+/// At most [`MAX_SNIPPET_LINES`] lines, without surrounding blank ones
+fn clip(lines: &[&str]) -> String {
+    let start = lines.iter().position(|l| !l.trim().is_empty()).unwrap_or(lines.len());
+    let end = lines.iter().rposition(|l| !l.trim().is_empty()).map_or(start, |e| e + 1);
+    let lines = &lines[start..end];
+    let mut snippet = lines.iter().take(MAX_SNIPPET_LINES).copied().collect::<Vec<_>>().join("\n");
+    if lines.len() > MAX_SNIPPET_LINES {
+        snippet.push_str(&format!("\n… {} more lines", lines.len() - MAX_SNIPPET_LINES));
+    }
+    snippet
+}
+
+fn tail(output: &str, count: usize) -> String {
+    let lines: Vec<&str> = output.lines().filter(|l| !l.trim().is_empty()).collect();
+    lines[lines.len().saturating_sub(count)..].join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cargo_and_pytest_output() {
+        let cargo = "\
+running 3 tests
+test tests::adds ... ok
+test tests::slow ... ignored, needs a database
+test tests::subtracts ... FAILED
+
+failures:
+
+---- tests::subtracts stdout ----
+
+thread 'tests::subtracts' panicked at src/lib.rs:12:9:
+assertion `left == right` failed
+  left: 1
+ right: 2
+
+failures:
+    tests::subtracts
+
+test result: FAILED. 1 passed; 1 failed; 1 ignored; 0 measured; 0 filtered out
+";
+        let report = parse_cargo(cargo);
+        assert_eq!((report.passed, report.failed, report.ignored), (1, 1, 1));
+        assert_eq!(report.failures[0].name, "tests::subtracts");
+        assert!(report.failures[0].snippet.starts_with("thread 'tests::subtracts' panicked"));
+        assert!(report.failures[0].snippet.ends_with("right: 2"));
+
+        let pytest = "\
+============================= test session starts ==============================
+collected 2 items
+
+test_math.py .F                                                          [100%]
+
+=================================== FAILURES ===================================
+_________________________________ test_divide __________________________________
+test_math.py:6: in test_divide
+    assert divide(1, 2) == 1
+E   assert 0.5 == 1
+=========================== short test summary info ============================
+PASSED test_math.py::test_add
+FAILED test_math.py::test_divide - assert 0.5 == 1
+========================= 1 failed, 1 passed in 0.02s ==========================
+";
+        let report = parse_pytest(pytest);
+        assert_eq!((report.passed, report.failed), (1, 1));
+        assert_eq!(report.failures[0].name, "test_math.py::test_divide");
+        assert!(report.failures[0].snippet.contains("E   assert 0.5 == 1"));
+    }
+
+    #[tokio::test]
+    async fn test_detects_missing_suite() {
+        let dir = tempfile::tempdir().unwrap();
+        let runner = TestRunnerTool::new(dir.path());
+        assert!(matches!(runner.run(&TestRequest::default()).await, Err(TestRunnerError::NoSuite(_))));
+
+        std::fs::write(dir.path().join("pyproject.toml"), "").unwrap();
+        assert_eq!(TestFramework::detect(dir.path()), Some(TestFramework::Pytest));
+    }
+}
@@ -0,0 +1,215 @@
+//! Execution timeouts for connector calls
+//!
+//! [`ConnectorRegistry`](crate::connector::ConnectorRegistry) runs every
+//! call under a watchdog so a hung MQTT publish or HTTP request can't stall
+//! the whole turn. When the limit passes the call is dropped and the caller
+//! gets back whatever the connector wrote to its [`PartialOutput`] so far;
+//! the IoT, web and webhook connectors note each request there before they
+//! make it.
+//!
+//! Limits are looked up by `connector.action`, then `connector`, then the
+//! default. There is no default limit, since test runs and self-improvement
+//! already stop themselves after their own, longer limits:
+//!
+//! ```toml
+//! [tools.timeouts]
+//! default_secs = 0
+//!
+//! [tools.timeouts.overrides]
+//! iot = 15
+//! network_web = 300
+//! "network_web.download" = 900
+//! ```
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Most partial output kept for a call; older text is dropped first
+const PARTIAL_OUTPUT_LIMIT: usize = 64 * 1024;
+
+/// Per-connector and per-action time limits
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct ToolTimeoutConfig {
+    /// Seconds a call may run when no override matches; 0 means no limit
+    pub default_secs: u64,
+    /// Limits keyed by connector ID or `connector.action`; 0 means no limit
+    pub overrides: BTreeMap<String, u64>,
+}
+
+impl ToolTimeoutConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(key) = self.overrides.keys().find(|key| {
+            key.trim().is_empty() || key.split('.').any(|part| part.trim().is_empty())
+        }) {
+            return Err(format!(
+                "tools.timeouts.overrides has an invalid key '{}' (expected 'connector' or 'connector.action')",
+                key
+            ));
+        }
+        Ok(())
+    }
+
+    /// Limit for `action` on `connector`, or `None` when it may run
+    /// indefinitely
+    pub fn timeout_for(&self, connector: &str, action: Option<&str>) -> Option<Duration> {
+        let specific = action.and_then(|action| self.overrides.get(&format!("{}.{}", connector, action)));
+        let secs = specific
+            .or_else(|| self.overrides.get(connector))
+            .copied()
+            .unwrap_or(self.default_secs);
+        (secs > 0).then(|| Duration::from_secs(secs))
+    }
+}
+
+/// Output a connector has produced so far, kept so a call that times out
+/// can still report it. Clones share the same buffer.
+#[derive(Debug, Clone, Default)]
+pub struct PartialOutput(Arc<Mutex<String>>);
+
+impl PartialOutput {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append `text`, dropping the oldest output past the size limit
+    pub fn push(&self, text: &str) {
+        let mut buffer = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        buffer.push_str(text);
+        if buffer.len() > PARTIAL_OUTPUT_LIMIT {
+            let mut cut = buffer.len() - PARTIAL_OUTPUT_LIMIT;
+            while !buffer.is_char_boundary(cut) {
+                cut += 1;
+            }
+            buffer.drain(..cut);
+        }
+    }
+
+    /// Append `line` and a newline
+    pub fn push_line(&self, line: &str) {
+        self.push(line);
+        self.push("\n");
+    }
+
+    /// Everything recorded so far, leaving the buffer empty
+    pub fn take(&self) -> String {
+        std::mem::take(&mut *self.0.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connector::{
+        CapabilityLevel, Connector, ConnectorMetadata, ConnectorRegistry, ConnectorResult, ExecutionContext,
+    };
+    use std::collections::HashMap;
+
+    struct HangingConnector(ConnectorMetadata);
+
+    #[async_trait::async_trait]
+    impl Connector for HangingConnector {
+        fn metadata(&self) -> &ConnectorMetadata {
+            &self.0
+        }
+
+        async fn execute(
+            &self,
+            _params: HashMap<String, String>,
+            context: &ExecutionContext,
+        ) -> anyhow::Result<ConnectorResult> {
+            context.partial_output.push_line("connected");
+            std::future::pending().await
+        }
+
+        fn validate(&self, _params: &HashMap<String, String>) -> anyhow::Result<()> {
+            Ok(())
+        }
+
+        fn required_params(&self) -> Vec<String> {
+            Vec::new()
+        }
+
+        fn is_enabled(&self) -> bool {
+            true
+        }
+
+        fn safety_checks(&self) -> Vec<String> {
+            Vec::new()
+        }
+
+        fn requires_network(&self) -> bool {
+            false
+        }
+
+        fn requires_credentials(&self) -> Vec<String> {
+            Vec::new()
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_hung_connector_times_out() {
+        let registry = ConnectorRegistry::new();
+        registry.register(Box::new(HangingConnector(ConnectorMetadata {
+            id: "hang".to_string(),
+            name: "Hang".to_string(),
+            version: "1.0.0".to_string(),
+            description: "Never finishes".to_string(),
+            capability_level: CapabilityLevel::ReadOnly,
+            requires_approval: false,
+            safety_checks: Vec::new(),
+        }))).await.unwrap();
+        registry.set_timeouts(ToolTimeoutConfig {
+            default_secs: 0,
+            overrides: BTreeMap::from([("hang.publish".to_string(), 5)]),
+        }).await;
+
+        let params = HashMap::from([("action".to_string(), "publish".to_string())]);
+        let result = registry
+            .execute_connector("hang", params, &ExecutionContext::default())
+            .await
+            .unwrap();
+        assert!(!result.success);
+        assert_eq!(result.output, "connected\n");
+        assert_eq!(result.metadata.get("timed_out").map(String::as_str), Some("true"));
+        assert_eq!(result.errors, vec!["Timed out after 5s".to_string()]);
+    }
+
+    #[test]
+    fn test_timeout_precedence() {
+        let config = ToolTimeoutConfig {
+            default_secs: 120,
+            overrides: BTreeMap::from([
+                ("iot".to_string(), 15),
+                ("iot.subscribe".to_string(), 0),
+                ("network.scan".to_string(), 600),
+            ]),
+        };
+        assert_eq!(config.timeout_for("iot", Some("publish")), Some(Duration::from_secs(15)));
+        assert_eq!(config.timeout_for("iot", Some("subscribe")), None);
+        assert_eq!(config.timeout_for("network", Some("scan")), Some(Duration::from_secs(600)));
+        assert_eq!(config.timeout_for("network", Some("ping")), Some(Duration::from_secs(120)));
+        assert_eq!(config.timeout_for("web", None), Some(Duration::from_secs(120)));
+        assert!(config.validate().is_ok());
+
+        let bad = ToolTimeoutConfig {
+            overrides: BTreeMap::from([("iot.".to_string(), 5)]),
+            ..Default::default()
+        };
+        assert!(bad.validate().is_err());
+    }
+
+    #[test]
+    fn test_partial_output_limit() {
+        let output = PartialOutput::new();
+        output.push_line("first");
+        output.push(&"é".repeat(PARTIAL_OUTPUT_LIMIT));
+        let kept = output.take();
+        assert!(kept.len() <= PARTIAL_OUTPUT_LIMIT);
+        assert!(!kept.starts_with("first"));
+        assert!(output.take().is_empty());
+    }
+}
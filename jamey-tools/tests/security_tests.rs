@@ -423,4 +423,335 @@ async fn test_list_processes_works() {
     let result = result.unwrap();
     assert!(result.success, "List processes should be successful");
     assert!(!result.output.is_empty(), "Should return process list");
-}
\ No newline at end of file
+}
+#[tokio::test]
+async fn test_tool_policy_enforced_by_registry() {
+    use jamey_tools::connector::{CapabilityLevel, ConnectorRegistry, ToolPolicy};
+
+    let temp_dir = TempDir::new().unwrap();
+    std::fs::write(temp_dir.path().join("notes.txt"), "hello").unwrap();
+    let registry = ConnectorRegistry::new();
+    registry
+        .register(Box::new(FullSystemConnector::new(temp_dir.path().to_path_buf())))
+        .await
+        .unwrap();
+
+    let read_only = ToolPolicy {
+        allowed: Vec::new(),
+        max_capability: CapabilityLevel::ReadOnly,
+    };
+    let other_tools = ToolPolicy {
+        allowed: vec!["network_web".to_string()],
+        max_capability: CapabilityLevel::FullAccess,
+    };
+    assert!(registry.list_permitted(&read_only).await.is_empty());
+    assert!(registry.list_permitted(&other_tools).await.is_empty());
+    assert_eq!(registry.list_permitted(&ToolPolicy::unrestricted()).await.len(), 1);
+
+    let mut params = HashMap::new();
+    params.insert("action".to_string(), "read_file".to_string());
+    params.insert("path".to_string(), "notes.txt".to_string());
+
+    // A read-only session cannot reach the connector even by naming it
+    for policy in [read_only, other_tools] {
+        let context = ExecutionContext { tool_policy: policy, ..ExecutionContext::default() };
+        let result = registry.execute_connector("full_system", params.clone(), &context).await;
+        assert!(result.is_err(), "Connector outside the session policy should be refused");
+    }
+
+    let result = registry
+        .execute_connector("full_system", params, &ExecutionContext::default())
+        .await;
+    assert!(result.is_ok(), "Unrestricted context should reach the connector");
+}
+
+#[tokio::test]
+async fn test_file_write_compensation() {
+    use jamey_tools::connector::ConnectorRegistry;
+
+    let temp_dir = TempDir::new().unwrap();
+    let backups = TempDir::new().unwrap();
+    let notes = temp_dir.path().join("notes.txt");
+    std::fs::write(&notes, "original").unwrap();
+    let registry = ConnectorRegistry::new();
+    let connector = FullSystemConnector::new(temp_dir.path().to_path_buf())
+        .with_backup_dir(backups.path().to_path_buf());
+    registry.register(Box::new(connector)).await.unwrap();
+    let context = ExecutionContext::default();
+
+    let mut params = HashMap::new();
+    params.insert("action".to_string(), "write_file".to_string());
+    params.insert("path".to_string(), "notes.txt".to_string());
+    params.insert("content".to_string(), "overwritten".to_string());
+    let result = registry.execute_connector("full_system", params, &context).await.unwrap();
+    assert_eq!(std::fs::read_to_string(&notes).unwrap(), "overwritten");
+    assert_eq!(result.compensations.len(), 1);
+
+    // Undo records are stored outside the connector, so a tampered one
+    // must not restore files outside the root
+    let mut escaping = result.compensations[0].clone();
+    escaping.params.insert("path".to_string(), "/etc/hostname".to_string());
+    assert!(registry.compensate(&escaping, &context).await.is_err());
+
+    registry.compensate(&result.compensations[0], &context).await.unwrap();
+    assert_eq!(std::fs::read_to_string(&notes).unwrap(), "original");
+}
+
+#[tokio::test]
+async fn test_workspace_confines_file_access() {
+    use jamey_tools::connector::{ConnectorRegistry, WorkspaceScope};
+
+    let temp_dir = TempDir::new().unwrap();
+    let project = temp_dir.path().join("app");
+    std::fs::create_dir_all(project.join("secrets")).unwrap();
+    std::fs::write(project.join("main.rs"), "fn main() {}").unwrap();
+    std::fs::write(project.join("secrets/key"), "hunter2").unwrap();
+    std::fs::write(temp_dir.path().join("outside.txt"), "elsewhere").unwrap();
+    let registry = ConnectorRegistry::new();
+    registry
+        .register(Box::new(FullSystemConnector::new(temp_dir.path().to_path_buf())))
+        .await
+        .unwrap();
+    let workspace = WorkspaceScope {
+        root: project.canonicalize().unwrap(),
+        ignore: vec!["secrets".to_string()],
+    };
+    let context = ExecutionContext { workspace: Some(workspace), ..ExecutionContext::default() };
+
+    let call = |action: &str, path: &str| {
+        HashMap::from([
+            ("action".to_string(), action.to_string()),
+            ("path".to_string(), path.to_string()),
+        ])
+    };
+    // Relative paths start at the workspace root, not the connector's
+    let read = registry.execute_connector("full_system", call("read_file", "main.rs"), &context).await;
+    assert_eq!(read.unwrap().output, "fn main() {}");
+    let outside = registry.execute_connector("full_system", call("read_file", "outside.txt"), &context).await;
+    assert!(outside.is_err());
+    let ignored = registry.execute_connector("full_system", call("read_file", "secrets/key"), &context).await;
+    assert!(ignored.is_err());
+    let listing = registry
+        .execute_connector("full_system", call("list_directory", "."), &context)
+        .await
+        .unwrap();
+    assert!(listing.output.contains("main.rs"));
+    assert!(!listing.output.contains("secrets"));
+
+    // A workspace can't reach past the connector's own root
+    let escaping = ExecutionContext {
+        workspace: Some(WorkspaceScope { root: std::env::temp_dir().canonicalize().unwrap(), ignore: Vec::new() }),
+        ..ExecutionContext::default()
+    };
+    assert!(registry.execute_connector("full_system", call("read_file", "main.rs"), &escaping).await.is_err());
+}
+
+#[tokio::test]
+async fn test_path_policy_limits_file_access() {
+    use jamey_tools::path_policy::{PathPolicy, PathPolicyConfig};
+
+    let temp_dir = TempDir::new().unwrap();
+    std::fs::create_dir_all(temp_dir.path().join("out")).unwrap();
+    std::fs::write(temp_dir.path().join("out/a.txt"), "").unwrap();
+    std::fs::write(temp_dir.path().join("out/b.txt"), "").unwrap();
+    std::fs::write(temp_dir.path().join("notes.txt"), "notes").unwrap();
+    std::fs::write(temp_dir.path().join("server.pem"), "key").unwrap();
+    let policy = PathPolicy::new(&PathPolicyConfig {
+        write: vec![temp_dir.path().join("out")],
+        deny: vec!["*.pem".to_string()],
+        max_file_bytes: Some(32),
+        ..Default::default()
+    })
+    .unwrap();
+    let connector = FullSystemConnector::new(temp_dir.path().to_path_buf()).with_path_policy(policy);
+    let context = ExecutionContext::default();
+    let call = |action: &str, path: &str, content: Option<&str>| {
+        let mut params = HashMap::new();
+        params.insert("action".to_string(), action.to_string());
+        params.insert("path".to_string(), path.to_string());
+        if let Some(content) = content {
+            params.insert("content".to_string(), content.to_string());
+        }
+        params
+    };
+
+    assert!(connector.execute(call("read_file", "notes.txt", None), &context).await.is_ok());
+    assert!(connector.execute(call("read_file", "server.pem", None), &context).await.is_err(), "Denied files can't be read");
+    assert!(connector.execute(call("write_file", "notes.txt", Some("x")), &context).await.is_err(), "Writes stay in the write directories");
+    assert!(connector.execute(call("write_file", "out/a.txt", Some("x")), &context).await.is_ok());
+    let too_big = "x".repeat(64);
+    assert!(connector.execute(call("write_file", "out/b.txt", Some(&too_big)), &context).await.is_err(), "Oversized writes are refused");
+
+    let listing = connector.execute(call("list_directory", ".", None), &context).await.unwrap();
+    assert!(listing.output.contains("notes.txt"));
+    assert!(!listing.output.contains("server.pem"), "Denied files are left out of listings");
+}
+
+#[tokio::test]
+async fn test_git_stays_inside_workspace() {
+    use jamey_tools::connector::WorkspaceScope;
+    use jamey_tools::connectors::GitConnector;
+
+    let temp_dir = TempDir::new().unwrap();
+    let project = temp_dir.path().join("app");
+    std::fs::create_dir_all(project.join("secrets")).unwrap();
+    std::fs::write(project.join("secrets/key"), "hunter2").unwrap();
+    git2::Repository::init(&project).unwrap();
+    let connector = GitConnector::new(temp_dir.path().to_path_buf());
+    let workspace = WorkspaceScope {
+        root: project.canonicalize().unwrap(),
+        ignore: vec!["secrets".to_string()],
+    };
+    let context = ExecutionContext { workspace: Some(workspace), ..ExecutionContext::default() };
+    let call = |pairs: &[(&str, &str)]| -> HashMap<String, String> {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    };
+
+    let status = connector.execute(call(&[("action", "status")]), &context).await.unwrap();
+    assert!(status.success);
+    assert!(!status.output.contains("secrets"), "{}", status.output);
+    let blame = connector.execute(call(&[("action", "blame"), ("path", "secrets/key")]), &context).await;
+    assert!(blame.is_err());
+    let escaping = connector.execute(call(&[("action", "log"), ("repo", "../..")]), &context).await;
+    assert!(escaping.is_err());
+
+    // Without a workspace, a repository above the connector's root is refused
+    let nested = temp_dir.path().join("app/secrets");
+    let connector = GitConnector::new(nested);
+    let above = connector.execute(call(&[("action", "status")]), &ExecutionContext::default()).await;
+    assert!(above.is_err());
+}
+
+#[tokio::test]
+async fn test_code_search_stays_inside_workspace() {
+    use jamey_tools::connector::WorkspaceScope;
+    use jamey_tools::connectors::CodeSearchConnector;
+
+    let temp_dir = TempDir::new().unwrap();
+    let project = temp_dir.path().join("app");
+    std::fs::create_dir_all(project.join("secrets")).unwrap();
+    std::fs::write(project.join("secrets/key"), "token = hunter2").unwrap();
+    std::fs::write(project.join("config.rs"), "let token = env();").unwrap();
+    std::fs::write(temp_dir.path().join("outside.rs"), "let token = 1;").unwrap();
+    let connector = CodeSearchConnector::new(temp_dir.path().to_path_buf());
+    let workspace = WorkspaceScope {
+        root: project.canonicalize().unwrap(),
+        ignore: vec!["secrets".to_string()],
+    };
+    let context = ExecutionContext { workspace: Some(workspace), ..ExecutionContext::default() };
+    let call = |pairs: &[(&str, &str)]| -> HashMap<String, String> {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    };
+
+    let found = connector.execute(call(&[("action", "search"), ("pattern", "token")]), &context).await.unwrap();
+    assert!(found.success);
+    assert!(found.output.contains("config.rs"), "{}", found.output);
+    assert!(!found.output.contains("hunter2"), "{}", found.output);
+    assert!(!found.output.contains("outside.rs"), "{}", found.output);
+    let escaping = connector
+        .execute(call(&[("action", "search"), ("pattern", "token"), ("path", "..")]), &context)
+        .await;
+    assert!(escaping.is_err());
+}
+
+#[tokio::test]
+async fn test_lsp_refuses_files_outside_workspace() {
+    use jamey_tools::connector::WorkspaceScope;
+    use jamey_tools::connectors::LspConnector;
+
+    let temp_dir = TempDir::new().unwrap();
+    let project = temp_dir.path().join("app");
+    std::fs::create_dir_all(project.join("vendor")).unwrap();
+    std::fs::write(project.join("vendor/lib.rs"), "pub fn f() {}").unwrap();
+    std::fs::write(temp_dir.path().join("outside.rs"), "fn main() {}").unwrap();
+    let connector = LspConnector::new(temp_dir.path().to_path_buf());
+    let workspace = WorkspaceScope {
+        root: project.canonicalize().unwrap(),
+        ignore: vec!["vendor".to_string()],
+    };
+    let context = ExecutionContext { workspace: Some(workspace), ..ExecutionContext::default() };
+    let call = |pairs: &[(&str, &str)]| -> HashMap<String, String> {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    };
+
+    // Both are refused before any language server is started
+    let outside = connector
+        .execute(call(&[("action", "hover"), ("path", "../outside.rs"), ("line", "1"), ("column", "4")]), &context)
+        .await;
+    assert!(outside.is_err());
+    let ignored = connector
+        .execute(call(&[("action", "diagnostics"), ("path", "vendor/lib.rs")]), &context)
+        .await;
+    assert!(ignored.is_err());
+}
+
+#[tokio::test]
+async fn test_terminal_confined_to_workspace_and_session() {
+    use jamey_tools::connector::WorkspaceScope;
+    use jamey_tools::connectors::TerminalConnector;
+
+    let temp_dir = TempDir::new().unwrap();
+    let project = temp_dir.path().join("app");
+    std::fs::create_dir_all(&project).unwrap();
+    let connector = TerminalConnector::new(temp_dir.path().to_path_buf());
+    let workspace = WorkspaceScope {
+        root: project.canonicalize().unwrap(),
+        ignore: Vec::new(),
+    };
+    let context = ExecutionContext { workspace: Some(workspace), ..ExecutionContext::default() };
+    let call = |pairs: &[(&str, &str)]| -> HashMap<String, String> {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    };
+
+    let outside = connector.execute(call(&[("action", "open"), ("path", "..")]), &context).await;
+    assert!(outside.is_err());
+
+    if std::process::Command::new("bash").arg("--version").output().is_err() {
+        return;
+    }
+    let opened = connector.execute(call(&[("action", "open")]), &context).await.unwrap();
+    let id = opened.metadata["id"].clone();
+    let refused = connector.execute(call(&[("action", "send"), ("id", &id), ("input", "cat /etc/passwd")]), &context).await;
+    assert!(refused.is_err());
+
+    // Another chat session can't reach this shell
+    let other = ExecutionContext { session_id: "someone-else".to_string(), ..context.clone() };
+    let stolen = connector.execute(call(&[("action", "read"), ("id", &id)]), &other).await;
+    assert!(stolen.is_err());
+    connector.execute(call(&[("action", "close"), ("id", &id)]), &context).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_code_interpreter_rejects_before_running() {
+    use jamey_tools::connectors::CodeInterpreterConnector;
+
+    let temp_dir = TempDir::new().unwrap();
+    let connector = CodeInterpreterConnector::new(temp_dir.path().to_path_buf());
+    let context = ExecutionContext::default();
+    let call = |pairs: &[(&str, &str)]| -> HashMap<String, String> {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    };
+
+    let shell = connector.execute(call(&[("action", "run"), ("language", "bash"), ("code", "id")]), &context).await;
+    assert!(shell.is_err());
+
+    // Oversized programs are refused before anything is written or started
+    let huge = "x = 1\n".repeat(64 * 1024);
+    let oversized = connector.execute(call(&[("action", "run"), ("language", "python"), ("code", &huge)]), &context).await;
+    assert!(oversized.is_err());
+    assert_eq!(std::fs::read_dir(temp_dir.path()).unwrap().count(), 0);
+}
+
+#[test]
+fn test_capability_levels_ordered() {
+    use jamey_tools::connector::CapabilityLevel;
+
+    assert!(CapabilityLevel::FullAccess.permits(CapabilityLevel::ReadWrite));
+    assert!(CapabilityLevel::ReadWrite.permits(CapabilityLevel::ReadOnly));
+    assert!(!CapabilityLevel::ReadOnly.permits(CapabilityLevel::ReadWrite));
+    assert!(!CapabilityLevel::WebAccess.permits(CapabilityLevel::SystemAdmin));
+    for level in CapabilityLevel::ALL {
+        assert_eq!(level.as_str().parse::<CapabilityLevel>(), Ok(level));
+    }
+    assert!("root".parse::<CapabilityLevel>().is_err());
+}
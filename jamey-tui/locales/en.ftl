@@ -0,0 +1,100 @@
+# Strings shown by `jamey-tui`. Keep every message here; translations
+# fall back to these for anything they lack.
+
+## Chat
+
+chat-title = Chat
+chat-scrolled = ↑ { $rows } rows · { $key } to follow
+
+## Dashboard
+
+dashboard-waiting = Waiting for the first sample…
+dashboard-runtime = Runtime
+dashboard-runtime-sampled = Runtime (sampled { $time })
+dashboard-connectors = Connectors
+dashboard-connectors-enabled = Connectors ({ $enabled }/{ $total } enabled)
+label-runtime = Runtime
+label-sessions = Sessions
+label-database = Database
+label-cache = Cache
+label-budget = Budget
+health-up = up
+health-down = down
+health-unknown = unknown
+dashboard-uptime = up { $uptime }
+dashboard-sessions-active = { $count } active
+dashboard-pool = pool { $size }/{ $max } ({ $idle } idle, { $waiting } waiting)
+dashboard-cache-hit-rate = { $rate }% hit rate
+dashboard-cache-empty = no lookups yet
+dashboard-providers = Providers
+dashboard-providers-empty = no requests yet
+dashboard-provider-stats = { $requests } req · p50 { $p50 } · p99 { $p99 }
+dashboard-provider-errors = { $errors } err
+connector-approval = approval
+connector-disabled = disabled
+budget-today = ${ $spent } today
+budget-of = of ${ $limit }
+
+## Usage insights
+
+insights-tools = Tool usage ({ $days }d)
+insights-models = Model latency ({ $days }d)
+insights-reading = Reading the usage log…
+insights-tool-stats = { $runs } runs · p95 { $p95 }
+insights-tool-failed = { $rate }% failed
+insights-tools-empty = no tool runs recorded
+insights-tools-summary = { $title } · { $runs } runs, { $rate }% failed
+insights-model-latency = p50 { $p50 } · p95 { $p95 } · p99 { $p99 }
+insights-no-timings = no timings
+insights-model-calls = { $calls ->
+    [one] { $calls } call
+   *[other] { $calls } calls
+}
+
+## Logs and search
+
+logs-title = Logs ≥ { $level } (l level · ↑/↓ scroll · Esc back)
+logs-scrolled = ↑ { $lines } lines
+search-title = Search { $position } (Enter/↑ older · ↓ newer · Esc close)
+search-no-matches = no matches
+rename-title = Rename session (Enter save · Esc cancel)
+
+## Input
+
+input-cancel-hint = { $key } cancels the reply
+input-send-hint = { $send } send · { $newline } newline
+input-title = Message ({ $hint })
+input-attached = { $count } attached
+
+## Session switcher and attachments
+
+switcher-title = Sessions (↑/↓ select · Enter open · Esc close)
+switcher-entry = { $id } · { $count } msgs · { $updated }
+switcher-empty = No matching sessions
+attachments-title = Attachments
+attachments-keys = ↑/↓ select · Enter save to downloads · Esc close
+preview-title = Preview
+preview-unavailable = No preview: { $reason }
+preview-loading = Loading…
+
+## Approvals
+
+approval-title = Approval required
+approval-connector = Connector
+approval-action = Action
+approval-requested = Requested
+approval-parameters = Parameters
+approval-safety-checks = Safety checks
+approval-approve = approve
+approval-always = always allow
+approval-deny = deny
+approval-cancel = cancel turn
+
+## Status bar
+
+status-ready = ready
+status-streaming = streaming
+status-error = error: { $error }
+status-tokens = ↑{ $prompt } ↓{ $completion } tokens
+status-session = session { $id }
+status-keys = { $sessions } sessions · { $quit } exit
@@ -0,0 +1,99 @@
+# Cadenas de `jamey-tui` en español
+
+## Chat
+
+chat-title = Chat
+chat-scrolled = ↑ { $rows } filas · { $key } para seguir
+
+## Dashboard
+
+dashboard-waiting = Esperando la primera muestra…
+dashboard-runtime = Runtime
+dashboard-runtime-sampled = Runtime (muestra de las { $time })
+dashboard-connectors = Conectores
+dashboard-connectors-enabled = Conectores ({ $enabled }/{ $total } activos)
+label-runtime = Runtime
+label-sessions = Sesiones
+label-database = Base datos
+label-cache = Caché
+label-budget = Gasto
+health-up = activo
+health-down = caído
+health-unknown = desconocido
+dashboard-uptime = activo desde hace { $uptime }
+dashboard-sessions-active = { $count } activas
+dashboard-pool = pool { $size }/{ $max } ({ $idle } libres, { $waiting } en espera)
+dashboard-cache-hit-rate = { $rate }% de aciertos
+dashboard-cache-empty = sin consultas aún
+dashboard-providers = Proveedores
+dashboard-providers-empty = sin peticiones aún
+dashboard-provider-stats = { $requests } pet · p50 { $p50 } · p99 { $p99 }
+dashboard-provider-errors = { $errors } err
+connector-approval = aprobación
+connector-disabled = desactivado
+budget-today = ${ $spent } hoy
+budget-of = de ${ $limit }
+
+## Usage insights
+
+insights-tools = Uso de herramientas ({ $days } d)
+insights-models = Latencia de modelos ({ $days } d)
+insights-reading = Leyendo el registro de uso…
+insights-tool-stats = { $runs } ejecuciones · p95 { $p95 }
+insights-tool-failed = { $rate }% fallidas
+insights-tools-empty = no hay ejecuciones registradas
+insights-tools-summary = { $title } · { $runs } ejecuciones, { $rate }% fallidas
+insights-model-latency = p50 { $p50 } · p95 { $p95 } · p99 { $p99 }
+insights-no-timings = sin tiempos
+insights-model-calls = { $calls ->
+    [one] { $calls } llamada
+   *[other] { $calls } llamadas
+}
+
+## Logs and search
+
+logs-title = Registros ≥ { $level } (l nivel · ↑/↓ desplazar · Esc volver)
+logs-scrolled = ↑ { $lines } líneas
+search-title = Buscar { $position } (Enter/↑ anterior · ↓ siguiente · Esc cerrar)
+search-no-matches = sin resultados
+rename-title = Renombrar sesión (Enter guardar · Esc cancelar)
+
+## Input
+
+input-cancel-hint = { $key } cancela la respuesta
+input-send-hint = { $send } enviar · { $newline } nueva línea
+input-title = Mensaje ({ $hint })
+input-attached = { $count } adjuntos
+
+## Session switcher and attachments
+
+switcher-title = Sesiones (↑/↓ elegir · Enter abrir · Esc cerrar)
+switcher-entry = { $id } · { $count } msjs · { $updated }
+switcher-empty = Ninguna sesión coincide
+attachments-title = Adjuntos
+attachments-keys = ↑/↓ elegir · Enter guardar en descargas · Esc cerrar
+preview-title = Vista previa
+preview-unavailable = Sin vista previa: { $reason }
+preview-loading = Cargando…
+
+## Approvals
+
+approval-title = Se necesita aprobación
+approval-connector = Conector
+approval-action = Acción
+approval-requested = Pedido
+approval-parameters = Parámetros
+approval-safety-checks = Comprobaciones
+approval-approve = aprobar
+approval-always = permitir siempre
+approval-deny = denegar
+approval-cancel = cancelar turno
+
+## Status bar
+
+status-ready = listo
+status-streaming = recibiendo
+status-error = error: { $error }
+status-tokens = ↑{ $prompt } ↓{ $completion } tokens
+status-session = sesión { $id }
+status-keys = { $sessions } sesiones · { $quit } salir
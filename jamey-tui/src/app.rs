@@ -1,85 +1,722 @@
 //! Application state and logic for the TUI
 
+use crate::attachments::{self, AttachmentPanel};
+use crate::config::TuiConfig;
+use crate::dashboard::Dashboard;
+use crate::editor::Editor;
+use crate::keymap::{Action, Keymap};
+use crate::logs::LogBuffer;
+use crate::preview::Graphics;
+use crate::switcher::{Switcher, SwitcherItem};
+use crate::tab::{Tab, TurnUpdate};
+use crate::theme::Themes;
 use anyhow::Result;
-use crossterm::event::{KeyCode, KeyEvent};
-use jamey_protocol::{Message, Role};
-use std::time::Instant;
-use tui_textarea::TextArea;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use jamey_protocol::Message;
+use jamey_runtime::approvals::ApprovalRequest;
+use jamey_runtime::chat::TurnEvent;
+use jamey_runtime::feedback::{local_user, Feedback, Rating};
+use jamey_runtime::voice::VoiceOutput;
+use jamey_runtime::Runtime;
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tracing::warn;
 use uuid::Uuid;
 
+/// Rows moved by PageUp/PageDown
+const PAGE_ROWS: usize = 10;
+
+/// Where key presses go
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Focus {
+    Input,
+    Search,
+    Switcher,
+    Rename,
+    Attachments,
+}
+
+/// What fills the main pane
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum View {
+    Chat,
+    Dashboard,
+}
+
+/// Answer to a tool call waiting for approval
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Decision {
+    Approve,
+    /// Approve, and stop asking for this connector action
+    AlwaysAllow,
+    Deny,
+}
+
 pub struct App {
     pub should_exit: bool,
-    pub messages: Vec<Message>,
-    pub input: TextArea<'static>,
-    pub status: String,
-    pub session_id: Uuid,
-    pub last_update: Instant,
+    runtime: Runtime,
+    pub tabs: Vec<Tab>,
+    pub active: usize,
+    pub editor: Editor,
+    pub keymap: Keymap,
+    pub themes: Themes,
+    pub focus: Focus,
+    pub view: View,
+    pub dashboard: Dashboard,
+    pub model: String,
+    /// Open while `focus` is `Switcher`
+    pub switcher: Option<Switcher>,
+    /// New title being typed while `focus` is `Rename`
+    pub rename: String,
+    /// Open while `focus` is `Attachments`
+    pub attachments: Option<AttachmentPanel>,
+    graphics: Graphics,
+    /// Set while replies are read aloud
+    voice: Option<Arc<VoiceOutput>>,
+    /// The reply being read; replaced when the next one arrives
+    speaking: Option<JoinHandle<()>>,
+    events_tx: mpsc::UnboundedSender<TurnUpdate>,
+    events: mpsc::UnboundedReceiver<TurnUpdate>,
 }
 
 impl App {
-    pub async fn new() -> Result<Self> {
-        let session_id = Uuid::new_v4();
-        
-        Ok(Self {
+    pub async fn new(runtime: Runtime, logs: LogBuffer, config: TuiConfig) -> Result<Self> {
+        let model = runtime.state().config.llm.openrouter_default_model.clone();
+        let (events_tx, events) = mpsc::unbounded_channel();
+        let dashboard = Dashboard::new(runtime.status_probe(), Arc::clone(&runtime.state().usage_log), logs);
+
+        let mut app = Self {
             should_exit: false,
-            messages: vec![
-                Message::system("Welcome to Digital Twin Jamey TUI!".to_string()),
-                Message::assistant("Hello! I'm Jamey, your digital twin assistant. How can I help you today?".to_string()),
-            ],
-            input: TextArea::default(),
-            status: "Ready".to_string(),
-            session_id,
-            last_update: Instant::now(),
-        })
-    }
-
-    pub fn handle_key(&mut self, key: KeyEvent) {
+            runtime,
+            tabs: Vec::new(),
+            active: 0,
+            editor: Editor::new(config.editing_mode),
+            keymap: config.keymap,
+            themes: config.themes,
+            focus: Focus::Input,
+            view: View::Chat,
+            dashboard,
+            model,
+            switcher: None,
+            rename: String::new(),
+            attachments: None,
+            graphics: Graphics::new(config.graphics),
+            voice: None,
+            speaking: None,
+            events_tx,
+            events,
+        };
+        app.open_tab();
+        Ok(app)
+    }
+
+    pub fn tab(&self) -> &Tab {
+        &self.tabs[self.active]
+    }
+
+    pub fn tab_mut(&mut self) -> &mut Tab {
+        &mut self.tabs[self.active]
+    }
+
+    pub async fn handle_key(&mut self, key: KeyEvent) {
+        if self.keymap.action(&key) == Some(Action::Quit) {
+            self.should_exit = true;
+            return;
+        }
+        match self.focus {
+            // The approval dialog is modal over the active tab
+            Focus::Input if self.tab().approval.is_some() => self.handle_approval_key(key).await,
+            Focus::Input if self.view == View::Dashboard => self.handle_dashboard_key(key),
+            Focus::Input => self.handle_input_key(key).await,
+            Focus::Search => self.handle_search_key(key),
+            Focus::Switcher => self.handle_switcher_key(key).await,
+            Focus::Rename => self.handle_rename_key(key).await,
+            Focus::Attachments => self.handle_attachments_key(key).await,
+        }
+    }
+
+    async fn handle_input_key(&mut self, key: KeyEvent) {
+        // Vim normal mode reads plain keys as commands before any binding
+        if self.editor.wants(&key) {
+            self.editor.input(key);
+            return;
+        }
+        if let KeyCode::Char(c @ '1'..='9') = key.code {
+            if key.modifiers.contains(KeyModifiers::ALT) {
+                let index = c as usize - '1' as usize;
+                if index < self.tabs.len() {
+                    self.select_tab(index);
+                }
+                return;
+            }
+        }
+        match self.keymap.action(&key) {
+            Some(action) => self.run_action(action).await,
+            None => self.editor.input(key),
+        }
+    }
+
+    async fn run_action(&mut self, action: Action) {
+        match action {
+            Action::Send => self.send_message().await,
+            Action::Newline => self.editor.insert_newline(),
+            Action::CancelTurn if self.tab().is_busy() => self.cancel_turn().await,
+            Action::CancelTurn => {}
+            Action::Search => {
+                self.tab_mut().chat.open_search();
+                self.focus = Focus::Search;
+            }
+            Action::Dashboard => self.view = View::Dashboard,
+            Action::NextTheme => {
+                let name = self.themes.cycle().name.clone();
+                self.tab_mut().chat.push(Message::system(format!("Theme: {}", name)));
+            }
+            Action::ToggleSpeech => self.toggle_speech(),
+            Action::NewTab => self.open_tab(),
+            Action::CloseTab => self.close_tab().await,
+            Action::NextTab => self.select_tab(self.active + 1),
+            Action::PrevTab => self.select_tab(self.active + self.tabs.len() - 1),
+            Action::Sessions => self.open_switcher().await,
+            Action::Rename => {
+                self.rename = self.tab().title.clone();
+                self.focus = Focus::Rename;
+            }
+            Action::Attachments => self.open_attachments().await,
+            Action::ScrollUp => self.tab_mut().chat.scroll_up(1),
+            Action::ScrollDown => self.tab_mut().chat.scroll_down(1),
+            Action::PageUp => self.tab_mut().chat.scroll_up(PAGE_ROWS),
+            Action::PageDown => self.tab_mut().chat.scroll_down(PAGE_ROWS),
+            Action::ScrollBottom => self.tab_mut().chat.scroll_to_bottom(),
+            Action::HistoryPrev => self.editor.history_prev(),
+            Action::HistoryNext => self.editor.history_next(),
+            Action::KillToEnd => self.editor.kill_to_end(),
+            Action::KillToStart => self.editor.kill_to_start(),
+            Action::KillWordBack => self.editor.kill_word_back(),
+            Action::Yank => self.editor.yank(),
+            Action::YankPop => self.editor.yank_pop(),
+            Action::Quit => self.should_exit = true,
+        }
+    }
+
+    fn handle_dashboard_key(&mut self, key: KeyEvent) {
+        match key.code {
+            KeyCode::Esc => self.view = View::Chat,
+            _ if self.keymap.action(&key) == Some(Action::Dashboard) => self.view = View::Chat,
+            KeyCode::Char('l') => self.dashboard.cycle_level(),
+            KeyCode::PageUp => self.dashboard.scroll_logs(true, PAGE_ROWS),
+            KeyCode::PageDown => self.dashboard.scroll_logs(false, PAGE_ROWS),
+            KeyCode::Up => self.dashboard.scroll_logs(true, 1),
+            KeyCode::Down => self.dashboard.scroll_logs(false, 1),
+            KeyCode::End => self.dashboard.log_offset = 0,
+            _ => {}
+        }
+    }
+
+    async fn handle_approval_key(&mut self, key: KeyEvent) {
         match key.code {
-            KeyCode::Char('c') if key.modifiers.contains(crossterm::event::KeyModifiers::CONTROL) => {
-                self.should_exit = true;
+            KeyCode::Char('y') | KeyCode::Enter => self.decide(Decision::Approve).await,
+            KeyCode::Char('a') => self.decide(Decision::AlwaysAllow).await,
+            KeyCode::Char('n') => self.decide(Decision::Deny).await,
+            KeyCode::Esc => self.cancel_turn().await,
+            // Tab switching still works so other conversations aren't blocked
+            KeyCode::Char('1'..='9') if key.modifiers.contains(KeyModifiers::ALT) => {
+                self.handle_input_key(key).await
+            }
+            _ => {
+                if let Some(action @ (Action::NextTab | Action::PrevTab)) = self.keymap.action(&key) {
+                    self.run_action(action).await;
+                }
+            }
+        }
+    }
+
+    /// Settle the active tab's pending approval; the turn picks the
+    /// decision up from the queue and carries on
+    async fn decide(&mut self, decision: Decision) {
+        let Some(request) = self.tab_mut().approval.take() else { return };
+        let queue = &self.runtime.state().approval_queue;
+        let by = approver();
+        let result = match decision {
+            Decision::Approve => queue.approve(request.id, &by, None).await,
+            Decision::AlwaysAllow => queue.approve_always(request.id, &by).await,
+            Decision::Deny => queue.deny(request.id, &by, None).await,
+        };
+        let note = match (result, decision) {
+            (Ok(_), Decision::Approve) => format!("✅ Approved {} {}", request.connector_id, request.action),
+            (Ok(_), Decision::AlwaysAllow) => format!(
+                "✅ Approved {} {}; it won't ask again",
+                request.connector_id, request.action
+            ),
+            (Ok(_), Decision::Deny) => format!("🚫 Denied {} {}", request.connector_id, request.action),
+            (Err(e), _) => {
+                warn!("Failed to record decision for approval {}: {}", request.id, e);
+                format!("Couldn't record the decision: {}", e)
+            }
+        };
+        self.tab_mut().chat.push(Message::system(note));
+    }
+
+    async fn cancel_turn(&mut self) {
+        let pending = self.tab_mut().cancel();
+        self.release(pending).await;
+    }
+
+    /// Deny an approval whose turn was cancelled, so it doesn't sit in the
+    /// queue until it expires
+    async fn release(&self, pending: Option<ApprovalRequest>) {
+        let Some(request) = pending else { return };
+        let reason = Some("turn cancelled".to_string());
+        if let Err(e) = self.runtime.state().approval_queue.deny(request.id, &approver(), reason).await {
+            warn!("Failed to withdraw approval {}: {}", request.id, e);
+        }
+    }
+
+    async fn handle_attachments_key(&mut self, key: KeyEvent) {
+        let Some(panel) = self.attachments.as_mut() else {
+            self.focus = Focus::Input;
+            return;
+        };
+        match key.code {
+            KeyCode::Esc => {
+                self.attachments = None;
+                self.focus = Focus::Input;
+            }
+            KeyCode::Up => panel.move_selection(-1),
+            KeyCode::Down | KeyCode::Tab => panel.move_selection(1),
+            KeyCode::Enter | KeyCode::Char('s') => {
+                let Some(item) = panel.selected_item().cloned() else { return };
+                let store = &self.runtime.state().attachment_store;
+                panel.notice = Some(match store.export(item.id, &attachments::download_dir()).await {
+                    Ok(path) => format!("Saved to {}", path.display()),
+                    Err(e) => {
+                        warn!("Failed to save attachment {}: {}", item.id, e);
+                        format!("Couldn't save {}: {}", item.name, e)
+                    }
+                });
+                return;
+            }
+            _ => return,
+        }
+        if let Some(panel) = self.attachments.as_mut() {
+            panel.load_selected(&self.runtime.state().attachment_store).await;
+        }
+    }
+
+    fn handle_search_key(&mut self, key: KeyEvent) {
+        let chat = &mut self.tabs[self.active].chat;
+        let query = chat.search().map(|s| s.query.clone()).unwrap_or_default();
+        match key.code {
+            KeyCode::Esc => {
+                chat.close_search();
+                self.focus = Focus::Input;
+            }
+            KeyCode::Enter | KeyCode::Up => chat.search_step(true),
+            KeyCode::Down => chat.search_step(false),
+            KeyCode::Backspace => {
+                let mut query = query;
+                query.pop();
+                chat.set_query(query);
+            }
+            KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+                chat.set_query(format!("{}{}", query, c));
+            }
+            _ => {}
+        }
+    }
+
+    async fn handle_switcher_key(&mut self, key: KeyEvent) {
+        let Some(switcher) = self.switcher.as_mut() else {
+            self.focus = Focus::Input;
+            return;
+        };
+        match key.code {
+            KeyCode::Esc => {
+                self.switcher = None;
+                self.focus = Focus::Input;
+            }
+            KeyCode::Up => switcher.move_selection(-1),
+            KeyCode::Down | KeyCode::Tab => switcher.move_selection(1),
+            KeyCode::Backspace => {
+                let mut query = switcher.query.clone();
+                query.pop();
+                switcher.set_query(query);
+            }
+            KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+                let query = format!("{}{}", switcher.query, c);
+                switcher.set_query(query);
             }
             KeyCode::Enter => {
-                if key.modifiers.contains(crossterm::event::KeyModifiers::CONTROL) {
-                    self.send_message();
+                let selected = switcher.selected_item().map(|item| item.session_id);
+                self.switcher = None;
+                self.focus = Focus::Input;
+                if let Some(id) = selected {
+                    self.open_session(id).await;
                 }
             }
-            _ => {
-                // Handle text input
-                self.input.input(key);
+            _ => {}
+        }
+    }
+
+    async fn handle_rename_key(&mut self, key: KeyEvent) {
+        match key.code {
+            KeyCode::Esc => self.focus = Focus::Input,
+            KeyCode::Backspace => {
+                self.rename.pop();
+            }
+            KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => self.rename.push(c),
+            KeyCode::Enter => {
+                self.focus = Focus::Input;
+                let title = std::mem::take(&mut self.rename);
+                if title.trim().is_empty() {
+                    return;
+                }
+                let id = self.tab().session_id;
+                match self.runtime.state().session_store.rename(id, &title).await {
+                    Ok(record) => self.tab_mut().title = record.title,
+                    Err(e) => {
+                        warn!("Failed to rename session {}: {}", id, e);
+                        self.tab_mut()
+                            .chat
+                            .push(Message::system(format!("Rename failed: {}", e)));
+                    }
+                }
             }
+            _ => {}
         }
     }
 
+    /// Open a tab on a fresh session and switch to it
+    fn open_tab(&mut self) {
+        let session_id = self.runtime.state().session_manager.create_session();
+        self.runtime.state().session_manager.set_user(session_id, &local_user());
+        let mut tab = Tab::new(session_id);
+        tab.chat.push(Message::system(self.help()));
+        self.tabs.push(tab);
+        self.select_tab(self.tabs.len() - 1);
+    }
+
+    /// Key overview shown in each new tab, following the configured bindings
+    fn help(&self) -> String {
+        let key = |action| self.keymap.label(action);
+        format!(
+            "{} sends, {} adds a line, {}/{} recall sent messages, {} searches the \
+             scrollback, {}/{} scroll and {} cancels a reply. {} opens a tab, {} closes it, \
+             {}/{} or Alt+1-9 switch, {} finds a session, {} renames this one, {} \
+             shows the dashboard, {} changes the theme and {} reads replies aloud. \
+             /attach <path> adds a file to your next message and {} lists the attachments; \
+             /up, /down [correction] and /correct <text> give feedback on the last reply; \
+             /pin <n> and /unpin <n> pin or unpin the memory it cites as [n].",
+            key(Action::Send),
+            key(Action::Newline),
+            key(Action::HistoryPrev),
+            key(Action::HistoryNext),
+            key(Action::Search),
+            key(Action::PageUp),
+            key(Action::PageDown),
+            key(Action::CancelTurn),
+            key(Action::NewTab),
+            key(Action::CloseTab),
+            key(Action::PrevTab),
+            key(Action::NextTab),
+            key(Action::Sessions),
+            key(Action::Rename),
+            key(Action::Dashboard),
+            key(Action::NextTheme),
+            key(Action::ToggleSpeech),
+            key(Action::Attachments),
+        )
+    }
+
+    /// Close the active tab, cancelling its turn; the session stays on disk
+    async fn close_tab(&mut self) {
+        let mut tab = self.tabs.remove(self.active);
+        self.release(tab.cancel()).await;
+        if self.tabs.is_empty() {
+            self.open_tab();
+        } else {
+            self.select_tab(self.active.min(self.tabs.len() - 1));
+        }
+    }
+
+    fn select_tab(&mut self, index: usize) {
+        self.active = index % self.tabs.len();
+    }
+
+    async fn open_switcher(&mut self) {
+        let open: HashSet<Uuid> = self.tabs.iter().map(|t| t.session_id).collect();
+        let mut items: Vec<SwitcherItem> = self
+            .tabs
+            .iter()
+            .map(|tab| SwitcherItem {
+                session_id: tab.session_id,
+                title: tab.title.clone(),
+                open: true,
+                message_count: tab.chat.messages().count(),
+                updated_at: tab.chat.messages().last().map(|m| m.timestamp).unwrap_or_else(chrono::Utc::now),
+            })
+            .collect();
+
+        match self.runtime.state().session_store.list().await {
+            Ok(sessions) => items.extend(
+                sessions
+                    .into_iter()
+                    .filter(|s| !open.contains(&s.id))
+                    .map(|s| SwitcherItem {
+                        session_id: s.id,
+                        title: s.title,
+                        open: false,
+                        message_count: s.message_count,
+                        updated_at: s.updated_at,
+                    }),
+            ),
+            Err(e) => warn!("Failed to list saved sessions: {}", e),
+        }
+
+        self.switcher = Some(Switcher::new(items));
+        self.focus = Focus::Switcher;
+    }
+
+    async fn open_attachments(&mut self) {
+        let mut panel = AttachmentPanel::new(self.tab().chat.messages());
+        if panel.items.is_empty() {
+            self.tab_mut()
+                .chat
+                .push(Message::system("No attachments in this conversation; add one with /attach <path>"));
+            return;
+        }
+        panel.load_selected(&self.runtime.state().attachment_store).await;
+        self.attachments = Some(panel);
+        self.focus = Focus::Attachments;
+    }
+
+    /// Whether image previews are drawn as ASCII art in the frame itself
+    pub fn graphics_in_cells(&self) -> bool {
+        self.graphics.uses_cells()
+    }
+
+    /// Draw or clear the attachment image preview through the terminal's
+    /// graphics protocol; returns true when the screen needs a full redraw
+    pub fn sync_graphics(&mut self) -> std::io::Result<bool> {
+        let want = self
+            .attachments
+            .as_ref()
+            .and_then(|panel| Some((panel.selected_image()?, panel.image_area?)))
+            .map(|((id, image), area)| (id, area, image));
+        self.graphics.sync(&mut std::io::stdout(), want)
+    }
+
+    /// Switch to the session's tab, resuming it from disk if it isn't open
+    async fn open_session(&mut self, id: Uuid) {
+        if let Some(index) = self.tabs.iter().position(|t| t.session_id == id) {
+            self.select_tab(index);
+            return;
+        }
+        // Reviving also takes the session out of the archive if it went idle
+        match self.runtime.state().revive_session(id).await {
+            Ok(record) => {
+                self.runtime.state().session_manager.set_user(id, &local_user());
+                self.tabs.push(Tab::resume(record));
+                self.select_tab(self.tabs.len() - 1);
+            }
+            Err(e) => {
+                warn!("Failed to resume session {}: {}", id, e);
+                self.tab_mut()
+                    .chat
+                    .push(Message::system(format!("Couldn't resume session: {}", e)));
+            }
+        }
+    }
+
+    /// Route whatever running turns have sent since the last tick
     pub async fn update(&mut self) -> Result<()> {
-        // Update status based on time
-        let elapsed = self.last_update.elapsed();
-        if elapsed.as_secs() % 10 == 0 {
-            self.status = format!("Session: {} | Uptime: {}s", 
-                self.session_id.to_string()[..8].to_uppercase(),
-                elapsed.as_secs());
-        }
-        
+        while let Ok(update) = self.events.try_recv() {
+            let reply = match &update.event {
+                TurnEvent::Completed(message) => Some(message.content.clone()),
+                _ => None,
+            };
+            // Events for a closed tab are dropped
+            if let Some(tab) = self.tabs.iter_mut().find(|t| t.session_id == update.session_id) {
+                // A turn that was cancelled isn't busy when its late events arrive
+                let running = tab.is_busy();
+                tab.apply(self.runtime.state(), &self.model, update).await;
+                if let Some(reply) = reply.filter(|_| running && !tab.is_busy()) {
+                    self.speak(reply);
+                }
+            }
+        }
         Ok(())
     }
 
-    fn send_message(&mut self) {
-        let input_text = self.input.lines().join(" ").trim().to_string();
-        
-        if input_text.is_empty() {
+    fn toggle_speech(&mut self) {
+        let note = if self.voice.take().is_some() {
+            self.stop_speaking();
+            "🔇 Voice replies off".to_string()
+        } else {
+            match VoiceOutput::new(&self.runtime.state().config.voice) {
+                Ok(voice) => {
+                    self.voice = Some(Arc::new(voice));
+                    "🔊 Voice replies on".to_string()
+                }
+                Err(e) => format!("Voice replies are not available: {}", e),
+            }
+        };
+        self.tab_mut().chat.push(Message::system(note));
+    }
+
+    /// Read a finished reply aloud in the background, cutting off the last one
+    fn speak(&mut self, reply: String) {
+        let Some(voice) = self.voice.clone() else { return };
+        self.stop_speaking();
+        self.speaking = Some(tokio::spawn(async move {
+            match voice.speak(&reply).await {
+                Ok(spoken) if !spoken.played => {
+                    warn!("No audio player found; reply saved to {:?}", spoken.files)
+                }
+                Ok(_) => {}
+                Err(e) => warn!("Failed to speak reply: {}", e),
+            }
+        }));
+    }
+
+    fn stop_speaking(&mut self) {
+        if let Some(task) = self.speaking.take() {
+            task.abort();
+        }
+    }
+
+    /// Whether replies are being read aloud
+    pub fn speech_on(&self) -> bool {
+        self.voice.is_some()
+    }
+
+    async fn send_message(&mut self) {
+        let draft = self.editor.lines().join("\n");
+        let command = draft.trim().strip_prefix("/attach").filter(|rest| rest.is_empty() || rest.starts_with(' '));
+        if let Some(path) = command.map(|p| p.trim().to_string()) {
+            self.editor.take_text();
+            self.attach(&path).await;
+            return;
+        }
+        if let Some(feedback) = Feedback::from_command(draft.trim()) {
+            self.editor.take_text();
+            self.give_feedback(feedback).await;
+            return;
+        }
+        if let Some((pinned, target)) = pin_command(draft.trim()) {
+            self.editor.take_text();
+            self.pin(pinned, target).await;
+            return;
+        }
+        // Keep the draft while a reply is still streaming
+        if self.tab().is_busy() || draft.trim().is_empty() {
+            return;
+        }
+        let text = self.editor.take_text();
+        let tab = &mut self.tabs[self.active];
+        tab.send(self.runtime.state(), text, &self.events_tx);
+    }
+
+    /// Upload a file for the active tab's next message, or list what is
+    /// queued when no path is given
+    async fn attach(&mut self, path: &str) {
+        if path.is_empty() {
+            let queued: Vec<String> = self.tab().pending_attachments.iter().map(|a| a.name.clone()).collect();
+            let note = if queued.is_empty() {
+                "Usage: /attach <path>".to_string()
+            } else {
+                format!("📎 Queued for your next message: {}", queued.join(", "))
+            };
+            self.tab_mut().chat.push(Message::system(note));
             return;
         }
 
-        // Add user message
-        let user_message = Message::user(input_text.clone());
-        self.messages.push(user_message);
+        let path = PathBuf::from(path.trim_matches(|c| c == '"' || c == '\''));
+        let note = match self.runtime.state().attachment_store.upload(&path).await {
+            Ok(attachment) => {
+                let note = format!(
+                    "📎 Attached {} ({}); it will be sent with your next message",
+                    attachment.name, attachment.mime_type
+                );
+                self.tab_mut().pending_attachments.push(attachment);
+                note
+            }
+            Err(e) => format!("Could not attach {}: {}", path.display(), e),
+        };
+        self.tab_mut().chat.push(Message::system(note));
+    }
 
-        // Clear input
-        self.input = TextArea::default();
+    /// Record feedback on the active tab's last reply
+    async fn give_feedback(&mut self, feedback: Feedback) {
+        let note = if feedback.rating.is_none() && feedback.correction.is_none() {
+            "Usage: /correct <what the reply should have been>".to_string()
+        } else if let Some(reply) = self.tab().last_reply() {
+            let icon = match feedback.rating {
+                Some(Rating::Up) => "👍",
+                Some(Rating::Down) => "👎",
+                None => "✏️",
+            };
+            let session_id = self.tab().session_id;
+            match self
+                .runtime
+                .state()
+                .record_feedback(session_id, reply, &local_user(), feedback)
+                .await
+            {
+                Ok(_) => format!("{} Thanks, noted for future replies", icon),
+                Err(e) => format!("Could not save feedback: {}", e),
+            }
+        } else {
+            "There is no reply to give feedback on yet".to_string()
+        };
+        self.tab_mut().chat.push(Message::system(note));
+    }
 
-        // Simulate response (in real implementation, this would call the runtime)
-        let response = Message::assistant(format!("I received your message: {}", input_text));
-        self.messages.push(response);
+    /// Pin or unpin a memory, given as its footnote number on the last
+    /// reply or its full ID
+    async fn pin(&mut self, pinned: bool, target: &str) {
+        let command = if pinned { "/pin" } else { "/unpin" };
+        let id = match target.parse::<usize>() {
+            Ok(n) => self.tab().cited_memory(n),
+            Err(_) => Uuid::parse_str(target).ok(),
+        };
+        let note = match id {
+            None if target.is_empty() => format!("Usage: {} <footnote number or memory ID>", command),
+            None => format!("The last reply has no memory [{}]", target),
+            Some(id) => match self.runtime.state().memory_store.set_pinned(id, pinned).await {
+                Ok(()) if pinned => format!("📌 Pinned {}; every reply will see it", &id.to_string()[..8]),
+                Ok(()) => format!("Unpinned {}", &id.to_string()[..8]),
+                Err(e) => format!("Could not update memory {}: {}", &id.to_string()[..8], e),
+            },
+        };
+        self.tab_mut().chat.push(Message::system(note));
+    }
 
-        self.status = "Message sent".to_string();
+    pub async fn shutdown(&mut self) {
+        self.dashboard.stop();
+        self.stop_speaking();
+        let pending: Vec<_> = self.tabs.iter_mut().filter_map(Tab::cancel).collect();
+        for request in pending {
+            self.release(Some(request)).await;
+        }
+        self.runtime.shutdown().await;
     }
-}
\ No newline at end of file
+}
+
+/// `/pin <target>` or `/unpin <target>` as whether to pin and the target
+fn pin_command(input: &str) -> Option<(bool, &str)> {
+    let (pinned, rest) = match input.strip_prefix("/pin") {
+        Some(rest) => (true, rest),
+        None => (false, input.strip_prefix("/unpin")?),
+    };
+    (rest.is_empty() || rest.starts_with(' ')).then(|| (pinned, rest.trim()))
+}
+
+/// Name recorded as the decider on approvals
+fn approver() -> String {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_else(|_| "tui".to_string())
+}
@@ -0,0 +1,183 @@
+//! Attachments panel: every file attached in the active conversation, with
+//! a preview and a way to save it
+//!
+//! Previews are loaded from the runtime's attachment store as the selection
+//! moves and kept for as long as the panel is open.
+
+use crate::preview;
+use chrono::{DateTime, Utc};
+use image::DynamicImage;
+use jamey_protocol::{ContentPart, Message};
+use jamey_runtime::attachments::AttachmentStore;
+use ratatui::layout::Rect;
+use ratatui::text::Line;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// Lines of extracted text shown for documents
+const TEXT_PREVIEW_LINES: usize = 200;
+
+/// Images are scaled down to this many pixels a side once loaded, so
+/// redrawing a preview stays cheap
+const MAX_IMAGE_SIDE: u32 = 1024;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AttachmentItem {
+    pub id: Uuid,
+    pub name: String,
+    pub mime_type: String,
+    /// When the message carrying it was sent
+    pub sent_at: DateTime<Utc>,
+}
+
+impl AttachmentItem {
+    pub fn is_image(&self) -> bool {
+        self.mime_type.starts_with("image/")
+    }
+}
+
+pub enum Preview {
+    Image(DynamicImage),
+    Text(String),
+    Unavailable(String),
+}
+
+pub struct AttachmentPanel {
+    /// Newest first
+    pub items: Vec<AttachmentItem>,
+    pub selected: usize,
+    previews: HashMap<Uuid, Preview>,
+    /// Outcome of the last save, shown under the list
+    pub notice: Option<String>,
+    /// Where the image preview was laid out in the last frame, for the
+    /// graphics protocol to draw into
+    pub image_area: Option<Rect>,
+    /// ASCII rendering of the selected image for the last preview size
+    ascii: Option<(Uuid, u16, u16, Vec<Line<'static>>)>,
+}
+
+impl AttachmentPanel {
+    pub fn new<'a>(messages: impl Iterator<Item = &'a Message>) -> Self {
+        let mut items: Vec<AttachmentItem> = messages
+            .flat_map(|message| {
+                message.parts.iter().map(move |part| match part {
+                    ContentPart::Attachment { attachment_id, name, mime_type } => AttachmentItem {
+                        id: *attachment_id,
+                        name: name.clone(),
+                        mime_type: mime_type.clone(),
+                        sent_at: message.timestamp,
+                    },
+                })
+            })
+            .collect();
+        items.reverse();
+        Self {
+            items,
+            selected: 0,
+            previews: HashMap::new(),
+            notice: None,
+            image_area: None,
+            ascii: None,
+        }
+    }
+
+    pub fn selected_item(&self) -> Option<&AttachmentItem> {
+        self.items.get(self.selected)
+    }
+
+    pub fn move_selection(&mut self, delta: isize) {
+        if self.items.is_empty() {
+            return;
+        }
+        let len = self.items.len() as isize;
+        self.selected = (self.selected as isize + delta).rem_euclid(len) as usize;
+        self.notice = None;
+    }
+
+    pub fn preview(&self) -> Option<&Preview> {
+        self.selected_item().and_then(|item| self.previews.get(&item.id))
+    }
+
+    /// The selected image as ASCII art fitted to `cols` x `rows`
+    pub fn ascii_preview(&mut self, cols: u16, rows: u16) -> Option<Vec<Line<'static>>> {
+        let id = self.selected_item()?.id;
+        if !matches!(&self.ascii, Some((cached, c, r, _)) if *cached == id && *c == cols && *r == rows) {
+            let Some(Preview::Image(image)) = self.previews.get(&id) else { return None };
+            self.ascii = Some((id, cols, rows, preview::ascii(image, cols, rows)));
+        }
+        self.ascii.as_ref().map(|(_, _, _, lines)| lines.clone())
+    }
+
+    /// The selected image and its id, for the graphics protocol
+    pub fn selected_image(&self) -> Option<(Uuid, &DynamicImage)> {
+        let item = self.selected_item()?;
+        match self.previews.get(&item.id) {
+            Some(Preview::Image(image)) => Some((item.id, image)),
+            _ => None,
+        }
+    }
+
+    /// Fetch the selected attachment's preview if it isn't loaded yet
+    pub async fn load_selected(&mut self, store: &AttachmentStore) {
+        let Some(item) = self.selected_item().cloned() else { return };
+        if self.previews.contains_key(&item.id) {
+            return;
+        }
+        let preview = if item.is_image() {
+            match store.original(item.id).await {
+                Ok(bytes) => match image::load_from_memory(&bytes) {
+                    Ok(image) => Preview::Image(image.thumbnail(MAX_IMAGE_SIDE, MAX_IMAGE_SIDE)),
+                    Err(e) => Preview::Unavailable(format!("Can't decode {}: {}", item.mime_type, e)),
+                },
+                Err(e) => Preview::Unavailable(e.to_string()),
+            }
+        } else {
+            match store.text(item.id).await {
+                Ok(text) => Preview::Text(text.lines().take(TEXT_PREVIEW_LINES).collect::<Vec<_>>().join("\n")),
+                Err(e) => Preview::Unavailable(e.to_string()),
+            }
+        };
+        self.previews.insert(item.id, preview);
+    }
+}
+
+/// Where saved attachments go: the user's downloads folder, else home
+pub fn download_dir() -> std::path::PathBuf {
+    dirs::download_dir()
+        .or_else(dirs::home_dir)
+        .unwrap_or_else(|| std::path::PathBuf::from("."))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jamey_protocol::Attachment;
+
+    fn attachment(name: &str, mime_type: &str) -> Attachment {
+        Attachment {
+            id: Uuid::new_v4(),
+            name: name.to_string(),
+            mime_type: mime_type.to_string(),
+            size_bytes: 10,
+            created_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_collects_newest_first() {
+        let notes = attachment("notes.md", "text/markdown");
+        let chart = attachment("chart.png", "image/png");
+        let messages = [
+            Message::user("first").with_attachments(&[notes]),
+            Message::assistant("no files"),
+            Message::user("second").with_attachments(&[chart]),
+        ];
+        let mut panel = AttachmentPanel::new(messages.iter());
+        let names: Vec<&str> = panel.items.iter().map(|i| i.name.as_str()).collect();
+        assert_eq!(names, ["chart.png", "notes.md"]);
+        assert!(panel.selected_item().unwrap().is_image());
+
+        panel.move_selection(-1);
+        assert_eq!(panel.selected_item().unwrap().name, "notes.md");
+    }
+}
@@ -0,0 +1,384 @@
+//! Chat pane state: transcript, streaming reply, scrollback and search
+//!
+//! Messages are rendered to wrapped lines once per pane width and cached, so
+//! only the reply being streamed is re-rendered as tokens arrive. Scrolling
+//! is measured in wrapped rows up from the bottom; at zero the view follows
+//! new output.
+
+use crate::markdown;
+use crate::theme::Theme;
+use jamey_protocol::{ContentPart, Message, Role};
+use ratatui::style::{Modifier, Style};
+use ratatui::text::{Line, Span};
+
+struct Entry {
+    message: Message,
+    /// Wrapped lines and the width they were wrapped for
+    cache: Option<(usize, Vec<Line<'static>>)>,
+}
+
+/// Scrollback search; matches are wrapped-row indices, oldest first
+#[derive(Debug, Default)]
+pub struct Search {
+    pub query: String,
+    matches: Vec<usize>,
+    /// Index into `matches` of the selected hit
+    current: Option<usize>,
+    /// Scroll to the selected hit on the next layout
+    jump: bool,
+}
+
+impl Search {
+    /// "3/7"-style position, or `None` before the first layout
+    pub fn position(&self) -> Option<(usize, usize)> {
+        self.current.map(|i| (i + 1, self.matches.len()))
+    }
+
+    pub fn match_count(&self) -> usize {
+        self.matches.len()
+    }
+}
+
+#[derive(Default)]
+pub struct ChatView {
+    entries: Vec<Entry>,
+    /// Reply streamed so far; shown after the entries while a turn runs
+    streaming: Option<String>,
+    /// Rows scrolled up from the bottom
+    offset: usize,
+    /// Row count at the last layout, to hold the view still as output grows
+    last_total: usize,
+    search: Option<Search>,
+    theme: Theme,
+}
+
+impl ChatView {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, message: Message) {
+        self.entries.push(Entry { message, cache: None });
+    }
+
+    pub fn messages(&self) -> impl Iterator<Item = &Message> {
+        self.entries.iter().map(|e| &e.message)
+    }
+
+    pub fn is_streaming(&self) -> bool {
+        self.streaming.is_some()
+    }
+
+    pub fn start_reply(&mut self) {
+        self.streaming = Some(String::new());
+    }
+
+    pub fn push_token(&mut self, token: &str) {
+        self.streaming.get_or_insert_with(String::new).push_str(token);
+    }
+
+    /// Replace the streamed text with the final reply
+    pub fn finish_reply(&mut self, message: Message) {
+        self.streaming = None;
+        self.push(message);
+    }
+
+    /// Stop streaming, keeping whatever arrived as a visibly cut-off reply
+    pub fn abort_reply(&mut self, note: &str) {
+        if let Some(partial) = self.streaming.take().filter(|p| !p.trim().is_empty()) {
+            self.push(Message::assistant(partial));
+        }
+        self.push(Message::system(note));
+    }
+
+    /// Pause streaming around tool activity, keeping the text so far
+    pub fn split_reply(&mut self) {
+        if let Some(partial) = self.streaming.take().filter(|p| !p.trim().is_empty()) {
+            self.push(Message::assistant(partial));
+            self.streaming = Some(String::new());
+        }
+    }
+
+    /// Render with `theme` from now on; cached lines are redrawn on change
+    pub fn set_theme(&mut self, theme: &Theme) {
+        if self.theme != *theme {
+            self.theme = theme.clone();
+            for entry in &mut self.entries {
+                entry.cache = None;
+            }
+        }
+    }
+
+    pub fn scroll_up(&mut self, rows: usize) {
+        self.offset += rows;
+    }
+
+    pub fn scroll_down(&mut self, rows: usize) {
+        self.offset = self.offset.saturating_sub(rows);
+    }
+
+    pub fn scroll_to_bottom(&mut self) {
+        self.offset = 0;
+    }
+
+    /// Rows scrolled up from the bottom; zero while following new output
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    pub fn search(&self) -> Option<&Search> {
+        self.search.as_ref()
+    }
+
+    pub fn open_search(&mut self) {
+        self.search.get_or_insert_with(Search::default);
+    }
+
+    pub fn close_search(&mut self) {
+        self.search = None;
+    }
+
+    /// Edit the query; the nearest match above the view is selected next layout
+    pub fn set_query(&mut self, query: String) {
+        let search = self.search.get_or_insert_with(Search::default);
+        search.query = query;
+        search.current = None;
+        search.jump = true;
+    }
+
+    /// Select the next older (`older`) or newer match
+    pub fn search_step(&mut self, older: bool) {
+        let Some(search) = self.search.as_mut() else { return };
+        let count = search.matches.len();
+        if count == 0 {
+            return;
+        }
+        search.current = Some(match (search.current, older) {
+            (None, _) => count - 1,
+            (Some(0), true) => count - 1,
+            (Some(i), true) => i - 1,
+            (Some(i), false) => (i + 1) % count,
+        });
+        search.jump = true;
+    }
+
+    /// Lay out the transcript for a `width` x `height` pane and return the
+    /// rows that are visible
+    pub fn visible(&mut self, width: usize, height: usize) -> Vec<Line<'static>> {
+        let mut rows: Vec<Line<'static>> = Vec::new();
+        for entry in &mut self.entries {
+            if entry.cache.as_ref().map(|(w, _)| *w) != Some(width) {
+                entry.cache = Some((width, render_entry(&entry.message, false, width, &self.theme)));
+            }
+            if let Some((_, lines)) = &entry.cache {
+                rows.extend(lines.iter().cloned());
+            }
+        }
+        if let Some(partial) = &self.streaming {
+            let mut reply = Message::assistant(partial.clone());
+            reply.content.push('▌');
+            rows.extend(render_entry(&reply, true, width, &self.theme));
+        }
+
+        // Keep a scrolled-back view on the same rows while output grows
+        let total = rows.len();
+        if self.offset > 0 && total > self.last_total {
+            self.offset += total - self.last_total;
+        }
+        self.last_total = total;
+
+        let max_offset = total.saturating_sub(height);
+        if let Some(search) = self.search.as_mut() {
+            update_matches(search, &rows, total.saturating_sub(self.offset + 1));
+            if let (true, Some(current)) = (search.jump, search.current) {
+                // Centre the hit in the pane
+                let row = search.matches[current];
+                self.offset = total.saturating_sub(row + 1 + height / 2);
+            }
+            search.jump = false;
+            highlight(&mut rows, search, &self.theme);
+        }
+        self.offset = self.offset.min(max_offset);
+
+        let end = total - self.offset;
+        rows.drain(end.saturating_sub(height)..end).collect()
+    }
+}
+
+/// Header plus body for one message, wrapped to `width`
+fn render_entry(message: &Message, streaming: bool, width: usize, theme: &Theme) -> Vec<Line<'static>> {
+    let (label, color) = match message.role {
+        Role::User => ("You", theme.user),
+        Role::Assistant => ("Jamey", theme.assistant),
+        Role::System => ("System", theme.system),
+        Role::Tool => ("Tool", theme.tool),
+    };
+    let mut header = vec![Span::styled(label, Style::default().fg(color).add_modifier(Modifier::BOLD))];
+    if !streaming {
+        header.push(Span::styled(
+            format!("  {}", message.timestamp.format("%H:%M")),
+            Style::default().fg(theme.muted),
+        ));
+    }
+
+    let body = match message.role {
+        Role::Assistant => markdown::render(&message.content, theme),
+        Role::User => message
+            .content
+            .lines()
+            .map(|l| Line::from(Span::styled(l.to_string(), Style::default().fg(theme.text))))
+            .collect(),
+        Role::System | Role::Tool => message
+            .content
+            .lines()
+            .map(|l| Line::from(Span::styled(l.to_string(), Style::default().fg(theme.subtle))))
+            .collect(),
+    };
+
+    let mut lines = vec![Line::from(header)];
+    lines.extend(markdown::wrap(body, width));
+    let files = message
+        .parts
+        .iter()
+        .map(|part| {
+            let ContentPart::Attachment { name, mime_type, .. } = part;
+            Line::from(vec![
+                Span::styled("📎 ", Style::default().fg(theme.accent)),
+                Span::raw(name.clone()),
+                Span::styled(format!("  {}", mime_type), Style::default().fg(theme.muted)),
+            ])
+        })
+        .collect();
+    lines.extend(markdown::wrap(files, width));
+    // Footnotes for the memories the reply was given, matching its [n] marks
+    let notes = message
+        .citations
+        .iter()
+        .enumerate()
+        .map(|(i, citation)| {
+            Line::from(vec![
+                Span::styled(format!("[{}] ", i + 1), Style::default().fg(theme.accent)),
+                Span::styled(citation.snippet.clone(), Style::default().fg(theme.subtle)),
+                Span::styled(
+                    format!("  {:.0}% · {}", citation.similarity * 100.0, &citation.memory_id.to_string()[..8]),
+                    Style::default().fg(theme.muted),
+                ),
+            ])
+        })
+        .collect();
+    lines.extend(markdown::wrap(notes, width));
+    lines.push(Line::default());
+    lines
+}
+
+/// Recompute matching rows, defaulting the selection to the last hit at or
+/// above the `bottom` visible row
+fn update_matches(search: &mut Search, rows: &[Line<'static>], bottom: usize) {
+    let query = search.query.to_lowercase();
+    search.matches = if query.is_empty() {
+        Vec::new()
+    } else {
+        rows.iter()
+            .enumerate()
+            .filter(|(_, row)| markdown::plain_text(row).to_lowercase().contains(&query))
+            .map(|(i, _)| i)
+            .collect()
+    };
+    if search.matches.is_empty() {
+        search.current = None;
+    } else if !matches!(search.current, Some(i) if i < search.matches.len()) {
+        let above = search.matches.iter().rposition(|&row| row <= bottom);
+        search.current = Some(above.unwrap_or(search.matches.len() - 1));
+    }
+}
+
+fn highlight(rows: &mut [Line<'static>], search: &Search, theme: &Theme) {
+    for (i, &row) in search.matches.iter().enumerate() {
+        let style = if Some(i) == search.current {
+            let (bg, fg) = theme.search_current;
+            Style::default().bg(bg).fg(fg)
+        } else {
+            Style::default().bg(theme.search_match)
+        };
+        rows[row].patch_style(style);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn view_with(count: usize) -> ChatView {
+        let mut view = ChatView::new();
+        for i in 0..count {
+            view.push(Message::user(format!("message {}", i)));
+        }
+        view
+    }
+
+    fn text(rows: &[Line<'static>]) -> Vec<String> {
+        rows.iter().map(markdown::plain_text).collect()
+    }
+
+    #[test]
+    fn test_follows_bottom_and_holds_scrollback() {
+        // Each message is a header, one body row and a blank row
+        let mut view = view_with(10);
+        let rows = view.visible(40, 3);
+        assert_eq!(text(&rows)[1], "message 9");
+
+        view.scroll_up(3);
+        assert_eq!(text(&view.visible(40, 3))[1], "message 8");
+
+        // New output doesn't move a scrolled-back view
+        view.push(Message::user("message 10"));
+        assert_eq!(text(&view.visible(40, 3))[1], "message 8");
+
+        view.scroll_to_bottom();
+        assert_eq!(text(&view.visible(40, 3))[1], "message 10");
+
+        // Scrolling past the top stops at the first row
+        view.scroll_up(1000);
+        assert_eq!(text(&view.visible(40, 3))[1], "message 0");
+    }
+
+    #[test]
+    fn test_streaming_reply() {
+        let mut view = view_with(1);
+        view.start_reply();
+        view.push_token("Hello");
+        view.push_token(" there");
+        assert!(text(&view.visible(40, 10)).contains(&"Hello there▌".to_string()));
+
+        let citation = jamey_protocol::Citation {
+            memory_id: uuid::Uuid::new_v4(),
+            snippet: "Greets people".to_string(),
+            similarity: 0.9,
+        };
+        view.finish_reply(Message::assistant("Hello there [1]").with_citations(vec![citation]));
+        assert!(!view.is_streaming());
+        assert_eq!(view.messages().count(), 2);
+        assert!(text(&view.visible(60, 10)).iter().any(|row| row.starts_with("[1] Greets people  90%")));
+    }
+
+    #[test]
+    fn test_search_jumps_to_matches() {
+        let mut view = view_with(20);
+        view.visible(40, 3);
+        view.set_query("MESSAGE 1".to_string());
+        // "message 1" and "message 10".."message 19"
+        let rows = view.visible(40, 3);
+        let search = view.search().unwrap();
+        assert_eq!(search.match_count(), 11);
+        assert_eq!(search.position(), Some((11, 11)));
+        assert!(text(&rows).contains(&"message 19".to_string()));
+
+        view.search_step(true);
+        let rows = view.visible(40, 3);
+        assert!(text(&rows).contains(&"message 18".to_string()));
+        assert_eq!(view.search().unwrap().position(), Some((10, 11)));
+
+        view.close_search();
+        assert!(view.search().is_none());
+    }
+}
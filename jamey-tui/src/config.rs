@@ -0,0 +1,153 @@
+//! TUI settings from `tui.toml` in the Jamey config directory
+//!
+//! ```toml
+//! editing_mode = "vim"
+//! theme = "light"
+//! graphics = "ascii"
+//! language = "es"
+//!
+//! [keys]
+//! new_tab = "ctrl+n"
+//! dashboard = ["f2", "ctrl+d"]
+//! ```
+//!
+//! Custom themes go under `[themes.<name>]`; see [`crate::theme`].
+//!
+//! A missing file means defaults; `JAMEY_TUI_CONFIG` points somewhere else.
+
+use crate::keymap::{Action, Keymap, KeymapError, Keys};
+use crate::preview::GraphicsMode;
+use crate::theme::{ThemeError, Themes};
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    #[error("Failed to read {0}: {1}")]
+    Io(PathBuf, std::io::Error),
+    #[error("Invalid {0}: {1}")]
+    Parse(PathBuf, toml::de::Error),
+    #[error("Invalid key binding in {0}: {1}")]
+    Keymap(PathBuf, KeymapError),
+    #[error("Invalid theme in {0}: {1}")]
+    Theme(PathBuf, ThemeError),
+}
+
+/// How the input box edits text
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EditingMode {
+    /// Readline-style: always inserting, with Ctrl/Alt editing keys
+    #[default]
+    Emacs,
+    /// Modal: typing inserts, Esc switches to normal mode for vim motions
+    /// and operators, `i`/`a`/`o` go back to inserting
+    Vim,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ConfigFile {
+    editing_mode: EditingMode,
+    /// Theme in use at startup
+    theme: Option<String>,
+    graphics: GraphicsMode,
+    language: Option<String>,
+    keys: HashMap<Action, Keys>,
+    themes: BTreeMap<String, BTreeMap<String, String>>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct TuiConfig {
+    pub editing_mode: EditingMode,
+    pub keymap: Keymap,
+    pub themes: Themes,
+    /// How image attachments are previewed
+    pub graphics: GraphicsMode,
+    /// Language of the interface; `JAMEY_LANGUAGE` overrides it and the
+    /// system locale is used when neither is set
+    pub language: Option<String>,
+}
+
+impl TuiConfig {
+    /// `$JAMEY_TUI_CONFIG`, else `~/.config/jamey/tui.toml`
+    pub fn path() -> PathBuf {
+        match std::env::var("JAMEY_TUI_CONFIG") {
+            Ok(path) => PathBuf::from(path),
+            Err(_) => dirs::home_dir()
+                .unwrap_or_else(|| PathBuf::from("."))
+                .join(".config")
+                .join("jamey")
+                .join("tui.toml"),
+        }
+    }
+
+    pub fn load() -> Result<Self, ConfigError> {
+        Self::load_from(&Self::path())
+    }
+
+    pub fn load_from(path: &Path) -> Result<Self, ConfigError> {
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(ConfigError::Io(path.to_path_buf(), e)),
+        };
+        Self::parse(path, &content)
+    }
+
+    fn parse(path: &Path, content: &str) -> Result<Self, ConfigError> {
+        let file: ConfigFile =
+            toml::from_str(content).map_err(|e| ConfigError::Parse(path.to_path_buf(), e))?;
+        Ok(Self {
+            editing_mode: file.editing_mode,
+            keymap: Keymap::new(&file.keys).map_err(|e| ConfigError::Keymap(path.to_path_buf(), e))?,
+            themes: Themes::new(&file.themes, file.theme.as_deref())
+                .map_err(|e| ConfigError::Theme(path.to_path_buf(), e))?,
+            graphics: file.graphics,
+            language: file.language,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+    #[test]
+    fn test_parse_config() {
+        let path = Path::new("tui.toml");
+        let config = TuiConfig::parse(
+            path,
+            r#"
+            editing_mode = "vim"
+            theme = "high-contrast"
+            language = "es"
+
+            [keys]
+            new_tab = "ctrl+n"
+            dashboard = ["f2", "ctrl+d"]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.editing_mode, EditingMode::Vim);
+        assert_eq!(config.themes.current().name, "high-contrast");
+        assert_eq!(config.language.as_deref(), Some("es"));
+        let ctrl_n = KeyEvent::new(KeyCode::Char('n'), KeyModifiers::CONTROL);
+        assert_eq!(config.keymap.action(&ctrl_n), Some(Action::NewTab));
+        assert_eq!(config.keymap.action(&KeyEvent::new(KeyCode::F(2), KeyModifiers::NONE)), Some(Action::Dashboard));
+
+        assert!(matches!(TuiConfig::parse(path, "[keys]\nteleport = \"f9\""), Err(ConfigError::Parse(..))));
+        assert!(matches!(TuiConfig::parse(path, "[keys]\nsend = \"ctrl+t\""), Err(ConfigError::Keymap(..))));
+        assert!(matches!(TuiConfig::parse(path, "theme = \"sepia\""), Err(ConfigError::Theme(..))));
+    }
+
+    #[test]
+    fn test_missing_file_uses_defaults() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let config = TuiConfig::load_from(&dir.path().join("tui.toml")).unwrap();
+        assert_eq!(config.editing_mode, EditingMode::Emacs);
+    }
+}
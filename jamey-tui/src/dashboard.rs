@@ -0,0 +1,133 @@
+//! Dashboard view: runtime metrics, connector health, usage insights and
+//! recent logs
+//!
+//! Background tasks sample the runtime's [`StatusProbe`] and the usage log on
+//! intervals and publish the latest results; the view just renders whatever
+//! they hold.
+
+use crate::logs::{LogBuffer, LogEntry};
+use chrono::{DateTime, Local, Utc};
+use jamey_runtime::analytics::Insights;
+use jamey_runtime::status::{RuntimeStatus, StatusProbe};
+use jamey_runtime::usage::UsageLog;
+use jamey_tools::connector::ConnectorInfo;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tracing::Level;
+
+/// How often the probe is sampled
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(3);
+
+/// How often insights are recomputed; they read days of log files
+const INSIGHTS_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Period the insights panels cover, in days
+pub const INSIGHTS_DAYS: i64 = 7;
+
+/// One sample of the runtime
+#[derive(Clone)]
+pub struct Snapshot {
+    pub status: RuntimeStatus,
+    pub connectors: Vec<ConnectorInfo>,
+    pub taken_at: DateTime<Local>,
+}
+
+pub struct Dashboard {
+    logs: LogBuffer,
+    snapshots: watch::Receiver<Option<Snapshot>>,
+    sampler: JoinHandle<()>,
+    insights: watch::Receiver<Option<Insights>>,
+    analyzer: JoinHandle<()>,
+    /// Least severe level shown in the log pane
+    pub min_level: Level,
+    /// Rows scrolled up from the newest log line
+    pub log_offset: usize,
+}
+
+impl Dashboard {
+    pub fn new(probe: StatusProbe, usage_log: Arc<UsageLog>, logs: LogBuffer) -> Self {
+        let (tx, snapshots) = watch::channel(None);
+        let sampler = tokio::spawn(async move {
+            let mut interval = tokio::time::interval(SAMPLE_INTERVAL);
+            loop {
+                interval.tick().await;
+                let snapshot = Snapshot {
+                    status: probe.status().await,
+                    connectors: probe.connectors().await,
+                    taken_at: Local::now(),
+                };
+                if tx.send(Some(snapshot)).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let (tx, insights) = watch::channel(None);
+        let analyzer = tokio::spawn(async move {
+            let mut interval = tokio::time::interval(INSIGHTS_INTERVAL);
+            loop {
+                interval.tick().await;
+                let since = Utc::now() - chrono::Duration::days(INSIGHTS_DAYS);
+                match Insights::gather(&usage_log, Some(since)).await {
+                    Ok(latest) => {
+                        if tx.send(Some(latest)).is_err() {
+                            break;
+                        }
+                    }
+                    Err(e) => tracing::warn!("Could not compute usage insights: {}", e),
+                }
+            }
+        });
+
+        Self {
+            logs,
+            snapshots,
+            sampler,
+            insights,
+            analyzer,
+            min_level: Level::INFO,
+            log_offset: 0,
+        }
+    }
+
+    /// Latest sample, if the first one has arrived
+    pub fn snapshot(&self) -> Option<Snapshot> {
+        self.snapshots.borrow().clone()
+    }
+
+    /// Latest insights, once the usage log has been read
+    pub fn insights(&self) -> Option<Insights> {
+        self.insights.borrow().clone()
+    }
+
+    pub fn logs(&self) -> Vec<LogEntry> {
+        self.logs.filtered(self.min_level)
+    }
+
+    /// Show one more level of detail, wrapping from DEBUG (the most the
+    /// capture layer keeps) back to ERROR
+    pub fn cycle_level(&mut self) {
+        self.min_level = match self.min_level {
+            Level::ERROR => Level::WARN,
+            Level::WARN => Level::INFO,
+            Level::INFO => Level::DEBUG,
+            _ => Level::ERROR,
+        };
+        self.log_offset = 0;
+    }
+
+    pub fn scroll_logs(&mut self, up: bool, rows: usize) {
+        self.log_offset = if up {
+            self.log_offset + rows
+        } else {
+            self.log_offset.saturating_sub(rows)
+        };
+    }
+
+    pub fn stop(&self) {
+        self.sampler.abort();
+        self.analyzer.abort();
+    }
+}
@@ -0,0 +1,488 @@
+//! The message input box
+//!
+//! Wraps a [`TextArea`] with what a chat prompt needs on top of plain
+//! editing: recall of sent messages, an Emacs-style kill ring, and an
+//! optional vim mode. Keys the [`Keymap`](crate::keymap::Keymap) doesn't
+//! claim end up in [`Editor::input`].
+
+use crate::config::EditingMode;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::widgets::{Block, Widget};
+use std::collections::VecDeque;
+use tui_textarea::{CursorMove, TextArea};
+
+/// Kills remembered for yank-pop
+const KILL_RING_SIZE: usize = 20;
+/// Sent messages remembered for recall
+const HISTORY_SIZE: usize = 200;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VimState {
+    Normal,
+    Insert,
+}
+
+/// Killed text; whole lines (`dd`, `yy`) paste as lines in vim mode
+#[derive(Debug, Clone, PartialEq)]
+struct Kill {
+    text: String,
+    linewise: bool,
+}
+
+/// The last yank, so yank-pop can swap it for an older kill
+struct Yanked {
+    ring_index: usize,
+    edits: usize,
+}
+
+pub struct Editor {
+    textarea: TextArea<'static>,
+    mode: EditingMode,
+    vim: VimState,
+    /// Operator (`d`, `c`, `y`, `g`) waiting for its motion
+    pending: Option<char>,
+    kill_ring: VecDeque<Kill>,
+    yanked: Option<Yanked>,
+    history: Vec<String>,
+    /// Position in `history` while recalling; `None` when editing a draft
+    history_index: Option<usize>,
+    /// What was typed before recall started, restored past the newest entry
+    draft: Vec<String>,
+}
+
+impl Editor {
+    pub fn new(mode: EditingMode) -> Self {
+        Self {
+            textarea: new_textarea(Vec::new()),
+            mode,
+            vim: VimState::Insert,
+            pending: None,
+            kill_ring: VecDeque::new(),
+            yanked: None,
+            history: Vec::new(),
+            history_index: None,
+            draft: Vec::new(),
+        }
+    }
+
+    pub fn lines(&self) -> &[String] {
+        self.textarea.lines()
+    }
+
+    pub fn set_block(&mut self, block: Block<'static>) {
+        self.textarea.set_block(block);
+    }
+
+    pub fn widget(&self) -> impl Widget + '_ {
+        self.textarea.widget()
+    }
+
+    /// "NORMAL"/"INSERT" in vim mode, with any pending operator
+    pub fn mode_label(&self) -> Option<String> {
+        if self.mode != EditingMode::Vim {
+            return None;
+        }
+        let mut label = match self.vim {
+            VimState::Normal => "NORMAL".to_string(),
+            VimState::Insert => "INSERT".to_string(),
+        };
+        if let Some(op) = self.pending {
+            label.push(' ');
+            label.push(op);
+        }
+        Some(label)
+    }
+
+    /// Whether the editor takes `key` ahead of the keymap: in vim normal
+    /// mode plain keys are commands, and Esc leaves insert mode
+    pub fn wants(&self, key: &KeyEvent) -> bool {
+        if self.mode != EditingMode::Vim {
+            return false;
+        }
+        let plain = !key.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT);
+        match self.vim {
+            VimState::Insert => key.code == KeyCode::Esc,
+            VimState::Normal if self.pending.is_some() => true,
+            VimState::Normal => plain && matches!(key.code, KeyCode::Char(_) | KeyCode::Backspace),
+        }
+    }
+
+    pub fn input(&mut self, key: KeyEvent) {
+        self.yanked = None;
+        if self.mode == EditingMode::Vim {
+            match self.vim {
+                VimState::Insert if key.code == KeyCode::Esc => {
+                    self.vim = VimState::Normal;
+                    // Like vim, leaving insert mode steps back onto the last character
+                    if self.textarea.cursor().1 > 0 {
+                        self.textarea.move_cursor(CursorMove::Back);
+                    }
+                    return;
+                }
+                VimState::Normal => {
+                    self.normal_key(key);
+                    return;
+                }
+                VimState::Insert => {}
+            }
+        }
+        self.textarea.input(key);
+    }
+
+    pub fn insert_newline(&mut self) {
+        self.yanked = None;
+        self.textarea.insert_newline();
+    }
+
+    /// Hand over the text for sending; it goes into the history and the box
+    /// is cleared
+    pub fn take_text(&mut self) -> String {
+        let text = self.lines().join("\n").trim().to_string();
+        if !text.is_empty() && self.history.last() != Some(&text) {
+            self.history.push(text.clone());
+            if self.history.len() > HISTORY_SIZE {
+                self.history.remove(0);
+            }
+        }
+        self.textarea = new_textarea(Vec::new());
+        self.history_index = None;
+        self.draft.clear();
+        self.yanked = None;
+        self.pending = None;
+        self.vim = VimState::Insert;
+        text
+    }
+
+    /// Move up a line, or recall the previous message from the first line
+    pub fn history_prev(&mut self) {
+        self.yanked = None;
+        if self.textarea.cursor().0 > 0 {
+            self.textarea.move_cursor(CursorMove::Up);
+            return;
+        }
+        let index = match self.history_index {
+            Some(0) => return,
+            Some(index) => index - 1,
+            None if self.history.is_empty() => return,
+            None => {
+                self.draft = self.lines().to_vec();
+                self.history.len() - 1
+            }
+        };
+        self.history_index = Some(index);
+        self.replace_lines(self.history[index].lines().map(str::to_string).collect());
+    }
+
+    /// Move down a line, or recall the next message from the last line
+    pub fn history_next(&mut self) {
+        self.yanked = None;
+        if self.textarea.cursor().0 + 1 < self.lines().len() {
+            self.textarea.move_cursor(CursorMove::Down);
+            return;
+        }
+        let Some(index) = self.history_index else { return };
+        if index + 1 < self.history.len() {
+            self.history_index = Some(index + 1);
+            self.replace_lines(self.history[index + 1].lines().map(str::to_string).collect());
+        } else {
+            self.history_index = None;
+            let draft = std::mem::take(&mut self.draft);
+            self.replace_lines(draft);
+        }
+    }
+
+    pub fn kill_to_end(&mut self) {
+        self.kill_with(TextArea::delete_line_by_end);
+    }
+
+    pub fn kill_to_start(&mut self) {
+        self.kill_with(TextArea::delete_line_by_head);
+    }
+
+    pub fn kill_word_back(&mut self) {
+        self.kill_with(TextArea::delete_word);
+    }
+
+    /// Insert the newest kill
+    pub fn yank(&mut self) {
+        let Some(kill) = self.kill_ring.front().cloned() else { return };
+        let edits = self.insert_kill(&kill);
+        self.yanked = Some(Yanked { ring_index: 0, edits });
+    }
+
+    /// Straight after a yank, swap the yanked text for the next older kill
+    pub fn yank_pop(&mut self) {
+        let Some(yanked) = self.yanked.take() else { return };
+        for _ in 0..yanked.edits {
+            self.textarea.undo();
+        }
+        let ring_index = (yanked.ring_index + 1) % self.kill_ring.len();
+        let kill = self.kill_ring[ring_index].clone();
+        let edits = self.insert_kill(&kill);
+        self.yanked = Some(Yanked { ring_index, edits });
+    }
+
+    /// Yank at the cursor; a whole-line kill brings its line break along
+    fn insert_kill(&mut self, kill: &Kill) -> usize {
+        if kill.linewise {
+            self.insert_text(&format!("{}\n", kill.text))
+        } else {
+            self.insert_text(&kill.text)
+        }
+    }
+
+    /// Run a textarea deletion and keep what it removed
+    fn kill_with(&mut self, delete: impl FnOnce(&mut TextArea<'static>) -> bool) {
+        self.yanked = None;
+        self.textarea.set_yank_text("");
+        if delete(&mut self.textarea) {
+            let text = self.textarea.yank_text().to_string();
+            self.push_kill(text, false);
+        }
+    }
+
+    fn push_kill(&mut self, text: String, linewise: bool) {
+        if text.is_empty() && !linewise {
+            return;
+        }
+        self.kill_ring.push_front(Kill { text, linewise });
+        self.kill_ring.truncate(KILL_RING_SIZE);
+    }
+
+    /// Insert text that may span lines; returns the number of undo steps
+    fn insert_text(&mut self, text: &str) -> usize {
+        let mut edits = 0;
+        for (i, line) in text.split('\n').enumerate() {
+            if i > 0 {
+                self.textarea.insert_newline();
+                edits += 1;
+            }
+            if self.textarea.insert_str(line) {
+                edits += 1;
+            }
+        }
+        edits
+    }
+
+    fn replace_lines(&mut self, lines: Vec<String>) {
+        self.textarea = new_textarea(lines);
+        self.textarea.move_cursor(CursorMove::Bottom);
+        self.textarea.move_cursor(CursorMove::End);
+    }
+
+    fn current_line(&self) -> String {
+        self.lines()[self.textarea.cursor().0].clone()
+    }
+
+    /// Remove the cursor's line entirely and keep it as a linewise kill
+    fn delete_line(&mut self) {
+        let line = self.current_line();
+        let (row, _) = self.textarea.cursor();
+        self.textarea.move_cursor(CursorMove::Head);
+        self.textarea.delete_str(0, usize::MAX);
+        if row + 1 < self.lines().len() {
+            self.textarea.delete_next_char();
+        } else {
+            self.textarea.delete_newline();
+            self.textarea.move_cursor(CursorMove::Head);
+        }
+        self.push_kill(line, true);
+    }
+
+    /// `p`/`P`: linewise kills go below/above the line, others after/at the cursor
+    fn put(&mut self, after: bool) {
+        let Some(kill) = self.kill_ring.front().cloned() else { return };
+        if kill.linewise {
+            if after {
+                self.textarea.move_cursor(CursorMove::End);
+                self.textarea.insert_newline();
+            } else {
+                self.textarea.move_cursor(CursorMove::Head);
+                self.textarea.insert_newline();
+                self.textarea.move_cursor(CursorMove::Up);
+            }
+            self.insert_text(&kill.text);
+            self.textarea.move_cursor(CursorMove::Head);
+        } else {
+            if after && self.textarea.cursor().1 < self.current_line().chars().count() {
+                self.textarea.move_cursor(CursorMove::Forward);
+            }
+            self.insert_text(&kill.text);
+        }
+    }
+
+    fn normal_key(&mut self, key: KeyEvent) {
+        let KeyCode::Char(c) = key.code else {
+            self.pending = None;
+            match key.code {
+                KeyCode::Backspace => self.textarea.move_cursor(CursorMove::Back),
+                _ => {
+                    self.textarea.input(key);
+                }
+            }
+            return;
+        };
+
+        if let Some(op) = self.pending.take() {
+            self.operator(op, c);
+            return;
+        }
+
+        match c {
+            'h' => self.textarea.move_cursor(CursorMove::Back),
+            'l' => self.textarea.move_cursor(CursorMove::Forward),
+            'k' => self.history_prev(),
+            'j' => self.history_next(),
+            'w' => self.textarea.move_cursor(CursorMove::WordForward),
+            'b' => self.textarea.move_cursor(CursorMove::WordBack),
+            '0' | '^' => self.textarea.move_cursor(CursorMove::Head),
+            '$' => self.textarea.move_cursor(CursorMove::End),
+            'G' => self.textarea.move_cursor(CursorMove::Bottom),
+            'i' => self.vim = VimState::Insert,
+            'a' => {
+                if self.textarea.cursor().1 < self.current_line().chars().count() {
+                    self.textarea.move_cursor(CursorMove::Forward);
+                }
+                self.vim = VimState::Insert;
+            }
+            'I' => {
+                self.textarea.move_cursor(CursorMove::Head);
+                self.vim = VimState::Insert;
+            }
+            'A' => {
+                self.textarea.move_cursor(CursorMove::End);
+                self.vim = VimState::Insert;
+            }
+            'o' => {
+                self.textarea.move_cursor(CursorMove::End);
+                self.textarea.insert_newline();
+                self.vim = VimState::Insert;
+            }
+            'O' => {
+                self.textarea.move_cursor(CursorMove::Head);
+                self.textarea.insert_newline();
+                self.textarea.move_cursor(CursorMove::Up);
+                self.vim = VimState::Insert;
+            }
+            'x' => {
+                let col = self.textarea.cursor().1;
+                self.kill_with(|t| t.delete_str(col, 1));
+            }
+            'D' => self.kill_to_end(),
+            'C' => {
+                self.kill_to_end();
+                self.vim = VimState::Insert;
+            }
+            'p' => self.put(true),
+            'P' => self.put(false),
+            'u' => {
+                self.textarea.undo();
+            }
+            'd' | 'c' | 'y' | 'g' => self.pending = Some(c),
+            _ => {}
+        }
+    }
+
+    /// Second key of `dd`, `dw`, `cw`, `yy`, `gg` and friends
+    fn operator(&mut self, op: char, motion: char) {
+        match (op, motion) {
+            ('g', 'g') => self.textarea.move_cursor(CursorMove::Top),
+            ('d', 'd') => self.delete_line(),
+            ('y', 'y') => {
+                let line = self.current_line();
+                self.push_kill(line, true);
+            }
+            ('c', 'c') => {
+                self.textarea.move_cursor(CursorMove::Head);
+                self.kill_to_end();
+                self.vim = VimState::Insert;
+            }
+            ('d' | 'c', 'w') => self.kill_with(TextArea::delete_next_word),
+            ('d' | 'c', 'b') => self.kill_word_back(),
+            ('d' | 'c', '$') => self.kill_to_end(),
+            ('d' | 'c', '0') => self.kill_to_start(),
+            _ => {}
+        }
+        if op == 'c' && motion != 'c' {
+            self.vim = VimState::Insert;
+        }
+    }
+}
+
+fn new_textarea(lines: Vec<String>) -> TextArea<'static> {
+    let mut textarea = TextArea::new(lines);
+    textarea.set_placeholder_text("Message Jamey");
+    textarea
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn type_str(editor: &mut Editor, text: &str) {
+        for c in text.chars() {
+            editor.input(KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE));
+        }
+    }
+
+    #[test]
+    fn test_kill_ring_and_yank_pop() {
+        let mut editor = Editor::new(EditingMode::Emacs);
+        type_str(&mut editor, "first");
+        editor.kill_to_start();
+        type_str(&mut editor, "second");
+        editor.kill_word_back();
+        assert_eq!(editor.lines(), [""]);
+
+        editor.yank();
+        assert_eq!(editor.lines(), ["second"]);
+        editor.yank_pop();
+        assert_eq!(editor.lines(), ["first"]);
+        editor.yank_pop();
+        assert_eq!(editor.lines(), ["second"]);
+
+        // Anything else in between ends the yank
+        type_str(&mut editor, "!");
+        editor.yank_pop();
+        assert_eq!(editor.lines(), ["second!"]);
+    }
+
+    #[test]
+    fn test_history_recall() {
+        let mut editor = Editor::new(EditingMode::Emacs);
+        type_str(&mut editor, "one");
+        assert_eq!(editor.take_text(), "one");
+        type_str(&mut editor, "two");
+        editor.take_text();
+        type_str(&mut editor, "draft");
+
+        editor.history_prev();
+        assert_eq!(editor.lines(), ["two"]);
+        editor.history_prev();
+        assert_eq!(editor.lines(), ["one"]);
+        editor.history_prev();
+        assert_eq!(editor.lines(), ["one"]);
+        editor.history_next();
+        editor.history_next();
+        assert_eq!(editor.lines(), ["draft"]);
+    }
+
+    #[test]
+    fn test_vim_delete_and_put_line() {
+        let mut editor = Editor::new(EditingMode::Vim);
+        type_str(&mut editor, "alpha");
+        editor.insert_newline();
+        type_str(&mut editor, "beta");
+
+        let esc = KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE);
+        assert!(editor.wants(&esc));
+        editor.input(esc);
+        assert_eq!(editor.mode_label().as_deref(), Some("NORMAL"));
+
+        // dd on "beta", back up to "alpha", put it above
+        type_str(&mut editor, "ddggP");
+        assert_eq!(editor.lines(), ["beta", "alpha"]);
+        type_str(&mut editor, "A!");
+        assert_eq!(editor.lines(), ["beta!", "alpha"]);
+    }
+}
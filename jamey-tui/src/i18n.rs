@@ -0,0 +1,50 @@
+//! Localized TUI text
+//!
+//! Strings live in `locales/<language>.ftl`. The language is set at startup
+//! from `JAMEY_LANGUAGE`, `language` in `tui.toml` or the system locale;
+//! see [`jamey_runtime::i18n`].
+
+use jamey_runtime::i18n::{self, Localizer};
+use std::sync::OnceLock;
+
+/// Languages the TUI has strings for; English must be complete
+pub const RESOURCES: &[(&str, &str)] = &[
+    ("en", include_str!("../locales/en.ftl")),
+    ("es", include_str!("../locales/es.ftl")),
+];
+
+static LOCALIZER: OnceLock<Localizer> = OnceLock::new();
+
+/// Pick the language, with `configured` from `tui.toml`; later calls
+/// change nothing
+pub fn init(configured: Option<&str>) {
+    LOCALIZER.get_or_init(|| Localizer::new(&i18n::requested_language(configured), RESOURCES));
+}
+
+pub fn localizer() -> &'static Localizer {
+    LOCALIZER.get_or_init(|| Localizer::new(&i18n::requested_language(None), RESOURCES))
+}
+
+/// A TUI string, with `"name" => value` arguments
+macro_rules! t {
+    ($($arg:tt)+) => {
+        jamey_runtime::tr!($crate::i18n::localizer(), $($arg)+)
+    };
+}
+pub(crate) use t;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_translations_are_complete() {
+        let english = i18n::message_ids(RESOURCES[0].1);
+        assert!(!english.is_empty());
+        for (language, source) in &RESOURCES[1..] {
+            let translated = i18n::message_ids(source);
+            let missing: Vec<_> = english.iter().filter(|id| !translated.contains(id)).collect();
+            assert!(missing.is_empty(), "{} lacks {:?}", language, missing);
+        }
+    }
+}
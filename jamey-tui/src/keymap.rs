@@ -0,0 +1,353 @@
+//! Key bindings for the chat input
+//!
+//! Every action has default keys; `[keys]` in `tui.toml` replaces the keys
+//! for the actions it names and leaves the rest alone. Keys are written like
+//! `"ctrl+t"`, `"alt+enter"`, `"pageup"` or `"f2"`.
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum KeymapError {
+    #[error("Unknown key \"{0}\"")]
+    UnknownKey(String),
+    #[error("Unknown modifier \"{modifier}\" in \"{key}\"")]
+    UnknownModifier { key: String, modifier: String },
+    #[error("{key} is bound to both {first} and {second}")]
+    Conflict { key: String, first: Action, second: Action },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    Send,
+    Newline,
+    CancelTurn,
+    Search,
+    NewTab,
+    CloseTab,
+    NextTab,
+    PrevTab,
+    Sessions,
+    Rename,
+    /// Browse, preview and save this conversation's attachments
+    Attachments,
+    Dashboard,
+    /// Switch to the next colour theme
+    NextTheme,
+    /// Read replies aloud, or stop
+    ToggleSpeech,
+    ScrollUp,
+    ScrollDown,
+    PageUp,
+    PageDown,
+    ScrollBottom,
+    /// Older sent message, once the cursor is on the first line
+    HistoryPrev,
+    /// Newer sent message, once the cursor is on the last line
+    HistoryNext,
+    KillToEnd,
+    KillToStart,
+    KillWordBack,
+    Yank,
+    /// Replace the text just yanked with the next older kill
+    YankPop,
+    Quit,
+}
+
+impl Action {
+    const ALL: [Action; 27] = [
+        Action::Send,
+        Action::Newline,
+        Action::CancelTurn,
+        Action::Search,
+        Action::NewTab,
+        Action::CloseTab,
+        Action::NextTab,
+        Action::PrevTab,
+        Action::Sessions,
+        Action::Rename,
+        Action::Attachments,
+        Action::Dashboard,
+        Action::NextTheme,
+        Action::ToggleSpeech,
+        Action::ScrollUp,
+        Action::ScrollDown,
+        Action::PageUp,
+        Action::PageDown,
+        Action::ScrollBottom,
+        Action::HistoryPrev,
+        Action::HistoryNext,
+        Action::KillToEnd,
+        Action::KillToStart,
+        Action::KillWordBack,
+        Action::Yank,
+        Action::YankPop,
+        Action::Quit,
+    ];
+
+    fn default_keys(self) -> &'static [&'static str] {
+        match self {
+            Action::Send => &["enter"],
+            Action::Newline => &["alt+enter"],
+            Action::CancelTurn => &["esc"],
+            Action::Search => &["ctrl+f"],
+            Action::NewTab => &["ctrl+t"],
+            Action::CloseTab => &["ctrl+w"],
+            Action::NextTab => &["alt+right"],
+            Action::PrevTab => &["alt+left"],
+            Action::Sessions => &["ctrl+p"],
+            Action::Rename => &["ctrl+r"],
+            Action::Attachments => &["ctrl+o"],
+            Action::Dashboard => &["ctrl+d"],
+            Action::NextTheme => &["f6"],
+            Action::ToggleSpeech => &["f7"],
+            Action::ScrollUp => &["ctrl+up"],
+            Action::ScrollDown => &["ctrl+down"],
+            Action::PageUp => &["pageup"],
+            Action::PageDown => &["pagedown"],
+            Action::ScrollBottom => &["ctrl+end"],
+            Action::HistoryPrev => &["up"],
+            Action::HistoryNext => &["down"],
+            Action::KillToEnd => &["ctrl+k"],
+            Action::KillToStart => &["ctrl+u"],
+            Action::KillWordBack => &["alt+backspace"],
+            Action::Yank => &["ctrl+y"],
+            Action::YankPop => &["alt+y"],
+            Action::Quit => &["ctrl+c"],
+        }
+    }
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Same spelling as the config file
+        let name = serde_json::to_value(self).ok();
+        write!(f, "{}", name.as_ref().and_then(|v| v.as_str()).unwrap_or("?"))
+    }
+}
+
+/// A key with its modifiers, as written in the config
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeyBinding {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl KeyBinding {
+    /// Shift is folded into the character for printable keys, so `ctrl+T`
+    /// and `ctrl+shift+t` are the same binding
+    fn normalized(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        match code {
+            KeyCode::Char(c) => Self {
+                code: KeyCode::Char(c.to_ascii_lowercase()),
+                modifiers: modifiers - KeyModifiers::SHIFT,
+            },
+            KeyCode::BackTab => Self { code, modifiers: modifiers - KeyModifiers::SHIFT },
+            _ => Self { code, modifiers },
+        }
+    }
+}
+
+impl From<&KeyEvent> for KeyBinding {
+    fn from(key: &KeyEvent) -> Self {
+        Self::normalized(key.code, key.modifiers)
+    }
+}
+
+impl FromStr for KeyBinding {
+    type Err = KeymapError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lower = s.trim().to_lowercase();
+        let mut parts: Vec<&str> = lower.split('+').collect();
+        // "ctrl++" binds the plus key
+        let key = match parts.pop() {
+            Some("") if lower.ends_with("++") => {
+                parts.pop();
+                "+"
+            }
+            Some(key) => key,
+            None => "",
+        };
+
+        let mut modifiers = KeyModifiers::NONE;
+        for modifier in parts {
+            modifiers |= match modifier {
+                "ctrl" | "control" | "c" => KeyModifiers::CONTROL,
+                "alt" | "meta" | "m" => KeyModifiers::ALT,
+                "shift" | "s" => KeyModifiers::SHIFT,
+                other => {
+                    return Err(KeymapError::UnknownModifier {
+                        key: s.to_string(),
+                        modifier: other.to_string(),
+                    })
+                }
+            };
+        }
+
+        let code = match key {
+            "enter" | "return" => KeyCode::Enter,
+            "esc" | "escape" => KeyCode::Esc,
+            "tab" => KeyCode::Tab,
+            "backtab" => KeyCode::BackTab,
+            "backspace" => KeyCode::Backspace,
+            "delete" | "del" => KeyCode::Delete,
+            "insert" => KeyCode::Insert,
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            "pageup" => KeyCode::PageUp,
+            "pagedown" => KeyCode::PageDown,
+            "space" => KeyCode::Char(' '),
+            f if f.len() > 1 && f.starts_with('f') => match f[1..].parse::<u8>() {
+                Ok(n @ 1..=12) => KeyCode::F(n),
+                _ => return Err(KeymapError::UnknownKey(s.to_string())),
+            },
+            c if c.chars().count() == 1 => KeyCode::Char(c.chars().next().unwrap_or_default()),
+            _ => return Err(KeymapError::UnknownKey(s.to_string())),
+        };
+        Ok(Self::normalized(code, modifiers))
+    }
+}
+
+impl fmt::Display for KeyBinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (modifier, name) in [
+            (KeyModifiers::CONTROL, "Ctrl+"),
+            (KeyModifiers::ALT, "Alt+"),
+            (KeyModifiers::SHIFT, "Shift+"),
+        ] {
+            if self.modifiers.contains(modifier) {
+                write!(f, "{}", name)?;
+            }
+        }
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "Space"),
+            KeyCode::Char(c) => write!(f, "{}", c.to_ascii_uppercase()),
+            KeyCode::F(n) => write!(f, "F{}", n),
+            KeyCode::PageUp => write!(f, "PgUp"),
+            KeyCode::PageDown => write!(f, "PgDn"),
+            KeyCode::Up => write!(f, "↑"),
+            KeyCode::Down => write!(f, "↓"),
+            KeyCode::Left => write!(f, "←"),
+            KeyCode::Right => write!(f, "→"),
+            other => write!(f, "{:?}", other),
+        }
+    }
+}
+
+/// One key or several, in the config file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Keys {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl Keys {
+    fn as_slice(&self) -> &[String] {
+        match self {
+            Keys::One(key) => std::slice::from_ref(key),
+            Keys::Many(keys) => keys,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    bindings: HashMap<KeyBinding, Action>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self::new(&HashMap::new()).expect("default key bindings parse and don't overlap")
+    }
+}
+
+impl Keymap {
+    /// Defaults with `overrides` replacing the keys of the actions they name;
+    /// an empty list unbinds the action
+    pub fn new(overrides: &HashMap<Action, Keys>) -> Result<Self, KeymapError> {
+        let mut bindings: HashMap<KeyBinding, Action> = HashMap::new();
+        for action in Action::ALL {
+            let keys: Vec<String> = match overrides.get(&action) {
+                Some(keys) => keys.as_slice().to_vec(),
+                None => action.default_keys().iter().map(|k| k.to_string()).collect(),
+            };
+            for key in keys {
+                let binding: KeyBinding = key.parse()?;
+                if let Some(first) = bindings.insert(binding, action) {
+                    return Err(KeymapError::Conflict {
+                        key: binding.to_string(),
+                        first,
+                        second: action,
+                    });
+                }
+            }
+        }
+        Ok(Self { bindings })
+    }
+
+    pub fn action(&self, key: &KeyEvent) -> Option<Action> {
+        self.bindings.get(&KeyBinding::from(key)).copied()
+    }
+
+    /// First key bound to `action`, for hints; "unbound" when there is none
+    pub fn label(&self, action: Action) -> String {
+        let mut keys: Vec<String> = self
+            .bindings
+            .iter()
+            .filter(|(_, a)| **a == action)
+            .map(|(key, _)| key.to_string())
+            .collect();
+        keys.sort_by_key(|k| k.len());
+        keys.into_iter().next().unwrap_or_else(|| "unbound".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(code, modifiers)
+    }
+
+    #[test]
+    fn test_parse_key_binding() {
+        let binding: KeyBinding = "Ctrl+Shift+T".parse().unwrap();
+        assert_eq!(binding, KeyBinding { code: KeyCode::Char('t'), modifiers: KeyModifiers::CONTROL });
+        assert_eq!("alt+enter".parse::<KeyBinding>().unwrap().to_string(), "Alt+Enter");
+        assert_eq!("f5".parse::<KeyBinding>().unwrap().code, KeyCode::F(5));
+        assert_eq!("ctrl++".parse::<KeyBinding>().unwrap().code, KeyCode::Char('+'));
+        assert!(matches!("hyper+x".parse::<KeyBinding>(), Err(KeymapError::UnknownModifier { .. })));
+        assert!(matches!("ctrl+banana".parse::<KeyBinding>(), Err(KeymapError::UnknownKey(_))));
+    }
+
+    #[test]
+    fn test_overrides_and_conflicts() {
+        let keymap = Keymap::default();
+        assert_eq!(keymap.action(&key(KeyCode::Char('t'), KeyModifiers::CONTROL)), Some(Action::NewTab));
+        assert_eq!(keymap.action(&key(KeyCode::Char('T'), KeyModifiers::CONTROL | KeyModifiers::SHIFT)), Some(Action::NewTab));
+
+        let overrides = HashMap::from([
+            (Action::NewTab, Keys::Many(vec!["ctrl+n".to_string(), "f2".to_string()])),
+            (Action::Dashboard, Keys::Many(Vec::new())),
+        ]);
+        let keymap = Keymap::new(&overrides).unwrap();
+        assert_eq!(keymap.action(&key(KeyCode::Char('t'), KeyModifiers::CONTROL)), None);
+        assert_eq!(keymap.action(&key(KeyCode::F(2), KeyModifiers::NONE)), Some(Action::NewTab));
+        assert_eq!(keymap.label(Action::Dashboard), "unbound");
+
+        let clash = HashMap::from([(Action::Search, Keys::One("ctrl+t".to_string()))]);
+        assert!(matches!(Keymap::new(&clash), Err(KeymapError::Conflict { .. })));
+    }
+}
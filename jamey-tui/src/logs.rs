@@ -0,0 +1,114 @@
+//! Recent tracing events, kept in memory for the dashboard
+//!
+//! [`LogCapture`] is a `tracing_subscriber` layer installed next to the file
+//! logger; it keeps the newest events in a ring buffer the dashboard reads.
+
+use chrono::{DateTime, Local};
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::sync::{Arc, Mutex};
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::layer::{Context, Layer};
+
+/// Events kept before the oldest are dropped
+const CAPACITY: usize = 1000;
+
+#[derive(Debug, Clone)]
+pub struct LogEntry {
+    pub time: DateTime<Local>,
+    pub level: Level,
+    pub target: String,
+    pub message: String,
+}
+
+/// Shared ring buffer of recent events
+#[derive(Clone, Default)]
+pub struct LogBuffer {
+    entries: Arc<Mutex<VecDeque<LogEntry>>>,
+}
+
+impl LogBuffer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn push(&self, entry: LogEntry) {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        if entries.len() == CAPACITY {
+            entries.pop_front();
+        }
+        entries.push_back(entry);
+    }
+
+    /// Entries at `min_level` or more severe, oldest first
+    pub fn filtered(&self, min_level: Level) -> Vec<LogEntry> {
+        let entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        // `Level` orders TRACE as the greatest, so "at least as severe" is <=
+        entries.iter().filter(|e| e.level <= min_level).cloned().collect()
+    }
+}
+
+/// Layer that copies every event into a [`LogBuffer`]
+pub struct LogCapture {
+    buffer: LogBuffer,
+}
+
+impl LogCapture {
+    pub fn new(buffer: LogBuffer) -> Self {
+        Self { buffer }
+    }
+}
+
+impl<S: Subscriber> Layer<S> for LogCapture {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let mut visitor = MessageVisitor::default();
+        event.record(&mut visitor);
+        self.buffer.push(LogEntry {
+            time: Local::now(),
+            level: *event.metadata().level(),
+            target: event.metadata().target().to_string(),
+            message: visitor.message,
+        });
+    }
+}
+
+/// Renders the `message` field followed by any others as `key=value`
+#[derive(Default)]
+struct MessageVisitor {
+    message: String,
+}
+
+impl Visit for MessageVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            let fields = std::mem::take(&mut self.message);
+            let _ = write!(self.message, "{:?}{}", value, fields);
+        } else {
+            let _ = write!(self.message, " {}={:?}", field.name(), value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tracing_subscriber::prelude::*;
+
+    #[test]
+    fn test_capture_and_filter() {
+        let buffer = LogBuffer::new();
+        let subscriber = tracing_subscriber::registry().with(LogCapture::new(buffer.clone()));
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!(session = 3, "Turn started");
+            tracing::warn!("Provider slow");
+            tracing::debug!("Token received");
+        });
+
+        assert_eq!(buffer.filtered(Level::TRACE).len(), 3);
+        let warnings = buffer.filtered(Level::WARN);
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].message, "Provider slow");
+        assert_eq!(buffer.filtered(Level::INFO)[0].message, "Turn started session=3");
+    }
+}
@@ -2,40 +2,68 @@
 //! 
 //! Terminal user interface for interacting with Jamey
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind},
+    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyEventKind},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
-use ratatui::{
-    backend::CrosstermBackend,
-    layout::{Constraint, Direction, Layout},
-    style::{Color, Modifier, Style},
-    text::{Line, Span},
-    widgets::{Block, Borders, List, ListItem, Paragraph, Wrap},
-    Frame, Terminal,
-};
+use jamey_runtime::{config::RuntimeConfig, Runtime};
+use ratatui::{backend::CrosstermBackend, Terminal};
 use std::io;
+use std::sync::Mutex;
 use std::time::Duration;
-use tokio::time::sleep;
-use tracing::{info, error, debug};
-use tracing_subscriber::FmtSubscriber;
+use tracing::error;
+use tracing_subscriber::filter::LevelFilter;
+use tracing_subscriber::prelude::*;
 
 mod app;
+mod attachments;
+mod chat;
+mod config;
+mod dashboard;
+mod editor;
+mod i18n;
+mod keymap;
+mod logs;
+mod markdown;
+mod preview;
+mod switcher;
+mod tab;
+mod theme;
 mod ui;
 
 use app::App;
+use config::TuiConfig;
+use logs::{LogBuffer, LogCapture};
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    // Initialize logging
-    let subscriber = FmtSubscriber::builder()
-        .with_max_level(tracing::Level::INFO)
+    // Log to a file; anything written to the terminal would tear the UI
+    let log_path = std::env::temp_dir().join("jamey-tui.log");
+    let log_file = std::fs::File::create(&log_path)
+        .with_context(|| format!("Failed to create {}", log_path.display()))?;
+    // The dashboard keeps its own copy of recent events, with more detail
+    let logs = LogBuffer::new();
+    let file_layer = tracing_subscriber::fmt::layer()
         .with_target(false)
-        .finish();
-
-    tracing::subscriber::set_global_default(subscriber)?;
+        .with_ansi(false)
+        .with_writer(Mutex::new(log_file))
+        .with_filter(LevelFilter::INFO);
+    tracing_subscriber::registry()
+        .with(file_layer)
+        .with(LogCapture::new(logs.clone()).with_filter(LevelFilter::DEBUG))
+        .try_init()?;
+
+    // Read settings before taking over the terminal, so errors stay readable
+    let tui_config = TuiConfig::load().context("Failed to load TUI config")?;
+    i18n::init(tui_config.language.as_deref());
+    let config = RuntimeConfig::from_env()
+        .map_err(|e| anyhow::anyhow!("Failed to load runtime config: {}", e))?;
+    let runtime = Runtime::new(config).await
+        .context("Failed to initialize runtime")?;
+    runtime.notify_desktop();
+    runtime.start_scheduler().await;
 
     // Setup terminal
     enable_raw_mode()?;
@@ -45,10 +73,11 @@ async fn main() -> Result<()> {
     let mut terminal = Terminal::new(backend)?;
 
     // Create app
-    let mut app = App::new().await?;
+    let mut app = App::new(runtime, logs, tui_config).await?;
 
     // Run app
     let res = run_app(&mut terminal, &mut app).await;
+    app.shutdown().await;
 
     // Restore terminal
     disable_raw_mode()?;
@@ -61,6 +90,7 @@ async fn main() -> Result<()> {
 
     if let Err(err) = res {
         error!("Application error: {}", err);
+        return Err(err);
     }
 
     Ok(())
@@ -70,24 +100,21 @@ async fn run_app<B: ratatui::backend::Backend>(
     terminal: &mut Terminal<B>,
     app: &mut App,
 ) -> Result<()> {
-    let mut last_tick = tokio::time::Instant::now();
-    let tick_rate = Duration::from_millis(100);
+    // Short enough that streamed tokens appear smoothly
+    let tick_rate = Duration::from_millis(30);
 
     loop {
         // Handle input
-        if event::poll(tick_rate - last_tick.elapsed())? {
+        if event::poll(tick_rate)? {
             if let Event::Key(key) = event::read()? {
                 if key.kind == KeyEventKind::Press {
-                    app.handle_key(key);
+                    app.handle_key(key).await;
                 }
             }
         }
 
-        // Update app state
-        if last_tick.elapsed() >= tick_rate {
-            app.update().await?;
-            last_tick = tokio::time::Instant::now();
-        }
+        // Pick up streamed output
+        app.update().await?;
 
         // Check if we should exit
         if app.should_exit {
@@ -96,6 +123,9 @@ async fn run_app<B: ratatui::backend::Backend>(
 
         // Draw UI
         terminal.draw(|f| ui::draw(f, app))?;
+        if app.sync_graphics()? {
+            terminal.clear()?;
+        }
     }
 }
 
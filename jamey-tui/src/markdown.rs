@@ -0,0 +1,371 @@
+//! Markdown to styled ratatui lines
+//!
+//! Covers what assistant replies actually use: headings, emphasis, inline
+//! code, links, lists, quotes, tables and fenced code blocks, the latter
+//! highlighted with syntect. Lines come out unwrapped; [`wrap`] fits them to
+//! the pane so the chat view can count and scroll by screen rows. Colours
+//! come from the active [`Theme`].
+
+use crate::theme::Theme;
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use std::sync::OnceLock;
+use syntect::easy::HighlightLines;
+use syntect::highlighting::{FontStyle, Theme as CodeTheme, ThemeSet};
+use syntect::parsing::SyntaxSet;
+use unicode_width::UnicodeWidthStr;
+
+/// syntect theme used when a theme names one that isn't bundled
+const FALLBACK_CODE_THEME: &str = "base16-ocean.dark";
+
+/// Gutter drawn in front of code and quoted lines
+const GUTTER: &str = "▎ ";
+
+fn highlighting() -> &'static (SyntaxSet, ThemeSet) {
+    static ASSETS: OnceLock<(SyntaxSet, ThemeSet)> = OnceLock::new();
+    ASSETS.get_or_init(|| {
+        let mut themes = ThemeSet::load_defaults();
+        themes.themes.entry(FALLBACK_CODE_THEME.to_string()).or_default();
+        (SyntaxSet::load_defaults_newlines(), themes)
+    })
+}
+
+/// Whether syntect bundles a code theme called `name`
+pub fn has_code_theme(name: &str) -> bool {
+    highlighting().1.themes.contains_key(name)
+}
+
+fn code_theme(name: &str) -> &'static CodeTheme {
+    let themes = &highlighting().1.themes;
+    themes.get(name).unwrap_or_else(|| &themes[FALLBACK_CODE_THEME])
+}
+
+/// Render Markdown into one [`Line`] per source line
+pub fn render(markdown: &str, theme: &Theme) -> Vec<Line<'static>> {
+    let (syntaxes, _) = highlighting();
+    let code_theme = code_theme(&theme.code_theme);
+    let gutter = Style::default().fg(theme.muted);
+    let mut lines = Vec::new();
+    let mut code: Option<HighlightLines> = None;
+
+    for raw in markdown.lines() {
+        if let Some(lang) = fence_info(raw) {
+            code = match code {
+                Some(_) => None,
+                None => {
+                    if !lang.is_empty() {
+                        lines.push(Line::from(vec![
+                            Span::styled(GUTTER, gutter),
+                            Span::styled(lang.to_string(), gutter.add_modifier(Modifier::ITALIC)),
+                        ]));
+                    }
+                    let syntax = syntaxes
+                        .find_syntax_by_token(lang)
+                        .unwrap_or_else(|| syntaxes.find_syntax_plain_text());
+                    Some(HighlightLines::new(syntax, code_theme))
+                }
+            };
+            continue;
+        }
+
+        if let Some(highlighter) = code.as_mut() {
+            let mut spans = vec![Span::styled(GUTTER, gutter)];
+            match highlighter.highlight_line(&format!("{}\n", raw), syntaxes) {
+                Ok(ranges) => spans.extend(ranges.into_iter().map(|(style, text)| {
+                    Span::styled(text.trim_end_matches('\n').to_string(), code_style(style))
+                })),
+                Err(_) => spans.push(Span::raw(raw.to_string())),
+            }
+            lines.push(Line::from(spans));
+            continue;
+        }
+
+        lines.push(render_line(raw, theme));
+    }
+    lines
+}
+
+/// Style a line of prose by its block-level syntax
+fn render_line(raw: &str, theme: &Theme) -> Line<'static> {
+    let trimmed = raw.trim_start();
+    let indent = &raw[..raw.len() - trimmed.len()];
+
+    if let Some((level, text)) = heading(trimmed) {
+        let mut style = Style::default().fg(theme.accent).add_modifier(Modifier::BOLD);
+        if level == 1 {
+            style = style.add_modifier(Modifier::UNDERLINED);
+        }
+        return Line::from(inline(text, style, theme));
+    }
+    if is_rule(trimmed) {
+        return Line::from(Span::styled("─".repeat(24), Style::default().fg(theme.muted)));
+    }
+    if let Some(text) = trimmed.strip_prefix('>') {
+        let mut spans = vec![Span::styled(GUTTER, Style::default().fg(theme.muted))];
+        spans.extend(inline(
+            text.trim_start(),
+            Style::default().fg(theme.subtle).add_modifier(Modifier::ITALIC),
+            theme,
+        ));
+        return Line::from(spans);
+    }
+    if let Some(text) = ["- ", "* ", "+ "].iter().find_map(|bullet| trimmed.strip_prefix(bullet)) {
+        let mut spans = vec![Span::raw(indent.to_string()), Span::styled("• ", Style::default().fg(theme.accent))];
+        spans.extend(inline(text, Style::default(), theme));
+        return Line::from(spans);
+    }
+    if let Some((number, text)) = ordered_item(trimmed) {
+        let mut spans = vec![
+            Span::raw(indent.to_string()),
+            Span::styled(format!("{}. ", number), Style::default().fg(theme.accent)),
+        ];
+        spans.extend(inline(text, Style::default(), theme));
+        return Line::from(spans);
+    }
+    if trimmed.starts_with('|') {
+        return table_row(trimmed, theme);
+    }
+    let mut spans = vec![Span::raw(indent.to_string())];
+    spans.extend(inline(trimmed, Style::default(), theme));
+    Line::from(spans)
+}
+
+/// Table rows keep their layout; only the borders are dimmed
+fn table_row(row: &str, theme: &Theme) -> Line<'static> {
+    let border = Style::default().fg(theme.muted);
+    if row.chars().all(|c| matches!(c, '|' | '-' | ':' | ' ')) {
+        return Line::from(Span::styled(row.to_string(), border));
+    }
+    let mut spans = Vec::new();
+    for (i, cell) in row.split('|').enumerate() {
+        if i > 0 {
+            spans.push(Span::styled("│", border));
+        }
+        spans.extend(inline(cell, Style::default(), theme));
+    }
+    Line::from(spans)
+}
+
+/// Spans for inline emphasis, code and links within one line
+fn inline(text: &str, base: Style, theme: &Theme) -> Vec<Span<'static>> {
+    let mut spans = Vec::new();
+    let mut plain = String::new();
+    let mut rest = text;
+
+    macro_rules! flush {
+        () => {
+            if !plain.is_empty() {
+                spans.push(Span::styled(std::mem::take(&mut plain), base));
+            }
+        };
+    }
+
+    while let Some(ch) = rest.chars().next() {
+        if ch == '`' {
+            if let Some(end) = rest[1..].find('`') {
+                flush!();
+                spans.push(Span::styled(rest[1..end + 1].to_string(), base.fg(theme.warning)));
+                rest = &rest[end + 2..];
+                continue;
+            }
+        }
+        if let Some(after) = rest.strip_prefix("**") {
+            if let Some(end) = after.find("**").filter(|&end| end > 0) {
+                flush!();
+                spans.extend(inline(&after[..end], base.add_modifier(Modifier::BOLD), theme));
+                rest = &after[end + 2..];
+                continue;
+            }
+        }
+        if let Some(after) = rest.strip_prefix('*').filter(|a| !a.starts_with([' ', '*'])) {
+            if let Some(end) = after.find('*').filter(|&end| end > 0) {
+                flush!();
+                spans.extend(inline(&after[..end], base.add_modifier(Modifier::ITALIC), theme));
+                rest = &after[end + 1..];
+                continue;
+            }
+        }
+        if ch == '[' {
+            if let Some((label, url, len)) = parse_link(rest) {
+                flush!();
+                let link = base.fg(theme.info).add_modifier(Modifier::UNDERLINED);
+                if label == url {
+                    spans.push(Span::styled(url.to_string(), link));
+                } else {
+                    spans.push(Span::styled(label.to_string(), link));
+                    spans.push(Span::styled(format!(" ({})", url), base.fg(theme.muted)));
+                }
+                rest = &rest[len..];
+                continue;
+            }
+        }
+        plain.push(ch);
+        rest = &rest[ch.len_utf8()..];
+    }
+    flush!();
+    spans
+}
+
+fn code_style(style: syntect::highlighting::Style) -> Style {
+    let fg = style.foreground;
+    let mut out = Style::default().fg(Color::Rgb(fg.r, fg.g, fg.b));
+    if style.font_style.contains(FontStyle::BOLD) {
+        out = out.add_modifier(Modifier::BOLD);
+    }
+    if style.font_style.contains(FontStyle::ITALIC) {
+        out = out.add_modifier(Modifier::ITALIC);
+    }
+    if style.font_style.contains(FontStyle::UNDERLINE) {
+        out = out.add_modifier(Modifier::UNDERLINED);
+    }
+    out
+}
+
+/// The info string of a code fence line (empty when no language is given)
+fn fence_info(line: &str) -> Option<&str> {
+    let trimmed = line.trim();
+    trimmed
+        .strip_prefix("```")
+        .or_else(|| trimmed.strip_prefix("~~~"))
+        .map(|info| info.split_whitespace().next().unwrap_or(""))
+}
+
+fn heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|&c| c == '#').count();
+    if !(1..=6).contains(&level) {
+        return None;
+    }
+    line[level..].strip_prefix(' ').map(|text| (level, text.trim()))
+}
+
+fn is_rule(line: &str) -> bool {
+    let line = line.trim();
+    line.len() >= 3 && ['-', '*', '_'].iter().any(|&c| line.chars().all(|ch| ch == c))
+}
+
+fn ordered_item(line: &str) -> Option<(&str, &str)> {
+    let digits = line.chars().take_while(char::is_ascii_digit).count();
+    if digits == 0 || digits > 3 {
+        return None;
+    }
+    line[digits..].strip_prefix(". ").map(|text| (&line[..digits], text))
+}
+
+/// Parse a link at the start of `s`: its label, target and length in bytes
+fn parse_link(s: &str) -> Option<(&str, &str, usize)> {
+    let close = s.find(']')?;
+    let label = &s[1..close];
+    if label.contains('[') {
+        return None;
+    }
+    let after = s[close + 1..].strip_prefix('(')?;
+    let end = after.find(')')?;
+    let url = after[..end].trim();
+    if url.is_empty() || url.contains(char::is_whitespace) {
+        return None;
+    }
+    Some((label, url, close + 2 + end + 1))
+}
+
+/// Word-wrap lines to `width` columns, keeping span styles; words longer
+/// than a whole row are split
+pub fn wrap(lines: Vec<Line<'static>>, width: usize) -> Vec<Line<'static>> {
+    let width = width.max(1);
+    let mut out = Vec::with_capacity(lines.len());
+
+    for line in lines {
+        if line.width() <= width {
+            out.push(line);
+            continue;
+        }
+
+        let mut row: Vec<Span<'static>> = Vec::new();
+        let mut row_width = 0;
+        for span in line.spans {
+            for word in span.content.split_inclusive(' ') {
+                let mut word = word.to_string();
+                let word_width = word.width();
+                if row_width > 0 && row_width + word_width.min(width) > width {
+                    out.push(Line::from(std::mem::take(&mut row)));
+                    row_width = 0;
+                    word = word.trim_start().to_string();
+                }
+                // Hard-split what still does not fit
+                while row_width + word.width() > width {
+                    let (head, tail) = split_at_width(&word, width - row_width);
+                    row.push(Span::styled(head, span.style));
+                    out.push(Line::from(std::mem::take(&mut row)));
+                    row_width = 0;
+                    word = tail;
+                }
+                row_width += word.width();
+                row.push(Span::styled(word, span.style));
+            }
+        }
+        if !row.is_empty() {
+            out.push(Line::from(row));
+        }
+    }
+    out
+}
+
+/// Split `text` so the head is at most `width` columns (and never empty)
+fn split_at_width(text: &str, width: usize) -> (String, String) {
+    let mut used = 0;
+    for (i, ch) in text.char_indices() {
+        let w = ch.to_string().width();
+        if used + w > width && i > 0 {
+            return (text[..i].to_string(), text[i..].to_string());
+        }
+        used += w;
+    }
+    (text.to_string(), String::new())
+}
+
+/// Text of a line without styling, e.g. for search
+pub fn plain_text(line: &Line<'_>) -> String {
+    line.spans.iter().map(|s| s.content.as_ref()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_inline_styles() {
+        let theme = Theme::dark();
+        let line = render_line("Use **bold**, `code` and [docs](https://docs.rs)", &theme);
+        assert_eq!(plain_text(&line), "Use bold, code and docs (https://docs.rs)");
+
+        let bold = line.spans.iter().find(|s| s.content == "bold").unwrap();
+        assert!(bold.style.add_modifier.contains(Modifier::BOLD));
+        let code = line.spans.iter().find(|s| s.content == "code").unwrap();
+        assert_eq!(code.style.fg, Some(Color::Yellow));
+
+        // Snake case and lone asterisks are left alone
+        assert_eq!(plain_text(&render_line("a * b and my_var_name", &theme)), "a * b and my_var_name");
+    }
+
+    #[test]
+    fn test_blocks() {
+        let lines = render("# Title\n\n- item\n1. first\n\n```rust\nlet x = 1;\n```\nafter", &Theme::dark());
+        let text: Vec<String> = lines.iter().map(plain_text).collect();
+        assert_eq!(
+            text,
+            vec!["Title", "", "• item", "1. first", "", "▎ rust", "▎ let x = 1;", "after"]
+        );
+        // Code is highlighted with the theme's true colours
+        assert!(lines[6].spans.iter().any(|s| matches!(s.style.fg, Some(Color::Rgb(..)))));
+    }
+
+    #[test]
+    fn test_wrap() {
+        let lines = wrap(vec![Line::from("the quick brown fox jumps")], 10);
+        let text: Vec<String> = lines.iter().map(plain_text).collect();
+        assert_eq!(text, vec!["the quick ", "brown fox ", "jumps"]);
+
+        let lines = wrap(vec![Line::from("abcdefghijklmnop")], 6);
+        let text: Vec<String> = lines.iter().map(plain_text).collect();
+        assert_eq!(text, vec!["abcdef", "ghijkl", "mnop"]);
+    }
+}
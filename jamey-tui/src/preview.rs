@@ -0,0 +1,248 @@
+//! Image previews in the terminal
+//!
+//! Terminals that speak the kitty graphics protocol or sixel get the real
+//! picture, written straight to the terminal after ratatui has drawn a blank
+//! area for it. Everything else gets coloured ASCII art drawn as ordinary
+//! cells.
+
+use base64::Engine as _;
+use crossterm::{cursor::MoveTo, QueueableCommand};
+use image::DynamicImage;
+use image::imageops::FilterType;
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Style};
+use ratatui::text::{Line, Span};
+use serde::Deserialize;
+use std::io::{self, Write};
+use uuid::Uuid;
+
+/// Darkest to brightest
+const ASCII_RAMP: &[u8] = b" .:-=+*#%@";
+
+/// Base64 bytes per kitty escape; the protocol caps chunks at 4096
+const KITTY_CHUNK: usize = 4096;
+
+/// Assumed cell size in pixels when the terminal doesn't report one
+const DEFAULT_CELL: (u16, u16) = (8, 16);
+
+/// How images are drawn; `tui.toml` sets it with `graphics = "..."`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GraphicsMode {
+    /// Pick from the terminal's environment
+    #[default]
+    Auto,
+    Kitty,
+    Sixel,
+    Ascii,
+}
+
+impl GraphicsMode {
+    /// Resolve `Auto` by looking at `TERM`, `TERM_PROGRAM` and friends
+    pub fn detect(self) -> Self {
+        if self != GraphicsMode::Auto {
+            return self;
+        }
+        let term = std::env::var("TERM").unwrap_or_default().to_lowercase();
+        let program = std::env::var("TERM_PROGRAM").unwrap_or_default();
+        if std::env::var_os("KITTY_WINDOW_ID").is_some()
+            || term.contains("kitty")
+            || matches!(program.as_str(), "WezTerm" | "ghostty")
+        {
+            GraphicsMode::Kitty
+        } else if term.contains("sixel") || ["foot", "mlterm", "contour", "yaft"].iter().any(|t| term.starts_with(t)) {
+            GraphicsMode::Sixel
+        } else {
+            GraphicsMode::Ascii
+        }
+    }
+}
+
+/// Largest size that fits `max_w` x `max_h` with the image's aspect ratio
+fn fit(width: u32, height: u32, max_w: u32, max_h: u32) -> (u32, u32) {
+    if width == 0 || height == 0 {
+        return (0, 0);
+    }
+    let scale = (max_w as f64 / width as f64).min(max_h as f64 / height as f64);
+    (((width as f64 * scale) as u32).max(1), ((height as f64 * scale) as u32).max(1))
+}
+
+/// The image as ASCII art at most `cols` x `rows`; a cell is about twice as
+/// tall as it is wide, so each character stands for a 1x2 block of pixels
+pub fn ascii(image: &DynamicImage, cols: u16, rows: u16) -> Vec<Line<'static>> {
+    let (w, h2) = fit(image.width(), image.height(), cols as u32, rows as u32 * 2);
+    let h = (h2 / 2).max(1);
+    if w == 0 {
+        return Vec::new();
+    }
+    let pixels = image.resize_exact(w, h, FilterType::Triangle).to_rgb8();
+    pixels
+        .rows()
+        .map(|row| {
+            Line::from(
+                row.map(|p| {
+                    let [r, g, b] = p.0;
+                    let luma = (r as u32 * 299 + g as u32 * 587 + b as u32 * 114) / 1000;
+                    let ch = ASCII_RAMP[luma as usize * (ASCII_RAMP.len() - 1) / 255] as char;
+                    Span::styled(ch.to_string(), Style::default().fg(Color::Rgb(r, g, b)))
+                })
+                .collect::<Vec<_>>(),
+            )
+        })
+        .collect()
+}
+
+/// Pixel size of one terminal cell
+fn cell_size() -> (u16, u16) {
+    match crossterm::terminal::window_size() {
+        Ok(size) if size.width > 0 && size.height > 0 && size.columns > 0 && size.rows > 0 => {
+            (size.width / size.columns, size.height / size.rows)
+        }
+        _ => DEFAULT_CELL,
+    }
+}
+
+/// Kitty escapes that draw `image` (already sized) at the cursor
+fn kitty(image: &DynamicImage) -> io::Result<Vec<u8>> {
+    let mut png = io::Cursor::new(Vec::new());
+    image
+        .write_to(&mut png, image::ImageFormat::Png)
+        .map_err(io::Error::other)?;
+    let data = base64::engine::general_purpose::STANDARD.encode(png.into_inner());
+
+    let mut out = Vec::new();
+    let chunks: Vec<&[u8]> = data.as_bytes().chunks(KITTY_CHUNK).collect();
+    for (i, chunk) in chunks.iter().enumerate() {
+        let more = u8::from(i + 1 < chunks.len());
+        if i == 0 {
+            // f=100: PNG, a=T: transmit and show, q=2: no replies, C=1: leave the cursor
+            write!(out, "\x1b_Gf=100,a=T,q=2,C=1,m={};", more)?;
+        } else {
+            write!(out, "\x1b_Gm={};", more)?;
+        }
+        out.extend_from_slice(chunk);
+        out.extend_from_slice(b"\x1b\\");
+    }
+    Ok(out)
+}
+
+/// Sixel data for `image` (already sized), quantized to a 6x6x6 colour cube
+fn sixel(image: &DynamicImage) -> Vec<u8> {
+    let pixels = image.to_rgb8();
+    let (w, h) = pixels.dimensions();
+    let level = |v: u8| (v as usize * 5 + 127) / 255;
+    let index = |p: &image::Rgb<u8>| level(p.0[0]) * 36 + level(p.0[1]) * 6 + level(p.0[2]);
+
+    let mut out = format!("\x1bP0;1;0q\"1;1;{};{}", w, h);
+    for i in 0..216 {
+        out.push_str(&format!("#{};2;{};{};{}", i, i / 36 * 20, i / 6 % 6 * 20, i % 6 * 20));
+    }
+    for band in (0..h).step_by(6) {
+        // One row of sixels per colour present in this band of six pixel rows
+        let mut layers: Vec<Option<Vec<u8>>> = vec![None; 216];
+        for dy in 0..6.min(h - band) {
+            for x in 0..w {
+                let color = index(pixels.get_pixel(x, band + dy));
+                layers[color].get_or_insert_with(|| vec![0; w as usize])[x as usize] |= 1 << dy;
+            }
+        }
+        for (color, bits) in layers.iter().enumerate() {
+            let Some(bits) = bits else { continue };
+            out.push_str(&format!("#{}", color));
+            let mut x = 0;
+            while x < bits.len() {
+                let run = bits[x..].iter().take_while(|&&b| b == bits[x]).count();
+                let ch = (63 + bits[x]) as char;
+                if run > 3 {
+                    out.push_str(&format!("!{}{}", run, ch));
+                } else {
+                    out.extend(std::iter::repeat_n(ch, run));
+                }
+                x += run;
+            }
+            out.push('$');
+        }
+        out.push('-');
+    }
+    out.push_str("\x1b\\");
+    out.into_bytes()
+}
+
+/// Tracks what the graphics protocol has put on screen, so an image is only
+/// sent when it changes
+pub struct Graphics {
+    mode: GraphicsMode,
+    shown: Option<(Uuid, Rect)>,
+}
+
+impl Graphics {
+    pub fn new(mode: GraphicsMode) -> Self {
+        Self { mode: mode.detect(), shown: None }
+    }
+
+    /// Whether images are drawn as cells by ratatui rather than by the terminal
+    pub fn uses_cells(&self) -> bool {
+        self.mode == GraphicsMode::Ascii
+    }
+
+    /// Bring the screen in line with `want`. Returns true when the terminal
+    /// must be cleared and redrawn first, as sixel pixels can only be erased
+    /// by drawing over them; the new image follows on the next call.
+    pub fn sync(&mut self, out: &mut impl Write, want: Option<(Uuid, Rect, &DynamicImage)>) -> io::Result<bool> {
+        if self.uses_cells() || want.map(|(id, area, _)| (id, area)) == self.shown {
+            return Ok(false);
+        }
+        if self.shown.take().is_some() {
+            match self.mode {
+                GraphicsMode::Kitty => out.write_all(b"\x1b_Ga=d,q=2\x1b\\")?,
+                _ => return Ok(true),
+            }
+        }
+        if let Some((id, area, image)) = want {
+            let (cell_w, cell_h) = cell_size();
+            let (w, h) = fit(
+                image.width(),
+                image.height(),
+                area.width as u32 * cell_w as u32,
+                area.height as u32 * cell_h as u32,
+            );
+            let sized = image.resize(w, h, FilterType::Triangle);
+            let data = match self.mode {
+                GraphicsMode::Kitty => kitty(&sized)?,
+                _ => sixel(&sized),
+            };
+            out.queue(MoveTo(area.x, area.y))?;
+            out.write_all(&data)?;
+            self.shown = Some((id, area));
+        }
+        out.flush()?;
+        Ok(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fit_keeps_aspect() {
+        assert_eq!(fit(400, 200, 40, 40), (40, 20));
+        assert_eq!(fit(100, 300, 60, 30), (10, 30));
+    }
+
+    #[test]
+    fn test_ascii_and_sixel() {
+        let image = DynamicImage::ImageRgb8(image::RgbImage::from_fn(20, 10, |x, _| {
+            if x < 10 { image::Rgb([0, 0, 0]) } else { image::Rgb([255, 255, 255]) }
+        }));
+        let lines = ascii(&image, 10, 10);
+        assert_eq!(lines.len(), 2);
+        let row: String = lines[0].spans.iter().map(|s| s.content.as_ref()).collect();
+        assert!(row.starts_with(' ') && row.ends_with('@'));
+
+        let data = String::from_utf8(sixel(&image)).unwrap();
+        assert!(data.starts_with("\x1bP") && data.ends_with("\x1b\\"));
+        // Two bands of six rows
+        assert_eq!(data.matches('-').count(), 2);
+    }
+}
@@ -0,0 +1,137 @@
+//! Fuzzy-find session switcher
+//!
+//! Lists the open tabs and every persisted session; typing narrows the list
+//! by a subsequence match on the title and ID.
+
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+/// A session the switcher can open
+#[derive(Debug, Clone)]
+pub struct SwitcherItem {
+    pub session_id: Uuid,
+    pub title: String,
+    /// Already open in a tab
+    pub open: bool,
+    pub message_count: usize,
+    pub updated_at: DateTime<Utc>,
+}
+
+pub struct Switcher {
+    pub query: String,
+    items: Vec<SwitcherItem>,
+    /// Index into the current matches
+    pub selected: usize,
+}
+
+impl Switcher {
+    /// Open tabs first, then the rest newest first
+    pub fn new(mut items: Vec<SwitcherItem>) -> Self {
+        items.sort_by(|a, b| b.open.cmp(&a.open).then(b.updated_at.cmp(&a.updated_at)));
+        Self {
+            query: String::new(),
+            items,
+            selected: 0,
+        }
+    }
+
+    /// Items matching the query, best match first
+    pub fn matches(&self) -> Vec<&SwitcherItem> {
+        if self.query.is_empty() {
+            return self.items.iter().collect();
+        }
+        let mut scored: Vec<(i64, &SwitcherItem)> = self
+            .items
+            .iter()
+            .filter_map(|item| {
+                let haystack = format!("{} {}", item.title, item.session_id);
+                fuzzy_score(&self.query, &haystack).map(|score| (score, item))
+            })
+            .collect();
+        // Stable, so equal scores keep tabs-then-recency order
+        scored.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+        scored.into_iter().map(|(_, item)| item).collect()
+    }
+
+    pub fn selected_item(&self) -> Option<&SwitcherItem> {
+        self.matches().get(self.selected).copied()
+    }
+
+    pub fn set_query(&mut self, query: String) {
+        self.query = query;
+        self.selected = 0;
+    }
+
+    pub fn move_selection(&mut self, delta: isize) {
+        let count = self.matches().len();
+        if count > 0 {
+            self.selected = (self.selected as isize + delta).rem_euclid(count as isize) as usize;
+        }
+    }
+}
+
+/// Score `text` against `query` as a case-insensitive subsequence, favouring
+/// consecutive runs and word starts; `None` when it doesn't match
+pub fn fuzzy_score(query: &str, text: &str) -> Option<i64> {
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut position = 0;
+    let mut previous: Option<usize> = None;
+
+    for wanted in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let found = (position..text.len()).find(|&i| text[i] == wanted)?;
+        score += 1;
+        if previous.is_some_and(|p| p + 1 == found) {
+            score += 5;
+        }
+        if found == 0 || !text[found - 1].is_alphanumeric() {
+            score += 3;
+        }
+        previous = Some(found);
+        position = found + 1;
+    }
+    // Earlier matches rank slightly higher
+    Some(score * 100 - previous.unwrap_or(0) as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(title: &str, open: bool, age_minutes: i64) -> SwitcherItem {
+        SwitcherItem {
+            session_id: Uuid::new_v4(),
+            title: title.to_string(),
+            open,
+            message_count: 2,
+            updated_at: Utc::now() - chrono::Duration::minutes(age_minutes),
+        }
+    }
+
+    #[test]
+    fn test_fuzzy_score() {
+        assert!(fuzzy_score("rl", "Release planning").is_some());
+        assert!(fuzzy_score("xyz", "Release planning").is_none());
+        // Word starts and runs beat scattered letters
+        assert!(fuzzy_score("rp", "Release planning") > fuzzy_score("rp", "dropped"));
+        assert!(fuzzy_score("plan", "Release planning") > fuzzy_score("plan", "p l a n"));
+    }
+
+    #[test]
+    fn test_switcher_ordering_and_selection() {
+        let mut switcher = Switcher::new(vec![
+            item("Old notes", false, 300),
+            item("Log rotation", true, 100),
+            item("Release planning", false, 5),
+        ]);
+        let titles = |s: &Switcher| s.matches().iter().map(|i| i.title.clone()).collect::<Vec<_>>();
+        assert_eq!(titles(&switcher), vec!["Log rotation", "Release planning", "Old notes"]);
+
+        switcher.set_query("rel pl".to_string());
+        assert_eq!(titles(&switcher), vec!["Release planning"]);
+
+        switcher.set_query(String::new());
+        switcher.move_selection(-1);
+        assert_eq!(switcher.selected_item().unwrap().title, "Old notes");
+    }
+}
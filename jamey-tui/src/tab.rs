@@ -0,0 +1,213 @@
+//! One conversation tab: its session, transcript view and running turn
+//!
+//! Turns keep running while their tab is in the background; their events
+//! are routed back by session ID and applied to the owning tab.
+
+use crate::chat::ChatView;
+use jamey_protocol::{Attachment, Message, Role};
+use jamey_runtime::approvals::ApprovalRequest;
+use jamey_runtime::chat::TurnEvent;
+use jamey_runtime::session_store::SessionRecord;
+use jamey_runtime::state::RuntimeState;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tracing::warn;
+use uuid::Uuid;
+
+/// Title of a tab before its first message
+pub const NEW_TAB_TITLE: &str = "New chat";
+
+/// Link to the model provider, as of the tab's last turn
+#[derive(Debug, Clone, PartialEq)]
+pub enum Connection {
+    Ready,
+    Streaming,
+    /// The last turn failed; cleared by the next one that succeeds
+    Error(String),
+}
+
+/// Token and cost totals for a session
+#[derive(Debug, Clone, Default)]
+pub struct SessionUsage {
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    /// `None` until the provider reports a price
+    pub cost_usd: Option<f64>,
+}
+
+/// An event from a tab's turn, tagged so it reaches the right tab
+pub struct TurnUpdate {
+    pub session_id: Uuid,
+    pub turn_id: u64,
+    pub event: TurnEvent,
+}
+
+pub struct Tab {
+    pub session_id: Uuid,
+    pub title: String,
+    pub chat: ChatView,
+    /// Messages replayed to the model each turn
+    history: Vec<Message>,
+    pub usage: SessionUsage,
+    pub connection: Connection,
+    /// Tool call the running turn is parked on until someone decides
+    pub approval: Option<ApprovalRequest>,
+    /// Files from `/attach`, sent with the next message
+    pub pending_attachments: Vec<Attachment>,
+    /// Forwards the running turn's events; aborting it cancels the turn
+    turn: Option<JoinHandle<()>>,
+    /// Numbers turns so events from a cancelled one can be told apart
+    turn_id: u64,
+}
+
+impl Tab {
+    pub fn new(session_id: Uuid) -> Self {
+        Self {
+            session_id,
+            title: NEW_TAB_TITLE.to_string(),
+            chat: ChatView::new(),
+            history: Vec::new(),
+            usage: SessionUsage::default(),
+            connection: Connection::Ready,
+            approval: None,
+            pending_attachments: Vec::new(),
+            turn: None,
+            turn_id: 0,
+        }
+    }
+
+    /// Reopen a persisted session with its transcript
+    pub fn resume(record: SessionRecord) -> Self {
+        let mut tab = Self::new(record.id);
+        tab.title = record.display_title().to_string();
+        for message in &record.messages {
+            tab.chat.push(message.clone());
+        }
+        tab.history = record.messages;
+        tab
+    }
+
+    /// The newest finished reply, which feedback commands apply to
+    pub fn last_reply(&self) -> Option<Uuid> {
+        self.history.iter().rev().find(|m| m.role == Role::Assistant).map(|m| m.id)
+    }
+
+    /// Memory the last reply cites as `[n]`
+    pub fn cited_memory(&self, n: usize) -> Option<Uuid> {
+        let reply = self.history.iter().rev().find(|m| m.role == Role::Assistant)?;
+        reply.citations.get(n.checked_sub(1)?).map(|c| c.memory_id)
+    }
+
+    pub fn is_busy(&self) -> bool {
+        self.turn.is_some()
+    }
+
+    /// Start a turn for `text`; ignored while one is already running
+    pub fn send(&mut self, state: &RuntimeState, text: String, events: &mpsc::UnboundedSender<TurnUpdate>) {
+        if self.is_busy() {
+            return;
+        }
+        let message = Message::user(text).with_attachments(&std::mem::take(&mut self.pending_attachments));
+        self.chat.push(message.clone());
+        self.history.push(message);
+        self.chat.start_reply();
+        self.chat.scroll_to_bottom();
+        self.connection = Connection::Streaming;
+
+        self.turn_id += 1;
+        let (session_id, turn_id, tx) = (self.session_id, self.turn_id, events.clone());
+        let mut turn = state.stream_session_turn(self.session_id, self.history.clone());
+        self.turn = Some(tokio::spawn(async move {
+            while let Some(event) = turn.next().await {
+                if tx.send(TurnUpdate { session_id, turn_id, event }).is_err() {
+                    break;
+                }
+            }
+        }));
+    }
+
+    /// Apply an event from this tab's current turn
+    pub async fn apply(&mut self, state: &RuntimeState, model: &str, update: TurnUpdate) {
+        if update.turn_id != self.turn_id {
+            return;
+        }
+        match update.event {
+            TurnEvent::Summarized(compaction) => {
+                // The on-disk transcript keeps every message; only the
+                // history sent to the model shrinks
+                self.history = compaction.apply(&self.history);
+                self.chat.push(Message::system(format!(
+                    "📝 Summarized {} earlier messages",
+                    compaction.replaced
+                )));
+            }
+            TurnEvent::Token(text) => self.chat.push_token(&text),
+            TurnEvent::ToolCall(call) => {
+                let action = call.args.get("action").and_then(|a| a.as_str()).unwrap_or("?");
+                self.chat.split_reply();
+                self.chat.push(Message::tool(format!("🔧 {} {}", call.name, action)));
+            }
+            TurnEvent::AwaitingApproval(request) => {
+                self.chat.push(Message::system(format!(
+                    "⏸ {} {} needs approval ({})",
+                    request.connector_id,
+                    request.action,
+                    &request.id.to_string()[..8]
+                )));
+                self.approval = Some(request);
+            }
+            TurnEvent::ToolResult(result) => {
+                // Decided here or from elsewhere, e.g. `jamey approvals`
+                self.approval = None;
+                let summary = match &result.error {
+                    Some(error) => format!("❌ {}: {}", result.name, error),
+                    None => format!("✅ {}: {} line(s) of output", result.name, result.output.lines().count()),
+                };
+                self.chat.push(Message::tool(summary));
+            }
+            TurnEvent::Usage { usage, cost_usd } => {
+                self.usage.prompt_tokens += u64::from(usage.prompt_tokens);
+                self.usage.completion_tokens += u64::from(usage.completion_tokens);
+                if let Some(cost) = cost_usd {
+                    *self.usage.cost_usd.get_or_insert(0.0) += cost;
+                }
+            }
+            TurnEvent::Completed(message) => {
+                self.turn = None;
+                self.approval = None;
+                self.connection = Connection::Ready;
+                self.chat.finish_reply(message.clone());
+
+                let exchange: Vec<Message> = self.history.last().cloned().into_iter()
+                    .chain(std::iter::once(message.clone()))
+                    .collect();
+                self.history.push(message);
+                // The store titles a session after its opening message
+                match state.session_store.append(self.session_id, &exchange, Some(model)).await {
+                    Ok(record) => self.title = record.display_title().to_string(),
+                    Err(e) => warn!("Failed to save session transcript: {}", e),
+                }
+            }
+            TurnEvent::Failed(error) => {
+                self.turn = None;
+                self.approval = None;
+                // Forget the prompt so it isn't replayed with the next turn
+                self.history.pop();
+                self.chat.abort_reply(&format!("Turn failed: {}", error));
+                self.connection = Connection::Error(error);
+            }
+        }
+    }
+
+    /// Drop the running turn; the partial reply stays on screen. Returns
+    /// the approval it was waiting on, which the caller should settle.
+    pub fn cancel(&mut self) -> Option<ApprovalRequest> {
+        if let Some(task) = self.turn.take() {
+            task.abort();
+            self.history.pop();
+            self.chat.abort_reply("Turn cancelled");
+            self.connection = Connection::Ready;
+        }
+        self.approval.take()
+    }
+}
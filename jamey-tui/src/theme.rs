@@ -0,0 +1,299 @@
+//! Colour themes
+//!
+//! Styles in the UI are picked by role (muted text, accent, warning, …)
+//! rather than by colour, so a theme is just the palette behind those roles
+//! plus a border style and the syntect theme used for code blocks.
+//!
+//! Built-ins are `dark`, `light` and `high-contrast`. `tui.toml` can define
+//! more, each starting from a built-in:
+//!
+//! ```toml
+//! theme = "solarized"
+//!
+//! [themes.solarized]
+//! base = "light"
+//! accent = "#268bd2"
+//! border_type = "rounded"
+//! code_theme = "Solarized (light)"
+//! ```
+
+use crate::markdown;
+use ratatui::style::{Color, Style};
+use ratatui::widgets::{Block, BorderType, Borders};
+use std::collections::BTreeMap;
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum ThemeError {
+    #[error("Unknown theme \"{0}\"")]
+    UnknownTheme(String),
+    #[error("Unknown theme setting \"{0}\"")]
+    UnknownSetting(String),
+    #[error("Invalid colour \"{value}\" for {setting}")]
+    InvalidColor { setting: String, value: String },
+    #[error("Unknown border type \"{0}\" (plain, rounded, double or thick)")]
+    UnknownBorderType(String),
+    #[error("Unknown code theme \"{0}\"")]
+    UnknownCodeTheme(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Theme {
+    pub name: String,
+    /// Body text
+    pub text: Color,
+    /// Secondary detail: timestamps, gutters, separators
+    pub muted: Color,
+    /// Quieter body text: system notes, quotes, the status line
+    pub subtle: Color,
+    pub border: Color,
+    pub border_type: BorderType,
+    /// Headings, list markers, the selected tab, model names
+    pub accent: Color,
+    /// Inline code, pending approvals, prompts
+    pub warning: Color,
+    pub success: Color,
+    pub error: Color,
+    /// Links and debug output
+    pub info: Color,
+    pub user: Color,
+    pub assistant: Color,
+    pub system: Color,
+    pub tool: Color,
+    /// Background of scrollback search hits
+    pub search_match: Color,
+    /// Background and text of the selected search hit
+    pub search_current: (Color, Color),
+    /// syntect theme for fenced code
+    pub code_theme: String,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::dark()
+    }
+}
+
+impl Theme {
+    pub fn dark() -> Self {
+        Self {
+            name: "dark".to_string(),
+            text: Color::Reset,
+            muted: Color::DarkGray,
+            subtle: Color::Gray,
+            border: Color::Reset,
+            border_type: BorderType::Plain,
+            accent: Color::Cyan,
+            warning: Color::Yellow,
+            success: Color::Green,
+            error: Color::Red,
+            info: Color::Blue,
+            user: Color::Green,
+            assistant: Color::Blue,
+            system: Color::Yellow,
+            tool: Color::Magenta,
+            search_match: Color::DarkGray,
+            search_current: (Color::Yellow, Color::Black),
+            code_theme: "base16-ocean.dark".to_string(),
+        }
+    }
+
+    /// For light terminal backgrounds, where yellow and grey wash out
+    pub fn light() -> Self {
+        Self {
+            name: "light".to_string(),
+            text: Color::Reset,
+            muted: Color::Rgb(110, 110, 110),
+            subtle: Color::Rgb(80, 80, 80),
+            border: Color::Rgb(150, 150, 150),
+            border_type: BorderType::Rounded,
+            accent: Color::Rgb(0, 95, 175),
+            warning: Color::Rgb(175, 95, 0),
+            success: Color::Rgb(0, 125, 0),
+            error: Color::Rgb(190, 0, 0),
+            info: Color::Rgb(0, 70, 200),
+            user: Color::Rgb(0, 125, 0),
+            assistant: Color::Rgb(0, 70, 200),
+            system: Color::Rgb(175, 95, 0),
+            tool: Color::Rgb(135, 0, 135),
+            search_match: Color::Rgb(220, 220, 220),
+            search_current: (Color::Rgb(255, 215, 95), Color::Black),
+            code_theme: "InspiredGitHub".to_string(),
+        }
+    }
+
+    /// Bright colours only and heavy borders
+    pub fn high_contrast() -> Self {
+        Self {
+            name: "high-contrast".to_string(),
+            text: Color::White,
+            muted: Color::Gray,
+            subtle: Color::White,
+            border: Color::White,
+            border_type: BorderType::Thick,
+            accent: Color::LightCyan,
+            warning: Color::LightYellow,
+            success: Color::LightGreen,
+            error: Color::LightRed,
+            info: Color::LightBlue,
+            user: Color::LightGreen,
+            assistant: Color::LightCyan,
+            system: Color::LightYellow,
+            tool: Color::LightMagenta,
+            search_match: Color::Blue,
+            search_current: (Color::LightYellow, Color::Black),
+            code_theme: "base16-eighties.dark".to_string(),
+        }
+    }
+
+    pub fn builtins() -> Vec<Self> {
+        vec![Self::dark(), Self::light(), Self::high_contrast()]
+    }
+
+    /// A bordered block in this theme's border style
+    pub fn block(&self) -> Block<'static> {
+        Block::default()
+            .borders(Borders::ALL)
+            .border_type(self.border_type)
+            .border_style(Style::default().fg(self.border))
+    }
+
+    /// Apply one `key = "value"` setting from a theme table
+    fn set(&mut self, setting: &str, value: &str) -> Result<(), ThemeError> {
+        let color = || {
+            value.parse::<Color>().map_err(|_| ThemeError::InvalidColor {
+                setting: setting.to_string(),
+                value: value.to_string(),
+            })
+        };
+        match setting {
+            "text" => self.text = color()?,
+            "muted" => self.muted = color()?,
+            "subtle" => self.subtle = color()?,
+            "border" => self.border = color()?,
+            "accent" => self.accent = color()?,
+            "warning" => self.warning = color()?,
+            "success" => self.success = color()?,
+            "error" => self.error = color()?,
+            "info" => self.info = color()?,
+            "user" => self.user = color()?,
+            "assistant" => self.assistant = color()?,
+            "system" => self.system = color()?,
+            "tool" => self.tool = color()?,
+            "search_match" => self.search_match = color()?,
+            "search_current" => self.search_current.0 = color()?,
+            "search_current_text" => self.search_current.1 = color()?,
+            "border_type" => {
+                self.border_type = match value.to_lowercase().as_str() {
+                    "plain" => BorderType::Plain,
+                    "rounded" => BorderType::Rounded,
+                    "double" => BorderType::Double,
+                    "thick" => BorderType::Thick,
+                    _ => return Err(ThemeError::UnknownBorderType(value.to_string())),
+                }
+            }
+            "code_theme" => {
+                if !markdown::has_code_theme(value) {
+                    return Err(ThemeError::UnknownCodeTheme(value.to_string()));
+                }
+                self.code_theme = value.to_string();
+            }
+            other => return Err(ThemeError::UnknownSetting(other.to_string())),
+        }
+        Ok(())
+    }
+}
+
+/// The themes on offer and which one is in use
+#[derive(Debug, Clone)]
+pub struct Themes {
+    themes: Vec<Theme>,
+    current: usize,
+}
+
+impl Default for Themes {
+    fn default() -> Self {
+        Self { themes: Theme::builtins(), current: 0 }
+    }
+}
+
+impl Themes {
+    /// Built-ins plus `custom` (replacing a built-in of the same name), with
+    /// `selected` in use
+    pub fn new(
+        custom: &BTreeMap<String, BTreeMap<String, String>>,
+        selected: Option<&str>,
+    ) -> Result<Self, ThemeError> {
+        let mut themes = Theme::builtins();
+        for (name, settings) in custom {
+            let base = settings.get("base").map(String::as_str).unwrap_or("dark");
+            let mut theme = Theme::builtins()
+                .into_iter()
+                .find(|t| t.name == base)
+                .ok_or_else(|| ThemeError::UnknownTheme(base.to_string()))?;
+            theme.name = name.clone();
+            for (setting, value) in settings.iter().filter(|(k, _)| k.as_str() != "base") {
+                theme.set(setting, value)?;
+            }
+            match themes.iter_mut().find(|t| t.name == *name) {
+                Some(existing) => *existing = theme,
+                None => themes.push(theme),
+            }
+        }
+
+        let current = match selected {
+            Some(name) => themes
+                .iter()
+                .position(|t| t.name == name)
+                .ok_or_else(|| ThemeError::UnknownTheme(name.to_string()))?,
+            None => 0,
+        };
+        Ok(Self { themes, current })
+    }
+
+    pub fn current(&self) -> &Theme {
+        &self.themes[self.current]
+    }
+
+    /// Switch to the next theme, wrapping around
+    pub fn cycle(&mut self) -> &Theme {
+        self.current = (self.current + 1) % self.themes.len();
+        self.current()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    #[test]
+    fn test_custom_theme() {
+        let custom = BTreeMap::from([(
+            "solarized".to_string(),
+            settings(&[("base", "light"), ("accent", "#268bd2"), ("border_type", "double")]),
+        )]);
+        let mut themes = Themes::new(&custom, Some("solarized")).unwrap();
+        let theme = themes.current();
+        assert_eq!(theme.accent, Color::Rgb(0x26, 0x8b, 0xd2));
+        assert_eq!(theme.border_type, BorderType::Double);
+        // Unset roles come from the base
+        assert_eq!(theme.warning, Theme::light().warning);
+
+        assert_eq!(themes.cycle().name, "dark");
+        assert_eq!(themes.cycle().name, "light");
+    }
+
+    #[test]
+    fn test_invalid_settings() {
+        let custom = |pairs: &[(&str, &str)]| BTreeMap::from([("mine".to_string(), settings(pairs))]);
+        assert!(matches!(Themes::new(&custom(&[("accent", "sparkly")]), None), Err(ThemeError::InvalidColor { .. })));
+        assert!(matches!(Themes::new(&custom(&[("base", "sepia")]), None), Err(ThemeError::UnknownTheme(_))));
+        assert!(matches!(Themes::new(&custom(&[("glow", "red")]), None), Err(ThemeError::UnknownSetting(_))));
+        assert!(matches!(Themes::new(&custom(&[("code_theme", "nope")]), None), Err(ThemeError::UnknownCodeTheme(_))));
+        assert!(matches!(Themes::new(&BTreeMap::new(), Some("neon")), Err(ThemeError::UnknownTheme(_))));
+    }
+}
@@ -2,78 +2,673 @@
 
 use ratatui::{
     backend::Backend,
-    layout::{Constraint, Direction, Layout, Margin},
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, List, ListItem, Paragraph, Wrap},
+    widgets::{block::{Position, Title}, Clear, List, ListItem, ListState, Paragraph, Tabs, Wrap},
     Frame,
 };
 
-use crate::app::App;
+use crate::app::{App, Focus, View};
+use crate::attachments::Preview;
+use crate::i18n::t;
+use crate::keymap::Action;
+use crate::theme::Theme;
+use crate::tab::Connection;
+use tracing::Level;
+
+/// Tallest the input box grows before it scrolls
+const MAX_INPUT_ROWS: u16 = 6;
+
+/// Longest tab title shown in the tab bar
+const TAB_TITLE_CHARS: usize = 20;
 
 pub fn draw<B: Backend>(f: &mut Frame<B>, app: &mut App) {
+    let theme = &app.themes.current().clone();
+    let input_rows = (app.editor.lines().len() as u16).clamp(1, MAX_INPUT_ROWS) + 2;
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
-            Constraint::Min(1),     // Messages area
-            Constraint::Length(3),  // Input area
-            Constraint::Length(1),  // Status bar
+            Constraint::Length(1),           // Tab bar
+            Constraint::Min(1),              // Messages area
+            Constraint::Length(input_rows),  // Input area
+            Constraint::Length(1),           // Status bar
         ])
         .split(f.size());
 
-    draw_messages(f, app, chunks[0]);
-    draw_input(f, app, chunks[1]);
-    draw_status(f, app, chunks[2]);
+    draw_tabs(f, app, chunks[0], theme);
+    match app.view {
+        View::Chat => draw_messages(f, app, chunks[1], theme),
+        View::Dashboard => draw_dashboard(f, app, chunks[1], theme),
+    }
+    draw_input(f, app, chunks[2], theme);
+    draw_status(f, app, chunks[3], theme);
+
+    if app.focus == Focus::Switcher {
+        draw_switcher(f, app, theme);
+    } else if app.focus == Focus::Attachments {
+        draw_attachments(f, app, theme);
+    } else if app.focus == Focus::Input && app.tab().approval.is_some() {
+        draw_approval(f, app, theme);
+    }
+}
+
+fn draw_tabs<B: Backend>(f: &mut Frame<B>, app: &mut App, area: Rect, theme: &Theme) {
+    let titles: Vec<Line> = app
+        .tabs
+        .iter()
+        .enumerate()
+        .map(|(i, tab)| {
+            let mut title: String = tab.title.chars().take(TAB_TITLE_CHARS).collect();
+            if tab.title.chars().count() > TAB_TITLE_CHARS {
+                title.push('…');
+            }
+            let mut spans = vec![Span::styled(format!("{} ", i + 1), Style::default().fg(theme.muted))];
+            if tab.approval.is_some() {
+                spans.push(Span::styled("⏸ ", Style::default().fg(theme.error)));
+            } else if tab.is_busy() {
+                spans.push(Span::styled("◐ ", Style::default().fg(theme.warning)));
+            }
+            spans.push(Span::raw(title));
+            Line::from(spans)
+        })
+        .collect();
+
+    let tabs = Tabs::new(titles)
+        .select(app.active)
+        .highlight_style(Style::default().fg(theme.accent).add_modifier(Modifier::BOLD | Modifier::REVERSED))
+        .divider(Span::styled("│", Style::default().fg(theme.muted)));
+    f.render_widget(tabs, area);
+}
+
+fn draw_messages<B: Backend>(f: &mut Frame<B>, app: &mut App, area: Rect, theme: &Theme) {
+    let follow = app.keymap.label(Action::ScrollBottom);
+    let chat = &mut app.tab_mut().chat;
+    chat.set_theme(theme);
+    let mut block = theme.block().title(t!("chat-title"));
+    let inner = block.inner(area);
+    let rows = chat.visible(inner.width as usize, inner.height as usize);
+
+    if chat.offset() > 0 {
+        block = block.title(
+            Title::from(Span::styled(
+                format!(" {} ", t!("chat-scrolled", "rows" => chat.offset(), "key" => follow)),
+                Style::default().fg(theme.warning),
+            ))
+            .alignment(Alignment::Right),
+        );
+    }
+    f.render_widget(Paragraph::new(rows).block(block), area);
+}
+
+fn draw_dashboard<B: Backend>(f: &mut Frame<B>, app: &mut App, area: Rect, theme: &Theme) {
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(12), Constraint::Length(8), Constraint::Min(3)])
+        .split(area);
+    let panels = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(55), Constraint::Percentage(45)])
+        .split(rows[0]);
+    draw_insights(f, app, rows[1], theme);
+
+    let dim = Style::default().fg(theme.muted);
+    let snapshot = app.dashboard.snapshot();
+    let Some(snapshot) = snapshot else {
+        let waiting = Paragraph::new(Span::styled(t!("dashboard-waiting"), dim));
+        f.render_widget(waiting.clone().block(theme.block().title(t!("dashboard-runtime"))), panels[0]);
+        f.render_widget(waiting.block(theme.block().title(t!("dashboard-connectors"))), panels[1]);
+        draw_logs(f, app, rows[2], theme);
+        return;
+    };
+
+    // Metrics
+    let status = &snapshot.status;
+    let label = |id: &str| Span::styled(format!("{:<10}", t!(id)), dim);
+    let health = |ok: Option<bool>| match ok {
+        Some(true) => Span::styled(format!("● {}", t!("health-up")), Style::default().fg(theme.success)),
+        Some(false) => Span::styled(format!("✕ {}", t!("health-down")), Style::default().fg(theme.error)),
+        None => Span::styled(format!("? {}", t!("health-unknown")), dim),
+    };
+    let mut lines = vec![
+        Line::from(vec![
+            label("label-runtime"),
+            health(Some(status.up)),
+            Span::raw(
+                status
+                    .uptime_seconds
+                    .map(|s| format!(" · {}", t!("dashboard-uptime", "uptime" => format_uptime(s))))
+                    .unwrap_or_default(),
+            ),
+        ]),
+        Line::from(vec![
+            label("label-sessions"),
+            Span::raw(
+                status
+                    .sessions_active
+                    .map(|n| t!("dashboard-sessions-active", "count" => n))
+                    .unwrap_or_else(|| "—".to_string()),
+            ),
+        ]),
+        Line::from(vec![
+            label("label-database"),
+            health(status.database.up),
+            Span::raw(match (status.database.pool_size, status.database.pool_max_size) {
+                (Some(size), Some(max)) => format!(
+                    " · {}",
+                    t!(
+                        "dashboard-pool",
+                        "size" => size,
+                        "max" => max,
+                        "idle" => status.database.pool_available.unwrap_or(0),
+                        "waiting" => status.database.pool_waiting.unwrap_or(0),
+                    )
+                ),
+                _ => String::new(),
+            }),
+        ]),
+        Line::from(vec![
+            label("label-cache"),
+            Span::raw(
+                status
+                    .cache_hit_rate
+                    .map(|r| t!("dashboard-cache-hit-rate", "rate" => format!("{:.1}", r * 100.0)))
+                    .unwrap_or_else(|| t!("dashboard-cache-empty")),
+            ),
+        ]),
+        budget_line(status, label("label-budget"), theme),
+    ];
+    lines.push(Line::from(Span::styled(t!("dashboard-providers"), Style::default().add_modifier(Modifier::BOLD))));
+    if status.providers.is_empty() {
+        lines.push(Line::from(Span::styled(format!("  {}", t!("dashboard-providers-empty")), dim)));
+    }
+    for provider in &status.providers {
+        let seconds = |v: Option<f64>| v.map(|v| format!("{:.2}s", v)).unwrap_or_else(|| "—".to_string());
+        let mut spans = vec![
+            Span::styled(format!("  {}", provider.model), Style::default().fg(theme.accent)),
+            Span::raw(format!(
+                "  {}",
+                t!(
+                    "dashboard-provider-stats",
+                    "requests" => provider.requests,
+                    "p50" => seconds(provider.p50_seconds),
+                    "p99" => seconds(provider.p99_seconds),
+                )
+            )),
+        ];
+        if provider.errors > 0 {
+            spans.push(Span::styled(
+                format!(" · {}", t!("dashboard-provider-errors", "errors" => provider.errors)),
+                Style::default().fg(theme.error),
+            ));
+        }
+        lines.push(Line::from(spans));
+    }
+    let title = t!("dashboard-runtime-sampled", "time" => snapshot.taken_at.format("%H:%M:%S").to_string());
+    f.render_widget(Paragraph::new(lines).block(theme.block().title(title)), panels[0]);
+
+    // Connectors
+    let items: Vec<ListItem> = snapshot
+        .connectors
+        .iter()
+        .map(|info| {
+            let (dot, color) = if info.enabled { ("● ", theme.success) } else { ("○ ", theme.muted) };
+            let mut spans = vec![
+                Span::styled(dot, Style::default().fg(color)),
+                Span::raw(info.metadata.id.clone()),
+                Span::styled(format!("  {:?}", info.metadata.capability_level), dim),
+            ];
+            if info.metadata.requires_approval {
+                spans.push(Span::styled(format!(" · {}", t!("connector-approval")), Style::default().fg(theme.warning)));
+            }
+            if !info.enabled {
+                spans.push(Span::styled(format!(" · {}", t!("connector-disabled")), dim));
+            }
+            ListItem::new(Line::from(spans))
+        })
+        .collect();
+    let enabled = snapshot.connectors.iter().filter(|c| c.enabled).count();
+    let title = t!("dashboard-connectors-enabled", "enabled" => enabled, "total" => snapshot.connectors.len());
+    f.render_widget(List::new(items).block(theme.block().title(title)), panels[1]);
+
+    draw_logs(f, app, rows[2], theme);
 }
 
-fn draw_messages<B: Backend>(f: &mut Frame<B>, app: &mut App, area: ratatui::layout::Rect) {
-    let messages: Vec<ListItem> = app
-        .messages
+/// Tool usage and model latency from the usage log, which unlike the
+/// runtime panel's metrics includes earlier runs
+fn draw_insights<B: Backend>(f: &mut Frame<B>, app: &mut App, area: Rect, theme: &Theme) {
+    let panels = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(55), Constraint::Percentage(45)])
+        .split(area);
+    let dim = Style::default().fg(theme.muted);
+    let days = crate::dashboard::INSIGHTS_DAYS;
+    let tools_title = t!("insights-tools", "days" => days);
+    let models_title = t!("insights-models", "days" => days);
+
+    let Some(insights) = app.dashboard.insights() else {
+        let waiting = Paragraph::new(Span::styled(t!("insights-reading"), dim));
+        f.render_widget(waiting.clone().block(theme.block().title(tools_title)), panels[0]);
+        f.render_widget(waiting.block(theme.block().title(models_title)), panels[1]);
+        return;
+    };
+    let ms = |ms: u64| if ms >= 1000 { format!("{:.1}s", ms as f64 / 1000.0) } else { format!("{}ms", ms) };
+
+    let mut tools: Vec<Line> = insights
+        .tools
+        .iter()
+        .map(|tool| {
+            let mut spans = vec![
+                Span::styled(tool.tool.clone(), Style::default().fg(theme.accent)),
+                Span::raw(format!("  {}", t!("insights-tool-stats", "runs" => tool.calls, "p95" => ms(tool.latency.p95_ms)))),
+            ];
+            if tool.failures > 0 {
+                spans.push(Span::styled(
+                    format!(" · {}", t!("insights-tool-failed", "rate" => format!("{:.0}", tool.failure_rate() * 100.0))),
+                    Style::default().fg(if tool.failure_rate() >= 0.25 { theme.error } else { theme.warning }),
+                ));
+            }
+            Line::from(spans)
+        })
+        .collect();
+    if tools.is_empty() {
+        tools.push(Line::from(Span::styled(t!("insights-tools-empty"), dim)));
+    }
+    let title = t!(
+        "insights-tools-summary",
+        "title" => tools_title.as_str(),
+        "runs" => insights.tool_calls,
+        "rate" => format!("{:.1}", insights.failure_rate() * 100.0),
+    );
+    f.render_widget(Paragraph::new(tools).block(theme.block().title(title)), panels[0]);
+
+    let mut models: Vec<Line> = insights
+        .models
         .iter()
-        .map(|msg| {
-            let (prefix, style) = match msg.role {
-                jamey_protocol::Role::System => ("🔧", Style::default().fg(Color::Yellow)),
-                jamey_protocol::Role::User => ("👤", Style::default().fg(Color::Green)),
-                jamey_protocol::Role::Assistant => ("🤖", Style::default().fg(Color::Blue)),
-                jamey_protocol::Role::Tool => ("🔧", Style::default().fg(Color::Magenta)),
+        .map(|model| {
+            let latency = match model.latency {
+                Some(p) => t!("insights-model-latency", "p50" => ms(p.p50_ms), "p95" => ms(p.p95_ms), "p99" => ms(p.p99_ms)),
+                None => t!("insights-no-timings"),
             };
+            Line::from(vec![
+                Span::styled(model.model.clone(), Style::default().fg(theme.accent)),
+                Span::raw(format!("  {} · ", t!("insights-model-calls", "calls" => model.calls))),
+                Span::styled(latency, dim),
+            ])
+        })
+        .collect();
+    if models.is_empty() {
+        models.push(Line::from(Span::styled(t!("insights-models-empty"), dim)));
+    }
+    f.render_widget(Paragraph::new(models).block(theme.block().title(models_title)), panels[1]);
+}
 
-            let content = vec![Line::from(vec![
-                Span::styled(format!("{} ", prefix), style),
-                Span::styled(&msg.content, Style::default()),
-            ])];
+fn budget_line(status: &jamey_runtime::status::RuntimeStatus, label: Span<'static>, theme: &Theme) -> Line<'static> {
+    let budget = &status.budget;
+    let Some(spent) = budget.spent_today_usd else {
+        return Line::from(vec![label, Span::raw("—")]);
+    };
+    let mut spans = vec![label, Span::raw(t!("budget-today", "spent" => format!("{:.2}", spent)))];
+    if let (Some(limit), Some(used)) = (budget.daily_limit_usd, budget.used_fraction()) {
+        let color = match used {
+            u if u >= 1.0 => theme.error,
+            u if u >= 0.8 => theme.warning,
+            _ => theme.success,
+        };
+        let filled = ((used.min(1.0) * 10.0).round()) as usize;
+        spans.push(Span::raw(format!(" {} ", t!("budget-of", "limit" => format!("{:.2}", limit)))));
+        spans.push(Span::styled("█".repeat(filled), Style::default().fg(color)));
+        spans.push(Span::styled("░".repeat(10 - filled), Style::default().fg(theme.muted)));
+        spans.push(Span::raw(format!(" {:.0}%", used * 100.0)));
+    }
+    Line::from(spans)
+}
 
-            ListItem::new(content)
+fn format_uptime(seconds: f64) -> String {
+    let seconds = seconds as u64;
+    match (seconds / 86_400, seconds / 3600 % 24, seconds / 60 % 60) {
+        (0, 0, m) => format!("{}m", m),
+        (0, h, m) => format!("{}h {:02}m", h, m),
+        (d, h, _) => format!("{}d {}h", d, h),
+    }
+}
+
+fn draw_logs<B: Backend>(f: &mut Frame<B>, app: &mut App, area: Rect, theme: &Theme) {
+    let entries = app.dashboard.logs();
+    let height = area.height.saturating_sub(2) as usize;
+    let max_offset = entries.len().saturating_sub(height);
+    app.dashboard.log_offset = app.dashboard.log_offset.min(max_offset);
+    let end = entries.len() - app.dashboard.log_offset;
+
+    let lines: Vec<Line> = entries[end.saturating_sub(height)..end]
+        .iter()
+        .map(|entry| {
+            let color = match entry.level {
+                Level::ERROR => theme.error,
+                Level::WARN => theme.warning,
+                Level::INFO => theme.success,
+                Level::DEBUG => theme.info,
+                Level::TRACE => theme.muted,
+            };
+            Line::from(vec![
+                Span::styled(entry.time.format("%H:%M:%S ").to_string(), Style::default().fg(theme.muted)),
+                Span::styled(format!("{:<5} ", entry.level), Style::default().fg(color)),
+                Span::styled(format!("{} ", entry.target), Style::default().fg(theme.muted)),
+                Span::raw(entry.message.clone()),
+            ])
         })
         .collect();
 
-    let messages_list = List::new(messages)
-        .block(Block::default().borders(Borders::ALL).title("Messages"))
-        .style(Style::default().fg(Color::White))
-        .highlight_style(Style::default().add_modifier(Modifier::BOLD));
+    let mut title = t!("logs-title", "level" => app.dashboard.min_level.to_string());
+    if app.dashboard.log_offset > 0 {
+        title.push_str(&format!(" · {}", t!("logs-scrolled", "lines" => app.dashboard.log_offset)));
+    }
+    f.render_widget(Paragraph::new(lines).block(theme.block().title(title)), area);
+}
 
-    f.render_widget(messages_list, area);
+fn draw_input<B: Backend>(f: &mut Frame<B>, app: &mut App, area: Rect, theme: &Theme) {
+    match app.focus {
+        Focus::Search => {
+            let search = app.tab().chat.search();
+            let query = search.map(|s| s.query.as_str()).unwrap_or_default();
+            let position = match search.and_then(|s| s.position()) {
+                Some((current, total)) => format!("{}/{}", current, total),
+                None if query.is_empty() => String::new(),
+                None => t!("search-no-matches"),
+            };
+            let title = t!("search-title", "position" => position);
+            let widget = Paragraph::new(Line::from(vec![
+                Span::styled("/", Style::default().fg(theme.warning)),
+                Span::raw(query.to_string()),
+            ]))
+            .block(theme.block().title(title));
+            f.render_widget(widget, area);
+        }
+        Focus::Rename => {
+            let widget = Paragraph::new(Line::from(vec![
+                Span::raw(app.rename.clone()),
+                Span::styled("▌", Style::default().fg(theme.warning)),
+            ]))
+            .block(theme.block().title(t!("rename-title")));
+            f.render_widget(widget, area);
+        }
+        Focus::Input | Focus::Switcher | Focus::Attachments => {
+            let hint = if app.tab().chat.is_streaming() {
+                t!("input-cancel-hint", "key" => app.keymap.label(Action::CancelTurn))
+            } else {
+                t!(
+                    "input-send-hint",
+                    "send" => app.keymap.label(Action::Send),
+                    "newline" => app.keymap.label(Action::Newline),
+                )
+            };
+            let mut block = theme.block().title(t!("input-title", "hint" => hint));
+            let queued = app.tab().pending_attachments.len();
+            if queued > 0 {
+                block = block.title(Span::styled(
+                    format!(" 📎 {} ", t!("input-attached", "count" => queued)),
+                    Style::default().fg(theme.accent),
+                ));
+            }
+            if let Some(mode) = app.editor.mode_label() {
+                block = block.title(
+                    Title::from(Span::styled(format!(" {} ", mode), Style::default().fg(theme.warning)))
+                        .alignment(Alignment::Right),
+                );
+            }
+            app.editor.set_block(block);
+            f.render_widget(app.editor.widget(), area);
+        }
+    }
 }
 
-fn draw_input<B: Backend>(f: &mut Frame<B>, app: &mut App, area: ratatui::layout::Rect) {
-    let input_widget = Paragraph::new(app.input.lines())
-        .block(Block::default().borders(Borders::ALL).title("Input (Ctrl+Enter to send)"))
-        .style(Style::default().fg(Color::White))
-        .wrap(Wrap { trim: true });
+fn draw_switcher<B: Backend>(f: &mut Frame<B>, app: &mut App, theme: &Theme) {
+    let Some(switcher) = app.switcher.as_ref() else { return };
+    let area = centered(f.size(), 70, 60);
+    f.render_widget(Clear, area);
+
+    let block = theme.block()
+        .title(t!("switcher-title"));
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(1), Constraint::Min(1)])
+        .split(inner);
+
+    let query = Paragraph::new(Line::from(vec![
+        Span::styled("> ", Style::default().fg(theme.warning)),
+        Span::raw(switcher.query.clone()),
+    ]));
+    f.render_widget(query, chunks[0]);
 
-    f.render_widget(input_widget, area);
+    let dim = Style::default().fg(theme.muted);
+    let items: Vec<ListItem> = switcher
+        .matches()
+        .into_iter()
+        .map(|item| {
+            let marker = if item.open { "● " } else { "  " };
+            ListItem::new(Line::from(vec![
+                Span::styled(marker, Style::default().fg(theme.success)),
+                Span::raw(item.title.clone()),
+                Span::styled(
+                    format!(
+                        "  {}",
+                        t!(
+                            "switcher-entry",
+                            "id" => item.session_id.to_string()[..8].to_string(),
+                            "count" => item.message_count,
+                            "updated" => item.updated_at.format("%Y-%m-%d %H:%M").to_string(),
+                        )
+                    ),
+                    dim,
+                ),
+            ]))
+        })
+        .collect();
+
+    if items.is_empty() {
+        f.render_widget(Paragraph::new(Span::styled(t!("switcher-empty"), dim)), chunks[1]);
+        return;
+    }
+    let mut state = ListState::default();
+    state.select(Some(switcher.selected));
+    let list = List::new(items).highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+    f.render_stateful_widget(list, chunks[1], &mut state);
 }
 
-fn draw_status<B: Backend>(f: &mut Frame<B>, app: &mut App, area: ratatui::layout::Rect) {
-    let status_text = vec![Line::from(vec![
-        Span::styled("Status: ", Style::default().fg(Color::Gray)),
-        Span::styled(&app.status, Style::default().fg(Color::Green)),
-        Span::styled(" | Ctrl+C to exit", Style::default().fg(Color::Gray)),
-    ])];
+fn draw_attachments<B: Backend>(f: &mut Frame<B>, app: &mut App, theme: &Theme) {
+    let uses_cells = app.graphics_in_cells();
+    let Some(panel) = app.attachments.as_mut() else { return };
+    let area = centered(f.size(), 80, 70);
+    f.render_widget(Clear, area);
 
-    let status_widget = Paragraph::new(status_text)
-        .style(Style::default().fg(Color::White))
-        .wrap(Wrap { trim: true });
+    let block = theme
+        .block()
+        .title(t!("attachments-title"))
+        .title(
+            Title::from(format!(" {} ", t!("attachments-keys")))
+                .position(Position::Bottom)
+                .alignment(Alignment::Center),
+        );
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(35), Constraint::Percentage(65)])
+        .split(inner);
+    let left = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(1), Constraint::Length(2)])
+        .split(columns[0]);
 
-    f.render_widget(status_widget, area);
-}
\ No newline at end of file
+    let dim = Style::default().fg(theme.muted);
+    let items: Vec<ListItem> = panel
+        .items
+        .iter()
+        .map(|item| {
+            let icon = if item.is_image() { "🖼 " } else { "📄 " };
+            ListItem::new(vec![
+                Line::from(vec![Span::raw(icon), Span::raw(item.name.clone())]),
+                Line::from(Span::styled(
+                    format!("   {} · {}", item.mime_type, item.sent_at.format("%Y-%m-%d %H:%M")),
+                    dim,
+                )),
+            ])
+        })
+        .collect();
+    let mut state = ListState::default();
+    state.select(Some(panel.selected));
+    let list = List::new(items).highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+    f.render_stateful_widget(list, left[0], &mut state);
+    if let Some(notice) = &panel.notice {
+        f.render_widget(
+            Paragraph::new(Span::styled(notice.clone(), Style::default().fg(theme.success))).wrap(Wrap { trim: true }),
+            left[1],
+        );
+    }
+
+    let preview_block = theme.block().title(t!("preview-title"));
+    let preview_area = preview_block.inner(columns[1]);
+    f.render_widget(preview_block, columns[1]);
+    panel.image_area = None;
+    match panel.preview() {
+        Some(Preview::Image(_)) if uses_cells => {
+            let lines = panel.ascii_preview(preview_area.width, preview_area.height).unwrap_or_default();
+            f.render_widget(Paragraph::new(lines), preview_area);
+        }
+        // Left blank for the terminal to draw the picture into after this frame
+        Some(Preview::Image(_)) => panel.image_area = Some(preview_area),
+        Some(Preview::Text(text)) => {
+            f.render_widget(Paragraph::new(text.clone()).wrap(Wrap { trim: false }), preview_area);
+        }
+        Some(Preview::Unavailable(reason)) => {
+            f.render_widget(
+                Paragraph::new(Span::styled(t!("preview-unavailable", "reason" => reason.as_str()), dim)).wrap(Wrap { trim: true }),
+                preview_area,
+            );
+        }
+        None => f.render_widget(Paragraph::new(Span::styled(t!("preview-loading"), dim)), preview_area),
+    }
+}
+
+fn draw_approval<B: Backend>(f: &mut Frame<B>, app: &mut App, theme: &Theme) {
+    let Some(request) = app.tab().approval.as_ref() else { return };
+    let area = centered(f.size(), 70, 60);
+    f.render_widget(Clear, area);
+
+    let dim = Style::default().fg(theme.muted);
+    let bold = Style::default().add_modifier(Modifier::BOLD);
+    let label = |id: &str| Span::styled(format!("{:<11}", t!(id)), dim);
+    let mut lines = vec![
+        Line::from(vec![label("approval-connector"), Span::styled(request.connector_id.clone(), bold.fg(theme.accent))]),
+        Line::from(vec![label("approval-action"), Span::styled(request.action.clone(), bold)]),
+        Line::from(vec![
+            label("approval-requested"),
+            Span::raw(request.requested_at.format("%H:%M:%S").to_string()),
+            Span::styled(format!("  ({})", &request.id.to_string()[..8]), dim),
+        ]),
+        Line::default(),
+    ];
+
+    let mut params: Vec<_> = request.params.iter().filter(|(k, _)| k.as_str() != "action").collect();
+    params.sort();
+    if !params.is_empty() {
+        lines.push(Line::from(Span::styled(t!("approval-parameters"), bold)));
+        for (key, value) in params {
+            lines.push(Line::from(vec![Span::styled(format!("  {}: ", key), dim), Span::raw(value.clone())]));
+        }
+        lines.push(Line::default());
+    }
+    if !request.safety_checks.is_empty() {
+        lines.push(Line::from(Span::styled(t!("approval-safety-checks"), bold)));
+        for check in &request.safety_checks {
+            lines.push(Line::from(vec![Span::styled("  • ", Style::default().fg(theme.warning)), Span::raw(check.clone())]));
+        }
+    }
+
+    // Keys sit on the border so they stay visible however long the body is
+    let key = |k: &'static str, color: Color| Span::styled(k, Style::default().fg(color).add_modifier(Modifier::BOLD));
+    let keys = Line::from(vec![
+        Span::raw(" "),
+        key("y", theme.success),
+        Span::raw(format!(" {} · ", t!("approval-approve"))),
+        key("a", theme.success),
+        Span::raw(format!(" {} · ", t!("approval-always"))),
+        key("n", theme.error),
+        Span::raw(format!(" {} · ", t!("approval-deny"))),
+        key("Esc", theme.subtle),
+        Span::raw(format!(" {} ", t!("approval-cancel"))),
+    ]);
+
+    let block = theme.block()
+        .border_style(Style::default().fg(theme.warning))
+        .title(Span::styled(format!(" ⏸ {} ", t!("approval-title")), bold.fg(theme.warning)))
+        .title(Title::from(keys).position(Position::Bottom).alignment(Alignment::Center));
+    f.render_widget(Paragraph::new(lines).block(block).wrap(Wrap { trim: false }), area);
+}
+
+/// A `width_pct` x `height_pct` rectangle in the middle of `area`
+fn centered(area: Rect, width_pct: u16, height_pct: u16) -> Rect {
+    let vertical = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Percentage((100 - height_pct) / 2),
+            Constraint::Percentage(height_pct),
+            Constraint::Percentage((100 - height_pct) / 2),
+        ])
+        .split(area);
+    Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage((100 - width_pct) / 2),
+            Constraint::Percentage(width_pct),
+            Constraint::Percentage((100 - width_pct) / 2),
+        ])
+        .split(vertical[1])[1]
+}
+
+fn draw_status<B: Backend>(f: &mut Frame<B>, app: &mut App, area: Rect, theme: &Theme) {
+    let tab = app.tab();
+    let dim = Style::default().fg(theme.subtle);
+    let (dot, state) = match &tab.connection {
+        Connection::Ready => (Span::styled("●", Style::default().fg(theme.success)), t!("status-ready")),
+        Connection::Streaming => (Span::styled("◐", Style::default().fg(theme.warning)), t!("status-streaming")),
+        Connection::Error(e) => (Span::styled("✕", Style::default().fg(theme.error)), t!("status-error", "error" => e.as_str())),
+    };
+    let cost = tab
+        .usage
+        .cost_usd
+        .map(|c| format!(" · ${:.4}", c))
+        .unwrap_or_default();
+
+    let status = Line::from(vec![
+        dot,
+        Span::styled(format!(" {} ", state), dim),
+        Span::styled("│ ", dim),
+        Span::styled(app.model.clone(), Style::default().fg(theme.accent).add_modifier(Modifier::BOLD)),
+        Span::styled(" │ ", dim),
+        Span::raw(format!(
+            "{}{}",
+            t!("status-tokens", "prompt" => tab.usage.prompt_tokens, "completion" => tab.usage.completion_tokens),
+            cost
+        )),
+        Span::styled(" │ ", dim),
+        Span::styled(t!("status-session", "id" => tab.session_id.to_string()[..8].to_string()), dim),
+        Span::styled(if app.speech_on() { " │ 🔊" } else { "" }, dim),
+        Span::styled(
+            format!(
+                " │ {}",
+                t!(
+                    "status-keys",
+                    "sessions" => app.keymap.label(Action::Sessions),
+                    "quit" => app.keymap.label(Action::Quit),
+                )
+            ),
+            dim,
+        ),
+    ]);
+
+    f.render_widget(Paragraph::new(status), area);
+}